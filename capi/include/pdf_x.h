@@ -0,0 +1,98 @@
+/*
+ * C API for PDF-X.
+ *
+ * Link against the pdf_x_capi library built from the capi crate
+ * (cargo build -p pdf-x-capi --profile release-capi [--features rendering]).
+ * The release-capi profile unwinds on panic, so a panic in the library is
+ * reported as PDFX_ERROR_PANIC; the plain release profile aborts instead.
+ *
+ * Every function returns PDFX_OK (0) on success or an error code. The
+ * message of the last failure on the calling thread is available from
+ * pdfx_last_error_message(). A document handle must not be used from two
+ * threads at the same time.
+ */
+
+#ifndef PDF_X_H
+#define PDF_X_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Status codes. 1-99 mirror pdf_x_core::ErrorCode; the values never change. */
+#define PDFX_OK 0
+#define PDFX_ERROR_OTHER 1
+#define PDFX_ERROR_UNEXPECTED_END_OF_STREAM 2
+#define PDFX_ERROR_INVALID_BYTE_RANGE 3
+#define PDFX_ERROR_DATA_NOT_LOADED 4
+#define PDFX_ERROR_DATA_MISSING 5
+#define PDFX_ERROR_INVALID_POSITION 6
+#define PDFX_ERROR_INVALID_OBJECT 7
+#define PDFX_ERROR_PARSE 8
+#define PDFX_ERROR_XREF 9
+#define PDFX_ERROR_PAGE 10
+#define PDFX_ERROR_FONT 11
+#define PDFX_ERROR_CONTENT_STREAM 12
+#define PDFX_ERROR_IO 13
+#define PDFX_ERROR_CORRUPTED 14
+#define PDFX_ERROR_UNSUPPORTED 15
+#define PDFX_ERROR_VALIDATION 16
+#define PDFX_ERROR_PERMISSION_DENIED 17
+#define PDFX_ERROR_CANCELLED 18
+#define PDFX_ERROR_STREAM 19
+#define PDFX_ERROR_MISSING_OBJECT 20
+#define PDFX_ERROR_FILTER 21
+#define PDFX_ERROR_ENCRYPTION 22
+#define PDFX_ERROR_LIMIT_EXCEEDED 23
+
+/* Errors of the C API itself. */
+#define PDFX_ERROR_NULL_POINTER 100
+#define PDFX_ERROR_INVALID_ARGUMENT 101
+#define PDFX_ERROR_BUFFER_TOO_SMALL 102
+#define PDFX_ERROR_PANIC 103
+
+typedef struct PdfxDocument PdfxDocument;
+
+/* Message of the last failed call on this thread, or NULL. Valid until the
+ * next failing call on the same thread; do not free. */
+const char *pdfx_last_error_message(void);
+
+/* Opens a document from memory (the bytes are copied) or from a file.
+ * password may be NULL. */
+uint32_t pdfx_document_open_bytes(const uint8_t *data, size_t len,
+                                  const char *password, PdfxDocument **out);
+uint32_t pdfx_document_open_file(const char *path, const char *password,
+                                 PdfxDocument **out);
+void pdfx_document_free(PdfxDocument *doc);
+
+uint32_t pdfx_document_page_count(PdfxDocument *doc, uint32_t *out);
+
+/* Text of a page as a UTF-8 JSON array of
+ * {"text", "font", "size", "x", "y", "width"} objects in PDF user space.
+ * Release the string with pdfx_string_free. */
+uint32_t pdfx_page_extract_text_json(PdfxDocument *doc, uint32_t page_index,
+                                     char **out_json);
+void pdfx_string_free(char *s);
+
+/* Size in pixels of a page rendered at scale (1.0 = 72 DPI). */
+uint32_t pdfx_page_render_size(PdfxDocument *doc, uint32_t page_index,
+                               float scale, uint32_t *out_width,
+                               uint32_t *out_height);
+
+/* Renders a page as non-premultiplied RGBA, rows from the top, into a
+ * buffer of at least width * height * 4 bytes. out_width and out_height
+ * may be NULL. Returns PDFX_ERROR_UNSUPPORTED if the library was built
+ * without the rendering feature. */
+uint32_t pdfx_page_render_rgba(PdfxDocument *doc, uint32_t page_index,
+                               float scale, uint8_t *buffer,
+                               size_t buffer_len, uint32_t *out_width,
+                               uint32_t *out_height);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* PDF_X_H */
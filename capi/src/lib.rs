@@ -0,0 +1,735 @@
+//! C ABI for PDF-X.
+//!
+//! This crate builds a shared and a static library exposing the document
+//! engine to C, C++ and anything else that can call C functions. The
+//! declarations are in `include/pdf_x.h`.
+//!
+//! ```c
+//! #include "pdf_x.h"
+//!
+//! PdfxDocument *doc = NULL;
+//! if (pdfx_document_open_file("document.pdf", NULL, &doc) != PDFX_OK) {
+//!     fprintf(stderr, "%s\n", pdfx_last_error_message());
+//!     return 1;
+//! }
+//!
+//! uint32_t pages = 0;
+//! pdfx_document_page_count(doc, &pages);
+//!
+//! char *json = NULL;
+//! if (pdfx_page_extract_text_json(doc, 0, &json) == PDFX_OK) {
+//!     puts(json);
+//!     pdfx_string_free(json);
+//! }
+//! pdfx_document_free(doc);
+//! ```
+//!
+//! Every function returns a status: `PDFX_OK` (0) on success, otherwise the
+//! [`ErrorCode`](pdf_x_core::core::ErrorCode) of the failure or one of the `PDFX_ERROR_*` codes below.
+//! The message of the last failure on the calling thread is available from
+//! [`pdfx_last_error_message`]. Panics never cross the boundary; they are
+//! reported as [`PDFX_ERROR_PANIC`]. That needs a build that unwinds on
+//! panic: use the `release-capi` profile, since the workspace's `release`
+//! profile aborts on panic.
+//!
+//! A document handle must not be used from two threads at the same time.
+
+use pdf_x_core::rendering::RenderOptions;
+use pdf_x_core::{PDFDocument, PDFError, Pdf};
+use serde_json::{Value, json};
+use std::cell::RefCell;
+use std::ffi::{CStr, CString, c_char};
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::ptr;
+
+/// The call succeeded.
+pub const PDFX_OK: u32 = 0;
+/// A required pointer argument was null.
+pub const PDFX_ERROR_NULL_POINTER: u32 = 100;
+/// An argument was invalid, such as a path that isn't UTF-8.
+pub const PDFX_ERROR_INVALID_ARGUMENT: u32 = 101;
+/// The caller's buffer can't hold the result.
+pub const PDFX_ERROR_BUFFER_TOO_SMALL: u32 = 102;
+/// The engine panicked. The document handle should not be used again.
+///
+/// Only reported by builds that unwind on panic, such as the `release-capi`
+/// profile; otherwise a panic aborts the process.
+pub const PDFX_ERROR_PANIC: u32 = 103;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// An open document, owned by the caller until [`pdfx_document_free`].
+pub struct PdfxDocument {
+    inner: PDFDocument,
+}
+
+/// A failure of a C API call.
+struct CallError {
+    status: u32,
+    message: String,
+}
+
+impl From<PDFError> for CallError {
+    fn from(error: PDFError) -> Self {
+        CallError {
+            status: error.code() as u32,
+            message: error.to_string(),
+        }
+    }
+}
+
+impl CallError {
+    fn new(status: u32, message: impl Into<String>) -> Self {
+        CallError {
+            status,
+            message: message.into(),
+        }
+    }
+
+    fn null(argument: &str) -> Self {
+        CallError::new(
+            PDFX_ERROR_NULL_POINTER,
+            format!("{} must not be null", argument),
+        )
+    }
+}
+
+fn set_last_error(message: &str) {
+    // Interior NULs would truncate the message; drop them
+    let message = CString::new(message.replace('\0', "")).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+}
+
+/// Runs `f`, turning its result or panic into a status code.
+fn call(f: impl FnOnce() -> Result<(), CallError>) -> u32 {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => PDFX_OK,
+        Ok(Err(error)) => {
+            set_last_error(&error.message);
+            error.status
+        }
+        Err(_) => {
+            set_last_error("internal error (panic)");
+            PDFX_ERROR_PANIC
+        }
+    }
+}
+
+/// Reads an optional NUL-terminated UTF-8 string.
+///
+/// # Safety
+/// `s` must be null or point to a NUL-terminated string.
+unsafe fn optional_str<'a>(s: *const c_char, argument: &str) -> Result<Option<&'a str>, CallError> {
+    if s.is_null() {
+        return Ok(None);
+    }
+    // SAFETY: guaranteed by the caller
+    let s = unsafe { CStr::from_ptr(s) };
+    s.to_str().map(Some).map_err(|_| {
+        CallError::new(
+            PDFX_ERROR_INVALID_ARGUMENT,
+            format!("{} is not valid UTF-8", argument),
+        )
+    })
+}
+
+/// Returns the document behind a handle.
+///
+/// # Safety
+/// `doc` must be null or a handle returned by one of the open functions and
+/// not yet freed.
+unsafe fn document<'a>(doc: *mut PdfxDocument) -> Result<&'a mut PDFDocument, CallError> {
+    // SAFETY: guaranteed by the caller
+    unsafe { doc.as_mut() }
+        .map(|doc| &mut doc.inner)
+        .ok_or_else(|| CallError::null("doc"))
+}
+
+/// Stores a new document handle in `out`.
+///
+/// # Safety
+/// `out` must be valid for writes.
+unsafe fn store_document(doc: PDFDocument, out: *mut *mut PdfxDocument) {
+    let handle = Box::into_raw(Box::new(PdfxDocument { inner: doc }));
+    // SAFETY: guaranteed by the caller
+    unsafe { *out = handle };
+}
+
+/// Returns the message of the last failed call on this thread, or null if
+/// no call has failed yet.
+///
+/// The string stays valid until the next failing call on the same thread
+/// and must not be freed.
+#[unsafe(no_mangle)]
+pub extern "C" fn pdfx_last_error_message() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(ptr::null(), |message| message.as_ptr())
+    })
+}
+
+/// Opens a document from bytes in memory.
+///
+/// The bytes are copied, so the buffer can be released as soon as the call
+/// returns.
+///
+/// # Arguments
+/// * `data`, `len` - The file contents
+/// * `password` - NUL-terminated UTF-8 password, or null
+/// * `out` - Receives the document handle on success
+///
+/// # Safety
+/// `data` must point to `len` readable bytes, `password` must be null or a
+/// NUL-terminated string, and `out` must be valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pdfx_document_open_bytes(
+    data: *const u8,
+    len: usize,
+    password: *const c_char,
+    out: *mut *mut PdfxDocument,
+) -> u32 {
+    call(|| {
+        if data.is_null() {
+            return Err(CallError::null("data"));
+        }
+        if out.is_null() {
+            return Err(CallError::null("out"));
+        }
+        // SAFETY: guaranteed by the caller
+        let bytes = unsafe { std::slice::from_raw_parts(data, len) }.to_vec();
+        // SAFETY: guaranteed by the caller
+        let password = unsafe { optional_str(password, "password") }?;
+
+        let mut options = Pdf::from_bytes(bytes);
+        if let Some(password) = password {
+            options = options.password(password);
+        }
+        let doc = options.load()?;
+        // SAFETY: checked for null above; validity guaranteed by the caller
+        unsafe { store_document(doc, out) };
+        Ok(())
+    })
+}
+
+/// Opens a document from a file, reading it progressively.
+///
+/// # Arguments
+/// * `path` - NUL-terminated UTF-8 path
+/// * `password` - NUL-terminated UTF-8 password, or null
+/// * `out` - Receives the document handle on success
+///
+/// # Safety
+/// `path` must be a NUL-terminated string, `password` must be null or a
+/// NUL-terminated string, and `out` must be valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pdfx_document_open_file(
+    path: *const c_char,
+    password: *const c_char,
+    out: *mut *mut PdfxDocument,
+) -> u32 {
+    call(|| {
+        if out.is_null() {
+            return Err(CallError::null("out"));
+        }
+        // SAFETY: guaranteed by the caller
+        let path = unsafe { optional_str(path, "path") }?.ok_or_else(|| CallError::null("path"))?;
+        // SAFETY: guaranteed by the caller
+        let password = unsafe { optional_str(password, "password") }?;
+
+        let mut options = Pdf::open(path);
+        if let Some(password) = password {
+            options = options.password(password);
+        }
+        let doc = options.load()?;
+        // SAFETY: checked for null above; validity guaranteed by the caller
+        unsafe { store_document(doc, out) };
+        Ok(())
+    })
+}
+
+/// Closes a document. Null is ignored.
+///
+/// # Safety
+/// `doc` must be null or a handle returned by one of the open functions,
+/// and must not be used afterwards.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pdfx_document_free(doc: *mut PdfxDocument) {
+    if !doc.is_null() {
+        // SAFETY: the handle was created by Box::into_raw in store_document
+        drop(unsafe { Box::from_raw(doc) });
+    }
+}
+
+/// Gets the number of pages.
+///
+/// # Safety
+/// `doc` must be a live document handle and `out` must be valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pdfx_document_page_count(doc: *mut PdfxDocument, out: *mut u32) -> u32 {
+    call(|| {
+        // SAFETY: guaranteed by the caller
+        let doc = unsafe { document(doc) }?;
+        if out.is_null() {
+            return Err(CallError::null("out"));
+        }
+        let count = doc.page_count()?;
+        // SAFETY: checked for null above; validity guaranteed by the caller
+        unsafe { *out = count };
+        Ok(())
+    })
+}
+
+/// Extracts the text of a page as a JSON array.
+///
+/// Each element describes one run of text:
+/// `{"text": "...", "font": "Helvetica", "size": 12.0, "x": 72.0, "y": 700.0, "width": 80.5}`.
+/// Coordinates are in PDF user space (origin at the bottom left). Fields
+/// that aren't known are `null`.
+///
+/// # Arguments
+/// * `doc` - The document
+/// * `page_index` - Zero-based page index
+/// * `out_json` - Receives a NUL-terminated UTF-8 string, to be released
+///   with [`pdfx_string_free`]
+///
+/// # Safety
+/// `doc` must be a live document handle and `out_json` must be valid for
+/// writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pdfx_page_extract_text_json(
+    doc: *mut PdfxDocument,
+    page_index: u32,
+    out_json: *mut *mut c_char,
+) -> u32 {
+    call(|| {
+        // SAFETY: guaranteed by the caller
+        let doc = unsafe { document(doc) }?;
+        if out_json.is_null() {
+            return Err(CallError::null("out_json"));
+        }
+        let items: Vec<Value> = doc
+            .extract_text_from_page(page_index as usize)?
+            .into_iter()
+            .map(|item| {
+                json!({
+                    "text": item.text,
+                    "font": item.font_name,
+                    "size": item.font_size,
+                    "x": item.position.map(|(x, _)| x),
+                    "y": item.position.map(|(_, y)| y),
+                    "width": item.width,
+                })
+            })
+            .collect();
+        let text = Value::Array(items).to_string();
+        // serde_json escapes control characters, so there is no NUL to trip on
+        let text = CString::new(text)
+            .map_err(|e| CallError::new(PDFX_ERROR_INVALID_ARGUMENT, e.to_string()))?;
+        // SAFETY: checked for null above; validity guaranteed by the caller
+        unsafe { *out_json = text.into_raw() };
+        Ok(())
+    })
+}
+
+/// Releases a string returned by this library. Null is ignored.
+///
+/// # Safety
+/// `s` must be null or a string returned by this library, and must not be
+/// used afterwards.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pdfx_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        // SAFETY: the string was created by CString::into_raw
+        drop(unsafe { CString::from_raw(s) });
+    }
+}
+
+/// Gets the size in pixels of a page rendered at `scale`.
+///
+/// Use it to size the buffer for [`pdfx_page_render_rgba`]: the page needs
+/// `width * height * 4` bytes.
+///
+/// # Safety
+/// `doc` must be a live document handle and `out_width` and `out_height`
+/// must be valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pdfx_page_render_size(
+    doc: *mut PdfxDocument,
+    page_index: u32,
+    scale: f32,
+    out_width: *mut u32,
+    out_height: *mut u32,
+) -> u32 {
+    call(|| {
+        // SAFETY: guaranteed by the caller
+        let doc = unsafe { document(doc) }?;
+        if out_width.is_null() || out_height.is_null() {
+            return Err(CallError::null("out_width and out_height"));
+        }
+        let options = render_options(scale)?;
+        let (width, height) = render_size(doc, page_index as usize, &options)?;
+        // SAFETY: checked for null above; validity guaranteed by the caller
+        unsafe {
+            *out_width = width;
+            *out_height = height;
+        }
+        Ok(())
+    })
+}
+
+/// Renders a page into a caller-provided RGBA buffer.
+///
+/// The pixels are written row by row from the top, 4 bytes per pixel
+/// (non-premultiplied R, G, B, A), on a white background. The image size is
+/// stored in `out_width` and `out_height` (either may be null), and is the
+/// one reported by [`pdfx_page_render_size`] for the same scale.
+///
+/// Fails with `PDFX_ERROR_BUFFER_TOO_SMALL` if `buffer_len` is less than
+/// `width * height * 4`, and with the `Unsupported` code if the library was
+/// built without the `rendering` feature.
+///
+/// # Safety
+/// `doc` must be a live document handle, `buffer` must point to
+/// `buffer_len` writable bytes, and `out_width` and `out_height` must be
+/// null or valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pdfx_page_render_rgba(
+    doc: *mut PdfxDocument,
+    page_index: u32,
+    scale: f32,
+    buffer: *mut u8,
+    buffer_len: usize,
+    out_width: *mut u32,
+    out_height: *mut u32,
+) -> u32 {
+    call(|| {
+        // SAFETY: guaranteed by the caller
+        let doc = unsafe { document(doc) }?;
+        if buffer.is_null() {
+            return Err(CallError::null("buffer"));
+        }
+        let options = render_options(scale)?;
+        // Check the buffer before spending the time to render
+        let (width, height) = render_size(doc, page_index as usize, &options)?;
+        check_buffer(width, height, buffer_len)?;
+        let (width, height, pixels) = render_page(doc, page_index as usize, options)?;
+        check_buffer(width, height, buffer_len)?;
+        // SAFETY: the buffer holds at least buffer_len >= pixels.len() bytes
+        unsafe { ptr::copy_nonoverlapping(pixels.as_ptr(), buffer, pixels.len()) };
+        // SAFETY: null or valid for writes, guaranteed by the caller
+        unsafe {
+            if let Some(out) = out_width.as_mut() {
+                *out = width;
+            }
+            if let Some(out) = out_height.as_mut() {
+                *out = height;
+            }
+        }
+        Ok(())
+    })
+}
+
+/// The size of the image rendered from a page with the given options.
+fn render_size(
+    doc: &mut PDFDocument,
+    page_index: usize,
+    options: &RenderOptions,
+) -> Result<(u32, u32), CallError> {
+    let page = doc.get_page(page_index)?;
+    let viewport = page.viewport(doc.xref_mut(), options);
+    Ok((viewport.width, viewport.height))
+}
+
+/// Fails unless a buffer of `buffer_len` bytes holds a `width` x `height`
+/// RGBA image.
+fn check_buffer(width: u32, height: u32, buffer_len: usize) -> Result<(), CallError> {
+    let needed = (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|pixels| pixels.checked_mul(4))
+        .ok_or_else(|| {
+            CallError::new(
+                PDFX_ERROR_INVALID_ARGUMENT,
+                format!(
+                    "a {}x{} page is too large to fit in memory; lower the scale",
+                    width, height
+                ),
+            )
+        })?;
+    if needed > buffer_len {
+        return Err(CallError::new(
+            PDFX_ERROR_BUFFER_TOO_SMALL,
+            format!(
+                "a {}x{} page needs {} bytes, the buffer has {}",
+                width, height, needed, buffer_len
+            ),
+        ));
+    }
+    Ok(())
+}
+
+fn render_options(scale: f32) -> Result<RenderOptions, CallError> {
+    if !(scale.is_finite() && scale > 0.0) {
+        return Err(CallError::new(
+            PDFX_ERROR_INVALID_ARGUMENT,
+            format!("scale must be positive, got {}", scale),
+        ));
+    }
+    Ok(RenderOptions {
+        scale,
+        ..Default::default()
+    })
+}
+
+#[cfg(feature = "rendering")]
+fn render_page(
+    doc: &mut PDFDocument,
+    page_index: usize,
+    options: RenderOptions,
+) -> Result<(u32, u32, Vec<u8>), CallError> {
+    Ok(doc.render_page_to_image(page_index, options)?)
+}
+
+#[cfg(not(feature = "rendering"))]
+fn render_page(
+    _doc: &mut PDFDocument,
+    _page_index: usize,
+    _options: RenderOptions,
+) -> Result<(u32, u32, Vec<u8>), CallError> {
+    Err(PDFError::unsupported("rendering (built without the `rendering` feature)").into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pdf_x_core::core::ErrorCode;
+    use pdf_x_core::test_support::build_pdf;
+
+    fn hello_pdf() -> Vec<u8> {
+        let content = "BT /F1 12 Tf 72 700 Td (Hello) Tj ET";
+        build_pdf(&[
+            "<< /Type /Catalog /Pages 2 0 R >>",
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>",
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 200 100] \
+             /Resources << /Font << /F1 4 0 R >> >> /Contents 5 0 R >>",
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>",
+            &format!(
+                "<< /Length {} >>\nstream\n{}\nendstream",
+                content.len(),
+                content
+            ),
+        ])
+    }
+
+    fn last_error() -> String {
+        let message = pdfx_last_error_message();
+        assert!(!message.is_null());
+        unsafe { CStr::from_ptr(message) }
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    #[test]
+    fn test_open_count_and_extract_text() {
+        let pdf = hello_pdf();
+        let mut doc = ptr::null_mut();
+        unsafe {
+            assert_eq!(
+                pdfx_document_open_bytes(pdf.as_ptr(), pdf.len(), ptr::null(), &mut doc),
+                PDFX_OK
+            );
+
+            let mut pages = 0;
+            assert_eq!(pdfx_document_page_count(doc, &mut pages), PDFX_OK);
+            assert_eq!(pages, 1);
+
+            let mut json = ptr::null_mut();
+            assert_eq!(pdfx_page_extract_text_json(doc, 0, &mut json), PDFX_OK);
+            let text = CStr::from_ptr(json).to_str().unwrap().to_string();
+            pdfx_string_free(json);
+            let items: Value = serde_json::from_str(&text).unwrap();
+            assert_eq!(items[0]["text"], "Hello");
+            assert_eq!(items[0]["x"], 72.0);
+
+            let (mut width, mut height) = (0, 0);
+            assert_eq!(
+                pdfx_page_render_size(doc, 0, 2.0, &mut width, &mut height),
+                PDFX_OK
+            );
+            assert_eq!((width, height), (400, 200));
+
+            pdfx_document_free(doc);
+        }
+    }
+
+    /// The header's name for an engine error code. The match is exhaustive,
+    /// so a new [`ErrorCode`] doesn't compile until it is named here.
+    fn header_name(code: ErrorCode) -> &'static str {
+        match code {
+            ErrorCode::Other => "PDFX_ERROR_OTHER",
+            ErrorCode::UnexpectedEndOfStream => "PDFX_ERROR_UNEXPECTED_END_OF_STREAM",
+            ErrorCode::InvalidByteRange => "PDFX_ERROR_INVALID_BYTE_RANGE",
+            ErrorCode::DataNotLoaded => "PDFX_ERROR_DATA_NOT_LOADED",
+            ErrorCode::DataMissing => "PDFX_ERROR_DATA_MISSING",
+            ErrorCode::InvalidPosition => "PDFX_ERROR_INVALID_POSITION",
+            ErrorCode::InvalidObject => "PDFX_ERROR_INVALID_OBJECT",
+            ErrorCode::Parse => "PDFX_ERROR_PARSE",
+            ErrorCode::XRef => "PDFX_ERROR_XREF",
+            ErrorCode::Page => "PDFX_ERROR_PAGE",
+            ErrorCode::Font => "PDFX_ERROR_FONT",
+            ErrorCode::ContentStream => "PDFX_ERROR_CONTENT_STREAM",
+            ErrorCode::IO => "PDFX_ERROR_IO",
+            ErrorCode::Corrupted => "PDFX_ERROR_CORRUPTED",
+            ErrorCode::Unsupported => "PDFX_ERROR_UNSUPPORTED",
+            ErrorCode::Validation => "PDFX_ERROR_VALIDATION",
+            ErrorCode::PermissionDenied => "PDFX_ERROR_PERMISSION_DENIED",
+            ErrorCode::Cancelled => "PDFX_ERROR_CANCELLED",
+            ErrorCode::Stream => "PDFX_ERROR_STREAM",
+            ErrorCode::MissingObject => "PDFX_ERROR_MISSING_OBJECT",
+            ErrorCode::Filter => "PDFX_ERROR_FILTER",
+            ErrorCode::Encryption => "PDFX_ERROR_ENCRYPTION",
+            ErrorCode::LimitExceeded => "PDFX_ERROR_LIMIT_EXCEEDED",
+        }
+    }
+
+    #[test]
+    fn test_header_mirrors_error_codes() {
+        let header = include_str!("../include/pdf_x.h");
+        let defines: Vec<(&str, u32)> = header
+            .lines()
+            .filter_map(|line| {
+                let mut parts = line.strip_prefix("#define ")?.split_whitespace();
+                Some((parts.next()?, parts.next()?.parse().ok()?))
+            })
+            .collect();
+
+        let codes = [
+            ErrorCode::Other,
+            ErrorCode::UnexpectedEndOfStream,
+            ErrorCode::InvalidByteRange,
+            ErrorCode::DataNotLoaded,
+            ErrorCode::DataMissing,
+            ErrorCode::InvalidPosition,
+            ErrorCode::InvalidObject,
+            ErrorCode::Parse,
+            ErrorCode::XRef,
+            ErrorCode::Page,
+            ErrorCode::Font,
+            ErrorCode::ContentStream,
+            ErrorCode::IO,
+            ErrorCode::Corrupted,
+            ErrorCode::Unsupported,
+            ErrorCode::Validation,
+            ErrorCode::PermissionDenied,
+            ErrorCode::Cancelled,
+            ErrorCode::Stream,
+            ErrorCode::MissingObject,
+            ErrorCode::Filter,
+            ErrorCode::Encryption,
+            ErrorCode::LimitExceeded,
+        ];
+        for code in codes {
+            let name = header_name(code);
+            assert!(
+                defines.contains(&(name, code as u32)),
+                "pdf_x.h lacks #define {} {}",
+                name,
+                code as u32
+            );
+        }
+        // Codes 1-99 are the engine's, and there is no define for a code
+        // that doesn't exist
+        let engine_codes = defines
+            .iter()
+            .filter(|(_, value)| (1..100).contains(value))
+            .count();
+        assert_eq!(engine_codes, codes.len());
+
+        for (name, value) in [
+            ("PDFX_OK", PDFX_OK),
+            ("PDFX_ERROR_NULL_POINTER", PDFX_ERROR_NULL_POINTER),
+            ("PDFX_ERROR_INVALID_ARGUMENT", PDFX_ERROR_INVALID_ARGUMENT),
+            ("PDFX_ERROR_BUFFER_TOO_SMALL", PDFX_ERROR_BUFFER_TOO_SMALL),
+            ("PDFX_ERROR_PANIC", PDFX_ERROR_PANIC),
+        ] {
+            assert!(defines.contains(&(name, value)), "{} differs", name);
+        }
+    }
+
+    #[test]
+    fn test_check_buffer() {
+        assert!(check_buffer(200, 100, 200 * 100 * 4).is_ok());
+        let error = check_buffer(200, 100, 200 * 100 * 4 - 1).unwrap_err();
+        assert_eq!(error.status, PDFX_ERROR_BUFFER_TOO_SMALL);
+
+        // A byte count that overflows usize is refused, not wrapped
+        let error = check_buffer(u32::MAX, u32::MAX, usize::MAX).unwrap_err();
+        assert_eq!(error.status, PDFX_ERROR_INVALID_ARGUMENT);
+    }
+
+    #[test]
+    fn test_errors_are_reported() {
+        let garbage = b"not a pdf";
+        let mut doc = ptr::null_mut();
+        unsafe {
+            let status =
+                pdfx_document_open_bytes(garbage.as_ptr(), garbage.len(), ptr::null(), &mut doc);
+            assert_ne!(status, PDFX_OK);
+            assert!(doc.is_null());
+            assert!(!last_error().is_empty());
+
+            assert_eq!(
+                pdfx_document_open_bytes(ptr::null(), 0, ptr::null(), &mut doc),
+                PDFX_ERROR_NULL_POINTER
+            );
+            assert_eq!(last_error(), "data must not be null");
+
+            let pdf = hello_pdf();
+            assert_eq!(
+                pdfx_document_open_bytes(pdf.as_ptr(), pdf.len(), ptr::null(), &mut doc),
+                PDFX_OK
+            );
+            let mut json = ptr::null_mut();
+            let status = pdfx_page_extract_text_json(doc, 5, &mut json);
+            assert_ne!(status, PDFX_OK);
+            assert!(json.is_null());
+
+            // Too small a buffer is refused before rendering
+            let mut pixel = [0u8; 4];
+            let status = pdfx_page_render_rgba(
+                doc,
+                0,
+                1.0,
+                pixel.as_mut_ptr(),
+                pixel.len(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+            );
+            assert_eq!(status, PDFX_ERROR_BUFFER_TOO_SMALL);
+            assert!(last_error().contains("needs"), "{}", last_error());
+
+            // A 200x100 page fits; without rendering it then fails as unsupported
+            let mut pixels = vec![0u8; 200 * 100 * 4];
+            let (mut width, mut height) = (0, 0);
+            let status = pdfx_page_render_rgba(
+                doc,
+                0,
+                1.0,
+                pixels.as_mut_ptr(),
+                pixels.len(),
+                &mut width,
+                &mut height,
+            );
+            if cfg!(feature = "rendering") {
+                assert_eq!(status, PDFX_OK);
+                assert_eq!((width, height), (200, 100));
+            } else {
+                assert_eq!(status, ErrorCode::Unsupported as u32);
+            }
+
+            pdfx_document_free(doc);
+        }
+    }
+}
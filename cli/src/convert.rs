@@ -0,0 +1,125 @@
+//! `pdf-inspect convert`: convert a document to Markdown or HTML.
+//!
+//! The output goes to stdout unless `-o` names a file.
+
+use pdf_x_core::PDFDocument;
+use pdf_x_core::convert::html::{self, HtmlLayout, HtmlOptions};
+use pdf_x_core::convert::markdown::{self, MarkdownOptions};
+use std::path::PathBuf;
+
+/// Output format of `pdf-inspect convert`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Markdown,
+    Html,
+}
+
+/// Settings for `pdf-inspect convert`.
+#[derive(Debug, Clone)]
+pub struct ConvertArgs {
+    /// PDF file to convert
+    pub file: PathBuf,
+    /// Output format
+    pub format: Format,
+    /// With HTML, write flowed paragraphs instead of positioned text
+    pub flowed: bool,
+    /// With Markdown, separate pages with a horizontal rule
+    pub page_breaks: bool,
+    /// File to write, stdout if not set
+    pub output: Option<PathBuf>,
+}
+
+/// Prints the usage of the convert subcommand.
+pub fn print_usage(program: &str) {
+    eprintln!(
+        "Usage: {} convert <pdf-file> --markdown|--html [options]",
+        program
+    );
+    eprintln!("\nOptions:");
+    eprintln!("  --markdown           Headings, paragraphs, lists and tables as Markdown");
+    eprintln!("  --html               Text and images as HTML");
+    eprintln!("  --flowed             With --html, flowed paragraphs instead of positioned text");
+    eprintln!("  --page-breaks        With --markdown, separate pages with a horizontal rule");
+    eprintln!("  -o, --out <file>     File to write (default: stdout)");
+}
+
+/// Parses the arguments following `convert`.
+pub fn parse_args(args: &[String]) -> Result<ConvertArgs, String> {
+    let mut file = None;
+    let mut format = None;
+    let mut flowed = false;
+    let mut page_breaks = false;
+    let mut output = None;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--markdown" | "--html" => {
+                let requested = if arg == "--html" {
+                    Format::Html
+                } else {
+                    Format::Markdown
+                };
+                if format.is_some_and(|format| format != requested) {
+                    return Err("--markdown and --html can't be combined".to_string());
+                }
+                format = Some(requested);
+            }
+            "--flowed" => flowed = true,
+            "--page-breaks" => page_breaks = true,
+            "-o" | "--out" => {
+                let value = iter
+                    .next()
+                    .ok_or_else(|| format!("{} requires a value", arg))?;
+                output = Some(PathBuf::from(value));
+            }
+            other if other.starts_with('-') => return Err(format!("Unknown option: {}", other)),
+            other if file.is_none() => file = Some(PathBuf::from(other)),
+            other => return Err(format!("Unexpected argument: {}", other)),
+        }
+    }
+
+    Ok(ConvertArgs {
+        file: file.ok_or_else(|| "convert requires a PDF file".to_string())?,
+        format: format.ok_or_else(|| "convert requires --markdown or --html".to_string())?,
+        flowed,
+        page_breaks,
+        output,
+    })
+}
+
+/// Converts the document and writes the result.
+pub fn run(args: &ConvertArgs) -> Result<(), String> {
+    let mut doc = PDFDocument::open_file(&args.file, None, None).map_err(|e| format!("{:?}", e))?;
+
+    let converted = match args.format {
+        Format::Markdown => {
+            let options = MarkdownOptions {
+                page_breaks: args.page_breaks,
+                ..Default::default()
+            };
+            markdown::document_to_markdown(&mut doc, &options)
+        }
+        Format::Html => {
+            let options = HtmlOptions {
+                layout: if args.flowed {
+                    HtmlLayout::Flowed
+                } else {
+                    HtmlLayout::Positioned
+                },
+                ..Default::default()
+            };
+            html::document_to_html(&mut doc, &options)
+        }
+    }
+    .map_err(|e| format!("Conversion failed: {}", e))?;
+
+    match &args.output {
+        Some(output) => std::fs::write(output, converted)
+            .map_err(|e| format!("Cannot write {}: {}", output.display(), e)),
+        None => {
+            print!("{}", converted);
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,253 @@
+//! `pdf-inspect --extract-images <dir>`: write each page's images to files.
+//!
+//! Images are decoded with [`Page::extract_images`], which converts CMYK,
+//! Indexed and other color spaces and /Decode arrays to plain gray or RGB
+//! samples (stencil masks to black on transparent), and written as PNG.
+//! A JPEG that decodes to gray or RGB is copied as is, which keeps the
+//! original quality. Images drawn by form XObjects are extracted too.
+//!
+//! Files are named `page<n>-<resource name>.<ext>`, with the names of the
+//! enclosing forms before the image's (`page1-Fm0-Im0.png`), so an image
+//! shared by several pages is written once per page. Names that are the
+//! same once sanitized get a `-2`, `-3`, ... suffix.
+
+use pdf_x_core::core::{ColorSpace, DecodedImage, ImageColorSpace};
+use pdf_x_core::{ImageFormat, PDFDocument, PDFObject, Page};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+/// Writes the images of every page to `dir`.
+pub fn extract_images(doc: &mut PDFDocument, dir: &Path) {
+    let page_count = match doc.page_count() {
+        Ok(count) => count as usize,
+        Err(e) => {
+            println!("Error getting page count: {:?}", e);
+            return;
+        }
+    };
+
+    if let Err(e) = fs::create_dir_all(dir) {
+        println!("Error creating {}: {}", dir.display(), e);
+        return;
+    }
+
+    let mut written = 0;
+    let mut stems = HashSet::new();
+    for index in 0..page_count {
+        let page = match doc.get_page(index) {
+            Ok(page) => page,
+            Err(e) => {
+                println!("  Page {}: error loading page: {:?}", index + 1, e);
+                continue;
+            }
+        };
+
+        for (prefix, holder) in image_holders(&page, doc) {
+            written += write_images(doc, &holder, &prefix, dir, &mut stems);
+        }
+    }
+
+    println!("Wrote {} image(s) to {}", written, dir.display());
+}
+
+/// Returns the page and the form XObjects it draws, each with the name
+/// prefix of its images. Forms drawn more than once are listed once.
+fn image_holders(page: &Page, doc: &mut PDFDocument) -> Vec<(String, Page)> {
+    let mut holders = vec![(format!("page{}-", page.index() + 1), page.clone())];
+    let mut seen = HashSet::new();
+    let mut next = 0;
+    while next < holders.len() {
+        let xref = doc.xref_mut();
+        let xobjects = holders[next]
+            .1
+            .resources()
+            .and_then(|resources| xref.fetch_if_ref(resources).ok())
+            .and_then(|resources| resources.as_dictionary()?.get("XObject").cloned())
+            .and_then(|xobjects| xref.fetch_if_ref(&xobjects).ok());
+        let mut forms: Vec<(String, PDFObject)> = Vec::new();
+        for (name, entry) in xobjects
+            .as_ref()
+            .and_then(PDFObject::as_dictionary)
+            .into_iter()
+            .flatten()
+        {
+            if let PDFObject::Ref(obj_ref) = entry
+                && !seen.insert((obj_ref.num, obj_ref.generation))
+            {
+                continue;
+            }
+            if let Ok(PDFObject::Stream { dict, .. }) = xref.fetch_if_ref(entry)
+                && matches!(dict.get("Subtype"), Some(PDFObject::Name(subtype)) if subtype == "Form")
+            {
+                forms.push((name.clone(), PDFObject::Dictionary(dict)));
+            }
+        }
+        forms.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let prefix = holders[next].0.clone();
+        for (name, dict) in forms {
+            let form = Page::new(page.index(), dict, None);
+            holders.push((format!("{}{}-", prefix, file_name(&name)), form));
+        }
+        next += 1;
+    }
+    holders
+}
+
+/// Writes the images of a page or form, returning how many were written.
+fn write_images(
+    doc: &mut PDFDocument,
+    holder: &Page,
+    prefix: &str,
+    dir: &Path,
+    stems: &mut HashSet<String>,
+) -> usize {
+    let mut images = match holder.extract_images(doc.xref_mut()) {
+        Ok(images) => images,
+        Err(e) => {
+            println!(
+                "  {}: error extracting images: {:?}",
+                prefix.trim_end_matches('-'),
+                e
+            );
+            return 0;
+        }
+    };
+    images.sort_by(|a, b| a.metadata.name.cmp(&b.metadata.name));
+
+    let mut written = 0;
+    for image in images {
+        let stem = unique_stem(
+            stems,
+            format!("{}{}", prefix, file_name(&image.metadata.name)),
+        );
+        let result = match original_jpeg(holder, doc, &image) {
+            Some(jpeg) => {
+                let path = dir.join(format!("{}.jpg", stem));
+                fs::write(&path, &jpeg)
+                    .map(|()| path)
+                    .map_err(|e| e.to_string())
+            }
+            None => {
+                let path = dir.join(format!("{}.png", stem));
+                write_png(&image, &path).map(|()| path)
+            }
+        };
+
+        match result {
+            Ok(path) => {
+                println!(
+                    "  {} ({}x{}, {:?})",
+                    path.display(),
+                    image.width,
+                    image.height,
+                    image.color_space
+                );
+                written += 1;
+            }
+            Err(e) => println!("  Error writing {}: {}", stem, e),
+        }
+    }
+    written
+}
+
+/// Returns `stem`, or `stem-2`, `stem-3`, ... if it was already used.
+fn unique_stem(stems: &mut HashSet<String>, stem: String) -> String {
+    let mut unique = stem.clone();
+    let mut index = 2;
+    while !stems.insert(unique.clone()) {
+        unique = format!("{}-{}", stem, index);
+        index += 1;
+    }
+    unique
+}
+
+/// The stream data of a JPEG image of a page or form that can be copied
+/// unchanged: only DCTDecode applied, no /Decode array, and gray or RGB
+/// samples (CMYK JPEGs are often stored inverted and most viewers show them
+/// wrong).
+fn original_jpeg(holder: &Page, doc: &mut PDFDocument, image: &DecodedImage) -> Option<Vec<u8>> {
+    if image.metadata.format != ImageFormat::JPEG
+        || !matches!(
+            image.color_space,
+            ImageColorSpace::Gray | ImageColorSpace::RGB
+        )
+    {
+        return None;
+    }
+
+    let xref = doc.xref_mut();
+    let resources = xref.fetch_if_ref(holder.resources()?).ok()?;
+    let xobjects = xref
+        .fetch_if_ref(resources.as_dictionary()?.get("XObject")?)
+        .ok()?;
+    let stream = xref
+        .fetch_if_ref(xobjects.as_dictionary()?.get(&image.metadata.name)?)
+        .ok()?;
+    let PDFObject::Stream { dict, data } = stream else {
+        return None;
+    };
+
+    let filter = dict.get("Filter").or_else(|| dict.get("F"))?;
+    let only_dct = match filter {
+        PDFObject::Name(name) => is_dct(name),
+        PDFObject::Array(filters) => {
+            matches!(filters.as_slice(), [filter] if matches!(&**filter, PDFObject::Name(name) if is_dct(name)))
+        }
+        _ => false,
+    };
+    let has_decode = dict.contains_key("Decode") || dict.contains_key("D");
+
+    (only_dct && !has_decode).then_some(data)
+}
+
+fn is_dct(filter: &str) -> bool {
+    filter == "DCTDecode" || filter == "DCT"
+}
+
+/// Encodes the decoded samples as PNG, converting CMYK to RGB.
+fn write_png(image: &DecodedImage, path: &Path) -> Result<(), String> {
+    use image::ExtendedColorType;
+
+    let (data, color_type) = match (&image.color_space, image.channels) {
+        (ImageColorSpace::CMYK, _) => (
+            ColorSpace::DeviceCMYK.image_to_rgb(
+                &image.data,
+                image.width as usize,
+                image.height as usize,
+                8,
+                None,
+            ),
+            ExtendedColorType::Rgb8,
+        ),
+        (_, 1) => (image.data.clone(), ExtendedColorType::L8),
+        (_, 2) => (image.data.clone(), ExtendedColorType::La8),
+        (_, 3) => (image.data.clone(), ExtendedColorType::Rgb8),
+        (_, 4) => (image.data.clone(), ExtendedColorType::Rgba8),
+        (_, channels) => return Err(format!("unsupported channel count {}", channels)),
+    };
+
+    image::save_buffer_with_format(
+        path,
+        &data,
+        image.width,
+        image.height,
+        color_type,
+        image::ImageFormat::Png,
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Resource names may contain characters that aren't valid in file names.
+fn file_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '+' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
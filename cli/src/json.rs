@@ -0,0 +1,170 @@
+//! `pdf-inspect --json`: the inspection results as one JSON document.
+//!
+//! Objects are written with the `serde` support of pdf-x-core: names keep
+//! their `/`, strings are `{"string": ...}` (or `{"hex": ...}` when binary),
+//! references are `{"ref": [num, gen]}` and streams show their dictionary
+//! and length. `--depth N` expands references N levels deep
+//! (references that loop back, like `/Parent`, stay references).
+
+use pdf_x_core::core::serialize::inline_refs;
+use pdf_x_core::{PDFDocument, PDFObject, XRefEntry};
+use serde_json::{Map, Value, json};
+
+/// The sections to include, from the command line flags.
+#[derive(Debug, Clone, Default)]
+pub struct Sections {
+    pub version: bool,
+    pub info: bool,
+    pub catalog: bool,
+    pub trailer: bool,
+    pub xref: bool,
+    pub pages: bool,
+    pub images: bool,
+    pub object: Option<u32>,
+    /// How many references deep to expand objects
+    pub depth: usize,
+}
+
+/// Collects the requested sections of a document.
+pub fn inspect(
+    doc: &mut PDFDocument,
+    file: &str,
+    file_size: Option<u64>,
+    sections: &Sections,
+) -> Value {
+    let mut result = Map::new();
+    result.insert("file".to_string(), json!(file));
+    result.insert("file_size".to_string(), json!(file_size));
+    result.insert("page_count".to_string(), or_error(doc.page_count()));
+    result.insert("xref_entries".to_string(), json!(doc.xref().len()));
+    result.insert("linearized".to_string(), json!(doc.is_linearized()));
+
+    if sections.version {
+        result.insert("version".to_string(), or_error(doc.pdf_version()));
+    }
+    if sections.info {
+        let info = doc
+            .document_info()
+            .map(|info| info.map(|info| object(doc, &info, sections.depth)));
+        result.insert("info".to_string(), or_error(info));
+    }
+    if sections.catalog {
+        let catalog = doc.catalog().cloned();
+        let value = catalog.map_or(Value::Null, |catalog| object(doc, &catalog, sections.depth));
+        result.insert("catalog".to_string(), value);
+    }
+    if sections.trailer {
+        let trailer = doc.xref().trailer().cloned();
+        let value = trailer.map_or(Value::Null, |trailer| object(doc, &trailer, sections.depth));
+        result.insert("trailer".to_string(), value);
+    }
+    if sections.xref {
+        result.insert("xref".to_string(), xref_table(doc));
+    }
+    if sections.pages {
+        let pages = doc
+            .pages_dict()
+            .map(|pages| object(doc, &pages, sections.depth));
+        result.insert("pages".to_string(), or_error(pages));
+    }
+    if sections.images {
+        result.insert("images".to_string(), images(doc));
+    }
+    if let Some(num) = sections.object {
+        let fetched = doc
+            .xref_mut()
+            .fetch(num, 0)
+            .map(|obj| object(doc, &obj, sections.depth));
+        result.insert(
+            "object".to_string(),
+            json!({ "num": num, "generation": 0, "value": or_error(fetched) }),
+        );
+    }
+
+    Value::Object(result)
+}
+
+/// A value, or `{"error": "..."}` if it couldn't be read.
+fn or_error<T: Into<Value>>(result: Result<T, pdf_x_core::PDFError>) -> Value {
+    match result {
+        Ok(value) => value.into(),
+        Err(e) => json!({ "error": e.to_string() }),
+    }
+}
+
+/// Serializes an object, expanding references `depth` levels deep.
+fn object(doc: &mut PDFDocument, obj: &PDFObject, depth: usize) -> Value {
+    match inline_refs(doc.xref_mut(), obj, depth) {
+        Ok(expanded) => json!(expanded),
+        Err(e) => json!({ "error": e.to_string() }),
+    }
+}
+
+fn xref_table(doc: &PDFDocument) -> Value {
+    let entries = doc
+        .xref()
+        .iter_entries()
+        .map(|(num, entry)| match entry {
+            XRefEntry::Free {
+                next_free,
+                generation,
+            } => json!({
+                "num": num,
+                "type": "free",
+                "next_free": next_free,
+                "generation": generation,
+            }),
+            XRefEntry::Uncompressed { offset, generation } => json!({
+                "num": num,
+                "type": "uncompressed",
+                "offset": offset,
+                "generation": generation,
+            }),
+            XRefEntry::Compressed {
+                obj_stream_num,
+                index,
+            } => json!({
+                "num": num,
+                "type": "compressed",
+                "object_stream": obj_stream_num,
+                "index": index,
+            }),
+        })
+        .collect();
+
+    Value::Array(entries)
+}
+
+fn images(doc: &mut PDFDocument) -> Value {
+    let page_count = doc.page_count().unwrap_or(0) as usize;
+    let mut images = Vec::new();
+
+    for index in 0..page_count {
+        let metadata = doc
+            .get_page(index)
+            .and_then(|page| page.get_image_metadata(doc.xref_mut()));
+        let metadata = match metadata {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                images.push(json!({ "page": index + 1, "error": e.to_string() }));
+                continue;
+            }
+        };
+
+        for image in metadata {
+            images.push(json!({
+                "page": index + 1,
+                "name": image.name,
+                "format": format!("{:?}", image.format),
+                "width": image.width,
+                "height": image.height,
+                "bits_per_component": image.bits_per_component,
+                "color_space": image.color_space,
+                "has_alpha": image.has_alpha,
+                "bytes": image.data_length,
+            }));
+        }
+    }
+
+    Value::Array(images)
+}
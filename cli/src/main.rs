@@ -1,17 +1,93 @@
-use pdf_x_core::decode::{decode_flate, decode_png_predictor};
+use pdf_x_core::decode::{apply_filters_with_params, image_filter};
+use pdf_x_core::{ExtractionMode, Font, PDFDocument, PDFObject, XRefEntry};
 use pdf_x_core::{ImageDecoder, ImageFormat, Page};
-use pdf_x_core::{PDFDocument, PDFObject, XRefEntry};
 use std::env;
 use std::fs;
 use std::path::Path;
 use std::process;
 
+mod convert;
+mod images;
+mod json;
+mod render;
+mod watch;
+
 fn main() {
+    // Library diagnostics go to stderr; PDF_X_LOG takes a filter such as
+    // "warn,pdf_x::font=off" or "pdf_x::xref=trace".
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_env("PDF_X_LOG")
+                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("warn")),
+        )
+        .init();
+
     let args: Vec<String> = env::args().collect();
 
+    if args.get(1).is_some_and(|arg| arg == "watch") {
+        let options = match watch::parse_args(&args[2..]) {
+            Ok(options) => options,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                watch::print_usage(&args[0]);
+                process::exit(1);
+            }
+        };
+        if let Err(e) = watch::run(&options) {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    if args.get(1).is_some_and(|arg| arg == "convert") {
+        let options = match convert::parse_args(&args[2..]) {
+            Ok(options) => options,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                convert::print_usage(&args[0]);
+                process::exit(1);
+            }
+        };
+        if let Err(e) = convert::run(&options) {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    if args.get(1).is_some_and(|arg| arg == "render") {
+        let options = match render::parse_args(&args[2..]) {
+            Ok(options) => options,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                render::print_usage(&args[0]);
+                process::exit(1);
+            }
+        };
+        if let Err(e) = render::run(&options) {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
     if args.len() < 2 {
         eprintln!("PDF Structure Inspector");
         eprintln!("Usage: {} <pdf-file> [options]", args[0]);
+        eprintln!(
+            "       {} watch <dir> [--format json|text] [--out <dir>]",
+            args[0]
+        );
+        eprintln!(
+            "       {} render <pdf-file> [--page N] [--scale F] [-o out.png]",
+            args[0]
+        );
+        eprintln!(
+            "       {} convert <pdf-file> --markdown|--html [-o out]",
+            args[0]
+        );
         eprintln!("\nOptions:");
         eprintln!("  --all            Show all information (default)");
         eprintln!("  --catalog        Show document catalog");
@@ -19,15 +95,25 @@ fn main() {
         eprintln!("  --trailer        Show trailer dictionary");
         eprintln!("  --pages          Show pages dictionary");
         eprintln!("  --images         Extract and show image information");
+        eprintln!("  --extract-images <dir> Write decoded images as PNG/JPEG files to a directory");
         eprintln!("  --object <num>   Show specific object by number");
         eprintln!("  --version        Show PDF version");
         eprintln!("  --info           Show document metadata (Title, Author, etc.)");
         eprintln!("  --fonts          List fonts used in the document");
+        eprintln!("  --dump-fonts <dir> Write embedded font programs to a directory");
         eprintln!("  --extract-text   Extract text from all pages");
+        eprintln!("  --raw            With --extract-text, keep content order and raw strings");
+        eprintln!(
+            "  --reading-order  With --extract-text, read multi-column pages column by column"
+        );
         eprintln!("  --outline        Show document outline (bookmarks)");
         eprintln!("  --annotations    Show document annotations (links, notes, etc.)");
         eprintln!("  --stats          Show summary statistics");
         eprintln!("  --page-sizes     Show page dimensions");
+        eprintln!(
+            "  --json           Print catalog, xref, trailer, pages, images, object, info and version as JSON"
+        );
+        eprintln!("  --depth <n>      With --json, expand object references n levels deep");
         process::exit(1);
     }
 
@@ -50,10 +136,13 @@ fn main() {
     let show_info = args.iter().any(|x| x == "--info");
     let show_fonts = args.iter().any(|x| x == "--fonts");
     let extract_text = args.iter().any(|x| x == "--extract-text");
+    let raw_text = args.iter().any(|x| x == "--raw");
+    let reading_order = args.iter().any(|x| x == "--reading-order");
     let show_outline = args.iter().any(|x| x == "--outline");
     let show_annotations = args.iter().any(|x| x == "--annotations");
     let show_stats = args.iter().any(|x| x == "--stats");
     let show_page_sizes = args.iter().any(|x| x == "--page-sizes");
+    let json_output = args.iter().any(|x| x == "--json");
 
     // Check for --object option
     let object_num = if let Some(pos) = args.iter().position(|arg| arg == "--object") {
@@ -67,6 +156,66 @@ fn main() {
         None
     };
 
+    // Check for --dump-fonts option
+    let dump_fonts_dir = if let Some(pos) = args.iter().position(|arg| arg == "--dump-fonts") {
+        match args.get(pos + 1) {
+            Some(dir) => Some(dir.clone()),
+            None => {
+                eprintln!("Error: --dump-fonts requires a directory");
+                process::exit(1);
+            }
+        }
+    } else {
+        None
+    };
+
+    // Check for --extract-images option
+    let extract_images_dir =
+        if let Some(pos) = args.iter().position(|arg| arg == "--extract-images") {
+            match args.get(pos + 1) {
+                Some(dir) => Some(dir.clone()),
+                None => {
+                    eprintln!("Error: --extract-images requires a directory");
+                    process::exit(1);
+                }
+            }
+        } else {
+            None
+        };
+
+    // Check for --depth option
+    let depth = if let Some(pos) = args.iter().position(|arg| arg == "--depth") {
+        match args
+            .get(pos + 1)
+            .and_then(|depth| depth.parse::<usize>().ok())
+        {
+            Some(depth) => depth,
+            None => {
+                eprintln!("Error: --depth requires a number");
+                process::exit(1);
+            }
+        }
+    } else {
+        0
+    };
+
+    if json_output {
+        let text_only = [
+            (show_fonts, "--fonts"),
+            (dump_fonts_dir.is_some(), "--dump-fonts"),
+            (extract_images_dir.is_some(), "--extract-images"),
+            (extract_text, "--extract-text"),
+            (show_outline, "--outline"),
+            (show_annotations, "--annotations"),
+            (show_stats, "--stats"),
+            (show_page_sizes, "--page-sizes"),
+        ];
+        if let Some((_, flag)) = text_only.iter().find(|(set, _)| *set) {
+            eprintln!("Error: {} is not available with --json", flag);
+            process::exit(1);
+        }
+    }
+
     // Open PDF document using progressive/chunked loading
     // This loads the PDF in 64KB chunks rather than reading the entire file into memory
     let mut doc = match PDFDocument::open_file(pdf_path, None, None) {
@@ -79,6 +228,41 @@ fn main() {
         }
     };
 
+    if json_output {
+        // Without section flags, show the same sections as running without
+        // options
+        let show_all = show_all
+            || !(show_version
+                || show_info
+                || show_catalog
+                || show_xref
+                || show_trailer
+                || show_pages
+                || show_images
+                || object_num.is_some());
+        let sections = json::Sections {
+            version: show_version,
+            info: show_info,
+            catalog: show_all || show_catalog,
+            trailer: show_all || show_trailer,
+            xref: show_all || show_xref,
+            pages: show_all || show_pages,
+            images: show_images,
+            object: object_num,
+            depth,
+        };
+        let file_size = fs::metadata(pdf_path).map(|m| m.len()).ok();
+        let result = json::inspect(&mut doc, pdf_path, file_size, &sections);
+        match serde_json::to_string_pretty(&result) {
+            Ok(text) => println!("{}", text),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                process::exit(1);
+            }
+        }
+        return;
+    }
+
     println!("╔═══════════════════════════════════════════════════════════╗");
     println!("║           PDF Structure Inspector                         ║");
     println!("╚═══════════════════════════════════════════════════════════╝");
@@ -134,6 +318,24 @@ fn main() {
             Ok(None) => println!("No document info dictionary found"),
             Err(e) => println!("Error retrieving document info: {:?}", e),
         }
+        match doc.info_conflicts() {
+            Ok(conflicts) if !conflicts.is_empty() => {
+                println!();
+                println!("Changed by incremental updates:");
+                for conflict in conflicts {
+                    println!("  {}:", conflict.key);
+                    for (revision, value) in &conflict.values {
+                        println!(
+                            "    revision {}: {}",
+                            revision,
+                            value.as_deref().unwrap_or("(absent)")
+                        );
+                    }
+                }
+            }
+            Ok(_) => {}
+            Err(e) => println!("Error comparing revisions: {:?}", e),
+        }
         println!();
     }
 
@@ -144,10 +346,23 @@ fn main() {
         println!();
     }
 
+    // Dump embedded fonts
+    if let Some(dir) = &dump_fonts_dir {
+        println!("═══════════════ EMBEDDED FONTS ═══════════════");
+        dump_fonts(&mut doc, Path::new(dir));
+        println!();
+    }
+
     // Extract text
     if extract_text {
         println!("═══════════════ TEXT EXTRACTION ═══════════════");
-        extract_all_text(&mut doc);
+        if raw_text {
+            extract_all_text_with_mode(&mut doc, ExtractionMode::Raw);
+        } else if reading_order {
+            extract_all_text_with_mode(&mut doc, ExtractionMode::ReadingOrder);
+        } else {
+            extract_all_text(&mut doc);
+        }
         println!();
     }
 
@@ -225,6 +440,13 @@ fn main() {
         println!();
     }
 
+    // Write decoded images to files
+    if let Some(dir) = &extract_images_dir {
+        println!("═══════════════ IMAGE FILES ═══════════════");
+        images::extract_images(&mut doc, Path::new(dir));
+        println!();
+    }
+
     // Show specific object
     if let Some(num) = object_num {
         println!("═══════════════ OBJECT {} 0 ═══════════════", num);
@@ -380,36 +602,34 @@ fn print_xref_table(xref: &pdf_x_core::XRef) {
     );
     println!("{}", "─".repeat(50));
 
-    for i in 0..xref.len() {
-        if let Some(entry) = xref.get_entry(i as u32) {
-            match entry {
-                XRefEntry::Free {
-                    next_free,
-                    generation,
-                } => {
-                    println!(
-                        "{:<8} {:<12} {:<12} {:<8}",
-                        i, "free", next_free, generation
-                    );
-                }
-                XRefEntry::Uncompressed { offset, generation } => {
-                    println!(
-                        "{:<8} {:<12} {:<12} {:<8}",
-                        i, "uncompressed", offset, generation
-                    );
-                }
-                XRefEntry::Compressed {
-                    obj_stream_num,
-                    index,
-                } => {
-                    println!(
-                        "{:<8} {:<12} {:<12} {:<8}",
-                        i,
-                        "compressed",
-                        format!("{}[{}]", obj_stream_num, index),
-                        "0"
-                    );
-                }
+    for (i, entry) in xref.iter_entries() {
+        match entry {
+            XRefEntry::Free {
+                next_free,
+                generation,
+            } => {
+                println!(
+                    "{:<8} {:<12} {:<12} {:<8}",
+                    i, "free", next_free, generation
+                );
+            }
+            XRefEntry::Uncompressed { offset, generation } => {
+                println!(
+                    "{:<8} {:<12} {:<12} {:<8}",
+                    i, "uncompressed", offset, generation
+                );
+            }
+            XRefEntry::Compressed {
+                obj_stream_num,
+                index,
+            } => {
+                println!(
+                    "{:<8} {:<12} {:<12} {:<8}",
+                    i,
+                    "compressed",
+                    format!("{}[{}]", obj_stream_num, index),
+                    "0"
+                );
             }
         }
     }
@@ -559,97 +779,28 @@ fn extract_image_info(
         data.len() as f64 / 1024.0
     );
 
-    // Detect format from PDF filter information first (more reliable for PDF images)
-    let format = if let Some(filter) = dict.get("Filter") {
-        match filter {
-            PDFObject::Name(filter_name) => {
-                match filter_name.as_str() {
-                    "DCTDecode" => ImageFormat::JPEG,
-                    "JPXDecode" => ImageFormat::JPEG2000,
-                    "JBIG2Decode" => ImageFormat::JBIG2,
-                    "FlateDecode" => {
-                        // FlateDecode images are raw compressed pixel data
-                        // Check if it's actually a PNG (rare but possible)
-                        if data.len() >= 4 {
-                            let detected = ImageDecoder::detect_format(&data[..4]);
-                            if detected != ImageFormat::Unknown {
-                                detected
-                            } else {
-                                ImageFormat::Raw
-                            }
-                        } else {
-                            ImageFormat::Raw
-                        }
-                    }
-                    "CCITTFaxDecode" | "RunLengthDecode" => {
-                        // For these filters, check the actual data header
-                        if data.len() >= 4 {
-                            ImageDecoder::detect_format(&data[..4])
-                        } else {
-                            ImageFormat::Unknown
-                        }
-                    }
-                    _ => ImageFormat::Unknown,
-                }
-            }
-            PDFObject::Array(filters) => {
-                // Handle multiple filters - check if any indicate the image format
-                let mut detected_format = ImageFormat::Unknown;
-                for filter_obj in filters {
-                    if let PDFObject::Name(filter_name) = &**filter_obj {
-                        match filter_name.as_str() {
-                            "DCTDecode" => {
-                                detected_format = ImageFormat::JPEG;
-                                break;
-                            }
-                            "JPXDecode" => {
-                                detected_format = ImageFormat::JPEG2000;
-                                break;
-                            }
-                            "JBIG2Decode" => {
-                                detected_format = ImageFormat::JBIG2;
-                                break;
-                            }
-                            "FlateDecode" => {
-                                detected_format = ImageFormat::Raw;
-                                break;
-                            }
-                            _ => continue,
-                        }
-                    }
-                }
-
-                if detected_format != ImageFormat::Unknown {
-                    detected_format
-                } else {
-                    // If no format-specific filter found, check data header
-                    if data.len() >= 4 {
-                        ImageDecoder::detect_format(&data[..4])
-                    } else {
-                        ImageFormat::Unknown
-                    }
-                }
-            }
-            _ => {
-                // Unknown filter type, check data header
-                if data.len() >= 4 {
-                    ImageDecoder::detect_format(&data[..4])
+    // Detect format from the codec in the filter chain (more reliable for PDF images)
+    let format = match dict.get("Filter") {
+        Some(filters) => match image_filter(filters) {
+            Some("DCTDecode") | Some("DCT") => ImageFormat::JPEG,
+            Some("JPXDecode") => ImageFormat::JPEG2000,
+            Some("JBIG2Decode") => ImageFormat::JBIG2,
+            Some(_) => ImageFormat::Unknown,
+            // Only compression/transport filters: raw pixel data
+            None => ImageFormat::Raw,
+        },
+        None => {
+            // No filter information, could be uncompressed raw data
+            if data.len() >= 4 {
+                let detected = ImageDecoder::detect_format(&data[..4]);
+                if detected != ImageFormat::Unknown {
+                    detected
                 } else {
-                    ImageFormat::Unknown
+                    ImageFormat::Raw
                 }
-            }
-        }
-    } else {
-        // No filter information, could be uncompressed raw data
-        if data.len() >= 4 {
-            let detected = ImageDecoder::detect_format(&data[..4]);
-            if detected != ImageFormat::Unknown {
-                detected
             } else {
                 ImageFormat::Raw
             }
-        } else {
-            ImageFormat::Raw
         }
     };
     println!("      Format: {:?}", format);
@@ -711,17 +862,21 @@ fn extract_image_info(
             ImageFormat::JPEG | ImageFormat::PNG | ImageFormat::Raw => {
                 // Attempt actual decoding
                 if format == ImageFormat::Raw {
-                    // For Raw images with FlateDecode, the stream data might already be decompressed
-                    // by the parser, or it might be raw. Try to decode as-is first, then try decompressing.
-                    let has_flate = if let Some(filter) = dict.get("Filter") {
-                        matches!(filter, PDFObject::Name(name) if name == "FlateDecode")
-                    } else {
-                        false
+                    // Undo all filters in the chain, with their DecodeParms
+                    let raw_data = match dict.get("Filter") {
+                        Some(filters) => {
+                            match apply_filters_with_params(data, filters, dict.get("DecodeParms"))
+                            {
+                                Ok(decoded) => decoded,
+                                Err(e) => {
+                                    println!("      ⚠️  Decompression failed: {:?}", e);
+                                    return;
+                                }
+                            }
+                        }
+                        None => data.to_vec(),
                     };
 
-                    // Try using data as-is first (might already be decompressed)
-                    let mut raw_data = data.to_vec();
-
                     // Extract metadata for decoding
                     let width = dict
                         .get("Width")
@@ -760,13 +915,12 @@ fn extract_image_info(
                         let color_space = ImageDecoder::parse_color_space(colorspace_obj);
 
                         if width > 0 && height > 0 {
-                            // Try decoding with current data
                             match ImageDecoder::decode_raw_image(
                                 &raw_data,
                                 width,
                                 height,
                                 bpc,
-                                color_space.clone(),
+                                color_space,
                             ) {
                                 Ok(decoded) => {
                                     println!(
@@ -775,107 +929,7 @@ fn extract_image_info(
                                     );
                                 }
                                 Err(e) => {
-                                    // If decoding failed and we have FlateDecode, try decompressing first
-                                    if has_flate {
-                                        match decode_flate(&data) {
-                                            Ok(mut decompressed) => {
-                                                // Check for PNG predictor in DecodeParms
-                                                if let Some(decode_parms) = dict.get("DecodeParms")
-                                                {
-                                                    if let PDFObject::Dictionary(parms) =
-                                                        decode_parms
-                                                    {
-                                                        if let Some(PDFObject::Number(predictor)) =
-                                                            parms.get("Predictor")
-                                                        {
-                                                            let pred_val = *predictor as u32;
-                                                            // PNG predictor is 10-15
-                                                            if pred_val >= 10 && pred_val <= 15 {
-                                                                let colors = parms
-                                                                    .get("Colors")
-                                                                    .and_then(|c| {
-                                                                        if let PDFObject::Number(
-                                                                            n,
-                                                                        ) = c
-                                                                        {
-                                                                            Some(*n as usize)
-                                                                        } else {
-                                                                            None
-                                                                        }
-                                                                    })
-                                                                    .unwrap_or(1);
-
-                                                                let columns = parms
-                                                                    .get("Columns")
-                                                                    .and_then(|c| {
-                                                                        if let PDFObject::Number(
-                                                                            n,
-                                                                        ) = c
-                                                                        {
-                                                                            Some(*n as usize)
-                                                                        } else {
-                                                                            None
-                                                                        }
-                                                                    })
-                                                                    .unwrap_or(width as usize);
-
-                                                                match decode_png_predictor(
-                                                                    &decompressed,
-                                                                    colors,
-                                                                    bpc as usize,
-                                                                    columns,
-                                                                ) {
-                                                                    Ok(unpredicted) => {
-                                                                        decompressed = unpredicted;
-                                                                    }
-                                                                    Err(pred_err) => {
-                                                                        println!(
-                                                                            "      ⚠️  PNG predictor failed: {:?}",
-                                                                            pred_err
-                                                                        );
-                                                                    }
-                                                                }
-                                                            }
-                                                        }
-                                                    }
-                                                }
-
-                                                raw_data = decompressed;
-
-                                                // Try decoding again with decompressed data
-                                                match ImageDecoder::decode_raw_image(
-                                                    &raw_data,
-                                                    width,
-                                                    height,
-                                                    bpc,
-                                                    color_space,
-                                                ) {
-                                                    Ok(decoded) => {
-                                                        println!(
-                                                            "      ✅ Decoded successfully: {}x{} ({} channels)",
-                                                            decoded.width,
-                                                            decoded.height,
-                                                            decoded.channels
-                                                        );
-                                                    }
-                                                    Err(decode_err) => {
-                                                        println!(
-                                                            "      ⚠️  Decoding failed: {:?}",
-                                                            decode_err
-                                                        );
-                                                    }
-                                                }
-                                            }
-                                            Err(decompress_err) => {
-                                                println!(
-                                                    "      ⚠️  Decompression failed: {:?}",
-                                                    decompress_err
-                                                );
-                                            }
-                                        }
-                                    } else {
-                                        println!("      ⚠️  Decoding failed: {:?}", e);
-                                    }
+                                    println!("      ⚠️  Decoding failed: {:?}", e);
                                 }
                             }
                         }
@@ -1086,6 +1140,35 @@ fn extract_all_text(doc: &mut PDFDocument) {
     println!("Total characters extracted: {}", total_chars);
 }
 
+fn extract_all_text_with_mode(doc: &mut PDFDocument, mode: ExtractionMode) {
+    let page_count = match doc.page_count() {
+        Ok(count) => count,
+        Err(e) => {
+            println!("Error getting page count: {:?}", e);
+            return;
+        }
+    };
+
+    let mut total_chars = 0;
+
+    for page_num in 0..page_count {
+        println!("═══ Page {} ═══", page_num + 1);
+
+        match doc.extract_text_from_page_as_string_with_mode(page_num as usize, mode) {
+            Ok(text) => {
+                println!("{}", text);
+                total_chars += text.chars().count();
+            }
+            Err(e) => {
+                println!("  Error extracting text: {:?}", e);
+            }
+        }
+        println!();
+    }
+
+    println!("Total characters extracted: {}", total_chars);
+}
+
 fn extract_outline(doc: &mut PDFDocument) {
     match doc.document_outline_items() {
         Ok(Some(outlines)) => {
@@ -1324,6 +1407,14 @@ fn extract_annotations(doc: &mut PDFDocument) {
                 for (i, annot) in annotations.iter().enumerate() {
                     println!("  [{}] {:?}", i + 1, annot.annotation_type);
 
+                    // Show stable identifiers
+                    if let Some((num, generation)) = annot.object_ref {
+                        println!("      Object: {} {} R", num, generation);
+                    }
+                    if let Some(ref name) = annot.name {
+                        println!("      Name: {}", name);
+                    }
+
                     // Show location
                     println!(
                         "      Location: [{}, {}, {}, {}]",
@@ -1351,6 +1442,12 @@ fn extract_annotations(doc: &mut PDFDocument) {
                             pdf_x_core::LinkAction::GoTo { page_index, dest } => {
                                 println!("      → Page {} ({:?})", page_index, dest);
                             }
+                            pdf_x_core::LinkAction::GoToPage { page_ref, dest } => {
+                                println!(
+                                    "      → Page object {} {} R ({:?})",
+                                    page_ref.0, page_ref.1, dest
+                                );
+                            }
                             pdf_x_core::LinkAction::GoToNamed { name } => {
                                 println!("      → Named destination: {}", name);
                             }
@@ -1368,6 +1465,9 @@ fn extract_annotations(doc: &mut PDFDocument) {
                                 println!("      Field name: {}", name);
                             }
                         }
+                        pdf_x_core::AnnotationData::Markup(markup) => {
+                            println!("      Quads: {}", markup.quad_points.len());
+                        }
                         _ => {}
                     }
 
@@ -1411,6 +1511,98 @@ fn extract_annotations(doc: &mut PDFDocument) {
     }
 }
 
+fn dump_fonts(doc: &mut PDFDocument, dir: &Path) {
+    let page_count = match doc.page_count() {
+        Ok(count) => count,
+        Err(e) => {
+            println!("Error getting page count: {:?}", e);
+            return;
+        }
+    };
+
+    if let Err(e) = fs::create_dir_all(dir) {
+        println!("Error creating {}: {}", dir.display(), e);
+        return;
+    }
+
+    // Fonts are shared between pages, so write each font object once
+    let mut seen = std::collections::HashSet::new();
+    let mut written = 0;
+
+    for page_num in 0..page_count {
+        let Ok(page) = doc.get_page(page_num as usize) else {
+            continue;
+        };
+        let Some(resources) = page.resources() else {
+            continue;
+        };
+        let Ok(PDFObject::Dictionary(res_dict)) = doc.xref_mut().fetch_if_ref(resources) else {
+            continue;
+        };
+        let Some(font_obj) = res_dict.get("Font") else {
+            continue;
+        };
+        let Ok(PDFObject::Dictionary(fonts)) = doc.xref_mut().fetch_if_ref(font_obj) else {
+            continue;
+        };
+
+        let mut fonts: Vec<_> = fonts.into_iter().collect();
+        fonts.sort_by(|a, b| a.0.cmp(&b.0));
+        for (resource_name, font_ref) in fonts {
+            let id = match &font_ref {
+                PDFObject::Ref(r) => format!("{}", r.num),
+                _ => format!("p{}-{}", page_num + 1, resource_name),
+            };
+            if !seen.insert(id.clone()) {
+                continue;
+            }
+
+            let font_dict = match doc.xref_mut().fetch_if_ref(&font_ref) {
+                Ok(dict) => dict,
+                Err(_) => continue,
+            };
+            let font = match Font::new(font_dict, doc.xref_mut()) {
+                Ok(font) => font,
+                Err(e) => {
+                    println!("  /{}: error loading font: {:?}", resource_name, e);
+                    continue;
+                }
+            };
+            let (data, format) = match font.extract_program(doc.xref_mut()) {
+                Ok(Some(program)) => program,
+                Ok(None) => continue,
+                Err(e) => {
+                    println!("  /{}: error reading font program: {:?}", resource_name, e);
+                    continue;
+                }
+            };
+
+            // Base font names may contain characters that aren't valid in file names
+            let base_font: String = font
+                .base_font()
+                .chars()
+                .map(|c| {
+                    if c.is_ascii_alphanumeric() || c == '-' || c == '+' {
+                        c
+                    } else {
+                        '_'
+                    }
+                })
+                .collect();
+            let path = dir.join(format!("{}-{}.{}", id, base_font, format.extension()));
+            match fs::write(&path, &data) {
+                Ok(()) => {
+                    println!("  {} ({:?}, {} bytes)", path.display(), format, data.len());
+                    written += 1;
+                }
+                Err(e) => println!("  Error writing {}: {}", path.display(), e),
+            }
+        }
+    }
+
+    println!("Wrote {} embedded font(s) to {}", written, dir.display());
+}
+
 fn count_fonts(doc: &mut PDFDocument) -> usize {
     use std::collections::HashSet;
 
@@ -0,0 +1,220 @@
+//! `pdf-inspect render`: rasterize one page to a PNG file.
+//!
+//! A quick way to check the rendering pipeline without the desktop app. The
+//! subcommand needs the `rendering` feature; without it, only the arguments
+//! are checked and an error explains how to rebuild.
+
+use std::path::{Path, PathBuf};
+
+/// Settings for `pdf-inspect render`.
+#[derive(Debug, Clone)]
+#[cfg_attr(not(feature = "rendering"), allow(dead_code))]
+pub struct RenderArgs {
+    /// PDF file to render
+    pub file: PathBuf,
+    /// 1-based page number
+    pub page: usize,
+    /// Pixels per PDF unit (1.0 renders at 72 DPI)
+    pub scale: f32,
+    /// Extra clockwise rotation in degrees (a multiple of 90)
+    pub rotation: i32,
+    /// Leave the background transparent instead of white
+    pub transparent: bool,
+    /// PNG file to write
+    pub output: PathBuf,
+}
+
+/// Prints the usage of the render subcommand.
+pub fn print_usage(program: &str) {
+    eprintln!("Usage: {} render <pdf-file> [options]", program);
+    eprintln!("\nOptions:");
+    eprintln!("  --page <n>           Page to render, starting at 1 (default: 1)");
+    eprintln!("  --scale <factor>     Pixels per point; 2.0 renders at 144 DPI (default: 1.0)");
+    eprintln!("  --rotate <degrees>   Extra clockwise rotation: 0, 90, 180 or 270");
+    eprintln!("  --transparent        Keep the background transparent instead of white");
+    eprintln!("  -o, --out <file>     PNG file to write (default: <name>-page<n>.png)");
+}
+
+/// Parses the arguments following `render`.
+pub fn parse_args(args: &[String]) -> Result<RenderArgs, String> {
+    let mut file = None;
+    let mut page = 1;
+    let mut scale = 1.0;
+    let mut rotation = 0;
+    let mut transparent = false;
+    let mut output = None;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let mut value = |name: &str| {
+            iter.next()
+                .cloned()
+                .ok_or_else(|| format!("{} requires a value", name))
+        };
+
+        match arg.as_str() {
+            "--page" => {
+                page = value("--page")?
+                    .parse()
+                    .ok()
+                    .filter(|&page| page >= 1)
+                    .ok_or_else(|| "--page requires a page number starting at 1".to_string())?;
+            }
+            "--scale" => {
+                scale = value("--scale")?
+                    .parse()
+                    .ok()
+                    .filter(|&scale: &f32| scale.is_finite() && scale > 0.0)
+                    .ok_or_else(|| "--scale requires a positive number".to_string())?;
+            }
+            "--rotate" => {
+                rotation = value("--rotate")?
+                    .parse()
+                    .ok()
+                    .filter(|&degrees: &i32| degrees % 90 == 0)
+                    .ok_or_else(|| "--rotate requires a multiple of 90".to_string())?;
+            }
+            "--transparent" => transparent = true,
+            "-o" | "--out" => output = Some(PathBuf::from(value(arg)?)),
+            other if other.starts_with('-') => return Err(format!("Unknown option: {}", other)),
+            other if file.is_none() => file = Some(PathBuf::from(other)),
+            other => return Err(format!("Unexpected argument: {}", other)),
+        }
+    }
+
+    let file = file.ok_or_else(|| "render requires a PDF file".to_string())?;
+    let output = output.unwrap_or_else(|| default_output(&file, page));
+
+    Ok(RenderArgs {
+        file,
+        page,
+        scale,
+        rotation,
+        transparent,
+        output,
+    })
+}
+
+/// `<name>-page<n>.png` in the current directory.
+fn default_output(file: &Path, page: usize) -> PathBuf {
+    let stem = file
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "output".to_string());
+    PathBuf::from(format!("{}-page{}.png", stem, page))
+}
+
+/// Renders the page and writes the PNG file.
+#[cfg(feature = "rendering")]
+pub fn run(args: &RenderArgs) -> Result<(), String> {
+    use pdf_x_core::PDFDocument;
+    use pdf_x_core::rendering::RenderOptions;
+
+    let mut doc = PDFDocument::open_file(&args.file, None, None).map_err(|e| e.to_string())?;
+    let page_count = doc.page_count().map_err(|e| e.to_string())? as usize;
+    if args.page > page_count {
+        return Err(format!(
+            "Page {} does not exist (the document has {} pages)",
+            args.page, page_count
+        ));
+    }
+
+    let options = RenderOptions {
+        scale: args.scale,
+        rotation: args.rotation,
+        background: (!args.transparent).then_some([255, 255, 255, 255]),
+        ..Default::default()
+    };
+    let (width, height, pixels) = doc
+        .render_page_to_image(args.page - 1, options)
+        .map_err(|e| format!("Rendering failed: {}", e))?;
+
+    image::save_buffer(
+        &args.output,
+        &pixels,
+        width,
+        height,
+        image::ExtendedColorType::Rgba8,
+    )
+    .map_err(|e| format!("Cannot write {}: {}", args.output.display(), e))?;
+
+    eprintln!(
+        "Page {} -> {} ({}x{})",
+        args.page,
+        args.output.display(),
+        width,
+        height
+    );
+    Ok(())
+}
+
+/// Without the `rendering` feature there is no rasterizer to call.
+#[cfg(not(feature = "rendering"))]
+pub fn run(_args: &RenderArgs) -> Result<(), String> {
+    Err("pdf-inspect was built without rendering support; \
+         rebuild with `cargo build -p pdf-inspect --features rendering`"
+        .to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_args_defaults() {
+        let parsed = parse_args(&args(&["docs/report.pdf"])).unwrap();
+        assert_eq!(parsed.file, PathBuf::from("docs/report.pdf"));
+        assert_eq!(parsed.page, 1);
+        assert_eq!(parsed.scale, 1.0);
+        assert_eq!(parsed.rotation, 0);
+        assert!(!parsed.transparent);
+        // Written to the current directory, named after the page
+        assert_eq!(parsed.output, PathBuf::from("report-page1.png"));
+    }
+
+    #[test]
+    fn test_parse_args_options() {
+        let parsed = parse_args(&args(&[
+            "report.pdf",
+            "--page",
+            "3",
+            "--scale",
+            "2.5",
+            "--rotate",
+            "-90",
+            "--transparent",
+        ]))
+        .unwrap();
+        assert_eq!(parsed.page, 3);
+        assert_eq!(parsed.scale, 2.5);
+        assert_eq!(parsed.rotation, -90);
+        assert!(parsed.transparent);
+        assert_eq!(parsed.output, PathBuf::from("report-page3.png"));
+
+        let parsed = parse_args(&args(&["-o", "out.png", "report.pdf"])).unwrap();
+        assert_eq!(parsed.output, PathBuf::from("out.png"));
+        let parsed = parse_args(&args(&["report.pdf", "--out", "page.png"])).unwrap();
+        assert_eq!(parsed.output, PathBuf::from("page.png"));
+    }
+
+    #[test]
+    fn test_parse_args_errors() {
+        for bad in [
+            &[][..],
+            &["report.pdf", "--page", "0"],
+            &["report.pdf", "--page", "two"],
+            &["report.pdf", "--scale", "-1"],
+            &["report.pdf", "--scale", "inf"],
+            &["report.pdf", "--rotate", "45"],
+            &["report.pdf", "--page"],
+            &["report.pdf", "--dpi", "300"],
+            &["report.pdf", "other.pdf"],
+        ] {
+            assert!(parse_args(&args(bad)).is_err(), "{:?} was accepted", bad);
+        }
+    }
+}
@@ -0,0 +1,532 @@
+//! `pdf-inspect watch`: process PDFs as they appear in a directory.
+//!
+//! The directory is polled for `.pdf` files. Each new or changed file is
+//! processed once its size and modification time have stayed the same for one
+//! polling interval (so files still being copied in are skipped), and the
+//! results are written to the output directory as `<name>.json` or
+//! `<name>.txt`.
+
+use pdf_x_core::{ImageFormat, PDFDocument};
+use serde_json::{Map, Value, json};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// Output format for extraction results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// One JSON document per PDF
+    Json,
+    /// Human-readable plain text
+    Text,
+}
+
+/// An extraction step applied to each PDF.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    /// Text of every page
+    Text,
+    /// Page count, version and the document metadata
+    Metadata,
+    /// Image XObjects on every page (name, format, size)
+    Images,
+}
+
+/// Settings for `pdf-inspect watch`.
+#[derive(Debug, Clone)]
+pub struct WatchOptions {
+    /// Directory to monitor
+    pub dir: PathBuf,
+    /// Directory the results are written to
+    pub out_dir: PathBuf,
+    /// Format of the result files
+    pub format: OutputFormat,
+    /// Extraction steps to run on each file
+    pub operations: Vec<Operation>,
+    /// Time between directory scans
+    pub interval: Duration,
+    /// Process the files currently in the directory and exit
+    pub once: bool,
+}
+
+/// Prints the usage of the watch subcommand.
+pub fn print_usage(program: &str) {
+    eprintln!("Usage: {} watch <dir> [options]", program);
+    eprintln!("\nOptions:");
+    eprintln!("  --out <dir>          Output directory (default: <dir>/out)");
+    eprintln!("  --format <fmt>       json (default) or text");
+    eprintln!("  --ops <list>         Comma-separated: text,metadata,images (default: all)");
+    eprintln!("  --interval <secs>    Seconds between scans (default: 2)");
+    eprintln!("  --once               Process existing files and exit");
+}
+
+/// Parses the arguments following `watch`.
+pub fn parse_args(args: &[String]) -> Result<WatchOptions, String> {
+    let mut dir = None;
+    let mut out_dir = None;
+    let mut format = OutputFormat::Json;
+    let mut operations = vec![Operation::Metadata, Operation::Text, Operation::Images];
+    let mut interval = Duration::from_secs(2);
+    let mut once = false;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let mut value = |name: &str| {
+            iter.next()
+                .cloned()
+                .ok_or_else(|| format!("{} requires a value", name))
+        };
+
+        match arg.as_str() {
+            "--out" => out_dir = Some(PathBuf::from(value("--out")?)),
+            "--format" => {
+                format = match value("--format")?.as_str() {
+                    "json" => OutputFormat::Json,
+                    "text" => OutputFormat::Text,
+                    other => return Err(format!("Unknown format: {}", other)),
+                }
+            }
+            "--ops" => {
+                operations = value("--ops")?
+                    .split(',')
+                    .map(|op| match op.trim() {
+                        "text" => Ok(Operation::Text),
+                        "metadata" => Ok(Operation::Metadata),
+                        "images" => Ok(Operation::Images),
+                        other => Err(format!("Unknown operation: {}", other)),
+                    })
+                    .collect::<Result<_, _>>()?;
+            }
+            "--interval" => {
+                let secs: f64 = value("--interval")?
+                    .parse()
+                    .map_err(|_| "--interval requires a number of seconds".to_string())?;
+                if !secs.is_finite() || secs <= 0.0 {
+                    return Err("--interval must be positive".to_string());
+                }
+                interval = Duration::from_secs_f64(secs);
+            }
+            "--once" => once = true,
+            other if other.starts_with("--") => return Err(format!("Unknown option: {}", other)),
+            other if dir.is_none() => dir = Some(PathBuf::from(other)),
+            other => return Err(format!("Unexpected argument: {}", other)),
+        }
+    }
+
+    let dir = dir.ok_or_else(|| "watch requires a directory".to_string())?;
+    let out_dir = out_dir.unwrap_or_else(|| dir.join("out"));
+
+    Ok(WatchOptions {
+        dir,
+        out_dir,
+        format,
+        operations,
+        interval,
+        once,
+    })
+}
+
+/// Runs the watcher until interrupted (or after one pass with `--once`).
+pub fn run(options: &WatchOptions) -> Result<(), String> {
+    if !options.dir.is_dir() {
+        return Err(format!("Not a directory: {}", options.dir.display()));
+    }
+    fs::create_dir_all(&options.out_dir)
+        .map_err(|e| format!("Cannot create {}: {}", options.out_dir.display(), e))?;
+
+    eprintln!(
+        "Watching {} (results in {})",
+        options.dir.display(),
+        options.out_dir.display()
+    );
+
+    // Size and modification time of each file, and whether that version has
+    // been processed
+    let mut seen: HashMap<PathBuf, ((u64, SystemTime), bool)> = HashMap::new();
+
+    loop {
+        let mut batch = Vec::new();
+        for path in scan(&options.dir)? {
+            let stamp = match fs::metadata(&path) {
+                Ok(meta) => (
+                    meta.len(),
+                    meta.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+                ),
+                Err(_) => continue, // Removed since the scan
+            };
+
+            // New or changed files wait one interval unless running once
+            let (stable, processed) = match seen.get(&path) {
+                Some((previous, processed)) if *previous == stamp => (true, *processed),
+                _ => (options.once, false),
+            };
+            let ready = stable && !processed;
+            seen.insert(path.clone(), (stamp, processed || ready));
+
+            if ready {
+                batch.push(path);
+            }
+        }
+
+        for (path, result) in process_batch(&batch, options) {
+            match result {
+                Ok(output) => eprintln!("{} -> {}", path.display(), output.display()),
+                Err(e) => eprintln!("Warning: Failed to process {}: {}", path.display(), e),
+            }
+        }
+
+        if options.once {
+            return Ok(());
+        }
+        thread::sleep(options.interval);
+    }
+}
+
+/// Lists the PDF files in a directory, sorted by name.
+fn scan(dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let entries = fs::read_dir(dir).map_err(|e| format!("Cannot read {}: {}", dir.display(), e))?;
+
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .extension()
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case("pdf"))
+        })
+        .collect();
+    files.sort();
+
+    Ok(files)
+}
+
+/// Processes a batch of PDF files, writing one result file per input to
+/// the output directory.
+///
+/// A file that fails doesn't stop the batch; its error is returned in its
+/// place.
+///
+/// # Returns
+/// Each input path with the path of its result file or the error
+pub fn process_batch(
+    paths: &[PathBuf],
+    options: &WatchOptions,
+) -> Vec<(PathBuf, Result<PathBuf, String>)> {
+    paths
+        .iter()
+        .map(|path| (path.clone(), process_file(path, options)))
+        .collect()
+}
+
+/// Runs the given operations on a document.
+///
+/// # Returns
+/// A JSON object with one entry per operation, keyed by its name
+pub fn extract(doc: &mut PDFDocument, operations: &[Operation]) -> Map<String, Value> {
+    let mut result = Map::new();
+    for operation in operations {
+        let (key, value) = match operation {
+            Operation::Metadata => ("metadata", metadata(doc)),
+            Operation::Text => ("text", text(doc)),
+            Operation::Images => ("images", images(doc)),
+        };
+        result.insert(key.to_string(), value);
+    }
+    result
+}
+
+/// Runs the configured operations on one file and writes the result.
+///
+/// # Returns
+/// The path of the result file
+fn process_file(path: &Path, options: &WatchOptions) -> Result<PathBuf, String> {
+    let mut doc = PDFDocument::open_file(path, None, None).map_err(|e| e.to_string())?;
+
+    let mut result = Map::new();
+    result.insert("file".to_string(), json!(path.display().to_string()));
+    result.extend(extract(&mut doc, &options.operations));
+    let result = Value::Object(result);
+
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "output".to_string());
+    let (extension, contents) = match options.format {
+        OutputFormat::Json => (
+            "json",
+            serde_json::to_string_pretty(&result).map_err(|e| e.to_string())?,
+        ),
+        OutputFormat::Text => ("txt", to_text(&result)),
+    };
+
+    let output = options.out_dir.join(format!("{}.{}", stem, extension));
+    fs::write(&output, contents)
+        .map_err(|e| format!("Cannot write {}: {}", output.display(), e))?;
+
+    Ok(output)
+}
+
+/// Collects page count, version and the document metadata.
+fn metadata(doc: &mut PDFDocument) -> Value {
+    let info = match doc.metadata() {
+        Ok(info) => json!({
+            "title": info.title,
+            "author": info.author,
+            "subject": info.subject,
+            "keywords": info.keywords,
+            "creator": info.creator,
+            "producer": info.producer,
+            "creation_date": info.creation_date.map(|date| date.to_string()),
+            "mod_date": info.mod_date.map(|date| date.to_string()),
+            "custom": info.custom,
+            "xmp": info.xmp,
+        }),
+        Err(e) => json!({ "error": e.to_string() }),
+    };
+
+    json!({
+        "page_count": doc.page_count().ok(),
+        "version": doc.pdf_version().ok(),
+        "linearized": doc.is_linearized(),
+        "info": info,
+    })
+}
+
+/// Extracts the text of every page.
+fn text(doc: &mut PDFDocument) -> Value {
+    let page_count = doc.page_count().unwrap_or(0) as usize;
+    let pages: Vec<Value> = (0..page_count)
+        .map(|index| match doc.extract_text_from_page_as_string(index) {
+            Ok(text) => json!({ "page": index + 1, "text": text }),
+            Err(e) => json!({ "page": index + 1, "error": e.to_string() }),
+        })
+        .collect();
+
+    Value::Array(pages)
+}
+
+/// Lists the images on every page.
+fn images(doc: &mut PDFDocument) -> Value {
+    let page_count = doc.page_count().unwrap_or(0) as usize;
+    let mut images = Vec::new();
+
+    for index in 0..page_count {
+        let page = match doc.get_page(index) {
+            Ok(page) => page,
+            Err(_) => continue,
+        };
+        let metadata = match page.get_image_metadata(doc.xref_mut()) {
+            Ok(metadata) => metadata,
+            Err(_) => continue,
+        };
+
+        for image in metadata {
+            images.push(json!({
+                "page": index + 1,
+                "name": image.name,
+                "format": format_name(image.format),
+                "width": image.width,
+                "height": image.height,
+                "bits_per_component": image.bits_per_component,
+                "color_space": image.color_space,
+                "bytes": image.data_length,
+            }));
+        }
+    }
+
+    Value::Array(images)
+}
+
+/// Names an image format in the output.
+fn format_name(format: ImageFormat) -> &'static str {
+    match format {
+        ImageFormat::JPEG => "jpeg",
+        ImageFormat::JPEG2000 => "jpeg2000",
+        ImageFormat::JBIG2 => "jbig2",
+        ImageFormat::PNG => "png",
+        ImageFormat::Raw => "raw",
+        ImageFormat::Unknown => "unknown",
+    }
+}
+
+/// Renders a result as indented `key: value` lines.
+fn to_text(value: &Value) -> String {
+    fn write(out: &mut String, value: &Value, indent: usize) {
+        let pad = "  ".repeat(indent);
+        match value {
+            Value::Object(map) => {
+                for (key, value) in map {
+                    match value {
+                        Value::Object(_) | Value::Array(_) => {
+                            out.push_str(&format!("{}{}:\n", pad, key));
+                            write(out, value, indent + 1);
+                        }
+                        _ => out.push_str(&format!("{}{}: {}\n", pad, key, scalar(value))),
+                    }
+                }
+            }
+            Value::Array(items) => {
+                for item in items {
+                    out.push_str(&format!("{}-\n", pad));
+                    write(out, item, indent + 1);
+                }
+            }
+            _ => out.push_str(&format!("{}{}\n", pad, scalar(value))),
+        }
+    }
+
+    fn scalar(value: &Value) -> String {
+        match value {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        }
+    }
+
+    let mut out = String::new();
+    write(&mut out, value, 0);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pdf_x_core::test_support::build_pdf;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    fn sample_pdf() -> Vec<u8> {
+        let content = "BT /F1 12 Tf 72 720 Td (Hello watch) Tj ET";
+        build_pdf(&[
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] \
+             /Resources << /Font << /F1 5 0 R >> >> /Contents 4 0 R >>"
+                .to_string(),
+            format!(
+                "<< /Length {} >>\nstream\n{}\nendstream",
+                content.len(),
+                content
+            ),
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>".to_string(),
+        ])
+    }
+
+    /// A fresh, empty directory under the system temp directory.
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("pdf-inspect-watch-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let options = parse_args(&args(&[
+            "inbox",
+            "--format",
+            "text",
+            "--ops",
+            "text, images",
+            "--once",
+        ]))
+        .unwrap();
+        assert_eq!(options.dir, PathBuf::from("inbox"));
+        assert_eq!(options.out_dir, PathBuf::from("inbox").join("out"));
+        assert_eq!(options.format, OutputFormat::Text);
+        assert_eq!(options.operations, vec![Operation::Text, Operation::Images]);
+        assert!(options.once);
+
+        let options =
+            parse_args(&args(&["inbox", "--out", "results", "--interval", "0.5"])).unwrap();
+        assert_eq!(options.out_dir, PathBuf::from("results"));
+        assert_eq!(options.interval, Duration::from_millis(500));
+        assert_eq!(options.format, OutputFormat::Json);
+
+        assert!(parse_args(&args(&[])).is_err());
+        assert!(parse_args(&args(&["inbox", "--ops", "fonts"])).is_err());
+        assert!(parse_args(&args(&["inbox", "--interval", "0"])).is_err());
+        assert!(parse_args(&args(&["inbox", "--out"])).is_err());
+        assert!(parse_args(&args(&["inbox", "other"])).is_err());
+    }
+
+    #[test]
+    fn test_extract() {
+        let mut doc = PDFDocument::open(sample_pdf()).unwrap();
+        let result = extract(&mut doc, &[Operation::Metadata, Operation::Text]);
+
+        assert_eq!(result["metadata"]["page_count"], json!(1));
+        let text = result["text"][0]["text"].as_str().unwrap();
+        assert!(text.contains("Hello watch"), "{:?}", text);
+        assert!(!result.contains_key("images"));
+    }
+
+    #[test]
+    fn test_images() {
+        let objects = vec![
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] \
+             /Resources << /XObject << /Im1 4 0 R >> >> >>"
+                .to_string(),
+            "<< /Type /XObject /Subtype /Image /Width 1 /Height 1 /ColorSpace /DeviceGray \
+             /BitsPerComponent 8 /Filter /DCTDecode /Length 1 >>\nstream\n\x00\nendstream"
+                .to_string(),
+        ];
+        let mut doc = PDFDocument::open(build_pdf(&objects)).unwrap();
+        let result = extract(&mut doc, &[Operation::Images]);
+
+        assert_eq!(result["images"][0]["name"], json!("Im1"));
+        assert_eq!(result["images"][0]["format"], json!("jpeg"));
+    }
+
+    #[test]
+    fn test_process_batch() {
+        let dir = temp_dir("batch");
+        let good = dir.join("good.pdf");
+        let bad = dir.join("bad.pdf");
+        fs::write(&good, sample_pdf()).unwrap();
+        fs::write(&bad, b"not a pdf").unwrap();
+
+        let mut options = parse_args(&args(&[dir.to_str().unwrap(), "--ops", "text"])).unwrap();
+        options.out_dir = dir.join("out");
+        fs::create_dir_all(&options.out_dir).unwrap();
+
+        let results = process_batch(&scan(&dir).unwrap(), &options);
+        assert_eq!(results.len(), 2);
+        // Sorted by name: the failure doesn't stop the batch
+        assert_eq!(results[0].0, bad);
+        let expected = match PDFDocument::open_file(&bad, None, None) {
+            Err(e) => e.to_string(),
+            Ok(_) => panic!("bad.pdf opened"),
+        };
+        assert_eq!(results[0].1, Err(expected));
+        let output = results[1].1.as_ref().unwrap();
+        assert_eq!(output, &options.out_dir.join("good.json"));
+
+        let written: Value = serde_json::from_str(&fs::read_to_string(output).unwrap()).unwrap();
+        assert!(
+            written["text"][0]["text"]
+                .as_str()
+                .unwrap()
+                .contains("Hello watch")
+        );
+
+        // Text output
+        options.format = OutputFormat::Text;
+        let results = process_batch(&[good], &options);
+        let output = results[0].1.as_ref().unwrap();
+        assert_eq!(output, &options.out_dir.join("good.txt"));
+        assert!(
+            fs::read_to_string(output)
+                .unwrap()
+                .contains("text: Hello watch")
+        );
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -138,13 +138,11 @@ fn analyze_xref_table(xref: &pdf_x::XRef) {
     let mut uncompressed_count = 0;
     let mut compressed_count = 0;
 
-    for i in 0..xref.len() {
-        if let Some(entry) = xref.get_entry(i as u32) {
-            match entry {
-                XRefEntry::Free { .. } => free_count += 1,
-                XRefEntry::Uncompressed { .. } => uncompressed_count += 1,
-                XRefEntry::Compressed { .. } => compressed_count += 1,
-            }
+    for (_, entry) in xref.iter_entries() {
+        match entry {
+            XRefEntry::Free { .. } => free_count += 1,
+            XRefEntry::Uncompressed { .. } => uncompressed_count += 1,
+            XRefEntry::Compressed { .. } => compressed_count += 1,
         }
     }
 
@@ -0,0 +1,67 @@
+/// Benchmarks for content stream tokenizing
+///
+/// Compares the Lexer/Parser path with the zero-copy ContentScanner.
+///
+/// Run with: cargo bench --bench content_stream
+use criterion::{Criterion, Throughput, black_box, criterion_group, criterion_main};
+use pdf_x_core::core::{ContentScanner, ContentStreamEvaluator, Lexer, Parser, Stream};
+
+/// Builds a text- and path-heavy page of roughly 1 MB.
+fn synthetic_page() -> Vec<u8> {
+    let mut content = String::new();
+    for i in 0..8000 {
+        let y = 800.0 - (i % 90) as f64 * 8.5;
+        content.push_str(&format!(
+            "BT /F1 9.5 Tf 1 0 0 1 72.25 {y} Tm [(Lorem) -250 (ipsum dolor) 120.5 (sit amet)] TJ ET\n\
+             q 0.5 0 0 0.5 0 0 cm 10.5 20 m 30.25 40 l 50 60 70 80 90 100 c S Q\n\
+             /P <</MCID {i}>> BDC 0.2 0.4 0.6 rg 72 {y} 144 12 re f EMC\n"
+        ));
+    }
+    content.into_bytes()
+}
+
+fn benchmark_content_tokenizing(c: &mut Criterion) {
+    let content = synthetic_page();
+    let mut group = c.benchmark_group("content_stream");
+    group.throughput(Throughput::Bytes(content.len() as u64));
+
+    group.bench_function("lexer_parser", |b| {
+        b.iter(|| {
+            let stream = Box::new(Stream::from_bytes(black_box(&content).clone()));
+            let mut evaluator =
+                ContentStreamEvaluator::new(Parser::new(Lexer::new(stream).unwrap()).unwrap());
+            let mut count = 0;
+            while let Some(op) = evaluator.read_operation().unwrap() {
+                count += op.args.len();
+            }
+            count
+        });
+    });
+
+    group.bench_function("scanner_operations", |b| {
+        b.iter(|| {
+            let mut evaluator = ContentStreamEvaluator::from_bytes(black_box(&content).clone());
+            let mut count = 0;
+            while let Some(op) = evaluator.read_operation().unwrap() {
+                count += op.args.len();
+            }
+            count
+        });
+    });
+
+    group.bench_function("scanner_borrowed", |b| {
+        b.iter(|| {
+            let mut scanner = ContentScanner::new(black_box(&content));
+            let mut count = 0;
+            while let Some(op) = scanner.next_operation().unwrap() {
+                count += op.operands.len();
+            }
+            count
+        });
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, benchmark_content_tokenizing);
+criterion_main!(benches);
@@ -0,0 +1,143 @@
+//! Custom ChunkLoader Example: S3 ranged GETs
+//!
+//! Opens a PDF stored in S3 (or any S3-compatible store) without downloading
+//! the whole object. Each chunk is fetched with an HTTP `Range` request against
+//! a presigned GET URL, so no AWS SDK or request signing is needed here.
+//!
+//! Usage:
+//!     cargo run --example s3_loader --features s3-example -- <presigned-url> [chunk_size]
+//!
+//! Generate a presigned URL with e.g.:
+//!     aws s3 presign s3://my-bucket/report.pdf --expires-in 3600
+
+use pdf_x_core::PDFDocument;
+use pdf_x_core::core::ChunkLoader;
+use pdf_x_core::core::error::{PDFError, PDFResult};
+use std::env;
+
+/// Loads chunks of an S3 object through a presigned GET URL.
+struct S3ChunkLoader {
+    url: String,
+    client: reqwest::Client,
+    runtime: tokio::runtime::Runtime,
+    chunk_size: usize,
+    total_length: usize,
+    requests: usize,
+}
+
+impl S3ChunkLoader {
+    /// Creates a loader and discovers the object size.
+    ///
+    /// Presigned URLs are only valid for the signed method, so instead of a
+    /// HEAD request this asks for the first byte and reads the total size from
+    /// the `Content-Range` header (`bytes 0-0/<size>`).
+    fn new(url: impl Into<String>, chunk_size: usize) -> PDFResult<Self> {
+        let url = url.into();
+        let runtime = tokio::runtime::Runtime::new()
+            .map_err(|e| PDFError::StreamError(format!("Failed to create runtime: {}", e)))?;
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(30))
+            .build()
+            .map_err(|e| PDFError::StreamError(format!("Failed to create HTTP client: {}", e)))?;
+
+        let total_length = runtime.block_on(async {
+            let response = client
+                .get(&url)
+                .header("Range", "bytes=0-0")
+                .send()
+                .await
+                .map_err(|e| PDFError::StreamError(format!("S3 request failed: {}", e)))?;
+
+            if response.status() != 206 {
+                return Err(PDFError::StreamError(format!(
+                    "Expected 206 status from S3, got {}",
+                    response.status()
+                )));
+            }
+
+            response
+                .headers()
+                .get("content-range")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.rsplit('/').next())
+                .and_then(|v| v.parse::<usize>().ok())
+                .ok_or_else(|| PDFError::StreamError("Missing Content-Range header".to_string()))
+        })?;
+
+        Ok(S3ChunkLoader {
+            url,
+            client,
+            runtime,
+            chunk_size,
+            total_length,
+            requests: 0,
+        })
+    }
+}
+
+impl ChunkLoader for S3ChunkLoader {
+    fn request_chunk(&mut self, chunk_num: usize) -> PDFResult<Vec<u8>> {
+        let chunk_start = chunk_num * self.chunk_size;
+        let chunk_end = std::cmp::min(chunk_start + self.chunk_size, self.total_length) - 1;
+        self.requests += 1;
+        eprintln!(
+            "GET {} bytes={}-{} (request #{})",
+            chunk_num, chunk_start, chunk_end, self.requests
+        );
+
+        self.runtime.block_on(async {
+            let response = self
+                .client
+                .get(&self.url)
+                .header("Range", format!("bytes={}-{}", chunk_start, chunk_end))
+                .send()
+                .await
+                .map_err(|e| {
+                    PDFError::StreamError(format!("Failed to fetch chunk {}: {}", chunk_num, e))
+                })?;
+
+            if response.status() != 206 {
+                return Err(PDFError::StreamError(format!(
+                    "Expected 206 status, got {}",
+                    response.status()
+                )));
+            }
+
+            Ok(response
+                .bytes()
+                .await
+                .map_err(|e| PDFError::StreamError(format!("Failed to read response: {}", e)))?
+                .to_vec())
+        })
+    }
+
+    fn chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+
+    fn total_length(&self) -> usize {
+        self.total_length
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Vec<String> = env::args().collect();
+
+    if args.len() < 2 {
+        eprintln!("Usage: {} <presigned-url> [chunk_size]", args[0]);
+        std::process::exit(1);
+    }
+
+    let chunk_size = args.get(2).and_then(|s| s.parse().ok()).unwrap_or(65536);
+
+    let loader = S3ChunkLoader::new(&args[1], chunk_size)?;
+    println!("Object size: {} bytes", loader.total_length());
+
+    let mut doc = PDFDocument::open_stream(loader, Some(16))?;
+    println!("Pages: {}", doc.page_count()?);
+
+    let text = doc.extract_text_from_page_as_string(0)?;
+    println!("--- Page 1 ---\n{}", text);
+
+    Ok(())
+}
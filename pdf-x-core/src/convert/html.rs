@@ -0,0 +1,435 @@
+//! PDF to HTML conversion.
+//!
+//! Pages are converted in one of two layouts:
+//!
+//! - [`HtmlLayout::Positioned`] places every text item and image absolutely
+//!   at its position on the page, so the page keeps the look of the PDF
+//!   (without vector graphics).
+//! - [`HtmlLayout::Flowed`] writes the paragraphs of
+//!   `PDFDocument::reflow_page()` as headings and paragraphs in reading
+//!   order, with bold and italic runs, so the text adapts to the window.
+//!
+//! Positioned text keeps its font family, weight, slant, size and fill
+//! color. Images are embedded as PNG data URIs. Paths, shadings and
+//! annotations are not converted; `PDFDocument::render_page_to_svg()` draws
+//! those.
+
+use crate::core::content_stream::TextItem;
+use crate::core::document::PDFDocument;
+use crate::core::error::{PDFError, PDFResult};
+use crate::core::layout::font_size;
+use crate::core::reflow::{FontSizeClass, ReflowParagraph};
+use crate::log_target;
+use crate::rendering::RenderOptions;
+use crate::rendering::svg_device::{base64, encode_png, escape_xml, image_to_rgba};
+use crate::rendering::vector_paths::{PathRecorder, RecordedImage};
+use std::fmt::Write as _;
+
+/// CSS for the elements written by [`page_to_html`]. [`document_to_html`]
+/// includes it in the document head.
+pub const STYLESHEET: &str = "\
+.pdf-page { position: relative; overflow: hidden; margin: 1em auto; background: white; box-shadow: 0 0 4px #888; }
+.pdf-page span { position: absolute; white-space: pre; line-height: 1; }
+.pdf-page img { position: absolute; }
+.pdf-flow { max-width: 40em; margin: 1em auto; }
+.pdf-flow img { display: block; max-width: 100%; height: auto; }
+";
+
+/// How the content of a page is laid out in HTML.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HtmlLayout {
+    /// Text items and images at their positions on the page
+    #[default]
+    Positioned,
+    /// Headings and paragraphs in reading order
+    Flowed,
+}
+
+/// Options for [`page_to_html`] and [`document_to_html`].
+#[derive(Debug, Clone)]
+pub struct HtmlOptions {
+    /// How the page content is laid out
+    pub layout: HtmlLayout,
+    /// CSS pixels per PDF unit for positioned pages (1.0 maps a point to a
+    /// pixel)
+    pub scale: f32,
+    /// Whether to embed the page's images
+    pub images: bool,
+}
+
+impl Default for HtmlOptions {
+    fn default() -> Self {
+        HtmlOptions {
+            layout: HtmlLayout::Positioned,
+            scale: 1.0,
+            images: true,
+        }
+    }
+}
+
+/// Converts one page to an HTML fragment.
+///
+/// The fragment is a `<div>` styled by [`STYLESHEET`]: `pdf-page` for the
+/// positioned layout, `pdf-flow` for the flowed one.
+///
+/// # Arguments
+/// * `doc` - The document
+/// * `page_index` - Zero-based page index
+/// * `options` - Layout, scale and whether to include images
+///
+/// # Example
+/// ```no_run
+/// use pdf_x_core::PDFDocument;
+/// use pdf_x_core::convert::html::{HtmlLayout, HtmlOptions, page_to_html};
+///
+/// let mut doc = PDFDocument::open_file("document.pdf", None, None).unwrap();
+/// let options = HtmlOptions {
+///     layout: HtmlLayout::Flowed,
+///     ..Default::default()
+/// };
+/// println!("{}", page_to_html(&mut doc, 0, &options).unwrap());
+/// ```
+pub fn page_to_html(
+    doc: &mut PDFDocument,
+    page_index: usize,
+    options: &HtmlOptions,
+) -> PDFResult<String> {
+    let images = if options.images {
+        page_images(doc, page_index)?
+    } else {
+        Vec::new()
+    };
+
+    match options.layout {
+        HtmlLayout::Positioned => positioned_page(doc, page_index, &images, options),
+        HtmlLayout::Flowed => flowed_page(doc, page_index, &images),
+    }
+}
+
+/// Converts a whole document to a standalone HTML document.
+///
+/// The title is the document's /Title, if any. Pages follow each other in
+/// the body, each converted as by [`page_to_html`].
+///
+/// # Example
+/// ```no_run
+/// use pdf_x_core::PDFDocument;
+/// use pdf_x_core::convert::html::{HtmlOptions, document_to_html};
+///
+/// let mut doc = PDFDocument::open_file("document.pdf", None, None).unwrap();
+/// let html = document_to_html(&mut doc, &HtmlOptions::default()).unwrap();
+/// std::fs::write("document.html", html).unwrap();
+/// ```
+pub fn document_to_html(doc: &mut PDFDocument, options: &HtmlOptions) -> PDFResult<String> {
+    let title = doc.metadata()?.title.unwrap_or_default();
+    let mut html = String::from("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    let _ = writeln!(html, "<title>{}</title>", escape_xml(&title));
+    let _ = write!(html, "<style>\n{}</style>\n</head>\n<body>\n", STYLESHEET);
+
+    for page_index in 0..doc.page_count()? as usize {
+        html.push_str(&page_to_html(doc, page_index, options)?);
+    }
+
+    html.push_str("</body>\n</html>\n");
+    Ok(html)
+}
+
+/// An image drawn on a page, placed in user space.
+struct PlacedImage {
+    /// PNG data URI
+    src: String,
+    /// Bounding box `[x0, y0, x1, y1]` in user space
+    bbox: [f64; 4],
+}
+
+fn positioned_page(
+    doc: &mut PDFDocument,
+    page_index: usize,
+    images: &[PlacedImage],
+    options: &HtmlOptions,
+) -> PDFResult<String> {
+    let items = doc.extract_text_from_page(page_index)?;
+    let page = doc.get_page(page_index)?;
+    let render_options = RenderOptions {
+        scale: options.scale,
+        ..Default::default()
+    };
+    let viewport = page.viewport(doc.xref_mut(), &render_options);
+    let m = viewport.transform;
+    let scale = options.scale as f64;
+
+    let mut html = format!(
+        "<div class=\"pdf-page\" style=\"width:{}px;height:{}px\">\n",
+        viewport.width, viewport.height
+    );
+
+    // Images first, so that text is drawn over them
+    for image in images {
+        let [left, top, right, bottom] = transform_bbox(&m, image.bbox);
+        let _ = writeln!(
+            html,
+            "<img src=\"{}\" alt=\"\" style=\"left:{}px;top:{}px;width:{}px;height:{}px\">",
+            image.src,
+            css_number(left),
+            css_number(top),
+            css_number(right - left),
+            css_number(bottom - top)
+        );
+    }
+
+    for item in &items {
+        let Some((x, y)) = item.position else {
+            continue;
+        };
+        if item.text.trim().is_empty() {
+            continue;
+        }
+        let size = font_size(item);
+        let ascent = item
+            .vertical_extent
+            .map_or(0.8 * size, |(_, ascent)| ascent);
+        let (left, baseline) = apply(&m, x, y);
+        let _ = writeln!(
+            html,
+            "<span style=\"left:{}px;top:{}px;{}\">{}</span>",
+            css_number(left),
+            css_number(baseline - ascent * scale),
+            text_style(item, size * scale),
+            escape_xml(&item.text)
+        );
+    }
+
+    html.push_str("</div>\n");
+    Ok(html)
+}
+
+fn flowed_page(
+    doc: &mut PDFDocument,
+    page_index: usize,
+    images: &[PlacedImage],
+) -> PDFResult<String> {
+    let paragraphs = doc.reflow_page(page_index)?;
+    let mut html = String::from("<div class=\"pdf-flow\">\n");
+
+    // Each image goes before the first paragraph that starts below its top
+    let mut images: Vec<&PlacedImage> = images.iter().collect();
+    for paragraph in &paragraphs {
+        images.retain(|image| {
+            if image.bbox[3] > paragraph.bbox[3] {
+                write_flowed_image(&mut html, image);
+                false
+            } else {
+                true
+            }
+        });
+        write_paragraph(&mut html, paragraph);
+    }
+    for image in images {
+        write_flowed_image(&mut html, image);
+    }
+
+    html.push_str("</div>\n");
+    Ok(html)
+}
+
+fn write_paragraph(html: &mut String, paragraph: &ReflowParagraph) {
+    let tag = match paragraph.heading_level {
+        Some(level) => format!("h{}", level.clamp(1, 6)),
+        None => "p".to_string(),
+    };
+    let _ = write!(html, "<{}>", tag);
+    for run in &paragraph.runs {
+        let mut text = escape_xml(&run.text);
+        if run.italic {
+            text = format!("<i>{}</i>", text);
+        }
+        if run.bold && paragraph.heading_level.is_none() {
+            text = format!("<b>{}</b>", text);
+        }
+        if run.size_class == FontSizeClass::Small {
+            text = format!("<small>{}</small>", text);
+        }
+        html.push_str(&text);
+    }
+    let _ = writeln!(html, "</{}>", tag);
+}
+
+fn write_flowed_image(html: &mut String, image: &PlacedImage) {
+    let [x0, y0, x1, y1] = image.bbox;
+    let _ = writeln!(
+        html,
+        "<img src=\"{}\" alt=\"\" width=\"{}\" height=\"{}\">",
+        image.src,
+        (x1 - x0).round(),
+        (y1 - y0).round()
+    );
+}
+
+/// Inline CSS for the font and color of a text item.
+fn text_style(item: &TextItem, size: f64) -> String {
+    let mut style = format!("font-size:{}px", css_number(size));
+    if let Some(family) = &item.font_family {
+        // Quotes and backslashes would end the CSS string
+        let family: String = family
+            .chars()
+            .filter(|c| !matches!(c, '"' | '\'' | '\\'))
+            .collect();
+        let _ = write!(style, ";font-family:'{}'", escape_xml(&family));
+    }
+    if item.bold {
+        style.push_str(";font-weight:bold");
+    }
+    if item.italic {
+        style.push_str(";font-style:italic");
+    }
+    // Invisible text (rendering mode 3), such as an OCR layer, stays
+    // selectable
+    if item.rendering_mode == Some(3) {
+        style.push_str(";color:transparent");
+    } else if let Some(color) = item.fill_color
+        && color != [0.0; 3]
+    {
+        let [r, g, b] = color.map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8);
+        let _ = write!(style, ";color:#{:02x}{:02x}{:02x}", r, g, b);
+    }
+    style
+}
+
+/// Formats a CSS length with at most two decimals.
+fn css_number(value: f64) -> String {
+    let rounded = (value * 100.0).round() / 100.0;
+    if rounded == 0.0 {
+        "0".to_string()
+    } else {
+        rounded.to_string()
+    }
+}
+
+fn apply(m: &[f64; 6], x: f64, y: f64) -> (f64, f64) {
+    (m[0] * x + m[2] * y + m[4], m[1] * x + m[3] * y + m[5])
+}
+
+/// Bounding box of a transformed rectangle `[x0, y0, x1, y1]`.
+fn transform_bbox(m: &[f64; 6], [x0, y0, x1, y1]: [f64; 4]) -> [f64; 4] {
+    [(x0, y0), (x1, y0), (x0, y1), (x1, y1)]
+        .map(|(x, y)| apply(m, x, y))
+        .iter()
+        .fold(
+            [
+                f64::INFINITY,
+                f64::INFINITY,
+                f64::NEG_INFINITY,
+                f64::NEG_INFINITY,
+            ],
+            |[x0, y0, x1, y1], &(x, y)| [x0.min(x), y0.min(y), x1.max(x), y1.max(y)],
+        )
+}
+
+/// Renders a page with a [`PathRecorder`] to find its images and where
+/// they are drawn.
+///
+/// Images that can't be converted to PNG are skipped, and a page that fails
+/// to render has no images.
+fn page_images(doc: &mut PDFDocument, page_index: usize) -> PDFResult<Vec<PlacedImage>> {
+    let page = doc.get_page(page_index)?;
+    let mut recorder = PathRecorder::with_images();
+    match page.render(doc.xref_mut(), &mut recorder) {
+        Ok(()) => {}
+        Err(e @ (PDFError::DataMissing { .. } | PDFError::Cancelled)) => return Err(e),
+        Err(e) => {
+            tracing::warn!(
+                target: log_target::RENDER,
+                "Failed to collect the images of page {}: {}", page_index + 1, e
+            );
+        }
+    }
+
+    let mut images = Vec::new();
+    for RecordedImage { image, matrix } in recorder.images {
+        let Some(rgba) = image_to_rgba(&image) else {
+            continue;
+        };
+        let png = encode_png(image.width, image.height, &rgba)?;
+        images.push(PlacedImage {
+            src: format!("data:image/png;base64,{}", base64(&png)),
+            // The image fills the unit square of its matrix
+            bbox: transform_bbox(&matrix, [0.0, 0.0, 1.0, 1.0]),
+        });
+    }
+    Ok(images)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::build_pdf_with_trailer;
+
+    fn sample_document() -> PDFDocument {
+        let content = "q 100 0 0 50 72 600 cm /Im1 Do Q \
+                       BT /F1 12 Tf 1 0 0 rg 72 700 Td (Fish & Chips) Tj ET";
+        let pdf = build_pdf_with_trailer(
+            &[
+                "<< /Type /Catalog /Pages 2 0 R >>",
+                "<< /Type /Pages /Kids [3 0 R] /Count 1 >>",
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] /Contents 4 0 R \
+             /Resources << /Font << /F1 5 0 R >> /XObject << /Im1 6 0 R >> >> >>",
+                &format!(
+                    "<< /Length {} >>\nstream\n{}\nendstream",
+                    content.len(),
+                    content
+                ),
+                "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold >>",
+                "<< /Type /XObject /Subtype /Image /Width 2 /Height 1 /ColorSpace /DeviceRGB \
+             /BitsPerComponent 8 /Length 6 >>\nstream\nABCDEF\nendstream",
+            ],
+            "/Info << /Title (Report <1>) >>",
+        );
+        PDFDocument::open(pdf).unwrap()
+    }
+
+    #[test]
+    fn test_positioned_page() {
+        let mut doc = sample_document();
+        let html = page_to_html(&mut doc, 0, &HtmlOptions::default()).unwrap();
+
+        assert!(html.starts_with("<div class=\"pdf-page\" style=\"width:612px;height:792px\">"));
+        assert!(html.contains("style=\"left:72px;top:142px;width:100px;height:50px\">"));
+        assert!(html.contains("src=\"data:image/png;base64,iVBORw0KGgo"));
+        // The baseline is at 92px from the top; the text starts 0.8 em above
+        assert!(html.contains(
+            "<span style=\"left:72px;top:82.4px;font-size:12px;font-family:'Helvetica';\
+             font-weight:bold;color:#ff0000\">Fish &amp; Chips</span>"
+        ));
+        // Images are behind the text
+        assert!(html.find("<img").unwrap() < html.find("<span").unwrap());
+
+        let options = HtmlOptions {
+            scale: 2.0,
+            images: false,
+            ..Default::default()
+        };
+        let html = page_to_html(&mut doc, 0, &options).unwrap();
+        assert!(html.contains("width:1224px;height:1584px"));
+        assert!(html.contains("left:144px;top:164.8px;font-size:24px"));
+        assert!(!html.contains("<img"));
+    }
+
+    #[test]
+    fn test_flowed_document() {
+        let mut doc = sample_document();
+        let options = HtmlOptions {
+            layout: HtmlLayout::Flowed,
+            ..Default::default()
+        };
+        let html = document_to_html(&mut doc, &options).unwrap();
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<title>Report &lt;1&gt;</title>"));
+        assert!(html.contains(STYLESHEET));
+        // The image is below the text, so it follows the paragraph
+        let paragraph = html.find("<p><b>Fish &amp; Chips</b></p>").unwrap();
+        let image = html.find("<img src=\"data:image/png;base64,").unwrap();
+        assert!(paragraph < image);
+        assert!(html.contains("width=\"100\" height=\"50\">"));
+    }
+}
@@ -0,0 +1,597 @@
+//! PDF to Markdown conversion.
+//!
+//! Builds on the lines of `PDFDocument::extract_lines_from_page()`, read
+//! top to bottom:
+//!
+//! - **Headings**: the font sizes of the document are clustered. The size
+//!   covering the most characters is the body size; clusters above it
+//!   become heading levels, the largest first (at most six).
+//! - **Lists**: lines starting with a bullet (`•`, `-`, `*`, ...) or a number
+//!   or letter followed by `.` or `)` start list items. Following lines
+//!   indented past the marker continue the item.
+//! - **Tables**: two or more consecutive lines that split into the same
+//!   number of short cells at wide gaps, with overlapping columns.
+//! - **Paragraphs**: the other lines, joined while they stay close together.
+//!   Words hyphenated across lines are rejoined.
+//!
+//! Bold and italic words are marked with `**` and `*`. Multi-column pages
+//! are read line by line across the columns.
+
+use crate::core::document::PDFDocument;
+use crate::core::error::PDFResult;
+use crate::core::layout::{DEFAULT_FONT_SIZE, TextLine, TextWord};
+use crate::core::reflow::{FontStyle, ends_with_hyphen};
+use std::collections::HashMap;
+
+/// Most words in a table cell; longer runs are taken for columns of text.
+const MAX_CELL_WORDS: usize = 8;
+
+/// Bullet characters that start list items.
+const BULLETS: [char; 9] = ['•', '◦', '▪', '▫', '‣', '●', '○', '-', '*'];
+
+/// Options for [`page_to_markdown`] and [`document_to_markdown`].
+#[derive(Debug, Clone)]
+pub struct MarkdownOptions {
+    /// Minimum gap between table cells, as a fraction of the font size
+    pub table_gap: f64,
+    /// Maximum distance between the bottoms of two lines of a paragraph, as
+    /// a fraction of the font size
+    pub paragraph_spacing: f64,
+    /// Whether to mark bold and italic words with `**` and `*`
+    pub emphasis: bool,
+    /// Whether to separate pages with a horizontal rule
+    pub page_breaks: bool,
+}
+
+impl Default for MarkdownOptions {
+    fn default() -> Self {
+        MarkdownOptions {
+            table_gap: 2.0,
+            paragraph_spacing: 1.6,
+            emphasis: true,
+            page_breaks: false,
+        }
+    }
+}
+
+/// Converts one page to Markdown.
+///
+/// Heading levels come from the font sizes of this page only; use
+/// [`document_to_markdown`] for levels that are consistent across pages.
+///
+/// # Arguments
+/// * `doc` - The document
+/// * `page_index` - Zero-based page index
+/// * `options` - Layout thresholds and output settings
+///
+/// # Example
+/// ```no_run
+/// use pdf_x_core::PDFDocument;
+/// use pdf_x_core::convert::markdown::{MarkdownOptions, page_to_markdown};
+///
+/// let mut doc = PDFDocument::open_file("document.pdf", None, None).unwrap();
+/// println!("{}", page_to_markdown(&mut doc, 0, &MarkdownOptions::default()).unwrap());
+/// ```
+pub fn page_to_markdown(
+    doc: &mut PDFDocument,
+    page_index: usize,
+    options: &MarkdownOptions,
+) -> PDFResult<String> {
+    let page = page_lines(doc, page_index)?;
+    let scale = HeadingScale::new(std::slice::from_ref(&page));
+    Ok(render_blocks(&page_blocks(&page, &scale, options), options))
+}
+
+/// Converts a whole document to Markdown.
+///
+/// # Example
+/// ```no_run
+/// use pdf_x_core::PDFDocument;
+/// use pdf_x_core::convert::markdown::{MarkdownOptions, document_to_markdown};
+///
+/// let mut doc = PDFDocument::open_file("document.pdf", None, None).unwrap();
+/// let markdown = document_to_markdown(&mut doc, &MarkdownOptions::default()).unwrap();
+/// std::fs::write("document.md", markdown).unwrap();
+/// ```
+pub fn document_to_markdown(doc: &mut PDFDocument, options: &MarkdownOptions) -> PDFResult<String> {
+    let pages = (0..doc.page_count()? as usize)
+        .map(|page_index| page_lines(doc, page_index))
+        .collect::<PDFResult<Vec<_>>>()?;
+    let scale = HeadingScale::new(&pages);
+
+    let separator = if options.page_breaks {
+        "\n---\n\n"
+    } else {
+        "\n"
+    };
+    let markdown: Vec<String> = pages
+        .iter()
+        .map(|page| render_blocks(&page_blocks(page, &scale, options), options))
+        .filter(|page| !page.is_empty())
+        .collect();
+    Ok(markdown.join(separator))
+}
+
+/// The lines of a page.
+struct PageLines {
+    lines: Vec<TextLine>,
+}
+
+fn page_lines(doc: &mut PDFDocument, page_index: usize) -> PDFResult<PageLines> {
+    let lines = doc.extract_lines_from_page(page_index)?;
+    Ok(PageLines { lines })
+}
+
+/// Font sizes of body text and headings.
+struct HeadingScale {
+    /// Smallest font size of each heading level, largest level first
+    levels: Vec<f64>,
+}
+
+impl HeadingScale {
+    /// Clusters the line font sizes of `pages`, weighted by characters.
+    fn new(pages: &[PageLines]) -> Self {
+        // Sizes rounded to half a point
+        let mut counts: HashMap<i64, usize> = HashMap::new();
+        for line in pages.iter().flat_map(|page| &page.lines) {
+            let key = (line_size(line) * 2.0).round() as i64;
+            *counts.entry(key).or_default() += line.text.chars().count();
+        }
+        let Some(body) = counts
+            .iter()
+            .max_by(|a, b| a.1.cmp(b.1).then(b.0.cmp(a.0)))
+            .map(|(key, _)| *key as f64 / 2.0)
+        else {
+            return HeadingScale { levels: Vec::new() };
+        };
+
+        let mut sizes: Vec<f64> = counts
+            .keys()
+            .map(|key| *key as f64 / 2.0)
+            .filter(|size| *size >= body * 1.15)
+            .collect();
+        sizes.sort_by(|a, b| b.total_cmp(a));
+
+        // Sizes within 10% of each other share a level
+        let mut levels: Vec<f64> = Vec::new();
+        for size in sizes {
+            match levels.last_mut() {
+                Some(last) if size >= *last * 0.9 => *last = size,
+                _ => levels.push(size),
+            }
+        }
+        levels.truncate(6);
+        HeadingScale { levels }
+    }
+
+    /// Returns the heading level of text set in `size`, if it is a heading.
+    fn level(&self, size: f64) -> Option<u8> {
+        let rounded = (size * 2.0).round() / 2.0;
+        self.levels
+            .iter()
+            .position(|min| rounded >= *min)
+            .map(|index| index as u8 + 1)
+    }
+}
+
+/// A block of Markdown output.
+#[derive(Debug, Clone, PartialEq)]
+enum Block {
+    Heading(u8, String),
+    Paragraph(Vec<Run>),
+    /// A list item; `marker` is `-` or a number followed by `.`
+    ListItem {
+        marker: String,
+        runs: Vec<Run>,
+    },
+    Table(Vec<Vec<String>>),
+}
+
+/// Text in one style.
+#[derive(Debug, Clone, PartialEq)]
+struct Run {
+    text: String,
+    style: FontStyle,
+}
+
+fn page_blocks(page: &PageLines, scale: &HeadingScale, options: &MarkdownOptions) -> Vec<Block> {
+    let lines = &page.lines;
+    let mut blocks = Vec::new();
+    let mut index = 0;
+
+    while index < lines.len() {
+        let line = &lines[index];
+        let size = line_size(line);
+
+        // Tables: aligned rows of cells
+        let rows = table_rows(&lines[index..], options);
+        if rows > 1 {
+            let table = lines[index..index + rows]
+                .iter()
+                .map(|line| {
+                    cells(line, options)
+                        .iter()
+                        .map(|cell| words_text(cell))
+                        .collect()
+                })
+                .collect();
+            blocks.push(Block::Table(table));
+            index += rows;
+            continue;
+        }
+
+        let marker = list_marker(&line.words);
+        if let Some(level) = scale.level(size).filter(|_| marker.is_none()) {
+            // A heading, continued by close lines of the same level
+            let mut text = words_text(&line.words);
+            index += 1;
+            while let Some(next) = lines.get(index)
+                && scale.level(line_size(next)) == Some(level)
+                && close(&lines[index - 1], next, options)
+            {
+                text.push(' ');
+                text.push_str(&words_text(&next.words));
+                index += 1;
+            }
+            blocks.push(Block::Heading(level, text));
+            continue;
+        }
+
+        // A list item or paragraph, continued by close body lines
+        let (marker, words) = match marker {
+            Some((marker, rest)) => (Some(marker), rest),
+            None => (None, &line.words[..]),
+        };
+        let mut runs = Vec::new();
+        push_runs(&mut runs, words);
+        index += 1;
+        while let Some(next) = lines.get(index)
+            && close(&lines[index - 1], next, options)
+            && scale.level(line_size(next)).is_none()
+            && list_marker(&next.words).is_none()
+            && table_rows(&lines[index..], options) < 2
+            && (marker.is_none() || next.bbox[0] > line.bbox[0] + 0.5 * size)
+        {
+            push_runs(&mut runs, &next.words);
+            index += 1;
+        }
+
+        blocks.push(match marker {
+            Some(marker) => Block::ListItem { marker, runs },
+            None => Block::Paragraph(runs),
+        });
+    }
+    blocks
+}
+
+fn render_blocks(blocks: &[Block], options: &MarkdownOptions) -> String {
+    let mut markdown = String::new();
+    for (index, block) in blocks.iter().enumerate() {
+        // List items follow each other without blank lines
+        let in_list = matches!(block, Block::ListItem { .. })
+            && matches!(
+                index.checked_sub(1).map(|i| &blocks[i]),
+                Some(Block::ListItem { .. })
+            );
+        if index > 0 && !in_list {
+            markdown.push('\n');
+        }
+
+        match block {
+            Block::Heading(level, text) => {
+                markdown.push_str(&"#".repeat(*level as usize));
+                markdown.push(' ');
+                markdown.push_str(&escape(text));
+            }
+            Block::Paragraph(runs) => {
+                let text = render_runs(runs, options);
+                // Keep paragraphs from reading as other blocks
+                if text.starts_with(['#', '>', '+', '-', '=']) {
+                    markdown.push('\\');
+                    markdown.push_str(&text);
+                } else if let Some(digits) = ordered_list_marker(&text) {
+                    // "1." or "1)" would start a numbered list
+                    markdown.push_str(&text[..digits]);
+                    markdown.push('\\');
+                    markdown.push_str(&text[digits..]);
+                } else {
+                    markdown.push_str(&text);
+                }
+            }
+            Block::ListItem { marker, runs } => {
+                markdown.push_str(marker);
+                markdown.push(' ');
+                markdown.push_str(&render_runs(runs, options));
+            }
+            Block::Table(rows) => {
+                let columns = rows.first().map_or(0, Vec::len);
+                for (row_index, row) in rows.iter().enumerate() {
+                    let cells: Vec<String> = row
+                        .iter()
+                        .map(|cell| escape(cell).replace('|', "\\|"))
+                        .collect();
+                    markdown.push_str(&format!("| {} |\n", cells.join(" | ")));
+                    if row_index == 0 {
+                        markdown.push_str(&format!("|{}\n", " --- |".repeat(columns)));
+                    }
+                }
+                markdown.pop();
+            }
+        }
+        markdown.push('\n');
+    }
+    markdown
+}
+
+fn render_runs(runs: &[Run], options: &MarkdownOptions) -> String {
+    let mut text = String::new();
+    for run in runs {
+        let content = escape(&run.text);
+        let mark = match (options.emphasis, run.style.bold, run.style.italic) {
+            (false, _, _) | (true, false, false) => "",
+            (true, true, false) => "**",
+            (true, false, true) => "*",
+            (true, true, true) => "***",
+        };
+        // Emphasis must not start or end inside whitespace
+        let trimmed = content.trim();
+        let leading = &content[..content.len() - content.trim_start().len()];
+        let trailing = &content[content.trim_end().len()..];
+        if mark.is_empty() || trimmed.is_empty() {
+            text.push_str(&content);
+        } else {
+            text.push_str(&format!("{leading}{mark}{trimmed}{mark}{trailing}"));
+        }
+    }
+    text
+}
+
+/// Appends the words of a line to the runs of a block, merging runs of the
+/// same style and rejoining words hyphenated at the previous line break.
+fn push_runs(runs: &mut Vec<Run>, words: &[TextWord]) {
+    for (index, word) in words.iter().enumerate() {
+        let style = FontStyle {
+            bold: word.bold,
+            italic: word.italic,
+        };
+
+        let mut separator = if runs.is_empty() { "" } else { " " };
+        if index == 0
+            && let Some(last) = runs.last_mut()
+            && ends_with_hyphen(&last.text)
+            && word.text.starts_with(char::is_lowercase)
+        {
+            last.text.pop();
+            separator = "";
+        }
+
+        match runs.last_mut() {
+            Some(last) if last.style == style => {
+                last.text.push_str(separator);
+                last.text.push_str(&word.text);
+            }
+            _ => runs.push(Run {
+                text: format!("{}{}", separator, word.text),
+                style,
+            }),
+        }
+    }
+}
+
+/// Returns the number of table rows at the start of `lines`: consecutive
+/// lines with the same number (two or more) of short cells, each
+/// overlapping the cell of the first row in its column.
+fn table_rows(lines: &[TextLine], options: &MarkdownOptions) -> usize {
+    let Some(first) = lines.first() else {
+        return 0;
+    };
+    let header = cells(first, options);
+    if header.len() < 2 || header.iter().any(|cell| cell.len() > MAX_CELL_WORDS) {
+        return 0;
+    }
+    let columns: Vec<(f64, f64)> = header.iter().map(|cell| cell_extent(cell)).collect();
+
+    let mut rows = 1;
+    for pair in lines.windows(2) {
+        let row = cells(&pair[1], options);
+        let aligned = row.len() == columns.len()
+            && row.iter().all(|cell| cell.len() <= MAX_CELL_WORDS)
+            && row.iter().zip(&columns).all(|(cell, (x0, x1))| {
+                let (c0, c1) = cell_extent(cell);
+                c0 <= *x1 && c1 >= *x0
+            });
+        if !aligned || !close(&pair[0], &pair[1], options) {
+            break;
+        }
+        rows += 1;
+    }
+    rows
+}
+
+/// Splits a line into cells at horizontal gaps wider than
+/// `options.table_gap` times the font size.
+fn cells<'a>(line: &'a TextLine, options: &MarkdownOptions) -> Vec<&'a [TextWord]> {
+    let gap = options.table_gap * line_size(line);
+    let mut cells = Vec::new();
+    let mut start = 0;
+    for index in 1..line.words.len() {
+        if line.words[index].bbox[0] - line.words[index - 1].bbox[2] > gap {
+            cells.push(&line.words[start..index]);
+            start = index;
+        }
+    }
+    if start < line.words.len() {
+        cells.push(&line.words[start..]);
+    }
+    cells
+}
+
+fn cell_extent(words: &[TextWord]) -> (f64, f64) {
+    let x0 = words.first().map_or(0.0, |word| word.bbox[0]);
+    let x1 = words.last().map_or(0.0, |word| word.bbox[2]);
+    (x0, x1)
+}
+
+/// Splits a list marker off the words of a line: a bullet, or a number or
+/// single letter followed by `.` or `)`.
+///
+/// # Returns
+/// The Markdown marker and the remaining words, or `None` if the line
+/// doesn't start a list item
+fn list_marker(words: &[TextWord]) -> Option<(String, &[TextWord])> {
+    let (first, rest) = words.split_first()?;
+    if rest.is_empty() {
+        return None;
+    }
+    let text = first.text.as_str();
+    let mut chars = text.chars();
+    if chars.next().is_some_and(|c| BULLETS.contains(&c)) && chars.next().is_none() {
+        return Some(("-".to_string(), rest));
+    }
+
+    let label = text.strip_suffix(['.', ')'])?;
+    if !label.is_empty() && label.len() <= 3 && label.chars().all(|c| c.is_ascii_digit()) {
+        return Some((format!("{}.", label), rest));
+    }
+    // Lettered items become bullets that keep their label
+    let mut chars = label.chars();
+    if chars.next().is_some_and(|c| c.is_ascii_lowercase()) && chars.next().is_none() {
+        return Some(("-".to_string(), words));
+    }
+    None
+}
+
+/// Returns whether `next` directly follows `line` in the same block.
+fn close(line: &TextLine, next: &TextLine, options: &MarkdownOptions) -> bool {
+    let size = line_size(line).max(line_size(next));
+    let distance = line.bbox[1] - next.bbox[1];
+    distance > 0.0 && distance <= options.paragraph_spacing * size
+}
+
+/// Font size of a line: the largest size of its words.
+fn line_size(line: &TextLine) -> f64 {
+    line.words
+        .iter()
+        .filter_map(|word| word.font_size)
+        .map(f64::abs)
+        .fold(None, |max: Option<f64>, size| {
+            Some(max.unwrap_or(0.0).max(size))
+        })
+        .filter(|size| *size > 0.0)
+        .unwrap_or(DEFAULT_FONT_SIZE)
+}
+
+fn words_text(words: &[TextWord]) -> String {
+    words
+        .iter()
+        .map(|word| word.text.as_str())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Returns the number of digits if `text` starts like an ordered list item:
+/// up to nine digits, then `.` or `)`, then a space or the end.
+fn ordered_list_marker(text: &str) -> Option<usize> {
+    let digits = text.bytes().take_while(u8::is_ascii_digit).count();
+    let rest = &text.as_bytes()[digits..];
+    ((1..=9).contains(&digits)
+        && matches!(rest.first(), Some(b'.' | b')'))
+        && rest.get(1).is_none_or(|b| *b == b' '))
+    .then_some(digits)
+}
+
+/// Escapes the characters that Markdown treats as inline markup.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '*' | '_' | '`' | '[' | ']') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::build_pdf;
+
+    #[test]
+    fn test_document_to_markdown() {
+        let content = "BT /F2 24 Tf 72 700 Td (Annual Report) Tj ET \
+                       BT /F1 10 Tf 72 660 Td (The results were ex-) Tj ET \
+                       BT /F1 10 Tf 72 648 Td (cellent this year.) Tj ET \
+                       BT /F2 10 Tf 72 620 Td (Note:) Tj /F1 10 Tf 40 0 Td (read *this*) Tj ET \
+                       BT /F1 10 Tf 72 590 Td (- First item) Tj ET \
+                       BT /F1 10 Tf 72 578 Td (- Second item) Tj ET \
+                       BT /F1 10 Tf 82 566 Td (continued) Tj ET \
+                       BT /F1 10 Tf 72 554 Td (2. Numbered item) Tj ET \
+                       BT /F1 14 Tf 72 520 Td (Details) Tj ET \
+                       BT /F1 10 Tf 72 490 Td (Name) Tj 228 0 Td (Qty) Tj ET \
+                       BT /F1 10 Tf 72 478 Td (Apples) Tj 228 0 Td (3) Tj ET \
+                       BT /F1 10 Tf 72 466 Td (Pears) Tj 228 0 Td (12) Tj ET";
+        let pdf = build_pdf(&[
+            "<< /Type /Catalog /Pages 2 0 R >>",
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>",
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] /Contents 4 0 R \
+             /Resources << /Font << /F1 5 0 R /F2 6 0 R >> >> >>",
+            &format!(
+                "<< /Length {} >>\nstream\n{}\nendstream",
+                content.len(),
+                content
+            ),
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>",
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold >>",
+        ]);
+        let mut doc = PDFDocument::open(pdf).unwrap();
+
+        let markdown = document_to_markdown(&mut doc, &MarkdownOptions::default()).unwrap();
+        assert_eq!(
+            markdown,
+            "# Annual Report\n\
+             \n\
+             The results were excellent this year.\n\
+             \n\
+             **Note:** read \\*this\\*\n\
+             \n\
+             - First item\n\
+             - Second item continued\n\
+             2. Numbered item\n\
+             \n\
+             ## Details\n\
+             \n\
+             | Name | Qty |\n\
+             | --- | --- |\n\
+             | Apples | 3 |\n\
+             | Pears | 12 |\n"
+        );
+
+        let options = MarkdownOptions {
+            emphasis: false,
+            ..Default::default()
+        };
+        let page = page_to_markdown(&mut doc, 0, &options).unwrap();
+        assert!(page.contains("\nNote: read"));
+    }
+
+    #[test]
+    fn test_paragraphs_not_read_as_lists() {
+        let paragraph = |text: &str| {
+            Block::Paragraph(vec![Run {
+                text: text.to_string(),
+                style: FontStyle::default(),
+            }])
+        };
+        let blocks = [
+            paragraph("1. of the plan"),
+            paragraph("12) below"),
+            paragraph("- so on"),
+            paragraph("1.5 million"),
+        ];
+        assert_eq!(
+            render_blocks(&blocks, &MarkdownOptions::default()),
+            "1\\. of the plan\n\n12\\) below\n\n\\- so on\n\n1.5 million\n"
+        );
+    }
+}
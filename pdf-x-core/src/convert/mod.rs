@@ -0,0 +1,8 @@
+//! Conversion of pages to other document formats.
+//!
+//! - [`html`]: HTML with absolutely positioned text and images, or flowed
+//!   paragraphs in reading order
+//! - [`markdown`]: Markdown with headings, paragraphs, lists and tables
+
+pub mod html;
+pub mod markdown;
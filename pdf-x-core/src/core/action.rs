@@ -0,0 +1,488 @@
+//! Action dictionaries (PDF spec section 12.6).
+//!
+//! Actions are attached to annotations (/A and /AA), outline items (/A),
+//! pages (/AA) and the document itself (/OpenAction and /AA). Every action
+//! may be followed by further actions through /Next, which holds a single
+//! action or an array of them, so an action is really the root of a tree.
+//!
+//! Destinations are kept as stored; resolve them with
+//! `PDFDocument::resolve_explicit_destination()` or
+//! `PDFDocument::resolve_named_destination()`.
+//!
+//! Based on PDF.js src/core/catalog.js (parseDestDictionary) and
+//! src/core/core_utils.js (collectActions).
+
+use crate::core::cycle_guard::CycleGuard;
+use crate::core::error::{PDFError, PDFResult};
+use crate::core::outline::decode_pdf_string;
+use crate::core::parser::PDFObject;
+use crate::core::xref::XRef;
+
+/// Maximum number of actions read from one /Next tree.
+const MAX_ACTIONS: usize = 1000;
+
+/// The target of a GoTo or GoToR action.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ActionDestination {
+    /// A named destination
+    Named(String),
+
+    /// An explicit destination array: [page /Type params...]. For GoToR
+    /// the page is a page number rather than a page object reference.
+    Explicit(PDFObject),
+}
+
+/// What an action does.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ActionKind {
+    /// Go to a destination in this document
+    GoTo {
+        /// The destination, if it is valid
+        dest: Option<ActionDestination>,
+    },
+
+    /// Go to a destination in another document
+    GoToRemote {
+        /// The file specification
+        file: String,
+        /// The destination in the other document
+        dest: Option<ActionDestination>,
+        /// Whether to open the document in a new window
+        new_window: bool,
+    },
+
+    /// Launch an application or open a file
+    Launch {
+        /// The file specification
+        file: String,
+        /// Parameters for the application (Windows-specific /P)
+        parameters: Option<String>,
+        /// Whether to open the document in a new window
+        new_window: bool,
+    },
+
+    /// Resolve a URI
+    URI {
+        /// The URI
+        uri: String,
+        /// Whether to append the mouse position as a map query
+        is_map: bool,
+    },
+
+    /// Execute a viewer-defined action (NextPage, PrevPage, Print, ...)
+    Named {
+        /// The action name
+        name: String,
+    },
+
+    /// Send form data to a URL
+    SubmitForm {
+        /// The URL to submit to
+        url: String,
+        /// Names of the fields to include or exclude (see `flags`); empty
+        /// means all fields
+        fields: Vec<String>,
+        /// The /Flags bit field
+        flags: u32,
+    },
+
+    /// Reset form fields to their default values
+    ResetForm {
+        /// Names of the fields to reset or leave alone (see `flags`); empty
+        /// means all fields
+        fields: Vec<String>,
+        /// The /Flags bit field
+        flags: u32,
+    },
+
+    /// Run a script
+    JavaScript {
+        /// The script source
+        script: String,
+    },
+
+    /// Any other action type (GoToE, Thread, Sound, Hide, ...)
+    Other {
+        /// The action type (/S)
+        action_type: String,
+    },
+}
+
+/// An action and the actions that follow it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Action {
+    /// What the action does
+    pub kind: ActionKind,
+
+    /// The actions performed after this one (/Next), in order
+    pub next: Vec<Action>,
+}
+
+impl Action {
+    /// Returns this action and every action that follows it, depth-first in
+    /// execution order.
+    pub fn flatten(&self) -> Vec<&Action> {
+        let mut actions = Vec::new();
+        let mut stack = vec![self];
+        while let Some(action) = stack.pop() {
+            actions.push(action);
+            stack.extend(action.next.iter().rev());
+        }
+        actions
+    }
+
+    /// Returns what every action in the chain reaches outside the document:
+    /// URIs, launched files, other PDF files, form submission URLs and
+    /// scripts, in execution order.
+    pub fn external_targets(&self) -> Vec<(ExternalLinkKind, &str)> {
+        self.flatten()
+            .into_iter()
+            .filter_map(|action| match &action.kind {
+                ActionKind::URI { uri, .. } => Some((ExternalLinkKind::Uri, uri.as_str())),
+                ActionKind::Launch { file, .. } => Some((ExternalLinkKind::Launch, file.as_str())),
+                ActionKind::GoToRemote { file, .. } => {
+                    Some((ExternalLinkKind::RemoteDocument, file.as_str()))
+                }
+                ActionKind::SubmitForm { url, .. } => {
+                    Some((ExternalLinkKind::SubmitForm, url.as_str()))
+                }
+                ActionKind::JavaScript { script } => {
+                    Some((ExternalLinkKind::JavaScript, script.as_str()))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns the source of every JavaScript action in the chain.
+    pub fn scripts(&self) -> Vec<&str> {
+        self.flatten()
+            .into_iter()
+            .filter_map(|action| match &action.kind {
+                ActionKind::JavaScript { script } => Some(script.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// What an [`ExternalLink`] reaches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExternalLinkKind {
+    /// A URI action; the target is the URI
+    Uri,
+
+    /// A Launch action; the target is the file to open or run
+    Launch,
+
+    /// A GoToR action; the target is the other PDF file
+    RemoteDocument,
+
+    /// A SubmitForm action; the target is the URL the form is sent to
+    SubmitForm,
+
+    /// A JavaScript action, which may open URLs itself; the target is the
+    /// script source
+    JavaScript,
+}
+
+/// An action of a page annotation that reaches outside the document, as
+/// listed by `PDFDocument::external_urls()`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExternalLink {
+    /// The 0-based index of the page
+    pub page_index: usize,
+
+    /// What the action does
+    pub kind: ExternalLinkKind,
+
+    /// The URI, file, form submission URL or script
+    pub target: String,
+}
+
+/// A script found in a document, as listed by `PDFDocument::javascript()`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocumentScript {
+    /// Where the script is attached, e.g. "OpenAction", "Names/JavaScript
+    /// (init)" or "Page 2 annotation 12 0 R /AA /K"
+    pub location: String,
+
+    /// The script source
+    pub script: String,
+}
+
+/// Parses an action dictionary, with its /Next chain.
+///
+/// A bare destination (as /OpenAction or a link's /Dest may hold) is read
+/// as a GoTo action.
+///
+/// # Arguments
+/// * `obj` - The action, or a reference to it
+/// * `xref` - Cross-reference table for resolving objects
+///
+/// # Returns
+/// The action, or None if `obj` is not an action
+pub fn parse_action(obj: &PDFObject, xref: &mut XRef) -> PDFResult<Option<Action>> {
+    let mut visited = CycleGuard::new();
+    let mut budget = MAX_ACTIONS;
+    parse_action_tree(obj, xref, &mut visited, &mut budget)
+}
+
+/// Parses an additional-actions dictionary (/AA).
+///
+/// # Arguments
+/// * `obj` - The /AA dictionary, or a reference to it
+/// * `xref` - Cross-reference table for resolving objects
+///
+/// # Returns
+/// (trigger, action) pairs sorted by trigger name, e.g. ("O", ...) for a
+/// page being opened or ("K", ...) for a keystroke in a form field
+pub fn parse_additional_actions(
+    obj: &PDFObject,
+    xref: &mut XRef,
+) -> PDFResult<Vec<(String, Action)>> {
+    let dict = match xref.fetch_if_ref(obj)? {
+        PDFObject::Dictionary(dict) => dict,
+        _ => return Ok(Vec::new()),
+    };
+
+    let mut triggers: Vec<_> = dict.into_iter().collect();
+    triggers.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut actions = Vec::new();
+    for (trigger, value) in triggers {
+        if let Some(action) = parse_action(&value, xref)? {
+            actions.push((trigger, action));
+        }
+    }
+    Ok(actions)
+}
+
+fn parse_action_tree(
+    obj: &PDFObject,
+    xref: &mut XRef,
+    visited: &mut CycleGuard,
+    budget: &mut usize,
+) -> PDFResult<Option<Action>> {
+    if !visited.visit_object(obj) {
+        return Ok(None);
+    }
+    if *budget == 0 {
+        return Ok(None);
+    }
+    *budget -= 1;
+
+    let dict = match xref.fetch_if_ref(obj)? {
+        PDFObject::Dictionary(dict) => dict,
+        dest @ (PDFObject::Array(_)
+        | PDFObject::String(_)
+        | PDFObject::HexString(_)
+        | PDFObject::Name(_)) => {
+            return Ok(Some(Action {
+                kind: ActionKind::GoTo {
+                    dest: parse_destination(&dest, xref)?,
+                },
+                next: Vec::new(),
+            }));
+        }
+        _ => return Ok(None),
+    };
+
+    let action_type = match dict.get("S") {
+        Some(PDFObject::Name(name)) => name.clone(),
+        _ => return Ok(None),
+    };
+
+    let flag = |key: &str| matches!(dict.get(key), Some(PDFObject::Boolean(true)));
+    let flags = match dict.get("Flags") {
+        Some(PDFObject::Number(n)) => *n as u32,
+        _ => 0,
+    };
+
+    let kind = match action_type.as_str() {
+        "GoTo" => ActionKind::GoTo {
+            dest: match dict.get("D") {
+                Some(dest) => parse_destination(dest, xref)?,
+                None => None,
+            },
+        },
+        "GoToR" => ActionKind::GoToRemote {
+            file: file_spec_name(dict.get("F"), xref)?,
+            dest: match dict.get("D") {
+                Some(dest) => parse_destination(dest, xref)?,
+                None => None,
+            },
+            new_window: flag("NewWindow"),
+        },
+        "Launch" => {
+            // The platform-specific /Win dictionary holds the file and its
+            // parameters when there's no /F
+            let win = match dict.get("Win") {
+                Some(win) => match xref.fetch_if_ref(win)? {
+                    PDFObject::Dictionary(win) => Some(win),
+                    _ => None,
+                },
+                None => None,
+            };
+            let file = match (dict.get("F"), &win) {
+                (Some(f), _) => file_spec_name(Some(f), xref)?,
+                (None, Some(win)) => file_spec_name(win.get("F"), xref)?,
+                _ => String::new(),
+            };
+            let parameters = match win.as_ref().and_then(|win| win.get("P")) {
+                Some(PDFObject::String(bytes)) | Some(PDFObject::HexString(bytes)) => {
+                    Some(decode_pdf_string(bytes))
+                }
+                _ => None,
+            };
+            ActionKind::Launch {
+                file,
+                parameters,
+                new_window: flag("NewWindow"),
+            }
+        }
+        "URI" => ActionKind::URI {
+            // URIs are 7-bit ASCII; don't reinterpret the bytes
+            uri: match dict
+                .get("URI")
+                .map(|uri| xref.fetch_if_ref(uri))
+                .transpose()?
+            {
+                Some(PDFObject::String(bytes)) | Some(PDFObject::HexString(bytes)) => {
+                    String::from_utf8_lossy(&bytes).to_string()
+                }
+                _ => String::new(),
+            },
+            is_map: flag("IsMap"),
+        },
+        "Named" => ActionKind::Named {
+            name: match dict.get("N") {
+                Some(PDFObject::Name(name)) => name.clone(),
+                _ => String::new(),
+            },
+        },
+        "SubmitForm" => ActionKind::SubmitForm {
+            url: file_spec_name(dict.get("F"), xref)?,
+            fields: field_names(dict.get("Fields"), xref)?,
+            flags,
+        },
+        "ResetForm" => ActionKind::ResetForm {
+            fields: field_names(dict.get("Fields"), xref)?,
+            flags,
+        },
+        "JavaScript" => ActionKind::JavaScript {
+            script: match dict.get("JS") {
+                Some(js) => script_source(js, xref)?,
+                None => String::new(),
+            },
+        },
+        _ => ActionKind::Other { action_type },
+    };
+
+    let mut next = Vec::new();
+    if let Some(next_obj) = dict.get("Next") {
+        let items = match next_obj {
+            PDFObject::Array(items) => items.iter().map(|item| (**item).clone()).collect(),
+            PDFObject::Ref(_) => match xref.fetch_if_ref(next_obj)? {
+                PDFObject::Array(items) => items.into_iter().map(|item| *item).collect(),
+                _ => vec![next_obj.clone()],
+            },
+            other => vec![other.clone()],
+        };
+        for item in items {
+            if let Some(action) = parse_action_tree(&item, xref, visited, budget)? {
+                next.push(action);
+            }
+        }
+    }
+
+    Ok(Some(Action { kind, next }))
+}
+
+/// Reads a destination: a name, a string or an explicit destination array.
+fn parse_destination(obj: &PDFObject, xref: &mut XRef) -> PDFResult<Option<ActionDestination>> {
+    Ok(match xref.fetch_if_ref(obj)? {
+        PDFObject::String(bytes) | PDFObject::HexString(bytes) => {
+            Some(ActionDestination::Named(decode_pdf_string(&bytes)))
+        }
+        PDFObject::Name(name) => Some(ActionDestination::Named(name)),
+        dest @ PDFObject::Array(_) => Some(ActionDestination::Explicit(dest)),
+        // A destination dictionary holds the array under /D
+        PDFObject::Dictionary(dict) => match dict.get("D") {
+            Some(dest) => parse_destination(dest, xref)?,
+            None => None,
+        },
+        _ => None,
+    })
+}
+
+/// Reads the file name of a file specification string or dictionary.
+fn file_spec_name(obj: Option<&PDFObject>, xref: &mut XRef) -> PDFResult<String> {
+    let Some(obj) = obj else {
+        return Ok(String::new());
+    };
+    Ok(match xref.fetch_if_ref(obj)? {
+        PDFObject::String(bytes) | PDFObject::HexString(bytes) => decode_pdf_string(&bytes),
+        PDFObject::Dictionary(dict) => ["UF", "F", "Unix", "DOS", "Mac"]
+            .iter()
+            .find_map(|key| match dict.get(*key) {
+                Some(PDFObject::String(bytes)) | Some(PDFObject::HexString(bytes)) => {
+                    Some(decode_pdf_string(bytes))
+                }
+                _ => None,
+            })
+            .unwrap_or_default(),
+        _ => String::new(),
+    })
+}
+
+/// Reads the /Fields of a form action: field names, or references to field
+/// dictionaries, which are named by their partial name (/T).
+fn field_names(obj: Option<&PDFObject>, xref: &mut XRef) -> PDFResult<Vec<String>> {
+    let Some(obj) = obj else {
+        return Ok(Vec::new());
+    };
+    let items = match xref.fetch_if_ref(obj)? {
+        PDFObject::Array(items) => items,
+        _ => return Ok(Vec::new()),
+    };
+
+    let mut names = Vec::new();
+    for item in items {
+        match *item {
+            PDFObject::String(bytes) | PDFObject::HexString(bytes) => {
+                names.push(decode_pdf_string(&bytes))
+            }
+            ref field @ PDFObject::Ref(_) => match xref.fetch_if_ref(field) {
+                Ok(PDFObject::Dictionary(dict)) => {
+                    if let Some(PDFObject::String(bytes)) | Some(PDFObject::HexString(bytes)) =
+                        dict.get("T")
+                    {
+                        names.push(decode_pdf_string(bytes));
+                    }
+                }
+                Err(e @ PDFError::DataMissing { .. }) => return Err(e),
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+    Ok(names)
+}
+
+/// Reads a script held in a text string or a stream.
+fn script_source(obj: &PDFObject, xref: &mut XRef) -> PDFResult<String> {
+    match obj {
+        PDFObject::Ref(_) => match xref.fetch_if_ref(obj)? {
+            PDFObject::Stream { .. } => {
+                let data = xref.decoded_stream(obj)?;
+                Ok(decode_pdf_string(&data))
+            }
+            PDFObject::String(bytes) | PDFObject::HexString(bytes) => Ok(decode_pdf_string(&bytes)),
+            _ => Ok(String::new()),
+        },
+        PDFObject::Stream { .. } => Ok(decode_pdf_string(&xref.decoded_stream(obj)?)),
+        PDFObject::String(bytes) | PDFObject::HexString(bytes) => Ok(decode_pdf_string(bytes)),
+        _ => Ok(String::new()),
+    }
+}
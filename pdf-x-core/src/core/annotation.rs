@@ -4,7 +4,13 @@
 //!
 //! Based on PDF.js src/core/annotation.js.
 
-use crate::core::error::PDFResult;
+use crate::core::action::{
+    Action, ActionDestination, ActionKind, parse_action, parse_additional_actions,
+};
+use crate::core::attachments::Attachment;
+use crate::core::cycle_guard::CycleGuard;
+use crate::core::error::{PDFError, PDFResult};
+use crate::core::layout::TextWord;
 use crate::core::parser::PDFObject;
 use rustc_hash::FxHashMap;
 use std::collections::HashSet;
@@ -184,6 +190,9 @@ impl AnnotationFlags {
 /// A rectangle defining the annotation's location on the page.
 pub type AnnotationRect = [f64; 4]; // [llx, lly, urx, ury]
 
+/// A quadrilateral covering marked-up text, as four (x, y) corners.
+pub type AnnotationQuad = [f64; 8]; // [x1, y1, x2, y2, x3, y3, x4, y4]
+
 /// Border style for annotations.
 #[derive(Debug, Clone)]
 pub struct AnnotationBorder {
@@ -204,8 +213,20 @@ pub struct AnnotationBorder {
 pub type AnnotationColor = Vec<f64>;
 
 /// An annotation on a PDF page.
+///
+/// `object_ref` and `name` identify the annotation independently of its
+/// position in the page's /Annots array. Both are preserved by delta edits
+/// and by `PDFDocument::save()` / `save_incremental()`, so they can be used
+/// to sync annotations with external systems.
 #[derive(Debug, Clone)]
 pub struct Annotation {
+    /// The annotation's indirect object reference (num, gen), or None if
+    /// the annotation is a direct object inside the /Annots array
+    pub object_ref: Option<(u32, u32)>,
+
+    /// The annotation name (/NM), unique among the annotations on its page
+    pub name: Option<String>,
+
     /// The annotation type
     pub annotation_type: AnnotationType,
 
@@ -230,10 +251,53 @@ pub struct Annotation {
     /// The appearance dictionary
     pub appearance: Option<PDFObject>,
 
+    /// The appearance state (/AS), selecting among per-state appearances
+    pub appearance_state: Option<String>,
+
+    /// The action performed when the annotation is activated (/A)
+    pub action: Option<Action>,
+
+    /// Actions for other triggers (/AA), such as the cursor entering the
+    /// annotation or a form field's value changing
+    pub additional_actions: Vec<(String, Action)>,
+
     /// Annotation-specific data
     pub data: AnnotationData,
 }
 
+impl Annotation {
+    /// Returns the normal appearance stream (/AP /N) to draw.
+    ///
+    /// When /N is a dictionary of appearances per state (check boxes, radio
+    /// buttons), the one named by /AS is chosen.
+    ///
+    /// # Returns
+    /// The appearance stream, or `None` if the annotation has none for its
+    /// current state
+    pub fn normal_appearance(&self, xref: &mut crate::core::XRef) -> PDFResult<Option<PDFObject>> {
+        let Some(appearance) = &self.appearance else {
+            return Ok(None);
+        };
+        let normal = match xref.fetch_if_ref(appearance)? {
+            PDFObject::Dictionary(dict) => match dict.get("N") {
+                Some(normal) => xref.fetch_if_ref(normal)?,
+                None => return Ok(None),
+            },
+            _ => return Ok(None),
+        };
+        let stream = match normal {
+            PDFObject::Dictionary(states) => {
+                match self.appearance_state.as_ref().and_then(|s| states.get(s)) {
+                    Some(stream) => xref.fetch_if_ref(stream)?,
+                    None => return Ok(None),
+                }
+            }
+            stream => stream,
+        };
+        Ok(matches!(stream, PDFObject::Stream { .. }).then_some(stream))
+    }
+}
+
 /// Annotation-specific data.
 #[derive(Debug, Clone)]
 pub enum AnnotationData {
@@ -254,6 +318,9 @@ pub enum AnnotationData {
 
     /// Popup annotation data
     Popup(PopupAnnotation),
+
+    /// Text markup annotation data (Highlight, Underline, Squiggly, StrikeOut)
+    Markup(MarkupAnnotation),
 }
 
 impl Default for AnnotationData {
@@ -265,11 +332,18 @@ impl Default for AnnotationData {
 /// Link annotation data.
 #[derive(Debug, Clone)]
 pub struct LinkAnnotation {
-    /// The link action
+    /// Where the link goes: the annotation's action (see
+    /// [`Annotation::action`]), or its /Dest when it has no action
     pub action: LinkAction,
 }
 
-/// Actions that can be performed by a link.
+/// Where a link goes, as seen by a viewer following it.
+///
+/// This is a view of the first [`Action`] of a link (see
+/// [`LinkAction::from_action`]): explicit destinations are split into a
+/// page and a destination type, and action types a viewer doesn't follow
+/// are [`Unknown`](Self::Unknown). The action itself, with its /Next chain,
+/// is [`Annotation::action`].
 #[derive(Debug, Clone)]
 pub enum LinkAction {
     /// Go to a destination in the same document
@@ -280,6 +354,15 @@ pub enum LinkAction {
         dest: crate::core::outline::DestinationType,
     },
 
+    /// Go to an explicit destination on a page given by reference, which
+    /// `PDFDocument::resolve_link_action()` maps to a page index
+    GoToPage {
+        /// Object reference (num, gen) of the target page
+        page_ref: (u32, u32),
+        /// Destination type and parameters
+        dest: crate::core::outline::DestinationType,
+    },
+
     /// Go to a named destination
     GoToNamed {
         /// Named destination string
@@ -322,6 +405,82 @@ pub enum LinkAction {
     Unknown,
 }
 
+impl LinkAction {
+    /// Returns what following `action` does; the actions after it (/Next)
+    /// are not considered.
+    ///
+    /// # Arguments
+    /// * `action` - An action parsed with `parse_action`
+    pub fn from_action(action: &Action) -> PDFResult<Self> {
+        Ok(match &action.kind {
+            ActionKind::GoTo { dest } => match dest {
+                Some(ActionDestination::Named(name)) => {
+                    LinkAction::GoToNamed { name: name.clone() }
+                }
+                Some(ActionDestination::Explicit(dest)) => explicit_link_destination(dest)?,
+                None => LinkAction::Unknown,
+            },
+            ActionKind::GoToRemote {
+                file,
+                dest,
+                new_window,
+            } => LinkAction::GoToRemote {
+                file_spec: file.clone(),
+                dest: match dest {
+                    Some(ActionDestination::Named(name)) => Some(name.clone()),
+                    _ => None,
+                },
+                new_window: *new_window,
+            },
+            ActionKind::Launch {
+                file, parameters, ..
+            } => LinkAction::Launch {
+                application: file.clone(),
+                parameters: parameters.clone(),
+            },
+            ActionKind::URI { uri, is_map } => LinkAction::URI {
+                url: uri.clone(),
+                is_map: *is_map,
+            },
+            ActionKind::Named { name } => LinkAction::Named { name: name.clone() },
+            _ => LinkAction::Unknown,
+        })
+    }
+}
+
+/// A link annotation of a page with its location and target.
+#[derive(Debug, Clone)]
+pub struct PageLink {
+    /// The link rectangle in user space
+    pub rect: AnnotationRect,
+
+    /// The link rectangle `[x0, y0, x1, y1]` in device space at scale 1:
+    /// origin at the top left of the visible page area, with the page's
+    /// /Rotate applied
+    pub device_rect: [f64; 4],
+
+    /// The action as written in the annotation
+    pub action: LinkAction,
+
+    /// Where the link goes, if it could be resolved
+    pub target: Option<LinkTarget>,
+}
+
+/// The resolved target of a [`PageLink`].
+#[derive(Debug, Clone)]
+pub enum LinkTarget {
+    /// A page of the same document
+    Page {
+        /// Page index (0-based)
+        page_index: usize,
+        /// Destination type and parameters
+        dest: crate::core::outline::DestinationType,
+    },
+
+    /// An external URI
+    Uri(String),
+}
+
 /// Text annotation data (sticky notes, comments).
 #[derive(Debug, Clone)]
 pub struct TextAnnotation {
@@ -341,6 +500,12 @@ pub struct TextAnnotation {
 /// Widget annotation data (form field).
 #[derive(Debug, Clone)]
 pub struct WidgetAnnotation {
+    /// The reference (num, gen) of the field dictionary this widget belongs to.
+    ///
+    /// For widgets merged with their field this equals the annotation's own
+    /// reference; for widgets that are kids of a field it is the parent's.
+    pub field_ref: Option<(u32, u32)>,
+
     /// The form field type
     pub field_type: FormFieldType,
 
@@ -387,6 +552,9 @@ pub struct FileAttachmentAnnotation {
 
     /// Description of the file
     pub description: Option<String>,
+
+    /// The embedded file, if the file specification embeds one
+    pub embedded_file: Option<Attachment>,
 }
 
 /// Popup annotation data.
@@ -399,6 +567,62 @@ pub struct PopupAnnotation {
     pub parent_ref: Option<(u32, u32)>,
 }
 
+/// Text markup annotation data (Highlight, Underline, Squiggly, StrikeOut).
+#[derive(Debug, Clone)]
+pub struct MarkupAnnotation {
+    /// The quadrilaterals (/QuadPoints) covering the marked-up text, usually
+    /// one per line
+    pub quad_points: Vec<AnnotationQuad>,
+}
+
+impl MarkupAnnotation {
+    /// Finds the words covered by the quadrilaterals.
+    ///
+    /// A word is covered when the center of its bounding box lies within the
+    /// bounding box of a quadrilateral. Words are returned quadrilateral by
+    /// quadrilateral, each in the order of `words`, and only once.
+    ///
+    /// # Arguments
+    /// * `words` - The page's words, from `Page::extract_words()`
+    pub fn covered_words<'a>(&self, words: &'a [TextWord]) -> Vec<&'a TextWord> {
+        let mut covered = Vec::new();
+        let mut seen = HashSet::new();
+        for quad in &self.quad_points {
+            let span = |values: [f64; 4]| {
+                values
+                    .iter()
+                    .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), v| {
+                        (lo.min(*v), hi.max(*v))
+                    })
+            };
+            let (x0, x1) = span([quad[0], quad[2], quad[4], quad[6]]);
+            let (y0, y1) = span([quad[1], quad[3], quad[5], quad[7]]);
+
+            for (i, word) in words.iter().enumerate() {
+                let cx = (word.bbox[0] + word.bbox[2]) / 2.0;
+                let cy = (word.bbox[1] + word.bbox[3]) / 2.0;
+                if cx >= x0 && cx <= x1 && cy >= y0 && cy <= y1 && seen.insert(i) {
+                    covered.push(word);
+                }
+            }
+        }
+        covered
+    }
+
+    /// Returns the text covered by the quadrilaterals, with words separated
+    /// by single spaces.
+    ///
+    /// # Arguments
+    /// * `words` - The page's words, from `Page::extract_words()`
+    pub fn covered_text(&self, words: &[TextWord]) -> String {
+        self.covered_words(words)
+            .iter()
+            .map(|word| word.text.as_str())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
 /// Parses annotations from a page's Annots array.
 ///
 /// # Arguments
@@ -425,26 +649,25 @@ pub fn parse_annotations(
     };
 
     let mut annotations = Vec::new();
-    let mut visited_refs: HashSet<(u32, u32)> = HashSet::new();
+    let mut visited_refs = CycleGuard::new();
 
     for annot_ref in annots_array.iter() {
-        let annot_dict = match &**annot_ref {
+        let (annot_dict, object_ref) = match &**annot_ref {
             PDFObject::Ref(ref_obj) => {
                 let ref_key = (ref_obj.num, ref_obj.generation);
 
-                // Prevent circular references
-                if visited_refs.contains(&ref_key) {
+                // Skip annotations listed twice
+                if !visited_refs.visit(*ref_obj) {
                     continue;
                 }
-                visited_refs.insert(ref_key);
 
                 let fetched = xref.fetch(ref_obj.num, ref_obj.generation)?;
                 match &*fetched {
-                    PDFObject::Dictionary(dict) => dict.clone(),
+                    PDFObject::Dictionary(dict) => (dict.clone(), Some(ref_key)),
                     _ => continue,
                 }
             }
-            PDFObject::Dictionary(dict) => dict.clone(),
+            PDFObject::Dictionary(dict) => (dict.clone(), None),
             _ => continue,
         };
 
@@ -455,7 +678,7 @@ pub fn parse_annotations(
             fx_dict.insert(k.clone(), v.clone());
         }
 
-        if let Ok(annot) = parse_annotation_dict(&fx_dict, xref) {
+        if let Ok(annot) = parse_annotation_dict(&fx_dict, object_ref, xref) {
             annotations.push(annot);
         } else {
             // Silently skip invalid annotations
@@ -468,6 +691,7 @@ pub fn parse_annotations(
 /// Parses a single annotation dictionary.
 fn parse_annotation_dict(
     dict: &FxHashMap<String, PDFObject>,
+    object_ref: Option<(u32, u32)>,
     xref: &mut crate::core::XRef,
 ) -> PDFResult<Annotation> {
     // Get the annotation subtype (type)
@@ -500,6 +724,14 @@ fn parse_annotation_dict(
         _ => None,
     };
 
+    // Get the annotation name
+    let name = match dict.get("NM") {
+        Some(PDFObject::String(bytes)) | Some(PDFObject::HexString(bytes)) => {
+            Some(String::from_utf8_lossy(bytes).to_string())
+        }
+        _ => None,
+    };
+
     // Get the flags
     let flags = match dict.get("F") {
         Some(PDFObject::Number(n)) => AnnotationFlags::from_flags(*n as i32),
@@ -531,11 +763,29 @@ fn parse_annotation_dict(
 
     // Get the appearance
     let appearance = dict.get("AP").cloned();
+    let appearance_state = match dict.get("AS") {
+        Some(PDFObject::Name(state)) => Some(state.clone()),
+        _ => None,
+    };
+
+    // A broken action doesn't make the annotation unusable
+    let action = match dict.get("A").map(|a| parse_action(a, xref)) {
+        Some(Ok(action)) => action,
+        Some(Err(e @ PDFError::DataMissing { .. })) => return Err(e),
+        _ => None,
+    };
+    let additional_actions = match dict.get("AA").map(|aa| parse_additional_actions(aa, xref)) {
+        Some(Ok(actions)) => actions,
+        Some(Err(e @ PDFError::DataMissing { .. })) => return Err(e),
+        _ => Vec::new(),
+    };
 
     // Parse annotation-specific data
-    let data = parse_annotation_data(&annotation_type, dict, xref)?;
+    let data = parse_annotation_data(&annotation_type, dict, object_ref, action.as_ref(), xref)?;
 
     Ok(Annotation {
+        object_ref,
+        name,
         annotation_type,
         rect,
         contents,
@@ -544,6 +794,9 @@ fn parse_annotation_dict(
         color,
         modification_date,
         appearance,
+        appearance_state,
+        action,
+        additional_actions,
         data,
     })
 }
@@ -552,11 +805,13 @@ fn parse_annotation_dict(
 fn parse_annotation_data(
     annotation_type: &AnnotationType,
     dict: &FxHashMap<String, PDFObject>,
+    object_ref: Option<(u32, u32)>,
+    action: Option<&Action>,
     xref: &mut crate::core::XRef,
 ) -> PDFResult<AnnotationData> {
     match annotation_type {
         AnnotationType::Link => {
-            let action = parse_link_action(dict, xref)?;
+            let action = parse_link_action(action, dict, xref)?;
             Ok(AnnotationData::Link(LinkAnnotation { action }))
         }
         AnnotationType::Text => {
@@ -587,7 +842,11 @@ fn parse_annotation_data(
             }))
         }
         AnnotationType::Widget => {
-            let field_type = match dict.get("FT") {
+            // A widget either is its field (merged dictionary) or is a kid of
+            // the field dictionary, in which case it has no /T of its own
+            let (field_dict, field_ref) = resolve_field_dict(dict, object_ref, xref);
+
+            let field_type = match get_inheritable_field_value(&field_dict, "FT", xref)?.as_ref() {
                 Some(PDFObject::Name(name)) => match name.as_str() {
                     "Btn" => FormFieldType::Button,
                     "Tx" => FormFieldType::Text,
@@ -598,14 +857,14 @@ fn parse_annotation_data(
                 _ => FormFieldType::Unknown("".to_string()),
             };
 
-            let field_name = match dict.get("T") {
+            let field_name = match field_dict.get("T") {
                 Some(PDFObject::String(bytes)) | Some(PDFObject::HexString(bytes)) => {
                     Some(String::from_utf8_lossy(bytes).to_string())
                 }
                 _ => None,
             };
 
-            let field_value = match dict.get("V") {
+            let field_value = match get_inheritable_field_value(&field_dict, "V", xref)?.as_ref() {
                 Some(PDFObject::String(bytes)) | Some(PDFObject::HexString(bytes)) => {
                     Some(String::from_utf8_lossy(bytes).to_string())
                 }
@@ -613,7 +872,8 @@ fn parse_annotation_data(
                 _ => None,
             };
 
-            let default_value = match dict.get("DV") {
+            let default_value = match get_inheritable_field_value(&field_dict, "DV", xref)?.as_ref()
+            {
                 Some(PDFObject::String(bytes)) | Some(PDFObject::HexString(bytes)) => {
                     Some(String::from_utf8_lossy(bytes).to_string())
                 }
@@ -628,6 +888,7 @@ fn parse_annotation_data(
             };
 
             Ok(AnnotationData::Widget(WidgetAnnotation {
+                field_ref,
                 field_type,
                 field_name,
                 field_value,
@@ -636,11 +897,23 @@ fn parse_annotation_data(
             }))
         }
         AnnotationType::FileAttachment => {
+            let embedded_file = match dict.get("FS") {
+                Some(fs) => match Attachment::from_file_spec(xref, fs, false) {
+                    Ok(file) => file,
+                    Err(e @ PDFError::DataMissing { .. }) => return Err(e),
+                    Err(_) => None,
+                },
+                None => None,
+            };
+
             let file_spec = match dict.get("FS") {
                 Some(PDFObject::String(bytes)) | Some(PDFObject::HexString(bytes)) => {
                     String::from_utf8_lossy(bytes).to_string()
                 }
-                _ => String::new(),
+                _ => embedded_file
+                    .as_ref()
+                    .map(|file| file.name.clone())
+                    .unwrap_or_default(),
             };
 
             let file_name = match dict.get("F") {
@@ -661,6 +934,7 @@ fn parse_annotation_data(
                 file_spec,
                 file_name,
                 description,
+                embedded_file,
             }))
         }
         AnnotationType::Popup => {
@@ -676,128 +950,162 @@ fn parse_annotation_data(
 
             Ok(AnnotationData::Popup(PopupAnnotation { open, parent_ref }))
         }
+        AnnotationType::Highlight
+        | AnnotationType::Underline
+        | AnnotationType::Squiggly
+        | AnnotationType::StrikeOut => {
+            let numbers: Vec<f64> = match dict.get("QuadPoints") {
+                Some(obj) => match xref.fetch_if_ref(obj)? {
+                    PDFObject::Array(arr) => arr
+                        .iter()
+                        .map(|val| match &**val {
+                            PDFObject::Number(n) => *n,
+                            _ => 0.0,
+                        })
+                        .collect(),
+                    _ => Vec::new(),
+                },
+                None => Vec::new(),
+            };
+
+            // Incomplete trailing quadrilaterals are ignored, as in PDF.js
+            let quad_points = numbers
+                .chunks_exact(8)
+                .map(|chunk| {
+                    let mut quad = [0.0; 8];
+                    quad.copy_from_slice(chunk);
+                    quad
+                })
+                .collect();
+
+            Ok(AnnotationData::Markup(MarkupAnnotation { quad_points }))
+        }
         _ => Ok(AnnotationData::None),
     }
 }
 
-/// Parses the action for a link annotation.
-fn parse_link_action(
+/// Resolves the field dictionary a widget annotation belongs to.
+///
+/// Returns the widget dictionary itself when it carries a /T entry (merged
+/// field and widget) or has no /Parent, otherwise the parent field dictionary.
+fn resolve_field_dict(
     dict: &FxHashMap<String, PDFObject>,
+    object_ref: Option<(u32, u32)>,
     xref: &mut crate::core::XRef,
-) -> PDFResult<LinkAction> {
-    // Check for action dictionary (/A)
-    if let Some(action_obj) = dict.get("A") {
-        let action_dict = match xref.fetch_if_ref(action_obj)? {
-            PDFObject::Dictionary(dict) => dict,
-            _ => return Ok(LinkAction::Unknown),
-        };
+) -> (FxHashMap<String, PDFObject>, Option<(u32, u32)>) {
+    if dict.contains_key("T") {
+        return (dict.clone(), object_ref);
+    }
 
-        let action_type = match action_dict.get("S") {
-            Some(PDFObject::Name(name)) => name.as_str(),
-            _ => return Ok(LinkAction::Unknown),
-        };
+    if let Some(PDFObject::Ref(parent_ref)) = dict.get("Parent")
+        && let Ok(parent) = xref.fetch(parent_ref.num, parent_ref.generation)
+        && let PDFObject::Dictionary(parent_dict) = &*parent
+    {
+        let fx_dict: FxHashMap<String, PDFObject> = parent_dict
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        return (fx_dict, Some((parent_ref.num, parent_ref.generation)));
+    }
 
-        match action_type {
-            "URI" => {
-                let uri = match action_dict.get("URI") {
-                    Some(PDFObject::String(bytes)) | Some(PDFObject::HexString(bytes)) => {
-                        String::from_utf8_lossy(bytes).to_string()
-                    }
-                    _ => String::new(),
-                };
-                let is_map = match action_dict.get("IsMap") {
-                    Some(PDFObject::Boolean(b)) => *b,
-                    _ => false,
-                };
-                return Ok(LinkAction::URI { url: uri, is_map });
-            }
-            "GoTo" => {
-                if let Some(dest_obj) = action_dict.get("D") {
-                    return parse_goto_destination(dest_obj, xref);
-                }
-            }
-            "GoToR" => {
-                let file_spec = match action_dict.get("F") {
-                    Some(PDFObject::String(bytes)) => String::from_utf8_lossy(bytes).to_string(),
-                    _ => String::new(),
-                };
-                let dest = match action_dict.get("D") {
-                    Some(PDFObject::String(bytes)) | Some(PDFObject::HexString(bytes)) => {
-                        Some(String::from_utf8_lossy(bytes).to_string())
-                    }
-                    _ => None,
-                };
-                let new_window = match action_dict.get("NewWindow") {
-                    Some(PDFObject::Boolean(b)) => *b,
-                    _ => false,
-                };
-                return Ok(LinkAction::GoToRemote {
-                    file_spec,
-                    dest,
-                    new_window,
-                });
-            }
-            "Launch" => {
-                let application = match action_dict.get("F") {
-                    Some(PDFObject::String(bytes)) => String::from_utf8_lossy(bytes).to_string(),
-                    _ => String::new(),
-                };
-                let parameters = match action_dict.get("P") {
-                    Some(PDFObject::String(bytes)) => {
-                        Some(String::from_utf8_lossy(bytes).to_string())
-                    }
-                    _ => None,
-                };
-                return Ok(LinkAction::Launch {
-                    application,
-                    parameters,
-                });
-            }
-            "Named" => {
-                let name = match action_dict.get("N") {
-                    Some(PDFObject::Name(name)) => name.clone(),
-                    _ => String::new(),
-                };
-                return Ok(LinkAction::Named { name });
-            }
-            _ => {}
-        }
+    (dict.clone(), object_ref)
+}
+
+/// Looks up an inheritable field attribute (/FT, /V, /DV, /Ff, /DA, ...),
+/// walking up the /Parent chain of the field hierarchy if needed.
+///
+/// Unreadable parents end the walk; a chain longer than the document's
+/// `max_object_depth` fails with [`PDFError::LimitExceeded`].
+fn get_inheritable_field_value(
+    dict: &FxHashMap<String, PDFObject>,
+    key: &str,
+    xref: &mut crate::core::XRef,
+) -> PDFResult<Option<PDFObject>> {
+    if let Some(value) = dict.get(key) {
+        return Ok(xref.fetch_if_ref(value).ok());
     }
 
-    // Check for destination (/Dest)
-    if let Some(dest_obj) = dict.get("Dest") {
-        return parse_goto_destination(dest_obj, xref);
+    let max_depth = xref.limits().max_object_depth;
+    let mut parent = dict.get("Parent").cloned();
+    let mut visited = CycleGuard::new();
+
+    while let Some(PDFObject::Ref(parent_ref)) = parent {
+        if visited.len() >= max_depth {
+            return Err(PDFError::limit_exceeded(
+                "form field /Parent chain length",
+                max_depth,
+            ));
+        }
+        if !visited.visit(parent_ref) {
+            break;
+        }
+
+        let Ok(fetched) = xref.fetch(parent_ref.num, parent_ref.generation) else {
+            return Ok(None);
+        };
+        let parent_dict = match &*fetched {
+            PDFObject::Dictionary(d) => d,
+            _ => break,
+        };
+
+        if let Some(value) = parent_dict.get(key) {
+            return Ok(xref.fetch_if_ref(value).ok());
+        }
+        parent = parent_dict.get("Parent").cloned();
     }
 
-    Ok(LinkAction::Unknown)
+    Ok(None)
 }
 
-/// Parses a GoTo destination.
-fn parse_goto_destination(
-    dest_obj: &PDFObject,
-    _xref: &mut crate::core::XRef,
+/// Reads where a link annotation goes: its action, or its /Dest if it
+/// has no action a viewer follows.
+fn parse_link_action(
+    action: Option<&Action>,
+    dict: &FxHashMap<String, PDFObject>,
+    xref: &mut crate::core::XRef,
 ) -> PDFResult<LinkAction> {
-    match dest_obj {
-        PDFObject::Array(arr) => {
-            if arr.is_empty() {
-                return Ok(LinkAction::Unknown);
-            }
+    if let Some(action) = action {
+        let link = LinkAction::from_action(action)?;
+        if !matches!(link, LinkAction::Unknown) {
+            return Ok(link);
+        }
+    }
 
-            // First element is the page reference
-            let page_ref = &arr[0];
+    // A bare destination is read as a GoTo action
+    match dict.get("Dest") {
+        Some(dest) => match parse_action(dest, xref)? {
+            Some(action) => LinkAction::from_action(&action),
+            None => Ok(LinkAction::Unknown),
+        },
+        None => Ok(LinkAction::Unknown),
+    }
+}
 
-            // Resolve page reference to page index
-            // For now, we'll use a placeholder since we don't have the document context here
-            // In the full implementation, we'd need to pass the document to resolve this
-            return Ok(LinkAction::GoToNamed {
-                name: format!("{:?}", page_ref), // Placeholder
-            });
-        }
-        PDFObject::String(bytes) | PDFObject::HexString(bytes) => {
-            let name = String::from_utf8_lossy(bytes).to_string();
-            Ok(LinkAction::GoToNamed { name })
+/// Splits an explicit destination array into a page and a destination type.
+fn explicit_link_destination(dest: &PDFObject) -> PDFResult<LinkAction> {
+    let arr = match dest {
+        PDFObject::Array(arr) if !arr.is_empty() => arr,
+        _ => return Ok(LinkAction::Unknown),
+    };
+
+    let dest = match arr.get(1).map(|t| &**t) {
+        Some(PDFObject::Name(type_name)) => {
+            crate::core::outline::parse_destination_type(type_name, &arr[2..])?
         }
-        PDFObject::Name(name) => Ok(LinkAction::GoToNamed { name: name.clone() }),
+        _ => crate::core::outline::DestinationType::Fit,
+    };
+
+    // First element is the page reference; some writers use a page
+    // number instead
+    match &*arr[0] {
+        PDFObject::Ref(page_ref) => Ok(LinkAction::GoToPage {
+            page_ref: (page_ref.num, page_ref.generation),
+            dest,
+        }),
+        PDFObject::Number(n) if *n >= 0.0 => Ok(LinkAction::GoTo {
+            page_index: *n as usize,
+            dest,
+        }),
         _ => Ok(LinkAction::Unknown),
     }
 }
@@ -829,6 +1137,60 @@ mod tests {
         assert!(!flags.hidden);
     }
 
+    #[test]
+    fn test_link_action_from_action() {
+        let action = |kind| Action {
+            kind,
+            next: Vec::new(),
+        };
+        let explicit = |first: PDFObject| {
+            ActionDestination::Explicit(PDFObject::Array(
+                vec![
+                    Box::new(first),
+                    Box::new(PDFObject::Name("Fit".to_string())),
+                ]
+                .into(),
+            ))
+        };
+
+        let link = LinkAction::from_action(&action(ActionKind::GoTo {
+            dest: Some(explicit(PDFObject::Ref(crate::core::Ref::new(4, 0)))),
+        }))
+        .unwrap();
+        assert!(matches!(
+            link,
+            LinkAction::GoToPage {
+                page_ref: (4, 0),
+                ..
+            }
+        ));
+
+        let link = LinkAction::from_action(&action(ActionKind::GoTo {
+            dest: Some(explicit(PDFObject::Number(2.0))),
+        }))
+        .unwrap();
+        assert!(matches!(link, LinkAction::GoTo { page_index: 2, .. }));
+
+        let link = LinkAction::from_action(&action(ActionKind::Launch {
+            file: "setup.exe".to_string(),
+            parameters: Some("/quiet".to_string()),
+            new_window: false,
+        }))
+        .unwrap();
+        assert!(matches!(
+            link,
+            LinkAction::Launch { application, parameters: Some(parameters) }
+                if application == "setup.exe" && parameters == "/quiet"
+        ));
+
+        // Actions a viewer doesn't follow
+        let link = LinkAction::from_action(&action(ActionKind::JavaScript {
+            script: "app.alert(1)".to_string(),
+        }))
+        .unwrap();
+        assert!(matches!(link, LinkAction::Unknown));
+    }
+
     #[test]
     fn test_form_field_type() {
         assert_eq!(FormFieldType::Button, FormFieldType::Button);
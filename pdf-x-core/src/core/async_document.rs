@@ -0,0 +1,591 @@
+//! Async document API for progressively loaded PDFs.
+//!
+//! [`AsyncPDFDocument`] wraps a [`PDFDocument`] whose bytes come from an
+//! [`AsyncChunkLoader`]. Every operation first runs synchronously against the
+//! chunks loaded so far. When it reaches a chunk that isn't there, the stream
+//! records the chunk and raises `DataMissing`. The missing chunks are then
+//! awaited and the operation is retried, so no thread ever blocks on I/O.
+//!
+//! Based on PDF.js's `NetworkPdfManager.ensure()` (src/core/pdf_manager.js),
+//! which catches `MissingDataException`, awaits `requestRange()` and retries.
+
+use super::async_http_chunked_stream::AsyncHttpChunkedStream;
+use super::base_stream::BaseStream;
+use super::chunk_manager::ChunkManager;
+use super::document::PDFDocument;
+use super::error::{PDFError, PDFResult};
+use super::open_options::ParseOptions;
+use super::page::Page;
+use std::collections::BTreeSet;
+use std::future::Future;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// An asynchronous source of PDF data, fetched in fixed-size chunks.
+///
+/// This is the async counterpart of [`ChunkLoader`](super::ChunkLoader).
+///
+/// # Example
+/// ```no_run
+/// use pdf_x_core::core::{AsyncChunkLoader, AsyncPDFDocument, PDFResult};
+/// use std::future::Future;
+///
+/// struct MemoryLoader(Vec<u8>);
+///
+/// impl AsyncChunkLoader for MemoryLoader {
+///     fn request_chunk(
+///         &mut self,
+///         chunk_num: usize,
+///     ) -> impl Future<Output = PDFResult<Vec<u8>>> + Send {
+///         let start = chunk_num * 4096;
+///         let chunk = self.0[start..(start + 4096).min(self.0.len())].to_vec();
+///         async move { Ok(chunk) }
+///     }
+///     fn chunk_size(&self) -> usize {
+///         4096
+///     }
+///     fn total_length(&self) -> usize {
+///         self.0.len()
+///     }
+/// }
+/// ```
+pub trait AsyncChunkLoader: Send {
+    /// Fetches a chunk. Only the last chunk may be shorter than `chunk_size`.
+    fn request_chunk(
+        &mut self,
+        chunk_num: usize,
+    ) -> impl Future<Output = PDFResult<Vec<u8>>> + Send;
+
+    /// Returns the chunk size in bytes.
+    fn chunk_size(&self) -> usize;
+
+    /// Returns the total data length in bytes.
+    fn total_length(&self) -> usize;
+}
+
+impl AsyncChunkLoader for AsyncHttpChunkedStream {
+    fn request_chunk(
+        &mut self,
+        chunk_num: usize,
+    ) -> impl Future<Output = PDFResult<Vec<u8>>> + Send {
+        AsyncHttpChunkedStream::request_chunk(self, chunk_num)
+    }
+
+    fn chunk_size(&self) -> usize {
+        AsyncHttpChunkedStream::chunk_size(self)
+    }
+
+    fn total_length(&self) -> usize {
+        self.length()
+    }
+}
+
+/// Chunks loaded so far, plus the chunks reads have asked for in vain.
+struct SharedChunks {
+    manager: ChunkManager,
+    missing: BTreeSet<usize>,
+}
+
+fn lock_chunks(chunks: &Arc<Mutex<SharedChunks>>) -> PDFResult<MutexGuard<'_, SharedChunks>> {
+    chunks.lock().map_err(|_| {
+        PDFError::StreamError("Failed to lock chunk manager (mutex poisoned)".to_string())
+    })
+}
+
+impl SharedChunks {
+    /// Checks that `begin..end` is loaded, recording the chunks that aren't.
+    fn check_range(&mut self, begin: usize, end: usize) -> PDFResult<()> {
+        if begin >= end {
+            return Ok(());
+        }
+        let chunk_size = self.manager.chunk_size();
+        let first_missing = (begin / chunk_size..=(end - 1) / chunk_size)
+            .filter(|&chunk| !self.manager.is_chunk_cached(chunk))
+            .inspect(|&chunk| {
+                self.missing.insert(chunk);
+            })
+            .collect::<Vec<_>>();
+
+        match first_missing.first() {
+            None => Ok(()),
+            Some(&chunk) => {
+                let position = chunk * chunk_size;
+                Err(PDFError::DataMissing {
+                    position,
+                    length: (position + chunk_size).min(self.manager.length()) - position,
+                })
+            }
+        }
+    }
+
+    /// Copies `begin..end`, which must have passed `check_range`.
+    fn copy_range(&self, begin: usize, end: usize) -> Vec<u8> {
+        let chunk_size = self.manager.chunk_size();
+        let mut result = Vec::with_capacity(end - begin);
+        for chunk_num in begin / chunk_size..=(end - 1) / chunk_size {
+            if let Some(chunk) = self.manager.get_chunk(chunk_num) {
+                let chunk_start = chunk_num * chunk_size;
+                let read_start = begin.max(chunk_start) - chunk_start;
+                let read_end = end.min(chunk_start + chunk.len()) - chunk_start;
+                result.extend_from_slice(&chunk[read_start..read_end]);
+            }
+        }
+        result
+    }
+}
+
+/// The synchronous view of the shared chunks that the parser reads from.
+///
+/// Reads never load anything: missing chunks are reported as `DataMissing`
+/// (and remembered, in case the caller swallows the error).
+struct AsyncLoadedStream {
+    chunks: Arc<Mutex<SharedChunks>>,
+    pos: usize,
+    start: usize,
+    total_length: usize,
+}
+
+impl BaseStream for AsyncLoadedStream {
+    fn length(&self) -> usize {
+        self.total_length
+    }
+
+    fn is_empty(&self) -> bool {
+        self.total_length == 0
+    }
+
+    fn pos(&self) -> usize {
+        self.pos
+    }
+
+    fn set_pos(&mut self, pos: usize) -> PDFResult<()> {
+        if pos > self.total_length {
+            return Err(PDFError::InvalidPosition {
+                pos,
+                length: self.total_length,
+            });
+        }
+        self.pos = pos;
+        Ok(())
+    }
+
+    fn is_data_loaded(&self) -> bool {
+        lock_chunks(&self.chunks)
+            .map(|chunks| chunks.manager.is_data_loaded())
+            .unwrap_or(false)
+    }
+
+    fn ensure_range(&mut self, start: usize, length: usize) -> PDFResult<()> {
+        let end = (start + length).min(self.total_length);
+        lock_chunks(&self.chunks)?.check_range(start, end)
+    }
+
+    fn get_byte(&mut self) -> PDFResult<u8> {
+        if self.pos >= self.total_length {
+            return Err(PDFError::UnexpectedEndOfStream);
+        }
+
+        let mut chunks = lock_chunks(&self.chunks)?;
+        chunks.check_range(self.pos, self.pos + 1)?;
+        let byte = chunks.manager.get_byte_from_cache(self.pos)?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    fn get_bytes(&mut self, length: usize) -> PDFResult<Vec<u8>> {
+        let end_pos = std::cmp::min(self.pos + length, self.total_length);
+        if end_pos <= self.pos {
+            return Ok(Vec::new());
+        }
+
+        let mut chunks = lock_chunks(&self.chunks)?;
+        chunks.check_range(self.pos, end_pos)?;
+        let result = chunks.copy_range(self.pos, end_pos);
+        self.pos = end_pos;
+        Ok(result)
+    }
+
+    fn get_byte_range(&self, begin: usize, end: usize) -> PDFResult<Vec<u8>> {
+        if begin >= end || end > self.total_length {
+            return Err(PDFError::InvalidByteRange { begin, end });
+        }
+
+        let mut chunks = lock_chunks(&self.chunks)?;
+        chunks.check_range(begin, end)?;
+        Ok(chunks.copy_range(begin, end))
+    }
+
+    fn reset(&mut self) -> PDFResult<()> {
+        self.pos = self.start;
+        Ok(())
+    }
+
+    fn move_start(&mut self) -> PDFResult<()> {
+        if self.pos > self.start {
+            self.start = self.pos;
+        }
+        Ok(())
+    }
+
+    fn make_sub_stream(&self, start: usize, length: usize) -> PDFResult<Box<dyn BaseStream>> {
+        if start + length > self.total_length {
+            return Err(PDFError::InvalidByteRange {
+                begin: start,
+                end: start + length,
+            });
+        }
+
+        let new_stream = AsyncLoadedStream {
+            chunks: Arc::clone(&self.chunks),
+            pos: 0,
+            start: 0,
+            total_length: self.total_length,
+        };
+        let sub = super::sub_stream::SubStream::new(Box::new(new_stream), start, length)?;
+        Ok(Box::new(sub))
+    }
+}
+
+/// Fetches the chunks that synchronous operations found missing.
+struct ChunkFetcher<L: AsyncChunkLoader> {
+    loader: L,
+    chunks: Arc<Mutex<SharedChunks>>,
+    chunk_size: usize,
+    total_length: usize,
+}
+
+impl<L: AsyncChunkLoader> ChunkFetcher<L> {
+    /// Loads every chunk of `begin..end` that isn't loaded yet.
+    async fn load_range(&mut self, begin: usize, end: usize) -> PDFResult<()> {
+        if begin >= end {
+            return Ok(());
+        }
+        for chunk_num in begin / self.chunk_size..=(end - 1) / self.chunk_size {
+            self.load_chunk(chunk_num).await?;
+        }
+        Ok(())
+    }
+
+    async fn load_chunk(&mut self, chunk_num: usize) -> PDFResult<()> {
+        if lock_chunks(&self.chunks)?
+            .manager
+            .is_chunk_cached(chunk_num)
+        {
+            return Ok(());
+        }
+
+        let data = self.loader.request_chunk(chunk_num).await?;
+
+        let chunk_start = chunk_num * self.chunk_size;
+        let expected = std::cmp::min(chunk_start + self.chunk_size, self.total_length)
+            .saturating_sub(chunk_start);
+        if data.len() != expected {
+            return Err(PDFError::StreamError(format!(
+                "Chunk loader returned {} bytes for chunk {} (expected {})",
+                data.len(),
+                chunk_num,
+                expected
+            )));
+        }
+        lock_chunks(&self.chunks)?
+            .manager
+            .on_receive_data(chunk_num, data)
+    }
+
+    /// Runs `operation` until it completes without hitting missing data.
+    ///
+    /// An operation may swallow a `DataMissing` error (a broken font is only
+    /// a warning, for example) and still succeed with degraded output, so it
+    /// is retried whenever a read came up empty, not only when it fails.
+    /// Each retry loads at least one new chunk and loaded chunks are never
+    /// evicted, so this terminates.
+    async fn retry<T>(&mut self, mut operation: impl FnMut() -> PDFResult<T>) -> PDFResult<T> {
+        loop {
+            let result = operation();
+
+            let missing = std::mem::take(&mut lock_chunks(&self.chunks)?.missing);
+            if missing.is_empty() {
+                return result;
+            }
+            for chunk_num in missing {
+                self.load_chunk(chunk_num).await?;
+            }
+        }
+    }
+}
+
+/// A PDF document with an async API, loaded on demand from an
+/// [`AsyncChunkLoader`].
+///
+/// Chunks are fetched as operations need them and kept for the lifetime of
+/// the document, so memory use grows with the part of the file that has
+/// been read.
+///
+/// Operations must be free of side effects outside the document, since an
+/// operation that hits missing data is run again once the data is loaded.
+///
+/// # Example
+/// ```no_run
+/// use pdf_x_core::core::AsyncPDFDocument;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let mut doc =
+///         AsyncPDFDocument::open_url("https://example.com/document.pdf", None).await?;
+///     println!("Pages: {}", doc.page_count().await?);
+///     println!("{}", doc.extract_text(0).await?);
+///     Ok(())
+/// }
+/// ```
+pub struct AsyncPDFDocument<L: AsyncChunkLoader> {
+    doc: PDFDocument,
+    fetcher: ChunkFetcher<L>,
+}
+
+impl AsyncPDFDocument<AsyncHttpChunkedStream> {
+    /// Opens a PDF document over HTTP using range requests.
+    ///
+    /// # Arguments
+    /// * `url` - URL of the PDF file
+    /// * `chunk_size` - Size of each range request (default: 64KB)
+    pub async fn open_url(url: impl Into<String>, chunk_size: Option<usize>) -> PDFResult<Self> {
+        let stream = AsyncHttpChunkedStream::open(url, chunk_size, None, None).await?;
+        Self::open(stream).await
+    }
+}
+
+impl<L: AsyncChunkLoader> AsyncPDFDocument<L> {
+    /// Opens a PDF document, fetching only the chunks needed to read the
+    /// cross-reference table and the catalog.
+    pub async fn open(loader: L) -> PDFResult<Self> {
+        let chunk_size = loader.chunk_size();
+        if chunk_size == 0 {
+            return Err(PDFError::StreamError(
+                "Chunk loader reported a chunk size of 0".to_string(),
+            ));
+        }
+        let total_length = loader.total_length();
+        let num_chunks = total_length.div_ceil(chunk_size);
+        let chunks = Arc::new(Mutex::new(SharedChunks {
+            manager: ChunkManager::new(total_length, Some(chunk_size), Some(num_chunks.max(1))),
+            missing: BTreeSet::new(),
+        }));
+        let mut fetcher = ChunkFetcher {
+            loader,
+            chunks: Arc::clone(&chunks),
+            chunk_size,
+            total_length,
+        };
+
+        // startxref is in the last 1024 bytes
+        fetcher
+            .load_range(total_length.saturating_sub(1024), total_length)
+            .await?;
+
+        let doc = fetcher
+            .retry(|| {
+                let stream = AsyncLoadedStream {
+                    chunks: Arc::clone(&chunks),
+                    pos: 0,
+                    start: 0,
+                    total_length,
+                };
+                PDFDocument::open_chunked(Box::new(stream), chunk_size, ParseOptions::default())
+            })
+            .await?;
+
+        Ok(AsyncPDFDocument { doc, fetcher })
+    }
+
+    /// Runs a synchronous operation on the document, awaiting whatever data
+    /// it turns out to need.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use pdf_x_core::core::{AsyncChunkLoader, AsyncPDFDocument, PDFResult};
+    /// # async fn example<L: AsyncChunkLoader>(doc: &mut AsyncPDFDocument<L>) -> PDFResult<()> {
+    /// let outline = doc.run(|doc| doc.document_outline_items()).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn run<T>(
+        &mut self,
+        mut operation: impl FnMut(&mut PDFDocument) -> PDFResult<T>,
+    ) -> PDFResult<T> {
+        let doc = &mut self.doc;
+        self.fetcher.retry(|| operation(doc)).await
+    }
+
+    /// Returns the number of pages.
+    pub async fn page_count(&mut self) -> PDFResult<u32> {
+        self.run(|doc| doc.page_count()).await
+    }
+
+    /// Returns the page at `page_index` (0-based).
+    pub async fn get_page(&mut self, page_index: usize) -> PDFResult<Page> {
+        self.run(|doc| doc.get_page(page_index)).await
+    }
+
+    /// Extracts the text of a page in layout order.
+    pub async fn extract_text(&mut self, page_index: usize) -> PDFResult<String> {
+        self.run(|doc| doc.extract_text_from_page_as_string(page_index))
+            .await
+    }
+
+    /// Renders a page to an SVG document.
+    pub async fn render_page_to_svg(
+        &mut self,
+        page_index: usize,
+        options: impl Into<crate::rendering::RenderOptions>,
+    ) -> PDFResult<String> {
+        let options = options.into();
+        self.run(|doc| doc.render_page_to_svg(page_index, options.clone()))
+            .await
+    }
+
+    /// Renders a page to RGBA pixels.
+    ///
+    /// Returns `(width, height, pixels)` as `PDFDocument::render_page_to_image`.
+    #[cfg(feature = "rendering")]
+    pub async fn render_page_to_image(
+        &mut self,
+        page_index: usize,
+        options: impl Into<crate::rendering::RenderOptions>,
+    ) -> PDFResult<(u32, u32, Vec<u8>)> {
+        let options = options.into();
+        self.run(|doc| doc.render_page_to_image(page_index, options.clone()))
+            .await
+    }
+
+    /// Loads the given byte range now, ahead of the operations that need it.
+    pub async fn preload_range(&mut self, begin: usize, end: usize) -> PDFResult<()> {
+        let end = end.min(self.fetcher.total_length);
+        self.fetcher.load_range(begin, end).await
+    }
+
+    /// Returns the underlying document, for operations that don't read data
+    /// (or whose data is known to be loaded).
+    pub fn document(&self) -> &PDFDocument {
+        &self.doc
+    }
+
+    /// Returns the underlying document mutably.
+    ///
+    /// Reads through it fail with `DataMissing` where data isn't loaded yet;
+    /// use [`run`](Self::run) to have it loaded.
+    pub fn document_mut(&mut self) -> &mut PDFDocument {
+        &mut self.doc
+    }
+
+    /// Returns the number of chunks fetched so far.
+    pub fn num_chunks_loaded(&self) -> usize {
+        lock_chunks(&self.fetcher.chunks)
+            .map(|chunks| chunks.manager.num_chunks_loaded())
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// In-memory loader that counts requests and yields before answering.
+    struct SlowLoader {
+        data: Vec<u8>,
+        chunk_size: usize,
+        requests: Arc<Mutex<Vec<usize>>>,
+    }
+
+    impl AsyncChunkLoader for SlowLoader {
+        fn request_chunk(
+            &mut self,
+            chunk_num: usize,
+        ) -> impl Future<Output = PDFResult<Vec<u8>>> + Send {
+            self.requests.lock().unwrap().push(chunk_num);
+            let start = chunk_num * self.chunk_size;
+            let end = (start + self.chunk_size).min(self.data.len());
+            let chunk = self.data[start..end].to_vec();
+            async move {
+                tokio::task::yield_now().await;
+                Ok(chunk)
+            }
+        }
+
+        fn chunk_size(&self) -> usize {
+            self.chunk_size
+        }
+
+        fn total_length(&self) -> usize {
+            self.data.len()
+        }
+    }
+
+    fn build_pdf(text: &str) -> Vec<u8> {
+        let content = format!("BT /F1 12 Tf 72 720 Td ({}) Tj ET", text);
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] \
+             /Resources << /Font << /F1 5 0 R >> >> /Contents 4 0 R >>"
+                .to_string(),
+            format!(
+                "<< /Length {} >>\nstream\n{}\nendstream",
+                content.len(),
+                content
+            ),
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>".to_string(),
+        ];
+
+        // Spread the objects over many chunks
+        let padded: Vec<String> = objects
+            .iter()
+            .map(|object| format!("{}\n%{}", object, "x".repeat(2000)))
+            .collect();
+        crate::test_support::build_pdf(&padded)
+    }
+
+    #[tokio::test]
+    async fn test_async_document_loads_on_demand() {
+        let data = build_pdf("Hello async");
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let loader = SlowLoader {
+            data,
+            chunk_size: 64,
+            requests: Arc::clone(&requests),
+        };
+
+        let mut doc = AsyncPDFDocument::open(loader).await.unwrap();
+        let loaded_after_open = doc.num_chunks_loaded();
+        assert!(loaded_after_open < doc.fetcher.total_length.div_ceil(64));
+
+        assert_eq!(doc.page_count().await.unwrap(), 1);
+        let text = doc.extract_text(0).await.unwrap();
+        assert!(text.contains("Hello async"), "got {:?}", text);
+
+        // Every chunk was requested once at most
+        let mut requested = requests.lock().unwrap().clone();
+        let count = requested.len();
+        requested.sort_unstable();
+        requested.dedup();
+        assert_eq!(requested.len(), count);
+    }
+
+    #[tokio::test]
+    async fn test_async_document_retries_swallowed_missing_data() {
+        let loader = SlowLoader {
+            data: build_pdf("Hello"),
+            chunk_size: 64,
+            requests: Arc::new(Mutex::new(Vec::new())),
+        };
+        let mut doc = AsyncPDFDocument::open(loader).await.unwrap();
+
+        // The page tree isn't loaded yet, so a synchronous read fails
+        assert!(matches!(
+            doc.document_mut().page_count(),
+            Err(PDFError::DataMissing { .. })
+        ));
+
+        // An operation that turns the error into a fallback is still rerun
+        let count = doc.run(|doc| Ok(doc.page_count().ok())).await.unwrap();
+        assert_eq!(count, Some(1));
+    }
+}
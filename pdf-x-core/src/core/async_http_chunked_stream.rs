@@ -6,10 +6,12 @@
 //! Based on PDF.js's network stream approach with HTTP range requests.
 
 #[cfg(feature = "async")]
-use super::chunk_manager::ChunkManager;
+use super::chunk_manager::{ChunkManager, ChunkedStreamOptions};
 #[cfg(feature = "async")]
 use super::error::{PDFError, PDFResult};
 #[cfg(feature = "async")]
+use std::ops::Range;
+#[cfg(feature = "async")]
 use std::sync::{Arc, Mutex};
 
 #[cfg(feature = "async")]
@@ -64,6 +66,7 @@ pub type ProgressCallback = Box<dyn Fn(usize, usize) + Send + Sync>;
 /// }
 /// ```
 #[cfg(feature = "async")]
+#[derive(Clone)]
 pub struct AsyncHttpChunkedStream {
     /// URL of the PDF file
     url: String,
@@ -127,6 +130,29 @@ impl AsyncHttpChunkedStream {
         chunk_size: Option<usize>,
         max_cached_chunks: Option<usize>,
         progress_callback: Option<ProgressCallback>,
+    ) -> PDFResult<Self> {
+        Self::open_with_options(
+            url,
+            ChunkedStreamOptions::from_chunk_counts(chunk_size, max_cached_chunks),
+            progress_callback,
+        )
+        .await
+    }
+
+    /// Creates a new AsyncHttpChunkedStream with the given chunking,
+    /// readahead and cache settings.
+    ///
+    /// With readahead, a sequential read fetches the missing chunk and the
+    /// ones after it in a single range request.
+    ///
+    /// # Arguments
+    /// * `url` - URL of the PDF file
+    /// * `options` - Chunk size, readahead and cache bound
+    /// * `progress_callback` - Optional callback for download progress
+    pub async fn open_with_options(
+        url: impl Into<String>,
+        options: ChunkedStreamOptions,
+        progress_callback: Option<ProgressCallback>,
     ) -> PDFResult<Self> {
         let url = url.into();
 
@@ -167,7 +193,7 @@ impl AsyncHttpChunkedStream {
             .parse()
             .map_err(|_| PDFError::StreamError("Invalid Content-Length value".to_string()))?;
 
-        let manager = ChunkManager::new(length, chunk_size, max_cached_chunks);
+        let manager = ChunkManager::with_options(length, &options);
 
         // Cache immutable values
         let cached_chunk_size = manager.chunk_size();
@@ -189,9 +215,15 @@ impl AsyncHttpChunkedStream {
     /// Requests a specific chunk from the server via HTTP range request.
     ///
     /// This is an async operation that downloads the chunk data.
-    async fn request_chunk(&self, chunk_num: usize) -> PDFResult<Vec<u8>> {
-        let chunk_start = chunk_num * self.chunk_size;
-        let chunk_end = std::cmp::min(chunk_start + self.chunk_size, self.total_length) - 1;
+    pub(crate) async fn request_chunk(&self, chunk_num: usize) -> PDFResult<Vec<u8>> {
+        self.request_chunks(chunk_num..chunk_num + 1).await
+    }
+
+    /// Requests a run of consecutive chunks with a single range request.
+    async fn request_chunks(&self, chunks: Range<usize>) -> PDFResult<Vec<u8>> {
+        let chunk_num = chunks.start;
+        let chunk_start = chunks.start * self.chunk_size;
+        let chunk_end = std::cmp::min(chunks.end * self.chunk_size, self.total_length) - 1;
 
         let range_header = format!("bytes={}-{}", chunk_start, chunk_end);
 
@@ -233,23 +265,23 @@ impl AsyncHttpChunkedStream {
 
     /// Ensures a chunk is loaded into the manager.
     ///
-    /// If not already loaded, requests the chunk and sends it to the manager.
+    /// If it isn't cached (never loaded, or evicted since), the chunk is
+    /// downloaded along with any readahead the manager asks for.
     pub async fn ensure_chunk_loaded(&self, chunk_num: usize) -> PDFResult<()> {
-        {
-            let manager = self.manager.read().await;
-            if manager.has_chunk(chunk_num) {
-                drop(manager);
-                let mut manager = self.manager.write().await;
+        let chunks = {
+            let mut manager = self.manager.write().await;
+            if manager.is_chunk_cached(chunk_num) {
                 manager.mark_chunk_accessed(chunk_num);
                 return Ok(());
             }
-        }
+            manager.load_range_for(chunk_num)
+        };
 
-        // Chunk not loaded, download it
-        let data = self.request_chunk(chunk_num).await?;
+        // Chunk not cached, download it
+        let data = self.request_chunks(chunks.clone()).await?;
 
         let mut manager = self.manager.write().await;
-        manager.on_receive_data(chunk_num, data)?;
+        manager.on_receive_range(chunks.start, data)?;
 
         Ok(())
     }
@@ -288,6 +320,11 @@ impl AsyncHttpChunkedStream {
         self.total_length
     }
 
+    /// Returns the chunk size in bytes.
+    pub fn chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+
     /// Returns the current read position.
     pub fn pos(&self) -> usize {
         self.pos
@@ -0,0 +1,826 @@
+//! Embedded files and associated files.
+//!
+//! Files can be embedded in a PDF through the /EmbeddedFiles name tree of the
+//! catalog's /Names dictionary, and PDF 2.0 (and PDF/A-3) lets a document
+//! declare some of them as associated files (/AF) together with their
+//! relationship to the document (/AFRelationship). Electronic invoices
+//! (Factur-X, ZUGFeRD, XRechnung) use this to ship the machine-readable XML
+//! invoice next to the human-readable pages.
+//!
+//! Based on PDF.js src/core/file_spec.js.
+
+use super::decode::encode_flate;
+use super::delta::{BaseObjectFetcher, Command, DeltaLayer};
+use super::error::{PDFError, PDFResult};
+use super::form::encode_text_string;
+use super::metadata::PDFDate;
+use super::name_number_tree::raw_name_tree_entries;
+use super::outline::decode_pdf_string;
+use super::parser::{PDFObject, Ref};
+use super::xref::XRef;
+use smallvec::SmallVec;
+use std::collections::{BTreeMap, HashMap};
+use std::rc::Rc;
+
+/// Relationship of an associated file to the document (/AFRelationship).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AFRelationship {
+    /// The original source material of the content (/Source)
+    Source,
+
+    /// Information used to derive a visual presentation, such as a table or
+    /// graph (/Data)
+    Data,
+
+    /// An alternative representation of the content, such as an e-invoice
+    /// (/Alternative)
+    Alternative,
+
+    /// A supplemental representation of the original source or data
+    /// (/Supplement)
+    Supplement,
+
+    /// An encrypted payload document (/EncryptedPayload)
+    EncryptedPayload,
+
+    /// Data associated with an AcroForm (/FormData)
+    FormData,
+
+    /// A schema definition (/Schema)
+    Schema,
+
+    /// The relationship is not known or cannot be described (/Unspecified)
+    Unspecified,
+
+    /// A second-class name not defined by the specification
+    Other(String),
+}
+
+impl AFRelationship {
+    /// Returns the /AFRelationship name.
+    pub fn name(&self) -> &str {
+        match self {
+            AFRelationship::Source => "Source",
+            AFRelationship::Data => "Data",
+            AFRelationship::Alternative => "Alternative",
+            AFRelationship::Supplement => "Supplement",
+            AFRelationship::EncryptedPayload => "EncryptedPayload",
+            AFRelationship::FormData => "FormData",
+            AFRelationship::Schema => "Schema",
+            AFRelationship::Unspecified => "Unspecified",
+            AFRelationship::Other(name) => name,
+        }
+    }
+
+    /// Parses an /AFRelationship name.
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "Source" => AFRelationship::Source,
+            "Data" => AFRelationship::Data,
+            "Alternative" => AFRelationship::Alternative,
+            "Supplement" => AFRelationship::Supplement,
+            "EncryptedPayload" => AFRelationship::EncryptedPayload,
+            "FormData" => AFRelationship::FormData,
+            "Schema" => AFRelationship::Schema,
+            "Unspecified" => AFRelationship::Unspecified,
+            other => AFRelationship::Other(other.to_string()),
+        }
+    }
+}
+
+/// A file embedded in the document.
+///
+/// Returned by `PDFDocument::attachments` and by file attachment
+/// annotations without reading the file: the contents are decoded on
+/// request with [`data`](Self::data), so listing the attachments of a large
+/// document stays cheap. Attachments made with [`new`](Self::new) hold
+/// their contents.
+///
+/// # Example
+/// ```no_run
+/// use pdf_x_core::core::PDFDocument;
+///
+/// let mut doc = PDFDocument::open_file("invoice.pdf", None, None).unwrap();
+/// for file in doc.attachments().unwrap() {
+///     println!("{} ({:?} bytes, {:?})", file.name, file.size, file.mime_type);
+///     if file.name.ends_with(".xml") {
+///         let xml = file.data(doc.xref_mut()).unwrap();
+///         std::fs::write(&file.name, xml).unwrap();
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attachment {
+    /// File name (/UF, falling back to /F)
+    pub name: String,
+
+    /// Description of the file (/Desc)
+    pub description: Option<String>,
+
+    /// MIME type of the embedded file stream (/Subtype), e.g. "text/xml"
+    pub mime_type: Option<String>,
+
+    /// Uncompressed size in bytes, if the writer recorded it (/Params /Size)
+    pub size: Option<usize>,
+
+    /// When the file was created (/Params /CreationDate)
+    pub creation_date: Option<PDFDate>,
+
+    /// When the file was last modified (/Params /ModDate)
+    pub modification_date: Option<PDFDate>,
+
+    /// Relationship to the document, if given (/AFRelationship)
+    pub relationship: Option<AFRelationship>,
+
+    /// Whether the file is listed in the /AF array of the catalog or a page
+    pub associated: bool,
+
+    /// Where the file contents are
+    contents: Contents,
+}
+
+/// Contents of an [`Attachment`].
+#[derive(Debug, Clone, PartialEq)]
+enum Contents {
+    /// The embedded file stream, usually a reference to it, not yet decoded
+    Stream(PDFObject),
+
+    /// Decoded file contents
+    Data(Vec<u8>),
+}
+
+impl Attachment {
+    /// Reads a file specification dictionary with an embedded file, without
+    /// decoding the file.
+    ///
+    /// Returns `None` if `file_spec` is not a dictionary or doesn't embed a
+    /// file stream (/EF); external file references can't be extracted.
+    ///
+    /// # Arguments
+    /// * `xref` - Cross-reference table for resolving indirect objects
+    /// * `file_spec` - The file specification (may be a reference)
+    /// * `associated` - Whether the file is referenced from an /AF array
+    pub fn from_file_spec(
+        xref: &mut XRef,
+        file_spec: &PDFObject,
+        associated: bool,
+    ) -> PDFResult<Option<Self>> {
+        let dict = match xref.fetch_if_ref(file_spec)? {
+            PDFObject::Dictionary(dict) => dict,
+            _ => return Ok(None),
+        };
+
+        let embedded = match dict.get("EF").map(|ef| xref.fetch_if_ref(ef)).transpose()? {
+            Some(PDFObject::Dictionary(ef)) => ef,
+            _ => return Ok(None),
+        };
+        let stream = match embedded.get("UF").or_else(|| embedded.get("F")) {
+            Some(stream) => stream.clone(),
+            None => return Ok(None),
+        };
+        let stream_dict = match xref.fetch_if_ref(&stream)? {
+            PDFObject::Stream { dict, .. } => dict,
+            _ => return Ok(None),
+        };
+
+        let text = |dict: &HashMap<String, PDFObject>, key: &str| match dict.get(key) {
+            Some(PDFObject::String(bytes)) | Some(PDFObject::HexString(bytes)) => {
+                Some(decode_pdf_string(bytes))
+            }
+            _ => None,
+        };
+        let name = text(&dict, "UF")
+            .or_else(|| text(&dict, "F"))
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| "attachment".to_string());
+        let relationship = match dict.get("AFRelationship") {
+            Some(PDFObject::Name(name)) => Some(AFRelationship::from_name(name)),
+            _ => None,
+        };
+        let mime_type = match stream_dict.get("Subtype") {
+            Some(PDFObject::Name(subtype)) => Some(subtype.clone()),
+            _ => None,
+        };
+
+        let params = match stream_dict.get("Params").map(|p| xref.fetch_if_ref(p)) {
+            Some(Ok(PDFObject::Dictionary(params))) => params,
+            _ => HashMap::new(),
+        };
+        let size = match params.get("Size") {
+            Some(PDFObject::Number(size)) if *size >= 0.0 => Some(*size as usize),
+            _ => None,
+        };
+        let date = |key: &str| text(&params, key).and_then(|date| PDFDate::parse(&date));
+
+        Ok(Some(Attachment {
+            name,
+            description: text(&dict, "Desc"),
+            mime_type,
+            size,
+            creation_date: date("CreationDate"),
+            modification_date: date("ModDate"),
+            relationship,
+            associated,
+            contents: Contents::Stream(stream),
+        }))
+    }
+
+    /// Returns the file contents, decoding the embedded file stream if it
+    /// hasn't been.
+    ///
+    /// # Arguments
+    /// * `xref` - Cross-reference table of the document the file came from
+    pub fn data(&self, xref: &mut XRef) -> PDFResult<Vec<u8>> {
+        match &self.contents {
+            Contents::Stream(stream) => Ok(Rc::unwrap_or_clone(xref.decoded_stream(stream)?)),
+            Contents::Data(data) => Ok(data.clone()),
+        }
+    }
+
+    /// Decodes the file contents and keeps them with the attachment, so that
+    /// [`text`](Self::text) can return them.
+    ///
+    /// # Arguments
+    /// * `xref` - Cross-reference table of the document the file came from
+    pub fn load(self, xref: &mut XRef) -> PDFResult<Self> {
+        let data = self.data(xref)?;
+        Ok(Attachment {
+            contents: Contents::Data(data),
+            ..self
+        })
+    }
+
+    /// Creates an attachment to be written with
+    /// [`PDFDocument::attach_file`](super::PDFDocument::attach_file).
+    ///
+    /// # Arguments
+    /// * `name` - File name shown by viewers
+    /// * `data` - File contents
+    /// * `relationship` - Relationship to the document or page
+    pub fn new(name: impl Into<String>, data: Vec<u8>, relationship: AFRelationship) -> Self {
+        Attachment {
+            name: name.into(),
+            description: None,
+            mime_type: None,
+            size: Some(data.len()),
+            creation_date: None,
+            modification_date: None,
+            relationship: Some(relationship),
+            associated: false,
+            contents: Contents::Data(data),
+        }
+    }
+
+    /// Returns the file contents as text, if they were loaded (see
+    /// [`load`](Self::load)) and are valid UTF-8.
+    pub fn text(&self) -> Option<&str> {
+        match &self.contents {
+            Contents::Data(data) => std::str::from_utf8(data).ok(),
+            Contents::Stream(_) => None,
+        }
+    }
+
+    /// Builds the embedded file stream.
+    fn embedded_file_stream(&self) -> PDFResult<PDFObject> {
+        let Contents::Data(data) = &self.contents else {
+            return Err(PDFError::Generic(format!(
+                "Attachment '{}' isn't loaded; call Attachment::load before attaching it",
+                self.name
+            )));
+        };
+        let mut params = HashMap::new();
+        params.insert("Size".to_string(), PDFObject::Number(data.len() as f64));
+        for (key, date) in [
+            ("CreationDate", self.creation_date),
+            ("ModDate", self.modification_date),
+        ] {
+            if let Some(date) = date {
+                params.insert(
+                    key.to_string(),
+                    PDFObject::String(date.to_pdf_string().into_bytes()),
+                );
+            }
+        }
+
+        let mut dict = HashMap::new();
+        dict.insert(
+            "Type".to_string(),
+            PDFObject::Name("EmbeddedFile".to_string()),
+        );
+        if let Some(mime_type) = &self.mime_type {
+            dict.insert("Subtype".to_string(), PDFObject::Name(mime_type.clone()));
+        }
+        dict.insert("Params".to_string(), PDFObject::Dictionary(params));
+        dict.insert(
+            "Filter".to_string(),
+            PDFObject::Name("FlateDecode".to_string()),
+        );
+        Ok(PDFObject::Stream {
+            dict,
+            data: encode_flate(data)?,
+        })
+    }
+
+    /// Builds the file specification dictionary pointing at `stream`.
+    fn file_spec(&self, stream: Ref) -> PDFObject {
+        let name = encode_text_string(&self.name);
+        let mut embedded = HashMap::new();
+        embedded.insert("F".to_string(), PDFObject::Ref(stream));
+        embedded.insert("UF".to_string(), PDFObject::Ref(stream));
+
+        let mut dict = HashMap::new();
+        dict.insert("Type".to_string(), PDFObject::Name("Filespec".to_string()));
+        dict.insert("F".to_string(), PDFObject::String(name.clone()));
+        dict.insert("UF".to_string(), PDFObject::String(name));
+        if let Some(description) = &self.description {
+            dict.insert(
+                "Desc".to_string(),
+                PDFObject::String(encode_text_string(description)),
+            );
+        }
+        let relationship = self
+            .relationship
+            .as_ref()
+            .map_or("Unspecified", AFRelationship::name);
+        dict.insert(
+            "AFRelationship".to_string(),
+            PDFObject::Name(relationship.to_string()),
+        );
+        dict.insert("EF".to_string(), PDFObject::Dictionary(embedded));
+        PDFObject::Dictionary(dict)
+    }
+}
+
+/// Electronic invoice standard of an embedded invoice.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EInvoiceStandard {
+    /// Factur-X, or ZUGFeRD 2.1 and later which share its file name
+    FacturX,
+
+    /// ZUGFeRD 1.0 and 2.0
+    ZUGFeRD,
+
+    /// XRechnung (German public sector CII or UBL invoice)
+    XRechnung,
+}
+
+impl EInvoiceStandard {
+    /// Recognises a standard from the name of the embedded XML file.
+    pub fn from_file_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "factur-x.xml" => Some(EInvoiceStandard::FacturX),
+            "zugferd-invoice.xml" => Some(EInvoiceStandard::ZUGFeRD),
+            "xrechnung.xml" => Some(EInvoiceStandard::XRechnung),
+            _ => None,
+        }
+    }
+}
+
+/// An electronic invoice embedded in the document.
+///
+/// # Example
+/// ```no_run
+/// use pdf_x_core::core::PDFDocument;
+///
+/// let mut doc = PDFDocument::open_file("invoice.pdf", None, None).unwrap();
+/// if let Some(invoice) = doc.e_invoice().unwrap() {
+///     println!("{:?} invoice ({:?})", invoice.standard, invoice.conformance_level);
+///     println!("{}", invoice.xml().unwrap_or_default());
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct EInvoice {
+    /// The invoice standard
+    pub standard: EInvoiceStandard,
+
+    /// Profile declared in the XMP metadata, e.g. "EN 16931" or "BASIC"
+    pub conformance_level: Option<String>,
+
+    /// Version of the standard declared in the XMP metadata
+    pub version: Option<String>,
+
+    /// The embedded XML file; `PDFDocument::e_invoice` returns it loaded
+    pub attachment: Attachment,
+}
+
+impl EInvoice {
+    /// Finds the invoice among the attachments of a document.
+    ///
+    /// The XML file is located by the name declared in the XMP metadata
+    /// (`fx:DocumentFileName`, `zf:DocumentFileName`, ...) or, failing that,
+    /// by the well-known file names of each standard. Files declared as
+    /// associated files are preferred over plain embedded files.
+    ///
+    /// # Arguments
+    /// * `attachments` - The document's attachments
+    /// * `xmp` - XMP properties, as in [`DocumentMetadata::xmp`](super::DocumentMetadata::xmp)
+    pub fn detect(attachments: &[Attachment], xmp: &BTreeMap<String, String>) -> Option<Self> {
+        let property = |name: &str| {
+            xmp.iter()
+                .find(|(key, _)| key.rsplit(':').next() == Some(name))
+                .map(|(_, value)| value.clone())
+        };
+        let declared_name = property("DocumentFileName");
+
+        let candidates = attachments.iter().filter_map(|attachment| {
+            let declared = declared_name
+                .as_deref()
+                .is_some_and(|name| name.eq_ignore_ascii_case(&attachment.name));
+            let standard = EInvoiceStandard::from_file_name(&attachment.name);
+            if !declared && standard.is_none() {
+                return None;
+            }
+            // ZUGFeRD 2.1 renamed its file to factur-x.xml, so a declared
+            // name that isn't well-known is assumed to be Factur-X
+            Some((attachment, standard.unwrap_or(EInvoiceStandard::FacturX)))
+        });
+        let (attachment, standard) = candidates.max_by_key(|(attachment, _)| {
+            (attachment.associated, attachment.relationship.is_some())
+        })?;
+
+        Some(EInvoice {
+            standard,
+            conformance_level: property("ConformanceLevel"),
+            version: property("Version"),
+            attachment: attachment.clone(),
+        })
+    }
+
+    /// Returns the invoice XML, if the attachment is loaded and the XML is
+    /// valid UTF-8.
+    pub fn xml(&self) -> Option<&str> {
+        self.attachment.text()
+    }
+}
+
+/// Command that embeds a file and declares it as an associated file of the
+/// document or of a page.
+///
+/// The file specification is appended to the /AF array of the target and
+/// listed in the /EmbeddedFiles name tree, so viewers without /AF support
+/// still show it. The catalog, the page and the name tree are read when the
+/// command runs; the catalog's /Names dictionary and the name tree are
+/// written back inline and flattened.
+pub(crate) struct AttachFileCommand {
+    /// The document catalog
+    catalog_ref: Ref,
+
+    /// The page the file is associated with, if not the document
+    page_ref: Option<Ref>,
+
+    /// The file to embed
+    attachment: Attachment,
+
+    /// How deeply the /EmbeddedFiles name tree may nest
+    max_tree_depth: usize,
+
+    /// Delta entries of the catalog and page before the command ran (for
+    /// undo)
+    originals: Vec<Option<PDFObject>>,
+
+    /// Catalog and page as written by the command (for redo)
+    written: Vec<PDFObject>,
+
+    /// The objects that were added, with their references
+    added: Vec<(Ref, PDFObject)>,
+}
+
+impl AttachFileCommand {
+    /// Create a new AttachFileCommand.
+    ///
+    /// # Arguments
+    /// * `catalog_ref` - The document catalog
+    /// * `page_ref` - Target page, or `None` to associate the file with the
+    ///   document
+    /// * `attachment` - The file to embed
+    /// * `max_tree_depth` - How deeply the /EmbeddedFiles name tree may nest
+    ///   (the document's `max_object_depth`)
+    pub(crate) fn new(
+        catalog_ref: Ref,
+        page_ref: Option<Ref>,
+        attachment: Attachment,
+        max_tree_depth: usize,
+    ) -> Self {
+        Self {
+            catalog_ref,
+            page_ref,
+            attachment,
+            max_tree_depth,
+            originals: Vec::new(),
+            written: Vec::new(),
+            added: Vec::new(),
+        }
+    }
+
+    /// Returns the catalog and, if any, the page reference.
+    fn targets(&self) -> Vec<Ref> {
+        std::iter::once(self.catalog_ref)
+            .chain(self.page_ref)
+            .collect()
+    }
+
+    /// Adds the new objects and updates the catalog and page.
+    fn apply<'a>(
+        &mut self,
+        delta: &mut DeltaLayer,
+        fetch_base: Option<&'a BaseObjectFetcher<'a>>,
+    ) -> PDFResult<()> {
+        let mut resolve = |obj: &PDFObject| -> PDFResult<PDFObject> {
+            match obj {
+                PDFObject::Ref(r) => match delta.get(r) {
+                    Some(delta_obj) => Ok(delta_obj.object.clone()),
+                    None => {
+                        let fetcher = fetch_base.ok_or_else(|| {
+                            PDFError::Generic(
+                                "Cannot fetch base object - no fetch callback provided. \
+                                Execute commands through PDFDocument::execute_command() instead."
+                                    .into(),
+                            )
+                        })?;
+                        fetcher(*r)
+                    }
+                },
+                other => Ok(other.clone()),
+            }
+        };
+        let as_dict = |obj: PDFObject, what: &str| match obj {
+            PDFObject::Dictionary(dict) => Ok(dict),
+            _ => Err(PDFError::Generic(format!("{} is not a dictionary", what))),
+        };
+
+        let mut catalog = as_dict(resolve(&PDFObject::Ref(self.catalog_ref))?, "Catalog")?;
+        let mut names = match catalog.remove("Names") {
+            Some(names) => match resolve(&names)? {
+                PDFObject::Dictionary(names) => names,
+                _ => HashMap::new(),
+            },
+            None => HashMap::new(),
+        };
+        let mut entries = match names.get("EmbeddedFiles") {
+            Some(tree) => raw_name_tree_entries(&mut resolve, tree, self.max_tree_depth)?,
+            None => Vec::new(),
+        };
+        let mut catalog_af = match catalog.get("AF") {
+            Some(files) => Some(resolve(files)?),
+            None => None,
+        };
+        let mut page = match self.page_ref {
+            Some(page_ref) => {
+                let mut page = as_dict(resolve(&PDFObject::Ref(page_ref))?, "Page")?;
+                if let Some(files) = page.get("AF") {
+                    let files = resolve(files)?;
+                    page.insert("AF".to_string(), files);
+                }
+                Some(page)
+            }
+            None => None,
+        };
+
+        let stream_obj = self.attachment.embedded_file_stream()?;
+        let stream = delta.add_object(stream_obj.clone());
+        let file_spec_obj = self.attachment.file_spec(stream);
+        let file_spec = delta.add_object(file_spec_obj.clone());
+        self.added = vec![(stream, stream_obj), (file_spec, file_spec_obj)];
+
+        // Name tree keys are sorted by their bytes. Entries sharing a name
+        // are all kept, the new file after the existing ones.
+        entries.push((
+            encode_text_string(&self.attachment.name),
+            PDFObject::Ref(file_spec),
+        ));
+        entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+        let names_array = entries
+            .into_iter()
+            .flat_map(|(key, value)| [Box::new(PDFObject::String(key)), Box::new(value)])
+            .collect();
+        let mut tree = HashMap::new();
+        tree.insert("Names".to_string(), PDFObject::Array(names_array));
+        names.insert("EmbeddedFiles".to_string(), PDFObject::Dictionary(tree));
+        catalog.insert("Names".to_string(), PDFObject::Dictionary(names));
+
+        match &mut page {
+            Some(page) => push_associated_file(page, file_spec),
+            None => {
+                if let Some(files) = catalog_af.take() {
+                    catalog.insert("AF".to_string(), files);
+                }
+                push_associated_file(&mut catalog, file_spec);
+            }
+        }
+
+        self.written = std::iter::once(PDFObject::Dictionary(catalog))
+            .chain(page.map(PDFObject::Dictionary))
+            .collect();
+        self.write(delta);
+        Ok(())
+    }
+
+    /// Writes the catalog and page recorded by [`apply`](Self::apply).
+    fn write(&self, delta: &mut DeltaLayer) {
+        for (obj_ref, obj) in self.targets().into_iter().zip(&self.written) {
+            delta.modify_object(obj_ref, obj.clone());
+        }
+    }
+}
+
+/// Appends a file specification to the /AF array of a dictionary.
+fn push_associated_file(dict: &mut HashMap<String, PDFObject>, file_spec: Ref) {
+    let mut files = match dict.remove("AF") {
+        Some(PDFObject::Array(files)) => files,
+        _ => SmallVec::new(),
+    };
+    files.push(Box::new(PDFObject::Ref(file_spec)));
+    dict.insert("AF".to_string(), PDFObject::Array(files));
+}
+
+impl Command for AttachFileCommand {
+    fn execute<'a>(
+        &mut self,
+        delta: &mut DeltaLayer,
+        fetch_base: Option<&'a BaseObjectFetcher<'a>>,
+    ) -> PDFResult<()> {
+        self.originals = self
+            .targets()
+            .iter()
+            .map(|r| delta.get(r).map(|obj| obj.object.clone()))
+            .collect();
+        self.apply(delta, fetch_base)
+    }
+
+    fn undo(&mut self, delta: &mut DeltaLayer) -> PDFResult<()> {
+        for (obj_ref, _) in &self.added {
+            delta.remove_new_object(*obj_ref);
+        }
+        for (obj_ref, original) in self.targets().iter().zip(&self.originals) {
+            match original {
+                Some(obj) => delta.modify_object(*obj_ref, obj.clone()),
+                None => delta.revert_object(*obj_ref),
+            }
+        }
+        Ok(())
+    }
+
+    fn redo(&mut self, delta: &mut DeltaLayer) -> PDFResult<()> {
+        for (obj_ref, obj) in &self.added {
+            delta.restore_new_object(*obj_ref, obj.clone());
+        }
+        self.write(delta);
+        Ok(())
+    }
+}
+
+/// Deduplicates embedded files found through several routes (name tree and
+/// /AF), keyed by file specification object number.
+pub(crate) fn merge_attachment(
+    files: &mut Vec<Attachment>,
+    seen: &mut HashMap<u32, usize>,
+    object_number: Option<u32>,
+    file: Attachment,
+) {
+    if let Some(num) = object_number
+        && let Some(&index) = seen.get(&num)
+    {
+        files[index].associated |= file.associated;
+        return;
+    }
+    if let Some(num) = object_number {
+        seen.insert(num, files.len());
+    }
+    files.push(file);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::decode::decode_stream_data;
+
+    fn attachment(name: &str, associated: bool) -> Attachment {
+        Attachment {
+            mime_type: Some("text/xml".to_string()),
+            relationship: associated.then_some(AFRelationship::Alternative),
+            associated,
+            ..Attachment::new(
+                name,
+                b"<rsm:CrossIndustryInvoice/>".to_vec(),
+                AFRelationship::Alternative,
+            )
+        }
+    }
+
+    fn embedded_file(name: &str, associated: bool) -> Attachment {
+        Attachment {
+            size: None,
+            relationship: None,
+            associated,
+            contents: Contents::Stream(PDFObject::Null),
+            ..Attachment::new(name, Vec::new(), AFRelationship::Unspecified)
+        }
+    }
+
+    #[test]
+    fn test_relationship_names() {
+        assert_eq!(
+            AFRelationship::from_name("Alternative"),
+            AFRelationship::Alternative
+        );
+        assert_eq!(
+            AFRelationship::from_name("EncryptedPayload"),
+            AFRelationship::EncryptedPayload
+        );
+        assert_eq!(
+            AFRelationship::from_name("ABC_Custom"),
+            AFRelationship::Other("ABC_Custom".to_string())
+        );
+    }
+
+    #[test]
+    fn test_detect_by_file_name() {
+        let attachments = vec![
+            attachment("logo.png", false),
+            attachment("ZUGFeRD-invoice.xml", false),
+        ];
+        let invoice = EInvoice::detect(&attachments, &BTreeMap::new()).unwrap();
+        assert_eq!(invoice.standard, EInvoiceStandard::ZUGFeRD);
+        assert_eq!(invoice.conformance_level, None);
+        assert_eq!(invoice.xml(), Some("<rsm:CrossIndustryInvoice/>"));
+
+        assert_eq!(
+            EInvoice::detect(&[attachment("notes.xml", true)], &BTreeMap::new()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_detect_from_xmp() {
+        let xmp = BTreeMap::from([
+            ("fx:DocumentFileName".to_string(), "invoice.xml".to_string()),
+            ("fx:ConformanceLevel".to_string(), "EN 16931".to_string()),
+            ("fx:Version".to_string(), "1.0".to_string()),
+        ]);
+        let attachments = vec![
+            attachment("factur-x.xml", false),
+            attachment("invoice.xml", true),
+        ];
+        let invoice = EInvoice::detect(&attachments, &xmp).unwrap();
+        assert_eq!(invoice.attachment.name, "invoice.xml");
+        assert_eq!(invoice.standard, EInvoiceStandard::FacturX);
+        assert_eq!(invoice.conformance_level.as_deref(), Some("EN 16931"));
+        assert_eq!(invoice.version.as_deref(), Some("1.0"));
+    }
+
+    #[test]
+    fn test_file_spec() {
+        let mut attachment =
+            Attachment::new("table.csv", b"a,b\n1,2\n".to_vec(), AFRelationship::Data);
+        attachment.mime_type = Some("text/csv".to_string());
+
+        let spec = match attachment.file_spec(Ref::new(9, 0)) {
+            PDFObject::Dictionary(dict) => dict,
+            other => panic!("expected a dictionary, got {:?}", other),
+        };
+        assert_eq!(
+            spec.get("AFRelationship"),
+            Some(&PDFObject::Name("Data".to_string()))
+        );
+        assert_eq!(
+            spec.get("UF"),
+            Some(&PDFObject::String(b"table.csv".to_vec()))
+        );
+
+        let (dict, data) = match attachment.embedded_file_stream().unwrap() {
+            PDFObject::Stream { dict, data } => (dict, data),
+            other => panic!("expected a stream, got {:?}", other),
+        };
+        assert_eq!(
+            dict.get("Subtype"),
+            Some(&PDFObject::Name("text/csv".to_string()))
+        );
+        assert_eq!(
+            decode_stream_data(&dict, &data).unwrap(),
+            b"a,b\n1,2\n".to_vec()
+        );
+        assert_eq!(attachment.size, Some(8));
+        assert_eq!(attachment.text(), Some("a,b\n1,2\n"));
+        assert!(
+            embedded_file("a.csv", false)
+                .embedded_file_stream()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_merge_attachment() {
+        let mut files = Vec::new();
+        let mut seen = HashMap::new();
+        merge_attachment(
+            &mut files,
+            &mut seen,
+            Some(5),
+            embedded_file("a.xml", false),
+        );
+        merge_attachment(&mut files, &mut seen, Some(5), embedded_file("a.xml", true));
+        merge_attachment(&mut files, &mut seen, None, embedded_file("b.xml", false));
+        assert_eq!(files.len(), 2);
+        assert!(files[0].associated);
+    }
+}
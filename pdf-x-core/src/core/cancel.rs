@@ -0,0 +1,92 @@
+//! Cooperative cancellation and progress reporting of long-running
+//! operations.
+//!
+//! Opening a large or damaged file, rendering a busy page and extracting the
+//! text of a whole document can all take a while. A [`CancellationToken`]
+//! lets another thread (typically a UI thread, when the user flips to another
+//! page) ask such an operation to stop early, and a [`Progress`] callback
+//! tells it how far the operation got.
+
+use super::error::{PDFError, PDFResult};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Receives the progress of a long-running operation as (done, total).
+///
+/// The units depend on the operation; see where the callback is set. The
+/// total may grow while the operation discovers more work, and the callback
+/// may be called from another thread than the one that set it.
+pub type Progress = Arc<dyn Fn(usize, usize) + Send + Sync>;
+
+/// A flag shared between a long-running operation and whoever may want to
+/// stop it.
+///
+/// Clones share the same flag, so one clone can be handed to the operation
+/// and another kept to cancel it. The operation checks the flag between
+/// units of work (objects read from the file, content stream operators,
+/// pages) and fails with [`PDFError::Cancelled`] once it is set. A cancelled
+/// token stays cancelled; use a new token for the next operation.
+///
+/// # Example
+/// ```no_run
+/// use pdf_x_core::core::{CancellationToken, PDFDocument, PDFError};
+///
+/// let mut doc = PDFDocument::open_file("large.pdf", None, None).unwrap();
+/// let token = CancellationToken::new();
+/// doc.set_cancellation_token(Some(token.clone()));
+///
+/// // On another thread, when the user moves on: token.cancel();
+/// match doc.render_page_to_svg(0, None) {
+///     Err(PDFError::Cancelled) => println!("render abandoned"),
+///     result => println!("{} bytes of SVG", result.unwrap().len()),
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    /// Creates a token that isn't cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Asks the operations holding this token (or a clone of it) to stop.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns true once [`cancel`](Self::cancel) has been called.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Returns [`PDFError::Cancelled`] if the token was cancelled.
+    pub fn check(&self) -> PDFResult<()> {
+        if self.is_cancelled() {
+            Err(PDFError::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clones_share_the_flag() {
+        let token = CancellationToken::new();
+        let handle = token.clone();
+        assert!(token.check().is_ok());
+
+        handle.cancel();
+        assert!(token.is_cancelled());
+        assert!(matches!(token.check(), Err(PDFError::Cancelled)));
+
+        // A fresh token is independent
+        assert!(!CancellationToken::new().is_cancelled());
+    }
+}
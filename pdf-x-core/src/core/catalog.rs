@@ -0,0 +1,355 @@
+//! Typed views of the document catalog and trailer dictionaries.
+//!
+//! `PDFDocument::catalog()` and `XRef::trailer()` return raw dictionaries.
+//! The views here wrap them and decode the commonly used entries, so simple
+//! introspection code doesn't have to match on `PDFObject` variants.
+//!
+//! Based on PDF.js src/core/catalog.js.
+
+use super::error::{PDFError, PDFResult};
+use super::outline::decode_pdf_string;
+use super::parser::{PDFObject, Ref};
+use std::collections::HashMap;
+
+/// How the document should be displayed when opened (catalog /PageMode).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PageMode {
+    /// Neither outline nor thumbnails visible
+    #[default]
+    UseNone,
+
+    /// Document outline visible
+    UseOutlines,
+
+    /// Thumbnail images visible
+    UseThumbs,
+
+    /// Full-screen mode, with no menu bar or window controls
+    FullScreen,
+
+    /// Optional content group panel visible
+    UseOC,
+
+    /// Attachments panel visible
+    UseAttachments,
+}
+
+impl PageMode {
+    /// Parses a /PageMode name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "UseNone" => Some(PageMode::UseNone),
+            "UseOutlines" => Some(PageMode::UseOutlines),
+            "UseThumbs" => Some(PageMode::UseThumbs),
+            "FullScreen" => Some(PageMode::FullScreen),
+            "UseOC" => Some(PageMode::UseOC),
+            "UseAttachments" => Some(PageMode::UseAttachments),
+            _ => None,
+        }
+    }
+}
+
+/// Typed view of the document catalog.
+///
+/// # Example
+/// ```no_run
+/// use pdf_x_core::core::PDFDocument;
+///
+/// let doc = PDFDocument::open_file("document.pdf", None, None).unwrap();
+/// let catalog = doc.catalog_view().unwrap();
+/// println!("Page mode: {:?}", catalog.page_mode().unwrap());
+/// println!("Language: {:?}", catalog.lang());
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Catalog<'a> {
+    dict: &'a HashMap<String, PDFObject>,
+}
+
+impl<'a> Catalog<'a> {
+    /// Wraps a catalog object.
+    ///
+    /// Fails if the object is not a dictionary.
+    pub fn new(catalog: &'a PDFObject) -> PDFResult<Self> {
+        match catalog {
+            PDFObject::Dictionary(dict) => Ok(Catalog { dict }),
+            other => Err(PDFError::Generic(format!(
+                "Catalog is not a dictionary: {:?}",
+                other
+            ))),
+        }
+    }
+
+    /// Returns the raw catalog dictionary.
+    pub fn dict(&self) -> &'a HashMap<String, PDFObject> {
+        self.dict
+    }
+
+    /// Returns how the document should be displayed when opened.
+    ///
+    /// Defaults to `UseNone` when the catalog has no /PageMode.
+    pub fn page_mode(&self) -> PDFResult<PageMode> {
+        match self.dict.get("PageMode") {
+            None => Ok(PageMode::default()),
+            Some(PDFObject::Name(name)) => PageMode::from_name(name).ok_or_else(|| {
+                PDFError::Generic(format!("Unknown /PageMode /{} in catalog", name))
+            }),
+            Some(other) => Err(PDFError::Generic(format!(
+                "Catalog /PageMode is not a name: {:?}",
+                other
+            ))),
+        }
+    }
+
+    /// Returns the document's natural language (e.g. "en-US"), if declared.
+    pub fn lang(&self) -> Option<String> {
+        match self.dict.get("Lang") {
+            Some(PDFObject::String(bytes)) | Some(PDFObject::HexString(bytes)) => {
+                Some(decode_pdf_string(bytes))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the PDF version override from /Version (e.g. "1.7"), if any.
+    pub fn version(&self) -> Option<&'a str> {
+        match self.dict.get("Version") {
+            Some(PDFObject::Name(version)) => Some(version),
+            _ => None,
+        }
+    }
+
+    /// Returns the /Names dictionary.
+    ///
+    /// The entry may be an indirect reference; resolve it with
+    /// `XRef::fetch_if_ref()`.
+    ///
+    /// # Returns
+    /// The dictionary or reference, `None` if absent, or an error if the
+    /// entry has the wrong type
+    pub fn names(&self) -> PDFResult<Option<&'a PDFObject>> {
+        match self.dict.get("Names") {
+            None | Some(PDFObject::Null) => Ok(None),
+            Some(names @ (PDFObject::Dictionary(_) | PDFObject::Ref(_))) => Ok(Some(names)),
+            Some(other) => Err(PDFError::Generic(format!(
+                "Catalog /Names is not a dictionary: {:?}",
+                other
+            ))),
+        }
+    }
+
+    /// Returns the reference of the page tree root.
+    pub fn pages_ref(&self) -> PDFResult<Ref> {
+        match self.dict.get("Pages") {
+            Some(PDFObject::Ref(r)) => Ok(*r),
+            Some(other) => Err(PDFError::Generic(format!(
+                "Catalog /Pages is not an indirect reference: {:?}",
+                other
+            ))),
+            None => Err(PDFError::Generic("Catalog has no /Pages entry".to_string())),
+        }
+    }
+
+    /// Returns the reference of the interactive form dictionary.
+    ///
+    /// `None` if the document has no form or the dictionary is stored inline.
+    pub fn acro_form_ref(&self) -> Option<Ref> {
+        match self.dict.get("AcroForm") {
+            Some(PDFObject::Ref(r)) => Some(*r),
+            _ => None,
+        }
+    }
+}
+
+/// Typed view of the trailer dictionary.
+///
+/// # Example
+/// ```no_run
+/// use pdf_x_core::core::PDFDocument;
+///
+/// let doc = PDFDocument::open_file("document.pdf", None, None).unwrap();
+/// let trailer = doc.xref().trailer_view().unwrap();
+/// println!("Root: {:?}", trailer.root_ref().unwrap());
+/// println!("Info: {:?}", trailer.info_ref());
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Trailer<'a> {
+    dict: &'a HashMap<String, PDFObject>,
+}
+
+impl<'a> Trailer<'a> {
+    /// Wraps a trailer object.
+    ///
+    /// Fails if the object is not a dictionary.
+    pub fn new(trailer: &'a PDFObject) -> PDFResult<Self> {
+        match trailer {
+            PDFObject::Dictionary(dict) => Ok(Trailer { dict }),
+            other => Err(PDFError::Generic(format!(
+                "Trailer is not a dictionary: {:?}",
+                other
+            ))),
+        }
+    }
+
+    /// Returns the raw trailer dictionary.
+    pub fn dict(&self) -> &'a HashMap<String, PDFObject> {
+        self.dict
+    }
+
+    /// Returns the reference of the document catalog.
+    pub fn root_ref(&self) -> PDFResult<Ref> {
+        match self.dict.get("Root") {
+            Some(PDFObject::Ref(r)) => Ok(*r),
+            Some(other) => Err(PDFError::Generic(format!(
+                "Trailer /Root is not an indirect reference: {:?}",
+                other
+            ))),
+            None => Err(PDFError::Generic("Trailer has no /Root entry".to_string())),
+        }
+    }
+
+    /// Returns the reference of the document information dictionary, if any.
+    pub fn info_ref(&self) -> Option<Ref> {
+        match self.dict.get("Info") {
+            Some(PDFObject::Ref(r)) => Some(*r),
+            _ => None,
+        }
+    }
+
+    /// Returns the reference of the encryption dictionary, if the document
+    /// is encrypted and the dictionary is indirect.
+    pub fn encrypt_ref(&self) -> Option<Ref> {
+        match self.dict.get("Encrypt") {
+            Some(PDFObject::Ref(r)) => Some(*r),
+            _ => None,
+        }
+    }
+
+    /// Returns the total number of entries in the cross-reference table.
+    pub fn size(&self) -> Option<u32> {
+        match self.dict.get("Size") {
+            Some(PDFObject::Number(n)) if *n >= 0.0 => Some(*n as u32),
+            _ => None,
+        }
+    }
+
+    /// Returns the file identifier pair (permanent ID, changing ID).
+    ///
+    /// # Returns
+    /// The two byte strings, `None` if the trailer has no /ID, or an error if
+    /// the entry is malformed
+    pub fn id_pair(&self) -> PDFResult<Option<(&'a [u8], &'a [u8])>> {
+        let items = match self.dict.get("ID") {
+            None => return Ok(None),
+            Some(PDFObject::Array(items)) if items.len() == 2 => items,
+            Some(other) => {
+                return Err(PDFError::Generic(format!(
+                    "Trailer /ID is not an array of two strings: {:?}",
+                    other
+                )));
+            }
+        };
+
+        let as_bytes = |obj: &'a PDFObject| match obj {
+            PDFObject::String(bytes) | PDFObject::HexString(bytes) => Ok(bytes.as_slice()),
+            other => Err(PDFError::Generic(format!(
+                "Trailer /ID entry is not a string: {:?}",
+                other
+            ))),
+        };
+
+        Ok(Some((as_bytes(&items[0])?, as_bytes(&items[1])?)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dict(entries: Vec<(&str, PDFObject)>) -> PDFObject {
+        PDFObject::Dictionary(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_catalog_accessors() {
+        let obj = dict(vec![
+            ("Type", PDFObject::Name("Catalog".to_string())),
+            ("Pages", PDFObject::Ref(Ref::new(2, 0))),
+            ("PageMode", PDFObject::Name("UseOutlines".to_string())),
+            ("Lang", PDFObject::String(b"en-US".to_vec())),
+            ("AcroForm", PDFObject::Ref(Ref::new(7, 0))),
+            ("Names", dict(vec![])),
+        ]);
+        let catalog = Catalog::new(&obj).unwrap();
+
+        assert_eq!(catalog.page_mode().unwrap(), PageMode::UseOutlines);
+        assert_eq!(catalog.lang().as_deref(), Some("en-US"));
+        assert_eq!(catalog.pages_ref().unwrap(), Ref::new(2, 0));
+        assert_eq!(catalog.acro_form_ref(), Some(Ref::new(7, 0)));
+        assert!(matches!(
+            catalog.names().unwrap(),
+            Some(PDFObject::Dictionary(_))
+        ));
+        assert_eq!(catalog.version(), None);
+    }
+
+    #[test]
+    fn test_catalog_defaults_and_errors() {
+        let obj = dict(vec![("PageMode", PDFObject::Name("Bogus".to_string()))]);
+        let catalog = Catalog::new(&obj).unwrap();
+        assert!(catalog.page_mode().is_err());
+        assert!(catalog.pages_ref().is_err());
+        assert!(catalog.names().unwrap().is_none());
+
+        let empty = dict(vec![]);
+        let catalog = Catalog::new(&empty).unwrap();
+        assert_eq!(catalog.page_mode().unwrap(), PageMode::UseNone);
+        assert_eq!(catalog.lang(), None);
+
+        assert!(Catalog::new(&PDFObject::Null).is_err());
+    }
+
+    #[test]
+    fn test_trailer_accessors() {
+        let obj = dict(vec![
+            ("Root", PDFObject::Ref(Ref::new(1, 0))),
+            ("Info", PDFObject::Ref(Ref::new(9, 0))),
+            ("Size", PDFObject::Number(12.0)),
+            (
+                "ID",
+                PDFObject::Array(smallvec::smallvec![
+                    Box::new(PDFObject::HexString(vec![0xAB, 0xCD])),
+                    Box::new(PDFObject::String(b"xy".to_vec())),
+                ]),
+            ),
+        ]);
+        let trailer = Trailer::new(&obj).unwrap();
+
+        assert_eq!(trailer.root_ref().unwrap(), Ref::new(1, 0));
+        assert_eq!(trailer.info_ref(), Some(Ref::new(9, 0)));
+        assert_eq!(trailer.encrypt_ref(), None);
+        assert_eq!(trailer.size(), Some(12));
+        assert_eq!(
+            trailer.id_pair().unwrap(),
+            Some((&[0xAB, 0xCD][..], &b"xy"[..]))
+        );
+    }
+
+    #[test]
+    fn test_trailer_errors() {
+        let obj = dict(vec![
+            ("Root", dict(vec![])),
+            ("ID", PDFObject::Array(smallvec::smallvec![])),
+        ]);
+        let trailer = Trailer::new(&obj).unwrap();
+        assert!(trailer.root_ref().is_err());
+        assert!(trailer.id_pair().is_err());
+
+        let empty = dict(vec![]);
+        assert!(Trailer::new(&empty).unwrap().id_pair().unwrap().is_none());
+    }
+}
@@ -1,5 +1,6 @@
 use super::error::{PDFError, PDFResult};
 use std::collections::{HashMap, HashSet, VecDeque};
+use std::ops::Range;
 
 /// Default chunk size: 64KB (same as PDF.js)
 pub const DEFAULT_CHUNK_SIZE: usize = 65536;
@@ -7,15 +8,121 @@ pub const DEFAULT_CHUNK_SIZE: usize = 65536;
 /// Default maximum number of chunks to keep in memory cache
 pub const DEFAULT_MAX_CACHED_CHUNKS: usize = 10;
 
+/// Default number of chunks loaded ahead of a sequential read
+pub const DEFAULT_READAHEAD_CHUNKS: usize = 2;
+
+/// Tuning for chunked streams: how data is split, read ahead and cached.
+///
+/// # Example
+/// ```no_run
+/// use pdf_x_core::core::{ChunkedStreamOptions, FileChunkedStream};
+///
+/// // 256KB chunks, 4 chunks of readahead, at most 8MB in memory
+/// let options = ChunkedStreamOptions {
+///     chunk_size: 256 * 1024,
+///     readahead_chunks: 4,
+///     max_cached_bytes: 8 * 1024 * 1024,
+/// };
+/// let stream = FileChunkedStream::open_with_options("large.pdf", options).unwrap();
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkedStreamOptions {
+    /// Size of each chunk in bytes (default: 64KB)
+    pub chunk_size: usize,
+    /// Number of chunks loaded along with a missing chunk once reads are
+    /// sequential, i.e. the missing chunk directly follows the last one
+    /// loaded (default: 2). Random access never reads ahead.
+    pub readahead_chunks: usize,
+    /// Upper bound on the chunk data kept in memory; the least recently
+    /// used chunks are evicted beyond it. At least one chunk is always
+    /// kept (default: 10 chunks of the default size)
+    pub max_cached_bytes: usize,
+}
+
+impl Default for ChunkedStreamOptions {
+    fn default() -> Self {
+        ChunkedStreamOptions {
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            readahead_chunks: DEFAULT_READAHEAD_CHUNKS,
+            max_cached_bytes: DEFAULT_CHUNK_SIZE * DEFAULT_MAX_CACHED_CHUNKS,
+        }
+    }
+}
+
+impl ChunkedStreamOptions {
+    /// Options matching the positional `chunk_size`/`max_cached_chunks`
+    /// arguments of the older constructors, without readahead.
+    pub(crate) fn from_chunk_counts(
+        chunk_size: Option<usize>,
+        max_cached_chunks: Option<usize>,
+    ) -> Self {
+        let chunk_size = chunk_size.unwrap_or(DEFAULT_CHUNK_SIZE);
+        ChunkedStreamOptions {
+            chunk_size,
+            readahead_chunks: 0,
+            max_cached_bytes: chunk_size
+                .saturating_mul(max_cached_chunks.unwrap_or(DEFAULT_MAX_CACHED_CHUNKS)),
+        }
+    }
+}
+
 /// Trait for loading chunks from various data sources.
 ///
 /// This trait is analogous to PDF.js's ChunkedStreamManager interface,
 /// which handles the actual data loading from network, filesystem, or other sources.
 ///
-/// Implementers (FileChunkedStream, HttpChunkedStream) are responsible for:
+/// Implementers are responsible for:
 /// - Managing their own data source (File handle, HTTP client, etc.)
 /// - Loading chunks on demand when requested
 /// - Returning chunk data to be managed by ChunkManager
+///
+/// This is the public extension point for custom data sources: implement it for
+/// anything that can serve byte ranges (object storage ranged GETs, encrypted
+/// container files, database blobs, ...) and pass it to
+/// `PDFDocument::open_stream`. Caching and progressive loading are handled by
+/// the library; the loader only has to fetch raw bytes.
+///
+/// # Contract
+/// - `chunk_size()` and `total_length()` must be constant for the loader's lifetime,
+///   and `chunk_size()` must be non-zero.
+/// - `request_chunk(n)` must return exactly the bytes
+///   `n * chunk_size .. min((n + 1) * chunk_size, total_length)`.
+///   A chunk of the wrong size is reported as a `StreamError`.
+/// - The same chunk may be requested more than once (after LRU eviction).
+/// - Loaders must be `Send` to be used with `PDFDocument::open_stream`.
+///
+/// # Example
+/// ```no_run
+/// use pdf_x_core::core::error::PDFResult;
+/// use pdf_x_core::core::{ChunkLoader, PDFDocument};
+///
+/// /// Serves a PDF stored as a blob in some database.
+/// struct BlobLoader {
+///     blob: Vec<u8>,
+/// }
+///
+/// impl ChunkLoader for BlobLoader {
+///     fn request_chunk(&mut self, chunk_num: usize) -> PDFResult<Vec<u8>> {
+///         let start = chunk_num * self.chunk_size();
+///         let end = (start + self.chunk_size()).min(self.blob.len());
+///         Ok(self.blob[start..end].to_vec())
+///     }
+///
+///     fn chunk_size(&self) -> usize {
+///         65536
+///     }
+///
+///     fn total_length(&self) -> usize {
+///         self.blob.len()
+///     }
+/// }
+///
+/// let loader = BlobLoader { blob: std::fs::read("document.pdf").unwrap() };
+/// let doc = PDFDocument::open_stream(loader, None).unwrap();
+/// ```
+///
+/// See `examples/s3_loader.rs` (feature `s3-example`) for a loader that
+/// issues HTTP range requests against S3.
 pub trait ChunkLoader {
     /// Requests and loads a specific chunk from the data source.
     ///
@@ -30,6 +137,19 @@ pub trait ChunkLoader {
     /// The chunk data as a Vec<u8>. May be shorter than chunk_size for the last chunk.
     fn request_chunk(&mut self, chunk_num: usize) -> PDFResult<Vec<u8>>;
 
+    /// Requests a run of consecutive chunks, concatenated.
+    ///
+    /// Used when reading ahead. The default requests the chunks one by one;
+    /// loaders that can fetch a whole range at once (a single read or range
+    /// request) should override it.
+    fn request_chunks(&mut self, chunks: Range<usize>) -> PDFResult<Vec<u8>> {
+        let mut data = Vec::new();
+        for chunk_num in chunks {
+            data.extend_from_slice(&self.request_chunk(chunk_num)?);
+        }
+        Ok(data)
+    }
+
     /// Returns the chunk size in bytes.
     fn chunk_size(&self) -> usize;
 
@@ -70,8 +190,17 @@ pub struct ChunkManager {
     /// LRU queue for cache eviction (stores chunk numbers)
     lru_queue: VecDeque<usize>,
 
-    /// Maximum number of chunks to keep in cache
-    max_cached_chunks: usize,
+    /// Bytes of chunk data currently in the cache
+    cached_bytes: usize,
+
+    /// Maximum bytes of chunk data to keep in cache
+    max_cached_bytes: usize,
+
+    /// Chunks to load ahead of a sequential read
+    readahead_chunks: usize,
+
+    /// Last chunk of the most recent load, to detect sequential reads
+    last_loaded: Option<usize>,
 }
 
 impl ChunkManager {
@@ -81,13 +210,23 @@ impl ChunkManager {
     /// * `total_length` - Total length of the data
     /// * `chunk_size` - Size of each chunk (default: 64KB)
     /// * `max_cached_chunks` - Maximum chunks to keep in memory (default: 10)
+    ///
+    /// The manager doesn't read ahead; use
+    /// [`with_options`](Self::with_options) for that.
     pub fn new(
         total_length: usize,
         chunk_size: Option<usize>,
         max_cached_chunks: Option<usize>,
     ) -> Self {
-        let chunk_size = chunk_size.unwrap_or(DEFAULT_CHUNK_SIZE);
-        let max_cached_chunks = max_cached_chunks.unwrap_or(DEFAULT_MAX_CACHED_CHUNKS);
+        Self::with_options(
+            total_length,
+            &ChunkedStreamOptions::from_chunk_counts(chunk_size, max_cached_chunks),
+        )
+    }
+
+    /// Creates a new ChunkManager from [`ChunkedStreamOptions`].
+    pub fn with_options(total_length: usize, options: &ChunkedStreamOptions) -> Self {
+        let chunk_size = options.chunk_size.max(1);
         let num_chunks = total_length.div_ceil(chunk_size);
 
         ChunkManager {
@@ -97,7 +236,10 @@ impl ChunkManager {
             chunk_cache: HashMap::new(),
             loaded_chunks: HashSet::new(),
             lru_queue: VecDeque::new(),
-            max_cached_chunks,
+            cached_bytes: 0,
+            max_cached_bytes: options.max_cached_bytes,
+            readahead_chunks: options.readahead_chunks,
+            last_loaded: None,
         }
     }
 
@@ -141,27 +283,60 @@ impl ChunkManager {
         self.loaded_chunks.insert(chunk_num);
 
         // If already in cache, update LRU
-        if self.chunk_cache.contains_key(&chunk_num) {
+        if let Some(old) = self.chunk_cache.remove(&chunk_num) {
+            self.cached_bytes -= old.len();
             self.lru_queue.retain(|&x| x != chunk_num);
-            self.lru_queue.push_back(chunk_num);
-            self.chunk_cache.insert(chunk_num, chunk);
-            return Ok(());
         }
 
-        // Evict LRU chunk if cache is full
-        if self.chunk_cache.len() >= self.max_cached_chunks {
-            if let Some(lru_chunk) = self.lru_queue.pop_front() {
-                self.chunk_cache.remove(&lru_chunk);
+        // Evict LRU chunks until the new one fits, always keeping one
+        while self.cached_bytes + chunk.len() > self.max_cached_bytes {
+            let Some(lru_chunk) = self.lru_queue.pop_front() else {
+                break;
+            };
+            if let Some(evicted) = self.chunk_cache.remove(&lru_chunk) {
+                self.cached_bytes -= evicted.len();
             }
         }
 
         // Add to cache
+        self.cached_bytes += chunk.len();
         self.chunk_cache.insert(chunk_num, chunk);
         self.lru_queue.push_back(chunk_num);
 
         Ok(())
     }
 
+    /// Receives the data of several consecutive chunks, starting at
+    /// `begin_chunk`, read in a single request.
+    pub fn on_receive_range(&mut self, begin_chunk: usize, data: Vec<u8>) -> PDFResult<()> {
+        for (i, chunk) in data.chunks(self.chunk_size).enumerate() {
+            self.on_receive_data(begin_chunk + i, chunk.to_vec())?;
+        }
+        Ok(())
+    }
+
+    /// Returns the chunks to load for a read of the missing chunk
+    /// `chunk_num`: the chunk itself plus, if it directly follows the last
+    /// chunk loaded, up to `readahead_chunks` following chunks that aren't
+    /// cached.
+    ///
+    /// Readahead is limited so that the whole range fits in the cache
+    /// together with the previous chunk, so a read that straddles the two
+    /// doesn't have its first chunk evicted by its second.
+    pub fn load_range_for(&mut self, chunk_num: usize) -> Range<usize> {
+        let sequential = self.last_loaded.is_some_and(|last| last + 1 == chunk_num);
+        let mut end = chunk_num + 1;
+        if sequential {
+            let budget = (self.max_cached_bytes / self.chunk_size).saturating_sub(2);
+            let limit = (chunk_num + 1 + self.readahead_chunks.min(budget)).min(self.num_chunks);
+            while end < limit && !self.chunk_cache.contains_key(&end) {
+                end += 1;
+            }
+        }
+        self.last_loaded = Some(end - 1);
+        chunk_num..end
+    }
+
     /// Checks if a specific chunk has been loaded.
     ///
     /// Analogous to ChunkedStream.hasChunk() in PDF.js.
@@ -245,6 +420,11 @@ impl ChunkManager {
     pub fn is_chunk_cached(&self, chunk_num: usize) -> bool {
         self.chunk_cache.contains_key(&chunk_num)
     }
+
+    /// Returns the bytes of chunk data currently in the cache.
+    pub fn cached_bytes(&self) -> usize {
+        self.cached_bytes
+    }
 }
 
 #[cfg(test)]
@@ -355,4 +535,66 @@ mod tests {
         assert_eq!(manager.get_byte_from_cache(50).unwrap(), 50);
         assert_eq!(manager.get_byte_from_cache(99).unwrap(), 99);
     }
+
+    #[test]
+    fn test_eviction_by_bytes() {
+        let options = ChunkedStreamOptions {
+            chunk_size: 100,
+            readahead_chunks: 0,
+            max_cached_bytes: 250,
+        };
+        let mut manager = ChunkManager::with_options(450, &options);
+
+        manager.on_receive_data(0, vec![0u8; 100]).unwrap();
+        manager.on_receive_data(1, vec![1u8; 100]).unwrap();
+        assert_eq!(manager.cached_bytes(), 200);
+
+        // A third full chunk doesn't fit in 250 bytes
+        manager.on_receive_data(2, vec![2u8; 100]).unwrap();
+        assert!(!manager.is_chunk_cached(0));
+        assert_eq!(manager.cached_bytes(), 200);
+
+        // The 50-byte last chunk does
+        manager.on_receive_data(4, vec![4u8; 50]).unwrap();
+        assert!(manager.is_chunk_cached(1));
+        assert_eq!(manager.cached_bytes(), 250);
+    }
+
+    #[test]
+    fn test_load_range_reads_ahead_when_sequential() {
+        let options = ChunkedStreamOptions {
+            chunk_size: 100,
+            readahead_chunks: 2,
+            max_cached_bytes: 1000,
+        };
+        let mut manager = ChunkManager::with_options(1000, &options);
+
+        // The first read and random reads load a single chunk
+        assert_eq!(manager.load_range_for(5), 5..6);
+        assert_eq!(manager.load_range_for(0), 0..1);
+
+        // A read of the next chunk brings the following two along
+        assert_eq!(manager.load_range_for(1), 1..4);
+        manager.on_receive_range(1, vec![1u8; 300]).unwrap();
+        assert!(manager.is_chunk_cached(3));
+
+        // Readahead stops at cached chunks and at the end of the data
+        manager.on_receive_data(6, vec![6u8; 100]).unwrap();
+        assert_eq!(manager.load_range_for(4), 4..6);
+        assert_eq!(manager.load_range_for(8), 8..9);
+        assert_eq!(manager.load_range_for(9), 9..10);
+    }
+
+    #[test]
+    fn test_readahead_fits_in_cache() {
+        let options = ChunkedStreamOptions {
+            chunk_size: 100,
+            readahead_chunks: 8,
+            max_cached_bytes: 300,
+        };
+        let mut manager = ChunkManager::with_options(1000, &options);
+
+        manager.load_range_for(0);
+        assert_eq!(manager.load_range_for(1), 1..3);
+    }
 }
@@ -7,13 +7,38 @@
 //! Based on PDF.js's CMap parser in src/core/cmap.js
 
 use crate::core::error::{PDFError, PDFResult};
+use crate::log_target;
 use rustc_hash::FxHashMap;
 
-/// CMap (Character Map) for mapping character codes to Unicode.
+/// Upper bound on the number of codes a single bfrange may expand to.
 ///
-/// CMaps support two types of mappings:
+/// Reference: pdf.js MAX_MAP_RANGE; guards against ranges like <0000> <FFFFFFFF>.
+const MAX_MAP_RANGE: u32 = 0xFFFF;
+
+/// A codespace range: codes of `bytes` length between `low` and `high`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CodespaceRange {
+    /// Number of bytes in codes of this range (1-4)
+    pub bytes: usize,
+    /// Lowest code in the range
+    pub low: u32,
+    /// Highest code in the range
+    pub high: u32,
+}
+
+/// CMap (Character Map) for mapping character codes to Unicode or CIDs.
+///
+/// ToUnicode CMaps support two types of mappings:
 /// - **bfchar**: Single character mappings (CID -> Unicode)
-/// - **bfrange**: Range mappings (CID range -> Unicode range)
+/// - **bfrange**: Range mappings (CID range -> Unicode range, or an array
+///   of destinations)
+///
+/// Embedded encoding CMaps of Type0 fonts use **cidchar** and **cidrange**
+/// instead, mapping character codes to CIDs.
+///
+/// Destinations are UTF-16BE strings, so a code may map to a surrogate pair
+/// (characters outside the BMP) or to several characters (ligatures such as
+/// "ffi").
 ///
 /// Example CMap stream:
 /// ```text
@@ -36,8 +61,130 @@ use rustc_hash::FxHashMap;
 /// endcmap
 /// ```
 pub struct CMap {
-    /// CID → Unicode mappings
-    mappings: FxHashMap<u16, char>,
+    /// Code → Unicode string mappings
+    mappings: FxHashMap<u32, String>,
+    /// Codespace ranges declared by the CMap
+    codespace_ranges: Vec<CodespaceRange>,
+    /// Code → CID mappings as (low, high, first CID) ranges
+    cid_ranges: Vec<(u32, u32, u32)>,
+    /// Writing mode from /WMode (1 = vertical)
+    vertical: bool,
+}
+
+/// Tokens of the PostScript-like CMap syntax that the parser cares about.
+#[derive(Debug, PartialEq)]
+enum Token<'a> {
+    /// Bytes of a `<...>` hex string
+    Hex(Vec<u8>),
+    ArrayStart,
+    ArrayEnd,
+    /// Keywords, names, numbers, and anything else
+    Word(&'a [u8]),
+}
+
+/// Splits CMap data into tokens, skipping comments, literal strings and
+/// dictionary delimiters.
+struct Tokenizer<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Tokenizer<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Tokenizer { data, pos: 0 }
+    }
+
+    fn is_delimiter(b: u8) -> bool {
+        b.is_ascii_whitespace() || b"<>[]()/%{}".contains(&b)
+    }
+
+    fn next_token(&mut self) -> PDFResult<Option<Token<'a>>> {
+        loop {
+            let Some(&b) = self.data.get(self.pos) else {
+                return Ok(None);
+            };
+            match b {
+                b if b.is_ascii_whitespace() || b == 0 => self.pos += 1,
+                b'%' => {
+                    while self.pos < self.data.len() && !b"\r\n".contains(&self.data[self.pos]) {
+                        self.pos += 1;
+                    }
+                }
+                b'(' => self.skip_literal_string(),
+                b'<' if self.data.get(self.pos + 1) == Some(&b'<') => self.pos += 2,
+                b'>' if self.data.get(self.pos + 1) == Some(&b'>') => self.pos += 2,
+                b'<' => return self.read_hex().map(Some),
+                b'[' => {
+                    self.pos += 1;
+                    return Ok(Some(Token::ArrayStart));
+                }
+                b']' => {
+                    self.pos += 1;
+                    return Ok(Some(Token::ArrayEnd));
+                }
+                _ => {
+                    let start = self.pos;
+                    self.pos += 1;
+                    while self.pos < self.data.len() && !Self::is_delimiter(self.data[self.pos]) {
+                        self.pos += 1;
+                    }
+                    return Ok(Some(Token::Word(&self.data[start..self.pos])));
+                }
+            }
+        }
+    }
+
+    fn skip_literal_string(&mut self) {
+        let mut depth = 0;
+        while let Some(&b) = self.data.get(self.pos) {
+            self.pos += 1;
+            match b {
+                b'\\' => self.pos += 1,
+                b'(' => depth += 1,
+                b')' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn read_hex(&mut self) -> PDFResult<Token<'a>> {
+        let start = self.pos;
+        self.pos += 1;
+        let mut digits = Vec::new();
+        loop {
+            let Some(&b) = self.data.get(self.pos) else {
+                return Err(PDFError::Generic(
+                    "Unterminated hex string in CMap".to_string(),
+                ));
+            };
+            self.pos += 1;
+            match b {
+                b'>' => break,
+                b if b.is_ascii_whitespace() => {}
+                b if b.is_ascii_hexdigit() => digits.push((b as char).to_digit(16).unwrap() as u8),
+                _ => {
+                    return Err(PDFError::Generic(format!(
+                        "Invalid hex string in CMap at offset {}",
+                        start
+                    )));
+                }
+            }
+        }
+        if digits.len() % 2 == 1 {
+            digits.push(0);
+        }
+        Ok(Token::Hex(
+            digits
+                .chunks(2)
+                .map(|pair| pair[0] << 4 | pair[1])
+                .collect(),
+        ))
+    }
 }
 
 impl CMap {
@@ -45,11 +192,18 @@ impl CMap {
     pub fn new() -> Self {
         CMap {
             mappings: FxHashMap::default(),
+            codespace_ranges: Vec::new(),
+            cid_ranges: Vec::new(),
+            vertical: false,
         }
     }
 
     /// Parses a /ToUnicode CMap stream.
     ///
+    /// Handles `begincodespacerange`, `beginbfchar` and `beginbfrange`
+    /// sections (including the array form of bfrange). Entries may be spread
+    /// over lines arbitrarily; malformed entries are skipped.
+    ///
     /// # Arguments
     /// * `stream_data` - The decompressed CMap stream data
     ///
@@ -66,149 +220,229 @@ impl CMap {
     /// ```
     pub fn parse(stream_data: &[u8]) -> PDFResult<Self> {
         let mut cmap = CMap::new();
-        let content = std::str::from_utf8(stream_data)
-            .map_err(|e| PDFError::Generic(format!("Invalid UTF-8 in CMap stream: {}", e)))?;
-
-        // Parse the CMap stream line by line
-        let mut lines = content.lines().peekable();
-
-        while let Some(line) = lines.next() {
-            let trimmed = line.trim();
+        let mut tokenizer = Tokenizer::new(stream_data);
 
-            // Skip empty lines and comments
-            if trimmed.is_empty() || trimmed.starts_with('%') {
-                continue;
-            }
-
-            // Parse bfchar mappings (single character mappings)
-            if trimmed.ends_with("beginbfchar") {
-                let count = Self::parse_count(trimmed)?;
-                Self::parse_bfchar(&mut cmap, &mut lines, count)?;
-            }
-            // Parse bfrange mappings (range mappings)
-            else if trimmed.ends_with("beginbfrange") {
-                let count = Self::parse_count(trimmed)?;
-                Self::parse_bfrange(&mut cmap, &mut lines, count)?;
+        while let Some(token) = tokenizer.next_token()? {
+            match token {
+                Token::Word(b"begincodespacerange") => {
+                    Self::parse_codespace_range(&mut cmap, &mut tokenizer)?
+                }
+                Token::Word(b"beginbfchar") => Self::parse_bfchar(&mut cmap, &mut tokenizer)?,
+                Token::Word(b"beginbfrange") => Self::parse_bfrange(&mut cmap, &mut tokenizer)?,
+                Token::Word(b"begincidchar") => {
+                    Self::parse_cid_mappings(&mut cmap, &mut tokenizer, b"endcidchar", false)?
+                }
+                Token::Word(b"begincidrange") => {
+                    Self::parse_cid_mappings(&mut cmap, &mut tokenizer, b"endcidrange", true)?
+                }
+                Token::Word(b"/WMode") => {
+                    if let Some(Token::Word(mode)) = tokenizer.next_token()? {
+                        cmap.vertical = mode == b"1";
+                    }
+                }
+                Token::Word(b"usecmap") => {
+                    tracing::warn!(
+                        target: log_target::FONT,
+                        "usecmap in embedded CMap is not supported"
+                    );
+                }
+                _ => {}
             }
         }
 
         Ok(cmap)
     }
 
-    /// Parses the count from a "N beginbfchar" or "N beginbfrange" line.
-    fn parse_count(line: &str) -> PDFResult<usize> {
-        let parts: Vec<&str> = line.split_whitespace().collect();
-        if parts.len() < 2 {
-            return Err(PDFError::Generic(format!("Invalid CMap line: '{}'", line)));
+    /// Parses codespace ranges until `endcodespacerange`.
+    ///
+    /// Format: `<low> <high>`; the byte length of `low` gives the code length.
+    fn parse_codespace_range(cmap: &mut CMap, tokenizer: &mut Tokenizer) -> PDFResult<()> {
+        loop {
+            match tokenizer.next_token()? {
+                Some(Token::Hex(low)) => {
+                    let Some(Token::Hex(high)) = tokenizer.next_token()? else {
+                        break;
+                    };
+                    if (1..=4).contains(&low.len()) {
+                        cmap.codespace_ranges.push(CodespaceRange {
+                            bytes: low.len(),
+                            low: Self::code_from_bytes(&low),
+                            high: Self::code_from_bytes(&high),
+                        });
+                    }
+                }
+                Some(Token::Word(b"endcodespacerange")) | None => break,
+                Some(_) => {}
+            }
         }
-
-        parts[0]
-            .parse::<usize>()
-            .map_err(|_| PDFError::Generic(format!("Invalid count in CMap line: '{}'", line)))
+        Ok(())
     }
 
-    /// Parses bfchar entries (single character mappings).
+    /// Parses bfchar entries (single character mappings) until `endbfchar`.
     ///
-    /// Format: `<srcCode> <dstUnicode>`
+    /// Format: `<srcCode> <dstString>`
     /// Example: `<0003> <0020>` maps CID 3 to Unicode U+0020 (space)
-    fn parse_bfchar<'a, I>(cmap: &mut CMap, lines: &mut I, count: usize) -> PDFResult<()>
-    where
-        I: Iterator<Item = &'a str>,
-    {
-        for _ in 0..count {
-            let line = lines
-                .next()
-                .ok_or_else(|| PDFError::Generic("Unexpected end of bfchar section".to_string()))?;
-
-            let trimmed = line.trim();
-
-            // Check for end marker
-            if trimmed == "endbfchar" {
-                break;
-            }
-
-            // Parse the mapping: <srcCode> <dstUnicode>
-            let parts: Vec<&str> = trimmed.split_whitespace().collect();
-            if parts.len() < 2 {
-                continue; // Skip invalid lines
+    fn parse_bfchar(cmap: &mut CMap, tokenizer: &mut Tokenizer) -> PDFResult<()> {
+        loop {
+            match tokenizer.next_token()? {
+                Some(Token::Hex(src)) => match tokenizer.next_token()? {
+                    Some(Token::Hex(dst)) if src.len() <= 4 => {
+                        cmap.mappings
+                            .insert(Self::code_from_bytes(&src), Self::decode_utf16be(&dst));
+                    }
+                    Some(Token::Word(b"endbfchar")) | None => break,
+                    _ => {} // Skip invalid entries
+                },
+                Some(Token::Word(b"endbfchar")) | None => break,
+                Some(_) => {}
             }
-
-            let src_code = Self::parse_hex_code(parts[0])?;
-            let dst_unicode = Self::parse_hex_unicode(parts[1])?;
-
-            cmap.mappings.insert(src_code, dst_unicode);
         }
-
         Ok(())
     }
 
-    /// Parses bfrange entries (range mappings).
+    /// Parses bfrange entries (range mappings) until `endbfrange`.
     ///
-    /// Format: `<srcCodeLo> <srcCodeHi> <dstUnicode>`
-    /// Example: `<0010> <0020> <0030>` maps CIDs 0x10-0x20 to Unicode U+0030-U+0040
-    fn parse_bfrange<'a, I>(cmap: &mut CMap, lines: &mut I, count: usize) -> PDFResult<()>
-    where
-        I: Iterator<Item = &'a str>,
-    {
-        for _ in 0..count {
-            let line = lines.next().ok_or_else(|| {
-                PDFError::Generic("Unexpected end of bfrange section".to_string())
-            })?;
-
-            let trimmed = line.trim();
-
-            // Check for end marker
-            if trimmed == "endbfrange" {
+    /// Formats:
+    /// - `<srcCodeLo> <srcCodeHi> <dstString>`: consecutive codes map to
+    ///   `dstString` with its last UTF-16 unit incremented.
+    ///   Example: `<0010> <0020> <0030>` maps CIDs 0x10-0x20 to U+0030-U+0040
+    /// - `<srcCodeLo> <srcCodeHi> [<dst1> <dst2> ...]`: one destination per code
+    fn parse_bfrange(cmap: &mut CMap, tokenizer: &mut Tokenizer) -> PDFResult<()> {
+        loop {
+            let low = match tokenizer.next_token()? {
+                Some(Token::Hex(low)) => low,
+                Some(Token::Word(b"endbfrange")) | None => break,
+                Some(_) => continue,
+            };
+            let Some(Token::Hex(high)) = tokenizer.next_token()? else {
                 break;
+            };
+            if low.len() > 4 || high.len() > 4 {
+                continue;
             }
-
-            // Parse the range: <srcCodeLo> <srcCodeHi> <dstUnicode>
-            let parts: Vec<&str> = trimmed.split_whitespace().collect();
-            if parts.len() < 3 {
-                continue; // Skip invalid lines
+            let low = Self::code_from_bytes(&low);
+            let high = Self::code_from_bytes(&high);
+            if high < low || high - low > MAX_MAP_RANGE {
+                tracing::warn!(
+                    target: log_target::FONT,
+                    "Ignoring invalid bfrange <{:X}> <{:X}> in CMap",
+                    low, high
+                );
+                // Still consume the destination
+                if let Some(Token::ArrayStart) = tokenizer.next_token()? {
+                    while !matches!(tokenizer.next_token()?, Some(Token::ArrayEnd) | None) {}
+                }
+                continue;
             }
 
-            let src_code_lo = Self::parse_hex_code(parts[0])?;
-            let src_code_hi = Self::parse_hex_code(parts[1])?;
-            let dst_unicode = Self::parse_hex_unicode(parts[2])?;
-
-            // Map the range
-            let dst_code = dst_unicode as u32;
-            for (i, src_code) in (src_code_lo..=src_code_hi).enumerate() {
-                if let Some(unicode_char) = char::from_u32(dst_code + i as u32) {
-                    cmap.mappings.insert(src_code, unicode_char);
+            match tokenizer.next_token()? {
+                Some(Token::Hex(dst)) => {
+                    let mut units = Self::utf16_units(&dst);
+                    for code in low..=high {
+                        cmap.mappings.insert(code, String::from_utf16_lossy(&units));
+                        if let Some(last) = units.last_mut() {
+                            *last = last.wrapping_add(1);
+                        }
+                    }
                 }
+                Some(Token::ArrayStart) => {
+                    // None once the range is used up, even at the top of the
+                    // code space
+                    let mut code = Some(low);
+                    loop {
+                        match tokenizer.next_token()? {
+                            Some(Token::Hex(dst)) => {
+                                if let Some(current) = code.filter(|&c| c <= high) {
+                                    cmap.mappings.insert(current, Self::decode_utf16be(&dst));
+                                }
+                                code = code.and_then(|c| c.checked_add(1));
+                            }
+                            Some(Token::ArrayEnd) | None => break,
+                            Some(_) => {}
+                        }
+                    }
+                }
+                Some(Token::Word(b"endbfrange")) | None => break,
+                _ => {}
             }
         }
-
         Ok(())
     }
 
-    /// Parses a hex code like `<0003>` or `<03>` into a u16.
-    #[inline]
-    fn parse_hex_code(hex_str: &str) -> PDFResult<u16> {
-        // Remove angle brackets
-        let hex = hex_str.trim_start_matches('<').trim_end_matches('>');
-
-        u16::from_str_radix(hex, 16)
-            .map_err(|_| PDFError::Generic(format!("Invalid hex code: '{}'", hex_str)))
+    /// Parses cidchar (`<code> cid`) or cidrange (`<low> <high> cid`) entries
+    /// until `end`.
+    fn parse_cid_mappings(
+        cmap: &mut CMap,
+        tokenizer: &mut Tokenizer,
+        end: &[u8],
+        range: bool,
+    ) -> PDFResult<()> {
+        loop {
+            let low = match tokenizer.next_token()? {
+                Some(Token::Hex(low)) => low,
+                Some(Token::Word(word)) if word == end => break,
+                None => break,
+                Some(_) => continue,
+            };
+            let high = if range {
+                match tokenizer.next_token()? {
+                    Some(Token::Hex(high)) => high,
+                    _ => break,
+                }
+            } else {
+                low.clone()
+            };
+            let cid = match tokenizer.next_token()? {
+                Some(Token::Word(word)) => std::str::from_utf8(word)
+                    .ok()
+                    .and_then(|cid| cid.parse::<u32>().ok()),
+                _ => None,
+            };
+            if let Some(cid) = cid
+                && low.len() <= 4
+                && high.len() <= 4
+            {
+                let (low, high) = (Self::code_from_bytes(&low), Self::code_from_bytes(&high));
+                if low <= high {
+                    cmap.cid_ranges.push((low, high, cid));
+                }
+            }
+        }
+        Ok(())
     }
 
-    /// Parses a hex Unicode value like `<0020>` into a char.
+    /// Combines big-endian code bytes into a code.
     #[inline]
-    fn parse_hex_unicode(hex_str: &str) -> PDFResult<char> {
-        // Remove angle brackets
-        let hex = hex_str.trim_start_matches('<').trim_end_matches('>');
+    fn code_from_bytes(bytes: &[u8]) -> u32 {
+        bytes.iter().fold(0u32, |code, &b| code << 8 | b as u32)
+    }
 
-        let code = u32::from_str_radix(hex, 16)
-            .map_err(|_| PDFError::Generic(format!("Invalid hex Unicode: '{}'", hex_str)))?;
+    /// Splits a destination string into UTF-16 code units.
+    ///
+    /// A lone byte is treated as a single code unit, which some producers
+    /// emit for Latin-1 destinations.
+    fn utf16_units(bytes: &[u8]) -> Vec<u16> {
+        if bytes.len() == 1 {
+            return vec![bytes[0] as u16];
+        }
+        bytes
+            .chunks_exact(2)
+            .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+            .collect()
+    }
 
-        char::from_u32(code)
-            .ok_or_else(|| PDFError::Generic(format!("Invalid Unicode code point: 0x{:X}", code)))
+    /// Decodes a UTF-16BE destination string, combining surrogate pairs.
+    ///
+    /// Unpaired surrogates become U+FFFD.
+    fn decode_utf16be(bytes: &[u8]) -> String {
+        String::from_utf16_lossy(&Self::utf16_units(bytes))
     }
 
     /// Maps a character code (CID) to Unicode.
     ///
+    /// Only the first character of multi-character mappings is returned;
+    /// use [`lookup`](Self::lookup) for the full string.
+    ///
     /// # Arguments
     /// * `cid` - The character ID to look up
     ///
@@ -224,7 +458,59 @@ impl CMap {
     /// ```
     #[inline(always)] // Hot path - called for every character during text extraction
     pub fn to_unicode(&self, cid: u16) -> Option<char> {
-        self.mappings.get(&cid).copied()
+        self.mappings.get(&(cid as u32))?.chars().next()
+    }
+
+    /// Maps a character code to its full Unicode string.
+    ///
+    /// Codes may map to several characters (ligatures) or to characters
+    /// outside the BMP.
+    #[inline(always)] // Hot path - called for every character during text extraction
+    pub fn lookup(&self, code: u32) -> Option<&str> {
+        self.mappings.get(&code).map(String::as_str)
+    }
+
+    /// Maps a character code to a CID using cidchar/cidrange mappings.
+    ///
+    /// Later entries take precedence, as they would when defined in order.
+    pub fn lookup_cid(&self, code: u32) -> Option<u32> {
+        self.cid_ranges
+            .iter()
+            .rev()
+            .find(|(low, high, _)| (*low..=*high).contains(&code))
+            .map(|(low, _, cid)| cid + (code - low))
+    }
+
+    /// Returns true if the CMap declares vertical writing (/WMode 1).
+    pub fn is_vertical(&self) -> bool {
+        self.vertical
+    }
+
+    /// Returns the codespace ranges declared by the CMap.
+    pub fn codespace_ranges(&self) -> &[CodespaceRange] {
+        &self.codespace_ranges
+    }
+
+    /// Reads one character code from `bytes` at `offset` using the codespace ranges.
+    ///
+    /// Tries 1-byte codes first, then longer ones, as in pdf.js readCharCode.
+    /// Without a matching range a single byte is consumed.
+    ///
+    /// # Returns
+    /// The code and the number of bytes it occupies
+    pub fn read_code(&self, bytes: &[u8], offset: usize) -> (u32, usize) {
+        let mut code = 0u32;
+        for n in 1..=4.min(bytes.len().saturating_sub(offset)) {
+            code = code << 8 | bytes[offset + n - 1] as u32;
+            if self
+                .codespace_ranges
+                .iter()
+                .any(|r| r.bytes == n && (r.low..=r.high).contains(&code))
+            {
+                return (code, n);
+            }
+        }
+        (bytes.get(offset).copied().unwrap_or(0) as u32, 1)
     }
 
     /// Returns the number of mappings in this CMap.
@@ -339,24 +625,23 @@ endbfchar
 
     #[test]
     fn test_parse_hex_codes() {
-        // Test various hex formats
-        assert_eq!(CMap::parse_hex_code("<03>").unwrap(), 3);
-        assert_eq!(CMap::parse_hex_code("<0003>").unwrap(), 3);
-        assert_eq!(CMap::parse_hex_code("<00AB>").unwrap(), 0xAB);
-        assert_eq!(CMap::parse_hex_code("<FFFF>").unwrap(), 0xFFFF);
-    }
-
-    #[test]
-    fn test_parse_hex_unicode() {
-        assert_eq!(CMap::parse_hex_unicode("<0020>").unwrap(), ' ');
-        assert_eq!(CMap::parse_hex_unicode("<0041>").unwrap(), 'A');
-        assert_eq!(CMap::parse_hex_unicode("<4E2D>").unwrap(), '中');
-    }
-
-    #[test]
-    fn test_invalid_hex_code() {
-        assert!(CMap::parse_hex_code("<GGGG>").is_err());
-        assert!(CMap::parse_hex_code("not-hex").is_err());
+        let cmap_data = b"\
+3 beginbfchar
+<03> <0020>
+<00AB> <4E2D>
+<FFFF> <0041>
+endbfchar
+1 beginbfrange
+<FFFFFFFE> <FFFFFFFF> [<0042> <0043> <0044>]
+endbfrange
+";
+        let cmap = CMap::parse(cmap_data).unwrap();
+        assert_eq!(cmap.to_unicode(3), Some(' '));
+        assert_eq!(cmap.to_unicode(0xAB), Some('中'));
+        assert_eq!(cmap.to_unicode(0xFFFF), Some('A'));
+        // Extra array entries past the top of the code space are ignored
+        assert_eq!(cmap.lookup(0xFFFF_FFFE), Some("B"));
+        assert_eq!(cmap.lookup(0xFFFF_FFFF), Some("C"));
     }
 
     #[test]
@@ -401,4 +686,91 @@ end
 
         assert_eq!(cmap.len(), 19); // 2 bfchar + 17 bfrange
     }
+
+    #[test]
+    fn test_surrogate_pairs_and_ligatures() {
+        let cmap_data = b"\
+3 beginbfchar
+<01> <D835DC00>
+<02> <006600660069>
+<03> <>
+endbfchar
+";
+        let cmap = CMap::parse(cmap_data).unwrap();
+        assert_eq!(cmap.lookup(1), Some("\u{1D400}"));
+        assert_eq!(cmap.to_unicode(1), Some('\u{1D400}'));
+        assert_eq!(cmap.lookup(2), Some("ffi"));
+        assert_eq!(cmap.to_unicode(2), Some('f'));
+        assert_eq!(cmap.lookup(3), Some(""));
+    }
+
+    #[test]
+    fn test_bfrange_array_and_inline_entries() {
+        // Entries on one line, array destinations, and a multi-unit
+        // destination whose last unit is incremented
+        let cmap_data = b"\
+2 beginbfrange <20> <22> [<0041> <D835DC01> <0066006C>] <30> <31> <00660066> endbfrange
+";
+        let cmap = CMap::parse(cmap_data).unwrap();
+        assert_eq!(cmap.lookup(0x20), Some("A"));
+        assert_eq!(cmap.lookup(0x21), Some("\u{1D401}"));
+        assert_eq!(cmap.lookup(0x22), Some("fl"));
+        assert_eq!(cmap.lookup(0x30), Some("ff"));
+        assert_eq!(cmap.lookup(0x31), Some("fg"));
+    }
+
+    #[test]
+    fn test_codespace_ranges_and_read_code() {
+        // Mixed 1-byte and 2-byte codespace, as in Shift-JIS style CMaps
+        let cmap_data = b"\
+2 begincodespacerange
+<00> <80>
+<8140> <9FFC>
+endcodespacerange
+1 beginbfchar
+<8140> <3000>
+endbfchar
+";
+        let cmap = CMap::parse(cmap_data).unwrap();
+        assert_eq!(cmap.codespace_ranges().len(), 2);
+
+        let bytes = [0x41, 0x81, 0x40];
+        assert_eq!(cmap.read_code(&bytes, 0), (0x41, 1));
+        assert_eq!(cmap.read_code(&bytes, 1), (0x8140, 2));
+        assert_eq!(cmap.lookup(0x8140), Some("\u{3000}"));
+    }
+
+    #[test]
+    fn test_binary_data_and_invalid_ranges_are_tolerated() {
+        let mut cmap_data = b"%\xFF\xFE binary comment\n".to_vec();
+        cmap_data.extend_from_slice(
+            b"1 beginbfrange\n<0000> <FFFFFF> <0041>\nendbfrange\n\
+              1 beginbfchar\n<05> <0042>\nendbfchar\n",
+        );
+        let cmap = CMap::parse(&cmap_data).unwrap();
+        assert_eq!(cmap.len(), 1);
+        assert_eq!(cmap.to_unicode(5), Some('B'));
+    }
+
+    #[test]
+    fn test_cid_mappings_and_wmode() {
+        let cmap_data = b"\
+/CMapName /Custom-V def
+/WMode 1 def
+1 begincodespacerange <0000> <FFFF> endcodespacerange
+1 begincidrange
+<0020> <007E> 1
+endcidrange
+1 begincidchar
+<0041> 500
+endcidchar
+";
+        let cmap = CMap::parse(cmap_data).unwrap();
+        assert!(cmap.is_vertical());
+        assert_eq!(cmap.lookup_cid(0x20), Some(1));
+        assert_eq!(cmap.lookup_cid(0x22), Some(3));
+        assert_eq!(cmap.lookup_cid(0x41), Some(500));
+        assert_eq!(cmap.lookup_cid(0x10), None);
+        assert!(cmap.is_empty());
+    }
 }
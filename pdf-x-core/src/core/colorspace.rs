@@ -0,0 +1,678 @@
+//! Color spaces (PDF spec section 8.6).
+//!
+//! Parses every standard color space family and converts component values
+//! to RGB for display:
+//!
+//! - Device spaces: DeviceGray, DeviceRGB, DeviceCMYK
+//! - CIE-based spaces: CalGray, CalRGB, Lab, ICCBased (through its alternate)
+//! - Special spaces: Indexed, Separation, DeviceN, Pattern
+//!
+//! ICC profiles are not interpreted; ICCBased spaces are converted with their
+//! /Alternate space, or the device space with the same number of components.
+//!
+//! Based on PDF.js src/core/colorspace.js
+
+use super::decode::decode_stream_data_limited;
+use super::error::{PDFError, PDFResult};
+use super::function::PDFFunction;
+use super::parser::PDFObject;
+use super::xref::XRef;
+
+/// D65 white point, the white of sRGB.
+const D65: [f64; 3] = [0.95047, 1.0, 1.08883];
+
+/// A parsed color space.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColorSpace {
+    DeviceGray,
+    DeviceRGB,
+    DeviceCMYK,
+    /// CIE-based gray: A^gamma gives the luminance
+    CalGray {
+        white_point: [f64; 3],
+        gamma: f64,
+    },
+    /// CIE-based RGB: gamma per component, then a matrix into XYZ
+    CalRGB {
+        white_point: [f64; 3],
+        gamma: [f64; 3],
+        matrix: [f64; 9],
+    },
+    /// CIE L*a*b*, with the valid a* and b* ranges [amin amax bmin bmax]
+    Lab {
+        white_point: [f64; 3],
+        range: [f64; 4],
+    },
+    /// ICC profile with `components` inputs, converted through `alternate`
+    ICCBased {
+        components: usize,
+        alternate: Box<ColorSpace>,
+    },
+    /// Palette of `hival + 1` colors in the base space
+    Indexed {
+        base: Box<ColorSpace>,
+        hival: usize,
+        lookup: Vec<u8>,
+    },
+    /// A single colorant, mapped into `alternate` by the tint transform
+    Separation {
+        name: String,
+        alternate: Box<ColorSpace>,
+        tint_transform: PDFFunction,
+    },
+    /// Several colorants, mapped into `alternate` by the tint transform
+    DeviceN {
+        names: Vec<String>,
+        alternate: Box<ColorSpace>,
+        tint_transform: PDFFunction,
+    },
+    /// Pattern space; `base` is the space of uncolored pattern colors
+    Pattern {
+        base: Option<Box<ColorSpace>>,
+    },
+}
+
+impl ColorSpace {
+    /// Returns the color space for a family name that needs no parameters
+    /// (including the abbreviations used in inline images).
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "DeviceGray" | "G" => Some(ColorSpace::DeviceGray),
+            "DeviceRGB" | "RGB" => Some(ColorSpace::DeviceRGB),
+            "DeviceCMYK" | "CMYK" => Some(ColorSpace::DeviceCMYK),
+            "Pattern" => Some(ColorSpace::Pattern { base: None }),
+            _ => None,
+        }
+    }
+
+    /// Parses a color space name or array, resolving references.
+    ///
+    /// Names other than the device families (e.g. resource names like /CS0)
+    /// must be looked up by the caller first.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use pdf_x_core::core::{ColorSpace, PDFObject, XRef};
+    ///
+    /// # fn example(xref: &mut XRef, obj: &PDFObject) -> pdf_x_core::core::error::PDFResult<()> {
+    /// let color_space = ColorSpace::parse(obj, xref)?;
+    /// println!("{:?}", color_space.to_rgb(&[0.5]));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn parse(obj: &PDFObject, xref: &mut XRef) -> PDFResult<Self> {
+        Self::parse_nested(obj, xref, 0)
+    }
+
+    /// Base and alternate spaces nest at most `max_object_depth` deep,
+    /// which also stops reference cycles.
+    fn parse_nested(obj: &PDFObject, xref: &mut XRef, depth: usize) -> PDFResult<Self> {
+        let max_depth = xref.limits().max_object_depth;
+        if depth > max_depth {
+            return Err(PDFError::limit_exceeded(
+                "color space nesting depth",
+                max_depth,
+            ));
+        }
+
+        let obj = xref.fetch_if_ref(obj)?;
+        let items: Vec<PDFObject> = match &obj {
+            PDFObject::Name(name) => {
+                return Self::from_name(name)
+                    .ok_or_else(|| PDFError::Generic(format!("Unknown color space /{}", name)));
+            }
+            PDFObject::Array(items) => items.iter().map(|item| (**item).clone()).collect(),
+            _ => {
+                return Err(PDFError::Generic(format!(
+                    "Color space must be a name or array, got {:?}",
+                    obj
+                )));
+            }
+        };
+        let family = items.first().and_then(|f| f.as_name()).unwrap_or("");
+        match family {
+            "DeviceGray" | "G" | "DeviceRGB" | "RGB" | "DeviceCMYK" | "CMYK" => {
+                Ok(Self::from_name(family).unwrap())
+            }
+            "Pattern" => Ok(ColorSpace::Pattern {
+                base: items
+                    .get(1)
+                    .map(|b| Self::parse_entry(Some(b), family, xref, depth))
+                    .transpose()?,
+            }),
+            "CalGray" | "CalRGB" | "Lab" => {
+                let dict = match items.get(1).map(|d| xref.fetch_if_ref(d)).transpose()? {
+                    Some(PDFObject::Dictionary(dict)) => dict,
+                    _ => {
+                        return Err(PDFError::Generic(format!(
+                            "{} color space without a dictionary",
+                            family
+                        )));
+                    }
+                };
+                let numbers = |key: &str| -> Option<Vec<f64>> {
+                    dict.get(key)?
+                        .as_array()?
+                        .iter()
+                        .map(|n| n.as_number())
+                        .collect()
+                };
+                let white_point = match numbers("WhitePoint").as_deref() {
+                    Some(&[x, y, z]) if x > 0.0 && y > 0.0 && z > 0.0 => [x, y, z],
+                    _ => D65,
+                };
+                Ok(match family {
+                    "CalGray" => ColorSpace::CalGray {
+                        white_point,
+                        gamma: dict.get("Gamma").and_then(|g| g.as_number()).unwrap_or(1.0),
+                    },
+                    "CalRGB" => ColorSpace::CalRGB {
+                        white_point,
+                        gamma: match numbers("Gamma").as_deref() {
+                            Some(&[r, g, b]) => [r, g, b],
+                            _ => [1.0; 3],
+                        },
+                        matrix: numbers("Matrix")
+                            .and_then(|m| m.try_into().ok())
+                            .unwrap_or([1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0]),
+                    },
+                    _ => ColorSpace::Lab {
+                        white_point,
+                        range: match numbers("Range").as_deref() {
+                            Some(&[amin, amax, bmin, bmax]) => [amin, amax, bmin, bmax],
+                            _ => [-100.0, 100.0, -100.0, 100.0],
+                        },
+                    },
+                })
+            }
+            "ICCBased" => {
+                let dict = match items.get(1).map(|p| xref.fetch_if_ref(p)).transpose()? {
+                    Some(PDFObject::Stream { dict, .. }) => dict,
+                    _ => {
+                        return Err(PDFError::Generic(
+                            "ICCBased color space without a profile stream".to_string(),
+                        ));
+                    }
+                };
+                let components = dict.get("N").and_then(|n| n.as_number()).unwrap_or(0.0) as usize;
+                let alternate = match (dict.get("Alternate"), components) {
+                    (Some(alternate), _) => {
+                        Self::parse_entry(Some(alternate), family, xref, depth)?
+                    }
+                    (None, 1) => Box::new(ColorSpace::DeviceGray),
+                    (None, 3) => Box::new(ColorSpace::DeviceRGB),
+                    (None, 4) => Box::new(ColorSpace::DeviceCMYK),
+                    (None, n) => {
+                        return Err(PDFError::Generic(format!(
+                            "ICCBased color space has an invalid /N {}",
+                            n
+                        )));
+                    }
+                };
+                Ok(ColorSpace::ICCBased {
+                    components: if components == 0 {
+                        alternate.components()
+                    } else {
+                        components
+                    },
+                    alternate,
+                })
+            }
+            "Indexed" | "I" => {
+                let base = Self::parse_entry(items.get(1), family, xref, depth)?;
+                let hival = items.get(2).and_then(|h| h.as_number()).unwrap_or(0.0);
+                let lookup = match items.get(3).map(|l| xref.fetch_if_ref(l)).transpose()? {
+                    Some(PDFObject::String(bytes) | PDFObject::HexString(bytes)) => bytes,
+                    Some(PDFObject::Stream { dict, data }) => {
+                        decode_stream_data_limited(&dict, &data, xref.limits())?
+                    }
+                    _ => {
+                        return Err(PDFError::Generic(
+                            "Indexed color space without a lookup table".to_string(),
+                        ));
+                    }
+                };
+                Ok(ColorSpace::Indexed {
+                    base,
+                    hival: hival.clamp(0.0, 255.0) as usize,
+                    lookup,
+                })
+            }
+            "Separation" => Ok(ColorSpace::Separation {
+                name: items
+                    .get(1)
+                    .and_then(|n| n.as_name())
+                    .unwrap_or("")
+                    .to_string(),
+                alternate: Self::parse_entry(items.get(2), family, xref, depth)?,
+                tint_transform: match items.get(3) {
+                    Some(function) => PDFFunction::parse(function, xref)?,
+                    None => {
+                        return Err(PDFError::Generic(
+                            "Separation color space without a tint transform".to_string(),
+                        ));
+                    }
+                },
+            }),
+            "DeviceN" => {
+                let names: Vec<String> =
+                    match items.get(1).map(|n| xref.fetch_if_ref(n)).transpose()? {
+                        Some(PDFObject::Array(names)) => names
+                            .iter()
+                            .filter_map(|n| n.as_name().map(str::to_string))
+                            .collect(),
+                        _ => Vec::new(),
+                    };
+                if names.is_empty() {
+                    return Err(PDFError::Generic(
+                        "DeviceN color space without colorant names".to_string(),
+                    ));
+                }
+                Ok(ColorSpace::DeviceN {
+                    names,
+                    alternate: Self::parse_entry(items.get(2), family, xref, depth)?,
+                    tint_transform: match items.get(3) {
+                        Some(function) => PDFFunction::parse(function, xref)?,
+                        None => {
+                            return Err(PDFError::Generic(
+                                "DeviceN color space without a tint transform".to_string(),
+                            ));
+                        }
+                    },
+                })
+            }
+            other => Err(PDFError::unsupported(format!(
+                "{} color space",
+                if other.is_empty() { "Unknown" } else { other }
+            ))),
+        }
+    }
+
+    /// Parses the base or alternate space of a `family` space.
+    fn parse_entry(
+        obj: Option<&PDFObject>,
+        family: &str,
+        xref: &mut XRef,
+        depth: usize,
+    ) -> PDFResult<Box<Self>> {
+        match obj {
+            Some(obj) => Ok(Box::new(Self::parse_nested(obj, xref, depth + 1)?)),
+            None => Err(PDFError::Generic(format!(
+                "{} color space is missing entries",
+                family
+            ))),
+        }
+    }
+
+    /// Returns the number of color components.
+    pub fn components(&self) -> usize {
+        match self {
+            ColorSpace::DeviceGray | ColorSpace::CalGray { .. } => 1,
+            ColorSpace::DeviceRGB | ColorSpace::CalRGB { .. } | ColorSpace::Lab { .. } => 3,
+            ColorSpace::DeviceCMYK => 4,
+            ColorSpace::ICCBased { components, .. } => *components,
+            ColorSpace::Indexed { .. } | ColorSpace::Separation { .. } => 1,
+            ColorSpace::DeviceN { names, .. } => names.len(),
+            ColorSpace::Pattern { base } => base.as_ref().map_or(0, |base| base.components()),
+        }
+    }
+
+    /// Returns the color selected when this space is set with cs/CS.
+    ///
+    /// This is black for most spaces, and full tint for Separation/DeviceN.
+    pub fn initial_color(&self) -> Vec<f64> {
+        match self {
+            ColorSpace::DeviceCMYK => vec![0.0, 0.0, 0.0, 1.0],
+            ColorSpace::Lab { range, .. } => {
+                vec![
+                    0.0,
+                    0.0f64.clamp(range[0], range[1]),
+                    0.0f64.clamp(range[2], range[3]),
+                ]
+            }
+            ColorSpace::Separation { .. } | ColorSpace::DeviceN { .. } => {
+                vec![1.0; self.components()]
+            }
+            _ => vec![0.0; self.components()],
+        }
+    }
+
+    /// Returns the default /Decode array for image samples with
+    /// `bits_per_component` bits.
+    pub fn default_decode(&self, bits_per_component: u8) -> Vec<f64> {
+        match self {
+            ColorSpace::Indexed { .. } => {
+                vec![0.0, ((1u32 << bits_per_component.min(16)) - 1) as f64]
+            }
+            ColorSpace::Lab { range, .. } => {
+                vec![0.0, 100.0, range[0], range[1], range[2], range[3]]
+            }
+            _ => [0.0, 1.0].repeat(self.components()),
+        }
+    }
+
+    /// Converts color components to RGB, each in 0..1.
+    pub fn to_rgb(&self, components: &[f64]) -> [f64; 3] {
+        let c = |i: usize| components.get(i).copied().unwrap_or(0.0);
+        let unit = |i: usize| c(i).clamp(0.0, 1.0);
+        match self {
+            ColorSpace::DeviceGray => [unit(0); 3],
+            ColorSpace::DeviceRGB => [unit(0), unit(1), unit(2)],
+            ColorSpace::DeviceCMYK => {
+                let k = 1.0 - unit(3);
+                [
+                    (1.0 - unit(0)) * k,
+                    (1.0 - unit(1)) * k,
+                    (1.0 - unit(2)) * k,
+                ]
+            }
+            ColorSpace::CalGray { gamma, .. } => [srgb_gamma(unit(0).powf(*gamma)); 3],
+            ColorSpace::CalRGB {
+                white_point,
+                gamma,
+                matrix: m,
+            } => {
+                let (a, b, c) = (
+                    unit(0).powf(gamma[0]),
+                    unit(1).powf(gamma[1]),
+                    unit(2).powf(gamma[2]),
+                );
+                let xyz = [
+                    m[0] * a + m[3] * b + m[6] * c,
+                    m[1] * a + m[4] * b + m[7] * c,
+                    m[2] * a + m[5] * b + m[8] * c,
+                ];
+                xyz_to_srgb(xyz, white_point)
+            }
+            ColorSpace::Lab { white_point, range } => {
+                let l = c(0).clamp(0.0, 100.0);
+                let a = c(1).clamp(range[0], range[1]);
+                let b = c(2).clamp(range[2], range[3]);
+                let g = |t: f64| {
+                    if t > 6.0 / 29.0 {
+                        t * t * t
+                    } else {
+                        3.0 * (6.0f64 / 29.0).powi(2) * (t - 4.0 / 29.0)
+                    }
+                };
+                let fy = (l + 16.0) / 116.0;
+                let xyz = [
+                    white_point[0] * g(fy + a / 500.0),
+                    white_point[1] * g(fy),
+                    white_point[2] * g(fy - b / 200.0),
+                ];
+                xyz_to_srgb(xyz, white_point)
+            }
+            ColorSpace::ICCBased { alternate, .. } => alternate.to_rgb(components),
+            ColorSpace::Indexed {
+                base,
+                hival,
+                lookup,
+            } => {
+                let index = (c(0).round().max(0.0) as usize).min(*hival);
+                let n = base.components();
+                let decode = base.default_decode(8);
+                let values: Vec<f64> = (0..n)
+                    .map(|i| {
+                        let byte = lookup.get(index * n + i).copied().unwrap_or(0) as f64;
+                        let (min, max) = (decode[2 * i], decode[2 * i + 1]);
+                        min + byte / 255.0 * (max - min)
+                    })
+                    .collect();
+                base.to_rgb(&values)
+            }
+            ColorSpace::Separation {
+                alternate,
+                tint_transform,
+                ..
+            }
+            | ColorSpace::DeviceN {
+                alternate,
+                tint_transform,
+                ..
+            } => alternate.to_rgb(&tint_transform.eval(components)),
+            ColorSpace::Pattern { base } => match base {
+                Some(base) => base.to_rgb(components),
+                None => [0.0; 3],
+            },
+        }
+    }
+
+    /// Converts image samples to 8-bit RGB.
+    ///
+    /// # Arguments
+    /// * `data` - Samples, with each row starting on a byte boundary
+    /// * `width`, `height` - Image size in pixels
+    /// * `bits_per_component` - 1 to 16; values outside are clamped
+    /// * `decode` - /Decode array, or `None` for the default
+    ///
+    /// # Returns
+    /// `width * height * 3` bytes; missing samples are treated as zero
+    pub fn image_to_rgb(
+        &self,
+        data: &[u8],
+        width: usize,
+        height: usize,
+        bits_per_component: u8,
+        decode: Option<&[f64]>,
+    ) -> Vec<u8> {
+        let components = self.components().max(1);
+        let bpc = bits_per_component.clamp(1, 16) as usize;
+        let max_sample = ((1u32 << bpc) - 1) as f64;
+        let decode = match decode {
+            Some(decode) if decode.len() >= 2 * components => decode.to_vec(),
+            _ => self.default_decode(bits_per_component),
+        };
+        let to_byte = |rgb: [f64; 3]| rgb.map(|v| (v.clamp(0.0, 1.0) * 255.0).round() as u8);
+        let decode_sample = |i: usize, raw: u32| {
+            let (min, max) = (decode[2 * i], decode[2 * i + 1]);
+            min + raw as f64 * (max - min) / max_sample
+        };
+
+        // Single-component spaces with few sample values (Indexed,
+        // Separation, gray) are converted through a table
+        let table: Option<Vec<[u8; 3]>> = (components == 1 && bpc <= 8).then(|| {
+            (0..=max_sample as u32)
+                .map(|raw| to_byte(self.to_rgb(&[decode_sample(0, raw)])))
+                .collect()
+        });
+
+        let row_bytes = (width * components * bpc).div_ceil(8);
+        let mut rgb = Vec::with_capacity(width * height * 3);
+        let mut values = vec![0.0; components];
+        for row in 0..height {
+            let row_data = data.get(row * row_bytes..).unwrap_or(&[]);
+            for x in 0..width {
+                for (i, value) in values.iter_mut().enumerate() {
+                    let raw = read_sample(row_data, (x * components + i) * bpc, bpc);
+                    if let Some(table) = &table {
+                        rgb.extend_from_slice(&table[raw as usize]);
+                    } else {
+                        *value = decode_sample(i, raw);
+                    }
+                }
+                if table.is_none() {
+                    rgb.extend_from_slice(&to_byte(self.to_rgb(&values)));
+                }
+            }
+        }
+        rgb
+    }
+}
+
+/// Reads the `bits`-bit sample starting at bit `offset`, or 0 past the end.
+fn read_sample(data: &[u8], offset: usize, bits: usize) -> u32 {
+    match bits {
+        8 => data.get(offset / 8).copied().unwrap_or(0) as u32,
+        16 => {
+            let i = offset / 8;
+            match data.get(i..i + 2) {
+                Some(bytes) => u16::from_be_bytes([bytes[0], bytes[1]]) as u32,
+                None => 0,
+            }
+        }
+        _ if offset % 8 + bits <= 8 => {
+            let byte = data.get(offset / 8).copied().unwrap_or(0) as u32;
+            let shift = 8 - bits - offset % 8;
+            (byte >> shift) & ((1 << bits) - 1)
+        }
+        // Samples that straddle bytes (e.g. 12 bits per component)
+        _ => (offset..offset + bits).fold(0, |value, bit| {
+            let byte = data.get(bit / 8).copied().unwrap_or(0);
+            (value << 1) | ((byte >> (7 - bit % 8)) & 1) as u32
+        }),
+    }
+}
+
+/// Applies the sRGB transfer curve to a linear value.
+fn srgb_gamma(linear: f64) -> f64 {
+    let linear = linear.clamp(0.0, 1.0);
+    if linear <= 0.0031308 {
+        12.92 * linear
+    } else {
+        1.055 * linear.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Converts CIE XYZ relative to `white_point` to sRGB, scaling each
+/// component so the white point maps to D65 white.
+fn xyz_to_srgb(xyz: [f64; 3], white_point: &[f64; 3]) -> [f64; 3] {
+    let [x, y, z] = [0, 1, 2].map(|i| xyz[i] * D65[i] / white_point[i]);
+    [
+        3.2404542 * x - 1.5371385 * y - 0.4985314 * z,
+        -0.969266 * x + 1.8760108 * y + 0.041556 * z,
+        0.0556434 * x - 0.2040259 * y + 1.0572252 * z,
+    ]
+    .map(srgb_gamma)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Stream;
+
+    fn parse(source: &str) -> PDFResult<ColorSpace> {
+        let lexer =
+            crate::core::Lexer::new(Box::new(Stream::from_bytes(source.as_bytes().to_vec())))?;
+        let obj = crate::core::Parser::new(lexer)?.get_object()?;
+        let mut xref = XRef::new(Box::new(Stream::from_bytes(Vec::new())));
+        ColorSpace::parse(&obj, &mut xref)
+    }
+
+    fn assert_rgb(actual: [f64; 3], expected: [f64; 3]) {
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-3, "{:?} vs {:?}", actual, expected);
+        }
+    }
+
+    #[test]
+    fn test_device_and_cie_spaces() {
+        assert_eq!(parse("/DeviceCMYK").unwrap(), ColorSpace::DeviceCMYK);
+        assert_rgb(
+            ColorSpace::DeviceCMYK.to_rgb(&[0.0, 1.0, 0.0, 0.5]),
+            [0.5, 0.0, 0.5],
+        );
+        assert_eq!(
+            ColorSpace::DeviceCMYK.initial_color(),
+            vec![0.0, 0.0, 0.0, 1.0]
+        );
+
+        let cal_rgb = parse(
+            "[/CalRGB << /WhitePoint [0.9505 1 1.089] /Gamma [2.2 2.2 2.2] \
+             /Matrix [0.4124 0.2126 0.0193 0.3576 0.7152 0.1192 0.1805 0.0722 0.9505] >>]",
+        )
+        .unwrap();
+        assert_rgb(cal_rgb.to_rgb(&[1.0, 1.0, 1.0]), [1.0, 1.0, 1.0]);
+        assert_rgb(cal_rgb.to_rgb(&[0.0, 0.0, 0.0]), [0.0, 0.0, 0.0]);
+
+        let lab =
+            parse("[/Lab << /WhitePoint [0.9505 1 1.089] /Range [-128 127 -128 127] >>]").unwrap();
+        assert_eq!(lab.components(), 3);
+        assert_rgb(lab.to_rgb(&[100.0, 0.0, 0.0]), [1.0, 1.0, 1.0]);
+        assert_rgb(lab.to_rgb(&[0.0, 0.0, 0.0]), [0.0, 0.0, 0.0]);
+        let red = lab.to_rgb(&[53.24, 80.09, 67.2]);
+        assert!(red[0] > 0.95 && red[1] < 0.05 && red[2] < 0.05, "{:?}", red);
+
+        let gray = parse("[/CalGray << /WhitePoint [0.9505 1 1.089] /Gamma 1 >>]").unwrap();
+        assert_rgb(gray.to_rgb(&[1.0]), [1.0, 1.0, 1.0]);
+
+        assert!(matches!(parse("[/Foo]"), Err(PDFError::Unsupported { .. })));
+    }
+
+    #[test]
+    fn test_special_spaces() {
+        let indexed = parse("[/Indexed /DeviceRGB 1 <FF000000FF00>]").unwrap();
+        assert_eq!(indexed.components(), 1);
+        assert_rgb(indexed.to_rgb(&[1.0]), [0.0, 1.0, 0.0]);
+        // Out-of-range indices are clamped to hival
+        assert_rgb(indexed.to_rgb(&[7.0]), [0.0, 1.0, 0.0]);
+
+        let separation = parse(
+            "[/Separation /Spot /DeviceCMYK << /FunctionType 2 /Domain [0 1] \
+             /C0 [0 0 0 0] /C1 [0 0 0 1] /N 1 >>]",
+        )
+        .unwrap();
+        assert_eq!(separation.initial_color(), vec![1.0]);
+        assert_rgb(separation.to_rgb(&[0.25]), [0.75, 0.75, 0.75]);
+
+        let device_n = parse(
+            "[/DeviceN [/Cyan /Spot] /DeviceCMYK << /FunctionType 4 /Domain [0 1 0 1] \
+             /Range [0 1 0 1 0 1 0 1] /Length 20 >>]",
+        );
+        // A Type 4 function must be a stream
+        assert!(device_n.is_err());
+
+        let pattern = parse("[/Pattern /DeviceRGB]").unwrap();
+        assert_eq!(pattern.components(), 3);
+        assert_eq!(
+            parse("/Pattern").unwrap(),
+            ColorSpace::Pattern { base: None }
+        );
+    }
+
+    #[test]
+    fn test_nesting_limit() {
+        use crate::core::ResourceLimits;
+
+        let source = "[/Pattern [/Pattern [/Pattern /DeviceRGB]]]";
+        let lexer =
+            crate::core::Lexer::new(Box::new(Stream::from_bytes(source.as_bytes().to_vec())))
+                .unwrap();
+        let obj = crate::core::Parser::new(lexer)
+            .unwrap()
+            .get_object()
+            .unwrap();
+        let mut xref = XRef::new(Box::new(Stream::from_bytes(Vec::new())));
+        assert!(ColorSpace::parse(&obj, &mut xref).is_ok());
+
+        xref.set_limits(ResourceLimits {
+            max_object_depth: 2,
+            ..ResourceLimits::default()
+        });
+        assert!(matches!(
+            ColorSpace::parse(&obj, &mut xref),
+            Err(PDFError::LimitExceeded { max: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn test_image_to_rgb() {
+        let indexed = parse("[/Indexed /DeviceRGB 1 <FF000000FF00>]").unwrap();
+        // 2x2 1-bit image, rows padded to a byte: 01, 10
+        let rgb = indexed.image_to_rgb(&[0b0100_0000, 0b1000_0000], 2, 2, 1, None);
+        assert_eq!(rgb, vec![255, 0, 0, 0, 255, 0, 0, 255, 0, 255, 0, 0]);
+
+        // An inverting /Decode on 4-bit gray
+        let rgb = ColorSpace::DeviceGray.image_to_rgb(&[0x0F], 2, 1, 4, Some(&[1.0, 0.0]));
+        assert_eq!(rgb, vec![255, 255, 255, 0, 0, 0]);
+
+        // Samples that straddle bytes: 12-bit and 3-bit gray
+        let rgb = ColorSpace::DeviceGray.image_to_rgb(&[0xFF, 0xF0, 0x00], 2, 1, 12, None);
+        assert_eq!(rgb, vec![255, 255, 255, 0, 0, 0]);
+        let rgb = ColorSpace::DeviceGray.image_to_rgb(&[0b1110_0010, 0], 3, 1, 3, None);
+        assert_eq!(rgb, vec![255, 255, 255, 0, 0, 0, 146, 146, 146]);
+
+        let cmyk = ColorSpace::DeviceCMYK.image_to_rgb(&[0, 255, 0, 0], 1, 1, 8, None);
+        assert_eq!(cmyk, vec![255, 0, 255]);
+    }
+}
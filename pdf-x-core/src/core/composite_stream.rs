@@ -0,0 +1,296 @@
+use super::base_stream::BaseStream;
+use super::chunk_manager::ChunkLoader;
+use super::error::{PDFError, PDFResult};
+use super::loader_chunked_stream::LoaderChunkedStream;
+
+/// Chunk loader that serves locally known byte ranges and defers to a remote
+/// loader for everything else.
+///
+/// Known ranges are kept sorted and disjoint; adjacent or overlapping ranges are
+/// merged on insert, with newer bytes winning on overlap.
+struct OverlayLoader<L: ChunkLoader> {
+    remote: L,
+    /// Sorted, non-overlapping (offset, bytes) ranges
+    known: Vec<(usize, Vec<u8>)>,
+    /// Number of chunks fetched from the remote loader
+    remote_requests: usize,
+}
+
+impl<L: ChunkLoader> OverlayLoader<L> {
+    fn add_range(&mut self, offset: usize, data: Vec<u8>) {
+        if data.is_empty() {
+            return;
+        }
+        let mut start = offset;
+        let mut end = offset + data.len();
+
+        // Collect every range that touches [start, end) and merge them
+        let first = self.known.partition_point(|(o, d)| o + d.len() < start);
+        let mut last = first;
+        while last < self.known.len() && self.known[last].0 <= end {
+            last += 1;
+        }
+        if first < last {
+            start = start.min(self.known[first].0);
+            let (o, d) = &self.known[last - 1];
+            end = end.max(o + d.len());
+        }
+
+        let mut merged = vec![0u8; end - start];
+        for (o, d) in &self.known[first..last] {
+            merged[o - start..o - start + d.len()].copy_from_slice(d);
+        }
+        merged[offset - start..offset - start + data.len()].copy_from_slice(&data);
+
+        self.known.splice(first..last, [(start, merged)]);
+    }
+
+    /// Returns the known bytes for `begin..end` if the range is fully covered.
+    fn known_slice(&self, begin: usize, end: usize) -> Option<&[u8]> {
+        let idx = self.known.partition_point(|(o, _)| *o <= begin);
+        let (o, d) = self.known.get(idx.checked_sub(1)?)?;
+        (o + d.len() >= end).then(|| &d[begin - o..end - o])
+    }
+}
+
+impl<L: ChunkLoader> ChunkLoader for OverlayLoader<L> {
+    fn request_chunk(&mut self, chunk_num: usize) -> PDFResult<Vec<u8>> {
+        let chunk_start = chunk_num * self.chunk_size();
+        let chunk_end = std::cmp::min(chunk_start + self.chunk_size(), self.total_length());
+        if let Some(data) = self.known_slice(chunk_start, chunk_end) {
+            return Ok(data.to_vec());
+        }
+
+        // Partially known chunks are fetched whole; the remote is the source of truth
+        self.remote_requests += 1;
+        self.remote.request_chunk(chunk_num)
+    }
+
+    fn chunk_size(&self) -> usize {
+        self.remote.chunk_size()
+    }
+
+    fn total_length(&self) -> usize {
+        self.remote.total_length()
+    }
+}
+
+/// A stream that layers locally cached byte ranges over a remote [`ChunkLoader`].
+///
+/// Useful when an application already holds part of the file (for example the
+/// first N bytes from an upload preview): parsing can start right away, and
+/// only chunks that are not fully covered by known data are fetched from the
+/// remote loader, lazily and through the usual LRU chunk cache.
+///
+/// # Example
+/// ```no_run
+/// use pdf_x_core::core::error::PDFResult;
+/// use pdf_x_core::core::{ChunkLoader, CompositeStream, PDFDocument};
+///
+/// // Stands in for a loader that fetches ranges from a server
+/// struct RemoteLoader(Vec<u8>);
+///
+/// impl ChunkLoader for RemoteLoader {
+///     fn request_chunk(&mut self, chunk_num: usize) -> PDFResult<Vec<u8>> {
+///         let start = chunk_num * 4096;
+///         Ok(self.0[start..(start + 4096).min(self.0.len())].to_vec())
+///     }
+///     fn chunk_size(&self) -> usize {
+///         4096
+///     }
+///     fn total_length(&self) -> usize {
+///         self.0.len()
+///     }
+/// }
+///
+/// let remote = RemoteLoader(std::fs::read("document.pdf").unwrap());
+/// let preview: Vec<u8> = std::fs::read("first-megabyte.bin").unwrap();
+/// let stream = CompositeStream::with_prefix(remote, preview, None).unwrap();
+/// let mut doc = PDFDocument::open_composite(stream).unwrap();
+/// ```
+pub struct CompositeStream<L: ChunkLoader + Send + 'static> {
+    inner: LoaderChunkedStream<OverlayLoader<L>>,
+    max_cached_chunks: Option<usize>,
+}
+
+impl<L: ChunkLoader + Send + 'static> CompositeStream<L> {
+    /// Creates a composite stream with no known data yet.
+    ///
+    /// # Arguments
+    /// * `remote` - Loader for bytes that are not known locally
+    /// * `max_cached_chunks` - Maximum chunks to keep in memory (default: 10)
+    pub fn new(remote: L, max_cached_chunks: Option<usize>) -> PDFResult<Self> {
+        let overlay = OverlayLoader {
+            remote,
+            known: Vec::new(),
+            remote_requests: 0,
+        };
+        Ok(CompositeStream {
+            inner: LoaderChunkedStream::new(overlay, max_cached_chunks)?,
+            max_cached_chunks,
+        })
+    }
+
+    /// Creates a composite stream whose first `prefix.len()` bytes are known.
+    pub fn with_prefix(
+        remote: L,
+        prefix: Vec<u8>,
+        max_cached_chunks: Option<usize>,
+    ) -> PDFResult<Self> {
+        let stream = Self::new(remote, max_cached_chunks)?;
+        stream.add_range(0, prefix)?;
+        Ok(stream)
+    }
+
+    /// Adds locally known bytes starting at `offset`.
+    ///
+    /// Can be called at any time, e.g. as more of an upload arrives. Chunks that
+    /// were already fetched stay cached; new data only affects later requests.
+    pub fn add_range(&self, offset: usize, data: Vec<u8>) -> PDFResult<()> {
+        let end = offset + data.len();
+        if end > self.inner.length() {
+            return Err(PDFError::InvalidByteRange { begin: offset, end });
+        }
+        self.inner
+            .with_loader(|overlay| overlay.add_range(offset, data))
+    }
+
+    /// Returns the known ranges as `(offset, length)` pairs, sorted by offset.
+    pub fn known_ranges(&self) -> Vec<(usize, usize)> {
+        self.inner
+            .with_loader(|overlay| overlay.known.iter().map(|(o, d)| (*o, d.len())).collect())
+            .unwrap_or_default()
+    }
+
+    /// Returns how many chunks have been fetched from the remote loader.
+    pub fn remote_requests(&self) -> usize {
+        self.inner
+            .with_loader(|overlay| overlay.remote_requests)
+            .unwrap_or(0)
+    }
+
+    /// Returns the chunk size in bytes.
+    pub fn chunk_size(&self) -> usize {
+        self.inner.chunk_size()
+    }
+
+    /// Splits the stream into the loader serving the known ranges (and the
+    /// remote for the rest) and the cache size it was created with.
+    pub(crate) fn into_loader(
+        self,
+    ) -> PDFResult<(impl ChunkLoader + Send + 'static, Option<usize>)> {
+        Ok((self.inner.into_loader()?, self.max_cached_chunks))
+    }
+}
+
+impl<L: ChunkLoader + Send + 'static> BaseStream for CompositeStream<L> {
+    fn length(&self) -> usize {
+        self.inner.length()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    fn pos(&self) -> usize {
+        self.inner.pos()
+    }
+
+    fn set_pos(&mut self, pos: usize) -> PDFResult<()> {
+        self.inner.set_pos(pos)
+    }
+
+    fn is_data_loaded(&self) -> bool {
+        self.inner.is_data_loaded()
+    }
+
+    fn ensure_range(&mut self, start: usize, length: usize) -> PDFResult<()> {
+        self.inner.ensure_range(start, length)
+    }
+
+    fn get_byte(&mut self) -> PDFResult<u8> {
+        self.inner.get_byte()
+    }
+
+    fn get_bytes(&mut self, length: usize) -> PDFResult<Vec<u8>> {
+        self.inner.get_bytes(length)
+    }
+
+    fn get_byte_range(&self, begin: usize, end: usize) -> PDFResult<Vec<u8>> {
+        self.inner.get_byte_range(begin, end)
+    }
+
+    fn reset(&mut self) -> PDFResult<()> {
+        self.inner.reset()
+    }
+
+    fn move_start(&mut self) -> PDFResult<()> {
+        self.inner.move_start()
+    }
+
+    fn make_sub_stream(&self, start: usize, length: usize) -> PDFResult<Box<dyn BaseStream>> {
+        self.inner.make_sub_stream(start, length)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct RemoteLoader {
+        data: Vec<u8>,
+    }
+
+    impl ChunkLoader for RemoteLoader {
+        fn request_chunk(&mut self, chunk_num: usize) -> PDFResult<Vec<u8>> {
+            let start = chunk_num * 16;
+            Ok(self.data[start..(start + 16).min(self.data.len())].to_vec())
+        }
+
+        fn chunk_size(&self) -> usize {
+            16
+        }
+
+        fn total_length(&self) -> usize {
+            self.data.len()
+        }
+    }
+
+    fn remote(size: usize) -> RemoteLoader {
+        RemoteLoader {
+            data: (0..size).map(|i| i as u8).collect(),
+        }
+    }
+
+    #[test]
+    fn test_known_prefix_avoids_remote_requests() {
+        let prefix: Vec<u8> = (0..40).collect();
+        let mut stream = CompositeStream::with_prefix(remote(100), prefix, None).unwrap();
+
+        // Chunks 0 and 1 are fully known
+        assert_eq!(stream.get_bytes(32).unwrap(), (0..32).collect::<Vec<u8>>());
+        assert_eq!(stream.remote_requests(), 0);
+
+        // Chunk 2 (32..48) is only partially known, so it comes from the remote
+        assert_eq!(stream.get_bytes(16).unwrap(), (32..48).collect::<Vec<u8>>());
+        assert_eq!(stream.remote_requests(), 1);
+    }
+
+    #[test]
+    fn test_ranges_merge() {
+        let stream = CompositeStream::new(remote(100), None).unwrap();
+        stream.add_range(20, vec![1; 10]).unwrap();
+        stream.add_range(0, vec![2; 10]).unwrap();
+        stream.add_range(10, vec![3; 12]).unwrap();
+        stream.add_range(50, vec![4; 5]).unwrap();
+        assert_eq!(stream.known_ranges(), vec![(0, 30), (50, 5)]);
+
+        // Newer bytes win on overlap
+        let bytes = stream.get_byte_range(0, 16).unwrap();
+        assert_eq!(&bytes[..10], &[2; 10]);
+        assert_eq!(&bytes[10..], &[3; 6]);
+        assert_eq!(stream.remote_requests(), 0);
+
+        assert!(stream.add_range(95, vec![0; 10]).is_err());
+    }
+}
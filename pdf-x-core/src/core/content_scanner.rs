@@ -0,0 +1,836 @@
+//! Zero-copy scanner for content streams.
+//!
+//! The general-purpose [`Lexer`](crate::core::Lexer) reads a byte at a time
+//! through a `BaseStream` and copies every token into an owned buffer, and
+//! the [`Parser`](crate::core::Parser) then wraps each operand in a
+//! `PDFObject`. For content streams, which are already fully decoded in
+//! memory and consist mostly of numbers and short operators, that overhead
+//! dominates.
+//!
+//! [`ContentScanner`] works directly on the decoded bytes instead: names and
+//! strings borrow from the buffer unless they contain escapes, operands are
+//! collected in a `SmallVec`, and operators are looked up from their bytes
+//! with [`OpCode::from_bytes`] without allocating. Tokens are read the same
+//! way as by the lexer, including its recovery rules for malformed numbers.
+
+use crate::core::content_stream::{OpCode, Operation};
+use crate::core::error::{PDFError, PDFResult};
+use crate::core::limits::ResourceLimits;
+use crate::core::parser::{PDFObject, expand_inline_image_key, expand_inline_image_name};
+use crate::log_target;
+use smallvec::SmallVec;
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+/// An operand of a content stream operator, borrowing from the content.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScanOperand<'a> {
+    /// Numeric value (integers and reals)
+    Number(f64),
+
+    /// Boolean value
+    Boolean(bool),
+
+    /// Null value
+    Null,
+
+    /// Name, without the leading '/'
+    Name(Cow<'a, str>),
+
+    /// Literal string
+    String(Cow<'a, [u8]>),
+
+    /// Hexadecimal string, decoded
+    HexString(Vec<u8>),
+
+    /// Array of operands
+    Array(Vec<ScanOperand<'a>>),
+
+    /// Dictionary, in the order the entries appear
+    Dictionary(Vec<(Cow<'a, str>, ScanOperand<'a>)>),
+
+    /// Inline image (the operand of EI): its dictionary, with keys as
+    /// written (/W, /CS, ...), and its raw data
+    InlineImage {
+        dict: Vec<(Cow<'a, str>, ScanOperand<'a>)>,
+        data: &'a [u8],
+    },
+}
+
+impl ScanOperand<'_> {
+    /// Returns the value of a number operand.
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            ScanOperand::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// Converts the operand to an owned `PDFObject`.
+    ///
+    /// Inline images become streams with their abbreviated keys and names
+    /// expanded, as the parser returns them.
+    pub fn to_pdf_object(&self) -> PDFObject {
+        match self {
+            ScanOperand::Number(n) => PDFObject::Number(*n),
+            ScanOperand::Boolean(b) => PDFObject::Boolean(*b),
+            ScanOperand::Null => PDFObject::Null,
+            ScanOperand::Name(name) => PDFObject::Name(name.to_string()),
+            ScanOperand::String(bytes) => PDFObject::String(bytes.to_vec()),
+            ScanOperand::HexString(bytes) => PDFObject::HexString(bytes.clone()),
+            ScanOperand::Array(items) => PDFObject::Array(
+                items
+                    .iter()
+                    .map(|item| Box::new(item.to_pdf_object()))
+                    .collect(),
+            ),
+            ScanOperand::Dictionary(entries) => PDFObject::Dictionary(
+                entries
+                    .iter()
+                    .map(|(key, value)| (key.to_string(), value.to_pdf_object()))
+                    .collect(),
+            ),
+            ScanOperand::InlineImage { dict, data } => {
+                let dict: HashMap<String, PDFObject> = dict
+                    .iter()
+                    .map(|(key, value)| {
+                        let key = expand_inline_image_key(key);
+                        let value = if key == "Filter" || key == "ColorSpace" {
+                            expand_inline_image_name(value.to_pdf_object())
+                        } else {
+                            value.to_pdf_object()
+                        };
+                        (key.to_string(), value)
+                    })
+                    .collect();
+                PDFObject::Stream {
+                    dict,
+                    data: data.to_vec(),
+                }
+            }
+        }
+    }
+}
+
+/// An operation read by [`ContentScanner`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScannedOperation<'a> {
+    /// The operator code
+    pub op: OpCode,
+
+    /// The operands read before the operator
+    pub operands: SmallVec<[ScanOperand<'a>; 6]>,
+}
+
+impl ScannedOperation<'_> {
+    /// Converts the operation to an owned [`Operation`].
+    pub fn to_operation(&self) -> Operation {
+        Operation::new(
+            self.op,
+            self.operands.iter().map(|o| o.to_pdf_object()).collect(),
+        )
+    }
+}
+
+/// A token of the content stream.
+enum ScanToken<'a> {
+    Operand(ScanOperand<'a>),
+    Keyword(&'a [u8]),
+    ArrayStart,
+    ArrayEnd,
+    DictStart,
+    DictEnd,
+    End,
+}
+
+/// Reads operations from a decoded content stream without copying it.
+///
+/// # Example
+/// ```
+/// use pdf_x_core::core::content_scanner::ContentScanner;
+/// use pdf_x_core::core::content_stream::OpCode;
+///
+/// let mut scanner = ContentScanner::new(b"BT /F1 12 Tf (Hello) Tj ET");
+/// let mut ops = Vec::new();
+/// while let Some(op) = scanner.next_operation().unwrap() {
+///     ops.push(op.op);
+/// }
+/// assert_eq!(ops, [OpCode::BeginText, OpCode::SetFont, OpCode::ShowText, OpCode::EndText]);
+/// ```
+pub struct ContentScanner<'a> {
+    /// The decoded content
+    data: &'a [u8],
+
+    /// Position of the next byte to read
+    pos: usize,
+
+    /// How deeply arrays and dictionaries may nest in an operand
+    max_depth: usize,
+}
+
+impl<'a> ContentScanner<'a> {
+    /// Creates a scanner at the start of the content.
+    pub fn new(data: &'a [u8]) -> Self {
+        Self::at(data, 0)
+    }
+
+    /// Creates a scanner that resumes at a position returned by
+    /// [`position`](Self::position).
+    pub fn at(data: &'a [u8], pos: usize) -> Self {
+        Self {
+            data,
+            pos,
+            max_depth: ResourceLimits::default().max_object_depth,
+        }
+    }
+
+    /// Sets how deeply arrays and dictionaries may nest in an operand
+    /// before scanning fails with [`PDFError::LimitExceeded`] (see
+    /// [`ResourceLimits::max_object_depth`]).
+    pub fn set_max_depth(&mut self, max_depth: usize) {
+        self.max_depth = max_depth;
+    }
+
+    /// Returns the position of the next byte to read.
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// Checks if there is anything but whitespace and comments left.
+    pub fn has_more(&mut self) -> bool {
+        self.skip_whitespace();
+        self.pos < self.data.len()
+    }
+
+    /// Reads the next operation.
+    ///
+    /// # Returns
+    /// * `Ok(Some(operation))` - The next operation
+    /// * `Ok(None)` - End of content
+    /// * `Err(_)` - Unknown operator or malformed operand
+    pub fn next_operation(&mut self) -> PDFResult<Option<ScannedOperation<'a>>> {
+        let mut operands = SmallVec::new();
+        loop {
+            match self.next_token()? {
+                ScanToken::End => {
+                    if operands.is_empty() {
+                        return Ok(None);
+                    }
+                    return Err(PDFError::Generic(
+                        "Content stream ended with operands but no operator".to_string(),
+                    ));
+                }
+                ScanToken::Keyword(b"BI") => {
+                    let image = self.read_inline_image()?;
+                    operands.push(image);
+                    return Ok(Some(ScannedOperation {
+                        op: OpCode::EndInlineImage,
+                        operands,
+                    }));
+                }
+                ScanToken::Keyword(keyword) => {
+                    let op = OpCode::from_bytes(keyword).ok_or_else(|| {
+                        PDFError::content_stream_error(format!(
+                            "Unknown PDF operator: '{}'",
+                            String::from_utf8_lossy(keyword)
+                        ))
+                    })?;
+                    return Ok(Some(ScannedOperation { op, operands }));
+                }
+                token => operands.push(self.read_operand(token, 0)?),
+            }
+        }
+    }
+
+    /// Completes an operand that starts with `token`.
+    ///
+    /// `depth` is the number of arrays and dictionaries the operand is in.
+    fn read_operand(&mut self, token: ScanToken<'a>, depth: usize) -> PDFResult<ScanOperand<'a>> {
+        if matches!(token, ScanToken::ArrayStart | ScanToken::DictStart) && depth >= self.max_depth
+        {
+            return Err(PDFError::limit_exceeded(
+                "array and dictionary nesting depth",
+                self.max_depth,
+            ));
+        }
+        match token {
+            ScanToken::Operand(operand) => Ok(operand),
+            ScanToken::ArrayStart => {
+                let mut items = Vec::new();
+                loop {
+                    match self.next_token()? {
+                        ScanToken::ArrayEnd => return Ok(ScanOperand::Array(items)),
+                        ScanToken::End => {
+                            return Err(PDFError::Generic("Unterminated array".to_string()));
+                        }
+                        token => items.push(self.read_operand(token, depth + 1)?),
+                    }
+                }
+            }
+            ScanToken::DictStart => {
+                let mut entries = Vec::new();
+                loop {
+                    let key = match self.next_token()? {
+                        ScanToken::DictEnd => return Ok(ScanOperand::Dictionary(entries)),
+                        ScanToken::Operand(ScanOperand::Name(key)) => key,
+                        ScanToken::End => {
+                            return Err(PDFError::Generic("Unterminated dictionary".to_string()));
+                        }
+                        _ => {
+                            return Err(PDFError::Generic(
+                                "Dictionary key must be a name".to_string(),
+                            ));
+                        }
+                    };
+                    let token = self.next_token()?;
+                    if matches!(token, ScanToken::DictEnd) {
+                        return Ok(ScanOperand::Dictionary(entries));
+                    }
+                    entries.push((key, self.read_operand(token, depth + 1)?));
+                }
+            }
+            ScanToken::ArrayEnd => Err(PDFError::Generic("Unexpected array end token".to_string())),
+            ScanToken::DictEnd => Err(PDFError::Generic(
+                "Unexpected dictionary end token".to_string(),
+            )),
+            ScanToken::Keyword(keyword) => Err(PDFError::Generic(format!(
+                "Unexpected operator '{}' in operand",
+                String::from_utf8_lossy(keyword)
+            ))),
+            ScanToken::End => Err(PDFError::Generic("Unexpected end of content".to_string())),
+        }
+    }
+
+    /// Reads an inline image after its BI operator, up to and including EI.
+    ///
+    /// The data ends at the first "EI" surrounded by whitespace, and not
+    /// before /Length bytes when the dictionary gives one.
+    fn read_inline_image(&mut self) -> PDFResult<ScanOperand<'a>> {
+        let mut dict = Vec::new();
+        loop {
+            let key = match self.next_token()? {
+                ScanToken::Keyword(b"ID") => break,
+                ScanToken::Operand(ScanOperand::Name(key)) => key,
+                ScanToken::End => {
+                    return Err(PDFError::Generic(
+                        "Inline image dictionary not terminated by ID".to_string(),
+                    ));
+                }
+                _ => {
+                    return Err(PDFError::Generic(
+                        "Inline image key must be a name".to_string(),
+                    ));
+                }
+            };
+            match self.next_token()? {
+                ScanToken::Keyword(b"ID") => break,
+                token => dict.push((key, self.read_operand(token, 1)?)),
+            }
+        }
+
+        // A single whitespace byte separates ID from the data
+        let start = (self.pos + 1).min(self.data.len());
+        let min_length = dict
+            .iter()
+            .find(|(key, _)| key == "L" || key == "Length")
+            .and_then(|(_, value)| value.as_number())
+            .filter(|n| *n > 0.0)
+            .map_or(0, |n| n as usize);
+
+        let mut pos = start + min_length + 1;
+        while pos + 2 <= self.data.len() {
+            if self.data[pos..pos + 2] == *b"EI"
+                && is_whitespace(self.data[pos - 1])
+                && self.data.get(pos + 2).is_none_or(|&b| is_whitespace(b))
+            {
+                self.pos = pos + 2;
+                return Ok(ScanOperand::InlineImage {
+                    dict,
+                    data: &self.data[start..pos - 1],
+                });
+            }
+            pos += 1;
+        }
+
+        tracing::warn!(target: log_target::PARSER, "Inline image data not terminated by EI");
+        self.pos = self.data.len();
+        Ok(ScanOperand::InlineImage {
+            dict,
+            data: &self.data[start..],
+        })
+    }
+
+    /// Skips whitespace and comments.
+    fn skip_whitespace(&mut self) {
+        while let Some(&byte) = self.data.get(self.pos) {
+            if byte == b'%' {
+                while self
+                    .data
+                    .get(self.pos)
+                    .is_some_and(|&b| b != b'\n' && b != b'\r')
+                {
+                    self.pos += 1;
+                }
+            } else if is_whitespace(byte) {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// Reads the next token.
+    fn next_token(&mut self) -> PDFResult<ScanToken<'a>> {
+        self.skip_whitespace();
+        let Some(&byte) = self.data.get(self.pos) else {
+            return Ok(ScanToken::End);
+        };
+
+        match byte {
+            b'0'..=b'9' | b'+' | b'-' | b'.' => {
+                Ok(ScanToken::Operand(ScanOperand::Number(self.read_number()?)))
+            }
+            b'(' => Ok(ScanToken::Operand(ScanOperand::String(self.read_string()))),
+            b'/' => Ok(ScanToken::Operand(ScanOperand::Name(self.read_name()))),
+            b'[' => {
+                self.pos += 1;
+                Ok(ScanToken::ArrayStart)
+            }
+            b']' => {
+                self.pos += 1;
+                Ok(ScanToken::ArrayEnd)
+            }
+            b'<' if self.data.get(self.pos + 1) == Some(&b'<') => {
+                self.pos += 2;
+                Ok(ScanToken::DictStart)
+            }
+            b'<' => Ok(ScanToken::Operand(ScanOperand::HexString(
+                self.read_hex_string(),
+            ))),
+            b'>' if self.data.get(self.pos + 1) == Some(&b'>') => {
+                self.pos += 2;
+                Ok(ScanToken::DictEnd)
+            }
+            _ => {
+                let start = self.pos;
+                while self.data.get(self.pos).is_some_and(|&b| !is_special(b)) {
+                    self.pos += 1;
+                }
+                // A stray delimiter reads as a one-byte (unknown) operator
+                if self.pos == start {
+                    self.pos += 1;
+                }
+                Ok(match &self.data[start..self.pos] {
+                    b"true" => ScanToken::Operand(ScanOperand::Boolean(true)),
+                    b"false" => ScanToken::Operand(ScanOperand::Boolean(false)),
+                    b"null" => ScanToken::Operand(ScanOperand::Null),
+                    keyword => ScanToken::Keyword(keyword),
+                })
+            }
+        }
+    }
+
+    /// Returns the byte at the current position, if any.
+    fn peek(&self) -> Option<u8> {
+        self.data.get(self.pos).copied()
+    }
+
+    /// Reads a number, following the lexer's recovery rules.
+    fn read_number(&mut self) -> PDFResult<f64> {
+        let mut sign = 1.0;
+        match self.peek() {
+            Some(b'-') => {
+                sign = -1.0;
+                self.pos += 1;
+                // Ignore double negative (consistent with Adobe Reader)
+                if self.peek() == Some(b'-') {
+                    self.pos += 1;
+                }
+            }
+            Some(b'+') => self.pos += 1,
+            _ => {}
+        }
+        // Ignore line breaks after the sign
+        while matches!(self.peek(), Some(b'\n' | b'\r')) {
+            self.pos += 1;
+        }
+
+        let mut divide_by = 0.0;
+        if self.peek() == Some(b'.') {
+            divide_by = 10.0;
+            self.pos += 1;
+        }
+
+        let mut value = match self.peek() {
+            Some(digit @ b'0'..=b'9') => (digit - b'0') as f64,
+            // An invalid number before whitespace, a string or the end
+            // reads as 0 (consistent with Adobe Reader)
+            None | Some(b'(' | b'<') => return Ok(0.0),
+            Some(byte) if is_whitespace(byte) => return Ok(0.0),
+            Some(byte) => {
+                return Err(PDFError::Generic(format!(
+                    "Invalid number: {} (charCode {})",
+                    byte as char, byte
+                )));
+            }
+        };
+        self.pos += 1;
+
+        let mut e_notation = false;
+        let mut power = 0i32;
+        let mut power_sign = 1;
+        while let Some(byte) = self.peek() {
+            match byte {
+                b'0'..=b'9' if e_notation => {
+                    power = power
+                        .saturating_mul(10)
+                        .saturating_add((byte - b'0') as i32)
+                }
+                b'0'..=b'9' => {
+                    if divide_by != 0.0 {
+                        divide_by *= 10.0;
+                    }
+                    value = value * 10.0 + (byte - b'0') as f64;
+                }
+                b'.' if divide_by == 0.0 => divide_by = 1.0,
+                b'.' => break,
+                // Minus signs inside a number are ignored
+                b'-' => {}
+                b'e' | b'E' => match self.data.get(self.pos + 1) {
+                    Some(&sign @ (b'+' | b'-')) => {
+                        power_sign = if sign == b'-' { -1 } else { 1 };
+                        self.pos += 1;
+                        e_notation = true;
+                    }
+                    Some(b'0'..=b'9') => e_notation = true,
+                    // Not an exponent but the start of an operator
+                    _ => break,
+                },
+                _ => break,
+            }
+            self.pos += 1;
+        }
+
+        if divide_by != 0.0 {
+            value /= divide_by;
+        }
+        if e_notation {
+            value *= 10_f64.powi(power_sign * power);
+        }
+        Ok(sign * value)
+    }
+
+    /// Reads a literal string, borrowing it unless it contains escapes.
+    fn read_string(&mut self) -> Cow<'a, [u8]> {
+        let start = self.pos + 1;
+        let mut depth = 1;
+        let mut pos = start;
+        while let Some(&byte) = self.data.get(pos) {
+            match byte {
+                b'\\' => return Cow::Owned(self.read_escaped_string()),
+                b'(' => depth += 1,
+                b')' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        self.pos = pos + 1;
+                        return Cow::Borrowed(&self.data[start..pos]);
+                    }
+                }
+                _ => {}
+            }
+            pos += 1;
+        }
+        // Unterminated: the string runs to the end
+        self.pos = self.data.len();
+        Cow::Borrowed(&self.data[start..])
+    }
+
+    /// Reads a literal string that contains escape sequences.
+    fn read_escaped_string(&mut self) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut depth = 1;
+        self.pos += 1;
+        while let Some(byte) = self.peek() {
+            self.pos += 1;
+            match byte {
+                b'(' => {
+                    depth += 1;
+                    out.push(byte);
+                }
+                b')' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return out;
+                    }
+                    out.push(byte);
+                }
+                b'\\' => {
+                    let Some(escaped) = self.peek() else {
+                        break;
+                    };
+                    self.pos += 1;
+                    match escaped {
+                        b'n' => out.push(b'\n'),
+                        b'r' => out.push(b'\r'),
+                        b't' => out.push(b'\t'),
+                        b'b' => out.push(0x08),
+                        b'f' => out.push(0x0C),
+                        b'0'..=b'7' => {
+                            let mut value = escaped - b'0';
+                            for _ in 0..2 {
+                                match self.peek() {
+                                    Some(digit @ b'0'..=b'7') => {
+                                        value = (value << 3).wrapping_add(digit - b'0');
+                                        self.pos += 1;
+                                    }
+                                    _ => break,
+                                }
+                            }
+                            out.push(value);
+                        }
+                        // A backslash before a line break continues the line
+                        b'\r' => {
+                            if self.peek() == Some(b'\n') {
+                                self.pos += 1;
+                            }
+                        }
+                        b'\n' => {}
+                        other => out.push(other),
+                    }
+                }
+                _ => out.push(byte),
+            }
+        }
+        out
+    }
+
+    /// Reads a hexadecimal string; invalid digits are skipped and an odd
+    /// final digit is padded with 0.
+    fn read_hex_string(&mut self) -> Vec<u8> {
+        self.pos += 1;
+        let mut out = Vec::new();
+        let mut high: Option<u8> = None;
+        while let Some(byte) = self.peek() {
+            self.pos += 1;
+            if byte == b'>' {
+                break;
+            }
+            if let Some(digit) = hex_digit(byte) {
+                match high.take() {
+                    Some(h) => out.push((h << 4) | digit),
+                    None => high = Some(digit),
+                }
+            }
+        }
+        if let Some(h) = high {
+            out.push(h << 4);
+        }
+        out
+    }
+
+    /// Reads a name, borrowing it unless it contains '#' escapes or
+    /// invalid UTF-8.
+    fn read_name(&mut self) -> Cow<'a, str> {
+        let start = self.pos + 1;
+        let mut end = start;
+        while self.data.get(end).is_some_and(|&b| !is_special(b)) {
+            end += 1;
+        }
+        self.pos = end;
+        let raw = &self.data[start..end];
+
+        if !raw.contains(&b'#')
+            && let Ok(name) = std::str::from_utf8(raw)
+        {
+            return Cow::Borrowed(name);
+        }
+
+        let mut bytes = Vec::with_capacity(raw.len());
+        let mut i = 0;
+        while i < raw.len() {
+            if raw[i] == b'#'
+                && let (Some(h), Some(l)) = (
+                    raw.get(i + 1).and_then(|&b| hex_digit(b)),
+                    raw.get(i + 2).and_then(|&b| hex_digit(b)),
+                )
+            {
+                bytes.push((h << 4) | l);
+                i += 3;
+            } else {
+                bytes.push(raw[i]);
+                i += 1;
+            }
+        }
+        Cow::Owned(String::from_utf8_lossy(&bytes).into_owned())
+    }
+}
+
+/// Checks for PDF whitespace: NUL, TAB, LF, FF, CR, SPACE.
+#[inline(always)]
+fn is_whitespace(byte: u8) -> bool {
+    matches!(byte, 0x00 | 0x09 | 0x0A | 0x0C | 0x0D | 0x20)
+}
+
+/// Checks for whitespace or a delimiter: ( ) < > [ ] { } / %.
+#[inline(always)]
+fn is_special(byte: u8) -> bool {
+    is_whitespace(byte)
+        || matches!(
+            byte,
+            b'(' | b')' | b'<' | b'>' | b'[' | b']' | b'{' | b'}' | b'/' | b'%'
+        )
+}
+
+/// Returns the value of a hexadecimal digit.
+fn hex_digit(byte: u8) -> Option<u8> {
+    (byte as char).to_digit(16).map(|d| d as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::content_stream::ContentStreamEvaluator;
+    use crate::core::{Lexer, Parser, Stream};
+
+    fn scan_all(data: &[u8]) -> Vec<ScannedOperation<'_>> {
+        let mut scanner = ContentScanner::new(data);
+        let mut ops = Vec::new();
+        while let Some(op) = scanner.next_operation().unwrap() {
+            ops.push(op);
+        }
+        ops
+    }
+
+    #[test]
+    fn test_scan_operands() {
+        let ops =
+            scan_all(b"1 -2.5 .5 +3 1e2 true null /F1 (a(b)c) <48 65 6> [1 (x)] <</K /V>> gs");
+
+        assert_eq!(ops.len(), 1);
+        assert_eq!(ops[0].op, OpCode::SetGState);
+        let operands = &ops[0].operands;
+        assert_eq!(
+            operands[..5]
+                .iter()
+                .map(|o| o.as_number().unwrap())
+                .collect::<Vec<_>>(),
+            [1.0, -2.5, 0.5, 3.0, 100.0]
+        );
+        assert_eq!(operands[5], ScanOperand::Boolean(true));
+        assert_eq!(operands[6], ScanOperand::Null);
+        assert_eq!(operands[7], ScanOperand::Name(Cow::Borrowed("F1")));
+        assert_eq!(operands[8], ScanOperand::String(Cow::Borrowed(b"a(b)c")));
+        assert_eq!(operands[9], ScanOperand::HexString(b"He`".to_vec()));
+        assert_eq!(
+            operands[10],
+            ScanOperand::Array(vec![
+                ScanOperand::Number(1.0),
+                ScanOperand::String(Cow::Borrowed(b"x"))
+            ])
+        );
+        assert_eq!(
+            operands[11],
+            ScanOperand::Dictionary(vec![(
+                Cow::Borrowed("K"),
+                ScanOperand::Name(Cow::Borrowed("V"))
+            )])
+        );
+    }
+
+    #[test]
+    fn test_scan_borrows_unless_escaped() {
+        let ops = scan_all(b"/A#20B (x\\)\\101\\\ny) Tj");
+        assert!(
+            matches!(&ops[0].operands[0], ScanOperand::Name(Cow::Owned(name)) if name == "A B")
+        );
+        assert!(matches!(&ops[0].operands[1], ScanOperand::String(Cow::Owned(s)) if s == b"x)Ay"));
+
+        let ops = scan_all(b"/Name (plain) Tj");
+        assert!(matches!(
+            ops[0].operands[0],
+            ScanOperand::Name(Cow::Borrowed(_))
+        ));
+        assert!(matches!(
+            ops[0].operands[1],
+            ScanOperand::String(Cow::Borrowed(_))
+        ));
+    }
+
+    #[test]
+    fn test_scan_inline_image() {
+        let ops = scan_all(b"q BI /W 2 /H 1 /CS /G ID \x00EI\x01 EI Q");
+
+        assert_eq!(ops.len(), 3);
+        assert_eq!(ops[1].op, OpCode::EndInlineImage);
+        match &ops[1].operands[0] {
+            ScanOperand::InlineImage { dict, data } => {
+                assert_eq!(dict.len(), 3);
+                assert_eq!(*data, b"\x00EI\x01");
+            }
+            other => panic!("Expected inline image, got {:?}", other),
+        }
+        assert_eq!(ops[2].op, OpCode::Restore);
+
+        // Converted like the parser does it
+        match ops[1].operands[0].to_pdf_object() {
+            PDFObject::Stream { dict, .. } => {
+                assert_eq!(dict.get("Width"), Some(&PDFObject::Number(2.0)));
+                assert_eq!(
+                    dict.get("ColorSpace"),
+                    Some(&PDFObject::Name("DeviceGray".to_string()))
+                );
+            }
+            other => panic!("Expected stream, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_scan_errors() {
+        assert!(ContentScanner::new(b"1 2 foo").next_operation().is_err());
+        assert!(ContentScanner::new(b"1 2").next_operation().is_err());
+        assert!(ContentScanner::new(b"[1 2").next_operation().is_err());
+
+        // Nesting is bounded instead of overflowing the stack
+        let deep = "[".repeat(100_000);
+        assert!(matches!(
+            ContentScanner::new(deep.as_bytes()).next_operation(),
+            Err(PDFError::LimitExceeded { max: 256, .. })
+        ));
+        let mut scanner = ContentScanner::new(b"[[1]] << /A [1] >> d [[[1]]] TJ");
+        scanner.set_max_depth(2);
+        assert!(scanner.next_operation().is_ok());
+        assert!(scanner.next_operation().is_err());
+        assert!(
+            ContentScanner::new(b"  % only a comment\n")
+                .next_operation()
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_scan_matches_parser() {
+        let content: &[u8] = b"q 1 0 0 1 72 720 cm BT /F1 12 Tf 14.4 TL (Line\\0401) Tj T* \
+            [(A) -120 (W)] TJ 1 2 (q) \" ET 0.5 g 0 0 m 10 10 l S \
+            /P <</MCID 3>> BDC 10 20 30 40 re f EMC /Im1 Do Q";
+
+        let scanned: Vec<Operation> = scan_all(content)
+            .iter()
+            .map(|op| op.to_operation())
+            .collect();
+
+        let stream = Box::new(Stream::from_bytes(content.to_vec()));
+        let mut evaluator =
+            ContentStreamEvaluator::new(Parser::new(Lexer::new(stream).unwrap()).unwrap());
+        let mut parsed = Vec::new();
+        while let Some(op) = evaluator.read_operation().unwrap() {
+            parsed.push(op);
+        }
+
+        assert_eq!(scanned.len(), parsed.len());
+        for (scanned, parsed) in scanned.iter().zip(&parsed) {
+            assert_eq!(scanned.op, parsed.op);
+            assert_eq!(scanned.args, parsed.args);
+        }
+    }
+}
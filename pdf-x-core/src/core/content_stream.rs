@@ -5,9 +5,15 @@
 //!
 //! Based on PDF.js src/core/evaluator.js and src/shared/util.js (OPS constants).
 
+use super::colorspace::ColorSpace;
+use super::content_scanner::ContentScanner;
 use super::error::{PDFError, PDFResult};
 use super::font::Font;
+use super::limits::ResourceLimits;
+use super::outline::decode_pdf_string;
 use super::parser::{PDFObject, Parser};
+use super::reflow::FontStyle;
+use crate::log_target;
 use rustc_hash::FxHashMap;
 use std::fmt;
 
@@ -204,113 +210,122 @@ impl OpCode {
     /// # Returns
     /// The corresponding OpCode, or an error if the operator is unknown.
     pub fn from_command(cmd: &str) -> PDFResult<OpCode> {
+        Self::from_bytes(cmd.as_bytes()).ok_or_else(|| {
+            PDFError::content_stream_error(format!("Unknown PDF operator: '{}'", cmd))
+        })
+    }
+
+    /// Converts the raw bytes of an operator to an OpCode.
+    ///
+    /// Lets scanners look up operators without building a `String` first.
+    ///
+    /// # Returns
+    /// The corresponding OpCode, or `None` if the operator is unknown.
+    pub fn from_bytes(cmd: &[u8]) -> Option<OpCode> {
         match cmd {
             // Graphics state
-            "w" => Ok(OpCode::SetLineWidth),
-            "J" => Ok(OpCode::SetLineCap),
-            "j" => Ok(OpCode::SetLineJoin),
-            "M" => Ok(OpCode::SetMiterLimit),
-            "d" => Ok(OpCode::SetDash),
-            "ri" => Ok(OpCode::SetRenderingIntent),
-            "i" => Ok(OpCode::SetFlatness),
-            "gs" => Ok(OpCode::SetGState),
-            "q" => Ok(OpCode::Save),
-            "Q" => Ok(OpCode::Restore),
-            "cm" => Ok(OpCode::Transform),
+            b"w" => Some(OpCode::SetLineWidth),
+            b"J" => Some(OpCode::SetLineCap),
+            b"j" => Some(OpCode::SetLineJoin),
+            b"M" => Some(OpCode::SetMiterLimit),
+            b"d" => Some(OpCode::SetDash),
+            b"ri" => Some(OpCode::SetRenderingIntent),
+            b"i" => Some(OpCode::SetFlatness),
+            b"gs" => Some(OpCode::SetGState),
+            b"q" => Some(OpCode::Save),
+            b"Q" => Some(OpCode::Restore),
+            b"cm" => Some(OpCode::Transform),
 
             // Path construction
-            "m" => Ok(OpCode::MoveTo),
-            "l" => Ok(OpCode::LineTo),
-            "c" => Ok(OpCode::CurveTo),
-            "v" => Ok(OpCode::CurveTo2),
-            "y" => Ok(OpCode::CurveTo3),
-            "h" => Ok(OpCode::ClosePath),
-            "re" => Ok(OpCode::Rectangle),
+            b"m" => Some(OpCode::MoveTo),
+            b"l" => Some(OpCode::LineTo),
+            b"c" => Some(OpCode::CurveTo),
+            b"v" => Some(OpCode::CurveTo2),
+            b"y" => Some(OpCode::CurveTo3),
+            b"h" => Some(OpCode::ClosePath),
+            b"re" => Some(OpCode::Rectangle),
 
             // Path painting
-            "S" => Ok(OpCode::Stroke),
-            "s" => Ok(OpCode::CloseStroke),
-            "f" | "F" => Ok(OpCode::Fill),
-            "f*" => Ok(OpCode::EOFill),
-            "B" => Ok(OpCode::FillStroke),
-            "B*" => Ok(OpCode::EOFillStroke),
-            "b" => Ok(OpCode::CloseFillStroke),
-            "b*" => Ok(OpCode::CloseEOFillStroke),
-            "n" => Ok(OpCode::EndPath),
+            b"S" => Some(OpCode::Stroke),
+            b"s" => Some(OpCode::CloseStroke),
+            b"f" | b"F" => Some(OpCode::Fill),
+            b"f*" => Some(OpCode::EOFill),
+            b"B" => Some(OpCode::FillStroke),
+            b"B*" => Some(OpCode::EOFillStroke),
+            b"b" => Some(OpCode::CloseFillStroke),
+            b"b*" => Some(OpCode::CloseEOFillStroke),
+            b"n" => Some(OpCode::EndPath),
 
             // Clipping
-            "W" => Ok(OpCode::Clip),
-            "W*" => Ok(OpCode::EOClip),
+            b"W" => Some(OpCode::Clip),
+            b"W*" => Some(OpCode::EOClip),
 
             // Text object
-            "BT" => Ok(OpCode::BeginText),
-            "ET" => Ok(OpCode::EndText),
+            b"BT" => Some(OpCode::BeginText),
+            b"ET" => Some(OpCode::EndText),
 
             // Text state
-            "Tc" => Ok(OpCode::SetCharSpacing),
-            "Tw" => Ok(OpCode::SetWordSpacing),
-            "Tz" => Ok(OpCode::SetHScale),
-            "TL" => Ok(OpCode::SetLeading),
-            "Tf" => Ok(OpCode::SetFont),
-            "Tr" => Ok(OpCode::SetTextRenderingMode),
-            "Ts" => Ok(OpCode::SetTextRise),
+            b"Tc" => Some(OpCode::SetCharSpacing),
+            b"Tw" => Some(OpCode::SetWordSpacing),
+            b"Tz" => Some(OpCode::SetHScale),
+            b"TL" => Some(OpCode::SetLeading),
+            b"Tf" => Some(OpCode::SetFont),
+            b"Tr" => Some(OpCode::SetTextRenderingMode),
+            b"Ts" => Some(OpCode::SetTextRise),
 
             // Text positioning
-            "Td" => Ok(OpCode::MoveText),
-            "TD" => Ok(OpCode::SetLeadingMoveText),
-            "Tm" => Ok(OpCode::SetTextMatrix),
-            "T*" => Ok(OpCode::NextLine),
+            b"Td" => Some(OpCode::MoveText),
+            b"TD" => Some(OpCode::SetLeadingMoveText),
+            b"Tm" => Some(OpCode::SetTextMatrix),
+            b"T*" => Some(OpCode::NextLine),
 
             // Text showing
-            "Tj" => Ok(OpCode::ShowText),
-            "TJ" => Ok(OpCode::ShowSpacedText),
-            "'" => Ok(OpCode::NextLineShowText),
-            "\"" => Ok(OpCode::NextLineSetSpacingShowText),
+            b"Tj" => Some(OpCode::ShowText),
+            b"TJ" => Some(OpCode::ShowSpacedText),
+            b"'" => Some(OpCode::NextLineShowText),
+            b"\"" => Some(OpCode::NextLineSetSpacingShowText),
 
             // Type 3 fonts
-            "d0" => Ok(OpCode::SetCharWidth),
-            "d1" => Ok(OpCode::SetCharWidthAndBounds),
+            b"d0" => Some(OpCode::SetCharWidth),
+            b"d1" => Some(OpCode::SetCharWidthAndBounds),
 
             // Color
-            "CS" => Ok(OpCode::SetStrokeColorSpace),
-            "cs" => Ok(OpCode::SetFillColorSpace),
-            "SC" => Ok(OpCode::SetStrokeColor),
-            "SCN" => Ok(OpCode::SetStrokeColorN),
-            "sc" => Ok(OpCode::SetFillColor),
-            "scn" => Ok(OpCode::SetFillColorN),
-            "G" => Ok(OpCode::SetStrokeGray),
-            "g" => Ok(OpCode::SetFillGray),
-            "RG" => Ok(OpCode::SetStrokeRGBColor),
-            "rg" => Ok(OpCode::SetFillRGBColor),
-            "K" => Ok(OpCode::SetStrokeCMYKColor),
-            "k" => Ok(OpCode::SetFillCMYKColor),
+            b"CS" => Some(OpCode::SetStrokeColorSpace),
+            b"cs" => Some(OpCode::SetFillColorSpace),
+            b"SC" => Some(OpCode::SetStrokeColor),
+            b"SCN" => Some(OpCode::SetStrokeColorN),
+            b"sc" => Some(OpCode::SetFillColor),
+            b"scn" => Some(OpCode::SetFillColorN),
+            b"G" => Some(OpCode::SetStrokeGray),
+            b"g" => Some(OpCode::SetFillGray),
+            b"RG" => Some(OpCode::SetStrokeRGBColor),
+            b"rg" => Some(OpCode::SetFillRGBColor),
+            b"K" => Some(OpCode::SetStrokeCMYKColor),
+            b"k" => Some(OpCode::SetFillCMYKColor),
 
             // Shading
-            "sh" => Ok(OpCode::ShadingFill),
+            b"sh" => Some(OpCode::ShadingFill),
 
             // Inline images
-            "BI" => Ok(OpCode::BeginInlineImage),
-            "ID" => Ok(OpCode::BeginImageData),
-            "EI" => Ok(OpCode::EndInlineImage),
+            b"BI" => Some(OpCode::BeginInlineImage),
+            b"ID" => Some(OpCode::BeginImageData),
+            b"EI" => Some(OpCode::EndInlineImage),
 
             // XObject
-            "Do" => Ok(OpCode::PaintXObject),
+            b"Do" => Some(OpCode::PaintXObject),
 
             // Marked content
-            "MP" => Ok(OpCode::MarkPoint),
-            "DP" => Ok(OpCode::MarkPointProps),
-            "BMC" => Ok(OpCode::BeginMarkedContent),
-            "BDC" => Ok(OpCode::BeginMarkedContentProps),
-            "EMC" => Ok(OpCode::EndMarkedContent),
+            b"MP" => Some(OpCode::MarkPoint),
+            b"DP" => Some(OpCode::MarkPointProps),
+            b"BMC" => Some(OpCode::BeginMarkedContent),
+            b"BDC" => Some(OpCode::BeginMarkedContentProps),
+            b"EMC" => Some(OpCode::EndMarkedContent),
 
             // Compatibility
-            "BX" => Ok(OpCode::BeginCompat),
-            "EX" => Ok(OpCode::EndCompat),
+            b"BX" => Some(OpCode::BeginCompat),
+            b"EX" => Some(OpCode::EndCompat),
 
-            _ => Err(PDFError::content_stream_error(format!(
-                "Unknown PDF operator: '{}'",
-                cmd
-            ))),
+            _ => None,
         }
     }
 
@@ -448,6 +463,322 @@ pub struct TextItem {
 
     /// Text rendering mode
     pub rendering_mode: Option<i32>,
+
+    /// Horizontal advance of the text in user space, when the font's glyph
+    /// widths are known
+    pub width: Option<f64>,
+
+    /// Marked-content ID of the enclosing marked-content sequence, linking
+    /// the text to the structure tree of a tagged PDF
+    pub mcid: Option<u32>,
+
+    /// Horizontal extent (start, end) of each character of `text`, as offsets
+    /// from `position` in user space, when the font's glyph widths are known.
+    /// Gaps between spans come from character and word spacing and TJ
+    /// adjustments.
+    pub char_spans: Option<Vec<(f64, f64)>>,
+
+    /// Descent and ascent of the font relative to the baseline, in user
+    /// space, when its font descriptor provides them
+    pub vertical_extent: Option<(f64, f64)>,
+
+    /// Geometry of each glyph shown, when requested with
+    /// [`ContentStreamEvaluator::set_glyph_geometry`] and the font's glyph
+    /// widths are known
+    pub glyphs: Option<Vec<TextGlyph>>,
+
+    /// Font family, from the font descriptor's /FontFamily or else the base
+    /// font name without its subset prefix and style suffix
+    pub font_family: Option<String>,
+
+    /// Whether the font is bold, from its descriptor (/Flags, /FontWeight,
+    /// /StemV) or its name
+    pub bold: bool,
+
+    /// Whether the font is italic, from its descriptor (/Flags,
+    /// /ItalicAngle) or its name
+    pub italic: bool,
+
+    /// Fill color when the text was shown, as RGB components in 0..1;
+    /// `None` for pattern fills and color spaces that couldn't be resolved
+    pub fill_color: Option<[f64; 3]>,
+}
+
+/// Position and extent of a single glyph shown by a text operator.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextGlyph {
+    /// Character code read from the string operand
+    pub code: u32,
+
+    /// Unicode text of the glyph; empty if it has no mapping, several
+    /// characters for ligatures
+    pub unicode: String,
+
+    /// Distance the glyph moves the text origin along the writing
+    /// direction (downwards for vertical fonts), in user space, including
+    /// character and word spacing
+    pub advance: f64,
+
+    /// Corners of the glyph box as x, y pairs in user space: lower left,
+    /// lower right, upper right, upper left. The box spans the glyph width
+    /// and the font's descent to ascent, raised by the text rise and mapped
+    /// through the text matrix, so it is rotated or skewed with it. For
+    /// vertical fonts it spans the vertical advance instead, centered on
+    /// the text origin.
+    pub quad: [f64; 8],
+}
+
+/// How text is assembled during extraction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExtractionMode {
+    /// Apply layout heuristics: insert word spaces for large TJ adjustments
+    /// and, when joining items into a string, sort them by position and
+    /// separate lines with newlines.
+    #[default]
+    Layout,
+
+    /// Disable all layout heuristics. Items are returned strictly in operator
+    /// order, one per string operand, with the decoded text and nothing
+    /// inserted; joined strings are plain concatenations.
+    Raw,
+
+    /// Like `Layout`, but when joining items into a string, group lines into
+    /// blocks and columns and emit them in reading order (see
+    /// [`layout_reading_order`](crate::core::layout::layout_reading_order)).
+    ReadingOrder,
+}
+
+/// Loads the fonts of a resources dictionary, keyed by resource name.
+///
+/// Fonts that fail to load are skipped with a warning.
+pub(crate) fn load_font_map(
+    resources: &PDFObject,
+    xref: &mut crate::core::xref::XRef,
+) -> PDFResult<FxHashMap<String, Font>> {
+    let mut fonts = FxHashMap::default();
+
+    // Get the Font dictionary from resources
+    let resources_dict = match resources {
+        PDFObject::Dictionary(d) => d,
+        _ => return Ok(fonts), // No resources, skip
+    };
+
+    let font_dict = match resources_dict.get("Font") {
+        Some(PDFObject::Dictionary(d)) => d.clone(),
+        Some(PDFObject::Ref(ref_obj)) => {
+            // Dereference the font dictionary
+            let font_obj = xref.fetch(ref_obj.num, ref_obj.generation)?;
+            match &*font_obj {
+                PDFObject::Dictionary(d) => d.clone(),
+                _ => return Ok(fonts), // Not a dictionary
+            }
+        }
+        _ => return Ok(fonts), // No fonts
+    };
+
+    // Load the font dictionaries' bytes in one pass over the file
+    let font_refs: Vec<_> = font_dict
+        .values()
+        .filter_map(|font_ref| match font_ref {
+            PDFObject::Ref(r) => Some(*r),
+            _ => None,
+        })
+        .collect();
+    xref.prefetch(&font_refs)?;
+
+    // Load each font
+    for (font_name, font_ref) in &font_dict {
+        // Fetch the font dictionary
+        let font_dict_obj = xref.fetch_if_ref(font_ref)?;
+
+        // Create Font object
+        match Font::new(font_dict_obj, xref) {
+            Ok(font) => {
+                fonts.insert(font_name.clone(), font);
+            }
+            Err(e) => {
+                // Log error but continue loading other fonts
+                tracing::warn!(
+                    target: log_target::FONT,
+                    "Failed to load font '{}': {:?}", font_name, e
+                );
+            }
+        }
+    }
+
+    Ok(fonts)
+}
+
+/// Converts fill color components to RGB, or `None` for pattern spaces
+/// and operands that don't match the space.
+fn fill_rgb(space: &ColorSpace, components: &[f64]) -> Option<[f64; 3]> {
+    match space {
+        ColorSpace::Pattern { .. } => None,
+        _ if components.len() != space.components() => None,
+        _ => Some(space.to_rgb(components)),
+    }
+}
+
+/// Text state that places glyphs, as set by Tf, Tc, Tw, Tz and Ts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct GlyphSpacing {
+    /// Font size (Tfs)
+    pub(crate) font_size: f64,
+
+    /// Character spacing (Tc) in unscaled text space units
+    pub(crate) char_spacing: f64,
+
+    /// Word spacing (Tw) in unscaled text space units
+    pub(crate) word_spacing: f64,
+
+    /// Horizontal scaling (Tz) as a fraction
+    pub(crate) horizontal_scaling: f64,
+
+    /// Text rise (Ts) in unscaled text space units
+    pub(crate) rise: f64,
+}
+
+/// Where one glyph of a shown string is drawn.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct GlyphPlacement {
+    /// Character code
+    pub(crate) code: u32,
+
+    /// Number of string bytes the code occupies
+    pub(crate) len: usize,
+
+    /// Glyph box [x0, y0, x1, y1] in text space, relative to the text
+    /// origin before the glyph
+    pub(crate) rect: [f64; 4],
+
+    /// Displacement (tx, ty) of the text origin by the glyph in text space,
+    /// character and word spacing included
+    pub(crate) advance: (f64, f64),
+
+    /// TJ adjustment, in thousandths of text space, that moves the text
+    /// origin by the same displacement (0 at font size 0)
+    pub(crate) adjustment: f64,
+}
+
+/// Lays out the glyphs of a string shown with a font (PDF spec 9.4.4).
+///
+/// Both horizontal and vertical writing are handled, and Type3 glyph
+/// widths go through the font's /FontMatrix. A glyph box spans the glyph
+/// width and the font's descent to ascent (or its /FontBBox for Type3
+/// fonts), falling back to the 0.2/0.8 em split PDF.js's text layer
+/// assumes; in vertical writing it spans the vertical advance, centered on
+/// the text origin.
+///
+/// # Arguments
+/// * `font` - The current font, if it could be loaded
+/// * `spacing` - The text state
+/// * `bytes` - The string operand
+/// * `fallback_width` - Glyph width in thousandths of text space to assume
+///   when the font is missing or has no glyph widths
+///
+/// # Returns
+/// The glyphs in order, or `None` if their widths are unknown
+pub(crate) fn layout_glyphs(
+    font: Option<&Font>,
+    spacing: &GlyphSpacing,
+    bytes: &[u8],
+    fallback_width: Option<f64>,
+) -> Option<Vec<GlyphPlacement>> {
+    let measured = font.filter(|font| font.has_widths());
+    if measured.is_none() && fallback_width.is_none() {
+        return None;
+    }
+    let font_size = spacing.font_size;
+    let scaling = spacing.horizontal_scaling;
+    let vertical = measured.filter(|font| font.is_vertical());
+    let (bottom, top) = measured.map_or((-0.2, 0.8), glyph_extent);
+    let rise = spacing.rise;
+
+    let mut glyphs = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let (code, len) = match font {
+            Some(font) => font.read_char_code(bytes, i),
+            None => (bytes[i] as u32, 1),
+        };
+        let len = len.clamp(1, bytes.len() - i);
+        // Width in text space units per unit of font size
+        let width = match (measured, fallback_width) {
+            (Some(font), _) => font.code_text_width(code),
+            (None, Some(width)) => width / 1000.0,
+            (None, None) => unreachable!("checked above"),
+        };
+        let mut extra = spacing.char_spacing;
+        if len == 1 && code == 32 {
+            extra += spacing.word_spacing;
+        }
+
+        let (rect, advance, adjustment) = match vertical {
+            Some(font) => {
+                // Horizontal scaling doesn't apply to vertical writing
+                let height = font.code_vertical_advance(code) / 1000.0 * font_size;
+                let half = width * font_size / 2.0;
+                let ty = height + extra;
+                let adjustment = if font_size != 0.0 {
+                    -ty * 1000.0 / font_size
+                } else {
+                    0.0
+                };
+                ([-half, height + rise, half, rise], (0.0, ty), adjustment)
+            }
+            None => {
+                let glyph_width = width * font_size * scaling;
+                let tx = (width * font_size + extra) * scaling;
+                let adjustment = if font_size * scaling != 0.0 {
+                    -tx * 1000.0 / (font_size * scaling)
+                } else {
+                    0.0
+                };
+                (
+                    [
+                        0.0,
+                        bottom * font_size + rise,
+                        glyph_width,
+                        top * font_size + rise,
+                    ],
+                    (tx, 0.0),
+                    adjustment,
+                )
+            }
+        };
+        let [x0, y0, x1, y1] = rect;
+        glyphs.push(GlyphPlacement {
+            code,
+            len,
+            rect: [x0.min(x1), y0.min(y1), x0.max(x1), y0.max(y1)],
+            advance,
+            adjustment,
+        });
+        i += len;
+    }
+    Some(glyphs)
+}
+
+/// Extent of a font's glyphs across the baseline, per unit of font size.
+fn glyph_extent(font: &Font) -> (f64, f64) {
+    let (bottom, top) = match (font.dict.font_matrix, font.dict.descent, font.dict.ascent) {
+        (Some([_, b, _, d, _, f]), descent, ascent) => match font.dict.font_bbox {
+            Some([x0, y0, x1, y1]) => {
+                let ys = [(x0, y0), (x1, y0), (x1, y1), (x0, y1)].map(|(x, y)| b * x + d * y + f);
+                (
+                    ys.iter().copied().fold(f64::MAX, f64::min),
+                    ys.iter().copied().fold(f64::MIN, f64::max),
+                )
+            }
+            None => match (descent, ascent) {
+                (Some(descent), Some(ascent)) => (descent * d, ascent * d),
+                _ => return (-0.2, 0.8),
+            },
+        },
+        (None, Some(descent), Some(ascent)) => (descent / 1000.0, ascent / 1000.0),
+        _ => return (-0.2, 0.8),
+    };
+    (bottom.min(top), bottom.max(top))
 }
 
 /// Content stream evaluator/preprocessor.
@@ -458,13 +789,36 @@ pub struct TextItem {
 ///
 /// Based on PDF.js src/core/evaluator.js EvaluatorPreprocessor class.
 pub struct ContentStreamEvaluator {
-    parser: Parser,
+    /// Where operations are read from
+    source: OperationSource,
 
     /// Text extraction state
     text_state: TextExtractionState,
 
     /// Font cache (font name -> Font object)
     fonts: FxHashMap<String, Font>,
+
+    /// Family and style of the loaded fonts, by font name
+    font_traits: FxHashMap<String, FontTraits>,
+
+    /// Color spaces of the /ColorSpace resources, by name
+    color_spaces: FxHashMap<String, ColorSpace>,
+
+    /// How text is assembled by `extract_text()`
+    extraction_mode: ExtractionMode,
+
+    /// Whether `extract_text()` fills in `TextItem::glyphs`
+    glyph_geometry: bool,
+
+    /// Resources set by `load_resources()`, where Form XObjects are looked
+    /// up
+    resources: Option<PDFObject>,
+
+    /// Evaluators of the Form XObjects drawn so far, by XObject name
+    forms: FxHashMap<String, ContentStreamEvaluator>,
+
+    /// How many Form XObjects this content is nested in
+    form_depth: usize,
 }
 
 /// State for text extraction from content streams.
@@ -488,10 +842,75 @@ struct TextExtractionState {
     /// Whether we're in a text object (BT...ET)
     in_text_object: bool,
 
+    /// Open marked-content sequences (BMC/BDC...EMC) and their MCIDs
+    marked_content: Vec<Option<u32>>,
+
+    /// Character spacing (Tc) in unscaled text space units
+    char_spacing: f64,
+
+    /// Word spacing (Tw) in unscaled text space units
+    word_spacing: f64,
+
+    /// Horizontal scaling (Tz) as a fraction
+    horizontal_scaling: f64,
+
+    /// Text rise (Ts) in unscaled text space units
+    text_rise: f64,
+
+    /// Current fill color space; `None` if it couldn't be resolved
+    fill_space: Option<ColorSpace>,
+
+    /// Current fill color as RGB, `None` for patterns and unknown spaces
+    fill_color: Option<[f64; 3]>,
+
+    /// Fill color space and color saved by q, restored by Q
+    saved_fill: Vec<(Option<ColorSpace>, Option<[f64; 3]>)>,
+
     /// Extracted text items
     extracted_text: Vec<TextItem>,
 }
 
+/// Family and style of a font, copied into the text items shown with it.
+#[derive(Debug, Clone, Default)]
+struct FontTraits {
+    family: Option<String>,
+    style: FontStyle,
+}
+
+impl FontTraits {
+    /// Reads the traits of a font from its resolved font descriptor.
+    fn new(font: &Font, descriptor: Option<&PDFObject>) -> Self {
+        let family = match descriptor {
+            Some(PDFObject::Dictionary(dict)) => match dict.get("FontFamily") {
+                Some(PDFObject::String(bytes) | PDFObject::HexString(bytes)) => {
+                    Some(decode_pdf_string(bytes))
+                }
+                _ => None,
+            },
+            _ => None,
+        };
+        // "ABCDEF+Arial-BoldMT" and "Arial,Bold" are both in family Arial
+        let family = family.filter(|family| !family.is_empty()).or_else(|| {
+            let name = font.base_font();
+            let name = name.split_once('+').map_or(name, |(_, name)| name);
+            let family = name.split(['-', ',']).next().unwrap_or(name);
+            (!family.is_empty()).then(|| family.to_string())
+        });
+
+        FontTraits {
+            family,
+            style: FontStyle::detect(font.base_font(), descriptor),
+        }
+    }
+}
+
+impl TextExtractionState {
+    /// Returns the MCID of the innermost marked-content sequence that has one.
+    fn current_mcid(&self) -> Option<u32> {
+        self.marked_content.iter().rev().find_map(|mcid| *mcid)
+    }
+}
+
 impl Default for TextExtractionState {
     fn default() -> Self {
         Self {
@@ -502,24 +921,99 @@ impl Default for TextExtractionState {
             current_font_size: None,
             text_rendering_mode: None,
             in_text_object: false,
+            marked_content: Vec::new(),
+            char_spacing: 0.0,
+            word_spacing: 0.0,
+            horizontal_scaling: 1.0,
+            text_rise: 0.0,
+            fill_space: Some(ColorSpace::DeviceGray),
+            fill_color: Some([0.0; 3]),
+            saved_fill: Vec::new(),
             extracted_text: Vec::new(),
         }
     }
 }
 
+/// The input of a `ContentStreamEvaluator`.
+enum OperationSource {
+    /// A parser over a stream, which may not be fully loaded yet
+    Parser(Parser),
+
+    /// Decoded content read with a `ContentScanner`, the position of the
+    /// next operation and the scanner's nesting limit
+    Bytes {
+        data: Vec<u8>,
+        pos: usize,
+        max_depth: usize,
+    },
+}
+
 impl ContentStreamEvaluator {
     /// Creates a new content stream evaluator.
     ///
     /// # Arguments
     /// * `parser` - Parser positioned at the start of the content stream
     pub fn new(parser: Parser) -> Self {
+        Self::with_source(OperationSource::Parser(parser))
+    }
+
+    /// Creates an evaluator over decoded content.
+    ///
+    /// Operations are read with a [`ContentScanner`], which is much faster
+    /// than going through a `Lexer` and `Parser`. Prefer this whenever the
+    /// whole content stream is in memory.
+    ///
+    /// # Arguments
+    /// * `data` - The decoded content stream
+    pub fn from_bytes(data: Vec<u8>) -> Self {
+        Self::with_source(OperationSource::Bytes {
+            data,
+            pos: 0,
+            max_depth: ResourceLimits::default().max_object_depth,
+        })
+    }
+
+    fn with_source(source: OperationSource) -> Self {
         ContentStreamEvaluator {
-            parser,
+            source,
             text_state: TextExtractionState::default(),
             fonts: FxHashMap::default(),
+            font_traits: FxHashMap::default(),
+            color_spaces: FxHashMap::default(),
+            extraction_mode: ExtractionMode::default(),
+            glyph_geometry: false,
+            resources: None,
+            forms: FxHashMap::default(),
+            form_depth: 0,
         }
     }
 
+    /// Sets how deeply arrays and dictionaries may nest in an operand
+    /// before reading fails with [`PDFError::LimitExceeded`] (see
+    /// [`ResourceLimits::max_object_depth`]).
+    pub fn set_max_depth(&mut self, max_depth: usize) {
+        match &mut self.source {
+            OperationSource::Parser(parser) => parser.set_max_depth(max_depth),
+            OperationSource::Bytes { max_depth: max, .. } => *max = max_depth,
+        }
+    }
+
+    /// Sets how `extract_text()` assembles text items.
+    ///
+    /// # Arguments
+    /// * `mode` - The extraction mode (defaults to `ExtractionMode::Layout`)
+    pub fn set_extraction_mode(&mut self, mode: ExtractionMode) {
+        self.extraction_mode = mode;
+    }
+
+    /// Sets whether `extract_text()` records the geometry of every glyph in
+    /// `TextItem::glyphs`.
+    ///
+    /// Off by default, since it allocates per glyph.
+    pub fn set_glyph_geometry(&mut self, enabled: bool) {
+        self.glyph_geometry = enabled;
+    }
+
     /// Loads fonts from a page's resources dictionary.
     ///
     /// This should be called before processing a page's content stream
@@ -533,42 +1027,88 @@ impl ContentStreamEvaluator {
         resources: &PDFObject,
         xref: &mut crate::core::xref::XRef,
     ) -> PDFResult<()> {
-        // Get the Font dictionary from resources
-        let resources_dict = match resources {
-            PDFObject::Dictionary(d) => d,
-            _ => return Ok(()), // No resources, skip
-        };
+        let fonts = load_font_map(resources, xref)?;
+        for (name, font) in &fonts {
+            // A missing or broken descriptor leaves only the name to go by
+            let descriptor = font
+                .dict
+                .font_descriptor
+                .as_ref()
+                .and_then(|descriptor| xref.fetch_if_ref(descriptor).ok());
+            self.font_traits
+                .insert(name.clone(), FontTraits::new(font, descriptor.as_ref()));
+        }
+        self.fonts.extend(fonts);
+        Ok(())
+    }
 
-        let font_dict = match resources_dict.get("Font") {
-            Some(PDFObject::Dictionary(d)) => d.clone(),
-            Some(PDFObject::Ref(ref_obj)) => {
-                // Dereference the font dictionary
-                let font_obj = xref.fetch(ref_obj.num, ref_obj.generation)?;
-                match &*font_obj {
-                    PDFObject::Dictionary(d) => d.clone(),
-                    _ => return Ok(()), // Not a dictionary
-                }
-            }
-            _ => return Ok(()), // No fonts
+    /// Loads the color spaces of a page's resources dictionary, so that
+    /// `TextItem::fill_color` can be resolved for text drawn in them.
+    ///
+    /// Color spaces that can't be parsed are skipped; text drawn in them
+    /// has no fill color.
+    ///
+    /// # Arguments
+    /// * `resources` - The page's /Resources dictionary
+    /// * `xref` - Cross-reference table for fetching color space objects
+    pub fn load_color_spaces(
+        &mut self,
+        resources: &PDFObject,
+        xref: &mut crate::core::xref::XRef,
+    ) -> PDFResult<()> {
+        let resources = xref.fetch_if_ref(resources)?;
+        let color_spaces = match &resources {
+            PDFObject::Dictionary(dict) => match dict.get("ColorSpace") {
+                Some(color_spaces) => xref.fetch_if_ref(color_spaces)?,
+                None => return Ok(()),
+            },
+            _ => return Ok(()),
+        };
+        let PDFObject::Dictionary(color_spaces) = color_spaces else {
+            return Ok(());
         };
 
-        // Load each font
-        for (font_name, font_ref) in &font_dict {
-            // Fetch the font dictionary
-            let font_dict_obj = xref.fetch_if_ref(font_ref)?;
-
-            // Create Font object
-            match Font::new(font_dict_obj, xref) {
-                Ok(font) => {
-                    self.fonts.insert(font_name.clone(), font);
+        for (name, obj) in &color_spaces {
+            match ColorSpace::parse(obj, xref) {
+                Ok(color_space) => {
+                    self.color_spaces.insert(name.clone(), color_space);
                 }
+                Err(e @ (PDFError::DataMissing { .. } | PDFError::Cancelled)) => return Err(e),
                 Err(e) => {
-                    // Log error but continue loading other fonts
-                    eprintln!("Warning: Failed to load font '{}': {:?}", font_name, e);
+                    tracing::warn!(
+                        target: log_target::TEXT,
+                        "Failed to load color space '{}': {}", name, e
+                    );
                 }
             }
         }
+        Ok(())
+    }
 
+    /// Loads the fonts and color spaces of a resources dictionary, and keeps
+    /// it to look up the Form XObjects drawn by
+    /// [`extract_text_with_forms`](Self::extract_text_with_forms).
+    ///
+    /// Fonts that fail to load are skipped (their text falls back to the
+    /// standard encoding), but missing data and cancellation are reported.
+    ///
+    /// # Arguments
+    /// * `resources` - The /Resources dictionary of the page or form
+    /// * `xref` - Cross-reference table for fetching resource objects
+    pub fn load_resources(
+        &mut self,
+        resources: &PDFObject,
+        xref: &mut crate::core::xref::XRef,
+    ) -> PDFResult<()> {
+        match self.load_fonts(resources, xref) {
+            Ok(()) => {}
+            Err(e @ (PDFError::DataMissing { .. } | PDFError::Cancelled)) => return Err(e),
+            Err(e) => {
+                tracing::warn!(target: log_target::TEXT, "Failed to load fonts: {}", e);
+            }
+        }
+        self.load_color_spaces(resources, xref)?;
+        self.resources = Some(resources.clone());
         Ok(())
     }
 
@@ -608,6 +1148,127 @@ impl ContentStreamEvaluator {
         Ok(self.text_state.extracted_text.clone())
     }
 
+    /// Like [`extract_text`](Self::extract_text), but also extracts the text
+    /// of the Form XObjects the content draws (`Do`).
+    ///
+    /// Forms are looked up in the resources given to
+    /// [`load_resources`](Self::load_resources) and start with the fill
+    /// color and text state in effect where they are drawn. Forms nested
+    /// deeper than [`ResourceLimits::max_form_depth`] are skipped.
+    ///
+    /// # Arguments
+    /// * `xref` - Cross-reference table for loading the forms
+    pub fn extract_text_with_forms(
+        &mut self,
+        xref: &mut crate::core::xref::XRef,
+    ) -> PDFResult<Vec<TextItem>> {
+        self.text_state = TextExtractionState::default();
+        self.run_with_forms(xref)?;
+        Ok(std::mem::take(&mut self.text_state.extracted_text))
+    }
+
+    /// Processes all operations from the current position, drawing forms.
+    fn run_with_forms(&mut self, xref: &mut crate::core::xref::XRef) -> PDFResult<()> {
+        while let Some(op) = self.read_operation()? {
+            match op.op {
+                OpCode::PaintXObject => {
+                    if let Some(name) = op.args.first().and_then(|arg| arg.as_name()) {
+                        self.draw_form(name, xref)?;
+                    }
+                }
+                _ => self.process_text_operation(&op)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Extracts the text of a Form XObject drawn with `Do`.
+    fn draw_form(&mut self, name: &str, xref: &mut crate::core::xref::XRef) -> PDFResult<()> {
+        let mut form = match self.forms.remove(name) {
+            Some(form) => form,
+            None => {
+                if self.form_depth >= xref.limits().max_form_depth {
+                    tracing::warn!(
+                        target: log_target::TEXT,
+                        "Skipping form '{}' nested deeper than {}",
+                        name,
+                        xref.limits().max_form_depth
+                    );
+                    return Ok(());
+                }
+                match self.load_form(name, xref)? {
+                    Some(form) => form,
+                    None => return Ok(()),
+                }
+            }
+        };
+
+        // The form runs in the graphics state of the `Do`, which its end
+        // restores
+        form.text_state = TextExtractionState {
+            in_text_object: false,
+            saved_fill: Vec::new(),
+            extracted_text: Vec::new(),
+            ..self.text_state.clone()
+        };
+        if let OperationSource::Bytes { pos, .. } = &mut form.source {
+            *pos = 0;
+        }
+        let result = form.run_with_forms(xref);
+        self.text_state
+            .extracted_text
+            .append(&mut form.text_state.extracted_text);
+        self.forms.insert(name.to_string(), form);
+        result
+    }
+
+    /// Creates the evaluator of a Form XObject of the resources, or returns
+    /// `None` if `name` isn't a form.
+    fn load_form(
+        &self,
+        name: &str,
+        xref: &mut crate::core::xref::XRef,
+    ) -> PDFResult<Option<ContentStreamEvaluator>> {
+        let Some(resources) = &self.resources else {
+            return Ok(None);
+        };
+        let xobjects = match xref.fetch_if_ref(resources)? {
+            PDFObject::Dictionary(dict) => match dict.get("XObject") {
+                Some(xobjects) => xref.fetch_if_ref(xobjects)?,
+                None => return Ok(None),
+            },
+            _ => return Ok(None),
+        };
+        let Some(source) = xobjects.as_dictionary().and_then(|dict| dict.get(name)) else {
+            return Ok(None);
+        };
+        let dict = match xref.fetch_if_ref(source)? {
+            PDFObject::Stream { dict, .. } => dict,
+            _ => return Ok(None),
+        };
+        if !matches!(dict.get("Subtype"), Some(PDFObject::Name(subtype)) if subtype == "Form") {
+            return Ok(None);
+        }
+        let data = match xref.decoded_stream(source) {
+            Ok(data) => data,
+            Err(e @ (PDFError::DataMissing { .. } | PDFError::Cancelled)) => return Err(e),
+            Err(e) => {
+                tracing::warn!(target: log_target::TEXT, "Failed to decode form '{}': {}", name, e);
+                return Ok(None);
+            }
+        };
+
+        let mut form = ContentStreamEvaluator::from_bytes(data.to_vec());
+        form.set_max_depth(xref.limits().max_object_depth);
+        form.extraction_mode = self.extraction_mode;
+        form.glyph_geometry = self.glyph_geometry;
+        form.form_depth = self.form_depth + 1;
+        // A form without /Resources uses those of the content drawing it
+        let form_resources = dict.get("Resources").unwrap_or(resources);
+        form.load_resources(form_resources, xref)?;
+        Ok(Some(form))
+    }
+
     /// Processes an operation for text extraction.
     fn process_text_operation(&mut self, op: &Operation) -> PDFResult<()> {
         match op.op {
@@ -620,6 +1281,61 @@ impl ContentStreamEvaluator {
             OpCode::EndText => {
                 self.text_state.in_text_object = false;
             }
+            OpCode::Save => {
+                let state = &mut self.text_state;
+                state
+                    .saved_fill
+                    .push((state.fill_space.clone(), state.fill_color));
+            }
+            OpCode::Restore => {
+                if let Some((space, color)) = self.text_state.saved_fill.pop() {
+                    self.text_state.fill_space = space;
+                    self.text_state.fill_color = color;
+                }
+            }
+            OpCode::SetFillGray | OpCode::SetFillRGBColor | OpCode::SetFillCMYKColor => {
+                let space = match op.op {
+                    OpCode::SetFillGray => ColorSpace::DeviceGray,
+                    OpCode::SetFillRGBColor => ColorSpace::DeviceRGB,
+                    _ => ColorSpace::DeviceCMYK,
+                };
+                self.set_fill_color(Some(space), &op.args);
+            }
+            OpCode::SetFillColorSpace => {
+                let space = op
+                    .args
+                    .first()
+                    .and_then(|arg| arg.as_name())
+                    .and_then(|name| {
+                        ColorSpace::from_name(name).or_else(|| self.color_spaces.get(name).cloned())
+                    });
+                self.text_state.fill_color = space
+                    .as_ref()
+                    .and_then(|space| fill_rgb(space, &space.initial_color()));
+                self.text_state.fill_space = space;
+            }
+            OpCode::SetFillColor | OpCode::SetFillColorN => {
+                let space = self.text_state.fill_space.take();
+                self.set_fill_color(space, &op.args);
+            }
+            OpCode::BeginMarkedContent => {
+                self.text_state.marked_content.push(None);
+            }
+            OpCode::BeginMarkedContentProps => {
+                // BDC - read the MCID of an inline property list; named
+                // property lists (/Properties resources) aren't resolved
+                let mcid = match op.args.get(1) {
+                    Some(PDFObject::Dictionary(props)) => match props.get("MCID") {
+                        Some(PDFObject::Number(n)) if *n >= 0.0 => Some(*n as u32),
+                        _ => None,
+                    },
+                    _ => None,
+                };
+                self.text_state.marked_content.push(mcid);
+            }
+            OpCode::EndMarkedContent => {
+                self.text_state.marked_content.pop();
+            }
             OpCode::SetFont => {
                 // Tf - set font and size
                 if op.args.len() >= 2 {
@@ -631,6 +1347,26 @@ impl ContentStreamEvaluator {
                     }
                 }
             }
+            OpCode::SetCharSpacing => {
+                if let Some(PDFObject::Number(spacing)) = op.args.first() {
+                    self.text_state.char_spacing = *spacing;
+                }
+            }
+            OpCode::SetWordSpacing => {
+                if let Some(PDFObject::Number(spacing)) = op.args.first() {
+                    self.text_state.word_spacing = *spacing;
+                }
+            }
+            OpCode::SetHScale => {
+                if let Some(PDFObject::Number(scale)) = op.args.first() {
+                    self.text_state.horizontal_scaling = *scale / 100.0;
+                }
+            }
+            OpCode::SetTextRise => {
+                if let Some(PDFObject::Number(rise)) = op.args.first() {
+                    self.text_state.text_rise = *rise;
+                }
+            }
             OpCode::SetTextRenderingMode => {
                 if op.args.len() >= 1 {
                     if let PDFObject::Number(mode) = &op.args[0] {
@@ -674,22 +1410,31 @@ impl ContentStreamEvaluator {
             OpCode::ShowText => {
                 // Tj - show text string
                 if op.args.len() >= 1 && self.text_state.in_text_object {
-                    if let PDFObject::String(text_bytes) = &op.args[0] {
+                    if let PDFObject::String(text_bytes) | PDFObject::HexString(text_bytes) =
+                        &op.args[0]
+                    {
                         let text = self.decode_text(text_bytes);
                         let position = Some((
                             self.text_state.text_matrix[4],
                             self.text_state.text_matrix[5],
                         ));
+                        let measured = self.measure_text(text_bytes);
+                        let glyphs = self.glyph_geometry(text_bytes);
 
                         let text_item = TextItem {
-                            text,
-                            font_name: self.text_state.current_font_name.clone(),
-                            font_size: self.text_state.current_font_size,
-                            position,
-                            rendering_mode: self.text_state.text_rendering_mode,
+                            width: measured.as_ref().map(|(_, tx)| tx * self.text_scale()),
+                            char_spans: measured.as_ref().map(|(spans, _)| spans.clone()),
+                            glyphs,
+                            ..self.text_item(text, position)
                         };
 
                         self.text_state.extracted_text.push(text_item);
+                        if let Some((_, tx)) = measured {
+                            self.advance_text_matrix(tx);
+                        } else if let Some(font) = self.vertical_font() {
+                            let ty = self.measure_vertical(font, text_bytes);
+                            self.advance_text_matrix_vertical(ty);
+                        }
                     }
                 }
             }
@@ -700,32 +1445,97 @@ impl ContentStreamEvaluator {
                 if op.args.len() >= 1 && self.text_state.in_text_object {
                     if let PDFObject::Array(items) = &op.args[0] {
                         let mut accumulated_text = String::new();
+                        let mut accumulated_spans = Some(Vec::new());
+                        let mut accumulated_glyphs = self.glyph_geometry.then(Vec::new);
+                        // Offset of the text matrix from the start, in user space
+                        let mut offset = 0.0;
                         let start_position = Some((
                             self.text_state.text_matrix[4],
                             self.text_state.text_matrix[5],
                         ));
+                        let raw = self.extraction_mode == ExtractionMode::Raw;
+                        let scale = self.text_scale();
 
                         for item in items {
                             match &**item {
-                                PDFObject::String(text_bytes) => {
+                                PDFObject::String(text_bytes)
+                                | PDFObject::HexString(text_bytes) => {
                                     // Decode text using font encoding (CMap)
                                     let text = self.decode_text(text_bytes);
-                                    accumulated_text.push_str(&text);
+                                    let measured = self.measure_text(text_bytes);
+                                    let glyphs = self.glyph_geometry(text_bytes);
+                                    if raw {
+                                        // One item per string, at its own position
+                                        let position = Some((
+                                            self.text_state.text_matrix[4],
+                                            self.text_state.text_matrix[5],
+                                        ));
+                                        let text_item = TextItem {
+                                            width: measured.as_ref().map(|(_, tx)| tx * scale),
+                                            char_spans: measured
+                                                .as_ref()
+                                                .map(|(spans, _)| spans.clone()),
+                                            glyphs,
+                                            ..self.text_item(text, position)
+                                        };
+                                        self.text_state.extracted_text.push(text_item);
+                                    } else {
+                                        accumulated_text.push_str(&text);
+                                        accumulated_glyphs = accumulated_glyphs.zip(glyphs).map(
+                                            |(mut all, glyphs)| {
+                                                all.extend(glyphs);
+                                                all
+                                            },
+                                        );
+                                    }
+                                    match measured {
+                                        Some((spans, tx)) => {
+                                            if let Some(accumulated) = accumulated_spans.as_mut() {
+                                                accumulated.extend(spans.iter().map(
+                                                    |(start, end)| (offset + start, offset + end),
+                                                ));
+                                            }
+                                            offset += tx * scale;
+                                            self.advance_text_matrix(tx);
+                                        }
+                                        None => {
+                                            accumulated_spans = None;
+                                            if let Some(font) = self.vertical_font() {
+                                                let ty = self.measure_vertical(font, text_bytes);
+                                                self.advance_text_matrix_vertical(ty);
+                                            }
+                                        }
+                                    }
                                 }
                                 PDFObject::Number(spacing) => {
                                     // Spacing adjustment in 1/1000ths of a text space unit
                                     // Negative numbers move text closer together (like kerning)
                                     // Large negative numbers (< -100) typically indicate word spaces
+                                    let font_size =
+                                        self.text_state.current_font_size.unwrap_or(12.0);
+                                    if self.vertical_font().is_some() {
+                                        // Vertical fonts: the adjustment moves along y
+                                        self.advance_text_matrix_vertical(
+                                            -spacing * font_size * 0.001,
+                                        );
+                                        continue;
+                                    }
+                                    let tx = -spacing
+                                        * font_size
+                                        * 0.001
+                                        * self.text_state.horizontal_scaling;
 
                                     // Add a space if the adjustment is significant (word boundary)
-                                    if *spacing < -100.0 {
+                                    if *spacing < -100.0 && !raw {
                                         accumulated_text.push(' ');
+                                        if let Some(accumulated) = accumulated_spans.as_mut() {
+                                            accumulated.push((offset, offset + tx * scale));
+                                        }
                                     }
 
                                     // Adjust text position for spacing
-                                    let font_size =
-                                        self.text_state.current_font_size.unwrap_or(12.0);
-                                    self.text_state.text_matrix[4] -= spacing * font_size * 0.001;
+                                    offset += tx * scale;
+                                    self.advance_text_matrix(tx);
                                 }
                                 _ => {}
                             }
@@ -734,11 +1544,10 @@ impl ContentStreamEvaluator {
                         // Create a single text item for the entire TJ operation
                         if !accumulated_text.is_empty() {
                             let text_item = TextItem {
-                                text: accumulated_text,
-                                font_name: self.text_state.current_font_name.clone(),
-                                font_size: self.text_state.current_font_size,
-                                position: start_position,
-                                rendering_mode: self.text_state.text_rendering_mode,
+                                width: accumulated_spans.as_ref().map(|_| offset),
+                                char_spans: accumulated_spans,
+                                glyphs: accumulated_glyphs,
+                                ..self.text_item(accumulated_text, start_position)
                             };
 
                             self.text_state.extracted_text.push(text_item);
@@ -753,6 +1562,213 @@ impl ContentStreamEvaluator {
         Ok(())
     }
 
+    /// A text item in the current text state, without measurements.
+    fn text_item(&self, text: String, position: Option<(f64, f64)>) -> TextItem {
+        let state = &self.text_state;
+        let traits = state
+            .current_font_name
+            .as_ref()
+            .and_then(|name| self.font_traits.get(name));
+        TextItem {
+            text,
+            font_name: state.current_font_name.clone(),
+            font_size: state.current_font_size,
+            position,
+            rendering_mode: state.text_rendering_mode,
+            width: None,
+            mcid: state.current_mcid(),
+            char_spans: None,
+            vertical_extent: self.vertical_extent(),
+            glyphs: None,
+            font_family: traits.and_then(|traits| traits.family.clone()),
+            bold: traits.is_some_and(|traits| traits.style.bold),
+            italic: traits.is_some_and(|traits| traits.style.italic),
+            fill_color: state.fill_color,
+        }
+    }
+
+    /// Sets the fill color space and the fill color from the numeric
+    /// operands of g, rg, k, sc or scn.
+    fn set_fill_color(&mut self, space: Option<ColorSpace>, args: &[PDFObject]) {
+        let components: Vec<f64> = args
+            .iter()
+            .filter_map(|arg| match arg {
+                PDFObject::Number(n) => Some(*n),
+                _ => None,
+            })
+            .collect();
+        self.text_state.fill_color = space
+            .as_ref()
+            .and_then(|space| fill_rgb(space, &components));
+        self.text_state.fill_space = space;
+    }
+
+    /// Horizontal scale of the text matrix.
+    fn text_scale(&self) -> f64 {
+        let [a, b, ..] = self.text_state.text_matrix;
+        (a * a + b * b).sqrt()
+    }
+
+    /// Moves the text matrix along the baseline by `tx` text space units.
+    fn advance_text_matrix(&mut self, tx: f64) {
+        let [a, b, ..] = self.text_state.text_matrix;
+        self.text_state.text_matrix[4] += a * tx;
+        self.text_state.text_matrix[5] += b * tx;
+    }
+
+    /// Moves the text matrix along the vertical writing direction by `ty`
+    /// text space units (negative moves down).
+    fn advance_text_matrix_vertical(&mut self, ty: f64) {
+        let [_, _, c, d, ..] = self.text_state.text_matrix;
+        self.text_state.text_matrix[4] += c * ty;
+        self.text_state.text_matrix[5] += d * ty;
+    }
+
+    /// Returns the current font if it uses vertical writing (Identity-V).
+    fn vertical_font(&self) -> Option<&Font> {
+        self.fonts
+            .get(self.text_state.current_font_name.as_ref()?)
+            .filter(|font| font.is_vertical())
+    }
+
+    /// Vertical advance of text bytes in a vertical font, in text space units.
+    ///
+    /// Uses the /W2 (or /DW2) advances plus character and word spacing as in
+    /// PDF spec 9.4.4; horizontal scaling does not apply.
+    fn measure_vertical(&self, font: &Font, text_bytes: &[u8]) -> f64 {
+        let font_size = self.text_state.current_font_size.unwrap_or(12.0);
+        let mut ty = 0.0;
+        let mut i = 0;
+        while i < text_bytes.len() {
+            let (code, len) = font.read_char_code(text_bytes, i);
+            let mut spacing = self.text_state.char_spacing;
+            if len == 1 && code == 32 {
+                spacing += self.text_state.word_spacing;
+            }
+            ty += font.code_vertical_advance(code) * font_size / 1000.0 + spacing;
+            i += len;
+        }
+        ty
+    }
+
+    /// Measures text bytes with the current font's glyph widths.
+    ///
+    /// Character codes are read with the font's code length, so Type0 fonts
+    /// (e.g. Identity-H) are measured per 2-byte code with their /W widths.
+    /// Applies character spacing, word spacing (to single-byte code 32) and
+    /// horizontal scaling as in PDF spec 9.4.4.
+    ///
+    /// # Returns
+    /// The extent of each decoded character relative to the text origin in user space,
+    /// and the total advance in text space units; `None` for vertical fonts and
+    /// fonts without glyph widths
+    fn measure_text(&self, text_bytes: &[u8]) -> Option<(Vec<(f64, f64)>, f64)> {
+        let font = self
+            .fonts
+            .get(self.text_state.current_font_name.as_ref()?)?;
+        if !font.has_widths() || font.is_vertical() {
+            return None;
+        }
+        let font_size = self.text_state.current_font_size.unwrap_or(12.0);
+        let scaling = self.text_state.horizontal_scaling;
+        let scale = self.text_scale();
+
+        let mut spans = Vec::with_capacity(text_bytes.len());
+        let mut tx = 0.0;
+        let mut glyph_text = String::new();
+        let mut i = 0;
+        while i < text_bytes.len() {
+            let (code, len) = font.read_char_code(text_bytes, i);
+            let glyph_width = font.code_text_width(code) * font_size * scaling;
+            // One span per decoded character: a ligature glyph mapped to
+            // several characters is split evenly between them
+            glyph_text.clear();
+            font.push_unicode(code, &mut glyph_text);
+            let count = glyph_text.chars().count();
+            for i in 0..count {
+                let start = tx + glyph_width * i as f64 / count as f64;
+                let end = tx + glyph_width * (i + 1) as f64 / count as f64;
+                spans.push((start * scale, end * scale));
+            }
+            let mut spacing = self.text_state.char_spacing;
+            if len == 1 && code == 32 {
+                spacing += self.text_state.word_spacing;
+            }
+            tx += glyph_width + spacing * scaling;
+            i += len;
+        }
+        Some((spans, tx))
+    }
+
+    /// Text state that places glyphs.
+    fn glyph_spacing(&self) -> GlyphSpacing {
+        GlyphSpacing {
+            font_size: self.text_state.current_font_size.unwrap_or(12.0),
+            char_spacing: self.text_state.char_spacing,
+            word_spacing: self.text_state.word_spacing,
+            horizontal_scaling: self.text_state.horizontal_scaling,
+            rise: self.text_state.text_rise,
+        }
+    }
+
+    /// Geometry of each glyph of text bytes shown at the current text matrix.
+    ///
+    /// See [`layout_glyphs`] for how glyphs are placed.
+    ///
+    /// # Returns
+    /// `None` unless glyph geometry is enabled, and for fonts without glyph
+    /// widths
+    fn glyph_geometry(&self, text_bytes: &[u8]) -> Option<Vec<TextGlyph>> {
+        if !self.glyph_geometry {
+            return None;
+        }
+        let font = self
+            .fonts
+            .get(self.text_state.current_font_name.as_ref()?)?;
+        let placements = layout_glyphs(Some(font), &self.glyph_spacing(), text_bytes, None)?;
+        let [a, b, c, d, e, f] = self.text_state.text_matrix;
+        let to_user = |x: f64, y: f64| (a * x + c * y + e, b * x + d * y + f);
+        let vertical_scale = (c * c + d * d).sqrt();
+
+        let mut origin = (0.0, 0.0);
+        let glyphs = placements
+            .into_iter()
+            .map(|glyph| {
+                let [x0, y0, x1, y1] = glyph.rect;
+                let (ox, oy) = origin;
+                let (qx0, qy0) = to_user(ox + x0, oy + y0);
+                let (qx1, qy1) = to_user(ox + x1, oy + y0);
+                let (qx2, qy2) = to_user(ox + x1, oy + y1);
+                let (qx3, qy3) = to_user(ox + x0, oy + y1);
+                let mut unicode = String::new();
+                font.push_unicode(glyph.code, &mut unicode);
+                let (tx, ty) = glyph.advance;
+                origin = (ox + tx, oy + ty);
+                TextGlyph {
+                    code: glyph.code,
+                    unicode,
+                    advance: if font.is_vertical() {
+                        -ty * vertical_scale
+                    } else {
+                        tx * self.text_scale()
+                    },
+                    quad: [qx0, qy0, qx1, qy1, qx2, qy2, qx3, qy3],
+                }
+            })
+            .collect();
+        Some(glyphs)
+    }
+
+    /// Descent and ascent of the current font in user space.
+    fn vertical_extent(&self) -> Option<(f64, f64)> {
+        let font = self
+            .fonts
+            .get(self.text_state.current_font_name.as_ref()?)?;
+        let (ascent, descent) = (font.dict.ascent?, font.dict.descent?);
+        let size = self.text_state.current_font_size.unwrap_or(12.0) * self.text_scale() / 1000.0;
+        Some((descent * size, ascent * size))
+    }
+
     /// Decodes text bytes using the current font's encoding (CMap).
     ///
     /// This method converts character codes (CIDs) to Unicode characters using
@@ -773,29 +1789,13 @@ impl ContentStreamEvaluator {
             if let Some(font) = self.fonts.get(font_name) {
                 let mut decoded = String::new();
 
-                // Check if this is a CID font (multi-byte character codes)
-                if font.font_type().is_cid_font() {
-                    // CID fonts use multi-byte character codes (typically 2 bytes)
-                    let mut i = 0;
-                    while i + 1 < text_bytes.len() {
-                        // Combine two bytes to form a CID
-                        let cid = u16::from_be_bytes([text_bytes[i], text_bytes[i + 1]]);
-                        let unicode_char = font.char_code_to_unicode(cid);
-                        decoded.push(unicode_char);
-                        i += 2;
-                    }
-                    // Handle any remaining single byte
-                    if i < text_bytes.len() {
-                        let cid = text_bytes[i] as u16;
-                        decoded.push(font.char_code_to_unicode(cid));
-                    }
-                } else {
-                    // Simple fonts use single-byte character codes
-                    for &byte in text_bytes {
-                        let cid = byte as u16;
-                        let unicode_char = font.char_code_to_unicode(cid);
-                        decoded.push(unicode_char);
-                    }
+                // CID fonts use multi-byte character codes (typically 2 bytes),
+                // simple fonts single bytes
+                let mut i = 0;
+                while i < text_bytes.len() {
+                    let (code, len) = font.read_char_code(text_bytes, i);
+                    font.push_unicode(code, &mut decoded);
+                    i += len;
                 }
 
                 return decoded;
@@ -834,11 +1834,25 @@ impl ContentStreamEvaluator {
     /// }
     /// ```
     pub fn read_operation(&mut self) -> PDFResult<Option<Operation>> {
+        let parser = match &mut self.source {
+            OperationSource::Parser(parser) => parser,
+            OperationSource::Bytes {
+                data,
+                pos,
+                max_depth,
+            } => {
+                let mut scanner = ContentScanner::at(data, *pos);
+                scanner.set_max_depth(*max_depth);
+                let op = scanner.next_operation();
+                *pos = scanner.position();
+                return Ok(op?.map(|op| op.to_operation()));
+            }
+        };
         let mut args = Vec::new();
 
         loop {
             // Check if we have more content
-            if !self.parser.has_more() {
+            if !parser.has_more() {
                 // End of stream
                 if args.is_empty() {
                     return Ok(None);
@@ -850,7 +1864,7 @@ impl ContentStreamEvaluator {
             }
 
             // Read next object - this can throw DataNotLoaded!
-            let obj = self.parser.get_object()?;
+            let obj = parser.get_object()?;
 
             match obj {
                 PDFObject::EOF => {
@@ -865,7 +1879,7 @@ impl ContentStreamEvaluator {
                 // Commands are operators
                 obj if obj.is_command_like() => {
                     // Extract command string
-                    let cmd_str = self.extract_command(&obj)?;
+                    let cmd_str = Self::extract_command(&obj)?;
                     let op = OpCode::from_command(&cmd_str)?;
                     return Ok(Some(Operation::new(op, args)));
                 }
@@ -878,7 +1892,7 @@ impl ContentStreamEvaluator {
     }
 
     /// Extracts the command string from a PDFObject.
-    fn extract_command(&self, obj: &PDFObject) -> PDFResult<String> {
+    fn extract_command(obj: &PDFObject) -> PDFResult<String> {
         match obj {
             PDFObject::Command(cmd) => Ok(cmd.clone()),
             _ => Err(PDFError::Generic(format!(
@@ -888,9 +1902,24 @@ impl ContentStreamEvaluator {
         }
     }
 
+    /// Returns the offset in the decoded content of the next operation, for
+    /// evaluators created with [`from_bytes`](Self::from_bytes).
+    ///
+    /// A parser reads ahead, so the position isn't known for evaluators
+    /// created with [`new`](Self::new), which return None.
+    pub fn position(&self) -> Option<usize> {
+        match &self.source {
+            OperationSource::Parser(_) => None,
+            OperationSource::Bytes { pos, .. } => Some(*pos),
+        }
+    }
+
     /// Checks if there are more operations to read.
     pub fn has_more(&self) -> bool {
-        self.parser.has_more()
+        match &self.source {
+            OperationSource::Parser(parser) => parser.has_more(),
+            OperationSource::Bytes { data, pos, .. } => ContentScanner::at(data, *pos).has_more(),
+        }
     }
 }
 
@@ -991,6 +2020,36 @@ mod tests {
         assert_eq!(op4.op, OpCode::EndText);
     }
 
+    #[test]
+    fn test_read_inline_image() {
+        let mut eval =
+            create_evaluator("q BI /W 2 /H 1 /CS /RGB /BPC 8 /F [/AHx] ID\n0a EIx)( 0b\nEI\nQ");
+
+        assert_eq!(eval.read_operation().unwrap().unwrap().op, OpCode::Save);
+
+        let op = eval.read_operation().unwrap().unwrap();
+        assert_eq!(op.op, OpCode::EndInlineImage);
+        let (dict, data) = match &op.args[..] {
+            [PDFObject::Stream { dict, data }] => (dict, data),
+            other => panic!("Expected inline image stream, got {:?}", other),
+        };
+        assert_eq!(dict.get("Width"), Some(&PDFObject::Number(2.0)));
+        assert_eq!(
+            dict.get("ColorSpace").and_then(|cs| cs.as_name()),
+            Some("DeviceRGB")
+        );
+        assert_eq!(
+            dict.get("Filter")
+                .and_then(|f| f.as_array())
+                .and_then(|f| f[0].as_name()),
+            Some("ASCIIHexDecode")
+        );
+        assert_eq!(data, b"0a EIx)( 0b");
+
+        assert_eq!(eval.read_operation().unwrap().unwrap().op, OpCode::Restore);
+        assert!(eval.read_operation().unwrap().is_none());
+    }
+
     #[test]
     fn test_unknown_operator() {
         let mut eval = create_evaluator("10 20 XYZ");
@@ -1041,6 +2100,36 @@ mod tests {
         assert_eq!(text_items[0].font_size, Some(12.0));
     }
 
+    #[test]
+    fn test_extract_text_raw_mode() {
+        let content = "BT\n/F1 12 Tf\n100 200 Td\n[(Hello) -250 (World)] TJ\n(!) Tj\nET";
+        let mut eval = create_evaluator(content);
+        eval.set_extraction_mode(ExtractionMode::Raw);
+
+        let text_items = eval.extract_text().unwrap();
+
+        // One item per string, no space for the -250 adjustment
+        let texts: Vec<&str> = text_items.iter().map(|item| item.text.as_str()).collect();
+        assert_eq!(texts, vec!["Hello", "World", "!"]);
+        assert_eq!(text_items[0].position, Some((100.0, 200.0)));
+        assert_eq!(text_items[1].position, Some((103.0, 200.0)));
+    }
+
+    #[test]
+    fn test_extract_text_marked_content_ids() {
+        let content = "/P <</MCID 0>> BDC BT /F1 12 Tf (Intro) Tj /Span BMC (nested) Tj EMC ET EMC \
+                       /Artifact BMC BT (Page 1) Tj ET EMC";
+        let mut eval = create_evaluator(content);
+
+        let mcids: Vec<Option<u32>> = eval
+            .extract_text()
+            .unwrap()
+            .iter()
+            .map(|item| item.mcid)
+            .collect();
+        assert_eq!(mcids, vec![Some(0), Some(0), None]);
+    }
+
     #[test]
     fn test_extract_text_ignores_graphics() {
         let content = "10 20 m\n30 40 l\nS\nBT\n/F1 12 Tf\n100 200 Td\n(Text) Tj\nET";
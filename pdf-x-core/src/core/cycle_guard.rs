@@ -0,0 +1,134 @@
+//! Detection of reference cycles in the object graph.
+//!
+//! Indirect references let any object point at any other, and nothing in
+//! the file format stops them from pointing back: an outline item whose
+//! /Next is an earlier item, a page tree node that is its own grandparent,
+//! a form XObject that paints itself. Every walk over the object graph
+//! records the references it follows in a [`CycleGuard`] and stops at the
+//! first one it has already seen.
+//!
+//! Walks over trees (the page tree, the outline, form fields) visit each
+//! object once, so any repeat is a cycle. Walks where objects may be shared
+//! (a form XObject painted by several other forms) only track the objects
+//! on the current path, removing each with [`leave`](CycleGuard::leave)
+//! once it is done.
+
+use super::error::{PDFError, PDFResult};
+use super::parser::{PDFObject, Ref};
+use std::collections::HashSet;
+
+/// The indirect objects visited by a walk over the object graph.
+///
+/// # Example
+/// ```no_run
+/// use pdf_x_core::core::{CycleGuard, PDFObject, Ref, XRef};
+///
+/// # fn walk(xref: &mut XRef, first: Ref) -> pdf_x_core::core::error::PDFResult<()> {
+/// // Follow a /Next chain that may loop back on itself
+/// let mut guard = CycleGuard::new();
+/// let mut next = Some(first);
+/// while let Some(r) = next {
+///     if !guard.visit(r) {
+///         break;
+///     }
+///     next = match &*xref.fetch(r.num, r.generation)? {
+///         PDFObject::Dictionary(dict) => match dict.get("Next") {
+///             Some(PDFObject::Ref(r)) => Some(*r),
+///             _ => None,
+///         },
+///         _ => None,
+///     };
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CycleGuard {
+    visited: HashSet<Ref>,
+}
+
+impl CycleGuard {
+    /// Creates a guard that hasn't visited anything.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a visit to `r`.
+    ///
+    /// # Returns
+    /// True the first time `r` is visited, false if it was visited before
+    pub fn visit(&mut self, r: Ref) -> bool {
+        self.visited.insert(r)
+    }
+
+    /// Records a visit to `object` if it is a reference.
+    ///
+    /// # Returns
+    /// False if `object` is a reference visited before; direct objects
+    /// can't form cycles and always return true
+    pub fn visit_object(&mut self, object: &PDFObject) -> bool {
+        match object {
+            PDFObject::Ref(r) => self.visit(*r),
+            _ => true,
+        }
+    }
+
+    /// Records a visit to `r`, failing if it was visited before.
+    ///
+    /// # Arguments
+    /// * `r` - The reference being followed
+    /// * `structure` - What is being walked, for the error message (e.g.
+    ///   "page tree")
+    pub fn enter(&mut self, r: Ref, structure: &str) -> PDFResult<()> {
+        if self.visit(r) {
+            Ok(())
+        } else {
+            Err(PDFError::corrupted_pdf(format!(
+                "Circular reference to object {} {} in {}",
+                r.num, r.generation, structure
+            )))
+        }
+    }
+
+    /// Forgets the visit to `r`, once the walk has come back out of it.
+    pub fn leave(&mut self, r: Ref) {
+        self.visited.remove(&r);
+    }
+
+    /// Returns true if `r` has been visited.
+    pub fn contains(&self, r: Ref) -> bool {
+        self.visited.contains(&r)
+    }
+
+    /// Returns the number of objects visited.
+    pub fn len(&self) -> usize {
+        self.visited.len()
+    }
+
+    /// Returns true if nothing has been visited.
+    pub fn is_empty(&self) -> bool {
+        self.visited.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cycle_guard() {
+        let mut guard = CycleGuard::new();
+        assert!(guard.visit(Ref::new(1, 0)));
+        assert!(!guard.visit(Ref::new(1, 0)));
+        assert!(guard.visit(Ref::new(1, 1)));
+        assert!(guard.visit_object(&PDFObject::Null));
+        assert!(!guard.visit_object(&PDFObject::Ref(Ref::new(1, 1))));
+        assert_eq!(guard.len(), 2);
+
+        let err = guard.enter(Ref::new(1, 0), "outline").unwrap_err();
+        assert!(err.to_string().contains("object 1 0 in outline"), "{}", err);
+        guard.leave(Ref::new(1, 0));
+        assert!(!guard.contains(Ref::new(1, 0)));
+        assert!(guard.enter(Ref::new(1, 0), "outline").is_ok());
+    }
+}
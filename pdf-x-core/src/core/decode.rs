@@ -5,8 +5,10 @@
 ///
 /// Based on PDF.js src/core/flate_stream.js, decode_stream.js, and predictor_stream.js
 use super::error::{PDFError, PDFResult};
+use super::limits::ResourceLimits;
 use super::parser::PDFObject;
 use flate2::read::ZlibDecoder;
+use std::collections::HashMap;
 use std::io::Read;
 
 /// PNG predictor algorithm types (used in DecodeParms)
@@ -43,8 +45,16 @@ pub enum PngPredictor {
 /// let decompressed = decode_flate(&compressed).unwrap();
 /// ```
 pub fn decode_flate(compressed_data: &[u8]) -> PDFResult<Vec<u8>> {
+    inflate(compressed_data, usize::MAX)
+}
+
+/// Decodes FlateDecode data, stopping once more than `max_len` bytes are
+/// decoded so that the caller can reject a decompression bomb without
+/// inflating it.
+fn inflate(compressed_data: &[u8], max_len: usize) -> PDFResult<Vec<u8>> {
+    let read_limit = max_len.saturating_add(1) as u64;
     // Try zlib format first (most common)
-    let mut decoder = ZlibDecoder::new(compressed_data);
+    let mut decoder = ZlibDecoder::new(compressed_data).take(read_limit);
     let mut decompressed = Vec::new();
 
     match decoder.read_to_end(&mut decompressed) {
@@ -54,24 +64,168 @@ pub fn decode_flate(compressed_data: &[u8]) -> PDFResult<Vec<u8>> {
             use flate2::read::DeflateDecoder;
 
             decompressed.clear();
-            let mut raw_decoder = DeflateDecoder::new(compressed_data);
+            let mut raw_decoder = DeflateDecoder::new(compressed_data).take(read_limit);
             match raw_decoder.read_to_end(&mut decompressed) {
                 Ok(_) => Ok(decompressed),
                 Err(deflate_err) => {
                     // Both failed - provide detailed error
-                    Err(PDFError::Generic(format!(
-                        "FlateDecode error: zlib failed ({}), raw deflate failed ({}). Data length: {} bytes, first 10 bytes: {:02x?}",
-                        zlib_err,
-                        deflate_err,
-                        compressed_data.len(),
-                        &compressed_data[..compressed_data.len().min(10)]
-                    )))
+                    Err(PDFError::filter_error(
+                        "FlateDecode",
+                        format!(
+                            "zlib failed ({}), raw deflate failed ({}). Data length: {} bytes, first 10 bytes: {:02x?}",
+                            zlib_err,
+                            deflate_err,
+                            compressed_data.len(),
+                            &compressed_data[..compressed_data.len().min(10)]
+                        ),
+                    ))
                 }
             }
         }
     }
 }
 
+/// Compresses data with FlateDecode (zlib).
+///
+/// Used when writing modified streams back to a document.
+///
+/// # Arguments
+/// * `data` - The data to compress
+///
+/// # Returns
+/// The zlib-compressed data
+pub fn encode_flate(data: &[u8]) -> PDFResult<Vec<u8>> {
+    use flate2::Compression;
+    use flate2::write::ZlibEncoder;
+    use std::io::Write;
+
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(data)
+        .map_err(|e| PDFError::filter_error("FlateDecode", format!("encoding failed: {}", e)))?;
+    encoder
+        .finish()
+        .map_err(|e| PDFError::filter_error("FlateDecode", format!("encoding failed: {}", e)))
+}
+
+/// Decodes an LZWDecode compressed stream.
+///
+/// LZW was the standard compression filter before FlateDecode and is still
+/// found in files produced by older tools. Codes are 9 to 12 bits wide, with
+/// 256 clearing the table and 257 marking the end of data.
+///
+/// # Arguments
+/// * `compressed_data` - The compressed stream data
+/// * `early_change` - Whether the code width grows one code early, as given
+///   by the EarlyChange entry in DecodeParms (default true)
+///
+/// # Returns
+/// The decompressed data as a Vec<u8>
+///
+/// Based on PDF.js src/core/lzw_stream.js
+pub fn decode_lzw(compressed_data: &[u8], early_change: bool) -> PDFResult<Vec<u8>> {
+    lzw(compressed_data, early_change, usize::MAX)
+}
+
+/// Decodes LZWDecode data, stopping once more than `max_len` bytes are
+/// decoded.
+fn lzw(compressed_data: &[u8], early_change: bool, max_len: usize) -> PDFResult<Vec<u8>> {
+    const CLEAR_TABLE: usize = 256;
+    const END_OF_DATA: usize = 257;
+    const FIRST_CODE: usize = 258;
+    const MAX_CODES: usize = 4096;
+
+    // The table stores each sequence as its prefix code plus one byte
+    let mut prefix = vec![0u16; MAX_CODES];
+    let mut suffix = vec![0u8; MAX_CODES];
+    let mut first_byte = vec![0u8; MAX_CODES];
+    let mut length = vec![0usize; MAX_CODES];
+    for i in 0..256 {
+        suffix[i] = i as u8;
+        first_byte[i] = i as u8;
+        length[i] = 1;
+    }
+
+    let early_change = early_change as usize;
+    let mut output = Vec::with_capacity(compressed_data.len().saturating_mul(2).min(max_len));
+    let mut next_code = FIRST_CODE;
+    let mut code_length = 9;
+    let mut prev_code: Option<usize> = None;
+
+    let mut bit_buffer: u32 = 0;
+    let mut bit_count = 0;
+    let mut bytes = compressed_data.iter();
+
+    while output.len() <= max_len {
+        while bit_count < code_length {
+            match bytes.next() {
+                Some(&byte) => {
+                    bit_buffer = (bit_buffer << 8) | byte as u32;
+                    bit_count += 8;
+                }
+                // Missing end-of-data marker; keep what was decoded
+                None => return Ok(output),
+            }
+        }
+        bit_count -= code_length;
+        let code = ((bit_buffer >> bit_count) & ((1 << code_length) - 1)) as usize;
+
+        if code == CLEAR_TABLE {
+            next_code = FIRST_CODE;
+            code_length = 9;
+            prev_code = None;
+            continue;
+        }
+        if code == END_OF_DATA {
+            break;
+        }
+
+        if let Some(prev) = prev_code {
+            // A code one past the table is the previous sequence plus its own
+            // first byte
+            let first = if code < next_code {
+                first_byte[code]
+            } else if code == next_code && next_code < MAX_CODES {
+                first_byte[prev]
+            } else {
+                return Err(PDFError::filter_error(
+                    "LZWDecode",
+                    format!("invalid code {}", code),
+                ));
+            };
+
+            if next_code < MAX_CODES {
+                prefix[next_code] = prev as u16;
+                suffix[next_code] = first;
+                first_byte[next_code] = first_byte[prev];
+                length[next_code] = length[prev] + 1;
+                next_code += 1;
+                if next_code + early_change >= 1 << code_length && code_length < 12 {
+                    code_length += 1;
+                }
+            }
+        } else if code > CLEAR_TABLE {
+            return Err(PDFError::filter_error(
+                "LZWDecode",
+                format!("invalid code {}", code),
+            ));
+        }
+
+        // Write the sequence back to front by following the prefix chain
+        let start = output.len();
+        output.resize(start + length[code], 0);
+        let mut entry = code;
+        for pos in (start..output.len()).rev() {
+            output[pos] = suffix[entry];
+            entry = prefix[entry] as usize;
+        }
+
+        prev_code = Some(code);
+    }
+
+    Ok(output)
+}
+
 /// Applies PNG predictor decoding to decompressed data.
 ///
 /// PNG predictors are used to improve compression by predicting pixel values
@@ -101,11 +255,14 @@ pub fn decode_png_predictor(
     // Calculate expected output size
     let num_rows = data.len() / stride;
     if data.len() % stride != 0 {
-        return Err(PDFError::Generic(format!(
-            "PNG predictor data size mismatch: {} bytes doesn't divide evenly by stride {}",
-            data.len(),
-            stride
-        )));
+        return Err(PDFError::filter_error(
+            "Predictor",
+            format!(
+                "PNG predictor data size mismatch: {} bytes doesn't divide evenly by stride {}",
+                data.len(),
+                stride
+            ),
+        ));
     }
 
     let mut output = Vec::with_capacity(num_rows * row_bytes);
@@ -193,10 +350,10 @@ pub fn decode_png_predictor(
                 }
             }
             _ => {
-                return Err(PDFError::Generic(format!(
-                    "Unsupported PNG predictor: {}",
-                    predictor_byte
-                )));
+                return Err(PDFError::filter_error(
+                    "Predictor",
+                    format!("invalid PNG predictor byte {}", predictor_byte),
+                ));
             }
         }
     }
@@ -204,6 +361,114 @@ pub fn decode_png_predictor(
     Ok(output)
 }
 
+/// Applies TIFF predictor 2 (horizontal differencing) decoding.
+///
+/// Each component is stored as the difference from the same component of
+/// the pixel to its left.
+///
+/// # Arguments
+/// * `data` - The decompressed data with TIFF prediction applied
+/// * `colors` - Number of color components per pixel
+/// * `bits_per_component` - Bits per color component (1, 2, 4, 8 or 16)
+/// * `columns` - Number of pixels per row
+///
+/// # Returns
+/// The data with TIFF prediction reversed (raw pixel data)
+pub fn decode_tiff_predictor(
+    data: &[u8],
+    colors: usize,
+    bits_per_component: usize,
+    columns: usize,
+) -> PDFResult<Vec<u8>> {
+    if !matches!(bits_per_component, 1 | 2 | 4 | 8 | 16) {
+        return Err(PDFError::unsupported(format!(
+            "TIFF predictor with {} bits per component",
+            bits_per_component
+        )));
+    }
+    let row_bytes = (columns * colors * bits_per_component).div_ceil(8);
+    if row_bytes == 0 {
+        return Ok(data.to_vec());
+    }
+
+    let mut output = data.to_vec();
+    for row in output.chunks_mut(row_bytes) {
+        match bits_per_component {
+            8 => {
+                for i in colors..row.len() {
+                    row[i] = row[i].wrapping_add(row[i - colors]);
+                }
+            }
+            16 => {
+                let pix_bytes = colors * 2;
+                for i in (pix_bytes..row.len().saturating_sub(1)).step_by(2) {
+                    let left = u16::from_be_bytes([row[i - pix_bytes], row[i - pix_bytes + 1]]);
+                    let value = u16::from_be_bytes([row[i], row[i + 1]]).wrapping_add(left);
+                    row[i..i + 2].copy_from_slice(&value.to_be_bytes());
+                }
+            }
+            bits => {
+                // Components are packed most significant bits first and
+                // added modulo 2^bits
+                let mask = (1u8 << bits) - 1;
+                let components = row.len() * 8 / bits;
+                for k in colors..components.min(columns * colors) {
+                    let left = read_packed(row, k - colors, bits) & mask;
+                    let value = read_packed(row, k, bits).wrapping_add(left) & mask;
+                    let bit = k * bits;
+                    let shift = 8 - bits - bit % 8;
+                    row[bit / 8] = (row[bit / 8] & !(mask << shift)) | (value << shift);
+                }
+            }
+        }
+    }
+
+    Ok(output)
+}
+
+/// Reads the `index`th `bits`-wide component of a row packed most
+/// significant bits first.
+fn read_packed(row: &[u8], index: usize, bits: usize) -> u8 {
+    let bit = index * bits;
+    row[bit / 8] >> (8 - bits - bit % 8)
+}
+
+/// Reverses the predictor described by a filter's DecodeParms, if any.
+///
+/// # Arguments
+/// * `data` - The output of a FlateDecode or LZWDecode filter
+/// * `params` - The filter's DecodeParms dictionary
+///
+/// # Returns
+/// The data with prediction reversed
+fn apply_predictor(data: Vec<u8>, params: Option<&PDFObject>) -> PDFResult<Vec<u8>> {
+    let predictor = param_int(params, "Predictor", 1);
+    if predictor <= 1 {
+        return Ok(data);
+    }
+
+    let colors = param_int(params, "Colors", 1).max(1) as usize;
+    let bits_per_component = param_int(params, "BitsPerComponent", 8).max(1) as usize;
+    let columns = param_int(params, "Columns", 1).max(1) as usize;
+
+    match predictor {
+        2 => decode_tiff_predictor(&data, colors, bits_per_component, columns),
+        10..=15 => decode_png_predictor(&data, colors, bits_per_component, columns),
+        other => Err(PDFError::unsupported(format!("predictor {}", other))),
+    }
+}
+
+/// Reads an integer entry from a DecodeParms dictionary.
+fn param_int(params: Option<&PDFObject>, key: &str, default: i64) -> i64 {
+    match params {
+        Some(PDFObject::Dictionary(dict)) => match dict.get(key) {
+            Some(PDFObject::Number(n)) => *n as i64,
+            _ => default,
+        },
+        _ => default,
+    }
+}
+
 /// Decodes a stream based on its Filter entry.
 ///
 /// PDF streams can have a /Filter entry specifying the compression algorithm.
@@ -211,6 +476,9 @@ pub fn decode_png_predictor(
 ///
 /// Supported filters:
 /// - /FlateDecode - zlib/deflate compression
+/// - /LZWDecode - LZW compression (with the default EarlyChange)
+/// - /RunLengthDecode - byte-oriented run-length encoding
+/// - /ASCIIHexDecode and /ASCII85Decode - ASCII transport encodings
 ///
 /// # Arguments
 /// * `data` - The stream data (potentially compressed)
@@ -220,8 +488,12 @@ pub fn decode_png_predictor(
 /// The decoded/decompressed data
 pub fn decode_stream(data: &[u8], filter_name: Option<&str>) -> PDFResult<Vec<u8>> {
     match filter_name {
-        Some("FlateDecode") => decode_flate(data),
-        Some(filter) => Err(PDFError::Generic(format!("Unsupported filter: {}", filter))),
+        Some(filter) => {
+            let limits = ResourceLimits::default();
+            let decoded = apply_filter(data, filter, None, limits.max_decoded_len(data.len()))?;
+            limits.check_decoded(data.len(), decoded.len())?;
+            Ok(decoded)
+        }
         None => {
             // No filter - return data as-is
             Ok(data.to_vec())
@@ -229,6 +501,47 @@ pub fn decode_stream(data: &[u8], filter_name: Option<&str>) -> PDFResult<Vec<u8
     }
 }
 
+/// Decodes a stream's data using the Filter and DecodeParms of its dictionary.
+///
+/// The default [`ResourceLimits`] apply; see [`decode_stream_data_limited`].
+///
+/// # Arguments
+/// * `dict` - The stream dictionary
+/// * `data` - The raw stream data
+///
+/// # Returns
+/// The decoded data, or a copy of `data` if the stream has no filter
+pub fn decode_stream_data(dict: &HashMap<String, PDFObject>, data: &[u8]) -> PDFResult<Vec<u8>> {
+    decode_stream_data_limited(dict, data, &ResourceLimits::default())
+}
+
+/// Decodes a stream's data, failing with [`PDFError::LimitExceeded`] if it
+/// decodes to more than the stream size or decompression ratio limit allow.
+///
+/// # Arguments
+/// * `dict` - The stream dictionary
+/// * `data` - The raw stream data
+/// * `limits` - The limits of the document the stream belongs to
+///
+/// # Returns
+/// The decoded data, or a copy of `data` if the stream has no filter
+pub fn decode_stream_data_limited(
+    dict: &HashMap<String, PDFObject>,
+    data: &[u8],
+    limits: &ResourceLimits,
+) -> PDFResult<Vec<u8>> {
+    match dict.get("Filter") {
+        Some(filters) => {
+            match decode_until_image_filter_limited(data, filters, dict.get("DecodeParms"), limits)?
+            {
+                (decoded, None) => Ok(decoded),
+                (_, Some(image_filter)) => Err(image_filter_error(&image_filter)),
+            }
+        }
+        None => Ok(data.to_vec()),
+    }
+}
+
 /// Decodes ASCIIHex-encoded data.
 ///
 /// ASCIIHex encoding represents each byte as two hexadecimal characters.
@@ -282,7 +595,8 @@ pub fn decode_ascii_hex(data: &[u8]) -> PDFResult<Vec<u8>> {
 /// The decoded binary data
 pub fn decode_ascii85(data: &[u8]) -> PDFResult<Vec<u8>> {
     let mut result = Vec::new();
-    let mut tuple = 0u32;
+    // Wide enough that out-of-range groups can't overflow
+    let mut tuple = 0u64;
     let mut count = 0usize;
 
     for &byte in data {
@@ -305,7 +619,7 @@ pub fn decode_ascii85(data: &[u8]) -> PDFResult<Vec<u8>> {
             continue;
         } else if ch >= '!' && ch <= 'u' {
             // Regular ASCII85 character
-            let value = (ch as u32) - ('!' as u32);
+            let value = (ch as u64) - ('!' as u64);
             tuple = tuple * 85 + value;
             count += 1;
 
@@ -320,18 +634,18 @@ pub fn decode_ascii85(data: &[u8]) -> PDFResult<Vec<u8>> {
             }
         } else {
             // Invalid character
-            return Err(PDFError::Generic(format!(
-                "Invalid ASCII85 character: '{}'",
-                ch
-            )));
+            return Err(PDFError::filter_error(
+                "ASCII85Decode",
+                format!("invalid character '{}'", ch),
+            ));
         }
     }
 
     // Handle partial tuple at end
     if count > 0 {
-        // Pad with zeros
+        // Pad with 'u' (the highest digit) so truncation rounds correctly
         for _ in count..5 {
-            tuple = tuple * 85;
+            tuple = tuple * 85 + 84;
         }
         // Convert to bytes, but only output (count - 1) bytes
         let bytes = [
@@ -346,34 +660,100 @@ pub fn decode_ascii85(data: &[u8]) -> PDFResult<Vec<u8>> {
     Ok(result)
 }
 
+/// Decodes RunLength-encoded data.
+///
+/// Each run starts with a length byte: 0-127 copies the next `length + 1`
+/// bytes literally, 129-255 repeats the next byte `257 - length` times and
+/// 128 marks the end of data.
+///
+/// # Arguments
+/// * `data` - The RunLength-encoded data
+///
+/// # Returns
+/// The decoded binary data
+pub fn decode_run_length(data: &[u8]) -> PDFResult<Vec<u8>> {
+    run_length(data, usize::MAX)
+}
+
+/// Decodes RunLengthDecode data, stopping once more than `max_len` bytes
+/// are decoded.
+fn run_length(data: &[u8], max_len: usize) -> PDFResult<Vec<u8>> {
+    let mut result = Vec::with_capacity(data.len().saturating_mul(2).min(max_len));
+    let mut pos = 0;
+
+    while pos < data.len() && result.len() <= max_len {
+        let length = data[pos] as usize;
+        pos += 1;
+
+        match length {
+            0..=127 => {
+                // Tolerate a truncated final run
+                let end = (pos + length + 1).min(data.len());
+                result.extend_from_slice(&data[pos..end]);
+                pos = end;
+            }
+            128 => break,
+            _ => {
+                if let Some(&byte) = data.get(pos) {
+                    result.extend(std::iter::repeat_n(byte, 257 - length));
+                }
+                pos += 1;
+            }
+        }
+    }
+
+    Ok(result)
+}
+
 /// Applies a single filter to data.
 ///
 /// # Arguments
 /// * `data` - The input data
 /// * `filter_name` - The filter name
+/// * `params` - The filter's DecodeParms dictionary, if any
+/// * `max_len` - Decoding stops once the output is longer than this
 ///
 /// # Returns
 /// The filtered data
-fn apply_filter(data: &[u8], filter_name: &str) -> PDFResult<Vec<u8>> {
+fn apply_filter(
+    data: &[u8],
+    filter_name: &str,
+    params: Option<&PDFObject>,
+    max_len: usize,
+) -> PDFResult<Vec<u8>> {
     match filter_name {
-        "FlateDecode" | "Fl" => decode_flate(data),
+        "FlateDecode" | "Fl" => apply_predictor(inflate(data, max_len)?, params),
+        "LZWDecode" | "LZW" => {
+            let early_change = param_int(params, "EarlyChange", 1) != 0;
+            apply_predictor(lzw(data, early_change, max_len)?, params)
+        }
         "ASCIIHexDecode" | "AHx" => decode_ascii_hex(data),
         "ASCII85Decode" | "A85" => decode_ascii85(data),
-        _ => Err(PDFError::Generic(format!(
-            "Unsupported filter: {}",
-            filter_name
-        ))),
+        "RunLengthDecode" | "RL" => run_length(data, max_len),
+        // Decrypted by the xref when the stream was read
+        "Crypt" => Ok(data.to_vec()),
+        _ => Err(PDFError::unsupported(format!("filter {}", filter_name))),
     }
 }
 
+/// Image codec filters, which are decoded by the image module rather than here.
+const IMAGE_FILTERS: &[&str] = &[
+    "DCTDecode",
+    "DCT",
+    "JPXDecode",
+    "JBIG2Decode",
+    "CCITTFaxDecode",
+    "CCF",
+];
+
 /// Applies multiple filters to data in sequence.
 ///
 /// PDF streams can have multiple filters that are applied in order.
-/// For example: [/FlateDecode /ASCIIHexDecode] means ASCIIHex decode first,
+/// For example: [/ASCII85Decode /FlateDecode] means ASCII85 decode first,
 /// then Flate decode the result.
 ///
-/// IMPORTANT: Filters in the array are applied in LAST-to-FIRST order.
-/// The LAST filter is applied FIRST.
+/// Filters are applied with default parameters; use
+/// [`apply_filters_with_params`] when the stream has a DecodeParms entry.
 ///
 /// # Arguments
 /// * `data` - The input data
@@ -382,6 +762,115 @@ fn apply_filter(data: &[u8], filter_name: &str) -> PDFResult<Vec<u8>> {
 /// # Returns
 /// The fully decoded data
 pub fn apply_filters(data: &[u8], filters: &PDFObject) -> PDFResult<Vec<u8>> {
+    apply_filters_with_params(data, filters, None)
+}
+
+/// Applies multiple filters to data, honoring the stream's DecodeParms.
+///
+/// `decode_parms` is either a single dictionary (for a single filter) or an
+/// array with one entry per filter, where null means default parameters.
+/// Image codecs (DCTDecode, JPXDecode, ...) are not handled here; see
+/// [`decode_until_image_filter`].
+///
+/// # Arguments
+/// * `data` - The input data
+/// * `filters` - The stream's Filter entry
+/// * `decode_parms` - The stream's DecodeParms entry, if any
+///
+/// # Returns
+/// The fully decoded data
+///
+/// # Example
+/// ```no_run
+/// use pdf_x_core::core::decode::apply_filters_with_params;
+/// use pdf_x_core::core::PDFObject;
+///
+/// # fn decode(dict: &std::collections::HashMap<String, PDFObject>, data: &[u8]) {
+/// if let Some(filters) = dict.get("Filter") {
+///     let decoded = apply_filters_with_params(data, filters, dict.get("DecodeParms")).unwrap();
+/// }
+/// # }
+/// ```
+pub fn apply_filters_with_params(
+    data: &[u8],
+    filters: &PDFObject,
+    decode_parms: Option<&PDFObject>,
+) -> PDFResult<Vec<u8>> {
+    match decode_until_image_filter(data, filters, decode_parms)? {
+        (decoded, None) => Ok(decoded),
+        (_, Some(image_filter)) => Err(image_filter_error(&image_filter)),
+    }
+}
+
+fn image_filter_error(image_filter: &str) -> PDFError {
+    PDFError::filter_error(
+        image_filter,
+        "image data must be decoded by the image decoder",
+    )
+}
+
+/// Returns the image codec in a Filter entry (e.g. "DCTDecode"), if any.
+///
+/// # Arguments
+/// * `filters` - The stream's Filter entry (a name or an array of names)
+pub fn image_filter(filters: &PDFObject) -> Option<&str> {
+    fn codec(obj: &PDFObject) -> Option<&str> {
+        match obj {
+            PDFObject::Name(name) if IMAGE_FILTERS.contains(&name.as_str()) => Some(name),
+            _ => None,
+        }
+    }
+
+    match filters {
+        PDFObject::Array(arr) => arr.iter().find_map(|f| codec(f)),
+        other => codec(other),
+    }
+}
+
+/// Applies a stream's filters up to the first image codec.
+///
+/// Image streams often wrap their codec in transport filters, e.g.
+/// [/ASCII85Decode /DCTDecode]. This undoes the transport filters and
+/// returns the codec so the caller can hand the data to the image decoder.
+///
+/// # Arguments
+/// * `data` - The input data
+/// * `filters` - The stream's Filter entry
+/// * `decode_parms` - The stream's DecodeParms entry, if any
+///
+/// # Returns
+/// The partially decoded data and the name of the image filter that remains
+/// to be applied, if any
+pub fn decode_until_image_filter(
+    data: &[u8],
+    filters: &PDFObject,
+    decode_parms: Option<&PDFObject>,
+) -> PDFResult<(Vec<u8>, Option<String>)> {
+    decode_until_image_filter_limited(data, filters, decode_parms, &ResourceLimits::default())
+}
+
+/// Applies a stream's filters up to the first image codec, failing with
+/// [`PDFError::LimitExceeded`] if the data grows past the stream size or
+/// decompression ratio limit.
+///
+/// Each filter stops decoding as soon as its output is over the limit, so a
+/// decompression bomb is rejected without being inflated.
+///
+/// # Arguments
+/// * `data` - The input data
+/// * `filters` - The stream's Filter entry
+/// * `decode_parms` - The stream's DecodeParms entry, if any
+/// * `limits` - The limits of the document the stream belongs to
+///
+/// # Returns
+/// The partially decoded data and the name of the image filter that remains
+/// to be applied, if any
+pub fn decode_until_image_filter_limited(
+    data: &[u8],
+    filters: &PDFObject,
+    decode_parms: Option<&PDFObject>,
+    limits: &ResourceLimits,
+) -> PDFResult<(Vec<u8>, Option<String>)> {
     // Extract filter list
     let filter_list = match filters {
         PDFObject::Name(name) => vec![name.clone()],
@@ -394,36 +883,52 @@ pub fn apply_filters(data: &[u8], filters: &PDFObject) -> PDFResult<Vec<u8>> {
             }
             list
         }
-        _ => return Ok(data.to_vec()), // No filters
+        _ => return Ok((data.to_vec(), None)), // No filters
     };
 
-    if filter_list.is_empty() {
-        return Ok(data.to_vec());
-    }
-
     #[cfg(feature = "debug-logging")]
-    eprintln!(
-        "DEBUG: Applying {} filters: {:?}",
+    tracing::trace!(
+        target: crate::log_target::DECODE,
+        "Applying {} filters: {:?}",
         filter_list.len(),
         filter_list
     );
 
-    // Apply filters in REVERSE order (last filter first)
+    // The first filter in the array is the first one to undo
+    let max_len = limits.max_decoded_len(data.len());
     let mut current_data = data.to_vec();
-    for filter_name in filter_list.iter().rev() {
+    for (index, filter_name) in filter_list.iter().enumerate() {
+        if IMAGE_FILTERS.contains(&filter_name.as_str()) {
+            return Ok((current_data, Some(filter_name.clone())));
+        }
+
         #[cfg(feature = "debug-logging")]
-        eprintln!("DEBUG: Applying filter: {}", filter_name);
-        current_data = apply_filter(&current_data, filter_name)
-            .map_err(|e| PDFError::Generic(format!("Filter {} failed: {}", filter_name, e)))?;
+        tracing::trace!(target: crate::log_target::DECODE, "Applying filter: {}", filter_name);
+        let params = match decode_parms {
+            Some(PDFObject::Array(arr)) => arr.get(index).map(|p| &**p),
+            Some(params) if index == 0 => Some(params),
+            _ => None,
+        };
+        current_data = apply_filter(&current_data, filter_name, params, max_len).map_err(|e| {
+            match e {
+                // Already structured: keep the filter (or predictor) that failed
+                PDFError::FilterError { .. }
+                | PDFError::Unsupported { .. }
+                | PDFError::LimitExceeded { .. } => e,
+                e => PDFError::filter_error(filter_name.as_str(), e),
+            }
+        })?;
+        limits.check_decoded(data.len(), current_data.len())?;
         #[cfg(feature = "debug-logging")]
-        eprintln!(
-            "DEBUG: After filter {}: {} bytes",
+        tracing::trace!(
+            target: crate::log_target::DECODE,
+            "After filter {}: {} bytes",
             filter_name,
             current_data.len()
         );
     }
 
-    Ok(current_data)
+    Ok((current_data, None))
 }
 
 #[cfg(test)]
@@ -450,6 +955,13 @@ mod tests {
         assert_eq!(&decompressed[..], original);
     }
 
+    #[test]
+    fn test_encode_flate_round_trip() {
+        let original = b"BT /F1 12 Tf (Hello) Tj ET";
+        let compressed = encode_flate(original).unwrap();
+        assert_eq!(decode_flate(&compressed).unwrap(), original.to_vec());
+    }
+
     #[test]
     fn test_decode_stream_with_flate() {
         let original = b"Test data for stream decoding";
@@ -487,6 +999,129 @@ mod tests {
         assert!(result.is_err());
     }
 
+    /// Minimal LZW encoder used to produce test input.
+    fn encode_lzw(data: &[u8], early_change: bool) -> Vec<u8> {
+        let early_change = early_change as usize;
+        let mut table: std::collections::HashMap<Vec<u8>, usize> =
+            (0..256).map(|i| (vec![i as u8], i)).collect();
+        let mut next_code = 258;
+        let mut code_length = 9;
+        let mut bits: Vec<bool> = Vec::new();
+        let write = |bits: &mut Vec<bool>, code: usize, len: usize| {
+            bits.extend((0..len).rev().map(|i| (code >> i) & 1 == 1));
+        };
+
+        write(&mut bits, 256, code_length);
+        let mut current: Vec<u8> = Vec::new();
+        for &byte in data {
+            let mut candidate = current.clone();
+            candidate.push(byte);
+            if table.contains_key(&candidate) {
+                current = candidate;
+                continue;
+            }
+            write(&mut bits, table[&current], code_length);
+            table.insert(candidate, next_code);
+            next_code += 1;
+            // The decoder adds its entries one code later
+            if next_code + early_change > 1 << code_length && code_length < 12 {
+                code_length += 1;
+            }
+            current = vec![byte];
+        }
+        if !current.is_empty() {
+            write(&mut bits, table[&current], code_length);
+        }
+        write(&mut bits, 257, code_length);
+
+        bits.chunks(8)
+            .map(|chunk| {
+                chunk
+                    .iter()
+                    .enumerate()
+                    .fold(0u8, |acc, (i, &bit)| acc | ((bit as u8) << (7 - i)))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_decode_lzw_spec_example() {
+        // Example from the PDF specification, section 7.4.4.2
+        let encoded = [0x80, 0x0B, 0x60, 0x50, 0x22, 0x0C, 0x0C, 0x85, 0x01];
+        assert_eq!(decode_lzw(&encoded, true).unwrap(), b"-----A---B");
+    }
+
+    #[test]
+    fn test_decode_lzw_round_trip() {
+        // Long enough to grow the code width past 9 bits
+        let original: Vec<u8> = (0..6000u32)
+            .map(|i| (i * 7 % 251) as u8 ^ (i / 100) as u8)
+            .collect();
+
+        for early_change in [true, false] {
+            let encoded = encode_lzw(&original, early_change);
+            assert_eq!(decode_lzw(&encoded, early_change).unwrap(), original);
+        }
+    }
+
+    #[test]
+    fn test_decode_lzw_invalid_code() {
+        let encoded = encode_lzw(b"", true);
+        assert_eq!(decode_lzw(&encoded, true).unwrap(), b"");
+
+        // Clear table followed by code 300, which is not in the table
+        assert!(decode_lzw(&[0x80, 0x4B, 0x00], true).is_err());
+    }
+
+    #[test]
+    fn test_apply_filters_lzw_with_predictor() {
+        // Two rows of three RGB pixels, PNG "Up" predictor on the second row
+        let rows: [&[u8]; 2] = [
+            &[0, 10, 20, 30, 40, 50, 60, 70, 80, 90],
+            &[2, 1, 1, 1, 1, 1, 1, 1, 1, 1],
+        ];
+        let predicted = rows.concat();
+        let encoded = encode_lzw(&predicted, false);
+
+        let mut params = std::collections::HashMap::new();
+        params.insert("Predictor".to_string(), PDFObject::Number(12.0));
+        params.insert("Colors".to_string(), PDFObject::Number(3.0));
+        params.insert("Columns".to_string(), PDFObject::Number(3.0));
+        params.insert("EarlyChange".to_string(), PDFObject::Number(0.0));
+
+        let decoded = apply_filters_with_params(
+            &encoded,
+            &PDFObject::Name("LZWDecode".into()),
+            Some(&PDFObject::Dictionary(params)),
+        )
+        .unwrap();
+        assert_eq!(
+            decoded,
+            vec![
+                10, 20, 30, 40, 50, 60, 70, 80, 90, 11, 21, 31, 41, 51, 61, 71, 81, 91
+            ]
+        );
+    }
+
+    #[test]
+    fn test_decode_tiff_predictor() {
+        let data = [10, 20, 1, 2, 1, 2, 5, 5, 1, 1, 1, 1];
+        let decoded = decode_tiff_predictor(&data, 2, 8, 3).unwrap();
+        assert_eq!(decoded, vec![10, 20, 11, 22, 12, 24, 5, 5, 6, 6, 7, 7]);
+
+        // 4 bits: 1, +2, +15 (wraps) per row of three pixels
+        let decoded = decode_tiff_predictor(&[0x12, 0xF0, 0x12, 0xF0], 1, 4, 3).unwrap();
+        assert_eq!(decoded, vec![0x13, 0x20, 0x13, 0x20]);
+
+        // 1 bit: each bit flips the one to its left
+        let decoded = decode_tiff_predictor(&[0b1000_0000, 0b1100_0000], 1, 1, 3).unwrap();
+        assert_eq!(decoded, vec![0b1110_0000, 0b1000_0000]);
+
+        // 2 bits, two colors
+        let decoded = decode_tiff_predictor(&[0b0110_0101], 2, 2, 2).unwrap();
+        assert_eq!(decoded, vec![0b0110_1011]);
+    }
+
     #[test]
     fn test_decode_ascii_hex_simple() {
         // Simple ASCIIHex: "48656C6C6F" = "Hello"
@@ -566,8 +1201,8 @@ mod tests {
 
         // Now decode using multi-filter
         let filters = PDFObject::Array(smallvec::smallvec![
-            Box::new(PDFObject::Name("FlateDecode".into())),
             Box::new(PDFObject::Name("ASCIIHexDecode".into())),
+            Box::new(PDFObject::Name("FlateDecode".into())),
         ]);
 
         let decoded = apply_filters(hex_encoded, &filters).unwrap();
@@ -581,7 +1216,7 @@ mod tests {
         use flate2::write::ZlibEncoder;
         use std::io::Write;
 
-        // Test that filters are applied in LAST-to-FIRST order
+        // Test that filters are applied in array order
         let original = b"Test data";
 
         // Compress
@@ -593,14 +1228,158 @@ mod tests {
         let hex_string = hex::encode_upper(&compressed);
         let hex_encoded = hex_string.as_bytes();
 
-        // Filters array: [/FlateDecode /ASCIIHexDecode]
+        // Filters array: [/ASCIIHexDecode /FlateDecode]
         // Should apply ASCIIHex FIRST, then Flate
         let filters = PDFObject::Array(smallvec::smallvec![
-            Box::new(PDFObject::Name("FlateDecode".into())),
             Box::new(PDFObject::Name("ASCIIHexDecode".into())),
+            Box::new(PDFObject::Name("FlateDecode".into())),
         ]);
 
         let decoded = apply_filters(hex_encoded, &filters).unwrap();
         assert_eq!(&decoded[..], original);
+
+        // The reverse order can't decode the data
+        let reversed = PDFObject::Array(smallvec::smallvec![
+            Box::new(PDFObject::Name("FlateDecode".into())),
+            Box::new(PDFObject::Name("ASCIIHexDecode".into())),
+        ]);
+        assert!(apply_filters(hex_encoded, &reversed).is_err());
+    }
+
+    #[test]
+    fn test_decode_run_length() {
+        // Literal run of 3, repeat run of 4, end of data, ignored trailer
+        let data = [2, b'a', b'b', b'c', 253, b'x', 128, b'z'];
+        assert_eq!(decode_run_length(&data).unwrap(), b"abcxxxx");
+
+        // Missing end-of-data marker and truncated literal run
+        assert_eq!(decode_run_length(&[255, b'y', 4, b'q']).unwrap(), b"yyq");
+        assert_eq!(
+            decode_stream(&[0, b'k', 128], Some("RunLengthDecode")).unwrap(),
+            b"k"
+        );
+    }
+
+    #[test]
+    fn test_multi_filter_ascii85_flate() {
+        let original = b"Hello";
+        let compressed = encode_flate(original).unwrap();
+
+        // Encode as ASCII85 (without 'z' shortcuts)
+        let mut ascii85 = Vec::new();
+        for chunk in compressed.chunks(4) {
+            let mut tuple = [0u8; 4];
+            tuple[..chunk.len()].copy_from_slice(chunk);
+            let mut value = u32::from_be_bytes(tuple);
+            let mut digits = [0u8; 5];
+            for digit in digits.iter_mut().rev() {
+                *digit = (value % 85) as u8 + b'!';
+                value /= 85;
+            }
+            ascii85.extend_from_slice(&digits[..chunk.len() + 1]);
+        }
+        ascii85.extend_from_slice(b"~>");
+
+        let filters = PDFObject::Array(smallvec::smallvec![
+            Box::new(PDFObject::Name("A85".into())),
+            Box::new(PDFObject::Name("Fl".into())),
+        ]);
+        assert_eq!(apply_filters(&ascii85, &filters).unwrap(), original);
+    }
+
+    #[test]
+    fn test_decode_stream_data_with_params_array() {
+        // Up predictor over two 2-byte rows, then hex-encoded
+        let predicted = [2, 1, 2, 2, 1, 1];
+        let compressed = encode_flate(&predicted).unwrap();
+        let hex = hex::encode_upper(&compressed);
+
+        let mut parms = HashMap::new();
+        parms.insert("Predictor".to_string(), PDFObject::Number(12.0));
+        parms.insert("Columns".to_string(), PDFObject::Number(2.0));
+
+        let mut dict = HashMap::new();
+        dict.insert(
+            "Filter".to_string(),
+            PDFObject::Array(smallvec::smallvec![
+                Box::new(PDFObject::Name("AHx".into())),
+                Box::new(PDFObject::Name("Fl".into())),
+            ]),
+        );
+        dict.insert(
+            "DecodeParms".to_string(),
+            PDFObject::Array(smallvec::smallvec![
+                Box::new(PDFObject::Null),
+                Box::new(PDFObject::Dictionary(parms)),
+            ]),
+        );
+
+        let decoded = decode_stream_data(&dict, hex.as_bytes()).unwrap();
+        assert_eq!(decoded, vec![1, 2, 2, 3]);
+    }
+
+    #[test]
+    fn test_image_filter() {
+        let chain = PDFObject::Array(smallvec::smallvec![
+            Box::new(PDFObject::Name("ASCII85Decode".into())),
+            Box::new(PDFObject::Name("DCTDecode".into())),
+        ]);
+        assert_eq!(image_filter(&chain), Some("DCTDecode"));
+        assert_eq!(
+            image_filter(&PDFObject::Name("JPXDecode".into())),
+            Some("JPXDecode")
+        );
+        assert_eq!(image_filter(&PDFObject::Name("FlateDecode".into())), None);
+    }
+
+    #[test]
+    fn test_decode_until_image_filter() {
+        let jpeg = [0xFF, 0xD8, 0xFF, 0xD9];
+        let filters = PDFObject::Array(smallvec::smallvec![
+            Box::new(PDFObject::Name("ASCIIHexDecode".into())),
+            Box::new(PDFObject::Name("DCTDecode".into())),
+        ]);
+
+        let (data, image_filter) = decode_until_image_filter(b"FFD8FFD9>", &filters, None).unwrap();
+        assert_eq!(data, jpeg);
+        assert_eq!(image_filter.as_deref(), Some("DCTDecode"));
+
+        // Full decoding stops with an error instead of returning JPEG bytes
+        assert!(apply_filters(b"FFD8FFD9>", &filters).is_err());
+    }
+
+    #[test]
+    fn test_decompression_limits() {
+        let bomb = encode_flate(&vec![0u8; 4 * 1024 * 1024]).unwrap();
+        let mut dict = HashMap::new();
+        dict.insert(
+            "Filter".to_string(),
+            PDFObject::Name("FlateDecode".to_string()),
+        );
+
+        assert_eq!(
+            decode_stream_data(&dict, &bomb).unwrap().len(),
+            4 * 1024 * 1024
+        );
+        let small_streams = ResourceLimits {
+            max_stream_size: 1024 * 1024,
+            ..ResourceLimits::default()
+        };
+        assert!(matches!(
+            decode_stream_data_limited(&dict, &bomb, &small_streams),
+            Err(PDFError::LimitExceeded { .. })
+        ));
+        let low_ratio = ResourceLimits {
+            max_decompression_ratio: 100,
+            ..ResourceLimits::default()
+        };
+        assert!(matches!(
+            decode_stream_data_limited(&dict, &bomb, &low_ratio),
+            Err(PDFError::LimitExceeded { max: 100, .. })
+        ));
+
+        // The output is cut short, not inflated in full
+        assert_eq!(inflate(&bomb, 1000).unwrap().len(), 1001);
+        assert_eq!(run_length(&[129, 7, 129, 7], 100).unwrap().len(), 128);
     }
 }
@@ -2,9 +2,41 @@
 //!
 //! The delta layer enables editing capabilities while keeping the base PDF immutable.
 //! All modifications are tracked separately and can be applied as incremental updates.
-
+//!
+//! Every document owns one [`DeltaLayer`]. `XRef::fetch` consults it before
+//! the object cache and the file, so a replaced object is what every later
+//! read sees, a deleted one is missing and an added one resolves like any
+//! other. Saving with `PDFDocument::save_incremental` appends exactly the
+//! objects the layer holds to the original bytes.
+//!
+//! Edits are made through [`Command`]s, which the layer keeps for undo and
+//! redo. The editing features of `PDFDocument` (page rotation, form filling,
+//! text replacement, ...) are all commands; `PDFDocument::replace_object`,
+//! `add_object` and `delete_object` stage single objects for edits that
+//! have no dedicated command.
+//!
+//! ```no_run
+//! use pdf_x_core::core::{PDFDocument, PDFObject, Ref};
+//!
+//! let mut doc = PDFDocument::open_file("document.pdf", None, None).unwrap();
+//! let page = doc.get_page(0).unwrap();
+//! let (num, generation) = page.reference().unwrap();
+//! let PDFObject::Dictionary(mut dict) = page.dict().clone() else {
+//!     unreachable!()
+//! };
+//!
+//! // Give the first page a user unit of 2/72 inch
+//! let unit = doc.add_object(PDFObject::Number(2.0)).unwrap();
+//! dict.insert("UserUnit".to_string(), PDFObject::Ref(unit));
+//! doc.replace_object(Ref::new(num, generation), PDFObject::Dictionary(dict))
+//!     .unwrap();
+//! let updated = doc.save_incremental().unwrap();
+//! ```
+
+use crate::core::cycle_guard::CycleGuard;
 use crate::core::error::{PDFError, PDFResult};
 use crate::core::parser::{PDFObject, Ref};
+use smallvec::SmallVec;
 use std::collections::{HashMap, HashSet};
 
 /// Object reference ID (object number and generation number).
@@ -43,6 +75,9 @@ pub struct DeltaLayer {
 
     /// Initial base PDF size (for clearing)
     base_pdf_size: u32,
+
+    /// State when the open transaction began, if one is open
+    transaction: Option<DeltaCheckpoint>,
 }
 
 impl std::fmt::Debug for DeltaLayer {
@@ -55,6 +90,7 @@ impl std::fmt::Debug for DeltaLayer {
             .field("undo_stack_count", &self.undo_stack.len())
             .field("next_obj_num", &self.next_obj_num)
             .field("base_pdf_size", &self.base_pdf_size)
+            .field("in_transaction", &self.transaction.is_some())
             .finish()
     }
 }
@@ -72,6 +108,58 @@ pub struct DeltaObject {
     pub generation: u32,
 }
 
+/// Saved state of a delta layer, used to roll back a batch of edits.
+///
+/// Created by [`DeltaLayer::checkpoint`]. The redo stack is set aside while
+/// the checkpoint is held and put back by [`DeltaLayer::rollback`].
+pub struct DeltaCheckpoint {
+    modified: HashMap<ObjectId, DeltaObject>,
+    new_objects: Vec<DeltaObject>,
+    deleted: HashSet<ObjectId>,
+    next_obj_num: u32,
+    history_len: usize,
+    undo_stack: Vec<Box<dyn Command>>,
+}
+
+/// What a batch of edits changed.
+///
+/// Produced by dry runs (see `PDFDocument::dry_run()`, `ops::merge_report()`
+/// and `ops::split_report()`) so callers can review a change before applying
+/// it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChangeReport {
+    /// Existing objects whose content changed
+    pub modified: Vec<Ref>,
+
+    /// Objects that were created
+    pub added: Vec<Ref>,
+
+    /// Objects that were deleted
+    pub deleted: Vec<Ref>,
+
+    /// Zero-based indices of pages whose dictionary, content streams or
+    /// annotations changed
+    pub pages_affected: Vec<usize>,
+
+    /// Size in bytes of the incremental update that saving would append with
+    /// the change applied, including edits that were already pending, or
+    /// `None` if the document can't be saved. For merge and split reports,
+    /// the size of the new file
+    pub bytes_written: Option<usize>,
+}
+
+impl ChangeReport {
+    /// Total number of objects touched.
+    pub fn object_count(&self) -> usize {
+        self.modified.len() + self.added.len() + self.deleted.len()
+    }
+
+    /// Returns true if nothing would change.
+    pub fn is_empty(&self) -> bool {
+        self.object_count() == 0
+    }
+}
+
 /// Callback type for fetching objects from the base PDF.
 ///
 /// This function takes an object reference and returns the object
@@ -125,6 +213,7 @@ impl DeltaLayer {
             undo_stack: Vec::new(),
             next_obj_num: base_pdf_size,
             base_pdf_size,
+            transaction: None,
         }
     }
 
@@ -220,6 +309,54 @@ impl DeltaLayer {
         self.modified.remove(&key);
     }
 
+    /// Discard the modification of an object, restoring the base version.
+    ///
+    /// # Arguments
+    /// * `obj_ref` - Reference to the modified object
+    pub fn revert_object(&mut self, obj_ref: Ref) {
+        self.modified.remove(&(obj_ref.num, obj_ref.generation));
+    }
+
+    /// Remove an object previously added with `add_object`.
+    ///
+    /// The object number is not reused.
+    ///
+    /// # Arguments
+    /// * `obj_ref` - Reference returned by `add_object`
+    pub fn remove_new_object(&mut self, obj_ref: Ref) {
+        self.new_objects
+            .retain(|obj| obj.obj_num != obj_ref.num || obj.generation != obj_ref.generation);
+    }
+
+    /// Put back an object removed with `remove_new_object`, under the
+    /// reference it had.
+    ///
+    /// # Arguments
+    /// * `obj_ref` - Reference the object was given by `add_object`
+    /// * `obj` - The object
+    pub fn restore_new_object(&mut self, obj_ref: Ref, obj: PDFObject) {
+        let index = self
+            .new_objects
+            .partition_point(|existing| existing.obj_num < obj_ref.num);
+        self.new_objects.insert(
+            index,
+            DeltaObject {
+                object: obj,
+                obj_num: obj_ref.num,
+                generation: obj_ref.generation,
+            },
+        );
+    }
+
+    /// Cancel the deletion of an object, making its previous version
+    /// visible again.
+    ///
+    /// # Arguments
+    /// * `obj_ref` - Reference to the deleted object
+    pub fn undelete_object(&mut self, obj_ref: Ref) {
+        self.deleted.remove(&(obj_ref.num, obj_ref.generation));
+    }
+
     /// Check if an object is deleted.
     ///
     /// # Arguments
@@ -271,8 +408,9 @@ impl DeltaLayer {
     /// Undo the last command.
     ///
     /// # Returns
-    /// Error if there's nothing to undo
+    /// Error if there's nothing to undo or a transaction is open
     pub fn undo(&mut self) -> PDFResult<()> {
+        self.check_no_transaction("undo")?;
         let mut cmd = self
             .history
             .pop()
@@ -286,8 +424,9 @@ impl DeltaLayer {
     /// Redo the last undone command.
     ///
     /// # Returns
-    /// Error if there's nothing to redo
+    /// Error if there's nothing to redo or a transaction is open
     pub fn redo(&mut self) -> PDFResult<()> {
+        self.check_no_transaction("redo")?;
         let mut cmd = self
             .undo_stack
             .pop()
@@ -298,10 +437,89 @@ impl DeltaLayer {
         Ok(())
     }
 
+    /// Start a transaction: the commands executed until
+    /// [`commit_transaction`](Self::commit_transaction) are undone and
+    /// redone as one.
+    ///
+    /// # Returns
+    /// Error if a transaction is already open
+    ///
+    /// # Example
+    /// ```
+    /// use pdf_x_core::core::{DeltaLayer, ReplaceObjectsCommand, parser::PDFObject, Ref};
+    ///
+    /// let mut delta = DeltaLayer::new(100);
+    /// delta.begin_transaction().unwrap();
+    /// for num in 1..=3 {
+    ///     let cmd = ReplaceObjectsCommand::new(vec![(Ref::new(num, 0), PDFObject::Null)]);
+    ///     delta.execute_command(Box::new(cmd), None).unwrap();
+    /// }
+    /// delta.commit_transaction().unwrap();
+    /// delta.undo().unwrap();
+    /// assert_eq!(delta.change_count(), 0);
+    /// ```
+    pub fn begin_transaction(&mut self) -> PDFResult<()> {
+        self.check_no_transaction("begin a transaction")?;
+        self.transaction = Some(self.checkpoint());
+        Ok(())
+    }
+
+    /// End the open transaction, keeping its commands as one undo step.
+    ///
+    /// A transaction that executed no commands leaves the history and the
+    /// redo stack as they were.
+    ///
+    /// # Returns
+    /// Error if no transaction is open
+    pub fn commit_transaction(&mut self) -> PDFResult<()> {
+        let checkpoint = self
+            .transaction
+            .take()
+            .ok_or_else(|| PDFError::Generic("No transaction to commit".into()))?;
+
+        let commands = self.history.split_off(checkpoint.history_len);
+        if commands.is_empty() {
+            self.undo_stack = checkpoint.undo_stack;
+        } else {
+            self.history.push(Box::new(CommandGroup { commands }));
+        }
+        Ok(())
+    }
+
+    /// End the open transaction, discarding the edits made in it.
+    ///
+    /// # Returns
+    /// Error if no transaction is open
+    pub fn cancel_transaction(&mut self) -> PDFResult<()> {
+        let checkpoint = self
+            .transaction
+            .take()
+            .ok_or_else(|| PDFError::Generic("No transaction to cancel".into()))?;
+        self.rollback(checkpoint);
+        Ok(())
+    }
+
+    /// Check if a transaction is open.
+    pub fn in_transaction(&self) -> bool {
+        self.transaction.is_some()
+    }
+
+    /// Fails if a transaction is open, naming the operation it prevents.
+    fn check_no_transaction(&self, operation: &str) -> PDFResult<()> {
+        if self.transaction.is_some() {
+            return Err(PDFError::Generic(format!(
+                "Cannot {} while a transaction is open",
+                operation
+            )));
+        }
+        Ok(())
+    }
+
     /// Clear all modifications (reset to clean state).
     ///
     /// This preserves the base PDF size but removes all tracked changes.
     pub fn clear(&mut self) {
+        self.transaction = None;
         self.modified.clear();
         self.new_objects.clear();
         self.deleted.clear();
@@ -330,6 +548,76 @@ impl DeltaLayer {
         self.next_obj_num
     }
 
+    /// Save the current state so later edits can be rolled back.
+    ///
+    /// The redo stack is set aside until [`rollback`](Self::rollback).
+    pub fn checkpoint(&mut self) -> DeltaCheckpoint {
+        DeltaCheckpoint {
+            modified: self.modified.clone(),
+            new_objects: self.new_objects.clone(),
+            deleted: self.deleted.clone(),
+            next_obj_num: self.next_obj_num,
+            history_len: self.history.len(),
+            undo_stack: std::mem::take(&mut self.undo_stack),
+        }
+    }
+
+    /// Discard all edits made since a checkpoint, including their history.
+    pub fn rollback(&mut self, checkpoint: DeltaCheckpoint) {
+        self.modified = checkpoint.modified;
+        self.new_objects = checkpoint.new_objects;
+        self.deleted = checkpoint.deleted;
+        self.next_obj_num = checkpoint.next_obj_num;
+        self.history.truncate(checkpoint.history_len);
+        self.undo_stack = checkpoint.undo_stack;
+    }
+
+    /// List the objects changed since a checkpoint.
+    ///
+    /// Page and size information is left empty; `PDFDocument::dry_run()`
+    /// fills it in.
+    pub fn changes_since(&self, checkpoint: &DeltaCheckpoint) -> ChangeReport {
+        let to_ref = |key: &ObjectId| Ref::new(key.0, key.1);
+
+        let mut added: Vec<Ref> = self
+            .new_objects
+            .iter()
+            .filter(|obj| obj.obj_num >= checkpoint.next_obj_num)
+            .map(|obj| Ref::new(obj.obj_num, obj.generation))
+            .collect();
+        added.sort_by_key(|r| (r.num, r.generation));
+
+        let mut modified: Vec<Ref> = self
+            .modified
+            .iter()
+            .filter(|(key, obj)| {
+                key.0 < checkpoint.next_obj_num
+                    && checkpoint
+                        .modified
+                        .get(key)
+                        .is_none_or(|old| old.object != obj.object)
+            })
+            .map(|(key, _)| to_ref(key))
+            .collect();
+        modified.sort_by_key(|r| (r.num, r.generation));
+
+        let mut deleted: Vec<Ref> = self
+            .deleted
+            .iter()
+            .filter(|key| !checkpoint.deleted.contains(key))
+            .map(to_ref)
+            .collect();
+        deleted.sort_by_key(|r| (r.num, r.generation));
+
+        ChangeReport {
+            modified,
+            added,
+            deleted,
+            pages_affected: Vec::new(),
+            bytes_written: None,
+        }
+    }
+
     /// Get iterator over modified objects.
     pub fn iter_modified(&self) -> impl Iterator<Item = (&ObjectId, &DeltaObject)> {
         self.modified.iter()
@@ -346,6 +634,39 @@ impl DeltaLayer {
     }
 }
 
+/// The commands of a committed transaction, undone and redone as one.
+struct CommandGroup {
+    /// The commands, in the order they were executed
+    commands: Vec<Box<dyn Command>>,
+}
+
+impl Command for CommandGroup {
+    fn execute<'a>(
+        &mut self,
+        delta: &mut DeltaLayer,
+        fetch_base: Option<&'a BaseObjectFetcher<'a>>,
+    ) -> PDFResult<()> {
+        for cmd in &mut self.commands {
+            cmd.execute(delta, fetch_base)?;
+        }
+        Ok(())
+    }
+
+    fn undo(&mut self, delta: &mut DeltaLayer) -> PDFResult<()> {
+        for cmd in self.commands.iter_mut().rev() {
+            cmd.undo(delta)?;
+        }
+        Ok(())
+    }
+
+    fn redo(&mut self, delta: &mut DeltaLayer) -> PDFResult<()> {
+        for cmd in &mut self.commands {
+            cmd.redo(delta)?;
+        }
+        Ok(())
+    }
+}
+
 // ========== Common Commands ==========
 
 /// Command to rotate a page.
@@ -506,6 +827,733 @@ impl Command for RotatePageCommand {
     }
 }
 
+/// Command to add Link annotations with URI actions to a page.
+///
+/// Each link becomes a new annotation object appended to the page's /Annots
+/// array. This is how links found by `Page::detect_links()` are turned into
+/// real annotations.
+///
+/// # Example
+/// ```
+/// # use pdf_x_core::core::delta::AddLinkAnnotationsCommand;
+/// # use pdf_x_core::core::parser::Ref;
+/// let cmd = AddLinkAnnotationsCommand::new(
+///     Ref::new(3, 0),
+///     vec![([72.0, 700.0, 200.0, 712.0], "https://example.com".to_string())],
+/// );
+/// ```
+#[derive(Debug)]
+pub struct AddLinkAnnotationsCommand {
+    /// The page object reference
+    page_ref: Ref,
+
+    /// The links to add: (rect, uri)
+    links: Vec<([f64; 4], String)>,
+
+    /// The page dictionary before the command ran (for undo/redo)
+    original_page: Option<PDFObject>,
+
+    /// Whether the page was already modified in the delta before the command ran
+    page_was_modified: bool,
+
+    /// The page's annotations before the command ran, resolved to an inline array
+    existing_annots: SmallVec<[Box<PDFObject>; 4]>,
+
+    /// The annotation objects that were added
+    added_refs: Vec<Ref>,
+}
+
+impl AddLinkAnnotationsCommand {
+    /// Create a new AddLinkAnnotationsCommand.
+    ///
+    /// # Arguments
+    /// * `page_ref` - The object reference of the page
+    /// * `links` - The links to add as (rect, uri) pairs
+    pub fn new(page_ref: Ref, links: Vec<([f64; 4], String)>) -> Self {
+        Self {
+            page_ref,
+            links,
+            original_page: None,
+            page_was_modified: false,
+            existing_annots: SmallVec::new(),
+            added_refs: Vec::new(),
+        }
+    }
+
+    /// Builds a Link annotation dictionary for one link.
+    fn link_annotation(&self, rect: &[f64; 4], uri: &str) -> PDFObject {
+        let mut action = HashMap::new();
+        action.insert("S".to_string(), PDFObject::Name("URI".to_string()));
+        action.insert(
+            "URI".to_string(),
+            PDFObject::String(uri.as_bytes().to_vec()),
+        );
+
+        let mut annot = HashMap::new();
+        annot.insert("Type".to_string(), PDFObject::Name("Annot".to_string()));
+        annot.insert("Subtype".to_string(), PDFObject::Name("Link".to_string()));
+        annot.insert(
+            "Rect".to_string(),
+            PDFObject::Array(
+                rect.iter()
+                    .map(|v| Box::new(PDFObject::Number(*v)))
+                    .collect(),
+            ),
+        );
+        // No visible border, as for links created by most authoring tools
+        annot.insert(
+            "Border".to_string(),
+            PDFObject::Array(
+                [0.0, 0.0, 0.0]
+                    .iter()
+                    .map(|v| Box::new(PDFObject::Number(*v)))
+                    .collect(),
+            ),
+        );
+        annot.insert("P".to_string(), PDFObject::Ref(self.page_ref));
+        annot.insert("A".to_string(), PDFObject::Dictionary(action));
+        PDFObject::Dictionary(annot)
+    }
+
+    /// Adds the annotation objects and the updated page to the delta.
+    fn apply(&mut self, delta: &mut DeltaLayer) -> PDFResult<()> {
+        let mut page_dict = match &self.original_page {
+            Some(PDFObject::Dictionary(d)) => d.clone(),
+            _ => {
+                return Err(PDFError::Generic(format!(
+                    "Page object {} {} is not a dictionary",
+                    self.page_ref.num, self.page_ref.generation
+                )));
+            }
+        };
+
+        self.added_refs.clear();
+        let mut annots = self.existing_annots.clone();
+        for (rect, uri) in self.links.clone() {
+            let annot_ref = delta.add_object(self.link_annotation(&rect, &uri));
+            self.added_refs.push(annot_ref);
+            annots.push(Box::new(PDFObject::Ref(annot_ref)));
+        }
+
+        page_dict.insert("Annots".to_string(), PDFObject::Array(annots));
+        delta.modify_object(self.page_ref, PDFObject::Dictionary(page_dict));
+        Ok(())
+    }
+
+    /// Resolves the page's current annotations to an inline array.
+    fn resolve_annots<'a>(
+        &self,
+        fetch_base: Option<&'a BaseObjectFetcher<'a>>,
+        delta: &DeltaLayer,
+    ) -> PDFResult<SmallVec<[Box<PDFObject>; 4]>> {
+        let annots = match &self.original_page {
+            Some(PDFObject::Dictionary(d)) => d.get("Annots").cloned(),
+            _ => None,
+        };
+
+        let annots = match annots {
+            Some(PDFObject::Ref(annots_ref)) => match delta.get(&annots_ref) {
+                Some(delta_obj) => delta_obj.object.clone(),
+                None => match fetch_base {
+                    Some(fetcher) => fetcher(annots_ref)?,
+                    None => {
+                        return Err(PDFError::Generic(
+                            "Cannot fetch /Annots array - no fetch callback provided".into(),
+                        ));
+                    }
+                },
+            },
+            Some(other) => other,
+            None => return Ok(SmallVec::new()),
+        };
+
+        match annots {
+            PDFObject::Array(arr) => Ok(arr),
+            _ => Ok(SmallVec::new()),
+        }
+    }
+}
+
+impl Command for AddLinkAnnotationsCommand {
+    fn execute<'a>(
+        &mut self,
+        delta: &mut DeltaLayer,
+        fetch_base: Option<&'a BaseObjectFetcher<'a>>,
+    ) -> PDFResult<()> {
+        // Get the current page object from delta or base PDF
+        self.page_was_modified = delta.get(&self.page_ref).is_some();
+        let page = match delta.get(&self.page_ref) {
+            Some(delta_obj) => delta_obj.object.clone(),
+            None => {
+                let fetcher = fetch_base.ok_or_else(|| {
+                    PDFError::Generic(
+                        "Cannot fetch base page object - no fetch callback provided. \
+                        Execute commands through PDFDocument::execute_command() instead."
+                            .into(),
+                    )
+                })?;
+                fetcher(self.page_ref)?
+            }
+        };
+        self.original_page = Some(page);
+
+        self.existing_annots = self.resolve_annots(fetch_base, delta)?;
+        self.apply(delta)
+    }
+
+    fn undo(&mut self, delta: &mut DeltaLayer) -> PDFResult<()> {
+        for annot_ref in &self.added_refs {
+            delta.remove_new_object(*annot_ref);
+        }
+
+        if self.page_was_modified {
+            let original = self.original_page.clone().ok_or_else(|| {
+                PDFError::Generic("Original page not recorded during undo".into())
+            })?;
+            delta.modify_object(self.page_ref, original);
+        } else {
+            delta.revert_object(self.page_ref);
+        }
+        Ok(())
+    }
+
+    fn redo(&mut self, delta: &mut DeltaLayer) -> PDFResult<()> {
+        self.apply(delta)
+    }
+}
+
+/// Command that replaces whole objects.
+///
+/// Used by edits that compute the new objects up front, such as content
+/// stream rewriting. Undo restores each object to its state before the
+/// command ran.
+///
+/// # Example
+/// ```
+/// # use pdf_x_core::core::delta::ReplaceObjectsCommand;
+/// # use pdf_x_core::core::parser::{PDFObject, Ref};
+/// let cmd = ReplaceObjectsCommand::new(vec![(Ref::new(7, 0), PDFObject::Number(1.0))]);
+/// ```
+#[derive(Debug)]
+pub struct ReplaceObjectsCommand {
+    /// The new objects
+    objects: Vec<(Ref, PDFObject)>,
+
+    /// Each object's delta entry before the command ran, if any (for undo)
+    originals: Vec<Option<PDFObject>>,
+}
+
+impl ReplaceObjectsCommand {
+    /// Create a new ReplaceObjectsCommand.
+    ///
+    /// # Arguments
+    /// * `objects` - The objects to store, with the references they replace
+    pub fn new(objects: Vec<(Ref, PDFObject)>) -> Self {
+        Self {
+            objects,
+            originals: Vec::new(),
+        }
+    }
+}
+
+impl Command for ReplaceObjectsCommand {
+    fn execute<'a>(
+        &mut self,
+        delta: &mut DeltaLayer,
+        _fetch_base: Option<&'a BaseObjectFetcher<'a>>,
+    ) -> PDFResult<()> {
+        self.originals = self
+            .objects
+            .iter()
+            .map(|(obj_ref, _)| delta.get(obj_ref).map(|obj| obj.object.clone()))
+            .collect();
+        self.redo(delta)
+    }
+
+    fn undo(&mut self, delta: &mut DeltaLayer) -> PDFResult<()> {
+        for ((obj_ref, _), original) in self.objects.iter().zip(&self.originals) {
+            match original {
+                Some(obj) => delta.modify_object(*obj_ref, obj.clone()),
+                None => delta.revert_object(*obj_ref),
+            }
+        }
+        Ok(())
+    }
+
+    fn redo(&mut self, delta: &mut DeltaLayer) -> PDFResult<()> {
+        for (obj_ref, obj) in &self.objects {
+            delta.modify_object(*obj_ref, obj.clone());
+        }
+        Ok(())
+    }
+}
+
+/// Command that adds a new object.
+///
+/// The object keeps its reference when the command is undone and redone, so
+/// references to it stored by later edits stay valid.
+///
+/// # Example
+/// ```
+/// # use pdf_x_core::core::delta::{AddObjectCommand, DeltaLayer};
+/// # use pdf_x_core::core::parser::PDFObject;
+/// let mut delta = DeltaLayer::new(10);
+/// delta
+///     .execute_command(Box::new(AddObjectCommand::new(PDFObject::Number(1.0))), None)
+///     .unwrap();
+/// assert!(delta.get(&pdf_x_core::core::Ref::new(10, 0)).is_some());
+/// ```
+#[derive(Debug)]
+pub struct AddObjectCommand {
+    /// The object to add
+    object: PDFObject,
+
+    /// The reference the object was given (set on execute)
+    added_ref: Option<Ref>,
+}
+
+impl AddObjectCommand {
+    /// Create a new AddObjectCommand.
+    ///
+    /// # Arguments
+    /// * `object` - The object to add
+    pub fn new(object: PDFObject) -> Self {
+        Self {
+            object,
+            added_ref: None,
+        }
+    }
+}
+
+impl Command for AddObjectCommand {
+    fn execute<'a>(
+        &mut self,
+        delta: &mut DeltaLayer,
+        _fetch_base: Option<&'a BaseObjectFetcher<'a>>,
+    ) -> PDFResult<()> {
+        self.added_ref = Some(delta.add_object(self.object.clone()));
+        Ok(())
+    }
+
+    fn undo(&mut self, delta: &mut DeltaLayer) -> PDFResult<()> {
+        if let Some(added_ref) = self.added_ref {
+            delta.remove_new_object(added_ref);
+        }
+        Ok(())
+    }
+
+    fn redo(&mut self, delta: &mut DeltaLayer) -> PDFResult<()> {
+        match self.added_ref {
+            Some(added_ref) => delta.restore_new_object(added_ref, self.object.clone()),
+            None => self.added_ref = Some(delta.add_object(self.object.clone())),
+        }
+        Ok(())
+    }
+}
+
+/// Command that deletes an object.
+///
+/// Undo restores the object as it was before the command ran, including
+/// any earlier modification. An object added in the same session is
+/// dropped from the new objects instead of being marked deleted, so it is
+/// never written out.
+#[derive(Debug)]
+pub struct DeleteObjectCommand {
+    /// The object to delete
+    obj_ref: Ref,
+
+    /// The object's modification before the command ran, if any (for undo)
+    original: Option<PDFObject>,
+
+    /// The object, if it was added by an earlier edit (for undo)
+    added: Option<PDFObject>,
+
+    /// Whether the object was already deleted (for undo)
+    was_deleted: bool,
+}
+
+impl DeleteObjectCommand {
+    /// Create a new DeleteObjectCommand.
+    ///
+    /// # Arguments
+    /// * `obj_ref` - Reference to the object to delete
+    pub fn new(obj_ref: Ref) -> Self {
+        Self {
+            obj_ref,
+            original: None,
+            added: None,
+            was_deleted: false,
+        }
+    }
+}
+
+impl Command for DeleteObjectCommand {
+    fn execute<'a>(
+        &mut self,
+        delta: &mut DeltaLayer,
+        _fetch_base: Option<&'a BaseObjectFetcher<'a>>,
+    ) -> PDFResult<()> {
+        let key = (self.obj_ref.num, self.obj_ref.generation);
+        self.original = delta.modified.get(&key).map(|obj| obj.object.clone());
+        self.added = delta
+            .new_objects
+            .iter()
+            .find(|obj| (obj.obj_num, obj.generation) == key)
+            .map(|obj| obj.object.clone());
+        self.was_deleted = delta.is_deleted(&self.obj_ref);
+        self.redo(delta)
+    }
+
+    fn undo(&mut self, delta: &mut DeltaLayer) -> PDFResult<()> {
+        if let Some(added) = &self.added {
+            delta.restore_new_object(self.obj_ref, added.clone());
+            return Ok(());
+        }
+        if !self.was_deleted {
+            delta.undelete_object(self.obj_ref);
+        }
+        if let Some(original) = &self.original {
+            delta.modify_object(self.obj_ref, original.clone());
+        }
+        Ok(())
+    }
+
+    fn redo(&mut self, delta: &mut DeltaLayer) -> PDFResult<()> {
+        if self.added.is_some() {
+            delta.remove_new_object(self.obj_ref);
+        } else {
+            delta.delete_object(self.obj_ref);
+        }
+        Ok(())
+    }
+}
+
+/// A change to the structure of the page tree.
+///
+/// Pages are placed relative to the page they should precede; `before: None`
+/// appends to the end of `parent`'s /Kids.
+#[derive(Debug, Clone)]
+pub enum PageTreeEdit {
+    /// Remove a page from its parent node
+    Delete { page: Ref },
+
+    /// Add a new page object under `parent`
+    Insert {
+        parent: Ref,
+        before: Option<Ref>,
+        page: PDFObject,
+    },
+
+    /// Move an existing page under `parent`, replacing its dictionary with
+    /// `page`
+    Move {
+        page_ref: Ref,
+        parent: Ref,
+        before: Option<Ref>,
+        page: PDFObject,
+    },
+}
+
+/// Command that inserts, deletes or moves a page.
+///
+/// Rewrites the /Kids of the affected nodes and the /Count of every node on
+/// the way up to the root. A deleted page object is left in the file, since
+/// outlines or links may still refer to it.
+///
+/// # Example
+/// ```
+/// # use pdf_x_core::core::delta::{EditPageTreeCommand, PageTreeEdit};
+/// # use pdf_x_core::core::parser::Ref;
+/// let cmd = EditPageTreeCommand::new(PageTreeEdit::Delete { page: Ref::new(4, 0) });
+/// ```
+#[derive(Debug)]
+pub struct EditPageTreeCommand {
+    /// The edit to make
+    edit: PageTreeEdit,
+
+    /// Parent of the page being deleted or moved
+    old_parent: Option<Ref>,
+
+    /// Page tree nodes the edit touches, as they were before it ran
+    nodes: HashMap<Ref, HashMap<String, PDFObject>>,
+
+    /// Delta entries of the touched objects before the command ran (for undo)
+    originals: Vec<(Ref, Option<PDFObject>)>,
+
+    /// The page object that was added
+    added_ref: Option<Ref>,
+}
+
+impl EditPageTreeCommand {
+    /// Create a new EditPageTreeCommand.
+    ///
+    /// # Arguments
+    /// * `edit` - The change to make to the page tree
+    pub fn new(edit: PageTreeEdit) -> Self {
+        Self {
+            edit,
+            old_parent: None,
+            nodes: HashMap::new(),
+            originals: Vec::new(),
+            added_ref: None,
+        }
+    }
+
+    /// Loads an object from the delta layer, falling back to the base PDF.
+    fn load_dict<'a>(
+        delta: &DeltaLayer,
+        fetch_base: Option<&'a BaseObjectFetcher<'a>>,
+        obj_ref: Ref,
+    ) -> PDFResult<HashMap<String, PDFObject>> {
+        let obj = match delta.get(&obj_ref) {
+            Some(delta_obj) => delta_obj.object.clone(),
+            None => {
+                let fetcher = fetch_base.ok_or_else(|| {
+                    PDFError::Generic(
+                        "Cannot fetch base object - no fetch callback provided. \
+                        Execute commands through PDFDocument::execute_command() instead."
+                            .into(),
+                    )
+                })?;
+                fetcher(obj_ref)?
+            }
+        };
+        match obj {
+            PDFObject::Dictionary(dict) => Ok(dict),
+            _ => Err(PDFError::Generic(format!(
+                "Object {} {} is not a dictionary",
+                obj_ref.num, obj_ref.generation
+            ))),
+        }
+    }
+
+    /// Records a node and all of its ancestors.
+    fn record_chain<'a>(
+        &mut self,
+        delta: &DeltaLayer,
+        fetch_base: Option<&'a BaseObjectFetcher<'a>>,
+        start: Ref,
+    ) -> PDFResult<()> {
+        let mut current = Some(start);
+        while let Some(node_ref) = current {
+            if self.nodes.contains_key(&node_ref) {
+                break;
+            }
+            let dict = Self::load_dict(delta, fetch_base, node_ref)?;
+            current = match dict.get("Parent") {
+                Some(PDFObject::Ref(parent)) => Some(*parent),
+                _ => None,
+            };
+            self.nodes.insert(node_ref, dict);
+        }
+        Ok(())
+    }
+
+    /// Gets the /Kids array of a recorded node.
+    fn kids_mut(
+        nodes: &mut HashMap<Ref, HashMap<String, PDFObject>>,
+        parent: Ref,
+    ) -> PDFResult<&mut SmallVec<[Box<PDFObject>; 4]>> {
+        match nodes.get_mut(&parent).and_then(|node| node.get_mut("Kids")) {
+            Some(PDFObject::Array(kids)) => Ok(kids),
+            _ => Err(PDFError::Generic(format!(
+                "Page tree node {} {} has no /Kids array",
+                parent.num, parent.generation
+            ))),
+        }
+    }
+
+    /// Adds `amount` to the /Count of a node and its ancestors.
+    fn adjust_counts(
+        nodes: &mut HashMap<Ref, HashMap<String, PDFObject>>,
+        start: Ref,
+        amount: f64,
+    ) {
+        let mut visited = CycleGuard::new();
+        let mut current = Some(start);
+        while let Some(node_ref) = current {
+            if !visited.visit(node_ref) {
+                break;
+            }
+            let Some(node) = nodes.get_mut(&node_ref) else {
+                break;
+            };
+            let count = match node.get("Count") {
+                Some(PDFObject::Number(n)) => *n,
+                _ => 0.0,
+            };
+            node.insert("Count".to_string(), PDFObject::Number(count + amount));
+            current = match node.get("Parent") {
+                Some(PDFObject::Ref(parent)) => Some(*parent),
+                _ => None,
+            };
+        }
+    }
+
+    /// Removes a page from its parent's /Kids.
+    fn detach(
+        nodes: &mut HashMap<Ref, HashMap<String, PDFObject>>,
+        parent: Ref,
+        page: Ref,
+    ) -> PDFResult<()> {
+        let kids = Self::kids_mut(nodes, parent)?;
+        let position = kids
+            .iter()
+            .position(|kid| **kid == PDFObject::Ref(page))
+            .ok_or_else(|| {
+                PDFError::Generic(format!(
+                    "Page {} {} is not a kid of its /Parent",
+                    page.num, page.generation
+                ))
+            })?;
+        kids.remove(position);
+        Self::adjust_counts(nodes, parent, -1.0);
+        Ok(())
+    }
+
+    /// Adds a page to `parent`'s /Kids, before `before` if given.
+    fn attach(
+        nodes: &mut HashMap<Ref, HashMap<String, PDFObject>>,
+        parent: Ref,
+        before: Option<Ref>,
+        page: Ref,
+    ) -> PDFResult<()> {
+        let kids = Self::kids_mut(nodes, parent)?;
+        let position = match before {
+            Some(anchor) => kids
+                .iter()
+                .position(|kid| **kid == PDFObject::Ref(anchor))
+                .ok_or_else(|| {
+                    PDFError::Generic(format!(
+                        "Page {} {} is not a kid of the target node",
+                        anchor.num, anchor.generation
+                    ))
+                })?,
+            None => kids.len(),
+        };
+        kids.insert(position, Box::new(PDFObject::Ref(page)));
+        Self::adjust_counts(nodes, parent, 1.0);
+        Ok(())
+    }
+
+    /// Returns a page dictionary with its /Parent pointing at `parent`.
+    fn with_parent(page: &PDFObject, parent: Ref) -> PDFResult<PDFObject> {
+        match page {
+            PDFObject::Dictionary(dict) => {
+                let mut dict = dict.clone();
+                dict.insert("Parent".to_string(), PDFObject::Ref(parent));
+                Ok(PDFObject::Dictionary(dict))
+            }
+            _ => Err(PDFError::Generic("Page is not a dictionary".into())),
+        }
+    }
+
+    /// Applies the edit to the recorded nodes and writes them to the delta.
+    fn apply(&mut self, delta: &mut DeltaLayer) -> PDFResult<()> {
+        let mut nodes = self.nodes.clone();
+        match &self.edit {
+            PageTreeEdit::Delete { page } => {
+                let old_parent = self.old_parent.ok_or_else(|| {
+                    PDFError::Generic("Page parent not recorded before apply".into())
+                })?;
+                Self::detach(&mut nodes, old_parent, *page)?;
+            }
+            PageTreeEdit::Insert {
+                parent,
+                before,
+                page,
+            } => {
+                let page = Self::with_parent(page, *parent)?;
+                let page_ref = delta.add_object(page);
+                self.added_ref = Some(page_ref);
+                Self::attach(&mut nodes, *parent, *before, page_ref)?;
+            }
+            PageTreeEdit::Move {
+                page_ref,
+                parent,
+                before,
+                page,
+            } => {
+                let old_parent = self.old_parent.ok_or_else(|| {
+                    PDFError::Generic("Page parent not recorded before apply".into())
+                })?;
+                Self::detach(&mut nodes, old_parent, *page_ref)?;
+                Self::attach(&mut nodes, *parent, *before, *page_ref)?;
+                delta.modify_object(*page_ref, Self::with_parent(page, *parent)?);
+            }
+        }
+
+        for (node_ref, node) in nodes {
+            delta.modify_object(node_ref, PDFObject::Dictionary(node));
+        }
+        Ok(())
+    }
+}
+
+impl Command for EditPageTreeCommand {
+    fn execute<'a>(
+        &mut self,
+        delta: &mut DeltaLayer,
+        fetch_base: Option<&'a BaseObjectFetcher<'a>>,
+    ) -> PDFResult<()> {
+        self.nodes.clear();
+        let mut touched = Vec::new();
+        let (moved, target) = match &self.edit {
+            PageTreeEdit::Delete { page } => (Some(*page), None),
+            PageTreeEdit::Insert { parent, .. } => (None, Some(*parent)),
+            PageTreeEdit::Move {
+                page_ref, parent, ..
+            } => {
+                touched.push(*page_ref);
+                (Some(*page_ref), Some(*parent))
+            }
+        };
+
+        if let Some(page) = moved {
+            let dict = Self::load_dict(delta, fetch_base, page)?;
+            let old_parent = match dict.get("Parent") {
+                Some(PDFObject::Ref(parent)) => *parent,
+                _ => {
+                    return Err(PDFError::Generic(format!(
+                        "Page {} {} has no /Parent",
+                        page.num, page.generation
+                    )));
+                }
+            };
+            self.old_parent = Some(old_parent);
+            self.record_chain(delta, fetch_base, old_parent)?;
+        }
+        if let Some(parent) = target {
+            self.record_chain(delta, fetch_base, parent)?;
+        }
+
+        touched.extend(self.nodes.keys().copied());
+        self.originals = touched
+            .into_iter()
+            .map(|obj_ref| (obj_ref, delta.get(&obj_ref).map(|obj| obj.object.clone())))
+            .collect();
+
+        self.apply(delta)
+    }
+
+    fn undo(&mut self, delta: &mut DeltaLayer) -> PDFResult<()> {
+        if let Some(page_ref) = self.added_ref.take() {
+            delta.remove_new_object(page_ref);
+        }
+        for (obj_ref, original) in &self.originals {
+            match original {
+                Some(obj) => delta.modify_object(*obj_ref, obj.clone()),
+                None => delta.revert_object(*obj_ref),
+            }
+        }
+        Ok(())
+    }
+
+    fn redo(&mut self, delta: &mut DeltaLayer) -> PDFResult<()> {
+        self.apply(delta)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -577,6 +1625,43 @@ mod tests {
         assert!(retrieved.is_some());
     }
 
+    #[test]
+    fn test_add_and_delete_commands_undo() {
+        let mut delta = DeltaLayer::new(10);
+        delta.modify_object(Ref::new(5, 0), PDFObject::Number(1.0));
+
+        delta
+            .execute_command(Box::new(DeleteObjectCommand::new(Ref::new(5, 0))), None)
+            .unwrap();
+        delta
+            .execute_command(
+                Box::new(AddObjectCommand::new(PDFObject::Number(2.0))),
+                None,
+            )
+            .unwrap();
+        assert!(delta.is_deleted(&Ref::new(5, 0)));
+        assert!(delta.get(&Ref::new(10, 0)).is_some());
+
+        // The added object keeps its number through undo and redo
+        delta.undo().unwrap();
+        assert!(delta.get(&Ref::new(10, 0)).is_none());
+        delta.redo().unwrap();
+        assert_eq!(
+            delta.get(&Ref::new(10, 0)).unwrap().object,
+            PDFObject::Number(2.0)
+        );
+        assert_eq!(delta.next_obj_num(), 11);
+
+        // The deleted object comes back with its modification
+        delta.undo().unwrap();
+        delta.undo().unwrap();
+        assert!(!delta.is_deleted(&Ref::new(5, 0)));
+        assert_eq!(
+            delta.get(&Ref::new(5, 0)).unwrap().object,
+            PDFObject::Number(1.0)
+        );
+    }
+
     #[test]
     fn test_clear() {
         let mut delta = DeltaLayer::new(100);
@@ -613,6 +1698,40 @@ mod tests {
         assert!(retrieved.is_none());
     }
 
+    #[test]
+    fn test_checkpoint_rollback() {
+        let mut delta = DeltaLayer::new(10);
+        delta.modify_object(Ref::new(1, 0), PDFObject::Number(1.0));
+        delta
+            .execute_command(
+                Box::new(ReplaceObjectsCommand::new(vec![(
+                    Ref::new(2, 0),
+                    PDFObject::Null,
+                )])),
+                None,
+            )
+            .unwrap();
+        delta.undo().unwrap();
+        assert!(delta.can_redo());
+
+        let checkpoint = delta.checkpoint();
+        delta.modify_object(Ref::new(1, 0), PDFObject::Number(2.0));
+        delta.modify_object(Ref::new(3, 0), PDFObject::Number(3.0));
+        let added = delta.add_object(PDFObject::Null);
+        delta.delete_object(Ref::new(4, 0));
+
+        let report = delta.changes_since(&checkpoint);
+        assert_eq!(report.modified, vec![Ref::new(1, 0), Ref::new(3, 0)]);
+        assert_eq!(report.added, vec![added]);
+        assert_eq!(report.deleted, vec![Ref::new(4, 0)]);
+        assert_eq!(report.object_count(), 4);
+
+        delta.rollback(checkpoint);
+        assert_eq!(delta.change_count(), 1);
+        assert_eq!(delta.next_obj_num(), 10);
+        assert!(delta.can_redo());
+    }
+
     #[test]
     fn test_change_count() {
         let mut delta = DeltaLayer::new(100);
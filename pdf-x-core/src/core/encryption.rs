@@ -5,11 +5,13 @@
 //! - Parsing /Encrypt dictionaries
 //! - Password verification (user and owner passwords)
 //! - File encryption key derivation
-//! - PDF object decryption (strings and streams)
+//! - PDF object decryption (strings and streams), including crypt filters
 
 use crate::core::crypto::{AES128Cipher, AES256Cipher, ARC4Cipher, PDF20, PDFPasswordAlgorithm};
 use crate::core::error::{PDFError, PDFResult};
 use crate::core::parser::PDFObject;
+use crate::log_target;
+use std::collections::HashMap;
 
 /// PDF encryption version
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -60,7 +62,63 @@ pub struct PDFPermissions {
     pub raw_value: u32,
 }
 
+/// An operation a document's permissions can restrict.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    /// Print the document
+    Print,
+    /// Modify the document contents
+    Modify,
+    /// Copy or extract text and graphics
+    Copy,
+    /// Add or modify annotations
+    Annotate,
+    /// Fill in form fields
+    FillForm,
+    /// Extract text and graphics for accessibility
+    Extract,
+    /// Insert, rotate or delete pages
+    Assemble,
+    /// Print at high quality
+    PrintHighQuality,
+}
+
+impl Permission {
+    /// Describes the operation, for error messages.
+    pub fn description(self) -> &'static str {
+        match self {
+            Permission::Print => "printing",
+            Permission::Modify => "modifying the document",
+            Permission::Copy => "copying text and graphics",
+            Permission::Annotate => "adding or changing annotations",
+            Permission::FillForm => "filling in form fields",
+            Permission::Extract => "extracting content for accessibility",
+            Permission::Assemble => "assembling the document",
+            Permission::PrintHighQuality => "printing at high quality",
+        }
+    }
+}
+
 impl PDFPermissions {
+    /// Permissions that allow everything, as for unencrypted documents
+    pub fn all() -> Self {
+        Self::from_p_value(0xFFFFFFFF)
+    }
+
+    /// Returns true if `permission` is granted.
+    pub fn allows(&self, permission: Permission) -> bool {
+        match permission {
+            Permission::Print => self.print,
+            Permission::Modify => self.modify,
+            Permission::Copy => self.copy,
+            Permission::Annotate => self.annotate,
+            Permission::FillForm => self.fill_form,
+            Permission::Extract => self.extract,
+            Permission::Assemble => self.assemble,
+            Permission::PrintHighQuality => self.print_high_quality,
+        }
+    }
+
     /// Parse permissions from the P value in the /Encrypt dictionary
     pub fn from_p_value(p: u32) -> Self {
         PDFPermissions {
@@ -117,49 +175,45 @@ impl EncryptDict {
         if let PDFObject::Dictionary(dict) = encrypt_obj {
             let filter = dict
                 .get("Filter")
-                .ok_or_else(|| PDFError::parse_error("Missing Filter in Encrypt dict", None))?
+                .ok_or_else(|| PDFError::encryption_error("Missing Filter in Encrypt dict"))?
                 .as_name()
-                .ok_or_else(|| PDFError::parse_error("Filter must be a name", None))?;
+                .ok_or_else(|| PDFError::encryption_error("Filter must be a name"))?;
 
             let version = dict
                 .get("V")
-                .ok_or_else(|| PDFError::parse_error("Missing V in Encrypt dict", None))?
+                .ok_or_else(|| PDFError::encryption_error("Missing V in Encrypt dict"))?
                 .as_number()
-                .ok_or_else(|| PDFError::parse_error("V must be a number", None))?
+                .ok_or_else(|| PDFError::encryption_error("V must be a number"))?
                 as i32;
 
             let revision = dict
                 .get("R")
-                .ok_or_else(|| PDFError::parse_error("Missing R in Encrypt dict", None))?
+                .ok_or_else(|| PDFError::encryption_error("Missing R in Encrypt dict"))?
                 .as_number()
-                .ok_or_else(|| PDFError::parse_error("R must be a number", None))?
+                .ok_or_else(|| PDFError::encryption_error("R must be a number"))?
                 as i32;
 
             let o = dict
                 .get("O")
-                .ok_or_else(|| PDFError::parse_error("Missing O in Encrypt dict", None))?
-                .as_string()
-                .ok_or_else(|| PDFError::parse_error("O must be a string", None))?;
+                .ok_or_else(|| PDFError::encryption_error("Missing O in Encrypt dict"))
+                .map(string_bytes)?
+                .ok_or_else(|| PDFError::encryption_error("O must be a string"))?;
 
             let u = dict
                 .get("U")
-                .ok_or_else(|| PDFError::parse_error("Missing U in Encrypt dict", None))?
-                .as_string()
-                .ok_or_else(|| PDFError::parse_error("U must be a string", None))?;
+                .ok_or_else(|| PDFError::encryption_error("Missing U in Encrypt dict"))
+                .map(string_bytes)?
+                .ok_or_else(|| PDFError::encryption_error("U must be a string"))?;
 
             let p = dict
                 .get("P")
-                .ok_or_else(|| PDFError::parse_error("Missing P in Encrypt dict", None))?
+                .ok_or_else(|| PDFError::encryption_error("Missing P in Encrypt dict"))?
                 .as_number()
-                .ok_or_else(|| PDFError::parse_error("P must be a number", None))?
-                as u32;
+                .ok_or_else(|| PDFError::encryption_error("P must be a number"))?
+                as i64 as u32; // Usually written as a negative number
 
-            let oe = dict
-                .get("OE")
-                .and_then(|obj| obj.as_string().map(|v| v.to_vec()));
-            let ue = dict
-                .get("UE")
-                .and_then(|obj| obj.as_string().map(|v| v.to_vec()));
+            let oe = dict.get("OE").and_then(string_bytes).map(|v| v.to_vec());
+            let ue = dict.get("UE").and_then(string_bytes).map(|v| v.to_vec());
 
             let encrypt_metadata = dict
                 .get("EncryptMetadata")
@@ -179,9 +233,8 @@ impl EncryptDict {
                 encryption_key: None,
             })
         } else {
-            Err(PDFError::parse_error(
+            Err(PDFError::encryption_error(
                 "Encrypt dict must be a dictionary",
-                None,
             ))
         }
     }
@@ -338,6 +391,33 @@ impl EncryptDict {
         }
     }
 
+    /// Returns true if `password` is the owner password, which lifts the
+    /// restrictions of the permissions flags.
+    ///
+    /// Unlike the password checks, this leaves the encryption key alone.
+    pub fn is_owner_password(&self, password: &[u8], file_id: &[u8]) -> bool {
+        match self.encryption_version() {
+            EncryptionVersion::V1 | EncryptionVersion::V2 | EncryptionVersion::V4 => {
+                let decoded_user_pwd =
+                    decode_user_password(password, &self.o, self.revision, self.key_length());
+                check_user_password_legacy(
+                    &decoded_user_pwd,
+                    &self.o,
+                    self.permissions.raw_value,
+                    file_id,
+                    self.revision,
+                    self.key_length(),
+                    self.encrypt_metadata,
+                    &self.u,
+                )
+                .is_some()
+            }
+            EncryptionVersion::V5R5 | EncryptionVersion::V5R6 => {
+                self.clone().check_owner_password(password)
+            }
+        }
+    }
+
     /// Get the file encryption key length in bytes
     pub fn key_length(&self) -> usize {
         match self.encryption_version() {
@@ -352,39 +432,17 @@ impl EncryptDict {
     pub fn get_encryption_key(&self) -> PDFResult<&[u8]> {
         self.encryption_key
             .as_deref()
-            .ok_or_else(|| PDFError::parse_error("Encryption key not derived", None))
+            .ok_or_else(|| PDFError::encryption_error("Encryption key not derived"))
     }
 
-    /// Build an object-specific encryption key.
-    ///
-    /// This implements the object key derivation from the PDF specification.
-    /// The key is computed as: MD5(encryption_key + obj_num(3) + gen_num(2) + optional_suffix)
-    fn build_object_key(&self, obj_num: u32, gen_num: u32) -> Vec<u8> {
-        use crate::core::crypto::calculate_md5;
-
-        let encryption_key = self.get_encryption_key().unwrap(); // Safe: caller should ensure key is derived
-
-        let is_aes = self.algorithm() == EncryptionAlgorithm::AES128
-            || self.algorithm() == EncryptionAlgorithm::AES256;
-
-        // Build the key data
-        let mut key_data =
-            Vec::with_capacity(encryption_key.len() + 5 + (if is_aes { 4 } else { 0 }));
-        key_data.extend_from_slice(encryption_key);
-        key_data.extend_from_slice(&obj_num.to_le_bytes()[0..3]); // 3 bytes, little-endian
-        key_data.extend_from_slice(&gen_num.to_le_bytes()[0..2]); // 2 bytes, little-endian
-
-        // For AES, add the "sAlt" suffix
-        if is_aes && (self.version == 4 || self.version == 5) {
-            key_data.extend_from_slice(&[0x73, 0x41, 0x6c, 0x54]); // "sAlt"
+    /// The crypt method used when the /Encrypt dictionary has no crypt
+    /// filters of its own.
+    fn default_crypt_method(&self) -> CryptMethod {
+        match self.algorithm() {
+            EncryptionAlgorithm::RC4 => CryptMethod::RC4,
+            EncryptionAlgorithm::AES128 => CryptMethod::AESV2,
+            EncryptionAlgorithm::AES256 => CryptMethod::AESV3,
         }
-
-        // Compute MD5 hash
-        let hash = calculate_md5(&key_data);
-
-        // Return the appropriate length (key length + 5, up to 16 bytes)
-        let key_len = (encryption_key.len() + 5).min(16);
-        hash[..key_len].to_vec()
     }
 
     /// Decrypt a string object.
@@ -392,123 +450,319 @@ impl EncryptDict {
     /// Strings in encrypted PDFs are stored as hexadecimal or literal strings
     /// that need to be decrypted using the object-specific key.
     pub fn decrypt_string(&self, data: &[u8], obj_num: u32, gen_num: u32) -> PDFResult<Vec<u8>> {
-        let _ = self.get_encryption_key()?; // Ensure key is derived
+        let key = self.get_encryption_key()?; // Ensure key is derived
+        self.default_crypt_method()
+            .decrypt(key, data, obj_num, gen_num)
+    }
 
-        match self.algorithm() {
-            EncryptionAlgorithm::RC4 => {
-                // Build object-specific key and decrypt with RC4
-                let key = self.build_object_key(obj_num, gen_num);
+    /// Decrypt a stream object.
+    ///
+    /// Streams in encrypted PDFs are stored as encrypted data that needs
+    /// to be decrypted using the object-specific key.
+    pub fn decrypt_stream(&self, data: &[u8], obj_num: u32, gen_num: u32) -> PDFResult<Vec<u8>> {
+        let key = self.get_encryption_key()?; // Ensure key is derived
+        self.default_crypt_method()
+            .decrypt(key, data, obj_num, gen_num)
+    }
+}
+
+/// Returns the bytes of a literal or hex string.
+fn string_bytes(obj: &PDFObject) -> Option<&[u8]> {
+    obj.as_string().or_else(|| obj.as_hex_string())
+}
+
+/// How a crypt filter encrypts data (the /CFM entry of a crypt filter).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CryptMethod {
+    /// Data is stored unencrypted (/Identity, or /CFM /None)
+    Identity,
+    /// RC4 with an object-specific key (/V2)
+    RC4,
+    /// AES-128 in CBC mode with an object-specific key (/AESV2)
+    AESV2,
+    /// AES-256 in CBC mode with the file key (/AESV3)
+    AESV3,
+}
+
+impl CryptMethod {
+    /// Decrypts the string or stream data of object `obj_num`.
+    ///
+    /// AES data starts with its 16-byte initialization vector; data too
+    /// short to hold one decrypts to nothing.
+    pub fn decrypt(
+        self,
+        file_key: &[u8],
+        data: &[u8],
+        obj_num: u32,
+        gen_num: u32,
+    ) -> PDFResult<Vec<u8>> {
+        match self {
+            CryptMethod::Identity => Ok(data.to_vec()),
+            CryptMethod::RC4 => {
+                let key = object_key(file_key, obj_num, gen_num, false);
                 let mut cipher = ARC4Cipher::new(&key);
                 Ok(cipher.encrypt_block(data)) // RC4 is symmetric
             }
-            EncryptionAlgorithm::AES128 => {
-                // Build object-specific key and decrypt with AES-128
-                let key = self.build_object_key(obj_num, gen_num);
-
-                // Convert Vec<u8> to [u8; 16]
-                if key.len() != 16 {
-                    return Err(PDFError::parse_error(
-                        &format!("AES-128 key length is {}, expected 16", key.len()),
-                        None,
-                    ));
-                }
-                let key_array: [u8; 16] = key.try_into().unwrap();
-                let cipher = AES128Cipher::new(&key_array);
-
-                // Decrypt with ECB mode (zero IV)
-                Ok(cipher.decrypt_block(data))
+            CryptMethod::AESV2 => {
+                let key = object_key(file_key, obj_num, gen_num, true);
+                let key_array: [u8; 16] = key.as_slice().try_into().map_err(|_| {
+                    PDFError::encryption_error(format!(
+                        "AES-128 key length is {}, expected 16",
+                        key.len()
+                    ))
+                })?;
+                let Some((iv, encrypted)) = data.split_first_chunk::<16>() else {
+                    return Ok(Vec::new());
+                };
+                Ok(AES128Cipher::new(&key_array).decrypt(encrypted, iv))
             }
-            EncryptionAlgorithm::AES256 => {
-                // AES-256 uses the encryption key directly
-                let key = self.get_encryption_key()?;
-
-                // Convert &[u8] to [u8; 32]
-                if key.len() != 32 {
-                    return Err(PDFError::parse_error(
-                        &format!("AES-256 key length is {}, expected 32", key.len()),
-                        None,
-                    ));
-                }
-                let key_array: [u8; 32] = key.try_into().unwrap();
-                let cipher = AES256Cipher::new(&key_array);
+            CryptMethod::AESV3 => {
+                let key_array: [u8; 32] = file_key.try_into().map_err(|_| {
+                    PDFError::encryption_error(format!(
+                        "AES-256 key length is {}, expected 32",
+                        file_key.len()
+                    ))
+                })?;
+                let Some((iv, encrypted)) = data.split_first_chunk::<16>() else {
+                    return Ok(Vec::new());
+                };
+                Ok(AES256Cipher::new(&key_array).decrypt(encrypted, iv))
+            }
+        }
+    }
 
-                // For V5 (AES-256), strings use CBC mode with a zero IV
-                let iv = [0u8; 16];
-                Ok(cipher.decrypt(data, &iv))
+    /// Maps a crypt filter's /CFM name to a method.
+    fn from_cfm(cfm: Option<&str>) -> CryptMethod {
+        match cfm {
+            Some("V2") => CryptMethod::RC4,
+            Some("AESV2") => CryptMethod::AESV2,
+            Some("AESV3") => CryptMethod::AESV3,
+            Some("None") | None => CryptMethod::Identity,
+            Some(other) => {
+                tracing::warn!(
+                    target: log_target::CRYPTO,
+                    "Unknown crypt filter method /{}, treating data as unencrypted",
+                    other
+                );
+                CryptMethod::Identity
             }
         }
     }
+}
 
-    /// Decrypt a stream object.
-    ///
-    /// Streams in encrypted PDFs are stored as encrypted data that needs
-    /// to be decrypted using the object-specific key.
-    pub fn decrypt_stream(&self, data: &[u8], obj_num: u32, gen_num: u32) -> PDFResult<Vec<u8>> {
-        let _ = self.get_encryption_key()?; // Ensure key is derived
+/// Decrypts the strings and streams of objects read from an encrypted file.
+///
+/// Built once the file encryption key is known. Strings use the /StrF crypt
+/// filter and streams the /StmF one, unless a stream names its own filter
+/// through a /Crypt entry in its /Filter array. Metadata streams are left
+/// alone when /EncryptMetadata is false, cross-reference streams are never
+/// encrypted, and neither are the /Contents of signature dictionaries.
+///
+/// Based on PDF.js src/core/crypto.js (CipherTransformFactory, CipherTransform)
+#[derive(Debug, Clone)]
+pub struct CipherTransform {
+    key: Vec<u8>,
+    string_method: CryptMethod,
+    stream_method: CryptMethod,
+    crypt_filters: HashMap<String, CryptMethod>,
+    encrypt_metadata: bool,
+}
 
-        match self.algorithm() {
-            EncryptionAlgorithm::RC4 => {
-                // Build object-specific key and decrypt with RC4
-                let key = self.build_object_key(obj_num, gen_num);
-                let mut cipher = ARC4Cipher::new(&key);
-                Ok(cipher.encrypt_block(data)) // RC4 is symmetric
+impl CipherTransform {
+    /// Creates the transform for an authenticated /Encrypt dictionary.
+    ///
+    /// # Arguments
+    /// * `encrypt` - The parsed dictionary, with its encryption key derived
+    /// * `encrypt_obj` - The raw dictionary, for its crypt filters (/CF, /StmF, /StrF)
+    ///
+    /// # Returns
+    /// The transform, or an error if no password has been authenticated
+    pub fn new(encrypt: &EncryptDict, encrypt_obj: &PDFObject) -> PDFResult<Self> {
+        let key = encrypt.get_encryption_key()?.to_vec();
+        let dict = encrypt_obj.as_dictionary();
+        let cf = dict
+            .and_then(|d| d.get("CF"))
+            .and_then(|cf| cf.as_dictionary());
+
+        let (crypt_filters, string_method, stream_method) = match cf {
+            Some(cf) if encrypt.version >= 4 => {
+                let crypt_filters: HashMap<String, CryptMethod> = cf
+                    .iter()
+                    .map(|(name, filter)| {
+                        let cfm = filter
+                            .as_dictionary()
+                            .and_then(|f| f.get("CFM"))
+                            .and_then(|cfm| cfm.as_name());
+                        (name.clone(), CryptMethod::from_cfm(cfm))
+                    })
+                    .collect();
+                let method = |entry: &str| {
+                    let name = dict
+                        .and_then(|d| d.get(entry))
+                        .and_then(|n| n.as_name())
+                        .unwrap_or("Identity");
+                    crypt_filters
+                        .get(name)
+                        .copied()
+                        .unwrap_or(CryptMethod::Identity)
+                };
+                let (string_method, stream_method) = (method("StrF"), method("StmF"));
+                (crypt_filters, string_method, stream_method)
             }
-            EncryptionAlgorithm::AES128 => {
-                // Build object-specific key and decrypt with AES-128
-                let key = self.build_object_key(obj_num, gen_num);
-
-                // Convert Vec<u8> to [u8; 16]
-                if key.len() != 16 {
-                    return Err(PDFError::parse_error(
-                        &format!("AES-128 key length is {}, expected 16", key.len()),
-                        None,
-                    ));
-                }
-                let key_array: [u8; 16] = key.try_into().unwrap();
-                let cipher = AES128Cipher::new(&key_array);
-
-                // AES-128 streams use CBC mode with IV prepended to the data
-                if data.len() < 16 {
-                    return Err(PDFError::parse_error(
-                        "AES-128 encrypted stream too short for IV",
-                        None,
-                    ));
-                }
+            _ => {
+                let method = encrypt.default_crypt_method();
+                (HashMap::new(), method, method)
+            }
+        };
 
-                let iv_array: [u8; 16] = data[0..16].try_into().unwrap();
-                let encrypted_data = &data[16..];
+        Ok(CipherTransform {
+            key,
+            string_method,
+            stream_method,
+            crypt_filters,
+            encrypt_metadata: encrypt.encrypt_metadata,
+        })
+    }
+
+    /// The method used for strings.
+    pub fn string_method(&self) -> CryptMethod {
+        self.string_method
+    }
+
+    /// The method used for streams without a /Crypt filter of their own.
+    pub fn stream_method(&self) -> CryptMethod {
+        self.stream_method
+    }
+
+    /// Decrypts a string of object `obj_num`.
+    pub fn decrypt_string(&self, data: &[u8], obj_num: u32, gen_num: u32) -> PDFResult<Vec<u8>> {
+        self.string_method
+            .decrypt(&self.key, data, obj_num, gen_num)
+    }
+
+    /// Decrypts the data of stream object `obj_num`, picking the method from
+    /// its dictionary.
+    pub fn decrypt_stream(
+        &self,
+        dict: &HashMap<String, PDFObject>,
+        data: &[u8],
+        obj_num: u32,
+        gen_num: u32,
+    ) -> PDFResult<Vec<u8>> {
+        self.stream_method_for(dict)
+            .decrypt(&self.key, data, obj_num, gen_num)
+    }
+
+    /// Returns the crypt method that applies to a stream with this dictionary.
+    pub fn stream_method_for(&self, dict: &HashMap<String, PDFObject>) -> CryptMethod {
+        let type_name = dict.get("Type").and_then(|t| t.as_name());
+        if type_name == Some("XRef") {
+            return CryptMethod::Identity;
+        }
+        if type_name == Some("Metadata") && !self.encrypt_metadata {
+            return CryptMethod::Identity;
+        }
+
+        // A /Crypt filter must come first in the filter list, with the crypt
+        // filter's name in its decode parameters
+        let first_filter = match dict.get("Filter") {
+            Some(PDFObject::Array(filters)) => filters.first().map(|f| &**f),
+            other => other,
+        };
+        if first_filter.and_then(|f| f.as_name()) == Some("Crypt") {
+            let params = match dict.get("DecodeParms") {
+                Some(PDFObject::Array(params)) => params.first().map(|p| &**p),
+                other => other,
+            };
+            let name = params
+                .and_then(|p| p.as_dictionary())
+                .and_then(|p| p.get("Name"))
+                .and_then(|n| n.as_name())
+                .unwrap_or("Identity");
+            return self
+                .crypt_filters
+                .get(name)
+                .copied()
+                .unwrap_or(CryptMethod::Identity);
+        }
+
+        self.stream_method
+    }
 
-                Ok(cipher.decrypt(encrypted_data, &iv_array))
+    /// Decrypts every string and stream in an object read from the file as
+    /// object `obj_num`.
+    pub fn decrypt_object(
+        &self,
+        object: PDFObject,
+        obj_num: u32,
+        gen_num: u32,
+    ) -> PDFResult<PDFObject> {
+        Ok(match object {
+            PDFObject::String(data) => {
+                PDFObject::String(self.decrypt_string(&data, obj_num, gen_num)?)
             }
-            EncryptionAlgorithm::AES256 => {
-                // AES-256 uses the encryption key directly
-                let key = self.get_encryption_key()?;
-
-                // Convert &[u8] to [u8; 32]
-                if key.len() != 32 {
-                    return Err(PDFError::parse_error(
-                        &format!("AES-256 key length is {}, expected 32", key.len()),
-                        None,
-                    ));
-                }
-                let key_array: [u8; 32] = key.try_into().unwrap();
-                let cipher = AES256Cipher::new(&key_array);
-
-                // AES-256 streams use CBC mode with IV prepended to the data
-                if data.len() < 16 {
-                    return Err(PDFError::parse_error(
-                        "AES-256 encrypted stream too short for IV",
-                        None,
-                    ));
+            PDFObject::HexString(data) => {
+                PDFObject::HexString(self.decrypt_string(&data, obj_num, gen_num)?)
+            }
+            PDFObject::Array(items) => PDFObject::Array(
+                items
+                    .into_iter()
+                    .map(|item| Ok(Box::new(self.decrypt_object(*item, obj_num, gen_num)?)))
+                    .collect::<PDFResult<_>>()?,
+            ),
+            PDFObject::Dictionary(dict) => {
+                PDFObject::Dictionary(self.decrypt_dict(dict, obj_num, gen_num)?)
+            }
+            PDFObject::Stream { dict, data } => {
+                let data = self.decrypt_stream(&dict, &data, obj_num, gen_num)?;
+                PDFObject::Stream {
+                    dict: self.decrypt_dict(dict, obj_num, gen_num)?,
+                    data,
                 }
+            }
+            other => other,
+        })
+    }
 
-                let iv_array: [u8; 16] = data[0..16].try_into().unwrap();
-                let encrypted_data = &data[16..];
+    fn decrypt_dict(
+        &self,
+        dict: HashMap<String, PDFObject>,
+        obj_num: u32,
+        gen_num: u32,
+    ) -> PDFResult<HashMap<String, PDFObject>> {
+        // The signature value is excluded from encryption, like the rest of
+        // the byte range it signs
+        let is_signature = dict.contains_key("ByteRange");
+        dict.into_iter()
+            .map(|(key, value)| {
+                let value = if is_signature && key == "Contents" {
+                    value
+                } else {
+                    self.decrypt_object(value, obj_num, gen_num)?
+                };
+                Ok((key, value))
+            })
+            .collect()
+    }
+}
 
-                Ok(cipher.decrypt(encrypted_data, &iv_array))
-            }
-        }
+/// Builds the key for one object from the file key (Algorithm 1 of the
+/// PDF specification): MD5(key + obj_num(3) + gen_num(2) [+ "sAlt"]),
+/// truncated to the key length plus 5, at most 16 bytes.
+fn object_key(file_key: &[u8], obj_num: u32, gen_num: u32, aes: bool) -> Vec<u8> {
+    use crate::core::crypto::calculate_md5;
+
+    let mut key_data = Vec::with_capacity(file_key.len() + 9);
+    key_data.extend_from_slice(file_key);
+    key_data.extend_from_slice(&obj_num.to_le_bytes()[0..3]); // 3 bytes, little-endian
+    key_data.extend_from_slice(&gen_num.to_le_bytes()[0..2]); // 2 bytes, little-endian
+    if aes {
+        key_data.extend_from_slice(&[0x73, 0x41, 0x6c, 0x54]); // "sAlt"
     }
+
+    let hash = calculate_md5(&key_data);
+    let key_len = (file_key.len() + 5).min(16);
+    hash[..key_len].to_vec()
 }
 
 // ============================================================================
@@ -607,16 +861,18 @@ fn check_user_password_legacy(
         encrypt_metadata,
     );
 
-    // For R >= 3, do 19 rounds of RC4 with varying keys
+    // For R >= 3 (Algorithm 5), encrypt MD5(padding + file ID) with the key,
+    // then 19 more times with the key XORed with the round number. Only the
+    // first 16 bytes of U are significant.
     let check_data = if revision >= 3 {
-        let mut check_data = DEFAULT_PASSWORD_PAD.to_vec();
-        // Iterate backwards from 19 to 0 (as per PDF spec)
-        for i in (0..=19).rev() {
-            let mut derived_key = key.clone();
+        use crate::core::crypto::calculate_md5;
+
+        let mut hash_data = DEFAULT_PASSWORD_PAD.to_vec();
+        hash_data.extend_from_slice(file_id);
+        let mut check_data = calculate_md5(&hash_data).to_vec();
+        for i in 0..=19u8 {
             // XOR each byte with the iteration count (not addition!)
-            for byte in derived_key.iter_mut() {
-                *byte ^= i as u8;
-            }
+            let derived_key: Vec<u8> = key.iter().map(|byte| byte ^ i).collect();
             let mut cipher = ARC4Cipher::new(&derived_key);
             check_data = cipher.encrypt_block(&check_data);
         }
@@ -689,6 +945,7 @@ fn decode_user_password(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_support::build_pdf_with_trailer;
 
     #[test]
     fn test_permissions_from_p_value() {
@@ -983,9 +1240,10 @@ mod tests {
 
         // Derive object key for object 123, generation 0
         // The key should be deterministic based on the encryption key + obj/num
-        let key1 = encrypt_dict.build_object_key(123, 0);
-        let key2 = encrypt_dict.build_object_key(123, 0);
-        let key3 = encrypt_dict.build_object_key(124, 0);
+        let file_key = encrypt_dict.get_encryption_key().unwrap();
+        let key1 = object_key(file_key, 123, 0, false);
+        let key2 = object_key(file_key, 123, 0, false);
+        let key3 = object_key(file_key, 124, 0, false);
 
         // Same obj/gen should produce same key
         assert_eq!(key1, key2);
@@ -1102,16 +1360,17 @@ mod tests {
 
         let key = &hash[..key_length];
 
-        // Now compute U with 19 XOR iterations
+        // Now compute U: MD5(padding + file ID), encrypted 20 times with
+        // the key XORed with the round number, padded to 32 bytes
         let mut check_data = DEFAULT_PASSWORD_PAD.to_vec();
-        for i in (0..=19).rev() {
-            let mut derived_key = key.to_vec();
-            for byte in derived_key.iter_mut() {
-                *byte ^= i as u8;
-            }
+        check_data.extend_from_slice(&file_id);
+        let mut check_data = calculate_md5(&check_data).to_vec();
+        for i in 0..=19u8 {
+            let derived_key: Vec<u8> = key.iter().map(|byte| byte ^ i).collect();
             let mut cipher = ARC4Cipher::new(&derived_key);
             check_data = cipher.encrypt_block(&check_data);
         }
+        check_data.resize(32, 0);
 
         // Test with our implementation
         let mut encrypt_dict = EncryptDict {
@@ -1319,4 +1578,201 @@ mod tests {
 
         assert_eq!(decrypted, original.to_vec());
     }
+
+    /// Builds a V=4 R=4 file encrypted with AESV2 crypt filters for `password`.
+    fn build_aes_encrypted_pdf(password: &[u8]) -> Vec<u8> {
+        build_aes_encrypted_pdf_with_permissions(password, -4)
+    }
+
+    /// Like `build_aes_encrypted_pdf`, with the given /P value.
+    fn build_aes_encrypted_pdf_with_permissions(password: &[u8], p: i32) -> Vec<u8> {
+        use crate::core::crypto::calculate_md5;
+
+        let file_id = [0x42u8; 16];
+        let o = [0x11u8; 32];
+        let p_value = p;
+        let p = p as u32;
+
+        // Algorithm 2: file key from the password, O, P and the file ID
+        let key = derive_encryption_key(password, &o, p, &file_id, 4, 16, true);
+
+        // Algorithm 5: U from MD5(padding + file ID)
+        let mut u = DEFAULT_PASSWORD_PAD.to_vec();
+        u.extend_from_slice(&file_id);
+        let mut u = calculate_md5(&u).to_vec();
+        for i in 0..=19u8 {
+            let round_key: Vec<u8> = key.iter().map(|byte| byte ^ i).collect();
+            u = ARC4Cipher::new(&round_key).encrypt_block(&u);
+        }
+        u.resize(32, 0);
+
+        let encrypt = |data: &[u8], obj_num: u32| {
+            let object_key: [u8; 16] = object_key(&key, obj_num, 0, true).try_into().unwrap();
+            let iv = [0x24u8; 16];
+            let mut encrypted = iv.to_vec();
+            encrypted.extend(AES128Cipher::new(&object_key).encrypt(data, &iv));
+            encrypted
+        };
+        let hex = |data: &[u8]| {
+            data.iter()
+                .map(|b| format!("{:02x}", b))
+                .collect::<String>()
+        };
+        let stream = |dict: &str, data: &[u8]| {
+            let mut object =
+                format!("<< {} /Length {} >>\nstream\n", dict, data.len()).into_bytes();
+            object.extend_from_slice(data);
+            object.extend_from_slice(b"\nendstream");
+            object
+        };
+
+        let content = encrypt(b"BT /F1 12 Tf 72 700 Td (Secret) Tj ET", 4);
+        let objects: Vec<Vec<u8>> = vec![
+            format!(
+                "<< /Type /Catalog /Pages 2 0 R /Lang <{}> >>",
+                hex(&encrypt(b"en-US", 1))
+            )
+            .into_bytes(),
+            b"<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_vec(),
+            b"<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] /Contents 4 0 R \
+              /Resources << /Font << /F1 5 0 R >> >> >>"
+                .to_vec(),
+            stream("", &content),
+            b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>".to_vec(),
+            format!(
+                "<< /Filter /Standard /V 4 /R 4 /Length 128 \
+                 /CF << /StdCF << /CFM /AESV2 /Length 16 >> >> /StmF /StdCF /StrF /StdCF \
+                 /O <{}> /U <{}> /P {} >>",
+                hex(&o),
+                hex(&u),
+                p_value
+            )
+            .into_bytes(),
+            stream(
+                "/Filter [/Crypt] /DecodeParms [<< /Name /Identity >>]",
+                b"plain data",
+            ),
+        ];
+
+        build_pdf_with_trailer(
+            &objects,
+            &format!(
+                "/Encrypt 6 0 R /ID [<{}> <{}>]",
+                hex(&file_id),
+                hex(&file_id)
+            ),
+        )
+    }
+
+    #[test]
+    fn test_fetched_objects_are_decrypted_after_authentication() {
+        use crate::core::PDFDocument;
+
+        let mut doc = PDFDocument::open(build_aes_encrypted_pdf(b"secret")).unwrap();
+        assert!(doc.is_encrypted());
+        assert!(!doc.is_unlocked());
+        assert!(!doc.authenticate_with_password(b"wrong").unwrap());
+
+        assert!(doc.authenticate_with_password(b"secret").unwrap());
+        assert!(doc.is_unlocked());
+        let cipher = doc.xref().cipher().unwrap();
+        assert_eq!(cipher.string_method(), CryptMethod::AESV2);
+        assert_eq!(cipher.stream_method(), CryptMethod::AESV2);
+
+        let page = doc.get_page(0).unwrap();
+        let text = page.extract_text_as_string(doc.xref_mut()).unwrap();
+        assert!(text.contains("Secret"), "got {:?}", text);
+
+        let catalog = doc.xref_mut().catalog().unwrap();
+        let lang = catalog.as_dictionary().unwrap().get("Lang").unwrap();
+        assert_eq!(lang.as_hex_string(), Some(&b"en-US"[..]));
+
+        // The /Identity crypt filter leaves the stream as it is
+        match &*doc.xref_mut().fetch(7, 0).unwrap() {
+            PDFObject::Stream { data, .. } => assert_eq!(data, b"plain data"),
+            other => panic!("Expected a stream, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_earlier_revision_is_unlocked_with_the_same_key() {
+        use crate::core::PDFDocument;
+
+        // An incremental update adding an unencrypted number
+        let mut pdf = build_aes_encrypted_pdf(b"secret");
+        let prev = pdf.windows(6).position(|w| w == b"\nxref\n").unwrap() + 1;
+        let trailer = pdf.windows(7).rposition(|w| w == b"trailer").unwrap();
+        let entries = String::from_utf8_lossy(&pdf[trailer..]).into_owned();
+        let id = &entries[entries.find("/ID").unwrap()..entries.find(" >>").unwrap()];
+        let offset = pdf.len();
+        pdf.extend_from_slice(b"8 0 obj\n42\nendobj\n");
+        let xref_offset = pdf.len();
+        pdf.extend_from_slice(
+            format!(
+                "xref\n8 1\n{:010} 00000 n \ntrailer\n<< /Size 9 /Root 1 0 R /Encrypt 6 0 R {} /Prev {} >>\nstartxref\n{}\n%%EOF\n",
+                offset, id, prev, xref_offset
+            )
+            .as_bytes(),
+        );
+
+        let mut doc = PDFDocument::open(pdf).unwrap();
+        assert!(!doc.open_revision(0).unwrap().is_unlocked());
+
+        assert!(doc.authenticate_with_password(b"secret").unwrap());
+        let mut original = doc.open_revision(0).unwrap();
+        assert!(original.is_unlocked());
+        assert!(
+            original
+                .extract_text_from_page_as_string(0)
+                .unwrap()
+                .contains("Secret")
+        );
+    }
+
+    #[test]
+    fn test_empty_user_password_unlocks_on_open() {
+        use crate::core::PDFDocument;
+
+        let mut doc = PDFDocument::open(build_aes_encrypted_pdf(b"")).unwrap();
+        assert!(doc.is_encrypted());
+        assert!(doc.is_unlocked());
+
+        let page = doc.get_page(0).unwrap();
+        let text = page.extract_text_as_string(doc.xref_mut()).unwrap();
+        assert!(text.contains("Secret"), "got {:?}", text);
+    }
+
+    #[test]
+    fn test_permissions_are_enforced_on_request() {
+        use crate::core::{PDFDocument, PDFError};
+
+        // Everything but copying text (bit 5)
+        let mut doc =
+            PDFDocument::open(build_aes_encrypted_pdf_with_permissions(b"", -4 & !16)).unwrap();
+        let permissions = doc.permissions();
+        assert!(permissions.print);
+        assert!(!permissions.copy);
+        assert!(!permissions.allows(Permission::Copy));
+
+        // Without enforcement, restrictions are only reported
+        assert!(!doc.enforces_permissions());
+        assert!(
+            doc.extract_text_from_page_as_string(0)
+                .unwrap()
+                .contains("Secret")
+        );
+
+        doc.set_enforce_permissions(true);
+        match doc.extract_text_from_page_as_string(0) {
+            Err(PDFError::PermissionDenied { operation }) => {
+                assert_eq!(operation, Permission::Copy.description())
+            }
+            other => panic!("Expected PermissionDenied, got {:?}", other),
+        }
+        assert!(doc.check_permission(Permission::Print).is_ok());
+
+        // The owner password is not known, so it doesn't lift the restriction
+        assert!(!doc.check_owner_password(b"owner"));
+        assert!(doc.check_permission(Permission::Copy).is_err());
+    }
 }
@@ -56,9 +56,41 @@ pub enum PDFError {
     /// Validation error
     ValidationError { message: String },
 
+    /// The document's permissions don't allow the operation
+    PermissionDenied { operation: String },
+
+    /// The operation was stopped through a `CancellationToken`
+    Cancelled,
+
     /// Stream operation failed
     StreamError(String),
 
+    /// An indirect object isn't in the cross-reference table, is free or
+    /// has been deleted; `revision` is set when the object was looked up
+    /// as of an earlier revision of the file
+    MissingObject {
+        num: u32,
+        generation: u32,
+        revision: Option<usize>,
+    },
+
+    /// A stream filter couldn't decode its input
+    FilterError { filter: String, cause: String },
+
+    /// Decryption failed or the /Encrypt dictionary is unusable
+    EncryptionError { message: String },
+
+    /// A configured resource limit was reached, see
+    /// [`ResourceLimits`](crate::core::ResourceLimits)
+    LimitExceeded { limit: String, max: usize },
+
+    /// An error raised while loading a particular indirect object
+    InObject {
+        num: u32,
+        generation: u32,
+        source: Box<PDFError>,
+    },
+
     /// Generic error with message
     Generic(String),
 }
@@ -131,9 +163,53 @@ impl fmt::Display for PDFError {
             PDFError::ValidationError { message } => {
                 write!(f, "Validation error: {}", message)
             }
+            PDFError::PermissionDenied { operation } => {
+                write!(
+                    f,
+                    "Permission denied: the document does not allow {}",
+                    operation
+                )
+            }
+            PDFError::Cancelled => {
+                write!(f, "Operation cancelled")
+            }
             PDFError::StreamError(msg) => {
                 write!(f, "Stream error: {}", msg)
             }
+            PDFError::MissingObject {
+                num,
+                generation,
+                revision: None,
+            } => {
+                write!(f, "Object {} {} R not found", num, generation)
+            }
+            PDFError::MissingObject {
+                num,
+                generation,
+                revision: Some(revision),
+            } => {
+                write!(
+                    f,
+                    "Object {} {} R not found in revision {}",
+                    num, generation, revision
+                )
+            }
+            PDFError::FilterError { filter, cause } => {
+                write!(f, "Filter {} failed: {}", filter, cause)
+            }
+            PDFError::EncryptionError { message } => {
+                write!(f, "Encryption error: {}", message)
+            }
+            PDFError::LimitExceeded { limit, max } => {
+                write!(f, "Resource limit exceeded: {} (limit {})", limit, max)
+            }
+            PDFError::InObject {
+                num,
+                generation,
+                source,
+            } => {
+                write!(f, "In object {} {} R: {}", num, generation, source)
+            }
             PDFError::Generic(msg) => {
                 write!(f, "{}", msg)
             }
@@ -220,6 +296,13 @@ impl PDFError {
         }
     }
 
+    /// Creates a permission error for an operation the document restricts.
+    pub fn permission_denied<S: Into<String>>(operation: S) -> Self {
+        PDFError::PermissionDenied {
+            operation: operation.into(),
+        }
+    }
+
     /// Creates a data missing error for progressive loading.
     pub fn data_missing(position: usize, length: usize) -> Self {
         PDFError::DataMissing { position, length }
@@ -232,9 +315,197 @@ impl PDFError {
             found: found.into(),
         }
     }
+
+    /// Creates a missing object error.
+    pub fn missing_object(num: u32, generation: u32) -> Self {
+        PDFError::MissingObject {
+            num,
+            generation,
+            revision: None,
+        }
+    }
+
+    /// Creates a missing object error for an object looked up as of an
+    /// earlier revision of the file.
+    pub fn missing_object_in_revision(num: u32, generation: u32, revision: usize) -> Self {
+        PDFError::MissingObject {
+            num,
+            generation,
+            revision: Some(revision),
+        }
+    }
+
+    /// Creates a filter error.
+    pub fn filter_error<S: Into<String>, C: fmt::Display>(filter: S, cause: C) -> Self {
+        PDFError::FilterError {
+            filter: filter.into(),
+            cause: cause.to_string(),
+        }
+    }
+
+    /// Creates an encryption error.
+    pub fn encryption_error<S: Into<String>>(message: S) -> Self {
+        PDFError::EncryptionError {
+            message: message.into(),
+        }
+    }
+
+    /// Creates an error for a resource limit that was reached.
+    pub fn limit_exceeded<S: Into<String>>(limit: S, max: usize) -> Self {
+        PDFError::LimitExceeded {
+            limit: limit.into(),
+            max,
+        }
+    }
+
+    /// Attaches the indirect object being loaded to this error.
+    ///
+    /// Progressive loading, cancellation and resource limit errors are
+    /// returned unchanged, since callers match on them to fetch data or
+    /// stop, and so are errors that already name an object: the innermost
+    /// object is the most precise one.
+    pub fn in_object(self, num: u32, generation: u32) -> Self {
+        match self {
+            PDFError::DataMissing { .. }
+            | PDFError::DataNotLoaded { .. }
+            | PDFError::Cancelled
+            | PDFError::LimitExceeded { .. }
+            | PDFError::MissingObject { .. }
+            | PDFError::InObject { .. } => self,
+            source => PDFError::InObject {
+                num,
+                generation,
+                source: Box::new(source),
+            },
+        }
+    }
+
+    /// Returns the error without the object context added by
+    /// [`in_object`](Self::in_object).
+    pub fn root_cause(&self) -> &PDFError {
+        match self {
+            PDFError::InObject { source, .. } => source.root_cause(),
+            other => other,
+        }
+    }
+
+    /// Returns the stable code of this error, for consumers that can't match
+    /// on the enum (FFI, WebAssembly). Object context is looked through.
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            PDFError::UnexpectedEndOfStream => ErrorCode::UnexpectedEndOfStream,
+            PDFError::InvalidByteRange { .. } => ErrorCode::InvalidByteRange,
+            PDFError::DataNotLoaded { .. } => ErrorCode::DataNotLoaded,
+            PDFError::DataMissing { .. } => ErrorCode::DataMissing,
+            PDFError::InvalidPosition { .. } => ErrorCode::InvalidPosition,
+            PDFError::InvalidObject { .. } => ErrorCode::InvalidObject,
+            PDFError::ParseError { .. } => ErrorCode::Parse,
+            PDFError::XRefError { .. } => ErrorCode::XRef,
+            PDFError::PageError { .. } => ErrorCode::Page,
+            PDFError::FontError { .. } => ErrorCode::Font,
+            PDFError::ContentStreamError { .. } => ErrorCode::ContentStream,
+            PDFError::IOError { .. } => ErrorCode::IO,
+            PDFError::CorruptedPDF { .. } => ErrorCode::Corrupted,
+            PDFError::Unsupported { .. } => ErrorCode::Unsupported,
+            PDFError::ValidationError { .. } => ErrorCode::Validation,
+            PDFError::PermissionDenied { .. } => ErrorCode::PermissionDenied,
+            PDFError::Cancelled => ErrorCode::Cancelled,
+            PDFError::StreamError(_) => ErrorCode::Stream,
+            PDFError::MissingObject { .. } => ErrorCode::MissingObject,
+            PDFError::FilterError { .. } => ErrorCode::Filter,
+            PDFError::EncryptionError { .. } => ErrorCode::Encryption,
+            PDFError::LimitExceeded { .. } => ErrorCode::LimitExceeded,
+            PDFError::InObject { source, .. } => source.code(),
+            PDFError::Generic(_) => ErrorCode::Other,
+        }
+    }
+
+    /// Returns the indirect object the error is about, if known.
+    pub fn object(&self) -> Option<(u32, u32)> {
+        match self {
+            PDFError::MissingObject {
+                num, generation, ..
+            } => Some((*num, *generation)),
+            PDFError::InObject {
+                num,
+                generation,
+                source,
+            } => source.object().or(Some((*num, *generation))),
+            _ => None,
+        }
+    }
+
+    /// Returns the byte offset in the file the error is about, if known.
+    pub fn position(&self) -> Option<usize> {
+        match self {
+            PDFError::DataMissing { position, .. } => Some(*position),
+            PDFError::InvalidPosition { pos, .. } => Some(*pos),
+            PDFError::ParseError { position, .. } => *position,
+            PDFError::InObject { source, .. } => source.position(),
+            _ => None,
+        }
+    }
 }
 
-impl std::error::Error for PDFError {}
+/// Stable numeric codes for [`PDFError`] variants.
+///
+/// The values never change once assigned, so they can cross an FFI or
+/// WebAssembly boundary where the enum itself can't.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u32)]
+pub enum ErrorCode {
+    /// [`PDFError::Generic`]: not classified yet
+    Other = 1,
+    UnexpectedEndOfStream = 2,
+    InvalidByteRange = 3,
+    DataNotLoaded = 4,
+    DataMissing = 5,
+    InvalidPosition = 6,
+    InvalidObject = 7,
+    Parse = 8,
+    XRef = 9,
+    Page = 10,
+    Font = 11,
+    ContentStream = 12,
+    IO = 13,
+    Corrupted = 14,
+    Unsupported = 15,
+    Validation = 16,
+    PermissionDenied = 17,
+    Cancelled = 18,
+    Stream = 19,
+    MissingObject = 20,
+    Filter = 21,
+    Encryption = 22,
+    LimitExceeded = 23,
+}
+
+impl std::error::Error for PDFError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PDFError::InObject { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<String> for PDFError {
+    fn from(message: String) -> Self {
+        PDFError::Generic(message)
+    }
+}
+
+impl From<&str> for PDFError {
+    fn from(message: &str) -> Self {
+        PDFError::Generic(message.to_string())
+    }
+}
+
+impl From<std::io::Error> for PDFError {
+    fn from(error: std::io::Error) -> Self {
+        PDFError::io_error(error.to_string())
+    }
+}
 
 /// Result type alias for PDF operations
 pub type PDFResult<T> = Result<T, PDFError>;
@@ -319,6 +590,36 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_object_context_and_codes() {
+        let err = PDFError::filter_error("FlateDecode", "corrupt deflate stream").in_object(12, 0);
+        assert_eq!(
+            err.to_string(),
+            "In object 12 0 R: Filter FlateDecode failed: corrupt deflate stream"
+        );
+        assert_eq!(err.code(), ErrorCode::Filter);
+        assert_eq!(err.object(), Some((12, 0)));
+        assert!(matches!(err.root_cause(), PDFError::FilterError { .. }));
+        assert!(std::error::Error::source(&err).is_some());
+
+        // The innermost object wins
+        let err = PDFError::missing_object(7, 0).in_object(3, 0);
+        assert_eq!(err.object(), Some((7, 0)));
+        assert_eq!(err.code(), ErrorCode::MissingObject);
+        let err = PDFError::missing_object_in_revision(7, 0, 1);
+        assert_eq!(err.to_string(), "Object 7 0 R not found in revision 1");
+        assert_eq!(err.object(), Some((7, 0)));
+
+        // Progressive loading errors are never wrapped
+        let err = PDFError::data_missing(100, 50).in_object(3, 0);
+        assert!(matches!(err, PDFError::DataMissing { .. }));
+        assert_eq!(err.position(), Some(100));
+
+        let err: PDFError = "something went wrong".into();
+        assert_eq!(err.code(), ErrorCode::Other);
+        assert_eq!(ErrorCode::Encryption as u32, 22);
+    }
+
     #[test]
     fn test_error_chain_compatibility() {
         let err = PDFError::io_error("File not found");
@@ -6,6 +6,7 @@
 //! The main components are:
 //! - **FontDict**: PDF font dictionary representation
 //! - **Font**: Complete font with encoding, metrics, and glyph access
+//! - **StandardFont**: Built-in widths of the standard 14 fonts
 //! - Integration with hayro-font for CFF/Type1 glyph metrics
 //! - Integration with CMap for character-to-Unicode mapping
 //!
@@ -16,8 +17,70 @@ use crate::core::decode;
 use crate::core::encoding::Encoding;
 use crate::core::error::{PDFError, PDFResult};
 use crate::core::parser::PDFObject;
+use crate::log_target;
 use rustc_hash::FxHashMap;
 
+mod standard_metrics;
+
+pub use standard_metrics::StandardFont;
+
+/// Format of an embedded font program.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FontProgramFormat {
+    /// TrueType (sfnt with glyf outlines), usually from /FontFile2
+    TrueType,
+    /// Bare CFF table, from /FontFile3 with /Type1C or /CIDFontType0C
+    CFF,
+    /// Type1 font program, from /FontFile
+    Type1,
+    /// OpenType (sfnt with CFF outlines), from /FontFile3 with /OpenType
+    OpenType,
+}
+
+impl FontProgramFormat {
+    /// Detects the format of a decoded font program.
+    ///
+    /// The leading bytes decide when they are recognizable, since writers
+    /// regularly put programs under the wrong /FontFile key; otherwise the
+    /// descriptor key and the stream's /Subtype are used.
+    ///
+    /// # Arguments
+    /// * `data` - The decoded font program
+    /// * `key` - The descriptor key the program was found under
+    /// * `subtype` - The /Subtype of a /FontFile3 stream
+    pub fn detect(data: &[u8], key: &str, subtype: Option<&str>) -> Self {
+        match data {
+            [b'O', b'T', b'T', b'O', ..] => return FontProgramFormat::OpenType,
+            [0x00, 0x01, 0x00, 0x00, ..]
+            | [b't', b'r', b'u', b'e', ..]
+            | [b't', b't', b'c', b'f', ..] => {
+                return FontProgramFormat::TrueType;
+            }
+            [b'%', b'!', ..] | [0x80, 0x01, ..] => return FontProgramFormat::Type1,
+            // CFF header: major version 1, then minor version and header size
+            [0x01, _, header_size, ..] if *header_size >= 4 => return FontProgramFormat::CFF,
+            _ => {}
+        }
+
+        match (key, subtype) {
+            ("FontFile", _) => FontProgramFormat::Type1,
+            ("FontFile2", _) => FontProgramFormat::TrueType,
+            (_, Some("OpenType")) => FontProgramFormat::OpenType,
+            _ => FontProgramFormat::CFF,
+        }
+    }
+
+    /// Returns the customary file extension for the format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            FontProgramFormat::TrueType => "ttf",
+            FontProgramFormat::CFF => "cff",
+            FontProgramFormat::Type1 => "pfa",
+            FontProgramFormat::OpenType => "otf",
+        }
+    }
+}
+
 /// PDF font type enumeration.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum FontType {
@@ -33,6 +96,8 @@ pub enum FontType {
     CIDFontType0,
     /// CID TrueType font
     CIDFontType2,
+    /// Composite font (code → CID through its /Encoding CMap)
+    Type0,
     /// Unknown font type
     Unknown,
 }
@@ -47,6 +112,7 @@ impl FontType {
             "Type3" => FontType::Type3,
             "CIDFontType0" => FontType::CIDFontType0,
             "CIDFontType2" => FontType::CIDFontType2,
+            "Type0" => FontType::Type0,
             _ => FontType::Unknown,
         }
     }
@@ -90,8 +156,20 @@ pub struct FontDict {
     /// Default width for missing characters
     pub default_width: f64,
 
+    /// /Ascent of the font descriptor, in glyph space units
+    pub ascent: Option<f64>,
+
+    /// /Descent of the font descriptor (usually negative), in glyph space units
+    pub descent: Option<f64>,
+
     /// CID font information (for CIDFonts)
     pub descendant_fonts: Option<PDFObject>,
+
+    /// /FontMatrix of a Type3 font, mapping glyph space to text space
+    pub font_matrix: Option<[f64; 6]>,
+
+    /// /FontBBox of a Type3 font, in glyph space units
+    pub font_bbox: Option<[f64; 4]>,
 }
 
 impl FontDict {
@@ -159,6 +237,24 @@ impl FontDict {
         // Get DescendantFonts (for Type0 composite fonts)
         let descendant_fonts = dict.get("DescendantFonts").cloned();
 
+        // Type3 glyphs are described in their own glyph space
+        let numbers = |key: &str| -> Option<Vec<f64>> {
+            match dict.get(key) {
+                Some(PDFObject::Array(arr)) if font_type == FontType::Type3 => {
+                    arr.iter().map(|v| v.as_number()).collect()
+                }
+                _ => None,
+            }
+        };
+        let font_matrix = match numbers("FontMatrix").as_deref() {
+            Some(&[a, b, c, d, e, f]) if a * d - b * c != 0.0 => Some([a, b, c, d, e, f]),
+            _ => None,
+        };
+        let font_bbox = match numbers("FontBBox").as_deref() {
+            Some(&[x0, y0, x1, y1]) => Some([x0.min(x1), y0.min(y1), x0.max(x1), y0.max(y1)]),
+            _ => None,
+        };
+
         Ok(FontDict {
             font_type,
             base_font,
@@ -169,11 +265,291 @@ impl FontDict {
             first_char,
             last_char,
             default_width: 250.0, // PDF default width
+            ascent: None,
+            descent: None,
             descendant_fonts,
+            font_matrix,
+            font_bbox,
         })
     }
 }
 
+/// How a Type0 font maps character codes to CIDs (its /Encoding).
+pub enum CIDEncoding {
+    /// Identity-H / Identity-V: 2-byte codes, CID = code
+    Identity {
+        /// True for Identity-V (vertical writing)
+        vertical: bool,
+    },
+    /// Embedded CMap stream with codespace and cidchar/cidrange mappings
+    Embedded(CMap),
+    /// Predefined CMap other than Identity-H/V, whose tables aren't bundled.
+    /// Codes are split with the codespace of the font's ToUnicode CMap (2
+    /// bytes without one) and CID = code.
+    Predefined {
+        /// Name of the CMap, e.g. 90ms-RKSJ-H
+        name: String,
+        /// True for vertical writing (a -V CMap)
+        vertical: bool,
+    },
+}
+
+impl CIDEncoding {
+    /// Returns true for vertical writing mode.
+    pub fn is_vertical(&self) -> bool {
+        match self {
+            CIDEncoding::Identity { vertical } | CIDEncoding::Predefined { vertical, .. } => {
+                *vertical
+            }
+            CIDEncoding::Embedded(cmap) => cmap.is_vertical(),
+        }
+    }
+}
+
+/// Metrics and glyph mapping of the descendant CIDFont of a Type0 font.
+///
+/// Reference: PDF 32000-1:2008 section 9.7.4 (CIDFonts) and 9.7.5 (CMaps);
+/// pdf.js src/core/fonts.js (extractWidths, cidToGidMap).
+pub struct CIDFontInfo {
+    /// Descendant font subtype (CIDFontType0 or CIDFontType2)
+    pub subtype: FontType,
+    /// Code → CID mapping
+    pub encoding: CIDEncoding,
+    /// Horizontal widths from /W, in glyph space units
+    pub widths: FxHashMap<u32, f64>,
+    /// /DW (default 1000)
+    pub default_width: f64,
+    /// Vertical advances (w1y) from /W2, in glyph space units
+    pub vertical_advances: FxHashMap<u32, f64>,
+    /// w1y of /DW2 (default -1000)
+    pub default_vertical_advance: f64,
+    /// vy of /DW2: height of the vertical origin above the baseline (default 880)
+    pub default_vertical_origin: f64,
+    /// /CIDToGIDMap stream as GIDs indexed by CID; None means Identity
+    pub cid_to_gid: Option<Vec<u16>>,
+}
+
+impl CIDFontInfo {
+    /// Reads the descendant CIDFont of a Type0 font dictionary.
+    ///
+    /// Returns the info together with the descendant's /FontDescriptor. A
+    /// missing descendant is tolerated with default metrics (/DW 1000).
+    fn from_type0(
+        dict: &FontDict,
+        xref: &mut crate::core::xref::XRef,
+    ) -> PDFResult<(Self, Option<PDFObject>)> {
+        let encoding = Self::parse_encoding(dict.encoding.as_ref(), xref);
+
+        let descendant = match dict.descendant_fonts.as_ref() {
+            Some(obj) => match xref.fetch_if_ref(obj)? {
+                PDFObject::Array(arr) if !arr.is_empty() => xref.fetch_if_ref(&arr[0])?,
+                other @ PDFObject::Dictionary(_) => other,
+                _ => PDFObject::Null,
+            },
+            None => PDFObject::Null,
+        };
+        let cid_dict = match descendant {
+            PDFObject::Dictionary(cid_dict) => cid_dict,
+            _ => {
+                tracing::warn!(
+                    target: log_target::FONT,
+                    "Type0 font '{}' has no descendant CIDFont, using default widths",
+                    dict.base_font
+                );
+                Default::default()
+            }
+        };
+
+        let subtype = match cid_dict.get("Subtype") {
+            Some(PDFObject::Name(name)) => FontType::from_subtype(name),
+            _ => FontType::CIDFontType2,
+        };
+
+        let number = |obj: &PDFObject| match obj {
+            PDFObject::Number(n) if n.is_finite() => Some(*n),
+            _ => None,
+        };
+        let default_width = cid_dict
+            .get("DW")
+            .and_then(|dw| xref.fetch_if_ref(dw).ok())
+            .and_then(|dw| number(&dw))
+            .unwrap_or(1000.0);
+        let (default_vertical_origin, default_vertical_advance) =
+            match cid_dict.get("DW2").map(|dw2| xref.fetch_if_ref(dw2)) {
+                Some(Ok(PDFObject::Array(arr))) if arr.len() >= 2 => (
+                    number(&arr[0]).unwrap_or(880.0),
+                    number(&arr[1]).unwrap_or(-1000.0),
+                ),
+                _ => (880.0, -1000.0),
+            };
+
+        let widths = match cid_dict.get("W").map(|w| xref.fetch_if_ref(w)) {
+            Some(Ok(PDFObject::Array(arr))) => Self::parse_width_array(&arr, 1, xref),
+            _ => FxHashMap::default(),
+        };
+        let vertical_advances = match cid_dict.get("W2").map(|w| xref.fetch_if_ref(w)) {
+            Some(Ok(PDFObject::Array(arr))) => Self::parse_width_array(&arr, 3, xref),
+            _ => FxHashMap::default(),
+        };
+
+        let cid_to_gid = match cid_dict.get("CIDToGIDMap").map(|m| xref.fetch_if_ref(m)) {
+            Some(Ok(PDFObject::Stream {
+                dict: map_dict,
+                data,
+            })) => match decode::decode_stream_data_limited(&map_dict, &data, xref.limits()) {
+                Ok(bytes) => Some(
+                    bytes
+                        .chunks_exact(2)
+                        .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                        .collect(),
+                ),
+                Err(e) => {
+                    tracing::warn!(
+                        target: log_target::FONT,
+                        "Ignoring undecodable CIDToGIDMap of '{}': {:?}",
+                        dict.base_font, e
+                    );
+                    None
+                }
+            },
+            _ => None, // /Identity or absent
+        };
+
+        let info = CIDFontInfo {
+            subtype,
+            encoding,
+            widths,
+            default_width,
+            vertical_advances,
+            default_vertical_advance,
+            default_vertical_origin,
+            cid_to_gid,
+        };
+        Ok((info, cid_dict.get("FontDescriptor").cloned()))
+    }
+
+    /// Resolves the /Encoding of a Type0 font.
+    ///
+    /// Predefined CMaps other than Identity-H/V are not bundled; they become
+    /// [`CIDEncoding::Predefined`], which reads codes with the ToUnicode
+    /// codespace and maps them to CIDs as identity. An embedded CMap that
+    /// fails to parse falls back to Identity-H.
+    fn parse_encoding(
+        encoding: Option<&PDFObject>,
+        xref: &mut crate::core::xref::XRef,
+    ) -> CIDEncoding {
+        match encoding.map(|enc| xref.fetch_if_ref(enc)) {
+            Some(Ok(PDFObject::Name(name))) => {
+                let vertical = name.ends_with("-V");
+                if name == "Identity-H" || name == "Identity-V" {
+                    return CIDEncoding::Identity { vertical };
+                }
+                tracing::warn!(
+                    target: log_target::FONT,
+                    "Predefined CMap '{}' not available, using the ToUnicode codespace and identity CIDs",
+                    name
+                );
+                CIDEncoding::Predefined { name, vertical }
+            }
+            Some(Ok(PDFObject::Stream { dict, data })) => {
+                match decode::decode_stream_data_limited(&dict, &data, xref.limits())
+                    .and_then(|decoded| CMap::parse(&decoded))
+                {
+                    Ok(cmap) => CIDEncoding::Embedded(cmap),
+                    Err(e) => {
+                        tracing::warn!(
+                            target: log_target::FONT,
+                            "Failed to parse embedded CMap, assuming Identity-H: {:?}", e
+                        );
+                        CIDEncoding::Identity { vertical: false }
+                    }
+                }
+            }
+            _ => CIDEncoding::Identity { vertical: false },
+        }
+    }
+
+    /// Parses a /W or /W2 array, keeping the first of every `stride` values.
+    ///
+    /// Entries are either `c [v1 v2 ...]` (consecutive CIDs from c) or
+    /// `cfirst clast v` (one value for the whole range).
+    fn parse_width_array(
+        arr: &[Box<PDFObject>],
+        stride: usize,
+        xref: &mut crate::core::xref::XRef,
+    ) -> FxHashMap<u32, f64> {
+        let mut widths = FxHashMap::default();
+        let number = |obj: &PDFObject| match obj {
+            PDFObject::Number(n) if n.is_finite() => Some(*n),
+            _ => None,
+        };
+
+        let mut i = 0;
+        while i < arr.len() {
+            let Some(first) = number(&arr[i]).filter(|n| *n >= 0.0) else {
+                break;
+            };
+            let first = first as u32;
+            match arr.get(i + 1).map(|next| xref.fetch_if_ref(next)) {
+                Some(Ok(PDFObject::Array(values))) => {
+                    for (j, chunk) in values.chunks(stride).enumerate() {
+                        if let Some(value) = number(&chunk[0]) {
+                            widths.insert(first + j as u32, value);
+                        }
+                    }
+                    i += 2;
+                }
+                Some(Ok(PDFObject::Number(last))) => {
+                    let Some(value) = arr.get(i + 2).and_then(|v| number(v)) else {
+                        break;
+                    };
+                    let last = (last.max(first as f64) as u32).min(first.saturating_add(0xFFFF));
+                    for cid in first..=last {
+                        widths.insert(cid, value);
+                    }
+                    i += 2 + stride;
+                }
+                _ => break,
+            }
+        }
+        widths
+    }
+
+    /// Maps a character code to a CID.
+    pub fn code_to_cid(&self, code: u32) -> u32 {
+        match &self.encoding {
+            CIDEncoding::Identity { .. } | CIDEncoding::Predefined { .. } => code,
+            CIDEncoding::Embedded(cmap) => cmap.lookup_cid(code).unwrap_or(0),
+        }
+    }
+
+    /// Horizontal width of a CID in glyph space units.
+    pub fn width(&self, cid: u32) -> f64 {
+        self.widths.get(&cid).copied().unwrap_or(self.default_width)
+    }
+
+    /// Vertical advance (w1y) of a CID in glyph space units; usually negative.
+    pub fn vertical_advance(&self, cid: u32) -> f64 {
+        self.vertical_advances
+            .get(&cid)
+            .copied()
+            .unwrap_or(self.default_vertical_advance)
+    }
+
+    /// Glyph ID of a CID in the embedded font program.
+    ///
+    /// For CIDFontType2 this applies /CIDToGIDMap; CIDFontType0 (CFF) fonts
+    /// are indexed by CID directly.
+    pub fn glyph_id(&self, cid: u32) -> u16 {
+        match &self.cid_to_gid {
+            Some(map) if self.subtype == FontType::CIDFontType2 => {
+                map.get(cid as usize).copied().unwrap_or(0)
+            }
+            _ => cid.min(u16::MAX as u32) as u16,
+        }
+    }
+}
+
 /// Complete font with encoding, metrics, and glyph access.
 ///
 /// This is the main font object used during text extraction.
@@ -193,6 +569,9 @@ pub struct Font {
 
     /// Embedded font data (CFF or Type1), if available
     pub embedded_font: Option<Vec<u8>>,
+
+    /// Descendant CIDFont of a Type0 font
+    pub cid_font: Option<CIDFontInfo>,
 }
 
 impl Font {
@@ -204,14 +583,31 @@ impl Font {
     pub fn new(font_dict: PDFObject, xref: &mut crate::core::xref::XRef) -> PDFResult<Self> {
         let mut dict = FontDict::from_pdf_object(&font_dict)?;
 
+        // Type0 fonts keep their metrics and descriptor on the descendant
+        let cid_font = if dict.font_type == FontType::Type0 {
+            let (info, descriptor) = CIDFontInfo::from_type0(&dict, xref)?;
+            if dict.font_descriptor.is_none() {
+                dict.font_descriptor = descriptor;
+            }
+            Some(info)
+        } else {
+            None
+        };
+
         if let Some(descriptor_ref) = &dict.font_descriptor {
             if let Some(default_width) = Self::extract_missing_width(descriptor_ref, xref) {
                 dict.default_width = default_width;
             }
+            if let Some((ascent, descent)) = Self::extract_vertical_metrics(descriptor_ref, xref) {
+                dict.ascent = Some(ascent);
+                dict.descent = Some(descent);
+            }
         }
 
-        // Parse encoding from the font dictionary
-        let encoding = if let Some(enc_obj) = &dict.encoding {
+        // Parse encoding from the font dictionary (a CMap for Type0 fonts)
+        let encoding = if cid_font.is_some() {
+            Encoding::Standard
+        } else if let Some(enc_obj) = &dict.encoding {
             Encoding::from_pdf_object(enc_obj).unwrap_or(Encoding::Standard) // Default to StandardEncoding
         } else {
             // No encoding specified - default to StandardEncoding for simple fonts
@@ -221,22 +617,22 @@ impl Font {
         // Parse ToUnicode CMap if present
         let cmap = if let Some(to_unicode_ref) = &dict.to_unicode {
             match xref.fetch_if_ref(to_unicode_ref)? {
-                PDFObject::Stream {
-                    dict: stream_dict,
-                    data,
-                } => {
-                    // Decompress the stream
-                    let filter_name = stream_dict.get("Filter").and_then(|f| match f {
-                        PDFObject::Name(name) => Some(name.as_str()),
-                        _ => None,
-                    });
-
-                    let decompressed = decode::decode_stream(&data, filter_name).map_err(|e| {
-                        PDFError::Generic(format!("ToUnicode stream decode error: {}", e))
-                    })?;
-
-                    // Parse CMap
-                    Some(CMap::parse(&decompressed)?)
+                PDFObject::Stream { .. } => {
+                    // A broken ToUnicode only costs us the mapping, not the font
+                    match xref
+                        .decoded_stream(to_unicode_ref)
+                        .and_then(|decompressed| CMap::parse(&decompressed))
+                    {
+                        Ok(cmap) => Some(cmap),
+                        Err(e) => {
+                            tracing::warn!(
+                                target: log_target::FONT,
+                                "Ignoring unreadable ToUnicode CMap for font '{}': {:?}",
+                                dict.base_font, e
+                            );
+                            None
+                        }
+                    }
                 }
                 _ => None,
             }
@@ -253,6 +649,18 @@ impl Font {
             }
         }
 
+        // Without /Widths, simple standard 14 fonts use their built-in metrics
+        if dict.widths.is_none()
+            && cid_font.is_none()
+            && let Some(standard) = StandardFont::from_name(&dict.base_font)
+        {
+            for code in 0..=u8::MAX {
+                if let Some(width) = standard.code_width(code, &encoding) {
+                    width_cache.insert(code as u16, width as f64);
+                }
+            }
+        }
+
         // Try to extract embedded font data (CFF or Type1)
         let embedded_font = if let Some(descriptor_ref) = &dict.font_descriptor {
             Self::extract_embedded_font(descriptor_ref, xref)?
@@ -266,6 +674,7 @@ impl Font {
             encoding,
             width_cache,
             embedded_font,
+            cid_font,
         })
     }
 
@@ -313,17 +722,14 @@ impl Font {
         let stream_obj = xref.fetch_if_ref(stream_ref)?;
 
         match stream_obj {
-            PDFObject::Stream { dict, data } => {
-                // Decompress the stream
-                let filter_name = dict.get("Filter").and_then(|f| match f {
-                    PDFObject::Name(name) => Some(name.as_str()),
-                    _ => None,
-                });
-
-                let decompressed = decode::decode_stream(&data, filter_name)
+            PDFObject::Stream { .. } => {
+                // Decompress the stream, through the cache since every page
+                // that uses the font loads the same program
+                let decompressed = xref
+                    .decoded_stream(stream_ref)
                     .map_err(|e| PDFError::Generic(format!("Font stream decode error: {}", e)))?;
 
-                Ok(Some(decompressed))
+                Ok(Some(decompressed.to_vec()))
             }
             _ => Ok(None),
         }
@@ -345,6 +751,28 @@ impl Font {
         }
     }
 
+    /// Reads /Ascent and /Descent from the font descriptor.
+    ///
+    /// Both must be present and span a positive height; some writers emit
+    /// zeros for fonts they don't know the metrics of.
+    fn extract_vertical_metrics(
+        descriptor_ref: &PDFObject,
+        xref: &mut crate::core::xref::XRef,
+    ) -> Option<(f64, f64)> {
+        let descriptor = xref.fetch_if_ref(descriptor_ref).ok()?;
+        let PDFObject::Dictionary(dict) = descriptor else {
+            return None;
+        };
+        match (dict.get("Ascent"), dict.get("Descent")) {
+            (Some(PDFObject::Number(ascent)), Some(PDFObject::Number(descent)))
+                if ascent.is_finite() && descent.is_finite() && *ascent - *descent > 0.0 =>
+            {
+                Some((*ascent, *descent))
+            }
+            _ => None,
+        }
+    }
+
     /// Maps a character code (CID) to Unicode using the ToUnicode CMap.
     ///
     /// Falls back to the character code itself if no mapping exists.
@@ -379,6 +807,133 @@ impl Font {
         char::from_u32(code as u32).unwrap_or('\u{FFFD}') // U+FFFD = replacement character
     }
 
+    /// Reads one character code from a string at `offset`.
+    ///
+    /// Simple fonts always use 1-byte codes. Type0 fonts use the codespace of
+    /// their encoding CMap (2 bytes for Identity-H/V, the ToUnicode codespace
+    /// for predefined CMaps that aren't bundled), bare CID fonts 2 bytes.
+    /// For unknown font types the code length comes from the codespace ranges
+    /// of the ToUnicode CMap, falling back to single bytes.
+    ///
+    /// # Returns
+    /// The code and the number of bytes it occupies
+    #[inline]
+    pub fn read_char_code(&self, bytes: &[u8], offset: usize) -> (u32, usize) {
+        match self.font_type() {
+            FontType::Type1 | FontType::Type1C | FontType::TrueType | FontType::Type3 => {}
+            FontType::Type0 => {
+                if let Some(CIDFontInfo {
+                    encoding: CIDEncoding::Embedded(cmap),
+                    ..
+                }) = &self.cid_font
+                    && !cmap.codespace_ranges().is_empty()
+                {
+                    return cmap.read_code(bytes, offset);
+                }
+                if let Some(CIDFontInfo {
+                    encoding: CIDEncoding::Predefined { .. },
+                    ..
+                }) = &self.cid_font
+                    && let Some(cmap) = &self.cmap
+                    && !cmap.codespace_ranges().is_empty()
+                {
+                    return cmap.read_code(bytes, offset);
+                }
+                if let [hi, lo, ..] = bytes[offset..] {
+                    return (u16::from_be_bytes([hi, lo]) as u32, 2);
+                }
+            }
+            FontType::CIDFontType0 | FontType::CIDFontType2 => {
+                if let [hi, lo, ..] = bytes[offset..] {
+                    return (u16::from_be_bytes([hi, lo]) as u32, 2);
+                }
+            }
+            FontType::Unknown => {
+                if let Some(cmap) = &self.cmap
+                    && !cmap.codespace_ranges().is_empty()
+                {
+                    return cmap.read_code(bytes, offset);
+                }
+            }
+        }
+        (bytes[offset] as u32, 1)
+    }
+
+    /// Width of the glyph for a character code, in glyph space units.
+    ///
+    /// Type0 fonts use the /W array of the descendant (via the code's CID),
+    /// simple fonts /Widths.
+    #[inline]
+    pub fn code_width(&self, code: u32) -> f64 {
+        match &self.cid_font {
+            Some(cid_font) => cid_font.width(cid_font.code_to_cid(code)),
+            None => u16::try_from(code)
+                .map(|code| self.get_char_width(code))
+                .unwrap_or(self.dict.default_width),
+        }
+    }
+
+    /// Returns the matrix that maps glyph space to text space.
+    ///
+    /// This is the /FontMatrix of a Type3 font; glyph space is 1/1000 of
+    /// text space for all other fonts.
+    pub fn font_matrix(&self) -> [f64; 6] {
+        self.dict
+            .font_matrix
+            .unwrap_or([0.001, 0.0, 0.0, 0.001, 0.0, 0.0])
+    }
+
+    /// Width of the glyph for a character code in text space units, per
+    /// unit of font size.
+    ///
+    /// This is [`code_width`](Self::code_width) mapped through the
+    /// [`font_matrix`](Self::font_matrix).
+    #[inline]
+    pub fn code_text_width(&self, code: u32) -> f64 {
+        match self.dict.font_matrix {
+            Some([a, ..]) => self.code_width(code) * a,
+            None => self.code_width(code) / 1000.0,
+        }
+    }
+
+    /// Returns true if the font uses vertical writing (e.g. Identity-V).
+    pub fn is_vertical(&self) -> bool {
+        self.cid_font
+            .as_ref()
+            .is_some_and(|cid_font| cid_font.encoding.is_vertical())
+    }
+
+    /// Vertical advance for a character code in glyph space units (negative
+    /// moves down); only meaningful for vertical fonts.
+    pub fn code_vertical_advance(&self, code: u32) -> f64 {
+        match &self.cid_font {
+            Some(cid_font) => cid_font.vertical_advance(cid_font.code_to_cid(code)),
+            None => -1000.0,
+        }
+    }
+
+    /// Glyph ID in the embedded font program for a Type0 character code.
+    pub fn code_to_glyph_id(&self, code: u32) -> Option<u16> {
+        let cid_font = self.cid_font.as_ref()?;
+        Some(cid_font.glyph_id(cid_font.code_to_cid(code)))
+    }
+
+    /// Appends the Unicode text for a character code to `out`.
+    ///
+    /// Like [`char_code_to_unicode`](Self::char_code_to_unicode), but keeps
+    /// the full ToUnicode mapping, which may be several characters (ligatures)
+    /// or a character outside the BMP.
+    #[inline(always)] // Hot path during text extraction
+    pub fn push_unicode(&self, code: u32, out: &mut String) {
+        if let Some(text) = self.cmap.as_ref().and_then(|cmap| cmap.lookup(code)) {
+            out.push_str(text);
+        } else if let Ok(code) = u16::try_from(code) {
+            out.push(self.char_code_to_unicode(code));
+        } else {
+            out.push('\u{FFFD}');
+        }
+    }
+
     /// Gets the width of a character in glyph space units (typically 1/1000 em).
     ///
     /// # Arguments
@@ -419,6 +974,12 @@ impl Font {
         &self.dict.base_font
     }
 
+    /// Returns true if glyph widths are known from the font's /Widths array,
+    /// or for Type0 fonts from the descendant's /W and /DW.
+    pub fn has_widths(&self) -> bool {
+        self.cid_font.is_some() || !self.width_cache.is_empty()
+    }
+
     /// Returns true if this font has a ToUnicode CMap.
     pub fn has_to_unicode(&self) -> bool {
         self.cmap.is_some()
@@ -428,6 +989,59 @@ impl Font {
     pub fn has_embedded_font(&self) -> bool {
         self.embedded_font.is_some()
     }
+
+    /// Returns the decoded embedded font program and its detected format.
+    ///
+    /// Unlike `embedded_font`, which only holds programs the renderer can
+    /// use, this also returns TrueType programs from /FontFile2.
+    ///
+    /// # Arguments
+    /// * `xref` - Cross-reference table for fetching the font file stream
+    ///
+    /// # Returns
+    /// The program bytes and format, or `None` if the font is not embedded
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use pdf_x_core::core::{Font, PDFDocument, PDFObject};
+    /// # fn example(doc: &mut PDFDocument, font_dict: PDFObject) -> Result<(), pdf_x_core::core::PDFError> {
+    /// let font = Font::new(font_dict, doc.xref_mut())?;
+    /// if let Some((data, format)) = font.extract_program(doc.xref_mut())? {
+    ///     std::fs::write(format!("font.{}", format.extension()), data).unwrap();
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn extract_program(
+        &self,
+        xref: &mut crate::core::xref::XRef,
+    ) -> PDFResult<Option<(Vec<u8>, FontProgramFormat)>> {
+        let Some(descriptor_ref) = &self.dict.font_descriptor else {
+            return Ok(None);
+        };
+        let PDFObject::Dictionary(descriptor) = xref.fetch_if_ref(descriptor_ref)? else {
+            return Ok(None);
+        };
+
+        for key in ["FontFile", "FontFile2", "FontFile3"] {
+            let Some(stream_ref) = descriptor.get(key) else {
+                continue;
+            };
+            let subtype = match xref.fetch_if_ref(stream_ref)? {
+                PDFObject::Stream { dict, .. } => match dict.get("Subtype") {
+                    Some(PDFObject::Name(name)) => Some(name.clone()),
+                    _ => None,
+                },
+                _ => continue,
+            };
+            if let Some(data) = Self::extract_font_stream(stream_ref, xref)? {
+                let format = FontProgramFormat::detect(&data, key, subtype.as_deref());
+                return Ok(Some((data, format)));
+            }
+        }
+
+        Ok(None)
+    }
 }
 
 #[cfg(test)]
@@ -451,6 +1065,39 @@ mod tests {
         assert!(!FontType::TrueType.is_cid_font());
     }
 
+    #[test]
+    fn test_font_program_format_detect() {
+        let detect = FontProgramFormat::detect;
+        assert_eq!(
+            detect(b"OTTO\0\x0a", "FontFile3", None),
+            FontProgramFormat::OpenType
+        );
+        assert_eq!(
+            detect(&[0, 1, 0, 0, 0, 0x0c], "FontFile2", None),
+            FontProgramFormat::TrueType
+        );
+        assert_eq!(
+            detect(b"%!PS-AdobeFont-1.0", "FontFile", None),
+            FontProgramFormat::Type1
+        );
+        assert_eq!(
+            detect(&[1, 0, 4, 2], "FontFile3", Some("Type1C")),
+            FontProgramFormat::CFF
+        );
+        // The bytes win over a mislabelled descriptor key
+        assert_eq!(
+            detect(&[0, 1, 0, 0], "FontFile3", Some("Type1C")),
+            FontProgramFormat::TrueType
+        );
+        // Unrecognizable bytes fall back to the key and subtype
+        assert_eq!(detect(b"????", "FontFile", None), FontProgramFormat::Type1);
+        assert_eq!(
+            detect(b"????", "FontFile3", Some("OpenType")),
+            FontProgramFormat::OpenType
+        );
+        assert_eq!(FontProgramFormat::OpenType.extension(), "otf");
+    }
+
     #[test]
     fn test_font_dict_from_simple_font() {
         let mut dict = std::collections::HashMap::new();
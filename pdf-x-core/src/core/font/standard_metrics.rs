@@ -0,0 +1,440 @@
+//! Glyph widths of the 14 standard fonts.
+//!
+//! PDF viewers must know the metrics of the standard 14 fonts, which are
+//! often used without /Widths or an embedded font program. The widths come
+//! from Adobe's AFM files (Core14 AFM set), in glyph space units.
+//!
+//! Helvetica and Times are tabulated for the WinAnsiEncoding character set
+//! and looked up by Unicode character, so they work with any of the simple
+//! encodings. Helvetica-Narrow, one of the PostScript core fonts, is
+//! Helvetica condensed to 82% of its width. Every Courier glyph is 600 units
+//! wide. Symbol and ZapfDingbats use their own built-in encodings and are
+//! looked up by character code.
+//!
+//! Based on PDF.js src/core/metrics.js and src/core/standard_fonts.js.
+
+use crate::core::encoding::Encoding;
+
+/// One of the 14 standard Type 1 fonts, or a style of Helvetica-Narrow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StandardFont {
+    Helvetica,
+    HelveticaBold,
+    HelveticaOblique,
+    HelveticaBoldOblique,
+    HelveticaNarrow,
+    HelveticaNarrowBold,
+    HelveticaNarrowOblique,
+    HelveticaNarrowBoldOblique,
+    TimesRoman,
+    TimesBold,
+    TimesItalic,
+    TimesBoldItalic,
+    Courier,
+    CourierBold,
+    CourierOblique,
+    CourierBoldOblique,
+    Symbol,
+    ZapfDingbats,
+}
+
+impl StandardFont {
+    /// Resolves a /BaseFont name to a standard font.
+    ///
+    /// Accepts the standard names, subset-prefixed names ("ABCDEF+Helvetica"),
+    /// and the usual substitutes written by Windows producers: Arial, Times
+    /// New Roman and Courier New, including their "MT"/"PSMT" and ",Bold"
+    /// style variants. Helvetica-Narrow and Arial Narrow resolve to the
+    /// narrow Helvetica styles.
+    ///
+    /// # Example
+    /// ```
+    /// use pdf_x_core::core::font::StandardFont;
+    ///
+    /// assert_eq!(
+    ///     StandardFont::from_name("Arial,BoldItalic"),
+    ///     Some(StandardFont::HelveticaBoldOblique)
+    /// );
+    /// assert_eq!(StandardFont::from_name("Garamond"), None);
+    /// ```
+    pub fn from_name(base_font: &str) -> Option<Self> {
+        let name = base_font
+            .split_once('+')
+            .map_or(base_font, |(_, name)| name);
+        let (family, style) = match name.find(['-', ',']) {
+            Some(index) => (&name[..index], name[index + 1..].to_ascii_lowercase()),
+            None => (name, String::new()),
+        };
+        let bold = style.contains("bold");
+        let italic = style.contains("italic") || style.contains("oblique");
+        let family = family.trim_end_matches("MT").trim_end_matches("PS");
+        let (family, narrow) = match family.strip_suffix("Narrow") {
+            Some(family) => (family, true),
+            None => (family, style.contains("narrow")),
+        };
+
+        let font = match (family, bold, italic) {
+            ("Helvetica" | "Arial", false, false) if narrow => StandardFont::HelveticaNarrow,
+            ("Helvetica" | "Arial", true, false) if narrow => StandardFont::HelveticaNarrowBold,
+            ("Helvetica" | "Arial", false, true) if narrow => StandardFont::HelveticaNarrowOblique,
+            ("Helvetica" | "Arial", true, true) if narrow => {
+                StandardFont::HelveticaNarrowBoldOblique
+            }
+            ("Symbol", _, _) => StandardFont::Symbol,
+            ("ZapfDingbats", _, _) => StandardFont::ZapfDingbats,
+            ("Helvetica" | "Arial", false, false) => StandardFont::Helvetica,
+            ("Helvetica" | "Arial", true, false) => StandardFont::HelveticaBold,
+            ("Helvetica" | "Arial", false, true) => StandardFont::HelveticaOblique,
+            ("Helvetica" | "Arial", true, true) => StandardFont::HelveticaBoldOblique,
+            ("Times" | "TimesNewRoman", false, false) => StandardFont::TimesRoman,
+            ("Times" | "TimesNewRoman", true, false) => StandardFont::TimesBold,
+            ("Times" | "TimesNewRoman", false, true) => StandardFont::TimesItalic,
+            ("Times" | "TimesNewRoman", true, true) => StandardFont::TimesBoldItalic,
+            ("Courier" | "CourierNew", false, false) => StandardFont::Courier,
+            ("Courier" | "CourierNew", true, false) => StandardFont::CourierBold,
+            ("Courier" | "CourierNew", false, true) => StandardFont::CourierOblique,
+            ("Courier" | "CourierNew", true, true) => StandardFont::CourierBoldOblique,
+            _ => return None,
+        };
+        Some(font)
+    }
+
+    /// Returns true for Symbol and ZapfDingbats, whose widths are indexed by
+    /// code in their built-in encoding rather than by character.
+    pub fn is_symbolic(self) -> bool {
+        matches!(self, StandardFont::Symbol | StandardFont::ZapfDingbats)
+    }
+
+    /// Width of the glyph for a Unicode character, in glyph space units.
+    ///
+    /// Returns `None` for characters outside the tabulated set and for the
+    /// symbolic fonts.
+    pub fn char_width(self, c: char) -> Option<u16> {
+        let index = CHARSET.iter().position(|&entry| entry == c)?;
+        let narrow = |widths: &[u16]| (widths[index] as f64 * NARROW_SCALE).round() as u16;
+        let widths = match self {
+            StandardFont::Helvetica => &HELVETICA,
+            StandardFont::HelveticaBold => &HELVETICA_BOLD,
+            StandardFont::HelveticaOblique => &HELVETICA_OBLIQUE,
+            StandardFont::HelveticaBoldOblique => &HELVETICA_BOLD_OBLIQUE,
+            StandardFont::HelveticaNarrow => return Some(narrow(&HELVETICA)),
+            StandardFont::HelveticaNarrowBold => return Some(narrow(&HELVETICA_BOLD)),
+            StandardFont::HelveticaNarrowOblique => return Some(narrow(&HELVETICA_OBLIQUE)),
+            StandardFont::HelveticaNarrowBoldOblique => {
+                return Some(narrow(&HELVETICA_BOLD_OBLIQUE));
+            }
+            StandardFont::TimesRoman => &TIMES_ROMAN,
+            StandardFont::TimesBold => &TIMES_BOLD,
+            StandardFont::TimesItalic => &TIMES_ITALIC,
+            StandardFont::TimesBoldItalic => &TIMES_BOLD_ITALIC,
+            StandardFont::Courier
+            | StandardFont::CourierBold
+            | StandardFont::CourierOblique
+            | StandardFont::CourierBoldOblique => return Some(COURIER_WIDTH),
+            StandardFont::Symbol | StandardFont::ZapfDingbats => return None,
+        };
+        Some(widths[index])
+    }
+
+    /// Width of the glyph for a character code, in glyph space units.
+    ///
+    /// Text fonts map the code to a character through `encoding`; Symbol
+    /// and ZapfDingbats use their built-in encoding.
+    ///
+    /// # Arguments
+    /// * `code` - Single-byte character code
+    /// * `encoding` - The font's encoding (ignored for the symbolic fonts)
+    pub fn code_width(self, code: u8, encoding: &Encoding) -> Option<u16> {
+        let builtin = match self {
+            StandardFont::Symbol => &SYMBOL,
+            StandardFont::ZapfDingbats => &ZAPF_DINGBATS,
+            _ => return self.char_width(encoding.char_to_unicode(code)),
+        };
+        let index = code.checked_sub(FIRST_SYMBOLIC_CODE)? as usize;
+        builtin.get(index).copied().filter(|&width| width != 0)
+    }
+}
+
+/// Width of every glyph of the Courier fonts.
+const COURIER_WIDTH: u16 = 600;
+
+/// Width of Helvetica-Narrow glyphs relative to Helvetica.
+const NARROW_SCALE: f64 = 0.82;
+
+/// First code of the Symbol and ZapfDingbats tables.
+const FIRST_SYMBOLIC_CODE: u8 = 32;
+
+/// Characters with a width in the tables below: the WinAnsiEncoding
+/// character set, in code order.
+const CHARSET: [char; 218] = [
+    ' ', '!', '"', '#', '$', '%', '&', '\'', '(', ')', '*', '+', ',', '-', '.', '/', '0', '1', '2',
+    '3', '4', '5', '6', '7', '8', '9', ':', ';', '<', '=', '>', '?', '@', 'A', 'B', 'C', 'D', 'E',
+    'F', 'G', 'H', 'I', 'J', 'K', 'L', 'M', 'N', 'O', 'P', 'Q', 'R', 'S', 'T', 'U', 'V', 'W', 'X',
+    'Y', 'Z', '[', '\\', ']', '^', '_', '`', 'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j', 'k',
+    'l', 'm', 'n', 'o', 'p', 'q', 'r', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z', '{', '|', '}', '~',
+    '\u{20AC}', '\u{201A}', '\u{0192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}', '\u{02C6}',
+    '\u{2030}', '\u{0160}', '\u{2039}', '\u{0152}', '\u{017D}', '\u{2018}', '\u{2019}', '\u{201C}',
+    '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}', '\u{02DC}', '\u{2122}', '\u{0161}', '\u{203A}',
+    '\u{0153}', '\u{017E}', '\u{0178}', '\u{00A0}', '\u{00A1}', '\u{00A2}', '\u{00A3}', '\u{00A4}',
+    '\u{00A5}', '\u{00A6}', '\u{00A7}', '\u{00A8}', '\u{00A9}', '\u{00AA}', '\u{00AB}', '\u{00AC}',
+    '\u{00AD}', '\u{00AE}', '\u{00AF}', '\u{00B0}', '\u{00B1}', '\u{00B2}', '\u{00B3}', '\u{00B4}',
+    '\u{00B5}', '\u{00B6}', '\u{00B7}', '\u{00B8}', '\u{00B9}', '\u{00BA}', '\u{00BB}', '\u{00BC}',
+    '\u{00BD}', '\u{00BE}', '\u{00BF}', '\u{00C0}', '\u{00C1}', '\u{00C2}', '\u{00C3}', '\u{00C4}',
+    '\u{00C5}', '\u{00C6}', '\u{00C7}', '\u{00C8}', '\u{00C9}', '\u{00CA}', '\u{00CB}', '\u{00CC}',
+    '\u{00CD}', '\u{00CE}', '\u{00CF}', '\u{00D0}', '\u{00D1}', '\u{00D2}', '\u{00D3}', '\u{00D4}',
+    '\u{00D5}', '\u{00D6}', '\u{00D7}', '\u{00D8}', '\u{00D9}', '\u{00DA}', '\u{00DB}', '\u{00DC}',
+    '\u{00DD}', '\u{00DE}', '\u{00DF}', '\u{00E0}', '\u{00E1}', '\u{00E2}', '\u{00E3}', '\u{00E4}',
+    '\u{00E5}', '\u{00E6}', '\u{00E7}', '\u{00E8}', '\u{00E9}', '\u{00EA}', '\u{00EB}', '\u{00EC}',
+    '\u{00ED}', '\u{00EE}', '\u{00EF}', '\u{00F0}', '\u{00F1}', '\u{00F2}', '\u{00F3}', '\u{00F4}',
+    '\u{00F5}', '\u{00F6}', '\u{00F7}', '\u{00F8}', '\u{00F9}', '\u{00FA}', '\u{00FB}', '\u{00FC}',
+    '\u{00FD}', '\u{00FE}', '\u{00FF}',
+];
+
+/// Helvetica widths, in [`CHARSET`] order.
+const HELVETICA: [u16; 218] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556,
+    556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556, 1015, 667, 667, 722, 722, 667,
+    611, 778, 722, 278, 500, 667, 556, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667,
+    667, 611, 278, 278, 278, 469, 556, 333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500,
+    222, 833, 556, 556, 556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+    556, 222, 556, 333, 1000, 556, 556, 333, 1000, 667, 333, 1000, 611, 222, 222, 333, 333, 350,
+    556, 1000, 333, 1000, 500, 333, 944, 500, 667, 278, 333, 556, 556, 556, 556, 260, 556, 333,
+    737, 370, 556, 584, 333, 737, 552, 400, 549, 333, 333, 333, 576, 537, 278, 333, 333, 365, 556,
+    834, 834, 834, 611, 667, 667, 667, 667, 667, 667, 1000, 722, 667, 667, 667, 667, 278, 278, 278,
+    278, 722, 722, 778, 778, 778, 778, 778, 584, 778, 722, 722, 722, 722, 667, 667, 611, 556, 556,
+    556, 556, 556, 556, 889, 500, 556, 556, 556, 556, 278, 278, 278, 278, 556, 556, 556, 556, 556,
+    556, 556, 549, 611, 556, 556, 556, 556, 500, 556, 500,
+];
+
+/// Helvetica-Bold widths, in [`CHARSET`] order.
+const HELVETICA_BOLD: [u16; 218] = [
+    278, 333, 474, 556, 556, 889, 722, 238, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556,
+    556, 556, 556, 556, 556, 556, 556, 333, 333, 584, 584, 584, 611, 975, 722, 722, 722, 722, 667,
+    611, 778, 722, 278, 556, 722, 611, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667,
+    667, 611, 333, 278, 333, 584, 556, 333, 556, 611, 556, 611, 556, 333, 611, 611, 278, 278, 556,
+    278, 889, 611, 611, 611, 611, 389, 556, 333, 611, 556, 778, 556, 556, 500, 389, 280, 389, 584,
+    556, 278, 556, 500, 1000, 556, 556, 333, 1000, 667, 333, 1000, 611, 278, 278, 500, 500, 350,
+    556, 1000, 333, 1000, 556, 333, 944, 500, 667, 278, 333, 556, 556, 556, 556, 280, 556, 333,
+    737, 370, 556, 584, 333, 737, 552, 400, 549, 333, 333, 333, 576, 556, 278, 333, 333, 365, 556,
+    834, 834, 834, 611, 722, 722, 722, 722, 722, 722, 1000, 722, 667, 667, 667, 667, 278, 278, 278,
+    278, 722, 722, 778, 778, 778, 778, 778, 584, 778, 722, 722, 722, 722, 667, 667, 611, 556, 556,
+    556, 556, 556, 556, 889, 556, 556, 556, 556, 556, 278, 278, 278, 278, 611, 611, 611, 611, 611,
+    611, 611, 549, 611, 611, 611, 611, 611, 556, 611, 556,
+];
+
+/// Helvetica-Oblique widths, in [`CHARSET`] order.
+const HELVETICA_OBLIQUE: [u16; 218] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556,
+    556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556, 1015, 667, 667, 722, 722, 667,
+    611, 778, 722, 278, 500, 667, 556, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667,
+    667, 611, 278, 278, 278, 469, 556, 333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500,
+    222, 833, 556, 556, 556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+    556, 222, 556, 333, 1000, 556, 556, 333, 1000, 667, 333, 1000, 611, 222, 222, 333, 333, 350,
+    556, 1000, 333, 1000, 500, 333, 944, 500, 667, 278, 333, 556, 556, 556, 556, 260, 556, 333,
+    737, 370, 556, 584, 333, 737, 552, 400, 549, 333, 333, 333, 576, 537, 278, 333, 333, 365, 556,
+    834, 834, 834, 611, 667, 667, 667, 667, 667, 667, 1000, 722, 667, 667, 667, 667, 278, 278, 278,
+    278, 722, 722, 778, 778, 778, 778, 778, 584, 778, 722, 722, 722, 722, 667, 667, 611, 556, 556,
+    556, 556, 556, 556, 889, 500, 556, 556, 556, 556, 278, 278, 278, 278, 556, 556, 556, 556, 556,
+    556, 556, 549, 611, 556, 556, 556, 556, 500, 556, 500,
+];
+
+/// Helvetica-BoldOblique widths, in [`CHARSET`] order.
+const HELVETICA_BOLD_OBLIQUE: [u16; 218] = [
+    278, 333, 474, 556, 556, 889, 722, 238, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556,
+    556, 556, 556, 556, 556, 556, 556, 333, 333, 584, 584, 584, 611, 975, 722, 722, 722, 722, 667,
+    611, 778, 722, 278, 556, 722, 611, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667,
+    667, 611, 333, 278, 333, 584, 556, 333, 556, 611, 556, 611, 556, 333, 611, 611, 278, 278, 556,
+    278, 889, 611, 611, 611, 611, 389, 556, 333, 611, 556, 778, 556, 556, 500, 389, 280, 389, 584,
+    556, 278, 556, 500, 1000, 556, 556, 333, 1000, 667, 333, 1000, 611, 278, 278, 500, 500, 350,
+    556, 1000, 333, 1000, 556, 333, 944, 500, 667, 278, 333, 556, 556, 556, 556, 280, 556, 333,
+    737, 370, 556, 584, 333, 737, 552, 400, 549, 333, 333, 333, 576, 556, 278, 333, 333, 365, 556,
+    834, 834, 834, 611, 722, 722, 722, 722, 722, 722, 1000, 722, 667, 667, 667, 667, 278, 278, 278,
+    278, 722, 722, 778, 778, 778, 778, 778, 584, 778, 722, 722, 722, 722, 667, 667, 611, 556, 556,
+    556, 556, 556, 556, 889, 556, 556, 556, 556, 556, 278, 278, 278, 278, 611, 611, 611, 611, 611,
+    611, 611, 549, 611, 611, 611, 611, 611, 556, 611, 556,
+];
+
+/// Times-Roman widths, in [`CHARSET`] order.
+const TIMES_ROMAN: [u16; 218] = [
+    250, 333, 408, 500, 500, 833, 778, 180, 333, 333, 500, 564, 250, 333, 250, 278, 500, 500, 500,
+    500, 500, 500, 500, 500, 500, 500, 278, 278, 564, 564, 564, 444, 921, 722, 667, 667, 722, 611,
+    556, 722, 722, 333, 389, 722, 611, 889, 722, 722, 556, 722, 667, 556, 611, 722, 722, 944, 722,
+    722, 611, 333, 278, 333, 469, 500, 333, 444, 500, 444, 500, 444, 333, 500, 500, 278, 278, 500,
+    278, 778, 500, 500, 500, 500, 333, 389, 278, 500, 500, 722, 500, 500, 444, 480, 200, 480, 541,
+    500, 333, 500, 444, 1000, 500, 500, 333, 1000, 556, 333, 889, 611, 333, 333, 444, 444, 350,
+    500, 1000, 333, 980, 389, 333, 722, 444, 722, 250, 333, 500, 500, 500, 500, 200, 500, 333, 760,
+    276, 500, 564, 333, 760, 333, 400, 564, 300, 300, 333, 500, 453, 250, 333, 300, 310, 500, 750,
+    750, 750, 444, 722, 722, 722, 722, 722, 722, 889, 667, 611, 611, 611, 611, 333, 333, 333, 333,
+    722, 722, 722, 722, 722, 722, 722, 564, 722, 722, 722, 722, 722, 722, 556, 500, 444, 444, 444,
+    444, 444, 444, 667, 444, 444, 444, 444, 444, 278, 278, 278, 278, 500, 500, 500, 500, 500, 500,
+    500, 564, 500, 500, 500, 500, 500, 500, 500, 500,
+];
+
+/// Times-Bold widths, in [`CHARSET`] order.
+const TIMES_BOLD: [u16; 218] = [
+    250, 333, 555, 500, 500, 1000, 833, 278, 333, 333, 500, 570, 250, 333, 250, 278, 500, 500, 500,
+    500, 500, 500, 500, 500, 500, 500, 333, 333, 570, 570, 570, 500, 930, 722, 667, 722, 722, 667,
+    611, 778, 778, 389, 500, 778, 667, 944, 722, 778, 611, 778, 722, 556, 667, 722, 722, 1000, 722,
+    722, 667, 333, 278, 333, 581, 500, 333, 500, 556, 444, 556, 444, 333, 500, 556, 278, 333, 556,
+    278, 833, 556, 500, 556, 556, 444, 389, 333, 556, 500, 722, 500, 500, 444, 394, 220, 394, 520,
+    500, 333, 500, 500, 1000, 500, 500, 333, 1000, 556, 333, 1000, 667, 333, 333, 500, 500, 350,
+    500, 1000, 333, 1000, 389, 333, 722, 444, 722, 250, 333, 500, 500, 500, 500, 220, 500, 333,
+    747, 300, 500, 570, 333, 747, 333, 400, 570, 300, 300, 333, 556, 540, 250, 333, 300, 330, 500,
+    750, 750, 750, 500, 722, 722, 722, 722, 722, 722, 1000, 722, 667, 667, 667, 667, 389, 389, 389,
+    389, 722, 722, 778, 778, 778, 778, 778, 570, 778, 722, 722, 722, 722, 722, 611, 556, 500, 500,
+    500, 500, 500, 500, 722, 444, 444, 444, 444, 444, 278, 278, 278, 278, 500, 556, 500, 500, 500,
+    500, 500, 570, 500, 556, 556, 556, 556, 500, 556, 500,
+];
+
+/// Times-Italic widths, in [`CHARSET`] order.
+const TIMES_ITALIC: [u16; 218] = [
+    250, 333, 420, 500, 500, 833, 778, 214, 333, 333, 500, 675, 250, 333, 250, 278, 500, 500, 500,
+    500, 500, 500, 500, 500, 500, 500, 333, 333, 675, 675, 675, 500, 920, 611, 611, 667, 722, 611,
+    611, 722, 722, 333, 444, 667, 556, 833, 667, 722, 611, 722, 611, 500, 556, 722, 611, 833, 611,
+    556, 556, 389, 278, 389, 422, 500, 333, 500, 500, 444, 500, 444, 278, 500, 500, 278, 278, 444,
+    278, 722, 500, 500, 500, 500, 389, 389, 278, 500, 444, 667, 444, 444, 389, 400, 275, 400, 541,
+    500, 333, 500, 556, 889, 500, 500, 333, 1000, 500, 333, 944, 556, 333, 333, 556, 556, 350, 500,
+    889, 333, 980, 389, 333, 667, 389, 556, 250, 389, 500, 500, 500, 500, 275, 500, 333, 760, 276,
+    500, 675, 333, 760, 333, 400, 675, 300, 300, 333, 500, 523, 250, 333, 300, 310, 500, 750, 750,
+    750, 500, 611, 611, 611, 611, 611, 611, 889, 667, 611, 611, 611, 611, 333, 333, 333, 333, 722,
+    667, 722, 722, 722, 722, 722, 675, 722, 722, 722, 722, 722, 556, 611, 500, 500, 500, 500, 500,
+    500, 500, 667, 444, 444, 444, 444, 444, 278, 278, 278, 278, 500, 500, 500, 500, 500, 500, 500,
+    675, 500, 500, 500, 500, 500, 444, 500, 444,
+];
+
+/// Times-BoldItalic widths, in [`CHARSET`] order.
+const TIMES_BOLD_ITALIC: [u16; 218] = [
+    250, 389, 555, 500, 500, 833, 778, 278, 333, 333, 500, 570, 250, 333, 250, 278, 500, 500, 500,
+    500, 500, 500, 500, 500, 500, 500, 333, 333, 570, 570, 570, 500, 832, 667, 667, 667, 722, 667,
+    667, 722, 778, 389, 500, 667, 611, 889, 722, 722, 611, 722, 667, 556, 611, 722, 667, 889, 667,
+    611, 611, 333, 278, 333, 570, 500, 333, 500, 500, 444, 500, 444, 333, 500, 556, 278, 278, 500,
+    278, 778, 556, 500, 500, 500, 389, 389, 278, 556, 444, 667, 500, 444, 389, 348, 220, 348, 570,
+    500, 333, 500, 500, 1000, 500, 500, 333, 1000, 556, 333, 944, 611, 333, 333, 500, 500, 350,
+    500, 1000, 333, 1000, 389, 333, 722, 389, 611, 250, 389, 500, 500, 500, 500, 220, 500, 333,
+    747, 266, 500, 606, 333, 747, 333, 400, 570, 300, 300, 333, 576, 500, 250, 333, 300, 300, 500,
+    750, 750, 750, 500, 667, 667, 667, 667, 667, 667, 944, 667, 667, 667, 667, 667, 389, 389, 389,
+    389, 722, 722, 722, 722, 722, 722, 722, 570, 722, 722, 722, 722, 722, 611, 611, 500, 500, 500,
+    500, 500, 500, 500, 722, 444, 444, 444, 444, 444, 278, 278, 278, 278, 500, 556, 500, 500, 500,
+    500, 500, 570, 500, 556, 556, 556, 556, 444, 500, 444,
+];
+
+/// Symbol widths for codes 32-254 of its built-in encoding; 0 marks an
+/// unused code.
+const SYMBOL: [u16; 223] = [
+    250, 333, 713, 500, 549, 833, 778, 439, 333, 333, 500, 549, 250, 549, 250, 278, 500, 500, 500,
+    500, 500, 500, 500, 500, 500, 500, 278, 278, 549, 549, 549, 444, 549, 722, 667, 722, 612, 611,
+    763, 603, 722, 333, 631, 722, 686, 889, 722, 722, 768, 741, 556, 592, 611, 690, 439, 768, 645,
+    795, 611, 333, 863, 333, 658, 500, 500, 631, 549, 549, 494, 439, 521, 411, 603, 329, 603, 549,
+    549, 576, 521, 549, 549, 521, 549, 603, 439, 576, 713, 686, 493, 686, 494, 480, 200, 480, 549,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 750, 620, 247, 549, 167, 713, 500, 753, 753, 753, 753, 1042, 987, 603, 987, 603, 400, 549,
+    411, 549, 549, 713, 494, 460, 549, 549, 549, 549, 1000, 603, 1000, 658, 823, 686, 795, 987,
+    768, 768, 823, 768, 768, 713, 713, 713, 713, 713, 713, 713, 768, 713, 790, 790, 890, 823, 549,
+    250, 713, 603, 603, 1042, 987, 603, 987, 603, 494, 329, 790, 790, 786, 713, 384, 384, 384, 384,
+    384, 384, 494, 494, 494, 494, 0, 329, 274, 686, 686, 686, 384, 384, 384, 384, 384, 384, 494,
+    494, 494,
+];
+
+/// ZapfDingbats widths for codes 32-254 of its built-in encoding; 0 marks
+/// an unused code.
+const ZAPF_DINGBATS: [u16; 223] = [
+    278, 974, 961, 974, 980, 719, 789, 790, 791, 690, 960, 939, 549, 855, 911, 933, 911, 945, 974,
+    755, 846, 762, 761, 571, 677, 763, 760, 759, 754, 494, 552, 537, 577, 692, 786, 788, 788, 790,
+    793, 794, 816, 823, 789, 841, 823, 833, 816, 831, 923, 744, 723, 749, 790, 792, 695, 776, 768,
+    792, 759, 707, 708, 682, 701, 826, 815, 789, 789, 707, 687, 696, 689, 786, 787, 713, 791, 785,
+    791, 873, 761, 762, 762, 759, 759, 892, 892, 788, 784, 438, 138, 277, 415, 392, 392, 668, 668,
+    0, 390, 390, 317, 317, 276, 276, 509, 509, 410, 410, 234, 234, 334, 334, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 732, 544, 544, 910, 667, 760, 760, 776, 595, 694, 626, 788,
+    788, 788, 788, 788, 788, 788, 788, 788, 788, 788, 788, 788, 788, 788, 788, 788, 788, 788, 788,
+    788, 788, 788, 788, 788, 788, 788, 788, 788, 788, 788, 788, 788, 788, 788, 788, 788, 788, 788,
+    788, 894, 838, 1016, 458, 748, 924, 748, 918, 927, 928, 928, 834, 873, 828, 924, 924, 917, 930,
+    931, 463, 883, 836, 836, 867, 867, 696, 696, 874, 0, 874, 760, 946, 771, 865, 771, 888, 967,
+    888, 831, 873, 927, 970, 918,
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_standard_font_names() {
+        assert_eq!(
+            StandardFont::from_name("Times-Roman"),
+            Some(StandardFont::TimesRoman)
+        );
+        assert_eq!(
+            StandardFont::from_name("ABCDEF+Helvetica-Oblique"),
+            Some(StandardFont::HelveticaOblique)
+        );
+        assert_eq!(
+            StandardFont::from_name("TimesNewRomanPS-BoldMT"),
+            Some(StandardFont::TimesBold)
+        );
+        assert_eq!(
+            StandardFont::from_name("CourierNewPSMT"),
+            Some(StandardFont::Courier)
+        );
+        assert_eq!(
+            StandardFont::from_name("Symbol"),
+            Some(StandardFont::Symbol)
+        );
+        assert_eq!(
+            StandardFont::from_name("Helvetica-Narrow-Bold"),
+            Some(StandardFont::HelveticaNarrowBold)
+        );
+        assert_eq!(
+            StandardFont::from_name("ArialNarrow,Italic"),
+            Some(StandardFont::HelveticaNarrowOblique)
+        );
+        assert_eq!(StandardFont::from_name("Verdana"), None);
+    }
+
+    #[test]
+    fn test_standard_font_widths() {
+        let win_ansi = Encoding::WinAnsi;
+        assert_eq!(
+            StandardFont::Helvetica.code_width(b'W', &win_ansi),
+            Some(944)
+        );
+        assert_eq!(
+            StandardFont::Helvetica.code_width(b'i', &win_ansi),
+            Some(222)
+        );
+        assert_eq!(
+            StandardFont::HelveticaBold.code_width(b'i', &win_ansi),
+            Some(278)
+        );
+        assert_eq!(
+            StandardFont::TimesRoman.code_width(b'a', &win_ansi),
+            Some(444)
+        );
+        assert_eq!(StandardFont::Courier.code_width(b'i', &win_ansi), Some(600));
+
+        // Accented letters and WinAnsi punctuation
+        assert_eq!(StandardFont::TimesRoman.char_width('\u{00E9}'), Some(444));
+        assert_eq!(
+            StandardFont::Helvetica.code_width(0x85, &win_ansi),
+            Some(1000)
+        );
+
+        // Symbolic fonts use their built-in encoding
+        assert_eq!(StandardFont::Symbol.code_width(b'a', &win_ansi), Some(631));
+        assert_eq!(
+            StandardFont::ZapfDingbats.code_width(b'!', &win_ansi),
+            Some(974)
+        );
+        assert_eq!(StandardFont::Symbol.code_width(b'\n', &win_ansi), None);
+        assert_eq!(StandardFont::Symbol.code_width(0xE5, &win_ansi), Some(713));
+        assert_eq!(StandardFont::Symbol.code_width(0xF0, &win_ansi), None);
+        assert_eq!(
+            StandardFont::ZapfDingbats.code_width(0xAC, &win_ansi),
+            Some(788)
+        );
+
+        // Helvetica-Narrow is Helvetica at 82% width
+        assert_eq!(
+            StandardFont::HelveticaNarrow.code_width(b'W', &win_ansi),
+            Some(774)
+        );
+    }
+}
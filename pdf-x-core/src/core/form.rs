@@ -0,0 +1,973 @@
+//! Interactive form (AcroForm) fields and field filling.
+//!
+//! Fields are collected from the catalog's /AcroForm /Fields tree. Setting a
+//! field value updates /V and regenerates the appearance streams of the
+//! field's widgets; most viewers draw the stored appearance rather than the
+//! value, so without this they would keep showing the old value.
+//!
+//! Based on PDF.js src/core/annotation.js (WidgetAnnotation._getAppearance).
+
+use crate::core::annotation::FormFieldType;
+use crate::core::cycle_guard::CycleGuard;
+use crate::core::delta::{BaseObjectFetcher, Command, DeltaLayer};
+use crate::core::error::{PDFError, PDFResult};
+use crate::core::parser::{PDFObject, Ref};
+use crate::core::xref::XRef;
+use std::collections::HashMap;
+
+/// Field flag: the text field may contain multiple lines (bit 13).
+const FLAG_MULTILINE: u32 = 1 << 12;
+
+/// Field flag: the text field is a password field (bit 14).
+const FLAG_PASSWORD: u32 = 1 << 13;
+
+/// Field flag: the button is a radio button (bit 16).
+const FLAG_RADIO: u32 = 1 << 15;
+
+/// Field flag: the button is a push button (bit 17).
+const FLAG_PUSHBUTTON: u32 = 1 << 16;
+
+/// Default appearance used when neither the field nor the form has a /DA.
+const DEFAULT_APPEARANCE: &str = "/Helv 0 Tf 0 g";
+
+/// Font size used for auto-sized (size 0) multiline text.
+const DEFAULT_FONT_SIZE: f64 = 12.0;
+
+/// Space between the widget border and the text.
+const PADDING: f64 = 2.0;
+
+/// Line height as a multiple of the font size.
+const LINE_FACTOR: f64 = 1.35;
+
+/// Portion of the font size below the baseline.
+const LINE_DESCENT_FACTOR: f64 = 0.35;
+
+/// Average glyph width as a fraction of the font size, used to lay out text.
+const AVERAGE_GLYPH_WIDTH: f64 = 0.5;
+
+/// A terminal form field.
+#[derive(Debug, Clone)]
+pub struct FormField {
+    /// Fully qualified field name (e.g. "address.city")
+    pub name: String,
+
+    /// Reference to the field dictionary
+    pub field_ref: Ref,
+
+    /// The field type (/FT, possibly inherited)
+    pub field_type: FormFieldType,
+
+    /// The field flags (/Ff, possibly inherited)
+    pub flags: u32,
+
+    /// The current value (/V, possibly inherited)
+    pub value: Option<String>,
+
+    /// The default appearance string (/DA, inherited from the form if absent)
+    pub default_appearance: Option<String>,
+
+    /// Text alignment: 0 = left, 1 = centered, 2 = right (/Q)
+    pub quadding: u8,
+
+    /// References to the field's widget annotations
+    pub widgets: Vec<Ref>,
+}
+
+/// Attributes inherited down the field hierarchy.
+#[derive(Clone, Default)]
+struct Inherited {
+    field_type: Option<String>,
+    flags: u32,
+    value: Option<PDFObject>,
+    default_appearance: Option<String>,
+    quadding: u8,
+}
+
+/// Collects all terminal fields of the document's interactive form.
+///
+/// # Arguments
+/// * `xref` - The cross-reference table used to resolve the field tree
+///
+/// # Returns
+/// The fields in tree order, or an empty list if the document has no form
+pub fn collect_form_fields(xref: &mut XRef) -> PDFResult<Vec<FormField>> {
+    let acro_form = match acro_form_dict(xref)? {
+        Some(dict) => dict,
+        None => return Ok(Vec::new()),
+    };
+
+    let inherited = Inherited {
+        default_appearance: acro_form.get("DA").and_then(string_value),
+        quadding: match acro_form.get("Q") {
+            Some(PDFObject::Number(n)) => *n as u8,
+            _ => 0,
+        },
+        ..Inherited::default()
+    };
+
+    let roots = match acro_form.get("Fields") {
+        Some(obj) => xref.fetch_if_ref(obj)?,
+        None => return Ok(Vec::new()),
+    };
+
+    let mut fields = Vec::new();
+    let mut visited = CycleGuard::new();
+    if let PDFObject::Array(roots) = roots {
+        for root in roots.iter() {
+            if let PDFObject::Ref(field_ref) = &**root {
+                collect_field(
+                    xref,
+                    *field_ref,
+                    "",
+                    &inherited,
+                    &mut visited,
+                    0,
+                    &mut fields,
+                )?;
+            }
+        }
+    }
+
+    Ok(fields)
+}
+
+/// Returns the /DR (default resources) dictionary of the document's form.
+pub fn form_resources(xref: &mut XRef) -> PDFResult<Option<HashMap<String, PDFObject>>> {
+    let acro_form = match acro_form_dict(xref)? {
+        Some(dict) => dict,
+        None => return Ok(None),
+    };
+
+    match acro_form.get("DR") {
+        Some(obj) => match xref.fetch_if_ref(obj)? {
+            PDFObject::Dictionary(dict) => Ok(Some(dict)),
+            _ => Ok(None),
+        },
+        None => Ok(None),
+    }
+}
+
+/// Fetches the catalog's /AcroForm dictionary.
+fn acro_form_dict(xref: &mut XRef) -> PDFResult<Option<HashMap<String, PDFObject>>> {
+    let catalog = xref.catalog()?;
+    let acro_form = match &catalog {
+        PDFObject::Dictionary(dict) => match dict.get("AcroForm") {
+            Some(obj) => xref.fetch_if_ref(obj)?,
+            None => return Ok(None),
+        },
+        _ => return Ok(None),
+    };
+
+    match acro_form {
+        PDFObject::Dictionary(dict) => Ok(Some(dict)),
+        _ => Ok(None),
+    }
+}
+
+/// Visits one node of the field tree.
+fn collect_field(
+    xref: &mut XRef,
+    field_ref: Ref,
+    parent_name: &str,
+    parent: &Inherited,
+    visited: &mut CycleGuard,
+    depth: usize,
+    fields: &mut Vec<FormField>,
+) -> PDFResult<()> {
+    let max_depth = xref.limits().max_object_depth;
+    if depth >= max_depth {
+        return Err(PDFError::limit_exceeded(
+            "form field hierarchy depth",
+            max_depth,
+        ));
+    }
+    if !visited.visit(field_ref) {
+        return Ok(());
+    }
+
+    let dict = match &*xref.fetch(field_ref.num, field_ref.generation)? {
+        PDFObject::Dictionary(dict) => dict.clone(),
+        _ => return Ok(()),
+    };
+
+    let mut inherited = parent.clone();
+    if let Some(PDFObject::Name(name)) = dict.get("FT") {
+        inherited.field_type = Some(name.clone());
+    }
+    if let Some(PDFObject::Number(n)) = dict.get("Ff") {
+        inherited.flags = *n as u32;
+    }
+    if let Some(value) = dict.get("V") {
+        inherited.value = Some(xref.fetch_if_ref(value)?);
+    }
+    if let Some(da) = dict.get("DA").and_then(string_value) {
+        inherited.default_appearance = Some(da);
+    }
+    if let Some(PDFObject::Number(n)) = dict.get("Q") {
+        inherited.quadding = *n as u8;
+    }
+
+    let partial_name = dict.get("T").and_then(string_value);
+    let name = match (&partial_name, parent_name.is_empty()) {
+        (Some(partial), true) => partial.clone(),
+        (Some(partial), false) => format!("{}.{}", parent_name, partial),
+        (None, _) => parent_name.to_string(),
+    };
+
+    // Kids are either child fields (with /T) or the field's widgets
+    let mut child_fields = Vec::new();
+    let mut widgets = Vec::new();
+    if let Some(kids) = dict.get("Kids")
+        && let PDFObject::Array(kids) = xref.fetch_if_ref(kids)?
+    {
+        for kid in kids.iter() {
+            let kid_ref = match &**kid {
+                PDFObject::Ref(r) => *r,
+                _ => continue,
+            };
+            let is_field = match &*xref.fetch(kid_ref.num, kid_ref.generation)? {
+                PDFObject::Dictionary(kid_dict) => kid_dict.contains_key("T"),
+                _ => continue,
+            };
+            if is_field {
+                child_fields.push(kid_ref);
+            } else {
+                widgets.push(kid_ref);
+            }
+        }
+    }
+
+    if !child_fields.is_empty() {
+        for child in child_fields {
+            collect_field(xref, child, &name, &inherited, visited, depth + 1, fields)?;
+        }
+        return Ok(());
+    }
+
+    // A field without widget kids is merged with its only widget
+    if widgets.is_empty() {
+        widgets.push(field_ref);
+    }
+
+    let field_type = match inherited.field_type.as_deref() {
+        Some("Btn") => FormFieldType::Button,
+        Some("Tx") => FormFieldType::Text,
+        Some("Ch") => FormFieldType::Choice,
+        Some("Sig") => FormFieldType::Signature,
+        Some(other) => FormFieldType::Unknown(other.to_string()),
+        None => FormFieldType::Unknown(String::new()),
+    };
+
+    let value = match &inherited.value {
+        Some(PDFObject::Name(name)) => Some(name.clone()),
+        Some(obj) => string_value(obj),
+        None => None,
+    };
+
+    fields.push(FormField {
+        name,
+        field_ref,
+        field_type,
+        flags: inherited.flags,
+        value,
+        default_appearance: inherited.default_appearance,
+        quadding: inherited.quadding,
+        widgets,
+    });
+
+    Ok(())
+}
+
+/// Decodes a string object to text.
+fn string_value(obj: &PDFObject) -> Option<String> {
+    match obj {
+        PDFObject::String(bytes) | PDFObject::HexString(bytes) => {
+            if bytes.starts_with(&[0xFE, 0xFF]) {
+                let units: Vec<u16> = bytes[2..]
+                    .chunks_exact(2)
+                    .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                    .collect();
+                Some(String::from_utf16_lossy(&units))
+            } else {
+                Some(bytes.iter().map(|&b| b as char).collect())
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Encodes text as a PDF text string.
+///
+/// ASCII text is stored as-is, since it reads the same in PDFDocEncoding;
+/// anything else is stored as UTF-16BE with a byte order mark.
+pub(crate) fn encode_text_string(text: &str) -> Vec<u8> {
+    if text.chars().all(|c| (c as u32) < 0x80) {
+        return text.as_bytes().to_vec();
+    }
+
+    let mut bytes = vec![0xFE, 0xFF];
+    for unit in text.encode_utf16() {
+        bytes.extend_from_slice(&unit.to_be_bytes());
+    }
+    bytes
+}
+
+/// The parts of a /DA string needed to draw text.
+#[derive(Debug, Clone, PartialEq)]
+struct DefaultAppearance {
+    /// Font resource name (without the slash)
+    font_name: String,
+
+    /// Font size; 0 means auto-size
+    font_size: f64,
+
+    /// Color operators (e.g. "0 g", "1 0 0 rg")
+    color: String,
+}
+
+impl DefaultAppearance {
+    /// Parses a default appearance string such as "/Helv 12 Tf 0 g".
+    fn parse(da: &str) -> Self {
+        let mut appearance = DefaultAppearance {
+            font_name: "Helv".to_string(),
+            font_size: 0.0,
+            color: "0 g".to_string(),
+        };
+
+        let tokens: Vec<&str> = da.split_whitespace().collect();
+        let mut operands_start = 0;
+        for (i, token) in tokens.iter().enumerate() {
+            match *token {
+                "Tf" if i >= 2 => {
+                    appearance.font_name = tokens[i - 2].trim_start_matches('/').to_string();
+                    appearance.font_size = tokens[i - 1].parse().unwrap_or(0.0);
+                }
+                "g" | "rg" | "k" => {
+                    appearance.color = tokens[operands_start..=i].join(" ");
+                }
+                _ => {
+                    if token.parse::<f64>().is_ok() || token.starts_with('/') {
+                        continue;
+                    }
+                }
+            }
+            operands_start = i + 1;
+        }
+
+        appearance
+    }
+}
+
+/// Writes text as a literal string for use in a content stream.
+///
+/// Characters outside Latin-1 are replaced with '?', as the standard fonts
+/// used for form fields can't show them.
+pub(crate) fn content_string(text: &str) -> String {
+    let mut out = String::from("(");
+    for c in text.chars() {
+        match c {
+            '(' | ')' | '\\' => {
+                out.push('\\');
+                out.push(c);
+            }
+            c if (c as u32) < 0x20 || (c as u32) > 0xFF => out.push('?'),
+            c if (c as u32) >= 0x80 => out.push_str(&format!("\\{:03o}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push(')');
+    out
+}
+
+/// Formats a number for a content stream.
+pub(crate) fn fmt_num(value: f64) -> String {
+    let rounded = (value * 100.0).round() / 100.0;
+    if rounded.fract() == 0.0 {
+        format!("{}", rounded as i64)
+    } else {
+        format!("{}", rounded)
+    }
+}
+
+/// Splits text into lines that fit the given width.
+fn wrap_lines(text: &str, max_width: f64, font_size: f64) -> Vec<String> {
+    let max_chars = ((max_width / (font_size * AVERAGE_GLYPH_WIDTH)).floor() as usize).max(1);
+    let mut lines = Vec::new();
+
+    for paragraph in text.split('\n') {
+        let mut line = String::new();
+        for word in paragraph.split(' ') {
+            let needed = if line.is_empty() {
+                word.chars().count()
+            } else {
+                line.chars().count() + 1 + word.chars().count()
+            };
+            if needed > max_chars && !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(word);
+        }
+        lines.push(line);
+    }
+
+    lines
+}
+
+/// Generates the content stream of a text field appearance.
+///
+/// # Arguments
+/// * `value` - The text to show
+/// * `width`, `height` - Size of the widget rectangle
+/// * `da` - The default appearance string
+/// * `quadding` - 0 = left, 1 = centered, 2 = right
+/// * `multiline` - Whether to wrap text over several lines
+fn text_appearance(
+    value: &str,
+    width: f64,
+    height: f64,
+    da: &DefaultAppearance,
+    quadding: u8,
+    multiline: bool,
+) -> Vec<u8> {
+    let font_size = if da.font_size > 0.0 {
+        da.font_size
+    } else if multiline {
+        DEFAULT_FONT_SIZE
+    } else {
+        ((height - 2.0 * PADDING) / LINE_FACTOR).floor().max(1.0)
+    };
+    let line_height = font_size * LINE_FACTOR;
+
+    let lines = if multiline {
+        wrap_lines(value, width - 2.0 * PADDING, font_size)
+    } else {
+        vec![value.replace(['\r', '\n'], " ")]
+    };
+
+    let mut content = String::from("/Tx BMC\nq\n");
+    content.push_str(&format!(
+        "{} {} {} {} re W n\n",
+        fmt_num(PADDING / 2.0),
+        fmt_num(PADDING / 2.0),
+        fmt_num(width - PADDING),
+        fmt_num(height - PADDING)
+    ));
+    content.push_str(&format!(
+        "BT\n/{} {} Tf {}\n",
+        da.font_name,
+        fmt_num(font_size),
+        da.color
+    ));
+
+    // Baseline of the first line
+    let mut y = if multiline {
+        height - PADDING - font_size
+    } else {
+        (height - line_height) / 2.0 + font_size * LINE_DESCENT_FACTOR
+    };
+
+    for line in &lines {
+        let text_width = line.chars().count() as f64 * font_size * AVERAGE_GLYPH_WIDTH;
+        let x = match quadding {
+            1 => (width - text_width) / 2.0,
+            2 => width - PADDING - text_width,
+            _ => PADDING,
+        };
+        content.push_str(&format!(
+            "1 0 0 1 {} {} Tm {} Tj\n",
+            fmt_num(x),
+            fmt_num(y),
+            content_string(line)
+        ));
+        y -= line_height;
+    }
+
+    content.push_str("ET\nQ\nEMC\n");
+    content.into_bytes()
+}
+
+/// Generates the content stream of a checked check box or radio button.
+///
+/// The mark is a ZapfDingbats check (check box) or dot (radio button),
+/// centered in the widget.
+fn check_appearance(width: f64, height: f64, radio: bool) -> Vec<u8> {
+    let font_size = (width.min(height) * 0.8).max(1.0);
+    let (glyph, glyph_width) = if radio { ("l", 0.791) } else { ("4", 0.846) };
+    let x = (width - glyph_width * font_size) / 2.0;
+    let y = (height - font_size * 0.7) / 2.0;
+
+    format!(
+        "q\nBT\n/ZaDb {} Tf 0 g\n1 0 0 1 {} {} Tm ({}) Tj\nET\nQ\n",
+        fmt_num(font_size),
+        fmt_num(x),
+        fmt_num(y),
+        glyph
+    )
+    .into_bytes()
+}
+
+/// Builds a standard Type1 font dictionary.
+pub(crate) fn standard_font(base_font: &str) -> PDFObject {
+    let mut font = HashMap::new();
+    font.insert("Type".to_string(), PDFObject::Name("Font".to_string()));
+    font.insert("Subtype".to_string(), PDFObject::Name("Type1".to_string()));
+    font.insert(
+        "BaseFont".to_string(),
+        PDFObject::Name(base_font.to_string()),
+    );
+    if base_font != "ZapfDingbats" {
+        font.insert(
+            "Encoding".to_string(),
+            PDFObject::Name("WinAnsiEncoding".to_string()),
+        );
+    }
+    PDFObject::Dictionary(font)
+}
+
+/// Builds a form XObject for an appearance stream.
+fn appearance_stream(
+    width: f64,
+    height: f64,
+    font_name: &str,
+    font: PDFObject,
+    data: Vec<u8>,
+) -> PDFObject {
+    let mut fonts = HashMap::new();
+    fonts.insert(font_name.to_string(), font);
+    let mut resources = HashMap::new();
+    resources.insert("Font".to_string(), PDFObject::Dictionary(fonts));
+
+    let mut dict = HashMap::new();
+    dict.insert("Type".to_string(), PDFObject::Name("XObject".to_string()));
+    dict.insert("Subtype".to_string(), PDFObject::Name("Form".to_string()));
+    dict.insert(
+        "BBox".to_string(),
+        PDFObject::Array(
+            [0.0, 0.0, width, height]
+                .iter()
+                .map(|v| Box::new(PDFObject::Number(*v)))
+                .collect(),
+        ),
+    );
+    dict.insert("Resources".to_string(), PDFObject::Dictionary(resources));
+    PDFObject::Stream { dict, data }
+}
+
+/// Returns the width and height of a widget's /Rect.
+fn widget_size(dict: &HashMap<String, PDFObject>) -> (f64, f64) {
+    let rect: Vec<f64> = match dict.get("Rect") {
+        Some(PDFObject::Array(arr)) => arr
+            .iter()
+            .filter_map(|v| match &**v {
+                PDFObject::Number(n) => Some(*n),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    if rect.len() == 4 {
+        ((rect[2] - rect[0]).abs(), (rect[3] - rect[1]).abs())
+    } else {
+        (0.0, 0.0)
+    }
+}
+
+/// Returns the "on" state names of a button widget's normal appearance.
+fn on_states(dict: &HashMap<String, PDFObject>) -> Vec<String> {
+    match dict.get("AP") {
+        Some(PDFObject::Dictionary(ap)) => match ap.get("N") {
+            Some(PDFObject::Dictionary(states)) => {
+                let mut names: Vec<String> = states
+                    .keys()
+                    .filter(|name| name.as_str() != "Off")
+                    .cloned()
+                    .collect();
+                names.sort();
+                names
+            }
+            _ => Vec::new(),
+        },
+        _ => Vec::new(),
+    }
+}
+
+/// Command that sets the value of a form field.
+///
+/// Updates /V on the field dictionary and regenerates the appearance of each
+/// widget. Text and choice fields get a new normal appearance showing the
+/// value. Check boxes and radio buttons get their /AS switched, and an
+/// appearance is generated for the "on" state when the widget has none.
+///
+/// Execute through `PDFDocument::set_field_value()`, which looks the field up
+/// by name.
+#[derive(Debug)]
+pub struct SetFieldValueCommand {
+    /// The field to update
+    field: FormField,
+
+    /// The new value
+    value: String,
+
+    /// The form's default resources (/DR), used to find the /DA font
+    resources: Option<HashMap<String, PDFObject>>,
+
+    /// Field and widget objects before the command ran, with whether each was
+    /// already modified in the delta
+    originals: Vec<(Ref, PDFObject, bool)>,
+
+    /// The appearance stream objects that were added
+    added_refs: Vec<Ref>,
+}
+
+impl SetFieldValueCommand {
+    /// Create a new SetFieldValueCommand.
+    ///
+    /// # Arguments
+    /// * `field` - The field to update, as returned by `collect_form_fields()`
+    /// * `value` - The new value; "Off" clears a button, any other value
+    ///   checks a check box or selects the radio button with that state name
+    /// * `resources` - The form's /DR dictionary, if any
+    pub fn new(
+        field: FormField,
+        value: impl Into<String>,
+        resources: Option<HashMap<String, PDFObject>>,
+    ) -> Self {
+        Self {
+            field,
+            value: value.into(),
+            resources,
+            originals: Vec::new(),
+            added_refs: Vec::new(),
+        }
+    }
+
+    /// Looks up the font for an appearance stream in /DR, falling back to a
+    /// standard font.
+    fn font_resource(&self, font_name: &str, fallback: &str) -> PDFObject {
+        if let Some(resources) = &self.resources
+            && let Some(PDFObject::Dictionary(fonts)) = resources.get("Font")
+            && let Some(font) = fonts.get(font_name)
+        {
+            return font.clone();
+        }
+        standard_font(fallback)
+    }
+
+    /// Writes the updated field and widgets to the delta.
+    fn apply(&mut self, delta: &mut DeltaLayer) -> PDFResult<()> {
+        let mut objects: Vec<(Ref, HashMap<String, PDFObject>)> = Vec::new();
+        for (obj_ref, obj, _) in &self.originals {
+            match obj {
+                PDFObject::Dictionary(dict) => objects.push((*obj_ref, dict.clone())),
+                _ => {
+                    return Err(PDFError::Generic(format!(
+                        "Form field object {} {} is not a dictionary",
+                        obj_ref.num, obj_ref.generation
+                    )));
+                }
+            }
+        }
+
+        let field_value = match self.field.field_type {
+            FormFieldType::Text | FormFieldType::Choice => self.apply_text(delta, &mut objects),
+            FormFieldType::Button => self.apply_button(delta, &mut objects)?,
+            _ => {
+                return Err(PDFError::unsupported(format!(
+                    "setting the value of {:?} field '{}'",
+                    self.field.field_type, self.field.name
+                )));
+            }
+        };
+
+        for (obj_ref, dict) in &mut objects {
+            if *obj_ref == self.field.field_ref {
+                dict.insert("V".to_string(), field_value.clone());
+            }
+        }
+        for (obj_ref, dict) in objects {
+            delta.modify_object(obj_ref, PDFObject::Dictionary(dict));
+        }
+        Ok(())
+    }
+
+    /// Regenerates text widget appearances and returns the new /V.
+    fn apply_text(
+        &mut self,
+        delta: &mut DeltaLayer,
+        objects: &mut [(Ref, HashMap<String, PDFObject>)],
+    ) -> PDFObject {
+        let password = self.field.flags & FLAG_PASSWORD != 0;
+        let multiline = self.field.flags & FLAG_MULTILINE != 0;
+        let shown = if password {
+            "*".repeat(self.value.chars().count())
+        } else {
+            self.value.clone()
+        };
+
+        for (obj_ref, dict) in objects.iter_mut() {
+            if !self.field.widgets.contains(obj_ref) {
+                continue;
+            }
+
+            let da = dict
+                .get("DA")
+                .and_then(string_value)
+                .or_else(|| self.field.default_appearance.clone())
+                .unwrap_or_else(|| DEFAULT_APPEARANCE.to_string());
+            let da = DefaultAppearance::parse(&da);
+            let quadding = match dict.get("Q") {
+                Some(PDFObject::Number(n)) => *n as u8,
+                _ => self.field.quadding,
+            };
+            let (width, height) = widget_size(dict);
+
+            let data = text_appearance(&shown, width, height, &da, quadding, multiline);
+            let font = self.font_resource(&da.font_name, "Helvetica");
+            let stream = appearance_stream(width, height, &da.font_name, font, data);
+            let stream_ref = delta.add_object(stream);
+            self.added_refs.push(stream_ref);
+
+            let mut ap = HashMap::new();
+            ap.insert("N".to_string(), PDFObject::Ref(stream_ref));
+            dict.insert("AP".to_string(), PDFObject::Dictionary(ap));
+        }
+
+        PDFObject::String(encode_text_string(&self.value))
+    }
+
+    /// Switches button widget states and returns the new /V.
+    fn apply_button(
+        &mut self,
+        delta: &mut DeltaLayer,
+        objects: &mut [(Ref, HashMap<String, PDFObject>)],
+    ) -> PDFResult<PDFObject> {
+        if self.field.flags & FLAG_PUSHBUTTON != 0 {
+            return Err(PDFError::unsupported(format!(
+                "setting the value of push button '{}'",
+                self.field.name
+            )));
+        }
+        let radio = self.field.flags & FLAG_RADIO != 0;
+        let checked = self.value != "Off";
+
+        // Pick the "on" state of each widget before touching the delta
+        let mut states = Vec::new();
+        for (obj_ref, dict) in objects.iter() {
+            if !self.field.widgets.contains(obj_ref) {
+                continue;
+            }
+            // Radio buttons are told apart by their on-state name; check
+            // boxes without an appearance get the conventional "Yes"
+            let existing = on_states(dict);
+            let on_state = existing
+                .first()
+                .cloned()
+                .unwrap_or_else(|| "Yes".to_string());
+            states.push((*obj_ref, on_state, existing.is_empty()));
+        }
+
+        if radio && checked && !states.iter().any(|(_, state, _)| *state == self.value) {
+            return Err(PDFError::Generic(format!(
+                "Radio button '{}' has no option '{}'",
+                self.field.name, self.value
+            )));
+        }
+
+        let mut field_value = "Off".to_string();
+        for (widget_ref, on_state, needs_appearance) in states {
+            let selected = checked && (!radio || on_state == self.value);
+            if selected {
+                field_value = on_state.clone();
+            }
+
+            let dict = match objects.iter_mut().find(|(r, _)| *r == widget_ref) {
+                Some((_, dict)) => dict,
+                None => continue,
+            };
+
+            if needs_appearance {
+                let (width, height) = widget_size(dict);
+                let on = appearance_stream(
+                    width,
+                    height,
+                    "ZaDb",
+                    self.font_resource("ZaDb", "ZapfDingbats"),
+                    check_appearance(width, height, radio),
+                );
+                let off = appearance_stream(
+                    width,
+                    height,
+                    "ZaDb",
+                    self.font_resource("ZaDb", "ZapfDingbats"),
+                    Vec::new(),
+                );
+                let on_ref = delta.add_object(on);
+                let off_ref = delta.add_object(off);
+                self.added_refs.push(on_ref);
+                self.added_refs.push(off_ref);
+
+                let mut normal = HashMap::new();
+                normal.insert(on_state.clone(), PDFObject::Ref(on_ref));
+                normal.insert("Off".to_string(), PDFObject::Ref(off_ref));
+                let mut ap = HashMap::new();
+                ap.insert("N".to_string(), PDFObject::Dictionary(normal));
+                dict.insert("AP".to_string(), PDFObject::Dictionary(ap));
+            }
+
+            let state = if selected {
+                on_state
+            } else {
+                "Off".to_string()
+            };
+            dict.insert("AS".to_string(), PDFObject::Name(state));
+        }
+
+        Ok(PDFObject::Name(field_value))
+    }
+}
+
+impl Command for SetFieldValueCommand {
+    fn execute<'a>(
+        &mut self,
+        delta: &mut DeltaLayer,
+        fetch_base: Option<&'a BaseObjectFetcher<'a>>,
+    ) -> PDFResult<()> {
+        let mut refs = vec![self.field.field_ref];
+        for widget in &self.field.widgets {
+            if !refs.contains(widget) {
+                refs.push(*widget);
+            }
+        }
+
+        // Record the current field and widget objects from delta or base PDF
+        self.originals.clear();
+        for obj_ref in refs {
+            let (obj, was_modified) = match delta.get(&obj_ref) {
+                Some(delta_obj) => (delta_obj.object.clone(), true),
+                None => {
+                    let fetcher = fetch_base.ok_or_else(|| {
+                        PDFError::Generic(
+                            "Cannot fetch base field object - no fetch callback provided. \
+                            Execute commands through PDFDocument::execute_command() instead."
+                                .into(),
+                        )
+                    })?;
+                    (fetcher(obj_ref)?, false)
+                }
+            };
+            self.originals.push((obj_ref, obj, was_modified));
+        }
+
+        // Appearance dictionaries may be indirect; resolve them so the
+        // button states can be read
+        for (_, obj, _) in &mut self.originals {
+            if let PDFObject::Dictionary(dict) = obj
+                && let Some(PDFObject::Ref(ap_ref)) = dict.get("AP").cloned()
+            {
+                let ap = match delta.get(&ap_ref) {
+                    Some(delta_obj) => delta_obj.object.clone(),
+                    None => match fetch_base {
+                        Some(fetcher) => fetcher(ap_ref)?,
+                        None => continue,
+                    },
+                };
+                dict.insert("AP".to_string(), ap);
+            }
+        }
+
+        self.added_refs.clear();
+        self.apply(delta)
+    }
+
+    fn undo(&mut self, delta: &mut DeltaLayer) -> PDFResult<()> {
+        for stream_ref in self.added_refs.drain(..) {
+            delta.remove_new_object(stream_ref);
+        }
+
+        for (obj_ref, original, was_modified) in &self.originals {
+            if *was_modified {
+                delta.modify_object(*obj_ref, original.clone());
+            } else {
+                delta.revert_object(*obj_ref);
+            }
+        }
+        Ok(())
+    }
+
+    fn redo(&mut self, delta: &mut DeltaLayer) -> PDFResult<()> {
+        self.added_refs.clear();
+        self.apply(delta)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_default_appearance() {
+        let da = DefaultAppearance::parse("/Helv 10 Tf 1 0 0 rg");
+        assert_eq!(da.font_name, "Helv");
+        assert_eq!(da.font_size, 10.0);
+        assert_eq!(da.color, "1 0 0 rg");
+
+        let da = DefaultAppearance::parse("0.5 g /TiRo 0 Tf");
+        assert_eq!(da.font_name, "TiRo");
+        assert_eq!(da.font_size, 0.0);
+        assert_eq!(da.color, "0.5 g");
+    }
+
+    #[test]
+    fn test_content_string_escapes() {
+        assert_eq!(content_string("a(b)\\c"), "(a\\(b\\)\\\\c)");
+        assert_eq!(content_string("é€"), "(\\351?)");
+    }
+
+    #[test]
+    fn test_encode_text_string() {
+        assert_eq!(encode_text_string("abc"), b"abc".to_vec());
+        assert_eq!(encode_text_string("é"), vec![0xFE, 0xFF, 0x00, 0xE9]);
+        assert_eq!(
+            string_value(&PDFObject::String(encode_text_string("é"))),
+            Some("é".to_string())
+        );
+    }
+
+    #[test]
+    fn test_text_appearance_auto_size() {
+        let da = DefaultAppearance::parse("/Helv 0 Tf 0 g");
+        let content = String::from_utf8(text_appearance("Hi", 100.0, 20.0, &da, 0, false)).unwrap();
+
+        assert!(content.starts_with("/Tx BMC\n"));
+        assert!(content.contains("/Helv 11 Tf 0 g"));
+        assert!(content.contains("(Hi) Tj"));
+        assert!(content.ends_with("EMC\n"));
+    }
+
+    #[test]
+    fn test_text_appearance_multiline_wraps() {
+        let da = DefaultAppearance::parse("/Helv 10 Tf 0 g");
+        let content = String::from_utf8(text_appearance(
+            "one two three four",
+            34.0,
+            100.0,
+            &da,
+            0,
+            true,
+        ))
+        .unwrap();
+
+        // 30pt of usable width fits 6 characters at 10pt
+        assert_eq!(content.matches(" Tj").count(), 4);
+    }
+
+    #[test]
+    fn test_wrap_lines_keeps_paragraphs() {
+        let lines = wrap_lines("ab cd\nef", 100.0, 10.0);
+        assert_eq!(lines, vec!["ab cd".to_string(), "ef".to_string()]);
+    }
+}
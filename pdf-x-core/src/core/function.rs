@@ -0,0 +1,872 @@
+//! PDF functions (PDF spec section 7.10).
+//!
+//! Functions map m input values to n output values. They drive shading
+//! colors, Separation/DeviceN tint transforms and transfer functions. All
+//! four function types are supported:
+//!
+//! - Type 0: sampled, with multilinear interpolation
+//! - Type 2: exponential interpolation
+//! - Type 3: stitching of 1-input functions
+//! - Type 4: PostScript calculator
+//!
+//! Based on PDF.js src/core/function.js
+
+use super::decode::decode_stream_data_limited;
+use super::error::{PDFError, PDFResult};
+use super::parser::PDFObject;
+use super::xref::XRef;
+use crate::log_target;
+
+/// A parsed PDF function.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PDFFunction {
+    /// Type 0: a table of samples, interpolated linearly
+    Sampled {
+        domain: Vec<f64>,
+        range: Vec<f64>,
+        size: Vec<usize>,
+        encode: Vec<f64>,
+        decode: Vec<f64>,
+        /// Samples normalized to 0..1, output values innermost
+        samples: Vec<f64>,
+    },
+
+    /// Type 2: C0 + x^N * (C1 - C0)
+    Exponential {
+        domain: Vec<f64>,
+        range: Option<Vec<f64>>,
+        c0: Vec<f64>,
+        c1: Vec<f64>,
+        n: f64,
+    },
+
+    /// Type 3: one of several functions, chosen by the input's subdomain
+    Stitching {
+        domain: Vec<f64>,
+        range: Option<Vec<f64>>,
+        functions: Vec<PDFFunction>,
+        bounds: Vec<f64>,
+        encode: Vec<f64>,
+    },
+
+    /// Type 4: a PostScript calculator program
+    PostScript {
+        domain: Vec<f64>,
+        range: Vec<f64>,
+        program: Vec<PsOp>,
+    },
+}
+
+/// An instruction of a Type 4 function program.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PsOp {
+    /// Push a number
+    Number(f64),
+    /// Push a boolean
+    Bool(bool),
+    /// Run the block if the popped boolean is true
+    If(Vec<PsOp>),
+    /// Run the first block if the popped boolean is true, else the second
+    IfElse(Vec<PsOp>, Vec<PsOp>),
+    /// Any other operator, e.g. "add" or "exch"
+    Operator(String),
+}
+
+impl PDFFunction {
+    /// Parses a function dictionary or stream, resolving references.
+    ///
+    /// # Arguments
+    /// * `obj` - The function object (or a reference to it)
+    /// * `xref` - Cross-reference table for resolving references
+    pub fn parse(obj: &PDFObject, xref: &mut XRef) -> PDFResult<Self> {
+        Self::parse_nested(obj, xref, 0)
+    }
+
+    /// Stitching functions nest at most `max_object_depth` deep, which also
+    /// stops reference cycles.
+    fn parse_nested(obj: &PDFObject, xref: &mut XRef, depth: usize) -> PDFResult<Self> {
+        let max_depth = xref.limits().max_object_depth;
+        if depth > max_depth {
+            return Err(PDFError::limit_exceeded(
+                "function nesting depth",
+                max_depth,
+            ));
+        }
+
+        let obj = xref.fetch_if_ref(obj)?;
+        let (dict, data) = match &obj {
+            PDFObject::Dictionary(dict) => (dict, None),
+            PDFObject::Stream { dict, data } => (dict, Some(data)),
+            _ => {
+                return Err(PDFError::Generic(format!(
+                    "Function must be a dictionary or stream, got {:?}",
+                    obj
+                )));
+            }
+        };
+        let function_type = match dict.get("FunctionType") {
+            Some(PDFObject::Number(n)) => *n as i32,
+            _ => {
+                return Err(PDFError::Generic(
+                    "Function missing /FunctionType".to_string(),
+                ));
+            }
+        };
+        let domain = numbers(xref, dict.get("Domain"))
+            .filter(|d| d.len() >= 2 && d.len() % 2 == 0)
+            .ok_or_else(|| PDFError::Generic("Function missing /Domain".to_string()))?;
+
+        match function_type {
+            0 => {
+                let data = data.ok_or_else(|| {
+                    PDFError::Generic("Sampled function must be a stream".to_string())
+                })?;
+                let range = numbers(xref, dict.get("Range"))
+                    .filter(|r| r.len() >= 2 && r.len() % 2 == 0)
+                    .ok_or_else(|| {
+                        PDFError::Generic("Sampled function missing /Range".to_string())
+                    })?;
+                let size: Vec<usize> = numbers(xref, dict.get("Size"))
+                    .map(|s| s.iter().map(|&n| n.max(1.0) as usize).collect())
+                    .filter(|s: &Vec<usize>| s.len() == domain.len() / 2)
+                    .ok_or_else(|| {
+                        PDFError::Generic("Sampled function has an invalid /Size".to_string())
+                    })?;
+                let bits = match dict.get("BitsPerSample") {
+                    Some(PDFObject::Number(n)) => *n as u32,
+                    _ => 0,
+                };
+                if !matches!(bits, 1 | 2 | 4 | 8 | 12 | 16 | 24 | 32) {
+                    return Err(PDFError::Generic(format!(
+                        "Invalid /BitsPerSample {} in sampled function",
+                        bits
+                    )));
+                }
+                let encode = numbers(xref, dict.get("Encode"))
+                    .unwrap_or_else(|| size.iter().flat_map(|&s| [0.0, (s - 1) as f64]).collect());
+                let decode = numbers(xref, dict.get("Decode")).unwrap_or_else(|| range.clone());
+
+                let count = size
+                    .iter()
+                    .try_fold(range.len() / 2, |acc, &s| acc.checked_mul(s))
+                    .ok_or_else(|| {
+                        PDFError::corrupted_pdf("Sampled function /Size is too large")
+                    })?;
+                let bytes = decode_stream_data_limited(dict, data, xref.limits())?;
+                let samples = read_samples(&bytes, bits, count);
+
+                Ok(PDFFunction::Sampled {
+                    domain,
+                    range,
+                    size,
+                    encode,
+                    decode,
+                    samples,
+                })
+            }
+            2 => {
+                let c0 = numbers(xref, dict.get("C0")).unwrap_or_else(|| vec![0.0]);
+                let c1 = numbers(xref, dict.get("C1")).unwrap_or_else(|| vec![1.0]);
+                if c0.len() != c1.len() {
+                    return Err(PDFError::Generic(
+                        "Exponential function /C0 and /C1 differ in length".to_string(),
+                    ));
+                }
+                let n = match dict.get("N") {
+                    Some(PDFObject::Number(n)) => *n,
+                    _ => {
+                        return Err(PDFError::Generic(
+                            "Exponential function missing /N".to_string(),
+                        ));
+                    }
+                };
+                Ok(PDFFunction::Exponential {
+                    domain,
+                    range: numbers(xref, dict.get("Range")),
+                    c0,
+                    c1,
+                    n,
+                })
+            }
+            3 => {
+                let items = match dict.get("Functions").map(|f| xref.fetch_if_ref(f)) {
+                    Some(Ok(PDFObject::Array(items))) => items,
+                    _ => {
+                        return Err(PDFError::Generic(
+                            "Stitching function missing /Functions".to_string(),
+                        ));
+                    }
+                };
+                let mut functions = Vec::with_capacity(items.len());
+                for item in items.iter() {
+                    functions.push(Self::parse_nested(item, xref, depth + 1)?);
+                }
+                let bounds = numbers(xref, dict.get("Bounds")).unwrap_or_default();
+                let encode = numbers(xref, dict.get("Encode")).unwrap_or_default();
+                if functions.is_empty()
+                    || bounds.len() + 1 != functions.len()
+                    || encode.len() != functions.len() * 2
+                {
+                    return Err(PDFError::Generic(
+                        "Stitching function has inconsistent /Bounds or /Encode".to_string(),
+                    ));
+                }
+                Ok(PDFFunction::Stitching {
+                    domain,
+                    range: numbers(xref, dict.get("Range")),
+                    functions,
+                    bounds,
+                    encode,
+                })
+            }
+            4 => {
+                let data = data.ok_or_else(|| {
+                    PDFError::Generic("PostScript function must be a stream".to_string())
+                })?;
+                let range = numbers(xref, dict.get("Range"))
+                    .filter(|r| r.len() >= 2 && r.len() % 2 == 0)
+                    .ok_or_else(|| {
+                        PDFError::Generic("PostScript function missing /Range".to_string())
+                    })?;
+                let code = decode_stream_data_limited(dict, data, xref.limits())?;
+                let program = parse_ps_program(&String::from_utf8_lossy(&code))?;
+                Ok(PDFFunction::PostScript {
+                    domain,
+                    range,
+                    program,
+                })
+            }
+            other => Err(PDFError::Generic(format!(
+                "Unknown function type {}",
+                other
+            ))),
+        }
+    }
+
+    /// Returns the number of input values.
+    pub fn input_count(&self) -> usize {
+        self.domain().len() / 2
+    }
+
+    /// Returns the number of output values.
+    pub fn output_count(&self) -> usize {
+        match self {
+            PDFFunction::Sampled { range, .. } | PDFFunction::PostScript { range, .. } => {
+                range.len() / 2
+            }
+            PDFFunction::Exponential { c0, .. } => c0.len(),
+            PDFFunction::Stitching { functions, .. } => functions[0].output_count(),
+        }
+    }
+
+    fn domain(&self) -> &[f64] {
+        match self {
+            PDFFunction::Sampled { domain, .. }
+            | PDFFunction::Exponential { domain, .. }
+            | PDFFunction::Stitching { domain, .. }
+            | PDFFunction::PostScript { domain, .. } => domain,
+        }
+    }
+
+    /// Evaluates the function.
+    ///
+    /// Inputs are clipped to the domain and outputs to the range. Missing
+    /// inputs are treated as 0.
+    pub fn eval(&self, input: &[f64]) -> Vec<f64> {
+        let domain = self.domain();
+        let x: Vec<f64> = (0..domain.len() / 2)
+            .map(|i| {
+                clip(
+                    input.get(i).copied().unwrap_or(0.0),
+                    domain[2 * i],
+                    domain[2 * i + 1],
+                )
+            })
+            .collect();
+
+        match self {
+            PDFFunction::Sampled {
+                domain,
+                range,
+                size,
+                encode,
+                decode,
+                samples,
+            } => eval_sampled(&x, domain, range, size, encode, decode, samples),
+            PDFFunction::Exponential {
+                range, c0, c1, n, ..
+            } => {
+                let xn = x[0].powf(*n);
+                let out = c0.iter().zip(c1).map(|(a, b)| a + xn * (b - a)).collect();
+                clip_to_range(out, range.as_deref())
+            }
+            PDFFunction::Stitching {
+                domain,
+                range,
+                functions,
+                bounds,
+                encode,
+            } => {
+                let x = x[0];
+                let k = bounds.iter().position(|&b| x < b).unwrap_or(bounds.len());
+                let low = if k == 0 { domain[0] } else { bounds[k - 1] };
+                let high = if k == bounds.len() {
+                    domain[1]
+                } else {
+                    bounds[k]
+                };
+                let t = interpolate(x, low, high, encode[2 * k], encode[2 * k + 1]);
+                clip_to_range(functions[k].eval(&[t]), range.as_deref())
+            }
+            PDFFunction::PostScript { range, program, .. } => {
+                let mut stack: Vec<PsValue> = x.iter().map(|&v| PsValue::Number(v)).collect();
+                if let Err(e) = run_ps(program, &mut stack) {
+                    tracing::warn!(target: log_target::RENDER, "PostScript function failed: {}", e);
+                }
+                let n = range.len() / 2;
+                let start = stack.len().saturating_sub(n);
+                let mut out: Vec<f64> = stack[start..].iter().map(|v| v.number()).collect();
+                out.resize(n, 0.0);
+                clip_to_range(out, Some(range))
+            }
+        }
+    }
+}
+
+/// Reads an array of numbers, resolving a reference to the array itself.
+fn numbers(xref: &mut XRef, obj: Option<&PDFObject>) -> Option<Vec<f64>> {
+    match xref.fetch_if_ref(obj?).ok()? {
+        PDFObject::Array(items) => items.iter().map(|item| item.as_number()).collect(),
+        _ => None,
+    }
+}
+
+fn clip(value: f64, min: f64, max: f64) -> f64 {
+    value.max(min).min(max)
+}
+
+/// Maps `x` from [x_min, x_max] to [y_min, y_max].
+fn interpolate(x: f64, x_min: f64, x_max: f64, y_min: f64, y_max: f64) -> f64 {
+    if x_max == x_min {
+        return y_min;
+    }
+    y_min + (x - x_min) * (y_max - y_min) / (x_max - x_min)
+}
+
+fn clip_to_range(mut values: Vec<f64>, range: Option<&[f64]>) -> Vec<f64> {
+    if let Some(range) = range {
+        for (i, value) in values.iter_mut().enumerate() {
+            if 2 * i + 1 < range.len() {
+                *value = clip(*value, range[2 * i], range[2 * i + 1]);
+            }
+        }
+    }
+    values
+}
+
+/// Unpacks `count` big-endian samples of `bits` bits each, normalized to 0..1.
+fn read_samples(data: &[u8], bits: u32, count: usize) -> Vec<f64> {
+    // Samples past the end of the data read as zero when evaluating, so
+    // never materialize more than the stream actually holds
+    let count = count.min(data.len().saturating_mul(8) / bits as usize);
+    let max = ((1u64 << bits) - 1) as f64;
+    let mut samples = Vec::with_capacity(count);
+    let mut buffer: u64 = 0;
+    let mut buffered = 0;
+    let mut bytes = data.iter();
+    while samples.len() < count {
+        while buffered < bits {
+            buffer = (buffer << 8) | *bytes.next().unwrap_or(&0) as u64;
+            buffered += 8;
+        }
+        buffered -= bits;
+        samples.push(((buffer >> buffered) & ((1u64 << bits) - 1)) as f64 / max);
+        buffer &= (1u64 << buffered) - 1;
+    }
+    samples
+}
+
+fn eval_sampled(
+    x: &[f64],
+    domain: &[f64],
+    range: &[f64],
+    size: &[usize],
+    encode: &[f64],
+    decode: &[f64],
+    samples: &[f64],
+) -> Vec<f64> {
+    let m = x.len();
+    let n = range.len() / 2;
+
+    // Position of the input in sample space, split into the lower sample
+    // index and the interpolation weight towards the next one
+    let mut index = Vec::with_capacity(m);
+    let mut weight = Vec::with_capacity(m);
+    for i in 0..m {
+        let e = interpolate(
+            x[i],
+            domain[2 * i],
+            domain[2 * i + 1],
+            encode[2 * i],
+            encode[2 * i + 1],
+        );
+        let e = clip(e, 0.0, (size[i] - 1) as f64);
+        let low = (e.floor() as usize).min(size[i].saturating_sub(2));
+        index.push(low);
+        weight.push(if size[i] > 1 { e - low as f64 } else { 0.0 });
+    }
+
+    let mut out = vec![0.0; n];
+    // Sum over the 2^m corners of the enclosing cell
+    for corner in 0..1usize << m {
+        let mut offset = 0;
+        let mut stride = 1;
+        let mut factor = 1.0;
+        for i in 0..m {
+            let upper = corner & (1 << i) != 0 && size[i] > 1;
+            offset += (index[i] + upper as usize) * stride;
+            stride *= size[i];
+            factor *= if upper { weight[i] } else { 1.0 - weight[i] };
+        }
+        if factor == 0.0 {
+            continue;
+        }
+        for (j, value) in out.iter_mut().enumerate() {
+            *value += factor * samples.get(offset * n + j).copied().unwrap_or(0.0);
+        }
+    }
+
+    out.iter()
+        .enumerate()
+        .map(|(j, &s)| {
+            let d = interpolate(s, 0.0, 1.0, decode[2 * j], decode[2 * j + 1]);
+            clip(d, range[2 * j], range[2 * j + 1])
+        })
+        .collect()
+}
+
+/// Parses a Type 4 function program: `{ ... }` with nested blocks for
+/// `if` and `ifelse`.
+fn parse_ps_program(source: &str) -> PDFResult<Vec<PsOp>> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    for ch in source.chars() {
+        match ch {
+            '{' | '}' => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+                tokens.push(ch.to_string());
+            }
+            c if c.is_whitespace() => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+
+    let mut tokens = tokens.into_iter();
+    if tokens.next().as_deref() != Some("{") {
+        return Err(PDFError::Generic(
+            "PostScript function must start with '{'".to_string(),
+        ));
+    }
+    parse_ps_block(&mut tokens)
+}
+
+/// Parses instructions up to the closing brace of the current block.
+fn parse_ps_block(tokens: &mut impl Iterator<Item = String>) -> PDFResult<Vec<PsOp>> {
+    let mut ops = Vec::new();
+    // Blocks waiting for their if/ifelse operator
+    let mut blocks: Vec<Vec<PsOp>> = Vec::new();
+    while let Some(token) = tokens.next() {
+        match token.as_str() {
+            "}" => return Ok(ops),
+            "{" => blocks.push(parse_ps_block(tokens)?),
+            "if" => {
+                let block = blocks.pop().ok_or_else(|| {
+                    PDFError::Generic("PostScript 'if' without a block".to_string())
+                })?;
+                ops.push(PsOp::If(block));
+            }
+            "ifelse" => {
+                let (else_block, then_block) = match (blocks.pop(), blocks.pop()) {
+                    (Some(e), Some(t)) => (e, t),
+                    _ => {
+                        return Err(PDFError::Generic(
+                            "PostScript 'ifelse' without two blocks".to_string(),
+                        ));
+                    }
+                };
+                ops.push(PsOp::IfElse(then_block, else_block));
+            }
+            "true" => ops.push(PsOp::Bool(true)),
+            "false" => ops.push(PsOp::Bool(false)),
+            _ => match token.parse::<f64>() {
+                Ok(n) => ops.push(PsOp::Number(n)),
+                Err(_) => ops.push(PsOp::Operator(token)),
+            },
+        }
+    }
+    Err(PDFError::Generic(
+        "Unterminated PostScript function block".to_string(),
+    ))
+}
+
+/// A value on the PostScript operand stack.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PsValue {
+    Number(f64),
+    Bool(bool),
+}
+
+impl PsValue {
+    fn number(self) -> f64 {
+        match self {
+            PsValue::Number(n) => n,
+            PsValue::Bool(b) => b as i32 as f64,
+        }
+    }
+}
+
+/// Limit on the operand stack, as in the PostScript language reference.
+const PS_STACK_LIMIT: usize = 100;
+
+fn run_ps(program: &[PsOp], stack: &mut Vec<PsValue>) -> Result<(), String> {
+    fn pop(stack: &mut Vec<PsValue>) -> Result<PsValue, String> {
+        stack.pop().ok_or_else(|| "stack underflow".to_string())
+    }
+    fn pop_num(stack: &mut Vec<PsValue>) -> Result<f64, String> {
+        pop(stack).map(PsValue::number)
+    }
+    fn pop_bool(stack: &mut Vec<PsValue>) -> Result<bool, String> {
+        match pop(stack)? {
+            PsValue::Bool(b) => Ok(b),
+            PsValue::Number(n) => Ok(n != 0.0),
+        }
+    }
+
+    for op in program {
+        match op {
+            PsOp::Number(n) => stack.push(PsValue::Number(*n)),
+            PsOp::Bool(b) => stack.push(PsValue::Bool(*b)),
+            PsOp::If(block) => {
+                if pop_bool(stack)? {
+                    run_ps(block, stack)?;
+                }
+            }
+            PsOp::IfElse(then_block, else_block) => {
+                if pop_bool(stack)? {
+                    run_ps(then_block, stack)?;
+                } else {
+                    run_ps(else_block, stack)?;
+                }
+            }
+            PsOp::Operator(name) => {
+                let name = name.as_str();
+                match name {
+                    // Arithmetic
+                    "abs" | "ceiling" | "cos" | "cvi" | "cvr" | "floor" | "ln" | "log" | "neg"
+                    | "round" | "sin" | "sqrt" | "truncate" => {
+                        let a = pop_num(stack)?;
+                        let r = match name {
+                            "abs" => a.abs(),
+                            "ceiling" => a.ceil(),
+                            "cos" => a.to_radians().cos(),
+                            "cvi" | "truncate" => a.trunc(),
+                            "cvr" => a,
+                            "floor" => a.floor(),
+                            "ln" => a.ln(),
+                            "log" => a.log10(),
+                            "neg" => -a,
+                            "round" => (a + 0.5).floor(),
+                            "sin" => a.to_radians().sin(),
+                            _ => a.sqrt(),
+                        };
+                        stack.push(PsValue::Number(r));
+                    }
+                    "add" | "sub" | "mul" | "div" | "idiv" | "mod" | "exp" | "atan" => {
+                        let b = pop_num(stack)?;
+                        let a = pop_num(stack)?;
+                        let r = match name {
+                            "add" => a + b,
+                            "sub" => a - b,
+                            "mul" => a * b,
+                            "div" => {
+                                if b == 0.0 {
+                                    return Err("division by zero".to_string());
+                                }
+                                a / b
+                            }
+                            "idiv" | "mod" => {
+                                let (a, b) = (a as i64, b as i64);
+                                if b == 0 {
+                                    return Err("division by zero".to_string());
+                                }
+                                (if name == "idiv" { a / b } else { a % b }) as f64
+                            }
+                            "exp" => a.powf(b),
+                            _ => {
+                                let angle = a.atan2(b).to_degrees();
+                                if angle < 0.0 { angle + 360.0 } else { angle }
+                            }
+                        };
+                        stack.push(PsValue::Number(r));
+                    }
+                    // Relational, boolean and bitwise
+                    "eq" | "ne" => {
+                        let b = pop(stack)?;
+                        let a = pop(stack)?;
+                        let equal = a.number() == b.number();
+                        stack.push(PsValue::Bool(equal == (name == "eq")));
+                    }
+                    "gt" | "ge" | "lt" | "le" => {
+                        let b = pop_num(stack)?;
+                        let a = pop_num(stack)?;
+                        let r = match name {
+                            "gt" => a > b,
+                            "ge" => a >= b,
+                            "lt" => a < b,
+                            _ => a <= b,
+                        };
+                        stack.push(PsValue::Bool(r));
+                    }
+                    "and" | "or" | "xor" => {
+                        let b = pop(stack)?;
+                        let a = pop(stack)?;
+                        let r = match (a, b) {
+                            (PsValue::Bool(a), PsValue::Bool(b)) => PsValue::Bool(match name {
+                                "and" => a && b,
+                                "or" => a || b,
+                                _ => a != b,
+                            }),
+                            (a, b) => {
+                                let (a, b) = (a.number() as i64, b.number() as i64);
+                                PsValue::Number(match name {
+                                    "and" => a & b,
+                                    "or" => a | b,
+                                    _ => a ^ b,
+                                } as f64)
+                            }
+                        };
+                        stack.push(r);
+                    }
+                    "not" => {
+                        let r = match pop(stack)? {
+                            PsValue::Bool(b) => PsValue::Bool(!b),
+                            PsValue::Number(n) => PsValue::Number(!(n as i64) as f64),
+                        };
+                        stack.push(r);
+                    }
+                    "bitshift" => {
+                        let shift = pop_num(stack)? as i64;
+                        let a = pop_num(stack)? as i64;
+                        let r = if shift >= 0 {
+                            a.checked_shl(shift as u32).unwrap_or(0)
+                        } else {
+                            a.checked_shr((-shift) as u32).unwrap_or(0)
+                        };
+                        stack.push(PsValue::Number(r as f64));
+                    }
+                    // Stack manipulation
+                    "pop" => {
+                        pop(stack)?;
+                    }
+                    "dup" => {
+                        let a = *stack.last().ok_or("stack underflow")?;
+                        stack.push(a);
+                    }
+                    "exch" => {
+                        let b = pop(stack)?;
+                        let a = pop(stack)?;
+                        stack.push(b);
+                        stack.push(a);
+                    }
+                    "copy" => {
+                        let n = pop_num(stack)? as usize;
+                        if n > stack.len() {
+                            return Err("stack underflow".to_string());
+                        }
+                        stack.extend_from_within(stack.len() - n..);
+                    }
+                    "index" => {
+                        let n = pop_num(stack)? as usize;
+                        let value = *stack
+                            .len()
+                            .checked_sub(n + 1)
+                            .and_then(|i| stack.get(i))
+                            .ok_or("stack underflow")?;
+                        stack.push(value);
+                    }
+                    "roll" => {
+                        let j = pop_num(stack)? as i64;
+                        let n = pop_num(stack)? as usize;
+                        if n > stack.len() {
+                            return Err("stack underflow".to_string());
+                        }
+                        if n > 0 {
+                            let start = stack.len() - n;
+                            let shift = j.rem_euclid(n as i64) as usize;
+                            stack[start..].rotate_right(shift);
+                        }
+                    }
+                    other => return Err(format!("unknown operator '{}'", other)),
+                }
+            }
+        }
+        if stack.len() > PS_STACK_LIMIT {
+            return Err("stack overflow".to_string());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Stream;
+    use std::collections::HashMap;
+
+    fn dict(entries: &[(&str, PDFObject)]) -> HashMap<String, PDFObject> {
+        entries
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.clone()))
+            .collect()
+    }
+
+    fn xref() -> XRef {
+        XRef::new(Box::new(Stream::from_bytes(Vec::new())))
+    }
+
+    fn array(values: &[f64]) -> PDFObject {
+        PDFObject::Array(
+            values
+                .iter()
+                .map(|&v| Box::new(PDFObject::Number(v)))
+                .collect(),
+        )
+    }
+
+    fn assert_close(actual: &[f64], expected: &[f64]) {
+        assert_eq!(
+            actual.len(),
+            expected.len(),
+            "{:?} vs {:?}",
+            actual,
+            expected
+        );
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-6, "{:?} vs {:?}", actual, expected);
+        }
+    }
+
+    #[test]
+    fn test_exponential_and_stitching() {
+        let red_to_blue = PDFObject::Dictionary(dict(&[
+            ("FunctionType", PDFObject::Number(2.0)),
+            ("Domain", array(&[0.0, 1.0])),
+            ("C0", array(&[1.0, 0.0, 0.0])),
+            ("C1", array(&[0.0, 0.0, 1.0])),
+            ("N", PDFObject::Number(1.0)),
+        ]));
+        let f = PDFFunction::parse(&red_to_blue, &mut xref()).unwrap();
+        assert_eq!(f.output_count(), 3);
+        assert_close(&f.eval(&[0.25]), &[0.75, 0.0, 0.25]);
+        // Inputs are clipped to the domain
+        assert_close(&f.eval(&[2.0]), &[0.0, 0.0, 1.0]);
+
+        let stitched = PDFObject::Dictionary(dict(&[
+            ("FunctionType", PDFObject::Number(3.0)),
+            ("Domain", array(&[0.0, 1.0])),
+            (
+                "Functions",
+                PDFObject::Array(vec![Box::new(red_to_blue.clone()), Box::new(red_to_blue)].into()),
+            ),
+            ("Bounds", array(&[0.5])),
+            ("Encode", array(&[0.0, 1.0, 1.0, 0.0])),
+        ]));
+        let f = PDFFunction::parse(&stitched, &mut xref()).unwrap();
+        assert_close(&f.eval(&[0.25]), &[0.5, 0.0, 0.5]);
+        assert_close(&f.eval(&[0.5]), &[0.0, 0.0, 1.0]);
+        assert_close(&f.eval(&[1.0]), &[1.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_sampled() {
+        // 2x2 grid of 8-bit samples with one output: 0, 255 / 255, 0
+        let f = PDFObject::Stream {
+            dict: dict(&[
+                ("FunctionType", PDFObject::Number(0.0)),
+                ("Domain", array(&[0.0, 1.0, 0.0, 1.0])),
+                ("Range", array(&[0.0, 1.0])),
+                ("Size", array(&[2.0, 2.0])),
+                ("BitsPerSample", PDFObject::Number(8.0)),
+            ]),
+            data: vec![0, 255, 255, 0],
+        };
+        let f = PDFFunction::parse(&f, &mut xref()).unwrap();
+        assert_close(&f.eval(&[0.0, 0.0]), &[0.0]);
+        assert_close(&f.eval(&[1.0, 0.0]), &[1.0]);
+        assert_close(&f.eval(&[0.5, 0.5]), &[0.5]);
+        assert_close(&f.eval(&[0.25, 0.0]), &[0.25]);
+
+        assert_eq!(
+            read_samples(&[0xAB, 0xCD, 0xEF], 12, 2),
+            vec![0xABC as f64 / 4095.0, 0xDEF as f64 / 4095.0]
+        );
+        // A /Size far larger than the data holds only what the data has
+        assert_eq!(read_samples(&[0xFF], 8, 1 << 40).len(), 1);
+
+        let huge = PDFObject::Stream {
+            dict: dict(&[
+                ("FunctionType", PDFObject::Number(0.0)),
+                ("Domain", array(&[0.0, 1.0, 0.0, 1.0])),
+                ("Range", array(&[0.0, 1.0, 0.0, 1.0])),
+                ("Size", array(&[1e300, 1e300])),
+                ("BitsPerSample", PDFObject::Number(8.0)),
+            ]),
+            data: vec![0, 255],
+        };
+        assert!(matches!(
+            PDFFunction::parse(&huge, &mut xref()),
+            Err(PDFError::CorruptedPDF { .. })
+        ));
+    }
+
+    #[test]
+    fn test_postscript() {
+        let f = PDFObject::Stream {
+            dict: dict(&[
+                ("FunctionType", PDFObject::Number(4.0)),
+                ("Domain", array(&[0.0, 1.0])),
+                ("Range", array(&[0.0, 1.0, 0.0, 1.0])),
+            ]),
+            data: b"{ dup 0.5 gt { 1 exch sub } { 2 mul } ifelse dup }".to_vec(),
+        };
+        let f = PDFFunction::parse(&f, &mut xref()).unwrap();
+        assert_close(&f.eval(&[0.25]), &[0.5, 0.5]);
+        assert_close(&f.eval(&[0.75]), &[0.25, 0.25]);
+
+        let mut stack = vec![PsValue::Number(7.0), PsValue::Number(2.0)];
+        run_ps(
+            &parse_ps_program("{ idiv 1 2 bitshift 1 index 5 3 eq not }").unwrap(),
+            &mut stack,
+        )
+        .unwrap();
+        assert_eq!(
+            stack,
+            vec![
+                PsValue::Number(3.0),
+                PsValue::Number(4.0),
+                PsValue::Number(3.0),
+                PsValue::Bool(true),
+            ]
+        );
+        assert!(parse_ps_program("{ 1 { 2 }").is_err());
+    }
+}
@@ -0,0 +1,448 @@
+//! Hint tables of linearized PDFs (PDF 32000-1:2008, Annex F).
+//!
+//! The primary hint stream (/H in the linearization dictionary) holds a page
+//! offset hint table, which gives the byte range of every page's objects, and
+//! a shared object hint table, which gives the byte ranges of the objects
+//! several pages use (fonts, images, ...). Together they tell a progressive
+//! loader exactly which ranges to fetch for a page, without walking the page
+//! tree.
+//!
+//! Offsets in the tables are written as if the hint stream were absent, so
+//! every offset at or after the hint stream is shifted by its length.
+
+use super::error::{PDFError, PDFResult};
+
+/// Location of one page's objects.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageHint {
+    /// Offset of the page object, which starts the page's objects
+    pub offset: u64,
+
+    /// Length in bytes of the page's objects
+    pub length: u64,
+
+    /// Number of objects belonging to the page
+    pub object_count: u32,
+
+    /// Indices into [`HintTables::shared_groups`] of the shared objects the
+    /// page uses
+    pub shared_groups: Vec<usize>,
+}
+
+/// Location of a group of shared objects.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SharedObjectGroup {
+    /// Offset of the group's first object
+    pub offset: u64,
+
+    /// Length in bytes of the group
+    pub length: u64,
+
+    /// Number of objects in the group
+    pub object_count: u32,
+}
+
+/// The decoded page offset and shared object hint tables.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HintTables {
+    /// One entry per page, in page order
+    pub pages: Vec<PageHint>,
+
+    /// Shared object groups: first the groups of the first page's objects,
+    /// then those of the shared objects section
+    pub shared_groups: Vec<SharedObjectGroup>,
+}
+
+/// Reads big-endian bit fields, most significant bit first.
+struct BitReader<'a> {
+    data: &'a [u8],
+    bit_pos: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8], byte_pos: usize) -> Self {
+        BitReader {
+            data,
+            bit_pos: byte_pos * 8,
+        }
+    }
+
+    fn read(&mut self, bits: u32) -> PDFResult<u64> {
+        if bits > 64 {
+            return Err(PDFError::Generic(format!(
+                "Hint table field of {} bits is too wide",
+                bits
+            )));
+        }
+        let mut value = 0u64;
+        for _ in 0..bits {
+            let byte = self.data.get(self.bit_pos / 8).ok_or_else(|| {
+                PDFError::Generic("Hint table extends past the end of the hint stream".to_string())
+            })?;
+            let bit = (byte >> (7 - self.bit_pos % 8)) & 1;
+            value = (value << 1) | bit as u64;
+            self.bit_pos += 1;
+        }
+        Ok(value)
+    }
+
+    /// Reads a field whose width was itself read from the table header.
+    fn read_bits(&mut self, bits: u64) -> PDFResult<u64> {
+        self.read(bits.min(u32::MAX as u64) as u32)
+    }
+
+    /// Reads a field stored as a difference from the table's minimum.
+    fn read_delta(&mut self, min: u64, bits: u64) -> PDFResult<u64> {
+        min.checked_add(self.read_bits(bits)?)
+            .ok_or_else(|| PDFError::corrupted_pdf("Hint table value overflows"))
+    }
+
+    /// Each item of a hint table starts at a byte boundary.
+    fn align(&mut self) {
+        self.bit_pos = self.bit_pos.div_ceil(8) * 8;
+    }
+}
+
+impl HintTables {
+    /// Decodes the hint tables from the data of the primary hint stream.
+    ///
+    /// # Arguments
+    /// * `data` - Decoded hint stream data
+    /// * `shared_table_offset` - Offset of the shared object hint table in
+    ///   `data` (/S of the hint stream)
+    /// * `page_count` - Number of pages (/N of the linearization dictionary)
+    /// * `hint_offset`, `hint_length` - Location of the hint stream in the
+    ///   file (/H of the linearization dictionary)
+    pub fn parse(
+        data: &[u8],
+        shared_table_offset: usize,
+        page_count: usize,
+        hint_offset: u64,
+        hint_length: u64,
+    ) -> PDFResult<Self> {
+        let overflow = || PDFError::corrupted_pdf("Hint table offsets overflow");
+        let adjust = |offset: u64| {
+            if offset >= hint_offset {
+                offset.checked_add(hint_length).ok_or_else(overflow)
+            } else {
+                Ok(offset)
+            }
+        };
+        // Every item takes at least one bit unless its field width is zero,
+        // so no valid table describes more items than the stream has bits
+        let max_items = data.len().saturating_mul(8);
+        if page_count > max_items {
+            return Err(PDFError::corrupted_pdf(format!(
+                "Hint stream of {} bytes cannot describe {} pages",
+                data.len(),
+                page_count
+            )));
+        }
+
+        // Page offset hint table header (Table F.3)
+        let mut reader = BitReader::new(data, 0);
+        let min_objects = reader.read(32)?;
+        let first_page_offset = reader.read(32)?;
+        let objects_bits = reader.read(16)?;
+        let min_page_length = reader.read(32)?;
+        let page_length_bits = reader.read(16)?;
+        let _min_content_offset = reader.read(32)?;
+        let content_offset_bits = reader.read(16)?;
+        let _min_content_length = reader.read(32)?;
+        let content_length_bits = reader.read(16)?;
+        let shared_refs_bits = reader.read(16)?;
+        let shared_id_bits = reader.read(16)?;
+        let numerator_bits = reader.read(16)?;
+        let _denominator = reader.read(16)?;
+
+        // Per-page entries (Table F.4), stored item by item
+        let mut object_counts = Vec::with_capacity(page_count);
+        for _ in 0..page_count {
+            object_counts.push(reader.read_delta(min_objects, objects_bits)?);
+        }
+        reader.align();
+
+        let mut page_lengths = Vec::with_capacity(page_count);
+        for _ in 0..page_count {
+            page_lengths.push(reader.read_delta(min_page_length, page_length_bits)?);
+        }
+        reader.align();
+
+        let mut shared_ref_counts = Vec::with_capacity(page_count);
+        for _ in 0..page_count {
+            let count = reader.read_bits(shared_refs_bits)?;
+            if count > max_items as u64 {
+                return Err(PDFError::corrupted_pdf(format!(
+                    "Hint table lists {} shared objects for one page",
+                    count
+                )));
+            }
+            shared_ref_counts.push(count);
+        }
+        reader.align();
+
+        let mut shared_refs = Vec::with_capacity(page_count);
+        for &count in &shared_ref_counts {
+            let mut refs = Vec::new();
+            for _ in 0..count {
+                refs.push(reader.read_bits(shared_id_bits)? as usize);
+            }
+            shared_refs.push(refs);
+        }
+        reader.align();
+
+        // Fractional positions of the shared objects and the content stream
+        // offsets and lengths are only needed for incremental drawing
+        for &count in &shared_ref_counts {
+            for _ in 0..count {
+                reader.read_bits(numerator_bits)?;
+            }
+        }
+        reader.align();
+        for _ in 0..page_count {
+            reader.read_bits(content_offset_bits)?;
+        }
+        reader.align();
+        for _ in 0..page_count {
+            reader.read_bits(content_length_bits)?;
+        }
+
+        let mut pages = Vec::with_capacity(page_count);
+        let mut offset = first_page_offset;
+        for ((object_count, length), shared_groups) in
+            object_counts.into_iter().zip(page_lengths).zip(shared_refs)
+        {
+            pages.push(PageHint {
+                offset: adjust(offset)?,
+                length,
+                object_count: object_count as u32,
+                shared_groups,
+            });
+            offset = offset.checked_add(length).ok_or_else(overflow)?;
+        }
+
+        // Shared object hint table header (Table F.5)
+        let mut reader = BitReader::new(data, shared_table_offset);
+        let _first_shared_object = reader.read(32)?;
+        let first_shared_offset = reader.read(32)?;
+        let first_page_groups = reader.read(32)? as usize;
+        let total_groups = reader.read(32)? as usize;
+        let group_objects_bits = reader.read(16)?;
+        let min_group_length = reader.read(32)?;
+        let group_length_bits = reader.read(16)?;
+
+        if first_page_groups > total_groups || total_groups > max_items {
+            return Err(PDFError::Generic(format!(
+                "Invalid shared object hint table: {} first page groups of {}",
+                first_page_groups, total_groups
+            )));
+        }
+
+        // Shared object entries (Table F.6)
+        let mut group_lengths = Vec::with_capacity(total_groups);
+        for _ in 0..total_groups {
+            group_lengths.push(reader.read_delta(min_group_length, group_length_bits)?);
+        }
+        reader.align();
+
+        let mut signed = Vec::with_capacity(total_groups);
+        for _ in 0..total_groups {
+            signed.push(reader.read(1)? == 1);
+        }
+        reader.align();
+        for _ in signed.iter().filter(|&&signed| signed) {
+            // MD5 signature of the group
+            reader.read(64)?;
+            reader.read(64)?;
+        }
+        reader.align();
+
+        let mut shared_groups = Vec::with_capacity(total_groups);
+        // The first page's groups lie in the first page section, the others
+        // in the shared objects section
+        let mut offset = first_page_offset;
+        for (index, length) in group_lengths.into_iter().enumerate() {
+            if index == first_page_groups {
+                offset = first_shared_offset;
+            }
+            let object_count = reader.read_delta(1, group_objects_bits)?;
+            shared_groups.push(SharedObjectGroup {
+                offset: adjust(offset)?,
+                length,
+                object_count: object_count as u32,
+            });
+            offset = offset.checked_add(length).ok_or_else(overflow)?;
+        }
+
+        Ok(HintTables {
+            pages,
+            shared_groups,
+        })
+    }
+
+    /// Returns the byte ranges `(offset, length)` holding a page's objects and
+    /// the shared objects it uses, sorted and with adjacent ranges merged.
+    pub fn page_ranges(&self, page_index: usize) -> Vec<(u64, u64)> {
+        let Some(page) = self.pages.get(page_index) else {
+            return Vec::new();
+        };
+
+        let mut ranges = vec![(page.offset, page.length)];
+        ranges.extend(
+            page.shared_groups
+                .iter()
+                .filter_map(|&group| self.shared_groups.get(group))
+                .map(|group| (group.offset, group.length)),
+        );
+        ranges.sort_unstable();
+
+        let mut merged: Vec<(u64, u64)> = Vec::with_capacity(ranges.len());
+        for (offset, length) in ranges {
+            match merged.last_mut() {
+                Some((last_offset, last_length)) if offset <= *last_offset + *last_length => {
+                    *last_length = (*last_length).max(offset + length - *last_offset);
+                }
+                _ => merged.push((offset, length)),
+            }
+        }
+        merged
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Packs big-endian bit fields, as a linearizer would.
+    #[derive(Default)]
+    struct BitWriter {
+        data: Vec<u8>,
+        bits: usize,
+    }
+
+    impl BitWriter {
+        fn write(&mut self, value: u64, bits: u32) {
+            for i in (0..bits).rev() {
+                if self.bits.is_multiple_of(8) {
+                    self.data.push(0);
+                }
+                let bit = ((value >> i) & 1) as u8;
+                *self.data.last_mut().unwrap() |= bit << (7 - self.bits % 8);
+                self.bits += 1;
+            }
+        }
+
+        fn align(&mut self) {
+            self.bits = self.bits.div_ceil(8) * 8;
+        }
+    }
+
+    #[test]
+    fn test_parse_hint_tables() {
+        let mut w = BitWriter::default();
+        // Page offset header: 2 pages of 3 and 4 objects, 200 and 150
+        // bytes, the first one at 1000
+        for (value, bits) in [
+            (3, 32),
+            (1000, 32),
+            (1, 16),
+            (150, 32),
+            (8, 16),
+            (0, 32),
+            (0, 16),
+            (0, 32),
+            (0, 16),
+            (1, 16),
+            (1, 16),
+            (0, 16),
+            (1, 16),
+        ] {
+            w.write(value, bits);
+        }
+        w.write(0, 1);
+        w.write(1, 1);
+        w.align();
+        w.write(50, 8);
+        w.write(0, 8);
+        w.align();
+        // Only the second page uses a shared object: group 1
+        w.write(0, 1);
+        w.write(1, 1);
+        w.align();
+        w.write(1, 1);
+        w.align();
+        let shared_offset = w.data.len();
+
+        // Shared object header: one group for the first page, then one
+        // shared group of 2 objects and 80 bytes at 2000
+        for (value, bits) in [
+            (10, 32),
+            (2000, 32),
+            (1, 32),
+            (2, 32),
+            (1, 16),
+            (80, 32),
+            (8, 16),
+        ] {
+            w.write(value, bits);
+        }
+        w.write(120, 8);
+        w.write(0, 8);
+        w.align();
+        w.write(0, 1);
+        w.write(1, 1);
+        w.align();
+        w.write(u64::MAX, 64);
+        w.write(u64::MAX, 64);
+        w.align();
+        w.write(0, 1);
+        w.write(1, 1);
+
+        // A 100 byte hint stream at 1100 shifts everything after it
+        let hints = HintTables::parse(&w.data, shared_offset, 2, 1100, 100).unwrap();
+
+        assert_eq!(hints.pages.len(), 2);
+        assert_eq!(hints.pages[0].offset, 1000);
+        assert_eq!(hints.pages[0].length, 200);
+        assert_eq!(hints.pages[0].object_count, 3);
+        assert!(hints.pages[0].shared_groups.is_empty());
+        assert_eq!(hints.pages[1].offset, 1300);
+        assert_eq!(hints.pages[1].length, 150);
+        assert_eq!(hints.pages[1].object_count, 4);
+        assert_eq!(hints.pages[1].shared_groups, [1]);
+
+        assert_eq!(hints.shared_groups.len(), 2);
+        assert_eq!(hints.shared_groups[1].offset, 2100);
+        assert_eq!(hints.shared_groups[1].length, 80);
+        assert_eq!(hints.shared_groups[1].object_count, 2);
+
+        assert_eq!(hints.page_ranges(1), [(1300, 150), (2100, 80)]);
+        assert!(hints.page_ranges(2).is_empty());
+    }
+
+    #[test]
+    fn test_parse_truncated_hint_table() {
+        assert!(HintTables::parse(&[0; 20], 0, 1, 0, 0).is_err());
+        // More pages than the stream has bits
+        assert!(matches!(
+            HintTables::parse(&[0; 64], 0, usize::MAX, 0, 0),
+            Err(PDFError::CorruptedPDF { .. })
+        ));
+
+        // A page length delta that overflows the minimum it is added to
+        let mut w = BitWriter::default();
+        for (value, bits) in [(0, 32), (0, 32), (0, 16), (u32::MAX as u64, 32), (64, 16)] {
+            w.write(value, bits);
+        }
+        // Content offsets and lengths, shared object and numerator widths
+        for _ in 0..5 {
+            w.write(0, 32);
+        }
+        w.write(u64::MAX, 64);
+        w.write(0, 64);
+        assert!(matches!(
+            HintTables::parse(&w.data, 0, 1, 0, 0),
+            Err(PDFError::CorruptedPDF { .. })
+        ));
+    }
+}
@@ -10,7 +10,11 @@ use super::async_http_chunked_stream::AsyncHttpChunkedStream;
 #[cfg(feature = "async")]
 use super::base_stream::BaseStream;
 #[cfg(feature = "async")]
+use super::chunk_manager::ChunkedStreamOptions;
+#[cfg(feature = "async")]
 use super::error::{PDFError, PDFResult};
+#[cfg(feature = "async")]
+use std::sync::Arc;
 
 /// Synchronous HTTP chunked stream (wraps AsyncHttpChunkedStream with blocking runtime).
 ///
@@ -34,8 +38,8 @@ use super::error::{PDFError, PDFResult};
 pub struct HttpChunkedStream {
     /// The underlying async stream
     async_stream: AsyncHttpChunkedStream,
-    /// Tokio runtime for blocking operations
-    runtime: tokio::runtime::Runtime,
+    /// Tokio runtime for blocking operations (shared with sub-streams)
+    runtime: Arc<tokio::runtime::Runtime>,
 }
 
 #[cfg(feature = "async")]
@@ -52,17 +56,33 @@ impl HttpChunkedStream {
         url: impl Into<String>,
         chunk_size: Option<usize>,
         max_cached_chunks: Option<usize>,
+    ) -> PDFResult<Self> {
+        Self::open_with_options(
+            url,
+            ChunkedStreamOptions::from_chunk_counts(chunk_size, max_cached_chunks),
+        )
+    }
+
+    /// Creates a new HttpChunkedStream with the given chunking, readahead
+    /// and cache settings (blocking).
+    ///
+    /// # Arguments
+    /// * `url` - URL of the PDF file
+    /// * `options` - Chunk size, readahead and cache bound
+    pub fn open_with_options(
+        url: impl Into<String>,
+        options: ChunkedStreamOptions,
     ) -> PDFResult<Self> {
         let runtime = tokio::runtime::Runtime::new()
             .map_err(|e| PDFError::StreamError(format!("Failed to create runtime: {}", e)))?;
 
         let async_stream = runtime.block_on(async {
-            AsyncHttpChunkedStream::open(url, chunk_size, max_cached_chunks, None).await
+            AsyncHttpChunkedStream::open_with_options(url, options, None).await
         })?;
 
         Ok(HttpChunkedStream {
             async_stream,
-            runtime,
+            runtime: Arc::new(runtime),
         })
     }
 
@@ -83,6 +103,11 @@ impl HttpChunkedStream {
             .block_on(self.async_stream.preload_range(begin, end))
     }
 
+    /// Returns the chunk size in bytes.
+    pub fn chunk_size(&self) -> usize {
+        self.async_stream.chunk_size()
+    }
+
     /// Returns the number of chunks currently loaded in the cache.
     pub fn num_chunks_loaded(&self) -> usize {
         self.runtime.block_on(self.async_stream.num_chunks_loaded())
@@ -121,6 +146,10 @@ impl BaseStream for HttpChunkedStream {
         self.is_fully_loaded()
     }
 
+    fn ensure_range(&mut self, start: usize, length: usize) -> PDFResult<()> {
+        self.preload_range(start, start + length)
+    }
+
     fn get_byte(&mut self) -> PDFResult<u8> {
         self.runtime.block_on(self.async_stream.get_byte())
     }
@@ -139,20 +168,11 @@ impl BaseStream for HttpChunkedStream {
             return Err(PDFError::InvalidByteRange { begin, end });
         }
 
-        // Create a temporary stream for async operation
-        // This is safe because we're in a blocking context
-        let temp_stream = AsyncHttpChunkedStream::open(
-            self.async_stream.url(),
-            Some(self.async_stream.num_chunks()),
-            Some(10),
-            None,
-        );
-
-        self.runtime.block_on(async {
-            let mut stream = temp_stream.await?;
-            stream.set_pos(begin)?;
-            stream.get_bytes(end - begin).await
-        })
+        // Read through a clone, which shares the chunk cache but has its own
+        // position
+        let mut stream = self.async_stream.clone();
+        stream.set_pos(begin)?;
+        self.runtime.block_on(stream.get_bytes(end - begin))
     }
 
     fn reset(&mut self) -> PDFResult<()> {
@@ -172,9 +192,11 @@ impl BaseStream for HttpChunkedStream {
             });
         }
 
-        // Create a new stream sharing the same URL
-        let mut new_stream =
-            HttpChunkedStream::open(self.url(), Some(self.num_chunks()), Some(10))?;
+        // Share the client, chunk cache and runtime with the new stream
+        let mut new_stream = HttpChunkedStream {
+            async_stream: self.async_stream.clone(),
+            runtime: Arc::clone(&self.runtime),
+        };
         new_stream.set_pos(start)?;
 
         let sub = super::sub_stream::SubStream::new(Box::new(new_stream), start, length)?;
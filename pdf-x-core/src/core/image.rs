@@ -10,7 +10,9 @@
 //! 1. Image metadata extraction (always available) - get image info without full decoding
 //! 2. Complete image decoding (feature-gated) - full image data when requested
 
+use super::colorspace::ColorSpace;
 use super::error::{PDFError, PDFResult};
+use super::limits::ResourceLimits;
 
 /// Image format types supported by PDF-X.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -60,6 +62,89 @@ impl ImageFormat {
     }
 }
 
+/// Result of classifying an XObject stream as an image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImageDetection {
+    /// The stream declares /Subtype /Image
+    Declared,
+    /// Lenient mode inferred an image despite a missing or nonstandard
+    /// /Subtype; carries a description for the recorded warning
+    Inferred(String),
+    /// Not an image (a form XObject or an unrecognized stream)
+    NotImage,
+}
+
+impl ImageDetection {
+    /// Classifies an XObject stream.
+    ///
+    /// A stream with /Subtype /Image is always an image and one with /Subtype
+    /// /Form never is. In lenient mode, other streams are treated as images if
+    /// they carry /Width and /Height together with /BitsPerComponent,
+    /// /ImageMask or /ColorSpace (abbreviations included), put /Image in /Type,
+    /// or if their data starts with a JPEG, JPEG2000 or PNG signature.
+    ///
+    /// # Arguments
+    /// * `dict` - The stream dictionary
+    /// * `data` - The raw (still encoded) stream data
+    /// * `lenient` - Whether to infer image-ness from other keys and the data
+    pub fn classify(
+        dict: &std::collections::HashMap<String, super::PDFObject>,
+        data: &[u8],
+        lenient: bool,
+    ) -> Self {
+        use super::PDFObject;
+
+        let subtype = match dict.get("Subtype") {
+            Some(PDFObject::Name(name)) => Some(name.as_str()),
+            _ => None,
+        };
+        match subtype {
+            Some("Image") => return ImageDetection::Declared,
+            Some("Form") => return ImageDetection::NotImage,
+            _ if !lenient => return ImageDetection::NotImage,
+            _ => {}
+        }
+        // Forms always have a /BBox; images never do
+        if dict.contains_key("BBox") {
+            return ImageDetection::NotImage;
+        }
+
+        let found = match subtype {
+            Some(name) => format!("/Subtype /{}", name),
+            None => "no /Subtype".to_string(),
+        };
+        let has = |long: &str, short: &str| dict.contains_key(long) || dict.contains_key(short);
+
+        if has("Width", "W")
+            && has("Height", "H")
+            && (has("BitsPerComponent", "BPC") || has("ImageMask", "IM") || has("ColorSpace", "CS"))
+        {
+            return ImageDetection::Inferred(format!(
+                "XObject with {} treated as an image: it has /Width and /Height",
+                found
+            ));
+        }
+        if matches!(dict.get("Type"), Some(PDFObject::Name(name)) if name == "Image") {
+            return ImageDetection::Inferred(format!(
+                "XObject with {} treated as an image: /Type is /Image",
+                found
+            ));
+        }
+        match ImageFormat::from_header(data) {
+            ImageFormat::Unknown => ImageDetection::NotImage,
+            format => ImageDetection::Inferred(format!(
+                "XObject with {} treated as an image: data has a {:?} signature",
+                found, format
+            )),
+        }
+    }
+
+    /// Returns true if the stream is (or is treated as) an image.
+    pub fn is_image(&self) -> bool {
+        !matches!(self, ImageDetection::NotImage)
+    }
+}
+
 /// Image metadata extracted from XObject headers.
 #[derive(Debug, Clone)]
 pub struct ImageMetadata {
@@ -146,6 +231,60 @@ impl DecodedImage {
             metadata,
         }
     }
+
+    /// Convert the pixels to 8-bit RGBA
+    ///
+    /// Gray, gray with alpha, RGB and CMYK samples are expanded; RGBA images
+    /// are returned unchanged.
+    ///
+    /// # Returns
+    /// The image with `width * height * 4` bytes of RGBA data, or an error
+    /// for other channel layouts
+    pub fn into_rgba(mut self) -> PDFResult<DecodedImage> {
+        let (width, height) = (self.width as usize, self.height as usize);
+        let data = match (&self.color_space, self.channels) {
+            (ImageColorSpace::RGBA, 4) => return Ok(self),
+            (ImageColorSpace::CMYK, 4) => {
+                let rgb = ColorSpace::DeviceCMYK.image_to_rgb(&self.data, width, height, 8, None);
+                rgb.chunks_exact(3)
+                    .flat_map(|p| [p[0], p[1], p[2], 255])
+                    .collect()
+            }
+            (_, 1) => self.data.iter().flat_map(|&g| [g, g, g, 255]).collect(),
+            (_, 2) => self
+                .data
+                .chunks_exact(2)
+                .flat_map(|p| [p[0], p[0], p[0], p[1]])
+                .collect(),
+            (_, 3) => self
+                .data
+                .chunks_exact(3)
+                .flat_map(|p| [p[0], p[1], p[2], 255])
+                .collect(),
+            (color_space, channels) => {
+                return Err(PDFError::Unsupported {
+                    feature: format!(
+                        "RGBA conversion of {:?} images with {} channels",
+                        color_space, channels
+                    ),
+                });
+            }
+        };
+
+        self.data = data;
+        self.channels = 4;
+        self.color_space = ImageColorSpace::RGBA;
+        self.metadata.has_alpha = true;
+        Ok(self)
+    }
+}
+
+/// Multiplies image dimensions, failing on sizes that cannot be addressed.
+fn image_size(factors: &[usize]) -> PDFResult<usize> {
+    factors
+        .iter()
+        .try_fold(1usize, |size, &factor| size.checked_mul(factor))
+        .ok_or_else(|| PDFError::corrupted_pdf("Image dimensions are too large"))
 }
 
 /// Image decoder following hayro's specialized approach.
@@ -202,11 +341,12 @@ impl ImageDecoder {
             ImageColorSpace::Unknown(n) => n,
         };
 
-        let expected_size = (width as usize)
-            * (height as usize)
-            * (channels as usize)
-            * (bits_per_component as usize)
-            / 8;
+        let expected_size = image_size(&[
+            width as usize,
+            height as usize,
+            channels as usize,
+            bits_per_component as usize,
+        ])? / 8;
 
         if data.len() < expected_size {
             return Err(PDFError::Generic(format!(
@@ -227,12 +367,277 @@ impl ImageDecoder {
             data_length: Some(data.len()),
         };
 
-        // For raw data, just take the expected amount
-        Ok(DecodedImage::new(
-            metadata,
-            data[..expected_size].to_vec(),
-            channels,
-        ))
+        // For raw data, just take the expected amount. The channel count
+        // alone would take CMYK for RGBA, so keep the given color space
+        let mut image = DecodedImage::new(metadata, data[..expected_size].to_vec(), channels);
+        image.color_space = color_space;
+        Ok(image)
+    }
+
+    /// Decode raw samples in any PDF color space
+    ///
+    /// 8-bit DeviceGray and DeviceRGB samples without a /Decode array are
+    /// kept as they are. Everything else (CMYK, Indexed palettes,
+    /// Separation, Lab, other bit depths and /Decode remapping) is
+    /// converted to 8-bit RGB.
+    ///
+    /// # Arguments
+    /// * `data` - Samples, with each row starting on a byte boundary
+    /// * `width`, `height` - Image size in pixels
+    /// * `bits_per_component` - 1, 2, 4, 8 or 16
+    /// * `color_space` - The parsed /ColorSpace of the image
+    /// * `decode` - /Decode array, or `None` for the default
+    /// * `limits` - The document's limits; the converted RGB samples may not
+    ///   exceed its stream size limit
+    pub fn decode_raw_image_in(
+        data: &[u8],
+        width: u32,
+        height: u32,
+        bits_per_component: u8,
+        color_space: &ColorSpace,
+        decode: Option<&[f64]>,
+        limits: &ResourceLimits,
+    ) -> PDFResult<DecodedImage> {
+        match color_space {
+            ColorSpace::DeviceGray if bits_per_component == 8 && decode.is_none() => {
+                Self::decode_raw_image(data, width, height, 8, ImageColorSpace::Gray)
+            }
+            ColorSpace::DeviceRGB if bits_per_component == 8 && decode.is_none() => {
+                Self::decode_raw_image(data, width, height, 8, ImageColorSpace::RGB)
+            }
+            _ => {
+                let components = color_space.components().max(1);
+                let row_bytes =
+                    image_size(&[width as usize, components, bits_per_component as usize])?
+                        .div_ceil(8);
+                let expected_size = image_size(&[row_bytes, height as usize])?;
+                if data.len() < expected_size {
+                    return Err(PDFError::Generic(format!(
+                        "Insufficient raw image data: expected at least {} bytes, got {}",
+                        expected_size,
+                        data.len()
+                    )));
+                }
+                // A small palette image can expand many times over
+                if image_size(&[width as usize, height as usize, 3])? > limits.max_stream_size {
+                    return Err(PDFError::limit_exceeded(
+                        "decoded image size",
+                        limits.max_stream_size,
+                    ));
+                }
+
+                let rgb = color_space.image_to_rgb(
+                    data,
+                    width as usize,
+                    height as usize,
+                    bits_per_component,
+                    decode,
+                );
+                Self::decode_raw_image(&rgb, width, height, 8, ImageColorSpace::RGB)
+            }
+        }
+    }
+
+    /// Decode a JPXDecode (JPEG2000) image for use in a PDF
+    ///
+    /// The codestream's components are reconciled with the image
+    /// dictionary: 1, 3 and 4 components are Gray, RGB and CMYK, unless the
+    /// dictionary's /ColorSpace (which takes precedence over the one in the
+    /// codestream) needs converting, e.g. Indexed or Lab, in which case the
+    /// samples become RGB. An alpha channel in the codestream is only kept
+    /// when /SMaskInData is non-zero; the image is then RGBA.
+    ///
+    /// # Arguments
+    /// * `data` - The JPEG2000 codestream or JP2 file
+    /// * `color_space` - The parsed /ColorSpace of the image dictionary, if any
+    /// * `smask_in_data` - Whether /SMaskInData asks for the codestream's alpha
+    pub fn decode_jpx(
+        data: &[u8],
+        color_space: Option<&ColorSpace>,
+        smask_in_data: bool,
+    ) -> PDFResult<DecodedImage> {
+        let image = Self::decode_image(data, ImageFormat::JPEG2000)?;
+        Self::reconcile_jpx(image, color_space, smask_in_data)
+    }
+
+    /// Maps decoded JPEG2000 samples to the PDF color space (see
+    /// [`ImageDecoder::decode_jpx`]).
+    fn reconcile_jpx(
+        mut image: DecodedImage,
+        color_space: Option<&ColorSpace>,
+        smask_in_data: bool,
+    ) -> PDFResult<DecodedImage> {
+        let pixels = image.width as usize * image.height as usize;
+        if pixels == 0 {
+            return Ok(image);
+        }
+        let channels = image.data.len() / pixels;
+        if channels == 0 {
+            return Err(PDFError::corrupted_pdf(format!(
+                "JPEG2000 image of {} pixels decoded to only {} bytes",
+                pixels,
+                image.data.len()
+            )));
+        }
+        let has_alpha = image.metadata.has_alpha && channels > 1;
+        let components = channels - has_alpha as usize;
+
+        // Split off the alpha channel
+        let mut samples = Vec::with_capacity(pixels * components);
+        let mut alpha = Vec::with_capacity(if has_alpha { pixels } else { 0 });
+        for pixel in image.data.chunks_exact(channels) {
+            samples.extend_from_slice(&pixel[..components]);
+            if has_alpha {
+                alpha.push(pixel[components]);
+            }
+        }
+
+        let device = |components: usize| match components {
+            1 => Some(ImageColorSpace::Gray),
+            3 => Some(ImageColorSpace::RGB),
+            4 => Some(ImageColorSpace::CMYK),
+            _ => None,
+        };
+        let (samples, color_space) = match color_space {
+            Some(
+                ColorSpace::DeviceGray
+                | ColorSpace::DeviceRGB
+                | ColorSpace::DeviceCMYK
+                | ColorSpace::ICCBased { .. },
+            )
+            | None => match device(components) {
+                Some(device) => (samples, device),
+                None => (samples, ImageColorSpace::Unknown(components as u8)),
+            },
+            Some(color_space) if color_space.components() == components => {
+                let rgb = color_space.image_to_rgb(
+                    &samples,
+                    image.width as usize,
+                    image.height as usize,
+                    8,
+                    None,
+                );
+                (rgb, ImageColorSpace::RGB)
+            }
+            Some(color_space) => {
+                tracing::warn!(
+                    target: crate::log_target::IMAGE,
+                    "JPEG2000 image has {} components but /ColorSpace {:?} needs {}",
+                    components,
+                    color_space,
+                    color_space.components()
+                );
+                (
+                    samples,
+                    device(components).unwrap_or(ImageColorSpace::Unknown(components as u8)),
+                )
+            }
+        };
+
+        // The alpha channel is only used with /SMaskInData; it is then put
+        // back onto RGB samples
+        if has_alpha && smask_in_data {
+            let rgb = match color_space {
+                ImageColorSpace::Gray => ColorSpace::DeviceGray.image_to_rgb(
+                    &samples,
+                    image.width as usize,
+                    image.height as usize,
+                    8,
+                    None,
+                ),
+                ImageColorSpace::CMYK => ColorSpace::DeviceCMYK.image_to_rgb(
+                    &samples,
+                    image.width as usize,
+                    image.height as usize,
+                    8,
+                    None,
+                ),
+                ImageColorSpace::RGB => samples,
+                _ => {
+                    image.data = samples;
+                    image.channels = components as u8;
+                    image.color_space = color_space;
+                    image.metadata.has_alpha = false;
+                    return Ok(image);
+                }
+            };
+            image.data = rgb
+                .chunks_exact(3)
+                .zip(alpha)
+                .flat_map(|(rgb, a)| [rgb[0], rgb[1], rgb[2], a])
+                .collect();
+            image.channels = 4;
+            image.color_space = ImageColorSpace::RGBA;
+        } else {
+            image.channels = match color_space {
+                ImageColorSpace::RGB => 3,
+                ImageColorSpace::Gray => 1,
+                _ => components as u8,
+            };
+            image.data = samples;
+            image.color_space = color_space;
+            image.metadata.has_alpha = false;
+        }
+        image.metadata.bits_per_component = 8;
+        Ok(image)
+    }
+
+    /// Expand a stencil mask (/ImageMask true) to RGBA
+    ///
+    /// Samples of 0 are painted with `fill` and samples of 1 are left
+    /// transparent; a /Decode array of `[1 0]` swaps the two.
+    ///
+    /// # Arguments
+    /// * `data` - 1-bit samples, with each row starting on a byte boundary
+    /// * `width`, `height` - Mask size in pixels
+    /// * `decode` - /Decode array, or `None` for `[0 1]`
+    /// * `fill` - RGB color to paint with (the current fill color)
+    pub fn decode_image_mask(
+        data: &[u8],
+        width: u32,
+        height: u32,
+        decode: Option<&[f64]>,
+        fill: [u8; 3],
+    ) -> PDFResult<DecodedImage> {
+        let row_bytes = (width as usize).div_ceil(8);
+        let expected_size = image_size(&[row_bytes, height as usize])?;
+        if data.len() < expected_size {
+            return Err(PDFError::Generic(format!(
+                "Insufficient image mask data: expected at least {} bytes, got {}",
+                expected_size,
+                data.len()
+            )));
+        }
+
+        let paint_bit = match decode {
+            Some([first, ..]) if *first >= 0.5 => 1,
+            _ => 0,
+        };
+        let painted = [fill[0], fill[1], fill[2], 255];
+
+        let mut rgba = Vec::with_capacity(image_size(&[width as usize, height as usize, 4])?);
+        for row in data.chunks(row_bytes.max(1)).take(height as usize) {
+            for x in 0..width as usize {
+                let bit = (row[x / 8] >> (7 - x % 8)) & 1;
+                if bit == paint_bit {
+                    rgba.extend_from_slice(&painted);
+                } else {
+                    rgba.extend_from_slice(&[0, 0, 0, 0]);
+                }
+            }
+        }
+
+        let metadata = ImageMetadata {
+            name: "ImageMask".to_string(),
+            format: ImageFormat::Raw,
+            width,
+            height,
+            bits_per_component: 8,
+            color_space: format!("{:?}", ImageColorSpace::RGBA),
+            has_alpha: true,
+            data_length: Some(data.len()),
+        };
+        Ok(DecodedImage::new(metadata, rgba, 4))
     }
 
     /// Parse color space from PDF object
@@ -454,3 +859,91 @@ pub trait ImageExtraction {
     /// Extract complete images with full decoding.
     fn extract_images(&self) -> PDFResult<Vec<DecodedImage>>;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn jpx_output(data: Vec<u8>, width: u32, channels: u8, has_alpha: bool) -> DecodedImage {
+        let mut metadata = ImageMetadata::new("JPEG2000".to_string(), ImageFormat::JPEG2000);
+        metadata.width = width;
+        metadata.height = 1;
+        metadata.has_alpha = has_alpha;
+        DecodedImage::new(metadata, data, channels)
+    }
+
+    #[test]
+    fn test_reconcile_jpx_components() {
+        // Four components are CMYK; the alpha channel is dropped without
+        // /SMaskInData
+        let cmyka = jpx_output(vec![0, 0, 0, 0, 128], 1, 5, true);
+        let image = ImageDecoder::reconcile_jpx(cmyka.clone(), None, false).unwrap();
+        assert_eq!(image.color_space, ImageColorSpace::CMYK);
+        assert_eq!((image.channels, image.data), (4, vec![0, 0, 0, 0]));
+
+        // ...and kept on RGB samples with it
+        let image = ImageDecoder::reconcile_jpx(cmyka, None, true).unwrap();
+        assert_eq!(image.color_space, ImageColorSpace::RGBA);
+        assert_eq!(image.data, [255, 255, 255, 128]);
+
+        // A one-component codestream in an Indexed space goes through the
+        // palette
+        let indexed = ColorSpace::Indexed {
+            base: Box::new(ColorSpace::DeviceRGB),
+            hival: 1,
+            lookup: vec![255, 0, 0, 0, 0, 255],
+        };
+        let image =
+            ImageDecoder::reconcile_jpx(jpx_output(vec![1, 0], 2, 1, false), Some(&indexed), false)
+                .unwrap();
+        assert_eq!(image.color_space, ImageColorSpace::RGB);
+        assert_eq!(
+            (image.channels, image.data),
+            (3, vec![0, 0, 255, 255, 0, 0])
+        );
+
+        // Fewer bytes than pixels is a broken codestream, not zero channels
+        assert!(matches!(
+            ImageDecoder::reconcile_jpx(jpx_output(vec![0], 2, 1, false), None, false),
+            Err(PDFError::CorruptedPDF { .. })
+        ));
+    }
+
+    #[test]
+    fn test_image_size_overflow() {
+        assert!(matches!(
+            ImageDecoder::decode_raw_image(&[], u32::MAX, u32::MAX, 16, ImageColorSpace::CMYK),
+            Err(PDFError::CorruptedPDF { .. })
+        ));
+        assert!(image_size(&[usize::MAX, 2]).is_err());
+
+        // 1-bit palette samples grow 24 times when converted to RGB
+        let indexed = ColorSpace::Indexed {
+            base: Box::new(ColorSpace::DeviceRGB),
+            hival: 1,
+            lookup: vec![255, 0, 0, 0, 0, 255],
+        };
+        let limits = ResourceLimits {
+            max_stream_size: 1024,
+            ..ResourceLimits::default()
+        };
+        let data = vec![0u8; 8 * 64];
+        assert!(matches!(
+            ImageDecoder::decode_raw_image_in(&data, 64, 64, 1, &indexed, None, &limits),
+            Err(PDFError::LimitExceeded { .. })
+        ));
+        assert!(
+            ImageDecoder::decode_raw_image_in(
+                &data,
+                64,
+                64,
+                1,
+                &indexed,
+                None,
+                &ResourceLimits::default()
+            )
+            .is_ok()
+        );
+        assert_eq!(image_size(&[3, 4, 5]).unwrap(), 60);
+    }
+}
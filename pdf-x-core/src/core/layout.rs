@@ -0,0 +1,750 @@
+//! Assembly of extracted text items into lines and words.
+//!
+//! Text items carry a baseline position and font size but no glyph widths,
+//! so line breaks and word spaces are inferred from geometry: items whose
+//! baselines are close (relative to the font size) share a line, and a space
+//! is inserted when the horizontal gap between two items is wide compared to
+//! an average glyph.
+//!
+//! [`layout_reading_order`] additionally groups lines into blocks and orders
+//! the blocks with a recursive XY-cut, so multi-column pages are read column
+//! by column.
+//!
+//! [`extract_lines`] and [`extract_words`] split the same lines into words
+//! with bounding boxes, using the per-character extents measured from the
+//! font's glyph widths when they are available.
+//!
+//! Based on PDF.js src/core/evaluator.js (buildTextContentItem).
+
+use super::content_stream::TextItem;
+use std::cmp::Ordering;
+
+/// Font size used when a text item doesn't specify one.
+pub(crate) const DEFAULT_FONT_SIZE: f64 = 12.0;
+
+/// Thresholds used to turn positioned text items into lines and words.
+///
+/// All values are relative to the font size, so the same options work for
+/// dense 6pt footnotes and 72pt display type.
+///
+/// # Example
+/// ```no_run
+/// use pdf_x_core::core::{PDFDocument, TextLayoutOptions};
+///
+/// let mut doc = PDFDocument::open_file("document.pdf", None, None).unwrap();
+/// let options = TextLayoutOptions {
+///     space_threshold: 0.5,
+///     ..TextLayoutOptions::default()
+/// };
+/// let text = doc
+///     .extract_text_from_page_as_string_with_options(0, &options)
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextLayoutOptions {
+    /// Maximum baseline shift, as a fraction of the font size, for two items
+    /// to be placed on the same line
+    pub line_threshold: f64,
+
+    /// Minimum horizontal gap, in average glyph widths, for a space to be
+    /// inserted between two items on the same line
+    pub space_threshold: f64,
+
+    /// Estimated glyph width as a fraction of the font size, used to work out
+    /// where an item ends
+    pub average_glyph_width: f64,
+
+    /// Minimum horizontal gap, as a fraction of the font size, that splits a
+    /// line into separate blocks (e.g. the gutter between columns). Only used
+    /// by [`layout_reading_order`].
+    pub column_gap: f64,
+
+    /// Maximum distance between consecutive baselines, as a fraction of the
+    /// font size, for two lines to belong to the same block. Only used by
+    /// [`layout_reading_order`].
+    pub block_line_spacing: f64,
+}
+
+impl Default for TextLayoutOptions {
+    fn default() -> Self {
+        TextLayoutOptions {
+            line_threshold: 0.5,
+            space_threshold: 0.3,
+            average_glyph_width: 0.5,
+            column_gap: 1.0,
+            block_line_spacing: 1.6,
+        }
+    }
+}
+
+/// Joins text items into a string, top to bottom and left to right.
+///
+/// Items are grouped into lines by baseline, lines are separated by `\n`,
+/// and items within a line are ordered by x and joined with a space when the
+/// gap between them exceeds `options.space_threshold`. Items without a
+/// position are appended at the end, in their original order.
+///
+/// # Arguments
+/// * `items` - Text items as produced by text extraction
+/// * `options` - Line and space thresholds
+///
+/// # Returns
+/// The page text
+pub fn layout_text(items: Vec<TextItem>, options: &TextLayoutOptions) -> String {
+    let (positioned, unpositioned): (Vec<TextItem>, Vec<TextItem>) =
+        items.into_iter().partition(|item| item.position.is_some());
+
+    let mut result = String::new();
+    for (index, line) in group_lines(positioned, options).into_iter().enumerate() {
+        if index > 0 {
+            result.push('\n');
+        }
+        result.push_str(&join_line(&line, options));
+    }
+
+    for item in unpositioned {
+        result.push_str(&item.text);
+    }
+
+    result
+}
+
+/// Joins text items into a string in reading order.
+///
+/// Lines are split into fragments at gaps wider than `options.column_gap`,
+/// fragments are grouped into blocks of vertically close, horizontally
+/// overlapping lines, and the blocks are ordered by a recursive XY-cut that
+/// splits the page at its widest gap each time: columns are read left to
+/// right and bands top to bottom. Lines within a block are separated by `\n`
+/// and blocks by a blank line.
+///
+/// # Arguments
+/// * `items` - Text items as produced by text extraction
+/// * `options` - Line, space, column and block thresholds
+///
+/// # Returns
+/// The page text
+pub fn layout_reading_order(items: Vec<TextItem>, options: &TextLayoutOptions) -> String {
+    let (positioned, unpositioned): (Vec<TextItem>, Vec<TextItem>) =
+        items.into_iter().partition(|item| item.position.is_some());
+
+    let fragments = line_fragments(positioned, options);
+    let blocks = group_blocks(fragments, options);
+    let mut ordered = Vec::with_capacity(blocks.len());
+    xy_cut(blocks, options, &mut ordered);
+
+    let mut result = ordered
+        .iter()
+        .map(|block| {
+            block
+                .lines
+                .iter()
+                .map(|line| join_line(&line.items, options))
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    for item in unpositioned {
+        result.push_str(&item.text);
+    }
+    result
+}
+
+/// A word of page text with its bounding box.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextWord {
+    /// The word, without surrounding whitespace
+    pub text: String,
+
+    /// Bounding box [x0, y0, x1, y1] in user space (y up), from the glyph
+    /// advances and the font's ascent and descent
+    pub bbox: [f64; 4],
+
+    /// Font name of the word's first character
+    pub font_name: Option<String>,
+
+    /// Font size of the word's first character
+    pub font_size: Option<f64>,
+
+    /// Whether the font of the word's first character is bold
+    pub bold: bool,
+
+    /// Whether the font of the word's first character is italic
+    pub italic: bool,
+}
+
+/// A line of page text and its words.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextLine {
+    /// The words of the line joined by single spaces
+    pub text: String,
+
+    /// Bounding box [x0, y0, x1, y1] of all words in user space (y up)
+    pub bbox: [f64; 4],
+
+    /// The words, left to right
+    pub words: Vec<TextWord>,
+}
+
+/// Splits text items into lines of words with bounding boxes.
+///
+/// Lines are grouped as in [`layout_text`]. Words break at whitespace and
+/// at horizontal gaps wider than `options.space_threshold`, so runs shown by
+/// separate operators merge into one word when they touch, and a single run
+/// splits where character or word spacing opens a gap. Items without a
+/// position are skipped.
+///
+/// # Arguments
+/// * `items` - Text items as produced by text extraction
+/// * `options` - Line and space thresholds
+///
+/// # Returns
+/// The lines, top to bottom
+pub fn extract_lines(items: Vec<TextItem>, options: &TextLayoutOptions) -> Vec<TextLine> {
+    let positioned = items
+        .into_iter()
+        .filter(|item| item.position.is_some())
+        .collect();
+
+    let mut lines = Vec::new();
+    for line in group_lines(positioned, options) {
+        let mut words: Vec<TextWord> = Vec::new();
+        let mut in_word = false;
+        for item in &line {
+            let size = font_size(item);
+            let gap_threshold = options.space_threshold * options.average_glyph_width * size;
+            let (descent, ascent) = item.vertical_extent.unwrap_or((-0.2 * size, 0.8 * size));
+            let (y0, y1) = (y(item) + descent, y(item) + ascent);
+
+            for (ch, (start, end)) in item.text.chars().zip(char_spans(item, options)) {
+                if ch.is_whitespace() {
+                    in_word = false;
+                    continue;
+                }
+                let (x0, x1) = (x(item) + start.min(end), x(item) + start.max(end));
+                match words.last_mut() {
+                    Some(word) if in_word && x0 - word.bbox[2] <= gap_threshold => {
+                        word.text.push(ch);
+                        word.bbox = [
+                            word.bbox[0].min(x0),
+                            word.bbox[1].min(y0),
+                            word.bbox[2].max(x1),
+                            word.bbox[3].max(y1),
+                        ];
+                    }
+                    _ => words.push(TextWord {
+                        text: ch.to_string(),
+                        bbox: [x0, y0, x1, y1],
+                        font_name: item.font_name.clone(),
+                        font_size: item.font_size,
+                        bold: item.bold,
+                        italic: item.italic,
+                    }),
+                }
+                in_word = true;
+            }
+        }
+
+        if let Some(first) = words.first() {
+            let bbox = words.iter().fold(first.bbox, |bbox, word| {
+                [
+                    bbox[0].min(word.bbox[0]),
+                    bbox[1].min(word.bbox[1]),
+                    bbox[2].max(word.bbox[2]),
+                    bbox[3].max(word.bbox[3]),
+                ]
+            });
+            let text = words
+                .iter()
+                .map(|word| word.text.as_str())
+                .collect::<Vec<_>>()
+                .join(" ");
+            lines.push(TextLine { text, bbox, words });
+        }
+    }
+    lines
+}
+
+/// Splits text items into words with bounding boxes, in line order.
+///
+/// See [`extract_lines`] for how words are delimited.
+///
+/// # Arguments
+/// * `items` - Text items as produced by text extraction
+/// * `options` - Line and space thresholds
+pub fn extract_words(items: Vec<TextItem>, options: &TextLayoutOptions) -> Vec<TextWord> {
+    extract_lines(items, options)
+        .into_iter()
+        .flat_map(|line| line.words)
+        .collect()
+}
+
+/// Horizontal extent of each character of an item, relative to its
+/// position: the measured spans, or the item's width (or an estimate)
+/// divided evenly.
+fn char_spans(item: &TextItem, options: &TextLayoutOptions) -> Vec<(f64, f64)> {
+    let count = item.text.chars().count();
+    if let Some(spans) = &item.char_spans
+        && spans.len() == count
+    {
+        return spans.clone();
+    }
+    let advance = estimated_width(item, options) / count.max(1) as f64;
+    (0..count)
+        .map(|index| (index as f64 * advance, (index + 1) as f64 * advance))
+        .collect()
+}
+
+/// A run of items on one baseline with no column-sized gap.
+struct Fragment {
+    items: Vec<TextItem>,
+    bbox: BBox,
+    baseline: f64,
+    font_size: f64,
+}
+
+/// Lines of text forming a paragraph-like block.
+struct Block {
+    lines: Vec<Fragment>,
+    bbox: BBox,
+}
+
+/// Axis-aligned box in user space (y up).
+#[derive(Clone, Copy)]
+struct BBox {
+    x0: f64,
+    y0: f64,
+    x1: f64,
+    y1: f64,
+}
+
+impl BBox {
+    fn union(&self, other: &BBox) -> BBox {
+        BBox {
+            x0: self.x0.min(other.x0),
+            y0: self.y0.min(other.y0),
+            x1: self.x1.max(other.x1),
+            y1: self.y1.max(other.y1),
+        }
+    }
+}
+
+/// Estimated box of an item: ascent 0.8 and descent 0.2 of the font size.
+fn item_bbox(item: &TextItem, options: &TextLayoutOptions) -> BBox {
+    let size = font_size(item);
+    BBox {
+        x0: x(item),
+        y0: y(item) - 0.2 * size,
+        x1: x(item) + estimated_width(item, options),
+        y1: y(item) + 0.8 * size,
+    }
+}
+
+/// Groups positioned items into lines by baseline, top to bottom, with the
+/// items of each line ordered by x.
+fn group_lines(mut items: Vec<TextItem>, options: &TextLayoutOptions) -> Vec<Vec<TextItem>> {
+    // Y-axis in PDF goes bottom to top, so lines are read in descending Y
+    items.sort_by(|a, b| compare(y(b), y(a)));
+
+    let mut lines: Vec<Vec<TextItem>> = Vec::new();
+    let mut line_y = 0.0;
+    for item in items {
+        let threshold = options.line_threshold * font_size(&item);
+        match lines.last_mut() {
+            Some(line) if (line_y - y(&item)).abs() <= threshold => line.push(item),
+            _ => {
+                line_y = y(&item);
+                lines.push(vec![item]);
+            }
+        }
+    }
+
+    for line in &mut lines {
+        line.sort_by(|a, b| compare(x(a), x(b)));
+    }
+    lines
+}
+
+/// Groups items into lines by baseline and splits each line at wide gaps.
+fn line_fragments(items: Vec<TextItem>, options: &TextLayoutOptions) -> Vec<Fragment> {
+    let mut fragments = Vec::new();
+    for line in group_lines(items, options) {
+        let mut current: Option<Fragment> = None;
+        for item in line {
+            let bbox = item_bbox(&item, options);
+            let size = font_size(&item);
+            match current.as_mut() {
+                Some(fragment) if bbox.x0 - fragment.bbox.x1 < options.column_gap * size => {
+                    fragment.bbox = fragment.bbox.union(&bbox);
+                    fragment.font_size = fragment.font_size.max(size);
+                    fragment.items.push(item);
+                }
+                _ => {
+                    fragments.extend(current.take());
+                    current = Some(Fragment {
+                        baseline: y(&item),
+                        font_size: size,
+                        items: vec![item],
+                        bbox,
+                    });
+                }
+            }
+        }
+        fragments.extend(current);
+    }
+    fragments
+}
+
+/// Merges fragments, top to bottom, into blocks of close, overlapping lines.
+fn group_blocks(fragments: Vec<Fragment>, options: &TextLayoutOptions) -> Vec<Block> {
+    let mut blocks: Vec<Block> = Vec::new();
+    for fragment in fragments {
+        let target = blocks.iter_mut().rev().find(|block| {
+            let last = match block.lines.last() {
+                Some(last) => last,
+                None => return false,
+            };
+            let spacing = last.baseline - fragment.baseline;
+            let max_spacing = options.block_line_spacing * last.font_size.max(fragment.font_size);
+            let overlaps = fragment.bbox.x0 < last.bbox.x1 && last.bbox.x0 < fragment.bbox.x1;
+            spacing > 0.0 && spacing <= max_spacing && overlaps
+        });
+        match target {
+            Some(block) => {
+                block.bbox = block.bbox.union(&fragment.bbox);
+                block.lines.push(fragment);
+            }
+            None => blocks.push(Block {
+                bbox: fragment.bbox,
+                lines: vec![fragment],
+            }),
+        }
+    }
+    blocks
+}
+
+/// Orders blocks by recursively cutting them at the widest gap: vertical
+/// gutters split columns (read left to right) and horizontal gaps split bands
+/// (read top to bottom).
+fn xy_cut(mut blocks: Vec<Block>, options: &TextLayoutOptions, out: &mut Vec<Block>) {
+    if blocks.len() <= 1 {
+        out.extend(blocks);
+        return;
+    }
+
+    // Widest gap in the x projection
+    blocks.sort_by(|a, b| compare(a.bbox.x0, b.bbox.x0));
+    let min_gap = options.column_gap
+        * blocks
+            .iter()
+            .flat_map(|block| block.lines.iter().map(|line| line.font_size))
+            .fold(f64::INFINITY, f64::min);
+    let mut vertical: Option<(usize, f64)> = None;
+    let mut right_edge = blocks[0].bbox.x1;
+    for (index, block) in blocks.iter().enumerate().skip(1) {
+        let gap = block.bbox.x0 - right_edge;
+        if gap >= min_gap && vertical.is_none_or(|(_, widest)| gap > widest) {
+            vertical = Some((index, gap));
+        }
+        right_edge = right_edge.max(block.bbox.x1);
+    }
+
+    // Widest gap in the y projection
+    let mut by_top: Vec<usize> = (0..blocks.len()).collect();
+    by_top.sort_by(|&a, &b| compare(blocks[b].bbox.y1, blocks[a].bbox.y1));
+    let mut horizontal: Option<(usize, f64)> = None;
+    let mut bottom_edge = blocks[by_top[0]].bbox.y0;
+    for (position, &index) in by_top.iter().enumerate().skip(1) {
+        let gap = bottom_edge - blocks[index].bbox.y1;
+        if gap > 0.0 && horizontal.is_none_or(|(_, widest)| gap > widest) {
+            horizontal = Some((position, gap));
+        }
+        bottom_edge = bottom_edge.min(blocks[index].bbox.y0);
+    }
+
+    match (vertical, horizontal) {
+        (Some((index, v)), h) if h.is_none_or(|(_, h)| v >= h) => {
+            let right = blocks.split_off(index);
+            xy_cut(blocks, options, out);
+            xy_cut(right, options, out);
+        }
+        (_, Some((position, _))) => {
+            // Same stable sort as `by_top`, so `position` splits it the same way
+            blocks.sort_by(|a, b| compare(b.bbox.y1, a.bbox.y1));
+            let below = blocks.split_off(position);
+            xy_cut(blocks, options, out);
+            xy_cut(below, options, out);
+        }
+        // No clean cut (overlapping blocks): read top to bottom
+        _ => {
+            blocks.sort_by(|a, b| compare(b.bbox.y1, a.bbox.y1));
+            out.extend(blocks);
+        }
+    }
+}
+
+/// Groups positioned items into blocks in reading order, as lines of items.
+///
+/// This is the grouping behind [`layout_reading_order`]; unpositioned items
+/// are dropped.
+pub(crate) fn reading_order_blocks(
+    items: Vec<TextItem>,
+    options: &TextLayoutOptions,
+) -> Vec<Vec<Vec<TextItem>>> {
+    let positioned = items
+        .into_iter()
+        .filter(|item| item.position.is_some())
+        .collect();
+    let blocks = group_blocks(line_fragments(positioned, options), options);
+    let mut ordered = Vec::with_capacity(blocks.len());
+    xy_cut(blocks, options, &mut ordered);
+    ordered
+        .into_iter()
+        .map(|block| block.lines.into_iter().map(|line| line.items).collect())
+        .collect()
+}
+
+/// Groups items into lines, top to bottom, keeping unpositioned items as a
+/// last line of their own.
+pub(crate) fn text_lines(items: Vec<TextItem>, options: &TextLayoutOptions) -> Vec<Vec<TextItem>> {
+    let (positioned, unpositioned): (Vec<TextItem>, Vec<TextItem>) =
+        items.into_iter().partition(|item| item.position.is_some());
+    let mut lines = group_lines(positioned, options);
+    if !unpositioned.is_empty() {
+        lines.push(unpositioned);
+    }
+    lines
+}
+
+/// Returns whether a word space belongs between two consecutive items of a
+/// line.
+pub(crate) fn word_gap(previous: &TextItem, item: &TextItem, options: &TextLayoutOptions) -> bool {
+    let end = x(previous) + estimated_width(previous, options);
+    let glyph_width = options.average_glyph_width * font_size(item);
+    x(item) - end > options.space_threshold * glyph_width
+        && !previous.text.ends_with(char::is_whitespace)
+        && !item.text.starts_with(char::is_whitespace)
+}
+
+/// Returns the estimated box of an item as `[x0, y0, x1, y1]`.
+pub(crate) fn item_box(item: &TextItem, options: &TextLayoutOptions) -> [f64; 4] {
+    let bbox = item_bbox(item, options);
+    [bbox.x0, bbox.y0, bbox.x1, bbox.y1]
+}
+
+/// Joins the items of one line, inserting spaces at word gaps.
+fn join_line(items: &[TextItem], options: &TextLayoutOptions) -> String {
+    let mut result = String::new();
+    let mut previous: Option<&TextItem> = None;
+    for item in items {
+        if previous.is_some_and(|previous| word_gap(previous, item, options))
+            && !result.ends_with(char::is_whitespace)
+        {
+            result.push(' ');
+        }
+        previous = Some(item);
+        result.push_str(&item.text);
+    }
+    result
+}
+
+/// Advance of an item: its measured width, or an estimate from its
+/// character count.
+fn estimated_width(item: &TextItem, options: &TextLayoutOptions) -> f64 {
+    item.width.unwrap_or_else(|| {
+        item.text.chars().count() as f64 * options.average_glyph_width * font_size(item)
+    })
+}
+
+pub(crate) fn font_size(item: &TextItem) -> f64 {
+    item.font_size
+        .map(f64::abs)
+        .filter(|size| *size > 0.0)
+        .unwrap_or(DEFAULT_FONT_SIZE)
+}
+
+fn x(item: &TextItem) -> f64 {
+    item.position.map_or(0.0, |(x, _)| x)
+}
+
+fn y(item: &TextItem) -> f64 {
+    item.position.map_or(0.0, |(_, y)| y)
+}
+
+fn compare(a: f64, b: f64) -> Ordering {
+    a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(text: &str, x: f64, y: f64, size: f64) -> TextItem {
+        TextItem {
+            text: text.to_string(),
+            font_name: None,
+            font_size: Some(size),
+            position: Some((x, y)),
+            rendering_mode: None,
+            width: None,
+            mcid: None,
+            char_spans: None,
+            vertical_extent: None,
+            glyphs: None,
+            font_family: None,
+            bold: false,
+            italic: false,
+            fill_color: None,
+        }
+    }
+
+    #[test]
+    fn test_layout_lines_and_spaces() {
+        let items = vec![
+            item("second", 72.0, 680.0, 12.0),
+            item("Hello", 72.0, 700.0, 12.0),
+            // Well past the end of "Hello" (5 * 6pt)
+            item("world", 120.0, 700.0, 12.0),
+        ];
+        assert_eq!(
+            layout_text(items, &TextLayoutOptions::default()),
+            "Hello world\nsecond"
+        );
+    }
+
+    #[test]
+    fn test_layout_joins_adjacent_items() {
+        // A word split across two items (e.g. a font change mid-word)
+        let items = vec![
+            item("Bold", 72.0, 700.0, 12.0),
+            item("face", 96.0, 700.0, 12.0),
+        ];
+        assert_eq!(
+            layout_text(items, &TextLayoutOptions::default()),
+            "Boldface"
+        );
+    }
+
+    #[test]
+    fn test_layout_scales_with_font_size() {
+        // 5pt text with 6pt leading: 1pt of baseline jitter stays on the
+        // line, the next baseline starts a new one
+        let small = vec![
+            item("a", 10.0, 100.0, 5.0),
+            item("b", 20.0, 99.0, 5.0),
+            item("c", 10.0, 94.0, 5.0),
+        ];
+        assert_eq!(layout_text(small, &TextLayoutOptions::default()), "a b\nc");
+
+        // 72pt display type where a superscript sits 8pt above the baseline
+        let large = vec![
+            item("E", 0.0, 500.0, 72.0),
+            item("=mc", 40.0, 500.0, 72.0),
+            item("2", 150.0, 508.0, 72.0),
+        ];
+        assert_eq!(layout_text(large, &TextLayoutOptions::default()), "E=mc2");
+    }
+
+    #[test]
+    fn test_layout_custom_thresholds() {
+        let items = vec![
+            item("Bold", 72.0, 700.0, 12.0),
+            item("face", 96.0, 700.0, 12.0),
+        ];
+        // A negative threshold restores the old "always add a space" behavior
+        let options = TextLayoutOptions {
+            space_threshold: -1.0,
+            ..TextLayoutOptions::default()
+        };
+        assert_eq!(layout_text(items, &options), "Bold face");
+    }
+
+    #[test]
+    fn test_reading_order_columns() {
+        // A full-width title over two columns; lines of both columns share
+        // baselines, so plain layout would interleave them
+        let items = vec![
+            item("Title", 72.0, 740.0, 18.0),
+            item("Left one", 72.0, 700.0, 10.0),
+            item("Right one", 320.0, 700.0, 10.0),
+            item("Left two", 72.0, 688.0, 10.0),
+            item("Right two", 320.0, 688.0, 10.0),
+            item("Left three", 72.0, 676.0, 10.0),
+            item("Footer", 72.0, 40.0, 8.0),
+        ];
+        assert_eq!(
+            layout_text(items.clone(), &TextLayoutOptions::default()),
+            "Title\nLeft one Right one\nLeft two Right two\nLeft three\nFooter"
+        );
+        assert_eq!(
+            layout_reading_order(items, &TextLayoutOptions::default()),
+            "Title\n\nLeft one\nLeft two\nLeft three\n\nRight one\nRight two\n\nFooter"
+        );
+    }
+
+    #[test]
+    fn test_reading_order_uses_measured_width() {
+        // "Wide" is 60pt wide, so "text" follows it directly; by character
+        // count it would end at 96, leaving a column-sized gap
+        let mut wide = item("Wide", 72.0, 700.0, 12.0);
+        wide.width = Some(60.0);
+        let items = vec![wide, item("text", 133.0, 700.0, 12.0)];
+        assert_eq!(
+            layout_reading_order(items, &TextLayoutOptions::default()),
+            "Widetext"
+        );
+    }
+
+    #[test]
+    fn test_extract_words_merges_and_splits_runs() {
+        // "Hel" + "lo" touch and form one word; the gap before "world" and the
+        // space inside the second run separate words
+        let mut first = item("Hel", 10.0, 700.0, 10.0);
+        first.char_spans = Some(vec![(0.0, 6.0), (6.0, 12.0), (12.0, 15.0)]);
+        first.vertical_extent = Some((-3.0, 9.0));
+        let second = item("lo", 25.0, 700.0, 10.0);
+        let third = item("world is", 40.0, 700.0, 10.0);
+        let below = item("next", 10.0, 680.0, 10.0);
+
+        let options = TextLayoutOptions::default();
+        let lines = extract_lines(vec![below, third, second, first], &options);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].text, "Hello world is");
+        assert_eq!(lines[1].text, "next");
+
+        let words = &lines[0].words;
+        assert_eq!(words[0].text, "Hello");
+        assert_eq!(words[0].bbox, [10.0, 697.0, 35.0, 709.0]);
+        assert_eq!(words[1].text, "world");
+        assert_eq!(words[1].bbox, [40.0, 698.0, 65.0, 708.0]);
+        assert_eq!(words[2].bbox[0], 70.0);
+        assert_eq!(lines[0].bbox, [10.0, 697.0, 80.0, 709.0]);
+
+        let words = extract_words(vec![item("a  b", 0.0, 0.0, 10.0)], &options);
+        let texts: Vec<_> = words.iter().map(|word| word.text.as_str()).collect();
+        assert_eq!(texts, vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_extract_words_splits_at_spacing_gaps() {
+        // One run whose character spacing opens a gap between "ab" and "cd"
+        let mut spaced = item("abcd", 0.0, 0.0, 10.0);
+        spaced.char_spans = Some(vec![(0.0, 5.0), (5.0, 10.0), (20.0, 25.0), (25.0, 30.0)]);
+        let words = extract_words(vec![spaced], &TextLayoutOptions::default());
+        let texts: Vec<_> = words.iter().map(|word| word.text.as_str()).collect();
+        assert_eq!(texts, vec!["ab", "cd"]);
+        assert_eq!(words[1].bbox, [20.0, -2.0, 30.0, 8.0]);
+    }
+
+    #[test]
+    fn test_layout_unpositioned_items_last() {
+        let mut loose = item("tail", 0.0, 0.0, 12.0);
+        loose.position = None;
+        let items = vec![loose, item("head", 72.0, 700.0, 12.0)];
+        assert_eq!(
+            layout_text(items, &TextLayoutOptions::default()),
+            "headtail"
+        );
+    }
+}
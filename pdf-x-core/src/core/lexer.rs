@@ -314,15 +314,19 @@ impl Lexer {
             if Self::is_whitespace(ch) || ch == 0x28 || ch == 0x3C || ch == -1 {
                 return Ok(Token::Number(0.0));
             }
-            return Err(PDFError::Generic(format!(
-                "Invalid number: {} (charCode {})",
-                if ch >= 0 {
-                    (ch as u8 as char).to_string()
-                } else {
-                    "EOF".to_string()
-                },
-                ch
-            )));
+            return Err(PDFError::parse_error_at(
+                format!(
+                    "Invalid number: {} (charCode {})",
+                    if ch >= 0 {
+                        (ch as u8 as char).to_string()
+                    } else {
+                        "EOF".to_string()
+                    },
+                    ch
+                ),
+                None,
+                self.get_position(),
+            ));
         }
 
         let mut base_value = (ch - 0x30) as f64; // '0'
@@ -626,10 +630,11 @@ impl Lexer {
         // Read characters until we hit a special character
         while ch >= 0 && !Self::is_special(ch) {
             if self.cmd_buf.len() >= 128 {
-                return Err(PDFError::Generic(format!(
-                    "Command token too long: {}",
-                    self.cmd_buf.len()
-                )));
+                return Err(PDFError::parse_error_at(
+                    format!("Command token too long: {}", self.cmd_buf.len()),
+                    None,
+                    self.get_position(),
+                ));
             }
 
             self.cmd_buf.push(ch as u8 as char);
@@ -693,6 +698,12 @@ impl Lexer {
         Ok(byte)
     }
 
+    /// Returns the next byte `get_stream_byte` would return, without consuming it.
+    #[inline]
+    pub fn peek_byte(&self) -> Option<u8> {
+        (self.current_char >= 0).then_some(self.current_char as u8)
+    }
+
     /// Gets the current stream position, accounting for lookahead.
     ///
     /// The position is where the NEXT byte will be read from (current_char has already been read).
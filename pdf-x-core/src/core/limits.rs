@@ -0,0 +1,167 @@
+//! Bounds on the work a single document can cause.
+//!
+//! A PDF file is a program of sorts: a few kilobytes can declare millions of
+//! objects, nest Form XObjects hundreds of levels deep or inflate to
+//! gigabytes. Servers that open files from untrusted sources need such files
+//! to fail fast instead of exhausting memory or CPU. [`ResourceLimits`]
+//! gathers the bounds that parsing and rendering enforce; once one is
+//! reached the operation fails with [`PDFError::LimitExceeded`].
+//!
+//! ```no_run
+//! use pdf_x_core::prelude::*;
+//!
+//! let limits = ResourceLimits {
+//!     max_stream_size: 16 * 1024 * 1024,
+//!     max_operations: 1_000_000,
+//!     ..ResourceLimits::default()
+//! };
+//! let doc = Pdf::open("upload.pdf").limits(limits).load();
+//! if let Err(PDFError::LimitExceeded { limit, max }) = doc {
+//!     println!("rejected: {} over {}", limit, max);
+//! }
+//! ```
+
+use super::error::{PDFError, PDFResult};
+
+/// Decoded streams smaller than this are never rejected for their
+/// compression ratio, so that small streams of repeated bytes still decode.
+pub const DECOMPRESSION_RATIO_FLOOR: usize = 1024 * 1024;
+
+/// Resource limits enforced while parsing and rendering a document.
+///
+/// The defaults accept every well-formed document we know of; lower them for
+/// untrusted input. Each limit is checked where the resource is used, so a
+/// limit that is never reached costs nothing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResourceLimits {
+    /// Largest object number the cross-reference table may hold
+    /// (default: 8,388,607, the limit of PDF 1.7 Annex C)
+    pub max_objects: usize,
+
+    /// How many indirect objects may be loaded to load one object, e.g. a
+    /// stream whose /Length is in an object stream whose /Length is in
+    /// another one (default: 32)
+    pub max_reference_depth: usize,
+
+    /// How deeply arrays and dictionaries may nest in one object
+    /// (default: 256)
+    pub max_object_depth: usize,
+
+    /// How deeply /Pages nodes may nest in the page tree (default: 64)
+    pub max_page_tree_depth: usize,
+
+    /// How deeply Form XObjects may draw each other (default: 16)
+    pub max_form_depth: usize,
+
+    /// Largest decoded stream, in bytes (default: 256 MiB)
+    pub max_stream_size: usize,
+
+    /// Largest ratio of decoded to encoded size of a stream, applied to
+    /// streams that decode to more than [`DECOMPRESSION_RATIO_FLOOR`] bytes
+    /// (default: 2048)
+    pub max_decompression_ratio: usize,
+
+    /// How many content stream operators rendering one page may run,
+    /// including those of its Form XObjects, patterns and soft masks
+    /// (default: 10,000,000)
+    pub max_operations: usize,
+}
+
+impl Default for ResourceLimits {
+    fn default() -> Self {
+        ResourceLimits {
+            max_objects: 8_388_607,
+            max_reference_depth: 32,
+            max_object_depth: 256,
+            max_page_tree_depth: 64,
+            max_form_depth: 16,
+            max_stream_size: 256 * 1024 * 1024,
+            max_decompression_ratio: 2048,
+            max_operations: 10_000_000,
+        }
+    }
+}
+
+impl ResourceLimits {
+    /// Returns limits that never trigger, for trusted input.
+    pub fn unlimited() -> Self {
+        ResourceLimits {
+            max_objects: usize::MAX,
+            max_reference_depth: usize::MAX,
+            max_object_depth: usize::MAX,
+            max_page_tree_depth: usize::MAX,
+            max_form_depth: usize::MAX,
+            max_stream_size: usize::MAX,
+            max_decompression_ratio: usize::MAX,
+            max_operations: usize::MAX,
+        }
+    }
+
+    /// Returns the most bytes a stream of `encoded_len` bytes may decode to.
+    pub fn max_decoded_len(&self, encoded_len: usize) -> usize {
+        let by_ratio = encoded_len
+            .saturating_mul(self.max_decompression_ratio)
+            .max(DECOMPRESSION_RATIO_FLOOR);
+        self.max_stream_size.min(by_ratio)
+    }
+
+    /// Fails if a stream of `encoded_len` bytes decoded to `decoded_len`
+    /// bytes, or more, breaks the size or ratio limit.
+    pub fn check_decoded(&self, encoded_len: usize, decoded_len: usize) -> PDFResult<()> {
+        if decoded_len > self.max_stream_size {
+            return Err(PDFError::limit_exceeded(
+                "decompressed stream size",
+                self.max_stream_size,
+            ));
+        }
+        if decoded_len > self.max_decoded_len(encoded_len) {
+            return Err(PDFError::limit_exceeded(
+                format!("decompression ratio of a {} byte stream", encoded_len),
+                self.max_decompression_ratio,
+            ));
+        }
+        Ok(())
+    }
+
+    /// Fails if object number `num` is beyond [`max_objects`](Self::max_objects).
+    pub fn check_object_number(&self, num: usize) -> PDFResult<()> {
+        if num > self.max_objects {
+            return Err(PDFError::limit_exceeded(
+                format!("object number {}", num),
+                self.max_objects,
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_decoded() {
+        let limits = ResourceLimits {
+            max_stream_size: 10 * DECOMPRESSION_RATIO_FLOOR,
+            max_decompression_ratio: 100,
+            ..ResourceLimits::default()
+        };
+        // Small streams pass whatever their ratio
+        assert!(limits.check_decoded(1, DECOMPRESSION_RATIO_FLOOR).is_ok());
+        assert!(limits.check_decoded(20_000, 2_000_000).is_ok());
+        assert!(matches!(
+            limits.check_decoded(10_000, 2_000_000),
+            Err(PDFError::LimitExceeded { max: 100, .. })
+        ));
+        assert!(matches!(
+            limits.check_decoded(usize::MAX, 11 * DECOMPRESSION_RATIO_FLOOR),
+            Err(PDFError::LimitExceeded { .. })
+        ));
+        assert_eq!(limits.max_decoded_len(10), DECOMPRESSION_RATIO_FLOOR);
+        assert_eq!(limits.max_decoded_len(usize::MAX), limits.max_stream_size);
+
+        let unlimited = ResourceLimits::unlimited();
+        assert!(unlimited.check_decoded(1, usize::MAX).is_ok());
+        assert!(unlimited.check_object_number(u32::MAX as usize).is_ok());
+    }
+}
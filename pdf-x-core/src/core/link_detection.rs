@@ -0,0 +1,257 @@
+//! Automatic detection of URLs and e-mail addresses in page text.
+//!
+//! Authoring tools often leave URLs and e-mail addresses as plain text without
+//! a Link annotation. Viewers compensate by scanning the text layer and
+//! turning matches into "virtual" links; this module does the same on the
+//! items produced by text extraction.
+//!
+//! Based on PDF.js web/autolinker.js.
+
+use crate::core::annotation::AnnotationRect;
+use crate::core::content_stream::TextItem;
+
+/// Average glyph width as a fraction of the font size.
+///
+/// Text items don't carry glyph widths yet, so link rectangles are estimated
+/// from the character count.
+const AVERAGE_GLYPH_WIDTH: f64 = 0.5;
+
+/// Portion of the font size below the baseline covered by a link rectangle.
+const DESCENT: f64 = 0.2;
+
+/// Portion of the font size above the baseline covered by a link rectangle.
+const ASCENT: f64 = 0.8;
+
+/// Font size used when a text item doesn't specify one.
+const DEFAULT_FONT_SIZE: f64 = 12.0;
+
+/// The kind of link found in the text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DetectedLinkKind {
+    /// A web address (http, https, ftp or www.)
+    Url,
+
+    /// An e-mail address
+    Email,
+}
+
+/// A link found in the page text that has no annotation of its own.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DetectedLink {
+    /// What kind of link this is
+    pub kind: DetectedLinkKind,
+
+    /// The matched text as it appears on the page
+    pub text: String,
+
+    /// The URI the link points to (e.g. "http://www.example.com", "mailto:a@b.com")
+    pub uri: String,
+
+    /// Estimated location of the text on the page [x1, y1, x2, y2]
+    pub rect: AnnotationRect,
+}
+
+/// Detects URLs and e-mail addresses in extracted text items.
+///
+/// Each text item is scanned on its own; links split across several show
+/// operations are not joined.
+///
+/// # Arguments
+/// * `items` - Text items from `Page::extract_text()`
+///
+/// # Returns
+/// The detected links, in the order of the text items
+pub fn detect_links(items: &[TextItem]) -> Vec<DetectedLink> {
+    let mut links = Vec::new();
+
+    for item in items {
+        let (x, y) = match item.position {
+            Some(position) => position,
+            None => continue,
+        };
+        let font_size = item.font_size.unwrap_or(DEFAULT_FONT_SIZE).abs();
+        let glyph_width = font_size * AVERAGE_GLYPH_WIDTH;
+
+        for (start, len, kind, uri) in find_links(&item.text) {
+            let x1 = x + start as f64 * glyph_width;
+            let x2 = x1 + len as f64 * glyph_width;
+            links.push(DetectedLink {
+                kind,
+                text: item.text.chars().skip(start).take(len).collect(),
+                uri,
+                rect: [x1, y - font_size * DESCENT, x2, y + font_size * ASCENT],
+            });
+        }
+    }
+
+    links
+}
+
+/// Finds links in a string.
+///
+/// Returns (start, length, kind, uri) tuples, with start and length counted
+/// in characters.
+fn find_links(text: &str) -> Vec<(usize, usize, DetectedLinkKind, String)> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut results = Vec::new();
+
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i].is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        // Take the whitespace-delimited token
+        let token_start = i;
+        while i < chars.len() && !chars[i].is_whitespace() {
+            i += 1;
+        }
+        let mut start = token_start;
+        let mut end = i;
+
+        // Strip surrounding punctuation that is rarely part of a link
+        while start < end && "([{<\"'".contains(chars[start]) {
+            start += 1;
+        }
+        while end > start && ".,;:!?)]}>\"'".contains(chars[end - 1]) {
+            end -= 1;
+        }
+        if start == end {
+            continue;
+        }
+
+        let token: String = chars[start..end].iter().collect();
+        if let Some((kind, uri)) = classify_token(&token) {
+            results.push((start, end - start, kind, uri));
+        }
+    }
+
+    results
+}
+
+/// Decides whether a token is a URL or an e-mail address.
+fn classify_token(token: &str) -> Option<(DetectedLinkKind, String)> {
+    let lower = token.to_ascii_lowercase();
+
+    for scheme in ["http://", "https://", "ftp://"] {
+        if lower.starts_with(scheme) {
+            return is_host(host_part(&token[scheme.len()..]))
+                .then(|| (DetectedLinkKind::Url, token.to_string()));
+        }
+    }
+
+    if lower.starts_with("www.") {
+        return is_host(host_part(token))
+            .then(|| (DetectedLinkKind::Url, format!("http://{}", token)));
+    }
+
+    let address = if lower.starts_with("mailto:") {
+        &token["mailto:".len()..]
+    } else {
+        token
+    };
+    is_email(address).then(|| (DetectedLinkKind::Email, format!("mailto:{}", address)))
+}
+
+/// Returns the host portion of a URL without its scheme.
+fn host_part(rest: &str) -> &str {
+    let end = rest.find(['/', '?', '#', ':']).unwrap_or(rest.len());
+    &rest[..end]
+}
+
+/// Checks that a host name has at least two labels and a plausible TLD.
+fn is_host(host: &str) -> bool {
+    let labels: Vec<&str> = host.split('.').collect();
+    if labels.len() < 2 {
+        return false;
+    }
+
+    let labels_valid = labels
+        .iter()
+        .all(|label| !label.is_empty() && label.chars().all(|c| c.is_alphanumeric() || c == '-'));
+    let tld = labels[labels.len() - 1];
+
+    labels_valid && tld.chars().count() >= 2 && tld.chars().all(|c| c.is_alphabetic())
+}
+
+/// Checks whether a string looks like an e-mail address.
+fn is_email(address: &str) -> bool {
+    let (local, domain) = match address.split_once('@') {
+        Some(parts) => parts,
+        None => return false,
+    };
+
+    !local.is_empty()
+        && local
+            .chars()
+            .all(|c| c.is_alphanumeric() || "._%+-".contains(c))
+        && is_host(domain)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(text: &str, x: f64, y: f64, size: f64) -> TextItem {
+        TextItem {
+            text: text.to_string(),
+            font_name: None,
+            font_size: Some(size),
+            position: Some((x, y)),
+            rendering_mode: None,
+            width: None,
+            mcid: None,
+            char_spans: None,
+            vertical_extent: None,
+            glyphs: None,
+            font_family: None,
+            bold: false,
+            italic: false,
+            fill_color: None,
+        }
+    }
+
+    #[test]
+    fn test_find_urls() {
+        let links = find_links("See https://example.com/docs?x=1 and www.rust-lang.org.");
+        assert_eq!(links.len(), 2);
+        assert_eq!(links[0].2, DetectedLinkKind::Url);
+        assert_eq!(links[0].3, "https://example.com/docs?x=1");
+        // Trailing period is not part of the link
+        assert_eq!(links[1].3, "http://www.rust-lang.org");
+        assert_eq!(links[1].1, "www.rust-lang.org".len());
+    }
+
+    #[test]
+    fn test_find_emails() {
+        let links = find_links("Contact (support@example.co.uk) or mailto:me@x.io");
+        assert_eq!(links.len(), 2);
+        assert_eq!(links[0].2, DetectedLinkKind::Email);
+        assert_eq!(links[0].3, "mailto:support@example.co.uk");
+        assert_eq!(links[1].3, "mailto:me@x.io");
+    }
+
+    #[test]
+    fn test_rejects_non_links() {
+        assert!(find_links("version 1.2.3, a@b, http://, www.x, user@host").is_empty());
+    }
+
+    #[test]
+    fn test_detect_links_rect() {
+        let items = vec![item("Go to www.example.com now", 100.0, 700.0, 10.0)];
+        let links = detect_links(&items);
+
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].text, "www.example.com");
+        // "Go to " is 6 characters at 5pt each
+        assert_eq!(links[0].rect, [130.0, 698.0, 205.0, 708.0]);
+    }
+
+    #[test]
+    fn test_detect_links_skips_items_without_position() {
+        let mut text_item = item("https://example.com", 0.0, 0.0, 12.0);
+        text_item.position = None;
+        assert!(detect_links(&[text_item]).is_empty());
+    }
+}
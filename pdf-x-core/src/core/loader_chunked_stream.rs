@@ -0,0 +1,437 @@
+use super::base_stream::BaseStream;
+use super::chunk_manager::{ChunkLoader, ChunkManager, ChunkedStreamOptions};
+use super::error::{PDFError, PDFResult};
+use std::ops::Range;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Helper function to standardize mutex lock error handling for the loader.
+#[inline]
+fn lock_loader<L>(loader: &Arc<Mutex<L>>) -> PDFResult<MutexGuard<'_, L>> {
+    loader.lock().map_err(|_| {
+        PDFError::StreamError("Failed to lock chunk loader (mutex poisoned)".to_string())
+    })
+}
+
+/// Helper function to standardize mutex lock error handling for the chunk manager.
+#[inline]
+fn lock_manager(manager: &Arc<Mutex<ChunkManager>>) -> PDFResult<MutexGuard<'_, ChunkManager>> {
+    manager.lock().map_err(|_| {
+        PDFError::StreamError("Failed to lock chunk manager (mutex poisoned)".to_string())
+    })
+}
+
+/// A chunked stream backed by any user-supplied [`ChunkLoader`].
+///
+/// This is the extension point for custom data sources (object storage ranged
+/// GETs, encrypted containers, database blobs, ...). The loader only has to
+/// fetch raw chunks; caching, readahead, LRU eviction and the `BaseStream`
+/// plumbing are handled here. [`FileChunkedStream`](super::FileChunkedStream)
+/// is this stream over a file loader.
+///
+/// The loader and chunk manager are shared via Arc, so sub-streams created by
+/// the parser reuse the same cache.
+///
+/// Usually constructed indirectly through `PDFDocument::open_stream`.
+pub struct LoaderChunkedStream<L: ChunkLoader + Send + 'static> {
+    /// The data source (shared)
+    pub(super) loader: Arc<Mutex<L>>,
+    /// The chunk manager that tracks loaded chunks (shared)
+    pub(super) manager: Arc<Mutex<ChunkManager>>,
+    /// Current read position
+    pos: usize,
+    /// Starting offset in the data
+    start: usize,
+    /// Cached chunk size (immutable, no need to lock manager)
+    chunk_size: usize,
+    /// Cached total data length (immutable, no need to lock manager)
+    total_length: usize,
+}
+
+impl<L: ChunkLoader + Send + 'static> LoaderChunkedStream<L> {
+    /// Creates a new stream over a chunk loader.
+    ///
+    /// The chunk size and total length are taken from the loader. The stream
+    /// doesn't read ahead.
+    ///
+    /// # Arguments
+    /// * `loader` - The data source
+    /// * `max_cached_chunks` - Maximum chunks to keep in memory (default: 10)
+    pub fn new(loader: L, max_cached_chunks: Option<usize>) -> PDFResult<Self> {
+        let options =
+            ChunkedStreamOptions::from_chunk_counts(Some(loader.chunk_size()), max_cached_chunks);
+        Self::with_options(loader, &options)
+    }
+
+    /// Creates a new stream over a chunk loader with the readahead and cache
+    /// settings of `options`. The chunk size is the loader's.
+    pub(crate) fn with_options(loader: L, options: &ChunkedStreamOptions) -> PDFResult<Self> {
+        let chunk_size = loader.chunk_size();
+        if chunk_size == 0 {
+            return Err(PDFError::StreamError(
+                "Chunk loader reported a chunk size of 0".to_string(),
+            ));
+        }
+        let total_length = loader.total_length();
+        let manager = ChunkManager::with_options(
+            total_length,
+            &ChunkedStreamOptions {
+                chunk_size,
+                ..*options
+            },
+        );
+
+        Ok(LoaderChunkedStream {
+            loader: Arc::new(Mutex::new(loader)),
+            manager: Arc::new(Mutex::new(manager)),
+            pos: 0,
+            start: 0,
+            chunk_size,
+            total_length,
+        })
+    }
+
+    /// Requests a run of chunks from the loader and checks the data has the
+    /// expected size.
+    fn request_chunks(&self, chunks: Range<usize>) -> PDFResult<Vec<u8>> {
+        let data = lock_loader(&self.loader)?.request_chunks(chunks.clone())?;
+
+        let range_start = chunks.start * self.chunk_size;
+        let expected = std::cmp::min(chunks.end * self.chunk_size, self.total_length)
+            .saturating_sub(range_start);
+        if data.len() != expected {
+            return Err(PDFError::StreamError(format!(
+                "Chunk loader returned {} bytes for chunks {:?} (expected {})",
+                data.len(),
+                chunks,
+                expected
+            )));
+        }
+        Ok(data)
+    }
+
+    /// Ensures a chunk is loaded into the manager.
+    ///
+    /// If it isn't cached (never loaded, or evicted since), the chunk is
+    /// requested along with any readahead the manager asks for. Only needs
+    /// `&self` since the loader sits behind a mutex, which lets
+    /// `get_byte_range` load on demand instead of raising DataMissing.
+    fn ensure_chunk_loaded(&self, chunk_num: usize) -> PDFResult<()> {
+        let mut manager = lock_manager(&self.manager)?;
+
+        if manager.is_chunk_cached(chunk_num) {
+            manager.mark_chunk_accessed(chunk_num);
+        } else {
+            let chunks = manager.load_range_for(chunk_num);
+            // Release lock before loading
+            drop(manager);
+            let data = self.request_chunks(chunks.clone())?;
+            let mut manager = lock_manager(&self.manager)?;
+            manager.on_receive_range(chunks.start, data)?;
+        }
+        Ok(())
+    }
+
+    /// Runs `f` with exclusive access to the loader.
+    pub(crate) fn with_loader<R>(&self, f: impl FnOnce(&mut L) -> R) -> PDFResult<R> {
+        Ok(f(&mut *lock_loader(&self.loader)?))
+    }
+
+    /// Takes the loader back, dropping the cached chunks.
+    ///
+    /// Fails while a sub-stream still shares the loader.
+    pub(crate) fn into_loader(self) -> PDFResult<L> {
+        Arc::try_unwrap(self.loader)
+            .map_err(|_| {
+                PDFError::StreamError("Chunk loader is still shared by a sub-stream".to_string())
+            })?
+            .into_inner()
+            .map_err(|_| {
+                PDFError::StreamError("Failed to lock chunk loader (mutex poisoned)".to_string())
+            })
+    }
+
+    /// Returns the chunk size in bytes.
+    pub fn chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+
+    /// Returns the number of chunks currently loaded in the cache.
+    pub fn num_chunks_loaded(&self) -> usize {
+        self.manager
+            .lock()
+            .map(|m| m.num_chunks_loaded())
+            .unwrap_or(0)
+    }
+
+    /// Returns the total number of chunks.
+    pub fn num_chunks(&self) -> usize {
+        self.manager.lock().map(|m| m.num_chunks()).unwrap_or(0)
+    }
+
+    /// Returns true if all chunks are loaded.
+    pub fn is_fully_loaded(&self) -> bool {
+        self.manager
+            .lock()
+            .map(|m| m.is_data_loaded())
+            .unwrap_or(false)
+    }
+
+    /// Returns a list of chunk numbers that are not currently loaded.
+    pub fn get_missing_chunks(&self) -> Vec<usize> {
+        self.manager
+            .lock()
+            .map(|m| m.get_missing_chunks())
+            .unwrap_or_default()
+    }
+
+    /// Preloads a specific chunk into the cache.
+    pub fn preload_chunk(&self, chunk_num: usize) -> PDFResult<()> {
+        self.ensure_chunk_loaded(chunk_num)
+    }
+
+    /// Preloads a range of chunks into the cache.
+    pub fn preload_range(&self, begin: usize, end: usize) -> PDFResult<()> {
+        if begin >= end {
+            return Ok(());
+        }
+
+        let manager = lock_manager(&self.manager)?;
+        let begin_chunk = manager.get_chunk_number(begin);
+        let end_chunk = manager.get_chunk_number(end - 1);
+        let num_chunks = manager.num_chunks();
+        drop(manager);
+
+        for chunk in begin_chunk..=end_chunk.min(num_chunks.saturating_sub(1)) {
+            self.ensure_chunk_loaded(chunk)?;
+        }
+
+        Ok(())
+    }
+
+    /// Copies `begin..end` out of the cache, loading chunks as needed.
+    ///
+    /// Chunks are loaded and copied one at a time so ranges larger than the
+    /// cache still work.
+    fn copy_range(&self, begin: usize, end: usize) -> PDFResult<Vec<u8>> {
+        let begin_chunk = begin / self.chunk_size;
+        let end_chunk = (end - 1) / self.chunk_size;
+
+        let mut result = Vec::with_capacity(end - begin);
+        for chunk_num in begin_chunk..=end_chunk {
+            self.ensure_chunk_loaded(chunk_num)?;
+
+            let chunk_start = chunk_num * self.chunk_size;
+            let manager = lock_manager(&self.manager)?;
+            let chunk = manager
+                .get_chunk(chunk_num)
+                .ok_or_else(|| PDFError::DataMissing {
+                    position: chunk_start,
+                    length: std::cmp::min(chunk_start + self.chunk_size, self.total_length)
+                        - chunk_start,
+                })?;
+
+            let read_start = begin.max(chunk_start) - chunk_start;
+            let read_end = end.min(chunk_start + chunk.len()) - chunk_start;
+            result.extend_from_slice(&chunk[read_start..read_end]);
+        }
+        Ok(result)
+    }
+}
+
+impl<L: ChunkLoader + Send + 'static> BaseStream for LoaderChunkedStream<L> {
+    fn length(&self) -> usize {
+        self.total_length
+    }
+
+    fn is_empty(&self) -> bool {
+        self.total_length == 0
+    }
+
+    fn pos(&self) -> usize {
+        self.pos
+    }
+
+    fn set_pos(&mut self, pos: usize) -> PDFResult<()> {
+        if pos > self.length() {
+            return Err(PDFError::InvalidPosition {
+                pos,
+                length: self.length(),
+            });
+        }
+        self.pos = pos;
+        Ok(())
+    }
+
+    fn is_data_loaded(&self) -> bool {
+        self.is_fully_loaded()
+    }
+
+    fn ensure_range(&mut self, start: usize, length: usize) -> PDFResult<()> {
+        self.preload_range(start, start + length)
+    }
+
+    fn get_byte(&mut self) -> PDFResult<u8> {
+        if self.pos >= self.length() {
+            return Err(PDFError::UnexpectedEndOfStream);
+        }
+
+        let chunk_num = self.pos / self.chunk_size;
+        self.ensure_chunk_loaded(chunk_num)?;
+
+        let byte = lock_manager(&self.manager)?.get_byte_from_cache(self.pos)?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    fn get_bytes(&mut self, length: usize) -> PDFResult<Vec<u8>> {
+        let end_pos = std::cmp::min(self.pos + length, self.length());
+        if end_pos <= self.pos {
+            return Ok(Vec::new());
+        }
+
+        let result = self.copy_range(self.pos, end_pos)?;
+        self.pos = end_pos;
+        Ok(result)
+    }
+
+    fn get_byte_range(&self, begin: usize, end: usize) -> PDFResult<Vec<u8>> {
+        if begin >= end || end > self.length() {
+            return Err(PDFError::InvalidByteRange { begin, end });
+        }
+
+        // Unlike file streams, the loader is synchronous and shared, so missing
+        // chunks are fetched here rather than reported as DataMissing
+        self.copy_range(begin, end)
+    }
+
+    fn reset(&mut self) -> PDFResult<()> {
+        self.pos = self.start;
+        Ok(())
+    }
+
+    fn move_start(&mut self) -> PDFResult<()> {
+        if self.pos > self.start {
+            self.start = self.pos;
+        }
+        Ok(())
+    }
+
+    fn make_sub_stream(&self, start: usize, length: usize) -> PDFResult<Box<dyn BaseStream>> {
+        if start + length > self.length() {
+            return Err(PDFError::InvalidByteRange {
+                begin: start,
+                end: start + length,
+            });
+        }
+
+        // Share the loader and manager with the new stream
+        let new_stream = LoaderChunkedStream {
+            loader: Arc::clone(&self.loader),
+            manager: Arc::clone(&self.manager),
+            pos: 0,
+            start: 0,
+            chunk_size: self.chunk_size,
+            total_length: self.total_length,
+        };
+
+        let sub = super::sub_stream::SubStream::new(Box::new(new_stream), start, length)?;
+        Ok(Box::new(sub))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// In-memory loader that counts requests.
+    struct VecLoader {
+        data: Vec<u8>,
+        chunk_size: usize,
+        requests: usize,
+    }
+
+    impl ChunkLoader for VecLoader {
+        fn request_chunk(&mut self, chunk_num: usize) -> PDFResult<Vec<u8>> {
+            self.requests += 1;
+            let start = chunk_num * self.chunk_size;
+            let end = (start + self.chunk_size).min(self.data.len());
+            Ok(self.data[start..end].to_vec())
+        }
+
+        fn chunk_size(&self) -> usize {
+            self.chunk_size
+        }
+
+        fn total_length(&self) -> usize {
+            self.data.len()
+        }
+    }
+
+    fn make_stream(size: usize, chunk_size: usize) -> LoaderChunkedStream<VecLoader> {
+        let loader = VecLoader {
+            data: (0..size).map(|i| (i % 256) as u8).collect(),
+            chunk_size,
+            requests: 0,
+        };
+        LoaderChunkedStream::new(loader, None).unwrap()
+    }
+
+    #[test]
+    fn test_reads_across_chunks() {
+        let mut stream = make_stream(100, 16);
+        assert_eq!(stream.num_chunks(), 7);
+
+        stream.set_pos(10).unwrap();
+        let bytes = stream.get_bytes(20).unwrap();
+        assert_eq!(bytes, (10..30).map(|i| i as u8).collect::<Vec<_>>());
+        assert_eq!(stream.pos(), 30);
+        assert_eq!(stream.num_chunks_loaded(), 2);
+
+        // Last partial chunk
+        stream.set_pos(98).unwrap();
+        assert_eq!(stream.get_bytes(10).unwrap(), vec![98, 99]);
+    }
+
+    #[test]
+    fn test_byte_range_loads_on_demand() {
+        let stream = make_stream(100, 16);
+        assert_eq!(
+            stream.get_byte_range(40, 50).unwrap(),
+            (40..50).map(|i| i as u8).collect::<Vec<_>>()
+        );
+        assert_eq!(stream.num_chunks_loaded(), 2);
+        assert!(matches!(
+            stream.get_byte_range(90, 101),
+            Err(PDFError::InvalidByteRange { .. })
+        ));
+    }
+
+    #[test]
+    fn test_sub_stream_shares_cache() {
+        let stream = make_stream(64, 16);
+        stream.preload_range(0, 64).unwrap();
+
+        let mut sub = stream.make_sub_stream(20, 8).unwrap();
+        assert_eq!(sub.get_bytes(8).unwrap(), (20..28).collect::<Vec<u8>>());
+        assert_eq!(lock_loader(&stream.loader).unwrap().requests, 4);
+    }
+
+    #[test]
+    fn test_rejects_short_chunk() {
+        struct ShortLoader;
+        impl ChunkLoader for ShortLoader {
+            fn request_chunk(&mut self, _chunk_num: usize) -> PDFResult<Vec<u8>> {
+                Ok(vec![0; 3])
+            }
+            fn chunk_size(&self) -> usize {
+                8
+            }
+            fn total_length(&self) -> usize {
+                16
+            }
+        }
+
+        let mut stream = LoaderChunkedStream::new(ShortLoader, None).unwrap();
+        assert!(matches!(stream.get_byte(), Err(PDFError::StreamError(_))));
+    }
+}
@@ -0,0 +1,433 @@
+//! Checksum manifests for detecting changes between versions of a file.
+//!
+//! A [`Manifest`] holds stable digests of every object and every page of a
+//! document. Stream data is hashed after decoding and dictionaries are
+//! hashed with sorted keys, so re-compressing a stream or writing keys in a
+//! different order does not change a digest.
+//!
+//! Page digests follow references and ignore object numbers, so a page keeps
+//! its digest when a full rewrite renumbers its objects; comparing the page
+//! digests of two manifests tells which pages need to be re-rendered or
+//! re-OCRed.
+//!
+//! Digests are MD5 hex strings: they detect changes, they are not meant to
+//! resist tampering.
+
+use super::crypto::calculate_md5;
+use super::decode::decode_stream_data;
+use super::error::PDFResult;
+use super::page::Page;
+use super::parser::{PDFObject, Ref};
+use super::xref::XRef;
+use std::collections::{BTreeMap, HashMap};
+
+/// Page attributes that may be inherited from an ancestor in the page tree.
+const INHERITABLE_KEYS: [&str; 4] = ["Resources", "MediaBox", "CropBox", "Rotate"];
+
+/// Digests of one page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageDigest {
+    /// Zero-based page index
+    pub index: usize,
+
+    /// Reference to the page dictionary, if the page is an indirect object
+    pub page_ref: Option<Ref>,
+
+    /// Digest of the decoded content streams, concatenated
+    pub content: String,
+
+    /// Digest of everything the page draws with: the page dictionary with
+    /// its inherited attributes, content, resources and annotations
+    pub digest: String,
+}
+
+/// Digests of the objects and pages of a document.
+///
+/// # Example
+/// ```no_run
+/// use pdf_x_core::core::PDFDocument;
+///
+/// let mut old = PDFDocument::open_file("v1.pdf", None, None).unwrap();
+/// let mut new = PDFDocument::open_file("v2.pdf", None, None).unwrap();
+/// let changed = new.manifest().unwrap().changed_pages(&old.manifest().unwrap());
+/// println!("Pages to re-render: {:?}", changed);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Manifest {
+    /// Digest of the whole document: the catalog (without the page tree)
+    /// followed by the page digests
+    pub document: String,
+
+    /// Page digests, in page order
+    pub pages: Vec<PageDigest>,
+
+    /// Digest of each object, keyed by (object number, generation).
+    /// References inside an object are hashed as references, so these
+    /// digests depend on the numbering of the file.
+    pub objects: BTreeMap<(u32, u32), String>,
+}
+
+impl Manifest {
+    /// Returns the indices of the pages that differ from `previous`.
+    ///
+    /// Pages are compared by index; pages beyond the end of `previous` count
+    /// as changed.
+    pub fn changed_pages(&self, previous: &Manifest) -> Vec<usize> {
+        self.pages
+            .iter()
+            .filter(|page| {
+                previous
+                    .pages
+                    .get(page.index)
+                    .is_none_or(|old| old.digest != page.digest)
+            })
+            .map(|page| page.index)
+            .collect()
+    }
+
+    /// Returns the objects that were added or changed since `previous`.
+    pub fn changed_objects(&self, previous: &Manifest) -> Vec<(u32, u32)> {
+        self.objects
+            .iter()
+            .filter(|(id, digest)| previous.objects.get(*id) != Some(*digest))
+            .map(|(id, _)| *id)
+            .collect()
+    }
+}
+
+/// Computes the digest of a single object, with references kept shallow.
+pub(crate) fn object_digest(obj: &PDFObject) -> String {
+    let mut bytes = Vec::new();
+    write_canonical(obj, &mut bytes, &mut |obj_ref, out| {
+        out.extend_from_slice(format!("{} {};", obj_ref.num, obj_ref.generation).as_bytes())
+    });
+    hex_digest(&bytes)
+}
+
+/// Computes the digests of a page.
+///
+/// # Arguments
+/// * `xref` - Cross-reference table used to follow references
+/// * `page` - The page
+/// * `page_indices` - Index of every page by reference, so references to
+///   other pages (link destinations, annotation /P) hash as page indices
+pub(crate) fn page_digest(
+    xref: &mut XRef,
+    page: &Page,
+    page_indices: &HashMap<Ref, usize>,
+) -> PDFResult<PageDigest> {
+    let mut content = Vec::new();
+    if let Some(contents) = page.contents() {
+        let streams = match xref.fetch_if_ref(contents)? {
+            PDFObject::Array(items) => items.iter().map(|item| (**item).clone()).collect(),
+            other => vec![other],
+        };
+        for stream in streams {
+            if let PDFObject::Stream { dict, data } = xref.fetch_if_ref(&stream)? {
+                content.extend(decode_stream_data(&dict, &data).unwrap_or(data));
+                content.push(b'\n');
+            }
+        }
+    }
+
+    // Fill in inherited attributes so a page moved between tree nodes that
+    // define them differently counts as changed
+    let mut page_dict = match page.dict() {
+        PDFObject::Dictionary(dict) => dict.clone(),
+        _ => HashMap::new(),
+    };
+    for key in INHERITABLE_KEYS {
+        if !page_dict.contains_key(key)
+            && let Some(value) = inherited_attribute(xref, page.dict(), key)
+        {
+            page_dict.insert(key.to_string(), value);
+        }
+    }
+
+    let mut walker = DeepWalker::new(xref).with_pages(page_indices);
+    if let Some((num, generation)) = page.reference() {
+        walker.visited.insert(Ref::new(num, generation), 0);
+    }
+    let mut bytes = Vec::new();
+    walker.write(&PDFObject::Dictionary(page_dict), &mut bytes);
+
+    Ok(PageDigest {
+        index: page.index(),
+        page_ref: page
+            .reference()
+            .map(|(num, generation)| Ref::new(num, generation)),
+        content: hex_digest(&content),
+        digest: hex_digest(&bytes),
+    })
+}
+
+/// Computes the digest of the catalog, following references but leaving out
+/// the page tree.
+///
+/// # Arguments
+/// * `xref` - Cross-reference table used to follow references
+/// * `catalog` - The document catalog
+/// * `page_indices` - Index of every page by reference (see [`page_digest`])
+pub(crate) fn catalog_digest(
+    xref: &mut XRef,
+    catalog: &PDFObject,
+    page_indices: &HashMap<Ref, usize>,
+) -> String {
+    let catalog = match catalog {
+        PDFObject::Dictionary(dict) => {
+            let mut dict = dict.clone();
+            dict.remove("Pages");
+            PDFObject::Dictionary(dict)
+        }
+        other => other.clone(),
+    };
+    let mut bytes = Vec::new();
+    DeepWalker::new(xref)
+        .with_pages(page_indices)
+        .write(&catalog, &mut bytes);
+    hex_digest(&bytes)
+}
+
+/// Combines the catalog digest and the page digests into a document digest.
+pub(crate) fn document_digest(catalog: &str, pages: &[PageDigest]) -> String {
+    let mut bytes = catalog.as_bytes().to_vec();
+    for page in pages {
+        bytes.push(b'\n');
+        bytes.extend_from_slice(page.digest.as_bytes());
+    }
+    hex_digest(&bytes)
+}
+
+/// Looks up an inheritable attribute in the ancestors of a page.
+fn inherited_attribute(xref: &mut XRef, page_dict: &PDFObject, key: &str) -> Option<PDFObject> {
+    let mut node = page_dict.clone();
+    // Bounded walk: a malformed tree may loop through /Parent
+    for _ in 0..64 {
+        let parent = match &node {
+            PDFObject::Dictionary(dict) => dict.get("Parent")?.clone(),
+            _ => return None,
+        };
+        node = xref.fetch_if_ref(&parent).ok()?;
+        if let PDFObject::Dictionary(dict) = &node
+            && let Some(value) = dict.get(key)
+        {
+            return Some(value.clone());
+        }
+    }
+    None
+}
+
+/// Serializes objects for hashing, following references.
+///
+/// Each indirect object is written once; later references to it are written
+/// as the order in which it was first reached, which does not depend on
+/// object numbers. The walk does not go up or across the document: /Parent
+/// entries are skipped, /P references (an annotation's page, a structure
+/// element's parent) are not followed, and pages are written as their index
+/// rather than walked into. References nested deeper than the document's
+/// `max_object_depth` are not followed either.
+pub(super) struct DeepWalker<'a> {
+    xref: &'a mut XRef,
+    visited: HashMap<Ref, usize>,
+    pages: Option<&'a HashMap<Ref, usize>>,
+    depth: usize,
+    max_depth: usize,
+}
+
+impl<'a> DeepWalker<'a> {
+    pub(super) fn new(xref: &'a mut XRef) -> Self {
+        let max_depth = xref.limits().max_object_depth;
+        DeepWalker {
+            xref,
+            visited: HashMap::new(),
+            pages: None,
+            depth: 0,
+            max_depth,
+        }
+    }
+
+    /// Writes references to these pages as their index.
+    pub(super) fn with_pages(mut self, pages: &'a HashMap<Ref, usize>) -> Self {
+        self.pages = Some(pages);
+        self
+    }
+
+    pub(super) fn write(&mut self, obj: &PDFObject, out: &mut Vec<u8>) {
+        write_value(obj, out, &UPWARD_KEYS, &mut |obj_ref, out| {
+            if let Some(order) = self.visited.get(&obj_ref) {
+                out.extend_from_slice(format!("@{}", order).as_bytes());
+                return;
+            }
+            if let Some(index) = self.pages.and_then(|pages| pages.get(&obj_ref)) {
+                out.extend_from_slice(format!("P{};", index).as_bytes());
+                return;
+            }
+            if self.depth >= self.max_depth {
+                out.extend_from_slice(b"~");
+                return;
+            }
+            let order = self.visited.len();
+            self.visited.insert(obj_ref, order);
+            match self.xref.fetch(obj_ref.num, obj_ref.generation) {
+                Ok(target) if is_page(&target) => out.extend_from_slice(b"P;"),
+                Ok(target) => {
+                    self.depth += 1;
+                    self.write(&target, out);
+                    self.depth -= 1;
+                }
+                // Missing objects hash the same in every version
+                Err(_) => out.extend_from_slice(b"!"),
+            }
+        });
+    }
+}
+
+/// Keys whose references point up or across the object graph; a deep walk
+/// writes them without following them.
+const UPWARD_KEYS: [&str; 1] = ["P"];
+
+fn is_page(obj: &PDFObject) -> bool {
+    matches!(
+        obj,
+        PDFObject::Dictionary(dict)
+            if matches!(dict.get("Type"), Some(PDFObject::Name(name)) if name == "Page")
+    )
+}
+
+/// Writes an unambiguous, order-independent byte form of an object.
+///
+/// Dictionary keys are sorted and /Parent is skipped; stream data is
+/// decoded, and the entries that only describe the encoding are left out.
+/// References are handed to `on_ref`.
+pub(super) fn write_canonical(
+    obj: &PDFObject,
+    out: &mut Vec<u8>,
+    on_ref: &mut dyn FnMut(Ref, &mut Vec<u8>),
+) {
+    write_value(obj, out, &[], on_ref)
+}
+
+/// [`write_canonical`], writing references under the `upward` keys as a
+/// bare marker instead of handing them to `on_ref`.
+fn write_value(
+    obj: &PDFObject,
+    out: &mut Vec<u8>,
+    upward: &[&str],
+    on_ref: &mut dyn FnMut(Ref, &mut Vec<u8>),
+) {
+    match obj {
+        PDFObject::Null => out.push(b'n'),
+        PDFObject::Boolean(value) => out.push(if *value { b't' } else { b'f' }),
+        PDFObject::Number(value) => {
+            // -0 and 0 are the same number
+            let value = if *value == 0.0 { 0.0 } else { *value };
+            out.extend_from_slice(format!("#{};", value).as_bytes());
+        }
+        // Literal and hex strings differ only in syntax
+        PDFObject::String(bytes) | PDFObject::HexString(bytes) => write_bytes(b's', bytes, out),
+        PDFObject::Name(name) => write_bytes(b'/', name.as_bytes(), out),
+        PDFObject::Array(items) => {
+            out.push(b'[');
+            for item in items {
+                write_value(item, out, upward, on_ref);
+            }
+            out.push(b']');
+        }
+        PDFObject::Dictionary(dict) => write_dict(dict, &[], upward, out, on_ref),
+        PDFObject::Stream { dict, data } => {
+            let (data, encoding_keys): (Vec<u8>, &[&str]) = match decode_stream_data(dict, data) {
+                Ok(decoded) => (decoded, &["Length", "Filter", "DecodeParms", "DL"]),
+                // Undecodable data is hashed as stored, with its filters
+                Err(_) => (data.clone(), &["Length"]),
+            };
+            out.push(b'S');
+            write_dict(dict, encoding_keys, upward, out, on_ref);
+            write_bytes(b'd', &data, out);
+        }
+        PDFObject::Ref(obj_ref) => {
+            out.push(b'R');
+            on_ref(*obj_ref, out);
+        }
+        other => write_bytes(b'?', format!("{:?}", other).as_bytes(), out),
+    }
+}
+
+fn write_dict(
+    dict: &HashMap<String, PDFObject>,
+    skip: &[&str],
+    upward: &[&str],
+    out: &mut Vec<u8>,
+    on_ref: &mut dyn FnMut(Ref, &mut Vec<u8>),
+) {
+    let mut keys: Vec<&String> = dict
+        .keys()
+        .filter(|key| key.as_str() != "Parent" && !skip.contains(&key.as_str()))
+        .collect();
+    keys.sort();
+    out.push(b'<');
+    for key in keys {
+        write_bytes(b'/', key.as_bytes(), out);
+        match &dict[key] {
+            PDFObject::Ref(_) if upward.contains(&key.as_str()) => out.extend_from_slice(b"R^"),
+            value => write_value(value, out, upward, on_ref),
+        }
+    }
+    out.push(b'>');
+}
+
+/// Writes tagged, length-prefixed bytes.
+fn write_bytes(tag: u8, bytes: &[u8], out: &mut Vec<u8>) {
+    out.push(tag);
+    out.extend_from_slice(format!("{}:", bytes.len()).as_bytes());
+    out.extend_from_slice(bytes);
+}
+
+pub(super) fn hex_digest(bytes: &[u8]) -> String {
+    calculate_md5(bytes)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_object_digest_is_canonical() {
+        let mut a = HashMap::new();
+        a.insert("A".to_string(), PDFObject::Number(1.0));
+        a.insert("B".to_string(), PDFObject::String(b"x".to_vec()));
+        let mut b = HashMap::new();
+        b.insert("B".to_string(), PDFObject::HexString(b"x".to_vec()));
+        b.insert("A".to_string(), PDFObject::Number(1.0));
+        assert_eq!(
+            object_digest(&PDFObject::Dictionary(a.clone())),
+            object_digest(&PDFObject::Dictionary(b))
+        );
+
+        // Compressed and uncompressed copies of a stream hash the same
+        let mut flate = HashMap::new();
+        flate.insert(
+            "Filter".to_string(),
+            PDFObject::Name("FlateDecode".to_string()),
+        );
+        let compressed = crate::core::decode::encode_flate(b"0 0 m 10 10 l S").unwrap();
+        assert_eq!(
+            object_digest(&PDFObject::Stream {
+                dict: flate,
+                data: compressed,
+            }),
+            object_digest(&PDFObject::Stream {
+                dict: HashMap::new(),
+                data: b"0 0 m 10 10 l S".to_vec(),
+            })
+        );
+
+        a.insert("A".to_string(), PDFObject::Number(2.0));
+        assert_ne!(
+            object_digest(&PDFObject::Dictionary(a)),
+            object_digest(&PDFObject::Null)
+        );
+    }
+}
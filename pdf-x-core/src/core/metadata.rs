@@ -0,0 +1,684 @@
+//! Document metadata from the information dictionary and the XMP stream.
+//!
+//! PDF stores metadata twice: in the trailer's /Info dictionary (PDF strings,
+//! dates in the `D:YYYYMMDDHHmmSSOHH'mm'` format) and in an XMP packet
+//! referenced by the catalog's /Metadata entry. `DocumentMetadata` combines
+//! the two, preferring /Info and falling back to the XMP properties.
+//!
+//! Based on PDF.js src/core/document.js (documentInfo) and
+//! src/core/metadata_parser.js.
+
+use super::outline::decode_pdf_string;
+use super::parser::{PDFObject, Ref};
+use regex::Regex;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::sync::OnceLock;
+
+/// A calendar date and time as stored in PDF metadata.
+///
+/// Parsed from PDF date strings (`D:20240131120000+01'00'`) or XMP dates
+/// (`2024-01-31T12:00:00+01:00`). Fields missing from the source take their
+/// default (January, day 1, midnight).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PDFDate {
+    /// Year (e.g. 2024)
+    pub year: u16,
+    /// Month, 1-12
+    pub month: u8,
+    /// Day of the month, 1-31
+    pub day: u8,
+    /// Hour, 0-23
+    pub hour: u8,
+    /// Minute, 0-59
+    pub minute: u8,
+    /// Second, 0-59
+    pub second: u8,
+    /// Offset from UTC in minutes, `None` if the time zone is unknown
+    pub utc_offset_minutes: Option<i16>,
+}
+
+impl PDFDate {
+    /// Parses a PDF date string (ISO 32000-1, 7.9.4).
+    ///
+    /// The "D:" prefix is optional, as are all fields after the year.
+    /// Returns `None` if the string is not a valid date.
+    ///
+    /// # Example
+    /// ```
+    /// use pdf_x_core::core::PDFDate;
+    ///
+    /// let date = PDFDate::parse("D:20240131123000-05'00'").unwrap();
+    /// assert_eq!((date.year, date.month, date.day), (2024, 1, 31));
+    /// assert_eq!(date.utc_offset_minutes, Some(-300));
+    /// ```
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.trim();
+        let s = s.strip_prefix("D:").unwrap_or(s);
+        let bytes = s.as_bytes();
+
+        let digits = |start: usize, len: usize| -> Option<u16> {
+            let field = bytes.get(start..start + len)?;
+            if !field.iter().all(u8::is_ascii_digit) {
+                return None;
+            }
+            std::str::from_utf8(field).ok()?.parse().ok()
+        };
+
+        let year = digits(0, 4)?;
+        let mut pos = 4;
+        let mut fields = [1u8, 1, 0, 0, 0];
+        for field in fields.iter_mut() {
+            match digits(pos, 2) {
+                Some(value) => {
+                    *field = value as u8;
+                    pos += 2;
+                }
+                None => break,
+            }
+        }
+
+        let utc_offset_minutes = match bytes.get(pos) {
+            Some(b'Z') => Some(0),
+            Some(&sign @ (b'+' | b'-')) => {
+                let hours = digits(pos + 1, 2)? as i16;
+                // Minutes follow an apostrophe and may end with another
+                let minutes = digits(pos + 4, 2)
+                    .or_else(|| digits(pos + 3, 2))
+                    .unwrap_or(0) as i16;
+                let offset = hours * 60 + minutes;
+                Some(if sign == b'-' { -offset } else { offset })
+            }
+            _ => None,
+        };
+
+        Self::validated(year, fields, utc_offset_minutes)
+    }
+
+    /// Formats the date as a PDF date string, e.g. `D:20240131123000+01'00'`.
+    pub fn to_pdf_string(&self) -> String {
+        let mut s = format!(
+            "D:{:04}{:02}{:02}{:02}{:02}{:02}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        );
+        match self.utc_offset_minutes {
+            Some(0) => s.push('Z'),
+            Some(offset) => {
+                let sign = if offset < 0 { '-' } else { '+' };
+                let offset = offset.unsigned_abs();
+                s.push_str(&format!("{}{:02}'{:02}'", sign, offset / 60, offset % 60));
+            }
+            None => {}
+        }
+        s
+    }
+
+    /// Parses an XMP (ISO 8601) date such as `2024-01-31T12:30:00+01:00`.
+    ///
+    /// Fractional seconds are ignored. Returns `None` if the string is not a
+    /// valid date.
+    pub fn parse_xmp(s: &str) -> Option<Self> {
+        static DATE: OnceLock<Regex> = OnceLock::new();
+        let re = DATE.get_or_init(|| {
+            Regex::new(
+                r"^(\d{4})(?:-(\d{2})(?:-(\d{2})(?:T(\d{2}):(\d{2})(?::(\d{2})(?:\.\d+)?)?(Z|[+-]\d{2}:\d{2})?)?)?)?$",
+            )
+            .expect("valid XMP date pattern")
+        });
+        let caps = re.captures(s.trim())?;
+
+        let year = caps[1].parse().ok()?;
+        let mut fields = [1u8, 1, 0, 0, 0];
+        for (i, field) in fields.iter_mut().enumerate() {
+            if let Some(m) = caps.get(i + 2) {
+                *field = m.as_str().parse().ok()?;
+            }
+        }
+
+        let utc_offset_minutes = caps.get(7).map(|zone| match zone.as_str() {
+            "Z" => 0,
+            zone => {
+                let hours: i16 = zone[1..3].parse().unwrap_or(0);
+                let minutes: i16 = zone[4..6].parse().unwrap_or(0);
+                let offset = hours * 60 + minutes;
+                if zone.starts_with('-') {
+                    -offset
+                } else {
+                    offset
+                }
+            }
+        });
+
+        Self::validated(year, fields, utc_offset_minutes)
+    }
+
+    fn validated(year: u16, fields: [u8; 5], utc_offset_minutes: Option<i16>) -> Option<Self> {
+        let [month, day, hour, minute, second] = fields;
+        let valid = (1..=12).contains(&month)
+            && (1..=31).contains(&day)
+            && hour < 24
+            && minute < 60
+            && second < 60;
+
+        valid.then_some(PDFDate {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+            utc_offset_minutes,
+        })
+    }
+}
+
+impl fmt::Display for PDFDate {
+    /// Formats the date as ISO 8601, e.g. `2024-01-31T12:30:00+01:00`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )?;
+        match self.utc_offset_minutes {
+            Some(0) => write!(f, "Z"),
+            Some(offset) => {
+                let sign = if offset < 0 { '-' } else { '+' };
+                let offset = offset.unsigned_abs();
+                write!(f, "{}{:02}:{:02}", sign, offset / 60, offset % 60)
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+/// Structured document metadata.
+///
+/// # Example
+/// ```no_run
+/// use pdf_x_core::core::PDFDocument;
+///
+/// let mut doc = PDFDocument::open_file("document.pdf", None, None).unwrap();
+/// let metadata = doc.metadata().unwrap();
+/// println!("Title: {:?}", metadata.title);
+/// if let Some(created) = metadata.creation_date {
+///     println!("Created: {}", created);
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DocumentMetadata {
+    /// Document title
+    pub title: Option<String>,
+    /// Person who created the document
+    pub author: Option<String>,
+    /// Subject of the document
+    pub subject: Option<String>,
+    /// Keywords associated with the document
+    pub keywords: Option<String>,
+    /// Application that created the original document
+    pub creator: Option<String>,
+    /// Application that converted it to PDF
+    pub producer: Option<String>,
+    /// When the document was created
+    pub creation_date: Option<PDFDate>,
+    /// When the document was last modified
+    pub mod_date: Option<PDFDate>,
+    /// Non-standard /Info entries with string values
+    pub custom: BTreeMap<String, String>,
+    /// XMP properties keyed by qualified name (e.g. "dc:title")
+    pub xmp: BTreeMap<String, String>,
+}
+
+impl DocumentMetadata {
+    /// Builds metadata from a (resolved) information dictionary.
+    pub fn from_info(info: &HashMap<String, PDFObject>) -> Self {
+        let mut metadata = DocumentMetadata::default();
+
+        for (key, value) in info {
+            let text = match value {
+                PDFObject::String(bytes) | PDFObject::HexString(bytes) => decode_pdf_string(bytes),
+                _ => continue,
+            };
+            match key.as_str() {
+                "Title" => metadata.title = Some(text),
+                "Author" => metadata.author = Some(text),
+                "Subject" => metadata.subject = Some(text),
+                "Keywords" => metadata.keywords = Some(text),
+                "Creator" => metadata.creator = Some(text),
+                "Producer" => metadata.producer = Some(text),
+                "CreationDate" => metadata.creation_date = PDFDate::parse(&text),
+                "ModDate" => metadata.mod_date = PDFDate::parse(&text),
+                _ => {
+                    metadata.custom.insert(key.clone(), text);
+                }
+            }
+        }
+
+        metadata
+    }
+
+    /// Stores the XMP properties and fills fields missing from /Info.
+    pub fn merge_xmp(&mut self, xmp: BTreeMap<String, String>) {
+        let text = |key: &str| xmp.get(key).filter(|value| !value.is_empty()).cloned();
+        let date = |key: &str| xmp.get(key).and_then(|value| PDFDate::parse_xmp(value));
+
+        self.title = self.title.take().or_else(|| text("dc:title"));
+        self.author = self.author.take().or_else(|| text("dc:creator"));
+        self.subject = self.subject.take().or_else(|| text("dc:description"));
+        self.keywords = self.keywords.take().or_else(|| text("pdf:Keywords"));
+        self.creator = self.creator.take().or_else(|| text("xmp:CreatorTool"));
+        self.producer = self.producer.take().or_else(|| text("pdf:Producer"));
+        self.creation_date = self.creation_date.or_else(|| date("xmp:CreateDate"));
+        self.mod_date = self.mod_date.or_else(|| date("xmp:ModifyDate"));
+        self.xmp = xmp;
+    }
+}
+
+/// The document information dictionary as stored in one revision of a file.
+///
+/// Incremental updates may each write a new /Info dictionary. Only the newest
+/// one is in effect, but earlier values stay in the file; see
+/// `PDFDocument::info_revisions`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InfoRevision {
+    /// Revision index; 0 is the original file
+    pub revision: usize,
+    /// Byte offset of the revision's xref section
+    pub xref_offset: usize,
+    /// The /Info entry of the revision's trailer, if it is a reference
+    pub info_ref: Option<Ref>,
+    /// String entries of the dictionary, or `None` if the trailer has no /Info
+    pub entries: Option<BTreeMap<String, String>>,
+}
+
+/// An /Info entry whose value differs between revisions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InfoConflict {
+    /// The /Info key, e.g. "Author"
+    pub key: String,
+    /// The value in each revision that has an /Info dictionary, oldest first
+    pub values: Vec<(usize, Option<String>)>,
+}
+
+impl InfoConflict {
+    /// Returns the value in effect, i.e. the one from the newest revision.
+    pub fn current(&self) -> Option<&str> {
+        self.values.last().and_then(|(_, value)| value.as_deref())
+    }
+}
+
+/// Finds the /Info entries that changed between revisions.
+///
+/// Revisions whose trailer has no /Info are skipped. A key counts as
+/// conflicting when it has at least two distinct values, where being absent
+/// from a revision's dictionary is one of the values.
+pub fn find_info_conflicts(revisions: &[InfoRevision]) -> Vec<InfoConflict> {
+    let dicts: Vec<(usize, &BTreeMap<String, String>)> = revisions
+        .iter()
+        .filter_map(|r| r.entries.as_ref().map(|entries| (r.revision, entries)))
+        .collect();
+
+    let keys: BTreeSet<&String> = dicts
+        .iter()
+        .flat_map(|(_, entries)| entries.keys())
+        .collect();
+
+    let mut conflicts = Vec::new();
+    for key in keys {
+        let values: Vec<(usize, Option<String>)> = dicts
+            .iter()
+            .map(|(revision, entries)| (*revision, entries.get(key).cloned()))
+            .collect();
+        if values.iter().any(|(_, value)| *value != values[0].1) {
+            conflicts.push(InfoConflict {
+                key: key.clone(),
+                values,
+            });
+        }
+    }
+    conflicts
+}
+
+/// Extracts the simple properties of an XMP packet.
+///
+/// Returns one entry per property of each `rdf:Description`, keyed by its
+/// qualified name. Properties may be given as attributes or child elements;
+/// for language alternatives (`rdf:Alt`) the first item is used and ordered or
+/// unordered arrays (`rdf:Seq`, `rdf:Bag`) are joined with ", ". Structured
+/// values without text are skipped.
+///
+/// This is a lightweight scanner rather than a full XML parser; it doesn't
+/// resolve namespace prefixes.
+pub fn parse_xmp(xml: &str) -> BTreeMap<String, String> {
+    static TAG: OnceLock<Regex> = OnceLock::new();
+    static ATTR: OnceLock<Regex> = OnceLock::new();
+    let tag_re = TAG.get_or_init(|| Regex::new(r"<[^>]*>").expect("valid tag pattern"));
+    let attr_re = ATTR.get_or_init(|| {
+        Regex::new(r#"([\w.\-]+:[\w.\-]+)\s*=\s*(?:"([^"]*)"|'([^']*)')"#)
+            .expect("valid attribute pattern")
+    });
+
+    /// A property element being read: name, depth, array items, alternative?
+    struct Property {
+        name: String,
+        depth: usize,
+        items: Vec<String>,
+        alternative: bool,
+    }
+
+    let mut properties = BTreeMap::new();
+    let mut stack: Vec<String> = Vec::new();
+    let mut property: Option<Property> = None;
+    let mut text = String::new();
+    let mut last_end = 0;
+
+    for tag in tag_re.find_iter(xml) {
+        text.push_str(&xml[last_end..tag.start()]);
+        last_end = tag.end();
+
+        let inner = &tag.as_str()[1..tag.as_str().len() - 1];
+        if inner.starts_with('?') || inner.starts_with('!') {
+            continue;
+        }
+
+        if let Some(name) = inner.strip_prefix('/') {
+            let name = name.trim();
+            if name == "rdf:li"
+                && let Some(property) = property.as_mut()
+            {
+                property.items.push(unescape_xml(text.trim()));
+            }
+            if property.as_ref().is_some_and(|p| p.depth == stack.len()) {
+                let Property {
+                    name,
+                    items,
+                    alternative,
+                    ..
+                } = property.take().unwrap();
+                let items: Vec<String> = items.into_iter().filter(|i| !i.is_empty()).collect();
+                let value = if items.is_empty() {
+                    unescape_xml(text.trim())
+                } else if alternative {
+                    items[0].clone()
+                } else {
+                    items.join(", ")
+                };
+                if !value.is_empty() {
+                    properties.insert(name, value);
+                }
+            }
+            stack.pop();
+            text.clear();
+            continue;
+        }
+
+        let self_closing = inner.ends_with('/');
+        let inner = inner.trim_end_matches('/');
+        let name = inner
+            .split_whitespace()
+            .next()
+            .unwrap_or_default()
+            .to_string();
+        text.clear();
+
+        if name == "rdf:Description" {
+            for caps in attr_re.captures_iter(inner) {
+                let key = &caps[1];
+                if key.starts_with("xmlns:") || key.starts_with("rdf:") {
+                    continue;
+                }
+                let value = caps
+                    .get(2)
+                    .or_else(|| caps.get(3))
+                    .map_or("", |m| m.as_str());
+                properties.insert(key.to_string(), unescape_xml(value));
+            }
+        } else if property.is_none()
+            && stack
+                .last()
+                .is_some_and(|parent| parent == "rdf:Description")
+            && !name.starts_with("rdf:")
+        {
+            if self_closing {
+                // <ns:Prop rdf:resource="..."/>
+                if let Some(caps) = attr_re
+                    .captures_iter(inner)
+                    .find(|caps| &caps[1] == "rdf:resource")
+                {
+                    let value = caps
+                        .get(2)
+                        .or_else(|| caps.get(3))
+                        .map_or("", |m| m.as_str());
+                    properties.insert(name.clone(), unescape_xml(value));
+                }
+            } else {
+                property = Some(Property {
+                    name: name.clone(),
+                    depth: stack.len() + 1,
+                    items: Vec::new(),
+                    alternative: false,
+                });
+            }
+        } else if name == "rdf:Alt"
+            && let Some(property) = property.as_mut()
+        {
+            property.alternative = true;
+        }
+
+        if !self_closing {
+            stack.push(name);
+        }
+    }
+
+    properties
+}
+
+/// Replaces the predefined XML entities and character references.
+fn unescape_xml(s: &str) -> String {
+    if !s.contains('&') {
+        return s.to_string();
+    }
+
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+
+        let Some(semi) = rest.find(';') else { break };
+        let entity = &rest[1..semi];
+        let replacement = match entity {
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "amp" => Some('&'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(str::parse))
+                .and_then(Result::ok)
+                .and_then(char::from_u32),
+        };
+
+        match replacement {
+            Some(c) => {
+                out.push(c);
+                rest = &rest[semi + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_pdf_date() {
+        let date = PDFDate::parse("D:20240131123045+05'30'").unwrap();
+        assert_eq!(
+            date,
+            PDFDate {
+                year: 2024,
+                month: 1,
+                day: 31,
+                hour: 12,
+                minute: 30,
+                second: 45,
+                utc_offset_minutes: Some(330),
+            }
+        );
+        assert_eq!(date.to_string(), "2024-01-31T12:30:45+05:30");
+
+        // Optional fields and time zones
+        let date = PDFDate::parse("D:199812").unwrap();
+        assert_eq!((date.year, date.month, date.day), (1998, 12, 1));
+        assert_eq!(date.utc_offset_minutes, None);
+        assert_eq!(
+            PDFDate::parse("20010203040506Z").unwrap().to_string(),
+            "2001-02-03T04:05:06Z"
+        );
+        assert_eq!(
+            PDFDate::parse("D:20010203040506-08'00")
+                .unwrap()
+                .utc_offset_minutes,
+            Some(-480)
+        );
+
+        assert_eq!(PDFDate::parse("D:20241301"), None);
+        assert_eq!(PDFDate::parse("yesterday"), None);
+    }
+
+    #[test]
+    fn test_parse_xmp_date() {
+        let date = PDFDate::parse_xmp("2024-01-31T12:30:45.123-05:00").unwrap();
+        assert_eq!(date.to_string(), "2024-01-31T12:30:45-05:00");
+
+        let date = PDFDate::parse_xmp("2024-06").unwrap();
+        assert_eq!((date.year, date.month, date.day), (2024, 6, 1));
+
+        assert_eq!(PDFDate::parse_xmp("31/01/2024"), None);
+    }
+
+    #[test]
+    fn test_from_info() {
+        let mut info = HashMap::new();
+        info.insert("Title".to_string(), PDFObject::String(b"Report".to_vec()));
+        info.insert(
+            "Author".to_string(),
+            PDFObject::HexString(vec![0xFE, 0xFF, 0x00, 0x41, 0x00, 0x6E, 0x00, 0x6E]),
+        );
+        info.insert(
+            "CreationDate".to_string(),
+            PDFObject::String(b"D:20200102030405Z".to_vec()),
+        );
+        info.insert("Department".to_string(), PDFObject::String(b"R&D".to_vec()));
+        info.insert("Trapped".to_string(), PDFObject::Name("False".to_string()));
+
+        let metadata = DocumentMetadata::from_info(&info);
+        assert_eq!(metadata.title.as_deref(), Some("Report"));
+        assert_eq!(metadata.author.as_deref(), Some("Ann"));
+        assert_eq!(
+            metadata.creation_date.unwrap().to_string(),
+            "2020-01-02T03:04:05Z"
+        );
+        assert_eq!(metadata.mod_date, None);
+        assert_eq!(
+            metadata.custom.get("Department").map(String::as_str),
+            Some("R&D")
+        );
+        assert!(!metadata.custom.contains_key("Trapped"));
+    }
+
+    const XMP: &str = r#"<?xpacket begin="" id="W5M0MpCehiHzreSzNTczkc9d"?>
+<x:xmpmeta xmlns:x="adobe:ns:meta/">
+ <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+  <rdf:Description rdf:about="" xmlns:pdf="http://ns.adobe.com/pdf/1.3/"
+      pdf:Producer="Writer &amp; Co" pdf:Keywords='alpha, beta'/>
+  <rdf:Description rdf:about="" xmlns:dc="http://purl.org/dc/elements/1.1/"
+      xmlns:xmp="http://ns.adobe.com/xap/1.0/">
+   <dc:title><rdf:Alt>
+     <rdf:li xml:lang="x-default">Annual &lt;Report&gt;</rdf:li>
+     <rdf:li xml:lang="de">Jahresbericht</rdf:li>
+   </rdf:Alt></dc:title>
+   <dc:creator><rdf:Seq><rdf:li>Ann</rdf:li><rdf:li>Bob</rdf:li></rdf:Seq></dc:creator>
+   <xmp:CreateDate>2023-05-06T07:08:09Z</xmp:CreateDate>
+   <dc:source rdf:resource="http://example.com/"/>
+  </rdf:Description>
+ </rdf:RDF>
+</x:xmpmeta>
+<?xpacket end="w"?>"#;
+
+    #[test]
+    fn test_parse_xmp() {
+        let props = parse_xmp(XMP);
+        assert_eq!(props["dc:title"], "Annual <Report>");
+        assert_eq!(props["dc:creator"], "Ann, Bob");
+        assert_eq!(props["xmp:CreateDate"], "2023-05-06T07:08:09Z");
+        assert_eq!(props["pdf:Producer"], "Writer & Co");
+        assert_eq!(props["pdf:Keywords"], "alpha, beta");
+        assert_eq!(props["dc:source"], "http://example.com/");
+        assert_eq!(props.len(), 6);
+    }
+
+    #[test]
+    fn test_merge_xmp_prefers_info() {
+        let mut info = HashMap::new();
+        info.insert(
+            "Title".to_string(),
+            PDFObject::String(b"Info title".to_vec()),
+        );
+        let mut metadata = DocumentMetadata::from_info(&info);
+        metadata.merge_xmp(parse_xmp(XMP));
+
+        assert_eq!(metadata.title.as_deref(), Some("Info title"));
+        assert_eq!(metadata.author.as_deref(), Some("Ann, Bob"));
+        assert_eq!(metadata.producer.as_deref(), Some("Writer & Co"));
+        assert_eq!(
+            metadata.creation_date.unwrap().to_string(),
+            "2023-05-06T07:08:09Z"
+        );
+        assert_eq!(metadata.xmp.len(), 6);
+    }
+
+    #[test]
+    fn test_find_info_conflicts() {
+        let revision = |revision: usize, entries: Option<&[(&str, &str)]>| InfoRevision {
+            revision,
+            xref_offset: 0,
+            info_ref: None,
+            entries: entries.map(|entries| {
+                entries
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect()
+            }),
+        };
+        let revisions = [
+            revision(0, Some(&[("Author", "Ann"), ("Title", "Report")])),
+            revision(1, None),
+            revision(
+                2,
+                Some(&[("Author", "Bob"), ("Title", "Report"), ("Subject", "Q3")]),
+            ),
+        ];
+
+        let conflicts = find_info_conflicts(&revisions);
+        assert_eq!(conflicts.len(), 2);
+        assert_eq!(conflicts[0].key, "Author");
+        assert_eq!(
+            conflicts[0].values,
+            vec![(0, Some("Ann".to_string())), (2, Some("Bob".to_string()))]
+        );
+        assert_eq!(conflicts[0].current(), Some("Bob"));
+        assert_eq!(conflicts[1].key, "Subject");
+        assert_eq!(conflicts[1].values[0], (0, None));
+    }
+}
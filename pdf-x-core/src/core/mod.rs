@@ -1,9 +1,17 @@
+pub mod action;
 pub mod annotation;
+pub mod attachments;
 pub mod base_stream;
+pub mod cancel;
+pub mod catalog;
 pub mod chunk_manager;
 pub mod cmap;
+pub mod colorspace;
+pub mod composite_stream;
+pub mod content_scanner;
 pub mod content_stream;
 pub mod crypto;
+pub mod cycle_guard;
 pub mod decode;
 pub mod delta;
 pub mod document;
@@ -12,54 +20,131 @@ pub mod encryption;
 pub mod error;
 pub mod file_chunked_stream;
 pub mod font;
+pub mod form;
+pub mod function;
+pub mod hint_tables;
 pub mod image;
+pub mod layout;
 pub mod lexer;
+pub mod limits;
+pub mod link_detection;
+pub mod loader_chunked_stream;
+pub mod manifest;
+pub mod metadata;
+pub mod name_number_tree;
+pub mod object_cache;
+pub mod open_options;
 pub mod outline;
 pub mod page;
+pub mod page_boxes;
+pub mod page_hash;
+pub mod page_labels;
 pub mod parser;
 pub mod pdf_writer;
+pub mod redact;
+pub mod reflow;
 pub mod retry;
+pub mod revision;
+pub mod shading;
+pub mod signature;
 pub mod stream;
+pub mod struct_tree;
 pub mod sub_stream;
+pub mod text_replace;
+pub mod toc;
+pub mod validate;
 pub mod xref;
 
+#[cfg(feature = "async")]
+pub mod async_document;
 #[cfg(feature = "async")]
 pub mod async_http_chunked_stream;
 #[cfg(feature = "async")]
 pub mod http_chunked_stream;
+#[cfg(feature = "serde")]
+pub mod serialize;
 
+pub use action::{
+    Action, ActionDestination, ActionKind, DocumentScript, ExternalLink, ExternalLinkKind,
+};
 pub use annotation::{
-    Annotation, AnnotationBorder, AnnotationColor, AnnotationData, AnnotationFlags, AnnotationRect,
-    AnnotationType, FileAttachmentAnnotation, FormFieldType, LinkAction, LinkAnnotation,
-    PopupAnnotation, TextAnnotation, WidgetAnnotation,
+    Annotation, AnnotationBorder, AnnotationColor, AnnotationData, AnnotationFlags, AnnotationQuad,
+    AnnotationRect, AnnotationType, FileAttachmentAnnotation, FormFieldType, LinkAction,
+    LinkAnnotation, LinkTarget, MarkupAnnotation, PageLink, PopupAnnotation, TextAnnotation,
+    WidgetAnnotation,
 };
+pub use attachments::{AFRelationship, Attachment, EInvoice, EInvoiceStandard};
 pub use base_stream::BaseStream;
-pub use chunk_manager::{ChunkLoader, ChunkManager};
-pub use cmap::CMap;
-pub use content_stream::{ContentStreamEvaluator, OpCode, Operation, TextItem};
+pub use cancel::{CancellationToken, Progress};
+pub use catalog::{Catalog, PageMode, Trailer};
+pub use chunk_manager::{ChunkLoader, ChunkManager, ChunkedStreamOptions};
+pub use cmap::{CMap, CodespaceRange};
+pub use colorspace::ColorSpace;
+pub use composite_stream::CompositeStream;
+pub use content_scanner::{ContentScanner, ScanOperand, ScannedOperation};
+pub use content_stream::{
+    ContentStreamEvaluator, ExtractionMode, OpCode, Operation, TextGlyph, TextItem,
+};
 pub use crypto::{
     AES128Cipher, AES256Cipher, ARC4Cipher, PDF17, PDF20, PDFPasswordAlgorithm, calculate_md5,
     calculate_sha256, calculate_sha384, calculate_sha512,
 };
-pub use delta::{Command, DeltaLayer, DeltaObject, RotatePageCommand};
-pub use document::{LinearizedInfo, PDFDocument};
+pub use cycle_guard::CycleGuard;
+pub use delta::{
+    AddLinkAnnotationsCommand, AddObjectCommand, ChangeReport, Command, DeleteObjectCommand,
+    DeltaCheckpoint, DeltaLayer, DeltaObject, EditPageTreeCommand, PageTreeEdit,
+    ReplaceObjectsCommand, RotatePageCommand,
+};
+pub use document::{
+    DocumentSummary, ExtractAllTextOptions, LinearizedInfo, PDFDocument, PageSummary, PageText,
+};
 pub use encoding::Encoding;
-pub use encryption::{EncryptDict, EncryptionAlgorithm, EncryptionVersion, PDFPermissions};
-pub use error::PDFError;
-pub use file_chunked_stream::FileChunkedStream;
-pub use font::{Font, FontDict, FontType};
+pub use encryption::{
+    CipherTransform, CryptMethod, EncryptDict, EncryptionAlgorithm, EncryptionVersion,
+    PDFPermissions, Permission,
+};
+pub use error::{ErrorCode, PDFError};
+pub use file_chunked_stream::{FileChunkedStream, FileLoader};
+pub use font::{
+    CIDEncoding, CIDFontInfo, Font, FontDict, FontProgramFormat, FontType, StandardFont,
+};
+pub use form::{FormField, SetFieldValueCommand};
+pub use function::PDFFunction;
 pub use image::{
-    DecodedImage, ImageColorSpace, ImageDecoder, ImageExtraction, ImageFormat, ImageMetadata,
+    DecodedImage, ImageColorSpace, ImageDecoder, ImageDetection, ImageExtraction, ImageFormat,
+    ImageMetadata,
 };
+pub use layout::{TextLayoutOptions, TextLine, TextWord};
 pub use lexer::{Lexer, Token};
-pub use outline::{DestinationType, OutlineDestination, OutlineItem};
+pub use limits::ResourceLimits;
+pub use link_detection::{DetectedLink, DetectedLinkKind};
+pub use loader_chunked_stream::LoaderChunkedStream;
+pub use manifest::{Manifest, PageDigest};
+pub use metadata::{DocumentMetadata, InfoConflict, InfoRevision, PDFDate};
+pub use object_cache::{CacheBudget, CacheStats, ObjectCache};
+pub use open_options::{OpenOptions, ParseOptions, Pdf};
+pub use outline::{DestinationType, OutlineDestination, OutlineItem, ResolvedOutlineItem};
 pub use page::{Page, PageTreeCache};
+pub use page_boxes::{PageBox, PageBoxes};
+pub use page_hash::PerceptualHash;
+pub use page_labels::{PageLabelRange, PageLabelStyle, PageLabels};
 pub use parser::{PDFObject, Parser, Ref};
 pub use pdf_writer::PDFWriter;
+pub use redact::{RedactOptions, RedactionReport, redact_content};
+pub use reflow::{FontSizeClass, FontStyle, ReflowParagraph, StyleRun};
+pub use revision::Revision;
+pub use shading::{Shading, ShadingGeometry};
+pub use signature::Signature;
 pub use stream::Stream;
+pub use struct_tree::{StructElement, StructKid, StructTree};
 pub use sub_stream::SubStream;
-pub use xref::{XRef, XRefEntry};
+pub use text_replace::{TextReplaceReport, TextReplacer};
+pub use toc::TocOptions;
+pub use validate::{ValidationProfile, ValidationReport, ValidationRule, Violation};
+pub use xref::{Diagnostic, DiagnosticKind, XRef, XRefEntry, XRefRevision};
 
+#[cfg(feature = "async")]
+pub use async_document::{AsyncChunkLoader, AsyncPDFDocument};
 #[cfg(feature = "async")]
 pub use async_http_chunked_stream::{AsyncHttpChunkedStream, ProgressCallback};
 #[cfg(feature = "async")]
@@ -0,0 +1,177 @@
+//! Flattening of name trees and number trees.
+//!
+//! Name trees (ISO 32000-1, 7.9.6) and number trees (7.9.7) map keys to
+//! values through a tree of /Kids nodes with /Names or /Nums leaves. They are
+//! used for embedded files, named destinations, page labels and more.
+//!
+//! Based on PDF.js src/core/name_number_tree.js.
+
+use super::cycle_guard::CycleGuard;
+use super::error::{PDFError, PDFResult};
+use super::outline::decode_pdf_string;
+use super::parser::PDFObject;
+use super::xref::XRef;
+use crate::log_target;
+
+/// Callback resolving an indirect reference (other objects are returned as
+/// is).
+pub(crate) type Resolver<'a> = dyn FnMut(&PDFObject) -> PDFResult<PDFObject> + 'a;
+
+/// Callback receiving the /Names or /Nums array of a tree node.
+type LeafVisitor<'a> = dyn FnMut(&mut Resolver, &[Box<PDFObject>]) -> PDFResult<()> + 'a;
+
+/// Walks a tree and calls `leaf` with the resolved /Names or /Nums array of
+/// every node, in tree order.
+///
+/// A node reached a second time is skipped, so a /Kids entry pointing back
+/// up the tree (or listing a node twice) neither loops nor repeats entries.
+/// A tree nested deeper than `max_depth` fails with
+/// [`PDFError::LimitExceeded`].
+fn walk(
+    resolve: &mut Resolver,
+    node: &PDFObject,
+    leaf_key: &str,
+    leaf: &mut LeafVisitor,
+    guard: &mut CycleGuard,
+    depth: usize,
+    max_depth: usize,
+) -> PDFResult<()> {
+    if depth > max_depth {
+        return Err(PDFError::limit_exceeded(
+            format!("/{} tree depth", leaf_key),
+            max_depth,
+        ));
+    }
+    if !guard.visit_object(node) {
+        tracing::warn!(
+            target: log_target::DOCUMENT,
+            "Skipping repeated node {:?} in /{} tree",
+            node,
+            leaf_key
+        );
+        return Ok(());
+    }
+    let dict = match resolve(node)? {
+        PDFObject::Dictionary(dict) => dict,
+        _ => return Ok(()),
+    };
+
+    if let Some(entries) = dict.get(leaf_key)
+        && let PDFObject::Array(entries) = resolve(entries)?
+    {
+        leaf(resolve, &entries)?;
+    }
+    if let Some(kids) = dict.get("Kids")
+        && let PDFObject::Array(kids) = resolve(kids)?
+    {
+        for kid in kids.iter() {
+            walk(resolve, kid, leaf_key, leaf, guard, depth + 1, max_depth)?;
+        }
+    }
+    Ok(())
+}
+
+/// Collects the entries of a number tree as (key, resolved value) pairs, in
+/// tree order.
+pub(crate) fn number_tree_entries(
+    xref: &mut XRef,
+    node: &PDFObject,
+) -> PDFResult<Vec<(usize, PDFObject)>> {
+    let mut entries = Vec::new();
+    let max_depth = xref.limits().max_object_depth;
+    walk(
+        &mut |obj| xref.fetch_if_ref(obj),
+        node,
+        "Nums",
+        &mut |resolve, nums| {
+            for pair in nums.chunks_exact(2) {
+                if let PDFObject::Number(key) = *pair[0] {
+                    let value = resolve(&pair[1])?;
+                    entries.push((key.max(0.0) as usize, value));
+                }
+            }
+            Ok(())
+        },
+        &mut CycleGuard::new(),
+        0,
+        max_depth,
+    )?;
+    Ok(entries)
+}
+
+/// Collects the entries of a name tree as (key, value) pairs, in tree order.
+///
+/// Keys are decoded as PDF text strings. Values are returned as stored, so
+/// indirect references are kept (callers often need them to recognise
+/// shared objects).
+pub(crate) fn name_tree_entries(
+    xref: &mut XRef,
+    node: &PDFObject,
+) -> PDFResult<Vec<(String, PDFObject)>> {
+    let mut entries = Vec::new();
+    let max_depth = xref.limits().max_object_depth;
+    walk(
+        &mut |obj| xref.fetch_if_ref(obj),
+        node,
+        "Names",
+        &mut |_, names| {
+            for pair in names.chunks_exact(2) {
+                match &*pair[0] {
+                    PDFObject::String(bytes) | PDFObject::HexString(bytes) => {
+                        entries.push((decode_pdf_string(bytes), (*pair[1]).clone()));
+                    }
+                    // Some writers use names instead of strings for keys
+                    PDFObject::Name(name) => entries.push((name.clone(), (*pair[1]).clone())),
+                    _ => {}
+                }
+            }
+            Ok(())
+        },
+        &mut CycleGuard::new(),
+        0,
+        max_depth,
+    )?;
+    Ok(entries)
+}
+
+/// Collects the entries of a name tree as (key bytes, value) pairs, in tree
+/// order.
+///
+/// Unlike [`name_tree_entries`], keys are left undecoded, so a tree can be
+/// rewritten with its keys and their byte order unchanged.
+///
+/// # Arguments
+/// * `resolve` - Resolves the references of the tree nodes
+/// * `node` - Root of the tree
+/// * `max_depth` - How deeply the tree may nest (the document's
+///   `max_object_depth`)
+pub(crate) fn raw_name_tree_entries(
+    resolve: &mut Resolver,
+    node: &PDFObject,
+    max_depth: usize,
+) -> PDFResult<Vec<(Vec<u8>, PDFObject)>> {
+    let mut entries = Vec::new();
+    walk(
+        resolve,
+        node,
+        "Names",
+        &mut |_, names| {
+            for pair in names.chunks_exact(2) {
+                match &*pair[0] {
+                    PDFObject::String(bytes) | PDFObject::HexString(bytes) => {
+                        entries.push((bytes.clone(), (*pair[1]).clone()));
+                    }
+                    PDFObject::Name(name) => {
+                        entries.push((name.as_bytes().to_vec(), (*pair[1]).clone()))
+                    }
+                    _ => {}
+                }
+            }
+            Ok(())
+        },
+        &mut CycleGuard::new(),
+        0,
+        max_depth,
+    )?;
+    Ok(entries)
+}
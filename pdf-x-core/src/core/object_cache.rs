@@ -0,0 +1,315 @@
+//! Byte-budgeted caches for parsed objects and decoded stream data.
+//!
+//! `XRef` keeps every object it parses in an LRU cache so that repeated
+//! fetches don't re-read and re-parse the file. Decoding is cached
+//! separately: content streams, font programs and object streams are inflated
+//! once and shared until they fall out of the cache. Both caches are bounded
+//! by an estimate of the memory they hold rather than by an entry count, since
+//! a single image stream can outweigh thousands of small dictionaries.
+
+use super::parser::PDFObject;
+use lru::LruCache;
+use std::hash::{BuildHasherDefault, Hash};
+use std::rc::Rc;
+
+type FxBuildHasher = BuildHasherDefault<rustc_hash::FxHasher>;
+
+/// Memory budgets of the object and decoded-stream caches, in bytes.
+///
+/// Sizes are estimates: string and stream bytes are counted exactly, other
+/// values by a fixed per-node overhead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheBudget {
+    /// Budget for parsed objects (default 16 MiB)
+    pub object_bytes: usize,
+
+    /// Budget for decoded stream data (default 32 MiB)
+    pub stream_bytes: usize,
+}
+
+impl Default for CacheBudget {
+    fn default() -> Self {
+        CacheBudget {
+            object_bytes: 16 * 1024 * 1024,
+            stream_bytes: 32 * 1024 * 1024,
+        }
+    }
+}
+
+/// Counters describing how well the caches are doing.
+///
+/// Hits and misses count lookups since the document was opened or the
+/// statistics were last reset; the sizes describe the current contents.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Object fetches answered from the cache
+    pub object_hits: u64,
+
+    /// Object fetches that had to parse the file
+    pub object_misses: u64,
+
+    /// Objects dropped to stay within the budget
+    pub object_evictions: u64,
+
+    /// Number of objects currently cached
+    pub object_count: usize,
+
+    /// Estimated size of the cached objects
+    pub object_bytes: usize,
+
+    /// Decoded-stream lookups answered from the cache
+    pub stream_hits: u64,
+
+    /// Decoded-stream lookups that had to decode the stream
+    pub stream_misses: u64,
+
+    /// Decoded streams dropped to stay within the budget
+    pub stream_evictions: u64,
+
+    /// Number of decoded streams currently cached
+    pub stream_count: usize,
+
+    /// Total size of the cached decoded streams
+    pub stream_bytes: usize,
+}
+
+/// An LRU cache whose entries carry a size, evicting once the sizes exceed
+/// the budget.
+struct BudgetedLru<K: Hash + Eq, V> {
+    entries: LruCache<K, (V, usize), FxBuildHasher>,
+    bytes: usize,
+    budget: usize,
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+}
+
+impl<K: Hash + Eq, V: Clone> BudgetedLru<K, V> {
+    fn new(budget: usize) -> Self {
+        BudgetedLru {
+            entries: LruCache::unbounded_with_hasher(FxBuildHasher::default()),
+            bytes: 0,
+            budget,
+            hits: 0,
+            misses: 0,
+            evictions: 0,
+        }
+    }
+
+    fn get(&mut self, key: &K) -> Option<V> {
+        match self.entries.get(key) {
+            Some((value, _)) => {
+                self.hits += 1;
+                Some(value.clone())
+            }
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    fn put(&mut self, key: K, value: V, size: usize) {
+        if let Some((_, old_size)) = self.entries.pop(&key) {
+            self.bytes -= old_size;
+        }
+        // An entry larger than the whole budget would only flush everything
+        // else and then be evicted itself
+        if size > self.budget {
+            return;
+        }
+        self.entries.put(key, (value, size));
+        self.bytes += size;
+        self.shrink();
+    }
+
+    fn set_budget(&mut self, budget: usize) {
+        self.budget = budget;
+        self.shrink();
+    }
+
+    fn shrink(&mut self) {
+        while self.bytes > self.budget {
+            match self.entries.pop_lru() {
+                Some((_, (_, size))) => {
+                    self.bytes -= size;
+                    self.evictions += 1;
+                }
+                None => break,
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.bytes = 0;
+    }
+
+    fn reset_stats(&mut self) {
+        self.hits = 0;
+        self.misses = 0;
+        self.evictions = 0;
+    }
+}
+
+/// Caches of parsed objects and of decoded stream data, both keyed by object
+/// number and generation.
+pub struct ObjectCache {
+    objects: BudgetedLru<(u32, u32), Rc<PDFObject>>,
+    streams: BudgetedLru<(u32, u32), Rc<Vec<u8>>>,
+}
+
+impl ObjectCache {
+    /// Creates empty caches with the given budget.
+    pub fn new(budget: CacheBudget) -> Self {
+        ObjectCache {
+            objects: BudgetedLru::new(budget.object_bytes),
+            streams: BudgetedLru::new(budget.stream_bytes),
+        }
+    }
+
+    /// Looks up a parsed object.
+    pub fn get_object(&mut self, obj_num: u32, generation: u32) -> Option<Rc<PDFObject>> {
+        self.objects.get(&(obj_num, generation))
+    }
+
+    /// Caches a parsed object, evicting the least recently used objects if
+    /// the budget is exceeded.
+    pub fn put_object(&mut self, obj_num: u32, generation: u32, object: Rc<PDFObject>) {
+        let size = estimated_size(&object);
+        self.objects.put((obj_num, generation), object, size);
+    }
+
+    /// Looks up the decoded data of a stream object.
+    pub fn get_stream(&mut self, obj_num: u32, generation: u32) -> Option<Rc<Vec<u8>>> {
+        self.streams.get(&(obj_num, generation))
+    }
+
+    /// Caches the decoded data of a stream object.
+    pub fn put_stream(&mut self, obj_num: u32, generation: u32, data: Rc<Vec<u8>>) {
+        let size = data.len();
+        self.streams.put((obj_num, generation), data, size);
+    }
+
+    /// Returns the current budget.
+    pub fn budget(&self) -> CacheBudget {
+        CacheBudget {
+            object_bytes: self.objects.budget,
+            stream_bytes: self.streams.budget,
+        }
+    }
+
+    /// Changes the budget, evicting entries right away if it shrank.
+    pub fn set_budget(&mut self, budget: CacheBudget) {
+        self.objects.set_budget(budget.object_bytes);
+        self.streams.set_budget(budget.stream_bytes);
+    }
+
+    /// Returns hit, miss and size counters for both caches.
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            object_hits: self.objects.hits,
+            object_misses: self.objects.misses,
+            object_evictions: self.objects.evictions,
+            object_count: self.objects.entries.len(),
+            object_bytes: self.objects.bytes,
+            stream_hits: self.streams.hits,
+            stream_misses: self.streams.misses,
+            stream_evictions: self.streams.evictions,
+            stream_count: self.streams.entries.len(),
+            stream_bytes: self.streams.bytes,
+        }
+    }
+
+    /// Resets the hit, miss and eviction counters.
+    pub fn reset_stats(&mut self) {
+        self.objects.reset_stats();
+        self.streams.reset_stats();
+    }
+
+    /// Drops every cached entry. The counters are kept.
+    pub fn clear(&mut self) {
+        self.objects.clear();
+        self.streams.clear();
+    }
+}
+
+/// Estimates the memory held by a parsed object.
+fn estimated_size(object: &PDFObject) -> usize {
+    const NODE: usize = std::mem::size_of::<PDFObject>();
+    match object {
+        PDFObject::String(bytes) | PDFObject::HexString(bytes) => NODE + bytes.len(),
+        PDFObject::Name(name) => NODE + name.len(),
+        PDFObject::Array(items) => {
+            NODE + items.iter().map(|item| estimated_size(item)).sum::<usize>()
+        }
+        PDFObject::Dictionary(dict) => NODE + dict_size(dict),
+        PDFObject::Stream { dict, data } => NODE + dict_size(dict) + data.len(),
+        _ => NODE,
+    }
+}
+
+fn dict_size(dict: &std::collections::HashMap<String, PDFObject>) -> usize {
+    dict.iter()
+        .map(|(key, value)| key.len() + estimated_size(value))
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evicts_least_recently_used_within_budget() {
+        let mut cache = ObjectCache::new(CacheBudget {
+            object_bytes: 1024,
+            stream_bytes: 100,
+        });
+
+        cache.put_stream(1, 0, Rc::new(vec![0; 40]));
+        cache.put_stream(2, 0, Rc::new(vec![0; 40]));
+        assert!(cache.get_stream(1, 0).is_some());
+        cache.put_stream(3, 0, Rc::new(vec![0; 40]));
+
+        // Stream 2 was the least recently used one
+        assert!(cache.get_stream(2, 0).is_none());
+        assert!(cache.get_stream(1, 0).is_some());
+        assert!(cache.get_stream(3, 0).is_some());
+
+        // Entries larger than the whole budget are not cached
+        cache.put_stream(4, 0, Rc::new(vec![0; 200]));
+        assert!(cache.get_stream(4, 0).is_none());
+
+        let stats = cache.stats();
+        assert_eq!(stats.stream_count, 2);
+        assert_eq!(stats.stream_bytes, 80);
+        assert_eq!(stats.stream_evictions, 1);
+        assert_eq!(stats.stream_hits, 3);
+        assert_eq!(stats.stream_misses, 2);
+
+        cache.set_budget(CacheBudget {
+            object_bytes: 1024,
+            stream_bytes: 50,
+        });
+        assert_eq!(cache.stats().stream_count, 1);
+        assert_eq!(cache.stats().stream_bytes, 40);
+    }
+
+    #[test]
+    fn test_object_sizes_are_estimated() {
+        let mut cache = ObjectCache::new(CacheBudget::default());
+        cache.put_object(1, 0, Rc::new(PDFObject::Number(1.0)));
+        let small = cache.stats().object_bytes;
+
+        cache.put_object(1, 0, Rc::new(PDFObject::String(vec![b'x'; 1000])));
+        let stats = cache.stats();
+        assert_eq!(stats.object_count, 1);
+        assert!(stats.object_bytes >= small + 1000);
+
+        // Another generation of the same object number is a separate entry
+        assert!(cache.get_object(1, 1).is_none());
+        cache.put_object(1, 1, Rc::new(PDFObject::Number(2.0)));
+        assert_eq!(cache.stats().object_count, 2);
+        assert!(cache.get_object(1, 0).is_some());
+    }
+}
@@ -0,0 +1,297 @@
+//! Builder-style entry point for opening documents.
+//!
+//! `PDFDocument::open`, `open_file` and friends take their settings as
+//! positional arguments, and options such as lenient mode or a password are
+//! applied afterwards with separate calls. `Pdf` gathers all of them in one
+//! place so new options can be added without changing existing call sites:
+//!
+//! ```no_run
+//! use pdf_x_core::prelude::*;
+//!
+//! let mut doc = Pdf::open("document.pdf")
+//!     .password("secret")
+//!     .lenient(true)
+//!     .load()?;
+//! println!("{} pages", doc.page_count()?);
+//! # Ok::<(), PDFError>(())
+//! ```
+
+use super::cancel::{CancellationToken, Progress};
+use super::chunk_manager::{
+    ChunkedStreamOptions, DEFAULT_CHUNK_SIZE, DEFAULT_MAX_CACHED_CHUNKS, DEFAULT_READAHEAD_CHUNKS,
+};
+use super::document::PDFDocument;
+use super::error::{PDFError, PDFResult};
+use super::file_chunked_stream::FileChunkedStream;
+use super::limits::ResourceLimits;
+use std::path::{Path, PathBuf};
+
+/// Entry point for opening PDF documents with [`OpenOptions`].
+pub struct Pdf;
+
+impl Pdf {
+    /// Starts opening the file at `path`.
+    ///
+    /// The file is loaded progressively, as with `PDFDocument::open_file`.
+    pub fn open<P: AsRef<Path>>(path: P) -> OpenOptions {
+        OpenOptions::new(Source::Path(path.as_ref().to_path_buf()))
+    }
+
+    /// Starts opening a document held in memory.
+    pub fn from_bytes(data: Vec<u8>) -> OpenOptions {
+        OpenOptions::new(Source::Bytes(data))
+    }
+}
+
+/// Settings that affect how a document is parsed, which must be known when
+/// it is opened.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// Repair common spec violations instead of failing (see
+    /// `PDFDocument::open_with_options`)
+    pub lenient: bool,
+
+    /// Bounds on what parsing and rendering the document may use
+    pub limits: ResourceLimits,
+}
+
+/// Where the document is read from.
+enum Source {
+    Path(PathBuf),
+    Bytes(Vec<u8>),
+}
+
+/// Settings for opening a document, finished by [`load`](Self::load).
+pub struct OpenOptions {
+    source: Source,
+    password: Option<Vec<u8>>,
+    lenient: bool,
+    enforce_permissions: bool,
+    chunk_size: Option<usize>,
+    max_cached_chunks: Option<usize>,
+    max_cached_bytes: Option<usize>,
+    readahead_chunks: Option<usize>,
+    cancel: Option<CancellationToken>,
+    progress: Option<Progress>,
+    limits: ResourceLimits,
+}
+
+impl OpenOptions {
+    fn new(source: Source) -> Self {
+        OpenOptions {
+            source,
+            password: None,
+            lenient: false,
+            enforce_permissions: false,
+            chunk_size: None,
+            max_cached_chunks: None,
+            max_cached_bytes: None,
+            readahead_chunks: None,
+            cancel: None,
+            progress: None,
+            limits: ResourceLimits::default(),
+        }
+    }
+
+    /// Sets the password for an encrypted document.
+    ///
+    /// `load` fails if the document is encrypted and the password is wrong.
+    /// The password is ignored for unencrypted documents.
+    pub fn password(mut self, password: impl AsRef<[u8]>) -> Self {
+        self.password = Some(password.as_ref().to_vec());
+        self
+    }
+
+    /// Enables or disables lenient mode (see `PDFDocument::open_with_options`).
+    ///
+    /// Repairs made while opening and reading the document are listed by
+    /// `PDFDocument::diagnostics`.
+    pub fn lenient(mut self, lenient: bool) -> Self {
+        self.lenient = lenient;
+        self
+    }
+
+    /// Enables or disables enforcement of the document's permissions (see
+    /// `PDFDocument::set_enforce_permissions`).
+    pub fn enforce_permissions(mut self, enforce: bool) -> Self {
+        self.enforce_permissions = enforce;
+        self
+    }
+
+    /// Sets the chunk size for progressive loading (default: 64KB).
+    ///
+    /// Only used when opening a file.
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = Some(chunk_size);
+        self
+    }
+
+    /// Sets how many chunks are kept in memory (default: 10).
+    ///
+    /// Only used when opening a file.
+    pub fn max_cached_chunks(mut self, max_cached_chunks: usize) -> Self {
+        self.max_cached_chunks = Some(max_cached_chunks);
+        self
+    }
+
+    /// Bounds the chunk data kept in memory, in bytes. Takes precedence
+    /// over [`max_cached_chunks`](Self::max_cached_chunks).
+    ///
+    /// Only used when opening a file.
+    pub fn max_cached_bytes(mut self, max_cached_bytes: usize) -> Self {
+        self.max_cached_bytes = Some(max_cached_bytes);
+        self
+    }
+
+    /// Sets how many chunks are read ahead once reads are sequential
+    /// (default: 2). Zero turns readahead off.
+    ///
+    /// Only used when opening a file.
+    pub fn readahead_chunks(mut self, readahead_chunks: usize) -> Self {
+        self.readahead_chunks = Some(readahead_chunks);
+        self
+    }
+
+    /// Sets a token that stops opening the document, and later operations
+    /// on it, once cancelled (see `PDFDocument::set_cancellation_token`).
+    ///
+    /// `load` then fails with `PDFError::Cancelled`.
+    pub fn cancellation_token(mut self, token: CancellationToken) -> Self {
+        self.cancel = Some(token);
+        self
+    }
+
+    /// Sets a callback told how far opening the document got, and later
+    /// operations on it (see `PDFDocument::set_progress_callback`).
+    ///
+    /// While the xref table is read it receives (sections read, sections
+    /// found so far); if the table has to be rebuilt, (object headers
+    /// indexed, headers found).
+    pub fn progress(mut self, progress: Progress) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    /// Sets the resource limits for parsing and rendering the document
+    /// (default: `ResourceLimits::default()`).
+    ///
+    /// Operations that reach a limit fail with `PDFError::LimitExceeded`.
+    pub fn limits(mut self, limits: ResourceLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Opens the document with these settings.
+    ///
+    /// # Returns
+    /// The document, or an error if it can't be parsed or the password is
+    /// incorrect
+    pub fn load(self) -> PDFResult<PDFDocument> {
+        let options = ParseOptions {
+            lenient: self.lenient,
+            limits: self.limits,
+        };
+        let mut doc = match self.source {
+            Source::Path(path) => {
+                let chunk_size = self.chunk_size.unwrap_or(DEFAULT_CHUNK_SIZE);
+                let max_cached_chunks = self.max_cached_chunks.unwrap_or(DEFAULT_MAX_CACHED_CHUNKS);
+                let stream_options = ChunkedStreamOptions {
+                    chunk_size,
+                    readahead_chunks: self.readahead_chunks.unwrap_or(DEFAULT_READAHEAD_CHUNKS),
+                    max_cached_bytes: self
+                        .max_cached_bytes
+                        .unwrap_or(chunk_size.saturating_mul(max_cached_chunks)),
+                };
+                let stream = FileChunkedStream::open_with_options(path, stream_options)?;
+                let chunk_size = stream.chunk_size();
+                PDFDocument::open_chunked_cancellable(
+                    Box::new(stream),
+                    chunk_size,
+                    options,
+                    self.cancel,
+                    self.progress,
+                )?
+            }
+            Source::Bytes(data) => {
+                PDFDocument::open_bytes(data, options, self.cancel, self.progress)?
+            }
+        };
+        doc.set_enforce_permissions(self.enforce_permissions);
+
+        if let Some(password) = self.password {
+            doc.load_encrypt_dict()?;
+            if doc.is_encrypted() && !doc.authenticate_with_password(&password)? {
+                return Err(PDFError::Generic("Incorrect password".to_string()));
+            }
+        }
+
+        Ok(doc)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::build_pdf;
+
+    fn minimal_pdf() -> Vec<u8> {
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R >>",
+            "<< /Type /Pages /Kids [] /Count 0 >>",
+        ];
+        build_pdf(&objects)
+    }
+
+    #[test]
+    fn test_open_options() {
+        let mut doc = Pdf::from_bytes(minimal_pdf())
+            .lenient(true)
+            .password("ignored")
+            .load()
+            .unwrap();
+        assert!(doc.is_lenient());
+        assert!(!doc.is_encrypted());
+        assert_eq!(doc.page_count().unwrap(), 0);
+
+        let file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), minimal_pdf()).unwrap();
+        let doc = Pdf::open(file.path()).chunk_size(1024).load().unwrap();
+        assert!(!doc.is_lenient());
+
+        // Small chunks with readahead and a tight cache still parse
+        let mut doc = Pdf::open(file.path())
+            .chunk_size(32)
+            .readahead_chunks(4)
+            .max_cached_bytes(256)
+            .load()
+            .unwrap();
+        assert_eq!(doc.page_count().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_open_cancelled() {
+        let token = CancellationToken::new();
+        token.cancel();
+        let result = Pdf::from_bytes(minimal_pdf())
+            .cancellation_token(token)
+            .load();
+        assert!(matches!(result, Err(PDFError::Cancelled)));
+    }
+
+    #[test]
+    fn test_open_progress() {
+        use std::sync::{Arc, Mutex};
+
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let progress_calls = calls.clone();
+        let doc = Pdf::from_bytes(minimal_pdf())
+            .progress(Arc::new(move |done, total| {
+                progress_calls.lock().unwrap().push((done, total));
+            }))
+            .load()
+            .unwrap();
+        assert_eq!(*calls.lock().unwrap(), vec![(1, 1)]);
+        // The callback stays on the document
+        assert!(doc.progress_callback().is_some());
+    }
+}
@@ -6,9 +6,12 @@
 //! Outlines provide a hierarchical table of contents for navigating PDF documents.
 
 use crate::core::PDFDocument;
+use crate::core::action::{Action, parse_action};
+use crate::core::cycle_guard::CycleGuard;
 use crate::core::error::{PDFError, PDFResult};
 use crate::core::parser::{PDFObject, Ref};
-use std::collections::{HashMap, HashSet};
+use crate::log_target;
+use std::collections::HashMap;
 
 /// Decodes a PDF string to a Rust String, handling various encodings.
 ///
@@ -19,7 +22,7 @@ use std::collections::{HashMap, HashSet};
 /// - UTF-8 with BOM (0xEF 0xBB 0xBF)
 ///
 /// Based on PDF.js's stringToPDFString function.
-fn decode_pdf_string(bytes: &[u8]) -> String {
+pub(crate) fn decode_pdf_string(bytes: &[u8]) -> String {
     if bytes.is_empty() {
         return String::new();
     }
@@ -28,25 +31,17 @@ fn decode_pdf_string(bytes: &[u8]) -> String {
     if bytes.len() >= 2 {
         // UTF-16BE BOM
         if bytes[0] == 0xFE && bytes[1] == 0xFF {
-            if bytes.len() % 2 != 0 {
-                // Remove trailing byte if odd length
-                let data = &bytes[..bytes.len() - 1];
-                return decode_utf16be(data)
-                    .unwrap_or_else(|_| String::from_utf8_lossy(data).to_string());
-            }
-            return decode_utf16be(bytes)
-                .unwrap_or_else(|_| String::from_utf8_lossy(bytes).to_string());
+            // Skip the BOM and remove trailing byte if odd length
+            let data = &bytes[2..bytes.len() - bytes.len() % 2];
+            return decode_utf16be(data)
+                .unwrap_or_else(|_| String::from_utf8_lossy(data).to_string());
         }
 
         // UTF-16LE BOM
         if bytes[0] == 0xFF && bytes[1] == 0xFE {
-            if bytes.len() % 2 != 0 {
-                let data = &bytes[..bytes.len() - 1];
-                return decode_utf16le(data)
-                    .unwrap_or_else(|_| String::from_utf8_lossy(data).to_string());
-            }
-            return decode_utf16le(bytes)
-                .unwrap_or_else(|_| String::from_utf8_lossy(bytes).to_string());
+            let data = &bytes[2..bytes.len() - bytes.len() % 2];
+            return decode_utf16le(data)
+                .unwrap_or_else(|_| String::from_utf8_lossy(data).to_string());
         }
     }
 
@@ -153,6 +148,9 @@ pub struct OutlineItem {
     /// The destination this bookmark points to (page, location, etc.)
     pub dest: Option<OutlineDestination>,
 
+    /// The bookmark's action (/A) with its whole /Next chain
+    pub action: Option<Action>,
+
     /// RGB color for the bookmark text (defaults to black [0, 0, 0])
     pub color: Option<[u8; 3]>,
 
@@ -233,14 +231,67 @@ pub enum DestinationType {
     /// FitBV destination - fit bounding box vertically
     /// [page, /FitBV, left]
     FitBV { left: Option<f64> },
+
+    /// FitR destination - fit a rectangle of the page to the window
+    /// [page, /FitR, left, bottom, right, top]
+    FitR {
+        left: Option<f64>,
+        bottom: Option<f64>,
+        right: Option<f64>,
+        top: Option<f64>,
+    },
 }
 
+/// An outline item with its target resolved against the document.
+///
+/// Returned by `PDFDocument::outline`. Named destinations and indirect page
+/// references are already looked up, so items pointing into the document
+/// carry the index of their page.
+#[derive(Debug, Clone)]
+pub struct ResolvedOutlineItem {
+    /// The title text displayed for this bookmark
+    pub title: String,
+
+    /// Zero-based index of the target page, for items pointing into this
+    /// document
+    pub page_index: Option<usize>,
+
+    /// How the target page is shown: position and zoom, or the area to fit
+    pub dest_type: Option<DestinationType>,
+
+    /// Target of a URI action, or the file of a GoToR action
+    pub uri: Option<String>,
+
+    /// RGB color for the bookmark text (defaults to black [0, 0, 0])
+    pub color: Option<[u8; 3]>,
+
+    /// Whether the bookmark text should be bold
+    pub bold: bool,
+
+    /// Whether the bookmark text should be italic
+    pub italic: bool,
+
+    /// Whether the children are shown initially (positive /Count)
+    pub open: bool,
+
+    /// Child outline items (nested bookmarks)
+    pub children: Vec<ResolvedOutlineItem>,
+}
+
+/// Maximum number of outline items read, so that broken or hostile /Next
+/// chains can't make the traversal run away.
+const MAX_OUTLINE_ITEMS: usize = 65_536;
+
+/// Maximum nesting depth of the outline tree.
+const MAX_OUTLINE_DEPTH: usize = 64;
+
 impl OutlineItem {
     /// Creates a new outline item with minimal required fields.
     pub fn new(title: String) -> Self {
         Self {
             title,
             dest: None,
+            action: None,
             color: None,
             bold: false,
             italic: false,
@@ -290,12 +341,22 @@ pub fn parse_document_outline(doc: &mut PDFDocument) -> PDFResult<Option<Vec<Out
     // We use indices into a Vec to avoid lifetime issues with references
     let mut queue: Vec<((u32, u32), usize)> = vec![(first_ref, 0)];
     let mut items: Vec<OutlineItem> = vec![OutlineItem::new(String::new())]; // Root placeholder at index 0
+    let mut parents: Vec<usize> = vec![0];
+    let mut depths: Vec<usize> = vec![0];
 
     // Track visited references to prevent cycles
-    let mut visited: HashSet<(u32, u32)> = HashSet::new();
-    visited.insert(first_ref);
+    let mut visited = CycleGuard::new();
+    visited.visit(Ref::new(first_ref.0, first_ref.1));
 
     while let Some((ref_num_gen, parent_idx)) = queue.pop() {
+        if items.len() > MAX_OUTLINE_ITEMS {
+            tracing::warn!(
+                target: log_target::DOCUMENT,
+                "Outline has more than {} items, ignoring the rest",
+                MAX_OUTLINE_ITEMS
+            );
+            break;
+        }
         let (num, generation) = ref_num_gen;
 
         // Fetch the outline dictionary
@@ -325,8 +386,28 @@ pub fn parse_document_outline(doc: &mut PDFDocument) -> PDFResult<Option<Vec<Out
             }
         };
 
-        // Parse destination
-        let dest = parse_destination(&outline_dict, doc)?;
+        // Parse destination; an unusable one doesn't drop the whole outline
+        let dest = match parse_destination(&outline_dict, doc) {
+            Ok(dest) => dest,
+            Err(e @ PDFError::DataMissing { .. }) => return Err(e),
+            Err(e) => {
+                tracing::warn!(
+                    target: log_target::DOCUMENT,
+                    "Failed to read destination of outline item {} {}: {:?}",
+                    num, generation, e
+                );
+                None
+            }
+        };
+
+        let action = match outline_dict
+            .get("A")
+            .map(|a| parse_action(a, doc.xref_mut()))
+        {
+            Some(Ok(action)) => action,
+            Some(Err(e @ PDFError::DataMissing { .. })) => return Err(e),
+            _ => None,
+        };
 
         // Parse color (optional, defaults to black)
         let color = parse_color(&outline_dict);
@@ -346,40 +427,95 @@ pub fn parse_document_outline(doc: &mut PDFDocument) -> PDFResult<Option<Vec<Out
         // Create the outline item
         let mut item = OutlineItem::new(title);
         item.dest = dest;
+        item.action = action;
         item.color = color;
         item.bold = bold;
         item.italic = italic;
         item.count = count;
 
-        // Add item to its parent
+        // Record the item; it is attached to its parent once its own
+        // children have been read
         let item_idx = items.len();
+        let depth = depths[parent_idx] + 1;
         items.push(item);
-
-        // Clone the item and add to parent (avoids borrow checker issues)
-        let item_clone = items[item_idx].clone();
-        items[parent_idx].children.push(item_clone);
+        parents.push(parent_idx);
+        depths.push(depth);
 
         // Add /First (children) to queue
-        if let Some(PDFObject::Ref(ref_obj)) = outline_dict.get("First") {
-            let ref_tuple = (ref_obj.num, ref_obj.generation);
-            if !visited.contains(&ref_tuple) {
-                visited.insert(ref_tuple);
-                queue.push((ref_tuple, item_idx));
-            }
+        if depth < MAX_OUTLINE_DEPTH
+            && let Some(PDFObject::Ref(ref_obj)) = outline_dict.get("First")
+            && visited.visit(*ref_obj)
+        {
+            queue.push(((ref_obj.num, ref_obj.generation), item_idx));
         }
 
         // Add /Next (siblings) to queue
-        if let Some(PDFObject::Ref(ref_obj)) = outline_dict.get("Next") {
-            let ref_tuple = (ref_obj.num, ref_obj.generation);
-            if !visited.contains(&ref_tuple) {
-                visited.insert(ref_tuple);
-                queue.push((ref_tuple, parent_idx));
-            }
+        if let Some(PDFObject::Ref(ref_obj)) = outline_dict.get("Next")
+            && visited.visit(*ref_obj)
+        {
+            queue.push(((ref_obj.num, ref_obj.generation), parent_idx));
         }
     }
 
+    // Children always come after their parent and siblings are visited in
+    // order, so attaching from the back builds the tree bottom-up
+    for idx in (1..items.len()).rev() {
+        let item = std::mem::replace(&mut items[idx], OutlineItem::new(String::new()));
+        items[parents[idx]].children.insert(0, item);
+    }
+
     // Return root's children (top-level items)
-    Ok(Some(items.remove(0).children))
+    Ok(Some(items.swap_remove(0).children))
+}
+
+/// Reads the document outline and resolves every item's target.
+///
+/// Explicit destinations whose page is not part of the document, and named
+/// destinations that can't be found, leave `page_index` unset.
+///
+/// # Returns
+///
+/// The top-level items, or an empty list if the document has no outline
+pub fn resolve_document_outline(doc: &mut PDFDocument) -> PDFResult<Vec<ResolvedOutlineItem>> {
+    let items = match parse_document_outline(doc)? {
+        Some(items) => items,
+        None => return Ok(Vec::new()),
+    };
+    let page_count = doc.page_count()? as usize;
+    Ok(items
+        .into_iter()
+        .map(|item| ResolvedOutlineItem::resolve(item, page_count))
+        .collect())
+}
+
+impl ResolvedOutlineItem {
+    fn resolve(item: OutlineItem, page_count: usize) -> Self {
+        let (page_index, dest_type, uri) = match item.dest {
+            Some(OutlineDestination::Explicit {
+                page_index,
+                dest_type,
+            }) if page_index < page_count => (Some(page_index), Some(dest_type), None),
+            Some(OutlineDestination::URL(uri)) => (None, None, Some(uri)),
+            Some(OutlineDestination::GoToRemote { url, .. }) => (None, None, Some(url)),
+            _ => (None, None, None),
+        };
+
+        ResolvedOutlineItem {
+            title: item.title,
+            page_index,
+            dest_type,
+            uri,
+            color: item.color,
+            bold: item.bold,
+            italic: item.italic,
+            open: item.count.is_some_and(|count| count > 0),
+            children: item
+                .children
+                .into_iter()
+                .map(|child| ResolvedOutlineItem::resolve(child, page_count))
+                .collect(),
+        }
+    }
 }
 
 /// Parses the destination from an outline dictionary.
@@ -393,89 +529,52 @@ fn parse_destination(
 ) -> PDFResult<Option<OutlineDestination>> {
     // Check for /Dest entry first
     if let Some(dest_obj) = dict.get("Dest") {
-        return Ok(Some(parse_dest_entry(dest_obj, doc)?));
+        return parse_dest_entry(dest_obj, doc);
     }
 
     // Check for /A (action) entry
-    if let Some(action_obj) = dict.get("A") {
-        if let PDFObject::Dictionary(action_dict) = action_obj {
-            return parse_action_destination(action_dict, doc);
-        }
+    if let Some(action_obj) = dict.get("A")
+        && let PDFObject::Dictionary(action_dict) = doc.xref_mut().fetch_if_ref(action_obj)?
+    {
+        return parse_action_destination(&action_dict, doc);
     }
 
     Ok(None)
 }
 
 /// Parses a /Dest entry (can be explicit array or named destination).
-fn parse_dest_entry(dest_obj: &PDFObject, doc: &mut PDFDocument) -> PDFResult<OutlineDestination> {
-    match dest_obj {
-        PDFObject::Array(arr) => {
-            // Explicit destination: [page_ref, /Type, params...]
-            if arr.is_empty() {
-                return Ok(OutlineDestination::Named(String::new()));
-            }
-
-            // First element is the page reference
-            let page_ref = &arr[0];
-
-            // Resolve page reference to page index
-            let page_index = resolve_page_index(page_ref, doc)?;
-
-            // Second element is the destination type name
-            let dest_type = if arr.len() > 1 {
-                match &*arr[1] {
-                    PDFObject::Name(name) => name.as_str(),
-                    _ => return Ok(OutlineDestination::Named(String::new())),
-                }
-            } else {
-                return Ok(OutlineDestination::Named(String::new()));
-            };
-
-            // Parse the destination type and parameters
-            let dest_type = parse_destination_type(dest_type, &arr[2..])?;
-
-            Ok(OutlineDestination::Explicit {
-                page_index,
-                dest_type,
-            })
-        }
-        PDFObject::String(bytes) | PDFObject::HexString(bytes) => {
-            // Named destination - try to resolve it
-            let name = String::from_utf8_lossy(bytes).to_string();
-
-            // Try to resolve the named destination
-            match doc.resolve_named_destination(&name) {
-                Ok(Some((page_index, dest_type))) => Ok(OutlineDestination::Explicit {
+///
+/// The entry may be an indirect reference to either. Named destinations are
+/// looked up right away and only kept by name if they can't be found.
+/// Explicit destinations to pages outside the page tree give `None`.
+fn parse_dest_entry(
+    dest_obj: &PDFObject,
+    doc: &mut PDFDocument,
+) -> PDFResult<Option<OutlineDestination>> {
+    let dest_obj = doc.xref_mut().fetch_if_ref(dest_obj)?;
+    let name = match &dest_obj {
+        PDFObject::String(bytes) | PDFObject::HexString(bytes) => decode_pdf_string(bytes),
+        PDFObject::Name(name) => name.strip_prefix('/').unwrap_or(name).to_string(),
+        // Explicit destination: [page_ref, /Type, params...], or a
+        // dictionary holding one under /D
+        _ => {
+            return Ok(doc.resolve_explicit_destination(&dest_obj)?.map(
+                |(page_index, dest_type)| OutlineDestination::Explicit {
                     page_index,
                     dest_type,
-                }),
-                _ => {
-                    // Could not resolve, keep as named destination
-                    Ok(OutlineDestination::Named(name))
-                }
-            }
+                },
+            ));
         }
-        PDFObject::Name(name) => {
-            // Named destination (as a name object) - try to resolve it
-            // Remove leading '/' if present
-            let clean_name = if name.starts_with('/') {
-                &name[1..]
-            } else {
-                name
-            };
+    };
 
-            match doc.resolve_named_destination(clean_name) {
-                Ok(Some((page_index, dest_type))) => Ok(OutlineDestination::Explicit {
-                    page_index,
-                    dest_type,
-                }),
-                _ => {
-                    // Could not resolve, keep as named destination
-                    Ok(OutlineDestination::Named(name.clone()))
-                }
-            }
-        }
-        _ => Ok(OutlineDestination::Named(String::new())),
+    match doc.resolve_named_destination(&name) {
+        Ok(Some((page_index, dest_type))) => Ok(Some(OutlineDestination::Explicit {
+            page_index,
+            dest_type,
+        })),
+        Err(e @ PDFError::DataMissing { .. }) => Err(e),
+        // Could not resolve, keep as named destination
+        _ => Ok(Some(OutlineDestination::Named(name))),
     }
 }
 
@@ -507,7 +606,7 @@ fn parse_action_destination(
         "GoTo" => {
             // GoTo action - has /D entry with destination
             if let Some(dest_obj) = action_dict.get("D") {
-                return Ok(Some(parse_dest_entry(dest_obj, doc)?));
+                return parse_dest_entry(dest_obj, doc);
             }
         }
         "GoToR" => {
@@ -560,32 +659,16 @@ pub fn parse_destination_type(
         "FitB" => DestinationType::FitB,
         "FitBH" => DestinationType::FitBH { top: get_num(0) },
         "FitBV" => DestinationType::FitBV { left: get_num(0) },
+        "FitR" => DestinationType::FitR {
+            left: get_num(0),
+            bottom: get_num(1),
+            right: get_num(2),
+            top: get_num(3),
+        },
         _ => DestinationType::Fit, // Default to Fit for unknown types
     })
 }
 
-/// Resolves a page reference to a zero-based page index.
-///
-/// This uses the document's page reference cache to efficiently look up
-/// page indices from page object references.
-fn resolve_page_index(page_ref: &PDFObject, doc: &mut PDFDocument) -> PDFResult<usize> {
-    match page_ref {
-        PDFObject::Ref(ref_obj) => {
-            // Use the document's resolve_page_index method
-            doc.resolve_page_index(ref_obj.num, ref_obj.generation)
-                .ok_or_else(|| {
-                    PDFError::Generic(format!(
-                        "Page reference {} {} not found in document",
-                        ref_obj.num, ref_obj.generation
-                    ))
-                })
-        }
-        _ => Err(PDFError::Generic(
-            "Page reference is not a Ref object".to_string(),
-        )),
-    }
-}
-
 /// Parses the /C entry (color) from an outline dictionary.
 ///
 /// Returns RGB color as [r, g, b] or None if default black.
@@ -1,7 +1,18 @@
+use super::annotation::{AnnotationData, LinkAction, LinkTarget, PageLink};
+use super::content_stream::ExtractionMode;
+use super::cycle_guard::CycleGuard;
 use super::error::{PDFError, PDFResult};
+use super::layout::{
+    TextLayoutOptions, TextLine, TextWord, extract_lines, extract_words, layout_reading_order,
+    layout_text,
+};
+use super::page_boxes::{PageBox, PageBoxes};
 use super::parser::PDFObject;
+use super::reflow::{FontStyle, ReflowParagraph, load_font_styles, reflow_items};
 use rustc_hash::FxHashMap;
+use std::collections::HashMap;
 
+use crate::log_target;
 #[cfg(feature = "rendering")]
 use crate::rendering::FontWidthMetrics;
 
@@ -103,14 +114,14 @@ impl Page {
 
     /// Resolve an inheritable page property, merging dictionaries across the page tree.
     /// Reference: pdf.js/src/core/core_utils.js - getInheritableProperty
-    fn get_inheritable_property(
+    pub(crate) fn get_inheritable_property(
         &self,
         xref: &mut super::xref::XRef,
         key: &str,
         stop_when_found: bool,
     ) -> PDFResult<Option<PDFObject>> {
         let mut current = self.page_dict.clone();
-        let mut visited_refs: std::collections::HashSet<(u32, u32)> = std::collections::HashSet::new();
+        let mut visited_refs = CycleGuard::new();
         let mut values: Vec<PDFObject> = Vec::new();
 
         loop {
@@ -134,13 +145,7 @@ impl Page {
 
             match parent {
                 PDFObject::Ref(ref_obj) => {
-                    let ref_key = (ref_obj.num, ref_obj.generation);
-                    if visited_refs.contains(&ref_key) {
-                        return Err(PDFError::Generic(
-                            "Circular reference in page tree".to_string(),
-                        ));
-                    }
-                    visited_refs.insert(ref_key);
+                    visited_refs.enter(*ref_obj, "page tree")?;
                     let parent_obj = xref.fetch(ref_obj.num, ref_obj.generation)?;
                     current = (*parent_obj).clone();
                 }
@@ -177,31 +182,31 @@ impl Page {
         PDFObject::Dictionary(merged)
     }
 
-    fn resolve_rect(value: &PDFObject) -> Option<[f64; 4]> {
-        let PDFObject::Array(arr) = value else {
-            return None;
-        };
-        if arr.len() < 4 {
-            return None;
-        }
-        let mut vals = [0.0; 4];
-        for i in 0..4 {
-            vals[i] = match &**&arr[i] {
-                PDFObject::Number(n) => *n,
-                _ => return None,
-            };
-        }
-        let (min_x, max_x) = if vals[0] <= vals[2] {
-            (vals[0], vals[2])
-        } else {
-            (vals[2], vals[0])
-        };
-        let (min_y, max_y) = if vals[1] <= vals[3] {
-            (vals[1], vals[3])
-        } else {
-            (vals[3], vals[1])
-        };
-        Some([min_x, min_y, max_x, max_y])
+    /// Returns the page's five boxes, with the MediaBox and CropBox
+    /// inherited from the page tree, and its rotation.
+    ///
+    /// # Arguments
+    /// * `xref` - The cross-reference table of the document
+    pub fn page_boxes(&self, xref: &mut super::xref::XRef) -> PageBoxes {
+        let rotate = self.resolve_rotate_for_rendering(xref);
+        PageBoxes::resolve(
+            |kind| {
+                if kind.is_inheritable() {
+                    self.get_inheritable_property(xref, kind.key(), false)
+                        .ok()
+                        .flatten()
+                } else {
+                    self.get(kind.key())
+                        .and_then(|value| xref.fetch_if_ref(value).ok())
+                }
+            },
+            rotate,
+        )
+    }
+
+    /// Returns one of the page's boxes; see [`page_boxes`](Self::page_boxes).
+    pub fn page_box(&self, xref: &mut super::xref::XRef, kind: PageBox) -> [f64; 4] {
+        self.page_boxes(xref).get(kind)
     }
 
     pub(crate) fn resolve_view_box_for_rendering(
@@ -209,30 +214,7 @@ impl Page {
         xref: &mut super::xref::XRef,
     ) -> [f64; 4] {
         // Reference: pdf.js/src/core/document.js - Page.view
-        let media_box = self
-            .get_inheritable_property(xref, "MediaBox", false)
-            .ok()
-            .and_then(|value| value.as_ref().and_then(Self::resolve_rect))
-            .unwrap_or([0.0, 0.0, 612.0, 792.0]);
-
-        let crop_box = self
-            .get_inheritable_property(xref, "CropBox", false)
-            .ok()
-            .and_then(|value| value.as_ref().and_then(Self::resolve_rect))
-            .unwrap_or(media_box);
-
-        let intersect = [
-            media_box[0].max(crop_box[0]),
-            media_box[1].max(crop_box[1]),
-            media_box[2].min(crop_box[2]),
-            media_box[3].min(crop_box[3]),
-        ];
-
-        if intersect[2] - intersect[0] > 0.0 && intersect[3] - intersect[1] > 0.0 {
-            intersect
-        } else {
-            media_box
-        }
+        self.page_boxes(xref).crop
     }
 
     pub(crate) fn resolve_rotate_for_rendering(&self, xref: &mut super::xref::XRef) -> i32 {
@@ -257,6 +239,29 @@ impl Page {
         rotate
     }
 
+    /// Returns the viewport for rendering this page with the given options.
+    ///
+    /// The visible area is `options.crop_to` if set, and the CropBox clipped
+    /// to the MediaBox otherwise. The rotation is the page's /Rotate plus
+    /// `options.rotation`.
+    pub fn viewport(
+        &self,
+        xref: &mut super::xref::XRef,
+        options: &crate::rendering::RenderOptions,
+    ) -> crate::rendering::Viewport {
+        let view_box = match options.crop_to {
+            Some([x0, y0, x1, y1]) if x0 != x1 && y0 != y1 => {
+                [x0.min(x1), y0.min(y1), x0.max(x1), y0.max(y1)]
+            }
+            _ => self.resolve_view_box_for_rendering(xref),
+        };
+        let mut rotation = self.resolve_rotate_for_rendering(xref);
+        if options.rotation % 90 == 0 {
+            rotation = (rotation + options.rotation).rem_euclid(360);
+        }
+        crate::rendering::Viewport::new(view_box, rotation, options.scale as f64)
+    }
+
     pub(crate) fn get_inheritable_resources(
         &self,
         xref: &mut super::xref::XRef,
@@ -326,6 +331,7 @@ impl Page {
         xref: &mut super::xref::XRef,
         device: &mut D,
         resources: Option<&PDFObject>,
+        font_resolver: &dyn crate::rendering::FontResolver,
     ) -> PDFResult<()> {
         let resources = match resources {
             Some(r) => r,
@@ -347,6 +353,16 @@ impl Page {
             _ => return Ok(()),
         };
 
+        // Load the font dictionaries' bytes in one pass over the file
+        let font_refs: Vec<_> = font_dict
+            .values()
+            .filter_map(|font_ref| match font_ref {
+                PDFObject::Ref(r) => Some(*r),
+                _ => None,
+            })
+            .collect();
+        xref.prefetch(&font_refs)?;
+
         for (font_name, font_ref) in font_dict {
             if let Ok(font_obj) = xref.fetch_if_ref(&font_ref) {
                 if let Ok(pdf_font) = super::font::Font::new(font_obj, xref) {
@@ -357,19 +373,23 @@ impl Page {
                             .and_then(|_| device.set_font_width_metrics(&font_name, &width_metrics))
                         {
                             if !e.to_string().contains("UnknownMagic") {
-                                eprintln!(
-                                    "Warning: Failed to load embedded font '{}': {}",
+                                tracing::warn!(
+                                    target: log_target::FONT,
+                                    "Failed to load embedded font '{}': {}",
                                     font_name, e
                                 );
                             }
                         }
-                    } else if let Some(fallback_data) = Self::get_fallback_font_data(pdf_font.base_font()) {
+                    } else if let Some(fallback_data) = font_resolver.resolve(
+                        &crate::rendering::FontQuery::from_font_dict(&pdf_font.dict, xref),
+                    ) {
                         if let Err(e) = device
                             .load_font_data(&font_name, fallback_data, None)
                             .and_then(|_| device.set_font_width_metrics(&font_name, &width_metrics))
                         {
-                            eprintln!(
-                                "Warning: Failed to load fallback font for '{}': {}",
+                            tracing::warn!(
+                                target: log_target::FONT,
+                                "Failed to load fallback font for '{}': {}",
                                 font_name, e
                             );
                         }
@@ -406,8 +426,56 @@ impl Page {
         &self,
         xref: &mut super::xref::XRef,
     ) -> PDFResult<Vec<super::content_stream::TextItem>> {
-        use super::decode::decode_flate;
-        use super::{ContentStreamEvaluator, Lexer, Stream};
+        self.extract_text_with_mode(xref, ExtractionMode::Layout)
+    }
+
+    /// Extracts text from this page's content streams using the given mode.
+    ///
+    /// With `ExtractionMode::Raw` items are returned strictly in operator
+    /// order, one per string operand, without inserted spaces.
+    ///
+    /// # Arguments
+    /// * `xref` - Cross-reference table for resolving objects
+    /// * `mode` - How text is assembled
+    ///
+    /// # Returns
+    /// A vector of TextItem objects containing the extracted text
+    pub fn extract_text_with_mode(
+        &self,
+        xref: &mut super::xref::XRef,
+        mode: ExtractionMode,
+    ) -> PDFResult<Vec<super::content_stream::TextItem>> {
+        self.extract_text_items(xref, mode, false)
+    }
+
+    /// Extracts text from this page with the geometry of every glyph.
+    ///
+    /// Like [`Page::extract_text_with_mode`], with `TextItem::glyphs` filled
+    /// in for text in fonts whose glyph widths are known: each glyph's
+    /// character code, Unicode text, advance and bounding quadrilateral.
+    ///
+    /// # Arguments
+    /// * `xref` - Cross-reference table for resolving objects
+    /// * `mode` - How text is assembled
+    pub fn extract_text_with_glyphs(
+        &self,
+        xref: &mut super::xref::XRef,
+        mode: ExtractionMode,
+    ) -> PDFResult<Vec<super::content_stream::TextItem>> {
+        self.extract_text_items(xref, mode, true)
+    }
+
+    fn extract_text_items(
+        &self,
+        xref: &mut super::xref::XRef,
+        mode: ExtractionMode,
+        glyph_geometry: bool,
+    ) -> PDFResult<Vec<super::content_stream::TextItem>> {
+        use super::ContentStreamEvaluator;
+
+        let _span =
+            tracing::debug_span!(target: log_target::TEXT, "extract_text", page = self.page_index)
+                .entered();
 
         let contents = match self.contents() {
             Some(contents) => contents,
@@ -415,22 +483,21 @@ impl Page {
         };
 
         // Dereference if it's a reference
-        let contents = xref.fetch_if_ref(contents)?;
-
-        let mut all_text_items = Vec::new();
+        let contents_obj = xref.fetch_if_ref(contents)?;
 
-        // Handle single content stream
-        let content_streams = match contents {
-            PDFObject::Stream { dict, data } => {
-                vec![(dict.clone(), data.clone())]
+        // Handle single content stream. The stream (or the reference to it)
+        // is kept so the decoded data can come from the xref's cache.
+        let content_streams = match &contents_obj {
+            PDFObject::Stream { .. } => {
+                vec![contents.clone()]
             }
             PDFObject::Array(arr) => {
                 // Multiple content streams - fetch each one
                 let mut streams = Vec::new();
-                for content_obj in &arr {
+                for content_obj in arr {
                     match xref.fetch_if_ref(content_obj)? {
-                        PDFObject::Stream { dict, data } => {
-                            streams.push((dict, data));
+                        PDFObject::Stream { .. } => {
+                            streams.push((**content_obj).clone());
                         }
                         _ => {
                             return Err(super::PDFError::Generic(
@@ -449,42 +516,28 @@ impl Page {
             }
         };
 
-        // Process each content stream
-        for (dict, data) in content_streams {
-            // Decode the stream if it's compressed
-            let decoded_data = if let Some(filter) = dict.get("Filter") {
-                match filter {
-                    PDFObject::Name(filter_name) if filter_name == "FlateDecode" => {
-                        // Decompress FlateDecode stream
-                        match decode_flate(&data) {
-                            Ok(decompressed) => decompressed,
-                            Err(_) => continue, // Skip this stream if decompression fails
-                        }
-                    }
-                    _ => data, // Other filters not yet supported, use raw data
+        // The streams of an array are one content stream split in parts
+        // (ISO 32000-1, 7.8.2), so they share one graphics state
+        let mut data = Vec::new();
+        for source in content_streams {
+            match xref.decoded_stream(&source) {
+                Ok(decompressed) => {
+                    data.extend_from_slice(&decompressed);
+                    data.push(b'\n');
                 }
-            } else {
-                data // No filter, use raw data
-            };
-
-            // Create a stream from the (decoded) content data
-            let stream = Box::new(Stream::from_bytes(decoded_data)) as Box<dyn super::BaseStream>;
-            let lexer = Lexer::new(stream)?;
-            let parser = super::Parser::new(lexer)?;
-            let mut evaluator = ContentStreamEvaluator::new(parser);
-
-            // Load fonts from page resources (for proper character encoding)
-            if let Some(resources) = self.resources() {
-                // Ignore font loading errors - text extraction will still work with fallback encoding
-                let _ = evaluator.load_fonts(resources, xref);
+                Err(e @ (PDFError::DataMissing { .. } | PDFError::Cancelled)) => return Err(e),
+                Err(_) => continue, // Skip this stream if decompression fails
             }
-
-            // Extract text from this stream
-            let text_items = evaluator.extract_text()?;
-            all_text_items.extend(text_items);
         }
 
-        Ok(all_text_items)
+        let mut evaluator = ContentStreamEvaluator::from_bytes(data);
+        evaluator.set_max_depth(xref.limits().max_object_depth);
+        evaluator.set_extraction_mode(mode);
+        evaluator.set_glyph_geometry(glyph_geometry);
+        if let Some(resources) = self.resources() {
+            evaluator.load_resources(resources, xref)?;
+        }
+        evaluator.extract_text_with_forms(xref)
     }
 
     /// Extracts all text from the page as a single string.
@@ -506,53 +559,149 @@ impl Page {
     /// println!("Page text:\n{}", text);
     /// ```
     pub fn extract_text_as_string(&self, xref: &mut super::xref::XRef) -> PDFResult<String> {
-        let mut text_items = self.extract_text(xref)?;
-
-        // Sort text items by position (top to bottom, left to right)
-        // Y-axis in PDF goes bottom to top, so we sort by descending Y, then ascending X
-        text_items.sort_by(|a, b| {
-            match (a.position, b.position) {
-                (Some((x1, y1)), Some((x2, y2))) => {
-                    // First sort by Y (descending - top to bottom)
-                    let y_cmp = y2.partial_cmp(&y1).unwrap_or(std::cmp::Ordering::Equal);
-                    if y_cmp != std::cmp::Ordering::Equal {
-                        y_cmp
-                    } else {
-                        // Then sort by X (ascending - left to right)
-                        x1.partial_cmp(&x2).unwrap_or(std::cmp::Ordering::Equal)
-                    }
-                }
-                (Some(_), None) => std::cmp::Ordering::Less,
-                (None, Some(_)) => std::cmp::Ordering::Greater,
-                (None, None) => std::cmp::Ordering::Equal,
-            }
-        });
-
-        // Group text items into lines based on Y position
-        let mut result = String::new();
-        let mut last_y: Option<f64> = None;
-        let line_threshold = 2.0; // Y-distance threshold to consider same line
-
-        for item in text_items {
-            if let Some((_, y)) = item.position {
-                if let Some(prev_y) = last_y {
-                    // If Y position changed significantly, start a new line
-                    if (y - prev_y).abs() > line_threshold {
-                        result.push('\n');
-                    } else {
-                        // Same line, add a space between items
-                        if !result.is_empty() && !result.ends_with(' ') && !result.ends_with('\n') {
-                            result.push(' ');
-                        }
-                    }
-                }
-                last_y = Some(y);
-            }
+        self.extract_text_as_string_with_mode(xref, ExtractionMode::Layout)
+    }
 
-            result.push_str(&item.text);
+    /// Extracts all text from the page as a single string using the given mode.
+    ///
+    /// With `ExtractionMode::Raw` the decoded strings are concatenated in
+    /// content order, without sorting and without inserted spaces or newlines.
+    /// `ExtractionMode::ReadingOrder` reads multi-column pages column by
+    /// column.
+    ///
+    /// # Arguments
+    /// * `xref` - Cross-reference table for resolving objects
+    /// * `mode` - How text is assembled
+    ///
+    /// # Returns
+    /// A single string containing all the text from the page
+    pub fn extract_text_as_string_with_mode(
+        &self,
+        xref: &mut super::xref::XRef,
+        mode: ExtractionMode,
+    ) -> PDFResult<String> {
+        let text_items = self.extract_text_with_mode(xref, mode)?;
+
+        match mode {
+            ExtractionMode::Raw => Ok(text_items.into_iter().map(|item| item.text).collect()),
+            ExtractionMode::Layout => Ok(layout_text(text_items, &TextLayoutOptions::default())),
+            ExtractionMode::ReadingOrder => Ok(layout_reading_order(
+                text_items,
+                &TextLayoutOptions::default(),
+            )),
         }
+    }
+
+    /// Extracts all text from the page as a single string, using custom line
+    /// and word-space thresholds.
+    ///
+    /// # Arguments
+    /// * `xref` - Cross-reference table for resolving objects
+    /// * `options` - Thresholds for grouping items into lines and words
+    ///
+    /// # Returns
+    /// A single string containing all the text from the page
+    pub fn extract_text_as_string_with_options(
+        &self,
+        xref: &mut super::xref::XRef,
+        options: &TextLayoutOptions,
+    ) -> PDFResult<String> {
+        let text_items = self.extract_text_with_mode(xref, ExtractionMode::Layout)?;
+        Ok(layout_text(text_items, options))
+    }
 
-        Ok(result)
+    /// Extracts the words of the page with their bounding boxes.
+    ///
+    /// Runs shown by separate operators are merged and split into words
+    /// using the glyph widths, character spacing and word spacing, so the
+    /// boxes can be used to highlight search results.
+    ///
+    /// # Arguments
+    /// * `xref` - Cross-reference table for resolving objects
+    ///
+    /// # Returns
+    /// The words in line order, with boxes in user space
+    pub fn extract_words(&self, xref: &mut super::xref::XRef) -> PDFResult<Vec<TextWord>> {
+        let text_items = self.extract_text_with_mode(xref, ExtractionMode::Layout)?;
+        Ok(extract_words(text_items, &TextLayoutOptions::default()))
+    }
+
+    /// Extracts the lines of the page, each with its words and bounding box.
+    ///
+    /// # Arguments
+    /// * `xref` - Cross-reference table for resolving objects
+    ///
+    /// # Returns
+    /// The lines, top to bottom, with boxes in user space
+    pub fn extract_lines(&self, xref: &mut super::xref::XRef) -> PDFResult<Vec<TextLine>> {
+        let text_items = self.extract_text_with_mode(xref, ExtractionMode::Layout)?;
+        Ok(extract_lines(text_items, &TextLayoutOptions::default()))
+    }
+
+    /// Reflows the text of the page into styled paragraphs (experimental).
+    ///
+    /// Paragraphs follow the geometric reading order; see
+    /// [`PDFDocument::reflow_page`](super::PDFDocument::reflow_page) for
+    /// tagged documents.
+    ///
+    /// # Arguments
+    /// * `xref` - Cross-reference table for resolving objects
+    ///
+    /// # Returns
+    /// The paragraphs in reading order
+    pub fn reflow(&self, xref: &mut super::xref::XRef) -> PDFResult<Vec<ReflowParagraph>> {
+        let text_items = self.extract_text_with_mode(xref, ExtractionMode::Layout)?;
+        let styles = self.font_styles(xref)?;
+        Ok(reflow_items(
+            text_items,
+            &styles,
+            &TextLayoutOptions::default(),
+        ))
+    }
+
+    /// Returns the styles of the page's fonts, keyed by resource name.
+    pub(crate) fn font_styles(
+        &self,
+        xref: &mut super::xref::XRef,
+    ) -> PDFResult<HashMap<String, FontStyle>> {
+        match self.resources() {
+            Some(resources) => load_font_styles(resources, xref),
+            None => Ok(HashMap::new()),
+        }
+    }
+
+    /// Extracts the filled and stroked vector paths of the page.
+    ///
+    /// The paths are collected without rasterizing: each one has the CTM
+    /// applied, so coordinates are in default user space, along with its
+    /// fill and stroke paints and stroke properties. Clipping paths, text
+    /// and images are left out.
+    ///
+    /// # Arguments
+    /// * `xref` - Cross-reference table for resolving objects
+    ///
+    /// # Returns
+    /// The paths in painting order
+    ///
+    /// # Example
+    /// ```no_run
+    /// use pdf_x_core::PDFDocument;
+    ///
+    /// let mut doc = PDFDocument::open_file("document.pdf", None, None).unwrap();
+    /// let page = doc.get_page(0).unwrap();
+    /// for path in page.extract_paths(doc.xref_mut()).unwrap() {
+    ///     if let Some(rect) = path.as_rect() {
+    ///         println!("rectangle {:?}", rect);
+    ///     }
+    /// }
+    /// ```
+    pub fn extract_paths(
+        &self,
+        xref: &mut super::xref::XRef,
+    ) -> PDFResult<Vec<crate::rendering::VectorPath>> {
+        let mut recorder = crate::rendering::vector_paths::PathRecorder::default();
+        self.render(xref, &mut recorder)?;
+        Ok(recorder.paths)
     }
 
     /// Renders this page to a rendering device.
@@ -593,24 +742,78 @@ impl Page {
         xref: &mut super::xref::XRef,
         device: &mut D,
     ) -> PDFResult<()> {
-        use super::{Lexer, Parser, Stream};
+        self.render_with_report(xref, device).map(|_| ())
+    }
+
+    /// Renders this page and reports what the device could not express.
+    ///
+    /// The report lists the painting operations that requested overprint and
+    /// the rendering intents in use, for prepress checks.
+    ///
+    /// # Arguments
+    /// * `xref` - The cross-reference table for fetching objects
+    /// * `device` - A mutable reference to a rendering device
+    ///
+    /// # Returns
+    /// The render report of all content streams of the page
+    pub fn render_with_report<D: crate::rendering::Device>(
+        &self,
+        xref: &mut super::xref::XRef,
+        device: &mut D,
+    ) -> PDFResult<crate::rendering::RenderReport> {
+        self.render_with_font_resolver(xref, device, &crate::rendering::DefaultFontResolver)
+    }
+
+    /// Renders this page, substituting non-embedded fonts with `font_resolver`.
+    ///
+    /// Like [`Page::render_with_report`], which uses the
+    /// [`DefaultFontResolver`](crate::rendering::DefaultFontResolver).
+    ///
+    /// If the xref's cancellation token (see
+    /// [`XRef::set_cancellation_token`](super::xref::XRef::set_cancellation_token))
+    /// is cancelled, rendering stops before the next operator and fails with
+    /// [`PDFError::Cancelled`](super::PDFError::Cancelled). The xref's
+    /// progress callback (see
+    /// [`XRef::set_progress`](super::xref::XRef::set_progress)) is told
+    /// (bytes of content processed, bytes of decoded content) as the
+    /// content streams are read.
+    ///
+    /// # Arguments
+    /// * `xref` - The cross-reference table for fetching objects
+    /// * `device` - A mutable reference to a rendering device
+    /// * `font_resolver` - Chooses the font data for fonts that aren't embedded
+    ///
+    /// # Returns
+    /// The render report of all content streams of the page
+    pub fn render_with_font_resolver<D: crate::rendering::Device>(
+        &self,
+        xref: &mut super::xref::XRef,
+        device: &mut D,
+        font_resolver: &dyn crate::rendering::FontResolver,
+    ) -> PDFResult<crate::rendering::RenderReport> {
         use crate::rendering::RenderingContext;
 
+        let _span =
+            tracing::debug_span!(target: log_target::RENDER, "render_page", page = self.page_index)
+                .entered();
+
         // Reference: pdf.js/src/core/document.js - Page.view (MediaBox/CropBox handling)
         let view_box = self.resolve_view_box_for_rendering(xref);
 
         let contents = match self.contents() {
             Some(contents) => contents,
-            None => return Ok(()), // No content streams to render
+            None => return Ok(Default::default()), // No content streams to render
         };
 
         // Dereference if it's a reference
-        let contents = xref.fetch_if_ref(contents)?;
-
-        // Handle single content stream or array of streams
-        let content_streams = match contents {
-            PDFObject::Stream { dict, data } => {
-                vec![(dict.clone(), data.clone())]
+        let contents_obj = xref.fetch_if_ref(contents)?;
+
+        // Handle single content stream or array of streams. Each stream's
+        // dictionary is kept with its source, whose decoded data can come
+        // from the xref's cache.
+        let content_streams = match contents_obj {
+            PDFObject::Stream { dict, .. } => {
+                vec![(contents.clone(), dict)]
             }
             PDFObject::Array(arr) => {
                 // Multiple content streams - fetch each one
@@ -625,8 +828,9 @@ impl Page {
                     };
 
                     if seen_streams.contains(&obj_key) {
-                        eprintln!(
-                            "Warning: Skipping duplicate content stream reference: {}",
+                        tracing::warn!(
+                            target: log_target::RENDER,
+                            "Skipping duplicate content stream reference: {}",
                             obj_key
                         );
                         continue;
@@ -634,8 +838,8 @@ impl Page {
                     seen_streams.insert(obj_key);
 
                     match xref.fetch_if_ref(content_obj)? {
-                        PDFObject::Stream { dict, data } => {
-                            streams.push((dict, data));
+                        PDFObject::Stream { dict, .. } => {
+                            streams.push(((**content_obj).clone(), dict));
                         }
                         _ => {
                             return Err(super::PDFError::Generic(
@@ -648,7 +852,7 @@ impl Page {
             }
             _ => {
                 // Handle unexpected Contents types gracefully
-                return Ok(());
+                return Ok(Default::default());
             }
         };
 
@@ -666,41 +870,65 @@ impl Page {
         // Reference: pdf.js/src/core/document.js - #getInheritableProperty("Resources")
         let page_resources = self.get_inheritable_resources(xref)?;
 
-        // Process each content stream
+        // The context borrows the xref, so keep a handle on its token and
+        // progress callback
+        let cancel = xref.cancellation_token().cloned();
+        let check_cancelled = || match &cancel {
+            Some(cancel) => cancel.check(),
+            None => Ok(()),
+        };
+        let progress = xref.progress().cloned();
+        let report_progress = |done: usize, total: usize| {
+            if let Some(progress) = &progress {
+                progress(done, total);
+            }
+        };
+
+        // Decode the streams up front, so that progress can be reported
+        // against the total size of the content. Streams that fail to decode
+        // are skipped.
+        let mut decoded_streams = Vec::with_capacity(content_streams.len());
+        for (stream_idx, (source, dict)) in content_streams.into_iter().enumerate() {
+            check_cancelled()?;
+            match xref.decoded_stream(&source) {
+                Ok(decoded) => decoded_streams.push((stream_idx, decoded, dict)),
+                Err(e @ PDFError::LimitExceeded { .. }) => return Err(e),
+                Err(e) => {
+                    tracing::warn!(
+                        target: log_target::RENDER,
+                        "Failed to decode content stream {}: {}",
+                        stream_idx, e
+                    );
+                }
+            }
+        }
+        let total_bytes: usize = decoded_streams.iter().map(|(_, data, _)| data.len()).sum();
+        let mut done_bytes = 0;
+        report_progress(done_bytes, total_bytes);
+
+        // Process each content stream. Operators of all streams, and of the
+        // forms, patterns and soft masks they paint, count towards the
+        // document's max_operations limit.
         let mut total_operations = 0;
-        for (stream_idx, (dict, data)) in content_streams.into_iter().enumerate() {
+        let mut operation_count = 0;
+        let mut report = crate::rendering::RenderReport::default();
+        for (stream_idx, decoded_data, dict) in decoded_streams {
+            check_cancelled()?;
+
             // Save device state before processing this stream
             // This ensures each stream starts with the same CTM
             device.save_state();
 
-            // Decode the stream if it has filters
-            let decoded_data = if let Some(filter) = dict.get("Filter") {
-                match super::decode::apply_filters(&data, filter) {
-                    Ok(decoded) => decoded,
-                    Err(e) => {
-                        eprintln!(
-                            "Warning: Failed to decode content stream {}: {}",
-                            stream_idx, e
-                        );
-                        device.restore_state();
-                        continue; // Skip this stream if decoding fails
-                    }
-                }
-            } else {
-                data.to_vec() // No filter, use raw data
-            };
-
-            eprintln!(
-                "Info: Processing content stream {} ({} bytes)",
+            tracing::debug!(
+                target: log_target::RENDER,
+                "Processing content stream {} ({} bytes)",
                 stream_idx,
                 decoded_data.len()
             );
 
-            // Create a stream from the (decoded) content data
-            let stream = Box::new(Stream::from_bytes(decoded_data)) as Box<dyn super::BaseStream>;
-            let lexer = Lexer::new(stream)?;
-            let parser = Parser::new(lexer)?;
-            let mut evaluator = super::content_stream::ContentStreamEvaluator::new(parser);
+            let mut evaluator =
+                super::content_stream::ContentStreamEvaluator::from_bytes(decoded_data.to_vec());
+            evaluator.set_max_depth(xref.limits().max_object_depth);
 
             // Merge any stream-level Resources with page-level Resources.
             // Reference: pdf.js/src/core/document.js - #getMergedResources
@@ -708,10 +936,18 @@ impl Page {
 
             // Load fonts from merged resources (for proper text rendering)
             #[cfg(feature = "rendering")]
-            self.load_fonts_for_rendering_with_resources(xref, device, resources.as_ref())?;
+            self.load_fonts_for_rendering_with_resources(
+                xref,
+                device,
+                resources.as_ref(),
+                font_resolver,
+            )?;
 
             // Create a rendering context to process operations
             let mut ctx = RenderingContext::new(device);
+            ctx.set_font_resolver(font_resolver);
+            ctx.set_operation_count(operation_count);
+            ctx.set_limits(*xref.limits());
 
             // Set xref and resources for XObject (image) rendering
             // Note: We need to extend the lifetime of the fetched resources
@@ -723,40 +959,230 @@ impl Page {
             // Parse and process each operation in the content stream
             let mut stream_operations = 0;
             loop {
+                if let Err(e) = check_cancelled() {
+                    drop(ctx);
+                    device.restore_state();
+                    return Err(e);
+                }
                 match evaluator.read_operation() {
                     Ok(Some(op)) => {
                         stream_operations += 1;
-                        if let Err(e) = ctx.process_operation(&op) {
-                            // Log but continue processing - one bad operator shouldn't stop entire rendering
-                            eprintln!("Warning: Failed to process operator {:?}: {}", op.op, e);
+                        if stream_operations % 1024 == 0 {
+                            let position = evaluator.position().unwrap_or_default();
+                            report_progress(done_bytes + position, total_bytes);
+                        }
+                        match ctx.process_operation(&op) {
+                            Ok(()) => {}
+                            Err(e @ PDFError::LimitExceeded { .. }) => {
+                                drop(ctx);
+                                device.restore_state();
+                                return Err(e);
+                            }
+                            Err(e) => {
+                                // Log but continue processing - one bad operator shouldn't stop entire rendering
+                                tracing::warn!(
+                                    target: log_target::RENDER,
+                                    "Failed to process operator {:?}: {}", op.op, e
+                                );
+                            }
                         }
                     }
                     Ok(None) => break, // End of stream
                     Err(e) => {
-                        eprintln!(
-                            "Warning: Failed to read operation in stream {}, stopping: {}",
+                        tracing::warn!(
+                            target: log_target::RENDER,
+                            "Failed to read operation in stream {}, stopping: {}",
                             stream_idx, e
                         );
                         break; // Can't continue after a read error
                     }
                 }
             }
-            eprintln!(
-                "Info: Processed {} operations in stream {}",
+            tracing::debug!(
+                target: log_target::RENDER,
+                "Processed {} operations in stream {}",
                 stream_operations, stream_idx
             );
             total_operations += stream_operations;
+            done_bytes += decoded_data.len();
+            report_progress(done_bytes, total_bytes);
+            operation_count = ctx.operation_count();
+            report.merge(ctx.take_report());
 
             // Restore device state after processing this stream
             // This resets the CTM to the state before this stream
             device.restore_state();
         }
-        eprintln!(
-            "Info: Total {} operations processed for page {}",
+        tracing::debug!(
+            target: log_target::RENDER,
+            "Total {} operations processed for page {}",
             total_operations, self.page_index
         );
 
-        Ok(())
+        // Annotations are drawn over the page content
+        check_cancelled()?;
+        match self.draw_annotations(xref, device, font_resolver) {
+            Ok(_) => {}
+            Err(e @ PDFError::LimitExceeded { .. }) => return Err(e),
+            Err(e) => {
+                tracing::warn!(target: log_target::RENDER, "Failed to render annotations: {}", e);
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Draws the appearance streams of the page's annotations.
+    ///
+    /// Each annotation's normal appearance (see
+    /// [`Annotation::normal_appearance`](super::annotation::Annotation::normal_appearance))
+    /// is painted as a form XObject whose /BBox, transformed by its /Matrix,
+    /// is fitted to the annotation /Rect (PDF spec 12.5.5). Hidden and
+    /// NoView annotations are skipped. [`Page::render`] calls this after the
+    /// page content.
+    ///
+    /// # Arguments
+    /// * `xref` - The cross-reference table for fetching objects
+    /// * `device` - The rendering device
+    ///
+    /// # Returns
+    /// The number of annotations drawn
+    pub fn render_annotations<D: crate::rendering::Device>(
+        &self,
+        xref: &mut super::xref::XRef,
+        device: &mut D,
+    ) -> PDFResult<usize> {
+        self.draw_annotations(xref, device, &crate::rendering::DefaultFontResolver)
+    }
+
+    fn draw_annotations<D: crate::rendering::Device>(
+        &self,
+        xref: &mut super::xref::XRef,
+        device: &mut D,
+        font_resolver: &dyn crate::rendering::FontResolver,
+    ) -> PDFResult<usize> {
+        use crate::rendering::RenderingContext;
+        use crate::rendering::graphics_state::multiply_matrix;
+
+        let annotations = self.extract_annotations(xref)?;
+        if annotations.is_empty() {
+            return Ok(0);
+        }
+        let resources = self
+            .get_inheritable_resources(xref)?
+            .unwrap_or_else(|| PDFObject::Dictionary(Default::default()));
+
+        let mut drawn = 0;
+        for annotation in &annotations {
+            if annotation.flags.hidden || annotation.flags.no_view {
+                continue;
+            }
+            let Some(PDFObject::Stream { dict, data }) = annotation.normal_appearance(xref)? else {
+                continue;
+            };
+
+            let number = |value: &PDFObject| value.as_number().unwrap_or(0.0);
+            let bbox = match dict.get("BBox").and_then(|b| b.as_array()) {
+                Some([x0, y0, x1, y1]) => [number(x0), number(y0), number(x1), number(y1)],
+                _ => continue,
+            };
+            let matrix = match dict.get("Matrix").and_then(|m| m.as_array()) {
+                Some([a, b, c, d, e, f]) => [
+                    number(a),
+                    number(b),
+                    number(c),
+                    number(d),
+                    number(e),
+                    number(f),
+                ],
+                _ => [1.0, 0.0, 0.0, 1.0, 0.0, 0.0],
+            };
+
+            // Fit the transformed bounding box to the rectangle
+            let corners = [
+                (bbox[0], bbox[1]),
+                (bbox[2], bbox[1]),
+                (bbox[2], bbox[3]),
+                (bbox[0], bbox[3]),
+            ]
+            .map(|(x, y)| {
+                (
+                    matrix[0] * x + matrix[2] * y + matrix[4],
+                    matrix[1] * x + matrix[3] * y + matrix[5],
+                )
+            });
+            let min_x = corners.iter().map(|c| c.0).fold(f64::INFINITY, f64::min);
+            let max_x = corners
+                .iter()
+                .map(|c| c.0)
+                .fold(f64::NEG_INFINITY, f64::max);
+            let min_y = corners.iter().map(|c| c.1).fold(f64::INFINITY, f64::min);
+            let max_y = corners
+                .iter()
+                .map(|c| c.1)
+                .fold(f64::NEG_INFINITY, f64::max);
+            let [rx0, ry0, rx1, ry1] = annotation.rect;
+            let (rx0, ry0, rx1, ry1) = (rx0.min(rx1), ry0.min(ry1), rx0.max(rx1), ry0.max(ry1));
+            let scale = |rect: f64, bbox: f64| if bbox > 0.0 { rect / bbox } else { 1.0 };
+            let (sx, sy) = (
+                scale(rx1 - rx0, max_x - min_x),
+                scale(ry1 - ry0, max_y - min_y),
+            );
+            let fit = [sx, 0.0, 0.0, sy, rx0 - min_x * sx, ry0 - min_y * sy];
+
+            // Reference: pdf.js/src/core/annotation.js - Annotation.getOperatorList
+            device.save_state();
+            let mut ctx = RenderingContext::new(&mut *device);
+            ctx.set_font_resolver(font_resolver);
+            ctx.set_xobject_resources(xref, &resources);
+            let ctm = multiply_matrix(&fit, &ctx.current_state().ctm);
+            ctx.current_state_mut().ctm = ctm;
+            ctx.device().concat_matrix(&fit);
+            let name = annotation.name.as_deref().unwrap_or("annotation");
+            let result = ctx.paint_form_xobject(name, None, &dict, &data);
+            drop(ctx);
+            device.restore_state();
+            match result {
+                Ok(()) => drawn += 1,
+                Err(e @ PDFError::LimitExceeded { .. }) => return Err(e),
+                Err(e) => {
+                    tracing::warn!(
+                        target: log_target::RENDER,
+                        "Failed to draw appearance of annotation {}: {}",
+                        name, e
+                    );
+                }
+            }
+        }
+        Ok(drawn)
+    }
+
+    /// Re-renders the page into a single, flattened content stream.
+    ///
+    /// Form XObjects and annotation appearances are inlined and images become
+    /// inline images; see [`ContentStreamDevice`](crate::rendering::ContentStreamDevice)
+    /// for what is simplified. Text still refers to the page's font resources.
+    ///
+    /// # Arguments
+    /// * `xref` - The cross-reference table for fetching objects
+    ///
+    /// # Returns
+    /// The content stream bytes
+    ///
+    /// # Example
+    /// ```no_run
+    /// use pdf_x_core::PDFDocument;
+    ///
+    /// let mut doc = PDFDocument::open_file("document.pdf", None, None).unwrap();
+    /// let page = doc.get_page(0).unwrap();
+    /// let content = page.flatten_content(doc.xref_mut()).unwrap();
+    /// println!("{}", String::from_utf8_lossy(&content));
+    /// ```
+    pub fn flatten_content(&self, xref: &mut super::xref::XRef) -> PDFResult<Vec<u8>> {
+        let [x0, y0, x1, y1] = self.resolve_view_box_for_rendering(xref);
+        let mut device = crate::rendering::ContentStreamDevice::new(x1 - x0, y1 - y0);
+        self.render(xref, &mut device)?;
+        Ok(device.finish())
     }
 
     // ========== Font Loading Methods ==========
@@ -802,7 +1228,8 @@ impl Page {
     /// This method:
     /// 1. Extracts all fonts from the page's Resources
     /// 2. Loads embedded font data (if available)
-    /// 3. For standard fonts (Helvetica, Times, Courier), attempts to use fallback fonts
+    /// 3. For fonts without embedded data, loads a substitute from the
+    ///    [`DefaultFontResolver`](crate::rendering::DefaultFontResolver)
     ///
     /// # Arguments
     /// * `xref` - Cross-reference table for fetching objects
@@ -816,139 +1243,43 @@ impl Page {
         xref: &mut super::xref::XRef,
         device: &mut D,
     ) -> PDFResult<()> {
-        let fonts = self.get_fonts(xref)?;
-
-        for (font_name, font_ref) in fonts {
-            // Try to fetch and load the font
-            match xref.fetch_if_ref(&font_ref) {
-                Ok(font_obj) => {
-                    // Try to create a Font and extract embedded data
-                    match super::font::Font::new(font_obj, xref) {
-                        Ok(pdf_font) => {
-                            let width_metrics = Self::build_font_width_metrics(&pdf_font);
-                            // If we have embedded font data, load it
-                            if let Some(embedded_data) = pdf_font.embedded_font {
-                                // Note: page.rs doesn't have access to the encoding dictionary here
-                                // The rendering context will handle encoding properly
-                                if let Err(e) = device
-                                    .load_font_data(&font_name, embedded_data, None)
-                                    .and_then(|_| {
-                                        device.set_font_width_metrics(&font_name, &width_metrics)
-                                    })
-                                {
-                                    // Only log for non-Type1 fonts (Type1 "UnknownMagic" is expected)
-                                    if !e.to_string().contains("UnknownMagic") {
-                                        eprintln!(
-                                            "Warning: Failed to load embedded font '{}': {}",
-                                            font_name, e
-                                        );
-                                    }
-                                }
-                            } else {
-                                // No embedded font - try to use a fallback for standard fonts
-                                let base_font = pdf_font.base_font();
-                                if let Some(fallback_data) = Self::get_fallback_font_data(base_font)
-                                {
-                                    if let Err(e) = device
-                                        .load_font_data(&font_name, fallback_data, None)
-                                        .and_then(|_| {
-                                            device
-                                                .set_font_width_metrics(&font_name, &width_metrics)
-                                        })
-                                    {
-                                        eprintln!(
-                                            "Warning: Failed to load fallback font for '{}': {}",
-                                            font_name, e
-                                        );
-                                    }
-                                }
-                                // Silently skip fonts without embedded data or fallback
-                            }
-                        }
-                        Err(_) => {
-                            // Silently skip fonts that fail to parse (Type1 fonts are expected to fail)
-                        }
-                    }
-                }
-                Err(_) => {
-                    // Silently skip fonts that fail to fetch
-                }
-            }
-        }
-
-        Ok(())
+        self.load_fonts_for_rendering_with_font_resolver(
+            xref,
+            device,
+            &crate::rendering::DefaultFontResolver,
+        )
     }
 
-    /// Gets fallback font data for standard PDF fonts.
+    /// Loads all fonts from the page into the device, substituting
+    /// non-embedded fonts with `font_resolver`.
+    ///
+    /// Like [`Page::load_fonts_for_rendering`], which uses the
+    /// [`DefaultFontResolver`](crate::rendering::DefaultFontResolver).
     ///
-    /// Returns font data for common PDF base fonts (Helvetica, Times, Courier, etc.)
-    /// by loading bundled Liberation fonts (which are metric-compatible with PDF standard fonts).
+    /// # Arguments
+    /// * `xref` - Cross-reference table for fetching objects
+    /// * `device` - The device to load fonts into
+    /// * `font_resolver` - Chooses the font data for fonts that aren't embedded
+    ///
+    /// # Returns
+    /// Ok(()) if successful, even if some fonts fail to load
     #[cfg(feature = "rendering")]
-    fn get_fallback_font_data(base_font: &str) -> Option<Vec<u8>> {
-        // Normalize font name (remove suffixes like -Bold, -Italic, etc.)
-        let font_name = base_font.split('-').next().unwrap_or(base_font);
-
-        // Map standard PDF fonts to bundled Liberation fonts
-        // Liberation fonts are metric-compatible with standard PDF fonts and are OFL licensed
-        let fallback_font = match font_name {
-            // Helvetica family -> Liberation Sans
-            "Helvetica" | "Arial" | "sans-serif" => "LiberationSans-Regular.ttf",
-            "Helvetica-Bold" | "Arial-Bold" => "LiberationSans-Bold.ttf",
-            "Helvetica-Oblique" | "Helvetica-Italic" | "Arial-Italic" => {
-                "LiberationSans-Italic.ttf"
-            }
-            "Helvetica-BoldOblique" | "Helvetica-BoldItalic" | "Arial-BoldItalic" => {
-                "LiberationSans-BoldItalic.ttf"
-            }
-
-            // Times family -> Liberation Serif
-            "Times" | "Times-Roman" | "serif" => "LiberationSerif-Regular.ttf",
-            "Times-Bold" => "LiberationSerif-Bold.ttf",
-            "Times-Italic" | "Times-Roman-Italic" => "LiberationSerif-Italic.ttf",
-            "Times-BoldItalic" | "Times-Roman-BoldItalic" => "LiberationSerif-BoldItalic.ttf",
-
-            // Courier family -> Liberation Mono
-            "Courier" | "CourierNew" | "monospace" => "LiberationMono-Regular.ttf",
-            "Courier-Bold" | "CourierNew-Bold" => "LiberationMono-Bold.ttf",
-            "Courier-Oblique" | "Courier-Italic" | "CourierNew-Italic" => {
-                "LiberationMono-Italic.ttf"
-            }
-            "Courier-BoldOblique" | "Courier-BoldItalic" | "CourierNew-BoldItalic" => {
-                "LiberationMono-BoldItalic.ttf"
-            }
-
-            // Symbol and ZapfDingbats don't have Liberation equivalents, return None
-            "Symbol" | "ZapfDingbats" => return None,
-
-            _ => return None,
+    pub fn load_fonts_for_rendering_with_font_resolver<D: crate::rendering::Device>(
+        &self,
+        xref: &mut super::xref::XRef,
+        device: &mut D,
+        font_resolver: &dyn crate::rendering::FontResolver,
+    ) -> PDFResult<()> {
+        let resources = match self.resources() {
+            Some(r) => Some(xref.fetch_if_ref(r)?),
+            None => None,
         };
-
-        // Load the font from bundled assets
-        let font_path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
-            .join("assets")
-            .join("fonts")
-            .join(fallback_font);
-
-        match std::fs::read(&font_path) {
-            Ok(data) => {
-                #[cfg(feature = "debug-logging")]
-                eprintln!(
-                    "DEBUG: Loaded fallback font '{}' for '{}' ({} bytes)",
-                    fallback_font,
-                    base_font,
-                    data.len()
-                );
-                Some(data)
-            }
-            Err(e) => {
-                eprintln!(
-                    "Warning: Failed to load fallback font '{}': {}",
-                    font_path.display(),
-                    e
-                );
-                None
-            }
-        }
+        self.load_fonts_for_rendering_with_resources(
+            xref,
+            device,
+            resources.as_ref(),
+            font_resolver,
+        )
     }
 }
 
@@ -1030,7 +1361,7 @@ impl Page {
         &self,
         xref: &mut super::xref::XRef,
     ) -> PDFResult<Vec<super::image::ImageMetadata>> {
-        use super::image::{ImageDecoder, ImageFormat, ImageMetadata};
+        use super::image::{ImageDecoder, ImageDetection, ImageFormat, ImageMetadata};
 
         let mut images = Vec::new();
 
@@ -1061,19 +1392,17 @@ impl Page {
             // Resolve the XObject reference
             let xobject = self.fetch_if_ref(xobject_ref, xref)?;
 
-            // Check if it's an image (Subtype == /Image)
+            // Check if it's an image (Subtype == /Image, or inferred in lenient mode)
             if let PDFObject::Stream { dict, data } = xobject {
-                // Check Subtype
-                let is_image = dict
-                    .get("Subtype")
-                    .and_then(|subtype| match subtype {
-                        PDFObject::Name(n) => Some(n == "Image"),
-                        _ => None,
-                    })
-                    .unwrap_or(false);
-
-                if !is_image {
-                    continue; // Not an image XObject (could be Form)
+                match ImageDetection::classify(&dict, &data, xref.is_lenient()) {
+                    ImageDetection::Declared => {}
+                    ImageDetection::Inferred(reason) => {
+                        xref.record_diagnostic(
+                            super::xref::DiagnosticKind::ImageSubtype,
+                            format!("Image '{}': {}", name, reason),
+                        );
+                    }
+                    ImageDetection::NotImage => continue, // Not an image XObject (could be Form)
                 }
 
                 // Extract metadata
@@ -1121,39 +1450,18 @@ impl Page {
                     })
                     .unwrap_or_else(|| "Unknown".to_string());
 
-                // Detect image format from filter
-                let format = dict
-                    .get("Filter")
-                    .or_else(|| dict.get("F"))
-                    .map(|filter| match filter {
-                        PDFObject::Name(name) => match name.as_str() {
-                            "DCTDecode" => ImageFormat::JPEG,
-                            "JPXDecode" => ImageFormat::JPEG2000,
-                            "JBIG2Decode" => ImageFormat::JBIG2,
-                            "FlateDecode" => ImageFormat::Raw,
-                            _ => ImageFormat::Unknown,
-                        },
-                        PDFObject::Array(arr) => {
-                            // Multiple filters - check the first one
-                            arr.get(0)
-                                .and_then(|obj| match &**obj {
-                                    PDFObject::Name(n) => match n.as_str() {
-                                        "DCTDecode" => Some(ImageFormat::JPEG),
-                                        "JPXDecode" => Some(ImageFormat::JPEG2000),
-                                        "JBIG2Decode" => Some(ImageFormat::JBIG2),
-                                        "FlateDecode" => Some(ImageFormat::Raw),
-                                        _ => Some(ImageFormat::Unknown),
-                                    },
-                                    _ => None,
-                                })
-                                .unwrap_or(ImageFormat::Unknown)
-                        }
-                        _ => ImageFormat::Unknown,
-                    })
-                    .unwrap_or_else(|| {
-                        // No filter - try to detect from data
-                        ImageDecoder::detect_format(&data)
-                    });
+                // Detect image format from the codec in the filter chain
+                let format = match dict.get("Filter").or_else(|| dict.get("F")) {
+                    Some(filters) => match super::decode::image_filter(filters) {
+                        Some("DCTDecode") | Some("DCT") => ImageFormat::JPEG,
+                        Some("JPXDecode") => ImageFormat::JPEG2000,
+                        Some("JBIG2Decode") => ImageFormat::JBIG2,
+                        Some(_) => ImageFormat::Unknown,
+                        None => ImageFormat::Raw,
+                    },
+                    // No filter - try to detect from data
+                    None => ImageDecoder::detect_format(&data),
+                };
 
                 // Check for SMask (soft mask / alpha channel)
                 let has_alpha = dict.get("SMask").is_some();
@@ -1179,7 +1487,11 @@ impl Page {
     /// Extract complete images with full decoding.
     ///
     /// This method extracts and decodes all images from the page, returning
-    /// complete pixel data ready for use.
+    /// complete pixel data ready for use. Each image's `metadata.name` is its
+    /// XObject resource name. Raw samples other than plain 8-bit gray or RGB
+    /// (CMYK, Indexed and other color spaces, other bit depths and /Decode
+    /// arrays) are converted to 8-bit RGB, and stencil masks (/ImageMask)
+    /// become black on transparent RGBA.
     ///
     /// # Arguments
     /// * `xref` - Mutable reference to the XRef table for resolving object references
@@ -1205,8 +1517,7 @@ impl Page {
         &self,
         xref: &mut super::xref::XRef,
     ) -> PDFResult<Vec<super::image::DecodedImage>> {
-        use super::decode;
-        use super::image::{ImageColorSpace, ImageDecoder};
+        use super::image::ImageDetection;
 
         let mut decoded_images = Vec::new();
 
@@ -1237,202 +1548,172 @@ impl Page {
             // Resolve the XObject reference
             let xobject = self.fetch_if_ref(xobject_ref, xref)?;
 
-            // Check if it's an image (Subtype == /Image)
+            // Check if it's an image (Subtype == /Image, or inferred in lenient mode)
             if let PDFObject::Stream { dict, data } = xobject {
-                // Check Subtype
-                let is_image = dict
-                    .get("Subtype")
-                    .and_then(|subtype| match subtype {
-                        PDFObject::Name(n) => Some(n == "Image"),
-                        _ => None,
-                    })
-                    .unwrap_or(false);
-
-                if !is_image {
-                    continue; // Not an image XObject
+                match ImageDetection::classify(&dict, &data, xref.is_lenient()) {
+                    ImageDetection::Declared => {}
+                    ImageDetection::Inferred(reason) => {
+                        xref.record_diagnostic(
+                            super::xref::DiagnosticKind::ImageSubtype,
+                            format!("Image '{}': {}", name, reason),
+                        );
+                    }
+                    ImageDetection::NotImage => continue, // Not an image XObject
                 }
 
-                // Get filter to determine decoding strategy
-                let filter = dict.get("Filter").or_else(|| dict.get("F"));
-
-                // Decode the image stream based on filter
-                match filter {
-                    Some(PDFObject::Name(filter_name)) => {
-                        match filter_name.as_str() {
-                            "DCTDecode" => {
-                                // JPEG - decode directly
-                                match ImageDecoder::decode_image(
-                                    &data,
-                                    super::image::ImageFormat::JPEG,
-                                ) {
-                                    Ok(img) => decoded_images.push(img),
-                                    Err(e) => {
-                                        eprintln!(
-                                            "Warning: Failed to decode JPEG image '{}': {}",
-                                            name, e
-                                        );
-                                    }
-                                }
-                            }
-                            "JPXDecode" => {
-                                // JPEG2000 - decode directly
-                                match ImageDecoder::decode_image(
-                                    &data,
-                                    super::image::ImageFormat::JPEG2000,
-                                ) {
-                                    Ok(img) => decoded_images.push(img),
-                                    Err(e) => {
-                                        eprintln!(
-                                            "Warning: Failed to decode JPEG2000 image '{}': {}",
-                                            name, e
-                                        );
-                                    }
-                                }
-                            }
-                            "JBIG2Decode" => {
-                                // JBIG2 - decode directly
-                                match ImageDecoder::decode_image(
-                                    &data,
-                                    super::image::ImageFormat::JBIG2,
-                                ) {
-                                    Ok(img) => decoded_images.push(img),
-                                    Err(e) => {
-                                        eprintln!(
-                                            "Warning: Failed to decode JBIG2 image '{}': {}",
-                                            name, e
-                                        );
-                                    }
-                                }
-                            }
-                            "FlateDecode" => {
-                                // Decompress first, then decode as raw image
-                                match decode::decode_flate(&data) {
-                                    Ok(decompressed) => {
-                                        // Get image parameters for raw decoding
-                                        let width = dict
-                                            .get("Width")
-                                            .or_else(|| dict.get("W"))
-                                            .and_then(|w| match w {
-                                                PDFObject::Number(n) => Some(*n as u32),
-                                                _ => None,
-                                            })
-                                            .unwrap_or(0);
-
-                                        let height = dict
-                                            .get("Height")
-                                            .or_else(|| dict.get("H"))
-                                            .and_then(|h| match h {
-                                                PDFObject::Number(n) => Some(*n as u32),
-                                                _ => None,
-                                            })
-                                            .unwrap_or(0);
-
-                                        let bpc = dict
-                                            .get("BitsPerComponent")
-                                            .or_else(|| dict.get("BPC"))
-                                            .and_then(|bpc| match bpc {
-                                                PDFObject::Number(n) => Some(*n as u8),
-                                                _ => None,
-                                            })
-                                            .unwrap_or(8);
-
-                                        let color_space = dict
-                                            .get("ColorSpace")
-                                            .or_else(|| dict.get("CS"))
-                                            .map(|cs| ImageDecoder::parse_color_space(cs))
-                                            .unwrap_or(ImageColorSpace::RGB);
-
-                                        // Decode as raw image
-                                        match ImageDecoder::decode_raw_image(
-                                            &decompressed,
-                                            width,
-                                            height,
-                                            bpc,
-                                            color_space,
-                                        ) {
-                                            Ok(img) => decoded_images.push(img),
-                                            Err(e) => {
-                                                eprintln!(
-                                                    "Warning: Failed to decode raw image '{}': {}",
-                                                    name, e
-                                                );
-                                            }
-                                        }
-                                    }
-                                    Err(e) => {
-                                        eprintln!(
-                                            "Warning: Failed to decompress FlateDecode image '{}': {}",
-                                            name, e
-                                        );
-                                    }
-                                }
-                            }
-                            other => {
-                                eprintln!(
-                                    "Warning: Unsupported image filter '{}' for image '{}'",
-                                    other, name
-                                );
-                            }
-                        }
+                match decode_image_xobject(&dict, data, xref) {
+                    Ok(mut img) => {
+                        img.metadata.name = name.clone();
+                        decoded_images.push(img);
                     }
-                    Some(PDFObject::Array(_arr)) => {
-                        eprintln!(
-                            "Warning: Multiple filters not yet supported for image '{}'",
-                            name
+                    Err(e) => {
+                        tracing::warn!(
+                            target: log_target::IMAGE,
+                            "Failed to decode image '{}': {}", name, e
                         );
                     }
-                    None => {
-                        // No filter - raw uncompressed image data
-                        let width = dict
-                            .get("Width")
-                            .or_else(|| dict.get("W"))
-                            .and_then(|w| match w {
-                                PDFObject::Number(n) => Some(*n as u32),
-                                _ => None,
-                            })
-                            .unwrap_or(0);
+                }
+            }
+        }
 
-                        let height = dict
-                            .get("Height")
-                            .or_else(|| dict.get("H"))
-                            .and_then(|h| match h {
-                                PDFObject::Number(n) => Some(*n as u32),
-                                _ => None,
-                            })
-                            .unwrap_or(0);
+        Ok(decoded_images)
+    }
 
-                        let bpc = dict
-                            .get("BitsPerComponent")
-                            .or_else(|| dict.get("BPC"))
-                            .and_then(|bpc| match bpc {
-                                PDFObject::Number(n) => Some(*n as u8),
-                                _ => None,
-                            })
-                            .unwrap_or(8);
+    /// Decode the page's embedded thumbnail (/Thumb).
+    ///
+    /// Thumbnails are small preview images some producers store with each
+    /// page; they are much cheaper to show than a rendered page, for
+    /// example in a viewer's sidebar.
+    ///
+    /// # Arguments
+    /// * `xref` - Mutable reference to the XRef table for resolving object references
+    ///
+    /// # Returns
+    /// The thumbnail as 8-bit RGBA, or `None` if the page has none
+    ///
+    /// # Example
+    /// ```no_run
+    /// use pdf_x::core::PDFDocument;
+    ///
+    /// let mut doc = PDFDocument::open(pdf_data).unwrap();
+    /// let page = doc.get_page(0).unwrap();
+    /// if let Some(thumb) = page.thumbnail(doc.xref_mut()).unwrap() {
+    ///     println!("Thumbnail: {}x{}", thumb.width, thumb.height);
+    /// }
+    /// ```
+    pub fn thumbnail(
+        &self,
+        xref: &mut super::xref::XRef,
+    ) -> PDFResult<Option<super::image::DecodedImage>> {
+        let Some(thumb) = self.get("Thumb") else {
+            return Ok(None);
+        };
+        let PDFObject::Stream { dict, data } = self.fetch_if_ref(thumb, xref)? else {
+            return Ok(None);
+        };
 
-                        let color_space = dict
-                            .get("ColorSpace")
-                            .or_else(|| dict.get("CS"))
-                            .map(|cs| ImageDecoder::parse_color_space(cs))
-                            .unwrap_or(ImageColorSpace::RGB);
+        let mut image = decode_image_xobject(&dict, data, xref)?.into_rgba()?;
+        image.metadata.name = "Thumb".to_string();
+        Ok(Some(image))
+    }
+}
 
-                        // Decode as raw image
-                        match ImageDecoder::decode_raw_image(&data, width, height, bpc, color_space)
-                        {
-                            Ok(img) => decoded_images.push(img),
-                            Err(e) => {
-                                eprintln!("Warning: Failed to decode raw image '{}': {}", name, e);
-                            }
-                        }
-                    }
-                    Some(_) => {
-                        eprintln!("Warning: Unexpected filter type for image '{}'", name);
-                    }
-                }
-            }
+/// Decodes an image XObject to pixels.
+///
+/// Transport and compression filters are undone first. JPEG, JPEG2000 and
+/// JBIG2 streams go to their codecs; raw samples are converted from their
+/// color space (CMYK, Indexed, /Decode...), and stencil masks are painted
+/// in black, the initial fill color, with the unpainted samples left
+/// transparent.
+fn decode_image_xobject(
+    dict: &HashMap<String, PDFObject>,
+    data: Vec<u8>,
+    xref: &mut super::xref::XRef,
+) -> PDFResult<super::image::DecodedImage> {
+    use super::colorspace::ColorSpace;
+    use super::decode;
+    use super::image::{ImageColorSpace, ImageDecoder, ImageFormat};
+
+    // Undo transport and compression filters; image codecs are left for
+    // the image decoder
+    let filter = dict.get("Filter").or_else(|| dict.get("F"));
+    let decode_parms = dict.get("DecodeParms").or_else(|| dict.get("DP"));
+    let (data, image_filter) = match filter {
+        Some(filters) => {
+            decode::decode_until_image_filter_limited(&data, filters, decode_parms, xref.limits())?
         }
+        None => (data, None),
+    };
+
+    // Decode the image stream based on its codec
+    let codec = match image_filter.as_deref() {
+        Some("DCTDecode") | Some("DCT") => Some(ImageFormat::JPEG),
+        Some("JPXDecode") => Some(ImageFormat::JPEG2000),
+        Some("JBIG2Decode") => Some(ImageFormat::JBIG2),
+        Some(other) => {
+            return Err(PDFError::Unsupported {
+                feature: format!("image filter '{}'", other),
+            });
+        }
+        None => None,
+    };
+
+    let color_space_obj = dict.get("ColorSpace").or_else(|| dict.get("CS"));
+    if let Some(format) = codec {
+        if format == ImageFormat::JPEG2000 {
+            // The dictionary's /ColorSpace overrides the codestream's
+            let color_space = color_space_obj.and_then(|cs| ColorSpace::parse(cs, xref).ok());
+            let smask_in_data = dict
+                .get("SMaskInData")
+                .and_then(|v| v.as_number())
+                .is_some_and(|v| v != 0.0);
+            return ImageDecoder::decode_jpx(&data, color_space.as_ref(), smask_in_data);
+        }
+        return ImageDecoder::decode_image(&data, format);
+    }
 
-        Ok(decoded_images)
+    // No codec - raw (possibly decompressed) image data
+    let number = |keys: [&str; 2]| {
+        keys.iter()
+            .find_map(|key| dict.get(*key))
+            .and_then(|value| value.as_number())
+    };
+    let width = number(["Width", "W"]).unwrap_or(0.0) as u32;
+    let height = number(["Height", "H"]).unwrap_or(0.0) as u32;
+    let bpc = number(["BitsPerComponent", "BPC"]).unwrap_or(8.0) as u8;
+
+    let image_mask = matches!(
+        dict.get("ImageMask").or_else(|| dict.get("IM")),
+        Some(PDFObject::Boolean(true))
+    );
+    let decode: Option<Vec<f64>> = dict
+        .get("Decode")
+        .or_else(|| dict.get("D"))
+        .and_then(|d| d.as_array())
+        .and_then(|d| d.iter().map(|n| n.as_number()).collect());
+
+    if image_mask {
+        return ImageDecoder::decode_image_mask(&data, width, height, decode.as_deref(), [0, 0, 0]);
+    }
+    match color_space_obj.and_then(|cs| ColorSpace::parse(cs, xref).ok()) {
+        Some(parsed) => ImageDecoder::decode_raw_image_in(
+            &data,
+            width,
+            height,
+            bpc,
+            &parsed,
+            decode.as_deref(),
+            xref.limits(),
+        ),
+        None => ImageDecoder::decode_raw_image(
+            &data,
+            width,
+            height,
+            bpc,
+            color_space_obj
+                .map(ImageDecoder::parse_color_space)
+                .unwrap_or(ImageColorSpace::RGB),
+        ),
     }
 }
 
@@ -1549,4 +1830,124 @@ impl Page {
 
         parse_annotations(annots, xref)
     }
+
+    /// Returns the Link annotations of the page with their locations and
+    /// targets.
+    ///
+    /// URI actions and destinations given by page number are resolved here.
+    /// Named destinations and destinations given by page reference need the
+    /// document's page tree; use
+    /// [`PDFDocument::page_links`](super::PDFDocument::page_links) to have
+    /// those resolved too.
+    ///
+    /// # Arguments
+    /// * `xref` - Mutable reference to the XRef table for resolving object references
+    ///
+    /// # Returns
+    /// The links in /Annots order
+    ///
+    /// # Example
+    /// ```no_run
+    /// use pdf_x_core::{LinkTarget, PDFDocument};
+    ///
+    /// let mut doc = PDFDocument::open_file("document.pdf", None, None).unwrap();
+    /// let page = doc.get_page(0).unwrap();
+    /// for link in page.links(doc.xref_mut()).unwrap() {
+    ///     if let Some(LinkTarget::Uri(url)) = &link.target {
+    ///         println!("{} at {:?}", url, link.device_rect);
+    ///     }
+    /// }
+    /// ```
+    pub fn links(&self, xref: &mut super::xref::XRef) -> PDFResult<Vec<PageLink>> {
+        let viewport = self.viewport(xref, &crate::rendering::RenderOptions::default());
+
+        let mut links = Vec::new();
+        for annotation in self.extract_annotations(xref)? {
+            let AnnotationData::Link(link) = annotation.data else {
+                continue;
+            };
+            let target = match &link.action {
+                LinkAction::GoTo { page_index, dest } => Some(LinkTarget::Page {
+                    page_index: *page_index,
+                    dest: dest.clone(),
+                }),
+                LinkAction::URI { url, .. } => Some(LinkTarget::Uri(url.clone())),
+                _ => None,
+            };
+
+            let [x0, y0, x1, y1] = annotation.rect;
+            let (ax, ay) = viewport.apply(x0, y0);
+            let (bx, by) = viewport.apply(x1, y1);
+            links.push(PageLink {
+                rect: annotation.rect,
+                device_rect: [ax.min(bx), ay.min(by), ax.max(bx), ay.max(by)],
+                action: link.action,
+                target,
+            });
+        }
+        Ok(links)
+    }
+
+    /// Detects URLs and e-mail addresses in the page text that are not
+    /// covered by an existing Link annotation.
+    ///
+    /// The returned links are "virtual": nothing is added to the document.
+    /// Pass them to `PDFDocument::add_link_annotations()` to turn them into
+    /// real Link annotations.
+    ///
+    /// Link rectangles are estimated from the text position, font size and
+    /// character count, so they are approximate for proportional fonts.
+    ///
+    /// # Arguments
+    /// * `xref` - Mutable reference to the XRef table for resolving object references
+    ///
+    /// # Example
+    /// ```no_run
+    /// use pdf_x_core::core::PDFDocument;
+    ///
+    /// let mut doc = PDFDocument::open_file("document.pdf", None, None).unwrap();
+    /// let page = doc.get_page(0).unwrap();
+    /// for link in page.detect_links(doc.xref_mut()).unwrap() {
+    ///     println!("{} at {:?}", link.uri, link.rect);
+    /// }
+    /// ```
+    pub fn detect_links(
+        &self,
+        xref: &mut super::xref::XRef,
+    ) -> PDFResult<Vec<super::link_detection::DetectedLink>> {
+        use super::annotation::AnnotationType;
+
+        let text_items = self.extract_text(xref)?;
+        let detected = super::link_detection::detect_links(&text_items);
+        if detected.is_empty() {
+            return Ok(detected);
+        }
+
+        // Skip text that already has a real link on top of it
+        let existing_rects: Vec<[f64; 4]> = self
+            .extract_annotations(xref)?
+            .into_iter()
+            .filter(|annot| annot.annotation_type == AnnotationType::Link)
+            .map(|annot| annot.rect)
+            .collect();
+
+        Ok(detected
+            .into_iter()
+            .filter(|link| {
+                !existing_rects
+                    .iter()
+                    .any(|rect| rects_overlap(rect, &link.rect))
+            })
+            .collect())
+    }
+}
+
+/// Returns true if two rectangles [x1, y1, x2, y2] overlap.
+fn rects_overlap(a: &[f64; 4], b: &[f64; 4]) -> bool {
+    let (ax1, ax2) = (a[0].min(a[2]), a[0].max(a[2]));
+    let (ay1, ay2) = (a[1].min(a[3]), a[1].max(a[3]));
+    let (bx1, bx2) = (b[0].min(b[2]), b[0].max(b[2]));
+    let (by1, by2) = (b[1].min(b[3]), b[1].max(b[3]));
+
+    ax1 < bx2 && bx1 < ax2 && ay1 < by2 && by1 < ay2
 }
@@ -0,0 +1,245 @@
+//! Page boundaries (PDF spec 14.11.2).
+//!
+//! A page has up to five boxes, all rectangles in default user space:
+//! the MediaBox is the physical medium, the CropBox the area shown and
+//! printed, the BleedBox the area to clip to in production, the TrimBox the
+//! finished page after trimming and the ArtBox the meaningful content. Only
+//! the MediaBox is required; the CropBox defaults to it and the other three
+//! default to the CropBox. Every box is clipped to the MediaBox.
+
+use super::parser::PDFObject;
+
+/// US Letter, used when a page has no usable MediaBox.
+pub const DEFAULT_MEDIA_BOX: [f64; 4] = [0.0, 0.0, 612.0, 792.0];
+
+/// One of the five page boxes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PageBox {
+    /// The physical medium (/MediaBox)
+    Media,
+    /// The area shown and printed (/CropBox)
+    Crop,
+    /// The area to clip to in production, including bleed (/BleedBox)
+    Bleed,
+    /// The finished page after trimming (/TrimBox)
+    Trim,
+    /// The meaningful content (/ArtBox)
+    Art,
+}
+
+impl PageBox {
+    /// All boxes, from the outermost.
+    pub const ALL: [PageBox; 5] = [
+        PageBox::Media,
+        PageBox::Crop,
+        PageBox::Bleed,
+        PageBox::Trim,
+        PageBox::Art,
+    ];
+
+    /// Returns the page dictionary key of the box, e.g. "TrimBox".
+    pub fn key(self) -> &'static str {
+        match self {
+            PageBox::Media => "MediaBox",
+            PageBox::Crop => "CropBox",
+            PageBox::Bleed => "BleedBox",
+            PageBox::Trim => "TrimBox",
+            PageBox::Art => "ArtBox",
+        }
+    }
+
+    /// Returns true if the box is inherited from the page tree when the
+    /// page doesn't set it.
+    pub fn is_inheritable(self) -> bool {
+        matches!(self, PageBox::Media | PageBox::Crop)
+    }
+}
+
+/// The five boxes of a page, resolved, and its rotation.
+///
+/// Boxes are `[x0, y0, x1, y1]` with `x0 <= x1` and `y0 <= y1`.
+///
+/// # Example
+/// ```no_run
+/// use pdf_x_core::core::{PDFDocument, PageBox};
+///
+/// let mut doc = PDFDocument::open_file("print.pdf", None, None).unwrap();
+/// let boxes = doc.page_boxes(0).unwrap();
+/// let [x0, y0, x1, y1] = boxes.get(PageBox::Trim);
+/// println!("trimmed to {} x {} pt", x1 - x0, y1 - y0);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PageBoxes {
+    /// The MediaBox
+    pub media: [f64; 4],
+    /// The CropBox, clipped to the MediaBox
+    pub crop: [f64; 4],
+    /// The BleedBox, clipped to the MediaBox
+    pub bleed: [f64; 4],
+    /// The TrimBox, clipped to the MediaBox
+    pub trim: [f64; 4],
+    /// The ArtBox, clipped to the MediaBox
+    pub art: [f64; 4],
+    /// Clockwise rotation of the page when displayed (0, 90, 180 or 270)
+    pub rotate: i32,
+}
+
+impl PageBoxes {
+    /// Resolves the boxes from the values of the page's entries.
+    ///
+    /// # Arguments
+    /// * `entry` - Returns the value of a box's entry, inherited if the box
+    ///   is inheritable, or `None` if the page doesn't set it
+    /// * `rotate` - The page's rotation, a multiple of 90 in 0..360
+    pub(crate) fn resolve(
+        mut entry: impl FnMut(PageBox) -> Option<PDFObject>,
+        rotate: i32,
+    ) -> Self {
+        let mut value = |kind| entry(kind).as_ref().and_then(rect_from_object);
+        let media = value(PageBox::Media).unwrap_or(DEFAULT_MEDIA_BOX);
+        let crop = clip(value(PageBox::Crop), media, media);
+        PageBoxes {
+            media,
+            crop,
+            bleed: clip(value(PageBox::Bleed), media, crop),
+            trim: clip(value(PageBox::Trim), media, crop),
+            art: clip(value(PageBox::Art), media, crop),
+            rotate,
+        }
+    }
+
+    /// Returns one of the boxes.
+    pub fn get(&self, kind: PageBox) -> [f64; 4] {
+        match kind {
+            PageBox::Media => self.media,
+            PageBox::Crop => self.crop,
+            PageBox::Bleed => self.bleed,
+            PageBox::Trim => self.trim,
+            PageBox::Art => self.art,
+        }
+    }
+
+    /// Returns the boxes as the page is displayed, after its rotation.
+    ///
+    /// The result is in a space whose origin is the lower-left corner of
+    /// the rotated MediaBox, with x to the right and y up; widths and
+    /// heights are swapped for pages rotated by 90 or 270 degrees. Its
+    /// rotation is 0.
+    pub fn rotated(&self) -> PageBoxes {
+        let [mx0, my0, mx1, my1] = self.media;
+        let (width, height) = (mx1 - mx0, my1 - my0);
+        let rotate = |[x0, y0, x1, y1]: [f64; 4]| {
+            let (u0, v0, u1, v1) = (x0 - mx0, y0 - my0, x1 - mx0, y1 - my0);
+            match self.rotate {
+                90 => [v0, width - u1, v1, width - u0],
+                180 => [width - u1, height - v1, width - u0, height - v0],
+                270 => [height - v1, u0, height - v0, u1],
+                _ => [u0, v0, u1, v1],
+            }
+        };
+        PageBoxes {
+            media: rotate(self.media),
+            crop: rotate(self.crop),
+            bleed: rotate(self.bleed),
+            trim: rotate(self.trim),
+            art: rotate(self.art),
+            rotate: 0,
+        }
+    }
+}
+
+/// Reads a rectangle array, normalizing its corners.
+///
+/// # Returns
+/// `[x0, y0, x1, y1]` with `x0 <= x1` and `y0 <= y1`, or `None` if `value`
+/// is not an array of at least four numbers
+pub fn rect_from_object(value: &PDFObject) -> Option<[f64; 4]> {
+    let PDFObject::Array(arr) = value else {
+        return None;
+    };
+    if arr.len() < 4 {
+        return None;
+    }
+    let mut vals = [0.0; 4];
+    for (val, item) in vals.iter_mut().zip(arr.iter()) {
+        *val = item.as_number()?;
+    }
+    Some([
+        vals[0].min(vals[2]),
+        vals[1].min(vals[3]),
+        vals[0].max(vals[2]),
+        vals[1].max(vals[3]),
+    ])
+}
+
+/// Clips `rect` to `media`, using `default` if `rect` is missing or
+/// doesn't overlap `media`.
+fn clip(rect: Option<[f64; 4]>, media: [f64; 4], default: [f64; 4]) -> [f64; 4] {
+    let Some(rect) = rect else {
+        return default;
+    };
+    let clipped = [
+        rect[0].max(media[0]),
+        rect[1].max(media[1]),
+        rect[2].min(media[2]),
+        rect[3].min(media[3]),
+    ];
+    if clipped[2] > clipped[0] && clipped[3] > clipped[1] {
+        clipped
+    } else {
+        default
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> PDFObject {
+        let stream = crate::core::Stream::from_bytes(source.as_bytes().to_vec());
+        let lexer = crate::core::Lexer::new(Box::new(stream)).unwrap();
+        crate::core::Parser::new(lexer)
+            .unwrap()
+            .get_object()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_resolve_and_rotate() {
+        let boxes = PageBoxes::resolve(
+            |kind| match kind {
+                PageBox::Media => Some(parse("[0 0 600 800]")),
+                PageBox::Crop => Some(parse("[-10 -10 500 700]")),
+                PageBox::Trim => Some(parse("[550 750 50 50]")),
+                PageBox::Art => Some(parse("[700 0 900 100]")),
+                PageBox::Bleed => None,
+            },
+            90,
+        );
+        assert_eq!(boxes.media, [0.0, 0.0, 600.0, 800.0]);
+        assert_eq!(boxes.crop, [0.0, 0.0, 500.0, 700.0]);
+        // Missing or outside the MediaBox: the CropBox
+        assert_eq!(boxes.bleed, boxes.crop);
+        assert_eq!(boxes.art, boxes.crop);
+        assert_eq!(boxes.get(PageBox::Trim), [50.0, 50.0, 550.0, 750.0]);
+
+        // Turned clockwise, the page is 800 wide and the left edge is on top
+        let rotated = boxes.rotated();
+        assert_eq!(rotated.media, [0.0, 0.0, 800.0, 600.0]);
+        assert_eq!(rotated.crop, [0.0, 100.0, 700.0, 600.0]);
+        assert_eq!(rotated.trim, [50.0, 50.0, 750.0, 550.0]);
+        assert_eq!(rotated.rotate, 0);
+
+        let unrotated = PageBoxes { rotate: 0, ..boxes };
+        assert_eq!(unrotated.rotated(), unrotated);
+        let upside_down = PageBoxes {
+            rotate: 180,
+            ..boxes
+        }
+        .rotated();
+        assert_eq!(upside_down.crop, [100.0, 100.0, 600.0, 800.0]);
+
+        let defaults = PageBoxes::resolve(|_| None, 0);
+        assert_eq!(defaults.trim, DEFAULT_MEDIA_BOX);
+    }
+}
@@ -0,0 +1,291 @@
+//! Content hashes of pages, for finding duplicate pages and documents.
+//!
+//! Two kinds of hash are computed:
+//!
+//! - A structural hash of what a page draws: its content stream operations,
+//!   with operands normalized, and digests of the resources they use. Pages
+//!   written by different producers hash the same as long as they draw the
+//!   same thing the same way: whitespace, comments, number formatting,
+//!   stream compression, the split into several content streams, resource
+//!   names and object numbers don't matter.
+//! - A perceptual hash of the rendered page ([`PerceptualHash`]), which
+//!   stays close for pages that look alike: compare two with
+//!   [`PerceptualHash::distance`].
+//!
+//! Structural hashes are MD5 hex strings, like the digests of
+//! [`Manifest`](super::manifest::Manifest).
+
+use super::content_stream::{ContentStreamEvaluator, OpCode};
+use super::decode::decode_stream_data;
+use super::error::{PDFError, PDFResult};
+use super::manifest::{DeepWalker, hex_digest, write_canonical};
+use super::page::Page;
+use super::parser::PDFObject;
+use super::xref::XRef;
+use std::collections::HashMap;
+
+/// Operands are rounded to this many decimal places, so that `0.1` and
+/// `0.1000001` written by different producers hash the same.
+const OPERAND_PRECISION: f64 = 1000.0;
+
+/// Width and height of the grid a page is reduced to for its perceptual
+/// hash; each row gives 8 bits from comparing 9 neighboring cells.
+const PHASH_COLUMNS: usize = 9;
+const PHASH_ROWS: usize = 8;
+
+/// Size in pixels of the longer side of the page rendered for a perceptual
+/// hash.
+#[cfg(feature = "rendering")]
+pub(crate) const PHASH_RENDER_SIZE: f64 = 128.0;
+
+/// A 64-bit perceptual hash of a rendered page.
+///
+/// Each bit tells whether a cell of a 9×8 grid over the page is brighter
+/// than its right neighbor, so the hash survives scaling, compression
+/// artifacts and small edits.
+///
+/// # Example
+/// ```no_run
+/// use pdf_x_core::PDFDocument;
+///
+/// let mut doc = PDFDocument::open_file("archive.pdf", None, None).unwrap();
+/// let first = doc.page_perceptual_hash(0).unwrap();
+/// let second = doc.page_perceptual_hash(1).unwrap();
+/// if first.distance(&second) <= 5 {
+///     println!("Pages 1 and 2 look alike");
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PerceptualHash(pub u64);
+
+impl PerceptualHash {
+    /// Computes the hash of an RGBA image, composited on white.
+    ///
+    /// # Arguments
+    /// * `width` - Image width in pixels
+    /// * `height` - Image height in pixels
+    /// * `pixels` - RGBA pixel data, row by row
+    pub fn from_rgba(width: u32, height: u32, pixels: &[u8]) -> Self {
+        let (width, height) = (width as usize, height as usize);
+        let mut sums = [[0.0f64; PHASH_COLUMNS]; PHASH_ROWS];
+        let mut counts = [[0u32; PHASH_COLUMNS]; PHASH_ROWS];
+        for (index, pixel) in pixels.chunks_exact(4).take(width * height).enumerate() {
+            let (x, y) = (index % width, index / width);
+            let alpha = pixel[3] as f64 / 255.0;
+            let luma = 0.299 * pixel[0] as f64 + 0.587 * pixel[1] as f64 + 0.114 * pixel[2] as f64;
+            let (row, column) = (y * PHASH_ROWS / height, x * PHASH_COLUMNS / width);
+            sums[row][column] += luma * alpha + 255.0 * (1.0 - alpha);
+            counts[row][column] += 1;
+        }
+
+        // Images smaller than the grid leave cells empty; those count as white
+        let cell = |row: usize, column: usize| match counts[row][column] {
+            0 => 255.0,
+            count => sums[row][column] / count as f64,
+        };
+        let mut hash = 0u64;
+        for row in 0..PHASH_ROWS {
+            for column in 0..PHASH_COLUMNS - 1 {
+                hash <<= 1;
+                if cell(row, column) > cell(row, column + 1) {
+                    hash |= 1;
+                }
+            }
+        }
+        PerceptualHash(hash)
+    }
+
+    /// Returns the number of differing bits, from 0 for pages that look the
+    /// same to 64.
+    pub fn distance(&self, other: &PerceptualHash) -> u32 {
+        (self.0 ^ other.0).count_ones()
+    }
+}
+
+impl std::fmt::Display for PerceptualHash {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+/// Computes the structural hash of a page.
+///
+/// The hash covers the visible area and rotation of the page and its
+/// content stream operations. Operands naming a resource are replaced by a
+/// digest of the resource, which follows references and ignores object
+/// numbers. Annotations are not included.
+///
+/// # Arguments
+/// * `xref` - Cross-reference table used to follow references
+/// * `page` - The page
+pub(crate) fn content_hash(xref: &mut XRef, page: &Page) -> PDFResult<String> {
+    let mut out = Vec::new();
+    for value in page.resolve_view_box_for_rendering(xref) {
+        write_number(value, &mut out);
+    }
+    write_number(page.resolve_rotate_for_rendering(xref) as f64, &mut out);
+
+    let mut data = Vec::new();
+    if let Some(contents) = page.contents() {
+        let streams = match xref.fetch_if_ref(contents)? {
+            PDFObject::Array(items) => items.iter().map(|item| (**item).clone()).collect(),
+            other => vec![other],
+        };
+        for stream in streams {
+            if let PDFObject::Stream { dict, data: raw } = xref.fetch_if_ref(&stream)? {
+                data.extend(decode_stream_data(&dict, &raw).unwrap_or(raw));
+                data.push(b'\n');
+            }
+        }
+    }
+
+    let resources = page.get_inheritable_resources(xref)?;
+    let mut resource_digests = ResourceDigests {
+        resources: match resources {
+            Some(resources) => xref.fetch_if_ref(&resources)?,
+            None => PDFObject::Null,
+        },
+        digests: HashMap::new(),
+    };
+
+    let mut evaluator = ContentStreamEvaluator::from_bytes(data);
+    evaluator.set_max_depth(xref.limits().max_object_depth);
+    loop {
+        let op = match evaluator.read_operation() {
+            Ok(Some(op)) => op,
+            Ok(None) => break,
+            Err(e @ (PDFError::DataMissing { .. } | PDFError::Cancelled)) => return Err(e),
+            // The rest of the stream can't be read; hash what was
+            Err(_) => break,
+        };
+
+        out.extend_from_slice(op.op.to_command().as_bytes());
+        out.push(b'(');
+        let resource_operand = resource_operand(op.op, op.args.len());
+        for (index, arg) in op.args.iter().enumerate() {
+            match (resource_operand, arg) {
+                (Some((category, position)), PDFObject::Name(name)) if position == index => {
+                    let digest = resource_digests.digest(xref, category, name);
+                    out.extend_from_slice(digest.as_bytes());
+                }
+                _ => write_operand(arg, &mut out),
+            }
+        }
+        out.push(b')');
+    }
+
+    Ok(hex_digest(&out))
+}
+
+/// Returns the resource category and operand position of the resource an
+/// operator names, if it names one.
+fn resource_operand(op: OpCode, arg_count: usize) -> Option<(&'static str, usize)> {
+    match op {
+        OpCode::SetFont => Some(("Font", 0)),
+        OpCode::PaintXObject => Some(("XObject", 0)),
+        OpCode::SetGState => Some(("ExtGState", 0)),
+        OpCode::ShadingFill => Some(("Shading", 0)),
+        OpCode::SetFillColorSpace | OpCode::SetStrokeColorSpace => Some(("ColorSpace", 0)),
+        // The pattern name comes after any color components
+        OpCode::SetFillColorN | OpCode::SetStrokeColorN => {
+            Some(("Pattern", arg_count.checked_sub(1)?))
+        }
+        OpCode::BeginMarkedContentProps | OpCode::MarkPointProps => Some(("Properties", 1)),
+        _ => None,
+    }
+}
+
+/// Digests of the resources of a page, by category and name.
+struct ResourceDigests {
+    resources: PDFObject,
+    digests: HashMap<(&'static str, String), String>,
+}
+
+impl ResourceDigests {
+    /// Returns the digest of a named resource, or the name itself if the
+    /// page has no such resource (device color spaces, broken files).
+    fn digest(&mut self, xref: &mut XRef, category: &'static str, name: &str) -> String {
+        if let Some(digest) = self.digests.get(&(category, name.to_string())) {
+            return digest.clone();
+        }
+
+        let resource = match &self.resources {
+            PDFObject::Dictionary(resources) => resources
+                .get(category)
+                .and_then(|dict| xref.fetch_if_ref(dict).ok())
+                .and_then(|dict| match dict {
+                    PDFObject::Dictionary(dict) => dict.get(name).cloned(),
+                    _ => None,
+                }),
+            _ => None,
+        };
+        let digest = match resource {
+            Some(resource) => {
+                let mut bytes = Vec::new();
+                DeepWalker::new(xref).write(&resource, &mut bytes);
+                format!("<{}>", hex_digest(&bytes))
+            }
+            None => format!("/{}", name),
+        };
+        self.digests
+            .insert((category, name.to_string()), digest.clone());
+        digest
+    }
+}
+
+/// Writes an operand with its numbers rounded.
+fn write_operand(obj: &PDFObject, out: &mut Vec<u8>) {
+    match obj {
+        PDFObject::Number(value) => write_number(*value, out),
+        PDFObject::Array(items) => {
+            out.push(b'[');
+            for item in items {
+                write_operand(item, out);
+            }
+            out.push(b']');
+        }
+        // Content streams hold no references
+        other => write_canonical(other, out, &mut |_, _| {}),
+    }
+}
+
+fn write_number(value: f64, out: &mut Vec<u8>) {
+    let rounded = (value * OPERAND_PRECISION).round() / OPERAND_PRECISION;
+    // -0 and 0 are the same number
+    let rounded = if rounded == 0.0 { 0.0 } else { rounded };
+    out.extend_from_slice(format!("#{};", rounded).as_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_perceptual_hash() {
+        // Left half black, right half white: every row compares dark with
+        // light at the middle of the grid
+        let (width, height) = (90u32, 80u32);
+        let pixels: Vec<u8> = (0..width * height)
+            .flat_map(|i| {
+                let value = if i % width < width / 2 { 0 } else { 255 };
+                [value, value, value, 255]
+            })
+            .collect();
+        let hash = PerceptualHash::from_rgba(width, height, &pixels);
+        assert_eq!(hash.0.count_ones(), 0);
+
+        let mirrored: Vec<u8> = pixels
+            .chunks_exact(4)
+            .map(|pixel| 255 - pixel[0])
+            .flat_map(|value| [value, value, value, 255])
+            .collect();
+        let mirrored = PerceptualHash::from_rgba(width, height, &mirrored);
+        assert_eq!(mirrored.0.count_ones(), 16);
+        assert_eq!(hash.distance(&mirrored), 16);
+
+        // Transparent pixels count as white
+        let blank = PerceptualHash::from_rgba(width, height, &vec![0; pixels.len()]);
+        assert_eq!(blank, PerceptualHash(0));
+        assert_eq!(blank.to_string(), "0000000000000000");
+    }
+}
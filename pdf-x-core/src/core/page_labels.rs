@@ -0,0 +1,300 @@
+//! Page labels (/PageLabels number tree).
+//!
+//! Page labels are the numbers a viewer shows for each page, e.g. "iv" for
+//! front matter or "A-3" for an appendix. The catalog's /PageLabels number
+//! tree maps the first page index of each range to a label dictionary with a
+//! numbering style (/S), a prefix (/P) and a start value (/St); pages before
+//! the first range use plain decimal numbers.
+//!
+//! Based on PDF.js src/core/catalog.js (Catalog.readPageLabels).
+
+use super::error::PDFResult;
+use super::name_number_tree::number_tree_entries;
+use super::outline::decode_pdf_string;
+use super::parser::PDFObject;
+use super::xref::XRef;
+
+/// Numbering style of a page label range (/S).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PageLabelStyle {
+    /// Decimal arabic numerals (/D)
+    Decimal,
+
+    /// Uppercase roman numerals (/R)
+    UpperRoman,
+
+    /// Lowercase roman numerals (/r)
+    LowerRoman,
+
+    /// Uppercase letters: A to Z, then AA to ZZ, ... (/A)
+    UpperAlpha,
+
+    /// Lowercase letters: a to z, then aa to zz, ... (/a)
+    LowerAlpha,
+}
+
+impl PageLabelStyle {
+    /// Parses a /S name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "D" => Some(PageLabelStyle::Decimal),
+            "R" => Some(PageLabelStyle::UpperRoman),
+            "r" => Some(PageLabelStyle::LowerRoman),
+            "A" => Some(PageLabelStyle::UpperAlpha),
+            "a" => Some(PageLabelStyle::LowerAlpha),
+            _ => None,
+        }
+    }
+
+    /// Formats a page number in this style.
+    pub fn format(&self, number: usize) -> String {
+        match self {
+            PageLabelStyle::Decimal => number.to_string(),
+            PageLabelStyle::UpperRoman => to_roman_numeral(number),
+            PageLabelStyle::LowerRoman => to_roman_numeral(number).to_lowercase(),
+            PageLabelStyle::UpperAlpha => to_alpha_label(number),
+            PageLabelStyle::LowerAlpha => to_alpha_label(number).to_lowercase(),
+        }
+    }
+}
+
+/// A range of pages sharing one label dictionary.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageLabelRange {
+    /// Index of the first page in the range
+    pub start: usize,
+
+    /// Numbering style; `None` means the labels consist of the prefix only
+    pub style: Option<PageLabelStyle>,
+
+    /// Text put before the number
+    pub prefix: String,
+
+    /// Number of the first page in the range (/St, at least 1)
+    pub first_number: usize,
+}
+
+impl PageLabelRange {
+    /// Parses a page label dictionary.
+    pub fn from_dict(start: usize, label: &PDFObject) -> Self {
+        let dict = match label {
+            PDFObject::Dictionary(dict) => Some(dict),
+            _ => None,
+        };
+        let get = |key: &str| dict.and_then(|dict| dict.get(key));
+
+        let style = match get("S") {
+            Some(PDFObject::Name(name)) => PageLabelStyle::from_name(name),
+            _ => None,
+        };
+        let prefix = match get("P") {
+            Some(PDFObject::String(bytes)) | Some(PDFObject::HexString(bytes)) => {
+                decode_pdf_string(bytes)
+            }
+            _ => String::new(),
+        };
+        let first_number = match get("St") {
+            Some(PDFObject::Number(n)) if *n >= 1.0 => *n as usize,
+            _ => 1,
+        };
+
+        PageLabelRange {
+            start,
+            style,
+            prefix,
+            first_number,
+        }
+    }
+
+    /// Returns the label of a page in this range.
+    fn label(&self, page_index: usize) -> String {
+        let number = self.first_number + (page_index - self.start);
+        match self.style {
+            Some(style) => format!("{}{}", self.prefix, style.format(number)),
+            None => self.prefix.clone(),
+        }
+    }
+}
+
+/// The page labels of a document.
+///
+/// # Example
+/// ```no_run
+/// use pdf_x_core::core::PDFDocument;
+///
+/// let mut doc = PDFDocument::open_file("document.pdf", None, None).unwrap();
+/// if let Some(labels) = doc.page_labels().unwrap() {
+///     for index in 0..doc.page_count().unwrap() as usize {
+///         println!("Page {} is labelled {}", index + 1, labels.label(index));
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PageLabels {
+    ranges: Vec<PageLabelRange>,
+}
+
+impl PageLabels {
+    /// Builds page labels from ranges; they are sorted by first page.
+    pub fn new(mut ranges: Vec<PageLabelRange>) -> Self {
+        ranges.sort_by_key(|range| range.start);
+        PageLabels { ranges }
+    }
+
+    /// Reads a /PageLabels number tree.
+    ///
+    /// # Arguments
+    /// * `xref` - Cross-reference table for resolving indirect objects
+    /// * `tree` - The /PageLabels entry of the catalog
+    pub fn parse(xref: &mut XRef, tree: &PDFObject) -> PDFResult<Self> {
+        let ranges = number_tree_entries(xref, tree)?
+            .into_iter()
+            .map(|(start, label)| PageLabelRange::from_dict(start, &label))
+            .collect();
+        Ok(PageLabels::new(ranges))
+    }
+
+    /// Returns the ranges, ordered by first page.
+    pub fn ranges(&self) -> &[PageLabelRange] {
+        &self.ranges
+    }
+
+    /// Returns the display label of a page.
+    ///
+    /// Pages before the first range are numbered 1, 2, ...
+    pub fn label(&self, page_index: usize) -> String {
+        match self
+            .ranges
+            .iter()
+            .rev()
+            .find(|range| range.start <= page_index)
+        {
+            Some(range) => range.label(page_index),
+            None => (page_index + 1).to_string(),
+        }
+    }
+
+    /// Returns the labels of the first `page_count` pages.
+    pub fn labels(&self, page_count: usize) -> Vec<String> {
+        (0..page_count).map(|index| self.label(index)).collect()
+    }
+
+    /// Finds the first page with the given label.
+    pub fn find(&self, label: &str, page_count: usize) -> Option<usize> {
+        (0..page_count).find(|&index| self.label(index) == label)
+    }
+}
+
+/// Converts a number to a Roman numeral.
+fn to_roman_numeral(mut num: usize) -> String {
+    if num == 0 {
+        return String::new();
+    }
+    if num > 3999 {
+        return num.to_string(); // Too large for Roman numerals
+    }
+
+    let values = [
+        (1000, "M"),
+        (900, "CM"),
+        (500, "D"),
+        (400, "CD"),
+        (100, "C"),
+        (90, "XC"),
+        (50, "L"),
+        (40, "XL"),
+        (10, "X"),
+        (9, "IX"),
+        (5, "V"),
+        (4, "IV"),
+        (1, "I"),
+    ];
+
+    let mut result = String::new();
+
+    for (value, name) in values.iter() {
+        while num >= *value {
+            result.push_str(name);
+            num -= *value;
+        }
+        if num == 0 {
+            break;
+        }
+    }
+
+    result
+}
+
+/// Converts a number to a letter label: A to Z for 1-26, AA to ZZ for
+/// 27-52, and so on.
+fn to_alpha_label(num: usize) -> String {
+    if num == 0 {
+        return String::new();
+    }
+    let letter = (b'A' + ((num - 1) % 26) as u8) as char;
+    letter.to_string().repeat((num - 1) / 26 + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn label_dict(style: Option<&str>, prefix: Option<&str>, start: Option<f64>) -> PDFObject {
+        let mut dict = HashMap::new();
+        if let Some(style) = style {
+            dict.insert("S".to_string(), PDFObject::Name(style.to_string()));
+        }
+        if let Some(prefix) = prefix {
+            dict.insert(
+                "P".to_string(),
+                PDFObject::String(prefix.as_bytes().to_vec()),
+            );
+        }
+        if let Some(start) = start {
+            dict.insert("St".to_string(), PDFObject::Number(start));
+        }
+        PDFObject::Dictionary(dict)
+    }
+
+    #[test]
+    fn test_styles() {
+        assert_eq!(PageLabelStyle::UpperRoman.format(1994), "MCMXCIV");
+        assert_eq!(PageLabelStyle::LowerRoman.format(4), "iv");
+        assert_eq!(PageLabelStyle::UpperAlpha.format(1), "A");
+        assert_eq!(PageLabelStyle::UpperAlpha.format(2), "B");
+        assert_eq!(PageLabelStyle::UpperAlpha.format(26), "Z");
+        assert_eq!(PageLabelStyle::UpperAlpha.format(27), "AA");
+        assert_eq!(PageLabelStyle::LowerAlpha.format(54), "bbb");
+        assert_eq!(PageLabelStyle::Decimal.format(7), "7");
+    }
+
+    #[test]
+    fn test_labels() {
+        let labels = PageLabels::new(vec![
+            PageLabelRange::from_dict(4, &label_dict(Some("D"), None, None)),
+            PageLabelRange::from_dict(0, &label_dict(Some("r"), None, None)),
+            PageLabelRange::from_dict(7, &label_dict(Some("D"), Some("A-"), Some(8.0))),
+            PageLabelRange::from_dict(9, &label_dict(None, Some("Cover"), None)),
+        ]);
+
+        assert_eq!(
+            labels.labels(11),
+            vec![
+                "i", "ii", "iii", "iv", "1", "2", "3", "A-8", "A-9", "Cover", "Cover"
+            ]
+        );
+        assert_eq!(labels.find("A-9", 11), Some(8));
+        assert_eq!(labels.find("v", 11), None);
+    }
+
+    #[test]
+    fn test_pages_before_first_range() {
+        let labels = PageLabels::new(vec![PageLabelRange::from_dict(
+            2,
+            &label_dict(Some("A"), None, None),
+        )]);
+        assert_eq!(labels.labels(4), vec!["1", "2", "A", "B"]);
+        assert_eq!(PageLabels::default().label(5), "6");
+    }
+}
@@ -1,5 +1,7 @@
 use super::error::{PDFError, PDFResult};
 use super::lexer::{Lexer, Token};
+use super::limits::ResourceLimits;
+use crate::log_target;
 use smallvec::SmallVec;
 use std::collections::HashMap;
 
@@ -93,6 +95,23 @@ pub enum PDFObject {
 }
 
 impl PDFObject {
+    /// Returns the name of the object's type, for error messages.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            PDFObject::Null => "Null",
+            PDFObject::Boolean(_) => "Boolean",
+            PDFObject::Number(_) => "Number",
+            PDFObject::String(_) | PDFObject::HexString(_) => "String",
+            PDFObject::Name(_) => "Name",
+            PDFObject::Array(_) => "Array",
+            PDFObject::Dictionary(_) => "Dictionary",
+            PDFObject::Stream { .. } => "Stream",
+            PDFObject::Ref(_) => "Ref",
+            PDFObject::EOF => "EOF",
+            PDFObject::Command(_) => "Command",
+        }
+    }
+
     /// Returns true if this object is the EOF marker.
     pub fn is_eof(&self) -> bool {
         matches!(self, PDFObject::EOF)
@@ -200,6 +219,19 @@ pub struct Parser {
     /// Optional reference resolver for resolving indirect references during parsing
     /// This is needed when parsing streams with indirect /Length references
     ref_resolver: Option<Box<dyn Fn(u32, u32) -> PDFResult<PDFObject>>>,
+
+    /// Whether a stream whose /Length doesn't end at "endstream" is repaired
+    /// by scanning for it, instead of failing
+    lenient: bool,
+
+    /// Repairs made in lenient mode, for the caller to collect
+    repairs: Vec<String>,
+
+    /// Arrays and dictionaries being parsed, one inside the other
+    depth: usize,
+
+    /// How deeply arrays and dictionaries may nest
+    max_depth: usize,
 }
 
 impl Parser {
@@ -214,9 +246,40 @@ impl Parser {
             buf1,
             buf2,
             ref_resolver: None,
+            lenient: false,
+            repairs: Vec::new(),
+            depth: 0,
+            max_depth: ResourceLimits::default().max_object_depth,
         })
     }
 
+    /// Enables or disables lenient mode.
+    ///
+    /// When a stream's /Length doesn't end right before "endstream", strict
+    /// mode fails with a `CorruptedPDF` error, while lenient mode reads up to
+    /// the next "endstream" instead and records the repair (see
+    /// [`take_repairs`](Self::take_repairs)).
+    pub fn set_lenient(&mut self, lenient: bool) {
+        self.lenient = lenient;
+    }
+
+    /// Sets how deeply arrays and dictionaries may nest before parsing fails
+    /// with [`PDFError::LimitExceeded`] (see
+    /// [`ResourceLimits::max_object_depth`]).
+    pub fn set_max_depth(&mut self, max_depth: usize) {
+        self.max_depth = max_depth;
+    }
+
+    /// Creates a parse error at the lexer's position.
+    fn error_here(&self, message: impl Into<String>) -> PDFError {
+        PDFError::parse_error_at(message.into(), None, self.lexer.get_position())
+    }
+
+    /// Returns and clears the repairs made so far in lenient mode.
+    pub fn take_repairs(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.repairs)
+    }
+
     /// Sets a reference resolver function that can resolve indirect references.
     /// This is needed when parsing streams with indirect /Length references.
     pub fn set_ref_resolver<F>(&mut self, resolver: F)
@@ -230,8 +293,16 @@ impl Parser {
     ///
     /// This moves buf2 -> buf1 and reads a new token into buf2.
     fn shift(&mut self) -> PDFResult<()> {
-        self.buf1 = self.buf2.take();
-        self.buf2 = Some(self.lexer.get_object()?);
+        self.buf1 = match self.buf2.take() {
+            Some(token) => Some(token),
+            None => Some(self.lexer.get_object()?),
+        };
+        // The bytes after an inline image's ID operator are raw image data, so
+        // leave them for parse_inline_image instead of tokenizing them.
+        self.buf2 = match &self.buf1 {
+            Some(Token::Command(cmd)) if cmd == "ID" => None,
+            _ => Some(self.lexer.get_object()?),
+        };
         Ok(())
     }
 
@@ -245,24 +316,23 @@ impl Parser {
     ///
     /// Based on PDF.js Parser.getObj()
     pub fn get_object(&mut self) -> PDFResult<PDFObject> {
-        let token = self.buf1.take().ok_or_else(|| {
-            PDFError::Generic("Parser buffer is empty (this should not happen)".to_string())
-        })?;
+        let token = self
+            .buf1
+            .take()
+            .ok_or_else(|| self.error_here("Parser buffer is empty (this should not happen)"))?;
 
         self.shift()?;
 
         match token {
             // Array start: [ ... ]
-            Token::ArrayStart => self.parse_array(),
+            Token::ArrayStart => self.nested(Self::parse_array),
 
             // Dictionary start: << ... >>
-            Token::DictStart => self.parse_dictionary(),
+            Token::DictStart => self.nested(Self::parse_dictionary),
 
             // Array/dict end tokens are errors here (should be consumed by parse_array/parse_dictionary)
-            Token::ArrayEnd => Err(PDFError::Generic("Unexpected array end token".to_string())),
-            Token::DictEnd => Err(PDFError::Generic(
-                "Unexpected dictionary end token".to_string(),
-            )),
+            Token::ArrayEnd => Err(self.error_here("Unexpected array end token")),
+            Token::DictEnd => Err(self.error_here("Unexpected dictionary end token")),
 
             // Number: could be the start of an indirect reference (N1 N2 R)
             Token::Number(n) => {
@@ -293,10 +363,26 @@ impl Parser {
             Token::String(s) => Ok(PDFObject::String(s)),
             Token::HexString(s) => Ok(PDFObject::HexString(s)),
             Token::Name(n) => Ok(PDFObject::Name(n)),
+            Token::Command(c) if c == "BI" => self.parse_inline_image(),
             Token::Command(c) => Ok(PDFObject::Command(c)), // Keep as Command for content streams
         }
     }
 
+    /// Parses an array or dictionary one level deeper, failing if that is
+    /// deeper than the nesting limit.
+    fn nested(&mut self, parse: fn(&mut Self) -> PDFResult<PDFObject>) -> PDFResult<PDFObject> {
+        if self.depth >= self.max_depth {
+            return Err(PDFError::limit_exceeded(
+                "array and dictionary nesting depth",
+                self.max_depth,
+            ));
+        }
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
     /// Parses an array: [ obj1 obj2 ... ]
     fn parse_array(&mut self) -> PDFResult<PDFObject> {
         // Use SmallVec to avoid heap allocation for small arrays
@@ -311,17 +397,19 @@ impl Parser {
 
             // Check for EOF (error: unterminated array)
             if let Some(Token::EOF) = &self.buf1 {
-                return Err(PDFError::Generic(
-                    "Unterminated array (missing ']')".to_string(),
-                ));
+                return Err(self.error_here("Unterminated array (missing ']')"));
             }
 
             // Parse the next object in the array with error recovery
             match self.get_object() {
                 Ok(obj) => array.push(Box::new(obj)),
+                Err(e @ PDFError::LimitExceeded { .. }) => return Err(e),
                 Err(e) => {
                     // Try to recover by inserting null and continuing
-                    eprintln!("Warning: Error parsing array element: {:?}, using null", e);
+                    tracing::warn!(
+                        target: log_target::PARSER,
+                        "Error parsing array element: {:?}, using null", e
+                    );
                     array.push(Box::new(PDFObject::Null));
                     // Try to recover by finding the next token that looks like array end
                     // Skip ahead until we find ']' or some reasonable stopping point
@@ -359,9 +447,7 @@ impl Parser {
 
             // Check for EOF (error: unterminated dictionary)
             if let Some(Token::EOF) = &self.buf1 {
-                return Err(PDFError::Generic(
-                    "Unterminated dictionary (missing '>>')".to_string(),
-                ));
+                return Err(self.error_here("Unterminated dictionary (missing '>>')"));
             }
 
             // The key must be a name
@@ -386,9 +472,7 @@ impl Parser {
                     }
                 }
                 None => {
-                    return Err(PDFError::Generic(
-                        "Unexpected empty buffer in dictionary parsing".to_string(),
-                    ));
+                    return Err(self.error_here("Unexpected empty buffer in dictionary parsing"));
                 }
             };
 
@@ -396,9 +480,7 @@ impl Parser {
 
             // Check if we have a value (could be EOF or >>)
             if let Some(Token::EOF) = &self.buf1 {
-                return Err(PDFError::Generic(
-                    "Unterminated dictionary (EOF after key)".to_string(),
-                ));
+                return Err(self.error_here("Unterminated dictionary (EOF after key)"));
             }
 
             if let Some(Token::DictEnd) = &self.buf1 {
@@ -410,11 +492,13 @@ impl Parser {
             // Parse the value with error recovery
             let value = match self.get_object() {
                 Ok(val) => val,
+                Err(e @ PDFError::LimitExceeded { .. }) => return Err(e),
                 Err(e) => {
                     // Try to recover from parsing errors by inserting null
                     // and continuing with the next key-value pair
-                    eprintln!(
-                        "Warning: Error parsing dictionary value for key '{}': {:?}, using null",
+                    tracing::warn!(
+                        target: log_target::PARSER,
+                        "Error parsing dictionary value for key '{}': {:?}, using null",
                         key, e
                     );
                     PDFObject::Null
@@ -500,8 +584,9 @@ impl Parser {
         }
 
         // Now we're positioned right after the newline following "stream"
-        // This is where the stream data starts
-        let _stream_start_pos = self.lexer.get_position();
+        // This is where the stream data starts (the lexer has already read
+        // the byte at that position)
+        let stream_start_pos = self.lexer.get_position().saturating_sub(1);
 
         // Get the Length from the dictionary
         // For progressive loading, we need to check for DataMissing errors separately
@@ -517,8 +602,9 @@ impl Parser {
                         Ok(resolved) => match resolved {
                             PDFObject::Number(n) => Some(n as usize),
                             _ => {
-                                eprintln!(
-                                    "Warning: Resolved /Length is not a number, scanning for endstream"
+                                tracing::warn!(
+                                    target: log_target::PARSER,
+                                    "Resolved /Length is not a number, scanning for endstream"
                                 );
                                 None
                             }
@@ -529,8 +615,9 @@ impl Parser {
                             return Err(e);
                         }
                         Err(e) => {
-                            eprintln!(
-                                "Warning: Failed to resolve /Length reference {} {} R: {:?}, scanning for endstream",
+                            tracing::warn!(
+                                target: log_target::PARSER,
+                                "Failed to resolve /Length reference {} {} R: {:?}, scanning for endstream",
                                 ref_obj.num, ref_obj.generation, e
                             );
                             None
@@ -538,8 +625,9 @@ impl Parser {
                     }
                 } else {
                     // No resolver available, fall back to scanning
-                    eprintln!(
-                        "Warning: /Length is an indirect reference but no resolver available, scanning for endstream"
+                    tracing::warn!(
+                        target: log_target::PARSER,
+                        "/Length is an indirect reference but no resolver available, scanning for endstream"
                     );
                     None
                 }
@@ -558,52 +646,189 @@ impl Parser {
                     Err(_) => break, // EOF or error
                 }
             }
-            bytes
+
+            if self.endstream_follows()? {
+                bytes
+            } else if self.lenient {
+                self.lexer.set_position(stream_start_pos)?;
+                let bytes = self.scan_to_endstream()?;
+                self.repairs.push(format!(
+                    "Stream /Length {} is wrong, found endstream after {} bytes",
+                    len,
+                    bytes.len()
+                ));
+                bytes
+            } else {
+                return Err(PDFError::corrupted_pdf(format!(
+                    "Stream data of /Length {} is not followed by endstream",
+                    len
+                )));
+            }
         } else {
             // No length or indirect length - scan for 'endstream'
-            // This is a simplified implementation
-            let mut bytes = Vec::new();
-            let endstream_marker = b"endstream";
-            let mut match_pos = 0;
+            self.scan_to_endstream()?
+        };
 
-            loop {
-                match self.lexer.get_stream_byte() {
-                    Ok(b) => {
-                        bytes.push(b);
-
-                        // Check if we're matching 'endstream'
-                        if b == endstream_marker[match_pos] {
-                            match_pos += 1;
-                            if match_pos == endstream_marker.len() {
-                                // Found endstream - remove it from bytes
-                                bytes.truncate(bytes.len() - endstream_marker.len());
-                                // Also trim trailing whitespace before endstream
-                                while bytes.last() == Some(&b'\n')
-                                    || bytes.last() == Some(&b'\r')
-                                    || bytes.last() == Some(&b' ')
-                                {
-                                    bytes.pop();
-                                }
-                                break;
+        // Skip past 'endstream' if we haven't already
+        // We need to refill the token buffer
+        self.buf1 = Some(self.lexer.get_object()?);
+        self.buf2 = Some(self.lexer.get_object()?);
+
+        Ok(PDFObject::Stream { dict, data })
+    }
+
+    /// Reads stream data up to the next "endstream" keyword, leaving the
+    /// lexer on it. Whitespace before the keyword is dropped.
+    fn scan_to_endstream(&mut self) -> PDFResult<Vec<u8>> {
+        let mut bytes = Vec::new();
+        let endstream_marker = b"endstream";
+        let mut match_pos = 0;
+
+        loop {
+            match self.lexer.get_stream_byte() {
+                Ok(b) => {
+                    bytes.push(b);
+
+                    // Check if we're matching 'endstream'
+                    if b == endstream_marker[match_pos] {
+                        match_pos += 1;
+                        if match_pos == endstream_marker.len() {
+                            // Found endstream - remove it from bytes and go
+                            // back to its start (the position is one past the
+                            // byte the lexer holds, unless it's at the end)
+                            bytes.truncate(bytes.len() - endstream_marker.len());
+                            let after = self.lexer.get_position()
+                                - usize::from(self.lexer.peek_byte().is_some());
+                            self.lexer.set_position(after - endstream_marker.len())?;
+                            // Also trim trailing whitespace before endstream
+                            while bytes.last() == Some(&b'\n')
+                                || bytes.last() == Some(&b'\r')
+                                || bytes.last() == Some(&b' ')
+                            {
+                                bytes.pop();
                             }
-                        } else {
-                            match_pos = 0;
+                            return Ok(bytes);
                         }
+                    } else {
+                        match_pos = usize::from(b == endstream_marker[0]);
                     }
-                    Err(_) => {
-                        return Err(PDFError::Generic(
-                            "EOF while reading stream data".to_string(),
-                        ));
-                    }
                 }
+                Err(PDFError::UnexpectedEndOfStream) => {
+                    return Err(self.error_here("EOF while reading stream data"));
+                }
+                Err(e) => return Err(e),
             }
-            bytes
+        }
+    }
+
+    /// Returns true if "endstream", possibly after whitespace, comes next.
+    /// The lexer position is left unchanged.
+    fn endstream_follows(&mut self) -> PDFResult<bool> {
+        if self.lexer.peek_byte().is_none() {
+            return Ok(false);
+        }
+        let saved_pos = self.lexer.get_position() - 1;
+
+        while self
+            .lexer
+            .peek_byte()
+            .is_some_and(|b| matches!(b, b' ' | b'\t' | b'\r' | b'\n' | b'\x0C' | b'\0'))
+        {
+            self.lexer.get_stream_byte()?;
+        }
+        let mut follows = true;
+        for &expected in b"endstream" {
+            match self.lexer.peek_byte() {
+                Some(b) if b == expected => {
+                    self.lexer.get_stream_byte()?;
+                }
+                _ => {
+                    follows = false;
+                    break;
+                }
+            }
+        }
+
+        self.lexer.set_position(saved_pos)?;
+        Ok(follows)
+    }
+
+    /// Parses an inline image: BI <key-value pairs> ID <data> EI
+    ///
+    /// Called after the BI operator has been consumed. Returns the image as a
+    /// stream object with the abbreviated keys and names expanded (/W becomes
+    /// /Width, /CS /G becomes /ColorSpace /DeviceGray, ...). The parser is left
+    /// at the EI operator, so a content stream reads the image as the operand
+    /// of EI.
+    ///
+    /// The image data is everything after the single whitespace byte that
+    /// follows ID, up to the first "EI" that is surrounded by whitespace (and
+    /// past /Length bytes, when given).
+    ///
+    /// Based on PDF.js Parser.makeInlineImage()
+    fn parse_inline_image(&mut self) -> PDFResult<PDFObject> {
+        let mut dict = HashMap::new();
+        loop {
+            match &self.buf1 {
+                Some(Token::Command(cmd)) if cmd == "ID" => break,
+                Some(Token::EOF) | None => {
+                    return Err(self.error_here("Inline image dictionary not terminated by ID"));
+                }
+                _ => {}
+            }
+            let key = match self.get_object()? {
+                PDFObject::Name(key) => key,
+                other => {
+                    return Err(self
+                        .error_here(format!("Inline image key must be a name, got {:?}", other)));
+                }
+            };
+            if matches!(&self.buf1, Some(Token::Command(cmd)) if cmd == "ID") {
+                break;
+            }
+            let value = self.get_object()?;
+            let key = expand_inline_image_key(&key);
+            let value = if key == "Filter" || key == "ColorSpace" {
+                expand_inline_image_name(value)
+            } else {
+                value
+            };
+            dict.insert(key.to_string(), value);
+        }
+
+        // buf1 is ID and the lexer stands on the whitespace byte after it
+        let _ = self.lexer.get_stream_byte();
+
+        let min_length = match dict.get("Length") {
+            Some(PDFObject::Number(n)) if *n > 0.0 => *n as usize,
+            _ => 0,
         };
+        let is_space = |b: u8| matches!(b, b' ' | b'\t' | b'\r' | b'\n' | b'\x0C' | b'\0');
+
+        let mut data = Vec::new();
+        let mut terminated = false;
+        while let Ok(byte) = self.lexer.get_stream_byte() {
+            data.push(byte);
+            let n = data.len();
+            if n >= min_length + 3
+                && data[n - 2..] == *b"EI"
+                && is_space(data[n - 3])
+                && self.lexer.peek_byte().is_none_or(is_space)
+            {
+                data.truncate(n - 3);
+                terminated = true;
+                break;
+            }
+        }
 
-        // Skip past 'endstream' if we haven't already
-        // We need to refill the token buffer
-        self.buf1 = Some(self.lexer.get_object()?);
-        self.buf2 = Some(self.lexer.get_object()?);
+        if terminated {
+            self.buf1 = Some(Token::Command("EI".to_string()));
+            self.buf2 = Some(self.lexer.get_object()?);
+        } else {
+            tracing::warn!(target: log_target::PARSER, "Inline image data not terminated by EI");
+            self.buf1 = Some(Token::EOF);
+            self.buf2 = Some(Token::EOF);
+        }
 
         Ok(PDFObject::Stream { dict, data })
     }
@@ -615,6 +840,54 @@ impl Parser {
     }
 }
 
+/// Expands an abbreviated inline image key (PDF spec Table 91).
+pub(crate) fn expand_inline_image_key(key: &str) -> &str {
+    match key {
+        "BPC" => "BitsPerComponent",
+        "CS" => "ColorSpace",
+        "D" => "Decode",
+        "DP" => "DecodeParms",
+        "F" => "Filter",
+        "H" => "Height",
+        "W" => "Width",
+        "IM" => "ImageMask",
+        "I" => "Interpolate",
+        "L" => "Length",
+        _ => key,
+    }
+}
+
+/// Expands abbreviated color space and filter names (PDF spec Tables 92 and
+/// 93), including those inside arrays such as [/I /RGB 1 <...>].
+pub(crate) fn expand_inline_image_name(value: PDFObject) -> PDFObject {
+    match value {
+        PDFObject::Name(name) => {
+            let expanded = match name.as_str() {
+                "G" => "DeviceGray",
+                "RGB" => "DeviceRGB",
+                "CMYK" => "DeviceCMYK",
+                "I" => "Indexed",
+                "AHx" => "ASCIIHexDecode",
+                "A85" => "ASCII85Decode",
+                "LZW" => "LZWDecode",
+                "Fl" => "FlateDecode",
+                "RL" => "RunLengthDecode",
+                "CCF" => "CCITTFaxDecode",
+                "DCT" => "DCTDecode",
+                _ => return PDFObject::Name(name),
+            };
+            PDFObject::Name(expanded.to_string())
+        }
+        PDFObject::Array(items) => PDFObject::Array(
+            items
+                .into_iter()
+                .map(|item| Box::new(expand_inline_image_name(*item)))
+                .collect(),
+        ),
+        other => other,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -882,4 +1155,22 @@ mod tests {
         let result = parse_string("<< /Type /Font");
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_nesting_limit() {
+        let deep = format!("{}{}", "[".repeat(1000), "]".repeat(1000));
+        assert!(matches!(
+            parse_string(&deep),
+            Err(PDFError::LimitExceeded { max: 256, .. })
+        ));
+
+        let stream = Box::new(Stream::from_bytes(b"<< /A [[1]] >>".to_vec()));
+        let mut parser = Parser::new(Lexer::new(stream).unwrap()).unwrap();
+        parser.set_max_depth(2);
+        assert!(matches!(
+            parser.get_object(),
+            Err(PDFError::LimitExceeded { max: 2, .. })
+        ));
+        assert!(parse_string("<< /A [[1]] >>").is_ok());
+    }
 }
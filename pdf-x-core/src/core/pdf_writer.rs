@@ -1,9 +1,12 @@
-//! PDF writer for incremental updates.
+//! PDF writer for incremental updates and full document saves.
 //!
 //! This module handles serializing the delta layer as PDF incremental updates.
 //! Incremental updates append changes to the end of the original PDF file,
 //! preserving the original data and following the PDF specification (section 7.5.6).
 //!
+//! It can also serialize a complete set of objects as a new PDF file, which is
+//! used by `PDFDocument::save()` to rewrite a document from scratch.
+//!
 //! ## Incremental Update Format
 //!
 //! ```text
@@ -14,12 +17,22 @@
 //! %%EOF
 //! ```
 
+use super::decode::encode_flate;
 use super::delta::DeltaLayer;
 use super::error::{PDFError, PDFResult};
 use super::parser::PDFObject;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::io::Write;
 
+/// An entry in a cross-reference section being written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WrittenXRefEntry {
+    /// Object stored at a byte offset in the file
+    InUse { offset: u64, generation: u32 },
+    /// Free (deleted or unused) object number
+    Free { next_free: u32, generation: u32 },
+}
+
 /// PDF writer for incremental updates.
 ///
 /// This writer serializes delta layer changes as PDF incremental updates,
@@ -61,56 +74,104 @@ impl PDFWriter {
         total_object_count: u32,
         prev_xref_offset: usize,
     ) -> PDFResult<Vec<u8>> {
-        let mut buffer = Vec::new();
-
-        // Track object offsets for the new xref table
-        let mut object_offsets: HashMap<(u32, u32), u64> = HashMap::new();
-        let mut current_offset = original_size as u64;
-
-        // Collect all objects to write (modified + new)
-        let objects_to_write: Vec<_> = delta
-            .iter_modified()
-            .map(|(id, obj)| (*id, obj))
-            .chain(
-                delta
-                    .iter_new_objects()
-                    .map(|obj| ((obj.obj_num, obj.generation), obj)),
-            )
-            .collect();
+        Self::write_incremental_update_with_trailer(
+            delta,
+            original_size,
+            total_object_count,
+            prev_xref_offset,
+            &HashMap::new(),
+        )
+    }
 
-        // Write each object
-        for (obj_id, delta_obj) in &objects_to_write {
-            object_offsets.insert(*obj_id, current_offset);
+    /// Write an incremental update, carrying extra entries into the new trailer.
+    ///
+    /// A reader only looks at the newest trailer for document-level entries, so
+    /// /Root, /Info, /ID and /Encrypt from the original trailer must be repeated
+    /// in the update for the result to remain a valid PDF. /Size and /Prev are
+    /// always computed by the writer and are ignored if present in
+    /// `trailer_entries`.
+    ///
+    /// Deleted objects are written as free xref entries with their generation
+    /// number incremented, as required by the PDF specification.
+    ///
+    /// # Arguments
+    /// * `delta` - The delta layer to serialize
+    /// * `original_size` - The size of the original PDF file (for xref offset)
+    /// * `total_object_count` - Total number of objects in the original document
+    /// * `prev_xref_offset` - The offset of the previous xref table (from original trailer)
+    /// * `trailer_entries` - Additional trailer entries (e.g. /Root, /Info, /ID)
+    ///
+    /// # Returns
+    /// The incremental update as a byte vector that can be appended to the original PDF
+    pub fn write_incremental_update_with_trailer(
+        delta: &DeltaLayer,
+        original_size: usize,
+        total_object_count: u32,
+        prev_xref_offset: usize,
+        trailer_entries: &HashMap<String, PDFObject>,
+    ) -> PDFResult<Vec<u8>> {
+        let mut buffer = Vec::new();
 
-            // Write object header: "N G obj"
-            write!(buffer, "{} {} obj\n", obj_id.0, obj_id.1)
-                .map_err(|e| PDFError::Generic(format!("Failed to write object header: {}", e)))?;
+        // Collect all objects to write (new + modified), keyed by object number.
+        // Modified entries win over new ones so that edits made to a freshly
+        // added object are the ones that get saved.
+        let mut objects_to_write: BTreeMap<u32, (u32, &PDFObject)> = BTreeMap::new();
+        for obj in delta.iter_new_objects() {
+            objects_to_write.insert(obj.obj_num, (obj.generation, &obj.object));
+        }
+        for (id, obj) in delta.iter_modified() {
+            objects_to_write.insert(id.0, (id.1, &obj.object));
+        }
 
-            // Write object content
-            Self::write_object(&mut buffer, &delta_obj.object)?;
+        // Track entries for the new xref table
+        let mut entries: BTreeMap<u32, WrittenXRefEntry> = BTreeMap::new();
 
-            // Write object footer
-            buffer.extend_from_slice(b"endobj\n");
+        // Write each object
+        for (obj_num, (generation, object)) in &objects_to_write {
+            let offset = (original_size as u64) + (buffer.len() as u64);
+            entries.insert(
+                *obj_num,
+                WrittenXRefEntry::InUse {
+                    offset,
+                    generation: *generation,
+                },
+            );
+            Self::write_indirect_object(&mut buffer, *obj_num, *generation, object)?;
+        }
 
-            // Update offset (account for what we just wrote)
-            current_offset = (original_size as u64) + (buffer.len() as u64);
+        // Deleted objects become free entries
+        for (obj_num, generation) in delta.iter_deleted() {
+            if objects_to_write.contains_key(obj_num) {
+                continue;
+            }
+            entries.insert(
+                *obj_num,
+                WrittenXRefEntry::Free {
+                    next_free: 0,
+                    generation: generation.saturating_add(1),
+                },
+            );
         }
 
+        let size = entries
+            .keys()
+            .next_back()
+            .map(|max| max + 1)
+            .unwrap_or(0)
+            .max(total_object_count)
+            .max(delta.next_obj_num());
+
         // Write the new xref table
         let xref_start_offset = (original_size as u64) + (buffer.len() as u64);
-        Self::write_xref_table(
-            &mut buffer,
-            &object_offsets,
-            original_size,
-            prev_xref_offset,
-        )?;
+        Self::write_xref_table(&mut buffer, &entries)?;
 
         // Write the new trailer
         Self::write_trailer(
             &mut buffer,
             xref_start_offset,
-            total_object_count,
-            prev_xref_offset,
+            size,
+            Some(prev_xref_offset),
+            trailer_entries,
         )?;
 
         // Write EOF marker
@@ -119,8 +180,166 @@ impl PDFWriter {
         Ok(buffer)
     }
 
+    /// Write a complete PDF file containing the given objects.
+    ///
+    /// Objects keep their numbers, so references between them stay valid.
+    /// Object numbers that are not present are written as free entries. The
+    /// resulting file has a single classic xref table and no /Prev chain.
+    ///
+    /// # Arguments
+    /// * `version` - PDF version for the header (e.g. "1.7")
+    /// * `objects` - Map of object number to (generation, object)
+    /// * `trailer_entries` - Trailer entries such as /Root, /Info and /ID
+    ///
+    /// # Returns
+    /// The complete PDF file as a byte vector
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use pdf_x_core::core::PDFWriter;
+    /// # use pdf_x_core::core::parser::{PDFObject, Ref};
+    /// # use std::collections::{BTreeMap, HashMap};
+    /// let mut objects = BTreeMap::new();
+    /// let mut catalog = HashMap::new();
+    /// catalog.insert("Type".to_string(), PDFObject::Name("Catalog".to_string()));
+    /// objects.insert(1, (0, PDFObject::Dictionary(catalog)));
+    ///
+    /// let mut trailer = HashMap::new();
+    /// trailer.insert("Root".to_string(), PDFObject::Ref(Ref::new(1, 0)));
+    ///
+    /// let bytes = PDFWriter::write_document("1.7", &objects, &trailer)?;
+    /// # pdf_x_core::core::error::PDFResult::Ok(())
+    /// ```
+    pub fn write_document(
+        version: &str,
+        objects: &BTreeMap<u32, (u32, PDFObject)>,
+        trailer_entries: &HashMap<String, PDFObject>,
+    ) -> PDFResult<Vec<u8>> {
+        let mut buffer = Vec::new();
+
+        // Header, followed by a comment with high-bit bytes so that
+        // transfer tools treat the file as binary
+        writeln!(buffer, "%PDF-{}", version)
+            .map_err(|e| PDFError::Generic(format!("Failed to write header: {}", e)))?;
+        buffer.extend_from_slice(b"%\xE2\xE3\xCF\xD3\n");
+
+        let size = objects.keys().next_back().map(|max| max + 1).unwrap_or(1);
+
+        let mut offsets: HashMap<u32, (u64, u32)> = HashMap::new();
+        for (obj_num, (generation, object)) in objects {
+            if *obj_num == 0 {
+                // Object 0 is always the head of the free list
+                continue;
+            }
+            offsets.insert(*obj_num, (buffer.len() as u64, *generation));
+            Self::write_indirect_object(&mut buffer, *obj_num, *generation, object)?;
+        }
+
+        // Build a single subsection covering 0..size, linking free entries
+        // into the free list (object 0 is the head, the last one points back to 0)
+        let mut entries: BTreeMap<u32, WrittenXRefEntry> = BTreeMap::new();
+        let free_nums: Vec<u32> = (0..size).filter(|n| !offsets.contains_key(n)).collect();
+        for (i, obj_num) in free_nums.iter().enumerate() {
+            let next_free = free_nums.get(i + 1).copied().unwrap_or(0);
+            let generation = if *obj_num == 0 { 65535 } else { 0 };
+            entries.insert(
+                *obj_num,
+                WrittenXRefEntry::Free {
+                    next_free,
+                    generation,
+                },
+            );
+        }
+        for (obj_num, (offset, generation)) in offsets {
+            entries.insert(obj_num, WrittenXRefEntry::InUse { offset, generation });
+        }
+
+        let xref_start_offset = buffer.len() as u64;
+        Self::write_xref_table(&mut buffer, &entries)?;
+        Self::write_trailer(&mut buffer, xref_start_offset, size, None, trailer_entries)?;
+        buffer.extend_from_slice(b"%%EOF\n");
+
+        Ok(buffer)
+    }
+
+    /// Packs objects into an object stream (PDF 1.5+, section 7.5.7).
+    ///
+    /// The result is a Flate-compressed /Type /ObjStm stream to be written
+    /// as an indirect object of its own. Object `i` of `objects` is stored at
+    /// index `i`, which is what its compressed entry in a cross-reference
+    /// stream refers to.
+    ///
+    /// # Arguments
+    /// * `objects` - Object numbers and objects to store; their generation is
+    ///   implicitly 0
+    ///
+    /// # Returns
+    /// The object stream, or an error if one of the objects is a stream
+    /// (streams can't be stored in object streams)
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use pdf_x_core::core::{PDFObject, PDFWriter};
+    /// let name = PDFObject::Name("Value".to_string());
+    /// let number = PDFObject::Number(42.0);
+    /// let obj_stm = PDFWriter::write_object_stream(&[(4, &name), (5, &number)])?;
+    /// # pdf_x_core::core::error::PDFResult::Ok(())
+    /// ```
+    pub fn write_object_stream(objects: &[(u32, &PDFObject)]) -> PDFResult<PDFObject> {
+        let mut header = Vec::new();
+        let mut body = Vec::new();
+        for (obj_num, object) in objects {
+            if matches!(object, PDFObject::Stream { .. }) {
+                return Err(PDFError::Generic(format!(
+                    "Object {} is a stream and can't be stored in an object stream",
+                    obj_num
+                )));
+            }
+            write!(header, "{} {} ", obj_num, body.len())
+                .map_err(|e| PDFError::Generic(format!("Failed to write offset: {}", e)))?;
+            Self::write_object(&mut body, object)?;
+            body.push(b'\n');
+        }
+        header.push(b'\n');
+
+        let mut dict = HashMap::new();
+        dict.insert("Type".to_string(), PDFObject::Name("ObjStm".to_string()));
+        dict.insert("N".to_string(), PDFObject::Number(objects.len() as f64));
+        dict.insert("First".to_string(), PDFObject::Number(header.len() as f64));
+        dict.insert(
+            "Filter".to_string(),
+            PDFObject::Name("FlateDecode".to_string()),
+        );
+
+        header.extend_from_slice(&body);
+        Ok(PDFObject::Stream {
+            dict,
+            data: encode_flate(&header)?,
+        })
+    }
+
+    /// Write an indirect object: "N G obj ... endobj".
+    fn write_indirect_object<W: Write>(
+        buffer: &mut W,
+        obj_num: u32,
+        generation: u32,
+        object: &PDFObject,
+    ) -> PDFResult<()> {
+        // Write object header: "N G obj"
+        writeln!(buffer, "{} {} obj", obj_num, generation)
+            .map_err(|e| PDFError::Generic(format!("Failed to write object header: {}", e)))?;
+
+        // Write object content
+        Self::write_object(buffer, object)?;
+
+        // Write object footer
+        buffer
+            .write_all(b"\nendobj\n")
+            .map_err(|e| PDFError::Generic(format!("Failed to write object footer: {}", e)))
+    }
+
     /// Write a PDF object to the buffer.
-    fn write_object<W: Write>(buffer: &mut W, obj: &PDFObject) -> PDFResult<()> {
+    pub(crate) fn write_object<W: Write>(buffer: &mut W, obj: &PDFObject) -> PDFResult<()> {
         match obj {
             PDFObject::Null => {
                 buffer
@@ -216,8 +435,12 @@ impl PDFWriter {
                 })?;
             }
             PDFObject::Stream { dict, data } => {
-                // Write stream dictionary
-                Self::write_object(buffer, &PDFObject::Dictionary(dict.clone()))?;
+                // Write stream dictionary. /Length always reflects the data we
+                // write, since the original may be an indirect reference or
+                // stale after the data was replaced.
+                let mut dict = dict.clone();
+                dict.insert("Length".to_string(), PDFObject::Number(data.len() as f64));
+                Self::write_object(buffer, &PDFObject::Dictionary(dict))?;
 
                 buffer.write_all(b"\nstream\n").map_err(|e| {
                     PDFError::Generic(format!("Failed to write stream prefix: {}", e))
@@ -308,68 +531,50 @@ impl PDFWriter {
 
     /// Write a cross-reference table.
     ///
-    /// This writes a hybrid xref table that can reference both objects in the
-    /// original PDF and new/modified objects in the incremental update.
+    /// Consecutive object numbers are grouped into subsections, each with its
+    /// own header, as required when an update only touches a few objects.
     ///
-    /// Format per PDF 1.5+ specification (hybrid xref):
+    /// Format per PDF specification (section 7.5.4):
     /// ```text
     /// xref
     /// start_index count
     /// offset generation n  (for in-use objects)
-    /// 0000000000 65535 f   (for free objects)
+    /// next_free generation f   (for free objects)
     /// ```
     fn write_xref_table<W: Write>(
         buffer: &mut W,
-        object_offsets: &HashMap<(u32, u32), u64>,
-        _original_size: usize,
-        _prev_xref_offset: usize,
+        entries: &BTreeMap<u32, WrittenXRefEntry>,
     ) -> PDFResult<()> {
         buffer
             .write_all(b"xref\n")
             .map_err(|e| PDFError::Generic(format!("Failed to write xref header: {}", e)))?;
 
-        // Collect all object numbers we need to reference
-        let mut obj_nums: Vec<u32> = object_offsets.keys().map(|(num, _)| *num).collect();
-        obj_nums.sort();
-
         // Group consecutive objects into subsections
-        // PDF spec requires subsection headers: "start_index count"
-        let mut subsection_start: Option<u32> = None;
-        let mut subsection_objs: Vec<(u32, u64)> = Vec::new();
-
-        for obj_num in obj_nums {
-            let offset = object_offsets.get(&(obj_num, 0)).ok_or_else(|| {
-                PDFError::Generic(format!("Missing offset for object {}", obj_num))
-            })?;
-
-            match subsection_start {
-                None => {
-                    // Start a new subsection
-                    subsection_start = Some(obj_num);
-                    subsection_objs.push((obj_num, *offset));
-                }
-                Some(start) => {
-                    let last_obj = subsection_objs.last().map(|(n, _)| *n).unwrap_or(start);
-                    if obj_num == last_obj + 1 {
-                        // Consecutive - add to current subsection
-                        subsection_objs.push((obj_num, *offset));
-                    } else {
-                        // Non-consecutive - write current subsection and start new one
-                        Self::write_xref_subsection(buffer, start, subsection_objs.len() as u32)?;
-                        subsection_start = Some(obj_num);
-                        subsection_objs = vec![(obj_num, *offset)];
-                    }
+        let mut subsections: Vec<Vec<(u32, WrittenXRefEntry)>> = Vec::new();
+        for (obj_num, entry) in entries {
+            match subsections.last_mut() {
+                Some(current) if current.last().map(|(n, _)| n + 1) == Some(*obj_num) => {
+                    current.push((*obj_num, *entry));
                 }
+                _ => subsections.push(vec![(*obj_num, *entry)]),
             }
         }
 
-        // Write the last subsection
-        if let Some(start) = subsection_start {
-            Self::write_xref_subsection(buffer, start, subsection_objs.len() as u32)?;
-            for (_obj_num, offset) in subsection_objs {
-                // Format: offset (10 digits) + space + generation (5 digits) + space + type (n/f) + newline
-                write!(buffer, "{:010} {:05} n \n", offset, 0)
-                    .map_err(|e| PDFError::Generic(format!("Failed to write xref entry: {}", e)))?;
+        for subsection in subsections {
+            Self::write_xref_subsection(buffer, subsection[0].0, subsection.len() as u32)?;
+            for (_obj_num, entry) in subsection {
+                // Each entry is exactly 20 bytes: 10-digit field, space,
+                // 5-digit generation, space, type (n/f), space, newline
+                match entry {
+                    WrittenXRefEntry::InUse { offset, generation } => {
+                        writeln!(buffer, "{:010} {:05} n ", offset, generation)
+                    }
+                    WrittenXRefEntry::Free {
+                        next_free,
+                        generation,
+                    } => writeln!(buffer, "{:010} {:05} f ", next_free, generation),
+                }
+                .map_err(|e| PDFError::Generic(format!("Failed to write xref entry: {}", e)))?;
             }
         }
 
@@ -384,20 +589,21 @@ impl PDFWriter {
         start_index: u32,
         count: u32,
     ) -> PDFResult<()> {
-        write!(buffer, "{} {}\n", start_index, count).map_err(|e| {
+        writeln!(buffer, "{} {}", start_index, count).map_err(|e| {
             PDFError::Generic(format!("Failed to write xref subsection header: {}", e))
         })
     }
 
     /// Write the trailer dictionary.
     ///
-    /// The trailer points to the new xref table and includes a /Prev entry
-    /// pointing to the previous xref table (for incremental update chain).
+    /// The trailer points to the new xref table and, for incremental updates,
+    /// includes a /Prev entry pointing to the previous xref table.
     fn write_trailer<W: Write>(
         buffer: &mut W,
         xref_start_offset: u64,
-        total_object_count: u32,
-        prev_xref_offset: usize,
+        size: u32,
+        prev_xref_offset: Option<usize>,
+        trailer_entries: &HashMap<String, PDFObject>,
     ) -> PDFResult<()> {
         buffer
             .write_all(b"trailer\n")
@@ -406,13 +612,32 @@ impl PDFWriter {
             PDFError::Generic(format!("Failed to write trailer dict prefix: {}", e))
         })?;
 
-        // Size: total number of objects (original + new)
-        write!(buffer, "/Size {}", total_object_count)
+        // Size: one greater than the highest object number
+        write!(buffer, "/Size {}", size)
             .map_err(|e| PDFError::Generic(format!("Failed to write /Size: {}", e)))?;
 
         // Previous: offset of previous xref table
-        write!(buffer, " /Prev {}", prev_xref_offset)
-            .map_err(|e| PDFError::Generic(format!("Failed to write /Prev: {}", e)))?;
+        if let Some(prev) = prev_xref_offset {
+            write!(buffer, " /Prev {}", prev)
+                .map_err(|e| PDFError::Generic(format!("Failed to write /Prev: {}", e)))?;
+        }
+
+        // Remaining entries in a stable order
+        let mut keys: Vec<&String> = trailer_entries
+            .keys()
+            .filter(|k| k.as_str() != "Size" && k.as_str() != "Prev")
+            .collect();
+        keys.sort();
+        for key in keys {
+            buffer
+                .write_all(b" /")
+                .map_err(|e| PDFError::Generic(format!("Failed to write key prefix: {}", e)))?;
+            Self::write_escaped_name(buffer, key)?;
+            buffer
+                .write_all(b" ")
+                .map_err(|e| PDFError::Generic(format!("Failed to write separator: {}", e)))?;
+            Self::write_object(buffer, &trailer_entries[key])?;
+        }
 
         buffer.write_all(b">>\n").map_err(|e| {
             PDFError::Generic(format!("Failed to write trailer dict suffix: {}", e))
@@ -567,4 +792,170 @@ mod tests {
         // Verify /Size is in the trailer
         assert!(update_str.contains("/Size 100"));
     }
+
+    #[test]
+    fn test_incremental_update_multiple_subsections() {
+        let mut delta = DeltaLayer::new(20);
+        delta.modify_object(Ref::new(3, 0), PDFObject::Number(1.0));
+        delta.modify_object(Ref::new(4, 0), PDFObject::Number(2.0));
+        delta.modify_object(Ref::new(9, 2), PDFObject::Number(3.0));
+
+        let update = PDFWriter::write_incremental_update(&delta, 1000, 20, 900).unwrap();
+        let update_str = String::from_utf8_lossy(&update);
+
+        // Every subsection gets its own header and entries
+        assert!(update_str.contains("xref\n3 2\n"));
+        assert!(update_str.contains("\n9 1\n"));
+        assert!(update_str.contains(" 00002 n \n"));
+        assert_eq!(update_str.matches(" n \n").count(), 3);
+    }
+
+    #[test]
+    fn test_incremental_update_offsets_point_at_objects() {
+        let mut delta = DeltaLayer::new(5);
+        delta.modify_object(Ref::new(2, 0), PDFObject::Number(7.0));
+        let new_ref = delta.add_object(PDFObject::Boolean(true));
+
+        let original_size = 123;
+        let update = PDFWriter::write_incremental_update(&delta, original_size, 5, 100).unwrap();
+        let update_str = String::from_utf8_lossy(&update);
+
+        for (num, header) in [(2, "2 0 obj"), (new_ref.num, "5 0 obj")] {
+            let pos = update_str.find(header).unwrap() + original_size;
+            let line_start = update_str.find(&format!("{} 1\n", num)).unwrap();
+            let entry = &update_str[line_start..].lines().nth(1).unwrap();
+            assert_eq!(entry[..10].parse::<usize>().unwrap(), pos);
+        }
+    }
+
+    #[test]
+    fn test_incremental_update_deleted_object_is_free() {
+        let mut delta = DeltaLayer::new(10);
+        delta.delete_object(Ref::new(6, 0));
+
+        let update = PDFWriter::write_incremental_update(&delta, 500, 10, 400).unwrap();
+        let update_str = String::from_utf8_lossy(&update);
+
+        assert!(update_str.contains("6 1\n0000000000 00001 f \n"));
+    }
+
+    #[test]
+    fn test_incremental_update_trailer_entries() {
+        let delta = DeltaLayer::new(10);
+        let mut trailer = HashMap::new();
+        trailer.insert("Root".to_string(), PDFObject::Ref(Ref::new(1, 0)));
+        trailer.insert("Info".to_string(), PDFObject::Ref(Ref::new(2, 0)));
+        trailer.insert("Size".to_string(), PDFObject::Number(3.0));
+
+        let update =
+            PDFWriter::write_incremental_update_with_trailer(&delta, 500, 10, 400, &trailer)
+                .unwrap();
+        let update_str = String::from_utf8_lossy(&update);
+
+        assert!(update_str.contains("/Size 10 /Prev 400 /Info 2 0 R /Root 1 0 R>>"));
+    }
+
+    #[test]
+    fn test_write_stream_overrides_length() {
+        let mut dict = HashMap::new();
+        dict.insert("Length".to_string(), PDFObject::Ref(Ref::new(8, 0)));
+        let stream = PDFObject::Stream {
+            dict,
+            data: b"BT ET".to_vec(),
+        };
+
+        let mut buffer = Vec::new();
+        PDFWriter::write_object(&mut buffer, &stream).unwrap();
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "<</Length 5 >>\nstream\nBT ET\nendstream"
+        );
+    }
+
+    #[test]
+    fn test_object_stream_round_trip() {
+        use super::super::base_stream::BaseStream;
+        use super::super::stream::Stream;
+        use super::super::xref::{XRef, XRefEntry};
+
+        let mut catalog = HashMap::new();
+        catalog.insert("Type".to_string(), PDFObject::Name("Catalog".to_string()));
+        let catalog = PDFObject::Dictionary(catalog);
+        let title = PDFObject::String(b"Packed (title)".to_vec());
+        let obj_stm = PDFWriter::write_object_stream(&[(2, &catalog), (3, &title)]).unwrap();
+        assert!(PDFWriter::write_object_stream(&[(4, &obj_stm)]).is_err());
+
+        let mut bytes = b"%PDF-1.5\n".to_vec();
+        let obj_stm_offset = bytes.len();
+        PDFWriter::write_indirect_object(&mut bytes, 1, 0, &obj_stm).unwrap();
+
+        // Cross-reference stream: object 1 in the file, 2 and 3 in object 1
+        let xref_offset = bytes.len();
+        let mut entries = vec![0, 0, 0, 0xFF];
+        entries.extend_from_slice(&[1, (obj_stm_offset >> 8) as u8, obj_stm_offset as u8, 0]);
+        entries.extend_from_slice(&[2, 0, 1, 0, 2, 0, 1, 1]);
+        let mut dict = HashMap::new();
+        dict.insert("Type".to_string(), PDFObject::Name("XRef".to_string()));
+        dict.insert("Size".to_string(), PDFObject::Number(4.0));
+        dict.insert(
+            "W".to_string(),
+            PDFObject::Array(
+                [1.0, 2.0, 1.0]
+                    .iter()
+                    .map(|w| Box::new(PDFObject::Number(*w)))
+                    .collect(),
+            ),
+        );
+        dict.insert("Root".to_string(), PDFObject::Ref(Ref::new(2, 0)));
+        let xref_stream = PDFObject::Stream {
+            dict,
+            data: entries,
+        };
+        PDFWriter::write_indirect_object(&mut bytes, 4, 0, &xref_stream).unwrap();
+
+        let mut xref = XRef::new(Box::new(Stream::from_bytes(bytes)) as Box<dyn BaseStream>);
+        xref.set_stream_pos(xref_offset).unwrap();
+        xref.parse().unwrap();
+
+        let entries: Vec<u32> = xref.iter_entries().map(|(num, _)| num).collect();
+        assert_eq!(entries, [0, 1, 2, 3]);
+        assert!(matches!(
+            xref.get_entry(3),
+            Some(XRefEntry::Compressed {
+                obj_stream_num: 1,
+                index: 1
+            })
+        ));
+        assert_eq!(
+            xref.object_stream_objects(1).unwrap(),
+            [Ref::new(2, 0), Ref::new(3, 0)]
+        );
+        assert_eq!(*xref.fetch(3, 0).unwrap(), title);
+        assert_eq!(xref.catalog().unwrap(), catalog);
+    }
+
+    #[test]
+    fn test_write_document_structure() {
+        let mut objects = BTreeMap::new();
+        let mut catalog = HashMap::new();
+        catalog.insert("Type".to_string(), PDFObject::Name("Catalog".to_string()));
+        objects.insert(1, (0, PDFObject::Dictionary(catalog)));
+        objects.insert(3, (0, PDFObject::Number(42.0)));
+
+        let mut trailer = HashMap::new();
+        trailer.insert("Root".to_string(), PDFObject::Ref(Ref::new(1, 0)));
+
+        let bytes = PDFWriter::write_document("1.7", &objects, &trailer).unwrap();
+        let text = String::from_utf8_lossy(&bytes);
+
+        assert!(text.starts_with("%PDF-1.7\n"));
+        // Object 0 heads the free list and links to unused object 2
+        assert!(text.contains("xref\n0 4\n0000000002 65535 f \n"));
+        assert!(text.contains("0000000000 00000 f \n"));
+        assert!(text.contains("trailer\n<</Size 4 /Root 1 0 R>>"));
+        assert!(text.ends_with("%%EOF\n"));
+
+        let obj_offset = bytes.windows(7).position(|w| w == b"3 0 obj").unwrap();
+        assert!(text.contains(&format!("{:010} 00000 n \n", obj_offset)));
+    }
 }
@@ -0,0 +1,981 @@
+//! Redaction of page content.
+//!
+//! Drawing a black box over text only hides it: the text is still in the
+//! content stream and can be copied or extracted. Redaction here removes
+//! what lies under the given regions from the content itself:
+//!
+//! - Glyphs whose boxes intersect a region are dropped from their
+//!   text-showing operation. Each dropped glyph is replaced by a TJ
+//!   adjustment of the same width, so the text around it stays in place.
+//! - Inline images that intersect a region are removed.
+//! - Image XObjects that intersect a region have the covered pixels
+//!   blanked when their data can be edited (8 bits per component, device
+//!   color space, no image codec); other images are removed.
+//! - Form XObjects are redacted recursively.
+//!
+//! Vector graphics and shadings are left alone. Rewriting works on the raw
+//! content bytes, as for [`text_replace`](crate::core::text_replace): only
+//! the affected operations are replaced.
+
+use crate::core::content_stream::{GlyphSpacing, layout_glyphs};
+use crate::core::error::{PDFError, PDFResult};
+use crate::core::font::Font;
+use crate::core::lexer::{Lexer, Token};
+use crate::core::parser::PDFObject;
+use crate::core::stream::Stream;
+use crate::core::text_replace::{
+    Composite, Operand, TjElement, find_inline_image_end, fmt_num, literal_string, skip_whitespace,
+};
+use rustc_hash::FxHashMap;
+use std::collections::HashMap;
+
+/// The identity matrix.
+pub(crate) const IDENTITY: [f64; 6] = [1.0, 0.0, 0.0, 1.0, 0.0, 0.0];
+
+/// Glyph width assumed by [`redact_content`], which has no fonts, in
+/// thousandths of text space.
+const DEFAULT_GLYPH_WIDTH: f64 = 500.0;
+
+/// Options for redaction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RedactOptions {
+    /// RGB color of the boxes drawn over the regions, or `None` to draw
+    /// nothing
+    pub fill: Option<[f64; 3]>,
+}
+
+impl Default for RedactOptions {
+    /// Black boxes.
+    fn default() -> Self {
+        Self {
+            fill: Some([0.0, 0.0, 0.0]),
+        }
+    }
+}
+
+/// Result of a redaction.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RedactionReport {
+    /// Number of glyphs removed from text-showing operations
+    pub glyphs_removed: usize,
+
+    /// Number of image placements whose covered pixels were blanked
+    pub images_redacted: usize,
+
+    /// Number of image placements removed entirely
+    pub images_removed: usize,
+
+    /// Number of glyphs that were left alone because their position is
+    /// unknown: their font couldn't be loaded or has no glyph widths, or
+    /// such a glyph came before them in the same line of text. They may lie
+    /// under a region.
+    pub glyphs_unmeasured: usize,
+}
+
+impl RedactionReport {
+    /// Adds the counts of another report.
+    pub(crate) fn merge(&mut self, other: &RedactionReport) {
+        self.glyphs_removed += other.glyphs_removed;
+        self.images_redacted += other.images_redacted;
+        self.images_removed += other.images_removed;
+        self.glyphs_unmeasured += other.glyphs_unmeasured;
+    }
+}
+
+/// What the redactor needs to know about an XObject.
+#[derive(Debug, Clone)]
+pub(crate) enum XObjectKind {
+    /// An image; `editable` if its pixels can be blanked in place
+    Image { editable: bool },
+
+    /// A form with its /Matrix and /BBox
+    Form { matrix: [f64; 6], bbox: [f64; 4] },
+}
+
+/// An XObject placement that intersects a region.
+///
+/// The content stream only refers to XObjects by name; the caller edits the
+/// objects themselves.
+#[derive(Debug, Clone)]
+pub(crate) enum XObjectUse {
+    /// An editable image drawn with `ctm`, whose covered pixels must be
+    /// blanked
+    RedactImage { name: String, ctm: [f64; 6] },
+
+    /// An image whose Do was removed; its data must be dropped too
+    RemovedImage { name: String },
+
+    /// A form drawn with `ctm` (its /Matrix included)
+    Form { name: String, ctm: [f64; 6] },
+}
+
+/// Graphics state that affects where things are drawn.
+#[derive(Debug, Clone)]
+struct GraphicsState {
+    ctm: [f64; 6],
+    font_name: Option<String>,
+    font_size: f64,
+    char_spacing: f64,
+    word_spacing: f64,
+    horizontal_scaling: f64,
+    leading: f64,
+    rise: f64,
+}
+
+/// Removes content under a set of regions from content streams.
+///
+/// Like [`TextReplacer`](crate::core::text_replace::TextReplacer), the
+/// redactor keeps its state between calls so that the content streams of a
+/// page can be processed in order.
+pub(crate) struct ContentRedactor<'a> {
+    /// Regions to redact, normalized to [x1, y1, x2, y2] with x1 <= x2 and
+    /// y1 <= y2, in the default user space of the page
+    regions: Vec<[f64; 4]>,
+
+    /// Fonts by resource name
+    fonts: &'a FxHashMap<String, Font>,
+
+    /// XObjects by resource name
+    xobjects: &'a HashMap<String, XObjectKind>,
+
+    /// Current graphics state and the q/Q stack
+    state: GraphicsState,
+    stack: Vec<GraphicsState>,
+
+    /// Text matrix and text line matrix
+    text_matrix: [f64; 6],
+    line_matrix: [f64; 6],
+
+    /// False once a glyph that couldn't be measured has moved the text
+    /// matrix by an unknown amount, until the next line starts
+    position_known: bool,
+
+    /// Glyph width to assume for fonts without glyph widths, in thousandths
+    /// of text space
+    assumed_glyph_width: Option<f64>,
+
+    /// XObject placements that intersect a region
+    uses: Vec<XObjectUse>,
+
+    /// Counts of removed content
+    report: RedactionReport,
+}
+
+impl<'a> ContentRedactor<'a> {
+    /// Creates a redactor.
+    ///
+    /// # Arguments
+    /// * `regions` - Rectangles [x1, y1, x2, y2] in default user space
+    /// * `fonts` - Fonts of the content's resources by name
+    /// * `xobjects` - XObjects of the content's resources by name
+    /// * `ctm` - The transformation in effect when the content starts
+    pub(crate) fn new(
+        regions: &[[f64; 4]],
+        fonts: &'a FxHashMap<String, Font>,
+        xobjects: &'a HashMap<String, XObjectKind>,
+        ctm: [f64; 6],
+    ) -> Self {
+        Self {
+            regions: regions.iter().map(normalize_rect).collect(),
+            fonts,
+            xobjects,
+            state: GraphicsState {
+                ctm,
+                font_name: None,
+                font_size: 0.0,
+                char_spacing: 0.0,
+                word_spacing: 0.0,
+                horizontal_scaling: 1.0,
+                leading: 0.0,
+                rise: 0.0,
+            },
+            stack: Vec::new(),
+            text_matrix: IDENTITY,
+            line_matrix: IDENTITY,
+            position_known: true,
+            assumed_glyph_width: None,
+            uses: Vec::new(),
+            report: RedactionReport::default(),
+        }
+    }
+
+    /// Assumes a glyph width for text whose font is missing or has no glyph
+    /// widths, instead of leaving it alone and counting it as unmeasured.
+    ///
+    /// # Arguments
+    /// * `width` - Glyph width in thousandths of text space
+    pub(crate) fn with_assumed_glyph_width(mut self, width: f64) -> Self {
+        self.assumed_glyph_width = Some(width);
+        self
+    }
+
+    /// Returns the counts accumulated so far.
+    pub(crate) fn report(&self) -> &RedactionReport {
+        &self.report
+    }
+
+    /// Returns the XObject placements found so far that intersect a region.
+    pub(crate) fn xobject_uses(&self) -> &[XObjectUse] {
+        &self.uses
+    }
+
+    /// Redacts one decoded content stream.
+    ///
+    /// # Arguments
+    /// * `data` - The decoded content stream
+    ///
+    /// # Returns
+    /// The rewritten content, or `None` if nothing was removed
+    pub(crate) fn redact_stream(&mut self, data: &[u8]) -> PDFResult<Option<Vec<u8>>> {
+        // See TextReplacer::replace_in_stream for the lookahead byte
+        let mut padded = data.to_vec();
+        padded.push(b'\n');
+        let mut lexer = Lexer::new(Box::new(Stream::from_bytes(padded.clone())))?;
+
+        let mut edits: Vec<(usize, usize, Vec<u8>)> = Vec::new();
+        let mut operands: Vec<(Operand, usize)> = Vec::new();
+        let mut composite: Option<Composite> = None;
+        let mut prev_end = 0;
+        let mut inline_image_start = 0;
+
+        loop {
+            let start = skip_whitespace(&padded, prev_end);
+            let token = lexer.get_object()?;
+            let end = lexer.get_position().saturating_sub(1);
+            prev_end = end;
+
+            if let Some(current) = composite.as_mut() {
+                match token {
+                    Token::ArrayStart | Token::DictStart => {
+                        current.depth += 1;
+                        current.is_tj_array = false;
+                    }
+                    Token::ArrayEnd | Token::DictEnd => {
+                        current.depth -= 1;
+                        if current.depth == 0 {
+                            let done = composite.take().unwrap();
+                            let operand = if done.is_tj_array {
+                                Operand::Array(done.elements)
+                            } else {
+                                Operand::Other
+                            };
+                            operands.push((operand, done.start));
+                        }
+                    }
+                    Token::String(bytes) | Token::HexString(bytes) if current.depth == 1 => {
+                        current.elements.push(TjElement::Text(bytes));
+                    }
+                    Token::Number(n) if current.depth == 1 => {
+                        current.elements.push(TjElement::Adjust(n));
+                    }
+                    Token::EOF => break,
+                    _ => current.is_tj_array = false,
+                }
+                continue;
+            }
+
+            match token {
+                Token::EOF => break,
+                Token::ArrayStart | Token::DictStart => {
+                    composite = Some(Composite {
+                        start,
+                        depth: 1,
+                        elements: Vec::new(),
+                        is_tj_array: token == Token::ArrayStart,
+                    });
+                }
+                Token::Number(n) => operands.push((Operand::Number(n), start)),
+                Token::Name(name) => operands.push((Operand::Name(name), start)),
+                Token::String(bytes) | Token::HexString(bytes) => {
+                    operands.push((Operand::String(bytes), start))
+                }
+                Token::Command(cmd) => {
+                    if cmd == "BI" {
+                        inline_image_start = start;
+                    } else if cmd == "ID" {
+                        prev_end = find_inline_image_end(&padded, end);
+                        lexer.set_position(prev_end)?;
+                        if self.intersects_unit_square(&self.state.ctm) {
+                            self.report.images_removed += 1;
+                            edits.push((inline_image_start, prev_end, Vec::new()));
+                        }
+                    } else if let Some(edit) = self.process_operator(&cmd, &operands, end) {
+                        edits.push(edit);
+                    }
+                    operands.clear();
+                }
+                _ => operands.push((Operand::Other, start)),
+            }
+        }
+
+        if edits.is_empty() {
+            return Ok(None);
+        }
+
+        let mut output = Vec::with_capacity(data.len());
+        let mut pos = 0;
+        for (start, end, bytes) in edits {
+            output.extend_from_slice(&data[pos..start]);
+            output.extend_from_slice(&bytes);
+            pos = end.min(data.len());
+        }
+        output.extend_from_slice(&data[pos..]);
+        Ok(Some(output))
+    }
+
+    /// Updates state for an operator and rewrites it if it draws something
+    /// under a region.
+    ///
+    /// Returns the (start, end, bytes) edit for a rewritten operation.
+    fn process_operator(
+        &mut self,
+        cmd: &str,
+        operands: &[(Operand, usize)],
+        end: usize,
+    ) -> Option<(usize, usize, Vec<u8>)> {
+        let number = |i: usize| match operands.get(i) {
+            Some((Operand::Number(n), _)) => Some(*n),
+            _ => None,
+        };
+        let matrix = || -> Option<[f64; 6]> {
+            let mut m = [0.0; 6];
+            for (i, value) in m.iter_mut().enumerate() {
+                *value = number(i)?;
+            }
+            Some(m)
+        };
+
+        match cmd {
+            "q" => {
+                self.stack.push(self.state.clone());
+                None
+            }
+            "Q" => {
+                if let Some(state) = self.stack.pop() {
+                    self.state = state;
+                }
+                None
+            }
+            "cm" => {
+                if let Some(m) = matrix() {
+                    self.state.ctm = multiply(&m, &self.state.ctm);
+                }
+                None
+            }
+            "BT" => {
+                self.text_matrix = IDENTITY;
+                self.line_matrix = IDENTITY;
+                self.position_known = true;
+                None
+            }
+            "Tf" => {
+                if let Some((Operand::Name(name), _)) = operands.first() {
+                    self.state.font_name = Some(name.clone());
+                }
+                if let Some(size) = number(1) {
+                    self.state.font_size = size;
+                }
+                None
+            }
+            "Tc" => {
+                self.state.char_spacing = number(0).unwrap_or(self.state.char_spacing);
+                None
+            }
+            "Tw" => {
+                self.state.word_spacing = number(0).unwrap_or(self.state.word_spacing);
+                None
+            }
+            "Tz" => {
+                if let Some(scale) = number(0) {
+                    self.state.horizontal_scaling = scale / 100.0;
+                }
+                None
+            }
+            "TL" => {
+                self.state.leading = number(0).unwrap_or(self.state.leading);
+                None
+            }
+            "Ts" => {
+                self.state.rise = number(0).unwrap_or(self.state.rise);
+                None
+            }
+            "Td" | "TD" => {
+                if let (Some(tx), Some(ty)) = (number(0), number(1)) {
+                    if cmd == "TD" {
+                        self.state.leading = -ty;
+                    }
+                    self.next_line(tx, ty);
+                }
+                None
+            }
+            "Tm" => {
+                if let Some(m) = matrix() {
+                    self.text_matrix = m;
+                    self.line_matrix = m;
+                    self.position_known = true;
+                }
+                None
+            }
+            "T*" => {
+                self.next_line(0.0, -self.state.leading);
+                None
+            }
+            "Tj" | "'" => match operands.last() {
+                Some((Operand::String(bytes), start)) => {
+                    if cmd == "'" {
+                        self.next_line(0.0, -self.state.leading);
+                    }
+                    let elements = [TjElement::Text(bytes.clone())];
+                    let prefix = if cmd == "'" { "T* " } else { "" };
+                    self.show(&elements, prefix)
+                        .map(|bytes| (*start, end, bytes))
+                }
+                _ => None,
+            },
+            "\"" => match (number(0), number(1), operands.get(2)) {
+                (Some(aw), Some(ac), Some((Operand::String(bytes), _))) => {
+                    self.state.word_spacing = aw;
+                    self.state.char_spacing = ac;
+                    self.next_line(0.0, -self.state.leading);
+                    let elements = [TjElement::Text(bytes.clone())];
+                    let prefix = format!("{} Tw {} Tc T* ", fmt_num(aw), fmt_num(ac));
+                    let start = operands[0].1;
+                    self.show(&elements, &prefix)
+                        .map(|bytes| (start, end, bytes))
+                }
+                _ => None,
+            },
+            "TJ" => match operands.last() {
+                Some((Operand::Array(elements), start)) => {
+                    self.show(elements, "").map(|bytes| (*start, end, bytes))
+                }
+                _ => None,
+            },
+            "Do" => match operands.last() {
+                Some((Operand::Name(name), start)) => self.draw_xobject(name, *start, end),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Moves to the start of the next line, offset by (tx, ty).
+    fn next_line(&mut self, tx: f64, ty: f64) {
+        self.line_matrix = multiply(&[1.0, 0.0, 0.0, 1.0, tx, ty], &self.line_matrix);
+        self.text_matrix = self.line_matrix;
+        // The line matrix isn't moved by glyphs
+        self.position_known = true;
+    }
+
+    /// Advances the text matrix through a text-showing operation and
+    /// rebuilds the operation without the glyphs under a region.
+    ///
+    /// # Arguments
+    /// * `elements` - The strings and adjustments shown by the operation
+    /// * `prefix` - Operators to emit before the new text (for ' and ")
+    ///
+    /// # Returns
+    /// The new operation, or `None` if no glyph was removed
+    fn show(&mut self, elements: &[TjElement], prefix: &str) -> Option<Vec<u8>> {
+        let font = self
+            .state
+            .font_name
+            .as_ref()
+            .and_then(|name| self.fonts.get(name));
+        let spacing = GlyphSpacing {
+            font_size: self.state.font_size,
+            char_spacing: self.state.char_spacing,
+            word_spacing: self.state.word_spacing,
+            horizontal_scaling: self.state.horizontal_scaling,
+            rise: self.state.rise,
+        };
+        let vertical = font.is_some_and(|font| font.is_vertical());
+
+        let mut output: Vec<TjElement> = Vec::new();
+        let mut removed = 0;
+        for element in elements {
+            let bytes = match element {
+                TjElement::Text(bytes) => bytes,
+                TjElement::Adjust(n) => {
+                    let shift = -n / 1000.0 * spacing.font_size;
+                    if vertical {
+                        self.advance(0.0, shift);
+                    } else {
+                        self.advance(shift * spacing.horizontal_scaling, 0.0);
+                    }
+                    push_adjust(&mut output, *n);
+                    continue;
+                }
+            };
+
+            let glyphs = layout_glyphs(font, &spacing, bytes, self.assumed_glyph_width)
+                .filter(|_| self.position_known);
+            let Some(glyphs) = glyphs else {
+                // Neither these glyphs nor the ones after them on the line
+                // can be placed
+                self.position_known = false;
+                self.report.glyphs_unmeasured += count_codes(font, bytes);
+                push_text(&mut output, bytes);
+                continue;
+            };
+
+            let mut i = 0;
+            for glyph in glyphs {
+                let trm = multiply(&self.text_matrix, &self.state.ctm);
+                let glyph_box = transform_rect(&trm, &glyph.rect);
+                if spacing.font_size != 0.0 && self.intersects(&glyph_box) {
+                    removed += 1;
+                    push_adjust(&mut output, glyph.adjustment);
+                } else {
+                    push_text(&mut output, &bytes[i..i + glyph.len]);
+                }
+                self.advance(glyph.advance.0, glyph.advance.1);
+                i += glyph.len;
+            }
+        }
+
+        if removed == 0 {
+            return None;
+        }
+        self.report.glyphs_removed += removed;
+
+        let mut out = String::from(prefix);
+        out.push('[');
+        for (i, element) in output.iter().enumerate() {
+            if i > 0 {
+                out.push(' ');
+            }
+            match element {
+                TjElement::Text(bytes) => out.push_str(&literal_string(bytes)),
+                TjElement::Adjust(n) => out.push_str(&fmt_num(*n)),
+            }
+        }
+        out.push_str("] TJ");
+        Some(out.into_bytes())
+    }
+
+    /// Moves the text matrix by (tx, ty) in text space.
+    fn advance(&mut self, tx: f64, ty: f64) {
+        self.text_matrix = multiply(&[1.0, 0.0, 0.0, 1.0, tx, ty], &self.text_matrix);
+    }
+
+    /// Handles a Do operator.
+    ///
+    /// Returns the edit that removes the operator if the XObject is an
+    /// image that must be removed.
+    fn draw_xobject(
+        &mut self,
+        name: &str,
+        start: usize,
+        end: usize,
+    ) -> Option<(usize, usize, Vec<u8>)> {
+        let ctm = self.state.ctm;
+        match self.xobjects.get(name)? {
+            XObjectKind::Image { editable } => {
+                if !self.intersects_unit_square(&ctm) {
+                    return None;
+                }
+                if *editable {
+                    self.report.images_redacted += 1;
+                    self.uses.push(XObjectUse::RedactImage {
+                        name: name.to_string(),
+                        ctm,
+                    });
+                    None
+                } else {
+                    self.report.images_removed += 1;
+                    self.uses.push(XObjectUse::RemovedImage {
+                        name: name.to_string(),
+                    });
+                    Some((start, end, Vec::new()))
+                }
+            }
+            XObjectKind::Form { matrix, bbox } => {
+                let form_ctm = multiply(matrix, &ctm);
+                if self.intersects(&transform_rect(&form_ctm, bbox)) {
+                    self.uses.push(XObjectUse::Form {
+                        name: name.to_string(),
+                        ctm: form_ctm,
+                    });
+                }
+                None
+            }
+        }
+    }
+
+    /// Returns true if a rectangle in device space intersects a region.
+    fn intersects(&self, rect: &[f64; 4]) -> bool {
+        self.regions.iter().any(|region| {
+            rect[0] < region[2] && region[0] < rect[2] && rect[1] < region[3] && region[1] < rect[3]
+        })
+    }
+
+    /// Returns true if the unit square drawn with `ctm` (an image)
+    /// intersects a region.
+    fn intersects_unit_square(&self, ctm: &[f64; 6]) -> bool {
+        self.intersects(&transform_rect(ctm, &[0.0, 0.0, 1.0, 1.0]))
+    }
+}
+
+/// Appends shown bytes, merging them with a preceding string.
+fn push_text(output: &mut Vec<TjElement>, bytes: &[u8]) {
+    match output.last_mut() {
+        Some(TjElement::Text(text)) => text.extend_from_slice(bytes),
+        _ => output.push(TjElement::Text(bytes.to_vec())),
+    }
+}
+
+/// Counts the character codes of a string shown with `font`.
+fn count_codes(font: Option<&Font>, bytes: &[u8]) -> usize {
+    let Some(font) = font else {
+        return bytes.len();
+    };
+    let mut count = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        i += font.read_char_code(bytes, i).1.max(1);
+        count += 1;
+    }
+    count
+}
+
+/// Appends a TJ adjustment, merging it with a preceding one.
+fn push_adjust(output: &mut Vec<TjElement>, n: f64) {
+    match output.last_mut() {
+        Some(TjElement::Adjust(previous)) => *previous += n,
+        _ => output.push(TjElement::Adjust(n)),
+    }
+}
+
+/// Multiplies two matrices: the result applies `a`, then `b`.
+pub(crate) fn multiply(a: &[f64; 6], b: &[f64; 6]) -> [f64; 6] {
+    [
+        a[0] * b[0] + a[1] * b[2],
+        a[0] * b[1] + a[1] * b[3],
+        a[2] * b[0] + a[3] * b[2],
+        a[2] * b[1] + a[3] * b[3],
+        a[4] * b[0] + a[5] * b[2] + b[4],
+        a[4] * b[1] + a[5] * b[3] + b[5],
+    ]
+}
+
+/// Inverts a matrix, or returns `None` if it is singular.
+fn invert(m: &[f64; 6]) -> Option<[f64; 6]> {
+    let det = m[0] * m[3] - m[1] * m[2];
+    if det.abs() < f64::EPSILON {
+        return None;
+    }
+    Some([
+        m[3] / det,
+        -m[1] / det,
+        -m[2] / det,
+        m[0] / det,
+        (m[2] * m[5] - m[3] * m[4]) / det,
+        (m[1] * m[4] - m[0] * m[5]) / det,
+    ])
+}
+
+/// Returns the bounding box of a rectangle after transformation.
+fn transform_rect(m: &[f64; 6], rect: &[f64; 4]) -> [f64; 4] {
+    let corners = [
+        (rect[0], rect[1]),
+        (rect[2], rect[1]),
+        (rect[2], rect[3]),
+        (rect[0], rect[3]),
+    ];
+    let mut bbox = [f64::MAX, f64::MAX, f64::MIN, f64::MIN];
+    for (x, y) in corners {
+        let tx = m[0] * x + m[2] * y + m[4];
+        let ty = m[1] * x + m[3] * y + m[5];
+        bbox = [
+            bbox[0].min(tx),
+            bbox[1].min(ty),
+            bbox[2].max(tx),
+            bbox[3].max(ty),
+        ];
+    }
+    bbox
+}
+
+/// Orders a rectangle's coordinates so that x1 <= x2 and y1 <= y2.
+fn normalize_rect(rect: &[f64; 4]) -> [f64; 4] {
+    [
+        rect[0].min(rect[2]),
+        rect[1].min(rect[3]),
+        rect[0].max(rect[2]),
+        rect[1].max(rect[3]),
+    ]
+}
+
+/// Blanks the pixels of an image that lie under the regions.
+///
+/// The image must have 8 bits per component. Pixels are set to black
+/// (zero, or full black for CMYK).
+///
+/// # Arguments
+/// * `pixels` - The decoded image data, row by row from the top
+/// * `width`, `height` - The image size in pixels
+/// * `components` - Color components per pixel (1, 3 or 4)
+/// * `ctm` - The transformation the image is drawn with
+/// * `regions` - Rectangles [x1, y1, x2, y2] in default user space
+///
+/// # Returns
+/// Whether any pixel was changed
+pub(crate) fn blank_image_pixels(
+    pixels: &mut [u8],
+    width: usize,
+    height: usize,
+    components: usize,
+    ctm: &[f64; 6],
+    regions: &[[f64; 4]],
+) -> PDFResult<bool> {
+    let size = width
+        .checked_mul(components)
+        .and_then(|stride| stride.checked_mul(height));
+    match size {
+        Some(size) if pixels.len() >= size => {}
+        _ => {
+            return Err(PDFError::Generic(format!(
+                "Image data is {} bytes, too short for {}x{} pixels of {} components",
+                pixels.len(),
+                width,
+                height,
+                components
+            )));
+        }
+    }
+    let stride = width * components;
+    let inverse = match invert(ctm) {
+        Some(inverse) => inverse,
+        None => return Ok(false),
+    };
+    let black: &[u8] = if components == 4 {
+        &[0, 0, 0, 255]
+    } else {
+        &[0, 0, 0]
+    };
+
+    let mut changed = false;
+    for region in regions {
+        // The region in image space, where the image is the unit square
+        // with its first row at the top
+        let [u0, v0, u1, v1] = transform_rect(&inverse, &normalize_rect(region));
+        let col_start = (u0.max(0.0) * width as f64).floor() as usize;
+        let col_end = ((u1.min(1.0) * width as f64).ceil().max(0.0) as usize).min(width);
+        let row_start = ((1.0 - v1).max(0.0) * height as f64).floor() as usize;
+        let row_end = (((1.0 - v0).min(1.0) * height as f64).ceil().max(0.0) as usize).min(height);
+
+        for row in row_start..row_end {
+            for col in col_start..col_end {
+                let offset = row * stride + col * components;
+                pixels[offset..offset + components].copy_from_slice(&black[..components]);
+                changed = true;
+            }
+        }
+    }
+    Ok(changed)
+}
+
+/// Returns the width, height and components per pixel of an image whose
+/// pixels can be blanked in place.
+///
+/// Only 8-bit images in DeviceGray, DeviceRGB or DeviceCMYK qualify.
+pub(crate) fn image_layout(dict: &HashMap<String, PDFObject>) -> Option<(usize, usize, usize)> {
+    let number = |key: &str| dict.get(key).and_then(|v| v.as_number());
+    if matches!(dict.get("ImageMask"), Some(PDFObject::Boolean(true)))
+        || number("BitsPerComponent") != Some(8.0)
+    {
+        return None;
+    }
+    let components = match dict.get("ColorSpace") {
+        Some(PDFObject::Name(name)) => match name.as_str() {
+            "DeviceGray" | "G" => 1,
+            "DeviceRGB" | "RGB" => 3,
+            "DeviceCMYK" | "CMYK" => 4,
+            _ => return None,
+        },
+        _ => return None,
+    };
+    let width = number("Width").filter(|w| *w >= 1.0)? as usize;
+    let height = number("Height").filter(|h| *h >= 1.0)? as usize;
+    Some((width, height, components))
+}
+
+/// Builds a one-pixel black image to stand in for a removed one.
+///
+/// The image object is replaced rather than just no longer drawn, so that
+/// its data doesn't stay in the file.
+pub(crate) fn blank_image() -> PDFObject {
+    let mut dict = HashMap::new();
+    dict.insert("Type".to_string(), PDFObject::Name("XObject".to_string()));
+    dict.insert("Subtype".to_string(), PDFObject::Name("Image".to_string()));
+    dict.insert("Width".to_string(), PDFObject::Number(1.0));
+    dict.insert("Height".to_string(), PDFObject::Number(1.0));
+    dict.insert(
+        "ColorSpace".to_string(),
+        PDFObject::Name("DeviceGray".to_string()),
+    );
+    dict.insert("BitsPerComponent".to_string(), PDFObject::Number(8.0));
+    PDFObject::Stream {
+        dict,
+        data: vec![0],
+    }
+}
+
+/// Builds the content that draws filled boxes over the regions.
+///
+/// The boxes are wrapped in q/Q so they don't affect later content.
+pub(crate) fn fill_boxes_content(regions: &[[f64; 4]], color: &[f64; 3]) -> Vec<u8> {
+    let mut out = format!(
+        "q {} {} {} rg",
+        fmt_num(color[0]),
+        fmt_num(color[1]),
+        fmt_num(color[2])
+    );
+    for region in regions {
+        let [x1, y1, x2, y2] = normalize_rect(region);
+        out.push_str(&format!(
+            " {} {} {} {} re",
+            fmt_num(x1),
+            fmt_num(y1),
+            fmt_num(x2 - x1),
+            fmt_num(y2 - y1)
+        ));
+    }
+    out.push_str(" f Q\n");
+    out.into_bytes()
+}
+
+/// Redacts one decoded content stream, without fonts or XObjects.
+///
+/// Without fonts, glyph positions are only estimated: every glyph is taken
+/// to be half an em wide, so glyphs near the edges of a region may be kept
+/// or removed wrongly. XObjects are left alone. Mostly useful for content
+/// whose resources are unavailable; use
+/// [`PDFDocument::redact_page`](crate::core::PDFDocument::redact_page) to
+/// redact with the actual glyph widths.
+///
+/// # Arguments
+/// * `data` - The decoded content stream
+/// * `regions` - Rectangles [x1, y1, x2, y2] in the content's user space
+///
+/// # Returns
+/// The rewritten content (unchanged if nothing was removed) and the counts
+pub fn redact_content(data: &[u8], regions: &[[f64; 4]]) -> PDFResult<(Vec<u8>, RedactionReport)> {
+    let fonts = FxHashMap::default();
+    let xobjects = HashMap::new();
+    let mut redactor = ContentRedactor::new(regions, &fonts, &xobjects, IDENTITY)
+        .with_assumed_glyph_width(DEFAULT_GLYPH_WIDTH);
+    let output = redactor
+        .redact_stream(data)
+        .map_err(|e| PDFError::Generic(format!("Failed to rewrite content stream: {}", e)))?
+        .unwrap_or_else(|| data.to_vec());
+    Ok((output, redactor.report().clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn redact(content: &str, region: [f64; 4]) -> (String, RedactionReport) {
+        let (data, report) = redact_content(content.as_bytes(), &[region]).unwrap();
+        (String::from_utf8_lossy(&data).into_owned(), report)
+    }
+
+    #[test]
+    fn test_redact_splits_text() {
+        // Glyphs are 6 units wide at 12pt: "SECRET" spans x = 112..148
+        let (content, report) = redact(
+            "BT /F1 12 Tf 100 700 Td (A SECRET B) Tj ET",
+            [112.5, 690.0, 147.5, 720.0],
+        );
+        assert_eq!(content, "BT /F1 12 Tf 100 700 Td [(A ) -3000 ( B)] TJ ET");
+        assert_eq!(report.glyphs_removed, 6);
+    }
+
+    #[test]
+    fn test_redact_tj_array_keeps_adjustments() {
+        let (content, report) = redact(
+            "BT /F1 10 Tf 2 Tc 0 0 Td [(ab) -100 (cd)] TJ ET",
+            [0.0, -5.0, 6.0, 10.0],
+        );
+        // "a" is removed; its advance (500 + 2 * 1000 / 10) becomes an adjustment
+        assert_eq!(
+            content,
+            "BT /F1 10 Tf 2 Tc 0 0 Td [-700 (b) -100 (cd)] TJ ET"
+        );
+        assert_eq!(report.glyphs_removed, 1);
+    }
+
+    #[test]
+    fn test_redact_follows_ctm_and_text_state() {
+        // The same run drawn twice; only the copy moved by cm is hit
+        let content =
+            "BT /F1 10 Tf 0 0 Td (x) Tj ET q 1 0 0 1 200 200 cm BT /F1 10 Tf 0 0 Td (x) ' ET Q";
+        let (content, report) = redact(&format!("10 TL {}", content), [190.0, 180.0, 220.0, 200.0]);
+        assert!(content.contains("BT /F1 10 Tf 0 0 Td (x) Tj ET"));
+        assert!(content.ends_with("T* [-500] TJ ET Q"));
+        assert_eq!(report.glyphs_removed, 1);
+    }
+
+    #[test]
+    fn test_redact_removes_inline_images() {
+        let original = b"q 50 0 0 50 10 10 cm BI /W 1 /H 1 /BPC 8 /CS /G ID \x00 EI Q";
+        let (content, report) = redact_content(original, &[[0.0, 0.0, 20.0, 20.0]]).unwrap();
+        assert_eq!(content, b"q 50 0 0 50 10 10 cm  Q");
+        assert_eq!(report.images_removed, 1);
+
+        let (content, report) = redact_content(original, &[[100.0, 100.0, 120.0, 120.0]]).unwrap();
+        assert_eq!(content, original);
+        assert_eq!(report, RedactionReport::default());
+    }
+
+    #[test]
+    fn test_redact_image_xobjects() {
+        let fonts = FxHashMap::default();
+        let mut xobjects = HashMap::new();
+        xobjects.insert("Im1".to_string(), XObjectKind::Image { editable: true });
+        xobjects.insert("Im2".to_string(), XObjectKind::Image { editable: false });
+
+        let mut redactor =
+            ContentRedactor::new(&[[0.0, 0.0, 10.0, 10.0]], &fonts, &xobjects, IDENTITY);
+        let content = b"q 100 0 0 100 0 0 cm /Im1 Do /Im2 Do Q";
+        let output = redactor.redact_stream(content).unwrap().unwrap();
+
+        assert_eq!(output, b"q 100 0 0 100 0 0 cm /Im1 Do  Q");
+        assert!(matches!(
+            &redactor.xobject_uses()[0],
+            XObjectUse::RedactImage { name, ctm } if name == "Im1" && ctm[0] == 100.0
+        ));
+        assert!(matches!(
+            &redactor.xobject_uses()[1],
+            XObjectUse::RemovedImage { name } if name == "Im2"
+        ));
+    }
+
+    #[test]
+    fn test_blank_image_pixels() {
+        // A 4x4 gray image drawn at 0,0 with a size of 40x40
+        let mut pixels = vec![255u8; 16];
+        let ctm = [40.0, 0.0, 0.0, 40.0, 0.0, 0.0];
+        let changed =
+            blank_image_pixels(&mut pixels, 4, 4, 1, &ctm, &[[0.0, 0.0, 15.0, 15.0]]).unwrap();
+
+        assert!(changed);
+        // The bottom-left 2x2 pixels are blanked; rows run from the top
+        let expected: Vec<u8> = [
+            [255, 255, 255, 255],
+            [255, 255, 255, 255],
+            [0, 0, 255, 255],
+            [0, 0, 255, 255],
+        ]
+        .concat();
+        assert_eq!(pixels, expected);
+    }
+
+    #[test]
+    fn test_fill_boxes_content() {
+        let content = fill_boxes_content(&[[10.0, 20.0, 5.0, 30.0]], &[0.0, 0.0, 0.0]);
+        assert_eq!(content, b"q 0 0 0 rg 5 20 5 10 re f Q\n");
+    }
+}
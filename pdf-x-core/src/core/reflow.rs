@@ -0,0 +1,591 @@
+//! Reflow of page text for reading on narrow screens (experimental).
+//!
+//! A page is turned into a reading-ordered list of paragraphs, each made of
+//! style runs that carry what a reader view needs to re-typeset the text:
+//! a font size class relative to the page's body text, and bold/italic.
+//! Line breaks inside a paragraph are dropped and words hyphenated across
+//! lines are joined again.
+//!
+//! Untagged pages are ordered with the block grouping and XY-cut of
+//! [`layout_reading_order`](super::layout::layout_reading_order); tagged
+//! pages follow the structure tree, with one paragraph per block-level
+//! element and heading levels taken from the H1-H6 roles.
+//!
+//! The output is meant for display, not for faithful extraction: the API may
+//! change as the heuristics improve.
+
+use super::content_stream::{TextItem, load_font_map};
+use super::error::PDFResult;
+use super::layout::{
+    TextLayoutOptions, font_size, item_box, reading_order_blocks, text_lines, word_gap,
+};
+use super::parser::PDFObject;
+use super::struct_tree::{StructElement, StructKid, StructTree};
+use super::xref::XRef;
+use std::collections::HashMap;
+
+/// Font descriptor flag: glyphs are italic (bit 7).
+const FLAG_ITALIC: i64 = 1 << 6;
+
+/// Font descriptor flag: bold glyphs are painted heavier (bit 19).
+const FLAG_FORCE_BOLD: i64 = 1 << 18;
+
+/// Vertical stem width from which a font without /FontWeight counts as bold.
+/// Regular weights of the standard fonts are below 90, bold ones about 140.
+const BOLD_STEM_V: f64 = 120.0;
+
+/// Size of text relative to the body text of the page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum FontSizeClass {
+    /// Noticeably smaller than the body text (footnotes, captions)
+    Small,
+    /// Body text
+    Body,
+    /// Somewhat larger than the body text (subheadings, lead paragraphs)
+    Large,
+    /// Much larger than the body text (titles, headings)
+    Huge,
+}
+
+impl FontSizeClass {
+    /// Classifies a font size against the body font size of the page.
+    pub fn classify(size: f64, body_size: f64) -> Self {
+        let ratio = size / body_size;
+        if ratio < 0.85 {
+            FontSizeClass::Small
+        } else if ratio <= 1.15 {
+            FontSizeClass::Body
+        } else if ratio < 1.5 {
+            FontSizeClass::Large
+        } else {
+            FontSizeClass::Huge
+        }
+    }
+}
+
+/// Weight and slant of a font.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FontStyle {
+    pub bold: bool,
+    pub italic: bool,
+}
+
+impl FontStyle {
+    /// Infers the style of a font from its base name and font descriptor.
+    ///
+    /// The descriptor's /Flags, /FontWeight and /ItalicAngle are used when
+    /// present, and a thick /StemV marks a font bold when it has no
+    /// /FontWeight; otherwise the name is searched for style suffixes such
+    /// as "Bold", "Black", "Italic" or "Oblique".
+    ///
+    /// # Arguments
+    /// * `base_font` - The /BaseFont name, with or without a subset prefix
+    /// * `descriptor` - The resolved /FontDescriptor dictionary, if any
+    pub fn detect(base_font: &str, descriptor: Option<&PDFObject>) -> Self {
+        let name = base_font
+            .split_once('+')
+            .map_or(base_font, |(_, name)| name)
+            .to_ascii_lowercase();
+        let mut style = FontStyle {
+            bold: ["bold", "black", "heavy", "demi", "semibold"]
+                .iter()
+                .any(|suffix| name.contains(suffix)),
+            italic: ["italic", "oblique", "slanted"]
+                .iter()
+                .any(|suffix| name.contains(suffix)),
+        };
+
+        if let Some(PDFObject::Dictionary(dict)) = descriptor {
+            let number = |key: &str| match dict.get(key) {
+                Some(PDFObject::Number(value)) => Some(*value),
+                _ => None,
+            };
+            if let Some(flags) = number("Flags") {
+                let flags = flags as i64;
+                style.italic |= flags & FLAG_ITALIC != 0;
+                style.bold |= flags & FLAG_FORCE_BOLD != 0;
+            }
+            match number("FontWeight") {
+                Some(weight) => style.bold |= weight >= 600.0,
+                None => style.bold |= number("StemV").is_some_and(|stem| stem >= BOLD_STEM_V),
+            }
+            if number("ItalicAngle").is_some_and(|angle| angle.abs() >= 1.0) {
+                style.italic = true;
+            }
+        }
+        style
+    }
+}
+
+/// A run of text in one style.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StyleRun {
+    pub text: String,
+    pub size_class: FontSizeClass,
+    pub bold: bool,
+    pub italic: bool,
+}
+
+/// A paragraph of reflowed text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReflowParagraph {
+    /// Style runs in reading order
+    pub runs: Vec<StyleRun>,
+
+    /// Heading level (1 is the most prominent), if the paragraph is a heading
+    pub heading_level: Option<u8>,
+
+    /// Estimated bounding box on the page as `[x0, y0, x1, y1]`
+    pub bbox: [f64; 4],
+}
+
+impl ReflowParagraph {
+    /// Returns the plain text of the paragraph.
+    pub fn text(&self) -> String {
+        self.runs.iter().map(|run| run.text.as_str()).collect()
+    }
+}
+
+/// Loads the styles of the fonts in a resources dictionary, keyed by
+/// resource name.
+pub(crate) fn load_font_styles(
+    resources: &PDFObject,
+    xref: &mut XRef,
+) -> PDFResult<HashMap<String, FontStyle>> {
+    let mut styles = HashMap::new();
+    for (name, font) in load_font_map(resources, xref)? {
+        let descriptor = match &font.dict.font_descriptor {
+            Some(descriptor) => Some(xref.fetch_if_ref(descriptor)?),
+            None => None,
+        };
+        styles.insert(
+            name,
+            FontStyle::detect(font.base_font(), descriptor.as_ref()),
+        );
+    }
+    Ok(styles)
+}
+
+/// Reflows the text items of an untagged page into paragraphs.
+///
+/// # Arguments
+/// * `items` - Text items as produced by text extraction
+/// * `styles` - Font styles keyed by resource name, as in [`TextItem::font_name`]
+/// * `options` - Layout thresholds used to find lines and blocks
+pub fn reflow_items(
+    items: Vec<TextItem>,
+    styles: &HashMap<String, FontStyle>,
+    options: &TextLayoutOptions,
+) -> Vec<ReflowParagraph> {
+    let body_size = body_font_size(&items);
+    reading_order_blocks(items, options)
+        .into_iter()
+        .filter_map(|lines| {
+            let paragraph = build_paragraph(&lines, styles, body_size, options)?;
+            Some(with_size_heading(paragraph, &lines, body_size))
+        })
+        .collect()
+}
+
+/// Reflows the text items of a tagged page into paragraphs, following the
+/// structure tree.
+///
+/// Each block-level element becomes a paragraph; /ActualText replaces the
+/// content of its element. Content outside the tree, such as artifacts, is
+/// left out.
+///
+/// # Arguments
+/// * `tree` - Structure tree of the document
+/// * `page_index` - Page the items were extracted from
+/// * `items` - Text items of the page, with their MCIDs
+/// * `styles` - Font styles keyed by resource name
+/// * `options` - Layout thresholds used to find lines within a paragraph
+pub fn reflow_tagged(
+    tree: &StructTree,
+    page_index: usize,
+    items: &[TextItem],
+    styles: &HashMap<String, FontStyle>,
+    options: &TextLayoutOptions,
+) -> Vec<ReflowParagraph> {
+    let body_size = body_font_size(items);
+    let mut by_mcid: HashMap<u32, Vec<TextItem>> = HashMap::new();
+    for item in items {
+        if let Some(mcid) = item.mcid {
+            by_mcid.entry(mcid).or_default().push(item.clone());
+        }
+    }
+
+    let mut groups = vec![TaggedGroup::default()];
+    for element in &tree.children {
+        collect_groups(element, page_index, &mut by_mcid, &mut groups);
+    }
+
+    groups
+        .into_iter()
+        .filter_map(|group| {
+            let lines = group
+                .items
+                .into_iter()
+                .flat_map(|items| text_lines(items, options))
+                .collect::<Vec<_>>();
+            let mut paragraph = build_paragraph(&lines, styles, body_size, options)?;
+            if let Some(actual_text) = group.actual_text {
+                paragraph.runs.truncate(1);
+                paragraph.runs[0].text = actual_text;
+            }
+            Some(match group.heading_level {
+                Some(level) => ReflowParagraph {
+                    heading_level: Some(level),
+                    ..paragraph
+                },
+                None => paragraph,
+            })
+        })
+        .collect()
+}
+
+/// Items of one block-level element, by marked-content sequence.
+#[derive(Default)]
+struct TaggedGroup {
+    items: Vec<Vec<TextItem>>,
+    heading_level: Option<u8>,
+    actual_text: Option<String>,
+}
+
+/// Appends the content of an element to `groups`, starting a new group at
+/// each block-level element.
+fn collect_groups(
+    element: &StructElement,
+    page_index: usize,
+    by_mcid: &mut HashMap<u32, Vec<TextItem>>,
+    groups: &mut Vec<TaggedGroup>,
+) {
+    let block = element.is_block();
+    if block {
+        groups.push(TaggedGroup {
+            heading_level: heading_level(&element.standard_role),
+            ..TaggedGroup::default()
+        });
+    }
+
+    if let Some(actual_text) = &element.actual_text {
+        let items: Vec<TextItem> = element
+            .mcids(page_index)
+            .into_iter()
+            .filter_map(|mcid| by_mcid.remove(&mcid))
+            .flatten()
+            .collect();
+        if !items.is_empty() {
+            // The replacement takes the style of the first item it replaces
+            let group = groups.last_mut().expect("groups start non-empty");
+            group.items.push(items);
+            group.actual_text = Some(actual_text.clone());
+        }
+    } else {
+        for kid in &element.children {
+            match kid {
+                StructKid::Element(child) => collect_groups(child, page_index, by_mcid, groups),
+                StructKid::MarkedContent {
+                    mcid,
+                    page_index: Some(page),
+                } if *page == page_index => {
+                    if let Some(items) = by_mcid.remove(mcid) {
+                        groups
+                            .last_mut()
+                            .expect("groups start non-empty")
+                            .items
+                            .push(items);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    if block {
+        groups.push(TaggedGroup::default());
+    }
+}
+
+/// Heading level of a standard structure role ("H" counts as level 1).
+fn heading_level(role: &str) -> Option<u8> {
+    match role {
+        "H" => Some(1),
+        _ => role
+            .strip_prefix('H')
+            .and_then(|level| level.parse::<u8>().ok())
+            .filter(|level| (1..=6).contains(level)),
+    }
+}
+
+/// Builds a paragraph from its lines, or `None` if it has no text.
+fn build_paragraph(
+    lines: &[Vec<TextItem>],
+    styles: &HashMap<String, FontStyle>,
+    body_size: f64,
+    options: &TextLayoutOptions,
+) -> Option<ReflowParagraph> {
+    let mut runs: Vec<StyleRun> = Vec::new();
+    let mut bbox: Option<[f64; 4]> = None;
+
+    for line in lines {
+        let mut previous: Option<&TextItem> = None;
+        for (index, item) in line.iter().enumerate() {
+            let style = item
+                .font_name
+                .as_ref()
+                .and_then(|name| styles.get(name))
+                .copied()
+                .unwrap_or_default();
+            let run = StyleRun {
+                text: String::new(),
+                size_class: FontSizeClass::classify(font_size(item), body_size),
+                bold: style.bold,
+                italic: style.italic,
+            };
+
+            let separator = if index == 0 {
+                // Line break: rejoin hyphenated words, otherwise a space
+                match runs.last_mut() {
+                    Some(last) if ends_with_hyphen(&last.text) => {
+                        if item.text.starts_with(char::is_lowercase) {
+                            last.text.pop();
+                        }
+                        ""
+                    }
+                    Some(_) => " ",
+                    None => "",
+                }
+            } else if previous.is_some_and(|previous| word_gap(previous, item, options)) {
+                " "
+            } else {
+                ""
+            };
+            previous = Some(item);
+
+            if item.position.is_some() {
+                let item_bbox = item_box(item, options);
+                bbox = Some(match bbox {
+                    Some(b) => [
+                        b[0].min(item_bbox[0]),
+                        b[1].min(item_bbox[1]),
+                        b[2].max(item_bbox[2]),
+                        b[3].max(item_bbox[3]),
+                    ],
+                    None => item_bbox,
+                });
+            }
+
+            match runs.last_mut() {
+                Some(last)
+                    if last.size_class == run.size_class
+                        && last.bold == run.bold
+                        && last.italic == run.italic =>
+                {
+                    push_text(&mut last.text, separator, &item.text);
+                }
+                last => {
+                    let mut text = String::new();
+                    if let Some(last) = last {
+                        push_text(&mut text, separator, "");
+                        if last.text.ends_with(char::is_whitespace) {
+                            text.clear();
+                        }
+                    }
+                    text.push_str(&item.text);
+                    runs.push(StyleRun { text, ..run });
+                }
+            }
+        }
+    }
+
+    // Collapse whitespace, which in PDFs is mostly positioning
+    for run in &mut runs {
+        run.text = collapse_whitespace(&run.text);
+    }
+    if let Some(first) = runs.first_mut() {
+        first.text = first.text.trim_start().to_string();
+    }
+    if let Some(last) = runs.last_mut() {
+        last.text = last.text.trim_end().to_string();
+    }
+    runs.retain(|run| !run.text.is_empty());
+    if runs.is_empty() {
+        return None;
+    }
+
+    Some(ReflowParagraph {
+        runs,
+        heading_level: None,
+        bbox: bbox.unwrap_or_default(),
+    })
+}
+
+/// Marks short paragraphs set entirely above the body size as headings.
+fn with_size_heading(
+    paragraph: ReflowParagraph,
+    lines: &[Vec<TextItem>],
+    body_size: f64,
+) -> ReflowParagraph {
+    let size = lines
+        .iter()
+        .flatten()
+        .map(font_size)
+        .fold(f64::INFINITY, f64::min);
+    let heading_level = match FontSizeClass::classify(size, body_size) {
+        FontSizeClass::Huge if lines.len() <= 3 => Some(1),
+        FontSizeClass::Large if lines.len() <= 3 => Some(2),
+        _ => None,
+    };
+    ReflowParagraph {
+        heading_level,
+        ..paragraph
+    }
+}
+
+/// Returns the body font size of a page: the size covering the most
+/// characters, rounded to half a point.
+fn body_font_size(items: &[TextItem]) -> f64 {
+    let mut counts: HashMap<i64, usize> = HashMap::new();
+    for item in items {
+        let key = (font_size(item) * 2.0).round() as i64;
+        *counts.entry(key).or_default() += item.text.chars().count();
+    }
+    counts
+        .into_iter()
+        .max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(&a.0)))
+        .map(|(key, _)| key as f64 / 2.0)
+        .filter(|size| *size > 0.0)
+        .unwrap_or(12.0)
+}
+
+/// Returns whether text ends with a hyphen that may split a word.
+pub(crate) fn ends_with_hyphen(text: &str) -> bool {
+    let mut chars = text.chars().rev();
+    matches!(chars.next(), Some('-' | '\u{00AD}')) && chars.next().is_some_and(char::is_alphabetic)
+}
+
+fn push_text(text: &mut String, separator: &str, next: &str) {
+    if !separator.is_empty() && !text.ends_with(char::is_whitespace) {
+        text.push_str(separator);
+    }
+    text.push_str(next);
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c.is_whitespace() {
+            if !result.ends_with(' ') {
+                result.push(' ');
+            }
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(text: &str, font: &str, x: f64, y: f64, size: f64) -> TextItem {
+        TextItem {
+            text: text.to_string(),
+            font_name: Some(font.to_string()),
+            font_size: Some(size),
+            position: Some((x, y)),
+            rendering_mode: None,
+            width: Some(text.chars().count() as f64 * 0.5 * size),
+            mcid: None,
+            char_spans: None,
+            vertical_extent: None,
+            glyphs: None,
+            font_family: None,
+            bold: false,
+            italic: false,
+            fill_color: None,
+        }
+    }
+
+    fn styles() -> HashMap<String, FontStyle> {
+        HashMap::from([
+            ("R".to_string(), FontStyle::detect("Times-Roman", None)),
+            (
+                "B".to_string(),
+                FontStyle::detect("ABCDEF+Times-Bold", None),
+            ),
+            ("I".to_string(), FontStyle::detect("Times-Italic", None)),
+        ])
+    }
+
+    #[test]
+    fn test_font_style_detection() {
+        assert_eq!(
+            FontStyle::detect("Helvetica-BoldOblique", None),
+            FontStyle {
+                bold: true,
+                italic: true
+            }
+        );
+        let mut descriptor = HashMap::new();
+        descriptor.insert("Flags".to_string(), PDFObject::Number(64.0));
+        descriptor.insert("FontWeight".to_string(), PDFObject::Number(700.0));
+        assert_eq!(
+            FontStyle::detect("F0", Some(&PDFObject::Dictionary(descriptor))),
+            FontStyle {
+                bold: true,
+                italic: true
+            }
+        );
+        assert_eq!(FontStyle::detect("Times-Roman", None), FontStyle::default());
+
+        // Vertical stem width stands in for a missing /FontWeight
+        let stem = |stem_v: f64| {
+            let descriptor = HashMap::from([("StemV".to_string(), PDFObject::Number(stem_v))]);
+            FontStyle::detect("F1", Some(&PDFObject::Dictionary(descriptor))).bold
+        };
+        assert!(stem(140.0));
+        assert!(!stem(84.0));
+    }
+
+    #[test]
+    fn test_reflow_paragraphs_and_runs() {
+        let items = vec![
+            item("A Title", "B", 72.0, 700.0, 24.0),
+            item("Body text with an ", "R", 72.0, 650.0, 10.0),
+            item("emphasized", "I", 162.0, 650.0, 10.0),
+            item("word, and a hyphen-", "R", 72.0, 638.0, 10.0),
+            item("ated line.", "R", 72.0, 626.0, 10.0),
+            item("1 A footnote.", "R", 72.0, 100.0, 7.0),
+        ];
+        let paragraphs = reflow_items(items, &styles(), &TextLayoutOptions::default());
+        assert_eq!(paragraphs.len(), 3);
+
+        assert_eq!(paragraphs[0].heading_level, Some(1));
+        assert_eq!(paragraphs[0].runs[0].size_class, FontSizeClass::Huge);
+        assert!(paragraphs[0].runs[0].bold);
+
+        let body = &paragraphs[1];
+        assert_eq!(body.heading_level, None);
+        assert_eq!(
+            body.text(),
+            "Body text with an emphasized word, and a hyphenated line."
+        );
+        assert_eq!(body.runs.len(), 3);
+        assert!(body.runs[1].italic);
+        assert_eq!(body.runs[1].text, "emphasized");
+        assert_eq!(body.runs[0].size_class, FontSizeClass::Body);
+
+        assert_eq!(paragraphs[2].runs[0].size_class, FontSizeClass::Small);
+    }
+
+    #[test]
+    fn test_heading_level_from_role() {
+        assert_eq!(heading_level("H"), Some(1));
+        assert_eq!(heading_level("H3"), Some(3));
+        assert_eq!(heading_level("H7"), None);
+        assert_eq!(heading_level("P"), None);
+    }
+}
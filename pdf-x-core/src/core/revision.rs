@@ -0,0 +1,146 @@
+//! The incremental updates a file is made of.
+//!
+//! Saving a PDF incrementally appends the changed objects, a new xref
+//! section and a trailer whose /Prev points at the previous section, ending
+//! with "%%EOF". Every earlier state of the file stays intact in front of
+//! the update, so the file can be read as it was after any save. This is
+//! what forensic tools use to see what changed after a document was signed.
+//!
+//! Reference: PDF 32000-1:2008 section 7.5.6.
+
+use super::error::PDFResult;
+use super::parser::PDFObject;
+use super::xref::{XRef, XRefEntry};
+use std::ops::Range;
+
+/// One revision of a file: the original document or an incremental update.
+#[derive(Debug, Clone)]
+pub struct Revision {
+    /// Revision index; 0 is the original file
+    pub index: usize,
+
+    /// Byte offset of the revision's xref section (its startxref)
+    pub xref_offset: usize,
+
+    /// The bytes the save that wrote this revision appended, up to and
+    /// including its "%%EOF" line
+    pub byte_range: Range<usize>,
+
+    /// The revision's trailer dictionary
+    pub trailer: PDFObject,
+
+    /// Whether the xref section is an xref stream rather than a table
+    pub xref_stream: bool,
+
+    /// Numbers of the objects the revision writes, in order
+    pub written: Vec<u32>,
+
+    /// Numbers of the objects the revision marks free, in order
+    pub freed: Vec<u32>,
+}
+
+impl Revision {
+    /// Returns the byte offset at which the file ends after this revision.
+    pub fn end(&self) -> usize {
+        self.byte_range.end
+    }
+}
+
+/// Lists the revisions of the file read by `xref`, oldest first.
+///
+/// A revision ends after the first "%%EOF" following its xref section and
+/// before the next revision's section; one without a "%%EOF" there ends
+/// where the next one's section starts, or at the end of the file. The
+/// next revision starts where it ends. Linearized files, whose first-page
+/// section comes before the section it points back to, may have revisions
+/// whose ranges overlap.
+pub(crate) fn collect_revisions(xref: &mut XRef) -> PDFResult<Vec<Revision>> {
+    let file_length = xref.stream_length();
+    let sections: Vec<_> = xref
+        .revisions()
+        .iter()
+        .map(|revision| {
+            let mut written = Vec::new();
+            let mut freed = Vec::new();
+            for (&num, entry) in &revision.entries {
+                match entry {
+                    // Object 0 heads the free list and is free in every table
+                    XRefEntry::Free { .. } if num == 0 => {}
+                    XRefEntry::Free { .. } => freed.push(num),
+                    _ => written.push(num),
+                }
+            }
+            written.sort_unstable();
+            freed.sort_unstable();
+            let xref_stream = matches!(
+                &revision.trailer,
+                PDFObject::Dictionary(dict)
+                    if dict.get("Type").and_then(|t| t.as_name()) == Some("XRef")
+            );
+            (
+                revision.offset,
+                revision.trailer.clone(),
+                xref_stream,
+                written,
+                freed,
+            )
+        })
+        .collect();
+
+    let offsets: Vec<usize> = sections.iter().map(|section| section.0).collect();
+    let mut revisions = Vec::with_capacity(sections.len());
+    let mut start = 0;
+    for (index, (xref_offset, trailer, xref_stream, written, freed)) in
+        sections.into_iter().enumerate()
+    {
+        let limit = match offsets.get(index + 1) {
+            Some(&next) if next > xref_offset => next,
+            _ => file_length,
+        };
+        let end = find_eof(xref, xref_offset, limit)?.unwrap_or(limit);
+        revisions.push(Revision {
+            index,
+            xref_offset,
+            byte_range: start.min(xref_offset)..end,
+            trailer,
+            xref_stream,
+            written,
+            freed,
+        });
+        start = end;
+    }
+    Ok(revisions)
+}
+
+/// Finds the end of the first "%%EOF" line in `start..limit`.
+///
+/// # Returns
+/// The offset just past the marker and its end-of-line, or `None` if there
+/// is no marker
+fn find_eof(xref: &mut XRef, start: usize, limit: usize) -> PDFResult<Option<usize>> {
+    const MARKER: &[u8] = b"%%EOF";
+    // Read in blocks: the marker usually follows the trailer closely, while
+    // the section itself may be long
+    const BLOCK_SIZE: usize = 4096;
+
+    let mut pos = start;
+    while pos < limit {
+        let block_end = (pos + BLOCK_SIZE).min(limit);
+        let bytes = xref.get_bytes(pos, block_end - pos)?;
+        if let Some(found) = bytes.windows(MARKER.len()).position(|w| w == MARKER) {
+            let marker_end = pos + found + MARKER.len();
+            let eol = match xref.get_bytes(marker_end, 2.min(limit - marker_end))?[..] {
+                [b'\r', b'\n'] => 2,
+                [b'\r' | b'\n', ..] => 1,
+                _ => 0,
+            };
+            return Ok(Some(marker_end + eol));
+        }
+        if block_end == limit {
+            break;
+        }
+        // Overlap the blocks so that a marker is never split between two
+        pos = block_end - (MARKER.len() - 1);
+    }
+    Ok(None)
+}
@@ -0,0 +1,294 @@
+//! Serde serialization of PDF objects (feature `serde`).
+//!
+//! [`PDFObject`] serializes to a JSON-friendly shape, so tools can dump
+//! objects for scripts without re-implementing the object model:
+//!
+//! | PDF object            | Serialized as                                 |
+//! |-----------------------|-----------------------------------------------|
+//! | null, boolean, number | null, boolean, number (integers without `.0`) |
+//! | `(text string)`       | `{"string": "decoded text"}`                  |
+//! | `(binary string)`     | `{"hex": "00ff10"}`                           |
+//! | `<hex string>`        | `{"hex": "48656c6c6f"}`                       |
+//! | `/Name`               | `"/Name"`                                     |
+//! | array, dictionary     | array, map (keys sorted, without the `/`)     |
+//! | stream                | `{"stream": {dict}, "length": bytes}`         |
+//! | `5 0 R`               | `{"ref": [5, 0]}`                             |
+//! | operator              | `{"operator": "Tj"}`                          |
+//!
+//! Strings are always tagged, so a string such as `(/Type)` can't be
+//! mistaken for a name. Literal strings holding control characters (an ID,
+//! a hash, encrypted data) aren't text and are written as hex.
+//!
+//! Serializing never follows references, so the object graph's cycles
+//! (`/Parent` links, for instance) can't cause endless output. To include
+//! referenced objects, expand them first with [`inline_refs`].
+
+use super::error::{PDFError, PDFResult};
+use super::outline::decode_pdf_string;
+use super::parser::{PDFObject, Ref};
+use super::xref::XRef;
+use serde::ser::{Serialize, SerializeMap, SerializeSeq, Serializer};
+use std::collections::HashMap;
+
+impl Serialize for PDFObject {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            PDFObject::Null | PDFObject::EOF => serializer.serialize_unit(),
+            PDFObject::Boolean(b) => serializer.serialize_bool(*b),
+            PDFObject::Number(n) => {
+                if n.fract() == 0.0 && n.abs() < i64::MAX as f64 {
+                    serializer.serialize_i64(*n as i64)
+                } else {
+                    serializer.serialize_f64(*n)
+                }
+            }
+            PDFObject::String(bytes) if is_text(bytes) => {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry("string", &decode_pdf_string(bytes))?;
+                map.end()
+            }
+            PDFObject::String(bytes) | PDFObject::HexString(bytes) => {
+                let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry("hex", &hex)?;
+                map.end()
+            }
+            PDFObject::Name(name) => serializer.serialize_str(&format!("/{}", name)),
+            PDFObject::Array(items) => {
+                let mut seq = serializer.serialize_seq(Some(items.len()))?;
+                for item in items {
+                    seq.serialize_element(&**item)?;
+                }
+                seq.end()
+            }
+            PDFObject::Dictionary(dict) => serialize_dict(dict, serializer),
+            PDFObject::Stream { dict, data } => {
+                let mut map = serializer.serialize_map(Some(2))?;
+                map.serialize_entry("stream", &SortedDict(dict))?;
+                map.serialize_entry("length", &data.len())?;
+                map.end()
+            }
+            PDFObject::Ref(r) => {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry("ref", &[r.num, r.generation])?;
+                map.end()
+            }
+            PDFObject::Command(op) => {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry("operator", op)?;
+                map.end()
+            }
+        }
+    }
+}
+
+/// Returns true if a literal string reads as text: it has a Unicode byte
+/// order mark, or no control characters other than whitespace.
+fn is_text(bytes: &[u8]) -> bool {
+    bytes.starts_with(&[0xFE, 0xFF])
+        || bytes.starts_with(&[0xFF, 0xFE])
+        || bytes.starts_with(&[0xEF, 0xBB, 0xBF])
+        || bytes
+            .iter()
+            .all(|&b| (b >= 0x20 && b != 0x7F) || matches!(b, b'\t' | b'\n' | b'\r' | 0x0C))
+}
+
+/// Serializes a dictionary with its keys sorted, for stable output.
+struct SortedDict<'a>(&'a HashMap<String, PDFObject>);
+
+impl Serialize for SortedDict<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_dict(self.0, serializer)
+    }
+}
+
+fn serialize_dict<S: Serializer>(
+    dict: &HashMap<String, PDFObject>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let mut entries: Vec<_> = dict.iter().collect();
+    entries.sort_by(|a, b| a.0.cmp(b.0));
+
+    let mut map = serializer.serialize_map(Some(entries.len()))?;
+    for (key, value) in entries {
+        map.serialize_entry(key, value)?;
+    }
+    map.end()
+}
+
+/// Replaces the references in `obj` by the objects they point to, up to
+/// `max_depth` levels of references deep.
+///
+/// A reference is kept as is when it points back to an object being
+/// expanded (a cycle, such as a page's `/Parent`), when it is deeper than
+/// `max_depth`, or when the object can't be read. An object referenced from
+/// several places (a shared font, say) is expanded once and reused.
+///
+/// # Arguments
+/// * `xref` - Cross-reference table to fetch objects from
+/// * `obj` - The object to expand
+/// * `max_depth` - How many references deep to follow (0 returns a copy)
+///
+/// # Example
+/// ```no_run
+/// use pdf_x_core::core::{PDFDocument, serialize::inline_refs};
+///
+/// let mut doc = PDFDocument::open_file("document.pdf", None, None).unwrap();
+/// let catalog = doc.catalog().unwrap().clone();
+/// let expanded = inline_refs(doc.xref_mut(), &catalog, 2).unwrap();
+/// println!("{}", serde_json::to_string_pretty(&expanded).unwrap());
+/// ```
+pub fn inline_refs(xref: &mut XRef, obj: &PDFObject, max_depth: usize) -> PDFResult<PDFObject> {
+    Inliner {
+        xref,
+        path: Vec::new(),
+        expanded: HashMap::new(),
+        lowest_cut: usize::MAX,
+    }
+    .inline(obj, max_depth)
+}
+
+/// State of an [`inline_refs`] expansion.
+struct Inliner<'a> {
+    xref: &'a mut XRef,
+
+    /// The references being expanded, outermost first
+    path: Vec<Ref>,
+
+    /// Finished expansions by reference and remaining depth
+    expanded: HashMap<(Ref, usize), PDFObject>,
+
+    /// Position in `path` of the outermost reference kept because it
+    /// loops back, since the current object started expanding. An
+    /// expansion that only loops back into itself doesn't depend on where
+    /// it was reached from and can be reused.
+    lowest_cut: usize,
+}
+
+impl Inliner<'_> {
+    fn inline(&mut self, obj: &PDFObject, depth: usize) -> PDFResult<PDFObject> {
+        Ok(match obj {
+            PDFObject::Ref(r) => {
+                if depth == 0 {
+                    return Ok(obj.clone());
+                }
+                if let Some(position) = self.path.iter().position(|p| p == r) {
+                    self.lowest_cut = self.lowest_cut.min(position);
+                    return Ok(obj.clone());
+                }
+                if let Some(expanded) = self.expanded.get(&(*r, depth)) {
+                    return Ok(expanded.clone());
+                }
+                let target = match self.xref.fetch(r.num, r.generation) {
+                    Ok(target) => target,
+                    Err(e @ (PDFError::DataMissing { .. } | PDFError::Cancelled)) => {
+                        return Err(e);
+                    }
+                    Err(_) => return Ok(obj.clone()),
+                };
+
+                let position = self.path.len();
+                let outer_cut = std::mem::replace(&mut self.lowest_cut, usize::MAX);
+                self.path.push(*r);
+                let expanded = self.inline(&target, depth - 1);
+                self.path.pop();
+                let expanded = expanded?;
+                if self.lowest_cut >= position {
+                    self.expanded.insert((*r, depth), expanded.clone());
+                }
+                self.lowest_cut = self.lowest_cut.min(outer_cut);
+                expanded
+            }
+            PDFObject::Array(items) => PDFObject::Array(
+                items
+                    .iter()
+                    .map(|item| self.inline(item, depth).map(Box::new))
+                    .collect::<PDFResult<_>>()?,
+            ),
+            PDFObject::Dictionary(dict) => PDFObject::Dictionary(self.inline_dict(dict, depth)?),
+            PDFObject::Stream { dict, data } => PDFObject::Stream {
+                dict: self.inline_dict(dict, depth)?,
+                data: data.clone(),
+            },
+            other => other.clone(),
+        })
+    }
+
+    fn inline_dict(
+        &mut self,
+        dict: &HashMap<String, PDFObject>,
+        depth: usize,
+    ) -> PDFResult<HashMap<String, PDFObject>> {
+        dict.iter()
+            .map(|(key, value)| Ok((key.clone(), self.inline(value, depth)?)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::PDFDocument;
+    use crate::test_support::build_pdf;
+
+    #[test]
+    fn test_serialize_objects() {
+        let mut dict = HashMap::new();
+        dict.insert("Type".to_string(), PDFObject::Name("Page".to_string()));
+        dict.insert("Rotate".to_string(), PDFObject::Number(90.0));
+        dict.insert("Scale".to_string(), PDFObject::Number(0.5));
+        dict.insert("Title".to_string(), PDFObject::String(b"Hello".to_vec()));
+        dict.insert("Label".to_string(), PDFObject::String(b"/Page".to_vec()));
+        dict.insert(
+            "Hash".to_string(),
+            PDFObject::String(vec![0x00, 0x9f, b'a']),
+        );
+        dict.insert("ID".to_string(), PDFObject::HexString(vec![0xab, 0x01]));
+        dict.insert(
+            "Parent".to_string(),
+            PDFObject::Ref(Ref {
+                num: 2,
+                generation: 0,
+            }),
+        );
+        let json = serde_json::to_string(&PDFObject::Dictionary(dict)).unwrap();
+        assert_eq!(
+            json,
+            r#"{"Hash":{"hex":"009f61"},"ID":{"hex":"ab01"},"Label":{"string":"/Page"},"Parent":{"ref":[2,0]},"Rotate":90,"Scale":0.5,"Title":{"string":"Hello"},"Type":"/Page"}"#
+        );
+    }
+
+    #[test]
+    fn test_inline_refs_stops_at_cycles() {
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R /Info 4 0 R /Meta 4 0 R >>",
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>",
+            "<< /Type /Page /Parent 2 0 R >>",
+            "<< /Author (A) /Self 4 0 R >>",
+        ];
+
+        let mut doc = PDFDocument::open(build_pdf(&objects)).unwrap();
+        let catalog = doc.catalog().unwrap().clone();
+        let expanded = inline_refs(doc.xref_mut(), &catalog, 10).unwrap();
+        let json = serde_json::to_value(&expanded).unwrap();
+
+        // The page is expanded, but its /Parent points back to the page tree
+        // being expanded and stays a reference
+        let page = &json["Pages"]["Kids"][0];
+        assert_eq!(page["Type"], "/Page");
+        assert_eq!(page["Parent"], serde_json::json!({ "ref": [2, 0] }));
+
+        // An object referenced twice expands the same way both times
+        assert_eq!(json["Info"]["Author"], serde_json::json!({ "string": "A" }));
+        assert_eq!(json["Info"]["Self"], serde_json::json!({ "ref": [4, 0] }));
+        assert_eq!(json["Info"], json["Meta"]);
+
+        // Depth 1 expands only the direct references
+        let shallow =
+            serde_json::to_value(inline_refs(doc.xref_mut(), &catalog, 1).unwrap()).unwrap();
+        assert_eq!(
+            shallow["Pages"]["Kids"][0],
+            serde_json::json!({ "ref": [3, 0] })
+        );
+    }
+}
@@ -0,0 +1,325 @@
+//! Smooth shadings (PDF spec section 8.7.4.5).
+//!
+//! A shading describes a color that varies continuously over an area. It is
+//! painted either directly with the `sh` operator or as the fill of a shading
+//! pattern (PatternType 2). Axial (Type 2) and radial (Type 3) shadings are
+//! supported; function-based and mesh shadings (Types 1, 4-7) are reported as
+//! unsupported.
+//!
+//! Based on PDF.js src/core/pattern.js (RadialAxialShading)
+
+use super::colorspace::ColorSpace;
+use super::error::{PDFError, PDFResult};
+use super::function::PDFFunction;
+use super::parser::PDFObject;
+use super::xref::XRef;
+
+/// Where the color of a shading varies, in shading space.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ShadingGeometry {
+    /// Color varies along the axis from (x0, y0) to (x1, y1)
+    Axial { coords: [f64; 4] },
+    /// Color varies between the circles (x0, y0, r0) and (x1, y1, r1)
+    Radial { coords: [f64; 6] },
+}
+
+impl ShadingGeometry {
+    /// Returns the position of a point along the shading, from 0.0 at the
+    /// start to 1.0 at the end.
+    ///
+    /// Points before the start or past the end map to the nearest end if
+    /// that end is extended, and to `None` otherwise. For radial shadings the
+    /// largest circle through the point wins, as later circles are painted
+    /// over earlier ones.
+    pub fn parameter(&self, x: f64, y: f64, extend: [bool; 2]) -> Option<f64> {
+        let in_range = |s: f64| {
+            if s < 0.0 {
+                extend[0].then_some(0.0)
+            } else if s > 1.0 {
+                extend[1].then_some(1.0)
+            } else {
+                Some(s)
+            }
+        };
+
+        match *self {
+            ShadingGeometry::Axial {
+                coords: [x0, y0, x1, y1],
+            } => {
+                let (dx, dy) = (x1 - x0, y1 - y0);
+                let length_squared = dx * dx + dy * dy;
+                if length_squared == 0.0 {
+                    return None;
+                }
+                in_range(((x - x0) * dx + (y - y0) * dy) / length_squared)
+            }
+            ShadingGeometry::Radial {
+                coords: [x0, y0, r0, x1, y1, r1],
+            } => {
+                // Solve |p - c(s)| = r(s) for s, where the circle c(s), r(s)
+                // moves linearly from the start circle to the end circle
+                let (cdx, cdy, dr) = (x1 - x0, y1 - y0, r1 - r0);
+                let (pdx, pdy) = (x - x0, y - y0);
+                let a = cdx * cdx + cdy * cdy - dr * dr;
+                let b = pdx * cdx + pdy * cdy + r0 * dr;
+                let c = pdx * pdx + pdy * pdy - r0 * r0;
+
+                let roots = if a.abs() < 1e-12 {
+                    if b.abs() < 1e-12 {
+                        return None;
+                    }
+                    [c / (2.0 * b), f64::NAN]
+                } else {
+                    let discriminant = b * b - a * c;
+                    if discriminant < 0.0 {
+                        return None;
+                    }
+                    let root = discriminant.sqrt();
+                    let (s1, s2) = ((b + root) / a, (b - root) / a);
+                    [s1.max(s2), s1.min(s2)]
+                };
+
+                roots
+                    .into_iter()
+                    .filter(|s| !s.is_nan() && r0 + s * dr >= 0.0)
+                    .find_map(in_range)
+            }
+        }
+    }
+}
+
+/// An axial or radial shading.
+///
+/// # Example
+/// ```no_run
+/// use pdf_x_core::core::PDFDocument;
+/// use pdf_x_core::core::shading::Shading;
+///
+/// let mut doc = PDFDocument::open_file("gradient.pdf", None, None).unwrap();
+/// # let shading_ref = pdf_x_core::core::PDFObject::Null;
+/// let shading = Shading::parse(&shading_ref, doc.xref_mut()).unwrap();
+/// if let Some(s) = shading.parameter(100.0, 200.0) {
+///     println!("RGB at (100, 200): {:?}", shading.rgb_at(s));
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Shading {
+    /// Axis or circles along which the color varies
+    pub geometry: ShadingGeometry,
+    /// Color space of the function outputs
+    pub color_space: ColorSpace,
+    /// Function input values at the start and end (/Domain)
+    pub domain: [f64; 2],
+    /// Whether to extend past the start and end (/Extend)
+    pub extend: [bool; 2],
+    /// One n-output function, or n 1-output functions (/Function)
+    pub functions: Vec<PDFFunction>,
+    /// Color for areas outside the shading when used as a pattern (/Background)
+    pub background: Option<Vec<f64>>,
+    /// Bounding box in shading space that clips the shading (/BBox)
+    pub bbox: Option<[f64; 4]>,
+}
+
+impl Shading {
+    /// Parses a shading dictionary or stream, resolving references.
+    ///
+    /// # Returns
+    /// The shading, or `PDFError::Unsupported` for shading types other than
+    /// axial and radial
+    pub fn parse(obj: &PDFObject, xref: &mut XRef) -> PDFResult<Self> {
+        let obj = xref.fetch_if_ref(obj)?;
+        let dict = match &obj {
+            PDFObject::Dictionary(dict) | PDFObject::Stream { dict, .. } => dict,
+            _ => {
+                return Err(PDFError::Generic(format!(
+                    "Shading must be a dictionary or stream, got {:?}",
+                    obj
+                )));
+            }
+        };
+        let numbers = |key: &str| -> Option<Vec<f64>> {
+            dict.get(key)?
+                .as_array()?
+                .iter()
+                .map(|n| n.as_number())
+                .collect()
+        };
+
+        let shading_type = match dict.get("ShadingType") {
+            Some(PDFObject::Number(n)) => *n as i32,
+            _ => {
+                return Err(PDFError::Generic(
+                    "Shading missing /ShadingType".to_string(),
+                ));
+            }
+        };
+        let coords = numbers("Coords").unwrap_or_default();
+        let geometry = match (shading_type, coords.as_slice()) {
+            (2, &[x0, y0, x1, y1]) => ShadingGeometry::Axial {
+                coords: [x0, y0, x1, y1],
+            },
+            (3, &[x0, y0, r0, x1, y1, r1]) => ShadingGeometry::Radial {
+                coords: [x0, y0, r0, x1, y1, r1],
+            },
+            (2 | 3, _) => {
+                return Err(PDFError::Generic(format!(
+                    "Shading type {} has invalid /Coords {:?}",
+                    shading_type, coords
+                )));
+            }
+            _ => {
+                return Err(PDFError::unsupported(format!(
+                    "shading type {}",
+                    shading_type
+                )));
+            }
+        };
+
+        let color_space = match dict.get("ColorSpace") {
+            Some(cs) => ColorSpace::parse(cs, xref)?,
+            None => {
+                return Err(PDFError::Generic("Shading missing /ColorSpace".to_string()));
+            }
+        };
+
+        let domain = match numbers("Domain").as_deref() {
+            Some(&[t0, t1]) => [t0, t1],
+            _ => [0.0, 1.0],
+        };
+        let extend = match dict.get("Extend").and_then(|e| e.as_array()) {
+            Some([start, end]) => [
+                start.as_boolean().unwrap_or(false),
+                end.as_boolean().unwrap_or(false),
+            ],
+            _ => [false, false],
+        };
+
+        let functions = match dict.get("Function").map(|f| xref.fetch_if_ref(f)) {
+            Some(Ok(PDFObject::Array(items))) => {
+                let mut functions = Vec::with_capacity(items.len());
+                for item in items.iter() {
+                    functions.push(PDFFunction::parse(item, xref)?);
+                }
+                functions
+            }
+            Some(Ok(function)) => vec![PDFFunction::parse(&function, xref)?],
+            Some(Err(e)) => return Err(e),
+            None => {
+                return Err(PDFError::Generic("Shading missing /Function".to_string()));
+            }
+        };
+        if functions.is_empty() {
+            return Err(PDFError::Generic("Shading has no functions".to_string()));
+        }
+
+        Ok(Shading {
+            geometry,
+            color_space,
+            domain,
+            extend,
+            functions,
+            background: numbers("Background"),
+            bbox: match numbers("BBox").as_deref() {
+                Some(&[x0, y0, x1, y1]) => Some([x0.min(x1), y0.min(y1), x0.max(x1), y0.max(y1)]),
+                _ => None,
+            },
+        })
+    }
+
+    /// Returns the position of a point along the shading (see
+    /// [`ShadingGeometry::parameter`]), honoring /Extend.
+    pub fn parameter(&self, x: f64, y: f64) -> Option<f64> {
+        self.geometry.parameter(x, y, self.extend)
+    }
+
+    /// Returns the color components at position `s` (0.0 to 1.0).
+    pub fn color_at(&self, s: f64) -> Vec<f64> {
+        let [t0, t1] = self.domain;
+        let t = t0 + s.clamp(0.0, 1.0) * (t1 - t0);
+        if let [function] = self.functions.as_slice() {
+            function.eval(&[t])
+        } else {
+            self.functions
+                .iter()
+                .map(|f| f.eval(&[t]).first().copied().unwrap_or(0.0))
+                .collect()
+        }
+    }
+
+    /// Returns the RGB color at position `s` (0.0 to 1.0).
+    pub fn rgb_at(&self, s: f64) -> [f64; 3] {
+        self.color_space.to_rgb(&self.color_at(s))
+    }
+
+    /// Returns the background color as RGB, if the shading has one.
+    pub fn background_rgb(&self) -> Option<[f64; 3]> {
+        self.background
+            .as_ref()
+            .map(|background| self.color_space.to_rgb(background))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Stream;
+
+    fn parse(source: &str) -> PDFResult<Shading> {
+        let lexer =
+            crate::core::Lexer::new(Box::new(Stream::from_bytes(source.as_bytes().to_vec())))?;
+        let obj = crate::core::Parser::new(lexer)?.get_object()?;
+        let mut xref = XRef::new(Box::new(Stream::from_bytes(Vec::new())));
+        Shading::parse(&obj, &mut xref)
+    }
+
+    fn assert_rgb(actual: [f64; 3], expected: [f64; 3]) {
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-6, "{:?} vs {:?}", actual, expected);
+        }
+    }
+
+    #[test]
+    fn test_axial_shading() {
+        let shading = parse(
+            "<< /ShadingType 2 /ColorSpace /DeviceRGB /Coords [0 0 100 0] /Extend [false true] \
+             /Function << /FunctionType 2 /Domain [0 1] /C0 [1 0 0] /C1 [0 0 1] /N 1 >> >>",
+        )
+        .unwrap();
+
+        assert_eq!(shading.parameter(25.0, 40.0), Some(0.25));
+        assert_eq!(shading.parameter(-10.0, 0.0), None);
+        assert_eq!(shading.parameter(150.0, 0.0), Some(1.0));
+        assert_rgb(shading.rgb_at(0.25), [0.75, 0.0, 0.25]);
+    }
+
+    #[test]
+    fn test_radial_shading() {
+        // Concentric circles from radius 10 to 50, split into two gray functions
+        let shading = parse(
+            "<< /ShadingType 3 /ColorSpace /DeviceCMYK /Coords [0 0 10 0 0 50] /Domain [0 2] \
+             /Function [<< /FunctionType 2 /Domain [0 2] /C0 [0] /C1 [1] /N 1 >> \
+                        << /FunctionType 2 /Domain [0 2] /C0 [0] /C1 [0] /N 1 >> \
+                        << /FunctionType 2 /Domain [0 2] /C0 [0] /C1 [0] /N 1 >> \
+                        << /FunctionType 2 /Domain [0 2] /C0 [0] /C1 [0] /N 1 >>] >>",
+        )
+        .unwrap();
+
+        assert_eq!(shading.parameter(30.0, 0.0), Some(0.5));
+        assert_eq!(shading.parameter(0.0, 5.0), None);
+        assert_eq!(shading.parameter(0.0, 60.0), None);
+        // t = 1 of the domain [0 2]: full cyan
+        assert_rgb(shading.rgb_at(0.5), [0.0, 1.0, 1.0]);
+
+        // A circle growing out of a point: the largest circle through a point wins
+        let geometry = ShadingGeometry::Radial {
+            coords: [0.0, 0.0, 0.0, 10.0, 0.0, 20.0],
+        };
+        let s = geometry.parameter(25.0, 0.0, [false, false]).unwrap();
+        assert!((s - 25.0 / 30.0).abs() < 1e-9);
+
+        assert!(matches!(
+            parse("<< /ShadingType 4 /ColorSpace /DeviceRGB >>"),
+            Err(PDFError::Unsupported { .. })
+        ));
+    }
+}
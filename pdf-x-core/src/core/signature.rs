@@ -0,0 +1,220 @@
+//! Signature fields and the byte ranges they sign.
+//!
+//! The value (/V) of a signature field is a signature dictionary whose
+//! /ByteRange lists the parts of the file the signature covers: everything
+//! from the start of the file to the end of the signed revision, except the
+//! /Contents hex string that holds the signature itself. Incremental updates
+//! appended after that revision leave the signature valid as long as the
+//! covered bytes are untouched.
+//!
+//! Cryptographic validation (PKCS#7/CMS) is out of scope; this module exposes
+//! the ranges and the signed data so callers can verify them, and checks that
+//! the ranges still describe the file.
+//!
+//! Reference: PDF 32000-1:2008 section 12.8.
+
+use super::annotation::FormFieldType;
+use super::error::{PDFError, PDFResult};
+use super::form::collect_form_fields;
+use super::outline::decode_pdf_string;
+use super::parser::{PDFObject, Ref};
+use super::xref::XRef;
+
+/// A signed signature field.
+#[derive(Debug, Clone)]
+pub struct Signature {
+    /// Fully qualified name of the signature field
+    pub field_name: String,
+
+    /// Reference to the field dictionary
+    pub field_ref: Ref,
+
+    /// Signed ranges as (offset, length) pairs, in file order (/ByteRange)
+    pub byte_range: Vec<(usize, usize)>,
+
+    /// The signature bytes (/Contents), including any zero padding
+    pub contents: Vec<u8>,
+
+    /// Preferred signature handler, e.g. "Adobe.PPKLite" (/Filter)
+    pub filter: Option<String>,
+
+    /// Signature encoding, e.g. "adbe.pkcs7.detached" (/SubFilter)
+    pub sub_filter: Option<String>,
+
+    /// Name of the signer (/Name)
+    pub signer_name: Option<String>,
+
+    /// Time of signing as a PDF date string (/M)
+    pub signing_time: Option<String>,
+}
+
+impl Signature {
+    /// Returns the length of the signed revision: the end of the last range.
+    pub fn signed_length(&self) -> usize {
+        self.byte_range
+            .iter()
+            .map(|&(offset, length)| offset.saturating_add(length))
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Returns the signed data: the concatenation of all byte ranges.
+    ///
+    /// # Arguments
+    /// * `file` - The complete file the signature belongs to
+    pub fn signed_data(&self, file: &[u8]) -> PDFResult<Vec<u8>> {
+        let mut data = Vec::new();
+        for &(offset, length) in &self.byte_range {
+            let end = offset
+                .checked_add(length)
+                .filter(|&end| end <= file.len())
+                .ok_or(PDFError::InvalidByteRange {
+                    begin: offset,
+                    end: offset.saturating_add(length),
+                })?;
+            data.extend_from_slice(&file[offset..end]);
+        }
+        Ok(data)
+    }
+
+    /// Checks that the byte ranges still describe `file`.
+    ///
+    /// The ranges must start at offset 0, be in order and lie within the file,
+    /// and every gap between them must hold exactly the /Contents hex string.
+    /// This is what validators check before hashing, so a file passing it has
+    /// its signed bytes where the signature expects them.
+    pub fn byte_ranges_intact(&self, file: &[u8]) -> bool {
+        if self.byte_range.first().map(|&(offset, _)| offset) != Some(0) {
+            return false;
+        }
+        if self.signed_length() > file.len() {
+            return false;
+        }
+        self.byte_range.windows(2).all(|pair| {
+            let gap_start = pair[0].0 + pair[0].1;
+            let gap_end = pair[1].0;
+            gap_start <= gap_end
+                && decode_hex_string(&file[gap_start..gap_end]).as_ref() == Some(&self.contents)
+        })
+    }
+}
+
+/// Decodes a `<...>` hex string token, ignoring whitespace.
+fn decode_hex_string(token: &[u8]) -> Option<Vec<u8>> {
+    let inner = token.strip_prefix(b"<")?.strip_suffix(b">")?;
+    let digits: Vec<u8> = inner
+        .iter()
+        .filter(|b| !b.is_ascii_whitespace())
+        .map(|&b| (b as char).to_digit(16).map(|d| d as u8))
+        .collect::<Option<_>>()?;
+    // An odd final digit is followed by an implicit 0 (PDF spec 7.3.4.3)
+    Some(
+        digits
+            .chunks(2)
+            .map(|pair| pair[0] << 4 | pair.get(1).copied().unwrap_or(0))
+            .collect(),
+    )
+}
+
+/// Collects the signed signature fields of the document's interactive form.
+///
+/// Unsigned signature fields (without a /V dictionary) are skipped.
+///
+/// # Arguments
+/// * `xref` - The cross-reference table used to resolve the field tree
+pub fn collect_signatures(xref: &mut XRef) -> PDFResult<Vec<Signature>> {
+    let mut signatures = Vec::new();
+    for field in collect_form_fields(xref)? {
+        if field.field_type != FormFieldType::Signature {
+            continue;
+        }
+        let value = match xref
+            .fetch(field.field_ref.num, field.field_ref.generation)?
+            .as_ref()
+        {
+            PDFObject::Dictionary(dict) => match dict.get("V") {
+                Some(value) => xref.fetch_if_ref(value)?,
+                None => continue,
+            },
+            _ => continue,
+        };
+        let PDFObject::Dictionary(dict) = value else {
+            continue;
+        };
+
+        let byte_range = match dict.get("ByteRange") {
+            Some(PDFObject::Array(values)) => values
+                .chunks_exact(2)
+                .filter_map(|pair| match (&*pair[0], &*pair[1]) {
+                    (PDFObject::Number(offset), PDFObject::Number(length))
+                        if *offset >= 0.0 && *length >= 0.0 =>
+                    {
+                        Some((*offset as usize, *length as usize))
+                    }
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+        let contents = match dict.get("Contents") {
+            Some(PDFObject::String(bytes)) | Some(PDFObject::HexString(bytes)) => bytes.clone(),
+            _ => Vec::new(),
+        };
+        let name = |key: &str| match dict.get(key) {
+            Some(PDFObject::Name(name)) => Some(name.clone()),
+            _ => None,
+        };
+        let text = |key: &str| match dict.get(key) {
+            Some(PDFObject::String(bytes)) | Some(PDFObject::HexString(bytes)) => {
+                Some(decode_pdf_string(bytes))
+            }
+            _ => None,
+        };
+
+        signatures.push(Signature {
+            field_name: field.name,
+            field_ref: field.field_ref,
+            byte_range,
+            contents,
+            filter: name("Filter"),
+            sub_filter: name("SubFilter"),
+            signer_name: text("Name"),
+            signing_time: text("M"),
+        });
+    }
+    Ok(signatures)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signature(byte_range: Vec<(usize, usize)>, contents: &[u8]) -> Signature {
+        Signature {
+            field_name: "Sig1".to_string(),
+            field_ref: Ref::new(1, 0),
+            byte_range,
+            contents: contents.to_vec(),
+            filter: None,
+            sub_filter: None,
+            signer_name: None,
+            signing_time: None,
+        }
+    }
+
+    #[test]
+    fn test_byte_ranges_intact() {
+        let file = b"abc<0A0b00>def\n";
+        let sig = signature(vec![(0, 3), (11, 3)], &[0x0A, 0x0B, 0x00]);
+        assert!(sig.byte_ranges_intact(file));
+        assert_eq!(sig.signed_length(), 14);
+        assert_eq!(sig.signed_data(file).unwrap(), b"abcdef");
+
+        // Appending keeps the ranges valid; shifting the contents does not
+        let mut appended = file.to_vec();
+        appended.extend_from_slice(b"more");
+        assert!(sig.byte_ranges_intact(&appended));
+        assert!(!sig.byte_ranges_intact(b"xabc<0A0b00>def"));
+        assert!(!sig.byte_ranges_intact(b"abc<0A0b00>de"));
+    }
+}
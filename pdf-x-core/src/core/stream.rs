@@ -12,7 +12,7 @@ use std::sync::Arc;
 /// share the same data without cloning.
 pub struct Stream {
     /// The underlying byte buffer (shared via Arc)
-    bytes: Arc<Vec<u8>>,
+    bytes: Arc<[u8]>,
     /// Current read position
     pos: usize,
     /// Starting offset in the buffer
@@ -36,17 +36,17 @@ impl Stream {
         };
 
         Stream {
-            bytes: Arc::new(bytes),
+            bytes: bytes.into(),
             pos: start,
             start,
             length: actual_length,
         }
     }
 
-    /// Creates a new Stream from an Arc-wrapped byte vector.
+    /// Creates a new Stream from an Arc-wrapped byte buffer.
     ///
     /// This is used internally for creating sub-streams that share data.
-    fn from_arc(bytes: Arc<Vec<u8>>, start: usize, length: usize) -> Self {
+    fn from_arc(bytes: Arc<[u8]>, start: usize, length: usize) -> Self {
         Stream {
             bytes,
             pos: start,
@@ -61,6 +61,13 @@ impl Stream {
         Self::new(bytes, 0, length)
     }
 
+    /// Creates a new Stream over a byte buffer shared with other owners,
+    /// without copying it.
+    pub fn from_shared(bytes: Arc<[u8]>) -> Self {
+        let length = bytes.len();
+        Self::from_arc(bytes, 0, length)
+    }
+
     /// Returns a reference to the underlying byte buffer.
     pub fn get_bytes_ref(&self) -> &[u8] {
         &self.bytes
@@ -0,0 +1,596 @@
+//! Logical structure tree of tagged PDFs.
+//!
+//! A tagged PDF describes its logical structure (headings, paragraphs, lists,
+//! tables, figures, ...) in a tree rooted at the catalog's /StructTreeRoot.
+//! Structure elements point at page content through marked-content IDs
+//! (MCIDs), which the content stream attaches to text with `BDC`. Walking the
+//! tree gives the author's reading order and accessibility information such
+//! as alternate descriptions and replacement text.
+//!
+//! Based on PDF.js src/core/struct_tree.js.
+
+use super::content_stream::TextItem;
+use super::cycle_guard::CycleGuard;
+use super::error::{PDFError, PDFResult};
+use super::layout::{TextLayoutOptions, layout_text};
+use super::outline::decode_pdf_string;
+use super::parser::{PDFObject, Ref};
+use super::xref::XRef;
+use crate::log_target;
+use rustc_hash::FxHashMap;
+use std::collections::HashMap;
+
+/// Standard structure types that start a new block of text.
+const BLOCK_ROLES: &[&str] = &[
+    "Document",
+    "Part",
+    "Art",
+    "Sect",
+    "Div",
+    "BlockQuote",
+    "Caption",
+    "TOC",
+    "TOCI",
+    "Index",
+    "P",
+    "H",
+    "H1",
+    "H2",
+    "H3",
+    "H4",
+    "H5",
+    "H6",
+    "L",
+    "LI",
+    "Table",
+    "TR",
+    "THead",
+    "TBody",
+    "TFoot",
+    "Title",
+    "FENote",
+    "Note",
+    "Figure",
+    "Formula",
+    "Form",
+];
+
+/// A child of a structure element.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StructKid {
+    /// A nested structure element
+    Element(Box<StructElement>),
+
+    /// A marked-content sequence in a page's content stream
+    MarkedContent {
+        /// The marked-content ID
+        mcid: u32,
+
+        /// Page containing the content, if it could be resolved
+        page_index: Option<usize>,
+    },
+
+    /// A whole PDF object, such as an annotation or an XObject (/OBJR)
+    Object {
+        /// The referenced object
+        obj: Ref,
+
+        /// Page the object is drawn on, if it could be resolved
+        page_index: Option<usize>,
+    },
+}
+
+/// A structure element (/StructElem).
+#[derive(Debug, Clone, PartialEq)]
+pub struct StructElement {
+    /// Structure type as written in the file (/S), e.g. "H1" or a custom role
+    pub role: String,
+
+    /// Structure type after applying the /RoleMap
+    pub standard_role: String,
+
+    /// Element identifier (/ID)
+    pub id: Option<String>,
+
+    /// Title (/T)
+    pub title: Option<String>,
+
+    /// Language (/Lang)
+    pub lang: Option<String>,
+
+    /// Alternate description, e.g. of a figure (/Alt)
+    pub alt: Option<String>,
+
+    /// Replacement text for the element's content (/ActualText)
+    pub actual_text: Option<String>,
+
+    /// Expansion of an abbreviation (/E)
+    pub expansion: Option<String>,
+
+    /// Page the element's content is on, when given (/Pg)
+    pub page_index: Option<usize>,
+
+    /// Children, in logical order
+    pub children: Vec<StructKid>,
+}
+
+impl StructElement {
+    /// Returns whether the element is block-level (paragraph, heading, list
+    /// item, ...) rather than inline.
+    pub fn is_block(&self) -> bool {
+        BLOCK_ROLES.contains(&self.standard_role.as_str())
+    }
+
+    /// Returns the MCIDs on a page that belong to this element and its
+    /// descendants, in logical order.
+    pub fn mcids(&self, page_index: usize) -> Vec<u32> {
+        let mut mcids = Vec::new();
+        self.collect_mcids(page_index, &mut mcids);
+        mcids
+    }
+
+    fn collect_mcids(&self, page_index: usize, mcids: &mut Vec<u32>) {
+        for kid in &self.children {
+            match kid {
+                StructKid::Element(element) => element.collect_mcids(page_index, mcids),
+                StructKid::MarkedContent {
+                    mcid,
+                    page_index: Some(page),
+                } if *page == page_index => mcids.push(*mcid),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// The structure tree of a tagged PDF.
+///
+/// # Example
+/// ```no_run
+/// use pdf_x_core::core::PDFDocument;
+///
+/// let mut doc = PDFDocument::open_file("tagged.pdf", None, None).unwrap();
+/// if let Some(tree) = doc.struct_tree().unwrap() {
+///     for element in &tree.children {
+///         println!("{} ({:?})", element.standard_role, element.alt);
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StructTree {
+    /// Top-level structure elements
+    pub children: Vec<StructElement>,
+
+    /// Mapping of custom structure types to standard ones (/RoleMap)
+    pub role_map: HashMap<String, String>,
+}
+
+impl StructTree {
+    /// Reads a /StructTreeRoot dictionary.
+    ///
+    /// # Arguments
+    /// * `xref` - Cross-reference table for resolving indirect objects
+    /// * `root` - The /StructTreeRoot entry of the catalog
+    /// * `pages` - Map of page object references to page indices, used to
+    ///   resolve /Pg entries
+    pub fn parse(
+        xref: &mut XRef,
+        root: &PDFObject,
+        pages: &FxHashMap<(u32, u32), usize>,
+    ) -> PDFResult<Self> {
+        let root = match xref.fetch_if_ref(root)? {
+            PDFObject::Dictionary(dict) => dict,
+            _ => return Ok(StructTree::default()),
+        };
+
+        let mut role_map = HashMap::new();
+        if let Some(map) = root.get("RoleMap")
+            && let PDFObject::Dictionary(map) = xref.fetch_if_ref(map)?
+        {
+            for (role, target) in map {
+                if let PDFObject::Name(target) = target {
+                    role_map.insert(role, target);
+                }
+            }
+        }
+
+        let mut reader = TreeReader {
+            xref,
+            pages,
+            role_map: &role_map,
+            visited: CycleGuard::new(),
+        };
+        let mut children = Vec::new();
+        if let Some(kids) = root.get("K") {
+            for kid in reader.kids(kids, None, 0)? {
+                match kid {
+                    StructKid::Element(element) => children.push(*element),
+                    // Content can't hang off the root directly
+                    _ => {
+                        tracing::warn!(
+                            target: log_target::DOCUMENT,
+                            "Ignoring content kid of /StructTreeRoot"
+                        )
+                    }
+                }
+            }
+        }
+
+        Ok(StructTree { children, role_map })
+    }
+
+    /// Maps a structure type to a standard one through the /RoleMap.
+    pub fn standard_role(&self, role: &str) -> String {
+        standard_role(&self.role_map, role)
+    }
+
+    /// Returns the marked content on a page in logical order, with the
+    /// innermost element owning each MCID.
+    pub fn marked_content(&self, page_index: usize) -> Vec<(u32, &StructElement)> {
+        fn walk<'a>(
+            element: &'a StructElement,
+            page_index: usize,
+            out: &mut Vec<(u32, &'a StructElement)>,
+        ) {
+            for kid in &element.children {
+                match kid {
+                    StructKid::Element(child) => walk(child, page_index, out),
+                    StructKid::MarkedContent {
+                        mcid,
+                        page_index: Some(page),
+                    } if *page == page_index => out.push((*mcid, element)),
+                    _ => {}
+                }
+            }
+        }
+
+        let mut out = Vec::new();
+        for element in &self.children {
+            walk(element, page_index, &mut out);
+        }
+        out
+    }
+
+    /// Joins a page's text items in the logical order of the tree.
+    ///
+    /// Block-level elements (paragraphs, headings, list items, ...) start a
+    /// new line, and the text of each marked-content sequence is laid out
+    /// with [`layout_text`]. /ActualText replaces the content of its element.
+    /// Content outside the tree, such as running headers marked as
+    /// artifacts, is left out.
+    ///
+    /// # Arguments
+    /// * `page_index` - Page the items were extracted from
+    /// * `items` - Text items of the page, with their MCIDs
+    /// * `options` - Layout thresholds used within each marked-content sequence
+    pub fn page_text(
+        &self,
+        page_index: usize,
+        items: &[TextItem],
+        options: &TextLayoutOptions,
+    ) -> String {
+        let mut by_mcid: HashMap<u32, Vec<TextItem>> = HashMap::new();
+        for item in items {
+            if let Some(mcid) = item.mcid {
+                by_mcid.entry(mcid).or_default().push(item.clone());
+            }
+        }
+
+        let mut text = String::new();
+        for element in &self.children {
+            element_text(element, page_index, &mut by_mcid, options, &mut text);
+        }
+        text.trim_end().to_string()
+    }
+}
+
+/// Appends the text of an element to `out`.
+fn element_text(
+    element: &StructElement,
+    page_index: usize,
+    by_mcid: &mut HashMap<u32, Vec<TextItem>>,
+    options: &TextLayoutOptions,
+    out: &mut String,
+) {
+    let block = element.is_block();
+    if block {
+        start_line(out);
+    }
+
+    if let Some(actual_text) = &element.actual_text {
+        // Only emit the replacement if the element has content on this page
+        let on_page = element
+            .mcids(page_index)
+            .into_iter()
+            .any(|mcid| by_mcid.remove(&mcid).is_some());
+        if on_page {
+            out.push_str(actual_text);
+        }
+    } else {
+        for kid in &element.children {
+            match kid {
+                StructKid::Element(child) => element_text(child, page_index, by_mcid, options, out),
+                StructKid::MarkedContent {
+                    mcid,
+                    page_index: Some(page),
+                } if *page == page_index => {
+                    if let Some(items) = by_mcid.remove(mcid) {
+                        out.push_str(&layout_text(items, options));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    if block {
+        start_line(out);
+    }
+}
+
+/// Ends the current line, unless it's empty.
+fn start_line(out: &mut String) {
+    if !out.is_empty() && !out.ends_with('\n') {
+        out.push('\n');
+    }
+}
+
+/// Maps a structure type to a standard one through a role map.
+///
+/// A chain of mappings can't be longer than the map without going round a
+/// cycle, so that many steps end every walk.
+fn standard_role(role_map: &HashMap<String, String>, role: &str) -> String {
+    let mut role = role;
+    for _ in 0..role_map.len() {
+        match role_map.get(role) {
+            Some(target) if target != role => role = target,
+            _ => break,
+        }
+    }
+    role.to_string()
+}
+
+/// State shared while reading the tree.
+struct TreeReader<'a> {
+    xref: &'a mut XRef,
+    pages: &'a FxHashMap<(u32, u32), usize>,
+    role_map: &'a HashMap<String, String>,
+
+    /// Structure elements already read, to break reference cycles
+    visited: CycleGuard,
+}
+
+impl TreeReader<'_> {
+    /// Reads a /K entry: a single kid or an array of kids.
+    fn kids(
+        &mut self,
+        kids: &PDFObject,
+        page_index: Option<usize>,
+        depth: usize,
+    ) -> PDFResult<Vec<StructKid>> {
+        let kids = match kids {
+            PDFObject::Ref(_) => match self.xref.fetch_if_ref(kids)? {
+                // An array stored indirectly
+                PDFObject::Array(arr) => arr.into_iter().map(|kid| *kid).collect(),
+                _ => vec![kids.clone()],
+            },
+            PDFObject::Array(arr) => arr.iter().map(|kid| (**kid).clone()).collect(),
+            other => vec![other.clone()],
+        };
+
+        let mut result = Vec::new();
+        for kid in kids {
+            if let Some(kid) = self.kid(&kid, page_index, depth)? {
+                result.push(kid);
+            }
+        }
+        Ok(result)
+    }
+
+    /// Reads one kid: an MCID, a marked-content reference, an object
+    /// reference or a structure element.
+    fn kid(
+        &mut self,
+        kid: &PDFObject,
+        page_index: Option<usize>,
+        depth: usize,
+    ) -> PDFResult<Option<StructKid>> {
+        if let PDFObject::Number(mcid) = kid {
+            return Ok(Some(StructKid::MarkedContent {
+                mcid: mcid.max(0.0) as u32,
+                page_index,
+            }));
+        }
+
+        if let PDFObject::Ref(r) = kid
+            && !self.visited.visit(*r)
+        {
+            tracing::warn!(
+                target: log_target::DOCUMENT,
+                "Structure tree cycle at object {} {}",
+                r.num, r.generation
+            );
+            return Ok(None);
+        }
+        let dict = match self.xref.fetch_if_ref(kid)? {
+            PDFObject::Dictionary(dict) => dict,
+            _ => return Ok(None),
+        };
+        let page_index = self.page(dict.get("Pg")).or(page_index);
+
+        match dict.get("Type") {
+            Some(PDFObject::Name(t)) if t == "MCR" => {
+                // Content in a form XObject (/Stm) isn't extracted as page text
+                if dict.contains_key("Stm") {
+                    return Ok(None);
+                }
+                Ok(match dict.get("MCID") {
+                    Some(PDFObject::Number(mcid)) => Some(StructKid::MarkedContent {
+                        mcid: mcid.max(0.0) as u32,
+                        page_index,
+                    }),
+                    _ => None,
+                })
+            }
+            Some(PDFObject::Name(t)) if t == "OBJR" => Ok(match dict.get("Obj") {
+                Some(PDFObject::Ref(obj)) => Some(StructKid::Object {
+                    obj: *obj,
+                    page_index,
+                }),
+                _ => None,
+            }),
+            _ => {
+                let max_depth = self.xref.limits().max_object_depth;
+                if depth >= max_depth {
+                    return Err(PDFError::limit_exceeded("structure tree depth", max_depth));
+                }
+                self.element(&dict, page_index, depth)
+                    .map(|element| element.map(|element| StructKid::Element(Box::new(element))))
+            }
+        }
+    }
+
+    /// Reads a structure element dictionary.
+    fn element(
+        &mut self,
+        dict: &HashMap<String, PDFObject>,
+        page_index: Option<usize>,
+        depth: usize,
+    ) -> PDFResult<Option<StructElement>> {
+        let role = match dict.get("S") {
+            Some(PDFObject::Name(role)) => role.clone(),
+            _ => return Ok(None),
+        };
+        let text = |key: &str| match dict.get(key) {
+            Some(PDFObject::String(bytes)) | Some(PDFObject::HexString(bytes)) => {
+                Some(decode_pdf_string(bytes))
+            }
+            _ => None,
+        };
+
+        let children = match dict.get("K") {
+            Some(kids) => self.kids(kids, page_index, depth + 1)?,
+            None => Vec::new(),
+        };
+
+        Ok(Some(StructElement {
+            standard_role: standard_role(self.role_map, &role),
+            role,
+            id: text("ID"),
+            title: text("T"),
+            lang: text("Lang"),
+            alt: text("Alt"),
+            actual_text: text("ActualText"),
+            expansion: text("E"),
+            page_index,
+            children,
+        }))
+    }
+
+    /// Resolves a /Pg entry to a page index.
+    fn page(&self, page: Option<&PDFObject>) -> Option<usize> {
+        match page {
+            Some(PDFObject::Ref(r)) => self.pages.get(&(r.num, r.generation)).copied(),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn element(role: &str, children: Vec<StructKid>) -> StructElement {
+        StructElement {
+            role: role.to_string(),
+            standard_role: role.to_string(),
+            id: None,
+            title: None,
+            lang: None,
+            alt: None,
+            actual_text: None,
+            expansion: None,
+            page_index: Some(0),
+            children,
+        }
+    }
+
+    fn mc(mcid: u32) -> StructKid {
+        StructKid::MarkedContent {
+            mcid,
+            page_index: Some(0),
+        }
+    }
+
+    fn item(text: &str, x: f64, y: f64, mcid: Option<u32>) -> TextItem {
+        TextItem {
+            text: text.to_string(),
+            font_name: None,
+            font_size: Some(12.0),
+            position: Some((x, y)),
+            rendering_mode: None,
+            width: None,
+            mcid,
+            char_spans: None,
+            vertical_extent: None,
+            glyphs: None,
+            font_family: None,
+            bold: false,
+            italic: false,
+            fill_color: None,
+        }
+    }
+
+    #[test]
+    fn test_standard_role() {
+        let role_map = HashMap::from([
+            ("Heading".to_string(), "Chapter".to_string()),
+            ("Chapter".to_string(), "H1".to_string()),
+            ("Loop".to_string(), "Loop".to_string()),
+        ]);
+        assert_eq!(standard_role(&role_map, "Heading"), "H1");
+        assert_eq!(standard_role(&role_map, "Loop"), "Loop");
+        assert_eq!(standard_role(&role_map, "P"), "P");
+    }
+
+    #[test]
+    fn test_page_text_follows_structure() {
+        // The sidebar is drawn first and higher on the page, but the tree
+        // puts it after the body
+        let mut abbr = element("Span", vec![mc(3)]);
+        abbr.actual_text = Some("for example".to_string());
+        let tree = StructTree {
+            children: vec![element(
+                "Document",
+                vec![
+                    StructKid::Element(Box::new(element("H1", vec![mc(1)]))),
+                    StructKid::Element(Box::new(element(
+                        "P",
+                        vec![mc(2), StructKid::Element(Box::new(abbr))],
+                    ))),
+                    StructKid::Element(Box::new(element("Aside", vec![mc(0)]))),
+                ],
+            )],
+            role_map: HashMap::new(),
+        };
+        let items = vec![
+            item("Sidebar", 400.0, 750.0, Some(0)),
+            item("Title", 72.0, 700.0, Some(1)),
+            item("Body text, ", 72.0, 650.0, Some(2)),
+            item("e.g.", 140.0, 650.0, Some(3)),
+            item("Page 1", 300.0, 30.0, None),
+        ];
+
+        assert_eq!(
+            tree.page_text(0, &items, &TextLayoutOptions::default()),
+            "Title\nBody text, for example\nSidebar"
+        );
+        let mcids: Vec<u32> = tree
+            .marked_content(0)
+            .iter()
+            .map(|(mcid, _)| *mcid)
+            .collect();
+        assert_eq!(mcids, vec![1, 2, 3, 0]);
+        assert!(tree.marked_content(1).is_empty());
+    }
+}
@@ -0,0 +1,588 @@
+//! Find-and-replace of text in page content streams.
+//!
+//! Only simple runs are handled: each text-showing operation (Tj, ', ", TJ)
+//! is matched on its own, so text split across several operations won't
+//! match. The replacement is re-encoded with the run's font and, when the
+//! font's widths are known, followed by a TJ adjustment that keeps the
+//! run's original advance so the rest of the line stays in place.
+//!
+//! Rewriting works on the raw content bytes: only the matched operations
+//! are replaced, everything else is copied through unchanged.
+
+use crate::core::error::{PDFError, PDFResult};
+use crate::core::font::{Font, FontType};
+use crate::core::lexer::{Lexer, Token};
+use crate::core::stream::Stream;
+use regex::Regex;
+use rustc_hash::FxHashMap;
+use std::collections::HashMap;
+
+/// Result of a text replacement.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TextReplaceReport {
+    /// Number of text operations that were rewritten
+    pub replaced: usize,
+
+    /// Number of matching operations left unchanged because the replacement
+    /// can't be encoded in the run's font
+    pub skipped: usize,
+}
+
+/// An element of a TJ array.
+#[derive(Debug, Clone)]
+pub(crate) enum TjElement {
+    Text(Vec<u8>),
+    Adjust(f64),
+}
+
+/// An operand of a content stream operator.
+#[derive(Debug, Clone)]
+pub(crate) enum Operand {
+    Number(f64),
+    Name(String),
+    String(Vec<u8>),
+    Array(Vec<TjElement>),
+    Other,
+}
+
+/// An array or dictionary operand being read.
+pub(crate) struct Composite {
+    pub(crate) start: usize,
+    pub(crate) depth: usize,
+    pub(crate) elements: Vec<TjElement>,
+    pub(crate) is_tj_array: bool,
+}
+
+/// Rewrites text-showing operations whose text matches a pattern.
+///
+/// The replacer keeps text state (font, spacing) between calls so that the
+/// content streams of a page can be processed in order.
+pub struct TextReplacer<'a> {
+    /// The pattern to find
+    pattern: &'a Regex,
+
+    /// The replacement, which may refer to capture groups ("$1")
+    replacement: &'a str,
+
+    /// The page's fonts by resource name
+    fonts: &'a FxHashMap<String, Font>,
+
+    /// Unicode to character code maps, built per font on first use
+    encoders: HashMap<String, HashMap<char, u8>>,
+
+    /// Current font resource name (Tf)
+    font_name: Option<String>,
+
+    /// Current font size (Tf)
+    font_size: f64,
+
+    /// Current character spacing (Tc)
+    char_spacing: f64,
+
+    /// Current word spacing (Tw)
+    word_spacing: f64,
+
+    /// Counts of rewritten and skipped operations
+    report: TextReplaceReport,
+}
+
+impl<'a> TextReplacer<'a> {
+    /// Creates a replacer for one page.
+    ///
+    /// # Arguments
+    /// * `pattern` - The pattern to find
+    /// * `replacement` - The replacement text; "$1"-style group references
+    ///   are expanded as in `Regex::replace_all`
+    /// * `fonts` - The page's fonts by resource name
+    pub fn new(
+        pattern: &'a Regex,
+        replacement: &'a str,
+        fonts: &'a FxHashMap<String, Font>,
+    ) -> Self {
+        Self {
+            pattern,
+            replacement,
+            fonts,
+            encoders: HashMap::new(),
+            font_name: None,
+            font_size: 0.0,
+            char_spacing: 0.0,
+            word_spacing: 0.0,
+            report: TextReplaceReport::default(),
+        }
+    }
+
+    /// Returns the counts accumulated so far.
+    pub fn report(&self) -> &TextReplaceReport {
+        &self.report
+    }
+
+    /// Rewrites one decoded content stream.
+    ///
+    /// # Arguments
+    /// * `data` - The decoded content stream
+    ///
+    /// # Returns
+    /// The rewritten content, or `None` if nothing was replaced
+    pub fn replace_in_stream(&mut self, data: &[u8]) -> PDFResult<Option<Vec<u8>>> {
+        // A trailing newline guarantees every token is followed by a
+        // lookahead byte, so token ends can be read off the lexer position
+        let mut padded = data.to_vec();
+        padded.push(b'\n');
+        let mut lexer = Lexer::new(Box::new(Stream::from_bytes(padded.clone())))?;
+
+        let mut edits: Vec<(usize, usize, Vec<u8>)> = Vec::new();
+        let mut operands: Vec<(Operand, usize)> = Vec::new();
+        let mut composite: Option<Composite> = None;
+        let mut prev_end = 0;
+
+        loop {
+            let start = skip_whitespace(&padded, prev_end);
+            let token = lexer.get_object()?;
+            let end = lexer.get_position().saturating_sub(1);
+            prev_end = end;
+
+            if let Some(current) = composite.as_mut() {
+                match token {
+                    Token::ArrayStart | Token::DictStart => {
+                        current.depth += 1;
+                        current.is_tj_array = false;
+                    }
+                    Token::ArrayEnd | Token::DictEnd => {
+                        current.depth -= 1;
+                        if current.depth == 0 {
+                            let done = composite.take().unwrap();
+                            let operand = if done.is_tj_array {
+                                Operand::Array(done.elements)
+                            } else {
+                                Operand::Other
+                            };
+                            operands.push((operand, done.start));
+                        }
+                    }
+                    Token::String(bytes) | Token::HexString(bytes) if current.depth == 1 => {
+                        current.elements.push(TjElement::Text(bytes));
+                    }
+                    Token::Number(n) if current.depth == 1 => {
+                        current.elements.push(TjElement::Adjust(n));
+                    }
+                    Token::EOF => break,
+                    _ => current.is_tj_array = false,
+                }
+                continue;
+            }
+
+            match token {
+                Token::EOF => break,
+                Token::ArrayStart | Token::DictStart => {
+                    composite = Some(Composite {
+                        start,
+                        depth: 1,
+                        elements: Vec::new(),
+                        is_tj_array: token == Token::ArrayStart,
+                    });
+                }
+                Token::Number(n) => operands.push((Operand::Number(n), start)),
+                Token::Name(name) => operands.push((Operand::Name(name), start)),
+                Token::String(bytes) | Token::HexString(bytes) => {
+                    operands.push((Operand::String(bytes), start))
+                }
+                Token::Command(cmd) => {
+                    if cmd == "ID" {
+                        // Skip inline image data up to the EI operator
+                        prev_end = find_inline_image_end(&padded, end);
+                        lexer.set_position(prev_end)?;
+                    } else if let Some(edit) = self.process_operator(&cmd, &operands, end) {
+                        edits.push(edit);
+                    }
+                    operands.clear();
+                }
+                _ => operands.push((Operand::Other, start)),
+            }
+        }
+
+        if edits.is_empty() {
+            return Ok(None);
+        }
+
+        let mut output = Vec::with_capacity(data.len());
+        let mut pos = 0;
+        for (start, end, bytes) in edits {
+            output.extend_from_slice(&data[pos..start]);
+            output.extend_from_slice(&bytes);
+            pos = end.min(data.len());
+        }
+        output.extend_from_slice(&data[pos..]);
+        Ok(Some(output))
+    }
+
+    /// Updates text state for an operator and rewrites it if it shows
+    /// matching text.
+    ///
+    /// Returns the (start, end, bytes) edit for a rewritten operation.
+    fn process_operator(
+        &mut self,
+        cmd: &str,
+        operands: &[(Operand, usize)],
+        end: usize,
+    ) -> Option<(usize, usize, Vec<u8>)> {
+        let number = |i: usize| match operands.get(i) {
+            Some((Operand::Number(n), _)) => Some(*n),
+            _ => None,
+        };
+
+        match cmd {
+            "Tf" => {
+                if let Some((Operand::Name(name), _)) = operands.first() {
+                    self.font_name = Some(name.clone());
+                }
+                if let Some(size) = number(1) {
+                    self.font_size = size;
+                }
+                None
+            }
+            "Tc" => {
+                self.char_spacing = number(0).unwrap_or(self.char_spacing);
+                None
+            }
+            "Tw" => {
+                self.word_spacing = number(0).unwrap_or(self.word_spacing);
+                None
+            }
+            "Tj" | "'" => match operands.last() {
+                Some((Operand::String(bytes), start)) => {
+                    let elements = [TjElement::Text(bytes.clone())];
+                    let prefix = if cmd == "'" { "T* " } else { "" };
+                    self.rewrite(&elements, prefix)
+                        .map(|bytes| (*start, end, bytes))
+                }
+                _ => None,
+            },
+            "\"" => match (number(0), number(1), operands.get(2)) {
+                (Some(aw), Some(ac), Some((Operand::String(bytes), _))) => {
+                    self.word_spacing = aw;
+                    self.char_spacing = ac;
+                    let elements = [TjElement::Text(bytes.clone())];
+                    let prefix = format!("{} Tw {} Tc T* ", fmt_num(aw), fmt_num(ac));
+                    let start = operands[0].1;
+                    self.rewrite(&elements, &prefix)
+                        .map(|bytes| (start, end, bytes))
+                }
+                _ => None,
+            },
+            "TJ" => match operands.last() {
+                Some((Operand::Array(elements), start)) => {
+                    self.rewrite(elements, "").map(|bytes| (*start, end, bytes))
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Builds the replacement for one text-showing operation, if its text
+    /// matches.
+    ///
+    /// # Arguments
+    /// * `elements` - The strings and adjustments shown by the operation
+    /// * `prefix` - Operators to emit before the new text (for ' and ")
+    fn rewrite(&mut self, elements: &[TjElement], prefix: &str) -> Option<Vec<u8>> {
+        let font = self
+            .font_name
+            .as_ref()
+            .and_then(|name| self.fonts.get(name));
+
+        let mut old_codes = Vec::new();
+        let mut adjustments = 0.0;
+        for element in elements {
+            match element {
+                TjElement::Text(bytes) => old_codes.extend(self.decode_codes(font, bytes)),
+                TjElement::Adjust(n) => adjustments += n,
+            }
+        }
+
+        let text: String = old_codes
+            .iter()
+            .map(|&code| self.code_to_char(font, code))
+            .collect();
+        if !self.pattern.is_match(&text) {
+            return None;
+        }
+        let replaced = self
+            .pattern
+            .replace_all(&text, self.replacement)
+            .into_owned();
+        if replaced == text {
+            return None;
+        }
+
+        let new_codes = match self.encode(font, &replaced) {
+            Some(codes) => codes,
+            None => {
+                self.report.skipped += 1;
+                return None;
+            }
+        };
+        self.report.replaced += 1;
+
+        // Keep the original advance: TJ numbers are subtracted from the
+        // advance, in thousandths of text space
+        let adjustment = match font {
+            Some(font) if self.font_size != 0.0 => {
+                let old_advance = self.advance(font, &old_codes) - adjustments;
+                let new_advance = self.advance(font, &new_codes);
+                new_advance - old_advance
+            }
+            _ => 0.0,
+        };
+
+        let string = literal_string(&new_codes.iter().map(|&c| c as u8).collect::<Vec<_>>());
+        let mut out = String::from(prefix);
+        if adjustment.abs() < 0.5 {
+            out.push_str(&format!("{} Tj", string));
+        } else {
+            out.push_str(&format!("[{} {}] TJ", string, fmt_num(adjustment)));
+        }
+        Some(out.into_bytes())
+    }
+
+    /// Splits string bytes into character codes.
+    fn decode_codes(&self, font: Option<&Font>, bytes: &[u8]) -> Vec<u16> {
+        match font {
+            Some(font) => {
+                let mut codes = Vec::with_capacity(bytes.len());
+                let mut offset = 0;
+                while offset < bytes.len() {
+                    let (code, length) = font.read_char_code(bytes, offset);
+                    codes.push(code as u16);
+                    offset += length;
+                }
+                codes
+            }
+            None => bytes.iter().map(|&b| b as u16).collect(),
+        }
+    }
+
+    /// Maps a character code to Unicode, using Latin-1 without a font.
+    fn code_to_char(&self, font: Option<&Font>, code: u16) -> char {
+        match font {
+            Some(font) => font.char_code_to_unicode(code),
+            None => char::from_u32(code as u32).unwrap_or('\u{FFFD}'),
+        }
+    }
+
+    /// Encodes text with the current font.
+    ///
+    /// Returns `None` if the font is a Type0 or CID font or lacks a code for
+    /// one of the characters.
+    fn encode(&mut self, font: Option<&Font>, text: &str) -> Option<Vec<u16>> {
+        let font = match font {
+            Some(font) => font,
+            None => {
+                return text
+                    .chars()
+                    .map(|c| ((c as u32) <= 0xFF).then_some(c as u16))
+                    .collect();
+            }
+        };
+        if font.font_type().is_cid_font() || *font.font_type() == FontType::Type0 {
+            return None;
+        }
+
+        let font_name = self.font_name.clone().unwrap_or_default();
+        let encoder = self.encoders.entry(font_name).or_insert_with(|| {
+            let mut map = HashMap::new();
+            for code in 0..=255u8 {
+                map.entry(font.char_code_to_unicode(code as u16))
+                    .or_insert(code);
+            }
+            map
+        });
+
+        text.chars()
+            .map(|c| encoder.get(&c).map(|&code| code as u16))
+            .collect()
+    }
+
+    /// Computes the advance of codes in thousandths of text space,
+    /// including character and word spacing.
+    fn advance(&self, font: &Font, codes: &[u16]) -> f64 {
+        let spacing_scale = 1000.0 / self.font_size;
+        codes
+            .iter()
+            .map(|&code| {
+                let mut width = font.get_char_width(code) + self.char_spacing * spacing_scale;
+                if code == 32 {
+                    width += self.word_spacing * spacing_scale;
+                }
+                width
+            })
+            .sum()
+    }
+}
+
+/// Returns the index of the first byte at or after `pos` that is not
+/// whitespace or part of a comment.
+pub(crate) fn skip_whitespace(data: &[u8], mut pos: usize) -> usize {
+    while pos < data.len() {
+        match data[pos] {
+            b' ' | b'\t' | b'\r' | b'\n' | b'\x0C' | b'\0' => pos += 1,
+            b'%' => {
+                while pos < data.len() && data[pos] != b'\n' && data[pos] != b'\r' {
+                    pos += 1;
+                }
+            }
+            _ => break,
+        }
+    }
+    pos
+}
+
+/// Finds the end of an inline image's data, just past the EI operator.
+///
+/// # Arguments
+/// * `data` - The content stream
+/// * `id_end` - Position just past the ID operator
+pub(crate) fn find_inline_image_end(data: &[u8], id_end: usize) -> usize {
+    let is_space = |b: u8| matches!(b, b' ' | b'\t' | b'\r' | b'\n' | b'\x0C' | b'\0');
+    let mut pos = id_end + 1;
+    while pos + 2 < data.len() {
+        if is_space(data[pos])
+            && data[pos + 1] == b'E'
+            && data[pos + 2] == b'I'
+            && data.get(pos + 3).is_none_or(|&b| is_space(b))
+        {
+            return pos + 3;
+        }
+        pos += 1;
+    }
+    data.len()
+}
+
+/// Writes bytes as a literal string.
+pub(crate) fn literal_string(bytes: &[u8]) -> String {
+    let mut out = String::from("(");
+    for &byte in bytes {
+        match byte {
+            b'(' | b')' | b'\\' => {
+                out.push('\\');
+                out.push(byte as char);
+            }
+            0x20..=0x7E => out.push(byte as char),
+            _ => out.push_str(&format!("\\{:03o}", byte)),
+        }
+    }
+    out.push(')');
+    out
+}
+
+/// Formats a number for a content stream.
+pub(crate) fn fmt_num(value: f64) -> String {
+    let rounded = (value * 1000.0).round() / 1000.0;
+    if rounded.fract() == 0.0 {
+        format!("{}", rounded as i64)
+    } else {
+        format!("{}", rounded)
+    }
+}
+
+/// Replaces matching text in one decoded content stream, without fonts.
+///
+/// Strings are treated as Latin-1 and no spacing adjustments are made.
+/// Mostly useful for content whose fonts are unavailable.
+///
+/// # Arguments
+/// * `data` - The decoded content stream
+/// * `pattern` - The pattern to find
+/// * `replacement` - The replacement text
+///
+/// # Returns
+/// The rewritten content (unchanged if nothing matched) and the counts
+pub fn replace_text_in_content(
+    data: &[u8],
+    pattern: &Regex,
+    replacement: &str,
+) -> PDFResult<(Vec<u8>, TextReplaceReport)> {
+    let fonts = FxHashMap::default();
+    let mut replacer = TextReplacer::new(pattern, replacement, &fonts);
+    let output = replacer
+        .replace_in_stream(data)
+        .map_err(|e| PDFError::Generic(format!("Failed to rewrite content stream: {}", e)))?
+        .unwrap_or_else(|| data.to_vec());
+    Ok((output, replacer.report().clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replace(content: &str, pattern: &str, replacement: &str) -> (String, TextReplaceReport) {
+        let regex = Regex::new(pattern).unwrap();
+        let (data, report) =
+            replace_text_in_content(content.as_bytes(), &regex, replacement).unwrap();
+        (String::from_utf8_lossy(&data).into_owned(), report)
+    }
+
+    #[test]
+    fn test_replace_tj() {
+        let (content, report) = replace(
+            "BT /F1 12 Tf 10 10 Td (Dear {name},) Tj ET",
+            r"\{name\}",
+            "Jane",
+        );
+        assert_eq!(content, "BT /F1 12 Tf 10 10 Td (Dear Jane,) Tj ET");
+        assert_eq!(report.replaced, 1);
+    }
+
+    #[test]
+    fn test_replace_tj_array_and_groups() {
+        let (content, _) = replace(
+            "BT [(Invoice ) -20 (2023-01)] TJ ET",
+            r"(\d{4})-(\d{2})",
+            "$2/$1",
+        );
+        assert_eq!(content, "BT (Invoice 01/2023) Tj ET");
+    }
+
+    #[test]
+    fn test_replace_quote_operators() {
+        let (content, report) = replace("BT (a) ' 1 2 (a) \" ET", "a", "b");
+        assert_eq!(content, "BT T* (b) Tj 1 Tw 2 Tc T* (b) Tj ET");
+        assert_eq!(report.replaced, 2);
+    }
+
+    #[test]
+    fn test_no_match_leaves_content_untouched() {
+        let original = "q 1 0 0 1 0 0 cm BT (hello) Tj ET Q";
+        let (content, report) = replace(original, "xyz", "abc");
+        assert_eq!(content, original);
+        assert_eq!(report, TextReplaceReport::default());
+    }
+
+    #[test]
+    fn test_unencodable_replacement_is_skipped() {
+        let (content, report) = replace("BT (price) Tj ET", "price", "€5");
+        assert_eq!(content, "BT (price) Tj ET");
+        assert_eq!(report.skipped, 1);
+    }
+
+    #[test]
+    fn test_skips_inline_images_and_dicts() {
+        let original = b"/P <</MCID 0>> BDC BI /W 1 /H 1 ID \x00(x)\xff EI EMC BT (x) Tj ET";
+        let regex = Regex::new("x").unwrap();
+        let (content, report) = replace_text_in_content(original, &regex, "y").unwrap();
+
+        assert_eq!(report.replaced, 1);
+        assert!(content.ends_with(b"BT (y) Tj ET"));
+        // The image data is copied through untouched
+        assert_eq!(
+            &content[..original.len() - 12],
+            &original[..original.len() - 12]
+        );
+    }
+
+    #[test]
+    fn test_literal_string_escapes() {
+        assert_eq!(literal_string(b"a(b)\\\xe9"), "(a\\(b\\)\\\\\\351)");
+    }
+}
@@ -0,0 +1,636 @@
+//! Table of contents pages generated from the document outline.
+//!
+//! `PDFDocument::insert_toc()` lays out the outline as a list of entries with
+//! dot leaders and page labels, prepends the resulting pages to the page tree
+//! and links each entry to its target page. The new pages are labelled with
+//! lowercase roman numerals and the existing page labels are shifted, so the
+//! numbers shown in the table keep matching the document's own pages.
+//!
+//! Text is drawn with the standard 14 fonts and measured with an average
+//! glyph width, as for form field appearances.
+
+use crate::core::delta::{BaseObjectFetcher, Command, DeltaLayer};
+use crate::core::error::{PDFError, PDFResult};
+use crate::core::form::{content_string, fmt_num, standard_font};
+use crate::core::parser::{PDFObject, Ref};
+use smallvec::SmallVec;
+use std::collections::HashMap;
+
+/// Average glyph width as a fraction of the font size.
+const AVERAGE_GLYPH_WIDTH: f64 = 0.5;
+
+/// Distance between leader characters as a fraction of the font size.
+const LEADER_SPACING: f64 = 0.5;
+
+/// Space kept clear around the leaders, as a fraction of the font size.
+const LEADER_GAP: f64 = 0.75;
+
+/// Portion of the font size below the baseline covered by an entry's link.
+const DESCENT: f64 = 0.25;
+
+/// Font resource names used in the generated content streams.
+const ENTRY_FONT: &str = "F1";
+const TITLE_FONT: &str = "F2";
+
+/// Appearance of a generated table of contents.
+///
+/// # Example
+/// ```no_run
+/// use pdf_x_core::core::{PDFDocument, TocOptions};
+///
+/// let mut doc = PDFDocument::open_file("packet.pdf", None, None).unwrap();
+/// let options = TocOptions {
+///     title: "Packet Contents".to_string(),
+///     font: "Times-Roman".to_string(),
+///     title_font: "Times-Bold".to_string(),
+///     ..TocOptions::default()
+/// };
+/// let toc_pages = doc.insert_toc(&options).unwrap();
+/// println!("Added {} contents page(s)", toc_pages);
+/// let bytes = doc.save_incremental().unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct TocOptions {
+    /// Heading shown at the top of the first page
+    pub title: String,
+
+    /// Standard 14 font for the entries (e.g. "Helvetica")
+    pub font: String,
+
+    /// Standard 14 font for the heading
+    pub title_font: String,
+
+    /// Font size of the entries
+    pub font_size: f64,
+
+    /// Font size of the heading
+    pub title_font_size: f64,
+
+    /// Character repeated between an entry and its page number; `None`
+    /// leaves the space empty
+    pub leader: Option<char>,
+
+    /// Horizontal indent per outline level
+    pub indent: f64,
+
+    /// Distance between baselines as a multiple of the font size
+    pub line_spacing: f64,
+
+    /// Margin on all four sides
+    pub margin: f64,
+
+    /// Deepest outline level to include (0 = top level only); `None`
+    /// includes every level
+    pub max_depth: Option<usize>,
+
+    /// Page width and height; `None` uses the size of the first page
+    pub page_size: Option<(f64, f64)>,
+
+    /// Label the new pages i, ii, ... and shift the existing page labels
+    pub label_pages: bool,
+}
+
+impl Default for TocOptions {
+    fn default() -> Self {
+        TocOptions {
+            title: "Contents".to_string(),
+            font: "Helvetica".to_string(),
+            title_font: "Helvetica-Bold".to_string(),
+            font_size: 11.0,
+            title_font_size: 18.0,
+            leader: Some('.'),
+            indent: 18.0,
+            line_spacing: 1.6,
+            margin: 72.0,
+            max_depth: None,
+            page_size: None,
+            label_pages: true,
+        }
+    }
+}
+
+/// One line of the table of contents.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct TocEntry {
+    /// Outline depth (0 = top level)
+    pub level: usize,
+
+    /// Text of the entry
+    pub title: String,
+
+    /// Label of the target page, `None` for entries without a page
+    pub page_label: Option<String>,
+
+    /// Target page object
+    pub target: Option<Ref>,
+}
+
+/// Content of one generated page.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct TocPage {
+    /// Content stream data
+    pub content: Vec<u8>,
+
+    /// Link rectangles and their target pages
+    pub links: Vec<([f64; 4], Ref)>,
+}
+
+/// Lays out the entries on as many pages as needed.
+///
+/// # Arguments
+/// * `entries` - Lines in reading order
+/// * `options` - Fonts, sizes and spacing
+/// * `width`, `height` - Page size
+pub(crate) fn layout_toc(
+    entries: &[TocEntry],
+    options: &TocOptions,
+    width: f64,
+    height: f64,
+) -> Vec<TocPage> {
+    let font_size = options.font_size;
+    let line_height = font_size * options.line_spacing;
+    let right = width - options.margin;
+    let bottom = options.margin;
+
+    let mut pages = Vec::new();
+    let mut content = String::new();
+    let mut links = Vec::new();
+
+    // Heading on the first page
+    let mut y = height - options.margin - options.title_font_size;
+    content.push_str(&format!(
+        "BT\n/{} {} Tf\n1 0 0 1 {} {} Tm {} Tj\n",
+        TITLE_FONT,
+        fmt_num(options.title_font_size),
+        fmt_num(options.margin),
+        fmt_num(y),
+        content_string(&options.title)
+    ));
+    content.push_str(&format!("/{} {} Tf\n", ENTRY_FONT, fmt_num(font_size)));
+    y -= options.title_font_size * options.line_spacing;
+
+    for entry in entries {
+        if y < bottom {
+            content.push_str("ET\n");
+            pages.push(TocPage {
+                content: std::mem::take(&mut content).into_bytes(),
+                links: std::mem::take(&mut links),
+            });
+            y = height - options.margin - font_size;
+            content.push_str(&format!("BT\n/{} {} Tf\n", ENTRY_FONT, fmt_num(font_size)));
+        }
+
+        let x = options.margin + entry.level as f64 * options.indent;
+        let label = entry.page_label.as_deref().unwrap_or("");
+        let label_x = right - text_width(label, font_size);
+        let gap = font_size * LEADER_GAP;
+
+        // Shorten titles that would run into the page number
+        let max_chars = ((label_x - gap - x) / (font_size * AVERAGE_GLYPH_WIDTH)).max(0.0) as usize;
+        let title = truncate(&entry.title, max_chars);
+        let title_end = x + text_width(&title, font_size);
+
+        content.push_str(&format!(
+            "1 0 0 1 {} {} Tm {} Tj\n",
+            fmt_num(x),
+            fmt_num(y),
+            content_string(&title)
+        ));
+
+        if entry.page_label.is_some() {
+            content.push_str(&format!(
+                "1 0 0 1 {} {} Tm {} Tj\n",
+                fmt_num(label_x),
+                fmt_num(y),
+                content_string(label)
+            ));
+
+            // Leaders sit on a fixed grid so they line up from row to row
+            if let Some(leader) = options.leader {
+                let spacing = font_size * LEADER_SPACING;
+                let leader = content_string(&leader.to_string());
+                let mut dot_x = ((title_end + gap) / spacing).ceil() * spacing;
+                while dot_x + spacing <= label_x - gap {
+                    content.push_str(&format!(
+                        "1 0 0 1 {} {} Tm {} Tj\n",
+                        fmt_num(dot_x),
+                        fmt_num(y),
+                        leader
+                    ));
+                    dot_x += spacing;
+                }
+            }
+        }
+
+        if let Some(target) = entry.target {
+            links.push(([x, y - font_size * DESCENT, right, y + font_size], target));
+        }
+        y -= line_height;
+    }
+
+    content.push_str("ET\n");
+    pages.push(TocPage {
+        content: content.into_bytes(),
+        links,
+    });
+    pages
+}
+
+/// Estimated width of a string.
+fn text_width(text: &str, font_size: f64) -> f64 {
+    text.chars().count() as f64 * font_size * AVERAGE_GLYPH_WIDTH
+}
+
+/// Shortens text to at most `max_chars` characters, ending with "...".
+fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let kept: String = text.chars().take(max_chars.saturating_sub(3)).collect();
+    format!("{}...", kept.trim_end())
+}
+
+/// Builds the /PageLabels tree after `toc_pages` pages were prepended.
+///
+/// The new pages use lowercase roman numerals and every existing range
+/// moves back by `toc_pages`; documents without labels get decimal numbers
+/// starting at 1 for their first original page.
+pub(crate) fn shifted_page_labels(existing: &[(usize, PDFObject)], toc_pages: usize) -> PDFObject {
+    let label = |style: &str| {
+        let mut dict = HashMap::new();
+        dict.insert("S".to_string(), PDFObject::Name(style.to_string()));
+        PDFObject::Dictionary(dict)
+    };
+
+    let mut nums: SmallVec<[Box<PDFObject>; 4]> = SmallVec::new();
+    nums.push(Box::new(PDFObject::Number(0.0)));
+    nums.push(Box::new(label("r")));
+
+    // Pages before the first range (if it doesn't start at 0) are decimal
+    if existing.first().is_none_or(|(start, _)| *start != 0) {
+        nums.push(Box::new(PDFObject::Number(toc_pages as f64)));
+        nums.push(Box::new(label("D")));
+    }
+    for (start, dict) in existing {
+        nums.push(Box::new(PDFObject::Number((start + toc_pages) as f64)));
+        nums.push(Box::new(dict.clone()));
+    }
+
+    let mut tree = HashMap::new();
+    tree.insert("Nums".to_string(), PDFObject::Array(nums));
+    PDFObject::Dictionary(tree)
+}
+
+/// Command that prepends generated table of contents pages.
+///
+/// Adds a content stream, link annotations and a page object for each
+/// `TocPage`, puts the pages at the front of the root page tree node and,
+/// if given, replaces the catalog's /PageLabels.
+#[derive(Debug)]
+pub(crate) struct InsertTocCommand {
+    /// The document catalog
+    catalog_ref: Ref,
+
+    /// The root of the page tree
+    pages_ref: Ref,
+
+    /// Page content to add
+    pages: Vec<TocPage>,
+
+    /// MediaBox of the new pages
+    media_box: [f64; 4],
+
+    /// Base fonts for the entries and the heading
+    fonts: (String, String),
+
+    /// New /PageLabels tree, if the labels should change
+    page_labels: Option<PDFObject>,
+
+    /// Catalog and page tree root before the command ran
+    current: Option<(HashMap<String, PDFObject>, HashMap<String, PDFObject>)>,
+
+    /// Delta entries of the catalog and page tree root before the command
+    /// ran (for undo)
+    originals: Vec<Option<PDFObject>>,
+
+    /// The objects that were added
+    added_refs: Vec<Ref>,
+}
+
+impl InsertTocCommand {
+    /// Create a new InsertTocCommand.
+    pub(crate) fn new(
+        catalog_ref: Ref,
+        pages_ref: Ref,
+        pages: Vec<TocPage>,
+        media_box: [f64; 4],
+        options: &TocOptions,
+        page_labels: Option<PDFObject>,
+    ) -> Self {
+        Self {
+            catalog_ref,
+            pages_ref,
+            pages,
+            media_box,
+            fonts: (options.font.clone(), options.title_font.clone()),
+            page_labels,
+            current: None,
+            originals: Vec::new(),
+            added_refs: Vec::new(),
+        }
+    }
+
+    /// Builds the page dictionary of one generated page.
+    fn page_dict(&self, contents: Ref, annots: Vec<Ref>) -> PDFObject {
+        let mut fonts = HashMap::new();
+        fonts.insert(ENTRY_FONT.to_string(), standard_font(&self.fonts.0));
+        fonts.insert(TITLE_FONT.to_string(), standard_font(&self.fonts.1));
+        let mut resources = HashMap::new();
+        resources.insert("Font".to_string(), PDFObject::Dictionary(fonts));
+
+        let mut page = HashMap::new();
+        page.insert("Type".to_string(), PDFObject::Name("Page".to_string()));
+        page.insert("Parent".to_string(), PDFObject::Ref(self.pages_ref));
+        page.insert(
+            "MediaBox".to_string(),
+            PDFObject::Array(
+                self.media_box
+                    .iter()
+                    .map(|v| Box::new(PDFObject::Number(*v)))
+                    .collect(),
+            ),
+        );
+        // Don't inherit a rotation from the page tree
+        page.insert("Rotate".to_string(), PDFObject::Number(0.0));
+        page.insert("Resources".to_string(), PDFObject::Dictionary(resources));
+        page.insert("Contents".to_string(), PDFObject::Ref(contents));
+        if !annots.is_empty() {
+            page.insert(
+                "Annots".to_string(),
+                PDFObject::Array(
+                    annots
+                        .into_iter()
+                        .map(|r| Box::new(PDFObject::Ref(r)))
+                        .collect(),
+                ),
+            );
+        }
+        PDFObject::Dictionary(page)
+    }
+
+    /// Builds a Link annotation that jumps to a page.
+    fn link_annotation(rect: &[f64; 4], target: Ref) -> PDFObject {
+        let mut annot = HashMap::new();
+        annot.insert("Type".to_string(), PDFObject::Name("Annot".to_string()));
+        annot.insert("Subtype".to_string(), PDFObject::Name("Link".to_string()));
+        annot.insert(
+            "Rect".to_string(),
+            PDFObject::Array(
+                rect.iter()
+                    .map(|v| Box::new(PDFObject::Number(*v)))
+                    .collect(),
+            ),
+        );
+        annot.insert(
+            "Border".to_string(),
+            PDFObject::Array(
+                [0.0, 0.0, 0.0]
+                    .iter()
+                    .map(|v| Box::new(PDFObject::Number(*v)))
+                    .collect(),
+            ),
+        );
+        annot.insert(
+            "Dest".to_string(),
+            PDFObject::Array(smallvec::smallvec![
+                Box::new(PDFObject::Ref(target)),
+                Box::new(PDFObject::Name("Fit".to_string())),
+            ]),
+        );
+        PDFObject::Dictionary(annot)
+    }
+
+    /// Adds the new objects and updates the catalog and page tree root.
+    fn apply(&mut self, delta: &mut DeltaLayer) -> PDFResult<()> {
+        let (mut catalog, mut root) = self.current.clone().ok_or_else(|| {
+            PDFError::Generic("Catalog and page tree not recorded before apply".into())
+        })?;
+
+        self.added_refs.clear();
+        let mut new_kids = Vec::new();
+        for page in self.pages.clone() {
+            let contents = delta.add_object(PDFObject::Stream {
+                dict: HashMap::new(),
+                data: page.content,
+            });
+            self.added_refs.push(contents);
+
+            let mut annots = Vec::new();
+            for (rect, target) in &page.links {
+                let annot = delta.add_object(Self::link_annotation(rect, *target));
+                self.added_refs.push(annot);
+                annots.push(annot);
+            }
+
+            let page_ref = delta.add_object(self.page_dict(contents, annots));
+            self.added_refs.push(page_ref);
+            new_kids.push(Box::new(PDFObject::Ref(page_ref)));
+        }
+
+        let kids = match root.get("Kids") {
+            Some(PDFObject::Array(kids)) => kids.clone(),
+            _ => SmallVec::new(),
+        };
+        let count = match root.get("Count") {
+            Some(PDFObject::Number(n)) => *n,
+            _ => kids.len() as f64,
+        };
+        let mut all_kids: SmallVec<[Box<PDFObject>; 4]> = new_kids.into_iter().collect();
+        all_kids.extend(kids);
+        root.insert("Kids".to_string(), PDFObject::Array(all_kids));
+        root.insert(
+            "Count".to_string(),
+            PDFObject::Number(count + self.pages.len() as f64),
+        );
+        delta.modify_object(self.pages_ref, PDFObject::Dictionary(root));
+
+        if let Some(labels) = &self.page_labels {
+            catalog.insert("PageLabels".to_string(), labels.clone());
+            delta.modify_object(self.catalog_ref, PDFObject::Dictionary(catalog));
+        }
+        Ok(())
+    }
+}
+
+impl Command for InsertTocCommand {
+    fn execute<'a>(
+        &mut self,
+        delta: &mut DeltaLayer,
+        fetch_base: Option<&'a BaseObjectFetcher<'a>>,
+    ) -> PDFResult<()> {
+        let refs = [self.catalog_ref, self.pages_ref];
+        self.originals = refs
+            .iter()
+            .map(|r| delta.get(r).map(|obj| obj.object.clone()))
+            .collect();
+
+        let mut dicts = Vec::new();
+        for (obj_ref, original) in refs.iter().zip(&self.originals) {
+            let obj = match original {
+                Some(obj) => obj.clone(),
+                None => {
+                    let fetcher = fetch_base.ok_or_else(|| {
+                        PDFError::Generic(
+                            "Cannot fetch base object - no fetch callback provided. \
+                            Execute commands through PDFDocument::execute_command() instead."
+                                .into(),
+                        )
+                    })?;
+                    fetcher(*obj_ref)?
+                }
+            };
+            match obj {
+                PDFObject::Dictionary(dict) => dicts.push(dict),
+                _ => {
+                    return Err(PDFError::Generic(format!(
+                        "Object {} {} is not a dictionary",
+                        obj_ref.num, obj_ref.generation
+                    )));
+                }
+            }
+        }
+        let root = dicts.pop().unwrap_or_default();
+        let catalog = dicts.pop().unwrap_or_default();
+        self.current = Some((catalog, root));
+
+        self.apply(delta)
+    }
+
+    fn undo(&mut self, delta: &mut DeltaLayer) -> PDFResult<()> {
+        for obj_ref in &self.added_refs {
+            delta.remove_new_object(*obj_ref);
+        }
+
+        let refs = [self.catalog_ref, self.pages_ref];
+        for (obj_ref, original) in refs.iter().zip(&self.originals) {
+            match original {
+                Some(obj) => delta.modify_object(*obj_ref, obj.clone()),
+                None => delta.revert_object(*obj_ref),
+            }
+        }
+        Ok(())
+    }
+
+    fn redo(&mut self, delta: &mut DeltaLayer) -> PDFResult<()> {
+        self.apply(delta)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(level: usize, title: &str, label: Option<&str>, target: u32) -> TocEntry {
+        TocEntry {
+            level,
+            title: title.to_string(),
+            page_label: label.map(str::to_string),
+            target: Some(Ref::new(target, 0)),
+        }
+    }
+
+    #[test]
+    fn test_layout_toc_single_page() {
+        let entries = vec![
+            entry(0, "Introduction", Some("1"), 10),
+            entry(1, "Scope (draft)", Some("2"), 11),
+        ];
+        let pages = layout_toc(&entries, &TocOptions::default(), 612.0, 792.0);
+        assert_eq!(pages.len(), 1);
+
+        let content = String::from_utf8(pages[0].content.clone()).unwrap();
+        assert!(content.starts_with("BT\n/F2 18 Tf\n1 0 0 1 72 702 Tm (Contents) Tj\n"));
+        assert!(content.contains("(Introduction) Tj"));
+        assert!(content.contains("(Scope \\(draft\\)) Tj"));
+        // Second entry is indented and page numbers are right-aligned
+        assert!(content.contains("1 0 0 1 90 "));
+        assert!(content.contains("1 0 0 1 534.5 "));
+        assert!(content.contains("(.) Tj"));
+        assert!(content.ends_with("ET\n"));
+
+        assert_eq!(pages[0].links.len(), 2);
+        assert_eq!(pages[0].links[1].1, Ref::new(11, 0));
+        assert_eq!(pages[0].links[0].0[2], 540.0);
+    }
+
+    #[test]
+    fn test_layout_toc_overflow_and_options() {
+        let entries: Vec<TocEntry> = (0..100)
+            .map(|i| entry(0, &format!("Section {}", i), Some("9"), i))
+            .collect();
+        let options = TocOptions {
+            leader: None,
+            ..TocOptions::default()
+        };
+        let pages = layout_toc(&entries, &options, 612.0, 792.0);
+        assert!(pages.len() > 1);
+        assert_eq!(pages.iter().map(|p| p.links.len()).sum::<usize>(), 100);
+
+        let content = String::from_utf8(pages[0].content.clone()).unwrap();
+        assert!(!content.contains("(.) Tj"));
+        let second = String::from_utf8(pages[1].content.clone()).unwrap();
+        assert!(!second.contains("(Contents)"));
+    }
+
+    #[test]
+    fn test_layout_toc_overflow_without_targets() {
+        // Entries without a target page still break onto new pages
+        let entries: Vec<TocEntry> = (0..100)
+            .map(|i| TocEntry {
+                target: None,
+                ..entry(0, &format!("Part {}", i), None, 0)
+            })
+            .collect();
+        let pages = layout_toc(&entries, &TocOptions::default(), 612.0, 792.0);
+        assert!(pages.len() > 1);
+        assert!(pages.iter().all(|page| page.links.is_empty()));
+    }
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!(truncate("Short", 10), "Short");
+        assert_eq!(truncate("A very long title", 9), "A very...");
+    }
+
+    #[test]
+    fn test_shifted_page_labels() {
+        let labels = shifted_page_labels(&[], 2);
+        let nums = match &labels {
+            PDFObject::Dictionary(dict) => match dict.get("Nums") {
+                Some(PDFObject::Array(nums)) => nums.clone(),
+                _ => panic!("no /Nums"),
+            },
+            _ => panic!("not a dictionary"),
+        };
+        assert_eq!(nums.len(), 4);
+        assert_eq!(*nums[2], PDFObject::Number(2.0));
+
+        let mut roman = HashMap::new();
+        roman.insert("S".to_string(), PDFObject::Name("R".to_string()));
+        let existing = vec![
+            (0, PDFObject::Dictionary(roman.clone())),
+            (3, PDFObject::Dictionary(roman)),
+        ];
+        match shifted_page_labels(&existing, 1) {
+            PDFObject::Dictionary(dict) => match dict.get("Nums") {
+                Some(PDFObject::Array(nums)) => {
+                    assert_eq!(nums.len(), 6);
+                    assert_eq!(*nums[2], PDFObject::Number(1.0));
+                    assert_eq!(*nums[4], PDFObject::Number(4.0));
+                }
+                _ => panic!("no /Nums"),
+            },
+            _ => panic!("not a dictionary"),
+        }
+    }
+}
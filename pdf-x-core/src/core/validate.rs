@@ -0,0 +1,578 @@
+//! Conformance checks for PDF/A and PDF/UA.
+//!
+//! The validator runs a subset of the rules of PDF/A-1b, PDF/A-2b, PDF/A-3b
+//! (ISO 19005) and PDF/UA-1 (ISO 14289) and collects the violations it finds,
+//! each with the object it was found in. The checks look at the document
+//! structure only: content streams are not interpreted, so rules about
+//! colour spaces, glyph widths or the marked content of the page contents
+//! are not covered. A report without violations therefore doesn't prove
+//! conformance, but a violation always means the file fails the profile.
+//!
+//! Whatever the profile, the validator also reports /Info entries that an
+//! incremental update changed after the document was signed. Such an edit
+//! doesn't break conformance, but it changes what readers of a signed file
+//! see without invalidating the signature.
+//!
+//! Use it through `PDFDocument::validate`:
+//!
+//! ```no_run
+//! use pdf_x_core::core::{PDFDocument, ValidationProfile};
+//!
+//! let mut doc = PDFDocument::open_file("archive.pdf", None, None).unwrap();
+//! let report = doc.validate(ValidationProfile::PdfA1b).unwrap();
+//! for violation in &report.violations {
+//!     println!("{:?} in {:?}: {}", violation.rule, violation.object, violation.message);
+//! }
+//! ```
+
+use super::error::{PDFError, PDFResult};
+use super::metadata::InfoRevision;
+use super::parser::{PDFObject, Ref};
+use super::signature::Signature;
+use super::xref::XRef;
+use std::collections::{BTreeMap, HashMap};
+
+/// A conformance profile to validate against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ValidationProfile {
+    /// PDF/A-1b (ISO 19005-1, level B)
+    PdfA1b,
+
+    /// PDF/A-2b (ISO 19005-2, level B)
+    PdfA2b,
+
+    /// PDF/A-3b (ISO 19005-3, level B)
+    PdfA3b,
+
+    /// PDF/UA-1 (ISO 14289-1)
+    PdfUA1,
+}
+
+impl ValidationProfile {
+    /// Returns the PDF/A part number, or `None` for PDF/UA.
+    pub fn pdfa_part(self) -> Option<u8> {
+        match self {
+            ValidationProfile::PdfA1b => Some(1),
+            ValidationProfile::PdfA2b => Some(2),
+            ValidationProfile::PdfA3b => Some(3),
+            ValidationProfile::PdfUA1 => None,
+        }
+    }
+}
+
+/// A rule of a conformance profile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ValidationRule {
+    /// PDF/A files must not be encrypted
+    Encrypted,
+
+    /// Fonts must be embedded
+    FontNotEmbedded,
+
+    /// The catalog must have an XMP metadata stream
+    MissingMetadata,
+
+    /// The PDF/A-1 metadata stream must not be filtered
+    FilteredMetadata,
+
+    /// The XMP metadata must identify the profile (pdfaid or pdfuaid schema)
+    ConformanceIdentifier,
+
+    /// PDF/A files must not contain JavaScript
+    JavaScript,
+
+    /// PDF/A-1 forbids transparency (soft masks, constant alpha below 1,
+    /// blend modes other than Normal, transparency groups)
+    Transparency,
+
+    /// PDF/A forbids LZW compression
+    LZWCompression,
+
+    /// PDF/A-1 forbids embedded files
+    EmbeddedFile,
+
+    /// PDF/UA requires /MarkInfo with /Marked true
+    NotMarked,
+
+    /// PDF/UA requires a structure tree
+    MissingStructTree,
+
+    /// PDF/UA requires the document language (/Lang)
+    MissingLanguage,
+
+    /// PDF/UA requires /ViewerPreferences /DisplayDocTitle true
+    DisplayDocTitle,
+
+    /// PDF/UA requires a dc:title in the XMP metadata
+    MissingTitle,
+
+    /// An incremental update changed an /Info entry after a signature was
+    /// applied (checked for every profile)
+    InfoChangedAfterSigning,
+}
+
+/// A rule violation found by the validator.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    /// The rule that is violated
+    pub rule: ValidationRule,
+
+    /// The object the violation was found in, if it is tied to one
+    pub object: Option<Ref>,
+
+    /// What is wrong
+    pub message: String,
+}
+
+/// The result of validating a document against a profile.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationReport {
+    /// The profile the document was checked against
+    pub profile: ValidationProfile,
+
+    /// Violations in the order they were found
+    pub violations: Vec<Violation>,
+}
+
+impl ValidationReport {
+    /// Returns true if no violation was found.
+    pub fn is_compliant(&self) -> bool {
+        self.violations.is_empty()
+    }
+
+    /// Returns the violations of one rule.
+    pub fn violations_of(&self, rule: ValidationRule) -> impl Iterator<Item = &Violation> {
+        self.violations.iter().filter(move |v| v.rule == rule)
+    }
+}
+
+/// Collects violations as the document's objects are visited.
+pub(crate) struct Validator {
+    profile: ValidationProfile,
+    violations: Vec<Violation>,
+}
+
+impl Validator {
+    pub(crate) fn new(profile: ValidationProfile) -> Self {
+        Validator {
+            profile,
+            violations: Vec::new(),
+        }
+    }
+
+    fn is_pdfa(&self) -> bool {
+        self.profile.pdfa_part().is_some()
+    }
+
+    fn report(&mut self, rule: ValidationRule, object: Option<Ref>, message: impl Into<String>) {
+        self.violations.push(Violation {
+            rule,
+            object,
+            message: message.into(),
+        });
+    }
+
+    /// Runs the document-level rules.
+    ///
+    /// # Arguments
+    /// * `xref` - Cross-reference table for resolving indirect objects
+    /// * `catalog_ref` - Reference of the document catalog
+    /// * `catalog` - The document catalog
+    /// * `encrypted` - Whether the trailer has an /Encrypt entry
+    pub(crate) fn check_document(
+        &mut self,
+        xref: &mut XRef,
+        catalog_ref: Ref,
+        catalog: &HashMap<String, PDFObject>,
+        encrypted: bool,
+    ) {
+        if encrypted && self.is_pdfa() {
+            self.report(ValidationRule::Encrypted, None, "The file is encrypted");
+        }
+
+        let xmp = self.check_metadata(xref, catalog_ref, catalog);
+
+        if let Some(part) = self.profile.pdfa_part() {
+            let declared = xmp.as_ref().and_then(|xmp| xmp.get("pdfaid:part"));
+            let conformance = xmp.as_ref().and_then(|xmp| xmp.get("pdfaid:conformance"));
+            let expected = part.to_string();
+            // Level A includes level B
+            let level_ok = conformance.is_some_and(|c| c.eq_ignore_ascii_case("B") || c == "A");
+            if xmp.is_some() && (declared != Some(&expected) || !level_ok) {
+                self.report(
+                    ValidationRule::ConformanceIdentifier,
+                    Some(catalog_ref),
+                    format!(
+                        "XMP metadata declares pdfaid:part {:?} and pdfaid:conformance {:?}, expected {} and B",
+                        declared, conformance, part
+                    ),
+                );
+            }
+        }
+
+        if self.profile == ValidationProfile::PdfUA1 {
+            self.check_accessibility(xref, catalog_ref, catalog, xmp.as_ref());
+        }
+    }
+
+    /// Checks the catalog's metadata stream and returns its properties.
+    fn check_metadata(
+        &mut self,
+        xref: &mut XRef,
+        catalog_ref: Ref,
+        catalog: &HashMap<String, PDFObject>,
+    ) -> Option<BTreeMap<String, String>> {
+        let metadata_ref = match catalog.get("Metadata") {
+            Some(PDFObject::Ref(r)) => Some(*r),
+            _ => None,
+        };
+        let stream = catalog
+            .get("Metadata")
+            .and_then(|metadata| xref.fetch_if_ref(metadata).ok());
+        let (dict, data) = match stream {
+            Some(PDFObject::Stream { dict, data }) => (dict, data),
+            _ => {
+                self.report(
+                    ValidationRule::MissingMetadata,
+                    Some(catalog_ref),
+                    "The catalog has no XMP metadata stream",
+                );
+                return None;
+            }
+        };
+
+        if self.profile == ValidationProfile::PdfA1b && dict.contains_key("Filter") {
+            self.report(
+                ValidationRule::FilteredMetadata,
+                metadata_ref,
+                "The metadata stream is filtered",
+            );
+        }
+
+        match super::decode::decode_stream_data(&dict, &data) {
+            Ok(xml) => Some(super::metadata::parse_xmp(&String::from_utf8_lossy(&xml))),
+            Err(e) => {
+                self.report(
+                    ValidationRule::MissingMetadata,
+                    metadata_ref,
+                    format!("The metadata stream can't be decoded: {}", e),
+                );
+                None
+            }
+        }
+    }
+
+    /// Runs the PDF/UA catalog rules.
+    fn check_accessibility(
+        &mut self,
+        xref: &mut XRef,
+        catalog_ref: Ref,
+        catalog: &HashMap<String, PDFObject>,
+        xmp: Option<&BTreeMap<String, String>>,
+    ) {
+        let mut entry = |key: &str| {
+            catalog
+                .get(key)
+                .and_then(|value| xref.fetch_if_ref(value).ok())
+        };
+
+        let marked = match entry("MarkInfo") {
+            Some(PDFObject::Dictionary(mark_info)) => {
+                matches!(mark_info.get("Marked"), Some(PDFObject::Boolean(true)))
+            }
+            _ => false,
+        };
+        if !marked {
+            self.report(
+                ValidationRule::NotMarked,
+                Some(catalog_ref),
+                "The catalog has no /MarkInfo with /Marked true",
+            );
+        }
+
+        if !matches!(entry("StructTreeRoot"), Some(PDFObject::Dictionary(_))) {
+            self.report(
+                ValidationRule::MissingStructTree,
+                Some(catalog_ref),
+                "The document has no structure tree",
+            );
+        }
+
+        let has_lang = matches!(
+            entry("Lang"),
+            Some(PDFObject::String(lang) | PDFObject::HexString(lang)) if !lang.is_empty()
+        );
+        if !has_lang {
+            self.report(
+                ValidationRule::MissingLanguage,
+                Some(catalog_ref),
+                "The catalog has no /Lang",
+            );
+        }
+
+        let display_title = match entry("ViewerPreferences") {
+            Some(PDFObject::Dictionary(prefs)) => {
+                matches!(prefs.get("DisplayDocTitle"), Some(PDFObject::Boolean(true)))
+            }
+            _ => false,
+        };
+        if !display_title {
+            self.report(
+                ValidationRule::DisplayDocTitle,
+                Some(catalog_ref),
+                "/ViewerPreferences doesn't set /DisplayDocTitle to true",
+            );
+        }
+
+        if let Some(xmp) = xmp {
+            if xmp.get("dc:title").is_none_or(|title| title.is_empty()) {
+                self.report(
+                    ValidationRule::MissingTitle,
+                    Some(catalog_ref),
+                    "The XMP metadata has no dc:title",
+                );
+            }
+            if xmp.get("pdfuaid:part").map(String::as_str) != Some("1") {
+                self.report(
+                    ValidationRule::ConformanceIdentifier,
+                    Some(catalog_ref),
+                    "The XMP metadata doesn't declare pdfuaid:part 1",
+                );
+            }
+        }
+    }
+
+    /// Reports the /Info entries changed by revisions that follow the first
+    /// signed revision.
+    ///
+    /// The signed state is the /Info of the newest revision whose xref
+    /// section lies inside the bytes covered by the earliest signature. Each
+    /// changed, added or removed key is reported once, for the first revision
+    /// that changed it.
+    ///
+    /// # Arguments
+    /// * `revisions` - The /Info of every revision, oldest first
+    /// * `signatures` - The document's signatures
+    pub(crate) fn check_info_history(
+        &mut self,
+        revisions: &[InfoRevision],
+        signatures: &[Signature],
+    ) {
+        let Some(signed_length) = signatures.iter().map(Signature::signed_length).min() else {
+            return;
+        };
+        let empty = BTreeMap::new();
+        let signed = revisions
+            .iter()
+            .rev()
+            .filter(|revision| revision.xref_offset < signed_length)
+            .find_map(|revision| revision.entries.as_ref())
+            .unwrap_or(&empty);
+
+        let mut reported = Vec::new();
+        for revision in revisions
+            .iter()
+            .filter(|revision| revision.xref_offset >= signed_length)
+        {
+            let Some(entries) = &revision.entries else {
+                continue;
+            };
+            for key in signed.keys().chain(entries.keys()) {
+                if signed.get(key) == entries.get(key) || reported.contains(key) {
+                    continue;
+                }
+                self.report(
+                    ValidationRule::InfoChangedAfterSigning,
+                    revision.info_ref,
+                    format!(
+                        "Revision {} changed /Info /{} after the document was signed",
+                        revision.revision, key
+                    ),
+                );
+                reported.push(key.clone());
+            }
+        }
+    }
+
+    /// Runs the object-level rules on an indirect object and the direct
+    /// objects nested in it.
+    ///
+    /// Fails with [`PDFError::LimitExceeded`] if the object nests deeper
+    /// than the document's `max_object_depth`.
+    pub(crate) fn check_object(
+        &mut self,
+        xref: &mut XRef,
+        obj_ref: Ref,
+        obj: &PDFObject,
+    ) -> PDFResult<()> {
+        self.visit(xref, obj_ref, obj, 0)
+    }
+
+    fn visit(
+        &mut self,
+        xref: &mut XRef,
+        obj_ref: Ref,
+        obj: &PDFObject,
+        depth: usize,
+    ) -> PDFResult<()> {
+        let max_depth = xref.limits().max_object_depth;
+        if depth > max_depth {
+            return Err(PDFError::limit_exceeded(
+                format!(
+                    "nesting depth of object {} {}",
+                    obj_ref.num, obj_ref.generation
+                ),
+                max_depth,
+            ));
+        }
+        match obj {
+            PDFObject::Dictionary(dict) => {
+                self.check_dict(xref, obj_ref, dict, false);
+                for value in dict.values() {
+                    self.visit(xref, obj_ref, value, depth + 1)?;
+                }
+            }
+            PDFObject::Stream { dict, .. } => {
+                self.check_dict(xref, obj_ref, dict, true);
+                for value in dict.values() {
+                    self.visit(xref, obj_ref, value, depth + 1)?;
+                }
+            }
+            PDFObject::Array(items) => {
+                for item in items {
+                    self.visit(xref, obj_ref, item, depth + 1)?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn check_dict(
+        &mut self,
+        xref: &mut XRef,
+        obj_ref: Ref,
+        dict: &HashMap<String, PDFObject>,
+        is_stream: bool,
+    ) {
+        let name = |key: &str| match dict.get(key) {
+            Some(PDFObject::Name(name)) => Some(name.as_str()),
+            _ => None,
+        };
+        let object = Some(obj_ref);
+
+        if name("Type") == Some("Font") {
+            self.check_font(xref, obj_ref, dict);
+        }
+
+        if !self.is_pdfa() {
+            return;
+        }
+
+        if name("S") == Some("JavaScript") || dict.contains_key("JS") {
+            self.report(ValidationRule::JavaScript, object, "JavaScript action");
+        }
+
+        if is_stream {
+            let uses_lzw = match dict.get("Filter") {
+                Some(PDFObject::Name(filter)) => filter == "LZWDecode",
+                Some(PDFObject::Array(filters)) => filters
+                    .iter()
+                    .any(|f| matches!(&**f, PDFObject::Name(filter) if filter == "LZWDecode")),
+                _ => false,
+            };
+            if uses_lzw {
+                self.report(
+                    ValidationRule::LZWCompression,
+                    object,
+                    "Stream uses LZWDecode",
+                );
+            }
+        }
+
+        if self.profile != ValidationProfile::PdfA1b {
+            return;
+        }
+
+        if name("Type") == Some("EmbeddedFile") || dict.contains_key("EF") {
+            self.report(ValidationRule::EmbeddedFile, object, "Embedded file");
+        }
+
+        match dict.get("SMask") {
+            Some(PDFObject::Name(mask)) if mask == "None" => {}
+            Some(_) => self.report(ValidationRule::Transparency, object, "Soft mask"),
+            None => {}
+        }
+        for key in ["CA", "ca"] {
+            if let Some(PDFObject::Number(alpha)) = dict.get(key)
+                && *alpha < 1.0
+            {
+                self.report(
+                    ValidationRule::Transparency,
+                    object,
+                    format!("Constant alpha /{} {}", key, alpha),
+                );
+            }
+        }
+        if let Some(blend_mode) = dict.get("BM") {
+            let normal = match blend_mode {
+                PDFObject::Name(mode) => mode == "Normal" || mode == "Compatible",
+                PDFObject::Array(modes) => modes.iter().all(
+                    |m| matches!(&**m, PDFObject::Name(mode) if mode == "Normal" || mode == "Compatible"),
+                ),
+                _ => true,
+            };
+            if !normal {
+                self.report(
+                    ValidationRule::Transparency,
+                    object,
+                    "Blend mode other than Normal",
+                );
+            }
+        }
+        if name("S") == Some("Transparency") {
+            self.report(ValidationRule::Transparency, object, "Transparency group");
+        }
+    }
+
+    /// Checks that a font dictionary has an embedded font program.
+    fn check_font(&mut self, xref: &mut XRef, obj_ref: Ref, dict: &HashMap<String, PDFObject>) {
+        let subtype = match dict.get("Subtype") {
+            Some(PDFObject::Name(subtype)) => subtype.as_str(),
+            _ => return,
+        };
+        // Type 3 glyphs are content streams; Type 0 fonts are checked
+        // through their descendant font
+        if subtype == "Type3" || subtype == "Type0" {
+            return;
+        }
+
+        let descriptor = dict
+            .get("FontDescriptor")
+            .and_then(|descriptor| xref.fetch_if_ref(descriptor).ok());
+        let embedded = match &descriptor {
+            Some(PDFObject::Dictionary(descriptor)) => ["FontFile", "FontFile2", "FontFile3"]
+                .iter()
+                .any(|key| descriptor.contains_key(*key)),
+            _ => false,
+        };
+        if !embedded {
+            let base_font = match dict.get("BaseFont") {
+                Some(PDFObject::Name(name)) => name.as_str(),
+                _ => "(unnamed)",
+            };
+            self.report(
+                ValidationRule::FontNotEmbedded,
+                Some(obj_ref),
+                format!("{} font {} is not embedded", subtype, base_font),
+            );
+        }
+    }
+
+    pub(crate) fn finish(self) -> ValidationReport {
+        ValidationReport {
+            profile: self.profile,
+            violations: self.violations,
+        }
+    }
+}
@@ -1,12 +1,18 @@
 use super::base_stream::BaseStream;
+use super::cancel::{CancellationToken, Progress};
+use super::catalog::Trailer;
 use super::decode;
+use super::delta::{Command, DeltaLayer};
+use super::encryption::CipherTransform;
 use super::error::{PDFError, PDFResult};
 use super::lexer::Lexer;
-use super::parser::{PDFObject, Parser};
+use super::limits::ResourceLimits;
+use super::object_cache::{CacheBudget, CacheStats, ObjectCache};
+use super::parser::{PDFObject, Parser, Ref};
 use super::stream::Stream;
-use lru::LruCache;
-use std::collections::HashMap; // Still needed for String keys in dictionaries
-use std::num::NonZeroUsize;
+use crate::log_target;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet}; // Still needed for String keys in dictionaries
 use std::rc::Rc;
 
 /// Cross-reference table entry.
@@ -51,38 +57,243 @@ pub struct XRef {
     /// The entries in the xref table, indexed by object number
     entries: Vec<Option<XRefEntry>>,
 
-    /// Cache of parsed objects (object number -> PDFObject)
+    /// Cache of parsed objects and decoded stream data
     /// Uses Rc to avoid expensive cloning of large objects
-    /// Uses LRU cache with FxHashMap for bounded memory and fast access
-    /// Default capacity: 1000 objects (~10MB for typical PDFs)
-    cache: LruCache<u32, Rc<PDFObject>, std::hash::BuildHasherDefault<rustc_hash::FxHasher>>,
+    /// Bounded by a byte budget (16 MiB of objects, 32 MiB of streams by default)
+    cache: ObjectCache,
 
     /// The trailer dictionary
     trailer: Option<PDFObject>,
 
     /// Stream to read PDF data from
     stream: Box<dyn BaseStream>,
+
+    /// Pending edits layered over the objects in the file.
+    /// Consulted before the cache and the file on every fetch.
+    delta: DeltaLayer,
+
+    /// Whether recoverable spec violations are repaired instead of ignored
+    lenient: bool,
+
+    /// Problems found and repaired, in the order they happened
+    diagnostics: Vec<Diagnostic>,
+
+    /// Revisions of the file (one per xref section), oldest first
+    revisions: Vec<XRefRevision>,
+
+    /// Entries read from the xref section currently being parsed
+    section_entries: HashMap<u32, XRefEntry>,
+
+    /// Decrypts objects read from an encrypted file, once the password is known
+    cipher: Option<CipherTransform>,
+
+    /// Whether the table was rebuilt by scanning the file for objects
+    reconstructed: bool,
+
+    /// Offset tables of the object streams read so far
+    obj_stream_tables: HashMap<u32, ObjStmTable>,
+
+    /// Sorted, distinct offsets of the objects in the file, built by the
+    /// first prefetch and dropped whenever the table changes
+    offset_index: Option<Vec<usize>>,
+
+    /// Stops reads from the file once cancelled
+    cancel: Option<CancellationToken>,
+
+    /// Told how far parsing or rebuilding the table got
+    progress: Option<Progress>,
+
+    /// Bounds on the objects and streams read from the file
+    limits: ResourceLimits,
+
+    /// Number of objects being loaded from the file, one inside the other
+    fetch_depth: usize,
+}
+
+/// Offset table of an object stream: the number of each object and the
+/// offset of its data in the decoded stream.
+type ObjStmTable = Rc<[(u32, usize)]>;
+
+/// The kind of problem a [`Diagnostic`] reports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticKind {
+    /// Bytes before the %PDF- header, which were skipped
+    JunkBeforeHeader,
+    /// An object not terminated by "endobj"
+    MissingEndobj,
+    /// A stream whose /Length doesn't end at "endstream"
+    StreamLength,
+    /// A missing or broken cross-reference table, rebuilt by scanning the file
+    XRefReconstructed,
+    /// An image XObject without /Subtype /Image, detected from its contents
+    ImageSubtype,
+    /// Any other repair
+    Other,
+}
+
+/// A spec violation found while reading a document, and what was done about
+/// it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// What kind of problem was found
+    pub kind: DiagnosticKind,
+
+    /// Human-readable description of the problem and the repair
+    pub message: String,
+}
+
+/// One revision of a PDF file: an xref section and the trailer that ends it.
+///
+/// The original file is revision 0. Each incremental update appends a new
+/// revision whose trailer points back at the previous one through /Prev.
+#[derive(Debug, Clone)]
+pub struct XRefRevision {
+    /// Byte offset of the revision's xref section
+    pub offset: usize,
+
+    /// The revision's trailer dictionary
+    pub trailer: PDFObject,
+
+    /// The entries this revision's xref section defines
+    pub entries: HashMap<u32, XRefEntry>,
 }
 
 impl XRef {
     /// Creates a new XRef table.
     pub fn new(stream: Box<dyn BaseStream>) -> Self {
-        // Default cache capacity: 1000 objects
-        // This is enough for most PDFs while keeping memory bounded
-        let capacity = NonZeroUsize::new(1000).unwrap();
-        let cache = LruCache::with_hasher(
-            capacity,
-            std::hash::BuildHasherDefault::<rustc_hash::FxHasher>::default(),
-        );
-
         XRef {
             entries: Vec::new(),
-            cache,
+            cache: ObjectCache::new(CacheBudget::default()),
             trailer: None,
             stream,
+            delta: DeltaLayer::new(0),
+            lenient: false,
+            diagnostics: Vec::new(),
+            revisions: Vec::new(),
+            section_entries: HashMap::new(),
+            cipher: None,
+            reconstructed: false,
+            obj_stream_tables: HashMap::new(),
+            cancel: None,
+            progress: None,
+            limits: ResourceLimits::default(),
+            fetch_depth: 0,
+            offset_index: None,
         }
     }
 
+    /// Enables or disables lenient mode.
+    ///
+    /// In lenient mode, objects missing "endobj" and streams with a wrong
+    /// /Length are read anyway instead of failing, and consumers of the xref
+    /// (image extraction, rendering) repair common spec violations, such as
+    /// image XObjects without /Subtype /Image. Each repair is recorded as a
+    /// [`Diagnostic`].
+    pub fn set_lenient(&mut self, lenient: bool) {
+        self.lenient = lenient;
+    }
+
+    /// Returns true if lenient mode is enabled.
+    pub fn is_lenient(&self) -> bool {
+        self.lenient
+    }
+
+    /// Sets the token that stops reading objects from the file.
+    ///
+    /// Once the token is cancelled, fetching an object that isn't cached
+    /// and parsing or rebuilding the xref table fail with
+    /// [`PDFError::Cancelled`], which cuts short any operation built on
+    /// them. Objects already cached can still be fetched.
+    pub fn set_cancellation_token(&mut self, token: Option<CancellationToken>) {
+        self.cancel = token;
+    }
+
+    /// Returns the token set by
+    /// [`set_cancellation_token`](Self::set_cancellation_token).
+    pub fn cancellation_token(&self) -> Option<&CancellationToken> {
+        self.cancel.as_ref()
+    }
+
+    /// Sets the callback told how far reading the xref table got.
+    ///
+    /// [`parse`](Self::parse) reports (sections read, sections known so
+    /// far), the total growing as /Prev links are followed, and
+    /// [`reconstruct`](Self::reconstruct) reports (object headers indexed,
+    /// headers found).
+    pub fn set_progress(&mut self, progress: Option<Progress>) {
+        self.progress = progress;
+    }
+
+    /// Returns the callback set by [`set_progress`](Self::set_progress).
+    pub fn progress(&self) -> Option<&Progress> {
+        self.progress.as_ref()
+    }
+
+    /// Sets the resource limits enforced while reading the file.
+    ///
+    /// The xref table, object parsing and stream decoding check them, and
+    /// so do page tree traversal and rendering through
+    /// [`limits`](Self::limits). Set them before the table is parsed.
+    pub fn set_limits(&mut self, limits: ResourceLimits) {
+        self.limits = limits;
+    }
+
+    /// Returns the resource limits set by [`set_limits`](Self::set_limits).
+    pub fn limits(&self) -> &ResourceLimits {
+        &self.limits
+    }
+
+    /// Fails with [`PDFError::Cancelled`] if the cancellation token was
+    /// cancelled.
+    fn check_cancelled(&self) -> PDFResult<()> {
+        match &self.cancel {
+            Some(token) => token.check(),
+            None => Ok(()),
+        }
+    }
+
+    /// Passes (done, total) to the progress callback, if any.
+    fn report_progress(&self, done: usize, total: usize) {
+        if let Some(progress) = &self.progress {
+            progress(done, total);
+        }
+    }
+
+    /// Records a repair of the given kind.
+    ///
+    /// A repair that was already recorded with the same kind and message
+    /// (the same object read again, or a page rendered twice) is recorded
+    /// only once.
+    pub fn record_diagnostic(&mut self, kind: DiagnosticKind, message: impl Into<String>) {
+        let diagnostic = Diagnostic {
+            kind,
+            message: message.into(),
+        };
+        if self.diagnostics.contains(&diagnostic) {
+            return;
+        }
+        tracing::warn!(target: log_target::XREF, "{}", diagnostic.message);
+        self.diagnostics.push(diagnostic);
+    }
+
+    /// Records a repair that doesn't fit any specific [`DiagnosticKind`].
+    pub fn record_warning(&mut self, message: impl Into<String>) {
+        self.record_diagnostic(DiagnosticKind::Other, message);
+    }
+
+    /// Returns the repairs recorded so far.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// Returns the messages of the repairs recorded so far.
+    pub fn warnings(&self) -> Vec<&str> {
+        self.diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.message.as_str())
+            .collect()
+    }
+
     /// Sets the stream position for parsing.
     pub fn set_stream_pos(&mut self, pos: usize) -> PDFResult<()> {
         self.stream.set_pos(pos)
@@ -122,6 +333,11 @@ impl XRef {
     /// endstream
     /// endobj
     /// ```
+    ///
+    /// In hybrid-reference files, a classic trailer points through /XRefStm
+    /// to an xref stream holding the entries of objects in object streams.
+    /// That stream belongs to the same revision as the table, and its entries
+    /// replace the free entries the table lists for those objects.
     pub fn parse(&mut self) -> PDFResult<()> {
         let start_pos = self.stream.pos();
 
@@ -134,7 +350,12 @@ impl XRef {
         // The first trailer we encounter (from the end of the file) is the main trailer
         let mut main_trailer: Option<PDFObject> = None;
 
+        // Revisions in the order they are read, newest first
+        let mut revisions = Vec::new();
+
         while let Some(pos) = xref_queue.pop() {
+            self.check_cancelled()?;
+
             // Skip if we've already parsed this position (circular reference protection)
             if !parsed_positions.insert(pos) {
                 continue;
@@ -148,6 +369,7 @@ impl XRef {
                     .make_sub_stream(pos, self.stream.length() - pos)?,
             )?;
             let mut parser = Parser::new(lexer)?;
+            parser.set_max_depth(self.limits.max_object_depth);
 
             // First token could be "xref" (traditional) or a number (XRef stream object)
             let obj = parser.get_object()?;
@@ -155,69 +377,42 @@ impl XRef {
             let trailer = match obj {
                 obj if obj.is_command("xref") => {
                     // Traditional xref table
-                    self.read_xref_table(&mut parser)?;
+                    let free_entries = self.read_xref_table(&mut parser)?;
 
                     // read_xref_table consumed the "trailer" keyword, so read the dictionary directly
                     let trailer = parser.get_object()?;
-                    if !matches!(trailer, PDFObject::Dictionary(_)) {
-                        return Err(PDFError::Generic("Expected trailer dictionary".to_string()));
+                    let dict = match &trailer {
+                        PDFObject::Dictionary(dict) => dict,
+                        _ => {
+                            return Err(PDFError::xref_error("Expected trailer dictionary"));
+                        }
+                    };
+
+                    // Hybrid-reference file: the rest of this revision's
+                    // entries are in an xref stream
+                    if let Some(PDFObject::Number(stm_pos)) = dict.get("XRefStm") {
+                        let stm_pos = *stm_pos as usize;
+                        if parsed_positions.insert(stm_pos) {
+                            match self.read_xref_stream_at(stm_pos, Some(&free_entries)) {
+                                Ok(_) => {}
+                                Err(e @ PDFError::DataMissing { .. }) => return Err(e),
+                                Err(e) => self.record_warning(format!(
+                                    "Ignoring the /XRefStm at offset {}: {}",
+                                    stm_pos, e
+                                )),
+                            }
+                        }
                     }
 
                     trailer
                 }
                 PDFObject::Number(_obj_num) => {
                     // Might be an XRef stream - format: N 0 obj << /Type /XRef >> stream...endstream
-                    let generation = parser.get_object()?;
-                    let obj_keyword = parser.get_object()?;
-
-                    // Verify this is an indirect object
-                    if !matches!(generation, PDFObject::Number(0.0)) {
-                        return Err(PDFError::Generic(
-                            "XRef stream must have generation 0".to_string(),
-                        ));
-                    }
-
-                    if !obj_keyword.is_command("obj") {
-                        return Err(PDFError::Generic(format!(
-                            "Expected 'obj' keyword, got {:?}",
-                            obj_keyword
-                        )));
-                    }
-
-                    // Read the object (should be a Stream with /Type /XRef)
-                    let xref_obj = parser.get_object()?;
-
-                    match xref_obj {
-                        PDFObject::Stream { dict, data } => {
-                            // Verify it's an XRef stream
-                            if let Some(PDFObject::Name(type_name)) = dict.get("Type") {
-                                if type_name != "XRef" {
-                                    return Err(PDFError::Generic(format!(
-                                        "Expected /Type /XRef, got /Type /{}",
-                                        type_name
-                                    )));
-                                }
-                            } else {
-                                return Err(PDFError::Generic(
-                                    "XRef stream missing /Type entry".to_string(),
-                                ));
-                            }
-
-                            // Parse the XRef stream
-                            self.parse_xref_stream(&dict, &data)?;
-
-                            // The trailer dictionary is the stream dictionary itself
-                            PDFObject::Dictionary(dict)
-                        }
-                        _ => {
-                            return Err(PDFError::Generic(
-                                "Expected XRef stream object".to_string(),
-                            ));
-                        }
-                    }
+                    // The trailer dictionary is the stream dictionary itself
+                    PDFObject::Dictionary(self.read_xref_stream_at(pos, None)?)
                 }
                 _ => {
-                    return Err(PDFError::Generic(format!(
+                    return Err(PDFError::xref_error(format!(
                         "Expected 'xref' keyword or object number, got {:?}",
                         obj
                     )));
@@ -229,6 +424,12 @@ impl XRef {
                 main_trailer = Some(trailer.clone());
             }
 
+            revisions.push(XRefRevision {
+                offset: pos,
+                trailer: trailer.clone(),
+                entries: std::mem::take(&mut self.section_entries),
+            });
+
             // Check for /Prev entry and add to queue
             if let PDFObject::Dictionary(ref dict) = trailer {
                 if let Some(prev_obj) = dict.get("Prev") {
@@ -250,14 +451,94 @@ impl XRef {
                     }
                 }
             }
+
+            self.report_progress(revisions.len(), revisions.len() + xref_queue.len());
         }
 
         // Set the main trailer
         self.trailer = main_trailer;
 
+        revisions.reverse();
+        self.revisions = revisions;
+        self.offset_index = None;
+
+        // New objects are numbered after the last object in the file
+        self.delta = DeltaLayer::new(self.entries.len() as u32);
+
         Ok(())
     }
 
+    /// Reads the xref stream object at `pos` and adds its entries.
+    ///
+    /// `table_free` is given for the /XRefStm of a hybrid-reference file:
+    /// the objects whose current entry is a free entry of the table.
+    ///
+    /// # Returns
+    /// The stream dictionary, which doubles as the trailer
+    fn read_xref_stream_at(
+        &mut self,
+        pos: usize,
+        table_free: Option<&HashSet<u32>>,
+    ) -> PDFResult<HashMap<String, PDFObject>> {
+        let lexer = Lexer::new(
+            self.stream
+                .make_sub_stream(pos, self.stream.length().saturating_sub(pos))?,
+        )?;
+        let mut parser = Parser::new(lexer)?;
+        parser.set_max_depth(self.limits.max_object_depth);
+
+        // Format: N 0 obj << /Type /XRef >> stream...endstream
+        let obj_num = parser.get_object()?;
+        let generation = parser.get_object()?;
+        let obj_keyword = parser.get_object()?;
+
+        let stream_num = match obj_num {
+            PDFObject::Number(n) => n as u32,
+            _ => {
+                return Err(PDFError::xref_error(format!(
+                    "Expected XRef stream object number, got {:?}",
+                    obj_num
+                )));
+            }
+        };
+
+        // Verify this is an indirect object
+        if !matches!(generation, PDFObject::Number(0.0)) {
+            return Err(PDFError::xref_error("XRef stream must have generation 0"));
+        }
+
+        if !obj_keyword.is_command("obj") {
+            return Err(PDFError::xref_error(format!(
+                "Expected 'obj' keyword, got {:?}",
+                obj_keyword
+            )));
+        }
+
+        // Read the object (should be a Stream with /Type /XRef)
+        match parser.get_object()? {
+            PDFObject::Stream { dict, data } => {
+                // Verify it's an XRef stream
+                match dict.get("Type") {
+                    Some(PDFObject::Name(type_name)) if type_name == "XRef" => {}
+                    Some(PDFObject::Name(type_name)) => {
+                        return Err(PDFError::xref_error(format!(
+                            "Expected /Type /XRef, got /Type /{}",
+                            type_name
+                        )));
+                    }
+                    _ => {
+                        return Err(PDFError::xref_error("XRef stream missing /Type entry"));
+                    }
+                }
+
+                self.parse_xref_stream(&dict, &data, table_free)
+                    .map_err(|e| e.in_object(stream_num, 0))?;
+                Ok(dict)
+            }
+            _ => Err(PDFError::xref_error("Expected XRef stream object")),
+        }
+    }
+
     /// Parses an XRef stream (PDF 1.5+).
     ///
     /// XRef streams encode the cross-reference table as binary data in a stream.
@@ -271,53 +552,58 @@ impl XRef {
     /// - Type 1: Uncompressed entry (offset = byte offset, generation = generation)
     /// - Type 2: Compressed entry (offset = obj stream num, generation = index in stream)
     ///
+    /// Entries are only added for objects that have none yet, except in the
+    /// /XRefStm of a hybrid-reference file (`table_free` given), whose
+    /// entries replace the free entries of the table they complement.
+    ///
     /// Based on PDF.js processXRefStream()
     fn parse_xref_stream(
         &mut self,
         dict: &HashMap<String, PDFObject>,
         data: &[u8],
+        table_free: Option<&HashSet<u32>>,
     ) -> PDFResult<()> {
         // Get W array (byte widths)
         let w_array = dict
             .get("W")
-            .ok_or_else(|| PDFError::Generic("XRef stream missing /W entry".to_string()))?;
+            .ok_or_else(|| PDFError::xref_error("XRef stream missing /W entry"))?;
 
         let widths = match w_array {
             PDFObject::Array(arr) => {
                 if arr.len() != 3 {
-                    return Err(PDFError::Generic(format!(
+                    return Err(PDFError::xref_error(format!(
                         "XRef stream /W must have 3 elements, got {}",
                         arr.len()
                     )));
                 }
                 let w1 = match &*arr[0] {
                     PDFObject::Number(n) => *n as usize,
-                    _ => return Err(PDFError::Generic("/W[0] must be a number".to_string())),
+                    _ => return Err(PDFError::xref_error("/W[0] must be a number")),
                 };
                 let w2 = match &*arr[1] {
                     PDFObject::Number(n) => *n as usize,
-                    _ => return Err(PDFError::Generic("/W[1] must be a number".to_string())),
+                    _ => return Err(PDFError::xref_error("/W[1] must be a number")),
                 };
                 let w3 = match &*arr[2] {
                     PDFObject::Number(n) => *n as usize,
-                    _ => return Err(PDFError::Generic("/W[2] must be a number".to_string())),
+                    _ => return Err(PDFError::xref_error("/W[2] must be a number")),
                 };
                 (w1, w2, w3)
             }
-            _ => return Err(PDFError::Generic("/W must be an array".to_string())),
+            _ => return Err(PDFError::xref_error("/W must be an array")),
         };
 
         // Get Index array (ranges) - default is [0, Size]
         let index_array = if let Some(index) = dict.get("Index") {
             match index {
                 PDFObject::Array(arr) => arr.clone(),
-                _ => return Err(PDFError::Generic("/Index must be an array".to_string())),
+                _ => return Err(PDFError::xref_error("/Index must be an array")),
             }
         } else {
             // Default: [0, Size]
             let size = dict
                 .get("Size")
-                .ok_or_else(|| PDFError::Generic("XRef stream missing /Size".to_string()))?;
+                .ok_or_else(|| PDFError::xref_error("XRef stream missing /Size"))?;
             match size {
                 PDFObject::Number(n) => {
                     use smallvec::smallvec;
@@ -326,68 +612,12 @@ impl XRef {
                         Box::new(PDFObject::Number(*n))
                     ]
                 }
-                _ => return Err(PDFError::Generic("/Size must be a number".to_string())),
+                _ => return Err(PDFError::xref_error("/Size must be a number")),
             }
         };
 
-        // Decompress the stream data if needed
-        let filter_name = dict.get("Filter").and_then(|f| match f {
-            PDFObject::Name(name) => Some(name.as_str()),
-            _ => None,
-        });
-
-        let mut decompressed_data = decode::decode_stream(data, filter_name)
-            .map_err(|e| PDFError::Generic(format!("XRef stream decode error: {}", e)))?;
-
-        // Apply PNG predictor if specified in DecodeParms
-        if let Some(decode_parms) = dict.get("DecodeParms") {
-            if let PDFObject::Dictionary(parms) = decode_parms {
-                // Check for Predictor
-                if let Some(PDFObject::Number(predictor)) = parms.get("Predictor") {
-                    let pred = *predictor as i32;
-                    // PNG predictor values are 10-14 (10 = None, 11 = Sub, 12 = Up, 13 = Average, 14 = Paeth)
-                    if pred >= 10 && pred <= 14 {
-                        // Get Columns parameter (required for PNG predictor)
-                        let columns = parms
-                            .get("Columns")
-                            .and_then(|obj| match obj {
-                                PDFObject::Number(n) => Some(*n as usize),
-                                _ => None,
-                            })
-                            .unwrap_or(1);
-
-                        // Get Colors parameter (default 1)
-                        let colors = parms
-                            .get("Colors")
-                            .and_then(|obj| match obj {
-                                PDFObject::Number(n) => Some(*n as usize),
-                                _ => None,
-                            })
-                            .unwrap_or(1);
-
-                        // Get BitsPerComponent (default 8)
-                        let bits_per_component = parms
-                            .get("BitsPerComponent")
-                            .and_then(|obj| match obj {
-                                PDFObject::Number(n) => Some(*n as usize),
-                                _ => None,
-                            })
-                            .unwrap_or(8);
-
-                        // Apply PNG predictor
-                        decompressed_data = decode::decode_png_predictor(
-                            &decompressed_data,
-                            colors,
-                            bits_per_component,
-                            columns,
-                        )
-                        .map_err(|e| {
-                            PDFError::Generic(format!("PNG predictor decode error: {}", e))
-                        })?;
-                    }
-                }
-            }
-        }
+        // Decompress the stream data, including any predictor in DecodeParms
+        let decompressed_data = decode::decode_stream_data_limited(dict, data, &self.limits)?;
 
         // Parse entries from the decompressed data
         let (w1, w2, w3) = widths;
@@ -396,29 +626,29 @@ impl XRef {
 
         // Process each range in the Index array
         let mut i = 0;
-        while i < index_array.len() {
+        while i + 1 < index_array.len() {
             let first = match &*index_array[i] {
                 PDFObject::Number(n) => *n as u32,
                 _ => {
-                    return Err(PDFError::Generic(
-                        "Index entry must be a number".to_string(),
-                    ));
+                    return Err(PDFError::xref_error("Index entry must be a number"));
                 }
             };
 
             let count = match &*index_array[i + 1] {
                 PDFObject::Number(n) => *n as usize,
                 _ => {
-                    return Err(PDFError::Generic(
-                        "Index entry must be a number".to_string(),
-                    ));
+                    return Err(PDFError::xref_error("Index entry must be a number"));
                 }
             };
 
+            // Same limit as for xref tables, against huge allocations
+            self.limits
+                .check_object_number((first as usize + count).saturating_sub(1))?;
+
             // Read 'count' entries starting from 'first'
             for j in 0..count {
                 if pos + entry_size > decompressed_data.len() {
-                    return Err(PDFError::Generic("XRef stream data truncated".to_string()));
+                    return Err(PDFError::xref_error("XRef stream data truncated"));
                 }
 
                 // Read type field (w1 bytes)
@@ -473,8 +703,19 @@ impl XRef {
                     self.entries.push(None);
                 }
 
-                // Only set if not already set (first entry wins)
-                if self.entries[obj_num as usize].is_none() {
+                // Only set if not already set (first entry wins), unless the
+                // entry fills in a free one of a hybrid file's table
+                let fills_table = table_free.is_some() && !entry.is_free();
+                if self
+                    .section_entries
+                    .get(&obj_num)
+                    .is_none_or(|existing| fills_table && existing.is_free())
+                {
+                    self.section_entries.insert(obj_num, entry.clone());
+                }
+                if self.entries[obj_num as usize].is_none()
+                    || (fills_table && table_free.is_some_and(|free| free.contains(&obj_num)))
+                {
                     self.entries[obj_num as usize] = Some(entry);
                 }
             }
@@ -489,7 +730,12 @@ impl XRef {
     ///
     /// Each subsection starts with two numbers: first object number and count.
     /// Then follows one entry per line with: offset generation_number type
-    fn read_xref_table(&mut self, parser: &mut Parser) -> PDFResult<()> {
+    ///
+    /// # Returns
+    /// The objects whose current entry is a free entry of this section,
+    /// which a hybrid file's /XRefStm may fill in
+    fn read_xref_table(&mut self, parser: &mut Parser) -> PDFResult<HashSet<u32>> {
+        let mut free_entries = HashSet::new();
         loop {
             // Peek at the next object to see if it's "trailer"
             let first_obj = parser.get_object()?;
@@ -500,14 +746,14 @@ impl XRef {
                 // Since we can't really put it back, we'll just break
                 // and let the caller know to expect trailer was already consumed
                 // Actually, let's just return Ok since we read the trailer keyword
-                return Ok(());
+                return Ok(free_entries);
             }
 
             // Get first object number
             let first = match first_obj {
                 PDFObject::Number(n) => n as u32,
                 _ => {
-                    return Err(PDFError::Generic(format!(
+                    return Err(PDFError::xref_error(format!(
                         "Expected subsection start number or 'trailer', got {:?}",
                         first_obj
                     )));
@@ -519,7 +765,7 @@ impl XRef {
             let count = match count_obj {
                 PDFObject::Number(n) => n as u32,
                 _ => {
-                    return Err(PDFError::Generic(format!(
+                    return Err(PDFError::xref_error(format!(
                         "Expected subsection count, got {:?}",
                         count_obj
                     )));
@@ -535,14 +781,9 @@ impl XRef {
                 ))
             })? as usize;
 
-            // Sanity check: prevent extremely large allocations
-            if needed_size > 10_000_000 {
-                // 10 million objects is unreasonable
-                return Err(PDFError::corrupted_pdf(format!(
-                    "XRef table size {} exceeds reasonable limit",
-                    needed_size
-                )));
-            }
+            // Prevent huge allocations from a forged subsection header
+            self.limits
+                .check_object_number(needed_size.saturating_sub(1))?;
 
             if self.entries.len() < needed_size {
                 self.entries.resize(needed_size, None);
@@ -553,8 +794,15 @@ impl XRef {
                 let entry = self.read_xref_entry(parser)?;
                 let obj_num = (first + i) as usize;
 
+                self.section_entries
+                    .entry(obj_num as u32)
+                    .or_insert_with(|| entry.clone());
+
                 // Only set if not already set (first xref wins)
                 if self.entries[obj_num].is_none() {
+                    if entry.is_free() {
+                        free_entries.insert(obj_num as u32);
+                    }
                     self.entries[obj_num] = Some(entry);
                 }
             }
@@ -571,7 +819,7 @@ impl XRef {
         let offset = match offset_obj {
             PDFObject::Number(n) => n as u64,
             _ => {
-                return Err(PDFError::Generic(format!(
+                return Err(PDFError::xref_error(format!(
                     "Expected offset in xref entry, got {:?}",
                     offset_obj
                 )));
@@ -583,7 +831,7 @@ impl XRef {
         let generation = match gen_obj {
             PDFObject::Number(n) => n as u32,
             _ => {
-                return Err(PDFError::Generic(format!(
+                return Err(PDFError::xref_error(format!(
                     "Expected generation in xref entry, got {:?}",
                     gen_obj
                 )));
@@ -596,7 +844,7 @@ impl XRef {
             obj if obj.is_command("f") => "f",
             obj if obj.is_command("n") => "n",
             _ => {
-                return Err(PDFError::Generic(format!(
+                return Err(PDFError::xref_error(format!(
                     "Expected 'f' or 'n' in xref entry, got {:?}",
                     type_obj
                 )));
@@ -620,6 +868,18 @@ impl XRef {
         self.entries.get(obj_num as usize)?.as_ref()
     }
 
+    /// Iterates over the objects that have an entry, with their object
+    /// numbers, in increasing order.
+    ///
+    /// Numbers below [`len`](Self::len) may have no entry at all, so use this
+    /// rather than looking up every number up to `len`.
+    pub fn iter_entries(&self) -> impl Iterator<Item = (u32, &XRefEntry)> + '_ {
+        self.entries
+            .iter()
+            .enumerate()
+            .filter_map(|(obj_num, entry)| Some((obj_num as u32, entry.as_ref()?)))
+    }
+
     /// Fetches an object from a compressed object stream (ObjStm).
     ///
     /// Object streams contain multiple PDF objects in a compressed format.
@@ -637,335 +897,395 @@ impl XRef {
     /// # Returns
     /// The requested object wrapped in Rc
     fn fetch_compressed(&mut self, obj_stream_num: u32, index: u32) -> PDFResult<Rc<PDFObject>> {
-        // First, fetch the object stream itself (as an uncompressed object)
-        let obj_stream_obj = self.fetch(obj_stream_num, 0)?;
+        let (decompressed_data, table) = self.load_object_stream(obj_stream_num)?;
 
-        // The object stream must be a Stream object with dictionary and data
-        match &*obj_stream_obj {
-            PDFObject::Stream { dict, data } => {
-                // Check if this is an ObjStm
-                if let Some(PDFObject::Name(type_name)) = dict.get("Type") {
-                    if type_name != "ObjStm" {
-                        return Err(PDFError::Generic(format!(
-                            "Expected ObjStm type, got /{}",
-                            type_name
-                        )));
-                    }
-                }
+        let (actual_obj_num, obj_offset) = table.get(index as usize).copied().ok_or_else(|| {
+            PDFError::corrupted_pdf(format!(
+                "Index {} out of range for ObjStm with {} objects",
+                index,
+                table.len()
+            ))
+        })?;
+
+        // Validate offset is within bounds
+        if obj_offset >= decompressed_data.len() {
+            return Err(PDFError::corrupted_pdf(format!(
+                "ObjStm: object offset {} exceeds stream length {}",
+                obj_offset,
+                decompressed_data.len()
+            )));
+        }
 
-                // Get N (number of objects) and First (byte offset of first object)
-                let n = dict
-                    .get("N")
-                    .and_then(|obj| match obj {
-                        PDFObject::Number(n) => Some(*n as u32),
-                        _ => None,
-                    })
-                    .ok_or_else(|| PDFError::Generic("ObjStm missing /N parameter".to_string()))?;
-
-                let first = dict
-                    .get("First")
-                    .and_then(|obj| match obj {
-                        PDFObject::Number(n) => Some(*n as usize),
-                        _ => None,
-                    })
-                    .ok_or_else(|| {
-                        PDFError::Generic("ObjStm missing /First parameter".to_string())
-                    })?;
-
-                if index >= n {
-                    return Err(PDFError::Generic(format!(
-                        "Index {} out of range for ObjStm with {} objects",
-                        index, n
-                    )));
-                }
+        // The object ends where the next one starts, the last one at the end
+        // of the data
+        let obj_end = table
+            .get(index as usize + 1)
+            .map_or(decompressed_data.len(), |&(_, next_offset)| next_offset);
+        if obj_end < obj_offset || obj_end > decompressed_data.len() {
+            return Err(PDFError::corrupted_pdf(format!(
+                "ObjStm: object range {}..{} exceeds stream length {}",
+                obj_offset,
+                obj_end,
+                decompressed_data.len()
+            )));
+        }
 
-                // Decompress the stream data if needed
-                let filter_name = dict.get("Filter").and_then(|f| match f {
-                    PDFObject::Name(name) => Some(name.as_str()),
-                    _ => None,
-                });
+        // Create a stream for just this object's data
+        let obj_data = decompressed_data[obj_offset..obj_end].to_vec();
+        let obj_stream = Stream::from_bytes(obj_data);
+        let obj_lexer = Lexer::new(Box::new(obj_stream) as Box<dyn BaseStream>)?;
+        let mut obj_parser = Parser::new(obj_lexer)?;
+        obj_parser.set_max_depth(self.limits.max_object_depth);
 
-                let mut decompressed_data = decode::decode_stream(data, filter_name)
-                    .map_err(|e| PDFError::Generic(format!("ObjStm decode error: {}", e)))?;
-
-                // Apply PNG predictor if specified in DecodeParms
-                if let Some(decode_parms) = dict.get("DecodeParms") {
-                    if let PDFObject::Dictionary(parms) = decode_parms {
-                        // Check for Predictor
-                        if let Some(PDFObject::Number(predictor)) = parms.get("Predictor") {
-                            let pred = *predictor as i32;
-                            // PNG predictor values are 10-14
-                            if pred >= 10 && pred <= 14 {
-                                let columns = parms
-                                    .get("Columns")
-                                    .and_then(|obj| match obj {
-                                        PDFObject::Number(n) => Some(*n as usize),
-                                        _ => None,
-                                    })
-                                    .unwrap_or(1);
-
-                                let colors = parms
-                                    .get("Colors")
-                                    .and_then(|obj| match obj {
-                                        PDFObject::Number(n) => Some(*n as usize),
-                                        _ => None,
-                                    })
-                                    .unwrap_or(1);
-
-                                let bits_per_component = parms
-                                    .get("BitsPerComponent")
-                                    .and_then(|obj| match obj {
-                                        PDFObject::Number(n) => Some(*n as usize),
-                                        _ => None,
-                                    })
-                                    .unwrap_or(8);
-
-                                decompressed_data = decode::decode_png_predictor(
-                                    &decompressed_data,
-                                    colors,
-                                    bits_per_component,
-                                    columns,
-                                )
-                                .map_err(|e| {
-                                    PDFError::Generic(format!("PNG predictor decode error: {}", e))
-                                })?;
-                            }
-                        }
-                    }
-                }
+        // Parse the object (no "obj"/"endobj" wrappers in ObjStm)
+        let object = Rc::new(obj_parser.get_object()?);
 
-                // Parse the object number/offset pairs (first N pairs of integers)
-                let index_stream = Stream::from_bytes(decompressed_data[..first].to_vec());
-                let lexer = Lexer::new(Box::new(index_stream) as Box<dyn BaseStream>)?;
-                let mut parser = Parser::new(lexer)?;
+        // Cache it with the actual object number; objects in object streams
+        // always have generation 0
+        self.cache.put_object(actual_obj_num, 0, Rc::clone(&object));
 
-                // Read all object numbers and offsets
-                let mut obj_nums = Vec::with_capacity(n as usize);
-                let mut offsets = Vec::with_capacity(n as usize);
+        Ok(object)
+    }
 
-                for _ in 0..n {
-                    let num = parser.get_object()?;
-                    let offset = parser.get_object()?;
+    /// Returns the decoded data of an object stream and its offset table:
+    /// the number of each object and the offset of its data in the decoded
+    /// stream.
+    ///
+    /// The decoded data goes through the decoded-stream cache, and the offset
+    /// table is parsed once per object stream, so objects of the same stream
+    /// are fetched without reading the table again.
+    fn load_object_stream(&mut self, obj_stream_num: u32) -> PDFResult<(Rc<Vec<u8>>, ObjStmTable)> {
+        // First, fetch the object stream itself (as an uncompressed object)
+        let obj_stream_obj = self.fetch(obj_stream_num, 0)?;
 
-                    let obj_num = match num {
-                        PDFObject::Number(n) => n as u32,
-                        _ => {
-                            return Err(PDFError::Generic(format!(
-                                "Expected object number, got {:?}",
-                                num
-                            )));
-                        }
-                    };
+        // The object stream must be a Stream object with dictionary and data
+        let (dict, data) = match &*obj_stream_obj {
+            PDFObject::Stream { dict, data } => (dict, data),
+            _ => {
+                return Err(PDFError::corrupted_pdf(format!(
+                    "ObjStm {} is not a stream",
+                    obj_stream_num
+                )));
+            }
+        };
 
-                    let obj_offset = match offset {
-                        PDFObject::Number(n) => n as usize,
-                        _ => {
-                            return Err(PDFError::Generic(format!(
-                                "Expected offset, got {:?}",
-                                offset
-                            )));
-                        }
-                    };
+        // Check if this is an ObjStm
+        if let Some(PDFObject::Name(type_name)) = dict.get("Type")
+            && type_name != "ObjStm"
+        {
+            return Err(PDFError::corrupted_pdf(format!(
+                "Expected ObjStm type, got /{}",
+                type_name
+            )));
+        }
 
-                    obj_nums.push(obj_num);
-                    offsets.push(obj_offset);
-                }
+        // Decompress the stream data, including any predictor in DecodeParms.
+        // Every object in the stream needs it, so it goes through the cache.
+        let decompressed_data = self
+            .decode_cached(obj_stream_num, 0, dict, data)
+            .map_err(|e| e.in_object(obj_stream_num, 0))?;
+
+        if let Some(table) = self.obj_stream_tables.get(&obj_stream_num) {
+            return Ok((decompressed_data, Rc::clone(table)));
+        }
+
+        // Get N (number of objects) and First (byte offset of first object)
+        let n = dict
+            .get("N")
+            .and_then(|obj| match obj {
+                PDFObject::Number(n) => Some(*n as u32),
+                _ => None,
+            })
+            .ok_or_else(|| PDFError::corrupted_pdf("ObjStm missing /N parameter"))?;
+
+        let first = dict
+            .get("First")
+            .and_then(|obj| match obj {
+                PDFObject::Number(n) => Some(*n as usize),
+                _ => None,
+            })
+            .ok_or_else(|| PDFError::corrupted_pdf("ObjStm missing /First parameter"))?;
+
+        if first > decompressed_data.len() {
+            return Err(PDFError::corrupted_pdf(format!(
+                "ObjStm: /First {} exceeds stream length {}",
+                first,
+                decompressed_data.len()
+            )));
+        }
+
+        // Parse the object number/offset pairs (first N pairs of integers)
+        let index_stream = Stream::from_bytes(decompressed_data[..first].to_vec());
+        let lexer = Lexer::new(Box::new(index_stream) as Box<dyn BaseStream>)?;
+        let mut parser = Parser::new(lexer)?;
 
-                // Now parse the object at the requested index
-                let obj_offset = first + offsets[index as usize];
+        let mut table = Vec::with_capacity((n as usize).min(first / 4 + 1));
+        for _ in 0..n {
+            let num = parser.get_object()?;
+            let offset = parser.get_object()?;
 
-                // Validate offset is within bounds
-                if obj_offset >= decompressed_data.len() {
+            let obj_num = match num {
+                PDFObject::Number(n) => n as u32,
+                _ => {
                     return Err(PDFError::corrupted_pdf(format!(
-                        "ObjStm: object offset {} exceeds stream length {}",
-                        obj_offset,
-                        decompressed_data.len()
+                        "Expected object number, got {:?}",
+                        num
                     )));
                 }
+            };
 
-                let obj_length = if (index as usize) < offsets.len() - 1 {
-                    // Length is the difference between consecutive offsets
-                    offsets[index as usize + 1] - offsets[index as usize]
-                } else {
-                    // Last object extends to end of data
-                    decompressed_data.len() - obj_offset
-                };
-
-                // Validate the calculated range is within bounds
-                let obj_end = obj_offset + obj_length;
-                if obj_end > decompressed_data.len() {
+            let obj_offset = match offset {
+                PDFObject::Number(n) => n as usize,
+                _ => {
                     return Err(PDFError::corrupted_pdf(format!(
-                        "ObjStm: object range {}..{} exceeds stream length {}",
-                        obj_offset,
-                        obj_end,
-                        decompressed_data.len()
+                        "Expected offset, got {:?}",
+                        offset
                     )));
                 }
+            };
 
-                // Create a stream for just this object's data
-                let obj_data = decompressed_data[obj_offset..obj_end].to_vec();
-                let obj_stream = Stream::from_bytes(obj_data);
-                let obj_lexer = Lexer::new(Box::new(obj_stream) as Box<dyn BaseStream>)?;
-                let mut obj_parser = Parser::new(obj_lexer)?;
+            table.push((obj_num, first + obj_offset));
+        }
 
-                // Parse the object (no "obj"/"endobj" wrappers in ObjStm)
-                let object = Rc::new(obj_parser.get_object()?);
+        let table: ObjStmTable = table.into();
+        self.obj_stream_tables
+            .insert(obj_stream_num, Rc::clone(&table));
+        Ok((decompressed_data, table))
+    }
+
+    /// Lists the objects stored in object stream `obj_stream_num`, in the
+    /// order of their indices.
+    ///
+    /// Only the stream's offset table is read; each object is parsed when
+    /// it's fetched. Objects in object streams always have generation 0.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use pdf_x_core::core::{PDFDocument, XRefEntry};
+    ///
+    /// let mut doc = PDFDocument::open(std::fs::read("document.pdf").unwrap()).unwrap();
+    /// let xref = doc.xref_mut();
+    /// let streams: Vec<u32> = xref
+    ///     .iter_entries()
+    ///     .filter_map(|(_, entry)| match entry {
+    ///         XRefEntry::Compressed { obj_stream_num, .. } => Some(*obj_stream_num),
+    ///         _ => None,
+    ///     })
+    ///     .collect();
+    /// for object in xref.object_stream_objects(streams[0]).unwrap() {
+    ///     println!("{} {} R", object.num, object.generation);
+    /// }
+    /// ```
+    pub fn object_stream_objects(&mut self, obj_stream_num: u32) -> PDFResult<Vec<Ref>> {
+        let (_, table) = self.load_object_stream(obj_stream_num)?;
+        Ok(table.iter().map(|&(num, _)| Ref::new(num, 0)).collect())
+    }
+
+    /// Fetches an indirect object by reference.
+    ///
+    /// This resolves an indirect reference like "5 0 R" to its actual object.
+    /// Objects modified or added in the delta layer take precedence over the
+    /// file; deleted objects cannot be fetched. Objects read from the file are
+    /// cached after being parsed. Returns an Rc to avoid expensive cloning.
+    pub fn fetch(&mut self, obj_num: u32, generation: u32) -> PDFResult<Rc<PDFObject>> {
+        let obj_ref = Ref::new(obj_num, generation);
+        if self.delta.is_deleted(&obj_ref) {
+            return Err(PDFError::missing_object(obj_num, generation));
+        }
+        if let Some(delta_obj) = self.delta.get(&obj_ref) {
+            return Ok(Rc::new(delta_obj.object.clone()));
+        }
 
-                // Cache it with the actual object number
-                let actual_obj_num = obj_nums[index as usize];
-                self.cache.put(actual_obj_num, Rc::clone(&object));
+        self.fetch_base(obj_num, generation)
+            .map_err(|e| e.in_object(obj_num, generation))
+    }
 
-                Ok(object)
+    /// Fetches several objects, reading them in file order.
+    ///
+    /// The objects' bytes are loaded up front with [`prefetch`](Self::prefetch)
+    /// and the objects are then parsed in the order they appear in the file,
+    /// so a chunked stream moves forward through the file once instead of
+    /// seeking back and forth. The objects are returned in the order of
+    /// `refs`.
+    ///
+    /// # Arguments
+    /// * `refs` - The references to fetch
+    ///
+    /// # Returns
+    /// The objects, or the error of the first one that can't be fetched
+    pub fn fetch_many(&mut self, refs: &[Ref]) -> PDFResult<Vec<Rc<PDFObject>>> {
+        self.prefetch(refs)?;
+
+        let mut order: Vec<usize> = (0..refs.len()).collect();
+        order.sort_by_key(|&i| self.file_position(refs[i].num));
+
+        let mut fetched = Vec::with_capacity(refs.len());
+        for i in order {
+            fetched.push((i, self.fetch(refs[i].num, refs[i].generation)?));
+        }
+        fetched.sort_by_key(|(i, _)| *i);
+        Ok(fetched.into_iter().map(|(_, object)| object).collect())
+    }
+
+    /// Loads the bytes of several objects ahead of fetching them.
+    ///
+    /// The byte ranges of the objects (or of the object streams holding
+    /// them) are sorted by offset and merged, and then requested from the
+    /// underlying stream in that order. For a chunked stream this loads
+    /// every missing chunk once, in file order, rather than one chunk at a
+    /// time as each object is parsed. Objects that are cached, in the delta
+    /// layer or not in the table are skipped, and a stream that is already
+    /// fully loaded is left alone.
+    ///
+    /// An object is assumed to extend up to the next object in the file, so
+    /// the ranges are an upper bound and never miss any of its bytes. The
+    /// sorted offsets of the objects are computed by the first call and
+    /// reused until the table is parsed or reconstructed again.
+    pub fn prefetch(&mut self, refs: &[Ref]) -> PDFResult<()> {
+        if self.stream.is_data_loaded() || refs.is_empty() {
+            return Ok(());
+        }
+
+        if self.offset_index.is_none() {
+            let mut offsets: Vec<usize> = self
+                .iter_entries()
+                .filter_map(|(_, entry)| match entry {
+                    XRefEntry::Uncompressed { offset, .. } => Some(*offset as usize),
+                    _ => None,
+                })
+                .collect();
+            offsets.sort_unstable();
+            offsets.dedup();
+            self.offset_index = Some(offsets);
+        }
+        let offsets = self.offset_index.as_deref().unwrap_or_default();
+
+        let length = self.stream.length();
+        let mut ranges: Vec<(usize, usize)> = Vec::new();
+        for obj_ref in refs {
+            if self.delta.get(obj_ref).is_some()
+                || self
+                    .cache
+                    .get_object(obj_ref.num, obj_ref.generation)
+                    .is_some()
+            {
+                continue;
             }
-            PDFObject::Dictionary(_) => {
-                // If it's just a dictionary without stream data, we can't decompress it yet
-                Err(PDFError::Generic(
-                    "ObjStm is a dictionary but stream data parsing not yet implemented"
-                        .to_string(),
-                ))
+            let container = match self.get_entry(obj_ref.num) {
+                Some(XRefEntry::Compressed { obj_stream_num, .. }) => *obj_stream_num,
+                _ => obj_ref.num,
+            };
+            let Some(XRefEntry::Uncompressed { offset, .. }) = self.get_entry(container) else {
+                continue;
+            };
+            let start = *offset as usize;
+            if start >= length {
+                continue;
             }
-            _ => Err(PDFError::Generic(
-                "ObjStm is not a stream or dictionary".to_string(),
-            )),
+            let end = match offsets.binary_search(&start) {
+                Ok(i) => offsets.get(i + 1).copied().unwrap_or(length),
+                Err(i) => offsets.get(i).copied().unwrap_or(length),
+            };
+            ranges.push((start, end.min(length)));
         }
+
+        for (start, end) in merge_ranges(ranges) {
+            self.stream.ensure_range(start, end - start)?;
+        }
+        Ok(())
     }
 
-    /// Fetches an indirect object by reference.
+    /// Returns where an object's bytes start in the file, following objects
+    /// in object streams to their stream. Unknown objects sort last.
+    fn file_position(&self, obj_num: u32) -> u64 {
+        let container = match self.get_entry(obj_num) {
+            Some(XRefEntry::Compressed { obj_stream_num, .. }) => *obj_stream_num,
+            _ => obj_num,
+        };
+        match self.get_entry(container) {
+            Some(XRefEntry::Uncompressed { offset, .. }) => *offset,
+            _ => u64::MAX,
+        }
+    }
+
+    /// Fetches an indirect object from the file, ignoring the delta layer.
     ///
-    /// This resolves an indirect reference like "5 0 R" to its actual object.
-    /// The object is cached after being parsed. Returns an Rc to avoid expensive cloning.
-    pub fn fetch(&mut self, obj_num: u32, generation: u32) -> PDFResult<Rc<PDFObject>> {
+    /// This is what editing commands see as the "original" version of an object.
+    ///
+    /// A reference whose generation doesn't match the current entry (a stale
+    /// reference to an object number that was freed and reused) fails.
+    /// Objects in object streams always have generation 0.
+    pub fn fetch_base(&mut self, obj_num: u32, generation: u32) -> PDFResult<Rc<PDFObject>> {
+        let entry_gen = match self.get_entry(obj_num) {
+            Some(XRefEntry::Uncompressed {
+                generation: entry_gen,
+                ..
+            }) => Some(*entry_gen),
+            Some(XRefEntry::Compressed { .. }) => Some(0),
+            _ => None,
+        };
+        if let Some(entry_gen) = entry_gen
+            && entry_gen != generation
+        {
+            // The object was replaced by another with a different generation
+            return Err(PDFError::missing_object(obj_num, generation));
+        }
+
         // Check cache first - Rc::clone is cheap (just increments refcount)
-        if let Some(cached) = self.cache.get(&obj_num) {
-            return Ok(Rc::clone(cached));
+        if let Some(cached) = self.cache.get_object(obj_num, generation) {
+            return Ok(cached);
         }
 
+        self.check_cancelled()?;
+
+        // Loading an object can load others (a /Length, the object stream
+        // holding it), which a crafted file can chain indefinitely
+        if self.fetch_depth >= self.limits.max_reference_depth {
+            return Err(PDFError::limit_exceeded(
+                "indirect reference depth",
+                self.limits.max_reference_depth,
+            ));
+        }
+        self.fetch_depth += 1;
+        let result = self.load_base(obj_num, generation);
+        self.fetch_depth -= 1;
+        result
+    }
+
+    /// Loads an object that isn't in the cache from the file.
+    fn load_base(&mut self, obj_num: u32, generation: u32) -> PDFResult<Rc<PDFObject>> {
         // Get xref entry
         let entry = self
             .get_entry(obj_num)
-            .ok_or_else(|| PDFError::Generic(format!("Object {} not found in xref", obj_num)))?;
+            .ok_or_else(|| PDFError::missing_object(obj_num, generation))?;
 
         match entry {
-            XRefEntry::Free { .. } => Err(PDFError::Generic(format!(
-                "Cannot fetch free object {}",
-                obj_num
-            ))),
+            XRefEntry::Free { .. } => Err(PDFError::missing_object(obj_num, generation)),
 
             XRefEntry::Uncompressed {
                 offset,
                 generation: entry_gen,
             } => {
-                // Verify generation number matches
-                if generation != *entry_gen {
-                    return Err(PDFError::Generic(format!(
-                        "Generation mismatch for object {}: expected {}, got {}",
-                        obj_num, entry_gen, generation
-                    )));
-                }
-
-                // Clone the offset to avoid borrow checker issues
-                let offset_value = *offset;
-                let stream_length = self.stream.length();
-
-                // Validate offset is within stream bounds
-                if offset_value as usize >= stream_length {
-                    return Err(PDFError::corrupted_pdf(format!(
-                        "Object offset {} exceeds stream length {}",
-                        offset_value, stream_length
-                    )));
-                }
-
-                // Create a sub-stream starting at the object's position
-                // No need to manipulate parent stream position - sub-stream is independent
-                let sub_stream = self.stream.make_sub_stream(
-                    offset_value as usize,
-                    stream_length - offset_value as usize,
-                )?;
-
-                // Parse the indirect object
-                // Format: N G obj ... endobj
-                let lexer = Lexer::new(sub_stream)?;
-                let mut parser = Parser::new(lexer)?;
-
-                // CRITICAL FIX: Set up a reference resolver so the parser can resolve
-                // indirect /Length references in streams. Without this, streams with
-                // /Length references fall back to scanning for "endstream" which can
-                // read incorrect boundaries and capture "endobj" as stream data.
-                //
-                // We create a closure that captures a mutable reference to self.
-                // This is safe because:
-                // 1. The resolver is only called during parser.get_object() below
-                // 2. We're not modifying the XRef entries during fetch (only reading/caching)
-                // 3. Rust's borrow checker ensures no other mutable borrows exist
-                //
-                // However, we can't directly capture &mut self in the closure because
-                // it would create a self-referential struct. Instead, we'll use an
-                // unsafe pointer cast. This is safe because:
-                // - The parser lifetime is scoped to this function
-                // - We ensure no re-entrant calls that could invalidate the pointer
-                // - The XRef object is not moved or dropped during parsing
-                let self_ptr = self as *mut XRef;
-                parser.set_ref_resolver(move |num, generation| {
-                    // SAFETY: This is safe because:
-                    // 1. self_ptr is valid for the duration of parser.get_object()
-                    // 2. No other code can modify or move the XRef during this time
-                    // 3. We're only calling fetch() which is part of XRef's public API
-                    unsafe { (*self_ptr).fetch(num, generation) }.map(|rc| (*rc).clone())
-                });
-
-                // Read object number
-                let num_obj = parser.get_object()?;
-                let parsed_num = match num_obj {
-                    PDFObject::Number(n) => n as u32,
-                    _ => {
-                        return Err(PDFError::Generic(format!(
-                            "Expected object number, got {:?}",
-                            num_obj
-                        )));
-                    }
-                };
-
-                if parsed_num != obj_num {
-                    return Err(PDFError::Generic(format!(
-                        "Object number mismatch: expected {}, got {}",
-                        obj_num, parsed_num
-                    )));
-                }
-
-                // Read generation number
-                let gen_obj = parser.get_object()?;
-                let parsed_gen = match gen_obj {
-                    PDFObject::Number(n) => n as u32,
-                    _ => {
-                        return Err(PDFError::Generic(format!(
-                            "Expected generation number, got {:?}",
-                            gen_obj
-                        )));
+                let (offset, entry_gen) = (*offset, *entry_gen);
+                let object = match self.read_uncompressed(obj_num, generation, offset, entry_gen) {
+                    Ok(object) => object,
+                    // The entry points somewhere else than the object: the
+                    // table is broken, so rebuild it once and try again
+                    Err(e @ PDFError::CorruptedPDF { .. }) if !self.reconstructed => {
+                        self.record_diagnostic(DiagnosticKind::XRefReconstructed, format!(
+                            "Cross-reference entry of object {} {} is wrong ({}), reconstructing the table",
+                            obj_num, generation, e
+                        ));
+                        self.reconstruct()?;
+                        return self.fetch_base(obj_num, generation);
                     }
+                    Err(e) => return Err(e),
                 };
-
-                if parsed_gen != generation {
-                    return Err(PDFError::Generic(format!(
-                        "Generation number mismatch: expected {}, got {}",
-                        generation, parsed_gen
-                    )));
-                }
-
-                // Read "obj" keyword
-                let obj_keyword = parser.get_object()?;
-                if !obj_keyword.is_command("obj") {
-                    return Err(PDFError::Generic(format!(
-                        "Expected 'obj' keyword, got {:?}",
-                        obj_keyword
-                    )));
-                }
-
-                // Read the actual object
-                let object = parser.get_object()?;
                 let object_rc = Rc::new(object);
 
                 // Cache the Rc - cheap clone
-                self.cache.put(obj_num, Rc::clone(&object_rc));
+                self.cache
+                    .put_object(obj_num, generation, Rc::clone(&object_rc));
 
                 Ok(object_rc)
             }
@@ -980,6 +1300,181 @@ impl XRef {
         }
     }
 
+    /// Reads the uncompressed object `obj_num` stored at `offset`.
+    fn read_uncompressed(
+        &mut self,
+        obj_num: u32,
+        generation: u32,
+        offset: u64,
+        entry_gen: u32,
+    ) -> PDFResult<PDFObject> {
+        // Verify generation number matches
+        if generation != entry_gen {
+            // The object was replaced by another with a different generation
+            return Err(PDFError::missing_object(obj_num, generation));
+        }
+
+        let offset_value = offset;
+        let stream_length = self.stream.length();
+
+        // Validate offset is within stream bounds
+        if offset_value as usize >= stream_length {
+            return Err(PDFError::corrupted_pdf(format!(
+                "Object offset {} exceeds stream length {}",
+                offset_value, stream_length
+            )));
+        }
+
+        // Create a sub-stream starting at the object's position
+        // No need to manipulate parent stream position - sub-stream is independent
+        let sub_stream = self
+            .stream
+            .make_sub_stream(offset_value as usize, stream_length - offset_value as usize)?;
+
+        // Parse the indirect object
+        // Format: N G obj ... endobj
+        let lexer = Lexer::new(sub_stream)?;
+        let mut parser = Parser::new(lexer)?;
+        parser.set_max_depth(self.limits.max_object_depth);
+
+        // CRITICAL FIX: Set up a reference resolver so the parser can resolve
+        // indirect /Length references in streams. Without this, streams with
+        // /Length references fall back to scanning for "endstream" which can
+        // read incorrect boundaries and capture "endobj" as stream data.
+        //
+        // We create a closure that captures a mutable reference to self.
+        // This is safe because:
+        // 1. The resolver is only called during parser.get_object() below
+        // 2. We're not modifying the XRef entries during fetch (only reading/caching)
+        // 3. Rust's borrow checker ensures no other mutable borrows exist
+        //
+        // However, we can't directly capture &mut self in the closure because
+        // it would create a self-referential struct. Instead, we'll use an
+        // unsafe pointer cast. This is safe because:
+        // - The parser lifetime is scoped to this function
+        // - We ensure no re-entrant calls that could invalidate the pointer
+        // - The XRef object is not moved or dropped during parsing
+        parser.set_lenient(self.lenient);
+        let self_ptr = self as *mut XRef;
+        parser.set_ref_resolver(move |num, generation| {
+            // SAFETY: This is safe because:
+            // 1. self_ptr is valid for the duration of parser.get_object()
+            // 2. No other code can modify or move the XRef during this time
+            // 3. We're only calling fetch() which is part of XRef's public API
+            unsafe { (*self_ptr).fetch(num, generation) }.map(|rc| (*rc).clone())
+        });
+
+        // Read object number
+        let num_obj = parser.get_object()?;
+        let parsed_num = match num_obj {
+            PDFObject::Number(n) => n as u32,
+            _ => {
+                return Err(PDFError::corrupted_pdf(format!(
+                    "Expected object number, got {:?}",
+                    num_obj
+                )));
+            }
+        };
+
+        if parsed_num != obj_num {
+            return Err(PDFError::corrupted_pdf(format!(
+                "Object number mismatch: expected {}, got {}",
+                obj_num, parsed_num
+            )));
+        }
+
+        // Read generation number
+        let gen_obj = parser.get_object()?;
+        let parsed_gen = match gen_obj {
+            PDFObject::Number(n) => n as u32,
+            _ => {
+                return Err(PDFError::corrupted_pdf(format!(
+                    "Expected generation number, got {:?}",
+                    gen_obj
+                )));
+            }
+        };
+
+        if parsed_gen != generation {
+            return Err(PDFError::corrupted_pdf(format!(
+                "Generation number mismatch: expected {}, got {}",
+                generation, parsed_gen
+            )));
+        }
+
+        // Read "obj" keyword
+        let obj_keyword = parser.get_object()?;
+        if !obj_keyword.is_command("obj") {
+            return Err(PDFError::corrupted_pdf(format!(
+                "Expected 'obj' keyword, got {:?}",
+                obj_keyword
+            )));
+        }
+
+        // Read the actual object. Objects inside object streams are covered
+        // by the encryption of the object stream itself, so only objects read
+        // here carry encrypted strings and stream data.
+        let object = parser.get_object()?;
+        for repair in parser.take_repairs() {
+            self.record_diagnostic(
+                DiagnosticKind::StreamLength,
+                format!("Object {} {}: {}", obj_num, generation, repair),
+            );
+        }
+
+        let terminated = match &object {
+            PDFObject::Stream { .. } => {
+                is_command(parser.get_object(), "endstream")?
+                    && is_command(parser.get_object(), "endobj")?
+            }
+            _ => is_command(parser.get_object(), "endobj")?,
+        };
+        if !terminated {
+            if !self.lenient {
+                return Err(PDFError::corrupted_pdf(format!(
+                    "Object {} {} is not terminated by endobj",
+                    obj_num, generation
+                )));
+            }
+            self.record_diagnostic(
+                DiagnosticKind::MissingEndobj,
+                format!(
+                    "Object {} {} is not terminated by endobj",
+                    obj_num, generation
+                ),
+            );
+        }
+
+        match &self.cipher {
+            Some(cipher) if !self.is_encrypt_dict(obj_num) => {
+                cipher.decrypt_object(object, obj_num, generation)
+            }
+            _ => Ok(object),
+        }
+    }
+
+    /// Returns true if `obj_num` is the trailer's /Encrypt dictionary, which
+    /// is never encrypted itself.
+    fn is_encrypt_dict(&self, obj_num: u32) -> bool {
+        matches!(self.get_encrypt_dict_ref(), Some(PDFObject::Ref(r)) if r.num == obj_num)
+    }
+
+    /// Sets the transform that decrypts objects read from the file.
+    ///
+    /// Cached objects and decoded streams are dropped, since they were read
+    /// with the previous transform.
+    pub fn set_cipher(&mut self, cipher: Option<CipherTransform>) {
+        self.cipher = cipher;
+        self.cache.clear();
+        self.obj_stream_tables.clear();
+    }
+
+    /// Returns the transform that decrypts objects, if the document is
+    /// encrypted and has been unlocked.
+    pub fn cipher(&self) -> Option<&CipherTransform> {
+        self.cipher.as_ref()
+    }
+
     /// Fetches an object if it's a reference, otherwise returns the object as-is.
     ///
     /// Returns an owned PDFObject (cloned from Rc if fetched from cache).
@@ -994,11 +1489,200 @@ impl XRef {
         }
     }
 
+    /// Returns the decoded data of a stream, given the stream or a reference
+    /// to it.
+    ///
+    /// Streams fetched by reference from the file are decoded once and kept
+    /// in the decoded-stream cache, so content streams and font programs
+    /// aren't inflated again every time a page is rendered. Direct streams and
+    /// streams edited in the delta layer are decoded without caching.
+    ///
+    /// # Returns
+    /// The decoded bytes, or an error if the object isn't a stream or its
+    /// filters fail
+    pub fn decoded_stream(&mut self, source: &PDFObject) -> PDFResult<Rc<Vec<u8>>> {
+        match source {
+            PDFObject::Ref(r) => {
+                let object = self.fetch(r.num, r.generation)?;
+                match &*object {
+                    PDFObject::Stream { dict, data } if self.delta.get(r).is_none() => {
+                        self.decode_cached(r.num, r.generation, dict, data)
+                    }
+                    PDFObject::Stream { dict, data } => Ok(Rc::new(
+                        decode::decode_stream_data_limited(dict, data, &self.limits)?,
+                    )),
+                    other => Err(PDFError::invalid_object("Stream", other.type_name())
+                        .in_object(r.num, r.generation)),
+                }
+            }
+            PDFObject::Stream { dict, data } => Ok(Rc::new(decode::decode_stream_data_limited(
+                dict,
+                data,
+                &self.limits,
+            )?)),
+            other => Err(PDFError::invalid_object("Stream", other.type_name())),
+        }
+    }
+
+    /// Decodes a stream from the file through the decoded-stream cache.
+    fn decode_cached(
+        &mut self,
+        obj_num: u32,
+        generation: u32,
+        dict: &HashMap<String, PDFObject>,
+        data: &[u8],
+    ) -> PDFResult<Rc<Vec<u8>>> {
+        if let Some(cached) = self.cache.get_stream(obj_num, generation) {
+            return Ok(cached);
+        }
+        let decoded = Rc::new(decode::decode_stream_data_limited(
+            dict,
+            data,
+            &self.limits,
+        )?);
+        self.cache
+            .put_stream(obj_num, generation, Rc::clone(&decoded));
+        Ok(decoded)
+    }
+
+    /// Returns hit, miss and size counters of the object and decoded-stream
+    /// caches.
+    pub fn cache_stats(&self) -> CacheStats {
+        self.cache.stats()
+    }
+
+    /// Resets the cache hit, miss and eviction counters.
+    pub fn reset_cache_stats(&mut self) {
+        self.cache.reset_stats();
+    }
+
+    /// Returns the memory budget of the caches.
+    pub fn cache_budget(&self) -> CacheBudget {
+        self.cache.budget()
+    }
+
+    /// Changes the memory budget of the caches, evicting entries right away
+    /// if it shrank.
+    pub fn set_cache_budget(&mut self, budget: CacheBudget) {
+        self.cache.set_budget(budget);
+    }
+
+    /// Returns the pending edits layered over this xref table.
+    pub fn delta(&self) -> &DeltaLayer {
+        &self.delta
+    }
+
+    /// Returns the pending edits for direct manipulation.
+    pub fn delta_mut(&mut self) -> &mut DeltaLayer {
+        &mut self.delta
+    }
+
+    /// Executes an editing command, recording its changes in the delta layer.
+    ///
+    /// The command receives a fetcher that reads unmodified objects from the file.
+    pub fn execute_command(&mut self, cmd: Box<dyn Command>) -> PDFResult<()> {
+        // Move the delta out so the command can mutate it while the fetcher
+        // borrows the rest of the xref table
+        let mut delta = std::mem::replace(&mut self.delta, DeltaLayer::new(0));
+
+        let result = {
+            let xref = RefCell::new(&mut *self);
+            let fetch_base = |obj_ref: Ref| -> PDFResult<PDFObject> {
+                let fetched = xref
+                    .borrow_mut()
+                    .fetch_base(obj_ref.num, obj_ref.generation)?;
+                Ok((*fetched).clone())
+            };
+            delta.execute_command(cmd, Some(&fetch_base))
+        };
+
+        self.delta = delta;
+        result
+    }
+
     /// Returns the trailer dictionary.
     pub fn trailer(&self) -> Option<&PDFObject> {
         self.trailer.as_ref()
     }
 
+    /// Returns the revisions of the file, oldest first.
+    ///
+    /// A file without incremental updates has a single revision. The last
+    /// revision's trailer is the one returned by [`trailer`](Self::trailer).
+    pub fn revisions(&self) -> &[XRefRevision] {
+        &self.revisions
+    }
+
+    /// Fetches an object as it was stored in an earlier revision.
+    ///
+    /// The object is looked up in the xref section of `revision` and then in
+    /// the sections before it, so versions written by later incremental
+    /// updates are ignored. The result bypasses the object cache and the
+    /// delta layer.
+    ///
+    /// Objects inside object streams can only be fetched when the entry is
+    /// the same one the current revision uses.
+    ///
+    /// # Arguments
+    /// * `revision` - Index into [`revisions`](Self::revisions)
+    /// * `obj_num` - The object number
+    /// * `generation` - The generation number
+    pub fn fetch_at_revision(
+        &mut self,
+        revision: usize,
+        obj_num: u32,
+        generation: u32,
+    ) -> PDFResult<PDFObject> {
+        let entry = self
+            .revisions
+            .get(..=revision)
+            .and_then(|revisions| revisions.iter().rev().find_map(|r| r.entries.get(&obj_num)))
+            .cloned()
+            .ok_or_else(|| PDFError::missing_object_in_revision(obj_num, generation, revision))?;
+
+        match entry {
+            XRefEntry::Free { .. } => Err(PDFError::missing_object_in_revision(
+                obj_num, generation, revision,
+            )),
+            XRefEntry::Uncompressed {
+                generation: entry_gen,
+                ..
+            } if entry_gen != generation => Err(PDFError::missing_object_in_revision(
+                obj_num, generation, revision,
+            )),
+            XRefEntry::Uncompressed {
+                offset,
+                generation: entry_gen,
+            } => self.read_uncompressed(obj_num, generation, offset, entry_gen),
+            XRefEntry::Compressed {
+                obj_stream_num,
+                index,
+            } => {
+                let is_current = matches!(
+                    self.get_entry(obj_num),
+                    Some(XRefEntry::Compressed { obj_stream_num: n, index: i })
+                        if *n == obj_stream_num && *i == index
+                );
+                if !is_current {
+                    return Err(PDFError::unsupported(format!(
+                        "object {} of revision {} is in a superseded object stream",
+                        obj_num, revision
+                    )));
+                }
+                self.fetch_base(obj_num, generation).map(|rc| (*rc).clone())
+            }
+        }
+    }
+
+    /// Returns a typed view of the trailer dictionary.
+    pub fn trailer_view(&self) -> PDFResult<Trailer<'_>> {
+        let trailer = self
+            .trailer
+            .as_ref()
+            .ok_or_else(|| PDFError::xref_error("No trailer dictionary"))?;
+        Trailer::new(trailer)
+    }
+
     /// Returns the length of the underlying stream.
     ///
     /// This is useful for determining the original PDF file size.
@@ -1013,16 +1697,16 @@ impl XRef {
             let trailer = self
                 .trailer
                 .as_ref()
-                .ok_or_else(|| PDFError::Generic("No trailer dictionary".to_string()))?;
+                .ok_or_else(|| PDFError::xref_error("No trailer dictionary"))?;
 
             let trailer_dict = match trailer {
                 PDFObject::Dictionary(dict) => dict,
-                _ => return Err(PDFError::Generic("Trailer is not a dictionary".to_string())),
+                _ => return Err(PDFError::xref_error("Trailer is not a dictionary")),
             };
 
             trailer_dict
                 .get("Root")
-                .ok_or_else(|| PDFError::Generic("No Root entry in trailer".to_string()))?
+                .ok_or_else(|| PDFError::xref_error("No Root entry in trailer"))?
                 .clone()
         };
 
@@ -1045,43 +1729,28 @@ impl XRef {
         let trailer = self
             .trailer
             .as_ref()
-            .ok_or_else(|| PDFError::Generic("No trailer dictionary".to_string()))?;
+            .ok_or_else(|| PDFError::xref_error("No trailer dictionary"))?;
 
         let trailer_dict = match trailer {
             PDFObject::Dictionary(dict) => dict,
-            _ => return Err(PDFError::Generic("Trailer is not a dictionary".to_string())),
+            _ => return Err(PDFError::xref_error("Trailer is not a dictionary")),
         };
 
         // Get the ID entry - it's an array of two byte strings
         let id_obj = trailer_dict
             .get("ID")
-            .ok_or_else(|| PDFError::Generic("No ID entry in trailer".to_string()))?;
+            .ok_or_else(|| PDFError::xref_error("No ID entry in trailer"))?;
 
         match id_obj {
             PDFObject::Array(arr) if !arr.is_empty() => {
                 // The first element of the ID array is the file ID
+                // The lexer has already decoded the digits of hex strings
                 match &*arr[0] {
-                    PDFObject::String(bytes) => Ok(bytes.to_vec()),
-                    PDFObject::HexString(hex) => {
-                        // Convert hex string to bytes
-                        let hex_str = String::from_utf8_lossy(hex);
-                        let mut bytes = Vec::new();
-                        for i in (0..hex_str.len()).step_by(2) {
-                            if i + 1 < hex_str.len() {
-                                let byte_str = &hex_str[i..i + 2];
-                                if let Ok(byte) = u8::from_str_radix(byte_str, 16) {
-                                    bytes.push(byte);
-                                }
-                            }
-                        }
-                        Ok(bytes)
-                    }
-                    _ => Err(PDFError::Generic(
-                        "ID array element is not a string".to_string(),
-                    )),
+                    PDFObject::String(bytes) | PDFObject::HexString(bytes) => Ok(bytes.to_vec()),
+                    _ => Err(PDFError::xref_error("ID array element is not a string")),
                 }
             }
-            _ => Err(PDFError::Generic("ID is not an array".to_string())),
+            _ => Err(PDFError::xref_error("ID is not an array")),
         }
     }
 
@@ -1115,6 +1784,28 @@ impl XRef {
         self.stream.pos()
     }
 
+    /// Returns the complete contents of the underlying stream.
+    ///
+    /// For chunked streams this loads any chunks that are still missing, so
+    /// it should only be used when the whole file is needed (e.g. when
+    /// appending an incremental update to the original bytes).
+    pub fn original_bytes(&mut self) -> PDFResult<Vec<u8>> {
+        // Read in small blocks so chunked streams never need more chunks
+        // resident at once than their cache allows.
+        const BLOCK_SIZE: usize = 16 * 1024;
+
+        let length = self.stream.length();
+        let mut bytes = Vec::with_capacity(length);
+        let mut pos = 0;
+        while pos < length {
+            let end = (pos + BLOCK_SIZE).min(length);
+            self.stream.ensure_range(pos, end - pos)?;
+            bytes.extend_from_slice(&self.stream.get_byte_range(pos, end)?);
+            pos = end;
+        }
+        Ok(bytes)
+    }
+
     /// Gets bytes from the stream at a specific position without changing current position.
     pub fn get_bytes(&mut self, pos: usize, length: usize) -> PDFResult<Vec<u8>> {
         // Save current position
@@ -1134,6 +1825,319 @@ impl XRef {
 
         Ok(bytes)
     }
+
+    /// Returns true if the table was rebuilt by [`reconstruct`](Self::reconstruct).
+    pub fn is_reconstructed(&self) -> bool {
+        self.reconstructed
+    }
+
+    /// Rebuilds the cross-reference table by scanning the whole file for
+    /// object headers ("N G obj").
+    ///
+    /// Used when the xref is missing or points at the wrong offsets. When
+    /// an object is defined several times, the last definition wins, as in
+    /// an incremental update; objects inside object streams are indexed
+    /// too. The trailer is the last "trailer" dictionary or cross-reference
+    /// stream dictionary whose /Root is a catalog, or, failing that, one made
+    /// up from the last catalog object found. What was repaired is recorded
+    /// in [`warnings`](Self::warnings).
+    ///
+    /// Based on PDF.js XRef.indexObjects.
+    pub fn reconstruct(&mut self) -> PDFResult<()> {
+        let length = self.stream.length();
+        let data = self.stream.get_byte_range(0, length)?;
+
+        let headers = scan_object_headers(&data);
+        if headers.is_empty() {
+            return Err(PDFError::corrupted_pdf(
+                "No objects found while reconstructing the cross-reference table",
+            ));
+        }
+
+        let mut entries: Vec<Option<XRefEntry>> = Vec::new();
+        let mut object_streams = Vec::new();
+        let mut catalogs = Vec::new();
+        // Trailer candidates: position, and either the offset of a
+        // "trailer" dictionary or the number of an xref stream
+        let mut trailers: Vec<(usize, Result<usize, u32>)> = find_keyword(&data, b"trailer")
+            .into_iter()
+            .map(|pos| (pos, Ok(pos + b"trailer".len())))
+            .collect();
+
+        for (index, header) in headers.iter().enumerate() {
+            self.check_cancelled()?;
+            if index % 1024 == 0 {
+                self.report_progress(index, headers.len());
+            }
+            let num = header.num as usize;
+            self.limits.check_object_number(num)?;
+            if entries.len() <= num {
+                entries.resize(num + 1, None);
+            }
+            entries[num] = Some(XRefEntry::Uncompressed {
+                offset: header.offset as u64,
+                generation: header.generation,
+            });
+
+            // The dictionary of the object, up to its stream data
+            let body = &data[header.body..];
+            let body = &body[..body.len().min(1024)];
+            let body = match find_keyword(body, b"stream").first() {
+                Some(&end) => &body[..end],
+                None => body,
+            };
+            if contains_name(body, b"/ObjStm") {
+                object_streams.push(header.num);
+            }
+            if contains_name(body, b"/XRef") {
+                trailers.push((header.offset, Err(header.num)));
+            }
+            if contains_name(body, b"/Catalog") {
+                catalogs.push((header.num, header.generation));
+            }
+        }
+
+        self.report_progress(headers.len(), headers.len());
+
+        self.entries = entries;
+        self.offset_index = None;
+        self.cache.clear();
+        self.obj_stream_tables.clear();
+        self.section_entries.clear();
+        self.revisions.clear();
+        self.trailer = None;
+        self.reconstructed = true;
+        self.record_diagnostic(
+            DiagnosticKind::XRefReconstructed,
+            format!(
+                "Rebuilt the cross-reference table from {} object headers",
+                headers.len()
+            ),
+        );
+
+        for num in object_streams {
+            if let Err(e) = self.index_object_stream(num) {
+                self.record_diagnostic(
+                    DiagnosticKind::XRefReconstructed,
+                    format!("Skipped object stream {}: {}", num, e),
+                );
+            }
+        }
+
+        trailers.sort_by_key(|(pos, _)| *pos);
+        for (pos, source) in trailers.into_iter().rev() {
+            let trailer = match source {
+                Ok(dict_pos) => self.parse_trailer_at(dict_pos),
+                Err(num) => match self.fetch_base(num, 0).as_deref() {
+                    Ok(PDFObject::Stream { dict, .. }) => Some(PDFObject::Dictionary(dict.clone())),
+                    _ => None,
+                },
+            };
+            if let Some(trailer) = trailer
+                && self.has_catalog(&trailer)
+            {
+                self.record_diagnostic(
+                    DiagnosticKind::XRefReconstructed,
+                    format!("Using the trailer found at offset {}", pos),
+                );
+                self.trailer = Some(trailer);
+                break;
+            }
+        }
+
+        if self.trailer.is_none() {
+            let (num, generation) = catalogs
+                .into_iter()
+                .rev()
+                .find(|(num, generation)| {
+                    let root = PDFObject::Ref(Ref::new(*num, *generation));
+                    let mut dict = HashMap::new();
+                    dict.insert("Root".to_string(), root);
+                    self.has_catalog(&PDFObject::Dictionary(dict))
+                })
+                .ok_or_else(|| {
+                    PDFError::corrupted_pdf("No trailer or catalog found while reconstructing")
+                })?;
+            self.record_diagnostic(
+                DiagnosticKind::XRefReconstructed,
+                format!(
+                    "No usable trailer; using catalog {} {} as the root",
+                    num, generation
+                ),
+            );
+            let mut trailer = HashMap::new();
+            trailer.insert(
+                "Root".to_string(),
+                PDFObject::Ref(Ref::new(num, generation)),
+            );
+            trailer.insert(
+                "Size".to_string(),
+                PDFObject::Number(self.entries.len() as f64),
+            );
+            self.trailer = Some(PDFObject::Dictionary(trailer));
+        }
+
+        self.delta = DeltaLayer::new(self.entries.len() as u32);
+        Ok(())
+    }
+
+    /// Adds entries for the objects stored in object stream `num`, unless
+    /// they are also defined outside of it.
+    fn index_object_stream(&mut self, num: u32) -> PDFResult<()> {
+        let (_, table) = self.load_object_stream(num)?;
+        for (index, &(obj_num, _)) in table.iter().enumerate() {
+            let slot = obj_num as usize;
+            self.limits.check_object_number(slot)?;
+            if self.entries.len() <= slot {
+                self.entries.resize(slot + 1, None);
+            }
+            if self.entries[slot].is_none() {
+                self.entries[slot] = Some(XRefEntry::Compressed {
+                    obj_stream_num: num,
+                    index: index as u32,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Parses the dictionary following a "trailer" keyword.
+    fn parse_trailer_at(&mut self, pos: usize) -> Option<PDFObject> {
+        let length = self.stream.length();
+        let sub_stream = self.stream.make_sub_stream(pos, length - pos).ok()?;
+        let mut parser = Parser::new(Lexer::new(sub_stream).ok()?).ok()?;
+        match parser.get_object() {
+            Ok(trailer @ PDFObject::Dictionary(_)) => Some(trailer),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the /Root of `trailer` is a catalog with a page tree.
+    fn has_catalog(&mut self, trailer: &PDFObject) -> bool {
+        let root = match trailer {
+            PDFObject::Dictionary(dict) => match dict.get("Root") {
+                Some(PDFObject::Ref(root)) => *root,
+                _ => return false,
+            },
+            _ => return false,
+        };
+        match self.fetch_base(root.num, root.generation).as_deref() {
+            Ok(PDFObject::Dictionary(catalog)) => catalog.contains_key("Pages"),
+            _ => false,
+        }
+    }
+}
+
+/// Returns true if `token` is the command `cmd`. Parse errors count as
+/// another token, except missing data, which is propagated.
+fn is_command(token: PDFResult<PDFObject>, cmd: &str) -> PDFResult<bool> {
+    match token {
+        Ok(token) => Ok(token.is_command(cmd)),
+        Err(e @ PDFError::DataMissing { .. }) => Err(e),
+        Err(_) => Ok(false),
+    }
+}
+
+/// Sorts byte ranges by start and merges the ones that overlap or touch.
+fn merge_ranges(mut ranges: Vec<(usize, usize)>) -> Vec<(usize, usize)> {
+    ranges.sort_unstable();
+    let mut merged: Vec<(usize, usize)> = Vec::with_capacity(ranges.len());
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+/// An object header found while scanning a file.
+struct ObjectHeader {
+    /// Offset of the object number
+    offset: usize,
+    /// Offset just after the "obj" keyword
+    body: usize,
+    num: u32,
+    generation: u32,
+}
+
+fn is_pdf_whitespace(byte: u8) -> bool {
+    matches!(byte, b'\0' | b'\t' | b'\n' | 0x0C | b'\r' | b' ')
+}
+
+fn is_pdf_delimiter(byte: u8) -> bool {
+    matches!(
+        byte,
+        b'(' | b')' | b'<' | b'>' | b'[' | b']' | b'{' | b'}' | b'/' | b'%'
+    )
+}
+
+/// Finds every "N G obj" header in `data`.
+fn scan_object_headers(data: &[u8]) -> Vec<ObjectHeader> {
+    let mut headers = Vec::new();
+    for pos in find_keyword(data, b"obj") {
+        // Walk back over: whitespace, generation, whitespace, number
+        let mut i = pos;
+        let mut fields = [(0usize, 0usize); 2];
+        for field in fields.iter_mut() {
+            let whitespace_end = i;
+            while i > 0 && is_pdf_whitespace(data[i - 1]) {
+                i -= 1;
+            }
+            let digits_end = i;
+            while i > 0 && data[i - 1].is_ascii_digit() {
+                i -= 1;
+            }
+            if i == whitespace_end || i == digits_end || digits_end == whitespace_end {
+                i = usize::MAX;
+                break;
+            }
+            *field = (i, digits_end);
+        }
+        if i == usize::MAX
+            || (i > 0 && !is_pdf_whitespace(data[i - 1]) && !is_pdf_delimiter(data[i - 1]))
+        {
+            continue;
+        }
+
+        let number = |(start, end): (usize, usize)| -> Option<u32> {
+            std::str::from_utf8(&data[start..end]).ok()?.parse().ok()
+        };
+        if let (Some(num), Some(generation)) = (number(fields[1]), number(fields[0])) {
+            headers.push(ObjectHeader {
+                offset: i,
+                body: pos + b"obj".len(),
+                num,
+                generation,
+            });
+        }
+    }
+    headers
+}
+
+/// Finds the positions of `keyword` where it is a whole token.
+fn find_keyword(data: &[u8], keyword: &[u8]) -> Vec<usize> {
+    let is_regular = |byte: &u8| !is_pdf_whitespace(*byte) && !is_pdf_delimiter(*byte);
+    data.windows(keyword.len())
+        .enumerate()
+        .filter(|(pos, window)| {
+            *window == keyword
+                && (*pos == 0 || !is_regular(&data[pos - 1]))
+                && !data.get(pos + keyword.len()).is_some_and(is_regular)
+        })
+        .map(|(pos, _)| pos)
+        .collect()
+}
+
+/// Returns true if `data` contains the name `name` (with its slash) as a
+/// whole token.
+fn contains_name(data: &[u8], name: &[u8]) -> bool {
+    data.windows(name.len()).enumerate().any(|(pos, window)| {
+        window == name
+            && data
+                .get(pos + name.len())
+                .is_none_or(|&byte| is_pdf_whitespace(byte) || is_pdf_delimiter(byte))
+    })
 }
 
 /// Helper function to read big-endian integer from bytes.
@@ -1398,6 +2402,126 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_hybrid_file_with_incremental_update() {
+        fn push_stream(data: &mut Vec<u8>, header: &str, dict: &str, bytes: &[u8]) -> usize {
+            let offset = data.len();
+            data.extend_from_slice(
+                format!(
+                    "{}\n<< {} /Length {} >>\nstream\n",
+                    header,
+                    dict,
+                    bytes.len()
+                )
+                .as_bytes(),
+            );
+            data.extend_from_slice(bytes);
+            data.extend_from_slice(b"\nendstream\nendobj\n");
+            offset
+        }
+
+        let mut data = b"%PDF-1.5\n".to_vec();
+        let catalog = data.len();
+        data.extend_from_slice(b"1 0 obj\n<< /Type /Catalog /Pages 2 0 R >>\nendobj\n");
+        let pages = data.len();
+        data.extend_from_slice(b"2 0 obj\n<< /Type /Pages /Kids [] /Count 0 >>\nendobj\n");
+        let obj_stm = push_stream(
+            &mut data,
+            "3 0 obj",
+            "/Type /ObjStm /N 1 /First 4",
+            b"4 0 (compressed)",
+        );
+
+        // Entries for objects 3 and 4, with 3-byte offsets and 2-byte
+        // generations and indices
+        let mut entries = vec![1];
+        entries.extend_from_slice(&(obj_stm as u32).to_be_bytes()[1..]);
+        entries.extend_from_slice(&[0, 0, 2, 0, 0, 3, 0, 0]);
+        let xref_stm = push_stream(
+            &mut data,
+            "5 0 obj",
+            "/Type /XRef /Size 6 /W [1 3 2] /Index [3 2]",
+            &entries,
+        );
+
+        // The classic table lists the hidden objects as free
+        let table = data.len();
+        data.extend_from_slice(b"xref\n0 6\n0000000000 65535 f \n");
+        for offset in [catalog, pages] {
+            data.extend_from_slice(format!("{:010} 00000 n \n", offset).as_bytes());
+        }
+        data.extend_from_slice(b"0000000000 65535 f \n".repeat(3).as_slice());
+        data.extend_from_slice(
+            format!(
+                "trailer\n<< /Size 6 /Root 1 0 R /XRefStm {} >>\nstartxref\n{}\n%%EOF\n",
+                xref_stm, table
+            )
+            .as_bytes(),
+        );
+        let original_len = data.len();
+
+        // Incremental update: object 4 is freed and its number reused with
+        // generation 1, in an xref stream with two subsections
+        let updated = data.len();
+        data.extend_from_slice(b"4 1 obj\n(updated)\nendobj\n");
+        let update_xref = data.len();
+        let mut entries = vec![1];
+        entries.extend_from_slice(&(updated as u32).to_be_bytes());
+        entries.extend_from_slice(&[1, 1]);
+        entries.extend_from_slice(&(update_xref as u32).to_be_bytes());
+        entries.push(0);
+        push_stream(
+            &mut data,
+            "7 0 obj",
+            &format!(
+                "/Type /XRef /Size 8 /W [1 4 1] /Index [4 1 7 1] /Root 1 0 R /Prev {}",
+                table
+            ),
+            &entries,
+        );
+        data.extend_from_slice(format!("startxref\n{}\n%%EOF\n", update_xref).as_bytes());
+
+        let open = |bytes: &[u8], startxref: usize| {
+            let stream = Box::new(Stream::from_bytes(bytes.to_vec())) as Box<dyn BaseStream>;
+            let mut xref = XRef::new(stream);
+            xref.set_stream_pos(startxref).unwrap();
+            xref.parse().unwrap();
+            xref
+        };
+
+        // The original file: entries of the /XRefStm replace the free ones
+        let mut xref = open(&data[..original_len], table);
+        assert_eq!(xref.revisions().len(), 1);
+        assert!(matches!(
+            xref.get_entry(3),
+            Some(XRefEntry::Uncompressed { offset, .. }) if *offset == obj_stm as u64
+        ));
+        assert_eq!(
+            *xref.fetch(4, 0).unwrap(),
+            PDFObject::String(b"compressed".to_vec())
+        );
+        assert!(xref.get_entry(5).unwrap().is_free());
+
+        // After the update, only the new generation resolves, even once the
+        // object is cached
+        let mut xref = open(&data, update_xref);
+        assert_eq!(xref.revisions().len(), 2);
+        assert_eq!(
+            *xref.fetch(4, 1).unwrap(),
+            PDFObject::String(b"updated".to_vec())
+        );
+        assert!(xref.fetch(4, 0).is_err());
+        assert!(matches!(
+            xref.revisions()[0].entries.get(&4),
+            Some(XRefEntry::Compressed {
+                obj_stream_num: 3,
+                index: 0
+            })
+        ));
+        assert!(xref.get_entry(7).is_some());
+        assert!(xref.catalog().is_ok());
+    }
+
     #[test]
     #[ignore] // TODO: Fix test - stream data needs to be properly positioned in complete PDF
     fn test_parse_xref_stream_with_compressed_entries() {
@@ -1462,4 +2586,11 @@ mod tests {
             panic!("Expected compressed entry, got {:?}", entry2);
         }
     }
+
+    #[test]
+    fn test_merge_ranges() {
+        let ranges = vec![(50, 60), (0, 10), (10, 20), (55, 70), (30, 40)];
+        assert_eq!(merge_ranges(ranges), vec![(0, 20), (30, 40), (50, 70)]);
+        assert!(merge_ranges(Vec::new()).is_empty());
+    }
 }
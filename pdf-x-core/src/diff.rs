@@ -0,0 +1,515 @@
+//! Comparison of two documents, page by page.
+//!
+//! [`compare`] pairs the pages of two documents by index and reports, for
+//! each page that differs:
+//!
+//! - the words deleted from the old page and inserted into the new one, with
+//!   their boxes in user space
+//! - optionally, the number of pixels that differ when both pages are
+//!   rendered, and the box around them (needs the `rendering` feature)
+//!
+//! ```no_run
+//! use pdf_x_core::PDFDocument;
+//! use pdf_x_core::diff::{DiffOptions, compare};
+//!
+//! let mut old = PDFDocument::open_file("report-v1.pdf", None, None)?;
+//! let mut new = PDFDocument::open_file("report-v2.pdf", None, None)?;
+//! let diff = compare(&mut old, &mut new, &DiffOptions::default())?;
+//! for page in &diff.pages {
+//!     for change in &page.text {
+//!         println!("page {}: {:?} {:?}", page.page_index + 1, change.kind, change.text);
+//!     }
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use crate::core::error::PDFResult;
+use crate::core::{PDFDocument, TextWord};
+use crate::rendering::RenderOptions;
+
+/// Largest word-by-word comparison table; beyond it the differing middle
+/// of a page is reported as one deletion and one insertion.
+const MAX_LCS_CELLS: usize = 4_000_000;
+
+/// What to compare.
+#[derive(Debug, Clone)]
+pub struct DiffOptions {
+    /// Compare the words of each page (default: true)
+    pub text: bool,
+    /// Render both pages and compare their pixels (default: None)
+    pub raster: Option<RasterDiffOptions>,
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        DiffOptions {
+            text: true,
+            raster: None,
+        }
+    }
+}
+
+/// Settings for comparing rendered pages.
+#[derive(Debug, Clone, Copy)]
+pub struct RasterDiffOptions {
+    /// Pixels per PDF unit (default: 1.0)
+    pub scale: f32,
+    /// Largest difference of a color or alpha channel between two pixels
+    /// still counted as equal, for anti-aliasing noise (default: 16)
+    pub tolerance: u8,
+}
+
+impl Default for RasterDiffOptions {
+    fn default() -> Self {
+        RasterDiffOptions {
+            scale: 1.0,
+            tolerance: 16,
+        }
+    }
+}
+
+/// The differences between two documents.
+#[derive(Debug, Clone, Default)]
+pub struct DocumentDiff {
+    /// Number of pages of the old document
+    pub old_page_count: usize,
+    /// Number of pages of the new document
+    pub new_page_count: usize,
+    /// The pages that differ, in page order
+    pub pages: Vec<PageDiff>,
+}
+
+impl DocumentDiff {
+    /// Returns true if no differences were found.
+    pub fn is_identical(&self) -> bool {
+        self.pages.is_empty()
+    }
+}
+
+/// Whether a page is in both documents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PageStatus {
+    /// The page is in both documents and differs
+    Changed,
+    /// The page is only in the new document
+    Added,
+    /// The page is only in the old document
+    Removed,
+}
+
+/// The differences of one page.
+#[derive(Debug, Clone)]
+pub struct PageDiff {
+    /// The 0-based page index
+    pub page_index: usize,
+    /// Whether the page is in both documents
+    pub status: PageStatus,
+    /// Deleted and inserted text, in reading order; empty for added and
+    /// removed pages
+    pub text: Vec<TextChange>,
+    /// Pixel differences, if a raster comparison was requested and the page
+    /// is in both documents
+    pub raster: Option<RasterDiff>,
+}
+
+/// Whether text was deleted or inserted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextChangeKind {
+    /// The text is only on the old page
+    Deleted,
+    /// The text is only on the new page
+    Inserted,
+}
+
+/// A run of consecutive words deleted or inserted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextChange {
+    /// Deleted or inserted
+    pub kind: TextChangeKind,
+    /// The words joined by single spaces
+    pub text: String,
+    /// Bounding box [x0, y0, x1, y1] of the words in user space, on the old
+    /// page for deletions and on the new page for insertions
+    pub bbox: [f64; 4],
+}
+
+/// The pixel differences of a rendered page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RasterDiff {
+    /// Width of the compared area in pixels, the larger of both renderings
+    pub width: u32,
+    /// Height of the compared area in pixels, the larger of both renderings
+    pub height: u32,
+    /// Number of pixels that differ by more than the tolerance; pixels
+    /// outside one of the renderings always differ
+    pub differing_pixels: u64,
+    /// Box [x0, y0, x1, y1) around the differing pixels in image
+    /// coordinates, None if no pixel differs
+    pub bbox: Option<[u32; 4]>,
+}
+
+/// Compares two documents page by page.
+///
+/// Pages are paired by index, so a page inserted in the middle shows up as
+/// changes on all following pages.
+///
+/// # Arguments
+/// * `old` - The reference document
+/// * `new` - The document to check against it
+/// * `options` - Whether to compare text and rendered pixels
+///
+/// # Returns
+/// The pages that differ. Raster comparison fails without the `rendering`
+/// feature.
+pub fn compare(
+    old: &mut PDFDocument,
+    new: &mut PDFDocument,
+    options: &DiffOptions,
+) -> PDFResult<DocumentDiff> {
+    let old_page_count = old.page_count()? as usize;
+    let new_page_count = new.page_count()? as usize;
+
+    let mut pages = Vec::new();
+    for page_index in 0..old_page_count.max(new_page_count) {
+        let status = if page_index >= new_page_count {
+            PageStatus::Removed
+        } else if page_index >= old_page_count {
+            PageStatus::Added
+        } else {
+            let diff = compare_pages(old, page_index, new, page_index, options)?;
+            if !diff.text.is_empty() || diff.raster.is_some_and(|r| r.differing_pixels > 0) {
+                pages.push(diff);
+            }
+            continue;
+        };
+        pages.push(PageDiff {
+            page_index,
+            status,
+            text: Vec::new(),
+            raster: None,
+        });
+    }
+
+    Ok(DocumentDiff {
+        old_page_count,
+        new_page_count,
+        pages,
+    })
+}
+
+/// Compares one page of each document.
+///
+/// # Arguments
+/// * `old` - The reference document
+/// * `old_index` - The 0-based page index in `old`
+/// * `new` - The document to check against it
+/// * `new_index` - The 0-based page index in `new`
+/// * `options` - Whether to compare text and rendered pixels
+///
+/// # Returns
+/// The differences, with status [`PageStatus::Changed`] and the index of the
+/// new page, even if there are none
+pub fn compare_pages(
+    old: &mut PDFDocument,
+    old_index: usize,
+    new: &mut PDFDocument,
+    new_index: usize,
+    options: &DiffOptions,
+) -> PDFResult<PageDiff> {
+    let text = if options.text {
+        let old_page = old.get_page(old_index)?;
+        let old_words = old_page.extract_words(old.xref_mut())?;
+        let new_page = new.get_page(new_index)?;
+        let new_words = new_page.extract_words(new.xref_mut())?;
+        diff_words(&old_words, &new_words)
+    } else {
+        Vec::new()
+    };
+
+    let raster = match &options.raster {
+        Some(raster) => {
+            let render_options = RenderOptions {
+                scale: raster.scale,
+                ..Default::default()
+            };
+            let old_image = render_page(old, old_index, render_options.clone())?;
+            let new_image = render_page(new, new_index, render_options)?;
+            Some(diff_pixels(&old_image, &new_image, raster.tolerance))
+        }
+        None => None,
+    };
+
+    Ok(PageDiff {
+        page_index: new_index,
+        status: PageStatus::Changed,
+        text,
+        raster,
+    })
+}
+
+/// Finds the words deleted and inserted between two pages, from their
+/// longest common subsequence.
+fn diff_words(old: &[TextWord], new: &[TextWord]) -> Vec<TextChange> {
+    // Most pages differ in a few places; leave the shared ends out of the
+    // table
+    let prefix = old
+        .iter()
+        .zip(new)
+        .take_while(|(a, b)| a.text == b.text)
+        .count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a.text == b.text)
+        .count();
+    let old_middle = &old[prefix..old.len() - suffix];
+    let new_middle = &new[prefix..new.len() - suffix];
+
+    let (n, m) = (old_middle.len(), new_middle.len());
+    let mut ops = Vec::with_capacity(n + m);
+    if n * m > MAX_LCS_CELLS {
+        ops.extend(
+            old_middle
+                .iter()
+                .map(|w| Some((TextChangeKind::Deleted, w))),
+        );
+        ops.extend(
+            new_middle
+                .iter()
+                .map(|w| Some((TextChangeKind::Inserted, w))),
+        );
+    } else {
+        // lengths[i][j]: common subsequence length of old[i..] and new[j..]
+        let mut lengths = vec![0u32; (n + 1) * (m + 1)];
+        for i in (0..n).rev() {
+            for j in (0..m).rev() {
+                lengths[i * (m + 1) + j] = if old_middle[i].text == new_middle[j].text {
+                    lengths[(i + 1) * (m + 1) + j + 1] + 1
+                } else {
+                    lengths[(i + 1) * (m + 1) + j].max(lengths[i * (m + 1) + j + 1])
+                };
+            }
+        }
+
+        let (mut i, mut j) = (0, 0);
+        while i < n || j < m {
+            if i < n && j < m && old_middle[i].text == new_middle[j].text {
+                // A common word ends the runs on both sides
+                ops.push(None);
+                i += 1;
+                j += 1;
+            } else if j < m
+                && (i == n || lengths[i * (m + 1) + j + 1] > lengths[(i + 1) * (m + 1) + j])
+            {
+                ops.push(Some((TextChangeKind::Inserted, &new_middle[j])));
+                j += 1;
+            } else {
+                ops.push(Some((TextChangeKind::Deleted, &old_middle[i])));
+                i += 1;
+            }
+        }
+    }
+
+    // Join runs of the same kind
+    let mut changes: Vec<TextChange> = Vec::new();
+    let mut joinable = false;
+    for op in ops {
+        let Some((kind, word)) = op else {
+            joinable = false;
+            continue;
+        };
+        match changes.last_mut() {
+            Some(change) if joinable && change.kind == kind => {
+                change.text.push(' ');
+                change.text.push_str(&word.text);
+                change.bbox = [
+                    change.bbox[0].min(word.bbox[0]),
+                    change.bbox[1].min(word.bbox[1]),
+                    change.bbox[2].max(word.bbox[2]),
+                    change.bbox[3].max(word.bbox[3]),
+                ];
+            }
+            _ => changes.push(TextChange {
+                kind,
+                text: word.text.clone(),
+                bbox: word.bbox,
+            }),
+        }
+        joinable = true;
+    }
+    changes
+}
+
+/// Counts the pixels that differ between two RGBA images.
+///
+/// # Arguments
+/// * `old`, `new` - Width, height and RGBA pixels of each image
+/// * `tolerance` - Largest channel difference counted as equal
+fn diff_pixels(old: &(u32, u32, Vec<u8>), new: &(u32, u32, Vec<u8>), tolerance: u8) -> RasterDiff {
+    let width = old.0.max(new.0);
+    let height = old.1.max(new.1);
+    fn pixel((w, h, data): &(u32, u32, Vec<u8>), x: u32, y: u32) -> Option<&[u8]> {
+        if x >= *w || y >= *h {
+            return None;
+        }
+        let offset = (y as usize * *w as usize + x as usize) * 4;
+        data.get(offset..offset + 4)
+    }
+
+    let mut differing_pixels = 0;
+    let mut bbox: Option<[u32; 4]> = None;
+    for y in 0..height {
+        for x in 0..width {
+            let same = match (pixel(old, x, y), pixel(new, x, y)) {
+                (Some(a), Some(b)) => a.iter().zip(b).all(|(a, b)| a.abs_diff(*b) <= tolerance),
+                _ => false,
+            };
+            if same {
+                continue;
+            }
+            differing_pixels += 1;
+            bbox = Some(match bbox {
+                Some([x0, y0, x1, y1]) => [x0.min(x), y0.min(y), x1.max(x + 1), y1.max(y + 1)],
+                None => [x, y, x + 1, y + 1],
+            });
+        }
+    }
+
+    RasterDiff {
+        width,
+        height,
+        differing_pixels,
+        bbox,
+    }
+}
+
+#[cfg(feature = "rendering")]
+fn render_page(
+    doc: &mut PDFDocument,
+    page_index: usize,
+    options: RenderOptions,
+) -> PDFResult<(u32, u32, Vec<u8>)> {
+    doc.render_page_to_image(page_index, options)
+}
+
+#[cfg(not(feature = "rendering"))]
+fn render_page(
+    _doc: &mut PDFDocument,
+    _page_index: usize,
+    _options: RenderOptions,
+) -> PDFResult<(u32, u32, Vec<u8>)> {
+    Err(crate::core::error::PDFError::unsupported(
+        "raster diffs (built without the `rendering` feature)",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_pdf(pages: &[&str]) -> Vec<u8> {
+        let kids: Vec<String> = (0..pages.len())
+            .map(|i| format!("{} 0 R", 3 + 2 * i))
+            .collect();
+        let mut objects = vec![
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            format!(
+                "<< /Type /Pages /Kids [{}] /Count {} >>",
+                kids.join(" "),
+                pages.len()
+            ),
+        ];
+        for (i, content) in pages.iter().enumerate() {
+            objects.push(format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] /Contents {} 0 R \
+                 /Resources << /Font << /F1 << /Type /Font /Subtype /Type1 /BaseFont /Helvetica >> >> >> >>",
+                4 + 2 * i
+            ));
+            objects.push(format!(
+                "<< /Length {} >>\nstream\n{}\nendstream",
+                content.len(),
+                content
+            ));
+        }
+
+        crate::test_support::build_pdf(&objects)
+    }
+
+    #[test]
+    fn test_compare_text() {
+        let old = build_pdf(&[
+            "BT /F1 12 Tf 72 700 Td (Total revenue was 100 dollars) Tj ET",
+            "BT /F1 12 Tf 72 700 Td (Unchanged page) Tj ET",
+        ]);
+        let new = build_pdf(&[
+            "BT /F1 12 Tf 72 700 Td (Total revenue was 120 dollars) Tj ET \
+             BT /F1 12 Tf 72 680 Td (Up by a fifth) Tj ET",
+            "BT /F1 12 Tf 72 700 Td (Unchanged page) Tj ET",
+            "BT /F1 12 Tf 72 700 Td (Appendix) Tj ET",
+        ]);
+        let mut old = PDFDocument::open(old).unwrap();
+        let mut new = PDFDocument::open(new).unwrap();
+
+        let diff = compare(&mut old, &mut new, &DiffOptions::default()).unwrap();
+        assert!(!diff.is_identical());
+        assert_eq!((diff.old_page_count, diff.new_page_count), (2, 3));
+        assert_eq!(diff.pages.len(), 2);
+
+        let page = &diff.pages[0];
+        assert_eq!((page.page_index, page.status), (0, PageStatus::Changed));
+        let changes: Vec<_> = page
+            .text
+            .iter()
+            .map(|c| (c.kind, c.text.as_str()))
+            .collect();
+        assert_eq!(
+            changes,
+            vec![
+                (TextChangeKind::Deleted, "100"),
+                (TextChangeKind::Inserted, "120"),
+                (TextChangeKind::Inserted, "Up by a fifth"),
+            ]
+        );
+        // The changed number is on the first line, the new sentence below it
+        assert!(page.text[0].bbox[0] > 72.0 && page.text[0].bbox[1] < 700.0);
+        assert!(page.text[2].bbox[3] < 700.0);
+
+        assert_eq!(
+            (diff.pages[1].page_index, diff.pages[1].status),
+            (2, PageStatus::Added)
+        );
+
+        let mut copy = PDFDocument::open(build_pdf(&[
+            "BT /F1 12 Tf 72 700 Td (Total revenue was 100 dollars) Tj ET",
+            "BT /F1 12 Tf 72 700 Td (Unchanged page) Tj ET",
+        ]))
+        .unwrap();
+        let same = compare(&mut old, &mut copy, &DiffOptions::default()).unwrap();
+        assert!(same.is_identical());
+    }
+
+    #[test]
+    fn test_diff_pixels() {
+        let white = |w: u32, h: u32| (w, h, vec![255u8; (w * h * 4) as usize]);
+        let old = white(4, 3);
+        let mut new = white(4, 3);
+        // Noise below the tolerance, and one changed pixel at (2, 1)
+        new.2[0] = 250;
+        new.2[(4 + 2) * 4] = 0;
+
+        let diff = diff_pixels(&old, &new, 16);
+        assert_eq!(diff.differing_pixels, 1);
+        assert_eq!(diff.bbox, Some([2, 1, 3, 2]));
+        assert_eq!(diff_pixels(&old, &old, 0).bbox, None);
+
+        // A wider page differs in the extra column
+        let diff = diff_pixels(&old, &white(5, 3), 0);
+        assert_eq!((diff.width, diff.differing_pixels), (5, 3));
+        assert_eq!(diff.bbox, Some([4, 0, 5, 3]));
+
+        // Pixels missing from a short buffer count as different
+        let short = (4, 3, vec![255u8; 4 * 4]);
+        assert_eq!(diff_pixels(&old, &short, 0).differing_pixels, 8);
+    }
+}
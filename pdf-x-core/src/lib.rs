@@ -44,16 +44,28 @@
 //! 3. **Document Layer**: Page tree navigation and metadata management
 //! 4. **Content Layer**: Content stream interpretation and text extraction
 //!
+//! Options such as a password or lenient mode can be given up front with the
+//! builder in [`prelude`], which also re-exports the most common types:
+//!
+//! ```no_run
+//! use pdf_x_core::prelude::*;
+//!
+//! let mut doc = Pdf::open("document.pdf")
+//!     .password("secret")
+//!     .lenient(true)
+//!     .load()?;
+//! # Ok::<(), PDFError>(())
+//! ```
+//!
 //! ## Progressive Loading
 //!
 //! PDF-X supports progressive loading that mirrors PDF.js's architecture:
 //!
-//! ```rust
-//! use pdf_x_core::core::{PDFDocument, FileChunkedStream};
+//! ```no_run
+//! use pdf_x_core::core::PDFDocument;
 //!
 //! // Open with chunked loading for large files
-//! let mut stream = FileChunkedStream::open("large.pdf")?;
-//! let mut doc = PDFDocument::open_stream(Box::new(stream))?;
+//! let mut doc = PDFDocument::open_file("large.pdf", None, None)?;
 //!
 //! // Pages are loaded on-demand, not all at once
 //! let page = doc.get_page(0)?; // This will trigger progressive loading
@@ -80,6 +92,26 @@
 //! # Ok::<(), Box<dyn std::error::Error>>(())
 //! ```
 //!
+//! ## Conversion
+//!
+//! [`convert::html`] turns pages into HTML, either with positioned text and
+//! images or as flowed paragraphs:
+//!
+//! ```no_run
+//! use pdf_x_core::PDFDocument;
+//! use pdf_x_core::convert::html::{HtmlOptions, document_to_html};
+//!
+//! let mut doc = PDFDocument::open_file("document.pdf", None, None)?;
+//! let html = document_to_html(&mut doc, &HtmlOptions::default())?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! ## Comparison
+//!
+//! [`diff::compare`] reports the text inserted and deleted on each page of
+//! two documents, and optionally the pixels that differ when both are
+//! rendered.
+//!
 //! ## CLI Tool
 //!
 //! PDF-X includes a command-line tool for PDF inspection:
@@ -99,21 +131,41 @@
 //!
 //! For more detailed examples and advanced usage, see the examples directory.
 
+pub mod convert;
 pub mod core;
+pub mod diff;
+pub mod log_target;
+pub mod ops;
+pub mod prelude;
 pub mod rendering;
 
+#[cfg(any(test, feature = "test-support"))]
+#[doc(hidden)]
+pub mod test_support;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
 // Re-export main types for convenience
 pub use core::{
-    Annotation, AnnotationBorder, AnnotationColor, AnnotationData, AnnotationFlags, AnnotationRect,
-    AnnotationType, BaseStream, DestinationType, FileAttachmentAnnotation, FileChunkedStream,
+    Action, ActionKind, Annotation, AnnotationBorder, AnnotationColor, AnnotationData,
+    AnnotationFlags, AnnotationRect, AnnotationType, Attachment, BaseStream, CacheBudget,
+    CacheStats, CancellationToken, Catalog, ChunkLoader, CompositeStream, DestinationType,
+    Diagnostic, DiagnosticKind, DocumentMetadata, DocumentSummary, EInvoice, ErrorCode,
+    ExternalLink, ExternalLinkKind, ExtractAllTextOptions, ExtractionMode,
+    FileAttachmentAnnotation, FileChunkedStream, Font, FontProgramFormat, FontSizeClass,
     FormFieldType, ImageDecoder, ImageFormat, Lexer, LinearizedInfo, LinkAction, LinkAnnotation,
-    OutlineDestination, OutlineItem, PDFDocument, PDFError, PDFObject, Page, Parser,
-    PopupAnnotation, Stream, TextAnnotation, TextItem, Token, WidgetAnnotation, XRef, XRefEntry,
+    LinkTarget, LoaderChunkedStream, MarkupAnnotation, OpenOptions, OutlineDestination,
+    OutlineItem, PDFDate, PDFDocument, PDFError, PDFObject, Page, PageLink, PageMode, PageSummary,
+    PageText, ParseOptions, Parser, Pdf, PopupAnnotation, Progress, ReflowParagraph,
+    ResolvedOutlineItem, ResourceLimits, Revision, Signature, Stream, StyleRun, TextAnnotation,
+    TextGlyph, TextItem, TextLayoutOptions, TextLine, TextWord, Token, Trailer, WidgetAnnotation,
+    XRef, XRefEntry,
 };
 
 // Re-export rendering types
 pub use rendering::{
-    Device, GraphicsState, Paint, Path, PathBuilder, PathDrawMode, RenderingContext,
+    Device, GraphicsState, Paint, Path, PathBuilder, PathDrawMode, PathFill, PathStroke,
+    RenderOptions, RenderingContext, SvgDevice, VectorPath,
 };
 
 #[cfg(feature = "rendering")]
@@ -122,5 +174,10 @@ pub use rendering::SkiaDevice;
 // Re-export decode module
 pub use core::decode;
 
+// Re-export the writer, which serializes documents and incremental updates
+pub use core::pdf_writer as writer;
+
 #[cfg(feature = "async")]
-pub use core::{AsyncHttpChunkedStream, HttpChunkedStream, ProgressCallback};
+pub use core::{
+    AsyncChunkLoader, AsyncHttpChunkedStream, AsyncPDFDocument, HttpChunkedStream, ProgressCallback,
+};
@@ -0,0 +1,55 @@
+//! Targets for the library's diagnostics.
+//!
+//! PDF-X reports recoverable problems (a damaged xref table, a font that
+//! can't be parsed, an image that fails to decode) through the
+//! [`tracing`](https://docs.rs/tracing) facade instead of printing to stderr.
+//! Nothing is shown unless the application installs a subscriber. Every
+//! event carries one of the targets below, so output can be filtered per
+//! subsystem:
+//!
+//! | Level   | Used for                                                   |
+//! |---------|------------------------------------------------------------|
+//! | `error` | Problems that lose content the caller asked for            |
+//! | `warn`  | Damaged or unsupported input that was worked around        |
+//! | `debug` | Notable decisions, such as falling back to a default       |
+//! | `trace` | Per-operator and per-glyph detail (`debug-logging` only)   |
+//!
+//! Rendering a page and extracting its text also open `render_page` and
+//! `extract_text` spans carrying the page index, so events can be attributed
+//! to a page.
+//!
+//! # Example
+//! ```ignore
+//! // With tracing-subscriber's `env-filter` feature: show warnings, except
+//! // for fonts, and everything the xref code has to say.
+//! tracing_subscriber::fmt()
+//!     .with_env_filter("warn,pdf_x::font=off,pdf_x::xref=trace")
+//!     .init();
+//! ```
+
+/// Lexing and parsing of objects and content streams.
+pub const PARSER: &str = "pdf_x::parser";
+
+/// Cross-reference tables and streams, including reconstruction.
+pub const XREF: &str = "pdf_x::xref";
+
+/// Document structure: catalog, metadata, outlines, attachments, saving.
+pub const DOCUMENT: &str = "pdf_x::document";
+
+/// Stream filters.
+pub const DECODE: &str = "pdf_x::decode";
+
+/// Encryption and decryption.
+pub const CRYPTO: &str = "pdf_x::crypto";
+
+/// Font loading, encodings and CMaps.
+pub const FONT: &str = "pdf_x::font";
+
+/// Image decoding and drawing.
+pub const IMAGE: &str = "pdf_x::image";
+
+/// Text extraction.
+pub const TEXT: &str = "pdf_x::text";
+
+/// Page rendering and the graphics state machine.
+pub const RENDER: &str = "pdf_x::render";
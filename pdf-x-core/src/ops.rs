@@ -0,0 +1,782 @@
+//! Whole-document operations that build new documents from existing pages.
+//!
+//! [`merge`] concatenates the pages of several documents and [`split`] cuts
+//! one document into page ranges. Each page is deep-copied together with
+//! everything it references (content streams, resources, fonts, images,
+//! annotations) into a fresh object space, so objects from different source
+//! files never collide. Objects referenced several times are copied once.
+//!
+//! Only pages travel: the outline, the AcroForm, page labels and the rest of
+//! the catalog are not carried over. References to pages that are not part of
+//! the result, for example from link annotations, become null.
+//!
+//! [`merge_report`] and [`split_report`] are their dry runs: they copy the
+//! same pages but only report what the new documents would hold.
+//!
+//! `PDFDocument::extract_pages()` goes further and analyzes each page's
+//! content streams, so fonts, images and other resources that the page tree
+//! shares between pages are only copied for the pages that use them.
+
+use crate::core::content_stream::{ContentStreamEvaluator, OpCode};
+use crate::core::delta::ChangeReport;
+use crate::core::document::PDFDocument;
+use crate::core::encryption::Permission;
+use crate::core::error::{PDFError, PDFResult};
+use crate::core::parser::{PDFObject, Ref};
+use crate::core::pdf_writer::PDFWriter;
+use smallvec::SmallVec;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ops::Range;
+
+/// Resource categories that are subset to the names a page's content uses.
+///
+/// /ColorSpace is kept whole since inline images may name color spaces in
+/// ways the operator scan doesn't see.
+const SUBSET_CATEGORIES: [&str; 6] = [
+    "Font",
+    "XObject",
+    "ExtGState",
+    "Pattern",
+    "Shading",
+    "Properties",
+];
+
+/// Options for `PDFDocument::extract_pages()`.
+#[derive(Debug, Clone, Copy)]
+pub struct ExtractPagesOptions {
+    /// Copy the pages' annotations (default: true)
+    pub keep_annotations: bool,
+}
+
+impl Default for ExtractPagesOptions {
+    fn default() -> Self {
+        Self {
+            keep_annotations: true,
+        }
+    }
+}
+
+/// Merges the pages of several documents, in order, into a new document.
+///
+/// # Arguments
+/// * `docs` - The documents to merge
+///
+/// # Returns
+/// A new document holding the pages of every input document
+///
+/// # Example
+/// ```no_run
+/// use pdf_x_core::core::PDFDocument;
+/// use pdf_x_core::ops;
+///
+/// let mut docs = vec![
+///     PDFDocument::open_file("a.pdf", None, None).unwrap(),
+///     PDFDocument::open_file("b.pdf", None, None).unwrap(),
+/// ];
+/// let mut merged = ops::merge(&mut docs).unwrap();
+/// std::fs::write("merged.pdf", merged.save().unwrap()).unwrap();
+/// ```
+pub fn merge(docs: &mut [PDFDocument]) -> PDFResult<PDFDocument> {
+    merge_builder(docs)?.finish()
+}
+
+/// Reports what [`merge`] would produce without building the document.
+///
+/// # Arguments
+/// * `docs` - The documents to merge
+///
+/// # Returns
+/// A report listing the objects and pages of the merged document and the
+/// size of the file it would be saved as
+///
+/// # Example
+/// ```no_run
+/// use pdf_x_core::core::PDFDocument;
+/// use pdf_x_core::ops;
+///
+/// let mut docs = vec![
+///     PDFDocument::open_file("a.pdf", None, None).unwrap(),
+///     PDFDocument::open_file("b.pdf", None, None).unwrap(),
+/// ];
+/// let report = ops::merge_report(&mut docs).unwrap();
+/// println!("{} pages, {:?} bytes", report.pages_affected.len(), report.bytes_written);
+/// ```
+pub fn merge_report(docs: &mut [PDFDocument]) -> PDFResult<ChangeReport> {
+    merge_builder(docs)?.report()
+}
+
+fn merge_builder(docs: &mut [PDFDocument]) -> PDFResult<DocumentBuilder> {
+    if docs.is_empty() {
+        return Err(PDFError::Generic("No documents to merge".to_string()));
+    }
+
+    let mut builder = DocumentBuilder::new();
+    for doc in docs.iter_mut() {
+        let count = doc.page_count()? as usize;
+        builder.add_pages(doc, 0..count)?;
+    }
+    Ok(builder)
+}
+
+/// Splits a document into one new document per page range.
+///
+/// # Arguments
+/// * `doc` - The document to split
+/// * `ranges` - 0-based, end-exclusive page ranges; ranges may overlap
+///
+/// # Returns
+/// One document per range, in the order of `ranges`
+///
+/// # Example
+/// ```no_run
+/// use pdf_x_core::core::PDFDocument;
+/// use pdf_x_core::ops;
+///
+/// let mut doc = PDFDocument::open_file("book.pdf", None, None).unwrap();
+/// // Chapters start on pages 1 and 11
+/// let parts = ops::split(&mut doc, &[0..10, 10..25]).unwrap();
+/// ```
+pub fn split(doc: &mut PDFDocument, ranges: &[Range<usize>]) -> PDFResult<Vec<PDFDocument>> {
+    split_builders(doc, ranges, DocumentBuilder::finish)
+}
+
+/// Reports what [`split`] would produce without building the documents.
+///
+/// # Arguments
+/// * `doc` - The document to split
+/// * `ranges` - 0-based, end-exclusive page ranges; ranges may overlap
+///
+/// # Returns
+/// One report per range, in the order of `ranges`
+///
+/// # Example
+/// ```no_run
+/// use pdf_x_core::core::PDFDocument;
+/// use pdf_x_core::ops;
+///
+/// let mut doc = PDFDocument::open_file("book.pdf", None, None).unwrap();
+/// for report in ops::split_report(&mut doc, &[0..10, 10..25]).unwrap() {
+///     println!("{} objects", report.object_count());
+/// }
+/// ```
+pub fn split_report(
+    doc: &mut PDFDocument,
+    ranges: &[Range<usize>],
+) -> PDFResult<Vec<ChangeReport>> {
+    split_builders(doc, ranges, DocumentBuilder::report)
+}
+
+/// Copies each range into its own builder and hands it to `build`.
+fn split_builders<T>(
+    doc: &mut PDFDocument,
+    ranges: &[Range<usize>],
+    build: impl Fn(DocumentBuilder) -> PDFResult<T>,
+) -> PDFResult<Vec<T>> {
+    let count = doc.page_count()? as usize;
+    if let Some(range) = ranges
+        .iter()
+        .find(|range| range.is_empty() || range.end > count)
+    {
+        return Err(PDFError::Generic(format!(
+            "Invalid page range {:?} (document has {} pages)",
+            range, count
+        )));
+    }
+
+    ranges
+        .iter()
+        .map(|range| {
+            let mut builder = DocumentBuilder::new();
+            builder.add_pages(doc, range.clone())?;
+            build(builder)
+        })
+        .collect()
+}
+
+/// Writes the given pages to a standalone file with only the resources
+/// they use.
+pub(crate) fn extract_pages(
+    doc: &mut PDFDocument,
+    pages: Range<usize>,
+    options: &ExtractPagesOptions,
+) -> PDFResult<Vec<u8>> {
+    let count = doc.page_count()? as usize;
+    if pages.is_empty() || pages.end > count {
+        return Err(PDFError::Generic(format!(
+            "Invalid page range {:?} (document has {} pages)",
+            pages, count
+        )));
+    }
+
+    let mut builder = DocumentBuilder::new();
+    builder.subset_resources = true;
+    builder.keep_annotations = options.keep_annotations;
+    builder.add_pages(doc, pages)?;
+    builder.write()
+}
+
+/// Collects copied objects for a new document.
+struct DocumentBuilder {
+    /// Copied objects by their new number
+    objects: BTreeMap<u32, (u32, PDFObject)>,
+
+    /// The page tree root of the new document
+    pages_ref: Ref,
+
+    /// The copied pages, in order
+    kids: Vec<Ref>,
+
+    /// Next free object number
+    next_num: u32,
+
+    /// Highest PDF version among the source documents
+    version: String,
+
+    /// Drop resources the pages' content doesn't use
+    subset_resources: bool,
+
+    /// Copy the pages' annotations
+    keep_annotations: bool,
+
+    /// Number of references being followed by the current copy
+    depth: usize,
+}
+
+impl DocumentBuilder {
+    fn new() -> Self {
+        Self {
+            objects: BTreeMap::new(),
+            // 1 is the catalog
+            pages_ref: Ref::new(2, 0),
+            kids: Vec::new(),
+            next_num: 3,
+            version: "1.4".to_string(),
+            subset_resources: false,
+            keep_annotations: true,
+            depth: 0,
+        }
+    }
+
+    fn allocate(&mut self) -> Ref {
+        let obj_ref = Ref::new(self.next_num, 0);
+        self.next_num += 1;
+        obj_ref
+    }
+
+    /// Copies pages of a document, with everything they reference.
+    fn add_pages(&mut self, doc: &mut PDFDocument, pages: Range<usize>) -> PDFResult<()> {
+        doc.check_permission(Permission::Assemble)?;
+        if let Ok(version) = doc.pdf_version()
+            && version > self.version
+        {
+            self.version = version;
+        }
+
+        // Number the pages first so references between them (annotation
+        // /P entries, link destinations) resolve to the copies
+        let mut map: HashMap<Ref, Ref> = HashMap::new();
+        let mut copies = Vec::new();
+        for index in pages {
+            let page = doc.get_page(index)?;
+            let mut dict = doc.flatten_page_dict(&page)?;
+            // The old /Parent would pull in the source page tree
+            dict.remove("Parent");
+            if !self.keep_annotations {
+                dict.remove("Annots");
+            }
+            if self.subset_resources {
+                subset_page_resources(doc, &mut dict)?;
+            }
+            let new_ref = self.allocate();
+            if let Some((num, generation)) = page.reference() {
+                map.insert(Ref::new(num, generation), new_ref);
+            }
+            copies.push((new_ref, dict));
+        }
+
+        for (new_ref, dict) in copies {
+            let PDFObject::Dictionary(mut copied) =
+                self.copy_value(doc, &mut map, PDFObject::Dictionary(dict))?
+            else {
+                unreachable!("copying a dictionary yields a dictionary");
+            };
+            copied.insert("Parent".to_string(), PDFObject::Ref(self.pages_ref));
+            self.objects
+                .insert(new_ref.num, (0, PDFObject::Dictionary(copied)));
+            self.kids.push(new_ref);
+        }
+        Ok(())
+    }
+
+    /// Copies a value, replacing references with references to copies.
+    fn copy_value(
+        &mut self,
+        doc: &mut PDFDocument,
+        map: &mut HashMap<Ref, Ref>,
+        value: PDFObject,
+    ) -> PDFResult<PDFObject> {
+        Ok(match value {
+            PDFObject::Ref(obj_ref) => self.copy_ref(doc, map, obj_ref)?,
+            PDFObject::Array(items) => {
+                let mut copied = SmallVec::with_capacity(items.len());
+                for item in items {
+                    copied.push(Box::new(self.copy_value(doc, map, *item)?));
+                }
+                PDFObject::Array(copied)
+            }
+            PDFObject::Dictionary(dict) => PDFObject::Dictionary(self.copy_dict(doc, map, dict)?),
+            PDFObject::Stream { dict, data } => PDFObject::Stream {
+                dict: self.copy_dict(doc, map, dict)?,
+                data,
+            },
+            other => other,
+        })
+    }
+
+    fn copy_dict(
+        &mut self,
+        doc: &mut PDFDocument,
+        map: &mut HashMap<Ref, Ref>,
+        dict: HashMap<String, PDFObject>,
+    ) -> PDFResult<HashMap<String, PDFObject>> {
+        let mut copied = HashMap::with_capacity(dict.len());
+        for (key, value) in dict {
+            copied.insert(key, self.copy_value(doc, map, value)?);
+        }
+        Ok(copied)
+    }
+
+    /// Copies an indirect object the first time it is referenced.
+    fn copy_ref(
+        &mut self,
+        doc: &mut PDFDocument,
+        map: &mut HashMap<Ref, Ref>,
+        obj_ref: Ref,
+    ) -> PDFResult<PDFObject> {
+        if let Some(new_ref) = map.get(&obj_ref) {
+            return Ok(PDFObject::Ref(*new_ref));
+        }
+
+        // A reference to a missing object is a reference to null
+        let obj = match doc.xref_mut().fetch(obj_ref.num, obj_ref.generation) {
+            Ok(obj) => (*obj).clone(),
+            Err(e @ PDFError::DataMissing { .. }) => return Err(e),
+            Err(_) => return Ok(PDFObject::Null),
+        };
+
+        // Following a reference to another page would pull in its page tree
+        // and, through it, the whole document
+        if let PDFObject::Dictionary(dict) = &obj
+            && matches!(dict.get("Type"), Some(PDFObject::Name(name)) if name == "Page" || name == "Pages")
+        {
+            return Ok(PDFObject::Null);
+        }
+
+        // A long chain of references (e.g. /Next entries) would otherwise
+        // exhaust the stack
+        let max_depth = doc.xref().limits().max_object_depth;
+        if self.depth >= max_depth {
+            return Err(PDFError::limit_exceeded(
+                "reference nesting depth",
+                max_depth,
+            ));
+        }
+
+        let new_ref = self.allocate();
+        map.insert(obj_ref, new_ref);
+        self.depth += 1;
+        let copied = self.copy_value(doc, map, obj);
+        self.depth -= 1;
+        self.objects.insert(new_ref.num, (0, copied?));
+        Ok(PDFObject::Ref(new_ref))
+    }
+
+    /// Adds the page tree and catalog and opens the result.
+    fn finish(self) -> PDFResult<PDFDocument> {
+        PDFDocument::open(self.write()?)
+    }
+
+    /// Describes the document without opening it.
+    ///
+    /// Every object is new, and so is every page.
+    fn report(mut self) -> PDFResult<ChangeReport> {
+        let trailer = self.add_catalog();
+        let bytes = PDFWriter::write_document(&self.version, &self.objects, &trailer)?;
+        Ok(ChangeReport {
+            added: self
+                .objects
+                .iter()
+                .map(|(num, (generation, _))| Ref::new(*num, *generation))
+                .collect(),
+            pages_affected: (0..self.kids.len()).collect(),
+            bytes_written: Some(bytes.len()),
+            ..ChangeReport::default()
+        })
+    }
+
+    /// Adds the page tree and catalog and writes the file.
+    fn write(mut self) -> PDFResult<Vec<u8>> {
+        let trailer = self.add_catalog();
+        PDFWriter::write_document(&self.version, &self.objects, &trailer)
+    }
+
+    /// Adds the page tree and catalog and returns the trailer.
+    fn add_catalog(&mut self) -> HashMap<String, PDFObject> {
+        let mut pages = HashMap::new();
+        pages.insert("Type".to_string(), PDFObject::Name("Pages".to_string()));
+        pages.insert(
+            "Count".to_string(),
+            PDFObject::Number(self.kids.len() as f64),
+        );
+        pages.insert(
+            "Kids".to_string(),
+            PDFObject::Array(
+                self.kids
+                    .iter()
+                    .map(|kid| Box::new(PDFObject::Ref(*kid)))
+                    .collect(),
+            ),
+        );
+        self.objects
+            .insert(self.pages_ref.num, (0, PDFObject::Dictionary(pages)));
+
+        let catalog_ref = Ref::new(1, 0);
+        let mut catalog = HashMap::new();
+        catalog.insert("Type".to_string(), PDFObject::Name("Catalog".to_string()));
+        catalog.insert("Pages".to_string(), PDFObject::Ref(self.pages_ref));
+        self.objects
+            .insert(catalog_ref.num, (0, PDFObject::Dictionary(catalog)));
+
+        let mut trailer = HashMap::new();
+        trailer.insert("Root".to_string(), PDFObject::Ref(catalog_ref));
+        trailer
+    }
+}
+
+/// Drops the entries of a page's resource dictionaries that its content
+/// streams never name.
+///
+/// The resources are left alone if the content can't be analyzed, or if a
+/// form XObject it draws has no resources of its own and so uses the page's.
+fn subset_page_resources(
+    doc: &mut PDFDocument,
+    page: &mut HashMap<String, PDFObject>,
+) -> PDFResult<()> {
+    let Some(resources) = page.get("Resources") else {
+        return Ok(());
+    };
+    let PDFObject::Dictionary(mut resources) = doc.xref_mut().fetch_if_ref(resources)? else {
+        return Ok(());
+    };
+    let used = match page.get("Contents") {
+        Some(contents) => match used_resource_names(doc, contents)? {
+            Some(used) => used,
+            None => return Ok(()),
+        },
+        None => HashMap::new(),
+    };
+
+    let mut subset = HashMap::new();
+    for category in SUBSET_CATEGORIES {
+        let Some(entries) = resources.get(category) else {
+            continue;
+        };
+        let PDFObject::Dictionary(entries) = doc.xref_mut().fetch_if_ref(entries)? else {
+            continue;
+        };
+        let names = used.get(category);
+        let kept: HashMap<String, PDFObject> = entries
+            .into_iter()
+            .filter(|(name, _)| names.is_some_and(|names| names.contains(name)))
+            .collect();
+
+        if category == "XObject" {
+            for xobject in kept.values() {
+                if let PDFObject::Stream { dict, .. } = doc.xref_mut().fetch_if_ref(xobject)?
+                    && matches!(dict.get("Subtype"), Some(PDFObject::Name(s)) if s == "Form")
+                    && !dict.contains_key("Resources")
+                {
+                    return Ok(());
+                }
+            }
+        }
+        subset.insert(category, kept);
+    }
+
+    for (category, kept) in subset {
+        if kept.is_empty() {
+            resources.remove(category);
+        } else {
+            resources.insert(category.to_string(), PDFObject::Dictionary(kept));
+        }
+    }
+    page.insert("Resources".to_string(), PDFObject::Dictionary(resources));
+    Ok(())
+}
+
+/// Collects the resource names a page's content streams use, by category.
+///
+/// Returns None if a content stream can't be decoded or parsed.
+fn used_resource_names(
+    doc: &mut PDFDocument,
+    contents: &PDFObject,
+) -> PDFResult<Option<HashMap<&'static str, HashSet<String>>>> {
+    let sources = match doc.xref_mut().fetch_if_ref(contents)? {
+        PDFObject::Array(items) => items.into_iter().map(|item| *item).collect(),
+        PDFObject::Stream { .. } => vec![contents.clone()],
+        _ => Vec::new(),
+    };
+
+    let mut used: HashMap<&'static str, HashSet<String>> = HashMap::new();
+    for source in sources {
+        let data = match doc.xref_mut().decoded_stream(&source) {
+            Ok(data) => data,
+            Err(e @ PDFError::DataMissing { .. }) => return Err(e),
+            Err(_) => return Ok(None),
+        };
+        let mut evaluator = ContentStreamEvaluator::from_bytes(data.to_vec());
+        evaluator.set_max_depth(doc.xref().limits().max_object_depth);
+        loop {
+            let op = match evaluator.read_operation() {
+                Ok(Some(op)) => op,
+                Ok(None) => break,
+                Err(_) => return Ok(None),
+            };
+            let (category, arg) = match op.op {
+                OpCode::SetFont => ("Font", op.args.first()),
+                OpCode::PaintXObject => ("XObject", op.args.first()),
+                OpCode::SetGState => ("ExtGState", op.args.first()),
+                OpCode::ShadingFill => ("Shading", op.args.first()),
+                OpCode::SetFillColorN | OpCode::SetStrokeColorN => ("Pattern", op.args.last()),
+                OpCode::BeginMarkedContentProps | OpCode::MarkPointProps => {
+                    ("Properties", op.args.get(1))
+                }
+                _ => continue,
+            };
+            if let Some(PDFObject::Name(name)) = arg {
+                used.entry(category).or_default().insert(name.clone());
+            }
+        }
+    }
+    Ok(Some(used))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::build_pdf;
+
+    /// Opens a PDF made of the given objects, numbered from 1.
+    fn open_objects(objects: &[String]) -> PDFDocument {
+        PDFDocument::open(build_pdf(objects)).unwrap()
+    }
+
+    /// Builds a PDF whose pages each draw their label with a shared font.
+    fn labelled_pdf(labels: &[&str]) -> PDFDocument {
+        let mut objects = vec![
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            String::new(),
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>".to_string(),
+        ];
+        let mut kids = Vec::new();
+        for label in labels {
+            let content = format!("BT /F1 12 Tf 72 700 Td ({}) Tj ET", label);
+            objects.push(format!(
+                "<< /Length {} >>\nstream\n{}\nendstream",
+                content.len(),
+                content
+            ));
+            let page_num = objects.len() + 1;
+            kids.push(format!("{} 0 R", page_num));
+            // Each page links to the first one
+            objects.push(format!(
+                "<< /Type /Page /Parent 2 0 R /Contents {} 0 R \
+                 /Resources << /Font << /F1 3 0 R >> >> \
+                 /Annots [<< /Type /Annot /Subtype /Link /Rect [0 0 10 10] /Dest [{} /Fit] >>] >>",
+                page_num - 1,
+                kids[0]
+            ));
+        }
+        objects[1] = format!(
+            "<< /Type /Pages /Kids [{}] /Count {} /MediaBox [0 0 612 792] >>",
+            kids.join(" "),
+            labels.len()
+        );
+
+        open_objects(&objects)
+    }
+
+    fn page_texts(doc: &mut PDFDocument) -> Vec<String> {
+        let count = doc.page_count().unwrap() as usize;
+        (0..count)
+            .map(|i| {
+                doc.extract_text_from_page_as_string(i)
+                    .unwrap()
+                    .trim()
+                    .to_string()
+            })
+            .collect()
+    }
+
+    /// Returns the page a page's first link annotation points at.
+    fn link_target(doc: &mut PDFDocument, index: usize) -> PDFObject {
+        let page = doc.get_page(index).unwrap();
+        let annot = match page.annotations() {
+            Some(PDFObject::Array(items)) => (*items[0]).clone(),
+            other => panic!("unexpected /Annots {:?}", other),
+        };
+        match doc.xref_mut().fetch_if_ref(&annot).unwrap() {
+            PDFObject::Dictionary(dict) => match dict.get("Dest") {
+                Some(PDFObject::Array(dest)) => (*dest[0]).clone(),
+                other => panic!("unexpected /Dest {:?}", other),
+            },
+            other => panic!("annotation is not a dictionary: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut docs = vec![labelled_pdf(&["A1", "A2"]), labelled_pdf(&["B1"])];
+        let mut merged = merge(&mut docs).unwrap();
+        assert_eq!(page_texts(&mut merged), vec!["A1", "A2", "B1"]);
+
+        // Inherited attributes are copied onto the pages
+        let page = merged.get_page(2).unwrap();
+        assert!(page.get("MediaBox").is_some());
+
+        // Links keep pointing at the first page of their own document
+        let first = merged.get_page(0).unwrap().reference().unwrap();
+        let first = PDFObject::Ref(Ref::new(first.0, first.1));
+        assert_eq!(link_target(&mut merged, 1), first);
+        let second_first = merged.get_page(2).unwrap().reference().unwrap();
+        let second_first = PDFObject::Ref(Ref::new(second_first.0, second_first.1));
+        assert_eq!(link_target(&mut merged, 2), second_first);
+
+        assert!(merge(&mut []).is_err());
+    }
+
+    #[test]
+    fn test_merge_report() {
+        let mut docs = vec![labelled_pdf(&["A1", "A2"]), labelled_pdf(&["B1"])];
+        let report = merge_report(&mut docs).unwrap();
+        assert_eq!(report.pages_affected, vec![0, 1, 2]);
+        assert!(report.modified.is_empty() && report.deleted.is_empty());
+
+        let saved = merge(&mut docs).unwrap().save().unwrap();
+        assert_eq!(report.bytes_written, Some(saved.len()));
+        // Catalog, page tree, a font per source document, and a page and
+        // content stream per page
+        assert_eq!(report.added.len(), 2 + 2 + 3 * 2);
+
+        assert!(merge_report(&mut []).is_err());
+    }
+
+    #[test]
+    fn test_split() {
+        let mut doc = labelled_pdf(&["P1", "P2", "P3"]);
+        let mut parts = split(&mut doc, &[0..1, 1..3]).unwrap();
+        assert_eq!(parts.len(), 2);
+        assert_eq!(page_texts(&mut parts[0]), vec!["P1"]);
+        assert_eq!(page_texts(&mut parts[1]), vec!["P2", "P3"]);
+
+        // The second part doesn't contain the page its links pointed at
+        assert_eq!(link_target(&mut parts[1], 0), PDFObject::Null);
+
+        assert!(split(&mut doc, &[1..2, 2..4]).is_err());
+    }
+
+    #[test]
+    fn test_split_report() {
+        let mut doc = labelled_pdf(&["P1", "P2", "P3"]);
+        let reports = split_report(&mut doc, &[0..1, 1..3]).unwrap();
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[0].pages_affected, vec![0]);
+        assert_eq!(reports[1].pages_affected, vec![0, 1]);
+        assert!(reports[0].object_count() < reports[1].object_count());
+        assert!(!doc.is_modified());
+
+        assert!(split_report(&mut doc, &[1..2, 2..4]).is_err());
+    }
+
+    #[test]
+    fn test_copy_depth_limit() {
+        // The page's annotation starts a /Next chain longer than the
+        // default nesting limit
+        let mut objects: Vec<String> = [
+            "<< /Type /Catalog /Pages 2 0 R >>",
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>",
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 10 10] /Annots [4 0 R] >>",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        for num in 4..304 {
+            objects.push(format!("<< /Next {} 0 R >>", num + 1));
+        }
+        objects.push("null".to_string());
+        let mut doc = open_objects(&objects);
+        assert!(matches!(
+            doc.extract_pages(0..1, &ExtractPagesOptions::default()),
+            Err(PDFError::LimitExceeded { .. })
+        ));
+    }
+
+    #[test]
+    fn test_extract_pages_subsets_resources() {
+        let objects: Vec<String> = [
+            "<< /Type /Catalog /Pages 2 0 R >>",
+            "<< /Type /Pages /Kids [3 0 R 4 0 R] /Count 2 /MediaBox [0 0 612 792] \
+             /Resources << /Font << /F1 5 0 R /F2 6 0 R >> /XObject << /Im1 7 0 R >> >> >>",
+            "<< /Type /Page /Parent 2 0 R /Contents 8 0 R \
+             /Annots [<< /Type /Annot /Subtype /Text /Rect [0 0 10 10] >>] >>",
+            "<< /Type /Page /Parent 2 0 R /Contents 9 0 R >>",
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>",
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Courier >>",
+            "<< /Type /XObject /Subtype /Image /Width 1 /Height 1 /ColorSpace /DeviceGray \
+             /BitsPerComponent 8 /Length 1 >>\nstream\n\x00\nendstream",
+            "<< /Length 33 >>\nstream\nBT /F1 12 Tf 72 700 Td (Hi) Tj ET\nendstream",
+            "<< /Length 43 >>\nstream\nq 10 0 0 10 0 0 cm /Im1 Do Q BT /F2 9 Tf ET\nendstream",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        let mut doc = open_objects(&objects);
+
+        let bytes = doc
+            .extract_pages(0..1, &ExtractPagesOptions::default())
+            .unwrap();
+        let text = String::from_utf8_lossy(&bytes);
+        assert!(text.contains("/Helvetica"));
+        assert!(!text.contains("/Courier"));
+        assert!(!text.contains("/Image"));
+
+        let mut extracted = PDFDocument::open(bytes).unwrap();
+        assert_eq!(extracted.page_count().unwrap(), 1);
+        assert_eq!(
+            extracted
+                .extract_text_from_page_as_string(0)
+                .unwrap()
+                .trim(),
+            "Hi"
+        );
+        assert!(extracted.get_page(0).unwrap().annotations().is_some());
+
+        let options = ExtractPagesOptions {
+            keep_annotations: false,
+        };
+        let mut extracted = PDFDocument::open(doc.extract_pages(0..2, &options).unwrap()).unwrap();
+        assert_eq!(extracted.page_count().unwrap(), 2);
+        assert!(extracted.get_page(0).unwrap().annotations().is_none());
+        let page = extracted.get_page(1).unwrap();
+        let PDFObject::Dictionary(resources) = page.get("Resources").unwrap() else {
+            panic!("resources are not a dictionary");
+        };
+        let PDFObject::Dictionary(fonts) = resources.get("Font").unwrap() else {
+            panic!("fonts are not a dictionary");
+        };
+        assert_eq!(fonts.keys().collect::<Vec<_>>(), vec!["F2"]);
+        assert!(resources.contains_key("XObject"));
+
+        assert!(doc.extract_pages(1..3, &options).is_err());
+    }
+}
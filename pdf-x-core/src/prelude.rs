@@ -0,0 +1,20 @@
+//! Commonly used types, for glob import.
+//!
+//! ```no_run
+//! use pdf_x_core::prelude::*;
+//!
+//! let mut doc = Pdf::open("document.pdf").load()?;
+//! let page = doc.get_page(0)?;
+//! # Ok::<(), PDFError>(())
+//! ```
+//!
+//! The prelude re-exports types by name, so code that imports from here keeps
+//! compiling when they move between modules.
+
+pub use crate::core::error::PDFResult;
+pub use crate::core::{
+    Annotation, AnnotationType, Attachment, Diagnostic, DiagnosticKind, DocumentMetadata,
+    ExtractionMode, OpenOptions, OutlineDestination, OutlineItem, PDFDate, PDFDocument, PDFError,
+    PDFObject, Page, ParseOptions, Pdf, Ref, ResolvedOutlineItem, ResourceLimits, TextItem,
+    TextLayoutOptions, TextLine, TextWord,
+};
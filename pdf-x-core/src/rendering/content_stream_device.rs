@@ -0,0 +1,466 @@
+//! Content stream output device.
+//!
+//! This module provides a Device implementation that serializes drawing
+//! operations back into PDF content stream syntax. Rendering a page through
+//! it produces a single, flattened content stream: form XObjects are
+//! inlined, images become inline images and every color is written in its
+//! device color space. This is useful for printing pipelines and for
+//! checking how the interpreter handled a page's operators.
+//!
+//! The output is simplified rather than exact:
+//! - Shadings are painted with their middle color and tiling patterns are
+//!   dropped
+//! - Blend modes, soft masks and transparency groups are ignored
+//! - Image alpha channels are dropped
+//! - Text keeps the font resource names of the original page, so the
+//!   stream must be paired with the page's /Font resources
+
+use super::device::{Device, FontWidthMetrics, ImageData, Paint, PathDrawMode};
+use super::graphics_state::{Color, FillRule, StrokeProps, invert_matrix, multiply_matrix};
+use crate::core::decode::encode_flate;
+use crate::core::error::{PDFError, PDFResult};
+use std::collections::HashMap;
+use std::fmt::Write as _;
+
+/// A device that writes PDF content stream operators.
+///
+/// # Example
+/// ```no_run
+/// use pdf_x_core::PDFDocument;
+/// use pdf_x_core::rendering::ContentStreamDevice;
+///
+/// let mut doc = PDFDocument::open_file("document.pdf", None, None).unwrap();
+/// let page = doc.get_page(0).unwrap();
+///
+/// let mut device = ContentStreamDevice::new(612.0, 792.0);
+/// page.render(doc.xref_mut(), &mut device).unwrap();
+/// println!("{}", String::from_utf8_lossy(&device.finish()));
+/// ```
+#[derive(Debug)]
+pub struct ContentStreamDevice {
+    /// Page width in user space units
+    page_width: f64,
+    /// Page height in user space units
+    page_height: f64,
+    /// Content stream written so far
+    output: String,
+    /// Current path construction operators
+    path: String,
+    /// CTM stack, one entry per `q` level
+    ctm_stack: Vec<[f64; 6]>,
+    /// Width metrics of loaded simple fonts, by font name
+    font_metrics: HashMap<String, FontWidthMetrics>,
+}
+
+impl ContentStreamDevice {
+    /// Create a new content stream device with the given page dimensions.
+    pub fn new(width: f64, height: f64) -> Self {
+        ContentStreamDevice {
+            page_width: width,
+            page_height: height,
+            output: String::new(),
+            path: String::new(),
+            ctm_stack: vec![[1.0, 0.0, 0.0, 1.0, 0.0, 0.0]],
+            font_metrics: HashMap::new(),
+        }
+    }
+
+    /// The content stream written so far.
+    pub fn content(&self) -> &str {
+        &self.output
+    }
+
+    /// Finish drawing and return the content stream, with any `q` levels
+    /// left open closed.
+    pub fn finish(mut self) -> Vec<u8> {
+        for _ in 1..self.ctm_stack.len() {
+            self.output.push_str("Q\n");
+        }
+        self.output.into_bytes()
+    }
+
+    fn ctm(&self) -> [f64; 6] {
+        *self.ctm_stack.last().unwrap()
+    }
+
+    /// Writes the current path with its colors and the painting operator.
+    ///
+    /// Uncolored tiling patterns can't be flattened, so the fill or stroke
+    /// painted with one is left out.
+    fn write_path(
+        &mut self,
+        mode: PathDrawMode,
+        fill: &Paint,
+        stroke: &Paint,
+        stroke_props: &StrokeProps,
+    ) {
+        let path = std::mem::take(&mut self.path);
+        let paints = |paint: &Paint| !matches!(paint, Paint::Tile(tile) if tile.color.is_none());
+        let (fill_rule, stroked) = match mode {
+            PathDrawMode::Fill(rule) => (Some(rule).filter(|_| paints(fill)), false),
+            PathDrawMode::Stroke => (None, paints(stroke)),
+            PathDrawMode::FillStroke(rule) => (Some(rule).filter(|_| paints(fill)), paints(stroke)),
+        };
+        let operator = match (fill_rule, stroked) {
+            (Some(FillRule::NonZero), false) => "f",
+            (Some(FillRule::EvenOdd), false) => "f*",
+            (None, true) => "S",
+            (Some(FillRule::NonZero), true) => "B",
+            (Some(FillRule::EvenOdd), true) => "B*",
+            (None, false) => return,
+        };
+        if path.is_empty() {
+            return;
+        }
+
+        if fill_rule.is_some() {
+            self.write_color(fill, false);
+        }
+        if stroked {
+            self.write_color(stroke, true);
+            self.write_stroke_props(stroke_props);
+        }
+        self.output.push_str(&path);
+        self.output.push_str(operator);
+        self.output.push('\n');
+    }
+
+    /// Writes the operator setting the stroke or fill color.
+    fn write_color(&mut self, paint: &Paint, stroke: bool) {
+        // Shadings flatten to their middle color; see the module docs
+        let color = match paint {
+            Paint::Solid(color) => *color,
+            Paint::Gradient(gradient) => gradient.middle_color(),
+            Paint::Tile(tile) => tile.color.unwrap_or_default(),
+        };
+        let (gray, rgb, cmyk) = if stroke {
+            ("G", "RG", "K")
+        } else {
+            ("g", "rg", "k")
+        };
+        let _ = match color {
+            Color::Gray(g) => writeln!(self.output, "{} {}", num(g), gray),
+            Color::RGB(r, g, b) => {
+                writeln!(self.output, "{} {} {} {}", num(r), num(g), num(b), rgb)
+            }
+            Color::CMYK(c, m, y, k) => writeln!(
+                self.output,
+                "{} {} {} {} {}",
+                num(c),
+                num(m),
+                num(y),
+                num(k),
+                cmyk
+            ),
+        };
+    }
+
+    fn write_stroke_props(&mut self, props: &StrokeProps) {
+        let dashes: Vec<String> = props.dash_array.iter().map(|&d| num(d)).collect();
+        let _ = writeln!(
+            self.output,
+            "{} w {} J {} j {} M [{}] {} d",
+            num(props.line_width),
+            props.line_cap as u8,
+            props.line_join as u8,
+            num(props.miter_limit),
+            dashes.join(" "),
+            num(props.dash_offset)
+        );
+    }
+
+    fn write_matrix(&mut self, matrix: &[f64; 6]) {
+        let values: Vec<String> = matrix.iter().map(|&v| num(v)).collect();
+        let _ = writeln!(self.output, "{} cm", values.join(" "));
+    }
+}
+
+impl Device for ContentStreamDevice {
+    fn begin_path(&mut self) {
+        self.path.clear();
+    }
+
+    fn move_to(&mut self, x: f64, y: f64) {
+        let _ = writeln!(self.path, "{} {} m", num(x), num(y));
+    }
+
+    fn line_to(&mut self, x: f64, y: f64) {
+        let _ = writeln!(self.path, "{} {} l", num(x), num(y));
+    }
+
+    fn curve_to(&mut self, cp1x: f64, cp1y: f64, cp2x: f64, cp2y: f64, x: f64, y: f64) {
+        let _ = writeln!(
+            self.path,
+            "{} {} {} {} {} {} c",
+            num(cp1x),
+            num(cp1y),
+            num(cp2x),
+            num(cp2y),
+            num(x),
+            num(y)
+        );
+    }
+
+    fn rect(&mut self, x: f64, y: f64, width: f64, height: f64) {
+        let _ = writeln!(
+            self.path,
+            "{} {} {} {} re",
+            num(x),
+            num(y),
+            num(width),
+            num(height)
+        );
+    }
+
+    fn close_path(&mut self) {
+        self.path.push_str("h\n");
+    }
+
+    fn draw_path(
+        &mut self,
+        mode: PathDrawMode,
+        paint: &Paint,
+        stroke_props: &StrokeProps,
+    ) -> PDFResult<()> {
+        self.write_path(mode, paint, paint, stroke_props);
+        Ok(())
+    }
+
+    fn fill_and_stroke_path(
+        &mut self,
+        rule: FillRule,
+        fill: &Paint,
+        stroke: &Paint,
+        stroke_props: &StrokeProps,
+    ) -> PDFResult<()> {
+        self.write_path(PathDrawMode::FillStroke(rule), fill, stroke, stroke_props);
+        Ok(())
+    }
+
+    fn clip_path(&mut self, rule: FillRule) -> PDFResult<()> {
+        let path = std::mem::take(&mut self.path);
+        self.output.push_str(&path);
+        self.output.push_str(match rule {
+            FillRule::NonZero => "W n\n",
+            FillRule::EvenOdd => "W* n\n",
+        });
+        Ok(())
+    }
+
+    fn save_state(&mut self) {
+        self.ctm_stack.push(self.ctm());
+        self.output.push_str("q\n");
+    }
+
+    fn restore_state(&mut self) {
+        if self.ctm_stack.len() > 1 {
+            self.ctm_stack.pop();
+            self.output.push_str("Q\n");
+        }
+    }
+
+    fn concat_matrix(&mut self, matrix: &[f64; 6]) {
+        let ctm = multiply_matrix(matrix, &self.ctm());
+        *self.ctm_stack.last_mut().unwrap() = ctm;
+        self.write_matrix(matrix);
+    }
+
+    fn set_matrix(&mut self, matrix: &[f64; 6]) {
+        // Content streams can only concatenate; undo the current CTM first
+        let Some(inverse) = invert_matrix(&self.ctm()) else {
+            return;
+        };
+        let relative = multiply_matrix(matrix, &inverse);
+        *self.ctm_stack.last_mut().unwrap() = *matrix;
+        self.write_matrix(&relative);
+    }
+
+    fn draw_text(
+        &mut self,
+        text_bytes: &[u8],
+        font_name: &str,
+        font_size: f64,
+        character_spacing: f64,
+        word_spacing: f64,
+        paint: &Paint,
+        text_matrix: &[f64; 6],
+        horizontal_scaling: f64,
+        text_rise: f64,
+    ) -> PDFResult<f64> {
+        let metrics = self.font_metrics.get(font_name);
+        let width: f64 = text_bytes
+            .iter()
+            .map(|&byte| {
+                let glyph = metrics.map_or(500, |m| m.width(byte)) as f64 * font_size / 1000.0;
+                glyph + character_spacing + if byte == b' ' { word_spacing } else { 0.0 }
+            })
+            .sum();
+
+        self.output.push_str("BT\n");
+        self.write_color(paint, false);
+        let matrix: Vec<String> = text_matrix.iter().map(|&v| num(v)).collect();
+        let hex: String = text_bytes.iter().map(|b| format!("{:02X}", b)).collect();
+        let _ = writeln!(
+            self.output,
+            "/{} {} Tf {} Tc {} Tw {} Tz {} Ts {} Tm <{}> Tj\nET",
+            font_name,
+            num(font_size),
+            num(character_spacing),
+            num(word_spacing),
+            num(horizontal_scaling),
+            num(text_rise),
+            matrix.join(" "),
+            hex
+        );
+        Ok(width)
+    }
+
+    fn draw_image(&mut self, image: ImageData, transform: &[f64; 6]) -> PDFResult<()> {
+        let pixels = image.width as usize * image.height as usize;
+        let len = image.data.len();
+        let (color_space, samples, decode) = match image.bits_per_component {
+            // Set bits are black in device image data
+            1 => ("G", image.data, " /D [1 0]"),
+            8 if image.has_alpha && len >= pixels * 4 => (
+                "RGB",
+                image
+                    .data
+                    .chunks(4)
+                    .take(pixels)
+                    .flat_map(|c| [c[0], c[1], c[2]])
+                    .collect(),
+                "",
+            ),
+            8 if len >= pixels * 4 => ("CMYK", image.data, ""),
+            8 if len >= pixels * 3 => ("RGB", image.data, ""),
+            8 if len >= pixels => ("G", image.data, ""),
+            _ => {
+                return Err(PDFError::Generic(format!(
+                    "Unsupported image data: {}x{}, {} bpc, {} bytes",
+                    image.width, image.height, image.bits_per_component, len
+                )));
+            }
+        };
+
+        let compressed = encode_flate(&samples)?;
+        let hex: String = compressed.iter().map(|b| format!("{:02X}", b)).collect();
+
+        self.output.push_str("q\n");
+        self.write_matrix(transform);
+        let _ = writeln!(
+            self.output,
+            "BI /W {} /H {} /CS /{} /BPC {}{} /F [/AHx /Fl] ID\n{}>\nEI\nQ",
+            image.width, image.height, color_space, image.bits_per_component, decode, hex
+        );
+        Ok(())
+    }
+
+    fn page_bounds(&self) -> (f64, f64) {
+        (self.page_width, self.page_height)
+    }
+
+    fn set_font_width_metrics(&mut self, name: &str, metrics: &FontWidthMetrics) -> PDFResult<()> {
+        self.font_metrics.insert(name.to_string(), metrics.clone());
+        Ok(())
+    }
+}
+
+/// Formats a number for a content stream, with at most four decimals.
+fn num(value: f64) -> String {
+    let rounded = (value * 10000.0).round() / 10000.0;
+    if rounded.fract() == 0.0 {
+        format!("{}", rounded as i64)
+    } else {
+        format!("{}", rounded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{ContentStreamEvaluator, Lexer, Parser, Stream};
+    use crate::rendering::{RenderingContext, TestDevice};
+
+    #[test]
+    fn test_content_stream_device_output() {
+        let mut device = ContentStreamDevice::new(612.0, 792.0);
+        device.set_matrix(&[2.0, 0.0, 0.0, 2.0, 0.0, 0.0]);
+        device.save_state();
+        device.set_matrix(&[1.0, 0.0, 0.0, 1.0, 10.0, 0.0]);
+        device.begin_path();
+        device.rect(0.0, 0.0, 10.0, 5.5);
+        device.clip_path(FillRule::EvenOdd).unwrap();
+        device.begin_path();
+        device.move_to(0.0, 0.0);
+        device.line_to(1.0 / 3.0, 1.0);
+        device
+            .draw_path(
+                PathDrawMode::Stroke,
+                &Paint::from_color(Color::CMYK(0.0, 1.0, 0.0, 0.0)),
+                &StrokeProps::default(),
+            )
+            .unwrap();
+        device
+            .draw_text(
+                b"Hi",
+                "F1",
+                12.0,
+                0.0,
+                0.0,
+                &Paint::black(),
+                &[1.0, 0.0, 0.0, 1.0, 72.0, 700.0],
+                100.0,
+                0.0,
+            )
+            .unwrap();
+
+        let content = String::from_utf8(device.finish()).unwrap();
+        assert_eq!(
+            content,
+            "2 0 0 2 0 0 cm\nq\n0.5 0 0 0.5 5 0 cm\n0 0 10 5.5 re\nW* n\n\
+             0 1 0 0 K\n1 w 0 J 0 j 10 M [] 0 d\n0 0 m\n0.3333 1 l\nS\n\
+             BT\n0 g\n/F1 12 Tf 0 Tc 0 Tw 100 Tz 0 Ts 1 0 0 1 72 700 Tm <4869> Tj\nET\nQ\n"
+        );
+    }
+
+    fn run<D: Device>(device: &mut D, content: &[u8]) {
+        let stream = Box::new(Stream::from_bytes(content.to_vec()));
+        let mut evaluator =
+            ContentStreamEvaluator::new(Parser::new(Lexer::new(stream).unwrap()).unwrap());
+        let mut ctx = RenderingContext::new(device);
+        while let Some(op) = evaluator.read_operation().unwrap() {
+            ctx.process_operation(&op).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_content_stream_round_trip() {
+        // Re-rendering the flattened stream draws the same paths
+        let source = b"q 1 0 0 RG 2 w 0 0 m 10 10 l S Q 0.5 g 0 0 5 5 re f";
+        let mut flattening = ContentStreamDevice::new(612.0, 792.0);
+        run(&mut flattening, source);
+        let flattened = flattening.finish();
+
+        let paths = |content: &[u8]| -> Vec<String> {
+            let mut device = TestDevice::new(612.0, 792.0);
+            run(&mut device, content);
+            device
+                .operations()
+                .iter()
+                .filter(|op| op.starts_with("draw_path") || op.starts_with("line_to"))
+                .cloned()
+                .collect()
+        };
+        assert_eq!(paths(source), paths(&flattened));
+        assert_eq!(paths(source).len(), 3);
+    }
+
+    #[test]
+    fn test_fill_and_stroke_colors() {
+        let mut device = ContentStreamDevice::new(612.0, 792.0);
+        run(&mut device, b"1 0 0 rg 0 0 1 RG 0 0 5 5 re B");
+        let content = String::from_utf8(device.finish()).unwrap();
+        assert!(content.contains("1 0 0 rg\n0 0 1 RG\n"));
+        assert!(content.ends_with("0 0 5 5 re\nB\n"));
+    }
+}
@@ -6,14 +6,110 @@
 //! - Device for rendering operations
 //! - Processing of content stream operators
 
-use super::device::{Device, FontWidthMetrics};
-use super::graphics_state::{Color, FillRule, GraphicsState};
+use super::device::{
+    Device, FontWidthMetrics, GlyphRun, Gradient, PositionedGlyph, SoftMaskKind, Tile,
+};
+use super::font_resolver::{DefaultFontResolver, FontQuery, FontResolver};
+use super::graphics_state::{
+    BlendMode, Color, FillRule, GraphicsState, RenderingIntent, invert_matrix, multiply_matrix,
+};
 use super::path::Path;
 use super::{Paint, PathDrawMode};
+use crate::core::colorspace::ColorSpace;
 use crate::core::content_stream::{OpCode, Operation};
+use crate::core::cycle_guard::CycleGuard;
+use crate::core::encoding::Encoding;
 use crate::core::error::{PDFError, PDFResult};
-use crate::core::parser::PDFObject;
-use crate::core::xref::XRef;
+use crate::core::font::{Font, FontType, StandardFont};
+use crate::core::image::ImageDetection;
+use crate::core::limits::ResourceLimits;
+use crate::core::parser::{PDFObject, Ref};
+use crate::core::shading::Shading;
+use crate::core::xref::{DiagnosticKind, XRef};
+use crate::log_target;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// How many soft mask groups, tiling pattern cells and form XObjects a
+/// context is nested in, the indirect ones among them, and the operators
+/// run so far by it and the contexts around it.
+#[derive(Debug, Clone, Default)]
+struct Nesting {
+    soft_masks: usize,
+    patterns: usize,
+    forms: usize,
+    groups: CycleGuard,
+    operations: Rc<Cell<usize>>,
+}
+
+impl Nesting {
+    /// Returns the nesting for the content of `group`, or `None` if `group`
+    /// is already being rendered and painting it again would never end.
+    fn enter(&self, group: Option<Ref>) -> Option<Nesting> {
+        let mut nesting = self.clone();
+        match group {
+            Some(r) if !nesting.groups.visit(r) => None,
+            _ => Some(nesting),
+        }
+    }
+}
+
+/// Kind of painting operation that requested overprint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverprintPaint {
+    /// Path fill (f, f*, and the fill part of B/b)
+    Fill,
+    /// Path stroke (S, s, and the stroke part of B/b)
+    Stroke,
+    /// Text showing (Tj, TJ, ', ")
+    Text,
+    /// Image XObject (Do)
+    Image,
+}
+
+/// A painting operation executed while overprint was enabled.
+///
+/// Overprint is not simulated by the renderer; this records where a
+/// separation-aware output device would have to honour it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OverprintUsage {
+    /// What was painted
+    pub paint: OverprintPaint,
+
+    /// Device-space bounding box [x0, y0, x1, y1], when it is known
+    pub bbox: Option<[f64; 4]>,
+
+    /// Overprint mode (/OPM) in effect
+    pub overprint_mode: u8,
+}
+
+/// Information collected while rendering that a device cannot express.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RenderReport {
+    /// Painting operations that requested overprint, in content order
+    pub overprint: Vec<OverprintUsage>,
+
+    /// Distinct rendering intents selected by ri or /RI, in the order seen
+    pub rendering_intents: Vec<RenderingIntent>,
+}
+
+impl RenderReport {
+    /// Returns true if any painting operation requested overprint.
+    pub fn uses_overprint(&self) -> bool {
+        !self.overprint.is_empty()
+    }
+
+    /// Appends another report, e.g. the one of a following content stream.
+    pub fn merge(&mut self, other: RenderReport) {
+        self.overprint.extend(other.overprint);
+        for intent in other.rendering_intents {
+            if !self.rendering_intents.contains(&intent) {
+                self.rendering_intents.push(intent);
+            }
+        }
+    }
+}
 
 /// Rendering context for processing PDF content streams.
 ///
@@ -32,8 +128,11 @@ pub struct RenderingContext<'a, D: Device> {
     /// Current path being constructed
     current_path: Path,
 
-    /// Clip path stack (for nesting)
-    clip_stack: Vec<FillRule>,
+    /// Graphics state depth at which each clip still in effect was pushed
+    clip_stack: Vec<usize>,
+
+    /// Clip requested by W/W*, applied by the next path-painting operator
+    pending_clip: Option<FillRule>,
 
     /// Whether we're in a text object (BT...ET)
     in_text_object: bool,
@@ -44,6 +143,24 @@ pub struct RenderingContext<'a, D: Device> {
     /// Page resources dictionary (for looking up XObjects, fonts, etc.)
     resources: Option<&'a PDFObject>,
 
+    /// Overprint and rendering intent usage seen so far
+    report: RenderReport,
+
+    /// Groups this context is nested in
+    nesting: Nesting,
+
+    /// Limits of the document being rendered
+    limits: ResourceLimits,
+
+    /// Tiling pattern cells rendered so far, by pattern name
+    tiles: HashMap<String, Tile>,
+
+    /// Composite (Type0) fonts by resource name, for code → glyph mapping and /W advances
+    composite_fonts: HashMap<String, Font>,
+
+    /// Chooses substitutes for fonts without a usable embedded program
+    font_resolver: &'a dyn FontResolver,
+
     /// Operation counter for debug logging
     #[cfg(feature = "debug-logging")]
     operation_count: usize,
@@ -60,20 +177,35 @@ impl<'a, D: Device> RenderingContext<'a, D> {
             state_stack: vec![GraphicsState::default()],
             current_path: Path::new(),
             clip_stack: Vec::new(),
+            pending_clip: None,
             in_text_object: false,
             xref: None,
             resources: None,
+            report: RenderReport::default(),
+            nesting: Nesting::default(),
+            limits: ResourceLimits::default(),
+            tiles: HashMap::new(),
+            composite_fonts: HashMap::new(),
+            font_resolver: &DefaultFontResolver,
             #[cfg(feature = "debug-logging")]
             operation_count: 0,
         }
     }
 
+    /// Set the resolver that picks substitutes for non-embedded fonts.
+    ///
+    /// Defaults to [`DefaultFontResolver`].
+    pub fn set_font_resolver(&mut self, resolver: &'a dyn FontResolver) {
+        self.font_resolver = resolver;
+    }
+
     /// Set the xref table and page resources for XObject rendering.
     ///
     /// # Arguments
     /// * `xref` - The cross-reference table for fetching objects
     /// * `resources` - The page's resources dictionary
     pub fn set_xobject_resources(&mut self, xref: &'a mut XRef, resources: &'a PDFObject) {
+        self.limits = *xref.limits();
         self.xref = Some(xref);
         self.resources = Some(resources);
     }
@@ -97,11 +229,51 @@ impl<'a, D: Device> RenderingContext<'a, D> {
         &mut *self.device
     }
 
+    /// Get the report of what has been rendered so far.
+    pub fn report(&self) -> &RenderReport {
+        &self.report
+    }
+
+    /// Take the report, leaving an empty one behind.
+    pub fn take_report(&mut self) -> RenderReport {
+        std::mem::take(&mut self.report)
+    }
+
+    /// Number of operators run so far, including those of form XObjects,
+    /// tiling patterns and soft masks.
+    pub fn operation_count(&self) -> usize {
+        self.nesting.operations.get()
+    }
+
+    /// Sets the number of operators already run, so that the contexts of a
+    /// page's content streams share one
+    /// [`max_operations`](ResourceLimits::max_operations) budget.
+    pub fn set_operation_count(&mut self, count: usize) {
+        self.nesting.operations.set(count);
+    }
+
+    /// Set the resource limits to enforce, which
+    /// [`set_xobject_resources`](Self::set_xobject_resources) takes from the
+    /// xref table.
+    pub fn set_limits(&mut self, limits: ResourceLimits) {
+        self.limits = limits;
+    }
+
     /// Process a content stream operation.
     ///
     /// This is the main entry point for interpreting PDF content streams.
     /// It dispatches to appropriate handler methods based on the operator.
     pub fn process_operation(&mut self, op: &Operation) -> PDFResult<()> {
+        let count = self.nesting.operations.get() + 1;
+        let max_operations = self.limits.max_operations;
+        if count > max_operations {
+            return Err(PDFError::limit_exceeded(
+                "content stream operations",
+                max_operations,
+            ));
+        }
+        self.nesting.operations.set(count);
+
         // Debug: Log first 20 operations to understand what's happening
         #[cfg(feature = "debug-logging")]
         {
@@ -109,8 +281,9 @@ impl<'a, D: Device> RenderingContext<'a, D> {
                 #[cfg(feature = "debug-logging")]
                 #[cfg(feature = "debug-logging")]
                 #[cfg(feature = "debug-logging")]
-                eprintln!(
-                    "DEBUG: Operation #{}: {:?} with {} args",
+                tracing::trace!(
+                    target: log_target::RENDER,
+                    "Operation #{}: {:?} with {} args",
                     self.operation_count,
                     op.op,
                     op.args.len()
@@ -124,6 +297,8 @@ impl<'a, D: Device> RenderingContext<'a, D> {
             OpCode::Save => self.save()?,
             OpCode::Restore => self.restore()?,
             OpCode::Transform => self.transform(&op.args)?,
+            OpCode::SetGState => self.set_gstate(&op.args)?,
+            OpCode::SetRenderingIntent => self.set_rendering_intent(&op.args)?,
 
             // Path construction operators
             OpCode::MoveTo => self.move_to(&op.args)?,
@@ -181,6 +356,13 @@ impl<'a, D: Device> RenderingContext<'a, D> {
             OpCode::SetFillRGBColor => self.set_fill_rgb(&op.args)?,
             OpCode::SetStrokeCMYKColor => self.set_stroke_cmyk(&op.args)?,
             OpCode::SetFillCMYKColor => self.set_fill_cmyk(&op.args)?,
+            OpCode::SetStrokeColorSpace => self.set_color_space(&op.args, false)?,
+            OpCode::SetFillColorSpace => self.set_color_space(&op.args, true)?,
+            OpCode::SetStrokeColor | OpCode::SetStrokeColorN => self.set_color(&op.args, false)?,
+            OpCode::SetFillColor | OpCode::SetFillColorN => self.set_color(&op.args, true)?,
+
+            // Shading operator
+            OpCode::ShadingFill => self.shading_fill(&op.args)?,
 
             // Line property operators
             OpCode::SetLineWidth => self.set_line_width(&op.args)?,
@@ -191,6 +373,7 @@ impl<'a, D: Device> RenderingContext<'a, D> {
 
             // XObject operator
             OpCode::PaintXObject => self.paint_xobject(&op.args)?,
+            OpCode::EndInlineImage => self.paint_inline_image(&op.args)?,
 
             _ => {
                 // Other operators not yet implemented
@@ -213,21 +396,28 @@ impl<'a, D: Device> RenderingContext<'a, D> {
     }
 
     fn restore(&mut self) -> PDFResult<()> {
-        // Restore any clipping paths
-        while self.clip_stack.len() >= self.state_stack.len() {
-            self.clip_stack.pop();
-            // Note: Device doesn't have a pop_clip method in our trait
-            // In a full implementation, we'd pop the clip here
-        }
-
-        // Restore graphics state
+        // Restore graphics state; an unbalanced Q is ignored
         if self.state_stack.len() > 1 {
+            // Clips set since the matching q end with it
+            self.pop_clips(self.state_stack.len());
             self.state_stack.pop();
             self.device.restore_state();
         }
         Ok(())
     }
 
+    /// Pops the clips pushed at graphics state depth `depth` or deeper.
+    fn pop_clips(&mut self, depth: usize) {
+        while self
+            .clip_stack
+            .last()
+            .is_some_and(|&pushed| pushed >= depth)
+        {
+            self.clip_stack.pop();
+            self.device.pop_clip();
+        }
+    }
+
     fn transform(&mut self, args: &[crate::core::parser::PDFObject]) -> PDFResult<()> {
         if args.len() < 6 {
             return Err(PDFError::content_stream_error(
@@ -251,6 +441,339 @@ impl<'a, D: Device> RenderingContext<'a, D> {
         Ok(())
     }
 
+    fn set_gstate(&mut self, args: &[crate::core::parser::PDFObject]) -> PDFResult<()> {
+        let name = match args.first() {
+            Some(PDFObject::Name(name)) => name,
+            _ => {
+                return Err(PDFError::content_stream_error(
+                    "gs operator requires a name argument".to_string(),
+                ));
+            }
+        };
+
+        let (Some(xref), Some(PDFObject::Dictionary(resources))) =
+            (self.xref.as_deref_mut(), self.resources)
+        else {
+            return Ok(());
+        };
+        let Some(ext_gstates) = resources.get("ExtGState") else {
+            return Ok(());
+        };
+        let gstate = match xref.fetch_if_ref(ext_gstates)? {
+            PDFObject::Dictionary(dict) => match dict.get(name.as_str()) {
+                Some(entry) => xref.fetch_if_ref(entry)?,
+                None => return Ok(()),
+            },
+            _ => return Ok(()),
+        };
+        let PDFObject::Dictionary(gstate) = gstate else {
+            return Ok(());
+        };
+
+        // Reference: pdf.js/src/core/evaluator.js - setGState
+        for (key, value) in &gstate {
+            match (key.as_str(), value) {
+                ("LW", PDFObject::Number(_)) => self.set_line_width(std::slice::from_ref(value))?,
+                ("LC", PDFObject::Number(_)) => self.set_line_cap(std::slice::from_ref(value))?,
+                ("LJ", PDFObject::Number(_)) => self.set_line_join(std::slice::from_ref(value))?,
+                ("ML", PDFObject::Number(_)) => {
+                    self.set_miter_limit(std::slice::from_ref(value))?
+                }
+                ("D", PDFObject::Array(dash)) if dash.len() == 2 => {
+                    let args = [(*dash[0]).clone(), (*dash[1]).clone()];
+                    self.set_dash(&args)?;
+                }
+                ("RI", PDFObject::Name(_)) => {
+                    self.set_rendering_intent(std::slice::from_ref(value))?
+                }
+                ("OP", PDFObject::Boolean(overprint)) => {
+                    let state = self.current_state_mut();
+                    state.stroke_overprint = *overprint;
+                    // /op defaults to the value of /OP
+                    if !gstate.contains_key("op") {
+                        state.fill_overprint = *overprint;
+                    }
+                }
+                ("op", PDFObject::Boolean(overprint)) => {
+                    self.current_state_mut().fill_overprint = *overprint;
+                }
+                ("SMask", _) => self.set_soft_mask(value)?,
+                ("BM", _) => {
+                    let mode = BlendMode::from_object(value);
+                    self.current_state_mut().blend_mode = mode;
+                    self.device.set_blend_mode(mode);
+                }
+                ("OPM", PDFObject::Number(mode)) => {
+                    self.current_state_mut().overprint_mode = if *mode == 1.0 { 1 } else { 0 };
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies the /SMask entry of an ExtGState.
+    ///
+    /// The mask group is a transparency group XObject painted through the
+    /// device with the CTM in effect now, as PDF spec 11.6.5.2 requires.
+    /// Transfer functions (/TR) are not applied.
+    fn set_soft_mask(&mut self, smask: &PDFObject) -> PDFResult<()> {
+        let smask = match (smask, self.xref.as_deref_mut()) {
+            (PDFObject::Ref(_), Some(xref)) => xref.fetch_if_ref(smask)?,
+            _ => smask.clone(),
+        };
+        let smask = match smask {
+            PDFObject::Dictionary(dict) => dict,
+            PDFObject::Name(name) if name == "None" => {
+                self.device.clear_soft_mask();
+                return Ok(());
+            }
+            _ => return Ok(()),
+        };
+
+        let kind = match smask.get("S") {
+            Some(PDFObject::Name(name)) if name == "Alpha" => SoftMaskKind::Alpha,
+            _ => SoftMaskKind::Luminosity,
+        };
+        let backdrop = match smask.get("BC") {
+            Some(PDFObject::Array(components)) => {
+                let values: Vec<f64> = components
+                    .iter()
+                    .filter_map(|value| match **value {
+                        PDFObject::Number(n) => Some(n.clamp(0.0, 1.0)),
+                        _ => None,
+                    })
+                    .collect();
+                match values[..] {
+                    [gray] => Color::Gray(gray),
+                    [r, g, b] => Color::RGB(r, g, b),
+                    [c, m, y, k] => Color::CMYK(c, m, y, k),
+                    _ => Color::black(),
+                }
+            }
+            _ => Color::black(),
+        };
+        if matches!(smask.get("TR"), Some(tr) if !matches!(tr, PDFObject::Name(name) if name == "Identity"))
+        {
+            tracing::warn!(
+                target: log_target::RENDER,
+                "Soft mask transfer function is not supported, ignoring /TR"
+            );
+        }
+
+        // Soft mask groups are content streams drawing each other, like forms
+        let max_depth = self.limits.max_form_depth;
+        if self.nesting.soft_masks >= max_depth {
+            return Err(PDFError::limit_exceeded(
+                "soft mask nesting depth",
+                max_depth,
+            ));
+        }
+
+        let group_ref = match smask.get("G") {
+            Some(PDFObject::Ref(r)) => Some(*r),
+            _ => None,
+        };
+        let Some(mut nesting) = self.nesting.enter(group_ref) else {
+            tracing::warn!(
+                target: log_target::RENDER,
+                "Soft mask group paints itself, painting unmasked"
+            );
+            return Ok(());
+        };
+        nesting.soft_masks += 1;
+
+        let ctm = self.current_state().ctm;
+        let Some(xref) = self.xref.as_deref_mut() else {
+            return Ok(());
+        };
+        let group = match smask.get("G") {
+            Some(group) => xref.fetch_if_ref(group)?,
+            None => return Ok(()),
+        };
+        let PDFObject::Stream { dict: group, data } = group else {
+            return Ok(());
+        };
+        let content =
+            crate::core::decode::decode_stream_data_limited(&group, &data, xref.limits())?;
+        let resources = match group.get("Resources") {
+            Some(resources) => Some(xref.fetch_if_ref(resources)?),
+            None => self.resources.cloned(),
+        };
+
+        if !self.device.begin_soft_mask(kind, &backdrop)? {
+            tracing::warn!(
+                target: log_target::RENDER,
+                "Device does not support soft masks, painting unmasked"
+            );
+            return Ok(());
+        }
+
+        // Reference: pdf.js/src/display/canvas.js - beginSMaskMode / paintFormXObjectBegin
+        self.device.save_state();
+        let mut group_ctm = ctm;
+        if let Some(PDFObject::Array(matrix)) = group.get("Matrix")
+            && let [a, b, c, d, e, f] = matrix
+                .iter()
+                .filter_map(|value| match **value {
+                    PDFObject::Number(n) => Some(n),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()[..]
+        {
+            let matrix = [a, b, c, d, e, f];
+            self.device.concat_matrix(&matrix);
+            let mut state = GraphicsState {
+                ctm,
+                ..GraphicsState::default()
+            };
+            state.concat_matrix(&matrix);
+            group_ctm = state.ctm;
+        }
+        if let Some(PDFObject::Array(bbox)) = group.get("BBox")
+            && let [x0, y0, x1, y1] = bbox
+                .iter()
+                .filter_map(|value| match **value {
+                    PDFObject::Number(n) => Some(n),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()[..]
+        {
+            self.device.begin_path();
+            self.device
+                .rect(x0.min(x1), y0.min(y1), (x1 - x0).abs(), (y1 - y0).abs());
+            self.device.clip_path(FillRule::NonZero)?;
+        }
+
+        let result = Self::render_group_content(
+            &mut *self.device,
+            self.font_resolver,
+            xref,
+            resources.as_ref(),
+            content,
+            GraphicsState {
+                ctm: group_ctm,
+                ..GraphicsState::default()
+            },
+            nesting,
+        );
+
+        self.device.restore_state();
+        self.device.end_soft_mask()?;
+        result
+    }
+
+    /// Runs the content stream of a form XObject, group or pattern cell
+    /// through a nested context starting with the graphics state `state`.
+    fn render_group_content(
+        device: &mut D,
+        font_resolver: &dyn FontResolver,
+        xref: &mut XRef,
+        resources: Option<&PDFObject>,
+        content: Vec<u8>,
+        state: GraphicsState,
+        nesting: Nesting,
+    ) -> PDFResult<()> {
+        use crate::core::ContentStreamEvaluator;
+
+        let mut evaluator = ContentStreamEvaluator::from_bytes(content);
+        evaluator.set_max_depth(xref.limits().max_object_depth);
+
+        let mut ctx = RenderingContext::new(device);
+        ctx.font_resolver = font_resolver;
+        ctx.nesting = nesting;
+        ctx.limits = *xref.limits();
+        *ctx.current_state_mut() = state;
+        if let Some(resources) = resources {
+            ctx.set_xobject_resources(xref, resources);
+        }
+        let mut result = Ok(());
+        while let Some(op) = evaluator.read_operation()? {
+            match ctx.process_operation(&op) {
+                Ok(()) => {}
+                Err(e @ PDFError::LimitExceeded { .. }) => {
+                    result = Err(e);
+                    break;
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        target: log_target::RENDER,
+                        "Failed to process group operator {:?}: {}",
+                        op.op, e
+                    );
+                }
+            }
+        }
+
+        // Close q operators left open and clips left set by the content
+        while ctx.state_stack.len() > 1 {
+            ctx.restore()?;
+        }
+        ctx.pop_clips(0);
+        result
+    }
+
+    fn set_rendering_intent(&mut self, args: &[crate::core::parser::PDFObject]) -> PDFResult<()> {
+        let intent = match args.first() {
+            Some(PDFObject::Name(name)) => RenderingIntent::from_name(name),
+            _ => {
+                return Err(PDFError::content_stream_error(
+                    "ri operator requires a name argument".to_string(),
+                ));
+            }
+        };
+
+        self.current_state_mut().rendering_intent = intent;
+        if !self.report.rendering_intents.contains(&intent) {
+            self.report.rendering_intents.push(intent);
+        }
+        Ok(())
+    }
+
+    /// Records a painting operation if overprint is enabled for it.
+    ///
+    /// # Arguments
+    /// * `paint` - The kind of painting operation
+    /// * `bbox` - Bounding box in user space, transformed here by the CTM
+    fn record_overprint(&mut self, paint: OverprintPaint, bbox: Option<[f64; 4]>) {
+        let state = self.current_state();
+        let enabled = match paint {
+            OverprintPaint::Stroke => state.stroke_overprint,
+            _ => state.fill_overprint,
+        };
+        if !enabled {
+            return;
+        }
+
+        let bbox = bbox.map(|[x0, y0, x1, y1]| {
+            let corners = [
+                state.transform_point(x0, y0),
+                state.transform_point(x1, y0),
+                state.transform_point(x1, y1),
+                state.transform_point(x0, y1),
+            ];
+            corners.iter().skip(1).fold(
+                [corners[0].0, corners[0].1, corners[0].0, corners[0].1],
+                |[min_x, min_y, max_x, max_y], &(x, y)| {
+                    [min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y)]
+                },
+            )
+        });
+        let overprint_mode = state.overprint_mode;
+        self.report.overprint.push(OverprintUsage {
+            paint,
+            bbox,
+            overprint_mode,
+        });
+    }
+
+    /// Bounding box of the current path in user space.
+    fn path_bbox(&self) -> Option<[f64; 4]> {
+        self.current_path
+            .bounding_box()
+            .map(|(x0, y0, x1, y1)| [x0, y0, x1, y1])
+    }
+
     // === Path Construction Operators ===
 
     fn move_to(&mut self, args: &[crate::core::parser::PDFObject]) -> PDFResult<()> {
@@ -367,16 +890,19 @@ impl<'a, D: Device> RenderingContext<'a, D> {
 
     fn stroke(&mut self) -> PDFResult<()> {
         let state = self.current_state();
-        let paint = Paint::from_color(state.stroke_color.clone());
+        let paint = state.stroke_paint();
         let stroke_props = state.stroke_props.clone();
         #[cfg(feature = "debug-logging")]
         #[cfg(feature = "debug-logging")]
         #[cfg(feature = "debug-logging")]
-        eprintln!("DEBUG: Stroke path with color {:?}", state.stroke_color);
+        tracing::trace!(
+            target: log_target::RENDER,
+            "Stroke path with color {:?}", state.stroke_color
+        );
         self.device
             .draw_path(PathDrawMode::Stroke, &paint, &stroke_props)?;
-        self.current_path.begin();
-        Ok(())
+        self.record_overprint(OverprintPaint::Stroke, self.path_bbox());
+        self.finish_path(true)
     }
 
     fn close_and_stroke(&mut self) -> PDFResult<()> {
@@ -386,28 +912,29 @@ impl<'a, D: Device> RenderingContext<'a, D> {
 
     fn fill(&mut self, rule: FillRule) -> PDFResult<()> {
         let state = self.current_state();
-        let paint = Paint::from_color(state.fill_color.clone());
+        let paint = state.fill_paint();
         let stroke_props = state.stroke_props.clone();
         #[cfg(feature = "debug-logging")]
         #[cfg(feature = "debug-logging")]
         #[cfg(feature = "debug-logging")]
-        eprintln!("DEBUG: Fill path with color {:?}", state.fill_color);
+        tracing::trace!(target: log_target::RENDER, "Fill path with color {:?}", state.fill_color);
         self.device
             .draw_path(PathDrawMode::Fill(rule), &paint, &stroke_props)?;
-        self.current_path.begin();
-        Ok(())
+        self.record_overprint(OverprintPaint::Fill, self.path_bbox());
+        self.finish_path(true)
     }
 
     fn fill_and_stroke(&mut self, rule: FillRule) -> PDFResult<()> {
         let state = self.current_state();
-        // For fill and stroke, we use fill color for fill, stroke color for stroke
-        // But our Device trait only takes one paint, so we use fill color
-        let paint = Paint::from_color(state.fill_color.clone());
+        let fill = state.fill_paint();
+        let stroke = state.stroke_paint();
         let stroke_props = state.stroke_props.clone();
         self.device
-            .draw_path(PathDrawMode::FillStroke(rule), &paint, &stroke_props)?;
-        self.current_path.begin();
-        Ok(())
+            .fill_and_stroke_path(rule, &fill, &stroke, &stroke_props)?;
+        let bbox = self.path_bbox();
+        self.record_overprint(OverprintPaint::Fill, bbox);
+        self.record_overprint(OverprintPaint::Stroke, bbox);
+        self.finish_path(true)
     }
 
     fn close_fill_stroke(&mut self, rule: FillRule) -> PDFResult<()> {
@@ -416,15 +943,52 @@ impl<'a, D: Device> RenderingContext<'a, D> {
     }
 
     fn end_path(&mut self) -> PDFResult<()> {
+        self.finish_path(false)
+    }
+
+    /// Ends the current path after a path-painting operator, applying the
+    /// clip requested by W/W* (which takes effect after painting).
+    ///
+    /// # Arguments
+    /// * `painted` - Whether the device consumed the path by painting it
+    fn finish_path(&mut self, painted: bool) -> PDFResult<()> {
+        if let Some(rule) = self.pending_clip.take() {
+            if painted {
+                self.replay_path();
+            }
+            self.device.push_clip(rule)?;
+            self.clip_stack.push(self.state_stack.len());
+        } else if !painted {
+            // Discard the path the device has been building
+            self.device.begin_path();
+        }
         self.current_path.begin();
         Ok(())
     }
 
+    /// Sends the current path to the device again.
+    fn replay_path(&mut self) {
+        use super::path::PathElement;
+
+        self.device.begin_path();
+        for element in self.current_path.elements() {
+            match *element {
+                PathElement::MoveTo(x, y) => self.device.move_to(x, y),
+                PathElement::LineTo(x, y) => self.device.line_to(x, y),
+                PathElement::CurveTo(cp1x, cp1y, cp2x, cp2y, x, y) => {
+                    self.device.curve_to(cp1x, cp1y, cp2x, cp2y, x, y)
+                }
+                PathElement::ClosePath => self.device.close_path(),
+            }
+        }
+    }
+
     // === Clipping Operators ===
 
     fn clip(&mut self, rule: FillRule) -> PDFResult<()> {
-        self.device.clip_path(rule)?;
-        self.clip_stack.push(rule);
+        // W/W* modify the clip only once the path has been painted (or
+        // discarded with n), so painting isn't clipped by its own path
+        self.pending_clip = Some(rule);
         Ok(())
     }
 
@@ -462,6 +1026,22 @@ impl<'a, D: Device> RenderingContext<'a, D> {
             }
         }
 
+        // Standard 14 fonts may omit /Widths; their metrics are built in
+        if font_dict.widths.is_none()
+            && let Some(standard) = StandardFont::from_name(&font_dict.base_font)
+        {
+            let encoding = font_dict
+                .encoding
+                .as_ref()
+                .and_then(Encoding::from_pdf_object)
+                .unwrap_or(Encoding::Standard);
+            for code in 0..=u8::MAX {
+                if let Some(width) = standard.code_width(code, &encoding) {
+                    metrics.code_widths.insert(code, width);
+                }
+            }
+        }
+
         let descriptor_default = font_dict
             .font_descriptor
             .as_ref()
@@ -511,6 +1091,8 @@ impl<'a, D: Device> RenderingContext<'a, D> {
             ));
         }
 
+        self.record_overprint(OverprintPaint::Text, None);
+
         let state = self.current_state();
         let font_name = state
             .font_name
@@ -519,13 +1101,17 @@ impl<'a, D: Device> RenderingContext<'a, D> {
         let font_size = state.font_size.unwrap_or(12.0);
         let character_spacing = state.character_spacing;
         let word_spacing = state.word_spacing;
-        let paint = Paint::from_color(state.fill_color.clone());
+        let paint = state.fill_paint();
         let text_matrix = state.text_matrix;
         let horizontal_scaling = state.text_horizontal_scaling;
         let text_rise = state.text_rise;
 
         // Extract text bytes (using font's encoding, NOT UTF-8)
-        if let crate::core::parser::PDFObject::String(bytes) = &args[0] {
+        if let PDFObject::String(bytes) | PDFObject::HexString(bytes) = &args[0] {
+            if self.composite_fonts.contains_key(&font_name) {
+                return self.show_composite_text(bytes);
+            }
+
             // Draw text and get the actual rendered width
             let rendered_width = self.device.draw_text(
                 bytes,
@@ -561,6 +1147,8 @@ impl<'a, D: Device> RenderingContext<'a, D> {
             ));
         }
 
+        self.record_overprint(OverprintPaint::Text, None);
+
         let state = self.current_state();
         let font_name = state
             .font_name
@@ -569,12 +1157,15 @@ impl<'a, D: Device> RenderingContext<'a, D> {
         let font_size = state.font_size.unwrap_or(12.0);
         let character_spacing = state.character_spacing;
         let word_spacing = state.word_spacing;
-        let paint = Paint::from_color(state.fill_color.clone());
+        let paint = state.fill_paint();
         let horizontal_scaling = state.text_horizontal_scaling;
         let text_rise = state.text_rise;
 
         #[cfg(feature = "debug-logging")]
-        eprintln!("DEBUG: show_spaced_text: fill_color={:?}, paint={:?}", state.fill_color, paint);
+        tracing::trace!(
+            target: log_target::RENDER,
+            "show_spaced_text: fill_color={:?}, paint={:?}", state.fill_color, paint
+        );
         // We'll get text_matrix fresh for each element since it changes as we render
 
         // Extract the array
@@ -587,12 +1178,19 @@ impl<'a, D: Device> RenderingContext<'a, D> {
             }
         };
 
+        let vertical = self
+            .composite_fonts
+            .get(&font_name)
+            .map(|font| font.is_vertical());
+
         // Process each element in the array
         for element in array {
             match element.as_ref() {
-                PDFObject::String(bytes) => {
+                PDFObject::String(bytes) | PDFObject::HexString(bytes) => {
                     // Render this text string (using font encoding, NOT UTF-8)
-                    if !bytes.is_empty() {
+                    if vertical.is_some() {
+                        self.show_composite_text(bytes)?;
+                    } else if !bytes.is_empty() {
                         let text_matrix = self.current_state().text_matrix;
                         let rendered_width = self.device.draw_text(
                             bytes,
@@ -613,8 +1211,12 @@ impl<'a, D: Device> RenderingContext<'a, D> {
                 PDFObject::Number(offset) => {
                     // Offset in thousandths of an em (negative = backspace, positive = space)
                     // TJ adjustment translates in the opposite direction of the number.
-                    let adjust = -(offset * font_size * horizontal_scaling / 100.0) / 1000.0;
-                    self.translate_text_matrix(adjust, 0.0);
+                    if vertical == Some(true) {
+                        self.translate_text_matrix(0.0, -(offset * font_size) / 1000.0);
+                    } else {
+                        let adjust = -(offset * font_size * horizontal_scaling / 100.0) / 1000.0;
+                        self.translate_text_matrix(adjust, 0.0);
+                    }
                 }
                 PDFObject::Null => {
                     // Explicit null - do nothing
@@ -628,6 +1230,90 @@ impl<'a, D: Device> RenderingContext<'a, D> {
         Ok(())
     }
 
+    /// Shows a string in a composite (Type0) font.
+    ///
+    /// Codes are read with the font's CMap and mapped to glyph IDs through
+    /// CIDToGIDMap; glyph positions and the text matrix advance come from the
+    /// /W widths (or /W2 for vertical writing) rather than the font program.
+    fn show_composite_text(&mut self, bytes: &[u8]) -> PDFResult<()> {
+        let state = self.current_state();
+        let font_name = state.font_name.clone().unwrap_or_default();
+        let font_size = state.font_size.unwrap_or(12.0);
+        let character_spacing = state.character_spacing;
+        let word_spacing = state.word_spacing;
+        let paint = state.fill_paint();
+        let text_matrix = state.text_matrix;
+        let horizontal_scaling = state.text_horizontal_scaling;
+        let text_rise = state.text_rise;
+
+        let Some(font) = self.composite_fonts.get(&font_name) else {
+            return Ok(());
+        };
+        let vertical = font.is_vertical();
+        let vertical_origin = font
+            .cid_font
+            .as_ref()
+            .map_or(880.0, |cid_font| cid_font.default_vertical_origin);
+
+        // Pen position in em
+        let (mut x, mut y) = (0.0, 0.0);
+        let mut glyphs = Vec::with_capacity(bytes.len() / 2);
+        let mut i = 0;
+        while i < bytes.len() {
+            let (code, len) = font.read_char_code(bytes, i);
+            i += len;
+            let glyph_id = font.code_to_glyph_id(code).unwrap_or(0);
+            let mut spacing = character_spacing;
+            if len == 1 && code == 32 {
+                spacing += word_spacing;
+            }
+            let spacing = if font_size != 0.0 {
+                spacing / font_size
+            } else {
+                0.0
+            };
+            let width = font.code_width(code) / 1000.0;
+            if vertical {
+                // The vertical origin sits at (w0 / 2, vy) in glyph space
+                glyphs.push(PositionedGlyph {
+                    glyph_id,
+                    x: x - width / 2.0,
+                    y: y - vertical_origin / 1000.0,
+                });
+                y += font.code_vertical_advance(code) / 1000.0 + spacing;
+            } else {
+                glyphs.push(PositionedGlyph { glyph_id, x, y });
+                x += width + spacing;
+            }
+        }
+
+        let run = GlyphRun {
+            font_name,
+            font_size,
+            glyphs,
+            text_matrix,
+            horizontal_scaling,
+            text_rise,
+        };
+        if !self.device.draw_glyph_run(&run, &paint)? {
+            self.device.draw_text(
+                bytes,
+                &run.font_name,
+                font_size,
+                character_spacing,
+                word_spacing,
+                &paint,
+                &text_matrix,
+                horizontal_scaling,
+                text_rise,
+            )?;
+        }
+
+        // Horizontal advances are scaled by Tz, vertical ones are not
+        self.translate_text_matrix(x * font_size * horizontal_scaling / 100.0, y * font_size);
+        Ok(())
+    }
+
     fn next_line_show_text(&mut self, args: &[crate::core::parser::PDFObject]) -> PDFResult<()> {
         self.next_line()?;
         self.show_text(args)
@@ -780,7 +1466,10 @@ impl<'a, D: Device> RenderingContext<'a, D> {
                 #[cfg(feature = "debug-logging")]
                 #[cfg(feature = "debug-logging")]
                 #[cfg(feature = "debug-logging")]
-                eprintln!("DEBUG: Font '{}' not found in Font dictionary", font_name);
+                tracing::trace!(
+                    target: log_target::FONT,
+                    "Font '{}' not found in Font dictionary", font_name
+                );
                 return Ok(());
             }
         };
@@ -798,8 +1487,9 @@ impl<'a, D: Device> RenderingContext<'a, D> {
                 #[cfg(feature = "debug-logging")]
                 #[cfg(feature = "debug-logging")]
                 #[cfg(feature = "debug-logging")]
-                eprintln!(
-                    "DEBUG: Failed to parse font dictionary for '{}': {:?}",
+                tracing::trace!(
+                    target: log_target::FONT,
+                    "Failed to parse font dictionary for '{}': {:?}",
                     font_name, e
                 );
                 return Ok(()); // Don't fail rendering just because font parsing failed
@@ -809,11 +1499,39 @@ impl<'a, D: Device> RenderingContext<'a, D> {
         #[cfg(feature = "debug-logging")]
         #[cfg(feature = "debug-logging")]
         #[cfg(feature = "debug-logging")]
-        eprintln!(
-            "DEBUG: Font '{}' - BaseFont: {}, Type: {:?}",
+        tracing::trace!(
+            target: log_target::FONT,
+            "Font '{}' - BaseFont: {}, Type: {:?}",
             font_name, font_dict_info.base_font, font_dict_info.font_type
         );
 
+        if font_dict_info.font_type == FontType::Type0 {
+            match Font::new(font_obj.clone(), xref) {
+                Ok(font) => {
+                    // The font program lives on the descendant; Font copied its descriptor
+                    if let Some(data) = self.extract_font_data(&font.dict, xref)
+                        && let Err(e) = self.device.load_font_data(font_name, data, None)
+                    {
+                        tracing::warn!(
+                            target: log_target::FONT,
+                            "Failed to load composite font '{}': {:?}",
+                            font_name, e
+                        );
+                    }
+                    self.composite_fonts.insert(font_name.to_string(), font);
+                    return Ok(());
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        target: log_target::FONT,
+                        "Failed to parse composite font '{}': {:?}",
+                        font_name, e
+                    );
+                }
+            }
+        }
+        self.composite_fonts.remove(font_name);
+
         let width_metrics = Self::build_font_width_metrics(&font_dict_info, xref);
 
         // Try to get font data
@@ -822,8 +1540,9 @@ impl<'a, D: Device> RenderingContext<'a, D> {
         if let Some(data) = font_data {
             #[cfg(feature = "debug-logging")]
             #[cfg(feature = "debug-logging")]
-            eprintln!(
-                "DEBUG: Loading font '{}' with {} bytes of data",
+            tracing::trace!(
+                target: log_target::FONT,
+                "Loading font '{}' with {} bytes of data",
                 font_name,
                 data.len()
             );
@@ -845,8 +1564,9 @@ impl<'a, D: Device> RenderingContext<'a, D> {
                         .set_font_width_metrics(font_name, &width_metrics)?;
                     #[cfg(feature = "debug-logging")]
                     #[cfg(feature = "debug-logging")]
-                    eprintln!(
-                        "DEBUG: Successfully loaded font '{}' into device",
+                    tracing::trace!(
+                        target: log_target::FONT,
+                        "Successfully loaded font '{}' into device",
                         font_name
                     );
                     return Ok(());
@@ -856,8 +1576,9 @@ impl<'a, D: Device> RenderingContext<'a, D> {
                     let _ = &e;
                     #[cfg(feature = "debug-logging")]
                     #[cfg(feature = "debug-logging")]
-                    eprintln!(
-                        "DEBUG: Failed to load font data for '{}': {:?}, falling back to system fonts",
+                    tracing::trace!(
+                        target: log_target::FONT,
+                        "Failed to load font data for '{}': {:?}, falling back to system fonts",
                         font_name, e
                     );
                     // Fall through to try system fonts
@@ -866,15 +1587,17 @@ impl<'a, D: Device> RenderingContext<'a, D> {
         } else {
             #[cfg(feature = "debug-logging")]
             #[cfg(feature = "debug-logging")]
-            eprintln!(
-                "DEBUG: No font data available for '{}', attempting standard font",
+            tracing::trace!(
+                target: log_target::FONT,
+                "No font data available for '{}', attempting standard font",
                 font_name
             );
         }
 
         // Try to load as a standard font
         // Pass font_name as the cache key (e.g., "F0") and base_font for system font mapping
-        self.load_standard_font(font_name, &font_dict_info.base_font, &width_metrics)?;
+        let query = FontQuery::from_font_dict(&font_dict_info, xref);
+        self.load_substitute_font(font_name, &query, &width_metrics)?;
         Ok(())
     }
 
@@ -888,8 +1611,9 @@ impl<'a, D: Device> RenderingContext<'a, D> {
     ) -> Option<Vec<u8>> {
         #[cfg(feature = "debug-logging")]
         #[cfg(feature = "debug-logging")]
-        eprintln!(
-            "DEBUG: extract_font_data - font_descriptor exists: {}",
+        tracing::trace!(
+            target: log_target::FONT,
+            "extract_font_data - font_descriptor exists: {}",
             font_dict.font_descriptor.is_some()
         );
 
@@ -911,8 +1635,9 @@ impl<'a, D: Device> RenderingContext<'a, D> {
 
             #[cfg(feature = "debug-logging")]
             #[cfg(feature = "debug-logging")]
-            eprintln!(
-                "DEBUG: FontDescriptor keys: {:?}",
+            tracing::trace!(
+                target: log_target::FONT,
+                "FontDescriptor keys: {:?}",
                 descriptor.keys().collect::<Vec<_>>()
             );
 
@@ -929,7 +1654,10 @@ impl<'a, D: Device> RenderingContext<'a, D> {
                     // Type1 might actually be Type1C (CFF), so check both
                     #[cfg(feature = "debug-logging")]
                     #[cfg(feature = "debug-logging")]
-                    eprintln!("DEBUG: Type1 font, checking FontFile and FontFile3");
+                    tracing::trace!(
+                        target: log_target::FONT,
+                        "Type1 font, checking FontFile and FontFile3"
+                    );
                     vec!["FontFile", "FontFile3"]
                 }
                 crate::core::font::FontType::Type1C => vec!["FontFile3"],
@@ -940,356 +1668,101 @@ impl<'a, D: Device> RenderingContext<'a, D> {
 
             #[cfg(feature = "debug-logging")]
             #[cfg(feature = "debug-logging")]
-            eprintln!("DEBUG: Looking for font file keys: {:?}", font_file_keys);
+            tracing::trace!(
+                target: log_target::FONT,
+                "Looking for font file keys: {:?}", font_file_keys
+            );
 
             for key in font_file_keys {
                 #[cfg(feature = "debug-logging")]
                 #[cfg(feature = "debug-logging")]
                 #[cfg(feature = "debug-logging")]
-                eprintln!("DEBUG: Checking for key '{}'", key);
+                tracing::trace!(target: log_target::RENDER, "Checking for key '{}'", key);
                 if let Some(font_file_ref) = descriptor.get(key) {
                     #[cfg(feature = "debug-logging")]
                     #[cfg(feature = "debug-logging")]
-                    eprintln!("DEBUG: Found font file ref for '{}'", key);
+                    tracing::trace!(target: log_target::FONT, "Found font file ref for '{}'", key);
                     // Fetch the font file stream
                     let font_file_obj = xref.fetch_if_ref(font_file_ref).ok()?;
                     if let PDFObject::Stream { dict, data } = font_file_obj {
                         #[cfg(feature = "debug-logging")]
-                        eprintln!("DEBUG: Font file stream found, data length: {}", data.len());
+                        tracing::trace!(
+                            target: log_target::FONT,
+                            "Font file stream found, data length: {}", data.len()
+                        );
 
                         // Decode if needed
-                        if let Some(filter) = dict.get("Filter") {
-                            match filter {
-                                PDFObject::Name(filter_name) => {
-                                    if filter_name == "FlateDecode" || filter_name == "Fl" {
-                                        if let Ok(decoded) =
-                                            crate::core::decode::decode_flate(&data)
-                                        {
-                                            #[cfg(feature = "debug-logging")]
-                                            eprintln!(
-                                                "DEBUG: Successfully decoded {} bytes",
-                                                decoded.len()
-                                            );
-                                            return Some(decoded);
-                                        }
-                                    }
-                                }
-                                PDFObject::Array(filters) => {
-                                    // Try first filter
-                                    if !filters.is_empty() {
-                                        if let Some(PDFObject::Name(filter_name)) =
-                                            filters.get(0).map(|f| f.as_ref())
-                                        {
-                                            if filter_name == "FlateDecode" || filter_name == "Fl" {
-                                                if let Ok(decoded) =
-                                                    crate::core::decode::decode_flate(&data)
-                                                {
-                                                    #[cfg(feature = "debug-logging")]
-                                                    eprintln!(
-                                                        "DEBUG: Successfully decoded {} bytes",
-                                                        decoded.len()
-                                                    );
-                                                    return Some(decoded);
-                                                }
-                                            }
-                                        }
-                                    }
-                                }
-                                _ => {}
+                        if dict.contains_key("Filter") {
+                            if let Ok(decoded) =
+                                crate::core::decode::decode_stream_data(&dict, &data)
+                            {
+                                #[cfg(feature = "debug-logging")]
+                                tracing::trace!(
+                                    target: log_target::RENDER,
+                                    "Successfully decoded {} bytes", decoded.len()
+                                );
+                                return Some(decoded);
                             }
                         }
 
                         // No filter or decode failed, return raw data
                         #[cfg(feature = "debug-logging")]
-                        eprintln!("DEBUG: Returning raw font data: {} bytes", data.len());
+                        tracing::trace!(
+                            target: log_target::FONT,
+                            "Returning raw font data: {} bytes", data.len()
+                        );
                         return Some(data.clone());
                     } else {
                         #[cfg(feature = "debug-logging")]
-                        eprintln!("DEBUG: Font file ref is not a stream");
+                        tracing::trace!(target: log_target::FONT, "Font file ref is not a stream");
                     }
                 } else {
                     #[cfg(feature = "debug-logging")]
                     #[cfg(feature = "debug-logging")]
-                    eprintln!("DEBUG: Key '{}' not found in descriptor", key);
+                    tracing::trace!(
+                        target: log_target::RENDER,
+                        "Key '{}' not found in descriptor", key
+                    );
                 }
             }
         }
 
         #[cfg(feature = "debug-logging")]
         #[cfg(feature = "debug-logging")]
-        eprintln!("DEBUG: No font data found in extract_font_data");
+        tracing::trace!(target: log_target::FONT, "No font data found in extract_font_data");
         None
     }
 
-    /// Load a standard PDF font by name (e.g., "Helvetica", "Times-Roman").
+    /// Load a substitute for a font without a usable embedded program.
     ///
-    /// This attempts to use system fonts as a fallback for standard PDF fonts.
-    /// It also handles subset prefixes (e.g., "AKXEFC+DogmaBold" -> "DogmaBold").
+    /// The font resolver picks the font data from the query's name, class and
+    /// style; nothing is loaded if it has no substitute.
     ///
     /// # Arguments
     /// * `cache_key` - The PDF font resource name to use as cache key (e.g., "F0")
-    /// * `base_font` - The BaseFont name from the PDF for system font mapping
-    fn load_standard_font(
+    /// * `query` - Description of the font to substitute
+    fn load_substitute_font(
         &mut self,
         cache_key: &str,
-        base_font: &str,
+        query: &FontQuery,
         width_metrics: &FontWidthMetrics,
     ) -> PDFResult<()> {
-        #[cfg(feature = "debug-logging")]
-        #[cfg(feature = "debug-logging")]
-        eprintln!(
-            "DEBUG: Attempting to load system font for cache_key='{}', base_font='{}'",
-            cache_key, base_font
-        );
-
-        // Strip subset prefix if present (e.g., "AKXEFC+DogmaBold" -> "DogmaBold")
-        let clean_font = if let Some(idx) = base_font.find('+') {
-            &base_font[idx + 1..]
-        } else {
-            base_font
+        let Some(data) = self.font_resolver.resolve(query) else {
+            #[cfg(feature = "debug-logging")]
+            tracing::trace!(
+                target: log_target::FONT,
+                "No substitute font for cache_key='{}', base_font='{}'",
+                cache_key, query.base_font
+            );
+            return Ok(());
         };
 
-        #[cfg(feature = "debug-logging")]
-        #[cfg(feature = "debug-logging")]
-        eprintln!("DEBUG: Clean font name: '{}'", clean_font);
-
-        // Map PDF font names to system font names
-        let system_font = match clean_font {
-            // Standard PDF fonts
-            "Times-Roman" | "TimesNewRoman" | "Times" => "Times New Roman",
-            "Times-Bold" => "Times New Roman Bold",
-            "Times-Italic" => "Times New Roman Italic",
-            "Times-BoldItalic" => "Times New Roman Bold Italic",
-            "Helvetica" => "Arial",
-            "Helvetica-Bold" => "Arial Bold",
-            "Helvetica-Oblique" | "Helvetica-Italic" => "Arial Italic",
-            "Helvetica-BoldOblique" | "Helvetica-BoldItalic" => "Arial Bold Italic",
-            "Courier" | "CourierNew" => "Courier New",
-            "Courier-Bold" => "Courier New Bold",
-            "Courier-Oblique" | "Courier-Italic" => "Courier New Italic",
-            "Courier-BoldOblique" | "Courier-BoldItalic" => "Courier New Bold Italic",
-
-            // Nimbus fonts (PostScript clones of standard fonts)
-            s if s.starts_with("NimbusRomNo9L") => "Times New Roman",
-            s if s.starts_with("NimbusSans") => "Arial",
-            s if s.starts_with("NimbusMono") => "Courier New",
-
-            // Liberation fonts (open source clones)
-            s if s.starts_with("LiberationSerif") => "Times New Roman",
-            s if s.starts_with("LiberationSans") => "Arial",
-            s if s.starts_with("LiberationMono") => "Courier New",
-
-            // TeX Gyre fonts (modern replacements)
-            s if s.starts_with("TeXGyreTermes") => "Times New Roman",
-            s if s.starts_with("TeXGyreHeros") => "Arial",
-            s if s.starts_with("TeXGyreCursor") => "Courier New",
-
-            // URW fonts (more PostScript clones)
-            s if s.starts_with("URWPalladio") => "Times New Roman",
-            s if s.starts_with("URWGothic") => "Arial",
-            s if s.starts_with("URWCourier") => "Courier New",
-
-            // Computer Modern fonts (TeX/LaTeX)
-            s if s.contains("ComputerModern")
-                || s.contains("Computer Modern")
-                || s.contains("CM")
-                || s.starts_with("ComputerModern")
-                || s.starts_with("CMR")
-                || s.starts_with("CMM")
-                || s.starts_with("CMS")
-                || s.starts_with("CMT")
-                || s.starts_with("CMEX")
-                || s.starts_with("CMB") =>
-            {
-                "Times New Roman"
-            }
-
-            // Latin Modern fonts (modern Computer Modern)
-            s if s.starts_with("LatinModern")
-                || s.starts_with("LMRoman")
-                || s.starts_with("LMSans")
-                || s.starts_with("LMTypewriter") =>
-            {
-                match clean_font {
-                    s if s.contains("Sans") => "Arial",
-                    s if s.contains("Mono") || s.contains("Typewriter") => "Courier New",
-                    _ => "Times New Roman",
-                }
-            }
-
-            // Any font with "Roman" or "Serif" in name -> Times
-            s if s.contains("Roman") || s.contains("Serif") || s.contains("Book") => {
-                "Times New Roman"
-            }
-
-            // Any font with "Sans" in name -> Arial
-            s if s.contains("Sans") => "Arial",
-
-            // Any font with "Mono" or "Code" in name -> Courier
-            s if s.contains("Mono") || s.contains("Code") || s.contains("Typewriter") => {
-                "Courier New"
-            }
-
-            _ => {
-                // Last resort: try to guess font family from name
-                #[cfg(feature = "debug-logging")]
-                eprintln!(
-                    "DEBUG: Unknown font '{}', attempting smart fallback",
-                    clean_font
-                );
-
-                // Default to Times for serif-like fonts, Arial for sans-serif
-                let fallback = if clean_font.contains("Bold") || clean_font.contains("Light") {
-                    "Times New Roman" // Serif default
-                } else {
-                    "Times New Roman" // Safe default
-                };
-
-                #[cfg(feature = "debug-logging")]
-                eprintln!(
-                    "DEBUG: Using fallback '{}' for font '{}' (cache_key='{}')",
-                    fallback, clean_font, cache_key
-                );
-
-                // Continue with the fallback instead of returning early
-                fallback
-            }
-        };
-
-        // Try to load the system font
-        #[cfg(target_os = "linux")]
-        {
-            // Map system font names to actual Linux font files
-            // Try multiple common locations and font families
-            let font_files: Vec<&str> = match system_font.as_ref() {
-                "Times New Roman" => vec![
-                    // Liberation fonts (RHEL/CentOS)
-                    "LiberationSerif-Regular.ttf",
-                    "LiberationSerif.ttf",
-                    // DejaVu fonts (most distros)
-                    "DejaVuSerif.ttf",
-                    // Free fonts
-                    "FreeSerif.ttf",
-                    // Noto fonts (modern distros)
-                    "NotoSerif-Regular.ttf",
-                    // URW fonts (TeX Live)
-                    "NimbusRomNo9L-Regular.ttf",
-                    "NimbusRoman-Regular.ttf",
-                ],
-                "Times New Roman Bold" => vec![
-                    "LiberationSerif-Bold.ttf",
-                    "DejaVuSerif-Bold.ttf",
-                    "FreeSerifBold.ttf",
-                    "NotoSerif-Bold.ttf",
-                ],
-                "Times New Roman Italic" => vec![
-                    "LiberationSerif-Italic.ttf",
-                    "DejaVuSerif-Italic.ttf",
-                    "FreeSerifItalic.ttf",
-                    "NotoSerif-Italic.ttf",
-                ],
-                "Times New Roman Bold Italic" => vec![
-                    "LiberationSerif-BoldItalic.ttf",
-                    "DejaVuSerif-BoldItalic.ttf",
-                    "FreeSerifBoldItalic.ttf",
-                    "NotoSerif-BoldItalic.ttf",
-                ],
-                "Arial" => vec![
-                    "LiberationSans-Regular.ttf",
-                    "LiberationSans.ttf",
-                    "DejaVuSans.ttf",
-                    "FreeSans.ttf",
-                    "NotoSans-Regular.ttf",
-                    "NimbusSans-Regular.ttf",
-                ],
-                "Arial Bold" => vec![
-                    "LiberationSans-Bold.ttf",
-                    "DejaVuSans-Bold.ttf",
-                    "FreeSansBold.ttf",
-                    "NotoSans-Bold.ttf",
-                ],
-                "Arial Italic" => vec![
-                    "LiberationSans-Italic.ttf",
-                    "DejaVuSans-Oblique.ttf",
-                    "FreeSansOblique.ttf",
-                    "NotoSans-Italic.ttf",
-                ],
-                "Arial Bold Italic" => vec![
-                    "LiberationSans-BoldItalic.ttf",
-                    "DejaVuSans-BoldOblique.ttf",
-                    "FreeSansBoldOblique.ttf",
-                    "NotoSans-BoldItalic.ttf",
-                ],
-                "Courier New" => vec![
-                    "LiberationMono-Regular.ttf",
-                    "LiberationMono.ttf",
-                    "DejaVuSansMono.ttf",
-                    "FreeMono.ttf",
-                    "NotoMono-Regular.ttf",
-                    "NimbusMono-Regular.ttf",
-                    "Courier10PitchBT-Roman.ttf",
-                ],
-                "Courier New Bold" => vec![
-                    "LiberationMono-Bold.ttf",
-                    "DejaVuSansMono-Bold.ttf",
-                    "FreeMonoBold.ttf",
-                    "NotoMono-Bold.ttf",
-                ],
-                "Courier New Italic" => vec![
-                    "LiberationMono-Italic.ttf",
-                    "DejaVuSansMono-Oblique.ttf",
-                    "FreeMonoOblique.ttf",
-                ],
-                "Courier New Bold Italic" => vec![
-                    "LiberationMono-BoldItalic.ttf",
-                    "DejaVuSansMono-BoldOblique.ttf",
-                    "FreeMonoBoldOblique.ttf",
-                ],
-                _ => vec![], // No mapping
-            };
-
-            // Try each font file in multiple directories
-            let font_dirs = vec![
-                "/usr/share/fonts/truetype",
-                "/usr/share/fonts/truetype/dejavu",
-                "/usr/share/fonts/truetype/liberation",
-                "/usr/share/fonts/truetype/freefont",
-                "/usr/share/fonts/truetype/noto",
-                "/usr/share/fonts/opentype/noto",
-                "/usr/share/fonts/truetype/lmodern",
-                "/usr/share/fonts/truetype/cmu", // Computer Modern Unicode
-                "/usr/share/fonts/truetype/cm-unicode", // Alternative CM path
-                "/usr/share/fonts/opentype/cm-unicode",
-                "/usr/share/fonts/truetype/computer-modern",
-                "/usr/share/fonts/opentype/public-lm",
-                "/usr/share/fonts/TTF",
-                "/usr/share/fonts",
-                "/usr/local/share/fonts",
-            ];
-
-            for font_file in font_files {
-                for dir in &font_dirs {
-                    let path = format!("{}/{}", dir, font_file);
-                    if let Ok(data) = std::fs::read(&path) {
-                        #[cfg(feature = "debug-logging")]
-                        eprintln!("DEBUG: Loaded font '{}' from {}", cache_key, path);
-                        // System fonts don't have custom encodings
-                        self.device.load_font_data(cache_key, data, None)?;
-                        self.device.set_font_width_metrics(cache_key, width_metrics)?;
-                        return Ok(());
-                    }
-                }
-            }
-        }
-
-        #[cfg(feature = "debug-logging")]
-        #[cfg(feature = "debug-logging")]
-        eprintln!(
-            "DEBUG: Could not find system font for cache_key='{}', base_font='{}'",
-            cache_key, base_font
-        );
-        Ok(())
-    }
+        // Substitutes don't have custom encodings
+        self.device.load_font_data(cache_key, data, None)?;
+        self.device
+            .set_font_width_metrics(cache_key, width_metrics)?;
+        Ok(())
+    }
 
     fn set_char_spacing(&mut self, args: &[crate::core::parser::PDFObject]) -> PDFResult<()> {
         if args.len() < 1 {
@@ -1386,7 +1859,7 @@ impl<'a, D: Device> RenderingContext<'a, D> {
         }
 
         let gray = extract_number(args, 0)?;
-        self.current_state_mut().stroke_color = Color::Gray(gray);
+        self.set_device_color(false, Color::Gray(gray), ColorSpace::DeviceGray);
         Ok(())
     }
 
@@ -1399,8 +1872,8 @@ impl<'a, D: Device> RenderingContext<'a, D> {
 
         let gray = extract_number(args, 0)?;
         #[cfg(feature = "debug-logging")]
-        eprintln!("DEBUG: set_fill_gray: gray={:.3}", gray);
-        self.current_state_mut().fill_color = Color::Gray(gray);
+        tracing::trace!(target: log_target::RENDER, "set_fill_gray: gray={:.3}", gray);
+        self.set_device_color(true, Color::Gray(gray), ColorSpace::DeviceGray);
         Ok(())
     }
 
@@ -1414,7 +1887,7 @@ impl<'a, D: Device> RenderingContext<'a, D> {
         let r = extract_number(args, 0)?;
         let g = extract_number(args, 1)?;
         let b = extract_number(args, 2)?;
-        self.current_state_mut().stroke_color = Color::RGB(r, g, b);
+        self.set_device_color(false, Color::RGB(r, g, b), ColorSpace::DeviceRGB);
         Ok(())
     }
 
@@ -1430,11 +1903,12 @@ impl<'a, D: Device> RenderingContext<'a, D> {
         let b = extract_number(args, 2)?;
         #[cfg(feature = "debug-logging")]
         #[cfg(feature = "debug-logging")]
-        eprintln!(
-            "DEBUG: set_fill_rgb: r={}, g={}, b={}, args={:?}",
+        tracing::trace!(
+            target: log_target::RENDER,
+            "set_fill_rgb: r={}, g={}, b={}, args={:?}",
             r, g, b, args
         );
-        self.current_state_mut().fill_color = Color::RGB(r, g, b);
+        self.set_device_color(true, Color::RGB(r, g, b), ColorSpace::DeviceRGB);
         Ok(())
     }
 
@@ -1449,7 +1923,7 @@ impl<'a, D: Device> RenderingContext<'a, D> {
         let m = extract_number(args, 1)?;
         let y = extract_number(args, 2)?;
         let k = extract_number(args, 3)?;
-        self.current_state_mut().stroke_color = Color::CMYK(c, m, y, k);
+        self.set_device_color(false, Color::CMYK(c, m, y, k), ColorSpace::DeviceCMYK);
         Ok(())
     }
 
@@ -1464,10 +1938,410 @@ impl<'a, D: Device> RenderingContext<'a, D> {
         let m = extract_number(args, 1)?;
         let y = extract_number(args, 2)?;
         let k = extract_number(args, 3)?;
-        self.current_state_mut().fill_color = Color::CMYK(c, m, y, k);
+        self.set_device_color(true, Color::CMYK(c, m, y, k), ColorSpace::DeviceCMYK);
+        Ok(())
+    }
+
+    /// Sets a color given by G/g, RG/rg or K/k, which also select the
+    /// matching device color space.
+    fn set_device_color(&mut self, fill: bool, color: Color, space: ColorSpace) {
+        let state = self.current_state_mut();
+        if fill {
+            state.fill_color = color;
+            state.fill_color_space = Some(space);
+            state.fill_pattern = None;
+        } else {
+            state.stroke_color = color;
+            state.stroke_color_space = Some(space);
+            state.stroke_pattern = None;
+        }
+    }
+
+    fn set_color_space(
+        &mut self,
+        args: &[crate::core::parser::PDFObject],
+        fill: bool,
+    ) -> PDFResult<()> {
+        let name = match args.first() {
+            Some(PDFObject::Name(name)) => name,
+            _ => {
+                return Err(PDFError::content_stream_error(format!(
+                    "{} operator requires a name argument",
+                    if fill { "cs" } else { "CS" }
+                )));
+            }
+        };
+
+        let color_space = match name.as_str() {
+            "DeviceGray" | "DeviceRGB" | "DeviceCMYK" | "Pattern" => PDFObject::Name(name.clone()),
+            _ => self
+                .lookup_resource("ColorSpace", name)?
+                .unwrap_or_else(|| PDFObject::Name(name.clone())),
+        };
+        // [/Pattern base] gives the color space of uncolored tiling patterns
+        let parsed = match self.xref.as_deref_mut() {
+            Some(xref) => match ColorSpace::parse(&color_space, xref) {
+                Ok(parsed) => Some(parsed),
+                Err(e) => {
+                    tracing::warn!(
+                        target: log_target::RENDER,
+                        "Ignoring color space /{}: {}", name, e
+                    );
+                    None
+                }
+            },
+            None => color_space.as_name().and_then(ColorSpace::from_name),
+        };
+
+        // Setting a color space also resets the color to its initial value,
+        // which is black in every space except Separation/DeviceN (full tint)
+        let initial = match &parsed {
+            Some(ColorSpace::Pattern { .. }) | None => Color::black(),
+            Some(space) => color_from_components(space, &space.initial_color()),
+        };
+        let state = self.current_state_mut();
+        if fill {
+            state.fill_color = initial;
+            state.fill_color_space = parsed;
+            state.fill_pattern = None;
+        } else {
+            state.stroke_color = initial;
+            state.stroke_color_space = parsed;
+            state.stroke_pattern = None;
+        }
+        Ok(())
+    }
+
+    fn set_color(&mut self, args: &[crate::core::parser::PDFObject], fill: bool) -> PDFResult<()> {
+        let state = self.current_state();
+        let color_space = if fill {
+            state.fill_color_space.clone()
+        } else {
+            state.stroke_color_space.clone()
+        };
+
+        if let Some(ColorSpace::Pattern { base }) = &color_space {
+            let Some((PDFObject::Name(name), components)) = args.split_last() else {
+                return Ok(());
+            };
+            let mut pattern = self.load_pattern(name)?;
+            // Uncolored tiling patterns are painted in the color given before the name
+            if let Some(Paint::Tile(tile)) = pattern.as_mut()
+                && tile.color.is_some()
+                && let Some(base) = base
+                && let Some(values) = components
+                    .iter()
+                    .map(|arg| arg.as_number())
+                    .collect::<Option<Vec<f64>>>()
+                    .filter(|values| values.len() == base.components())
+            {
+                tile.color = Some(color_from_components(base, &values));
+            }
+            let state = self.current_state_mut();
+            if fill {
+                state.fill_pattern = pattern;
+            } else {
+                state.stroke_pattern = pattern;
+            }
+            return Ok(());
+        }
+
+        // Colors in color spaces that failed to parse are ignored
+        let Some(color_space) = color_space else {
+            return Ok(());
+        };
+        let values = args
+            .iter()
+            .map(|arg| arg.as_number())
+            .collect::<Option<Vec<f64>>>()
+            .filter(|values| values.len() == color_space.components());
+        let Some(values) = values else {
+            return Err(PDFError::content_stream_error(format!(
+                "{} operator requires {} numeric arguments",
+                if fill { "sc" } else { "SC" },
+                color_space.components()
+            )));
+        };
+
+        let color = color_from_components(&color_space, &values);
+        if fill {
+            self.current_state_mut().fill_color = color;
+        } else {
+            self.current_state_mut().stroke_color = color;
+        }
         Ok(())
     }
 
+    /// Loads a pattern from the /Pattern resources as a paint in the page's
+    /// default space.
+    ///
+    /// # Returns
+    /// The paint, or `None` if the pattern is missing or not supported
+    fn load_pattern(&mut self, name: &str) -> PDFResult<Option<Paint>> {
+        if let Some(tile) = self.tiles.get(name) {
+            return Ok(Some(Paint::Tile(Box::new(*tile))));
+        }
+        let Some(entry) = self.resource_entry("Pattern", name)? else {
+            return Ok(None);
+        };
+        let pattern_ref = match entry {
+            PDFObject::Ref(r) => Some(r),
+            _ => None,
+        };
+        let pattern = match self.xref.as_deref_mut() {
+            Some(xref) => xref.fetch_if_ref(&entry)?,
+            None => entry,
+        };
+
+        match &pattern {
+            PDFObject::Stream { dict, data } if matches!(dict.get("PatternType"), Some(PDFObject::Number(n)) if *n == 1.0) =>
+            {
+                return self.load_tiling_pattern(name, pattern_ref, dict, data);
+            }
+            PDFObject::Dictionary(dict) if matches!(dict.get("PatternType"), Some(PDFObject::Number(n)) if *n == 2.0) =>
+                {}
+            _ => {
+                tracing::warn!(
+                    target: log_target::RENDER,
+                    "Pattern {} has an invalid /PatternType, skipping",
+                    name
+                );
+                return Ok(None);
+            }
+        }
+        let PDFObject::Dictionary(dict) = &pattern else {
+            return Ok(None);
+        };
+        let matrix = matrix_entry(dict.get("Matrix"));
+        let (Some(shading), Some(xref)) = (dict.get("Shading"), self.xref.as_deref_mut()) else {
+            return Ok(None);
+        };
+        match Shading::parse(shading, xref) {
+            Ok(shading) => {
+                let gradient = Gradient::from_shading(&shading, matrix);
+                Ok(Some(Paint::Gradient(Box::new(gradient))))
+            }
+            Err(e) => {
+                tracing::warn!(target: log_target::RENDER, "Skipping pattern {}: {}", name, e);
+                Ok(None)
+            }
+        }
+    }
+
+    /// Renders the cell of a tiling pattern (PatternType 1) through the
+    /// device, which keeps it for [`Paint::Tile`] fills.
+    ///
+    /// The cell is rendered once per pattern name and reused.
+    fn load_tiling_pattern(
+        &mut self,
+        name: &str,
+        pattern_ref: Option<Ref>,
+        dict: &HashMap<String, PDFObject>,
+        data: &[u8],
+    ) -> PDFResult<Option<Paint>> {
+        // Pattern cells are content streams drawing each other, like forms
+        let max_depth = self.limits.max_form_depth;
+        if self.nesting.patterns >= max_depth {
+            return Err(PDFError::limit_exceeded(
+                format!("tiling pattern nesting depth at {}", name),
+                max_depth,
+            ));
+        }
+        let Some(mut nesting) = self.nesting.enter(pattern_ref) else {
+            tracing::warn!(
+                target: log_target::RENDER,
+                "Tiling pattern {} is used by its own cell, skipping",
+                name
+            );
+            return Ok(None);
+        };
+        nesting.patterns += 1;
+
+        let number = |key: &str| dict.get(key).and_then(|v| v.as_number());
+        let bbox = match dict.get("BBox").and_then(|b| b.as_array()) {
+            Some([x0, y0, x1, y1]) => {
+                let n = |v: &PDFObject| v.as_number().unwrap_or(0.0);
+                let (x0, y0, x1, y1) = (n(x0), n(y0), n(x1), n(y1));
+                [x0.min(x1), y0.min(y1), x0.max(x1), y0.max(y1)]
+            }
+            _ => {
+                tracing::warn!(
+                    target: log_target::RENDER,
+                    "Tiling pattern {} has no valid /BBox, skipping",
+                    name
+                );
+                return Ok(None);
+            }
+        };
+        let (Some(x_step), Some(y_step)) = (number("XStep"), number("YStep")) else {
+            tracing::warn!(
+                target: log_target::RENDER,
+                "Tiling pattern {} has no /XStep or /YStep, skipping",
+                name
+            );
+            return Ok(None);
+        };
+        if x_step == 0.0 || y_step == 0.0 {
+            return Ok(None);
+        }
+        let cell = [
+            bbox[0],
+            bbox[1],
+            bbox[0] + x_step.abs(),
+            bbox[1] + y_step.abs(),
+        ];
+        let matrix = matrix_entry(dict.get("Matrix"));
+        let uncolored = number("PaintType") == Some(2.0);
+
+        let Some(inverse) = invert_matrix(&self.current_state().ctm) else {
+            return Ok(None);
+        };
+        let Some(xref) = self.xref.as_deref_mut() else {
+            return Ok(None);
+        };
+        let content = crate::core::decode::decode_stream_data_limited(dict, data, xref.limits())?;
+        let resources = match dict.get("Resources") {
+            Some(resources) => Some(xref.fetch_if_ref(resources)?),
+            None => self.resources.cloned(),
+        };
+
+        // Reference: pdf.js/src/display/pattern.js - TilingPattern.createPatternCanvas
+        if !self
+            .device
+            .begin_tile(&cell, &multiply_matrix(&matrix, &inverse))?
+        {
+            tracing::warn!(
+                target: log_target::RENDER,
+                "Device does not support tiling patterns, skipping {}",
+                name
+            );
+            return Ok(None);
+        }
+        self.device.save_state();
+        self.device.begin_path();
+        self.device
+            .rect(bbox[0], bbox[1], bbox[2] - bbox[0], bbox[3] - bbox[1]);
+        self.device.clip_path(FillRule::NonZero)?;
+
+        let result = Self::render_group_content(
+            &mut *self.device,
+            self.font_resolver,
+            xref,
+            resources.as_ref(),
+            content,
+            GraphicsState {
+                ctm: matrix,
+                ..GraphicsState::default()
+            },
+            nesting,
+        );
+
+        self.device.restore_state();
+        let id = self.device.end_tile()?;
+        result?;
+
+        let tile = Tile {
+            id,
+            cell,
+            matrix,
+            color: uncolored.then_some(Color::black()),
+        };
+        self.tiles.insert(name.to_string(), tile);
+        Ok(Some(Paint::Tile(Box::new(tile))))
+    }
+
+    // === Shading Operator ===
+
+    fn shading_fill(&mut self, args: &[crate::core::parser::PDFObject]) -> PDFResult<()> {
+        let name = match args.first() {
+            Some(PDFObject::Name(name)) => name,
+            _ => {
+                return Err(PDFError::content_stream_error(
+                    "sh operator requires a name argument".to_string(),
+                ));
+            }
+        };
+
+        let Some(shading) = self.lookup_resource("Shading", name)? else {
+            return Ok(());
+        };
+        let Some(xref) = self.xref.as_deref_mut() else {
+            return Ok(());
+        };
+        let shading = match Shading::parse(&shading, xref) {
+            Ok(shading) => shading,
+            Err(e) => {
+                tracing::warn!(target: log_target::RENDER, "Skipping shading {}: {}", name, e);
+                return Ok(());
+            }
+        };
+
+        // sh paints in current user space and ignores /Background
+        let mut gradient = Gradient::from_shading(&shading, [1.0, 0.0, 0.0, 1.0, 0.0, 0.0]);
+        gradient.background = None;
+
+        // Paint the shading's bounding box, or otherwise an area well beyond
+        // the page; the current clip limits what is visible
+        let corners = match shading.bbox {
+            Some([x0, y0, x1, y1]) => [(x0, y0), (x1, y0), (x1, y1), (x0, y1)],
+            None => {
+                let Some(inverse) = invert_matrix(&self.current_state().ctm) else {
+                    return Ok(());
+                };
+                let [a, b, c, d, e, f] = inverse;
+                let extent = 1.0e5;
+                [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)].map(|(x, y)| {
+                    let (x, y) = (x * extent, y * extent);
+                    (a * x + c * y + e, b * x + d * y + f)
+                })
+            }
+        };
+        self.device.begin_path();
+        self.device.move_to(corners[0].0, corners[0].1);
+        for &(x, y) in &corners[1..] {
+            self.device.line_to(x, y);
+        }
+        self.device.close_path();
+
+        let stroke_props = self.current_state().stroke_props.clone();
+        self.device.draw_path(
+            PathDrawMode::Fill(FillRule::NonZero),
+            &Paint::Gradient(Box::new(gradient)),
+            &stroke_props,
+        )?;
+        self.record_overprint(OverprintPaint::Fill, shading.bbox);
+        Ok(())
+    }
+
+    /// Looks up a named entry in a resource category (e.g. /Pattern),
+    /// resolving references.
+    fn lookup_resource(&mut self, category: &str, name: &str) -> PDFResult<Option<PDFObject>> {
+        let (Some(entry), Some(xref)) = (
+            self.resource_entry(category, name)?,
+            self.xref.as_deref_mut(),
+        ) else {
+            return Ok(None);
+        };
+        Ok(Some(xref.fetch_if_ref(&entry)?))
+    }
+
+    /// Looks up a named entry in a resource category as written, which may
+    /// be a reference.
+    fn resource_entry(&mut self, category: &str, name: &str) -> PDFResult<Option<PDFObject>> {
+        let (Some(xref), Some(PDFObject::Dictionary(resources))) =
+            (self.xref.as_deref_mut(), self.resources)
+        else {
+            return Ok(None);
+        };
+        let Some(entries) = resources.get(category) else {
+            return Ok(None);
+        };
+        match xref.fetch_if_ref(entries)? {
+            PDFObject::Dictionary(dict) => Ok(dict.get(name).cloned()),
+            _ => Ok(None),
+        }
+    }
+
     // === Line Property Operators ===
 
     fn set_line_width(&mut self, args: &[crate::core::parser::PDFObject]) -> PDFResult<()> {
@@ -1631,179 +2505,283 @@ impl<'a, D: Device> RenderingContext<'a, D> {
 
         let xobject = xref.fetch_if_ref(xobject_ref)?;
 
-        // Check if it's an image XObject
-        let xobject_dict = match &xobject {
-            PDFObject::Stream { dict, .. } => dict,
+        // Check if it's an image XObject (inferred from its keys in lenient mode)
+        let (xobject_dict, xobject_data) = match &xobject {
+            PDFObject::Stream { dict, data } => (dict, data),
             _ => return Ok(()),
         };
 
-        let subtype = match xobject_dict.get("Subtype") {
-            Some(PDFObject::Name(name)) => name,
-            _ => return Ok(()),
+        match ImageDetection::classify(xobject_dict, xobject_data, xref.is_lenient()) {
+            ImageDetection::Declared => {}
+            ImageDetection::Inferred(reason) => {
+                xref.record_diagnostic(
+                    DiagnosticKind::ImageSubtype,
+                    format!("Image '{}': {}", xobject_name, reason),
+                );
+            }
+            ImageDetection::NotImage => {
+                return match xobject_dict.get("Subtype") {
+                    Some(PDFObject::Name(subtype)) if subtype == "Form" => {
+                        let form_ref = match xobject_ref {
+                            PDFObject::Ref(r) => Some(*r),
+                            _ => None,
+                        };
+                        self.paint_form_xobject(xobject_name, form_ref, xobject_dict, xobject_data)
+                    }
+                    _ => Ok(()), // PostScript XObjects are not painted
+                };
+            }
+        }
+
+        self.draw_image_stream(xobject_name, xobject_dict, xobject_data)
+    }
+
+    /// Paints a form XObject (PDF spec 8.10) in the current graphics state.
+    ///
+    /// Forms with a transparency /Group are painted as a group and composited
+    /// as one object with the current blend mode and soft mask. A form that
+    /// is painted, directly or not, by its own content is skipped.
+    ///
+    /// # Arguments
+    /// * `xobject_name` - The resource name of the form, for messages
+    /// * `form_ref` - The form's object, if it is an indirect object
+    /// * `form_dict` - The form's stream dictionary
+    /// * `form_data` - The form's content stream, still encoded
+    pub(crate) fn paint_form_xobject(
+        &mut self,
+        xobject_name: &str,
+        form_ref: Option<Ref>,
+        form_dict: &HashMap<String, PDFObject>,
+        form_data: &[u8],
+    ) -> PDFResult<()> {
+        let limits = self.limits;
+        let Some(mut nesting) = self.nesting.enter(form_ref) else {
+            tracing::warn!(
+                target: log_target::RENDER,
+                "Form XObject {} paints itself, skipping",
+                xobject_name
+            );
+            return Ok(());
+        };
+        if nesting.forms >= limits.max_form_depth {
+            return Err(PDFError::limit_exceeded(
+                format!("Form XObject nesting depth at {}", xobject_name),
+                limits.max_form_depth,
+            ));
+        }
+        nesting.forms += 1;
+
+        let matrix = matrix_entry(form_dict.get("Matrix"));
+        let bbox = match form_dict.get("BBox").and_then(|b| b.as_array()) {
+            Some([x0, y0, x1, y1]) => {
+                let n = |v: &PDFObject| v.as_number().unwrap_or(0.0);
+                let (x0, y0, x1, y1) = (n(x0), n(y0), n(x1), n(y1));
+                Some([x0.min(x1), y0.min(y1), x0.max(x1), y0.max(y1)])
+            }
+            _ => None,
+        };
+        let mut state = self.current_state().clone();
+        let Some(xref) = self.xref.as_deref_mut() else {
+            return Ok(());
+        };
+        let content =
+            crate::core::decode::decode_stream_data_limited(form_dict, form_data, &limits)?;
+        let resources = match form_dict.get("Resources") {
+            Some(resources) => Some(xref.fetch_if_ref(resources)?),
+            None => self.resources.cloned(),
         };
+        let group = match form_dict.get("Group") {
+            Some(group) => Some(xref.fetch_if_ref(group)?),
+            None => None,
+        };
+        let transparency_group = match &group {
+            Some(PDFObject::Dictionary(group))
+                if group.get("S").and_then(|s| s.as_name()) == Some("Transparency") =>
+            {
+                Some(group)
+            }
+            _ => None,
+        };
+
+        // Reference: pdf.js/src/display/canvas.js - paintFormXObjectBegin / beginGroup
+        state.concat_matrix(&matrix);
+        self.device.save_state();
+        self.device.concat_matrix(&matrix);
+        if let Some([x0, y0, x1, y1]) = bbox {
+            self.device.begin_path();
+            self.device.rect(x0, y0, x1 - x0, y1 - y0);
+            self.device.clip_path(FillRule::NonZero)?;
+        }
+        let grouped = match transparency_group {
+            Some(group) => {
+                let flag = |key: &str| group.get(key).and_then(|v| v.as_boolean());
+                self.device
+                    .begin_group(flag("I").unwrap_or(false), flag("K").unwrap_or(false))?
+            }
+            None => false,
+        };
+        if grouped {
+            state.blend_mode = BlendMode::Normal;
+        }
+
+        let result = Self::render_group_content(
+            &mut *self.device,
+            self.font_resolver,
+            xref,
+            resources.as_ref(),
+            content,
+            state,
+            nesting,
+        );
+
+        if grouped {
+            self.device.end_group()?;
+        }
+        self.device.restore_state();
+        result
+    }
 
-        if subtype != "Image" {
-            return Ok(()); // Only support images for now
+    /// Paints an inline image (BI ... ID ... EI).
+    ///
+    /// The parser turns the inline image into a stream object with the
+    /// abbreviated keys expanded, which is passed as the operand of EI.
+    fn paint_inline_image(&mut self, args: &[crate::core::parser::PDFObject]) -> PDFResult<()> {
+        match args.first() {
+            Some(PDFObject::Stream { dict, data }) => self.draw_image_stream("inline", dict, data),
+            _ => Err(PDFError::content_stream_error(
+                "EI operator requires the inline image".to_string(),
+            )),
         }
+    }
 
+    /// Decodes an image stream and draws it into the unit square of the CTM.
+    fn draw_image_stream(
+        &mut self,
+        xobject_name: &str,
+        xobject_dict: &std::collections::HashMap<String, PDFObject>,
+        image_data: &[u8],
+    ) -> PDFResult<()> {
         // Extract image properties
-        let width = match xobject_dict.get("Width") {
+        let width = match xobject_dict.get("Width").or_else(|| xobject_dict.get("W")) {
             Some(PDFObject::Number(w)) => *w as u32,
             _ => return Ok(()),
         };
 
-        let height = match xobject_dict.get("Height") {
+        let height = match xobject_dict.get("Height").or_else(|| xobject_dict.get("H")) {
             Some(PDFObject::Number(h)) => *h as u32,
             _ => return Ok(()),
         };
 
-        let bits_per_component = match xobject_dict.get("BitsPerComponent") {
+        let bits_per_component = match xobject_dict
+            .get("BitsPerComponent")
+            .or_else(|| xobject_dict.get("BPC"))
+        {
             Some(PDFObject::Number(b)) => *b as u8,
             _ => 8, // Default to 8
         };
 
-        let color_space_name = match xobject_dict.get("ColorSpace") {
-            Some(PDFObject::Name(name)) => Some(name.as_str()),
-            Some(PDFObject::Array(_)) => {
-                // TODO: Handle complex color spaces
-                Some("DeviceRGB") // Default fallback
-            }
-            _ => Some("DeviceRGB"), // Default
-        };
+        let color_space = self.image_color_space(xobject_dict);
 
-        // Determine if image has alpha
-        let has_alpha = match color_space_name {
-            Some("DeviceRGB") | Some("DeviceGray") | Some("CalRGB") | Some("CalGray") => false,
-            _ => true, // Assume other color spaces might have alpha
-        };
-
-        // Get the image data stream
-        let image_data = match &xobject {
-            PDFObject::Stream { data, .. } => data,
-            _ => return Ok(()),
-        };
+        // Image samples never include alpha; soft masks are separate images
+        let has_alpha = false;
 
         // Decode the image data if needed
         // For JPEG, we also need to update the image metadata
         let (decoded_data, decoded_width, decoded_height, decoded_bpc, decoded_has_alpha) =
-            if let Some(filter) = xobject_dict.get("Filter") {
-                match filter {
-                    PDFObject::Name(filter_name) => {
-                        use crate::core::decode;
-                        match filter_name.as_str() {
-                            "FlateDecode" | "Fl" => (
-                                decode::decode_flate(image_data)
-                                    .unwrap_or_else(|_| image_data.clone()),
-                                width,
-                                height,
-                                bits_per_component,
-                                has_alpha,
-                            ),
-                            "DCTDecode" | "DCT" => {
-                                // JPEG data - decode it using zune-jpeg
-                                #[cfg(feature = "jpeg-decoding")]
-                                {
-                                    match crate::core::image::ImageDecoder::decode_image(
-                                        image_data,
-                                        crate::core::image::ImageFormat::JPEG,
-                                    ) {
-                                        Ok(decoded) => {
-                                            // Use decoded image's metadata since JPEG decoder knows best
-                                            (
-                                                decoded.data,
-                                                decoded.metadata.width,
-                                                decoded.metadata.height,
-                                                decoded.metadata.bits_per_component,
-                                                decoded.metadata.has_alpha,
-                                            )
-                                        }
-                                        Err(e) => {
-                                            eprintln!(
-                                                "Warning: Failed to decode JPEG image: {}",
-                                                e
-                                            );
-                                            (
-                                                image_data.clone(),
-                                                width,
-                                                height,
-                                                bits_per_component,
-                                                has_alpha,
-                                            )
-                                        }
+            match xobject_dict.get("Filter") {
+                Some(filters) => {
+                    use crate::core::decode;
+                    match decode::decode_until_image_filter_limited(
+                        image_data,
+                        filters,
+                        xobject_dict.get("DecodeParms"),
+                        &self.limits,
+                    ) {
+                        Ok((data, None)) => self.convert_image_samples(
+                            data,
+                            (width, height, bits_per_component, has_alpha),
+                            color_space.as_ref(),
+                            xobject_dict,
+                        ),
+                        Ok((data, Some(codec))) if codec == "DCTDecode" || codec == "DCT" => {
+                            // JPEG data - decode it using zune-jpeg
+                            #[cfg(feature = "jpeg-decoding")]
+                            {
+                                match crate::core::image::ImageDecoder::decode_image(
+                                    &data,
+                                    crate::core::image::ImageFormat::JPEG,
+                                ) {
+                                    Ok(decoded) => {
+                                        // Use decoded image's metadata since JPEG decoder knows best
+                                        (
+                                            decoded.data,
+                                            decoded.metadata.width,
+                                            decoded.metadata.height,
+                                            decoded.metadata.bits_per_component,
+                                            decoded.metadata.has_alpha,
+                                        )
+                                    }
+                                    Err(e) => {
+                                        tracing::warn!(
+                                            target: log_target::IMAGE,
+                                            "Failed to decode JPEG image: {}", e
+                                        );
+                                        (data, width, height, bits_per_component, has_alpha)
                                     }
-                                }
-                                #[cfg(not(feature = "jpeg-decoding"))]
-                                {
-                                    eprintln!("Warning: JPEG decoding not enabled, skipping image");
-                                    // Return empty data to prevent crash
-                                    (Vec::new(), width, height, bits_per_component, has_alpha)
                                 }
                             }
-                            "CCITTFaxDecode" | "CCF" => {
-                                // TODO: Implement CCITT decoding
-                                (
-                                    image_data.clone(),
-                                    width,
-                                    height,
-                                    bits_per_component,
-                                    has_alpha,
-                                )
-                            }
-                            _ => {
-                                // Unsupported filter - try raw data
-                                (
-                                    image_data.clone(),
-                                    width,
-                                    height,
-                                    bits_per_component,
-                                    has_alpha,
-                                )
+                            #[cfg(not(feature = "jpeg-decoding"))]
+                            {
+                                let _ = data;
+                                tracing::warn!(
+                                    target: log_target::IMAGE,
+                                    "JPEG decoding not enabled, skipping image"
+                                );
+                                // Return empty data to prevent crash
+                                (Vec::new(), width, height, bits_per_component, has_alpha)
                             }
                         }
-                    }
-                    PDFObject::Array(filters) => {
-                        // TODO: Handle multiple filters (apply in order)
-                        use crate::core::decode;
-                        // For now, try to apply the first filter if it's FlateDecode
-                        if !filters.is_empty() {
-                            match filters.get(0) {
-                                Some(filter_obj) => match filter_obj.as_ref() {
-                                    PDFObject::Name(filter_name) => match filter_name.as_str() {
-                                        "FlateDecode" | "Fl" => (
-                                            decode::decode_flate(image_data)
-                                                .unwrap_or_else(|_| image_data.clone()),
-                                            width,
-                                            height,
-                                            bits_per_component,
-                                            has_alpha,
-                                        ),
-                                        _ => (
-                                            image_data.clone(),
-                                            width,
-                                            height,
-                                            bits_per_component,
-                                            has_alpha,
-                                        ),
-                                    },
-                                    _ => (
-                                        image_data.clone(),
-                                        width,
-                                        height,
-                                        bits_per_component,
-                                        has_alpha,
-                                    ),
-                                },
-                                None => (
-                                    image_data.clone(),
-                                    width,
-                                    height,
-                                    bits_per_component,
-                                    has_alpha,
+                        Ok((data, Some(codec))) if codec == "JPXDecode" => {
+                            // The codestream's alpha is used only with /SMaskInData
+                            let smask_in_data = xobject_dict
+                                .get("SMaskInData")
+                                .and_then(|v| v.as_number())
+                                .is_some_and(|v| v != 0.0);
+                            match crate::core::image::ImageDecoder::decode_jpx(
+                                &data,
+                                color_space.as_ref(),
+                                smask_in_data,
+                            ) {
+                                Ok(decoded) => (
+                                    decoded.data,
+                                    decoded.width,
+                                    decoded.height,
+                                    8,
+                                    decoded.color_space
+                                        == crate::core::image::ImageColorSpace::RGBA,
                                 ),
+                                Err(e) => {
+                                    tracing::warn!(
+                                        target: log_target::IMAGE,
+                                        "Failed to decode JPEG2000 image '{}': {}", xobject_name, e
+                                    );
+                                    // Return empty data to prevent crash
+                                    (Vec::new(), width, height, bits_per_component, has_alpha)
+                                }
                             }
-                        } else {
+                        }
+                        Ok((data, Some(_))) => {
+                            // TODO: Implement CCITT and JBIG2 decoding here
+                            (data, width, height, bits_per_component, has_alpha)
+                        }
+                        Err(e @ PDFError::LimitExceeded { .. }) => return Err(e),
+                        Err(e) => {
+                            tracing::warn!(
+                                target: log_target::IMAGE,
+                                "Failed to decode image '{}': {}", xobject_name, e
+                            );
                             (
-                                image_data.clone(),
+                                image_data.to_vec(),
                                 width,
                                 height,
                                 bits_per_component,
@@ -1811,22 +2789,13 @@ impl<'a, D: Device> RenderingContext<'a, D> {
                             )
                         }
                     }
-                    _ => (
-                        image_data.clone(),
-                        width,
-                        height,
-                        bits_per_component,
-                        has_alpha,
-                    ),
                 }
-            } else {
-                (
-                    image_data.clone(),
-                    width,
-                    height,
-                    bits_per_component,
-                    has_alpha,
-                )
+                None => self.convert_image_samples(
+                    image_data.to_vec(),
+                    (width, height, bits_per_component, has_alpha),
+                    color_space.as_ref(),
+                    xobject_dict,
+                ),
             };
 
         // Create image data for the device
@@ -1839,8 +2808,9 @@ impl<'a, D: Device> RenderingContext<'a, D> {
         };
 
         #[cfg(feature = "debug-logging")]
-        eprintln!(
-            "DEBUG: PaintXObject: Drawing image '{}' ({}x{}, {} bpc, {} bytes)",
+        tracing::trace!(
+            target: log_target::IMAGE,
+            "PaintXObject: Drawing image '{}' ({}x{}, {} bpc, {} bytes)",
             xobject_name,
             width,
             height,
@@ -1853,12 +2823,114 @@ impl<'a, D: Device> RenderingContext<'a, D> {
         let transform = [1.0, 0.0, 0.0, 1.0, 0.0, 0.0];
 
         self.device.draw_image(image, &transform)?;
+        self.record_overprint(OverprintPaint::Image, Some([0.0, 0.0, 1.0, 1.0]));
 
         #[cfg(feature = "debug-logging")]
-        eprintln!("DEBUG: Image drawn successfully");
+        tracing::trace!(target: log_target::IMAGE, "Image drawn successfully");
 
         Ok(())
     }
+
+    /// Parses the /ColorSpace of an image, looking up resource names used by
+    /// inline images.
+    ///
+    /// # Returns
+    /// The color space, or `None` if it is missing (e.g. image masks) or
+    /// cannot be parsed
+    fn image_color_space(
+        &mut self,
+        image_dict: &std::collections::HashMap<String, PDFObject>,
+    ) -> Option<ColorSpace> {
+        let mut color_space = image_dict
+            .get("ColorSpace")
+            .or_else(|| image_dict.get("CS"))?
+            .clone();
+        if let PDFObject::Name(name) = &color_space
+            && ColorSpace::from_name(name).is_none()
+            && let Ok(Some(resource)) = self.lookup_resource("ColorSpace", name)
+        {
+            color_space = resource;
+        }
+
+        match self.xref.as_deref_mut() {
+            Some(xref) => match ColorSpace::parse(&color_space, xref) {
+                Ok(parsed) => Some(parsed),
+                Err(e) => {
+                    tracing::warn!(
+                        target: log_target::IMAGE,
+                        "Unsupported image color space: {}", e
+                    );
+                    None
+                }
+            },
+            None => color_space.as_name().and_then(ColorSpace::from_name),
+        }
+    }
+
+    /// Converts raw image samples to 8-bit RGB unless the device can draw
+    /// them directly (8-bit device gray, RGB or CMYK without /Decode).
+    /// Stencil masks (/ImageMask) become RGBA painted with the current fill
+    /// color.
+    ///
+    /// # Arguments
+    /// * `data` - Samples after all non-image filters
+    /// * `info` - Width, height, bits per component and alpha flag
+    /// * `color_space` - The image color space, `None` to leave data as is
+    /// * `image_dict` - The image dictionary, for /Decode
+    fn convert_image_samples(
+        &self,
+        data: Vec<u8>,
+        info: (u32, u32, u8, bool),
+        color_space: Option<&ColorSpace>,
+        image_dict: &std::collections::HashMap<String, PDFObject>,
+    ) -> (Vec<u8>, u32, u32, u8, bool) {
+        let (width, height, bits_per_component, has_alpha) = info;
+        let decode: Option<Vec<f64>> = image_dict
+            .get("Decode")
+            .or_else(|| image_dict.get("D"))
+            .and_then(|d| d.as_array())
+            .and_then(|d| d.iter().map(|n| n.as_number()).collect());
+
+        let image_mask = matches!(
+            image_dict.get("ImageMask").or_else(|| image_dict.get("IM")),
+            Some(PDFObject::Boolean(true))
+        );
+        if image_mask {
+            let (r, g, b, _) = self.current_state().fill_color.rgba();
+            match crate::core::image::ImageDecoder::decode_image_mask(
+                &data,
+                width,
+                height,
+                decode.as_deref(),
+                [r, g, b],
+            ) {
+                Ok(mask) => return (mask.data, width, height, 8, true),
+                Err(e) => {
+                    tracing::warn!(target: log_target::IMAGE, "Invalid image mask: {}", e);
+                    return (data, width, height, bits_per_component, has_alpha);
+                }
+            }
+        }
+
+        match color_space {
+            Some(ColorSpace::DeviceGray | ColorSpace::DeviceRGB | ColorSpace::DeviceCMYK)
+                if bits_per_component == 8 && decode.is_none() =>
+            {
+                (data, width, height, bits_per_component, has_alpha)
+            }
+            Some(color_space) => {
+                let rgb = color_space.image_to_rgb(
+                    &data,
+                    width as usize,
+                    height as usize,
+                    bits_per_component,
+                    decode.as_deref(),
+                );
+                (rgb, width, height, 8, false)
+            }
+            None => (data, width, height, bits_per_component, has_alpha),
+        }
+    }
 }
 
 /// Helper function to extract a number from a PDFObject.
@@ -1877,12 +2949,45 @@ fn extract_number(args: &[crate::core::parser::PDFObject], index: usize) -> PDFR
     }
 }
 
+/// Reads a /Matrix entry, defaulting to the identity matrix.
+fn matrix_entry(value: Option<&PDFObject>) -> [f64; 6] {
+    match value.and_then(|v| v.as_array()) {
+        Some([a, b, c, d, e, f]) => {
+            let n = |v: &PDFObject| v.as_number().unwrap_or(0.0);
+            [n(a), n(b), n(c), n(d), n(e), n(f)]
+        }
+        _ => [1.0, 0.0, 0.0, 1.0, 0.0, 0.0],
+    }
+}
+
+/// Converts color components in a color space to a device color.
+fn color_from_components(color_space: &ColorSpace, values: &[f64]) -> Color {
+    match (color_space, values) {
+        (ColorSpace::DeviceGray, &[gray]) => Color::Gray(gray),
+        (ColorSpace::DeviceRGB, &[r, g, b]) => Color::RGB(r, g, b),
+        (ColorSpace::DeviceCMYK, &[c, m, y, k]) => Color::CMYK(c, m, y, k),
+        _ => {
+            let [r, g, b] = color_space.to_rgb(values);
+            Color::RGB(r, g, b)
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::core::parser::PDFObject;
     use crate::rendering::device::TestDevice;
 
+    fn parse_object(source: &[u8]) -> PDFObject {
+        let stream = crate::core::Stream::from_bytes(source.to_vec());
+        let lexer = crate::core::Lexer::new(Box::new(stream)).unwrap();
+        crate::core::Parser::new(lexer)
+            .unwrap()
+            .get_object()
+            .unwrap()
+    }
+
     #[test]
     fn test_context_creation() {
         let mut device = TestDevice::new(612.0, 792.0);
@@ -1906,6 +3011,41 @@ mod tests {
         assert_eq!(ctx.current_state().stroke_color, Color::RGB(1.0, 0.0, 0.0));
     }
 
+    #[test]
+    fn test_rendering_intent_and_overprint_report() {
+        let mut device = TestDevice::new(612.0, 792.0);
+        let mut ctx = RenderingContext::new(&mut device);
+
+        let op = Operation::new(
+            OpCode::SetRenderingIntent,
+            vec![PDFObject::Name("AbsoluteColorimetric".to_string())],
+        );
+        ctx.process_operation(&op).unwrap();
+        assert_eq!(
+            ctx.current_state().rendering_intent,
+            RenderingIntent::AbsoluteColorimetric
+        );
+
+        // Without overprint nothing is recorded.
+        ctx.current_path.rect(0.0, 0.0, 10.0, 10.0);
+        ctx.fill(FillRule::NonZero).unwrap();
+        assert!(!ctx.report().uses_overprint());
+
+        ctx.current_state_mut().fill_overprint = true;
+        ctx.current_path.rect(0.0, 0.0, 10.0, 10.0);
+        ctx.fill(FillRule::NonZero).unwrap();
+
+        let report = ctx.take_report();
+        assert_eq!(report.overprint.len(), 1);
+        assert_eq!(report.overprint[0].paint, OverprintPaint::Fill);
+        assert_eq!(report.overprint[0].bbox, Some([0.0, 0.0, 10.0, 10.0]));
+        assert_eq!(
+            report.rendering_intents,
+            vec![RenderingIntent::AbsoluteColorimetric]
+        );
+        assert!(ctx.report().overprint.is_empty());
+    }
+
     #[test]
     fn test_move_to_operator() {
         let mut device = TestDevice::new(612.0, 792.0);
@@ -1919,4 +3059,398 @@ mod tests {
         ctx.process_operation(&op).unwrap();
         assert_eq!(ctx.current_path.current_point(), Some((10.0, 20.0)));
     }
+
+    #[test]
+    fn test_shading_and_pattern_fill() {
+        let source = b"<< /Shading << /Sh0 << /ShadingType 2 /ColorSpace /DeviceRGB \
+            /Coords [0 0 100 0] /Function << /FunctionType 2 /Domain [0 1] \
+            /C0 [1 0 0] /C1 [0 0 1] /N 1 >> >> >> \
+            /Pattern << /P0 << /PatternType 2 /Matrix [1 0 0 1 50 0] /Shading \
+            << /ShadingType 3 /ColorSpace /DeviceGray /Coords [0 0 0 0 0 10] \
+            /Function << /FunctionType 2 /Domain [0 1] /C0 [0] /C1 [1] /N 1 >> >> >> >> \
+            /ColorSpace << /CS0 [/Separation /Spot /DeviceCMYK << /FunctionType 2 \
+            /Domain [0 1] /C0 [0 0 0 0] /C1 [0 0 0 1] /N 1 >>] >> >>";
+        let resources = parse_object(source);
+        let mut xref = XRef::new(Box::new(crate::core::Stream::from_bytes(Vec::new())));
+
+        let mut device = TestDevice::new(612.0, 792.0);
+        let mut ctx = RenderingContext::new(&mut device);
+        ctx.set_xobject_resources(&mut xref, &resources);
+        let name = |n: &str| PDFObject::Name(n.to_string());
+        let run = |ctx: &mut RenderingContext<TestDevice>, op: OpCode, args: Vec<PDFObject>| {
+            ctx.process_operation(&Operation::new(op, args)).unwrap()
+        };
+
+        run(&mut ctx, OpCode::ShadingFill, vec![name("Sh0")]);
+
+        run(&mut ctx, OpCode::SetFillColorSpace, vec![name("Pattern")]);
+        run(&mut ctx, OpCode::SetFillColorN, vec![name("P0")]);
+        run(
+            &mut ctx,
+            OpCode::Transform,
+            vec![
+                PDFObject::Number(2.0),
+                PDFObject::Number(0.0),
+                PDFObject::Number(0.0),
+                PDFObject::Number(2.0),
+                PDFObject::Number(0.0),
+                PDFObject::Number(0.0),
+            ],
+        );
+        // Pattern space is the page's default space: (25, 0) in user space is
+        // (50, 0) on the page, the center of the pattern's circle
+        let Paint::Gradient(gradient) = ctx.current_state().fill_paint() else {
+            panic!("expected a gradient paint");
+        };
+        assert_eq!(
+            gradient.color_at(25.0, 0.0),
+            Some(Color::RGB(0.0, 0.0, 0.0))
+        );
+        assert_eq!(
+            gradient.color_at(25.0, 5.0),
+            Some(Color::RGB(1.0, 1.0, 1.0))
+        );
+        ctx.current_path.rect(0.0, 0.0, 10.0, 10.0);
+        ctx.fill(FillRule::NonZero).unwrap();
+
+        // A Separation space starts at full tint and maps tints through the alternate
+        run(&mut ctx, OpCode::SetFillColorSpace, vec![name("CS0")]);
+        assert_eq!(ctx.current_state().fill_color, Color::RGB(0.0, 0.0, 0.0));
+        run(&mut ctx, OpCode::SetFillColor, vec![PDFObject::Number(0.0)]);
+        assert_eq!(ctx.current_state().fill_color, Color::RGB(1.0, 1.0, 1.0));
+        assert!(ctx.current_state().fill_pattern.is_none());
+
+        drop(ctx);
+        let fills: Vec<&String> = device
+            .operations()
+            .iter()
+            .filter(|op| op.starts_with("draw_path"))
+            .collect();
+        assert_eq!(
+            fills,
+            vec![
+                "draw_path(fill, NonZero, gradient)",
+                "draw_path(fill, NonZero, gradient)"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tiling_pattern_fill() {
+        let tile_dict = |paint_type: u8| {
+            let dict = parse_object(
+                format!(
+                    "<< /PatternType 1 /PaintType {} /TilingType 1 /BBox [0 0 4 4] \
+                     /XStep 5 /YStep 5 /Resources << >> >>",
+                    paint_type
+                )
+                .as_bytes(),
+            );
+            let PDFObject::Dictionary(dict) = dict else {
+                unreachable!()
+            };
+            PDFObject::Stream {
+                dict,
+                data: b"1 0 0 rg 0 0 2 2 re f".to_vec(),
+            }
+        };
+        let mut patterns = HashMap::new();
+        patterns.insert("P1".to_string(), tile_dict(1));
+        patterns.insert("P2".to_string(), tile_dict(2));
+        let PDFObject::Dictionary(mut resources) =
+            parse_object(b"<< /ColorSpace << /CSP [/Pattern /DeviceRGB] >> >>")
+        else {
+            unreachable!()
+        };
+        resources.insert("Pattern".to_string(), PDFObject::Dictionary(patterns));
+        let resources = PDFObject::Dictionary(resources);
+        let mut xref = XRef::new(Box::new(crate::core::Stream::from_bytes(Vec::new())));
+
+        let mut device = TestDevice::new(612.0, 792.0);
+        let mut ctx = RenderingContext::new(&mut device);
+        ctx.set_xobject_resources(&mut xref, &resources);
+        let name = |n: &str| PDFObject::Name(n.to_string());
+        let run = |ctx: &mut RenderingContext<TestDevice>, op: OpCode, args: Vec<PDFObject>| {
+            ctx.process_operation(&Operation::new(op, args)).unwrap()
+        };
+
+        run(&mut ctx, OpCode::SetFillColorSpace, vec![name("Pattern")]);
+        run(&mut ctx, OpCode::SetFillColorN, vec![name("P1")]);
+        ctx.current_path.rect(0.0, 0.0, 20.0, 20.0);
+        ctx.fill(FillRule::NonZero).unwrap();
+        // The cell is rendered once and reused
+        run(&mut ctx, OpCode::SetFillColorN, vec![name("P1")]);
+        let Some(Paint::Tile(tile)) = ctx.current_state().fill_pattern.clone() else {
+            panic!("expected a tile paint");
+        };
+        assert_eq!(tile.id, 0);
+        assert_eq!(tile.cell, [0.0, 0.0, 5.0, 5.0]);
+        assert_eq!(tile.color, None);
+
+        // Uncolored patterns take their color from the scn operands
+        run(&mut ctx, OpCode::SetFillColorSpace, vec![name("CSP")]);
+        run(
+            &mut ctx,
+            OpCode::SetFillColorN,
+            vec![
+                PDFObject::Number(0.0),
+                PDFObject::Number(0.0),
+                PDFObject::Number(1.0),
+                name("P2"),
+            ],
+        );
+        let Some(Paint::Tile(tile)) = ctx.current_state().fill_pattern.clone() else {
+            panic!("expected a tile paint");
+        };
+        assert_eq!(tile.id, 1);
+        assert_eq!(tile.color, Some(Color::RGB(0.0, 0.0, 1.0)));
+
+        drop(ctx);
+        let operations = device.operations();
+        assert_eq!(
+            operations
+                .iter()
+                .filter(|op| op.starts_with("begin_tile"))
+                .count(),
+            2
+        );
+        let begin = operations
+            .iter()
+            .position(|op| op == "begin_tile([0.0, 0.0, 5.0, 5.0])")
+            .unwrap();
+        assert_eq!(
+            operations[begin..begin + 10],
+            [
+                "begin_tile([0.0, 0.0, 5.0, 5.0])",
+                "save_state",
+                "begin_path",
+                "rect(0,0,4,4)",
+                "clip_path(NonZero)",
+                "rect(0,0,2,2)",
+                "draw_path(fill, NonZero)",
+                "restore_state",
+                "end_tile",
+                "draw_path(fill, NonZero, tile)",
+            ]
+        );
+
+        // Pattern cells count against the form depth limit
+        xref.set_limits(ResourceLimits {
+            max_form_depth: 0,
+            ..ResourceLimits::default()
+        });
+        let mut device = TestDevice::new(612.0, 792.0);
+        let mut ctx = RenderingContext::new(&mut device);
+        ctx.set_xobject_resources(&mut xref, &resources);
+        run(&mut ctx, OpCode::SetFillColorSpace, vec![name("Pattern")]);
+        let result =
+            ctx.process_operation(&Operation::new(OpCode::SetFillColorN, vec![name("P1")]));
+        assert!(matches!(
+            result,
+            Err(PDFError::LimitExceeded { max: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn test_transparency_group_form() {
+        let form = |extra: &str, content: &[u8]| {
+            let dict = parse_object(
+                format!(
+                    "<< /Type /XObject /Subtype /Form /BBox [0 0 10 10] \
+                     /Matrix [1 0 0 1 5 5] {} >>",
+                    extra
+                )
+                .as_bytes(),
+            );
+            let PDFObject::Dictionary(dict) = dict else {
+                unreachable!()
+            };
+            PDFObject::Stream {
+                dict,
+                data: content.to_vec(),
+            }
+        };
+        let mut xobjects = HashMap::new();
+        xobjects.insert(
+            "Fm1".to_string(),
+            form("/Group << /S /Transparency /I true >>", b"0 0 4 4 re f"),
+        );
+        // Paints itself, until the form depth limit is reached
+        xobjects.insert("Fm2".to_string(), form("", b"/Fm2 Do"));
+        let PDFObject::Dictionary(mut resources) =
+            parse_object(b"<< /ExtGState << /GS1 << /BM [/Foo /Multiply] >> >> >>")
+        else {
+            unreachable!()
+        };
+        resources.insert("XObject".to_string(), PDFObject::Dictionary(xobjects));
+        let resources = PDFObject::Dictionary(resources);
+        let mut xref = XRef::new(Box::new(crate::core::Stream::from_bytes(Vec::new())));
+
+        let mut device = TestDevice::new(612.0, 792.0);
+        let mut ctx = RenderingContext::new(&mut device);
+        ctx.set_xobject_resources(&mut xref, &resources);
+        let name = |n: &str| PDFObject::Name(n.to_string());
+        let run = |ctx: &mut RenderingContext<TestDevice>, op: OpCode, args: Vec<PDFObject>| {
+            ctx.process_operation(&Operation::new(op, args)).unwrap()
+        };
+
+        run(
+            &mut ctx,
+            OpCode::SetFillRGBColor,
+            vec![
+                PDFObject::Number(1.0),
+                PDFObject::Number(0.0),
+                PDFObject::Number(0.0),
+            ],
+        );
+        run(&mut ctx, OpCode::SetGState, vec![name("GS1")]);
+        assert_eq!(ctx.current_state().blend_mode, BlendMode::Multiply);
+        run(&mut ctx, OpCode::PaintXObject, vec![name("Fm1")]);
+        let result =
+            ctx.process_operation(&Operation::new(OpCode::PaintXObject, vec![name("Fm2")]));
+        assert!(matches!(
+            result,
+            Err(PDFError::LimitExceeded { max, .. }) if max == ResourceLimits::default().max_form_depth
+        ));
+        // The form's graphics state doesn't leak out
+        assert_eq!(ctx.current_state().ctm, [1.0, 0.0, 0.0, 1.0, 0.0, 0.0]);
+
+        drop(ctx);
+        let operations = device.operations();
+        assert_eq!(
+            operations[..12],
+            [
+                "set_blend_mode(Multiply)",
+                "save_state",
+                "concat_matrix([1.0, 0.0, 0.0, 1.0, 5.0, 5.0])",
+                "begin_path",
+                "rect(0,0,10,10)",
+                "clip_path(NonZero)",
+                "begin_group(isolated=true, knockout=false)",
+                "rect(0,0,4,4)",
+                "draw_path(fill, NonZero)",
+                "end_group",
+                "restore_state",
+                "save_state",
+            ]
+        );
+        assert_eq!(
+            operations
+                .iter()
+                .filter(|op| op.as_str() == "save_state")
+                .count(),
+            1 + ResourceLimits::default().max_form_depth
+        );
+    }
+
+    #[test]
+    fn test_clip_stack_follows_save_restore() {
+        let mut device = TestDevice::new(612.0, 792.0);
+        let mut ctx = RenderingContext::new(&mut device);
+        let run = |ctx: &mut RenderingContext<TestDevice>, op: OpCode, args: &[f64]| {
+            let args = args.iter().map(|n| PDFObject::Number(*n)).collect();
+            ctx.process_operation(&Operation::new(op, args)).unwrap()
+        };
+
+        run(&mut ctx, OpCode::Save, &[]);
+        run(&mut ctx, OpCode::Rectangle, &[0.0, 0.0, 10.0, 10.0]);
+        run(&mut ctx, OpCode::Clip, &[]);
+        run(&mut ctx, OpCode::EndPath, &[]);
+        run(&mut ctx, OpCode::Save, &[]);
+        // W before f: the fill isn't clipped by its own path
+        run(&mut ctx, OpCode::Rectangle, &[2.0, 2.0, 4.0, 4.0]);
+        run(&mut ctx, OpCode::EOClip, &[]);
+        run(&mut ctx, OpCode::Fill, &[]);
+        assert_eq!(ctx.clip_stack, vec![2, 3]);
+        run(&mut ctx, OpCode::Restore, &[]);
+        assert_eq!(ctx.clip_stack, vec![2]);
+        run(&mut ctx, OpCode::Restore, &[]);
+        assert!(ctx.clip_stack.is_empty());
+        // An unbalanced Q neither pops a clip nor the device state
+        run(&mut ctx, OpCode::Restore, &[]);
+
+        drop(ctx);
+        assert_eq!(
+            device.operations(),
+            [
+                "save_state",
+                "rect(0,0,10,10)",
+                "push_clip(NonZero)",
+                "save_state",
+                "rect(2,2,4,4)",
+                "draw_path(fill, NonZero)",
+                "begin_path",
+                "move_to(2,2)",
+                "line_to(6,2)",
+                "line_to(6,6)",
+                "line_to(2,6)",
+                "close_path",
+                "push_clip(EvenOdd)",
+                "pop_clip",
+                "restore_state",
+                "pop_clip",
+                "restore_state",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_font_resolver_chooses_substitute() {
+        use crate::rendering::font_resolver::{FontClass, FontQuery, FontResolver};
+        use std::sync::Mutex;
+
+        struct Recording(Mutex<Vec<FontQuery>>);
+        impl FontResolver for Recording {
+            fn resolve(&self, query: &FontQuery) -> Option<Vec<u8>> {
+                self.0.lock().unwrap().push(query.clone());
+                None
+            }
+        }
+
+        let resources = parse_object(
+            b"<< /Font << /F1 << /Type /Font /Subtype /TrueType /BaseFont /ABCDEF+BrandMono-Bold \
+              /FontDescriptor << /Type /FontDescriptor /Flags 33 >> >> >> >>",
+        );
+        let mut xref = XRef::new(Box::new(crate::core::Stream::from_bytes(Vec::new())));
+        let resolver = Recording(Mutex::new(Vec::new()));
+
+        let mut device = TestDevice::new(612.0, 792.0);
+        let mut ctx = RenderingContext::new(&mut device);
+        ctx.set_font_resolver(&resolver);
+        ctx.set_xobject_resources(&mut xref, &resources);
+        ctx.process_operation(&Operation::new(
+            OpCode::SetFont,
+            vec![PDFObject::Name("F1".to_string()), PDFObject::Number(12.0)],
+        ))
+        .unwrap();
+        drop(ctx);
+
+        let queries = resolver.0.into_inner().unwrap();
+        assert_eq!(queries.len(), 1);
+        assert_eq!(queries[0].base_font, "BrandMono-Bold");
+        assert_eq!(queries[0].flags, 33);
+        assert_eq!(queries[0].class, FontClass::Monospace);
+        assert!(queries[0].bold && !queries[0].italic);
+    }
+
+    #[test]
+    fn test_image_mask_uses_fill_color() {
+        let mut device = TestDevice::new(612.0, 792.0);
+        let mut ctx = RenderingContext::new(&mut device);
+        ctx.current_state_mut().fill_color = Color::red();
+
+        // 0 paints, 1 stays transparent; /Decode [1 0] swaps them
+        let mask = parse_object(b"<< /Width 2 /Height 1 /ImageMask true >>");
+        let mask = mask.as_dictionary().unwrap();
+        let (data, _, _, bpc, has_alpha) =
+            ctx.convert_image_samples(vec![0b0100_0000], (2, 1, 1, false), None, mask);
+        assert_eq!((bpc, has_alpha), (8, true));
+        assert_eq!(data, [255, 0, 0, 255, 0, 0, 0, 0]);
+
+        let inverted = parse_object(b"<< /Width 2 /Height 1 /ImageMask true /Decode [1 0] >>");
+        let inverted = inverted.as_dictionary().unwrap();
+        let (data, ..) =
+            ctx.convert_image_samples(vec![0b0100_0000], (2, 1, 1, false), None, inverted);
+        assert_eq!(data, [0, 0, 0, 0, 255, 0, 0, 255]);
+    }
 }
@@ -4,9 +4,12 @@
 //! This allows different rendering implementations (e.g., CPU rendering, GPU rendering,
 //! image export) without changing the content stream interpretation logic.
 
-use super::graphics_state::{Color, FillRule, StrokeProps};
+use super::graphics_state::{
+    BlendMode, Color, FillRule, StrokeProps, invert_matrix, multiply_matrix,
+};
 use crate::core::error::PDFResult;
 use crate::core::parser::PDFObject;
+use crate::core::shading::{Shading, ShadingGeometry};
 use std::collections::HashMap;
 
 /// How to draw a path.
@@ -20,16 +23,28 @@ pub enum PathDrawMode {
     FillStroke(FillRule),
 }
 
+/// How a soft mask group is turned into mask values (/S in a soft mask
+/// dictionary, PDF spec 11.6.5.2).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SoftMaskKind {
+    /// Mask values are the group's alpha
+    Alpha,
+    /// Mask values are the luminosity of the group composited over the backdrop
+    Luminosity,
+}
+
 /// Paint for drawing operations.
 ///
-/// This represents how a shape should be filled/stroked.
-/// For now, we support solid colors. In the future, this will support
-/// patterns, shadings, and images.
+/// This represents how a shape should be filled/stroked: with a solid color,
+/// a smooth shading or a repeated pattern cell.
 #[derive(Debug, Clone)]
 pub enum Paint {
     /// Solid color
     Solid(Color),
-    // TODO: Add Pattern, Shading, Image support
+    /// Axial or radial gradient (sh operator or a shading pattern)
+    Gradient(Box<Gradient>),
+    /// Tiling pattern cell rendered by the device
+    Tile(Box<Tile>),
 }
 
 impl Paint {
@@ -47,6 +62,20 @@ impl Paint {
     pub fn from_color(color: Color) -> Self {
         Paint::Solid(color)
     }
+
+    /// Returns the paint with `matrix` applied after its pattern matrix.
+    ///
+    /// Solid colors are returned unchanged.
+    pub fn transform(&self, matrix: &[f64; 6]) -> Paint {
+        match self {
+            Paint::Solid(color) => Paint::Solid(*color),
+            Paint::Gradient(gradient) => Paint::Gradient(Box::new(gradient.transform(matrix))),
+            Paint::Tile(tile) => Paint::Tile(Box::new(Tile {
+                matrix: multiply_matrix(&tile.matrix, matrix),
+                ..**tile
+            })),
+        }
+    }
 }
 
 impl Default for Paint {
@@ -55,6 +84,126 @@ impl Default for Paint {
     }
 }
 
+/// A shading prepared for drawing.
+///
+/// Colors are sampled along the shading once, so devices only have to map a
+/// point to a position along the shading to find its color.
+#[derive(Debug, Clone)]
+pub struct Gradient {
+    /// Axis or circles along which the color varies, in shading space
+    pub geometry: ShadingGeometry,
+    /// Whether the shading extends past its start and end
+    pub extend: [bool; 2],
+    /// Clipping box in shading space
+    pub bbox: Option<[f64; 4]>,
+    /// Colors sampled evenly from the start to the end of the shading
+    pub colors: Vec<Color>,
+    /// Color outside the shading, if any
+    pub background: Option<Color>,
+    /// Maps shading space to user space at the time of drawing
+    pub matrix: [f64; 6],
+    /// Maps user space back to shading space
+    inverse: [f64; 6],
+}
+
+impl Gradient {
+    /// Number of color samples taken along the shading.
+    pub const SAMPLES: usize = 256;
+
+    /// Prepare a shading for drawing.
+    ///
+    /// # Arguments
+    /// * `shading` - The parsed shading
+    /// * `matrix` - Maps shading space to user space
+    pub fn from_shading(shading: &Shading, matrix: [f64; 6]) -> Self {
+        let rgb = |[r, g, b]: [f64; 3]| Color::RGB(r, g, b);
+        let colors = (0..Self::SAMPLES)
+            .map(|i| rgb(shading.rgb_at(i as f64 / (Self::SAMPLES - 1) as f64)))
+            .collect();
+        Gradient::new(
+            shading.geometry,
+            shading.extend,
+            shading.bbox,
+            colors,
+            shading.background_rgb().map(rgb),
+            matrix,
+        )
+    }
+
+    /// Creates a gradient from already sampled colors.
+    pub(crate) fn new(
+        geometry: ShadingGeometry,
+        extend: [bool; 2],
+        bbox: Option<[f64; 4]>,
+        colors: Vec<Color>,
+        background: Option<Color>,
+        matrix: [f64; 6],
+    ) -> Self {
+        Gradient {
+            geometry,
+            extend,
+            bbox,
+            colors,
+            background,
+            matrix,
+            // A singular matrix collapses the shading; nothing is painted
+            inverse: invert_matrix(&matrix).unwrap_or([0.0; 6]),
+        }
+    }
+
+    /// Color at a point in user space, or `None` if the point is outside the
+    /// shading and there is no background.
+    pub fn color_at(&self, x: f64, y: f64) -> Option<Color> {
+        let [a, b, c, d, e, f] = self.inverse;
+        let (sx, sy) = (a * x + c * y + e, b * x + d * y + f);
+        if let Some([x0, y0, x1, y1]) = self.bbox
+            && (sx < x0 || sx > x1 || sy < y0 || sy > y1)
+        {
+            return None;
+        }
+        match self.geometry.parameter(sx, sy, self.extend) {
+            Some(s) => {
+                let index = (s * (self.colors.len() - 1) as f64).round() as usize;
+                self.colors.get(index).copied()
+            }
+            None => self.background,
+        }
+    }
+
+    /// Returns the gradient with `matrix` applied after its own matrix.
+    pub fn transform(&self, matrix: &[f64; 6]) -> Gradient {
+        let matrix = multiply_matrix(&self.matrix, matrix);
+        Gradient {
+            matrix,
+            inverse: invert_matrix(&matrix).unwrap_or([0.0; 6]),
+            ..self.clone()
+        }
+    }
+
+    /// The color halfway along the gradient, for devices that can only
+    /// paint solid colors.
+    pub fn middle_color(&self) -> Color {
+        self.colors[self.colors.len() / 2]
+    }
+}
+
+/// A tiling pattern cell, repeated to fill an area.
+///
+/// The cell is rendered once by the device between [`Device::begin_tile`]
+/// and [`Device::end_tile`]; this refers to it by the id the device returned.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tile {
+    /// Id returned by [`Device::end_tile`]
+    pub id: usize,
+    /// One cell in pattern space, [x0 y0 x0+XStep y0+YStep]
+    pub cell: [f64; 4],
+    /// Maps pattern space to user space at the time of drawing
+    pub matrix: [f64; 6],
+    /// Color of an uncolored pattern (PaintType 2), which replaces the
+    /// colors the cell was drawn with
+    pub color: Option<Color>,
+}
+
 /// Image data for rendering.
 ///
 /// This represents image data that can be drawn by a device.
@@ -84,6 +233,49 @@ pub struct FontWidthMetrics {
     pub default_width: Option<u16>,
 }
 
+impl FontWidthMetrics {
+    /// Width of a character code, falling back to the default width and then
+    /// to half an em.
+    pub fn width(&self, code: u8) -> u16 {
+        self.code_widths
+            .get(&code)
+            .copied()
+            .or(self.default_width)
+            .unwrap_or(500)
+    }
+}
+
+/// A glyph of a [`GlyphRun`], positioned relative to the run origin.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PositionedGlyph {
+    /// Glyph ID in the embedded font program
+    pub glyph_id: u16,
+    /// Horizontal offset in em (multiply by font size for text space units)
+    pub x: f64,
+    /// Vertical offset in em
+    pub y: f64,
+}
+
+/// Glyphs of a composite (Type0) font string, already mapped and positioned.
+///
+/// Positions come from the PDF's /W widths (or /W2 for vertical writing)
+/// plus character and word spacing, so devices only need to draw outlines.
+#[derive(Debug, Clone)]
+pub struct GlyphRun {
+    /// Name of the loaded font
+    pub font_name: String,
+    /// Font size in points
+    pub font_size: f64,
+    /// Glyphs in drawing order
+    pub glyphs: Vec<PositionedGlyph>,
+    /// Text matrix at the start of the run
+    pub text_matrix: [f64; 6],
+    /// Horizontal text scaling as percentage (default: 100.0)
+    pub horizontal_scaling: f64,
+    /// Text rise in user space units
+    pub text_rise: f64,
+}
+
 /// A device that can render PDF drawing operations.
 ///
 /// This trait abstracts the rendering backend, allowing different implementations
@@ -128,14 +320,50 @@ pub trait Device {
         stroke_props: &StrokeProps,
     ) -> PDFResult<()>;
 
+    /// Fill the current path and then stroke it (B, B*, b, b*), each with
+    /// its own paint.
+    ///
+    /// The default implementation calls [`Device::draw_path`] with
+    /// [`PathDrawMode::FillStroke`] and the fill paint, so the stroke gets
+    /// the fill color. Devices that draw output must override it, as
+    /// `SkiaDevice`, `SvgDevice` and `ContentStreamDevice` do.
+    fn fill_and_stroke_path(
+        &mut self,
+        rule: FillRule,
+        fill: &Paint,
+        stroke: &Paint,
+        stroke_props: &StrokeProps,
+    ) -> PDFResult<()> {
+        let _ = stroke;
+        self.draw_path(PathDrawMode::FillStroke(rule), fill, stroke_props)
+    }
+
     /// Set a clipping path.
     ///
-    /// Subsequent drawing operations will be clipped to this path.
+    /// Intersects the current clip with the current path, which is consumed.
+    /// Subsequent drawing operations will be clipped to the result. The clip
+    /// is part of the graphics state, so [`Device::restore_state`] brings
+    /// back the clip in effect at the matching [`Device::save_state`].
     ///
     /// # Arguments
     /// * `rule` - Fill rule to use for the clipping path
     fn clip_path(&mut self, rule: FillRule) -> PDFResult<()>;
 
+    /// Clip to the current path (W/W*) so that [`Device::pop_clip`] can undo it.
+    ///
+    /// The default implementation calls [`Device::clip_path`].
+    fn push_clip(&mut self, rule: FillRule) -> PDFResult<()> {
+        self.clip_path(rule)
+    }
+
+    /// Undo the most recent [`Device::push_clip`] that is still in effect.
+    ///
+    /// The rendering context pops the clips pushed since a `q` before the
+    /// matching `Q`, and the clips left at the end of a form or pattern.
+    /// Devices that only keep the clip in the state snapshots taken by
+    /// [`Device::save_state`] can ignore this.
+    fn pop_clip(&mut self) {}
+
     /// Save the graphics state.
     fn save_state(&mut self);
 
@@ -182,6 +410,17 @@ pub trait Device {
         text_rise: f64,
     ) -> PDFResult<f64>;
 
+    /// Draw a positioned glyph run of a composite font.
+    ///
+    /// Glyph IDs index the font program loaded under `run.font_name`. Devices
+    /// that can't draw by glyph ID return `false`; the caller then falls back
+    /// to [`Device::draw_text`] for painting but keeps the run's advances.
+    fn draw_glyph_run(&mut self, run: &GlyphRun, paint: &Paint) -> PDFResult<bool> {
+        let _ = run;
+        let _ = paint;
+        Ok(false)
+    }
+
     /// Draw an image.
     ///
     /// # Arguments
@@ -194,6 +433,87 @@ pub trait Device {
     /// Returns (width, height) in user space units.
     fn page_bounds(&self) -> (f64, f64);
 
+    /// Begin rendering a soft mask group offscreen.
+    ///
+    /// Drawing operations up to the matching [`Device::end_soft_mask`] paint
+    /// the mask group instead of the page. Devices that can't do this return
+    /// `false`; the group is then skipped and painting stays unmasked.
+    ///
+    /// # Arguments
+    /// * `kind` - How mask values are derived from the group
+    /// * `backdrop` - Color the group is composited over (/BC)
+    fn begin_soft_mask(&mut self, kind: SoftMaskKind, backdrop: &Color) -> PDFResult<bool> {
+        let _ = kind;
+        let _ = backdrop;
+        Ok(false)
+    }
+
+    /// Finish the soft mask group begun by [`Device::begin_soft_mask`].
+    ///
+    /// The mask applies to all subsequent painting until the graphics state
+    /// is restored or the mask is cleared.
+    fn end_soft_mask(&mut self) -> PDFResult<()> {
+        Ok(())
+    }
+
+    /// Remove the soft mask of the current graphics state (/SMask /None).
+    fn clear_soft_mask(&mut self) {}
+
+    /// Set the blend mode for subsequent painting (ExtGState /BM).
+    ///
+    /// The blend mode is part of the device's graphics state, so it is saved
+    /// and restored with it. Devices that can't blend paint normally.
+    fn set_blend_mode(&mut self, mode: BlendMode) {
+        let _ = mode;
+    }
+
+    /// Begin rendering a transparency group offscreen (PDF spec 11.4).
+    ///
+    /// Drawing operations up to the matching [`Device::end_group`] paint the
+    /// group, which is then composited onto the page as a single object with
+    /// the blend mode, clip and soft mask in effect now. Inside the group the
+    /// blend mode starts as [`BlendMode::Normal`] and there is no soft mask.
+    /// Devices that can't do this return `false`; the group's contents are
+    /// then painted directly onto the page.
+    ///
+    /// # Arguments
+    /// * `isolated` - Whether the group starts from a transparent backdrop (/I)
+    /// * `knockout` - Whether objects in the group replace earlier objects in
+    ///   the group instead of compositing with them (/K)
+    fn begin_group(&mut self, isolated: bool, knockout: bool) -> PDFResult<bool> {
+        let _ = isolated;
+        let _ = knockout;
+        Ok(false)
+    }
+
+    /// Finish the group begun by [`Device::begin_group`] and composite it.
+    fn end_group(&mut self) -> PDFResult<()> {
+        Ok(())
+    }
+
+    /// Begin rendering one cell of a tiling pattern offscreen.
+    ///
+    /// Drawing operations up to the matching [`Device::end_tile`] paint the
+    /// cell instead of the page. Devices that can't do this return `false`;
+    /// areas filled with the pattern are then left unpainted.
+    ///
+    /// # Arguments
+    /// * `cell` - One cell in pattern space, [x0 y0 x0+XStep y0+YStep]
+    /// * `matrix` - Maps pattern space to the current user space
+    fn begin_tile(&mut self, cell: &[f64; 4], matrix: &[f64; 6]) -> PDFResult<bool> {
+        let _ = cell;
+        let _ = matrix;
+        Ok(false)
+    }
+
+    /// Finish the cell begun by [`Device::begin_tile`].
+    ///
+    /// # Returns
+    /// An id for the cell, used in [`Tile`] paints
+    fn end_tile(&mut self) -> PDFResult<usize> {
+        Ok(0)
+    }
+
     /// Load font data for rendering.
     ///
     /// This method allows loading font data (TrueType, CFF, etc.) for text rendering.
@@ -250,6 +570,8 @@ pub struct TestDevice {
     state_stack: Vec<TestGraphicsState>,
     /// Recorded operations for testing
     operations: Vec<String>,
+    /// Number of tiling pattern cells rendered
+    tile_count: usize,
 }
 
 #[derive(Debug, Clone)]
@@ -273,6 +595,7 @@ impl TestDevice {
             page_height: height,
             state_stack: vec![TestGraphicsState::default()],
             operations: Vec::new(),
+            tile_count: 0,
         }
     }
 
@@ -319,19 +642,26 @@ impl Device for TestDevice {
     fn draw_path(
         &mut self,
         mode: PathDrawMode,
-        _paint: &Paint,
+        paint: &Paint,
         _stroke_props: &StrokeProps,
     ) -> PDFResult<()> {
+        let pattern = match paint {
+            Paint::Solid(_) => "",
+            Paint::Gradient(_) => ", gradient",
+            Paint::Tile(_) => ", tile",
+        };
         match mode {
             PathDrawMode::Fill(rule) => {
-                self.operations.push(format!("draw_path(fill, {:?})", rule));
+                self.operations
+                    .push(format!("draw_path(fill, {:?}{})", rule, pattern));
             }
             PathDrawMode::Stroke => {
-                self.operations.push("draw_path(stroke)".to_string());
+                self.operations
+                    .push(format!("draw_path(stroke{})", pattern));
             }
             PathDrawMode::FillStroke(rule) => {
                 self.operations
-                    .push(format!("draw_path(fill_stroke, {:?})", rule));
+                    .push(format!("draw_path(fill_stroke, {:?}{})", rule, pattern));
             }
         }
         Ok(())
@@ -342,6 +672,15 @@ impl Device for TestDevice {
         Ok(())
     }
 
+    fn push_clip(&mut self, rule: FillRule) -> PDFResult<()> {
+        self.operations.push(format!("push_clip({:?})", rule));
+        Ok(())
+    }
+
+    fn pop_clip(&mut self) {
+        self.operations.push("pop_clip".to_string());
+    }
+
     fn save_state(&mut self) {
         let current = self.state_stack.last().unwrap().clone();
         self.state_stack.push(current);
@@ -404,6 +743,21 @@ impl Device for TestDevice {
         Ok(width)
     }
 
+    fn draw_glyph_run(&mut self, run: &GlyphRun, _paint: &Paint) -> PDFResult<bool> {
+        let glyphs: Vec<String> = run
+            .glyphs
+            .iter()
+            .map(|glyph| format!("{}@{},{}", glyph.glyph_id, glyph.x, glyph.y))
+            .collect();
+        self.operations.push(format!(
+            "draw_glyph_run({}, {}, [{}])",
+            run.font_name,
+            run.font_size,
+            glyphs.join(" ")
+        ));
+        Ok(true)
+    }
+
     fn draw_image(&mut self, image: ImageData, transform: &[f64; 6]) -> PDFResult<()> {
         self.operations.push(format!(
             "draw_image({}x{}, {:?})",
@@ -415,6 +769,49 @@ impl Device for TestDevice {
     fn page_bounds(&self) -> (f64, f64) {
         (self.page_width, self.page_height)
     }
+
+    fn begin_soft_mask(&mut self, kind: SoftMaskKind, backdrop: &Color) -> PDFResult<bool> {
+        self.operations
+            .push(format!("begin_soft_mask({:?}, {:?})", kind, backdrop));
+        Ok(true)
+    }
+
+    fn end_soft_mask(&mut self) -> PDFResult<()> {
+        self.operations.push("end_soft_mask".to_string());
+        Ok(())
+    }
+
+    fn clear_soft_mask(&mut self) {
+        self.operations.push("clear_soft_mask".to_string());
+    }
+
+    fn set_blend_mode(&mut self, mode: BlendMode) {
+        self.operations.push(format!("set_blend_mode({:?})", mode));
+    }
+
+    fn begin_group(&mut self, isolated: bool, knockout: bool) -> PDFResult<bool> {
+        self.operations.push(format!(
+            "begin_group(isolated={}, knockout={})",
+            isolated, knockout
+        ));
+        Ok(true)
+    }
+
+    fn end_group(&mut self) -> PDFResult<()> {
+        self.operations.push("end_group".to_string());
+        Ok(())
+    }
+
+    fn begin_tile(&mut self, cell: &[f64; 4], _matrix: &[f64; 6]) -> PDFResult<bool> {
+        self.operations.push(format!("begin_tile({:?})", cell));
+        Ok(true)
+    }
+
+    fn end_tile(&mut self) -> PDFResult<usize> {
+        self.operations.push("end_tile".to_string());
+        self.tile_count += 1;
+        Ok(self.tile_count - 1)
+    }
 }
 
 #[cfg(test)]
@@ -3,6 +3,27 @@
 use rustybuzz::{Face as BuzzFace, GlyphBuffer, UnicodeBuffer};
 use ttf_parser::{Face, FaceParsingError};
 
+pub mod cff;
+pub mod type1;
+
+/// Receives a glyph outline from a font program, in glyph space units.
+pub trait OutlineSink {
+    fn move_to(&mut self, x: f32, y: f32);
+    fn line_to(&mut self, x: f32, y: f32);
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32);
+    fn close(&mut self);
+}
+
+/// Discards outlines; used to measure glyph widths.
+pub(crate) struct NullSink;
+
+impl OutlineSink for NullSink {
+    fn move_to(&mut self, _x: f32, _y: f32) {}
+    fn line_to(&mut self, _x: f32, _y: f32) {}
+    fn curve_to(&mut self, _x1: f32, _y1: f32, _x2: f32, _y2: f32, _x: f32, _y: f32) {}
+    fn close(&mut self) {}
+}
+
 pub struct Font {
     face: Face<'static>,
     buzz_face: BuzzFace<'static>,
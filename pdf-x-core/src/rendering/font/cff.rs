@@ -0,0 +1,1545 @@
+//! CFF (Type1C / CIDFontType0C) font program parsing.
+//!
+//! /FontFile3 streams with /Subtype /Type1C or /CIDFontType0C hold a bare
+//! Compact Font Format program. This module reads its INDEX and DICT
+//! structures, the charset, encoding and private dictionaries (including the
+//! per-font private dictionaries of CID-keyed fonts), and interprets Type 2
+//! charstrings into glyph outlines.
+//!
+//! Hints are ignored apart from counting stems, which is needed to skip
+//! hintmask data.
+//!
+//! Reference: Adobe Technical Notes #5176 (CFF) and #5177 (Type 2
+//! charstrings).
+
+use std::collections::HashMap;
+
+use super::type1::standard_encoding;
+use super::{NullSink, OutlineSink};
+
+/// Maximum nesting of subroutine calls and accented characters.
+const MAX_CALL_DEPTH: usize = 10;
+
+/// Maximum size of the Type 2 argument stack.
+const MAX_STACK: usize = 48;
+
+/// Number of standard strings; string IDs at or above this index the
+/// font's String INDEX.
+const STANDARD_STRING_COUNT: usize = 391;
+
+/// The private dictionary values needed to run charstrings.
+#[derive(Debug, Clone, Default)]
+struct PrivateDict {
+    /// Local subroutines (/Subrs)
+    subrs: Vec<Vec<u8>>,
+    /// Width of glyphs that don't specify one (defaultWidthX)
+    default_width: f32,
+    /// Base of widths specified in charstrings (nominalWidthX)
+    nominal_width: f32,
+}
+
+/// A parsed CFF font program.
+#[derive(Debug, Clone)]
+pub struct CffFont {
+    /// The font name from the Name INDEX
+    pub font_name: Option<String>,
+
+    /// Glyph space to text space transform (FontMatrix)
+    pub font_matrix: [f64; 6],
+
+    /// Charstrings by glyph ID
+    charstrings: Vec<Vec<u8>>,
+
+    /// Global subroutines
+    global_subrs: Vec<Vec<u8>>,
+
+    /// Glyph ID to string ID, or to CID for CID-keyed fonts
+    charset: Vec<u16>,
+
+    /// The inverse of `charset`: string ID or CID to the first glyph ID
+    /// naming it
+    sid_to_gid: HashMap<u16, u16>,
+
+    /// Character code to glyph ID
+    encoding: HashMap<u8, u16>,
+
+    /// Private dictionaries; one per Font DICT in CID-keyed fonts
+    privates: Vec<PrivateDict>,
+
+    /// Glyph ID to private dictionary index (CID-keyed fonts only)
+    fd_select: Vec<u8>,
+
+    /// Whether the font is CID-keyed (has a ROS operator)
+    is_cid: bool,
+
+    /// Custom strings from the String INDEX
+    strings: Vec<String>,
+}
+
+impl CffFont {
+    /// Parses a CFF font program.
+    ///
+    /// # Arguments
+    /// * `data` - The bare CFF data of a /FontFile3 stream
+    ///
+    /// # Returns
+    /// The first font of the FontSet, or an error if the data isn't valid CFF
+    pub fn parse(data: &[u8]) -> Result<Self, String> {
+        if data.len() < 4 || data[0] != 1 {
+            return Err("Not a CFF font".to_string());
+        }
+        let header_size = data[2] as usize;
+
+        let (names, pos) = read_index(data, header_size).ok_or("Invalid CFF Name INDEX")?;
+        let (top_dicts, pos) = read_index(data, pos).ok_or("Invalid CFF Top DICT INDEX")?;
+        let (strings, pos) = read_index(data, pos).ok_or("Invalid CFF String INDEX")?;
+        let (global_subrs, _) = read_index(data, pos).ok_or("Invalid CFF Global Subr INDEX")?;
+
+        let top = parse_dict(top_dicts.first().ok_or("CFF font has no Top DICT")?);
+        let operand = |op: u16, index: usize| top.get(&op).and_then(|args| args.get(index));
+
+        let charstrings_offset = *operand(17, 0).ok_or("CFF font has no CharStrings")? as usize;
+        let (charstrings, _) =
+            read_index(data, charstrings_offset).ok_or("Invalid CFF CharStrings INDEX")?;
+        if charstrings.is_empty() {
+            return Err("CFF font has no glyphs".to_string());
+        }
+
+        let font_matrix = match top.get(&(12 << 8 | 7)) {
+            Some(values) if values.len() == 6 => [
+                values[0], values[1], values[2], values[3], values[4], values[5],
+            ],
+            _ => [0.001, 0.0, 0.0, 0.001, 0.0, 0.0],
+        };
+
+        let is_cid = top.contains_key(&(12 << 8 | 30));
+        let glyph_count = charstrings.len();
+        let charset = match operand(15, 0).map(|&offset| offset as usize) {
+            // Predefined charsets (ISOAdobe, Expert, ExpertSubset) are
+            // approximated by the identity mapping
+            None | Some(0..=2) => (0..glyph_count as u16).collect(),
+            Some(offset) => read_charset(data, offset, glyph_count),
+        };
+        let mut sid_to_gid = HashMap::with_capacity(charset.len());
+        for (gid, &sid) in charset.iter().enumerate() {
+            sid_to_gid.entry(sid).or_insert(gid as u16);
+        }
+
+        let strings: Vec<String> = strings
+            .iter()
+            .map(|bytes| String::from_utf8_lossy(bytes).into_owned())
+            .collect();
+
+        let (privates, fd_select) = if is_cid {
+            let fd_array = operand(12 << 8 | 36, 0).ok_or("CID-keyed CFF font has no FDArray")?;
+            let (font_dicts, _) =
+                read_index(data, *fd_array as usize).ok_or("Invalid CFF FDArray")?;
+            let privates = font_dicts
+                .iter()
+                .map(|font_dict| read_private(data, &parse_dict(font_dict)))
+                .collect();
+            let fd_select = operand(12 << 8 | 37, 0)
+                .and_then(|&offset| read_fd_select(data, offset as usize, glyph_count))
+                .unwrap_or_default();
+            (privates, fd_select)
+        } else {
+            (vec![read_private(data, &top)], Vec::new())
+        };
+
+        let mut font = CffFont {
+            font_name: names
+                .first()
+                .map(|name| String::from_utf8_lossy(name).into_owned()),
+            font_matrix,
+            charstrings,
+            global_subrs,
+            charset,
+            sid_to_gid,
+            encoding: HashMap::new(),
+            privates,
+            fd_select,
+            is_cid,
+            strings,
+        };
+        if !is_cid {
+            font.encoding = match operand(16, 0).map(|&offset| offset as usize) {
+                None | Some(0) => font.standard_encoding(),
+                // Expert encoding glyphs are looked up by name instead
+                Some(1) => HashMap::new(),
+                Some(offset) => font.read_encoding(data, offset),
+            };
+        }
+        Ok(font)
+    }
+
+    /// Returns true for CID-keyed fonts (CIDFontType0C).
+    pub fn is_cid(&self) -> bool {
+        self.is_cid
+    }
+
+    /// Returns the number of glyphs in the font.
+    pub fn glyph_count(&self) -> usize {
+        self.charstrings.len()
+    }
+
+    /// Returns the name of a glyph (not available in CID-keyed fonts).
+    pub fn glyph_name(&self, gid: u16) -> Option<&str> {
+        if self.is_cid {
+            return None;
+        }
+        self.string(*self.charset.get(gid as usize)?)
+    }
+
+    /// Looks up a glyph by name.
+    pub fn glyph_index_by_name(&self, name: &str) -> Option<u16> {
+        if self.is_cid {
+            return None;
+        }
+        let sid = match STANDARD_STRINGS.iter().position(|&s| s == name) {
+            Some(sid) => sid,
+            None => STANDARD_STRING_COUNT + self.strings.iter().position(|s| s == name)?,
+        };
+        self.glyph_index_for_sid(sid as u16)
+    }
+
+    /// Looks up a glyph by character code in the font's built-in encoding.
+    pub fn glyph_index(&self, code: u8) -> Option<u16> {
+        self.encoding.get(&code).copied()
+    }
+
+    /// Looks up a glyph by CID.
+    ///
+    /// CID-keyed fonts map CIDs to glyphs through their charset; in other
+    /// fonts used as a CIDFont the CID is the glyph ID.
+    pub fn glyph_index_for_cid(&self, cid: u16) -> Option<u16> {
+        if self.is_cid {
+            self.glyph_index_for_sid(cid)
+        } else {
+            ((cid as usize) < self.charstrings.len()).then_some(cid)
+        }
+    }
+
+    /// Returns the advance width of a glyph in glyph space units.
+    pub fn glyph_width(&self, gid: u16) -> Option<f32> {
+        let mut sink = NullSink;
+        let mut interpreter = Interpreter::new(self, gid, &mut sink);
+        interpreter.run_glyph(gid, 0.0, 0.0, 0)?;
+        Some(interpreter.width)
+    }
+
+    /// Outlines a glyph.
+    ///
+    /// # Arguments
+    /// * `gid` - The glyph ID
+    /// * `sink` - Receives the outline in glyph space units
+    ///
+    /// # Returns
+    /// The advance width, or None if the glyph doesn't exist or is malformed
+    pub fn outline(&self, gid: u16, sink: &mut dyn OutlineSink) -> Option<f32> {
+        let mut interpreter = Interpreter::new(self, gid, sink);
+        interpreter.run_glyph(gid, 0.0, 0.0, 0)?;
+        interpreter.close_path();
+        Some(interpreter.width)
+    }
+
+    fn string(&self, sid: u16) -> Option<&str> {
+        let sid = sid as usize;
+        if sid < STANDARD_STRING_COUNT {
+            Some(STANDARD_STRINGS[sid])
+        } else {
+            self.strings
+                .get(sid - STANDARD_STRING_COUNT)
+                .map(String::as_str)
+        }
+    }
+
+    fn glyph_index_for_sid(&self, sid: u16) -> Option<u16> {
+        self.sid_to_gid.get(&sid).copied()
+    }
+
+    fn private_for(&self, gid: u16) -> Option<&PrivateDict> {
+        let index = self.fd_select.get(gid as usize).copied().unwrap_or(0);
+        self.privates.get(index as usize)
+    }
+
+    fn standard_encoding(&self) -> HashMap<u8, u16> {
+        standard_encoding()
+            .into_iter()
+            .filter_map(|(code, name)| Some((code, self.glyph_index_by_name(&name)?)))
+            .collect()
+    }
+
+    /// Reads a custom encoding (formats 0 and 1, with supplements).
+    fn read_encoding(&self, data: &[u8], offset: usize) -> HashMap<u8, u16> {
+        let mut encoding = HashMap::new();
+        let Some(&format) = data.get(offset) else {
+            return encoding;
+        };
+        let mut pos = offset + 1;
+        let mut gid = 1u16;
+        match format & 0x7F {
+            0 => {
+                let count = data.get(pos).copied().unwrap_or(0) as usize;
+                pos += 1;
+                for &code in data.get(pos..pos + count).unwrap_or_default() {
+                    encoding.insert(code, gid);
+                    gid += 1;
+                }
+                pos += count;
+            }
+            1 => {
+                let ranges = data.get(pos).copied().unwrap_or(0) as usize;
+                pos += 1;
+                for range in data
+                    .get(pos..pos + ranges * 2)
+                    .unwrap_or_default()
+                    .chunks(2)
+                {
+                    for code in range[0] as u16..=range[0] as u16 + range[1] as u16 {
+                        encoding.insert(code as u8, gid);
+                        gid += 1;
+                    }
+                }
+                pos += ranges * 2;
+            }
+            _ => return encoding,
+        }
+
+        if format & 0x80 != 0 {
+            let count = data.get(pos).copied().unwrap_or(0) as usize;
+            pos += 1;
+            for supplement in data.get(pos..pos + count * 3).unwrap_or_default().chunks(3) {
+                let sid = u16::from_be_bytes([supplement[1], supplement[2]]);
+                if let Some(gid) = self.glyph_index_for_sid(sid) {
+                    encoding.insert(supplement[0], gid);
+                }
+            }
+        }
+        encoding
+    }
+}
+
+/// Reads an INDEX at `offset`, returning its entries and the offset just past
+/// its end.
+fn read_index(data: &[u8], offset: usize) -> Option<(Vec<Vec<u8>>, usize)> {
+    let count = u16::from_be_bytes([*data.get(offset)?, *data.get(offset + 1)?]) as usize;
+    if count == 0 {
+        return Some((Vec::new(), offset + 2));
+    }
+    let off_size = *data.get(offset + 2)? as usize;
+    if !(1..=4).contains(&off_size) {
+        return None;
+    }
+    let offsets_start = offset.checked_add(3)?;
+    // Offsets are relative to the byte before the object data
+    let base = offsets_start.checked_add((count + 1) * off_size - 1)?;
+    if base > data.len() {
+        return None;
+    }
+    let read_offset = |i: usize| {
+        let start = offsets_start + i * off_size;
+        let relative = data
+            .get(start..start + off_size)?
+            .iter()
+            .fold(0usize, |acc, &b| acc << 8 | b as usize);
+        base.checked_add(relative)
+    };
+    let mut entries = Vec::with_capacity(count);
+    for i in 0..count {
+        let start = read_offset(i)?;
+        let end = read_offset(i + 1)?;
+        entries.push(data.get(start..end)?.to_vec());
+    }
+    Some((entries, read_offset(count)?))
+}
+
+/// Parses a DICT into operator -> operands. Two-byte operators are keyed as
+/// `12 << 8 | op`.
+fn parse_dict(data: &[u8]) -> HashMap<u16, Vec<f64>> {
+    let mut dict = HashMap::new();
+    let mut operands = Vec::new();
+    let mut pos = 0;
+    while pos < data.len() {
+        let b0 = data[pos];
+        pos += 1;
+        match b0 {
+            0..=21 => {
+                let op = if b0 == 12 {
+                    let Some(&b1) = data.get(pos) else {
+                        break;
+                    };
+                    pos += 1;
+                    12 << 8 | b1 as u16
+                } else {
+                    b0 as u16
+                };
+                dict.insert(op, std::mem::take(&mut operands));
+            }
+            28 => {
+                let Some(bytes) = data.get(pos..pos + 2) else {
+                    break;
+                };
+                operands.push(i16::from_be_bytes([bytes[0], bytes[1]]) as f64);
+                pos += 2;
+            }
+            29 => {
+                let Some(bytes) = data.get(pos..pos + 4) else {
+                    break;
+                };
+                operands.push(i32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64);
+                pos += 4;
+            }
+            30 => {
+                let (value, len) = parse_real(&data[pos..]);
+                operands.push(value);
+                pos += len;
+            }
+            32..=246 => operands.push(b0 as f64 - 139.0),
+            247..=250 => {
+                let Some(&b1) = data.get(pos) else {
+                    break;
+                };
+                operands.push((b0 as f64 - 247.0) * 256.0 + b1 as f64 + 108.0);
+                pos += 1;
+            }
+            251..=254 => {
+                let Some(&b1) = data.get(pos) else {
+                    break;
+                };
+                operands.push(-(b0 as f64 - 251.0) * 256.0 - b1 as f64 - 108.0);
+                pos += 1;
+            }
+            _ => {}
+        }
+    }
+    dict
+}
+
+/// Parses a real number operand (nibble-encoded), returning the value and
+/// the number of bytes read.
+fn parse_real(data: &[u8]) -> (f64, usize) {
+    let mut text = String::new();
+    for (i, &byte) in data.iter().enumerate() {
+        for nibble in [byte >> 4, byte & 0x0F] {
+            match nibble {
+                0..=9 => text.push((b'0' + nibble) as char),
+                0xA => text.push('.'),
+                0xB => text.push('E'),
+                0xC => text.push_str("E-"),
+                0xE => text.push('-'),
+                0xF => return (text.parse().unwrap_or(0.0), i + 1),
+                _ => {}
+            }
+        }
+    }
+    (text.parse().unwrap_or(0.0), data.len())
+}
+
+/// Reads the Private DICT referenced by a Top or Font DICT, and its Subrs.
+fn read_private(data: &[u8], dict: &HashMap<u16, Vec<f64>>) -> PrivateDict {
+    let Some([size, offset]) = dict
+        .get(&18)
+        .and_then(|args| <[f64; 2]>::try_from(&args[..]).ok())
+    else {
+        return PrivateDict::default();
+    };
+    let (size, offset) = (size as usize, offset as usize);
+    let Some(bytes) = offset
+        .checked_add(size)
+        .and_then(|end| data.get(offset..end))
+    else {
+        return PrivateDict::default();
+    };
+    let private = parse_dict(bytes);
+    let value = |op: u16| private.get(&op).and_then(|args| args.first()).copied();
+    // Subrs offsets are relative to the start of the Private DICT
+    let subrs = value(19)
+        .and_then(|subrs| read_index(data, offset.checked_add(subrs as usize)?))
+        .map(|(subrs, _)| subrs)
+        .unwrap_or_default();
+    PrivateDict {
+        subrs,
+        default_width: value(20).unwrap_or(0.0) as f32,
+        nominal_width: value(21).unwrap_or(0.0) as f32,
+    }
+}
+
+/// Reads a charset (formats 0, 1 and 2). Glyph 0 is always .notdef.
+fn read_charset(data: &[u8], offset: usize, glyph_count: usize) -> Vec<u16> {
+    let mut charset = vec![0u16];
+    let read_u16 = |pos: usize| {
+        data.get(pos..pos + 2)
+            .map(|b| u16::from_be_bytes([b[0], b[1]]))
+    };
+    let Some(&format) = data.get(offset) else {
+        return charset;
+    };
+    let mut pos = offset + 1;
+    while charset.len() < glyph_count {
+        match format {
+            0 => {
+                let Some(sid) = read_u16(pos) else {
+                    break;
+                };
+                charset.push(sid);
+                pos += 2;
+            }
+            1 | 2 => {
+                let Some(first) = read_u16(pos) else {
+                    break;
+                };
+                let left = if format == 1 {
+                    data.get(pos + 2).map(|&n| n as u16)
+                } else {
+                    read_u16(pos + 2)
+                };
+                let Some(left) = left else {
+                    break;
+                };
+                pos += if format == 1 { 3 } else { 4 };
+                for i in 0..=left {
+                    if charset.len() >= glyph_count {
+                        break;
+                    }
+                    charset.push(first.wrapping_add(i));
+                }
+            }
+            _ => break,
+        }
+    }
+    charset
+}
+
+/// Reads an FDSelect (formats 0 and 3) into a glyph ID -> Font DICT table.
+fn read_fd_select(data: &[u8], offset: usize, glyph_count: usize) -> Option<Vec<u8>> {
+    let read_u16 = |pos: usize| {
+        data.get(pos..pos + 2)
+            .map(|b| u16::from_be_bytes([b[0], b[1]]))
+    };
+    match *data.get(offset)? {
+        0 => Some(data.get(offset + 1..offset + 1 + glyph_count)?.to_vec()),
+        3 => {
+            let ranges = read_u16(offset + 1)? as usize;
+            let mut fd_select = vec![0u8; glyph_count];
+            for i in 0..ranges {
+                let pos = offset + 3 + i * 3;
+                let first = read_u16(pos)? as usize;
+                let fd = *data.get(pos + 2)?;
+                let end = read_u16(pos + 3)? as usize;
+                for entry in fd_select.iter_mut().take(end.min(glyph_count)).skip(first) {
+                    *entry = fd;
+                }
+            }
+            Some(fd_select)
+        }
+        _ => None,
+    }
+}
+
+/// Bias added to subroutine numbers, depending on the number of subroutines.
+fn subr_bias(count: usize) -> i32 {
+    if count < 1240 {
+        107
+    } else if count < 33900 {
+        1131
+    } else {
+        32768
+    }
+}
+
+/// Type 2 charstring interpreter.
+struct Interpreter<'a> {
+    font: &'a CffFont,
+    private: Option<&'a PrivateDict>,
+    sink: &'a mut dyn OutlineSink,
+    stack: Vec<f32>,
+    /// Transient array for put/get
+    transient: [f32; 32],
+    x: f32,
+    y: f32,
+    /// Offset applied to every point (the accent position in seac-style endchar)
+    origin: (f32, f32),
+    width: f32,
+    /// Set once the optional width operand has been handled
+    width_parsed: bool,
+    stem_count: usize,
+    path_open: bool,
+    /// Set when the glyph's endchar has been reached
+    finished: bool,
+}
+
+impl<'a> Interpreter<'a> {
+    fn new(font: &'a CffFont, gid: u16, sink: &'a mut dyn OutlineSink) -> Self {
+        let private = font.private_for(gid);
+        Interpreter {
+            font,
+            private,
+            sink,
+            stack: Vec::new(),
+            transient: [0.0; 32],
+            x: 0.0,
+            y: 0.0,
+            origin: (0.0, 0.0),
+            width: private.map_or(0.0, |p| p.default_width),
+            width_parsed: false,
+            stem_count: 0,
+            path_open: false,
+            finished: false,
+        }
+    }
+
+    /// Runs the charstring of glyph `gid` with its origin at (dx, dy).
+    fn run_glyph(&mut self, gid: u16, dx: f32, dy: f32, depth: usize) -> Option<()> {
+        let charstring = self.font.charstrings.get(gid as usize)?;
+        self.origin = (dx, dy);
+        self.x = 0.0;
+        self.y = 0.0;
+        self.stack.clear();
+        self.stem_count = 0;
+        self.finished = false;
+        self.execute(charstring, depth)
+    }
+
+    /// Takes the width operand if the stack has one more than `expected`
+    /// arguments (or an odd count when `expected` is None).
+    fn parse_width(&mut self, expected: Option<usize>) {
+        if self.width_parsed {
+            return;
+        }
+        self.width_parsed = true;
+        let has_width = match expected {
+            Some(n) => self.stack.len() > n,
+            None => self.stack.len() % 2 == 1,
+        };
+        if has_width && !self.stack.is_empty() {
+            let nominal = self.private.map_or(0.0, |p| p.nominal_width);
+            self.width = nominal + self.stack.remove(0);
+        }
+    }
+
+    fn move_to(&mut self, dx: f32, dy: f32) {
+        self.close_path();
+        self.x += dx;
+        self.y += dy;
+        self.sink
+            .move_to(self.x + self.origin.0, self.y + self.origin.1);
+        self.path_open = true;
+    }
+
+    fn line_to(&mut self, dx: f32, dy: f32) {
+        self.x += dx;
+        self.y += dy;
+        self.sink
+            .line_to(self.x + self.origin.0, self.y + self.origin.1);
+    }
+
+    fn curve_to(&mut self, d: [f32; 6]) {
+        let (ox, oy) = self.origin;
+        let x1 = self.x + d[0];
+        let y1 = self.y + d[1];
+        let x2 = x1 + d[2];
+        let y2 = y1 + d[3];
+        self.x = x2 + d[4];
+        self.y = y2 + d[5];
+        self.sink
+            .curve_to(x1 + ox, y1 + oy, x2 + ox, y2 + oy, self.x + ox, self.y + oy);
+    }
+
+    fn close_path(&mut self) {
+        if self.path_open {
+            self.sink.close();
+            self.path_open = false;
+        }
+    }
+
+    fn call_subr(&mut self, global: bool, depth: usize) -> Option<()> {
+        let subrs = if global {
+            &self.font.global_subrs
+        } else {
+            &self.private?.subrs
+        };
+        let index = self.stack.pop()? as i32 + subr_bias(subrs.len());
+        let subr = subrs.get(usize::try_from(index).ok()?)?;
+        self.execute(subr, depth + 1)
+    }
+
+    fn execute(&mut self, charstring: &[u8], depth: usize) -> Option<()> {
+        if depth > MAX_CALL_DEPTH {
+            return None;
+        }
+
+        let mut pos = 0;
+        while pos < charstring.len() && !self.finished {
+            let byte = charstring[pos];
+            pos += 1;
+            match byte {
+                28 => {
+                    let bytes = charstring.get(pos..pos + 2)?;
+                    pos += 2;
+                    self.push(i16::from_be_bytes([bytes[0], bytes[1]]) as f32)?;
+                }
+                32..=246 => self.push(byte as f32 - 139.0)?,
+                247..=250 => {
+                    let next = *charstring.get(pos)? as f32;
+                    pos += 1;
+                    self.push((byte as f32 - 247.0) * 256.0 + next + 108.0)?;
+                }
+                251..=254 => {
+                    let next = *charstring.get(pos)? as f32;
+                    pos += 1;
+                    self.push(-(byte as f32 - 251.0) * 256.0 - next - 108.0)?;
+                }
+                255 => {
+                    let bytes = charstring.get(pos..pos + 4)?;
+                    pos += 4;
+                    let fixed = i32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+                    self.push(fixed as f32 / 65536.0)?;
+                }
+                // hintmask, cntrmask: stems given before them are vstems
+                19 | 20 => {
+                    self.parse_width(None);
+                    self.stem_count += self.stack.len() / 2;
+                    self.stack.clear();
+                    pos += self.stem_count.div_ceil(8);
+                }
+                12 => {
+                    let op = *charstring.get(pos)?;
+                    pos += 1;
+                    self.escape(op)?;
+                }
+                // return: back to the caller, operands stay on the stack
+                11 => break,
+                _ => self.operator(byte, depth)?,
+            }
+        }
+        Some(())
+    }
+
+    fn push(&mut self, value: f32) -> Option<()> {
+        if self.stack.len() >= MAX_STACK {
+            return None;
+        }
+        self.stack.push(value);
+        Some(())
+    }
+
+    fn operator(&mut self, op: u8, depth: usize) -> Option<()> {
+        let args = std::mem::take(&mut self.stack);
+        match op {
+            // hstem, vstem, hstemhm, vstemhm
+            1 | 3 | 18 | 23 => {
+                self.stack = args;
+                self.parse_width(None);
+                self.stem_count += self.stack.len() / 2;
+                self.stack.clear();
+            }
+            // rmoveto, hmoveto, vmoveto
+            21 | 22 | 4 => {
+                self.stack = args;
+                self.parse_width(Some(if op == 21 { 2 } else { 1 }));
+                let args = std::mem::take(&mut self.stack);
+                match (op, args.as_slice()) {
+                    (21, [dx, dy]) => self.move_to(*dx, *dy),
+                    (22, [dx]) => self.move_to(*dx, 0.0),
+                    (4, [dy]) => self.move_to(0.0, *dy),
+                    _ => return None,
+                }
+            }
+            // rlineto
+            5 => {
+                for pair in args.chunks_exact(2) {
+                    self.line_to(pair[0], pair[1]);
+                }
+            }
+            // hlineto, vlineto: alternating horizontal and vertical lines
+            6 | 7 => {
+                let mut horizontal = op == 6;
+                for &d in &args {
+                    if horizontal {
+                        self.line_to(d, 0.0);
+                    } else {
+                        self.line_to(0.0, d);
+                    }
+                    horizontal = !horizontal;
+                }
+            }
+            // rrcurveto
+            8 => {
+                for d in args.chunks_exact(6) {
+                    self.curve_to([d[0], d[1], d[2], d[3], d[4], d[5]]);
+                }
+            }
+            // callsubr, callgsubr
+            10 | 29 => {
+                self.stack = args;
+                self.call_subr(op == 29, depth)?;
+            }
+            // endchar
+            14 => {
+                self.stack = args;
+                self.parse_width(Some(if self.stack.len() >= 4 { 4 } else { 0 }));
+                let args = std::mem::take(&mut self.stack);
+                self.close_path();
+                if let [adx, ady, bchar, achar] = args[..] {
+                    self.seac(adx, ady, bchar as u8, achar as u8, depth)?;
+                }
+                self.finished = true;
+            }
+            // rcurveline
+            24 => {
+                let curves = args.len().saturating_sub(2) / 6;
+                for d in args.chunks_exact(6).take(curves) {
+                    self.curve_to([d[0], d[1], d[2], d[3], d[4], d[5]]);
+                }
+                if let [dx, dy] = args[curves * 6..] {
+                    self.line_to(dx, dy);
+                }
+            }
+            // rlinecurve
+            25 => {
+                let lines = args.len().saturating_sub(6) / 2;
+                for pair in args.chunks_exact(2).take(lines) {
+                    self.line_to(pair[0], pair[1]);
+                }
+                if let [a, b, c, d, e, f] = args[lines * 2..] {
+                    self.curve_to([a, b, c, d, e, f]);
+                }
+            }
+            // vvcurveto: optional dx1, then dya dxb dyb dyc
+            26 => {
+                let (mut dx1, rest) = match args.len() % 4 {
+                    1 => (args[0], &args[1..]),
+                    _ => (0.0, &args[..]),
+                };
+                for d in rest.chunks_exact(4) {
+                    self.curve_to([dx1, d[0], d[1], d[2], 0.0, d[3]]);
+                    dx1 = 0.0;
+                }
+            }
+            // hhcurveto: optional dy1, then dxa dxb dyb dxc
+            27 => {
+                let (mut dy1, rest) = match args.len() % 4 {
+                    1 => (args[0], &args[1..]),
+                    _ => (0.0, &args[..]),
+                };
+                for d in rest.chunks_exact(4) {
+                    self.curve_to([d[0], dy1, d[1], d[2], d[3], 0.0]);
+                    dy1 = 0.0;
+                }
+            }
+            // vhcurveto, hvcurveto: curves alternate between starting
+            // vertically and horizontally; the last may take a final delta
+            30 | 31 => {
+                let mut horizontal = op == 31;
+                let mut chunks = args.chunks(4).peekable();
+                while let Some(d) = chunks.next() {
+                    if d.len() < 4 {
+                        break;
+                    }
+                    let last = if chunks.peek().is_some_and(|next| next.len() == 1) {
+                        chunks.next().map_or(0.0, |next| next[0])
+                    } else {
+                        0.0
+                    };
+                    if horizontal {
+                        self.curve_to([d[0], 0.0, d[1], d[2], last, d[3]]);
+                    } else {
+                        self.curve_to([0.0, d[0], d[1], d[2], d[3], last]);
+                    }
+                    horizontal = !horizontal;
+                }
+            }
+            _ => {}
+        }
+        Some(())
+    }
+
+    fn escape(&mut self, op: u8) -> Option<()> {
+        match op {
+            // flex
+            35 => {
+                let [a, b, c, d, e, f, g, h, i, j, k, l, _fd] = self.take_args()?;
+                self.curve_to([a, b, c, d, e, f]);
+                self.curve_to([g, h, i, j, k, l]);
+            }
+            // hflex
+            34 => {
+                let [dx1, dx2, dy2, dx3, dx4, dx5, dx6] = self.take_args()?;
+                self.curve_to([dx1, 0.0, dx2, dy2, dx3, 0.0]);
+                self.curve_to([dx4, 0.0, dx5, -dy2, dx6, 0.0]);
+            }
+            // hflex1
+            36 => {
+                let [dx1, dy1, dx2, dy2, dx3, dx4, dx5, dy5, dx6] = self.take_args()?;
+                self.curve_to([dx1, dy1, dx2, dy2, dx3, 0.0]);
+                self.curve_to([dx4, 0.0, dx5, dy5, dx6, -(dy1 + dy2 + dy5)]);
+            }
+            // flex1: the last point moves along the dominant axis
+            37 => {
+                let [a, b, c, d, e, f, g, h, i, j, d6] = self.take_args()?;
+                let dx = a + c + e + g + i;
+                let dy = b + d + f + h + j;
+                let (dx6, dy6) = if dx.abs() > dy.abs() {
+                    (d6, -dy)
+                } else {
+                    (-dx, d6)
+                };
+                self.curve_to([a, b, c, d, e, f]);
+                self.curve_to([g, h, i, j, dx6, dy6]);
+            }
+            // and, or, eq
+            3 | 4 | 15 => {
+                let [a, b] = self.pop_args()?;
+                let result = match op {
+                    3 => a != 0.0 && b != 0.0,
+                    4 => a != 0.0 || b != 0.0,
+                    _ => a == b,
+                };
+                self.stack.push(if result { 1.0 } else { 0.0 });
+            }
+            // not
+            5 => {
+                let [a] = self.pop_args()?;
+                self.stack.push(if a == 0.0 { 1.0 } else { 0.0 });
+            }
+            // abs, neg, sqrt
+            9 | 14 | 26 => {
+                let [a] = self.pop_args()?;
+                self.stack.push(match op {
+                    9 => a.abs(),
+                    14 => -a,
+                    _ => a.abs().sqrt(),
+                });
+            }
+            // add, sub, div, mul
+            10 | 11 | 12 | 24 => {
+                let [a, b] = self.pop_args()?;
+                self.stack.push(match op {
+                    10 => a + b,
+                    11 => a - b,
+                    12 if b != 0.0 => a / b,
+                    12 => 0.0,
+                    _ => a * b,
+                });
+            }
+            // drop
+            18 => {
+                self.stack.pop()?;
+            }
+            // put
+            20 => {
+                let [value, index] = self.pop_args()?;
+                *self.transient.get_mut(index as usize)? = value;
+            }
+            // get
+            21 => {
+                let [index] = self.pop_args()?;
+                let value = *self.transient.get(index as usize)?;
+                self.stack.push(value);
+            }
+            // ifelse
+            22 => {
+                let [s1, s2, v1, v2] = self.pop_args()?;
+                self.stack.push(if v1 <= v2 { s1 } else { s2 });
+            }
+            // random: deterministic, in (0, 1]
+            23 => self.stack.push(0.5),
+            // dup
+            27 => {
+                let top = *self.stack.last()?;
+                self.push(top)?;
+            }
+            // exch
+            28 => {
+                let [a, b] = self.pop_args()?;
+                self.stack.extend([b, a]);
+            }
+            // index
+            29 => {
+                let [i] = self.pop_args()?;
+                let i = (i.max(0.0) as usize).min(self.stack.len().checked_sub(1)?);
+                let value = self.stack[self.stack.len() - 1 - i];
+                self.stack.push(value);
+            }
+            // roll
+            30 => {
+                let [n, j] = self.pop_args()?;
+                let n = n as usize;
+                let start = self.stack.len().checked_sub(n)?;
+                if n > 0 {
+                    let shift = (j as i64).rem_euclid(n as i64) as usize;
+                    self.stack[start..].rotate_right(shift);
+                }
+            }
+            // dotsection and reserved operators
+            _ => self.stack.clear(),
+        }
+        Some(())
+    }
+
+    /// Takes exactly `N` operands, clearing the stack.
+    fn take_args<const N: usize>(&mut self) -> Option<[f32; N]> {
+        let args = std::mem::take(&mut self.stack);
+        args.try_into().ok()
+    }
+
+    /// Pops the last `N` operands in push order.
+    fn pop_args<const N: usize>(&mut self) -> Option<[f32; N]> {
+        let start = self.stack.len().checked_sub(N)?;
+        let args = self.stack.split_off(start);
+        args.try_into().ok()
+    }
+
+    /// Draws an accented character from two StandardEncoding glyphs
+    /// (the deprecated seac form of endchar).
+    fn seac(&mut self, adx: f32, ady: f32, bchar: u8, achar: u8, depth: usize) -> Option<()> {
+        let encoding = standard_encoding();
+        let base = self.font.glyph_index_by_name(encoding.get(&bchar)?)?;
+        let accent = self.font.glyph_index_by_name(encoding.get(&achar)?)?;
+        let width = self.width;
+        self.run_glyph(base, 0.0, 0.0, depth + 1)?;
+        self.close_path();
+        self.run_glyph(accent, adx, ady, depth + 1)?;
+        self.close_path();
+        self.width = width;
+        Some(())
+    }
+}
+
+/// The CFF standard strings (Technical Note #5176, Appendix A).
+const STANDARD_STRINGS: [&str; STANDARD_STRING_COUNT] = [
+    ".notdef",
+    "space",
+    "exclam",
+    "quotedbl",
+    "numbersign",
+    "dollar",
+    "percent",
+    "ampersand",
+    "quoteright",
+    "parenleft",
+    "parenright",
+    "asterisk",
+    "plus",
+    "comma",
+    "hyphen",
+    "period",
+    "slash",
+    "zero",
+    "one",
+    "two",
+    "three",
+    "four",
+    "five",
+    "six",
+    "seven",
+    "eight",
+    "nine",
+    "colon",
+    "semicolon",
+    "less",
+    "equal",
+    "greater",
+    "question",
+    "at",
+    "A",
+    "B",
+    "C",
+    "D",
+    "E",
+    "F",
+    "G",
+    "H",
+    "I",
+    "J",
+    "K",
+    "L",
+    "M",
+    "N",
+    "O",
+    "P",
+    "Q",
+    "R",
+    "S",
+    "T",
+    "U",
+    "V",
+    "W",
+    "X",
+    "Y",
+    "Z",
+    "bracketleft",
+    "backslash",
+    "bracketright",
+    "asciicircum",
+    "underscore",
+    "quoteleft",
+    "a",
+    "b",
+    "c",
+    "d",
+    "e",
+    "f",
+    "g",
+    "h",
+    "i",
+    "j",
+    "k",
+    "l",
+    "m",
+    "n",
+    "o",
+    "p",
+    "q",
+    "r",
+    "s",
+    "t",
+    "u",
+    "v",
+    "w",
+    "x",
+    "y",
+    "z",
+    "braceleft",
+    "bar",
+    "braceright",
+    "asciitilde",
+    "exclamdown",
+    "cent",
+    "sterling",
+    "fraction",
+    "yen",
+    "florin",
+    "section",
+    "currency",
+    "quotesingle",
+    "quotedblleft",
+    "guillemotleft",
+    "guilsinglleft",
+    "guilsinglright",
+    "fi",
+    "fl",
+    "endash",
+    "dagger",
+    "daggerdbl",
+    "periodcentered",
+    "paragraph",
+    "bullet",
+    "quotesinglbase",
+    "quotedblbase",
+    "quotedblright",
+    "guillemotright",
+    "ellipsis",
+    "perthousand",
+    "questiondown",
+    "grave",
+    "acute",
+    "circumflex",
+    "tilde",
+    "macron",
+    "breve",
+    "dotaccent",
+    "dieresis",
+    "ring",
+    "cedilla",
+    "hungarumlaut",
+    "ogonek",
+    "caron",
+    "emdash",
+    "AE",
+    "ordfeminine",
+    "Lslash",
+    "Oslash",
+    "OE",
+    "ordmasculine",
+    "ae",
+    "dotlessi",
+    "lslash",
+    "oslash",
+    "oe",
+    "germandbls",
+    "onesuperior",
+    "logicalnot",
+    "mu",
+    "trademark",
+    "Eth",
+    "onehalf",
+    "plusminus",
+    "Thorn",
+    "onequarter",
+    "divide",
+    "brokenbar",
+    "degree",
+    "thorn",
+    "threequarters",
+    "twosuperior",
+    "registered",
+    "minus",
+    "eth",
+    "multiply",
+    "threesuperior",
+    "copyright",
+    "Aacute",
+    "Acircumflex",
+    "Adieresis",
+    "Agrave",
+    "Aring",
+    "Atilde",
+    "Ccedilla",
+    "Eacute",
+    "Ecircumflex",
+    "Edieresis",
+    "Egrave",
+    "Iacute",
+    "Icircumflex",
+    "Idieresis",
+    "Igrave",
+    "Ntilde",
+    "Oacute",
+    "Ocircumflex",
+    "Odieresis",
+    "Ograve",
+    "Otilde",
+    "Scaron",
+    "Uacute",
+    "Ucircumflex",
+    "Udieresis",
+    "Ugrave",
+    "Yacute",
+    "Ydieresis",
+    "Zcaron",
+    "aacute",
+    "acircumflex",
+    "adieresis",
+    "agrave",
+    "aring",
+    "atilde",
+    "ccedilla",
+    "eacute",
+    "ecircumflex",
+    "edieresis",
+    "egrave",
+    "iacute",
+    "icircumflex",
+    "idieresis",
+    "igrave",
+    "ntilde",
+    "oacute",
+    "ocircumflex",
+    "odieresis",
+    "ograve",
+    "otilde",
+    "scaron",
+    "uacute",
+    "ucircumflex",
+    "udieresis",
+    "ugrave",
+    "yacute",
+    "ydieresis",
+    "zcaron",
+    "exclamsmall",
+    "Hungarumlautsmall",
+    "dollaroldstyle",
+    "dollarsuperior",
+    "ampersandsmall",
+    "Acutesmall",
+    "parenleftsuperior",
+    "parenrightsuperior",
+    "twodotenleader",
+    "onedotenleader",
+    "zerooldstyle",
+    "oneoldstyle",
+    "twooldstyle",
+    "threeoldstyle",
+    "fouroldstyle",
+    "fiveoldstyle",
+    "sixoldstyle",
+    "sevenoldstyle",
+    "eightoldstyle",
+    "nineoldstyle",
+    "commasuperior",
+    "threequartersemdash",
+    "periodsuperior",
+    "questionsmall",
+    "asuperior",
+    "bsuperior",
+    "centsuperior",
+    "dsuperior",
+    "esuperior",
+    "isuperior",
+    "lsuperior",
+    "msuperior",
+    "nsuperior",
+    "osuperior",
+    "rsuperior",
+    "ssuperior",
+    "tsuperior",
+    "ff",
+    "ffi",
+    "ffl",
+    "parenleftinferior",
+    "parenrightinferior",
+    "Circumflexsmall",
+    "hyphensuperior",
+    "Gravesmall",
+    "Asmall",
+    "Bsmall",
+    "Csmall",
+    "Dsmall",
+    "Esmall",
+    "Fsmall",
+    "Gsmall",
+    "Hsmall",
+    "Ismall",
+    "Jsmall",
+    "Ksmall",
+    "Lsmall",
+    "Msmall",
+    "Nsmall",
+    "Osmall",
+    "Psmall",
+    "Qsmall",
+    "Rsmall",
+    "Ssmall",
+    "Tsmall",
+    "Usmall",
+    "Vsmall",
+    "Wsmall",
+    "Xsmall",
+    "Ysmall",
+    "Zsmall",
+    "colonmonetary",
+    "onefitted",
+    "rupiah",
+    "Tildesmall",
+    "exclamdownsmall",
+    "centoldstyle",
+    "Lslashsmall",
+    "Scaronsmall",
+    "Zcaronsmall",
+    "Dieresissmall",
+    "Brevesmall",
+    "Caronsmall",
+    "Dotaccentsmall",
+    "Macronsmall",
+    "figuredash",
+    "hypheninferior",
+    "Ogoneksmall",
+    "Ringsmall",
+    "Cedillasmall",
+    "questiondownsmall",
+    "oneeighth",
+    "threeeighths",
+    "fiveeighths",
+    "seveneighths",
+    "onethird",
+    "twothirds",
+    "zerosuperior",
+    "foursuperior",
+    "fivesuperior",
+    "sixsuperior",
+    "sevensuperior",
+    "eightsuperior",
+    "ninesuperior",
+    "zeroinferior",
+    "oneinferior",
+    "twoinferior",
+    "threeinferior",
+    "fourinferior",
+    "fiveinferior",
+    "sixinferior",
+    "seveninferior",
+    "eightinferior",
+    "nineinferior",
+    "centinferior",
+    "dollarinferior",
+    "periodinferior",
+    "commainferior",
+    "Agravesmall",
+    "Aacutesmall",
+    "Acircumflexsmall",
+    "Atildesmall",
+    "Adieresissmall",
+    "Aringsmall",
+    "AEsmall",
+    "Ccedillasmall",
+    "Egravesmall",
+    "Eacutesmall",
+    "Ecircumflexsmall",
+    "Edieresissmall",
+    "Igravesmall",
+    "Iacutesmall",
+    "Icircumflexsmall",
+    "Idieresissmall",
+    "Ethsmall",
+    "Ntildesmall",
+    "Ogravesmall",
+    "Oacutesmall",
+    "Ocircumflexsmall",
+    "Otildesmall",
+    "Odieresissmall",
+    "OEsmall",
+    "Oslashsmall",
+    "Ugravesmall",
+    "Uacutesmall",
+    "Ucircumflexsmall",
+    "Udieresissmall",
+    "Yacutesmall",
+    "Thornsmall",
+    "Ydieresissmall",
+    "001.000",
+    "001.001",
+    "001.002",
+    "001.003",
+    "Black",
+    "Bold",
+    "Book",
+    "Light",
+    "Medium",
+    "Regular",
+    "Roman",
+    "Semibold",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds an INDEX with 1-byte offsets.
+    fn index(entries: &[&[u8]]) -> Vec<u8> {
+        let mut out = (entries.len() as u16).to_be_bytes().to_vec();
+        if entries.is_empty() {
+            return out;
+        }
+        out.push(1);
+        let mut offset = 1u8;
+        out.push(offset);
+        for entry in entries {
+            offset += entry.len() as u8;
+            out.push(offset);
+        }
+        for entry in entries {
+            out.extend_from_slice(entry);
+        }
+        out
+    }
+
+    /// Encodes a DICT integer operand in the 5-byte form.
+    fn int(value: i32) -> Vec<u8> {
+        let mut out = vec![29];
+        out.extend_from_slice(&value.to_be_bytes());
+        out
+    }
+
+    /// Builds a font with .notdef and "A" (a triangle drawn through a local
+    /// subroutine, with a width operand) and a custom encoding for code 65.
+    fn test_font() -> Vec<u8> {
+        // 500 (width) 100 100 rmoveto 300 0 rlineto 0 callsubr endchar
+        let glyph: &[u8] = &[248, 136, 239, 239, 21, 247, 192, 139, 5, 32, 10, 14];
+        // -150 200 rlineto return, then a 300 0 rlineto that is never reached
+        let subr: &[u8] = &[251, 42, 247, 92, 5, 11, 247, 192, 139, 5];
+        let notdef: &[u8] = &[14];
+
+        let header = [1u8, 0, 4, 1];
+        let names = index(&[b"Test"]);
+        let strings = index(&[]);
+        let global_subrs = index(&[]);
+
+        // Top DICT with fixed-size operands so its length doesn't depend on
+        // the offsets it holds
+        let top_len = 3 * (5 + 1) + (5 + 5 + 1);
+        let top_index_len = 2 + 1 + 2 + top_len;
+        let charstrings_offset =
+            header.len() + names.len() + top_index_len + strings.len() + global_subrs.len();
+        let charstrings = index(&[notdef, glyph]);
+        let charset_offset = charstrings_offset + charstrings.len();
+        let charset = [0u8, 0, 34]; // format 0: glyph 1 is SID 34 ("A")
+        let encoding_offset = charset_offset + charset.len();
+        let encoding = [0u8, 1, 65]; // format 0: code 65 is glyph 1
+        let private_offset = encoding_offset + encoding.len();
+        // defaultWidthX 0, nominalWidthX 0, Subrs at offset 6
+        let private = [139u8, 20, 139, 21, 145, 19];
+        let subrs = index(&[subr]);
+
+        let mut top = Vec::new();
+        top.extend(int(charset_offset as i32));
+        top.push(15);
+        top.extend(int(encoding_offset as i32));
+        top.push(16);
+        top.extend(int(charstrings_offset as i32));
+        top.push(17);
+        top.extend(int(private.len() as i32));
+        top.extend(int(private_offset as i32));
+        top.push(18);
+        assert_eq!(top.len(), top_len);
+
+        let mut data = header.to_vec();
+        data.extend(names);
+        data.extend(index(&[&top]));
+        data.extend(strings);
+        data.extend(global_subrs);
+        data.extend(charstrings);
+        data.extend(charset);
+        data.extend(encoding);
+        data.extend(private);
+        data.extend(subrs);
+        data
+    }
+
+    #[derive(Default)]
+    struct Recorder(Vec<String>);
+
+    impl OutlineSink for Recorder {
+        fn move_to(&mut self, x: f32, y: f32) {
+            self.0.push(format!("M{x},{y}"));
+        }
+        fn line_to(&mut self, x: f32, y: f32) {
+            self.0.push(format!("L{x},{y}"));
+        }
+        fn curve_to(&mut self, _x1: f32, _y1: f32, _x2: f32, _y2: f32, x: f32, y: f32) {
+            self.0.push(format!("C{x},{y}"));
+        }
+        fn close(&mut self) {
+            self.0.push("Z".to_string());
+        }
+    }
+
+    #[test]
+    fn test_parse_cff_font() {
+        let font = CffFont::parse(&test_font()).unwrap();
+        assert_eq!(font.font_name.as_deref(), Some("Test"));
+        assert!(!font.is_cid());
+        assert_eq!(font.glyph_count(), 2);
+        assert_eq!(font.glyph_name(1), Some("A"));
+        assert_eq!(font.glyph_index_by_name("A"), Some(1));
+        assert_eq!(font.glyph_index(65), Some(1));
+        assert_eq!(font.glyph_index_for_cid(1), Some(1));
+        assert_eq!(font.glyph_width(1), Some(500.0));
+
+        let mut recorder = Recorder::default();
+        assert_eq!(font.outline(1, &mut recorder), Some(500.0));
+        assert_eq!(recorder.0, ["M100,100", "L400,100", "L250,300", "Z"]);
+    }
+
+    #[test]
+    fn test_dict_operands() {
+        // 1000 (28 form), -1.5e-3 (real), 0.001 (real) FontMatrix-style
+        let dict = parse_dict(&[
+            28, 0x03, 0xE8, 30, 0xE1, 0xA5, 0xC3, 0xFF, 30, 0xA0, 0x01, 0xFF, 17,
+        ]);
+        assert_eq!(dict[&17], [1000.0, -1.5e-3, 0.001]);
+        assert_eq!(STANDARD_STRINGS[34], "A");
+        assert_eq!(STANDARD_STRINGS[390], "Semibold");
+    }
+
+    #[test]
+    fn test_out_of_range_offsets() {
+        let data = test_font();
+        // A Private DICT whose end is past the addressable range
+        let dict = HashMap::from([(18, vec![16.0, usize::MAX as f64])]);
+        assert!(read_private(&data, &dict).subrs.is_empty());
+        // An INDEX whose offset array runs past the data
+        assert!(read_index(&[0, 2, 4, 0, 0, 0, 1], 0).is_none());
+        assert!(read_index(&data, usize::MAX - 1).is_none());
+    }
+}
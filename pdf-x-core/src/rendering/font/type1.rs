@@ -0,0 +1,1037 @@
+//! Type 1 font program parsing.
+//!
+//! Embedded Type 1 fonts (/FontFile) come as a cleartext PostScript part
+//! followed by an eexec-encrypted private part. Stand-alone font files wrap
+//! the two parts in PFB segments (binary) or store the encrypted part as hex
+//! (PFA). This module undoes both encodings, decrypts the private dictionary
+//! and its charstrings, and interprets the charstrings into glyph outlines.
+//!
+//! Hints are ignored; flex and hint replacement are handled through the
+//! standard OtherSubrs 0-3 so fonts using them render correctly.
+//!
+//! Reference: Adobe Type 1 Font Format (Adobe, 1990), chapters 6-8.
+
+use std::collections::HashMap;
+
+use super::{NullSink, OutlineSink};
+
+/// Key for decrypting the eexec-encrypted part of a font program.
+const EEXEC_KEY: u16 = 55665;
+
+/// Key for decrypting individual charstrings and subroutines.
+const CHARSTRING_KEY: u16 = 4330;
+
+/// Maximum nesting of subroutine calls and accented characters.
+const MAX_CALL_DEPTH: usize = 10;
+
+/// A parsed Type 1 font program.
+#[derive(Debug, Clone)]
+pub struct Type1Program {
+    /// The PostScript name of the font (/FontName)
+    pub font_name: Option<String>,
+
+    /// Glyph space to text space transform (/FontMatrix)
+    pub font_matrix: [f64; 6],
+
+    /// The built-in encoding: character code to glyph name (/Encoding)
+    pub encoding: HashMap<u8, String>,
+
+    /// Decrypted charstrings by glyph name
+    charstrings: HashMap<String, Vec<u8>>,
+
+    /// Decrypted subroutines (/Subrs)
+    subrs: Vec<Vec<u8>>,
+}
+
+impl Type1Program {
+    /// Parses a Type 1 font program.
+    ///
+    /// # Arguments
+    /// * `data` - The font program: PFB segments, PFA, or the cleartext and
+    ///   binary parts of an embedded /FontFile stream
+    ///
+    /// # Returns
+    /// The parsed program, or an error if it has no usable charstrings
+    pub fn parse(data: &[u8]) -> Result<Self, String> {
+        let (cleartext, encrypted) = split_program(data)?;
+        let private = decrypt(&encrypted, EEXEC_KEY, 4);
+
+        let mut lexer = Lexer::new(&cleartext);
+        let font_name = lexer
+            .seek("/FontName")
+            .then(|| lexer.token())
+            .flatten()
+            .and_then(|token| token.strip_prefix('/').map(str::to_string));
+
+        let mut lexer = Lexer::new(&cleartext);
+        let font_matrix = if lexer.seek("/FontMatrix") {
+            parse_font_matrix(&mut lexer)
+        } else {
+            None
+        };
+
+        let mut lexer = Lexer::new(&cleartext);
+        let encoding = if lexer.seek("/Encoding") {
+            parse_encoding(&mut lexer)
+        } else {
+            standard_encoding()
+        };
+
+        let mut lexer = Lexer::new(&private);
+        let len_iv = if lexer.seek("/lenIV") {
+            lexer
+                .token()
+                .and_then(|token| token.parse::<i32>().ok())
+                .unwrap_or(4)
+        } else {
+            4
+        };
+
+        let mut lexer = Lexer::new(&private);
+        let subrs = if lexer.seek("/Subrs") {
+            parse_subrs(&mut lexer, len_iv)
+        } else {
+            Vec::new()
+        };
+
+        let mut lexer = Lexer::new(&private);
+        if !lexer.seek("/CharStrings") {
+            return Err("Type1 font has no /CharStrings".to_string());
+        }
+        let charstrings = parse_charstrings(&mut lexer, len_iv);
+        if charstrings.is_empty() {
+            return Err("Type1 font has no charstrings".to_string());
+        }
+
+        Ok(Type1Program {
+            font_name,
+            font_matrix: font_matrix.unwrap_or([0.001, 0.0, 0.0, 0.001, 0.0, 0.0]),
+            encoding,
+            charstrings,
+            subrs,
+        })
+    }
+
+    /// Returns the glyph name for a character code in the built-in encoding.
+    pub fn code_to_name(&self, code: u8) -> Option<&str> {
+        self.encoding.get(&code).map(String::as_str)
+    }
+
+    /// Returns true if the font has a charstring for `name`.
+    pub fn has_glyph(&self, name: &str) -> bool {
+        self.charstrings.contains_key(name)
+    }
+
+    /// Returns the advance width of a glyph in glyph space units.
+    pub fn glyph_width(&self, name: &str) -> Option<f32> {
+        let mut sink = NullSink;
+        let mut interpreter = Interpreter::new(self, &mut sink);
+        interpreter.run_glyph(name, 0.0, 0.0, 0)?;
+        Some(interpreter.width)
+    }
+
+    /// Outlines a glyph by name.
+    ///
+    /// # Arguments
+    /// * `name` - The glyph name (e.g. "A", "exclam")
+    /// * `sink` - Receives the outline in glyph space units
+    ///
+    /// # Returns
+    /// The advance width, or None if the glyph doesn't exist or is malformed
+    pub fn outline(&self, name: &str, sink: &mut dyn OutlineSink) -> Option<f32> {
+        let mut interpreter = Interpreter::new(self, sink);
+        interpreter.run_glyph(name, 0.0, 0.0, 0)?;
+        interpreter.close_path();
+        Some(interpreter.width)
+    }
+}
+
+/// Splits a font program into its cleartext part and its (still encrypted)
+/// binary private part.
+///
+/// Handles PFB segments, hex-encoded private parts (PFA) and the plain
+/// cleartext + binary layout of embedded font streams.
+pub fn split_program(data: &[u8]) -> Result<(Vec<u8>, Vec<u8>), String> {
+    if data.first() == Some(&0x80) {
+        return split_pfb(data);
+    }
+
+    let eexec = find(data, b"eexec").ok_or("Type1 font has no eexec section")?;
+    let cleartext = data[..eexec + 5].to_vec();
+    let mut rest = &data[eexec + 5..];
+    while let Some((first, tail)) = rest.split_first() {
+        if !first.is_ascii_whitespace() {
+            break;
+        }
+        rest = tail;
+    }
+
+    // The private part is hex if its first four bytes are hex digits
+    let is_hex = rest.len() >= 4 && rest[..4].iter().all(u8::is_ascii_hexdigit);
+    let encrypted = if is_hex {
+        decode_hex(rest)
+    } else {
+        rest.to_vec()
+    };
+    Ok((cleartext, encrypted))
+}
+
+/// Splits a PFB file: segments start with 0x80, a type byte (1 = ASCII,
+/// 2 = binary, 3 = end of file) and a little-endian 32-bit length.
+fn split_pfb(data: &[u8]) -> Result<(Vec<u8>, Vec<u8>), String> {
+    let mut cleartext = Vec::new();
+    let mut encrypted = Vec::new();
+    let mut pos = 0;
+    while pos + 2 <= data.len() && data[pos] == 0x80 {
+        let segment_type = data[pos + 1];
+        if segment_type == 3 || pos + 6 > data.len() {
+            break;
+        }
+        let length =
+            u32::from_le_bytes([data[pos + 2], data[pos + 3], data[pos + 4], data[pos + 5]])
+                as usize;
+        let start = pos + 6;
+        let end = start.saturating_add(length).min(data.len());
+        match segment_type {
+            // ASCII segments after the binary part hold the trailing zeros
+            1 if encrypted.is_empty() => cleartext.extend_from_slice(&data[start..end]),
+            1 => {}
+            2 => encrypted.extend_from_slice(&data[start..end]),
+            _ => return Err(format!("Invalid PFB segment type {}", segment_type)),
+        }
+        pos = end;
+    }
+
+    if encrypted.is_empty() {
+        return Err("PFB font has no binary segment".to_string());
+    }
+    Ok((cleartext, encrypted))
+}
+
+/// Decodes hex digits up to the first byte that is neither a digit nor
+/// whitespace.
+fn decode_hex(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len() / 2);
+    let mut high: Option<u8> = None;
+    for &byte in data {
+        let digit = match (byte as char).to_digit(16) {
+            Some(digit) => digit as u8,
+            None if byte.is_ascii_whitespace() => continue,
+            None => break,
+        };
+        match high.take() {
+            Some(h) => out.push(h << 4 | digit),
+            None => high = Some(digit),
+        }
+    }
+    out
+}
+
+/// Decrypts eexec or charstring encrypted data and drops the first `skip`
+/// random bytes.
+///
+/// # Arguments
+/// * `data` - The encrypted bytes
+/// * `key` - 55665 for eexec, 4330 for charstrings
+/// * `skip` - Number of leading bytes to discard (4, or /lenIV)
+pub fn decrypt(data: &[u8], key: u16, skip: usize) -> Vec<u8> {
+    let mut r = key;
+    let plain: Vec<u8> = data
+        .iter()
+        .map(|&cipher| {
+            let plain = cipher ^ (r >> 8) as u8;
+            r = (cipher as u16)
+                .wrapping_add(r)
+                .wrapping_mul(52845)
+                .wrapping_add(22719);
+            plain
+        })
+        .collect();
+    plain.into_iter().skip(skip).collect()
+}
+
+fn decrypt_charstring(data: &[u8], len_iv: i32) -> Vec<u8> {
+    // A negative lenIV means the charstrings are not encrypted
+    if len_iv < 0 {
+        data.to_vec()
+    } else {
+        decrypt(data, CHARSTRING_KEY, len_iv as usize)
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// A minimal PostScript tokenizer that can also read the binary data
+/// following RD/-| tokens.
+struct Lexer<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Lexer { data, pos: 0 }
+    }
+
+    /// Moves past the next occurrence of `keyword`.
+    fn seek(&mut self, keyword: &str) -> bool {
+        match find(&self.data[self.pos..], keyword.as_bytes()) {
+            Some(offset) => {
+                self.pos += offset + keyword.len();
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn is_delimiter(byte: u8) -> bool {
+        matches!(
+            byte,
+            b'/' | b'[' | b']' | b'{' | b'}' | b'(' | b')' | b'<' | b'>' | b'%'
+        )
+    }
+
+    /// Reads the next token. Comments are skipped; names keep their '/'.
+    fn token(&mut self) -> Option<String> {
+        loop {
+            while self.pos < self.data.len() && self.data[self.pos].is_ascii_whitespace() {
+                self.pos += 1;
+            }
+            if self.data.get(self.pos) == Some(&b'%') {
+                while self.pos < self.data.len() && !matches!(self.data[self.pos], b'\r' | b'\n') {
+                    self.pos += 1;
+                }
+                continue;
+            }
+            break;
+        }
+
+        let start = self.pos;
+        let first = *self.data.get(self.pos)?;
+        self.pos += 1;
+        if matches!(first, b'[' | b']' | b'{' | b'}') {
+            return Some((first as char).to_string());
+        }
+        while self.pos < self.data.len()
+            && !self.data[self.pos].is_ascii_whitespace()
+            && !Self::is_delimiter(self.data[self.pos])
+        {
+            self.pos += 1;
+        }
+        Some(String::from_utf8_lossy(&self.data[start..self.pos]).into_owned())
+    }
+
+    /// Reads `<length> RD <binary>` after the length has been consumed.
+    fn binary(&mut self, length: usize) -> Option<&'a [u8]> {
+        self.token()?;
+        // Exactly one space separates RD from the binary data
+        let start = self.pos + 1;
+        let end = start.checked_add(length)?;
+        let bytes = self.data.get(start..end)?;
+        self.pos = end;
+        Some(bytes)
+    }
+}
+
+fn parse_font_matrix(lexer: &mut Lexer) -> Option<[f64; 6]> {
+    let open = lexer.token()?;
+    if open != "[" && open != "{" {
+        return None;
+    }
+    let mut matrix = [0.0; 6];
+    for value in &mut matrix {
+        *value = lexer.token()?.parse().ok()?;
+    }
+    Some(matrix)
+}
+
+fn parse_encoding(lexer: &mut Lexer) -> HashMap<u8, String> {
+    match lexer.token().as_deref() {
+        Some("StandardEncoding") | None => return standard_encoding(),
+        _ => {}
+    }
+
+    // Custom encodings are written as a series of "dup <code> /<name> put"
+    let mut encoding = HashMap::new();
+    while let Some(token) = lexer.token() {
+        match token.as_str() {
+            "dup" => {
+                let code = lexer.token().and_then(|token| token.parse::<u8>().ok());
+                let name = lexer.token();
+                if let (Some(code), Some(name)) = (code, name)
+                    && let Some(name) = name.strip_prefix('/')
+                {
+                    encoding.insert(code, name.to_string());
+                }
+            }
+            "def" | "readonly" => break,
+            _ => {}
+        }
+    }
+    encoding
+}
+
+fn parse_subrs(lexer: &mut Lexer, len_iv: i32) -> Vec<Vec<u8>> {
+    let count = lexer
+        .token()
+        .and_then(|token| token.parse::<usize>().ok())
+        .unwrap_or(0);
+    let mut subrs = vec![Vec::new(); count.min(u16::MAX as usize)];
+    while let Some(token) = lexer.token() {
+        match token.as_str() {
+            "dup" => {
+                let index = lexer.token().and_then(|token| token.parse::<usize>().ok());
+                let length = lexer.token().and_then(|token| token.parse::<usize>().ok());
+                let (Some(index), Some(length)) = (index, length) else {
+                    break;
+                };
+                let Some(bytes) = lexer.binary(length) else {
+                    break;
+                };
+                if index < subrs.len() {
+                    subrs[index] = decrypt_charstring(bytes, len_iv);
+                }
+            }
+            "array" | "NP" | "|" | "noaccess" | "put" => {}
+            _ => break,
+        }
+    }
+    subrs
+}
+
+fn parse_charstrings(lexer: &mut Lexer, len_iv: i32) -> HashMap<String, Vec<u8>> {
+    let mut charstrings = HashMap::new();
+    while let Some(token) = lexer.token() {
+        if token == "begin" {
+            break;
+        }
+    }
+    while let Some(token) = lexer.token() {
+        if token == "end" {
+            break;
+        }
+        let Some(name) = token.strip_prefix('/') else {
+            continue;
+        };
+        let Some(length) = lexer.token().and_then(|token| token.parse::<usize>().ok()) else {
+            break;
+        };
+        let Some(bytes) = lexer.binary(length) else {
+            break;
+        };
+        charstrings.insert(name.to_string(), decrypt_charstring(bytes, len_iv));
+    }
+    charstrings
+}
+
+/// Glyph names of Adobe StandardEncoding.
+pub(super) fn standard_encoding() -> HashMap<u8, String> {
+    const ASCII: [&str; 95] = [
+        "space",
+        "exclam",
+        "quotedbl",
+        "numbersign",
+        "dollar",
+        "percent",
+        "ampersand",
+        "quoteright",
+        "parenleft",
+        "parenright",
+        "asterisk",
+        "plus",
+        "comma",
+        "hyphen",
+        "period",
+        "slash",
+        "zero",
+        "one",
+        "two",
+        "three",
+        "four",
+        "five",
+        "six",
+        "seven",
+        "eight",
+        "nine",
+        "colon",
+        "semicolon",
+        "less",
+        "equal",
+        "greater",
+        "question",
+        "at",
+        "A",
+        "B",
+        "C",
+        "D",
+        "E",
+        "F",
+        "G",
+        "H",
+        "I",
+        "J",
+        "K",
+        "L",
+        "M",
+        "N",
+        "O",
+        "P",
+        "Q",
+        "R",
+        "S",
+        "T",
+        "U",
+        "V",
+        "W",
+        "X",
+        "Y",
+        "Z",
+        "bracketleft",
+        "backslash",
+        "bracketright",
+        "asciicircum",
+        "underscore",
+        "quoteleft",
+        "a",
+        "b",
+        "c",
+        "d",
+        "e",
+        "f",
+        "g",
+        "h",
+        "i",
+        "j",
+        "k",
+        "l",
+        "m",
+        "n",
+        "o",
+        "p",
+        "q",
+        "r",
+        "s",
+        "t",
+        "u",
+        "v",
+        "w",
+        "x",
+        "y",
+        "z",
+        "braceleft",
+        "bar",
+        "braceright",
+        "asciitilde",
+    ];
+    const HIGH: [(u8, &str); 54] = [
+        (0xA1, "exclamdown"),
+        (0xA2, "cent"),
+        (0xA3, "sterling"),
+        (0xA4, "fraction"),
+        (0xA5, "yen"),
+        (0xA6, "florin"),
+        (0xA7, "section"),
+        (0xA8, "currency"),
+        (0xA9, "quotesingle"),
+        (0xAA, "quotedblleft"),
+        (0xAB, "guillemotleft"),
+        (0xAC, "guilsinglleft"),
+        (0xAD, "guilsinglright"),
+        (0xAE, "fi"),
+        (0xAF, "fl"),
+        (0xB1, "endash"),
+        (0xB2, "dagger"),
+        (0xB3, "daggerdbl"),
+        (0xB4, "periodcentered"),
+        (0xB6, "paragraph"),
+        (0xB7, "bullet"),
+        (0xB8, "quotesinglbase"),
+        (0xB9, "quotedblbase"),
+        (0xBA, "quotedblright"),
+        (0xBB, "guillemotright"),
+        (0xBC, "ellipsis"),
+        (0xBD, "perthousand"),
+        (0xBF, "questiondown"),
+        (0xC1, "grave"),
+        (0xC2, "acute"),
+        (0xC3, "circumflex"),
+        (0xC4, "tilde"),
+        (0xC5, "macron"),
+        (0xC6, "breve"),
+        (0xC7, "dotaccent"),
+        (0xC8, "dieresis"),
+        (0xCA, "ring"),
+        (0xCB, "cedilla"),
+        (0xCD, "hungarumlaut"),
+        (0xCE, "ogonek"),
+        (0xCF, "caron"),
+        (0xD0, "emdash"),
+        (0xE1, "AE"),
+        (0xE3, "ordfeminine"),
+        (0xE8, "Lslash"),
+        (0xE9, "Oslash"),
+        (0xEA, "OE"),
+        (0xEB, "ordmasculine"),
+        (0xF1, "ae"),
+        (0xF5, "dotlessi"),
+        (0xF8, "lslash"),
+        (0xF9, "oslash"),
+        (0xFA, "oe"),
+        (0xFB, "germandbls"),
+    ];
+
+    (32u8..)
+        .zip(ASCII)
+        .chain(HIGH)
+        .map(|(code, name)| (code, name.to_string()))
+        .collect()
+}
+
+/// Type 1 charstring interpreter.
+struct Interpreter<'a> {
+    program: &'a Type1Program,
+    sink: &'a mut dyn OutlineSink,
+    stack: Vec<f32>,
+    /// Results of OtherSubrs, retrieved with `pop`
+    ps_stack: Vec<f32>,
+    x: f32,
+    y: f32,
+    /// Offset applied to every point (the accent position in seac)
+    origin: (f32, f32),
+    /// Left sidebearing x of the current glyph
+    sbx: f32,
+    width: f32,
+    path_open: bool,
+    flex_points: Option<Vec<(f32, f32)>>,
+    /// Set when the glyph's endchar has been reached
+    finished: bool,
+}
+
+impl<'a> Interpreter<'a> {
+    fn new(program: &'a Type1Program, sink: &'a mut dyn OutlineSink) -> Self {
+        Interpreter {
+            program,
+            sink,
+            stack: Vec::new(),
+            ps_stack: Vec::new(),
+            x: 0.0,
+            y: 0.0,
+            origin: (0.0, 0.0),
+            sbx: 0.0,
+            width: 0.0,
+            path_open: false,
+            flex_points: None,
+            finished: false,
+        }
+    }
+
+    /// Runs the charstring of glyph `name` with its origin at (dx, dy).
+    fn run_glyph(&mut self, name: &str, dx: f32, dy: f32, depth: usize) -> Option<()> {
+        let charstring = self.program.charstrings.get(name)?;
+        self.origin = (dx, dy);
+        self.stack.clear();
+        self.finished = false;
+        self.execute(charstring, depth)
+    }
+
+    fn move_to(&mut self) {
+        self.close_path();
+        self.sink
+            .move_to(self.x + self.origin.0, self.y + self.origin.1);
+        self.path_open = true;
+    }
+
+    fn line_to(&mut self, dx: f32, dy: f32) {
+        self.x += dx;
+        self.y += dy;
+        self.sink
+            .line_to(self.x + self.origin.0, self.y + self.origin.1);
+    }
+
+    fn curve_to(&mut self, d: [f32; 6]) {
+        let (ox, oy) = self.origin;
+        let x1 = self.x + d[0];
+        let y1 = self.y + d[1];
+        let x2 = x1 + d[2];
+        let y2 = y1 + d[3];
+        self.x = x2 + d[4];
+        self.y = y2 + d[5];
+        self.sink
+            .curve_to(x1 + ox, y1 + oy, x2 + ox, y2 + oy, self.x + ox, self.y + oy);
+    }
+
+    fn close_path(&mut self) {
+        if self.path_open {
+            self.sink.close();
+            self.path_open = false;
+        }
+    }
+
+    /// Relative move; while collecting flex points only the current point moves.
+    fn rmoveto(&mut self, dx: f32, dy: f32) {
+        self.x += dx;
+        self.y += dy;
+        if self.flex_points.is_none() {
+            self.move_to();
+        }
+    }
+
+    fn execute(&mut self, charstring: &[u8], depth: usize) -> Option<()> {
+        if depth > MAX_CALL_DEPTH {
+            return None;
+        }
+
+        let mut pos = 0;
+        while pos < charstring.len() && !self.finished {
+            let byte = charstring[pos];
+            pos += 1;
+            match byte {
+                32..=246 => self.stack.push(byte as f32 - 139.0),
+                247..=250 => {
+                    let next = *charstring.get(pos)? as f32;
+                    pos += 1;
+                    self.stack
+                        .push((byte as f32 - 247.0) * 256.0 + next + 108.0);
+                }
+                251..=254 => {
+                    let next = *charstring.get(pos)? as f32;
+                    pos += 1;
+                    self.stack
+                        .push(-(byte as f32 - 251.0) * 256.0 - next - 108.0);
+                }
+                255 => {
+                    let bytes = charstring.get(pos..pos + 4)?;
+                    pos += 4;
+                    let value = i32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+                    self.stack.push(value as f32);
+                }
+                12 => {
+                    let op = *charstring.get(pos)?;
+                    pos += 1;
+                    self.escape(op, depth)?;
+                }
+                // return: back to the caller of the subroutine
+                11 => break,
+                _ => self.operator(byte, depth)?,
+            }
+        }
+        Some(())
+    }
+
+    /// Pops the last `N` operands in push order.
+    fn args<const N: usize>(&mut self) -> Option<[f32; N]> {
+        let start = self.stack.len().checked_sub(N)?;
+        let mut args = [0.0; N];
+        args.copy_from_slice(&self.stack[start..]);
+        self.stack.truncate(start);
+        Some(args)
+    }
+
+    fn operator(&mut self, op: u8, depth: usize) -> Option<()> {
+        match op {
+            // hstem, vstem: hints are ignored
+            1 | 3 => self.stack.clear(),
+            // vmoveto
+            4 => {
+                let [dy] = self.args()?;
+                self.rmoveto(0.0, dy);
+            }
+            // rlineto
+            5 => {
+                let [dx, dy] = self.args()?;
+                self.line_to(dx, dy);
+            }
+            // hlineto
+            6 => {
+                let [dx] = self.args()?;
+                self.line_to(dx, 0.0);
+            }
+            // vlineto
+            7 => {
+                let [dy] = self.args()?;
+                self.line_to(0.0, dy);
+            }
+            // rrcurveto
+            8 => {
+                let d = self.args::<6>()?;
+                self.curve_to(d);
+            }
+            // closepath
+            9 => self.close_path(),
+            // callsubr
+            10 => {
+                let [index] = self.args()?;
+                let subr = self.program.subrs.get(index as usize)?;
+                self.execute(subr, depth + 1)?;
+            }
+            // hsbw
+            13 => {
+                let [sbx, wx] = self.args()?;
+                self.sbx = sbx;
+                self.width = wx;
+                self.x = sbx;
+                self.y = 0.0;
+            }
+            // endchar
+            14 => {
+                self.close_path();
+                self.finished = true;
+            }
+            // rmoveto
+            21 => {
+                let [dx, dy] = self.args()?;
+                self.rmoveto(dx, dy);
+            }
+            // hmoveto
+            22 => {
+                let [dx] = self.args()?;
+                self.rmoveto(dx, 0.0);
+            }
+            // vhcurveto
+            30 => {
+                let [dy1, dx2, dy2, dx3] = self.args()?;
+                self.curve_to([0.0, dy1, dx2, dy2, dx3, 0.0]);
+            }
+            // hvcurveto
+            31 => {
+                let [dx1, dx2, dy2, dy3] = self.args()?;
+                self.curve_to([dx1, 0.0, dx2, dy2, 0.0, dy3]);
+            }
+            _ => self.stack.clear(),
+        }
+        Some(())
+    }
+
+    fn escape(&mut self, op: u8, depth: usize) -> Option<()> {
+        match op {
+            // dotsection, vstem3, hstem3
+            0..=2 => self.stack.clear(),
+            // seac
+            6 => {
+                let [asb, adx, ady, bchar, achar] = self.args()?;
+                let encoding = standard_encoding();
+                let base = encoding.get(&(bchar as u8))?;
+                let accent = encoding.get(&(achar as u8))?;
+                let (sbx, width) = (self.sbx, self.width);
+                self.run_glyph(base, 0.0, 0.0, depth + 1)?;
+                self.close_path();
+                self.run_glyph(accent, adx + sbx - asb, ady, depth + 1)?;
+                // The composite keeps the metrics of the seac glyph
+                self.sbx = sbx;
+                self.width = width;
+                self.finished = true;
+            }
+            // sbw
+            7 => {
+                let [sbx, sby, wx, _wy] = self.args()?;
+                self.sbx = sbx;
+                self.width = wx;
+                self.x = sbx;
+                self.y = sby;
+            }
+            // div
+            12 => {
+                let [a, b] = self.args()?;
+                self.stack.push(if b != 0.0 { a / b } else { 0.0 });
+            }
+            // callothersubr
+            16 => {
+                let [count, index] = self.args()?;
+                let start = self.stack.len().checked_sub(count as usize)?;
+                let args = self.stack.split_off(start);
+                self.other_subr(index as i32, args);
+            }
+            // pop
+            17 => {
+                let value = self.ps_stack.pop().unwrap_or(0.0);
+                self.stack.push(value);
+            }
+            // setcurrentpoint
+            33 => {
+                let [x, y] = self.args()?;
+                self.x = x;
+                self.y = y;
+            }
+            _ => self.stack.clear(),
+        }
+        Some(())
+    }
+
+    /// Implements the standard OtherSubrs: 0-2 build flex curves and 3 is
+    /// hint replacement. Unknown OtherSubrs return their arguments.
+    fn other_subr(&mut self, index: i32, args: Vec<f32>) {
+        match index {
+            // Start flex
+            1 => self.flex_points = Some(Vec::new()),
+            // Add a flex point
+            2 => {
+                if let Some(points) = &mut self.flex_points {
+                    points.push((self.x, self.y));
+                }
+            }
+            // End flex: the first point is the reference point, the next six
+            // are the control and end points of two curves
+            0 => {
+                if let Some(points) = self.flex_points.take()
+                    && points.len() >= 7
+                {
+                    let (ox, oy) = self.origin;
+                    for curve in points[1..7].chunks(3) {
+                        self.sink.curve_to(
+                            curve[0].0 + ox,
+                            curve[0].1 + oy,
+                            curve[1].0 + ox,
+                            curve[1].1 + oy,
+                            curve[2].0 + ox,
+                            curve[2].1 + oy,
+                        );
+                    }
+                    (self.x, self.y) = points[6];
+                }
+                // "pop pop setcurrentpoint" follows
+                self.ps_stack = vec![self.y, self.x];
+            }
+            _ => self.ps_stack = args.into_iter().rev().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Encrypts data the way eexec and charstrings are encrypted.
+    fn encrypt(plain: &[u8], key: u16) -> Vec<u8> {
+        let mut r = key;
+        plain
+            .iter()
+            .map(|&p| {
+                let cipher = p ^ (r >> 8) as u8;
+                r = (cipher as u16)
+                    .wrapping_add(r)
+                    .wrapping_mul(52845)
+                    .wrapping_add(22719);
+                cipher
+            })
+            .collect()
+    }
+
+    fn charstring(ops: &[u8]) -> Vec<u8> {
+        let mut plain = vec![0u8; 4];
+        plain.extend_from_slice(ops);
+        encrypt(&plain, CHARSTRING_KEY)
+    }
+
+    /// Builds the eexec-encrypted private part of a font with one subroutine
+    /// and the glyphs "A" (a triangle) and ".notdef".
+    fn private_part() -> Vec<u8> {
+        // 0 500 hsbw 100 100 rmoveto 300 hlineto 0 callsubr endchar
+        // Numbers: v-139 for -107..107, two-byte form above that
+        let glyph = charstring(&[
+            139, 248, 136, 13, // 0 500 hsbw
+            239, 239, 21, // 100 100 rmoveto
+            247, 192, 6, // 300 hlineto
+            139, 10, // 0 callsubr
+            14, // endchar
+        ]);
+        // -150 200 rlineto closepath return, then a 300 hlineto that is
+        // never reached
+        let subr = charstring(&[251, 42, 247, 92, 5, 9, 11, 247, 192, 6]);
+        let notdef = charstring(&[139, 139, 13, 14]);
+
+        let mut private = b"dup /Private 8 dict dup begin /lenIV 4 def\n/Subrs 1 array\n".to_vec();
+        private.extend_from_slice(format!("dup 0 {} RD ", subr.len()).as_bytes());
+        private.extend_from_slice(&subr);
+        private.extend_from_slice(b" NP\nND\n2 index /CharStrings 2 dict dup begin\n");
+        private.extend_from_slice(format!("/A {} RD ", glyph.len()).as_bytes());
+        private.extend_from_slice(&glyph);
+        private.extend_from_slice(format!(" ND\n/.notdef {} RD ", notdef.len()).as_bytes());
+        private.extend_from_slice(&notdef);
+        private.extend_from_slice(b" ND\nend\nend\n");
+
+        let mut plain = vec![0u8; 4];
+        plain.extend_from_slice(&private);
+        encrypt(&plain, EEXEC_KEY)
+    }
+
+    const CLEARTEXT: &[u8] = b"%!PS-AdobeFont-1.0: Test 001\n/FontName /Test def\n\
+        /FontMatrix [0.001 0 0 0.001 0 0] readonly def\n\
+        /Encoding 256 array 0 1 255 {1 index exch /.notdef put} for\n\
+        dup 65 /A put\nreadonly def\ncurrentdict end\ncurrentfile eexec\n";
+
+    #[derive(Default)]
+    struct Recorder(Vec<String>);
+
+    impl OutlineSink for Recorder {
+        fn move_to(&mut self, x: f32, y: f32) {
+            self.0.push(format!("M{x},{y}"));
+        }
+        fn line_to(&mut self, x: f32, y: f32) {
+            self.0.push(format!("L{x},{y}"));
+        }
+        fn curve_to(&mut self, _x1: f32, _y1: f32, _x2: f32, _y2: f32, x: f32, y: f32) {
+            self.0.push(format!("C{x},{y}"));
+        }
+        fn close(&mut self) {
+            self.0.push("Z".to_string());
+        }
+    }
+
+    #[test]
+    fn test_decrypt_round_trip() {
+        let plain = b"abcd/lenIV 4 def";
+        let cipher = encrypt(plain, EEXEC_KEY);
+        assert_eq!(decrypt(&cipher, EEXEC_KEY, 4), b"/lenIV 4 def");
+    }
+
+    #[test]
+    fn test_parse_embedded_and_pfb_programs() {
+        let private = private_part();
+        let mut embedded = CLEARTEXT.to_vec();
+        embedded.extend_from_slice(&private);
+
+        // PFB: ASCII segment, binary segment, EOF marker
+        let mut pfb = vec![0x80, 1];
+        pfb.extend_from_slice(&(CLEARTEXT.len() as u32).to_le_bytes());
+        pfb.extend_from_slice(CLEARTEXT);
+        pfb.extend_from_slice(&[0x80, 2]);
+        pfb.extend_from_slice(&(private.len() as u32).to_le_bytes());
+        pfb.extend_from_slice(&private);
+        pfb.extend_from_slice(&[0x80, 3]);
+
+        // PFA: hex-encoded private part
+        let mut pfa = CLEARTEXT.to_vec();
+        for line in private.chunks(32) {
+            let hex: String = line.iter().map(|b| format!("{:02x}", b)).collect();
+            pfa.extend_from_slice(hex.as_bytes());
+            pfa.push(b'\n');
+        }
+        pfa.extend_from_slice(b"0000000000\ncleartomark\n");
+
+        for data in [embedded, pfb, pfa] {
+            let program = Type1Program::parse(&data).unwrap();
+            assert_eq!(program.font_name.as_deref(), Some("Test"));
+            assert_eq!(program.font_matrix[0], 0.001);
+            assert_eq!(program.code_to_name(65), Some("A"));
+            assert!(program.has_glyph(".notdef"));
+            assert_eq!(program.glyph_width("A"), Some(500.0));
+
+            let mut recorder = Recorder::default();
+            assert_eq!(program.outline("A", &mut recorder), Some(500.0));
+            assert_eq!(recorder.0, ["M100,100", "L400,100", "L250,300", "Z"]);
+        }
+    }
+
+    #[test]
+    fn test_standard_encoding() {
+        let encoding = standard_encoding();
+        assert_eq!(encoding[&32], "space");
+        assert_eq!(encoding[&39], "quoteright");
+        assert_eq!(encoding[&126], "asciitilde");
+        assert_eq!(encoding[&0xFB], "germandbls");
+    }
+}
@@ -0,0 +1,429 @@
+//! Font substitution for fonts that are not embedded.
+//!
+//! When a PDF font has no usable embedded program, the renderer asks a
+//! [`FontResolver`] for replacement font data. The resolver receives a
+//! [`FontQuery`] describing the font: its BaseFont name, its class (serif,
+//! sans-serif, monospace or symbolic) and its style, taken from the
+//! /FontDescriptor where the name alone doesn't tell.
+//!
+//! [`DefaultFontResolver`] maps fonts onto common system fonts and the
+//! Liberation fonts bundled with the crate. Applications that ship their own
+//! fonts implement [`FontResolver`] and pass it to
+//! `PDFDocument::set_font_resolver` or `Page::render_with_font_resolver`.
+//!
+//! Based on PDF.js's src/core/standard_fonts.js and the substitution in
+//! src/core/fonts.js (Font.fallbackToSystemFont)
+
+use crate::core::font::FontDict;
+use crate::core::parser::PDFObject;
+use crate::core::xref::XRef;
+
+/// /Flags bits of a font descriptor (PDF spec Table 121).
+const FLAG_FIXED_PITCH: i64 = 1 << 0;
+const FLAG_SERIF: i64 = 1 << 1;
+const FLAG_SYMBOLIC: i64 = 1 << 2;
+const FLAG_NONSYMBOLIC: i64 = 1 << 5;
+const FLAG_ITALIC: i64 = 1 << 6;
+const FLAG_FORCE_BOLD: i64 = 1 << 18;
+
+/// Broad design class of a font, used to pick a substitute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FontClass {
+    /// Serif text font (Times, Computer Modern, ...)
+    Serif,
+    /// Sans-serif text font (Helvetica, Arial, ...)
+    SansSerif,
+    /// Fixed-pitch font (Courier, ...)
+    Monospace,
+    /// Font with a non-Latin character set (Symbol, ZapfDingbats, ...)
+    Symbolic,
+}
+
+/// Description of a font that needs a substitute.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FontQuery {
+    /// BaseFont name without a subset prefix ("ABCDEF+Foo" becomes "Foo")
+    pub base_font: String,
+    /// /Flags of the font descriptor, or 0 if the font has none
+    pub flags: i64,
+    /// Design class, from the name for well-known families and otherwise
+    /// from the descriptor flags
+    pub class: FontClass,
+    /// Whether the font is bold
+    pub bold: bool,
+    /// Whether the font is italic or oblique
+    pub italic: bool,
+}
+
+impl FontQuery {
+    /// Builds a query from a BaseFont name and font descriptor /Flags.
+    ///
+    /// # Arguments
+    /// * `base_font` - The BaseFont name, with or without subset prefix
+    /// * `flags` - The descriptor's /Flags, or `None` without a descriptor
+    pub fn new(base_font: &str, flags: Option<i64>) -> Self {
+        let name = match base_font.find('+') {
+            Some(idx) => &base_font[idx + 1..],
+            None => base_font,
+        };
+        let bits = flags.unwrap_or(0);
+
+        let class = Self::class_from_name(name).unwrap_or(match flags {
+            Some(f) if f & FLAG_FIXED_PITCH != 0 => FontClass::Monospace,
+            Some(f) if f & FLAG_SYMBOLIC != 0 && f & FLAG_NONSYMBOLIC == 0 => FontClass::Symbolic,
+            Some(f) if f & FLAG_SERIF != 0 => FontClass::Serif,
+            Some(_) => FontClass::SansSerif,
+            // Nothing to go on; serif is the safest default for body text
+            None => FontClass::Serif,
+        });
+
+        let lower = name.to_ascii_lowercase();
+        let bold = bits & FLAG_FORCE_BOLD != 0
+            || ["bold", "black", "heavy", "demi", "semibold"]
+                .iter()
+                .any(|w| lower.contains(w));
+        let italic =
+            bits & FLAG_ITALIC != 0 || lower.contains("italic") || lower.contains("oblique");
+
+        FontQuery {
+            base_font: name.to_string(),
+            flags: bits,
+            class,
+            bold,
+            italic,
+        }
+    }
+
+    /// Builds a query for a parsed font dictionary, reading /Flags from its
+    /// font descriptor.
+    pub fn from_font_dict(font_dict: &FontDict, xref: &mut XRef) -> Self {
+        let flags = font_dict
+            .font_descriptor
+            .as_ref()
+            .and_then(|descriptor| xref.fetch_if_ref(descriptor).ok())
+            .and_then(|descriptor| match descriptor {
+                PDFObject::Dictionary(dict) => match dict.get("Flags") {
+                    Some(PDFObject::Number(flags)) => Some(*flags as i64),
+                    _ => None,
+                },
+                _ => None,
+            });
+        FontQuery::new(&font_dict.base_font, flags)
+    }
+
+    /// Classifies well-known font families by name.
+    fn class_from_name(name: &str) -> Option<FontClass> {
+        let class = match name {
+            s if s.starts_with("Symbol") || s.starts_with("ZapfDingbats") => FontClass::Symbolic,
+            s if s.starts_with("Times") => FontClass::Serif,
+            s if s.starts_with("Helvetica") || s.starts_with("Arial") => FontClass::SansSerif,
+            s if s.starts_with("Courier") => FontClass::Monospace,
+
+            // PostScript and open source clones of the standard fonts
+            s if s.starts_with("NimbusRomNo9L")
+                || s.starts_with("LiberationSerif")
+                || s.starts_with("TeXGyreTermes")
+                || s.starts_with("URWPalladio") =>
+            {
+                FontClass::Serif
+            }
+            s if s.starts_with("NimbusSans")
+                || s.starts_with("LiberationSans")
+                || s.starts_with("TeXGyreHeros")
+                || s.starts_with("URWGothic") =>
+            {
+                FontClass::SansSerif
+            }
+            s if s.starts_with("NimbusMono")
+                || s.starts_with("LiberationMono")
+                || s.starts_with("TeXGyreCursor")
+                || s.starts_with("URWCourier") =>
+            {
+                FontClass::Monospace
+            }
+
+            // Latin Modern (modern Computer Modern)
+            s if s.starts_with("LatinModern")
+                || s.starts_with("LMRoman")
+                || s.starts_with("LMSans")
+                || s.starts_with("LMTypewriter") =>
+            {
+                if s.contains("Sans") {
+                    FontClass::SansSerif
+                } else if s.contains("Mono") || s.contains("Typewriter") {
+                    FontClass::Monospace
+                } else {
+                    FontClass::Serif
+                }
+            }
+
+            // Computer Modern (TeX/LaTeX)
+            s if s.contains("ComputerModern")
+                || s.contains("Computer Modern")
+                || s.starts_with("CM") =>
+            {
+                FontClass::Serif
+            }
+
+            s if s.contains("Mono") || s.contains("Code") || s.contains("Typewriter") => {
+                FontClass::Monospace
+            }
+            s if s.contains("Sans") => FontClass::SansSerif,
+            s if s.contains("Roman") || s.contains("Serif") || s.contains("Book") => {
+                FontClass::Serif
+            }
+            _ => return None,
+        };
+        Some(class)
+    }
+}
+
+/// Chooses replacement font data for fonts that are not embedded.
+///
+/// Implementations must be deterministic for a given query if rendering
+/// output is to be reproducible.
+///
+/// # Example
+/// ```no_run
+/// use pdf_x_core::PDFDocument;
+/// use pdf_x_core::rendering::{FontClass, FontQuery, FontResolver};
+/// use std::sync::Arc;
+///
+/// struct BrandFonts;
+///
+/// impl FontResolver for BrandFonts {
+///     fn resolve(&self, query: &FontQuery) -> Option<Vec<u8>> {
+///         let file = match (query.class, query.bold) {
+///             (FontClass::Monospace, _) => "BrandMono.ttf",
+///             (_, true) => "BrandSans-Bold.ttf",
+///             _ => "BrandSans.ttf",
+///         };
+///         std::fs::read(file).ok()
+///     }
+/// }
+///
+/// let mut doc = PDFDocument::open_file("document.pdf", None, None).unwrap();
+/// doc.set_font_resolver(Arc::new(BrandFonts));
+/// let svg = doc.render_page_to_svg(0, None).unwrap();
+/// ```
+pub trait FontResolver: Send + Sync {
+    /// Returns TrueType or OpenType data to render the queried font with,
+    /// or `None` to leave the font unloaded.
+    fn resolve(&self, query: &FontQuery) -> Option<Vec<u8>>;
+}
+
+/// The built-in substitution policy.
+///
+/// Looks for a metric-compatible system font of the query's class and style
+/// (Liberation, DejaVu, FreeFont, Noto, ...) on Linux, then for the
+/// Liberation fonts bundled in the crate's assets. Symbolic fonts get no
+/// substitute, since a Latin font would draw the wrong glyphs for them.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultFontResolver;
+
+impl DefaultFontResolver {
+    /// Font file names to try for a class and style, best match first.
+    fn system_font_files(class: FontClass, bold: bool, italic: bool) -> &'static [&'static str] {
+        match (class, bold, italic) {
+            (FontClass::SansSerif, false, false) => &[
+                "LiberationSans-Regular.ttf",
+                "LiberationSans.ttf",
+                "DejaVuSans.ttf",
+                "FreeSans.ttf",
+                "NotoSans-Regular.ttf",
+                "NimbusSans-Regular.ttf",
+            ],
+            (FontClass::SansSerif, true, false) => &[
+                "LiberationSans-Bold.ttf",
+                "DejaVuSans-Bold.ttf",
+                "FreeSansBold.ttf",
+                "NotoSans-Bold.ttf",
+            ],
+            (FontClass::SansSerif, false, true) => &[
+                "LiberationSans-Italic.ttf",
+                "DejaVuSans-Oblique.ttf",
+                "FreeSansOblique.ttf",
+                "NotoSans-Italic.ttf",
+            ],
+            (FontClass::SansSerif, true, true) => &[
+                "LiberationSans-BoldItalic.ttf",
+                "DejaVuSans-BoldOblique.ttf",
+                "FreeSansBoldOblique.ttf",
+                "NotoSans-BoldItalic.ttf",
+            ],
+            (FontClass::Monospace, false, false) => &[
+                "LiberationMono-Regular.ttf",
+                "LiberationMono.ttf",
+                "DejaVuSansMono.ttf",
+                "FreeMono.ttf",
+                "NotoMono-Regular.ttf",
+                "NimbusMono-Regular.ttf",
+                "Courier10PitchBT-Roman.ttf",
+            ],
+            (FontClass::Monospace, true, false) => &[
+                "LiberationMono-Bold.ttf",
+                "DejaVuSansMono-Bold.ttf",
+                "FreeMonoBold.ttf",
+                "NotoMono-Bold.ttf",
+            ],
+            (FontClass::Monospace, false, true) => &[
+                "LiberationMono-Italic.ttf",
+                "DejaVuSansMono-Oblique.ttf",
+                "FreeMonoOblique.ttf",
+            ],
+            (FontClass::Monospace, true, true) => &[
+                "LiberationMono-BoldItalic.ttf",
+                "DejaVuSansMono-BoldOblique.ttf",
+                "FreeMonoBoldOblique.ttf",
+            ],
+            (_, false, false) => &[
+                "LiberationSerif-Regular.ttf",
+                "LiberationSerif.ttf",
+                "DejaVuSerif.ttf",
+                "FreeSerif.ttf",
+                "NotoSerif-Regular.ttf",
+                "NimbusRomNo9L-Regular.ttf",
+                "NimbusRoman-Regular.ttf",
+            ],
+            (_, true, false) => &[
+                "LiberationSerif-Bold.ttf",
+                "DejaVuSerif-Bold.ttf",
+                "FreeSerifBold.ttf",
+                "NotoSerif-Bold.ttf",
+            ],
+            (_, false, true) => &[
+                "LiberationSerif-Italic.ttf",
+                "DejaVuSerif-Italic.ttf",
+                "FreeSerifItalic.ttf",
+                "NotoSerif-Italic.ttf",
+            ],
+            (_, true, true) => &[
+                "LiberationSerif-BoldItalic.ttf",
+                "DejaVuSerif-BoldItalic.ttf",
+                "FreeSerifBoldItalic.ttf",
+                "NotoSerif-BoldItalic.ttf",
+            ],
+        }
+    }
+
+    /// Directories searched for system fonts.
+    #[cfg(target_os = "linux")]
+    const FONT_DIRS: &'static [&'static str] = &[
+        "/usr/share/fonts/truetype",
+        "/usr/share/fonts/truetype/dejavu",
+        "/usr/share/fonts/truetype/liberation",
+        "/usr/share/fonts/truetype/freefont",
+        "/usr/share/fonts/truetype/noto",
+        "/usr/share/fonts/opentype/noto",
+        "/usr/share/fonts/truetype/lmodern",
+        "/usr/share/fonts/truetype/cmu",
+        "/usr/share/fonts/truetype/cm-unicode",
+        "/usr/share/fonts/opentype/cm-unicode",
+        "/usr/share/fonts/truetype/computer-modern",
+        "/usr/share/fonts/opentype/public-lm",
+        "/usr/share/fonts/TTF",
+        "/usr/share/fonts",
+        "/usr/local/share/fonts",
+    ];
+}
+
+impl FontResolver for DefaultFontResolver {
+    fn resolve(&self, query: &FontQuery) -> Option<Vec<u8>> {
+        if query.class == FontClass::Symbolic {
+            return None;
+        }
+        let files = Self::system_font_files(query.class, query.bold, query.italic);
+
+        #[cfg(target_os = "linux")]
+        for file in files {
+            for dir in Self::FONT_DIRS {
+                if let Ok(data) = std::fs::read(format!("{}/{}", dir, file)) {
+                    #[cfg(feature = "debug-logging")]
+                    tracing::trace!(
+                        target: crate::log_target::FONT,
+                        "Substituting {}/{} for font '{}'",
+                        dir, file, query.base_font
+                    );
+                    return Some(data);
+                }
+            }
+        }
+
+        // The bundled assets are read from the source tree, which isn't
+        // available to WebAssembly builds
+        if cfg!(feature = "wasm") {
+            return None;
+        }
+        let assets = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("assets")
+            .join("fonts");
+        files
+            .iter()
+            .filter(|file| file.starts_with("Liberation"))
+            .find_map(|file| std::fs::read(assets.join(file)).ok())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_font_query_class_and_style() {
+        let query = FontQuery::new("ABCDEF+Helvetica-BoldOblique", None);
+        assert_eq!(query.base_font, "Helvetica-BoldOblique");
+        assert_eq!(query.class, FontClass::SansSerif);
+        assert!(query.bold && query.italic);
+
+        assert_eq!(FontQuery::new("CMR10", None).class, FontClass::Serif);
+        assert_eq!(
+            FontQuery::new("ZapfDingbats", None).class,
+            FontClass::Symbolic
+        );
+        assert_eq!(
+            FontQuery::new("LMTypewriter10", None).class,
+            FontClass::Monospace
+        );
+
+        // Unknown names are classified by the descriptor flags
+        let flags = FontQuery::new("Brand", Some(FLAG_FIXED_PITCH | FLAG_NONSYMBOLIC));
+        assert_eq!(flags.class, FontClass::Monospace);
+        assert_eq!(
+            FontQuery::new("Brand", Some(FLAG_SERIF)).class,
+            FontClass::Serif
+        );
+        assert_eq!(
+            FontQuery::new("Brand", Some(FLAG_SYMBOLIC)).class,
+            FontClass::Symbolic
+        );
+        assert_eq!(
+            FontQuery::new("Brand", Some(FLAG_NONSYMBOLIC)).class,
+            FontClass::SansSerif
+        );
+        assert_eq!(FontQuery::new("Brand", None).class, FontClass::Serif);
+
+        let styled = FontQuery::new("Brand", Some(FLAG_ITALIC | FLAG_FORCE_BOLD));
+        assert!(styled.bold && styled.italic);
+    }
+
+    #[test]
+    fn test_default_resolver_uses_bundled_fonts() {
+        // Liberation Sans is bundled, so sans-serif fonts always resolve
+        let data = DefaultFontResolver
+            .resolve(&FontQuery::new("Helvetica-Bold", None))
+            .unwrap();
+        assert!(data.starts_with(&[0, 1, 0, 0]) || data.starts_with(b"true"));
+
+        // Symbol and ZapfDingbats have no Latin look-alike
+        assert!(
+            DefaultFontResolver
+                .resolve(&FontQuery::new("Symbol", None))
+                .is_none()
+        );
+        assert!(
+            DefaultFontResolver
+                .resolve(&FontQuery::new("Brand", Some(FLAG_SYMBOLIC)))
+                .is_none()
+        );
+    }
+}
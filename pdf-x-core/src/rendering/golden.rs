@@ -0,0 +1,277 @@
+//! Golden-file regression tests on the draw calls of a page.
+//!
+//! A golden file lists the calls a [`TraceDevice`] recorded for a page, one
+//! per line in the format of [`write_trace`], which rounds numbers so that
+//! the file doesn't change with floating point noise. Comparing the draw
+//! calls instead of rendered pixels gives exact, readable failures and
+//! doesn't need a rasterizer.
+//!
+//! Run the tests with the `PDF_X_UPDATE_GOLDEN` environment variable set to
+//! write the golden files from the current output, then review the changes
+//! with version control.
+//!
+//! # Example
+//! ```no_run
+//! use pdf_x_core::PDFDocument;
+//! use pdf_x_core::assert_page_renders_like;
+//!
+//! let mut doc = PDFDocument::open_file("tests/invoice.pdf", None, None).unwrap();
+//! assert_page_renders_like!(doc, 0, "tests/golden/invoice-1.txt");
+//! ```
+
+use super::trace_device::{TraceCall, write_trace};
+use crate::core::error::{PDFError, PDFResult};
+use std::fmt;
+use std::path::Path;
+
+/// First line of a golden file.
+pub const GOLDEN_HEADER: &str = "# pdf-x golden v1";
+
+/// Environment variable that makes [`check_golden`] write golden files
+/// instead of comparing with them.
+pub const UPDATE_GOLDEN_ENV: &str = "PDF_X_UPDATE_GOLDEN";
+
+/// Returns recorded calls as the lines of a golden file.
+///
+/// Font programs loaded with `load_font_data` are left out; they come from
+/// the document, not from the rendering code under test.
+///
+/// # Arguments
+/// * `calls` - Calls from [`TraceDevice::calls`](super::TraceDevice::calls)
+pub fn snapshot(calls: &[TraceCall]) -> PDFResult<Vec<String>> {
+    let calls: Vec<TraceCall> = calls
+        .iter()
+        .filter(|call| !matches!(call, TraceCall::LoadFontData { .. }))
+        .cloned()
+        .collect();
+    let trace = write_trace(&calls)?;
+    Ok(String::from_utf8_lossy(&trace)
+        .lines()
+        .filter(|line| !line.starts_with('%'))
+        .map(str::to_string)
+        .collect())
+}
+
+/// Writes operations in the golden file format.
+pub fn write_golden(operations: &[String]) -> String {
+    let mut out = String::from(GOLDEN_HEADER);
+    out.push('\n');
+    for op in operations {
+        out.push_str(op);
+        out.push('\n');
+    }
+    out
+}
+
+/// Reads the operations of a golden file, skipping `#` comments and blank
+/// lines.
+pub fn read_golden(text: &str) -> Vec<String> {
+    text.lines()
+        .map(|line| line.trim_end_matches('\r'))
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect()
+}
+
+/// The first difference between expected and actual operations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GoldenMismatch {
+    /// Index of the first differing operation
+    pub index: usize,
+    /// Expected operation, `None` if the output has extra operations
+    pub expected: Option<String>,
+    /// Actual operation, `None` if the output is missing operations
+    pub actual: Option<String>,
+    /// Number of expected operations
+    pub expected_len: usize,
+    /// Number of actual operations
+    pub actual_len: usize,
+}
+
+impl fmt::Display for GoldenMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "operation {} differs ({} expected, {} recorded)",
+            self.index, self.expected_len, self.actual_len
+        )?;
+        writeln!(
+            f,
+            "  expected: {}",
+            self.expected.as_deref().unwrap_or("<end of operations>")
+        )?;
+        write!(
+            f,
+            "  actual:   {}",
+            self.actual.as_deref().unwrap_or("<end of operations>")
+        )
+    }
+}
+
+/// Returns the first difference between two operation lists, or `None` if
+/// they are the same.
+pub fn diff_golden(expected: &[String], actual: &[String]) -> Option<GoldenMismatch> {
+    let index = expected
+        .iter()
+        .zip(actual)
+        .position(|(expected, actual)| expected != actual)
+        .unwrap_or(expected.len().min(actual.len()));
+    if index == expected.len() && index == actual.len() {
+        return None;
+    }
+    Some(GoldenMismatch {
+        index,
+        expected: expected.get(index).cloned(),
+        actual: actual.get(index).cloned(),
+        expected_len: expected.len(),
+        actual_len: actual.len(),
+    })
+}
+
+/// Compares normalized operations with a golden file, or writes the
+/// golden file when [`UPDATE_GOLDEN_ENV`] is set.
+///
+/// When writing, creates the directory of the file if needed. See
+/// [`compare_golden`].
+pub fn check_golden(actual: &[String], path: impl AsRef<Path>) -> PDFResult<()> {
+    let path = path.as_ref();
+    if std::env::var_os(UPDATE_GOLDEN_ENV).is_some() {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir).map_err(|e| {
+                PDFError::Generic(format!("Failed to create {}: {}", dir.display(), e))
+            })?;
+        }
+        return std::fs::write(path, write_golden(actual))
+            .map_err(|e| PDFError::Generic(format!("Failed to write {}: {}", path.display(), e)));
+    }
+    compare_golden(actual, path)
+}
+
+/// Compares normalized operations with a golden file.
+///
+/// Unlike [`check_golden`], always compares, whether or not
+/// [`UPDATE_GOLDEN_ENV`] is set.
+///
+/// # Arguments
+/// * `actual` - Calls from [`snapshot`] or
+///   [`PDFDocument::render_page_to_operations`](crate::core::PDFDocument::render_page_to_operations)
+/// * `path` - The golden file
+///
+/// # Returns
+/// An error describing the first difference, or why the file couldn't be
+/// read
+pub fn compare_golden(actual: &[String], path: impl AsRef<Path>) -> PDFResult<()> {
+    let path = path.as_ref();
+    let text = std::fs::read_to_string(path).map_err(|e| {
+        PDFError::Generic(format!(
+            "Failed to read golden file {}: {} (set {} to create it)",
+            path.display(),
+            e,
+            UPDATE_GOLDEN_ENV
+        ))
+    })?;
+    match diff_golden(&read_golden(&text), actual) {
+        None => Ok(()),
+        Some(mismatch) => Err(PDFError::Generic(format!(
+            "Output differs from golden file {}: {}\n(set {} to update it)",
+            path.display(),
+            mismatch,
+            UPDATE_GOLDEN_ENV
+        ))),
+    }
+}
+
+/// Asserts that a [`TraceDevice`](super::TraceDevice) recorded the calls of
+/// a golden file.
+///
+/// # Example
+/// ```no_run
+/// use pdf_x_core::assert_renders_like;
+/// use pdf_x_core::rendering::{Device, TestDevice, TraceDevice};
+///
+/// let mut device = TraceDevice::new(TestDevice::new(100.0, 100.0));
+/// device.begin_path();
+/// device.rect(10.0, 10.0, 80.0, 80.0);
+/// assert_renders_like!(device, "tests/golden/square.txt");
+/// ```
+#[macro_export]
+macro_rules! assert_renders_like {
+    ($device:expr, $golden:expr $(,)?) => {
+        if let Err(e) = $crate::rendering::golden::snapshot($device.calls())
+            .and_then(|calls| $crate::rendering::golden::check_golden(&calls, $golden))
+        {
+            panic!("{}", e);
+        }
+    };
+}
+
+/// Asserts that a page of a document draws the operations of a golden file.
+///
+/// The page is rendered at scale 1 with
+/// [`PDFDocument::render_page_to_operations`](crate::core::PDFDocument::render_page_to_operations).
+///
+/// # Example
+/// ```no_run
+/// use pdf_x_core::PDFDocument;
+/// use pdf_x_core::assert_page_renders_like;
+///
+/// let mut doc = PDFDocument::open_file("tests/invoice.pdf", None, None).unwrap();
+/// assert_page_renders_like!(doc, 0, "tests/golden/invoice-1.txt");
+/// ```
+#[macro_export]
+macro_rules! assert_page_renders_like {
+    ($doc:expr, $page_index:expr, $golden:expr $(,)?) => {
+        match $doc.render_page_to_operations($page_index, None) {
+            Ok(operations) => {
+                if let Err(e) = $crate::rendering::golden::check_golden(&operations, $golden) {
+                    panic!("Page {}: {}", $page_index, e);
+                }
+            }
+            Err(e) => panic!("Failed to render page {}: {}", $page_index, e),
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rendering::{Device, Paint, PathDrawMode, StrokeProps, TestDevice, TraceDevice};
+
+    #[test]
+    fn test_golden_round_trip() {
+        let mut device = TraceDevice::new(TestDevice::new(100.0, 100.0));
+        device.begin_path();
+        device.rect(0.1 + 0.2, -0.0, 10.0, 1.9999999999);
+        device
+            .draw_path(
+                PathDrawMode::Stroke,
+                &Paint::from_color(crate::rendering::Color::RGB(1.0, 0.0, 0.0)),
+                &StrokeProps::default(),
+            )
+            .unwrap();
+        device.load_font_data("F1", vec![0; 16], None).unwrap();
+        let operations = snapshot(device.calls()).unwrap();
+        assert_eq!(operations.len(), 3, "{:?}", operations);
+        assert_eq!(operations[1], "[/rect 0.3 0 10 2]");
+        assert!(
+            operations[2].starts_with("[/draw_path /Stroke null [/Solid [/RGB 1 0 0]]"),
+            "{}",
+            operations[2]
+        );
+
+        let golden = write_golden(&operations);
+        assert!(golden.starts_with(GOLDEN_HEADER));
+        assert_eq!(read_golden(&golden.replace('\n', "\r\n")), operations);
+        assert_eq!(diff_golden(&operations, &read_golden(&golden)), None);
+
+        let mut changed = operations.clone();
+        changed[2] = "[/draw_path /Stroke]".to_string();
+        changed.push("[/end_tile 0]".to_string());
+        let mismatch = diff_golden(&operations, &changed).unwrap();
+        assert_eq!(mismatch.index, 2);
+        assert_eq!(mismatch.actual.as_deref(), Some("[/draw_path /Stroke]"));
+
+        let mismatch = diff_golden(&operations, &operations[..2]).unwrap();
+        assert_eq!((mismatch.index, mismatch.actual), (2, None));
+    }
+}
@@ -0,0 +1,436 @@
+//! Raster golden-image regression tests.
+//!
+//! Renders pages and compares them with stored PNG files. Pixels are
+//! compared by a perceptual color difference, so antialiasing noise below
+//! the threshold passes while a glyph that moved by a pixel doesn't. When a
+//! page doesn't match, the rendered page and a diff image (differing pixels
+//! in red over a faded copy of the expected page) are written next to the
+//! golden file.
+//!
+//! Like the draw-call golden files of [`golden`](super::golden), golden
+//! images are written instead of compared when the `PDF_X_UPDATE_GOLDEN`
+//! environment variable is set; [`compare_golden_image`] always compares.
+//!
+//! Available with the `golden-images` feature, which is meant for
+//! `[dev-dependencies]`.
+//!
+//! # Example
+//! ```no_run
+//! use pdf_x_core::PDFDocument;
+//! use pdf_x_core::assert_page_matches_png;
+//! use pdf_x_core::rendering::golden_image::ImageMatchOptions;
+//!
+//! let mut doc = PDFDocument::open_file("tests/invoice.pdf", None, None).unwrap();
+//! assert_page_matches_png!(doc, 0, "tests/golden/invoice-1.png");
+//!
+//! // Allow a few differing pixels at twice the resolution
+//! let mut options = ImageMatchOptions::default();
+//! options.render.scale = 2.0;
+//! options.max_differing_pixels = 20;
+//! assert_page_matches_png!(doc, 0, "tests/golden/invoice-1@2x.png", options);
+//! ```
+
+use super::golden::UPDATE_GOLDEN_ENV;
+use super::svg_device::encode_png;
+use super::viewport::RenderOptions;
+use crate::core::document::PDFDocument;
+use crate::core::error::{PDFError, PDFResult};
+use std::path::{Path, PathBuf};
+
+/// Largest YIQ difference between two colors, between black and white.
+const MAX_YIQ_DELTA: f64 = 35215.0;
+
+/// How closely a rendered page must match its golden image.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageMatchOptions {
+    /// Largest perceptual difference of two pixels that counts as equal,
+    /// from 0.0 (exact) to 1.0 (any two colors are equal)
+    pub threshold: f64,
+    /// Number of differing pixels that is still a match
+    pub max_differing_pixels: usize,
+    /// How the page is rendered
+    pub render: RenderOptions,
+}
+
+impl Default for ImageMatchOptions {
+    fn default() -> Self {
+        ImageMatchOptions {
+            threshold: 0.1,
+            max_differing_pixels: 0,
+            render: RenderOptions::default(),
+        }
+    }
+}
+
+/// The difference between two images of the same size.
+#[derive(Debug, Clone)]
+pub struct ImageDiff {
+    /// Width of both images in pixels
+    pub width: u32,
+    /// Height of both images in pixels
+    pub height: u32,
+    /// Number of pixels that differ by more than the threshold
+    pub differing_pixels: usize,
+    /// Largest perceptual difference of any pixel, from 0.0 to 1.0
+    pub max_delta: f64,
+    /// Pixel bounds [x0, y0, x1, y1] of the differing pixels
+    pub bbox: Option<[u32; 4]>,
+    /// RGBA image showing the differing pixels in red over a faded gray
+    /// copy of the expected image
+    pub image: Vec<u8>,
+}
+
+/// Returns the perceptual difference of two RGBA pixels, composited on
+/// white, from 0.0 to 1.0.
+///
+/// The difference is measured in the YIQ color space, weighted as in
+/// "Measuring perceived color difference using YIQ NTSC transmission color
+/// space in mobile applications" (Kotsarenko and Ramos, 2010).
+pub fn pixel_delta(a: &[u8], b: &[u8]) -> f64 {
+    let yiq = |pixel: &[u8]| {
+        let alpha = pixel[3] as f64 / 255.0;
+        let blend = |c: u8| 255.0 + (c as f64 - 255.0) * alpha;
+        let (r, g, b) = (blend(pixel[0]), blend(pixel[1]), blend(pixel[2]));
+        (
+            r * 0.29889531 + g * 0.58662247 + b * 0.11448223,
+            r * 0.59597799 - g * 0.27417610 - b * 0.32180189,
+            r * 0.21147017 - g * 0.52261711 + b * 0.31114694,
+        )
+    };
+    let (y1, i1, q1) = yiq(a);
+    let (y2, i2, q2) = yiq(b);
+    let delta = 0.5053 * (y1 - y2).powi(2) + 0.299 * (i1 - i2).powi(2) + 0.1957 * (q1 - q2).powi(2);
+    (delta / MAX_YIQ_DELTA).sqrt().min(1.0)
+}
+
+/// Compares two RGBA images pixel by pixel.
+///
+/// # Arguments
+/// * `expected`, `actual` - Width, height and RGBA pixels of each image
+/// * `threshold` - Largest [`pixel_delta`] that counts as equal
+///
+/// # Returns
+/// The difference, or an error if the images differ in size
+pub fn compare_images(
+    expected: &(u32, u32, Vec<u8>),
+    actual: &(u32, u32, Vec<u8>),
+    threshold: f64,
+) -> PDFResult<ImageDiff> {
+    let (width, height) = (expected.0, expected.1);
+    if (actual.0, actual.1) != (width, height) {
+        return Err(PDFError::Generic(format!(
+            "Image is {}x{}, expected {}x{}",
+            actual.0, actual.1, width, height
+        )));
+    }
+    let len = width as usize * height as usize * 4;
+    if expected.2.len() < len || actual.2.len() < len {
+        return Err(PDFError::Generic(format!(
+            "Image data is shorter than {}x{} RGBA pixels",
+            width, height
+        )));
+    }
+
+    let mut diff = ImageDiff {
+        width,
+        height,
+        differing_pixels: 0,
+        max_delta: 0.0,
+        bbox: None,
+        image: Vec::with_capacity(len),
+    };
+    let pixels = expected.2[..len]
+        .chunks_exact(4)
+        .zip(actual.2[..len].chunks_exact(4));
+    for (index, (a, b)) in pixels.enumerate() {
+        let delta = if a == b { 0.0 } else { pixel_delta(a, b) };
+        diff.max_delta = diff.max_delta.max(delta);
+        if delta <= threshold {
+            // Faded gray of the expected pixel, for context
+            let alpha = a[3] as f64 / 255.0;
+            let luma = 0.299 * a[0] as f64 + 0.587 * a[1] as f64 + 0.114 * a[2] as f64;
+            let gray = 255.0 - (255.0 - luma) * alpha * 0.1;
+            let gray = gray.round() as u8;
+            diff.image.extend_from_slice(&[gray, gray, gray, 255]);
+            continue;
+        }
+
+        diff.image.extend_from_slice(&[255, 0, 0, 255]);
+        diff.differing_pixels += 1;
+        let (x, y) = (
+            (index % width as usize) as u32,
+            (index / width as usize) as u32,
+        );
+        diff.bbox = Some(match diff.bbox {
+            Some([x0, y0, x1, y1]) => [x0.min(x), y0.min(y), x1.max(x + 1), y1.max(y + 1)],
+            None => [x, y, x + 1, y + 1],
+        });
+    }
+    Ok(diff)
+}
+
+/// Reads a PNG file as RGBA pixels.
+///
+/// # Returns
+/// Width, height and RGBA pixels
+pub fn read_png(data: &[u8]) -> PDFResult<(u32, u32, Vec<u8>)> {
+    let image = image::load_from_memory_with_format(data, image::ImageFormat::Png)
+        .map_err(|e| PDFError::Generic(format!("Invalid PNG file: {}", e)))?
+        .into_rgba8();
+    Ok((image.width(), image.height(), image.into_raw()))
+}
+
+/// Writes RGBA pixels as a PNG file.
+pub fn write_png(path: impl AsRef<Path>, image: &(u32, u32, Vec<u8>)) -> PDFResult<()> {
+    let path = path.as_ref();
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| PDFError::Generic(format!("Failed to create {}: {}", dir.display(), e)))?;
+    }
+    let png = encode_png(image.0, image.1, &image.2)?;
+    std::fs::write(path, png)
+        .map_err(|e| PDFError::Generic(format!("Failed to write {}: {}", path.display(), e)))
+}
+
+/// Returns the paths the rendered image and the diff image of a failed
+/// comparison are written to: `page.png` gives `page.actual.png` and
+/// `page.diff.png`.
+pub fn failure_paths(golden: impl AsRef<Path>) -> (PathBuf, PathBuf) {
+    let golden = golden.as_ref();
+    (
+        golden.with_extension("actual.png"),
+        golden.with_extension("diff.png"),
+    )
+}
+
+/// Compares an image with a golden PNG file, or writes the golden file
+/// when `PDF_X_UPDATE_GOLDEN` is set.
+///
+/// See [`compare_golden_image`].
+pub fn check_golden_image(
+    actual: &(u32, u32, Vec<u8>),
+    path: impl AsRef<Path>,
+    options: &ImageMatchOptions,
+) -> PDFResult<()> {
+    if std::env::var_os(UPDATE_GOLDEN_ENV).is_some() {
+        return write_png(path, actual);
+    }
+    compare_golden_image(actual, path, options)
+}
+
+/// Compares an image with a golden PNG file.
+///
+/// Unlike [`check_golden_image`], always compares, whether or not
+/// `PDF_X_UPDATE_GOLDEN` is set. When the image doesn't match, writes it
+/// and a diff image to the [`failure_paths`]; when it does, removes those
+/// left from an earlier run.
+///
+/// # Arguments
+/// * `actual` - Width, height and RGBA pixels of the image
+/// * `path` - The golden PNG file
+/// * `options` - Threshold and number of pixels allowed to differ
+///
+/// # Returns
+/// An error describing the difference, or why a file couldn't be read or
+/// written
+pub fn compare_golden_image(
+    actual: &(u32, u32, Vec<u8>),
+    path: impl AsRef<Path>,
+    options: &ImageMatchOptions,
+) -> PDFResult<()> {
+    let path = path.as_ref();
+    let data = std::fs::read(path).map_err(|e| {
+        PDFError::Generic(format!(
+            "Failed to read golden image {}: {} (set {} to create it)",
+            path.display(),
+            e,
+            UPDATE_GOLDEN_ENV
+        ))
+    })?;
+    let expected = read_png(&data)?;
+    let (actual_path, diff_path) = failure_paths(path);
+
+    let failure = match compare_images(&expected, actual, options.threshold) {
+        Ok(diff) if diff.differing_pixels <= options.max_differing_pixels => {
+            let _ = std::fs::remove_file(&actual_path);
+            let _ = std::fs::remove_file(&diff_path);
+            return Ok(());
+        }
+        Ok(diff) => {
+            write_png(&diff_path, &(diff.width, diff.height, diff.image))?;
+            format!(
+                "{} pixels differ (at most {} allowed) within {:?}, largest difference {:.3}; \
+                 see {}",
+                diff.differing_pixels,
+                options.max_differing_pixels,
+                diff.bbox.unwrap_or_default(),
+                diff.max_delta,
+                diff_path.display()
+            )
+        }
+        Err(e) => e.to_string(),
+    };
+    write_png(&actual_path, actual)?;
+    Err(PDFError::Generic(format!(
+        "Rendering differs from golden image {}: {}\nRendered image: {}\n(set {} to update it)",
+        path.display(),
+        failure,
+        actual_path.display(),
+        UPDATE_GOLDEN_ENV
+    )))
+}
+
+/// Renders a page and compares it with a golden PNG file.
+///
+/// See [`check_golden_image`].
+///
+/// # Arguments
+/// * `doc` - The document
+/// * `page_index` - The zero-based page index
+/// * `path` - The golden PNG file
+/// * `options` - Render options, threshold and number of pixels allowed to
+///   differ
+pub fn check_page(
+    doc: &mut PDFDocument,
+    page_index: usize,
+    path: impl AsRef<Path>,
+    options: &ImageMatchOptions,
+) -> PDFResult<()> {
+    let image = doc.render_page_to_image(page_index, options.render.clone())?;
+    check_golden_image(&image, path, options)
+}
+
+/// Asserts that a page renders like a golden PNG file.
+///
+/// Takes the document, the zero-based page index, the golden file and,
+/// optionally, [`ImageMatchOptions`]. On failure the rendered page and a
+/// diff image are written next to the golden file; see
+/// [`check_golden_image`].
+///
+/// # Example
+/// ```no_run
+/// use pdf_x_core::PDFDocument;
+/// use pdf_x_core::assert_page_matches_png;
+///
+/// let mut doc = PDFDocument::open_file("tests/invoice.pdf", None, None).unwrap();
+/// assert_page_matches_png!(doc, 0, "tests/golden/invoice-1.png");
+/// ```
+#[macro_export]
+macro_rules! assert_page_matches_png {
+    ($doc:expr, $page_index:expr, $golden:expr $(,)?) => {
+        $crate::assert_page_matches_png!(
+            $doc,
+            $page_index,
+            $golden,
+            $crate::rendering::golden_image::ImageMatchOptions::default()
+        )
+    };
+    ($doc:expr, $page_index:expr, $golden:expr, $options:expr $(,)?) => {
+        if let Err(e) =
+            $crate::rendering::golden_image::check_page(&mut $doc, $page_index, $golden, &$options)
+        {
+            panic!("Page {}: {}", $page_index, e);
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(width: u32, height: u32, pixel: [u8; 4]) -> (u32, u32, Vec<u8>) {
+        let pixels = (0..width * height).flat_map(|_| pixel).collect();
+        (width, height, pixels)
+    }
+
+    #[test]
+    fn test_pixel_delta() {
+        assert_eq!(pixel_delta(&[10, 20, 30, 255], &[10, 20, 30, 255]), 0.0);
+        assert!(pixel_delta(&[0, 0, 0, 255], &[255, 255, 255, 255]) > 0.95);
+        // Transparent pixels are white
+        assert!(pixel_delta(&[0, 0, 0, 0], &[255, 255, 255, 255]) < 1e-9);
+        // Antialiasing noise stays below the default threshold
+        assert!(pixel_delta(&[128, 128, 128, 255], &[133, 131, 128, 255]) < 0.1);
+    }
+
+    #[test]
+    fn test_compare_images() {
+        let expected = image(4, 3, [255, 255, 255, 255]);
+        let mut actual = expected.clone();
+        let diff = compare_images(&expected, &actual, 0.1).unwrap();
+        assert_eq!(diff.differing_pixels, 0);
+        assert_eq!(diff.bbox, None);
+
+        // Pixel (2, 1) turns black, pixel (0, 0) almost white
+        actual.2[(4 + 2) * 4..(4 + 2) * 4 + 3].copy_from_slice(&[0, 0, 0]);
+        actual.2[..3].copy_from_slice(&[250, 250, 250]);
+        let diff = compare_images(&expected, &actual, 0.1).unwrap();
+        assert_eq!(diff.differing_pixels, 1);
+        assert_eq!(diff.bbox, Some([2, 1, 3, 2]));
+        assert_eq!(&diff.image[(4 + 2) * 4..(4 + 2) * 4 + 4], &[255, 0, 0, 255]);
+        assert_eq!(&diff.image[..4], &[255, 255, 255, 255]);
+        assert_eq!(
+            compare_images(&expected, &actual, 0.0)
+                .unwrap()
+                .differing_pixels,
+            2
+        );
+
+        assert!(compare_images(&expected, &image(3, 4, [0, 0, 0, 255]), 0.1).is_err());
+    }
+
+    #[test]
+    fn test_golden_image_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let golden = dir.path().join("page.png");
+        let expected = (
+            2,
+            2,
+            vec![0, 0, 0, 255, 255, 0, 0, 255, 0, 255, 0, 128, 9, 9, 9, 0],
+        );
+        write_png(&golden, &expected).unwrap();
+        assert_eq!(
+            read_png(&std::fs::read(&golden).unwrap()).unwrap(),
+            expected
+        );
+
+        let options = ImageMatchOptions::default();
+        compare_golden_image(&expected, &golden, &options).unwrap();
+
+        let changed = image(2, 2, [0, 0, 255, 255]);
+        let error = compare_golden_image(&changed, &golden, &options).unwrap_err();
+        assert!(error.to_string().contains("pixels differ"), "{}", error);
+        let (actual_path, diff_path) = failure_paths(&golden);
+        assert_eq!(actual_path, dir.path().join("page.actual.png"));
+        assert_eq!(
+            read_png(&std::fs::read(&actual_path).unwrap()).unwrap(),
+            changed
+        );
+        let diff = read_png(&std::fs::read(&diff_path).unwrap()).unwrap();
+        assert_eq!(&diff.2[..4], &[255, 0, 0, 255]);
+
+        // A passing run cleans up
+        let tolerant = ImageMatchOptions {
+            max_differing_pixels: 4,
+            ..ImageMatchOptions::default()
+        };
+        compare_golden_image(&changed, &golden, &tolerant).unwrap();
+        assert!(!actual_path.exists() && !diff_path.exists());
+
+        assert!(read_png(b"GIF89a").is_err());
+
+        // 16-bit files from image tools read as 8-bit RGBA
+        let mut png16 = Vec::new();
+        image::DynamicImage::ImageRgb16(image::ImageBuffer::from_pixel(
+            1,
+            1,
+            image::Rgb([0xffff, 0x8080, 0]),
+        ))
+        .write_to(
+            &mut std::io::Cursor::new(&mut png16),
+            image::ImageFormat::Png,
+        )
+        .unwrap();
+        assert_eq!(read_png(&png16).unwrap(), (1, 1, vec![255, 128, 0, 255]));
+    }
+}
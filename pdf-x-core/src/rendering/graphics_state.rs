@@ -3,6 +3,10 @@
 //! This module handles the graphics state stack and all state properties
 //! as defined in the PDF specification (section 8.4).
 
+use super::device::Paint;
+use crate::core::colorspace::ColorSpace;
+use crate::core::parser::PDFObject;
+
 /// Line cap style (PDF spec 8.4.3.2).
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum LineCap {
@@ -221,6 +225,109 @@ impl Default for FillRule {
     }
 }
 
+/// Rendering intent for CIE-based color conversion (PDF spec 8.6.5.8).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum RenderingIntent {
+    /// Reproduce colors exactly, including the media white point
+    AbsoluteColorimetric,
+    /// Reproduce colors relative to the media white point (default)
+    #[default]
+    RelativeColorimetric,
+    /// Preserve saturation, typically for business graphics
+    Saturation,
+    /// Preserve the visual relationship between colors, typically for photographs
+    Perceptual,
+}
+
+impl RenderingIntent {
+    /// Parses a rendering intent name (the `ri` operand or /RI entry).
+    ///
+    /// Unknown names map to [`RenderingIntent::RelativeColorimetric`], as the
+    /// specification requires.
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "AbsoluteColorimetric" => RenderingIntent::AbsoluteColorimetric,
+            "Saturation" => RenderingIntent::Saturation,
+            "Perceptual" => RenderingIntent::Perceptual,
+            _ => RenderingIntent::RelativeColorimetric,
+        }
+    }
+
+    /// Returns the PDF name of the intent.
+    pub fn name(&self) -> &'static str {
+        match self {
+            RenderingIntent::AbsoluteColorimetric => "AbsoluteColorimetric",
+            RenderingIntent::RelativeColorimetric => "RelativeColorimetric",
+            RenderingIntent::Saturation => "Saturation",
+            RenderingIntent::Perceptual => "Perceptual",
+        }
+    }
+}
+
+/// Blend mode for compositing painted objects with the backdrop (PDF spec 11.3.5).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum BlendMode {
+    /// Paint the source over the backdrop (default, also /Compatible)
+    #[default]
+    Normal,
+    Multiply,
+    Screen,
+    Overlay,
+    Darken,
+    Lighten,
+    ColorDodge,
+    ColorBurn,
+    HardLight,
+    SoftLight,
+    Difference,
+    Exclusion,
+    Hue,
+    Saturation,
+    Color,
+    Luminosity,
+}
+
+impl BlendMode {
+    /// Parses a blend mode name, or `None` if it is not a standard mode.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "Normal" | "Compatible" => BlendMode::Normal,
+            "Multiply" => BlendMode::Multiply,
+            "Screen" => BlendMode::Screen,
+            "Overlay" => BlendMode::Overlay,
+            "Darken" => BlendMode::Darken,
+            "Lighten" => BlendMode::Lighten,
+            "ColorDodge" => BlendMode::ColorDodge,
+            "ColorBurn" => BlendMode::ColorBurn,
+            "HardLight" => BlendMode::HardLight,
+            "SoftLight" => BlendMode::SoftLight,
+            "Difference" => BlendMode::Difference,
+            "Exclusion" => BlendMode::Exclusion,
+            "Hue" => BlendMode::Hue,
+            "Saturation" => BlendMode::Saturation,
+            "Color" => BlendMode::Color,
+            "Luminosity" => BlendMode::Luminosity,
+            _ => return None,
+        })
+    }
+
+    /// Parses an ExtGState /BM value: a name, or an array of names of which
+    /// the first supported one is used.
+    ///
+    /// Unsupported values map to [`BlendMode::Normal`], as the specification
+    /// requires.
+    pub fn from_object(value: &PDFObject) -> Self {
+        match value {
+            PDFObject::Name(name) => Self::from_name(name),
+            PDFObject::Array(names) => names
+                .iter()
+                .find_map(|name| name.as_name().and_then(Self::from_name)),
+            _ => None,
+        }
+        .unwrap_or_default()
+    }
+}
+
 /// Graphics state for PDF rendering.
 ///
 /// This represents the current graphics state as defined in the PDF specification.
@@ -272,6 +379,33 @@ pub struct GraphicsState {
 
     /// Current font size
     pub font_size: Option<f64>,
+
+    /// Rendering intent (ri operator or /RI in an ExtGState)
+    pub rendering_intent: RenderingIntent,
+
+    /// Overprint for stroking operations (/OP)
+    pub stroke_overprint: bool,
+
+    /// Overprint for non-stroking operations (/op, defaults to /OP)
+    pub fill_overprint: bool,
+
+    /// Overprint mode (/OPM): 0 knocks out zero components, 1 leaves them
+    pub overprint_mode: u8,
+
+    /// Stroking color space for SC/SCN operands, `None` if unsupported
+    pub stroke_color_space: Option<ColorSpace>,
+
+    /// Non-stroking color space for sc/scn operands, `None` if unsupported
+    pub fill_color_space: Option<ColorSpace>,
+
+    /// Pattern used for stroking instead of `stroke_color` (set by SCN)
+    pub stroke_pattern: Option<Paint>,
+
+    /// Pattern used for filling instead of `fill_color` (set by scn)
+    pub fill_pattern: Option<Paint>,
+
+    /// Blend mode (/BM)
+    pub blend_mode: BlendMode,
 }
 
 impl Default for GraphicsState {
@@ -292,6 +426,15 @@ impl Default for GraphicsState {
             text_line_matrix: [1.0, 0.0, 0.0, 1.0, 0.0, 0.0],
             font_name: None,
             font_size: None,
+            rendering_intent: RenderingIntent::default(),
+            stroke_overprint: false,
+            fill_overprint: false,
+            overprint_mode: 0,
+            stroke_color_space: Some(ColorSpace::DeviceGray),
+            fill_color_space: Some(ColorSpace::DeviceGray),
+            stroke_pattern: None,
+            fill_pattern: None,
+            blend_mode: BlendMode::Normal,
         }
     }
 }
@@ -359,12 +502,82 @@ impl GraphicsState {
     pub fn text_position(&self) -> (f64, f64) {
         (self.text_matrix[4], self.text_matrix[5])
     }
+
+    /// Paint for stroking: the stroke pattern if one is set, else the stroke color.
+    pub fn stroke_paint(&self) -> Paint {
+        self.paint(self.stroke_pattern.as_ref(), self.stroke_color)
+    }
+
+    /// Paint for filling: the fill pattern if one is set, else the fill color.
+    pub fn fill_paint(&self) -> Paint {
+        self.paint(self.fill_pattern.as_ref(), self.fill_color)
+    }
+
+    fn paint(&self, pattern: Option<&Paint>, color: Color) -> Paint {
+        // Patterns live in the page's default space; map them into the
+        // current user space
+        match (pattern, invert_matrix(&self.ctm)) {
+            (Some(pattern), Some(inverse)) => pattern.transform(&inverse),
+            _ => Paint::from_color(color),
+        }
+    }
+}
+
+/// Multiply two matrices: the result applies `first`, then `second`.
+pub fn multiply_matrix(first: &[f64; 6], second: &[f64; 6]) -> [f64; 6] {
+    let [a, b, c, d, e, f] = *first;
+    let [a2, b2, c2, d2, e2, f2] = *second;
+    [
+        a * a2 + b * c2,
+        a * b2 + b * d2,
+        c * a2 + d * c2,
+        c * b2 + d * d2,
+        e * a2 + f * c2 + e2,
+        e * b2 + f * d2 + f2,
+    ]
+}
+
+/// Invert a matrix.
+///
+/// # Returns
+/// The inverse, or `None` if the matrix is singular
+pub fn invert_matrix(matrix: &[f64; 6]) -> Option<[f64; 6]> {
+    let [a, b, c, d, e, f] = *matrix;
+    let det = a * d - b * c;
+    if det.abs() < 1e-12 {
+        return None;
+    }
+    Some([
+        d / det,
+        -b / det,
+        -c / det,
+        a / det,
+        (c * f - d * e) / det,
+        (b * e - a * f) / det,
+    ])
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_blend_mode_from_object() {
+        let name = |n: &str| PDFObject::Name(n.to_string());
+        assert_eq!(BlendMode::from_object(&name("Screen")), BlendMode::Screen);
+        assert_eq!(
+            BlendMode::from_object(&name("Compatible")),
+            BlendMode::Normal
+        );
+        assert_eq!(BlendMode::from_object(&name("Foo")), BlendMode::Normal);
+        let names = PDFObject::Array(
+            vec![Box::new(name("Foo")), Box::new(name("ColorBurn"))]
+                .into_iter()
+                .collect(),
+        );
+        assert_eq!(BlendMode::from_object(&names), BlendMode::ColorBurn);
+    }
+
     #[test]
     fn test_default_state() {
         let state = GraphicsState::default();
@@ -434,6 +647,27 @@ mod tests {
         assert_eq!(state.text_position(), (100.0, 200.0));
     }
 
+    #[test]
+    fn test_rendering_intent_names() {
+        assert_eq!(
+            RenderingIntent::from_name("Perceptual"),
+            RenderingIntent::Perceptual
+        );
+        assert_eq!(
+            RenderingIntent::from_name("Bogus"),
+            RenderingIntent::RelativeColorimetric
+        );
+        assert_eq!(RenderingIntent::Saturation.name(), "Saturation");
+
+        let state = GraphicsState::default();
+        assert_eq!(
+            state.rendering_intent,
+            RenderingIntent::RelativeColorimetric
+        );
+        assert!(!state.stroke_overprint && !state.fill_overprint);
+        assert_eq!(state.overprint_mode, 0);
+    }
+
     #[test]
     fn test_stroke_props_default() {
         let props = StrokeProps::default();
@@ -444,4 +678,18 @@ mod tests {
         assert!(props.dash_array.is_empty());
         assert_eq!(props.dash_offset, 0.0);
     }
+
+    #[test]
+    fn test_matrix_helpers() {
+        let scale = [2.0, 0.0, 0.0, 3.0, 0.0, 0.0];
+        let translate = [1.0, 0.0, 0.0, 1.0, 10.0, 20.0];
+        assert_eq!(
+            multiply_matrix(&scale, &translate),
+            [2.0, 0.0, 0.0, 3.0, 10.0, 20.0]
+        );
+
+        let inverse = invert_matrix(&[2.0, 0.0, 0.0, 3.0, 10.0, 20.0]).unwrap();
+        assert_eq!(inverse, [0.5, 0.0, 0.0, 1.0 / 3.0, -5.0, -20.0 / 3.0]);
+        assert!(invert_matrix(&[1.0, 2.0, 2.0, 4.0, 0.0, 0.0]).is_none());
+    }
 }
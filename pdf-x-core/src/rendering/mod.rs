@@ -7,18 +7,37 @@
 //! - Path construction and rendering
 //! - Text rendering support
 
+pub mod content_stream_device;
 pub mod context;
 pub mod device;
+pub mod font_resolver;
+pub mod golden;
+#[cfg(feature = "golden-images")]
+pub mod golden_image;
 pub mod graphics_state;
 pub mod path;
+pub mod svg_device;
+pub mod trace_device;
+pub mod vector_paths;
+pub mod viewport;
 
 // Re-export key types
-pub use context::RenderingContext;
-pub use device::{Device, FontWidthMetrics, ImageData, Paint, PathDrawMode, TestDevice};
+pub use content_stream_device::ContentStreamDevice;
+pub use context::{OverprintPaint, OverprintUsage, RenderReport, RenderingContext};
+pub use device::{
+    Device, FontWidthMetrics, GlyphRun, Gradient, ImageData, Paint, PathDrawMode, PositionedGlyph,
+    SoftMaskKind, TestDevice, Tile,
+};
+pub use font_resolver::{DefaultFontResolver, FontClass, FontQuery, FontResolver};
 pub use graphics_state::{
-    Color, FillRule, GraphicsState, LineCap, LineJoin, StrokeProps, TextRenderingMode,
+    BlendMode, Color, FillRule, GraphicsState, LineCap, LineJoin, RenderingIntent, StrokeProps,
+    TextRenderingMode,
 };
 pub use path::{Path, PathBuilder, PathElement};
+pub use svg_device::SvgDevice;
+pub use trace_device::{TraceCall, TraceDevice, read_trace, replay, write_trace};
+pub use vector_paths::{PathFill, PathStroke, VectorPath};
+pub use viewport::{RenderOptions, Viewport};
 
 #[cfg(feature = "rendering")]
 pub mod skia_device;
@@ -1,9 +1,13 @@
 //! A tiny-skia based rendering device.
 
 use crate::core::error::{PDFError, PDFResult};
-use crate::rendering::device::{Device, FontWidthMetrics, ImageData, Paint, PathDrawMode};
+use crate::log_target;
+use crate::rendering::device::{
+    Device, FontWidthMetrics, GlyphRun, Gradient, ImageData, Paint, PathDrawMode, SoftMaskKind,
+    Tile,
+};
 use crate::rendering::type1_font::Type1Font;
-use crate::rendering::{Color, FillRule, LineCap, LineJoin, StrokeProps};
+use crate::rendering::{BlendMode, Color, FillRule, LineCap, LineJoin, StrokeProps};
 use std::collections::HashMap;
 use std::sync::Arc;
 use tiny_skia::{
@@ -18,17 +22,48 @@ fn to_skia_color(color: Color) -> tiny_skia::Color {
     tiny_skia::Color::from_rgba8(color.r(), color.g(), color.b(), color.a())
 }
 
-fn to_skia_paint(paint: &Paint) -> SkiaPaint<'_> {
+fn to_skia_paint(paint: &Paint, blend_mode: tiny_skia::BlendMode) -> SkiaPaint<'_> {
     let mut sk_paint = SkiaPaint::default();
+    sk_paint.blend_mode = blend_mode;
     match paint {
         Paint::Solid(color) => {
             sk_paint.set_color(to_skia_color(*color));
         }
+        // Paths with gradients are drawn by fill_gradient; anything else
+        // (e.g. text) gets a solid approximation
+        Paint::Gradient(gradient) => {
+            sk_paint.set_color(to_skia_color(gradient.middle_color()));
+        }
+        Paint::Tile(tile) => {
+            sk_paint.set_color(to_skia_color(tile.color.unwrap_or(Color::black())));
+        }
     }
     sk_paint.anti_alias = true;
     sk_paint
 }
 
+fn to_skia_blend_mode(blend_mode: BlendMode) -> tiny_skia::BlendMode {
+    use tiny_skia::BlendMode as Skia;
+    match blend_mode {
+        BlendMode::Normal => Skia::SourceOver,
+        BlendMode::Multiply => Skia::Multiply,
+        BlendMode::Screen => Skia::Screen,
+        BlendMode::Overlay => Skia::Overlay,
+        BlendMode::Darken => Skia::Darken,
+        BlendMode::Lighten => Skia::Lighten,
+        BlendMode::ColorDodge => Skia::ColorDodge,
+        BlendMode::ColorBurn => Skia::ColorBurn,
+        BlendMode::HardLight => Skia::HardLight,
+        BlendMode::SoftLight => Skia::SoftLight,
+        BlendMode::Difference => Skia::Difference,
+        BlendMode::Exclusion => Skia::Exclusion,
+        BlendMode::Hue => Skia::Hue,
+        BlendMode::Saturation => Skia::Saturation,
+        BlendMode::Color => Skia::Color,
+        BlendMode::Luminosity => Skia::Luminosity,
+    }
+}
+
 fn to_skia_line_cap(line_cap: LineCap) -> SkiaLineCap {
     match line_cap {
         LineCap::Butt => SkiaLineCap::Butt,
@@ -66,6 +101,8 @@ fn to_skia_stroke(stroke_props: &StrokeProps) -> Stroke {
 struct SkiaGraphicsState {
     transform: Transform,
     clip_mask: Option<Mask>,
+    soft_mask: Option<Mask>,
+    blend_mode: BlendMode,
 }
 
 impl Default for SkiaGraphicsState {
@@ -73,6 +110,8 @@ impl Default for SkiaGraphicsState {
         SkiaGraphicsState {
             transform: Transform::identity(),
             clip_mask: None,
+            soft_mask: None,
+            blend_mode: BlendMode::Normal,
         }
     }
 }
@@ -92,7 +131,7 @@ enum FontType {
         face: ttf_parser::Face<'static>,
         buzz_face: rustybuzz::Face<'static>,
     },
-    /// Type1 font (using hayro-font)
+    /// Type1 or CFF font
     Type1 { font: Type1Font },
 }
 
@@ -197,8 +236,9 @@ fn parse_encoding_dictionary(
         PDFObject::Name(name) => {
             // Named encoding - use predefined glyph name mappings
             #[cfg(feature = "debug-logging")]
-            eprintln!(
-                "DEBUG: Named encoding '{}', loading predefined mapping",
+            tracing::trace!(
+                target: log_target::FONT,
+                "Named encoding '{}', loading predefined mapping",
                 name
             );
             Some(get_predefined_encoding(name.as_str()))
@@ -208,7 +248,10 @@ fn parse_encoding_dictionary(
             if let Some(diff_obj) = dict.get("Differences") {
                 if let PDFObject::Array(arr) = diff_obj {
                     #[cfg(feature = "debug-logging")]
-                    eprintln!("DEBUG: Parsing custom encoding with {} entries", arr.len());
+                    tracing::trace!(
+                        target: log_target::FONT,
+                        "Parsing custom encoding with {} entries", arr.len()
+                    );
 
                     let mut encoding = HashMap::new();
                     let mut current_code: Option<u8> = None;
@@ -223,7 +266,10 @@ fn parse_encoding_dictionary(
                             PDFObject::Name(name) => {
                                 if let Some(code) = current_code {
                                     #[cfg(feature = "debug-logging")]
-                                    eprintln!("DEBUG: Encoding[{}] = '{}'", code, name);
+                                    tracing::trace!(
+                                        target: log_target::FONT,
+                                        "Encoding[{}] = '{}'", code, name
+                                    );
                                     encoding.insert(code, name.clone());
                                     // Increment for next name
                                     current_code = code.checked_add(1);
@@ -234,7 +280,10 @@ fn parse_encoding_dictionary(
                     }
 
                     #[cfg(feature = "debug-logging")]
-                    eprintln!("DEBUG: Parsed {} encoding entries", encoding.len());
+                    tracing::trace!(
+                        target: log_target::FONT,
+                        "Parsed {} encoding entries", encoding.len()
+                    );
                     return Some(encoding);
                 }
             }
@@ -243,8 +292,9 @@ fn parse_encoding_dictionary(
             if let Some(base_obj) = dict.get("BaseEncoding") {
                 if let PDFObject::Name(base_name) = base_obj {
                     #[cfg(feature = "debug-logging")]
-                    eprintln!(
-                        "DEBUG: BaseEncoding '{}', loading predefined mapping",
+                    tracing::trace!(
+                        target: log_target::FONT,
+                        "BaseEncoding '{}', loading predefined mapping",
                         base_name
                     );
                     return Some(get_predefined_encoding(base_name.as_str()));
@@ -505,8 +555,9 @@ fn get_predefined_encoding(name: &str) -> HashMap<u8, String> {
         "WinAnsiEncoding" => {
             // WinAnsiEncoding uses the mappings above (Windows ANSI / CP1252)
             #[cfg(feature = "debug-logging")]
-            eprintln!(
-                "DEBUG: WinAnsiEncoding mapping with {} entries",
+            tracing::trace!(
+                target: log_target::FONT,
+                "WinAnsiEncoding mapping with {} entries",
                 encoding.len()
             );
         }
@@ -514,8 +565,9 @@ fn get_predefined_encoding(name: &str) -> HashMap<u8, String> {
             // StandardEncoding (Adobe Standard) - some differences in 0x80+ range
             // For now, use same as WinAnsi for common characters
             #[cfg(feature = "debug-logging")]
-            eprintln!(
-                "DEBUG: StandardEncoding mapping with {} entries",
+            tracing::trace!(
+                target: log_target::FONT,
+                "StandardEncoding mapping with {} entries",
                 encoding.len()
             );
         }
@@ -523,22 +575,27 @@ fn get_predefined_encoding(name: &str) -> HashMap<u8, String> {
             // MacRoman has different mappings for 0x80+ range
             // For simplicity, using common subset for now
             #[cfg(feature = "debug-logging")]
-            eprintln!(
-                "DEBUG: MacRomanEncoding mapping with {} entries",
+            tracing::trace!(
+                target: log_target::FONT,
+                "MacRomanEncoding mapping with {} entries",
                 encoding.len()
             );
         }
         "MacExpertEncoding" => {
             // Expert encoding for expert fonts
             #[cfg(feature = "debug-logging")]
-            eprintln!(
-                "DEBUG: MacExpertEncoding mapping with {} entries",
+            tracing::trace!(
+                target: log_target::FONT,
+                "MacExpertEncoding mapping with {} entries",
                 encoding.len()
             );
         }
         _ => {
             #[cfg(feature = "debug-logging")]
-            eprintln!("DEBUG: Unknown encoding '{}', using standard mapping", name);
+            tracing::trace!(
+                target: log_target::FONT,
+                "Unknown encoding '{}', using standard mapping", name
+            );
         }
     }
 
@@ -552,6 +609,24 @@ pub struct SkiaDevice<'a> {
     font_cache: HashMap<String, StoredFont>,
     draw_count: usize,
     colors_seen: std::collections::HashMap<String, usize>,
+    /// Soft mask groups being rendered, with the page pixels they replaced
+    soft_mask_groups: Vec<(SoftMaskKind, Vec<u8>)>,
+    /// Tiling pattern cells being rendered, with the page pixels they replaced
+    tile_groups: Vec<(TileImage, Vec<u8>)>,
+    /// Rendered tiling pattern cells, indexed by tile id
+    tiles: Vec<TileImage>,
+    /// Clip masks replaced by push_clip, restored by pop_clip
+    clip_history: Vec<Option<Mask>>,
+    /// Transparency groups being rendered, with the page pixels they replaced
+    /// and whether they are knockout groups
+    groups: Vec<(Vec<u8>, bool)>,
+}
+
+/// A rendered tiling pattern cell.
+struct TileImage {
+    pixmap: Pixmap,
+    /// Maps pattern space to tile pixels
+    transform: Transform,
 }
 
 struct PathConverter(PathBuilder);
@@ -587,19 +662,25 @@ impl<'a> SkiaDevice<'a> {
             font_cache: HashMap::new(),
             draw_count: 0,
             colors_seen: std::collections::HashMap::new(),
+            soft_mask_groups: Vec::new(),
+            tile_groups: Vec::new(),
+            tiles: Vec::new(),
+            clip_history: Vec::new(),
+            groups: Vec::new(),
         }
     }
 
     pub fn print_color_summary(&self) {
         #[cfg(feature = "debug-logging")]
-        eprintln!(
-            "DEBUG: Color summary ({} unique colors):",
+        tracing::trace!(
+            target: log_target::RENDER,
+            "Color summary ({} unique colors):",
             self.colors_seen.len()
         );
         let mut colors: Vec<_> = self.colors_seen.iter().collect();
         colors.sort_by(|a, b| b.1.cmp(a.1)); // Sort by count descending
         for (color, count) in colors {
-            eprintln!("  {}: {} draws", color, count);
+            tracing::trace!(target: log_target::RENDER, "{}: {} draws", color, count);
         }
     }
 
@@ -625,8 +706,9 @@ impl<'a> SkiaDevice<'a> {
         if let Some(enc_obj) = encoding {
             if let Some(encoding_map) = parse_encoding_dictionary(enc_obj) {
                 #[cfg(feature = "debug-logging")]
-                eprintln!(
-                    "DEBUG: Setting custom encoding for font '{}' with {} entries",
+                tracing::trace!(
+                    target: log_target::FONT,
+                    "Setting custom encoding for font '{}' with {} entries",
                     name,
                     encoding_map.len()
                 );
@@ -646,99 +728,214 @@ impl<'a> SkiaDevice<'a> {
         self.state_stack.last_mut().unwrap()
     }
 
-    fn get_clip_mask(&self) -> Option<Mask> {
-        // The clip mask is created when the clip is set, so we just return it
-        // This ensures the mask is in the correct coordinate space (the CTM at the time the clip was set)
-        self.current_state().clip_mask.clone()
+    /// Blend mode for painting: the graphics state's blend mode, except that
+    /// objects in a knockout group replace what the group painted before.
+    fn paint_blend_mode(&self) -> tiny_skia::BlendMode {
+        match self.groups.last() {
+            Some((_, true)) => tiny_skia::BlendMode::Source,
+            _ => to_skia_blend_mode(self.current_state().blend_mode),
+        }
     }
-}
 
-impl<'a> Device for SkiaDevice<'a> {
-    fn begin_path(&mut self) {
-        self.path_builder = PathBuilder::new();
+    /// Fill a path with a gradient or tiling pattern.
+    fn fill_pattern(
+        &mut self,
+        path: &tiny_skia::Path,
+        fill_rule: FillRule,
+        paint: &Paint,
+        transform: Transform,
+        clip_mask: Option<&Mask>,
+    ) {
+        let Some(device_path) = path.clone().transform(transform) else {
+            return;
+        };
+        match paint {
+            Paint::Gradient(gradient) => {
+                self.fill_gradient(&device_path, fill_rule, gradient, transform, clip_mask)
+            }
+            Paint::Tile(tile) => {
+                self.fill_tile(&device_path, fill_rule, tile, transform, clip_mask)
+            }
+            Paint::Solid(_) => {}
+        }
     }
 
-    fn move_to(&mut self, x: f64, y: f64) {
-        self.path_builder.move_to(x as f32, y as f32);
-    }
+    /// Fill a device-space path with a gradient.
+    ///
+    /// The gradient is evaluated per pixel over the path's bounds into a
+    /// tile, which is then used as the fill shader.
+    fn fill_gradient(
+        &mut self,
+        device_path: &tiny_skia::Path,
+        fill_rule: FillRule,
+        gradient: &Gradient,
+        transform: Transform,
+        clip_mask: Option<&Mask>,
+    ) {
+        let Some(inverse) = transform.invert() else {
+            return;
+        };
+        let bounds = device_path.bounds();
+        let x0 = bounds.left().floor().max(0.0) as u32;
+        let y0 = bounds.top().floor().max(0.0) as u32;
+        let x1 = (bounds.right().ceil().max(0.0) as u32).min(self.pixmap.width());
+        let y1 = (bounds.bottom().ceil().max(0.0) as u32).min(self.pixmap.height());
+        if x0 >= x1 || y0 >= y1 {
+            return;
+        }
+        let Some(mut tile) = Pixmap::new(x1 - x0, y1 - y0) else {
+            return;
+        };
 
-    fn line_to(&mut self, x: f64, y: f64) {
-        self.path_builder.line_to(x as f32, y as f32);
-    }
+        let width = tile.width();
+        for (i, pixel) in tile.pixels_mut().iter_mut().enumerate() {
+            let px = (x0 + i as u32 % width) as f32 + 0.5;
+            let py = (y0 + i as u32 / width) as f32 + 0.5;
+            let x = inverse.sx * px + inverse.kx * py + inverse.tx;
+            let y = inverse.ky * px + inverse.sy * py + inverse.ty;
+            if let Some(color) = gradient.color_at(x as f64, y as f64) {
+                *pixel = tiny_skia::PremultipliedColorU8::from_rgba(
+                    color.r(),
+                    color.g(),
+                    color.b(),
+                    255,
+                )
+                .unwrap_or(tiny_skia::PremultipliedColorU8::TRANSPARENT);
+            }
+        }
 
-    fn curve_to(&mut self, cp1x: f64, cp1y: f64, cp2x: f64, cp2y: f64, x: f64, y: f64) {
-        self.path_builder.cubic_to(
-            cp1x as f32,
-            cp1y as f32,
-            cp2x as f32,
-            cp2y as f32,
-            x as f32,
-            y as f32,
+        let mut sk_paint = SkiaPaint::default();
+        sk_paint.anti_alias = true;
+        sk_paint.blend_mode = self.paint_blend_mode();
+        sk_paint.shader = tiny_skia::Pattern::new(
+            tile.as_ref(),
+            tiny_skia::SpreadMode::Pad,
+            tiny_skia::FilterQuality::Nearest,
+            1.0,
+            Transform::from_translate(x0 as f32, y0 as f32),
+        );
+        self.pixmap.fill_path(
+            device_path,
+            &sk_paint,
+            to_skia_fill_rule(fill_rule),
+            Transform::identity(),
+            clip_mask,
         );
     }
 
-    fn rect(&mut self, x: f64, y: f64, width: f64, height: f64) {
-        // Handle invalid rectangles gracefully
-        // PDFs may have negative or zero dimensions, or NaN values
-        let xf = x as f32;
-        let yf = y as f32;
-        let wf = width as f32;
-        let hf = height as f32;
+    /// Fill a device-space path by repeating a rendered tiling pattern cell.
+    fn fill_tile(
+        &mut self,
+        device_path: &tiny_skia::Path,
+        fill_rule: FillRule,
+        tile: &Tile,
+        transform: Transform,
+        clip_mask: Option<&Mask>,
+    ) {
+        let Some(image) = self.tiles.get(tile.id) else {
+            return;
+        };
+        let Some(pixels_to_pattern) = image.transform.invert() else {
+            return;
+        };
+        let [a, b, c, d, e, f] = tile.matrix.map(|v| v as f32);
+        let shader_transform = transform
+            .pre_concat(Transform::from_row(a, b, c, d, e, f))
+            .pre_concat(pixels_to_pattern);
+
+        // Uncolored patterns only use the cell's coverage
+        let recolored;
+        let pixmap = match tile.color {
+            Some(color) => {
+                let mut pixmap = image.pixmap.clone();
+                for pixel in pixmap.pixels_mut() {
+                    let alpha = pixel.alpha() as u16;
+                    let scale = |v: u8| ((v as u16 * alpha + 127) / 255) as u8;
+                    *pixel = tiny_skia::PremultipliedColorU8::from_rgba(
+                        scale(color.r()),
+                        scale(color.g()),
+                        scale(color.b()),
+                        alpha as u8,
+                    )
+                    .unwrap_or(tiny_skia::PremultipliedColorU8::TRANSPARENT);
+                }
+                recolored = pixmap;
+                &recolored
+            }
+            None => &image.pixmap,
+        };
 
-        // Create a valid rectangle, clamping to reasonable bounds
-        if wf > 0.0
-            && hf > 0.0
-            && xf.is_finite()
-            && yf.is_finite()
-            && wf.is_finite()
-            && hf.is_finite()
-        {
-            if let Some(rect) = Rect::from_xywh(xf, yf, wf, hf) {
-                self.path_builder.push_rect(rect);
-                return;
+        let mut sk_paint = SkiaPaint::default();
+        sk_paint.anti_alias = true;
+        sk_paint.blend_mode = self.paint_blend_mode();
+        sk_paint.shader = tiny_skia::Pattern::new(
+            pixmap.as_ref(),
+            tiny_skia::SpreadMode::Repeat,
+            tiny_skia::FilterQuality::Bilinear,
+            1.0,
+            shader_transform,
+        );
+        self.pixmap.fill_path(
+            device_path,
+            &sk_paint,
+            to_skia_fill_rule(fill_rule),
+            Transform::identity(),
+            clip_mask,
+        );
+    }
+
+    fn get_clip_mask(&self) -> Option<Mask> {
+        // The clip mask is created when the clip is set, so we just return it
+        // This ensures the mask is in the correct coordinate space (the CTM at the time the clip was set)
+        // A soft mask is combined with it by multiplying coverage values
+        let state = self.current_state();
+        match (&state.clip_mask, &state.soft_mask) {
+            (Some(clip_mask), Some(soft_mask)) => {
+                let mut mask = clip_mask.clone();
+                for (value, soft) in mask.data_mut().iter_mut().zip(soft_mask.data()) {
+                    *value = ((*value as u16 * *soft as u16 + 127) / 255) as u8;
+                }
+                Some(mask)
             }
+            (clip_mask, soft_mask) => clip_mask.clone().or_else(|| soft_mask.clone()),
         }
-
-        // Fallback: draw rectangle using lines for invalid rects
-        self.move_to(x, y);
-        self.line_to(x + width, y);
-        self.line_to(x + width, y + height);
-        self.line_to(x, y + height);
-        self.close_path();
     }
 
-    fn close_path(&mut self) {
-        self.path_builder.close();
+    /// Takes the current path, leaving an empty one.
+    fn take_path(&mut self) -> PDFResult<tiny_skia::Path> {
+        std::mem::replace(&mut self.path_builder, PathBuilder::new())
+            .finish()
+            .ok_or(PDFError::Generic("Invalid path".into()))
     }
 
-    fn draw_path(
+    /// Fills and/or strokes a path with one paint.
+    fn paint_path(
         &mut self,
+        path: &tiny_skia::Path,
         mode: PathDrawMode,
         paint: &Paint,
         stroke_props: &StrokeProps,
-    ) -> PDFResult<()> {
-        // Take ownership of the path_builder and create a new one
-        let path = std::mem::replace(&mut self.path_builder, PathBuilder::new())
-            .finish()
-            .ok_or(PDFError::Generic("Invalid path".into()))?;
-
+    ) {
         let transform = self.current_state().transform;
 
         // Debug logging for path info
         #[cfg(feature = "debug-logging")]
         {
             if self.draw_count < 5 {
-                eprintln!(
-                    "DEBUG: draw_path #{} path.bounds={:?}",
+                tracing::trace!(
+                    target: log_target::RENDER,
+                    "draw_path #{} path.bounds={:?}",
                     self.draw_count,
                     path.bounds()
                 );
-                eprintln!(
-                    "DEBUG: draw_path #{} transform={:?}",
+                tracing::trace!(
+                    target: log_target::RENDER,
+                    "draw_path #{} transform={:?}",
                     self.draw_count, transform
                 );
-                eprintln!(
-                    "DEBUG: draw_path #{} pixmap.size={}x{}",
+                tracing::trace!(
+                    target: log_target::RENDER,
+                    "draw_path #{} pixmap.size={}x{}",
                     self.draw_count,
                     self.pixmap.width(),
                     self.pixmap.height()
@@ -758,32 +955,54 @@ impl<'a> Device for SkiaDevice<'a> {
                 Paint::Solid(crate::rendering::Color::RGB(1.0, 1.0, 1.0))
             );
             if self.draw_count < 20 || !is_white {
-                eprintln!(
-                    "DEBUG: draw_path #{} mode={:?}, paint={}",
+                tracing::trace!(
+                    target: log_target::RENDER,
+                    "draw_path #{} mode={:?}, paint={}",
                     self.draw_count, mode, color_str
                 );
             }
 
             // Milestone logging
             if self.draw_count == 100 || self.draw_count == 500 || self.draw_count == 1000 {
-                eprintln!("DEBUG: {} draws completed, color summary:", self.draw_count);
+                tracing::trace!(
+                    target: log_target::RENDER,
+                    "{} draws completed, color summary:", self.draw_count
+                );
                 let mut colors: Vec<_> = self.colors_seen.iter().collect();
                 colors.sort_by(|a, b| b.1.cmp(a.1));
                 for (color, count) in colors.iter().take(5) {
-                    eprintln!("  {}: {} draws", color, count);
+                    tracing::trace!(target: log_target::RENDER, "{}: {} draws", color, count);
                 }
             }
         }
 
         self.draw_count += 1;
 
-        let sk_paint = to_skia_paint(paint);
         let clip_mask = self.get_clip_mask();
+        if matches!(paint, Paint::Gradient(_) | Paint::Tile(_)) {
+            if let PathDrawMode::Fill(fill_rule) | PathDrawMode::FillStroke(fill_rule) = mode {
+                self.fill_pattern(path, fill_rule, paint, transform, clip_mask.as_ref());
+            }
+            if let PathDrawMode::Stroke | PathDrawMode::FillStroke(_) = mode
+                && let Some(outline) = path.stroke(&to_skia_stroke(stroke_props), 1.0)
+            {
+                self.fill_pattern(
+                    &outline,
+                    FillRule::NonZero,
+                    paint,
+                    transform,
+                    clip_mask.as_ref(),
+                );
+            }
+            return;
+        }
+
+        let sk_paint = to_skia_paint(paint, self.paint_blend_mode());
 
         match mode {
             PathDrawMode::Fill(fill_rule) => {
                 self.pixmap.fill_path(
-                    &path,
+                    path,
                     &sk_paint,
                     to_skia_fill_rule(fill_rule),
                     transform,
@@ -792,33 +1011,104 @@ impl<'a> Device for SkiaDevice<'a> {
             }
             PathDrawMode::Stroke => {
                 let sk_stroke = to_skia_stroke(stroke_props);
-                self.pixmap.stroke_path(
-                    &path,
-                    &sk_paint,
-                    &sk_stroke,
-                    transform,
-                    clip_mask.as_ref(),
-                );
+                self.pixmap
+                    .stroke_path(path, &sk_paint, &sk_stroke, transform, clip_mask.as_ref());
             }
             PathDrawMode::FillStroke(fill_rule) => {
                 self.pixmap.fill_path(
-                    &path,
+                    path,
                     &sk_paint,
                     to_skia_fill_rule(fill_rule),
                     transform,
                     clip_mask.as_ref(),
                 );
                 let sk_stroke = to_skia_stroke(stroke_props);
-                self.pixmap.stroke_path(
-                    &path,
-                    &sk_paint,
-                    &sk_stroke,
-                    transform,
-                    clip_mask.as_ref(),
-                );
+                self.pixmap
+                    .stroke_path(path, &sk_paint, &sk_stroke, transform, clip_mask.as_ref());
+            }
+        }
+    }
+}
+
+impl<'a> Device for SkiaDevice<'a> {
+    fn begin_path(&mut self) {
+        self.path_builder = PathBuilder::new();
+    }
+
+    fn move_to(&mut self, x: f64, y: f64) {
+        self.path_builder.move_to(x as f32, y as f32);
+    }
+
+    fn line_to(&mut self, x: f64, y: f64) {
+        self.path_builder.line_to(x as f32, y as f32);
+    }
+
+    fn curve_to(&mut self, cp1x: f64, cp1y: f64, cp2x: f64, cp2y: f64, x: f64, y: f64) {
+        self.path_builder.cubic_to(
+            cp1x as f32,
+            cp1y as f32,
+            cp2x as f32,
+            cp2y as f32,
+            x as f32,
+            y as f32,
+        );
+    }
+
+    fn rect(&mut self, x: f64, y: f64, width: f64, height: f64) {
+        // Handle invalid rectangles gracefully
+        // PDFs may have negative or zero dimensions, or NaN values
+        let xf = x as f32;
+        let yf = y as f32;
+        let wf = width as f32;
+        let hf = height as f32;
+
+        // Create a valid rectangle, clamping to reasonable bounds
+        if wf > 0.0
+            && hf > 0.0
+            && xf.is_finite()
+            && yf.is_finite()
+            && wf.is_finite()
+            && hf.is_finite()
+        {
+            if let Some(rect) = Rect::from_xywh(xf, yf, wf, hf) {
+                self.path_builder.push_rect(rect);
+                return;
             }
         }
 
+        // Fallback: draw rectangle using lines for invalid rects
+        self.move_to(x, y);
+        self.line_to(x + width, y);
+        self.line_to(x + width, y + height);
+        self.line_to(x, y + height);
+        self.close_path();
+    }
+
+    fn close_path(&mut self) {
+        self.path_builder.close();
+    }
+
+    fn draw_path(
+        &mut self,
+        mode: PathDrawMode,
+        paint: &Paint,
+        stroke_props: &StrokeProps,
+    ) -> PDFResult<()> {
+        let path = self.take_path()?;
+        self.paint_path(&path, mode, paint, stroke_props);
+        Ok(())
+    }
+
+    fn fill_and_stroke_path(
+        &mut self,
+        rule: FillRule,
+        fill: &Paint,
+        stroke: &Paint,
+        stroke_props: &StrokeProps,
+    ) -> PDFResult<()> {
+        let path = self.take_path()?;
+        self.paint_path(&path, PathDrawMode::Fill(rule), fill, stroke_props);
+        self.paint_path(&path, PathDrawMode::Stroke, stroke, stroke_props);
         Ok(())
     }
 
@@ -836,17 +1126,35 @@ impl<'a> Device for SkiaDevice<'a> {
             FillRule::EvenOdd => SkiaFillRule::EvenOdd,
         };
 
-        if let Some(mut mask) = Mask::new(self.pixmap.width(), self.pixmap.height()) {
-            mask.fill_path(&path, fill_rule, false, transform);
-            self.current_state_mut().clip_mask = Some(mask);
-        } else {
-            // If mask creation failed, just don't clip
-            self.current_state_mut().clip_mask = None;
-        }
+        // Intersect with the clip already in effect
+        let mask = match self.current_state().clip_mask.clone() {
+            Some(mut mask) => {
+                mask.intersect_path(&path, fill_rule, false, transform);
+                Some(mask)
+            }
+            // If mask creation fails, just don't clip
+            None => Mask::new(self.pixmap.width(), self.pixmap.height()).map(|mut mask| {
+                mask.fill_path(&path, fill_rule, false, transform);
+                mask
+            }),
+        };
+        self.current_state_mut().clip_mask = mask;
 
         Ok(())
     }
 
+    fn push_clip(&mut self, rule: FillRule) -> PDFResult<()> {
+        self.clip_history
+            .push(self.current_state().clip_mask.clone());
+        self.clip_path(rule)
+    }
+
+    fn pop_clip(&mut self) {
+        if let Some(mask) = self.clip_history.pop() {
+            self.current_state_mut().clip_mask = mask;
+        }
+    }
+
     fn save_state(&mut self) {
         let current_state = self.current_state().clone();
         self.state_stack.push(current_state);
@@ -871,8 +1179,9 @@ impl<'a> Device for SkiaDevice<'a> {
         let new_transform = self.current_state().transform.pre_concat(transform);
         self.current_state_mut().transform = new_transform;
         #[cfg(feature = "debug-logging")]
-        eprintln!(
-            "DEBUG: concat_matrix: [{:.1},{:.1},{:.1},{:.1},{:.1},{:.1}] -> {:?}",
+        tracing::trace!(
+            target: log_target::RENDER,
+            "concat_matrix: [{:.1},{:.1},{:.1},{:.1},{:.1},{:.1}] -> {:?}",
             matrix[0], matrix[1], matrix[2], matrix[3], matrix[4], matrix[5], new_transform
         );
     }
@@ -888,8 +1197,9 @@ impl<'a> Device for SkiaDevice<'a> {
         );
         self.current_state_mut().transform = new_transform;
         #[cfg(feature = "debug-logging")]
-        eprintln!(
-            "DEBUG: set_matrix: [{:.1},{:.1},{:.1},{:.1},{:.1},{:.1}] -> {:?}",
+        tracing::trace!(
+            target: log_target::RENDER,
+            "set_matrix: [{:.1},{:.1},{:.1},{:.1},{:.1},{:.1}] -> {:?}",
             matrix[0], matrix[1], matrix[2], matrix[3], matrix[4], matrix[5], new_transform
         );
     }
@@ -907,8 +1217,9 @@ impl<'a> Device for SkiaDevice<'a> {
         text_rise: f64,
     ) -> PDFResult<f64> {
         #[cfg(feature = "debug-logging")]
-        eprintln!(
-            "DEBUG: draw_text called with font='{}', size={}, bytes={:?}, text_matrix={:?}",
+        tracing::trace!(
+            target: log_target::FONT,
+            "draw_text called with font='{}', size={}, bytes={:?}, text_matrix={:?}",
             font_name, font_size, text_bytes, text_matrix
         );
 
@@ -916,16 +1227,18 @@ impl<'a> Device for SkiaDevice<'a> {
         // Check if Type1 first
         let is_type1 = if let Some(font) = self.font_cache.get(font_name) {
             #[cfg(feature = "debug-logging")]
-            eprintln!(
-                "DEBUG: Font cache hit for '{}', is_type1={}",
+            tracing::trace!(
+                target: log_target::FONT,
+                "Font cache hit for '{}', is_type1={}",
                 font_name,
                 font.is_type1()
             );
             font.is_type1()
         } else {
             #[cfg(feature = "debug-logging")]
-            eprintln!(
-                "DEBUG: Font cache MISS for '{}' - skipping text rendering",
+            tracing::trace!(
+                target: log_target::FONT,
+                "Font cache MISS for '{}' - skipping text rendering",
                 font_name
             );
             return Ok(0.0); // Silent failure - return 0 width
@@ -995,7 +1308,7 @@ impl<'a> Device for SkiaDevice<'a> {
             let total_rendered_width = current_x as f64;
 
             if let Some(path) = text_path_builder.finish() {
-                let sk_paint = to_skia_paint(paint);
+                let sk_paint = to_skia_paint(paint, self.paint_blend_mode());
                 let ctm = self.current_state().transform;
 
                 // Reference: hayro/hayro-interpret/src/interpret/state.rs:104-179
@@ -1036,8 +1349,9 @@ impl<'a> Device for SkiaDevice<'a> {
                 let clip_mask = self.get_clip_mask();
 
                 #[cfg(feature = "debug-logging")]
-                eprintln!(
-                    "DEBUG: Text CTM={:?}, text_matrix=[{:.1},{:.1},{:.1},{:.1},{:.1},{:.1}], hscale={:.1}%, rise={:.1}, full_text={:?}, final={:?}",
+                tracing::trace!(
+                    target: log_target::RENDER,
+                    "Text CTM={:?}, text_matrix=[{:.1},{:.1},{:.1},{:.1},{:.1},{:.1}], hscale={:.1}%, rise={:.1}, full_text={:?}, final={:?}",
                     ctm, tm_a, tm_b, tm_c, tm_d, tm_e, tm_f, horizontal_scaling, text_rise, full_text, final_transform
                 );
 
@@ -1055,8 +1369,9 @@ impl<'a> Device for SkiaDevice<'a> {
 
         // Type1/CFF font rendering
         #[cfg(feature = "debug-logging")]
-        eprintln!(
-            "DEBUG: Type1/CFF font rendering, {:?} bytes",
+        tracing::trace!(
+            target: log_target::FONT,
+            "Type1/CFF font rendering, {:?} bytes",
             text_bytes.len()
         );
 
@@ -1129,8 +1444,9 @@ impl<'a> Device for SkiaDevice<'a> {
         }
 
         #[cfg(feature = "debug-logging")]
-        eprintln!(
-            "DEBUG: Text rendering: {}/{} glyphs rendered",
+        tracing::trace!(
+            target: log_target::FONT,
+            "Text rendering: {}/{} glyphs rendered",
             glyphs_rendered, glyphs_total
         );
 
@@ -1139,9 +1455,9 @@ impl<'a> Device for SkiaDevice<'a> {
 
         if let Some(path) = text_path_builder.finish() {
             #[cfg(feature = "debug-logging")]
-            eprintln!("DEBUG: Text path created successfully");
+            tracing::trace!(target: log_target::RENDER, "Text path created successfully");
 
-            let sk_paint = to_skia_paint(paint);
+            let sk_paint = to_skia_paint(paint, self.paint_blend_mode());
             let ctm = self.current_state().transform;
 
             // Reference: hayro/hayro-interpret/src/interpret/state.rs:104-179
@@ -1182,8 +1498,9 @@ impl<'a> Device for SkiaDevice<'a> {
             let clip_mask = self.get_clip_mask();
 
             #[cfg(feature = "debug-logging")]
-            eprintln!(
-                "DEBUG: CTM={:?}, text_matrix=[{:.1},{:.1},{:.1},{:.1},{:.1},{:.1}], hscale={:.1}%, rise={:.1}, full_text={:?}, final={:?}",
+            tracing::trace!(
+                target: log_target::RENDER,
+                "CTM={:?}, text_matrix=[{:.1},{:.1},{:.1},{:.1},{:.1},{:.1}], hscale={:.1}%, rise={:.1}, full_text={:?}, final={:?}",
                 ctm, tm_a, tm_b, tm_c, tm_d, tm_e, tm_f, horizontal_scaling, text_rise, full_text, final_transform
             );
 
@@ -1196,19 +1513,103 @@ impl<'a> Device for SkiaDevice<'a> {
             );
 
             #[cfg(feature = "debug-logging")]
-            eprintln!("DEBUG: Text path drawn to pixmap");
+            tracing::trace!(target: log_target::RENDER, "Text path drawn to pixmap");
         } else {
             #[cfg(feature = "debug-logging")]
-            eprintln!("DEBUG: Failed to create text path from path builder");
+            tracing::trace!(
+                target: log_target::RENDER,
+                "Failed to create text path from path builder"
+            );
         }
 
         Ok(total_rendered_width)
     }
 
+    fn draw_glyph_run(&mut self, run: &GlyphRun, paint: &Paint) -> PDFResult<bool> {
+        // Glyph IDs index sfnt fonts (CIDFontType2 / OpenType); CFF programs
+        // (CIDFontType0) are indexed by CID
+        let Some(font) = self.font_cache.get(&run.font_name) else {
+            return Ok(false);
+        };
+        let cff_font = font.type1_font().filter(|font| font.is_cff());
+        if font.face().is_none() && cff_font.is_none() {
+            return Ok(false);
+        }
+
+        // Glyph outlines in font units, placed at their em offsets
+        let mut text_path_builder = PathBuilder::new();
+        for glyph in &run.glyphs {
+            let (outline, glyph_space) = if let Some(face) = font.face() {
+                let mut converter = PathConverter(PathBuilder::new());
+                if face
+                    .outline_glyph(ttf_parser::GlyphId(glyph.glyph_id), &mut converter)
+                    .is_none()
+                {
+                    continue; // e.g. space
+                }
+                let units_per_em = face.units_per_em() as f32;
+                (
+                    converter.0.finish(),
+                    Transform::from_scale(1.0 / units_per_em, 1.0 / units_per_em),
+                )
+            } else if let Some(cff_font) = cff_font {
+                // CFF glyph space is mapped by the font's own FontMatrix,
+                // which is not always 1000 units per em
+                let mut builder = PathBuilder::new();
+                if cff_font
+                    .outline_glyph_cid(glyph.glyph_id, &mut builder)
+                    .is_none()
+                {
+                    continue;
+                }
+                let [a, b, c, d, e, f] = cff_font.glyph_matrix().map(|v| v as f32);
+                (builder.finish(), Transform::from_row(a, b, c, d, e, f))
+            } else {
+                continue;
+            };
+            let transform = glyph_space.post_translate(glyph.x as f32, glyph.y as f32);
+            if let Some(path) = outline
+                && let Some(path) = path.transform(transform)
+            {
+                text_path_builder.push_path(&path);
+            }
+        }
+
+        if let Some(path) = text_path_builder.finish() {
+            // Same text rendering matrix as draw_text: Tm × [Tfs·Th 0 0 Tfs 0 Trise]
+            let [a, b, c, d, e, f] = run.text_matrix.map(|v| v as f32);
+            let font_size = run.font_size as f32;
+            let h_scale = run.horizontal_scaling as f32 / 100.0;
+            let rise = run.text_rise as f32;
+            let full_text = Transform::from_row(
+                a * font_size * h_scale,
+                b * font_size * h_scale,
+                c * font_size,
+                d * font_size,
+                c * rise + e,
+                d * rise + f,
+            );
+            let final_transform = full_text.post_concat(self.current_state().transform);
+
+            let sk_paint = to_skia_paint(paint, self.paint_blend_mode());
+            let clip_mask = self.get_clip_mask();
+            self.pixmap.fill_path(
+                &path,
+                &sk_paint,
+                SkiaFillRule::Winding,
+                final_transform,
+                clip_mask.as_ref(),
+            );
+        }
+
+        Ok(true)
+    }
+
     fn draw_image(&mut self, image: ImageData, transform: &[f64; 6]) -> PDFResult<()> {
         #[cfg(feature = "debug-logging")]
-        eprintln!(
-            "DEBUG: SkiaDevice::draw_image: {}x{}, transform=[{:?}]",
+        tracing::trace!(
+            target: log_target::IMAGE,
+            "SkiaDevice::draw_image: {}x{}, transform=[{:?}]",
             image.width, image.height, transform
         );
 
@@ -1221,8 +1622,9 @@ impl<'a> Device for SkiaDevice<'a> {
                 if data_len >= ((image.width as usize * image.height as usize + 7) / 8) =>
             {
                 #[cfg(feature = "debug-logging")]
-                eprintln!(
-                    "DEBUG: Detected 1-bpp image, {}x{}, {} bytes",
+                tracing::trace!(
+                    target: log_target::IMAGE,
+                    "Detected 1-bpp image, {}x{}, {} bytes",
                     image.width, image.height, data_len
                 );
 
@@ -1382,8 +1784,9 @@ impl<'a> Device for SkiaDevice<'a> {
             }
 
             _ => {
-                eprintln!(
-                    "WARNING: Unsupported image format - has_alpha={}, bits={}, data_len={}, pixel_count={}",
+                tracing::warn!(
+                    target: log_target::IMAGE,
+                    "Unsupported image format - has_alpha={}, bits={}, data_len={}, pixel_count={}",
                     image.has_alpha,
                     image.bits_per_component,
                     image.data.len(),
@@ -1401,8 +1804,9 @@ impl<'a> Device for SkiaDevice<'a> {
             .ok_or(PDFError::Generic("Failed to create image pixmap".into()))?;
 
         #[cfg(feature = "debug-logging")]
-        eprintln!(
-            "DEBUG: Image pixmap created: {}x{}",
+        tracing::trace!(
+            target: log_target::IMAGE,
+            "Image pixmap created: {}x{}",
             image_pixmap.width(),
             image_pixmap.height()
         );
@@ -1421,8 +1825,9 @@ impl<'a> Device for SkiaDevice<'a> {
                     non_white_count += 1;
                 }
             }
-            eprintln!(
-                "DEBUG: Image pixmap has {} non-white pixels in first 100",
+            tracing::trace!(
+                target: log_target::IMAGE,
+                "Image pixmap has {} non-white pixels in first 100",
                 non_white_count
             );
         }
@@ -1439,8 +1844,9 @@ impl<'a> Device for SkiaDevice<'a> {
         let ctm = self.current_state().transform;
 
         #[cfg(feature = "debug-logging")]
-        eprintln!(
-            "DEBUG: CTM={:?}, image_transform={:?}",
+        tracing::trace!(
+            target: log_target::IMAGE,
+            "CTM={:?}, image_transform={:?}",
             ctm, image_transform
         );
         #[cfg(not(feature = "debug-logging"))]
@@ -1483,21 +1889,26 @@ impl<'a> Device for SkiaDevice<'a> {
         adjusted_transform.ty = final_transform.ty + final_transform.sy * image.height as f32; // Adjust Y position
 
         #[cfg(feature = "debug-logging")]
-        eprintln!(
-            "DEBUG: image_to_unit={:?}, final_transform={:?}",
+        tracing::trace!(
+            target: log_target::IMAGE,
+            "image_to_unit={:?}, final_transform={:?}",
             image_to_unit, final_transform
         );
         #[cfg(feature = "debug-logging")]
-        eprintln!("DEBUG: adjusted_transform={:?}", adjusted_transform);
+        tracing::trace!(target: log_target::RENDER, "adjusted_transform={:?}", adjusted_transform);
 
         #[cfg(feature = "debug-logging")]
-        eprintln!("DEBUG: clip_mask.is_some()={}", clip_mask.is_some());
+        tracing::trace!(target: log_target::RENDER, "clip_mask.is_some()={}", clip_mask.is_some());
 
+        let paint = tiny_skia::PixmapPaint {
+            blend_mode: self.paint_blend_mode(),
+            ..tiny_skia::PixmapPaint::default()
+        };
         self.pixmap.draw_pixmap(
             0,
             0,
             image_pixmap.as_ref(),
-            &tiny_skia::PixmapPaint::default(),
+            &paint,
             adjusted_transform,
             clip_mask.as_ref(),
         );
@@ -1509,6 +1920,183 @@ impl<'a> Device for SkiaDevice<'a> {
         (self.pixmap.width() as f64, self.pixmap.height() as f64)
     }
 
+    fn begin_soft_mask(&mut self, kind: SoftMaskKind, backdrop: &Color) -> PDFResult<bool> {
+        // Render the group into the page pixmap and put the page back afterwards,
+        // so drawing code doesn't need a separate offscreen target.
+        // Reference: pdf.js/src/display/canvas.js - beginSMaskMode
+        let saved = self.pixmap.data_mut().to_vec();
+        self.pixmap.fill(match kind {
+            SoftMaskKind::Luminosity => to_skia_color(*backdrop),
+            SoftMaskKind::Alpha => tiny_skia::Color::TRANSPARENT,
+        });
+        self.soft_mask_groups.push((kind, saved));
+
+        // The group is painted without the page's clip and soft mask
+        let mut state = self.current_state().clone();
+        state.clip_mask = None;
+        state.soft_mask = None;
+        state.blend_mode = BlendMode::Normal;
+        self.state_stack.push(state);
+        Ok(true)
+    }
+
+    fn end_soft_mask(&mut self) -> PDFResult<()> {
+        let Some((kind, saved)) = self.soft_mask_groups.pop() else {
+            return Ok(());
+        };
+        if self.state_stack.len() > 1 {
+            self.state_stack.pop();
+        }
+
+        let mut mask = Mask::new(self.pixmap.width(), self.pixmap.height());
+        if let Some(mask) = mask.as_mut() {
+            let pixels = self.pixmap.data_mut();
+            for (value, pixel) in mask.data_mut().iter_mut().zip(pixels.chunks_exact(4)) {
+                *value = match kind {
+                    SoftMaskKind::Alpha => pixel[3],
+                    // The backdrop is opaque, so premultiplied and straight colors agree
+                    SoftMaskKind::Luminosity => {
+                        (0.3 * pixel[0] as f32 + 0.59 * pixel[1] as f32 + 0.11 * pixel[2] as f32)
+                            .round()
+                            .min(255.0) as u8
+                    }
+                };
+            }
+        }
+        self.pixmap.data_mut().copy_from_slice(&saved);
+        self.current_state_mut().soft_mask = mask;
+        Ok(())
+    }
+
+    fn clear_soft_mask(&mut self) {
+        self.current_state_mut().soft_mask = None;
+    }
+
+    fn set_blend_mode(&mut self, mode: BlendMode) {
+        self.current_state_mut().blend_mode = mode;
+    }
+
+    fn begin_group(&mut self, isolated: bool, knockout: bool) -> PDFResult<bool> {
+        // Like soft masks, the group is rendered into the page pixmap and the
+        // page is put back afterwards. Non-isolated groups also start from a
+        // transparent backdrop, as in pdf.js, which only differs from the
+        // specification when objects inside the group use blend modes.
+        // Reference: pdf.js/src/display/canvas.js - beginGroup
+        let _ = isolated;
+        let saved = self.pixmap.data_mut().to_vec();
+        self.pixmap.fill(tiny_skia::Color::TRANSPARENT);
+        self.groups.push((saved, knockout));
+
+        let mut state = self.current_state().clone();
+        state.soft_mask = None;
+        state.blend_mode = BlendMode::Normal;
+        self.state_stack.push(state);
+        Ok(true)
+    }
+
+    fn end_group(&mut self) -> PDFResult<()> {
+        let Some((saved, _)) = self.groups.pop() else {
+            return Ok(());
+        };
+        if self.state_stack.len() > 1 {
+            self.state_stack.pop();
+        }
+
+        let size = tiny_skia::IntSize::from_wh(self.pixmap.width(), self.pixmap.height());
+        let group = size.and_then(|size| Pixmap::from_vec(self.pixmap.data_mut().to_vec(), size));
+        self.pixmap.data_mut().copy_from_slice(&saved);
+        let Some(group) = group else {
+            return Ok(());
+        };
+
+        // Composite the group as one object with the page's blend mode,
+        // clip and soft mask
+        let clip_mask = self.get_clip_mask();
+        let paint = tiny_skia::PixmapPaint {
+            blend_mode: self.paint_blend_mode(),
+            ..tiny_skia::PixmapPaint::default()
+        };
+        self.pixmap.draw_pixmap(
+            0,
+            0,
+            group.as_ref(),
+            &paint,
+            Transform::identity(),
+            clip_mask.as_ref(),
+        );
+        Ok(())
+    }
+
+    fn begin_tile(&mut self, cell: &[f64; 4], matrix: &[f64; 6]) -> PDFResult<bool> {
+        // Like soft masks, the cell is rendered into the page pixmap (at its
+        // top left) and the page is put back afterwards
+        let [a, b, c, d, e, f] = matrix.map(|v| v as f32);
+        let to_device = self
+            .current_state()
+            .transform
+            .pre_concat(Transform::from_row(a, b, c, d, e, f));
+        let (width, height) = ((cell[2] - cell[0]) as f32, (cell[3] - cell[1]) as f32);
+        let (page_width, page_height) = (self.pixmap.width(), self.pixmap.height());
+        let scale = (to_device.sx * to_device.sy - to_device.kx * to_device.ky)
+            .abs()
+            .sqrt()
+            .min(page_width as f32 / width)
+            .min(page_height as f32 / height);
+        if !scale.is_finite() || scale <= 0.0 {
+            return Ok(false);
+        }
+        let tile_width = ((width * scale).ceil() as u32).clamp(1, page_width);
+        let tile_height = ((height * scale).ceil() as u32).clamp(1, page_height);
+        let Some(pixmap) = Pixmap::new(tile_width, tile_height) else {
+            return Ok(false);
+        };
+        let transform = Transform::from_row(
+            scale,
+            0.0,
+            0.0,
+            scale,
+            -cell[0] as f32 * scale,
+            -cell[1] as f32 * scale,
+        );
+
+        let saved = self.pixmap.data_mut().to_vec();
+        self.pixmap.fill(tiny_skia::Color::TRANSPARENT);
+        self.tile_groups
+            .push((TileImage { pixmap, transform }, saved));
+
+        self.state_stack.push(SkiaGraphicsState {
+            transform,
+            ..SkiaGraphicsState::default()
+        });
+        Ok(true)
+    }
+
+    fn end_tile(&mut self) -> PDFResult<usize> {
+        let Some((mut image, saved)) = self.tile_groups.pop() else {
+            return Ok(usize::MAX);
+        };
+        if self.state_stack.len() > 1 {
+            self.state_stack.pop();
+        }
+
+        let row_bytes = image.pixmap.width() as usize * 4;
+        let page_row_bytes = self.pixmap.width() as usize * 4;
+        let page = self.pixmap.data_mut();
+        for (row, tile_row) in image
+            .pixmap
+            .data_mut()
+            .chunks_exact_mut(row_bytes)
+            .enumerate()
+        {
+            let start = row * page_row_bytes;
+            tile_row.copy_from_slice(&page[start..start + row_bytes]);
+        }
+        page.copy_from_slice(&saved);
+
+        self.tiles.push(image);
+        Ok(self.tiles.len() - 1)
+    }
+
     fn load_font_data(
         &mut self,
         name: &str,
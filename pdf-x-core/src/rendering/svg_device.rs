@@ -0,0 +1,811 @@
+//! SVG output device.
+//!
+//! This module provides a Device implementation that writes vector SVG
+//! instead of rasterizing. Paths keep their geometry, text is written as
+//! `<text>` elements positioned glyph by glyph, and images are embedded as
+//! PNG data URIs. The output is resolution independent and, being plain
+//! text, easy to diff.
+//!
+//! Every element carries its own `transform`, so clip paths and groups never
+//! change the coordinate system and can be nested freely.
+
+use super::device::{Device, FontWidthMetrics, Gradient, ImageData, Paint, PathDrawMode};
+use super::graphics_state::{
+    BlendMode, Color, FillRule, LineCap, LineJoin, StrokeProps, multiply_matrix,
+};
+use crate::core::error::{PDFError, PDFResult};
+use crate::core::shading::ShadingGeometry;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+
+/// Number of color stops written for a gradient.
+const GRADIENT_STOPS: usize = 32;
+
+/// A device that renders to an SVG document.
+///
+/// # Example
+/// ```no_run
+/// use pdf_x_core::PDFDocument;
+/// use pdf_x_core::rendering::{Device, SvgDevice};
+///
+/// let mut doc = PDFDocument::open_file("document.pdf", None, None).unwrap();
+/// let page = doc.get_page(0).unwrap();
+///
+/// // Flip the y axis so that the top of the page is at the top of the image
+/// let mut device = SvgDevice::new(612.0, 792.0);
+/// device.set_matrix(&[1.0, 0.0, 0.0, -1.0, 0.0, 792.0]);
+/// page.render(doc.xref_mut(), &mut device).unwrap();
+/// std::fs::write("page.svg", device.finish()).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct SvgDevice {
+    /// Image width in pixels
+    width: f64,
+    /// Image height in pixels
+    height: f64,
+    /// Gradients and clip paths, referenced by id
+    defs: String,
+    /// Drawing elements
+    body: String,
+    /// Current path data in user space
+    path: String,
+    /// Graphics state stack
+    state_stack: Vec<SvgGraphicsState>,
+    /// Width metrics of loaded simple fonts, by font name
+    font_metrics: HashMap<String, FontWidthMetrics>,
+    /// Counter for generated element ids
+    next_id: usize,
+}
+
+#[derive(Debug, Clone)]
+struct SvgGraphicsState {
+    ctm: [f64; 6],
+    blend_mode: BlendMode,
+    /// Clip `<g>` elements opened while this state was current
+    open_clips: usize,
+}
+
+impl Default for SvgGraphicsState {
+    fn default() -> Self {
+        SvgGraphicsState {
+            ctm: [1.0, 0.0, 0.0, 1.0, 0.0, 0.0],
+            blend_mode: BlendMode::Normal,
+            open_clips: 0,
+        }
+    }
+}
+
+impl SvgDevice {
+    /// Create a new SVG device with the given image size in pixels.
+    pub fn new(width: f64, height: f64) -> Self {
+        SvgDevice {
+            width,
+            height,
+            defs: String::new(),
+            body: String::new(),
+            path: String::new(),
+            state_stack: vec![SvgGraphicsState::default()],
+            font_metrics: HashMap::new(),
+            next_id: 0,
+        }
+    }
+
+    /// Fill the whole image with a color, covering anything drawn so far.
+    pub fn fill_background(&mut self, color: &Color) {
+        let _ = writeln!(
+            self.body,
+            r#"<rect width="{}" height="{}" fill="{}"/>"#,
+            num(self.width),
+            num(self.height),
+            css_color(color)
+        );
+    }
+
+    /// Finish drawing and return the SVG document.
+    pub fn finish(mut self) -> String {
+        let open_groups: usize = self.state_stack.iter().map(|s| s.open_clips).sum();
+        for _ in 0..open_groups {
+            self.body.push_str("</g>\n");
+        }
+
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">\n",
+            w = num(self.width),
+            h = num(self.height)
+        );
+        if !self.defs.is_empty() {
+            svg.push_str("<defs>\n");
+            svg.push_str(&self.defs);
+            svg.push_str("</defs>\n");
+        }
+        svg.push_str(&self.body);
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    fn current_state(&self) -> &SvgGraphicsState {
+        self.state_stack.last().unwrap()
+    }
+
+    fn current_state_mut(&mut self) -> &mut SvgGraphicsState {
+        self.state_stack.last_mut().unwrap()
+    }
+
+    fn new_id(&mut self, prefix: &str) -> String {
+        self.next_id += 1;
+        format!("{}{}", prefix, self.next_id)
+    }
+
+    /// Attributes shared by all painted elements: the CTM and the blend mode.
+    fn placement(&self, matrix: &[f64; 6]) -> String {
+        let mut attributes = format!(r#" transform="{}""#, svg_matrix(matrix));
+        let blend_mode = self.current_state().blend_mode;
+        if blend_mode != BlendMode::Normal {
+            let _ = write!(
+                attributes,
+                r#" style="mix-blend-mode:{}""#,
+                css_blend_mode(blend_mode)
+            );
+        }
+        attributes
+    }
+
+    /// Writes a `<path>` element with its paint attributes, adding the
+    /// stroke properties unless it is only filled.
+    fn write_path(
+        &mut self,
+        path: &str,
+        mut attributes: String,
+        mode: PathDrawMode,
+        stroke_props: &StrokeProps,
+    ) {
+        if !matches!(mode, PathDrawMode::Fill(_)) {
+            write_stroke_props(&mut attributes, stroke_props);
+        }
+
+        let placement = self.placement(&self.current_state().ctm);
+        let _ = writeln!(
+            self.body,
+            r#"<path{} d="{}" {}/>"#,
+            placement,
+            path.trim_end(),
+            attributes
+        );
+    }
+
+    /// Returns the SVG paint for `paint`, adding a gradient to the defs if
+    /// needed. Tiling patterns are not supported and paint nothing.
+    fn svg_paint(&mut self, paint: &Paint) -> String {
+        match paint {
+            Paint::Solid(color) => css_color(color),
+            Paint::Gradient(gradient) => {
+                let id = self.new_id("g");
+                self.write_gradient(&id, gradient);
+                format!("url(#{})", id)
+            }
+            Paint::Tile(_) => "none".to_string(),
+        }
+    }
+
+    fn write_gradient(&mut self, id: &str, gradient: &Gradient) {
+        // SVG gradients always pad; /Extend false is not representable
+        let (element, geometry) = match gradient.geometry {
+            ShadingGeometry::Axial {
+                coords: [x0, y0, x1, y1],
+            } => (
+                "linearGradient",
+                format!(
+                    r#"x1="{}" y1="{}" x2="{}" y2="{}""#,
+                    num(x0),
+                    num(y0),
+                    num(x1),
+                    num(y1)
+                ),
+            ),
+            ShadingGeometry::Radial {
+                coords: [x0, y0, r0, x1, y1, r1],
+            } => (
+                "radialGradient",
+                format!(
+                    r#"fx="{}" fy="{}" fr="{}" cx="{}" cy="{}" r="{}""#,
+                    num(x0),
+                    num(y0),
+                    num(r0),
+                    num(x1),
+                    num(y1),
+                    num(r1)
+                ),
+            ),
+        };
+        let _ = writeln!(
+            self.defs,
+            r#"<{} id="{}" gradientUnits="userSpaceOnUse" gradientTransform="{}" {}>"#,
+            element,
+            id,
+            svg_matrix(&gradient.matrix),
+            geometry
+        );
+        let last = gradient.colors.len().saturating_sub(1);
+        for i in 0..GRADIENT_STOPS {
+            let offset = i as f64 / (GRADIENT_STOPS - 1) as f64;
+            let color = gradient.colors[(offset * last as f64).round() as usize];
+            let _ = writeln!(
+                self.defs,
+                r#"<stop offset="{}" stop-color="{}"/>"#,
+                num(offset),
+                css_color(&color)
+            );
+        }
+        let _ = writeln!(self.defs, "</{}>", element);
+    }
+
+    /// Clip subsequent drawing to the current path with a new `<g>`.
+    fn open_clip(&mut self, rule: FillRule) {
+        let id = self.new_id("c");
+        let path = std::mem::take(&mut self.path);
+        let _ = writeln!(
+            self.defs,
+            r#"<clipPath id="{}" clipPathUnits="userSpaceOnUse"><path transform="{}" d="{}" clip-rule="{}"/></clipPath>"#,
+            id,
+            svg_matrix(&self.current_state().ctm),
+            path.trim_end(),
+            fill_rule(rule)
+        );
+        let _ = writeln!(self.body, r#"<g clip-path="url(#{})">"#, id);
+        self.current_state_mut().open_clips += 1;
+    }
+
+    fn close_clips(&mut self, count: usize) {
+        for _ in 0..count {
+            self.body.push_str("</g>\n");
+        }
+    }
+
+    /// Approximate advance of a glyph in text space, before spacing.
+    fn glyph_width(&self, font_name: &str, code: u8, font_size: f64) -> f64 {
+        let width = self
+            .font_metrics
+            .get(font_name)
+            .map_or(500, |metrics| metrics.width(code));
+        width as f64 * font_size / 1000.0
+    }
+}
+
+impl Device for SvgDevice {
+    fn begin_path(&mut self) {
+        self.path.clear();
+    }
+
+    fn move_to(&mut self, x: f64, y: f64) {
+        let _ = write!(self.path, "M{} {} ", num(x), num(y));
+    }
+
+    fn line_to(&mut self, x: f64, y: f64) {
+        let _ = write!(self.path, "L{} {} ", num(x), num(y));
+    }
+
+    fn curve_to(&mut self, cp1x: f64, cp1y: f64, cp2x: f64, cp2y: f64, x: f64, y: f64) {
+        let _ = write!(
+            self.path,
+            "C{} {} {} {} {} {} ",
+            num(cp1x),
+            num(cp1y),
+            num(cp2x),
+            num(cp2y),
+            num(x),
+            num(y)
+        );
+    }
+
+    fn rect(&mut self, x: f64, y: f64, width: f64, height: f64) {
+        let _ = write!(
+            self.path,
+            "M{} {} h{} v{} h{} Z ",
+            num(x),
+            num(y),
+            num(width),
+            num(height),
+            num(-width)
+        );
+    }
+
+    fn close_path(&mut self) {
+        self.path.push_str("Z ");
+    }
+
+    fn draw_path(
+        &mut self,
+        mode: PathDrawMode,
+        paint: &Paint,
+        stroke_props: &StrokeProps,
+    ) -> PDFResult<()> {
+        let path = std::mem::take(&mut self.path);
+        if path.is_empty() {
+            return Ok(());
+        }
+        let svg_paint = self.svg_paint(paint);
+
+        let attributes = match mode {
+            PathDrawMode::Fill(rule) => {
+                format!(r#"fill="{}" fill-rule="{}""#, svg_paint, fill_rule(rule))
+            }
+            PathDrawMode::Stroke => format!(r#"fill="none" stroke="{}""#, svg_paint),
+            PathDrawMode::FillStroke(rule) => format!(
+                r#"fill="{p}" fill-rule="{}" stroke="{p}""#,
+                fill_rule(rule),
+                p = svg_paint
+            ),
+        };
+        self.write_path(&path, attributes, mode, stroke_props);
+        Ok(())
+    }
+
+    fn fill_and_stroke_path(
+        &mut self,
+        rule: FillRule,
+        fill: &Paint,
+        stroke: &Paint,
+        stroke_props: &StrokeProps,
+    ) -> PDFResult<()> {
+        let path = std::mem::take(&mut self.path);
+        if path.is_empty() {
+            return Ok(());
+        }
+        let attributes = format!(
+            r#"fill="{}" fill-rule="{}" stroke="{}""#,
+            self.svg_paint(fill),
+            fill_rule(rule),
+            self.svg_paint(stroke)
+        );
+        self.write_path(
+            &path,
+            attributes,
+            PathDrawMode::FillStroke(rule),
+            stroke_props,
+        );
+        Ok(())
+    }
+
+    fn clip_path(&mut self, rule: FillRule) -> PDFResult<()> {
+        self.open_clip(rule);
+        Ok(())
+    }
+
+    fn pop_clip(&mut self) {
+        let state = self.current_state_mut();
+        if state.open_clips > 0 {
+            state.open_clips -= 1;
+            self.close_clips(1);
+        }
+    }
+
+    fn save_state(&mut self) {
+        let current = SvgGraphicsState {
+            open_clips: 0,
+            ..self.current_state().clone()
+        };
+        self.state_stack.push(current);
+    }
+
+    fn restore_state(&mut self) {
+        if self.state_stack.len() > 1 {
+            let state = self.state_stack.pop().unwrap();
+            self.close_clips(state.open_clips);
+        }
+    }
+
+    fn concat_matrix(&mut self, matrix: &[f64; 6]) {
+        let state = self.current_state_mut();
+        state.ctm = multiply_matrix(matrix, &state.ctm);
+    }
+
+    fn set_matrix(&mut self, matrix: &[f64; 6]) {
+        self.current_state_mut().ctm = *matrix;
+    }
+
+    fn draw_text(
+        &mut self,
+        text_bytes: &[u8],
+        font_name: &str,
+        font_size: f64,
+        character_spacing: f64,
+        word_spacing: f64,
+        paint: &Paint,
+        text_matrix: &[f64; 6],
+        horizontal_scaling: f64,
+        text_rise: f64,
+    ) -> PDFResult<f64> {
+        // Glyph positions along the baseline, in text space before scaling
+        let mut positions = Vec::with_capacity(text_bytes.len());
+        let mut width = 0.0;
+        for &byte in text_bytes {
+            positions.push(width);
+            width += self.glyph_width(font_name, byte, font_size) + character_spacing;
+            if byte == b' ' {
+                width += word_spacing;
+            }
+        }
+        if font_size == 0.0 || text_bytes.is_empty() {
+            return Ok(width);
+        }
+
+        // Glyphs are drawn one unit high and flipped, as SVG text runs
+        // downwards; the text matrix and CTM place them
+        // Reference: hayro/hayro-interpret/src/interpret/state.rs:104-179
+        let h_scale = horizontal_scaling / 100.0;
+        let glyph_space = [font_size * h_scale, 0.0, 0.0, -font_size, 0.0, text_rise];
+        let transform = multiply_matrix(
+            &multiply_matrix(&glyph_space, text_matrix),
+            &self.current_state().ctm,
+        );
+
+        let color = match paint {
+            Paint::Gradient(gradient) => css_color(&gradient.middle_color()),
+            paint => self.svg_paint(paint),
+        };
+        let x: Vec<String> = positions.iter().map(|x| num(x / font_size)).collect();
+        let text: String = text_bytes.iter().map(|&b| b as char).collect();
+        let placement = self.placement(&transform);
+        let _ = writeln!(
+            self.body,
+            r#"<text{} x="{}" font-size="1" font-family="sans-serif" fill="{}" xml:space="preserve">{}</text>"#,
+            placement,
+            x.join(" "),
+            color,
+            escape_xml(&text)
+        );
+        Ok(width)
+    }
+
+    fn draw_image(&mut self, image: ImageData, transform: &[f64; 6]) -> PDFResult<()> {
+        let Some(rgba) = image_to_rgba(&image) else {
+            return Err(PDFError::Generic(format!(
+                "Unsupported image data: {}x{}, {} bpc, {} bytes",
+                image.width,
+                image.height,
+                image.bits_per_component,
+                image.data.len()
+            )));
+        };
+        let png = encode_png(image.width, image.height, &rgba)?;
+
+        // The image fills the unit square with its first row at the top
+        let flip = [1.0, 0.0, 0.0, -1.0, 0.0, 1.0];
+        let matrix = multiply_matrix(
+            &multiply_matrix(&flip, transform),
+            &self.current_state().ctm,
+        );
+        let placement = self.placement(&matrix);
+        let _ = writeln!(
+            self.body,
+            r#"<image{} width="1" height="1" preserveAspectRatio="none" href="data:image/png;base64,{}"/>"#,
+            placement,
+            base64(&png)
+        );
+        Ok(())
+    }
+
+    fn page_bounds(&self) -> (f64, f64) {
+        (self.width, self.height)
+    }
+
+    fn set_blend_mode(&mut self, mode: BlendMode) {
+        self.current_state_mut().blend_mode = mode;
+    }
+
+    fn begin_group(&mut self, _isolated: bool, _knockout: bool) -> PDFResult<bool> {
+        // Knockout groups are painted as normal groups
+        let mut style = "isolation:isolate".to_string();
+        let blend_mode = self.current_state().blend_mode;
+        if blend_mode != BlendMode::Normal {
+            let _ = write!(style, ";mix-blend-mode:{}", css_blend_mode(blend_mode));
+        }
+        let _ = writeln!(self.body, r#"<g style="{}">"#, style);
+        self.save_state();
+        self.current_state_mut().blend_mode = BlendMode::Normal;
+        Ok(true)
+    }
+
+    fn end_group(&mut self) -> PDFResult<()> {
+        self.restore_state();
+        self.body.push_str("</g>\n");
+        Ok(())
+    }
+
+    fn set_font_width_metrics(&mut self, name: &str, metrics: &FontWidthMetrics) -> PDFResult<()> {
+        self.font_metrics.insert(name.to_string(), metrics.clone());
+        Ok(())
+    }
+}
+
+/// Formats a number with at most four decimals.
+fn num(value: f64) -> String {
+    let rounded = (value * 10000.0).round() / 10000.0;
+    if rounded == 0.0 {
+        "0".to_string()
+    } else {
+        rounded.to_string()
+    }
+}
+
+fn svg_matrix(matrix: &[f64; 6]) -> String {
+    let values: Vec<String> = matrix.iter().map(|&v| num(v)).collect();
+    format!("matrix({})", values.join(" "))
+}
+
+fn css_color(color: &Color) -> String {
+    let (r, g, b, _) = color.rgba();
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+fn fill_rule(rule: FillRule) -> &'static str {
+    match rule {
+        FillRule::NonZero => "nonzero",
+        FillRule::EvenOdd => "evenodd",
+    }
+}
+
+fn css_blend_mode(mode: BlendMode) -> &'static str {
+    match mode {
+        BlendMode::Normal => "normal",
+        BlendMode::Multiply => "multiply",
+        BlendMode::Screen => "screen",
+        BlendMode::Overlay => "overlay",
+        BlendMode::Darken => "darken",
+        BlendMode::Lighten => "lighten",
+        BlendMode::ColorDodge => "color-dodge",
+        BlendMode::ColorBurn => "color-burn",
+        BlendMode::HardLight => "hard-light",
+        BlendMode::SoftLight => "soft-light",
+        BlendMode::Difference => "difference",
+        BlendMode::Exclusion => "exclusion",
+        BlendMode::Hue => "hue",
+        BlendMode::Saturation => "saturation",
+        BlendMode::Color => "color",
+        BlendMode::Luminosity => "luminosity",
+    }
+}
+
+fn write_stroke_props(attributes: &mut String, props: &StrokeProps) {
+    let cap = match props.line_cap {
+        LineCap::Butt => "butt",
+        LineCap::Round => "round",
+        LineCap::ProjectingSquare => "square",
+    };
+    let join = match props.line_join {
+        LineJoin::Miter => "miter",
+        LineJoin::Round => "round",
+        LineJoin::Bevel => "bevel",
+    };
+    let _ = write!(
+        attributes,
+        r#" stroke-width="{}" stroke-linecap="{}" stroke-linejoin="{}" stroke-miterlimit="{}""#,
+        num(props.line_width),
+        cap,
+        join,
+        num(props.miter_limit)
+    );
+    if !props.dash_array.is_empty() && props.dash_array.iter().any(|&d| d > 0.0) {
+        let dashes: Vec<String> = props.dash_array.iter().map(|&d| num(d)).collect();
+        let _ = write!(
+            attributes,
+            r#" stroke-dasharray="{}" stroke-dashoffset="{}""#,
+            dashes.join(" "),
+            num(props.dash_offset)
+        );
+    }
+}
+
+pub(crate) fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            // Control characters are not allowed in XML
+            c if (c as u32) < 0x20 && !matches!(c, '\t' | '\n' | '\r') => escaped.push(' '),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Converts device image data (1-bit, gray, RGB, RGBA or CMYK) to RGBA.
+pub(crate) fn image_to_rgba(image: &ImageData) -> Option<Vec<u8>> {
+    let pixels = image.width as usize * image.height as usize;
+    let data = &image.data;
+    let rgba = match image.bits_per_component {
+        1 => {
+            // One bit per pixel, rows padded to whole bytes; set bits are black
+            let row_bytes = (image.width as usize).div_ceil(8);
+            if data.len() < row_bytes * image.height as usize {
+                return None;
+            }
+            let mut rgba = Vec::with_capacity(pixels * 4);
+            for row in data.chunks(row_bytes).take(image.height as usize) {
+                for x in 0..image.width as usize {
+                    let v = if (row[x / 8] >> (7 - x % 8)) & 1 != 0 {
+                        0
+                    } else {
+                        255
+                    };
+                    rgba.extend_from_slice(&[v, v, v, 255]);
+                }
+            }
+            rgba
+        }
+        8 if image.has_alpha && data.len() >= pixels * 4 => data[..pixels * 4].to_vec(),
+        8 if data.len() >= pixels * 4 => data
+            .chunks(4)
+            .take(pixels)
+            .flat_map(|c| {
+                let color = Color::CMYK(
+                    c[0] as f64 / 255.0,
+                    c[1] as f64 / 255.0,
+                    c[2] as f64 / 255.0,
+                    c[3] as f64 / 255.0,
+                );
+                let (r, g, b, a) = color.rgba();
+                [r, g, b, a]
+            })
+            .collect(),
+        8 if data.len() >= pixels * 3 => data
+            .chunks(3)
+            .take(pixels)
+            .flat_map(|c| [c[0], c[1], c[2], 255])
+            .collect(),
+        8 if data.len() >= pixels => data
+            .iter()
+            .take(pixels)
+            .flat_map(|&v| [v, v, v, 255])
+            .collect(),
+        _ => return None,
+    };
+    Some(rgba)
+}
+
+/// Encodes RGBA pixels as a PNG file.
+#[cfg(feature = "png-encoding")]
+pub(crate) fn encode_png(width: u32, height: u32, rgba: &[u8]) -> PDFResult<Vec<u8>> {
+    use image::ImageEncoder;
+
+    let length = width as usize * height as usize * 4;
+    let Some(pixels) = rgba.get(..length) else {
+        return Err(PDFError::Generic(format!(
+            "{} bytes of RGBA data for a {}x{} image",
+            rgba.len(),
+            width,
+            height
+        )));
+    };
+    let mut png = Vec::new();
+    image::codecs::png::PngEncoder::new(&mut png)
+        .write_image(pixels, width, height, image::ExtendedColorType::Rgba8)
+        .map_err(|e| PDFError::Generic(format!("PNG encoding failed: {}", e)))?;
+    Ok(png)
+}
+
+/// Encodes RGBA pixels as a PNG file when PNG encoding is not enabled
+#[cfg(not(feature = "png-encoding"))]
+pub(crate) fn encode_png(_width: u32, _height: u32, _rgba: &[u8]) -> PDFResult<Vec<u8>> {
+    Err(PDFError::unsupported(
+        "PNG encoding not enabled. Enable the 'png-encoding' feature.",
+    ))
+}
+
+/// Encodes bytes as standard base64 with padding, for data URLs.
+#[cfg(feature = "png-encoding")]
+pub(crate) fn base64(data: &[u8]) -> String {
+    use ::base64::Engine;
+
+    ::base64::engine::general_purpose::STANDARD.encode(data)
+}
+
+/// Without PNG encoding there is no image data to put in a data URL.
+#[cfg(not(feature = "png-encoding"))]
+pub(crate) fn base64(_data: &[u8]) -> String {
+    String::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_svg_paths_and_clips() {
+        let mut device = SvgDevice::new(100.0, 100.0);
+        device.set_matrix(&[1.0, 0.0, 0.0, -1.0, 0.0, 100.0]);
+
+        device.save_state();
+        device.begin_path();
+        device.rect(0.0, 0.0, 50.0, 50.0);
+        device.push_clip(FillRule::EvenOdd).unwrap();
+        device.set_blend_mode(BlendMode::Multiply);
+        device.begin_path();
+        device.move_to(10.0, 10.0);
+        device.line_to(20.5, 10.0);
+        device
+            .draw_path(
+                PathDrawMode::Stroke,
+                &Paint::from_color(Color::RGB(1.0, 0.0, 0.0)),
+                &StrokeProps::default(),
+            )
+            .unwrap();
+        device.restore_state();
+        device.begin_path();
+        device.rect(0.0, 0.0, 10.0, 10.0);
+        device
+            .draw_path(
+                PathDrawMode::Fill(FillRule::NonZero),
+                &Paint::black(),
+                &StrokeProps::default(),
+            )
+            .unwrap();
+
+        let svg = device.finish();
+        assert!(svg.contains(
+            r#"<clipPath id="c1" clipPathUnits="userSpaceOnUse"><path transform="matrix(1 0 0 -1 0 100)" d="M0 0 h50 v50 h-50 Z" clip-rule="evenodd"/></clipPath>"#
+        ));
+        assert!(svg.contains(
+            "<g clip-path=\"url(#c1)\">\n<path transform=\"matrix(1 0 0 -1 0 100)\" style=\"mix-blend-mode:multiply\" d=\"M10 10 L20.5 10\" fill=\"none\" stroke=\"#ff0000\" stroke-width=\"1\""
+        ));
+        // The clip and blend mode end with the restored state
+        assert!(svg.contains(
+            "</g>\n<path transform=\"matrix(1 0 0 -1 0 100)\" d=\"M0 0 h10 v10 h-10 Z\" fill=\"#000000\" fill-rule=\"nonzero\"/>\n</svg>"
+        ));
+    }
+
+    #[test]
+    fn test_svg_text_and_image() {
+        let mut device = SvgDevice::new(100.0, 100.0);
+        let mut metrics = FontWidthMetrics::default();
+        metrics.code_widths.insert(b'A', 600);
+        device.set_font_width_metrics("F1", &metrics).unwrap();
+
+        let width = device
+            .draw_text(
+                b"A<B",
+                "F1",
+                10.0,
+                1.0,
+                0.0,
+                &Paint::black(),
+                &[1.0, 0.0, 0.0, 1.0, 5.0, 20.0],
+                100.0,
+                0.0,
+            )
+            .unwrap();
+        assert_eq!(width, 6.0 + 5.0 + 5.0 + 3.0);
+
+        let image = ImageData {
+            width: 1,
+            height: 1,
+            data: vec![255, 0, 0],
+            has_alpha: false,
+            bits_per_component: 8,
+        };
+        device
+            .draw_image(image, &[1.0, 0.0, 0.0, 1.0, 0.0, 0.0])
+            .unwrap();
+
+        let svg = device.finish();
+        assert!(svg.contains(
+            r##"<text transform="matrix(10 0 0 -10 5 20)" x="0 0.7 1.3" font-size="1" font-family="sans-serif" fill="#000000" xml:space="preserve">A&lt;B</text>"##
+        ));
+        assert!(svg.contains(
+            r#"<image transform="matrix(1 0 0 -1 0 1)" width="1" height="1" preserveAspectRatio="none" href="data:image/png;base64,iVBORw0KGgo"#
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "png-encoding")]
+    fn test_encode_png_and_base64() {
+        assert_eq!(base64(b"fo"), "Zm8=");
+
+        let png = encode_png(2, 1, &[255, 0, 0, 255, 0, 0, 255, 128]).unwrap();
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+        assert!(encode_png(2, 2, &[0; 8]).is_err());
+    }
+}
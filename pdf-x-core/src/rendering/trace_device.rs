@@ -0,0 +1,1293 @@
+//! Recording and replay of device calls.
+//!
+//! A [`TraceDevice`] wraps another device, forwards every call to it and
+//! records the call with its arguments as a [`TraceCall`]. The recording
+//! can be written to a text file with [`write_trace`], read back with
+//! [`read_trace`] and fed into any device with [`replay`]. This gives
+//! minimal reproductions of rendering bugs that don't need the original
+//! document, and lets tests check what the rendering context asked a device
+//! to do without rasterizing anything.
+//!
+//! The trace format is one PDF array per call, such as
+//! `[/move_to 10 20]` or `[/draw_path /Fill /NonZero [/Solid [/Gray 0]] [1 0 0 10 [] 0]]`,
+//! so it can be read and trimmed by hand. Numbers are rounded to six
+//! decimal places, so a trace doesn't change with floating point noise and
+//! can serve as a golden file (see [`golden`](super::golden)).
+//!
+//! # Example
+//! ```no_run
+//! use pdf_x_core::PDFDocument;
+//! use pdf_x_core::rendering::{SvgDevice, TestDevice, TraceDevice, read_trace, replay, write_trace};
+//!
+//! let mut doc = PDFDocument::open_file("bug.pdf", None, None).unwrap();
+//! let page = doc.get_page(0).unwrap();
+//! let mut device = TraceDevice::new(TestDevice::new(612.0, 792.0));
+//! page.render(doc.xref_mut(), &mut device).unwrap();
+//! std::fs::write("bug.trace", write_trace(device.calls()).unwrap()).unwrap();
+//!
+//! // Later, without the document
+//! let calls = read_trace(&std::fs::read("bug.trace").unwrap()).unwrap();
+//! let mut svg = SvgDevice::new(612.0, 792.0);
+//! replay(&calls, &mut svg).unwrap();
+//! ```
+
+use super::device::{
+    Device, FontWidthMetrics, GlyphRun, Gradient, ImageData, Paint, PathDrawMode, PositionedGlyph,
+    SoftMaskKind, Tile,
+};
+use super::graphics_state::{BlendMode, Color, FillRule, LineCap, LineJoin, StrokeProps};
+use crate::core::error::{PDFError, PDFResult};
+use crate::core::lexer::Lexer;
+use crate::core::parser::{PDFObject, Parser};
+use crate::core::pdf_writer::PDFWriter;
+use crate::core::shading::ShadingGeometry;
+use crate::core::stream::Stream;
+use std::collections::HashMap;
+
+/// Numbers are written rounded to six decimal places.
+const NUMBER_PRECISION: f64 = 1_000_000.0;
+
+/// A recorded device call.
+///
+/// Each variant corresponds to the [`Device`] method of the same name. Calls
+/// that return whether the device handled them record the answer, so that
+/// [`replay`] can skip the ones the rendering context fell back from.
+#[derive(Debug, Clone)]
+pub enum TraceCall {
+    BeginPath,
+    MoveTo(f64, f64),
+    LineTo(f64, f64),
+    CurveTo(f64, f64, f64, f64, f64, f64),
+    Rect(f64, f64, f64, f64),
+    ClosePath,
+    DrawPath {
+        mode: PathDrawMode,
+        paint: Paint,
+        stroke_props: StrokeProps,
+    },
+    FillAndStrokePath {
+        rule: FillRule,
+        fill: Paint,
+        stroke: Paint,
+        stroke_props: StrokeProps,
+    },
+    ClipPath(FillRule),
+    PushClip(FillRule),
+    PopClip,
+    SaveState,
+    RestoreState,
+    ConcatMatrix([f64; 6]),
+    SetMatrix([f64; 6]),
+    DrawText {
+        text: Vec<u8>,
+        font_name: String,
+        font_size: f64,
+        character_spacing: f64,
+        word_spacing: f64,
+        paint: Paint,
+        text_matrix: [f64; 6],
+        horizontal_scaling: f64,
+        text_rise: f64,
+    },
+    DrawGlyphRun {
+        run: GlyphRun,
+        paint: Paint,
+        /// Whether the device drew the run; if not, the `DrawText` calls
+        /// of the fallback follow
+        drawn: bool,
+    },
+    DrawImage {
+        image: ImageData,
+        transform: [f64; 6],
+    },
+    BeginSoftMask {
+        kind: SoftMaskKind,
+        backdrop: Color,
+        /// Whether the device rendered the mask group
+        accepted: bool,
+    },
+    EndSoftMask,
+    ClearSoftMask,
+    SetBlendMode(BlendMode),
+    BeginGroup {
+        isolated: bool,
+        knockout: bool,
+        /// Whether the device rendered the group offscreen
+        accepted: bool,
+    },
+    EndGroup,
+    BeginTile {
+        cell: [f64; 4],
+        matrix: [f64; 6],
+        /// Whether the device rendered the pattern cell
+        accepted: bool,
+    },
+    EndTile {
+        /// Id the device returned for the cell, referred to by tile paints
+        id: usize,
+    },
+    LoadFontData {
+        name: String,
+        data: Vec<u8>,
+        encoding: Option<PDFObject>,
+    },
+    SetFontWidthMetrics {
+        name: String,
+        metrics: FontWidthMetrics,
+    },
+}
+
+/// A device that records every call before passing it on.
+///
+/// Results, such as text advances and tile ids, come from the wrapped
+/// device. To record without drawing, wrap a
+/// [`TestDevice`](super::TestDevice).
+pub struct TraceDevice<D: Device> {
+    inner: D,
+    calls: Vec<TraceCall>,
+}
+
+impl<D: Device> TraceDevice<D> {
+    /// Creates a device that records the calls made to `inner`.
+    pub fn new(inner: D) -> Self {
+        TraceDevice {
+            inner,
+            calls: Vec::new(),
+        }
+    }
+
+    /// Returns the calls recorded so far.
+    pub fn calls(&self) -> &[TraceCall] {
+        &self.calls
+    }
+
+    /// Returns the wrapped device.
+    pub fn inner(&self) -> &D {
+        &self.inner
+    }
+
+    /// Returns the wrapped device and the recorded calls.
+    pub fn into_parts(self) -> (D, Vec<TraceCall>) {
+        (self.inner, self.calls)
+    }
+}
+
+impl<D: Device> Device for TraceDevice<D> {
+    fn begin_path(&mut self) {
+        self.calls.push(TraceCall::BeginPath);
+        self.inner.begin_path();
+    }
+
+    fn move_to(&mut self, x: f64, y: f64) {
+        self.calls.push(TraceCall::MoveTo(x, y));
+        self.inner.move_to(x, y);
+    }
+
+    fn line_to(&mut self, x: f64, y: f64) {
+        self.calls.push(TraceCall::LineTo(x, y));
+        self.inner.line_to(x, y);
+    }
+
+    fn curve_to(&mut self, cp1x: f64, cp1y: f64, cp2x: f64, cp2y: f64, x: f64, y: f64) {
+        self.calls
+            .push(TraceCall::CurveTo(cp1x, cp1y, cp2x, cp2y, x, y));
+        self.inner.curve_to(cp1x, cp1y, cp2x, cp2y, x, y);
+    }
+
+    fn rect(&mut self, x: f64, y: f64, width: f64, height: f64) {
+        self.calls.push(TraceCall::Rect(x, y, width, height));
+        self.inner.rect(x, y, width, height);
+    }
+
+    fn close_path(&mut self) {
+        self.calls.push(TraceCall::ClosePath);
+        self.inner.close_path();
+    }
+
+    fn draw_path(
+        &mut self,
+        mode: PathDrawMode,
+        paint: &Paint,
+        stroke_props: &StrokeProps,
+    ) -> PDFResult<()> {
+        self.calls.push(TraceCall::DrawPath {
+            mode,
+            paint: paint.clone(),
+            stroke_props: stroke_props.clone(),
+        });
+        self.inner.draw_path(mode, paint, stroke_props)
+    }
+
+    fn fill_and_stroke_path(
+        &mut self,
+        rule: FillRule,
+        fill: &Paint,
+        stroke: &Paint,
+        stroke_props: &StrokeProps,
+    ) -> PDFResult<()> {
+        self.calls.push(TraceCall::FillAndStrokePath {
+            rule,
+            fill: fill.clone(),
+            stroke: stroke.clone(),
+            stroke_props: stroke_props.clone(),
+        });
+        self.inner
+            .fill_and_stroke_path(rule, fill, stroke, stroke_props)
+    }
+
+    fn clip_path(&mut self, rule: FillRule) -> PDFResult<()> {
+        self.calls.push(TraceCall::ClipPath(rule));
+        self.inner.clip_path(rule)
+    }
+
+    fn push_clip(&mut self, rule: FillRule) -> PDFResult<()> {
+        self.calls.push(TraceCall::PushClip(rule));
+        self.inner.push_clip(rule)
+    }
+
+    fn pop_clip(&mut self) {
+        self.calls.push(TraceCall::PopClip);
+        self.inner.pop_clip();
+    }
+
+    fn save_state(&mut self) {
+        self.calls.push(TraceCall::SaveState);
+        self.inner.save_state();
+    }
+
+    fn restore_state(&mut self) {
+        self.calls.push(TraceCall::RestoreState);
+        self.inner.restore_state();
+    }
+
+    fn concat_matrix(&mut self, matrix: &[f64; 6]) {
+        self.calls.push(TraceCall::ConcatMatrix(*matrix));
+        self.inner.concat_matrix(matrix);
+    }
+
+    fn set_matrix(&mut self, matrix: &[f64; 6]) {
+        self.calls.push(TraceCall::SetMatrix(*matrix));
+        self.inner.set_matrix(matrix);
+    }
+
+    fn draw_text(
+        &mut self,
+        text_bytes: &[u8],
+        font_name: &str,
+        font_size: f64,
+        character_spacing: f64,
+        word_spacing: f64,
+        paint: &Paint,
+        text_matrix: &[f64; 6],
+        horizontal_scaling: f64,
+        text_rise: f64,
+    ) -> PDFResult<f64> {
+        self.calls.push(TraceCall::DrawText {
+            text: text_bytes.to_vec(),
+            font_name: font_name.to_string(),
+            font_size,
+            character_spacing,
+            word_spacing,
+            paint: paint.clone(),
+            text_matrix: *text_matrix,
+            horizontal_scaling,
+            text_rise,
+        });
+        self.inner.draw_text(
+            text_bytes,
+            font_name,
+            font_size,
+            character_spacing,
+            word_spacing,
+            paint,
+            text_matrix,
+            horizontal_scaling,
+            text_rise,
+        )
+    }
+
+    fn draw_glyph_run(&mut self, run: &GlyphRun, paint: &Paint) -> PDFResult<bool> {
+        let drawn = self.inner.draw_glyph_run(run, paint)?;
+        self.calls.push(TraceCall::DrawGlyphRun {
+            run: run.clone(),
+            paint: paint.clone(),
+            drawn,
+        });
+        Ok(drawn)
+    }
+
+    fn draw_image(&mut self, image: ImageData, transform: &[f64; 6]) -> PDFResult<()> {
+        self.calls.push(TraceCall::DrawImage {
+            image: image.clone(),
+            transform: *transform,
+        });
+        self.inner.draw_image(image, transform)
+    }
+
+    fn page_bounds(&self) -> (f64, f64) {
+        self.inner.page_bounds()
+    }
+
+    fn begin_soft_mask(&mut self, kind: SoftMaskKind, backdrop: &Color) -> PDFResult<bool> {
+        let accepted = self.inner.begin_soft_mask(kind, backdrop)?;
+        self.calls.push(TraceCall::BeginSoftMask {
+            kind,
+            backdrop: *backdrop,
+            accepted,
+        });
+        Ok(accepted)
+    }
+
+    fn end_soft_mask(&mut self) -> PDFResult<()> {
+        self.calls.push(TraceCall::EndSoftMask);
+        self.inner.end_soft_mask()
+    }
+
+    fn clear_soft_mask(&mut self) {
+        self.calls.push(TraceCall::ClearSoftMask);
+        self.inner.clear_soft_mask();
+    }
+
+    fn set_blend_mode(&mut self, mode: BlendMode) {
+        self.calls.push(TraceCall::SetBlendMode(mode));
+        self.inner.set_blend_mode(mode);
+    }
+
+    fn begin_group(&mut self, isolated: bool, knockout: bool) -> PDFResult<bool> {
+        let accepted = self.inner.begin_group(isolated, knockout)?;
+        self.calls.push(TraceCall::BeginGroup {
+            isolated,
+            knockout,
+            accepted,
+        });
+        Ok(accepted)
+    }
+
+    fn end_group(&mut self) -> PDFResult<()> {
+        self.calls.push(TraceCall::EndGroup);
+        self.inner.end_group()
+    }
+
+    fn begin_tile(&mut self, cell: &[f64; 4], matrix: &[f64; 6]) -> PDFResult<bool> {
+        let accepted = self.inner.begin_tile(cell, matrix)?;
+        self.calls.push(TraceCall::BeginTile {
+            cell: *cell,
+            matrix: *matrix,
+            accepted,
+        });
+        Ok(accepted)
+    }
+
+    fn end_tile(&mut self) -> PDFResult<usize> {
+        let id = self.inner.end_tile()?;
+        self.calls.push(TraceCall::EndTile { id });
+        Ok(id)
+    }
+
+    fn load_font_data(
+        &mut self,
+        name: &str,
+        data: Vec<u8>,
+        encoding: Option<&PDFObject>,
+    ) -> PDFResult<()> {
+        self.calls.push(TraceCall::LoadFontData {
+            name: name.to_string(),
+            data: data.clone(),
+            encoding: encoding.cloned(),
+        });
+        self.inner.load_font_data(name, data, encoding)
+    }
+
+    fn set_font_width_metrics(&mut self, name: &str, metrics: &FontWidthMetrics) -> PDFResult<()> {
+        self.calls.push(TraceCall::SetFontWidthMetrics {
+            name: name.to_string(),
+            metrics: metrics.clone(),
+        });
+        self.inner.set_font_width_metrics(name, metrics)
+    }
+}
+
+/// Makes recorded calls on a device.
+///
+/// Calls the recording device declined (a glyph run it didn't draw, a group
+/// or mask it didn't render offscreen) are skipped, since the calls of the
+/// rendering context's fallback are in the trace too. Tile paints are
+/// updated to the ids `device` returns for the replayed cells.
+///
+/// # Arguments
+/// * `calls` - Calls from [`TraceDevice::calls`] or [`read_trace`]
+/// * `device` - The device to draw on
+pub fn replay<D: Device + ?Sized>(calls: &[TraceCall], device: &mut D) -> PDFResult<()> {
+    let mut tile_ids: HashMap<usize, usize> = HashMap::new();
+    let remap = |paint: &Paint, tile_ids: &HashMap<usize, usize>| match paint {
+        Paint::Tile(tile) => Paint::Tile(Box::new(Tile {
+            id: tile_ids.get(&tile.id).copied().unwrap_or(tile.id),
+            ..**tile
+        })),
+        other => other.clone(),
+    };
+
+    for call in calls {
+        match call {
+            TraceCall::BeginPath => device.begin_path(),
+            TraceCall::MoveTo(x, y) => device.move_to(*x, *y),
+            TraceCall::LineTo(x, y) => device.line_to(*x, *y),
+            TraceCall::CurveTo(cp1x, cp1y, cp2x, cp2y, x, y) => {
+                device.curve_to(*cp1x, *cp1y, *cp2x, *cp2y, *x, *y)
+            }
+            TraceCall::Rect(x, y, width, height) => device.rect(*x, *y, *width, *height),
+            TraceCall::ClosePath => device.close_path(),
+            TraceCall::DrawPath {
+                mode,
+                paint,
+                stroke_props,
+            } => device.draw_path(*mode, &remap(paint, &tile_ids), stroke_props)?,
+            TraceCall::FillAndStrokePath {
+                rule,
+                fill,
+                stroke,
+                stroke_props,
+            } => device.fill_and_stroke_path(
+                *rule,
+                &remap(fill, &tile_ids),
+                &remap(stroke, &tile_ids),
+                stroke_props,
+            )?,
+            TraceCall::ClipPath(rule) => device.clip_path(*rule)?,
+            TraceCall::PushClip(rule) => device.push_clip(*rule)?,
+            TraceCall::PopClip => device.pop_clip(),
+            TraceCall::SaveState => device.save_state(),
+            TraceCall::RestoreState => device.restore_state(),
+            TraceCall::ConcatMatrix(matrix) => device.concat_matrix(matrix),
+            TraceCall::SetMatrix(matrix) => device.set_matrix(matrix),
+            TraceCall::DrawText {
+                text,
+                font_name,
+                font_size,
+                character_spacing,
+                word_spacing,
+                paint,
+                text_matrix,
+                horizontal_scaling,
+                text_rise,
+            } => {
+                device.draw_text(
+                    text,
+                    font_name,
+                    *font_size,
+                    *character_spacing,
+                    *word_spacing,
+                    &remap(paint, &tile_ids),
+                    text_matrix,
+                    *horizontal_scaling,
+                    *text_rise,
+                )?;
+            }
+            TraceCall::DrawGlyphRun { run, paint, drawn } => {
+                if *drawn {
+                    device.draw_glyph_run(run, &remap(paint, &tile_ids))?;
+                }
+            }
+            TraceCall::DrawImage { image, transform } => {
+                device.draw_image(image.clone(), transform)?
+            }
+            TraceCall::BeginSoftMask {
+                kind,
+                backdrop,
+                accepted,
+            } => {
+                if *accepted {
+                    device.begin_soft_mask(*kind, backdrop)?;
+                }
+            }
+            TraceCall::EndSoftMask => device.end_soft_mask()?,
+            TraceCall::ClearSoftMask => device.clear_soft_mask(),
+            TraceCall::SetBlendMode(mode) => device.set_blend_mode(*mode),
+            TraceCall::BeginGroup {
+                isolated,
+                knockout,
+                accepted,
+            } => {
+                if *accepted {
+                    device.begin_group(*isolated, *knockout)?;
+                }
+            }
+            TraceCall::EndGroup => device.end_group()?,
+            TraceCall::BeginTile {
+                cell,
+                matrix,
+                accepted,
+            } => {
+                if *accepted {
+                    device.begin_tile(cell, matrix)?;
+                }
+            }
+            TraceCall::EndTile { id } => {
+                let replayed = device.end_tile()?;
+                tile_ids.insert(*id, replayed);
+            }
+            TraceCall::LoadFontData {
+                name,
+                data,
+                encoding,
+            } => device.load_font_data(name, data.clone(), encoding.as_ref())?,
+            TraceCall::SetFontWidthMetrics { name, metrics } => {
+                device.set_font_width_metrics(name, metrics)?
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Writes recorded calls in the trace format, one PDF array per line.
+///
+/// Numbers are rounded to six decimal places and `-0` is written as `0`,
+/// so `0.30000000000000004` becomes `0.3`.
+///
+/// # Arguments
+/// * `calls` - Calls from [`TraceDevice::calls`]
+pub fn write_trace(calls: &[TraceCall]) -> PDFResult<Vec<u8>> {
+    let mut out = b"% pdf-x device trace\n".to_vec();
+    for call in calls {
+        PDFWriter::write_object(&mut out, &encode_call(call))?;
+        out.push(b'\n');
+    }
+    Ok(out)
+}
+
+/// Reads calls written by [`write_trace`].
+///
+/// # Arguments
+/// * `data` - The trace; lines may have been removed or `%` comments added
+pub fn read_trace(data: &[u8]) -> PDFResult<Vec<TraceCall>> {
+    let stream = Box::new(Stream::from_bytes(data.to_vec()));
+    let mut parser = Parser::new(Lexer::new(stream)?)?;
+    let mut calls = Vec::new();
+    loop {
+        match parser.get_object()? {
+            PDFObject::EOF => return Ok(calls),
+            PDFObject::Array(items) => calls.push(decode_call(&items)?),
+            other => return Err(invalid(format!("expected a call, found {:?}", other))),
+        }
+    }
+}
+
+fn invalid(message: String) -> PDFError {
+    PDFError::Generic(format!("Invalid device trace: {}", message))
+}
+
+fn name(name: &str) -> PDFObject {
+    PDFObject::Name(name.to_string())
+}
+
+fn number(value: f64) -> PDFObject {
+    let rounded = (value * NUMBER_PRECISION).round() / NUMBER_PRECISION;
+    // -0 and 0 are the same number
+    PDFObject::Number(if rounded == 0.0 { 0.0 } else { rounded })
+}
+
+fn array(items: impl IntoIterator<Item = PDFObject>) -> PDFObject {
+    PDFObject::Array(items.into_iter().map(Box::new).collect())
+}
+
+fn numbers(values: &[f64]) -> PDFObject {
+    array(values.iter().map(|value| number(*value)))
+}
+
+fn encode_color(color: &Color) -> PDFObject {
+    match *color {
+        Color::Gray(g) => array([name("Gray"), number(g)]),
+        Color::RGB(r, g, b) => array([name("RGB"), number(r), number(g), number(b)]),
+        Color::CMYK(c, m, y, k) => {
+            array([name("CMYK"), number(c), number(m), number(y), number(k)])
+        }
+    }
+}
+
+fn encode_optional_color(color: Option<&Color>) -> PDFObject {
+    color.map_or(PDFObject::Null, encode_color)
+}
+
+fn encode_paint(paint: &Paint) -> PDFObject {
+    match paint {
+        Paint::Solid(color) => array([name("Solid"), encode_color(color)]),
+        Paint::Gradient(gradient) => {
+            let (kind, coords) = match &gradient.geometry {
+                ShadingGeometry::Axial { coords } => ("Axial", &coords[..]),
+                ShadingGeometry::Radial { coords } => ("Radial", &coords[..]),
+            };
+            array([
+                name("Gradient"),
+                name(kind),
+                numbers(coords),
+                array(gradient.extend.map(PDFObject::Boolean)),
+                gradient.bbox.map_or(PDFObject::Null, |bbox| numbers(&bbox)),
+                array(gradient.colors.iter().map(encode_color)),
+                encode_optional_color(gradient.background.as_ref()),
+                numbers(&gradient.matrix),
+            ])
+        }
+        Paint::Tile(tile) => array([
+            name("Tile"),
+            number(tile.id as f64),
+            numbers(&tile.cell),
+            numbers(&tile.matrix),
+            encode_optional_color(tile.color.as_ref()),
+        ]),
+    }
+}
+
+fn encode_stroke_props(props: &StrokeProps) -> PDFObject {
+    array([
+        number(props.line_width),
+        number(props.line_cap as i32 as f64),
+        number(props.line_join as i32 as f64),
+        number(props.miter_limit),
+        numbers(&props.dash_array),
+        number(props.dash_offset),
+    ])
+}
+
+fn encode_call(call: &TraceCall) -> PDFObject {
+    let call_name = |n: &str| name(n);
+    let text = |s: &str| PDFObject::String(s.as_bytes().to_vec());
+    match call {
+        TraceCall::BeginPath => array([call_name("begin_path")]),
+        TraceCall::MoveTo(x, y) => array([call_name("move_to"), number(*x), number(*y)]),
+        TraceCall::LineTo(x, y) => array([call_name("line_to"), number(*x), number(*y)]),
+        TraceCall::CurveTo(cp1x, cp1y, cp2x, cp2y, x, y) => array(
+            std::iter::once(call_name("curve_to"))
+                .chain([cp1x, cp1y, cp2x, cp2y, x, y].map(|v| number(*v))),
+        ),
+        TraceCall::Rect(x, y, width, height) => array(
+            std::iter::once(call_name("rect")).chain([x, y, width, height].map(|v| number(*v))),
+        ),
+        TraceCall::ClosePath => array([call_name("close_path")]),
+        TraceCall::DrawPath {
+            mode,
+            paint,
+            stroke_props,
+        } => {
+            let (mode, rule) = match mode {
+                PathDrawMode::Fill(rule) => ("Fill", name(&format!("{:?}", rule))),
+                PathDrawMode::Stroke => ("Stroke", PDFObject::Null),
+                PathDrawMode::FillStroke(rule) => ("FillStroke", name(&format!("{:?}", rule))),
+            };
+            array([
+                call_name("draw_path"),
+                name(mode),
+                rule,
+                encode_paint(paint),
+                encode_stroke_props(stroke_props),
+            ])
+        }
+        TraceCall::FillAndStrokePath {
+            rule,
+            fill,
+            stroke,
+            stroke_props,
+        } => array([
+            call_name("fill_and_stroke_path"),
+            name(&format!("{:?}", rule)),
+            encode_paint(fill),
+            encode_paint(stroke),
+            encode_stroke_props(stroke_props),
+        ]),
+        TraceCall::ClipPath(rule) => array([call_name("clip_path"), name(&format!("{:?}", rule))]),
+        TraceCall::PushClip(rule) => array([call_name("push_clip"), name(&format!("{:?}", rule))]),
+        TraceCall::PopClip => array([call_name("pop_clip")]),
+        TraceCall::SaveState => array([call_name("save_state")]),
+        TraceCall::RestoreState => array([call_name("restore_state")]),
+        TraceCall::ConcatMatrix(matrix) => array([call_name("concat_matrix"), numbers(matrix)]),
+        TraceCall::SetMatrix(matrix) => array([call_name("set_matrix"), numbers(matrix)]),
+        TraceCall::DrawText {
+            text: bytes,
+            font_name,
+            font_size,
+            character_spacing,
+            word_spacing,
+            paint,
+            text_matrix,
+            horizontal_scaling,
+            text_rise,
+        } => array([
+            call_name("draw_text"),
+            PDFObject::HexString(bytes.clone()),
+            text(font_name),
+            number(*font_size),
+            number(*character_spacing),
+            number(*word_spacing),
+            encode_paint(paint),
+            numbers(text_matrix),
+            number(*horizontal_scaling),
+            number(*text_rise),
+        ]),
+        TraceCall::DrawGlyphRun { run, paint, drawn } => array([
+            call_name("draw_glyph_run"),
+            text(&run.font_name),
+            number(run.font_size),
+            array(run.glyphs.iter().flat_map(|glyph| {
+                [
+                    number(glyph.glyph_id as f64),
+                    number(glyph.x),
+                    number(glyph.y),
+                ]
+            })),
+            numbers(&run.text_matrix),
+            number(run.horizontal_scaling),
+            number(run.text_rise),
+            encode_paint(paint),
+            PDFObject::Boolean(*drawn),
+        ]),
+        TraceCall::DrawImage { image, transform } => array([
+            call_name("draw_image"),
+            number(image.width as f64),
+            number(image.height as f64),
+            PDFObject::HexString(image.data.clone()),
+            PDFObject::Boolean(image.has_alpha),
+            number(image.bits_per_component as f64),
+            numbers(transform),
+        ]),
+        TraceCall::BeginSoftMask {
+            kind,
+            backdrop,
+            accepted,
+        } => array([
+            call_name("begin_soft_mask"),
+            name(&format!("{:?}", kind)),
+            encode_color(backdrop),
+            PDFObject::Boolean(*accepted),
+        ]),
+        TraceCall::EndSoftMask => array([call_name("end_soft_mask")]),
+        TraceCall::ClearSoftMask => array([call_name("clear_soft_mask")]),
+        TraceCall::SetBlendMode(mode) => {
+            array([call_name("set_blend_mode"), name(&format!("{:?}", mode))])
+        }
+        TraceCall::BeginGroup {
+            isolated,
+            knockout,
+            accepted,
+        } => array([
+            call_name("begin_group"),
+            PDFObject::Boolean(*isolated),
+            PDFObject::Boolean(*knockout),
+            PDFObject::Boolean(*accepted),
+        ]),
+        TraceCall::EndGroup => array([call_name("end_group")]),
+        TraceCall::BeginTile {
+            cell,
+            matrix,
+            accepted,
+        } => array([
+            call_name("begin_tile"),
+            numbers(cell),
+            numbers(matrix),
+            PDFObject::Boolean(*accepted),
+        ]),
+        TraceCall::EndTile { id } => array([call_name("end_tile"), number(*id as f64)]),
+        TraceCall::LoadFontData {
+            name: font_name,
+            data,
+            encoding,
+        } => array([
+            call_name("load_font_data"),
+            text(font_name),
+            PDFObject::HexString(data.clone()),
+            encoding.clone().unwrap_or(PDFObject::Null),
+        ]),
+        TraceCall::SetFontWidthMetrics {
+            name: font_name,
+            metrics,
+        } => {
+            let mut widths: Vec<(&u8, &u16)> = metrics.code_widths.iter().collect();
+            widths.sort();
+            array([
+                call_name("set_font_width_metrics"),
+                text(font_name),
+                array(
+                    widths
+                        .into_iter()
+                        .flat_map(|(code, width)| [number(*code as f64), number(*width as f64)]),
+                ),
+                metrics
+                    .default_width
+                    .map_or(PDFObject::Null, |width| number(width as f64)),
+            ])
+        }
+    }
+}
+
+/// Reads the operands of a call in order.
+struct Operands<'a> {
+    call: &'a str,
+    items: std::slice::Iter<'a, Box<PDFObject>>,
+}
+
+impl<'a> Operands<'a> {
+    fn next(&mut self) -> PDFResult<&'a PDFObject> {
+        self.items
+            .next()
+            .map(|item| &**item)
+            .ok_or_else(|| invalid(format!("missing operand of {}", self.call)))
+    }
+
+    fn error(&self, expected: &str, found: &PDFObject) -> PDFError {
+        invalid(format!(
+            "{} expects {}, found {:?}",
+            self.call, expected, found
+        ))
+    }
+
+    fn number(&mut self) -> PDFResult<f64> {
+        match self.next()? {
+            PDFObject::Number(value) => Ok(*value),
+            other => Err(self.error("a number", other)),
+        }
+    }
+
+    fn boolean(&mut self) -> PDFResult<bool> {
+        match self.next()? {
+            PDFObject::Boolean(value) => Ok(*value),
+            other => Err(self.error("a boolean", other)),
+        }
+    }
+
+    fn name(&mut self) -> PDFResult<&'a str> {
+        match self.next()? {
+            PDFObject::Name(name) => Ok(name),
+            other => Err(self.error("a name", other)),
+        }
+    }
+
+    fn text(&mut self) -> PDFResult<String> {
+        Ok(String::from_utf8_lossy(self.bytes()?).into_owned())
+    }
+
+    fn bytes(&mut self) -> PDFResult<&'a [u8]> {
+        match self.next()? {
+            PDFObject::String(bytes) | PDFObject::HexString(bytes) => Ok(bytes),
+            other => Err(self.error("a string", other)),
+        }
+    }
+
+    fn array(&mut self) -> PDFResult<Operands<'a>> {
+        match self.next()? {
+            PDFObject::Array(items) => Ok(Operands {
+                call: self.call,
+                items: items.iter(),
+            }),
+            other => Err(self.error("an array", other)),
+        }
+    }
+
+    /// Consumes the next operand if it is `null`.
+    fn null(&mut self) -> bool {
+        let is_null = matches!(
+            self.items.as_slice().first().map(|item| &**item),
+            Some(PDFObject::Null)
+        );
+        if is_null {
+            self.items.next();
+        }
+        is_null
+    }
+
+    fn numbers(&mut self) -> PDFResult<Vec<f64>> {
+        let mut items = self.array()?;
+        let mut values = Vec::new();
+        while items.items.len() > 0 {
+            values.push(items.number()?);
+        }
+        Ok(values)
+    }
+
+    fn fixed<const N: usize>(&mut self) -> PDFResult<[f64; N]> {
+        let values = self.numbers()?;
+        values.try_into().map_err(|values: Vec<f64>| {
+            invalid(format!(
+                "{} expects {} numbers, found {}",
+                self.call,
+                N,
+                values.len()
+            ))
+        })
+    }
+
+    fn rule(&mut self) -> PDFResult<FillRule> {
+        match self.name()? {
+            "NonZero" => Ok(FillRule::NonZero),
+            "EvenOdd" => Ok(FillRule::EvenOdd),
+            other => Err(invalid(format!("unknown fill rule {}", other))),
+        }
+    }
+
+    fn color(&mut self) -> PDFResult<Color> {
+        let mut items = self.array()?;
+        let color = match items.name()? {
+            "Gray" => Color::Gray(items.number()?),
+            "RGB" => Color::RGB(items.number()?, items.number()?, items.number()?),
+            "CMYK" => Color::CMYK(
+                items.number()?,
+                items.number()?,
+                items.number()?,
+                items.number()?,
+            ),
+            other => return Err(invalid(format!("unknown color space {}", other))),
+        };
+        Ok(color)
+    }
+
+    fn optional_color(&mut self) -> PDFResult<Option<Color>> {
+        if self.null() {
+            return Ok(None);
+        }
+        self.color().map(Some)
+    }
+
+    fn paint(&mut self) -> PDFResult<Paint> {
+        let mut items = self.array()?;
+        let paint = match items.name()? {
+            "Solid" => Paint::Solid(items.color()?),
+            "Gradient" => {
+                let geometry = match items.name()? {
+                    "Axial" => ShadingGeometry::Axial {
+                        coords: items.fixed()?,
+                    },
+                    "Radial" => ShadingGeometry::Radial {
+                        coords: items.fixed()?,
+                    },
+                    other => return Err(invalid(format!("unknown gradient {}", other))),
+                };
+                let mut extend = items.array()?;
+                let extend = [extend.boolean()?, extend.boolean()?];
+                let bbox = if items.null() {
+                    None
+                } else {
+                    Some(items.fixed()?)
+                };
+                let mut colors = Vec::new();
+                let mut color_items = items.array()?;
+                while color_items.items.len() > 0 {
+                    colors.push(color_items.color()?);
+                }
+                let background = items.optional_color()?;
+                let matrix = items.fixed()?;
+                Paint::Gradient(Box::new(Gradient::new(
+                    geometry, extend, bbox, colors, background, matrix,
+                )))
+            }
+            "Tile" => Paint::Tile(Box::new(Tile {
+                id: items.number()? as usize,
+                cell: items.fixed()?,
+                matrix: items.fixed()?,
+                color: items.optional_color()?,
+            })),
+            other => return Err(invalid(format!("unknown paint {}", other))),
+        };
+        Ok(paint)
+    }
+
+    fn stroke_props(&mut self) -> PDFResult<StrokeProps> {
+        let mut items = self.array()?;
+        Ok(StrokeProps {
+            line_width: items.number()?,
+            line_cap: match items.number()? as i32 {
+                1 => LineCap::Round,
+                2 => LineCap::ProjectingSquare,
+                _ => LineCap::Butt,
+            },
+            line_join: match items.number()? as i32 {
+                1 => LineJoin::Round,
+                2 => LineJoin::Bevel,
+                _ => LineJoin::Miter,
+            },
+            miter_limit: items.number()?,
+            dash_array: items.numbers()?,
+            dash_offset: items.number()?,
+        })
+    }
+}
+
+fn decode_call(items: &[Box<PDFObject>]) -> PDFResult<TraceCall> {
+    let call = match items.first().map(|item| &**item) {
+        Some(PDFObject::Name(call)) => call.as_str(),
+        _ => return Err(invalid("a call must start with its name".to_string())),
+    };
+    let mut ops = Operands {
+        call,
+        items: items[1..].iter(),
+    };
+
+    let call = match call {
+        "begin_path" => TraceCall::BeginPath,
+        "move_to" => TraceCall::MoveTo(ops.number()?, ops.number()?),
+        "line_to" => TraceCall::LineTo(ops.number()?, ops.number()?),
+        "curve_to" => TraceCall::CurveTo(
+            ops.number()?,
+            ops.number()?,
+            ops.number()?,
+            ops.number()?,
+            ops.number()?,
+            ops.number()?,
+        ),
+        "rect" => TraceCall::Rect(ops.number()?, ops.number()?, ops.number()?, ops.number()?),
+        "close_path" => TraceCall::ClosePath,
+        "draw_path" => {
+            let mode = match ops.name()? {
+                "Fill" => PathDrawMode::Fill(ops.rule()?),
+                "FillStroke" => PathDrawMode::FillStroke(ops.rule()?),
+                "Stroke" if ops.null() => PathDrawMode::Stroke,
+                other => return Err(invalid(format!("unknown draw mode {}", other))),
+            };
+            TraceCall::DrawPath {
+                mode,
+                paint: ops.paint()?,
+                stroke_props: ops.stroke_props()?,
+            }
+        }
+        "fill_and_stroke_path" => TraceCall::FillAndStrokePath {
+            rule: ops.rule()?,
+            fill: ops.paint()?,
+            stroke: ops.paint()?,
+            stroke_props: ops.stroke_props()?,
+        },
+        "clip_path" => TraceCall::ClipPath(ops.rule()?),
+        "push_clip" => TraceCall::PushClip(ops.rule()?),
+        "pop_clip" => TraceCall::PopClip,
+        "save_state" => TraceCall::SaveState,
+        "restore_state" => TraceCall::RestoreState,
+        "concat_matrix" => TraceCall::ConcatMatrix(ops.fixed()?),
+        "set_matrix" => TraceCall::SetMatrix(ops.fixed()?),
+        "draw_text" => TraceCall::DrawText {
+            text: ops.bytes()?.to_vec(),
+            font_name: ops.text()?,
+            font_size: ops.number()?,
+            character_spacing: ops.number()?,
+            word_spacing: ops.number()?,
+            paint: ops.paint()?,
+            text_matrix: ops.fixed()?,
+            horizontal_scaling: ops.number()?,
+            text_rise: ops.number()?,
+        },
+        "draw_glyph_run" => {
+            let font_name = ops.text()?;
+            let font_size = ops.number()?;
+            let mut glyphs = Vec::new();
+            let mut values = ops.array()?;
+            while values.items.len() > 0 {
+                glyphs.push(PositionedGlyph {
+                    glyph_id: values.number()? as u16,
+                    x: values.number()?,
+                    y: values.number()?,
+                });
+            }
+            TraceCall::DrawGlyphRun {
+                run: GlyphRun {
+                    font_name,
+                    font_size,
+                    glyphs,
+                    text_matrix: ops.fixed()?,
+                    horizontal_scaling: ops.number()?,
+                    text_rise: ops.number()?,
+                },
+                paint: ops.paint()?,
+                drawn: ops.boolean()?,
+            }
+        }
+        "draw_image" => TraceCall::DrawImage {
+            image: ImageData {
+                width: ops.number()? as u32,
+                height: ops.number()? as u32,
+                data: ops.bytes()?.to_vec(),
+                has_alpha: ops.boolean()?,
+                bits_per_component: ops.number()? as u8,
+            },
+            transform: ops.fixed()?,
+        },
+        "begin_soft_mask" => TraceCall::BeginSoftMask {
+            kind: match ops.name()? {
+                "Alpha" => SoftMaskKind::Alpha,
+                "Luminosity" => SoftMaskKind::Luminosity,
+                other => return Err(invalid(format!("unknown soft mask kind {}", other))),
+            },
+            backdrop: ops.color()?,
+            accepted: ops.boolean()?,
+        },
+        "end_soft_mask" => TraceCall::EndSoftMask,
+        "clear_soft_mask" => TraceCall::ClearSoftMask,
+        "set_blend_mode" => {
+            let mode = ops.name()?;
+            TraceCall::SetBlendMode(
+                BlendMode::from_name(mode)
+                    .ok_or_else(|| invalid(format!("unknown blend mode {}", mode)))?,
+            )
+        }
+        "begin_group" => TraceCall::BeginGroup {
+            isolated: ops.boolean()?,
+            knockout: ops.boolean()?,
+            accepted: ops.boolean()?,
+        },
+        "end_group" => TraceCall::EndGroup,
+        "begin_tile" => TraceCall::BeginTile {
+            cell: ops.fixed()?,
+            matrix: ops.fixed()?,
+            accepted: ops.boolean()?,
+        },
+        "end_tile" => TraceCall::EndTile {
+            id: ops.number()? as usize,
+        },
+        "load_font_data" => TraceCall::LoadFontData {
+            name: ops.text()?,
+            data: ops.bytes()?.to_vec(),
+            encoding: if ops.null() {
+                None
+            } else {
+                Some(ops.next()?.clone())
+            },
+        },
+        "set_font_width_metrics" => {
+            let name = ops.text()?;
+            let mut code_widths = HashMap::new();
+            let mut values = ops.array()?;
+            while values.items.len() > 0 {
+                code_widths.insert(values.number()? as u8, values.number()? as u16);
+            }
+            let default_width = if ops.null() {
+                None
+            } else {
+                Some(ops.number()? as u16)
+            };
+            TraceCall::SetFontWidthMetrics {
+                name,
+                metrics: FontWidthMetrics {
+                    code_widths,
+                    default_width,
+                },
+            }
+        }
+        other => return Err(invalid(format!("unknown call {}", other))),
+    };
+    Ok(call)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::ContentStreamEvaluator;
+    use crate::rendering::{RenderingContext, TestDevice};
+
+    fn run<D: Device>(device: &mut D, content: &[u8]) {
+        let stream = Box::new(Stream::from_bytes(content.to_vec()));
+        let mut evaluator =
+            ContentStreamEvaluator::new(Parser::new(Lexer::new(stream).unwrap()).unwrap());
+        let mut ctx = RenderingContext::new(device);
+        while let Some(op) = evaluator.read_operation().unwrap() {
+            ctx.process_operation(&op).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_trace_round_trip() {
+        let source = b"q 2 0 0 2 10 10 cm 1 0 0 RG 0.5 w [3 1] 0 d 1 J \
+                       0 0 m 10 10 l 5 0 5 5 0 5 c h S Q \
+                       0 0 100 100 re W n 0.2 0.4 0.6 0 k 0 0 5 5 re B* \
+                       /Multiply gs BT /F1 12 Tf 72 700 Td (Hi) Tj ET";
+        let mut direct = TestDevice::new(612.0, 792.0);
+        run(&mut direct, source);
+
+        let mut tracing = TraceDevice::new(TestDevice::new(612.0, 792.0));
+        run(&mut tracing, source);
+        let (traced, calls) = tracing.into_parts();
+        assert_eq!(traced.operations(), direct.operations());
+        assert!(
+            calls
+                .iter()
+                .any(|call| matches!(call, TraceCall::CurveTo(..)))
+        );
+
+        let written = write_trace(&calls).unwrap();
+        let read = read_trace(&written).unwrap();
+        assert_eq!(read.len(), calls.len());
+        assert_eq!(write_trace(&read).unwrap(), written);
+
+        let mut replayed = TestDevice::new(612.0, 792.0);
+        replay(&read, &mut replayed).unwrap();
+        assert_eq!(replayed.operations(), direct.operations());
+    }
+
+    #[test]
+    fn test_trace_paints() {
+        let gradient = Paint::Gradient(Box::new(Gradient::new(
+            ShadingGeometry::Radial {
+                coords: [0.0, 0.0, 0.0, 10.0, 10.0, 5.0],
+            },
+            [true, false],
+            Some([0.0, 0.0, 20.0, 20.0]),
+            vec![Color::RGB(1.0, 0.0, 0.0), Color::Gray(0.25)],
+            None,
+            [1.0, 0.0, 0.0, 1.0, 0.0, 0.0],
+        )));
+        let tile = Paint::Tile(Box::new(Tile {
+            id: 7,
+            cell: [0.0, 0.0, 4.0, 4.0],
+            matrix: [1.0, 0.0, 0.0, 1.0, 0.0, 0.0],
+            color: Some(Color::CMYK(0.0, 0.0, 0.0, 1.0)),
+        }));
+        let calls = vec![
+            TraceCall::BeginTile {
+                cell: [0.0, 0.0, 4.0, 4.0],
+                matrix: [1.0, 0.0, 0.0, 1.0, 0.0, 0.0],
+                accepted: true,
+            },
+            TraceCall::EndTile { id: 7 },
+            TraceCall::DrawPath {
+                mode: PathDrawMode::Stroke,
+                paint: gradient,
+                stroke_props: StrokeProps::default(),
+            },
+            TraceCall::DrawPath {
+                mode: PathDrawMode::Fill(FillRule::EvenOdd),
+                paint: tile,
+                stroke_props: StrokeProps::default(),
+            },
+        ];
+
+        let written = write_trace(&calls).unwrap();
+        let text = String::from_utf8(written.clone()).unwrap();
+        assert!(text.contains("[/end_tile 7]"), "{}", text);
+
+        let rounded = write_trace(&[TraceCall::MoveTo(0.1 + 0.2, -1e-9)]).unwrap();
+        assert!(rounded.ends_with(b"\n[/move_to 0.3 0]\n"));
+        let read = read_trace(&written).unwrap();
+        assert_eq!(write_trace(&read).unwrap(), written);
+        match &read[2] {
+            TraceCall::DrawPath {
+                paint: Paint::Gradient(gradient),
+                ..
+            } => {
+                assert_eq!(gradient.extend, [true, false]);
+                assert_eq!(gradient.bbox, Some([0.0, 0.0, 20.0, 20.0]));
+                assert_eq!(gradient.colors.len(), 2);
+            }
+            other => panic!("expected a gradient fill, found {:?}", other),
+        }
+
+        // Tile paints follow the ids the replaying device hands out
+        let mut device = TraceDevice::new(TestDevice::new(10.0, 10.0));
+        replay(&read, &mut device).unwrap();
+        let (_, replayed) = device.into_parts();
+        let TraceCall::EndTile { id } = replayed[1] else {
+            panic!("expected end_tile, found {:?}", replayed[1]);
+        };
+        match &replayed[3] {
+            TraceCall::DrawPath {
+                paint: Paint::Tile(tile),
+                ..
+            } => assert_eq!(tile.id, id),
+            other => panic!("expected a tile fill, found {:?}", other),
+        }
+
+        assert!(read_trace(b"[/warp_drive 1]").is_err());
+        assert!(read_trace(b"[/move_to 1]").is_err());
+    }
+}
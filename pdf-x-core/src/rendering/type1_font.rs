@@ -1,21 +1,24 @@
-//! Type1 and CFF font support using hayro-font
+//! Type1 and CFF font support
 //!
-//! This module provides Type1 and CFF font parsing and rendering support
-//! by integrating hayro-font with tiny-skia.
+//! This module provides Type1 and CFF font rendering support with tiny-skia.
+//! Type1 programs are parsed by `rendering::font::type1` and CFF programs
+//! (Type1C and CIDFontType0C) by `rendering::font::cff`.
 
 use std::collections::HashMap;
 use std::sync::Arc;
 use tiny_skia::PathBuilder;
 
-#[cfg(feature = "hayro-font")]
-use hayro_font::{OutlineBuilder as HayroOutlineBuilder, type1::Table as Type1Table};
+use super::font::OutlineSink;
+use super::font::cff::CffFont;
+use super::font::type1::Type1Program;
+use crate::log_target;
 
 /// Font variant - either Type1 or CFF
 #[cfg(feature = "hayro-font")]
 #[derive(Clone)]
 enum FontVariant {
-    Type1(Type1Table),
-    CFF(Arc<hayro_font::cff::Table<'static>>),
+    Type1(Arc<Type1Program>),
+    CFF(Arc<CffFont>),
 }
 
 /// A Type1 or CFF font that can be used for rendering
@@ -53,16 +56,11 @@ impl Type1Font {
 
             if is_cff {
                 // CFF font - use the CFF parser
-                // SAFETY: We extend the lifetime to 'static since we keep the data alive via Arc
-                let static_data: &'static [u8] =
-                    unsafe { std::slice::from_raw_parts(data.as_ptr(), data.len()) };
-
-                let cff_table =
-                    hayro_font::cff::Table::parse(static_data).ok_or("Failed to parse CFF font")?;
+                let cff_font = CffFont::parse(&data)?;
 
                 return Ok(Type1Font {
                     _data: Arc::new(data),
-                    variant: FontVariant::CFF(Arc::new(cff_table)),
+                    variant: FontVariant::CFF(Arc::new(cff_font)),
                     name_to_code: HashMap::new(),
                     custom_encoding: HashMap::new(),
                     pdf_widths: HashMap::new(),
@@ -75,19 +73,19 @@ impl Type1Font {
                 return Err("Not a Type1 or CFF font".to_string());
             }
 
-            let table = Type1Table::parse(&data).ok_or("Failed to parse Type1 font")?;
+            let program = Type1Program::parse(&data)?;
 
             // Build name to code mapping for reverse lookup
             let mut name_to_code: HashMap<String, u8> = HashMap::new();
             for code in 0..=255u8 {
-                if let Some(name) = table.code_to_string(code) {
+                if let Some(name) = program.code_to_name(code) {
                     name_to_code.insert(name.to_string(), code);
                 }
             }
 
             Ok(Type1Font {
                 _data: Arc::new(data),
-                variant: FontVariant::Type1(table),
+                variant: FontVariant::Type1(Arc::new(program)),
                 name_to_code,
                 custom_encoding: HashMap::new(),
                 pdf_widths: HashMap::new(),
@@ -118,24 +116,18 @@ impl Type1Font {
             return true;
         }
 
-        // Embedded Type1 font programs (stripped-down, without header)
-        let data_str = std::str::from_utf8(data);
-
-        if let Ok(text) = data_str {
-            let type1_keywords = ["/FontName", "/FontMatrix", "/CharStrings", "/Encoding"];
-            let mut found_count = 0;
-            for keyword in &type1_keywords {
-                if text.contains(keyword) {
-                    found_count += 1;
-                }
-            }
-
-            if found_count >= 2 || text.contains("eexec") {
-                return true;
-            }
-        }
-
-        false
+        // Embedded Type1 font programs (stripped-down, without header). The
+        // cleartext part is followed by binary eexec data, so search bytes
+        // rather than requiring the whole program to be UTF-8.
+        let contains = |keyword: &[u8]| data.windows(keyword.len()).any(|window| window == keyword);
+        let type1_keywords: [&[u8]; 4] =
+            [b"/FontName", b"/FontMatrix", b"/CharStrings", b"/Encoding"];
+        let found_count = type1_keywords
+            .iter()
+            .filter(|keyword| contains(keyword))
+            .count();
+
+        found_count >= 2 || contains(b"eexec")
     }
 
     /// Check if this is a CFF font
@@ -150,12 +142,34 @@ impl Type1Font {
         }
     }
 
+    /// Get the font's glyph space to text space matrix (FontMatrix)
+    ///
+    /// Fonts without one use the usual 1000 units per em.
+    pub fn glyph_matrix(&self) -> [f64; 6] {
+        #[cfg(feature = "hayro-font")]
+        {
+            match &self.variant {
+                FontVariant::Type1(program) => program.font_matrix,
+                FontVariant::CFF(font) => font.font_matrix,
+            }
+        }
+        #[cfg(not(feature = "hayro-font"))]
+        {
+            [0.001, 0.0, 0.0, 0.001, 0.0, 0.0]
+        }
+    }
+
     /// Get the font matrix for transformation
     #[cfg(feature = "hayro-font")]
     pub fn font_matrix(&self) -> hayro_font::Matrix {
-        match &self.variant {
-            FontVariant::Type1(table) => table.matrix(),
-            FontVariant::CFF(table) => table.matrix(),
+        let [a, b, c, d, e, f] = self.glyph_matrix().map(|v| v as f32);
+        hayro_font::Matrix {
+            sx: a,
+            ky: b,
+            kx: c,
+            sy: d,
+            tx: e,
+            ty: f,
         }
     }
 
@@ -173,11 +187,13 @@ impl Type1Font {
             let mut converter = PathConverter(path_builder);
 
             match &self.variant {
-                FontVariant::Type1(table) => table.outline(glyph_name, &mut converter),
-                FontVariant::CFF(table) => {
+                FontVariant::Type1(program) => {
+                    program.outline(glyph_name, &mut converter).map(|_| ())
+                }
+                FontVariant::CFF(font) => {
                     // For CFF, we need to convert glyph name to glyph ID first
-                    let gid = table.glyph_index_by_name(glyph_name)?;
-                    table.outline(gid, &mut converter).ok().map(|_| ())
+                    let gid = font.glyph_index_by_name(glyph_name)?;
+                    font.outline(gid, &mut converter).map(|_| ())
                 }
             }
         }
@@ -203,8 +219,9 @@ impl Type1Font {
             let code = ch as u8;
 
             #[cfg(feature = "debug-logging")]
-            eprintln!(
-                "DEBUG: outline_glyph_char: char='{}' (code={}), is_cff={}, has_custom_encoding={}",
+            tracing::trace!(
+                target: log_target::FONT,
+                "outline_glyph_char: char='{}' (code={}), is_cff={}, has_custom_encoding={}",
                 ch,
                 code,
                 self.is_cff(),
@@ -212,39 +229,48 @@ impl Type1Font {
             );
 
             match &self.variant {
-                FontVariant::Type1(table) => {
-                    // Type1 uses glyph names
-                    let name = table.code_to_string(code)?;
+                FontVariant::Type1(program) => {
+                    // Type1 uses glyph names; the PDF encoding overrides the
+                    // font's built-in encoding
+                    let name = self.get_glyph_name_for_code(code)?;
                     #[cfg(feature = "debug-logging")]
-                    eprintln!("DEBUG: Type1 code_to_string({}) -> Some({})", code, name);
-                    table.outline(name, &mut converter)
+                    tracing::trace!(
+                        target: log_target::FONT,
+                        "Type1 glyph name for code {} -> {}", code, name
+                    );
+                    program.outline(&name, &mut converter).map(|_| ())
                 }
-                FontVariant::CFF(table) => {
+                FontVariant::CFF(font) => {
                     // For CFF, check custom encoding first
                     if let Some(glyph_name) = self.get_glyph_name_for_code(code) {
                         #[cfg(feature = "debug-logging")]
-                        eprintln!(
-                            "DEBUG: CFF using custom encoding: code={} -> name='{}'",
+                        tracing::trace!(
+                            target: log_target::FONT,
+                            "CFF using custom encoding: code={} -> name='{}'",
                             code, glyph_name
                         );
 
                         // Look up glyph by name using custom encoding
-                        let gid = table.glyph_index_by_name(&glyph_name)?;
+                        let gid = font.glyph_index_by_name(&glyph_name)?;
                         #[cfg(feature = "debug-logging")]
-                        eprintln!(
-                            "DEBUG: CFF glyph_index_by_name({}) -> Some({:?})",
+                        tracing::trace!(
+                            target: log_target::FONT,
+                            "CFF glyph_index_by_name({}) -> Some({:?})",
                             glyph_name, gid
                         );
-                        return table.outline(gid, &mut converter).ok().map(|_| ());
+                        return font.outline(gid, &mut converter).map(|_| ());
                     }
 
                     // Fall back to direct code lookup (standard encoding)
                     #[cfg(feature = "debug-logging")]
-                    eprintln!("DEBUG: CFF calling glyph_index({})", code);
-                    let gid = table.glyph_index(code)?;
+                    tracing::trace!(target: log_target::FONT, "CFF calling glyph_index({})", code);
+                    let gid = font.glyph_index(code)?;
                     #[cfg(feature = "debug-logging")]
-                    eprintln!("DEBUG: CFF glyph_index({}) -> Some({:?})", code, gid);
-                    table.outline(gid, &mut converter).ok().map(|_| ())
+                    tracing::trace!(
+                        target: log_target::FONT,
+                        "CFF glyph_index({}) -> Some({:?})", code, gid
+                    );
+                    font.outline(gid, &mut converter).map(|_| ())
                 }
             }
         }
@@ -255,6 +281,33 @@ impl Type1Font {
         }
     }
 
+    /// Outline a glyph by CID to a tiny-skia path (CFF fonts used as a
+    /// CIDFontType0 descendant)
+    ///
+    /// # Arguments
+    /// * `cid` - The CID, mapped to a glyph through the charset of CID-keyed fonts
+    /// * `path_builder` - The tiny-skia PathBuilder to receive the outline
+    ///
+    /// # Returns
+    /// Some(()) if successful, None if the glyph doesn't exist or this is a Type1 font
+    pub fn outline_glyph_cid(&self, cid: u16, path_builder: &mut PathBuilder) -> Option<()> {
+        #[cfg(feature = "hayro-font")]
+        {
+            let FontVariant::CFF(font) = &self.variant else {
+                return None;
+            };
+            let gid = font.glyph_index_for_cid(cid)?;
+            font.outline(gid, &mut PathConverter(path_builder))
+                .map(|_| ())
+        }
+
+        #[cfg(not(feature = "hayro-font"))]
+        {
+            let _ = (cid, path_builder);
+            None
+        }
+    }
+
     /// Convert a character code to a glyph name (Type1 only)
     pub fn char_to_glyph_name(&self, ch: char) -> Option<String> {
         let code = ch as u32;
@@ -265,9 +318,7 @@ impl Type1Font {
         #[cfg(feature = "hayro-font")]
         {
             match &self.variant {
-                FontVariant::Type1(table) => table
-                    .code_to_string(code as u8)
-                    .map(|s: &str| s.to_string()),
+                FontVariant::Type1(program) => program.code_to_name(code as u8).map(str::to_string),
                 FontVariant::CFF(_) => None, // CFF doesn't use names for code->glyph mapping
             }
         }
@@ -300,21 +351,24 @@ impl Type1Font {
             }
 
             match &self.variant {
-                FontVariant::Type1(_table) => {
-                    // Type1 widths usually come from the PDF font dictionary.
-                    default_width
+                FontVariant::Type1(program) => {
+                    // Type1 widths usually come from the PDF font dictionary;
+                    // otherwise use the advance width from the charstring.
+                    self.get_glyph_name_for_code(code)
+                        .and_then(|name| program.glyph_width(&name))
+                        .map(|width| width.round() as u16)
+                        .unwrap_or(default_width)
                 }
-                FontVariant::CFF(table) => {
-                    // For CFF, try to get the glyph width
-                    if let Some(glyph_name) = self.get_glyph_name_for_code(code) {
-                        if let Some(gid) = table.glyph_index_by_name(&glyph_name) {
-                            if let Some(width) = table.glyph_width(gid) {
-                                return width;
-                            }
-                        }
-                    }
+                FontVariant::CFF(font) => {
+                    // For CFF, try to get the glyph width from the charstring
+                    let gid = match self.get_glyph_name_for_code(code) {
+                        Some(glyph_name) => font.glyph_index_by_name(&glyph_name),
+                        None => font.glyph_index(code),
+                    };
                     // Fall back to the PDF/default width
-                    default_width
+                    gid.and_then(|gid| font.glyph_width(gid))
+                        .map(|width| width.round() as u16)
+                        .unwrap_or(default_width)
                 }
             }
         }
@@ -353,10 +407,10 @@ impl Type1Font {
         // Fall back to built-in encoding (Type1 only)
         #[cfg(feature = "hayro-font")]
         {
-            if let FontVariant::Type1(table) = &self.variant {
-                if let Some(name) = table.code_to_string(code) {
-                    return Some(name.to_string());
-                }
+            if let FontVariant::Type1(program) = &self.variant
+                && let Some(name) = program.code_to_name(code)
+            {
+                return Some(name.to_string());
             }
         }
 
@@ -364,10 +418,10 @@ impl Type1Font {
     }
 }
 
-/// Adapter to convert hayro-font OutlineBuilder calls to tiny-skia PathBuilder
+/// Adapter to convert font program outlines to tiny-skia PathBuilder
 struct PathConverter<'a>(&'a mut PathBuilder);
 
-impl<'a> HayroOutlineBuilder for PathConverter<'a> {
+impl<'a> OutlineSink for PathConverter<'a> {
     fn move_to(&mut self, x: f32, y: f32) {
         self.0.move_to(x, y);
     }
@@ -376,10 +430,6 @@ impl<'a> HayroOutlineBuilder for PathConverter<'a> {
         self.0.line_to(x, y);
     }
 
-    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
-        self.0.quad_to(x1, y1, x, y);
-    }
-
     fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
         self.0.cubic_to(x1, y1, x2, y2, x, y);
     }
@@ -0,0 +1,392 @@
+//! Vector paths of a page as structured data.
+//!
+//! `Page::extract_paths()` runs the page through the rendering context with
+//! a [`PathRecorder`] device, which keeps every filled or stroked path with
+//! its paint and stroke properties instead of drawing it. Paths from form
+//! XObjects and shading fills (`sh`) are included; clipping paths and text
+//! are not. Created with [`PathRecorder::with_images`], the recorder also
+//! keeps the images drawn and where, which the HTML converter uses.
+
+use super::device::{Device, ImageData, Paint, PathDrawMode};
+use super::graphics_state::{FillRule, StrokeProps, multiply_matrix};
+use super::path::PathElement;
+use crate::core::error::PDFResult;
+
+/// A filled and/or stroked path, in default user space.
+#[derive(Debug, Clone)]
+pub struct VectorPath {
+    /// The path with the CTM applied
+    pub elements: Vec<PathElement>,
+    /// How the path is filled, if it is
+    pub fill: Option<PathFill>,
+    /// How the path is stroked, if it is
+    pub stroke: Option<PathStroke>,
+    /// Bounding box `[x0, y0, x1, y1]` of the path's points, including
+    /// curve control points
+    pub bbox: [f64; 4],
+}
+
+/// Fill of a [`VectorPath`].
+#[derive(Debug, Clone)]
+pub struct PathFill {
+    /// Nonzero winding or even-odd
+    pub rule: FillRule,
+    /// Color, gradient or tiling pattern
+    pub paint: Paint,
+}
+
+/// Stroke of a [`VectorPath`].
+#[derive(Debug, Clone)]
+pub struct PathStroke {
+    /// Color, gradient or tiling pattern
+    pub paint: Paint,
+    /// Line width, caps, joins and dashes; lengths are scaled to default
+    /// user space
+    pub props: StrokeProps,
+}
+
+impl VectorPath {
+    /// Returns the corners `[x0, y0, x1, y1]` if the path is a single
+    /// axis-aligned rectangle, as drawn by `re` (table cells and rules are
+    /// usually drawn this way).
+    pub fn as_rect(&self) -> Option<[f64; 4]> {
+        let points: Vec<(f64, f64)> = match self.elements.as_slice() {
+            [PathElement::MoveTo(x, y), rest @ ..] => std::iter::once((*x, *y))
+                .chain(rest.iter().filter_map(|element| match element {
+                    PathElement::LineTo(x, y) => Some((*x, *y)),
+                    _ => None,
+                }))
+                .collect(),
+            _ => return None,
+        };
+        let closed = matches!(self.elements.last(), Some(PathElement::ClosePath));
+        let lines = self.elements.len() - 1 - closed as usize;
+        if points.len() != lines + 1 || !(4..=5).contains(&points.len()) {
+            return None;
+        }
+
+        // Each side is horizontal or vertical
+        let corners = &points[..4];
+        let axis_aligned = (0..4).all(|i| {
+            let (a, b) = (corners[i], corners[(i + 1) % 4]);
+            a.0 == b.0 || a.1 == b.1
+        });
+        (axis_aligned && (points.len() == 4 || points[4] == points[0])).then_some(self.bbox)
+    }
+}
+
+/// An image drawn on a page, recorded by [`PathRecorder::with_images`].
+pub(crate) struct RecordedImage {
+    /// The image as the rendering context decoded it
+    pub(crate) image: ImageData,
+    /// Maps the unit square the image fills to default user space
+    pub(crate) matrix: [f64; 6],
+}
+
+/// A device that records the paths drawn, for `Page::extract_paths()`, and
+/// optionally the images.
+pub(crate) struct PathRecorder {
+    /// CTM stack, one entry per saved state
+    ctm: Vec<[f64; 6]>,
+    /// Path under construction, in the user space of its operators
+    current: Vec<PathElement>,
+    pub(crate) paths: Vec<VectorPath>,
+    /// Whether images are kept in `images`
+    record_images: bool,
+    pub(crate) images: Vec<RecordedImage>,
+}
+
+impl Default for PathRecorder {
+    fn default() -> Self {
+        PathRecorder {
+            ctm: vec![[1.0, 0.0, 0.0, 1.0, 0.0, 0.0]],
+            current: Vec::new(),
+            paths: Vec::new(),
+            record_images: false,
+            images: Vec::new(),
+        }
+    }
+}
+
+impl PathRecorder {
+    /// Creates a recorder that also keeps the images drawn.
+    pub(crate) fn with_images() -> Self {
+        PathRecorder {
+            record_images: true,
+            ..PathRecorder::default()
+        }
+    }
+
+    fn ctm(&self) -> [f64; 6] {
+        self.ctm
+            .last()
+            .copied()
+            .unwrap_or([1.0, 0.0, 0.0, 1.0, 0.0, 0.0])
+    }
+
+    /// Records the current path, transformed to default user space.
+    fn record(&mut self, fill: Option<PathFill>, stroke: Option<PathStroke>) {
+        let [a, b, c, d, e, f] = self.ctm();
+        let map = |x: f64, y: f64| (a * x + c * y + e, b * x + d * y + f);
+        let elements: Vec<PathElement> = self
+            .current
+            .iter()
+            .map(|element| match *element {
+                PathElement::MoveTo(x, y) => {
+                    let (x, y) = map(x, y);
+                    PathElement::MoveTo(x, y)
+                }
+                PathElement::LineTo(x, y) => {
+                    let (x, y) = map(x, y);
+                    PathElement::LineTo(x, y)
+                }
+                PathElement::CurveTo(x1, y1, x2, y2, x3, y3) => {
+                    let (x1, y1) = map(x1, y1);
+                    let (x2, y2) = map(x2, y2);
+                    let (x3, y3) = map(x3, y3);
+                    PathElement::CurveTo(x1, y1, x2, y2, x3, y3)
+                }
+                PathElement::ClosePath => PathElement::ClosePath,
+            })
+            .collect();
+
+        let mut bbox = [
+            f64::INFINITY,
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+            f64::NEG_INFINITY,
+        ];
+        let mut extend = |x: f64, y: f64| {
+            bbox = [
+                bbox[0].min(x),
+                bbox[1].min(y),
+                bbox[2].max(x),
+                bbox[3].max(y),
+            ];
+        };
+        for element in &elements {
+            match *element {
+                PathElement::MoveTo(x, y) | PathElement::LineTo(x, y) => extend(x, y),
+                PathElement::CurveTo(x1, y1, x2, y2, x3, y3) => {
+                    extend(x1, y1);
+                    extend(x2, y2);
+                    extend(x3, y3);
+                }
+                PathElement::ClosePath => {}
+            }
+        }
+        if bbox[0] > bbox[2] {
+            // No points, nothing was drawn
+            return;
+        }
+
+        self.paths.push(VectorPath {
+            elements,
+            fill,
+            stroke,
+            bbox,
+        });
+    }
+
+    /// Scales the lengths of stroke properties by the CTM.
+    fn scaled_props(&self, props: &StrokeProps) -> StrokeProps {
+        let [a, b, c, d, ..] = self.ctm();
+        let scale = (a * d - b * c).abs().sqrt();
+        StrokeProps {
+            line_width: props.line_width * scale,
+            dash_array: props.dash_array.iter().map(|dash| dash * scale).collect(),
+            dash_offset: props.dash_offset * scale,
+            ..props.clone()
+        }
+    }
+}
+
+impl Device for PathRecorder {
+    fn begin_path(&mut self) {
+        self.current.clear();
+    }
+
+    fn move_to(&mut self, x: f64, y: f64) {
+        self.current.push(PathElement::MoveTo(x, y));
+    }
+
+    fn line_to(&mut self, x: f64, y: f64) {
+        self.current.push(PathElement::LineTo(x, y));
+    }
+
+    fn curve_to(&mut self, cp1x: f64, cp1y: f64, cp2x: f64, cp2y: f64, x: f64, y: f64) {
+        self.current
+            .push(PathElement::CurveTo(cp1x, cp1y, cp2x, cp2y, x, y));
+    }
+
+    fn rect(&mut self, x: f64, y: f64, width: f64, height: f64) {
+        self.current.extend([
+            PathElement::MoveTo(x, y),
+            PathElement::LineTo(x + width, y),
+            PathElement::LineTo(x + width, y + height),
+            PathElement::LineTo(x, y + height),
+            PathElement::ClosePath,
+        ]);
+    }
+
+    fn close_path(&mut self) {
+        self.current.push(PathElement::ClosePath);
+    }
+
+    fn draw_path(
+        &mut self,
+        mode: PathDrawMode,
+        paint: &Paint,
+        stroke_props: &StrokeProps,
+    ) -> PDFResult<()> {
+        let (fill, stroke) = match mode {
+            PathDrawMode::Fill(rule) => (
+                Some(PathFill {
+                    rule,
+                    paint: paint.clone(),
+                }),
+                None,
+            ),
+            PathDrawMode::Stroke => (
+                None,
+                Some(PathStroke {
+                    paint: paint.clone(),
+                    props: self.scaled_props(stroke_props),
+                }),
+            ),
+            PathDrawMode::FillStroke(rule) => {
+                return self.fill_and_stroke_path(rule, paint, paint, stroke_props);
+            }
+        };
+        self.record(fill, stroke);
+        self.current.clear();
+        Ok(())
+    }
+
+    fn fill_and_stroke_path(
+        &mut self,
+        rule: FillRule,
+        fill: &Paint,
+        stroke: &Paint,
+        stroke_props: &StrokeProps,
+    ) -> PDFResult<()> {
+        let fill = PathFill {
+            rule,
+            paint: fill.clone(),
+        };
+        let stroke = PathStroke {
+            paint: stroke.clone(),
+            props: self.scaled_props(stroke_props),
+        };
+        self.record(Some(fill), Some(stroke));
+        self.current.clear();
+        Ok(())
+    }
+
+    fn clip_path(&mut self, _rule: FillRule) -> PDFResult<()> {
+        self.current.clear();
+        Ok(())
+    }
+
+    fn save_state(&mut self) {
+        self.ctm.push(self.ctm());
+    }
+
+    fn restore_state(&mut self) {
+        if self.ctm.len() > 1 {
+            self.ctm.pop();
+        }
+    }
+
+    fn concat_matrix(&mut self, matrix: &[f64; 6]) {
+        let ctm = multiply_matrix(matrix, &self.ctm());
+        if let Some(top) = self.ctm.last_mut() {
+            *top = ctm;
+        }
+    }
+
+    fn set_matrix(&mut self, matrix: &[f64; 6]) {
+        if let Some(top) = self.ctm.last_mut() {
+            *top = *matrix;
+        }
+    }
+
+    fn draw_text(
+        &mut self,
+        _text_bytes: &[u8],
+        _font_name: &str,
+        _font_size: f64,
+        _character_spacing: f64,
+        _word_spacing: f64,
+        _paint: &Paint,
+        _text_matrix: &[f64; 6],
+        _horizontal_scaling: f64,
+        _text_rise: f64,
+    ) -> PDFResult<f64> {
+        Ok(0.0)
+    }
+
+    fn draw_image(&mut self, image: ImageData, transform: &[f64; 6]) -> PDFResult<()> {
+        if self.record_images {
+            let matrix = multiply_matrix(transform, &self.ctm());
+            self.images.push(RecordedImage { image, matrix });
+        }
+        Ok(())
+    }
+
+    fn page_bounds(&self) -> (f64, f64) {
+        (0.0, 0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::PDFDocument;
+    use crate::rendering::{Color, FillRule, Paint};
+    use crate::test_support::build_pdf;
+
+    #[test]
+    fn test_extract_paths() {
+        // A scaled rectangle filled and stroked with B, a stroked line after
+        // Q, and a clipping path that is not painted.
+        let content = "q 2 0 0 2 0 0 cm 1 0 0 RG 0 0 1 rg 3 w [2 1] 0 d 10 10 50 20 re B Q \
+                       0 0 m 100 50 l S 5 5 m 6 6 l W n";
+        let pdf = build_pdf(&[
+            "<< /Type /Catalog /Pages 2 0 R >>",
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>",
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] /Contents 4 0 R >>",
+            &format!(
+                "<< /Length {} >>\nstream\n{}\nendstream",
+                content.len(),
+                content
+            ),
+        ]);
+        let mut doc = PDFDocument::open(pdf).unwrap();
+        let page = doc.get_page(0).unwrap();
+        let paths = page.extract_paths(doc.xref_mut()).unwrap();
+        assert_eq!(paths.len(), 2);
+
+        let rect = &paths[0];
+        assert_eq!(rect.bbox, [20.0, 20.0, 120.0, 60.0]);
+        assert_eq!(rect.as_rect(), Some([20.0, 20.0, 120.0, 60.0]));
+        let fill = rect.fill.as_ref().unwrap();
+        assert_eq!(fill.rule, FillRule::NonZero);
+        assert!(
+            matches!(fill.paint, Paint::Solid(Color::RGB(r, g, b)) if (r, g, b) == (0.0, 0.0, 1.0))
+        );
+        let stroke = rect.stroke.as_ref().unwrap();
+        assert!(
+            matches!(stroke.paint, Paint::Solid(Color::RGB(r, g, b)) if (r, g, b) == (1.0, 0.0, 0.0))
+        );
+        assert_eq!(stroke.props.line_width, 6.0);
+        assert_eq!(stroke.props.dash_array, vec![4.0, 2.0]);
+
+        let line = &paths[1];
+        assert!(line.fill.is_none());
+        assert_eq!(line.bbox, [0.0, 0.0, 100.0, 50.0]);
+        assert_eq!(line.as_rect(), None);
+        let stroke = line.stroke.as_ref().unwrap();
+        assert!(matches!(stroke.paint, Paint::Solid(Color::Gray(g)) if g == 0.0));
+        assert_eq!(stroke.props.line_width, 1.0);
+    }
+}
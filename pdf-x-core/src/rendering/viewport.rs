@@ -0,0 +1,163 @@
+//! Page viewports: mapping PDF user space onto raster images.
+//!
+//! A viewport combines a page's visible area (CropBox clipped to MediaBox),
+//! its rotation and a scale factor into the pixel size of the output image
+//! and the matrix that takes user space to device space, with the y axis
+//! flipped so that the origin is at the top left.
+//!
+//! Based on PDF.js src/display/display_utils.js (PageViewport)
+
+/// Options for rendering a page to a raster image.
+///
+/// # Example
+/// ```no_run
+/// use pdf_x_core::PDFDocument;
+/// use pdf_x_core::rendering::RenderOptions;
+///
+/// let mut doc = PDFDocument::open_file("document.pdf", None, None).unwrap();
+/// let options = RenderOptions {
+///     scale: 2.0,
+///     rotation: 90,
+///     background: None,
+///     ..Default::default()
+/// };
+/// let (width, height, pixels) = doc.render_page_to_image(0, options).unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderOptions {
+    /// Pixels per PDF unit (1.0 renders at 72 DPI)
+    pub scale: f32,
+    /// Extra clockwise rotation in degrees, added to the page's /Rotate.
+    /// Values that are not a multiple of 90 are ignored.
+    pub rotation: i32,
+    /// RGBA color the image is filled with before rendering, or `None` for a
+    /// transparent background
+    pub background: Option<[u8; 4]>,
+    /// Area of the page to render, in default user space, instead of the
+    /// page's CropBox
+    pub crop_to: Option<[f64; 4]>,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions {
+            scale: 1.0,
+            rotation: 0,
+            background: Some([255, 255, 255, 255]),
+            crop_to: None,
+        }
+    }
+}
+
+impl From<Option<f32>> for RenderOptions {
+    /// Default options with the given scale, if any.
+    fn from(scale: Option<f32>) -> Self {
+        RenderOptions {
+            scale: scale.unwrap_or(1.0),
+            ..Default::default()
+        }
+    }
+}
+
+/// The size and transform of a rendered page.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewport {
+    /// Image width in pixels
+    pub width: u32,
+    /// Image height in pixels
+    pub height: u32,
+    /// Matrix from user space to image space
+    pub transform: [f64; 6],
+}
+
+impl Viewport {
+    /// Creates the viewport for a page area.
+    ///
+    /// # Arguments
+    /// * `view_box` - Visible area `[x0, y0, x1, y1]` in user space
+    /// * `rotation` - Clockwise rotation in degrees: 0, 90, 180 or 270
+    /// * `scale` - Pixels per PDF unit
+    pub fn new(view_box: [f64; 4], rotation: i32, scale: f64) -> Self {
+        let [x0, y0, x1, y1] = view_box;
+        let center_x = (x0 + x1) / 2.0;
+        let center_y = (y0 + y1) / 2.0;
+
+        let (rotate_a, rotate_b, rotate_c, rotate_d) = match rotation.rem_euclid(360) {
+            90 => (0.0, 1.0, 1.0, 0.0),
+            180 => (-1.0, 0.0, 0.0, 1.0),
+            270 => (0.0, -1.0, -1.0, 0.0),
+            _ => (1.0, 0.0, 0.0, -1.0),
+        };
+
+        let (width, height, offset_x, offset_y) = if rotate_a == 0.0 {
+            (
+                (y1 - y0) * scale,
+                (x1 - x0) * scale,
+                (center_y - y0).abs() * scale,
+                (center_x - x0).abs() * scale,
+            )
+        } else {
+            (
+                (x1 - x0) * scale,
+                (y1 - y0) * scale,
+                (center_x - x0).abs() * scale,
+                (center_y - y0).abs() * scale,
+            )
+        };
+
+        Viewport {
+            width: width.ceil() as u32,
+            height: height.ceil() as u32,
+            transform: [
+                rotate_a * scale,
+                rotate_b * scale,
+                rotate_c * scale,
+                rotate_d * scale,
+                offset_x - rotate_a * scale * center_x - rotate_c * scale * center_y,
+                offset_y - rotate_b * scale * center_x - rotate_d * scale * center_y,
+            ],
+        }
+    }
+
+    /// Maps a point from user space to image space.
+    pub fn apply(&self, x: f64, y: f64) -> (f64, f64) {
+        let [a, b, c, d, e, f] = self.transform;
+        (a * x + c * y + e, b * x + d * y + f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_viewport_rotation() {
+        let letter = [0.0, 0.0, 612.0, 792.0];
+
+        let viewport = Viewport::new(letter, 0, 2.0);
+        assert_eq!((viewport.width, viewport.height), (1224, 1584));
+        assert_eq!(viewport.apply(0.0, 792.0), (0.0, 0.0));
+        assert_eq!(viewport.apply(612.0, 0.0), (1224.0, 1584.0));
+
+        // Rotated a quarter turn clockwise, the top left of the page is at
+        // the top right of the image
+        let viewport = Viewport::new(letter, 90, 1.0);
+        assert_eq!((viewport.width, viewport.height), (792, 612));
+        assert_eq!(viewport.apply(0.0, 792.0), (792.0, 0.0));
+        assert_eq!(viewport.apply(0.0, 0.0), (0.0, 0.0));
+
+        let viewport = Viewport::new(letter, -90, 1.0);
+        assert_eq!(viewport.apply(612.0, 0.0), (792.0, 0.0));
+
+        let viewport = Viewport::new(letter, 180, 1.0);
+        assert_eq!(viewport.apply(0.0, 0.0), (612.0, 0.0));
+    }
+
+    #[test]
+    fn test_viewport_offset_view_box() {
+        let viewport = Viewport::new([100.0, 200.0, 300.0, 500.0], 0, 1.0);
+        assert_eq!((viewport.width, viewport.height), (200, 300));
+        assert_eq!(viewport.apply(100.0, 500.0), (0.0, 0.0));
+        assert_eq!(viewport.apply(300.0, 200.0), (200.0, 300.0));
+    }
+}
@@ -0,0 +1,41 @@
+//! Fixtures shared by the unit tests of this crate and, through the
+//! `test-support` feature, by the tests of the other workspace crates.
+
+/// Builds a PDF from object bodies, numbering them from 1 and computing
+/// the xref offsets. Object 1 must be the catalog.
+pub fn build_pdf<S: AsRef<[u8]>>(objects: &[S]) -> Vec<u8> {
+    build_pdf_with_trailer(objects, "")
+}
+
+/// Like [`build_pdf`], with extra trailer entries such as `/Info 4 0 R`.
+pub fn build_pdf_with_trailer<S: AsRef<[u8]>>(objects: &[S], trailer: &str) -> Vec<u8> {
+    let mut pdf = b"%PDF-1.7\n".to_vec();
+    let mut offsets = Vec::new();
+    for (i, body) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend_from_slice(format!("{} 0 obj\n", i + 1).as_bytes());
+        pdf.extend_from_slice(body.as_ref());
+        pdf.extend_from_slice(b"\nendobj\n");
+    }
+
+    let xref_offset = pdf.len();
+    pdf.extend_from_slice(format!("xref\n0 {}\n", objects.len() + 1).as_bytes());
+    pdf.extend_from_slice(b"0000000000 65535 f \n");
+    for offset in offsets {
+        pdf.extend_from_slice(format!("{:010} 00000 n \n", offset).as_bytes());
+    }
+
+    let mut entries = format!("/Size {} /Root 1 0 R", objects.len() + 1);
+    if !trailer.is_empty() {
+        entries.push(' ');
+        entries.push_str(trailer);
+    }
+    pdf.extend_from_slice(
+        format!(
+            "trailer\n<< {} >>\nstartxref\n{}\n%%EOF\n",
+            entries, xref_offset
+        )
+        .as_bytes(),
+    );
+    pdf
+}
@@ -0,0 +1,219 @@
+//! WebAssembly bindings (feature `wasm`).
+//!
+//! This module exposes [`PDFDocument`] to JavaScript through wasm-bindgen.
+//! Documents are opened either from bytes already in memory or from a JS
+//! callback that returns byte ranges, so large files can be read lazily.
+//!
+//! ```js
+//! import init, { PDFDocument } from "./pkg/pdf_x_core.js";
+//!
+//! await init();
+//! const bytes = new Uint8Array(await (await fetch("document.pdf")).arrayBuffer());
+//! const doc = new PDFDocument(bytes);
+//! console.log(doc.pageCount(), doc.extractText(0));
+//! document.body.innerHTML = doc.renderPageToSvg(0, 1.5);
+//! ```
+//!
+//! The filesystem is never touched: with this feature the bundled fallback
+//! fonts are not loaded, and system font lookup is only compiled for Linux
+//! targets. Text in non-embedded fonts is therefore drawn only by devices
+//! that don't need font programs, such as the SVG device.
+
+use crate::core::error::PDFResult;
+use crate::core::{ChunkLoader, PDFDocument, PDFError};
+use js_sys::{Function, Uint8Array};
+use wasm_bindgen::prelude::*;
+
+/// Converts an error to a JS `Error` whose `code` property holds the
+/// [`ErrorCode`](crate::core::ErrorCode) of `error`.
+fn to_js_error(error: PDFError) -> JsValue {
+    let js_error: JsValue = JsError::new(&error.to_string()).into();
+    let _ = js_sys::Reflect::set(
+        &js_error,
+        &JsValue::from_str("code"),
+        &JsValue::from(error.code() as u32),
+    );
+    js_error
+}
+
+/// Returns the message of a value thrown by JS code.
+fn js_error_message(value: &JsValue) -> String {
+    if let Some(error) = value.dyn_ref::<js_sys::Error>() {
+        String::from(error.message())
+    } else if let Some(message) = value.as_string() {
+        message
+    } else {
+        js_sys::JSON::stringify(value)
+            .ok()
+            .and_then(|json| json.as_string())
+            .unwrap_or_else(|| "unknown error".to_string())
+    }
+}
+
+/// A chunk loader backed by a JS function `(begin, end) => Uint8Array`.
+///
+/// The function is called synchronously, so it must return the bytes
+/// directly, for example from a synchronous XMLHttpRequest in a worker or
+/// from an already downloaded buffer.
+struct JsChunkLoader {
+    fetch: Function,
+    length: usize,
+    chunk_size: usize,
+}
+
+// SAFETY: wasm32 without the atomics feature has a single thread, so the JS
+// function can never be used from another thread. `ChunkLoader` streams
+// require `Send` only to support native multi-threaded hosts.
+unsafe impl Send for JsChunkLoader {}
+
+impl ChunkLoader for JsChunkLoader {
+    fn request_chunk(&mut self, chunk_num: usize) -> PDFResult<Vec<u8>> {
+        let begin = chunk_num
+            .checked_mul(self.chunk_size)
+            .filter(|&begin| begin < self.length)
+            .ok_or_else(|| {
+                PDFError::Generic(format!(
+                    "Chunk {} is past the end of the {}-byte document",
+                    chunk_num, self.length
+                ))
+            })?;
+        let end = begin + self.chunk_size.min(self.length - begin);
+        let value = self
+            .fetch
+            .call2(
+                &JsValue::NULL,
+                &JsValue::from(begin as f64),
+                &JsValue::from(end as f64),
+            )
+            .map_err(|e| {
+                PDFError::Generic(format!("Chunk loader failed: {}", js_error_message(&e)))
+            })?;
+        if !value.is_instance_of::<Uint8Array>() {
+            return Err(PDFError::Generic(format!(
+                "Chunk loader must return a Uint8Array for bytes {}..{}",
+                begin, end
+            )));
+        }
+        Ok(Uint8Array::new(&value).to_vec())
+    }
+
+    fn chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+
+    fn total_length(&self) -> usize {
+        self.length
+    }
+}
+
+/// A PDF document, exported to JS as `PDFDocument`.
+#[wasm_bindgen(js_name = PDFDocument)]
+pub struct WasmDocument {
+    inner: PDFDocument,
+}
+
+#[wasm_bindgen(js_class = PDFDocument)]
+impl WasmDocument {
+    /// Opens a document from its bytes.
+    #[wasm_bindgen(constructor)]
+    pub fn new(data: Vec<u8>) -> Result<WasmDocument, JsValue> {
+        let inner = PDFDocument::open(data).map_err(to_js_error)?;
+        Ok(WasmDocument { inner })
+    }
+
+    /// Opens a document of `length` bytes whose data is read on demand, in
+    /// chunks of `chunk_size` bytes, through `fetch(begin, end)`.
+    #[wasm_bindgen(js_name = fromLoader)]
+    pub fn from_loader(
+        length: usize,
+        chunk_size: usize,
+        fetch: Function,
+    ) -> Result<WasmDocument, JsValue> {
+        let loader = JsChunkLoader {
+            fetch,
+            length,
+            chunk_size: chunk_size.max(1),
+        };
+        let inner = PDFDocument::open_stream(loader, None).map_err(to_js_error)?;
+        Ok(WasmDocument { inner })
+    }
+
+    /// Returns the number of pages.
+    #[wasm_bindgen(js_name = pageCount)]
+    pub fn page_count(&mut self) -> Result<u32, JsValue> {
+        self.inner.page_count().map_err(to_js_error)
+    }
+
+    /// Extracts the text of a page in layout order.
+    #[wasm_bindgen(js_name = extractText)]
+    pub fn extract_text(&mut self, page_index: usize) -> Result<String, JsValue> {
+        let page = self.inner.get_page(page_index).map_err(to_js_error)?;
+        page.extract_text_as_string(self.inner.xref_mut())
+            .map_err(to_js_error)
+    }
+
+    /// Renders a page to an SVG document.
+    #[wasm_bindgen(js_name = renderPageToSvg)]
+    pub fn render_page_to_svg(
+        &mut self,
+        page_index: usize,
+        scale: Option<f32>,
+    ) -> Result<String, JsValue> {
+        self.inner
+            .render_page_to_svg(page_index, scale)
+            .map_err(to_js_error)
+    }
+}
+
+#[cfg(feature = "rendering")]
+#[wasm_bindgen(js_class = PDFDocument)]
+impl WasmDocument {
+    /// Renders a page to RGBA pixels, ready for `new ImageData(...)`.
+    #[wasm_bindgen(js_name = renderPage)]
+    pub fn render_page(
+        &mut self,
+        page_index: usize,
+        scale: Option<f32>,
+    ) -> Result<RenderedPage, JsValue> {
+        let (width, height, pixels) = self
+            .inner
+            .render_page_to_image(page_index, scale)
+            .map_err(to_js_error)?;
+        Ok(RenderedPage {
+            width,
+            height,
+            pixels,
+        })
+    }
+}
+
+/// RGBA pixels of a rendered page.
+#[cfg(feature = "rendering")]
+#[wasm_bindgen]
+pub struct RenderedPage {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+#[cfg(feature = "rendering")]
+#[wasm_bindgen]
+impl RenderedPage {
+    /// Image width in pixels.
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Image height in pixels.
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Pixels as [R, G, B, A, ...], row by row from the top.
+    #[wasm_bindgen(getter)]
+    pub fn pixels(&self) -> Uint8Array {
+        Uint8Array::from(self.pixels.as_slice())
+    }
+}
@@ -383,11 +383,11 @@ fn test_bad_xref_pdf_loading() {
     }
 
     let result = assert_pdf_loads("bad-xref.pdf");
-    // The bad-xref.pdf has an invalid startxref, so it should fail gracefully
-    assert!(
-        result.is_err(),
-        "bad-xref.pdf should fail to load but not panic"
-    );
+    // The bad-xref.pdf has an invalid startxref; the xref table is rebuilt
+    // by scanning the file for objects
+    let mut doc = result.expect("bad-xref.pdf should load after reconstruction");
+    assert_eq!(doc.page_count().unwrap(), 1);
+    assert!(doc.xref().is_reconstructed());
 }
 
 // ============================================================================
@@ -162,7 +162,7 @@ fn test_preload_range() {
         return;
     }
 
-    let mut stream =
+    let stream =
         FileChunkedStream::open(test_pdf, Some(1024), Some(2)).expect("Should create stream");
 
     // Preload a range
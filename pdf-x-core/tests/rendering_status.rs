@@ -24,7 +24,7 @@ mod rendering_capabilities {
         println!("\n⚠ PARTIAL:");
         println!("  - Text rendering (font loading partially implemented)");
         println!("    - System fonts can be located");
-        println!("    - Font parsing has issues with Type1 format");
+        println!("    - Embedded Type1 programs (PFB, PFA, eexec) are parsed");
         println!("    - Text operators (Tj, TJ) are processed");
         println!("    - Text is skipped when fonts fail to load");
 
@@ -52,9 +52,8 @@ mod rendering_capabilities {
         println!("   Verification: Vector graphics rendering test passes");
 
         println!("\n2. FONT PARSING ERRORS");
-        println!("   Error: 'UnknownMagic' when parsing Type1 fonts");
-        println!("   Impact: Text rendering is skipped");
-        println!("   Fix needed: Implement Type1 font format parser");
+        println!("   Status: ✓ Fixed - Type1 parser handles PFB, PFA and eexec data");
+        println!("   Verification: rendering::font::type1 unit tests");
 
         println!("\n3. MATRIX CONCATENATION");
         println!("   Status: ✓ Fixed - using pre_concat for PDF spec compliance");
@@ -1,6 +1,7 @@
 use crate::state::AppState;
 use crate::types::*;
 use base64::{Engine as _, engine::general_purpose};
+use pdf_x_core::FontSizeClass;
 use std::fs;
 use std::path::PathBuf;
 use tauri::State;
@@ -41,29 +42,25 @@ pub async fn open_pdf_file(
     let is_encrypted = doc.is_encrypted();
     let requires_password = is_encrypted; // If encrypted, password is required
 
-    // Extract document info
-    let (title, author, subject, keywords, creator, producer) =
-        if let Ok(Some(info)) = doc.document_info() {
-            extract_info_fields(&info)
-        } else {
-            (None, None, None, None, None, None)
-        };
+    // Extract document info (Info dictionary + XMP)
+    let info = doc.metadata().unwrap_or_default();
 
-    // Store file path in state
+    // Store file path in state; the previous document's summary is stale
     {
         let mut path_guard = state.inner().file_path.lock().unwrap();
         *path_guard = Some(path);
     }
+    *state.inner().summary.lock().unwrap() = None;
 
     Ok(DocumentMetadata {
-        title,
-        author,
-        subject,
-        keywords,
-        creator,
-        producer,
-        creation_date: None,
-        modification_date: None,
+        title: info.title,
+        author: info.author,
+        subject: info.subject,
+        keywords: info.keywords,
+        creator: info.creator,
+        producer: info.producer,
+        creation_date: info.creation_date.map(|date| date.to_string()),
+        modification_date: info.mod_date.map(|date| date.to_string()),
         page_count,
         file_size,
         pdf_version,
@@ -120,9 +117,47 @@ pub async fn extract_text_from_page(
     })
 }
 
-/// Get document outline (bookmarks)
+/// Extract the words of a page with their bounding boxes
 #[tauri::command]
-pub async fn get_document_outline(state: State<'_, AppState>) -> AppResult<Vec<OutlineItem>> {
+pub async fn extract_words_from_page(
+    page_index: usize,
+    state: State<'_, AppState>,
+) -> AppResult<Vec<TextWord>> {
+    // Get file path from state
+    let file_path = {
+        let path_guard = state.inner().file_path.lock().unwrap();
+        path_guard.as_ref().cloned()
+    };
+
+    let file_path = file_path.ok_or(AppError::NoDocumentLoaded)?;
+
+    // Reload document
+    let mut doc = pdf_x_core::PDFDocument::open_file(&file_path, None, None)?;
+
+    let words = doc
+        .extract_words_from_page(page_index)?
+        .into_iter()
+        .map(|word| {
+            let [x0, y0, x1, y1] = word.bbox;
+            TextWord {
+                text: word.text,
+                x0,
+                y0,
+                x1,
+                y1,
+            }
+        })
+        .collect();
+
+    Ok(words)
+}
+
+/// Reflow a page into styled paragraphs for reader mode
+#[tauri::command]
+pub async fn get_reader_mode_page(
+    page_index: usize,
+    state: State<'_, AppState>,
+) -> AppResult<Vec<ReaderParagraph>> {
     // Get file path from state
     let file_path = {
         let path_guard = state.inner().file_path.lock().unwrap();
@@ -134,24 +169,73 @@ pub async fn get_document_outline(state: State<'_, AppState>) -> AppResult<Vec<O
     // Reload document
     let mut doc = pdf_x_core::PDFDocument::open_file(&file_path, None, None)?;
 
-    match doc.document_outline_items()? {
-        Some(core_items) => {
-            // Convert core library OutlineItem to Tauri OutlineItem
-            let items = core_items
+    let paragraphs = doc
+        .reflow_page(page_index)?
+        .into_iter()
+        .map(|paragraph| ReaderParagraph {
+            heading_level: paragraph.heading_level,
+            runs: paragraph
+                .runs
                 .into_iter()
-                .map(|item| convert_outline_item(item, &mut doc))
-                .collect::<AppResult<Vec<_>>>()?;
-            Ok(items)
-        }
-        None => Ok(vec![]),
+                .map(|run| ReaderRun {
+                    text: run.text,
+                    size: match run.size_class {
+                        FontSizeClass::Small => "small",
+                        FontSizeClass::Body => "body",
+                        FontSizeClass::Large => "large",
+                        FontSizeClass::Huge => "huge",
+                    }
+                    .to_string(),
+                    bold: run.bold,
+                    italic: run.italic,
+                })
+                .collect(),
+        })
+        .collect();
+
+    Ok(paragraphs)
+}
+
+/// Page sizes, outline and labels of the open document.
+///
+/// The summary is built in one pass over the document the first time a
+/// viewer panel asks for it and kept in the state until the document is
+/// closed.
+fn document_summary(state: &State<'_, AppState>) -> AppResult<pdf_x_core::DocumentSummary> {
+    if let Some(summary) = state.inner().summary.lock().unwrap().as_ref() {
+        return Ok(summary.clone());
     }
+
+    // Get file path from state
+    let file_path = {
+        let path_guard = state.inner().file_path.lock().unwrap();
+        path_guard.as_ref().cloned()
+    };
+
+    let file_path = file_path.ok_or(AppError::NoDocumentLoaded)?;
+
+    let mut doc = pdf_x_core::PDFDocument::open_file(&file_path, None, None)?;
+    let summary = doc.document_summary()?.clone();
+
+    *state.inner().summary.lock().unwrap() = Some(summary.clone());
+    Ok(summary)
+}
+
+/// Get document outline (bookmarks)
+#[tauri::command]
+pub async fn get_document_outline(state: State<'_, AppState>) -> AppResult<Vec<OutlineItem>> {
+    let summary = document_summary(&state)?;
+
+    // Convert core library OutlineItem to Tauri OutlineItem
+    Ok(summary
+        .outline
+        .into_iter()
+        .map(convert_outline_item)
+        .collect())
 }
 
 /// Converts a core library OutlineItem to a Tauri OutlineItem
-fn convert_outline_item(
-    core_item: pdf_x_core::OutlineItem,
-    doc: &mut pdf_x_core::PDFDocument,
-) -> AppResult<OutlineItem> {
+fn convert_outline_item(core_item: pdf_x_core::OutlineItem) -> OutlineItem {
     // Resolve destination to page number and other info
     let (page, dest_type, url) = match &core_item.dest {
         Some(pdf_x_core::OutlineDestination::Explicit {
@@ -182,10 +266,10 @@ fn convert_outline_item(
     let children = core_item
         .children
         .into_iter()
-        .map(|child| convert_outline_item(child, doc))
-        .collect::<AppResult<Vec<_>>>()?;
+        .map(convert_outline_item)
+        .collect();
 
-    Ok(OutlineItem {
+    OutlineItem {
         title: core_item.title,
         page,
         dest_type,
@@ -195,112 +279,31 @@ fn convert_outline_item(
         italic: core_item.italic,
         count: core_item.count,
         children,
-    })
+    }
 }
 
 /// Get page sizes
 #[tauri::command]
 pub async fn get_page_sizes(state: State<'_, AppState>) -> AppResult<Vec<PageInfo>> {
-    // Get file path from state
-    let file_path = {
-        let path_guard = state.inner().file_path.lock().unwrap();
-        path_guard.as_ref().cloned()
-    };
-
-    let file_path = file_path.ok_or(AppError::NoDocumentLoaded)?;
-
-    // Reload document
-    let mut doc = pdf_x_core::PDFDocument::open_file(&file_path, None, None)?;
-
-    let page_count = doc.page_count()?;
-    let mut pages = Vec::new();
-
-    for i in 0..page_count {
-        let page = doc.get_page(i as usize)?;
-
-        // Get media box
-        let media_box = page.media_box();
-        let (width, height) = if let Some(mediabox) = media_box {
-            extract_media_box_dimensions(mediabox).unwrap_or((595.0, 842.0)) // Default A4
-        } else {
-            (595.0, 842.0) // Default A4 size
-        };
-
-        pages.push(PageInfo {
-            index: i as usize,
-            width,
-            height,
-            rotation: 0,
-        });
-    }
+    let summary = document_summary(&state)?;
+
+    let pages = summary
+        .pages
+        .iter()
+        .zip(summary.labels)
+        .enumerate()
+        .map(|(index, (page, label))| PageInfo {
+            index,
+            label,
+            width: page.width,
+            height: page.height,
+            rotation: page.rotation as u32,
+        })
+        .collect();
 
     Ok(pages)
 }
 
-/// Helper function to extract info fields from document info dictionary
-fn extract_info_fields(
-    info: &pdf_x_core::PDFObject,
-) -> (
-    Option<String>,
-    Option<String>,
-    Option<String>,
-    Option<String>,
-    Option<String>,
-    Option<String>,
-) {
-    if let pdf_x_core::PDFObject::Dictionary(dict) = info {
-        let title = dict.get("Title").and_then(|v| extract_string_value(v));
-        let author = dict.get("Author").and_then(|v| extract_string_value(v));
-        let subject = dict.get("Subject").and_then(|v| extract_string_value(v));
-        let keywords = dict.get("Keywords").and_then(|v| extract_string_value(v));
-        let creator = dict.get("Creator").and_then(|v| extract_string_value(v));
-        let producer = dict.get("Producer").and_then(|v| extract_string_value(v));
-
-        (title, author, subject, keywords, creator, producer)
-    } else {
-        (None, None, None, None, None, None)
-    }
-}
-
-/// Helper function to extract string value from PDF object
-fn extract_string_value(obj: &pdf_x_core::PDFObject) -> Option<String> {
-    match obj {
-        pdf_x_core::PDFObject::String(s) => Some(String::from_utf8_lossy(s).to_string()),
-        pdf_x_core::PDFObject::HexString(s) => {
-            let hex_str: String = s.iter().map(|b| format!("{:02x}", b)).collect();
-            Some(String::from_utf8_lossy(hex_str.as_bytes()).to_string())
-        }
-        _ => None,
-    }
-}
-
-/// Helper function to extract width and height from MediaBox
-fn extract_media_box_dimensions(mediabox: &pdf_x_core::PDFObject) -> Option<(f64, f64)> {
-    if let pdf_x_core::PDFObject::Array(arr) = mediabox {
-        if arr.len() >= 4 {
-            let values: Vec<f64> = arr
-                .iter()
-                .take(4)
-                .filter_map(|v| {
-                    if let pdf_x_core::PDFObject::Number(n) = v.as_ref() {
-                        Some(*n)
-                    } else {
-                        None
-                    }
-                })
-                .collect();
-
-            if values.len() == 4 {
-                let width = values[2] - values[0];
-                let height = values[3] - values[1];
-                return Some((width, height));
-            }
-        }
-    }
-
-    None
-}
-
 /// Render a page to PNG image
 #[tauri::command]
 pub async fn render_page(
@@ -317,8 +320,12 @@ pub async fn render_page(
             .ok_or(AppError::NoDocumentLoaded)?
     };
 
+    // A newer render request cancels this one
+    let cancel = state.inner().start_render();
+
     // Parse PDF from cached data (much faster than reading from disk)
     let mut doc = pdf_x_core::PDFDocument::open(pdf_data)?;
+    doc.set_cancellation_token(Some(cancel));
 
     // Render the page to image (RGBA pixels)
     let (width, height, pixels) = doc
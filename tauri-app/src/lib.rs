@@ -12,6 +12,8 @@ pub fn run() {
             commands::open_pdf_file,
             commands::close_document,
             commands::extract_text_from_page,
+            commands::extract_words_from_page,
+            commands::get_reader_mode_page,
             commands::get_document_outline,
             commands::get_page_sizes,
             commands::render_page,
@@ -1,3 +1,4 @@
+use pdf_x_core::{CancellationToken, DocumentSummary};
 use std::path::PathBuf;
 use std::sync::Mutex;
 
@@ -10,6 +11,12 @@ pub struct AppState {
 
     /// Raw PDF file data (cached in memory for fast access)
     pub pdf_data: Mutex<Option<Vec<u8>>>,
+
+    /// Cancels the page render in progress, if any
+    pub render_cancel: Mutex<Option<CancellationToken>>,
+
+    /// Page sizes, outline and labels, built on first request
+    pub summary: Mutex<Option<DocumentSummary>>,
 }
 
 impl AppState {
@@ -18,6 +25,8 @@ impl AppState {
         Self {
             file_path: Mutex::new(None),
             pdf_data: Mutex::new(None),
+            render_cancel: Mutex::new(None),
+            summary: Mutex::new(None),
         }
     }
 
@@ -28,5 +37,30 @@ impl AppState {
 
         let mut data_guard = self.pdf_data.lock().unwrap();
         *data_guard = None;
+
+        let mut summary_guard = self.summary.lock().unwrap();
+        *summary_guard = None;
+
+        self.cancel_render();
+    }
+
+    /// Cancel the page render in progress and start tracking a new one.
+    ///
+    /// Only the most recently requested page is worth finishing, so each
+    /// render cancels the one before it when the user flips pages quickly.
+    pub fn start_render(&self) -> CancellationToken {
+        let token = CancellationToken::new();
+        let mut cancel_guard = self.render_cancel.lock().unwrap();
+        if let Some(previous) = cancel_guard.replace(token.clone()) {
+            previous.cancel();
+        }
+        token
+    }
+
+    /// Cancel the page render in progress, if any
+    pub fn cancel_render(&self) {
+        if let Some(token) = self.render_cancel.lock().unwrap().take() {
+            token.cancel();
+        }
     }
 }
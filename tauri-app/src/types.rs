@@ -37,6 +37,7 @@ pub struct OutlineItem {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PageInfo {
     pub index: usize,
+    pub label: String,
     pub width: f64,
     pub height: f64,
     pub rotation: u32,
@@ -59,6 +60,34 @@ pub struct TextItem {
     pub y: f64,
 }
 
+/// Word with its bounding box, for search highlighting
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TextWord {
+    pub text: String,
+    pub x0: f64,
+    pub y0: f64,
+    pub x1: f64,
+    pub y1: f64,
+}
+
+/// Paragraph of a reflowed page, for reader mode
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReaderParagraph {
+    /// Heading level (1 is the most prominent), if the paragraph is a heading
+    pub heading_level: Option<u8>,
+    pub runs: Vec<ReaderRun>,
+}
+
+/// Run of text in one style within a reader mode paragraph
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReaderRun {
+    pub text: String,
+    /// "small", "body", "large" or "huge", relative to the page's body text
+    pub size: String,
+    pub bold: bool,
+    pub italic: bool,
+}
+
 /// Error types for the Tauri app
 #[derive(Debug, thiserror::Error)]
 pub enum AppError {
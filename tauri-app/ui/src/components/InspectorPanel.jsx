@@ -231,7 +231,7 @@ function InspectorPanel({ documentLoaded, metadata: externalMetadata }) {
               <div className="page-sizes-list">
                 {pageSizes.map((page) => (
                   <div key={page.index} className="page-size-item">
-                    <span className="page-number">Page {page.index + 1}</span>
+                    <span className="page-number">Page {page.label}</span>
                     <span className="page-dimensions">
                       {page.width.toFixed(1)} × {page.height.toFixed(1)} pt
                     </span>